@@ -1,5 +1,11 @@
 use anyhow::Result;
-use std::{collections::HashSet, env, path::PathBuf, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
 
 use screenpipe_core::Language;
 use screenpipe_db::DatabaseManager;
@@ -32,6 +38,9 @@ pub struct AudioManagerOptions {
     pub deepgram_url: Option<String>,
     pub deepgram_websocket_url: Option<String>,
     pub output_path: Option<PathBuf>,
+    /// Registered transcription provider used by a device instead of the
+    /// transcription engine, by device name
+    pub device_transcription_providers: HashMap<String, String>,
 }
 
 impl Default for AudioManagerOptions {
@@ -56,6 +65,7 @@ impl Default for AudioManagerOptions {
             db_path: None,
             deepgram_url,
             deepgram_websocket_url,
+            device_transcription_providers: HashMap::new(),
         }
     }
 }
@@ -137,6 +147,11 @@ impl AudioManagerBuilder {
         self
     }
 
+    pub fn device_transcription_providers(mut self, providers: HashMap<String, String>) -> Self {
+        self.options.device_transcription_providers = providers;
+        self
+    }
+
     pub async fn build(&mut self, db: Arc<DatabaseManager>) -> Result<AudioManager> {
         self.validate_options()?;
         let options = &mut self.options;
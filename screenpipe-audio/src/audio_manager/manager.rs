@@ -27,6 +27,7 @@ use crate::{
         deepgram::streaming::stream_transcription_deepgram,
         handle_new_transcript,
         stt::process_audio_input,
+        transcription_provider,
         whisper::model::{create_whisper_context_parameters, download_whisper_model},
     },
     vad::{silero::SileroVad, webrtc::WebRtcVad, VadEngine, VadEngineEnum},
@@ -244,6 +245,11 @@ impl AudioManager {
         let deepgram_api_key = options.deepgram_api_key.clone();
         let realtime_enabled = options.enable_realtime;
         let device_clone = device.clone();
+        let streaming_provider = options
+            .device_transcription_providers
+            .get(&device.to_string())
+            .and_then(|name| transcription_provider(name))
+            .filter(|provider| provider.supports_streaming());
 
         let recording_handle = tokio::spawn(async move {
             let record_and_transcribe_handle = tokio::spawn(record_and_transcribe(
@@ -253,15 +259,21 @@ impl AudioManager {
                 is_running.clone(),
             ));
 
-            let realtime_handle = if realtime_enabled {
-                Some(tokio::spawn(stream_transcription_deepgram(
+            let realtime_handle = match (realtime_enabled, streaming_provider) {
+                (false, _) => None,
+                (true, Some(provider)) => Some(tokio::spawn(provider.stream(
+                    stream.subscribe().await,
+                    stream.device.clone(),
+                    stream.device_config.sample_rate().0,
+                    languages,
+                    is_running,
+                ))),
+                (true, None) => Some(tokio::spawn(stream_transcription_deepgram(
                     stream,
                     languages,
                     is_running,
                     deepgram_api_key,
-                )))
-            } else {
-                None
+                ))),
             };
 
             let (record_result, realtime_result) = if let Some(handle) = realtime_handle {
@@ -310,6 +322,7 @@ impl AudioManager {
         let languages = options.languages.clone();
         let deepgram_api_key = options.deepgram_api_key.clone();
         let audio_transcription_engine = options.transcription_engine.clone();
+        let device_providers = options.device_transcription_providers.clone();
         let vad_engine = self.vad_engine.clone();
         let whisper_receiver = self.recording_receiver.clone();
         let context_param = create_whisper_context_parameters(audio_transcription_engine.clone())?;
@@ -323,6 +336,10 @@ impl AudioManager {
         Ok(tokio::spawn(async move {
             while let Ok(audio) = whisper_receiver.recv() {
                 info!("Received audio from device: {:?}", audio.device.name);
+                // looked up each time, providers can be registered at any time
+                let provider = device_providers
+                    .get(&audio.device.to_string())
+                    .and_then(|name| transcription_provider(name));
                 if let Err(e) = process_audio_input(
                     audio.clone(),
                     vad_engine.clone(),
@@ -335,6 +352,7 @@ impl AudioManager {
                     languages.clone(),
                     &transcription_sender.clone(),
                     whisper_context.clone(),
+                    provider,
                 )
                 .await
                 {
@@ -1,9 +1,10 @@
 use anyhow::Result;
 use lazy_static::lazy_static;
-use tracing::{debug, info};
+use screenpipe_core::egress;
 use std::path::PathBuf;
 use std::sync::Once;
 use tokio::sync::Mutex;
+use tracing::{debug, info};
 
 lazy_static! {
     static ref SEGMENTATION_MODEL_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
@@ -31,6 +32,12 @@ pub async fn get_or_download_model(model_type: PyannoteModel) -> Result<PathBuf>
                 return Ok(path);
             }
 
+            if egress::local_only() {
+                anyhow::bail!(
+                    "local-only mode: segmentation model is not downloaded, run once without --local-only to download it"
+                );
+            }
+
             info!("initiating segmentation model download...");
             DOWNLOAD_SEGMENTATION_ONCE.call_once(|| {
                 tokio::spawn(async move {
@@ -63,6 +70,12 @@ pub async fn get_or_download_model(model_type: PyannoteModel) -> Result<PathBuf>
                 return Ok(path);
             }
 
+            if egress::local_only() {
+                anyhow::bail!(
+                    "local-only mode: embedding model is not downloaded, run once without --local-only to download it"
+                );
+            }
+
             info!("initiating embedding model download...");
             DOWNLOAD_EMBEDDING_ONCE.call_once(|| {
                 tokio::spawn(async move {
@@ -100,7 +113,7 @@ async fn download_model(model_type: PyannoteModel) -> Result<()> {
     };
 
     info!("downloading {} model from {}", filename, url);
-    let response = reqwest::get(url).await?;
+    let response = egress::http_client().get(url).send().await?;
     let model_data = response.bytes().await?;
 
     let cache_dir = get_cache_dir()?;
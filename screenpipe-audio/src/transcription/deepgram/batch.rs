@@ -1,7 +1,7 @@
 use anyhow::Result;
 use hound::{WavSpec, WavWriter};
-use reqwest::{Client, Response};
-use screenpipe_core::Language;
+use reqwest::Response;
+use screenpipe_core::{egress, Language};
 use serde_json::Value;
 use std::io::Cursor;
 use tracing::{debug, error, info};
@@ -16,6 +16,7 @@ pub async fn transcribe_with_deepgram(
     languages: Vec<Language>,
 ) -> Result<String> {
     debug!("starting deepgram transcription");
+    egress::check_egress(&DEEPGRAM_API_URL)?;
 
     // Use token from env var
     let custom_api_key = CUSTOM_DEEPGRAM_API_TOKEN.as_str();
@@ -42,7 +43,7 @@ pub async fn transcribe_with_deepgram(
     handle_deepgram_response(response, device).await
 }
 
-fn create_wav_file(audio_data: &[f32], sample_rate: u32) -> Result<Vec<u8>> {
+pub(crate) fn create_wav_file(audio_data: &[f32], sample_rate: u32) -> Result<Vec<u8>> {
     // Create a WAV file in memory
     let mut cursor = Cursor::new(Vec::new());
     {
@@ -91,7 +92,7 @@ async fn get_deepgram_response(
     wav_data: Vec<u8>,
     params: String,
 ) -> Result<Response, reqwest::Error> {
-    let client = Client::new();
+    let client = egress::http_client();
 
     client
         .post(format!("{}?{}", *DEEPGRAM_API_URL, params))
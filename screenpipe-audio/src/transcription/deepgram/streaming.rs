@@ -82,6 +82,14 @@ pub async fn start_deepgram_stream(
         return Err(anyhow::anyhow!("Deepgram API key not found"));
     }
 
+    // the default endpoint is deepgram's
+    let egress_url = if DEEPGRAM_WEBSOCKET_URL.as_str().is_empty() {
+        "wss://api.deepgram.com"
+    } else {
+        DEEPGRAM_WEBSOCKET_URL.as_str()
+    };
+    screenpipe_core::egress::check_egress(egress_url)?;
+
     // Remove the API key logging
     info!("initializing deepgram transcription service");
 
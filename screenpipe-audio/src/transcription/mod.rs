@@ -3,6 +3,7 @@ use std::sync::Arc;
 use crate::core::device::AudioDevice;
 
 pub mod deepgram;
+pub mod provider;
 pub mod stt;
 pub mod whisper;
 
@@ -22,3 +23,7 @@ pub use transcription_result::process_transcription_result;
 pub use transcription_result::TranscriptionResult;
 mod handle_new_transcript;
 pub use handle_new_transcript::handle_new_transcript;
+pub use provider::{
+    register_transcription_provider, transcription_provider, transcription_providers,
+    DeepgramProvider, RemoteTranscriptionProvider, TranscriptionProvider,
+};
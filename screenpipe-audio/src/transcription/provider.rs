@@ -0,0 +1,209 @@
+use anyhow::Result;
+use lazy_static::lazy_static;
+use reqwest::multipart::{Form, Part};
+use reqwest::Client;
+use screenpipe_core::{egress, Language};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::broadcast::Receiver;
+
+use crate::core::device::AudioDevice;
+use crate::transcription::deepgram::batch::{create_wav_file, transcribe_with_deepgram};
+use crate::transcription::deepgram::streaming::start_deepgram_stream;
+
+const REMOTE_TIMEOUT: Duration = Duration::from_secs(120);
+
+pub type TranscriptionFuture<'a> = Pin<Box<dyn Future<Output = Result<String>> + Send + 'a>>;
+
+pub type StreamFuture = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+
+/// A speech to text engine registered at runtime next to the built in
+/// whisper models, e.g. a whisper.cpp or faster-whisper server, Deepgram or
+/// a custom model. Devices are pointed at one by its name, which is recorded
+/// as the transcription engine of what it transcribes.
+pub trait TranscriptionProvider: Send + Sync {
+    /// Unique among the registered providers.
+    fn name(&self) -> &str;
+
+    /// Transcribes a speech segment, mono samples at `sample_rate`.
+    fn transcribe<'a>(
+        &'a self,
+        audio: &'a [f32],
+        sample_rate: u32,
+        device: &'a str,
+        languages: &'a [Language],
+    ) -> TranscriptionFuture<'a>;
+
+    /// Whether `stream` can be used for realtime transcription.
+    fn supports_streaming(&self) -> bool {
+        false
+    }
+
+    /// Transcribes the samples of a device as they are recorded, until
+    /// `is_running` is cleared, sending realtime transcription events.
+    fn stream(
+        &self,
+        _audio: Receiver<Vec<f32>>,
+        _device: Arc<AudioDevice>,
+        _sample_rate: u32,
+        _languages: Vec<Language>,
+        _is_running: Arc<AtomicBool>,
+    ) -> StreamFuture {
+        let name = self.name().to_string();
+        Box::pin(async move { anyhow::bail!("transcription provider {} can't stream", name) })
+    }
+}
+
+lazy_static! {
+    static ref PROVIDERS: RwLock<BTreeMap<String, Arc<dyn TranscriptionProvider>>> =
+        RwLock::new(BTreeMap::new());
+}
+
+/// Registers `provider` under its name, returning the provider it replaces.
+pub fn register_transcription_provider(
+    provider: Arc<dyn TranscriptionProvider>,
+) -> Option<Arc<dyn TranscriptionProvider>> {
+    PROVIDERS
+        .write()
+        .unwrap()
+        .insert(provider.name().to_string(), provider)
+}
+
+pub fn transcription_provider(name: &str) -> Option<Arc<dyn TranscriptionProvider>> {
+    PROVIDERS.read().unwrap().get(name).cloned()
+}
+
+/// The registered providers, by name.
+pub fn transcription_providers() -> Vec<Arc<dyn TranscriptionProvider>> {
+    PROVIDERS.read().unwrap().values().cloned().collect()
+}
+
+/// A transcription server reached over HTTP: the `/inference` endpoint of
+/// the whisper.cpp server or an OpenAI compatible `/v1/audio/transcriptions`
+/// such as faster-whisper-server. The segment is posted as a WAV `file` and
+/// the `text` of the JSON answer is the transcription.
+pub struct RemoteTranscriptionProvider {
+    name: String,
+    url: String,
+    api_key: Option<String>,
+    client: Client,
+}
+
+impl RemoteTranscriptionProvider {
+    pub fn new(name: &str, url: &str, api_key: Option<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            url: url.to_string(),
+            api_key,
+            client: egress::http_client(),
+        }
+    }
+
+    async fn post(
+        &self,
+        audio: &[f32],
+        sample_rate: u32,
+        languages: &[Language],
+    ) -> Result<String> {
+        egress::check_egress(&self.url)?;
+        let wav = create_wav_file(audio, sample_rate)?;
+        let mut form = Form::new()
+            .part(
+                "file",
+                Part::bytes(wav)
+                    .file_name("audio.wav")
+                    .mime_str("audio/wav")?,
+            )
+            .text("response_format", "json");
+        // both servers detect the language when given none
+        if let [language] = languages {
+            form = form.text("language", language.as_lang_code());
+        }
+
+        let mut request = self
+            .client
+            .post(&self.url)
+            .timeout(REMOTE_TIMEOUT)
+            .multipart(form);
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let response: Value = request.send().await?.error_for_status()?.json().await?;
+        match response["text"].as_str() {
+            Some(text) => Ok(text.trim().to_string()),
+            None => anyhow::bail!("no text in the answer of {}", self.url),
+        }
+    }
+}
+
+impl TranscriptionProvider for RemoteTranscriptionProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn transcribe<'a>(
+        &'a self,
+        audio: &'a [f32],
+        sample_rate: u32,
+        _device: &'a str,
+        languages: &'a [Language],
+    ) -> TranscriptionFuture<'a> {
+        Box::pin(self.post(audio, sample_rate, languages))
+    }
+}
+
+/// Deepgram, batch through its REST api and streaming through its websocket.
+pub struct DeepgramProvider {
+    api_key: Option<String>,
+}
+
+impl DeepgramProvider {
+    pub fn new(api_key: Option<String>) -> Self {
+        Self { api_key }
+    }
+}
+
+impl TranscriptionProvider for DeepgramProvider {
+    fn name(&self) -> &str {
+        "deepgram"
+    }
+
+    fn transcribe<'a>(
+        &'a self,
+        audio: &'a [f32],
+        sample_rate: u32,
+        device: &'a str,
+        languages: &'a [Language],
+    ) -> TranscriptionFuture<'a> {
+        Box::pin(async move {
+            let api_key = self.api_key.clone().unwrap_or_default();
+            transcribe_with_deepgram(&api_key, audio, device, sample_rate, languages.to_vec()).await
+        })
+    }
+
+    fn supports_streaming(&self) -> bool {
+        true
+    }
+
+    fn stream(
+        &self,
+        audio: Receiver<Vec<f32>>,
+        device: Arc<AudioDevice>,
+        sample_rate: u32,
+        _languages: Vec<Language>,
+        is_running: Arc<AtomicBool>,
+    ) -> StreamFuture {
+        Box::pin(start_deepgram_stream(
+            audio,
+            device,
+            sample_rate,
+            is_running,
+            self.api_key.clone(),
+        ))
+    }
+}
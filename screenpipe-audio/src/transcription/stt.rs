@@ -5,6 +5,7 @@ use crate::speaker::embedding_manager::EmbeddingManager;
 use crate::speaker::prepare_segments;
 use crate::speaker::segment::SpeechSegment;
 use crate::transcription::deepgram::batch::transcribe_with_deepgram;
+use crate::transcription::provider::TranscriptionProvider;
 use crate::transcription::whisper::batch::process_with_whisper;
 use crate::utils::audio::resample;
 use crate::utils::ffmpeg::{get_new_file_path, write_audio_to_file};
@@ -102,6 +103,7 @@ pub async fn process_audio_input(
     languages: Vec<Language>,
     output_sender: &crossbeam::channel::Sender<TranscriptionResult>,
     whisper_context: Arc<WhisperContext>,
+    provider: Option<Arc<dyn TranscriptionProvider>>,
 ) -> Result<()> {
     let timestamp = SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -161,6 +163,7 @@ pub async fn process_audio_input(
                         path,
                         timestamp,
                         whisper_context.clone(),
+                        provider.clone(),
                     )
                 })
                 .await?
@@ -179,6 +182,7 @@ pub async fn process_audio_input(
                 path,
                 timestamp,
                 whisper_context.clone(),
+                provider.clone(),
             )
             .await?
         };
@@ -201,20 +205,31 @@ pub async fn run_stt(
     path: String,
     timestamp: u64,
     whisper_context: Arc<WhisperContext>,
+    provider: Option<Arc<dyn TranscriptionProvider>>,
 ) -> Result<TranscriptionResult> {
     let audio = segment.samples.clone();
     let sample_rate = segment.sample_rate;
-    match stt_sync(
-        &audio,
-        sample_rate,
-        &device.to_string(),
-        audio_transcription_engine.clone(),
-        deepgram_api_key.clone(),
-        languages.clone(),
-        whisper_context,
-    )
-    .await
-    {
+    let transcription = match &provider {
+        Some(provider) => {
+            provider
+                .transcribe(&audio, sample_rate, &device.to_string(), &languages)
+                .await
+        }
+        None => {
+            stt_sync(
+                &audio,
+                sample_rate,
+                &device.to_string(),
+                audio_transcription_engine.clone(),
+                deepgram_api_key.clone(),
+                languages.clone(),
+                whisper_context,
+            )
+            .await
+        }
+    };
+    let transcription_provider = provider.map(|provider| provider.name().to_string());
+    match transcription {
         Ok(transcription) => Ok(TranscriptionResult {
             input: AudioInput {
                 data: Arc::new(audio),
@@ -229,6 +244,7 @@ pub async fn run_stt(
             speaker_embedding: segment.embedding.clone(),
             start_time: segment.start,
             end_time: segment.end,
+            transcription_provider,
         }),
         Err(e) => {
             error!("STT error for input {}: {:?}", device, e);
@@ -246,6 +262,7 @@ pub async fn run_stt(
                 speaker_embedding: Vec::new(),
                 start_time: segment.start,
                 end_time: segment.end,
+                transcription_provider,
             })
         }
     }
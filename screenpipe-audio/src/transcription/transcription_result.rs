@@ -17,6 +17,8 @@ pub struct TranscriptionResult {
     pub error: Option<String>,
     pub start_time: f64,
     pub end_time: f64,
+    /// The provider that transcribed it, recorded instead of the engine
+    pub transcription_provider: Option<String>,
 }
 
 impl TranscriptionResult {
@@ -64,7 +66,10 @@ pub async fn process_transcription_result(
     info!("Detected speaker: {:?}", speaker);
 
     let transcription = result.transcription.unwrap();
-    let transcription_engine = audio_transcription_engine.to_string();
+    let transcription_engine = result
+        .transcription_provider
+        .clone()
+        .unwrap_or_else(|| audio_transcription_engine.to_string());
     let mut chunk_id: Option<i64> = None;
 
     info!(
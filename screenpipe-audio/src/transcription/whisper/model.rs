@@ -1,20 +1,17 @@
 use crate::core::engine::AudioTranscriptionEngine;
 use anyhow::Result;
-use hf_hub::{api::sync::Api, Repo, RepoType};
+use hf_hub::{api::sync::Api, Cache, Repo, RepoType};
+use screenpipe_core::egress;
 use std::{path::PathBuf, sync::Arc};
 use tracing::info;
 use whisper_rs::WhisperContextParameters;
 
 pub fn download_whisper_model(engine: Arc<AudioTranscriptionEngine>) -> Result<PathBuf> {
-    let api = Api::new()?;
     let repo = Repo::with_revision(
         "ggerganov/whisper.cpp".to_string(),
         RepoType::Model,
         "main".to_string(),
     );
-    let api_repo = api.repo(repo);
-
-    info!("downloading model {:?}", engine);
     let model_name = match *engine {
         AudioTranscriptionEngine::WhisperLargeV3Turbo => "ggml-large-v3-turbo.bin",
         AudioTranscriptionEngine::WhisperTiny => "ggml-tiny.bin",
@@ -24,6 +21,20 @@ pub fn download_whisper_model(engine: Arc<AudioTranscriptionEngine>) -> Result<P
         _ => "ggml-large-v3-turbo-q8_0.bin",
     };
 
+    // local-only mode uses what an earlier run downloaded
+    if egress::local_only() {
+        return Cache::default().repo(repo).get(model_name).ok_or_else(|| {
+            anyhow::anyhow!(
+                "local-only mode: whisper model {} is not downloaded, run once without --local-only to download it",
+                model_name
+            )
+        });
+    }
+
+    let api = Api::new()?;
+    let api_repo = api.repo(repo);
+
+    info!("downloading model {:?}", engine);
     let model = api_repo.get(model_name)?;
 
     info!("model downloaded {}", model_name);
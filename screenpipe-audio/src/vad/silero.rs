@@ -1,5 +1,6 @@
 use anyhow;
 use dirs;
+use screenpipe_core::egress;
 use std::collections::VecDeque;
 use std::path::PathBuf;
 use tracing::debug;
@@ -49,6 +50,12 @@ impl SileroVad {
             return Ok(path);
         }
 
+        if egress::local_only() {
+            anyhow::bail!(
+                "local-only mode: silero vad model is not downloaded, run once without --local-only to download it"
+            );
+        }
+
         DOWNLOAD_ONCE.call_once(|| {
             tokio::spawn(async move {
                 if let Err(e) = Self::download_model().await {
@@ -70,7 +77,7 @@ impl SileroVad {
         debug!("downloading silerovad model...");
         let url =
             "https://github.com/k2-fsa/sherpa-onnx/releases/download/asr-models/silero_vad.onnx";
-        let response = reqwest::get(url).await?;
+        let response = egress::http_client().get(url).send().await?;
         let model_data = response.bytes().await?;
 
         let cache_dir = Self::get_cache_dir()?;
@@ -2,7 +2,7 @@ use anyhow::Error as E;
 use candle::{DType, Device, Module, Tensor};
 use candle_nn::VarBuilder;
 use candle_transformers::models::jina_bert::{BertModel, Config, PositionEmbeddingType};
-use hf_hub::{api::sync::Api, Repo, RepoType};
+use hf_hub::{api::sync::Api, Cache, Repo, RepoType};
 use tokenizers::Tokenizer;
 pub struct EmbeddingModel {
     model: BertModel,
@@ -17,12 +17,23 @@ impl EmbeddingModel {
 
         // default to jina-embeddings-v2-base-en if no paths provided
         let (model_path, tokenizer_path) = if model_path.is_none() || tokenizer_path.is_none() {
-            let api = Api::new()?;
-            let repo = api.repo(Repo::new(
+            let repo = Repo::new(
                 "jinaai/jina-embeddings-v2-base-en".to_string(),
                 RepoType::Model,
-            ));
-            (repo.get("model.safetensors")?, repo.get("tokenizer.json")?)
+            );
+            if crate::egress::local_only() {
+                // only what an earlier run downloaded
+                let cached = Cache::default().repo(repo);
+                match (cached.get("model.safetensors"), cached.get("tokenizer.json")) {
+                    (Some(model), Some(tokenizer)) => (model, tokenizer),
+                    _ => anyhow::bail!(
+                        "local-only mode: embedding model is not downloaded, run once without --local-only to download it"
+                    ),
+                }
+            } else {
+                let repo = Api::new()?.repo(repo);
+                (repo.get("model.safetensors")?, repo.get("tokenizer.json")?)
+            }
         } else {
             (
                 std::path::PathBuf::from(model_path.unwrap()),
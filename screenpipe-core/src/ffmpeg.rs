@@ -137,12 +137,13 @@ fn handle_ffmpeg_installation() -> Result<(), anyhow::Error> {
     }
 
     info!("ffmpeg not found. installing...");
+    let download_url = ffmpeg_download_url()?;
+    crate::egress::check_egress(download_url)?;
     match check_latest_version() {
         Ok(version) => debug!("latest version: {}", version),
         Err(e) => debug!("skipping version check due to error: {e}"),
     }
 
-    let download_url = ffmpeg_download_url()?;
     let destination = get_ffmpeg_install_dir()?;
 
     info!("downloading from: {:?}", download_url);
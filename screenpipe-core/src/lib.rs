@@ -1,3 +1,4 @@
+pub mod egress;
 pub mod ffmpeg;
 pub use ffmpeg::find_ffmpeg_path;
 #[cfg(feature = "llm")]
@@ -24,13 +25,19 @@ pub mod pipes;
 pub use pipes::*;
 mod language;
 #[cfg(feature = "security")]
+pub mod media_crypto;
+#[cfg(feature = "security")]
 pub mod pii_removal;
+#[cfg(feature = "security")]
+pub mod secrets;
 
 #[cfg(feature = "mkl")]
 extern crate intel_mkl_src;
 
 #[cfg(feature = "security")]
 pub use pii_removal::*;
+#[cfg(feature = "security")]
+pub use secrets::*;
 
 pub mod network;
 pub use network::*;
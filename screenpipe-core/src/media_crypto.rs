@@ -0,0 +1,300 @@
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+/// First bytes of an encrypted media file, followed by the nonce prefix and
+/// the encrypted segments.
+pub const MAGIC: &[u8; 8] = b"SPENC\x00\x00\x01";
+/// Plaintext bytes per segment, each segment is encrypted and authenticated
+/// on its own so files are never held in memory whole.
+pub const SEGMENT_SIZE: usize = 1 << 20;
+
+const KEY_LEN: usize = 32;
+const NONCE_PREFIX_LEN: usize = 7;
+const TAG_LEN: usize = 16;
+
+/// The AES-256 key media files are encrypted with, written as 64 hex
+/// characters in key files.
+#[derive(Clone)]
+pub struct MediaKey([u8; KEY_LEN]);
+
+impl MediaKey {
+    pub fn generate() -> Self {
+        let mut key = [0u8; KEY_LEN];
+        OsRng.fill_bytes(&mut key);
+        Self(key)
+    }
+
+    pub fn to_hex(&self) -> String {
+        self.0.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.0))
+    }
+}
+
+impl FromStr for MediaKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.len() != KEY_LEN * 2 || !s.is_ascii() {
+            return Err(format!("media key must be {} hex characters", KEY_LEN * 2));
+        }
+        let mut key = [0u8; KEY_LEN];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16)
+                .map_err(|_| "media key is not hex".to_string())?;
+        }
+        Ok(Self(key))
+    }
+}
+
+// keys stay out of logs
+impl fmt::Debug for MediaKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MediaKey(..)")
+    }
+}
+
+// STREAM construction: the nonce of a segment is the random prefix of the
+// file, the segment's index and whether it's the last one, so segments can't
+// be reordered, dropped or the file cut short without decryption failing
+fn segment_nonce(prefix: &[u8; NONCE_PREFIX_LEN], index: u32, last: bool) -> [u8; 12] {
+    let mut nonce = [0u8; 12];
+    nonce[..NONCE_PREFIX_LEN].copy_from_slice(prefix);
+    nonce[NONCE_PREFIX_LEN..11].copy_from_slice(&index.to_be_bytes());
+    nonce[11] = last as u8;
+    nonce
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+// reads until `buf` is full or the reader is done
+fn fill(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Whether `header`, the first bytes of a file, starts an encrypted file.
+pub fn is_encrypted(header: &[u8]) -> bool {
+    header.starts_with(MAGIC)
+}
+
+pub fn is_encrypted_file(path: &Path) -> io::Result<bool> {
+    let mut header = [0u8; MAGIC.len()];
+    let read = fill(&mut File::open(path)?, &mut header)?;
+    Ok(is_encrypted(&header[..read]))
+}
+
+pub fn encrypt_stream(
+    key: &MediaKey,
+    mut reader: impl Read,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    let cipher = key.cipher();
+    let mut prefix = [0u8; NONCE_PREFIX_LEN];
+    OsRng.fill_bytes(&mut prefix);
+    writer.write_all(MAGIC)?;
+    writer.write_all(&prefix)?;
+
+    let mut current = vec![0u8; SEGMENT_SIZE];
+    let mut next = vec![0u8; SEGMENT_SIZE];
+    let mut len = fill(&mut reader, &mut current)?;
+    let mut index = 0u32;
+    loop {
+        // a full segment is the last one only when nothing follows it
+        let next_len = if len == SEGMENT_SIZE {
+            fill(&mut reader, &mut next)?
+        } else {
+            0
+        };
+        let last = next_len == 0;
+        let nonce = segment_nonce(&prefix, index, last);
+        let segment = cipher
+            .encrypt(Nonce::from_slice(&nonce), &current[..len])
+            .map_err(|_| invalid_data("failed to encrypt media segment"))?;
+        writer.write_all(&segment)?;
+        if last {
+            break;
+        }
+        std::mem::swap(&mut current, &mut next);
+        len = next_len;
+        index = index
+            .checked_add(1)
+            .ok_or_else(|| invalid_data("media file is too large to encrypt"))?;
+    }
+    writer.flush()
+}
+
+/// Fails with `InvalidData` on a wrong key and on files tampered with or cut
+/// short, after writing the segments decrypted so far.
+pub fn decrypt_stream(
+    key: &MediaKey,
+    mut reader: impl Read,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    let cipher = key.cipher();
+    let mut header = [0u8; MAGIC.len() + NONCE_PREFIX_LEN];
+    if fill(&mut reader, &mut header)? < header.len() || !is_encrypted(&header) {
+        return Err(invalid_data("not an encrypted media file"));
+    }
+    let mut prefix = [0u8; NONCE_PREFIX_LEN];
+    prefix.copy_from_slice(&header[MAGIC.len()..]);
+
+    let mut current = vec![0u8; SEGMENT_SIZE + TAG_LEN];
+    let mut next = vec![0u8; SEGMENT_SIZE + TAG_LEN];
+    let mut len = fill(&mut reader, &mut current)?;
+    let mut index = 0u32;
+    loop {
+        let next_len = if len == current.len() {
+            fill(&mut reader, &mut next)?
+        } else {
+            0
+        };
+        let last = next_len == 0;
+        let nonce = segment_nonce(&prefix, index, last);
+        let segment = cipher
+            .decrypt(Nonce::from_slice(&nonce), &current[..len])
+            .map_err(|_| invalid_data("wrong media key or corrupted media file"))?;
+        writer.write_all(&segment)?;
+        if last {
+            break;
+        }
+        std::mem::swap(&mut current, &mut next);
+        len = next_len;
+        index = index
+            .checked_add(1)
+            .ok_or_else(|| invalid_data("corrupted media file"))?;
+    }
+    writer.flush()
+}
+
+/// Encrypts the file at `path` in place, through a temporary file next to it
+/// renamed over it, so the file is never half encrypted. Returns false for
+/// files encrypted already.
+pub fn encrypt_file(key: &MediaKey, path: &Path) -> io::Result<bool> {
+    if is_encrypted_file(path)? {
+        return Ok(false);
+    }
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut encrypted = tempfile::NamedTempFile::new_in(dir)?;
+    encrypt_stream(
+        key,
+        BufReader::new(File::open(path)?),
+        BufWriter::new(encrypted.as_file_mut()),
+    )?;
+    encrypted.as_file().sync_all()?;
+    encrypted.persist(path).map_err(|e| e.error)?;
+    Ok(true)
+}
+
+/// Decrypts the file at `path` to `output`, which is removed when decryption
+/// fails.
+pub fn decrypt_file(key: &MediaKey, path: &Path, output: &Path) -> io::Result<()> {
+    let result = decrypt_stream(
+        key,
+        BufReader::new(File::open(path)?),
+        BufWriter::new(File::create(output)?),
+    );
+    if result.is_err() {
+        let _ = std::fs::remove_file(output);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encrypt(key: &MediaKey, plaintext: &[u8]) -> Vec<u8> {
+        let mut encrypted = Vec::new();
+        encrypt_stream(key, plaintext, &mut encrypted).unwrap();
+        encrypted
+    }
+
+    fn decrypt(key: &MediaKey, encrypted: &[u8]) -> io::Result<Vec<u8>> {
+        let mut decrypted = Vec::new();
+        decrypt_stream(key, encrypted, &mut decrypted)?;
+        Ok(decrypted)
+    }
+
+    #[test]
+    fn test_media_encryption_roundtrip() {
+        let key = MediaKey::generate();
+        for len in [0, 1, 1000, SEGMENT_SIZE, 2 * SEGMENT_SIZE + 5] {
+            let plaintext: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+            let encrypted = encrypt(&key, &plaintext);
+            assert!(is_encrypted(&encrypted));
+            assert_ne!(&encrypted[MAGIC.len() + NONCE_PREFIX_LEN..], &plaintext[..]);
+            assert_eq!(
+                decrypt(&key, &encrypted).unwrap(),
+                plaintext,
+                "{} bytes",
+                len
+            );
+        }
+
+        let hex = key.to_hex();
+        let parsed: MediaKey = hex.parse().unwrap();
+        assert_eq!(parsed.to_hex(), hex);
+        assert!("abc".parse::<MediaKey>().is_err());
+        assert!("zz".repeat(KEY_LEN).parse::<MediaKey>().is_err());
+    }
+
+    #[test]
+    fn test_media_encryption_detects_tampering() {
+        let key = MediaKey::generate();
+        let plaintext = vec![7u8; SEGMENT_SIZE + 10];
+        let encrypted = encrypt(&key, &plaintext);
+
+        assert!(decrypt(&MediaKey::generate(), &encrypted).is_err());
+
+        let mut flipped = encrypted.clone();
+        flipped[MAGIC.len() + NONCE_PREFIX_LEN + 3] ^= 1;
+        assert!(decrypt(&key, &flipped).is_err());
+
+        // cut after the first segment, which isn't marked as the last one
+        let first_segment = MAGIC.len() + NONCE_PREFIX_LEN + SEGMENT_SIZE + TAG_LEN;
+        assert!(decrypt(&key, &encrypted[..first_segment]).is_err());
+
+        assert!(decrypt(&key, &plaintext).is_err());
+    }
+
+    #[test]
+    fn test_encrypt_file_in_place() {
+        let key = MediaKey::generate();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("monitor_1_2025-03-26_09-00-00.mp4");
+        std::fs::write(&path, b"not really an mp4").unwrap();
+
+        assert!(encrypt_file(&key, &path).unwrap());
+        assert!(is_encrypted_file(&path).unwrap());
+        // encrypting twice leaves the file as it is
+        assert!(!encrypt_file(&key, &path).unwrap());
+
+        let output = dir.path().join("plain.mp4");
+        decrypt_file(&key, &path, &output).unwrap();
+        assert_eq!(std::fs::read(&output).unwrap(), b"not really an mp4");
+
+        decrypt_file(&MediaKey::generate(), &path, &output).unwrap_err();
+        assert!(!output.exists());
+    }
+}
@@ -633,9 +633,23 @@ impl AccessibilityEngine for MacOSEngine {
 
                 Ok(ui_elements)
             }
-            Selector::Attributes(_attrs) => Err(AutomationError::UnsupportedOperation(
-                "Attributes selector not implemented for find_elements".to_string(),
-            )),
+            Selector::Attributes(_) => {
+                let selector = selector.clone();
+                let collector = ElementsCollectorWithWindows::new(start_element, move |e| {
+                    let role = e.role().unwrap_or(CFString::new("")).to_string();
+                    selector.matches_attributes(&role, element_subrole(e).as_deref())
+                });
+
+                let ax_ui_elements = collector.find_all();
+
+                // Convert AXUIElements to UIElements
+                let ui_elements = ax_ui_elements
+                    .into_iter()
+                    .map(|e| self.wrap_element(ThreadSafeAXUIElement::new(e)))
+                    .collect();
+
+                Ok(ui_elements)
+            }
             Selector::Path(_) => Err(AutomationError::UnsupportedOperation(
                 "Path selector not implemented for find_elements".to_string(),
             )),
@@ -1676,6 +1690,13 @@ fn parse_ax_attribute_value(
 }
 
 // Add this helper function after the selector handler
+fn element_subrole(e: &AXUIElement) -> Option<String> {
+    e.attribute(&AXAttribute::new(&CFString::new("AXSubrole")))
+        .ok()
+        .and_then(|value| value.downcast_into::<CFString>())
+        .map(|subrole| subrole.to_string())
+}
+
 fn element_contains_text(e: &AXUIElement, text: &str) -> bool {
     // Check immediate element attributes for text
     let contains_in_value = e
@@ -21,6 +21,24 @@ pub enum Selector {
     Chain(Vec<Selector>),
 }
 
+impl Selector {
+    /// Whether an element with `role` and `subrole` has all the attributes of
+    /// an `Attributes` selector, "AXRole" and "AXSubrole" being the ones known.
+    /// macOS tells some elements apart by subrole only: a password field is an
+    /// AXTextField with the AXSecureTextField subrole.
+    pub fn matches_attributes(&self, role: &str, subrole: Option<&str>) -> bool {
+        let Selector::Attributes(attributes) = self else {
+            return false;
+        };
+        !attributes.is_empty()
+            && attributes.iter().all(|(key, value)| match key.as_str() {
+                "AXRole" => role == value,
+                "AXSubrole" => subrole == Some(value.as_str()),
+                _ => false,
+            })
+    }
+}
+
 impl From<&str> for Selector {
     fn from(s: &str) -> Self {
         // Make common UI roles like "window", "button", etc. default to Role selectors
@@ -31,7 +31,6 @@ use http_cache_reqwest::{CACacheManager, Cache, CacheMode, HttpCache, HttpCacheO
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
 use reqwest_middleware::reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
-use reqwest_middleware::reqwest::Client;
 use reqwest_middleware::ClientBuilder;
 use std::collections::HashSet;
 use std::str::FromStr;
@@ -363,10 +362,19 @@ async fn spawn_watchdog(parent_pid: u32, child_pid: u32) -> Result<tokio::proces
     Ok(child)
 }
 
+// pipes install packages from npm and run code free to call anything
+fn ensure_pipes_allowed() -> Result<()> {
+    if crate::egress::local_only() {
+        anyhow::bail!("local-only mode: pipes are disabled, they reach the network");
+    }
+    Ok(())
+}
+
 pub async fn run_pipe(
     pipe: &str,
     screenpipe_dir: PathBuf,
 ) -> Result<(tokio::process::Child, PipeState)> {
+    ensure_pipes_allowed()?;
     let bun_path = find_bun_path().ok_or_else(|| {
         let err = anyhow::anyhow!("bun not found");
         sentry::capture_error(&err.source().unwrap());
@@ -814,6 +822,7 @@ async fn retry_install(bun_path: &Path, dest_dir: &Path, max_retries: u32) -> Re
 }
 
 pub async fn download_pipe(source: &str, screenpipe_dir: PathBuf) -> anyhow::Result<PathBuf> {
+    ensure_pipes_allowed()?;
     info!("Processing pipe from source: {}", source);
 
     let pipe_name = sanitize_pipe_name(Path::new(source).to_str().unwrap());
@@ -1036,7 +1045,7 @@ fn download_github_folder(
 
     Box::pin(async move {
         // Create a cached client
-        let client = ClientBuilder::new(Client::new())
+        let client = ClientBuilder::new(crate::egress::http_client())
             .with(Cache(HttpCache {
                 mode: CacheMode::Default,
                 manager: CACacheManager {
@@ -1388,7 +1397,7 @@ async fn run_cron_schedule(
         }
     };
 
-    let client = Client::new();
+    let client = crate::egress::http_client();
     let mut headers = HeaderMap::new();
     headers.insert(
         AUTHORIZATION,
@@ -1622,6 +1631,7 @@ pub async fn download_pipe_private(
     source: &str,
     screenpipe_dir: PathBuf,
 ) -> anyhow::Result<PathBuf> {
+    ensure_pipes_allowed()?;
     info!("processing private pipe from zip: {}", source);
 
     let dest_dir = screenpipe_dir.join("pipes").join(&pipe_name);
@@ -1689,7 +1699,7 @@ pub async fn download_pipe_private(
 
     // Download zip file
     debug!("downloading zip file from: {}", source);
-    let client = Client::new();
+    let client = crate::egress::http_client();
     let response = match client.get(source).send().await {
         Ok(res) => res,
         Err(e) => {
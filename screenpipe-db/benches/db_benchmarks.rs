@@ -2,7 +2,7 @@
 
 use criterion::{criterion_group, criterion_main, Criterion};
 use rand::Rng;
-use screenpipe_db::{AudioDevice, ContentType, DatabaseManager, DeviceType, OcrEngine};
+use screenpipe_db::{AudioDevice, ContentType, DatabaseManager, DeviceType, OcrEngine, TagFilter};
 use std::sync::Arc;
 use tokio::runtime::Runtime;
 
@@ -86,6 +86,9 @@ fn bench_search(c: &mut Criterion) {
                                 None,
                                 None,
                                 None,
+                                None,
+                                false,
+                                &TagFilter::default(),
                             )
                             .await
                             .unwrap()
@@ -0,0 +1,137 @@
+// cargo bench --bench hot_path_benchmarks
+//
+// latency of the recording and search hot paths with many of them running at
+// once, as when several monitors are recorded while pipes search
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use futures::future::join_all;
+use screenpipe_db::{AudioDevice, ContentType, DatabaseManager, DeviceType, OcrEngine, TagFilter};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+const APPS: [&str; 4] = ["Code", "Arc", "Slack", "zoom.us"];
+
+async fn setup_db(frames: usize) -> Arc<DatabaseManager> {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    db.insert_video_chunk("video.mp4", "monitor_1")
+        .await
+        .unwrap();
+    let audio_chunk_id = db.insert_audio_chunk("audio.mp4").await.unwrap();
+    let device = AudioDevice {
+        name: "mic".to_string(),
+        device_type: DeviceType::Input,
+    };
+    for i in 0..frames {
+        let frame_id = db
+            .insert_frame("monitor_1", None, None, Some(APPS[i % 4]), None, true)
+            .await
+            .unwrap();
+        let text = format!("meeting notes {} about the quarterly budget", i);
+        db.insert_ocr_text(frame_id, &text, "", Arc::new(OcrEngine::Tesseract))
+            .await
+            .unwrap();
+        if i % 10 == 0 {
+            db.insert_audio_transcription(
+                audio_chunk_id,
+                &text,
+                i as i64,
+                "",
+                &device,
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+    }
+    db
+}
+
+async fn search(db: &DatabaseManager, i: usize) {
+    let (query, content_type, app_name) = match i % 4 {
+        0 => ("budget", ContentType::OCR, None),
+        1 => ("", ContentType::OCR, Some(APPS[i % 4])),
+        2 => ("notes", ContentType::Audio, None),
+        _ => ("quarterly", ContentType::All, None),
+    };
+    db.search(
+        query,
+        content_type,
+        20,
+        0,
+        None,
+        None,
+        app_name,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        false,
+        &TagFilter::default(),
+    )
+    .await
+    .unwrap();
+}
+
+fn bench_hot_paths(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let db = rt.block_on(setup_db(10_000));
+
+    let mut group = c.benchmark_group("hot_paths_under_load");
+    group.sample_size(20);
+    group.measurement_time(Duration::from_secs(20));
+
+    for concurrency in [1, 8, 32] {
+        group.bench_with_input(
+            BenchmarkId::new("search", concurrency),
+            &concurrency,
+            |b, &concurrency| {
+                b.to_async(&rt)
+                    .iter(|| join_all((0..concurrency).map(|i| search(&db, i))));
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("insert_frame_and_ocr_text", concurrency),
+            &concurrency,
+            |b, &concurrency| {
+                b.to_async(&rt).iter(|| {
+                    join_all((0..concurrency).map(|i| {
+                        let db = db.clone();
+                        async move {
+                            let frame_id = db
+                                .insert_frame(
+                                    "monitor_1",
+                                    None,
+                                    None,
+                                    Some(APPS[i % 4]),
+                                    None,
+                                    true,
+                                )
+                                .await
+                                .unwrap();
+                            db.insert_ocr_text(
+                                frame_id,
+                                "new frame text",
+                                "",
+                                Arc::new(OcrEngine::Tesseract),
+                            )
+                            .await
+                            .unwrap();
+                        }
+                    }))
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_hot_paths);
+criterion_main!(benches);
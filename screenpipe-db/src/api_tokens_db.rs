@@ -0,0 +1,77 @@
+use chrono::{Duration, Utc};
+
+use crate::{ApiToken, DatabaseManager};
+
+const LAST_USED_RESOLUTION_SECS: i64 = 60;
+
+impl DatabaseManager {
+    pub async fn insert_api_token(
+        &self,
+        name: &str,
+        token_hash: &str,
+        scopes: &str,
+    ) -> Result<ApiToken, sqlx::Error> {
+        sqlx::query_as::<_, ApiToken>(
+            r#"
+            INSERT INTO api_tokens (name, token_hash, scopes, created_at)
+            VALUES (?1, ?2, ?3, ?4)
+            RETURNING id, name, scopes, created_at, last_used_at
+            "#,
+        )
+        .bind(name)
+        .bind(token_hash)
+        .bind(scopes)
+        .bind(Utc::now())
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn list_api_tokens(&self) -> Result<Vec<ApiToken>, sqlx::Error> {
+        sqlx::query_as::<_, ApiToken>(
+            "SELECT id, name, scopes, created_at, last_used_at FROM api_tokens ORDER BY id",
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Looks a token up by its hash and records that it was used. The use is
+    /// written at most once a minute, not on every request.
+    pub async fn use_api_token(&self, token_hash: &str) -> Result<Option<ApiToken>, sqlx::Error> {
+        let Some(mut token) = sqlx::query_as::<_, ApiToken>(
+            r#"
+            SELECT id, name, scopes, created_at, last_used_at
+            FROM api_tokens WHERE token_hash = ?1
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        let now = Utc::now();
+        let stale = token.last_used_at.map_or(true, |used| {
+            now - used >= Duration::seconds(LAST_USED_RESOLUTION_SECS)
+        });
+        if stale {
+            sqlx::query("UPDATE api_tokens SET last_used_at = ?2 WHERE id = ?1")
+                .bind(token.id)
+                .bind(now)
+                .execute(&self.pool)
+                .await?;
+            token.last_used_at = Some(now);
+        }
+        Ok(Some(token))
+    }
+
+    /// Returns whether the token existed.
+    pub async fn delete_api_token(&self, id: i64) -> Result<bool, sqlx::Error> {
+        let deleted = sqlx::query("DELETE FROM api_tokens WHERE id = ?1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        Ok(deleted > 0)
+    }
+}
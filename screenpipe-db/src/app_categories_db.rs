@@ -0,0 +1,85 @@
+use chrono::Utc;
+
+use crate::{AppCategoryRule, DatabaseManager, NewAppCategoryRule};
+
+pub const APP_CATEGORY_KINDS: &[&str] = &["app", "window", "url"];
+
+pub const APP_CATEGORIES: &[&str] = &["deep_work", "communication", "distraction"];
+
+const APP_CATEGORY_RULE_COLUMNS: &str = "id, kind, pattern, category, source, created_at";
+
+impl DatabaseManager {
+    /// Adds a rule, or changes the category of the one added at the api
+    /// with the same kind and pattern.
+    pub async fn insert_app_category_rule(
+        &self,
+        rule: &NewAppCategoryRule,
+    ) -> Result<AppCategoryRule, sqlx::Error> {
+        sqlx::query_as::<_, AppCategoryRule>(&format!(
+            r#"
+            INSERT INTO app_categories (kind, pattern, category, source, created_at)
+            VALUES (?1, ?2, ?3, 'api', ?4)
+            ON CONFLICT(kind, pattern, source) DO UPDATE SET category = excluded.category
+            RETURNING {APP_CATEGORY_RULE_COLUMNS}
+            "#
+        ))
+        .bind(&rule.kind)
+        .bind(rule.pattern.trim())
+        .bind(&rule.category)
+        .bind(Utc::now())
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn list_app_category_rules(&self) -> Result<Vec<AppCategoryRule>, sqlx::Error> {
+        sqlx::query_as::<_, AppCategoryRule>(&format!(
+            "SELECT {APP_CATEGORY_RULE_COLUMNS} FROM app_categories ORDER BY id"
+        ))
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Returns whether a rule added at the api had this id, the ones of the
+    /// command line are back at each start.
+    pub async fn delete_app_category_rule(&self, id: i64) -> Result<bool, sqlx::Error> {
+        let deleted = sqlx::query("DELETE FROM app_categories WHERE id = ?1 AND source = 'api'")
+            .bind(id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        Ok(deleted > 0)
+    }
+
+    /// Replaces the rules of the command line, leaving out the ones with an
+    /// empty pattern.
+    pub async fn set_config_app_category_rules(
+        &self,
+        rules: &[NewAppCategoryRule],
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM app_categories WHERE source = 'config'")
+            .execute(&mut *tx)
+            .await?;
+        let now = Utc::now();
+        for rule in rules {
+            if rule.pattern.trim().is_empty() {
+                continue;
+            }
+            // the last of the same kind and pattern wins
+            sqlx::query(
+                r#"
+                INSERT INTO app_categories (kind, pattern, category, source, created_at)
+                VALUES (?1, ?2, ?3, 'config', ?4)
+                ON CONFLICT(kind, pattern, source) DO UPDATE SET category = excluded.category
+                "#,
+            )
+            .bind(&rule.kind)
+            .bind(rule.pattern.trim())
+            .bind(&rule.category)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await
+    }
+}
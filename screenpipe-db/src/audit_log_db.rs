@@ -0,0 +1,62 @@
+use chrono::{DateTime, Utc};
+
+use crate::{AuditEntry, DatabaseManager};
+
+const AUDIT_ENTRY_COLUMNS: &str = "id, timestamp, actor, action, target, rows_affected";
+
+impl DatabaseManager {
+    pub async fn insert_audit_entry(
+        &self,
+        actor: &str,
+        action: &str,
+        target: &str,
+        rows_affected: Option<i64>,
+    ) -> Result<AuditEntry, sqlx::Error> {
+        sqlx::query_as::<_, AuditEntry>(&format!(
+            r#"
+            INSERT INTO audit_log (timestamp, actor, action, target, rows_affected)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            RETURNING {AUDIT_ENTRY_COLUMNS}
+            "#
+        ))
+        .bind(Utc::now())
+        .bind(actor)
+        .bind(action)
+        .bind(target)
+        .bind(rows_affected)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Newest first.
+    pub async fn list_audit_log(
+        &self,
+        action: Option<&str>,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<AuditEntry>, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        guard
+            .run(
+                sqlx::query_as::<_, AuditEntry>(&format!(
+                    r#"
+                    SELECT {AUDIT_ENTRY_COLUMNS} FROM audit_log
+                    WHERE (?1 IS NULL OR action = ?1)
+                        AND (?2 IS NULL OR timestamp >= ?2)
+                        AND (?3 IS NULL OR timestamp <= ?3)
+                    ORDER BY id DESC
+                    LIMIT ?4 OFFSET ?5
+                    "#
+                ))
+                .bind(action)
+                .bind(start_time)
+                .bind(end_time)
+                .bind(limit)
+                .bind(offset)
+                .fetch_all(&mut *conn),
+            )
+            .await
+    }
+}
@@ -0,0 +1,419 @@
+use std::future::Future;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{Duration as ChronoDuration, Utc};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::embedding_index_db::DEFAULT_FLOAT_CACHE_SIZE;
+use crate::{
+    AudioDevice, ContentType, DatabaseManager, DeviceType, EmbeddingQuantization, NewFrame,
+    OcrEngine, SearchResult, TagFilter, OCR_TEXT_EMBEDDINGS,
+};
+
+const APPS: &[&str] = &["Code", "Chrome", "Slack", "Terminal", "Notion", "Zoom"];
+const WORDS: &[&str] = &[
+    "meeting",
+    "invoice",
+    "deploy",
+    "release",
+    "budget",
+    "roadmap",
+    "customer",
+    "ticket",
+    "review",
+    "branch",
+    "merge",
+    "error",
+    "warning",
+    "database",
+    "index",
+    "query",
+    "latency",
+    "dashboard",
+    "report",
+    "quarter",
+    "design",
+    "sprint",
+    "backlog",
+    "feature",
+    "payment",
+    "account",
+    "settings",
+    "profile",
+    "message",
+    "channel",
+    "thread",
+    "calendar",
+    "schedule",
+    "project",
+    "document",
+    "spreadsheet",
+    "server",
+    "client",
+    "network",
+    "request",
+    "response",
+    "timeout",
+    "memory",
+    "storage",
+    "screen",
+    "window",
+    "browser",
+    "search",
+    "result",
+    "email",
+];
+const DEVICE: &str = "monitor_1";
+const WORDS_PER_FRAME: usize = 60;
+const WORDS_PER_TRANSCRIPTION: usize = 25;
+const SEARCH_LIMIT: u32 = 20;
+
+/// One dataset and database setup to measure.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchConfig {
+    pub frames: u32,
+    pub transcriptions: u32,
+    pub embeddings: u32,
+    pub embedding_dimensions: usize,
+    pub quantization: EmbeddingQuantization,
+    /// frames per `insert_frames_batch` call, 1 inserts each frame and its
+    /// text apart, like `insert_frame` and `insert_ocr_text`
+    pub batch_size: usize,
+    /// runs of each search, to take percentiles from
+    pub searches: u32,
+    pub seed: u64,
+}
+
+impl BenchConfig {
+    pub fn name(&self) -> String {
+        format!(
+            "{} frames, batch {}, {} embeddings",
+            self.frames,
+            self.batch_size,
+            self.quantization.as_str()
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InsertThroughput {
+    pub name: String,
+    pub rows: u64,
+    pub rows_per_sec: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryLatency {
+    pub name: String,
+    pub runs: u32,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+}
+
+/// What `run_bench` measured for a config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchReport {
+    pub name: String,
+    pub config: BenchConfig,
+    pub database_bytes: u64,
+    pub inserts: Vec<InsertThroughput>,
+    pub queries: Vec<QueryLatency>,
+}
+
+fn sentence(rng: &mut StdRng, words: usize) -> String {
+    (0..words)
+        .map(|_| *WORDS.choose(rng).unwrap())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn random_embedding(rng: &mut StdRng, dimensions: usize) -> Vec<f32> {
+    (0..dimensions).map(|_| rng.gen_range(-1.0..1.0)).collect()
+}
+
+fn throughput(name: &str, rows: u64, elapsed: Duration) -> InsertThroughput {
+    InsertThroughput {
+        name: name.to_string(),
+        rows,
+        rows_per_sec: rows as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+    }
+}
+
+/// Nearest rank percentile of sorted durations, in milliseconds.
+pub fn percentile_ms(sorted: &[Duration], percentile: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (percentile / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1].as_secs_f64() * 1000.0
+}
+
+async fn measure<F, Fut, T>(
+    name: &str,
+    runs: u32,
+    mut query: F,
+) -> Result<QueryLatency, sqlx::Error>
+where
+    F: FnMut(u32) -> Fut,
+    Fut: Future<Output = Result<T, sqlx::Error>>,
+{
+    let mut durations = Vec::with_capacity(runs as usize);
+    for run in 0..runs {
+        let started = Instant::now();
+        query(run).await?;
+        durations.push(started.elapsed());
+    }
+    durations.sort();
+    Ok(QueryLatency {
+        name: name.to_string(),
+        runs,
+        p50_ms: percentile_ms(&durations, 50.0),
+        p95_ms: percentile_ms(&durations, 95.0),
+        max_ms: percentile_ms(&durations, 100.0),
+    })
+}
+
+async fn search(
+    db: &DatabaseManager,
+    query: &str,
+    content_type: ContentType,
+    app_name: Option<&str>,
+) -> Result<Vec<SearchResult>, sqlx::Error> {
+    db.search(
+        query,
+        content_type,
+        SEARCH_LIMIT,
+        0,
+        None,
+        None,
+        app_name,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        false,
+        &TagFilter::default(),
+    )
+    .await
+}
+
+fn remove_database(path: &Path) {
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", path.to_string_lossy(), suffix));
+    }
+}
+
+/// Fills a new database in `dir` with synthetic frames, text, transcriptions
+/// and embeddings as `config` says, measuring how fast they go in, then how
+/// long the searches take on it. The database is removed afterwards.
+pub async fn run_bench(dir: &Path, config: &BenchConfig) -> Result<BenchReport, sqlx::Error> {
+    let path = dir.join(format!("bench-{}.sqlite", std::process::id()));
+    remove_database(&path);
+    let result = bench_database(&path, config).await;
+    remove_database(&path);
+    result
+}
+
+async fn bench_database(path: &Path, config: &BenchConfig) -> Result<BenchReport, sqlx::Error> {
+    let db = DatabaseManager::new(&path.to_string_lossy()).await?;
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut inserts = Vec::new();
+    db.set_embedding_index_settings(
+        OCR_TEXT_EMBEDDINGS,
+        config.quantization,
+        DEFAULT_FLOAT_CACHE_SIZE,
+    )
+    .await?;
+
+    // a frame every 2 seconds, up to now
+    let start = Utc::now() - ChronoDuration::seconds(2 * config.frames as i64);
+    let frames: Vec<NewFrame> = (0..config.frames)
+        .map(|i| {
+            let app = *APPS.choose(&mut rng).unwrap();
+            NewFrame {
+                timestamp: Some(start + ChronoDuration::seconds(2 * i as i64)),
+                browser_url: None,
+                app_name: Some(app.to_string()),
+                window_name: Some(format!("{} - {}", app, WORDS.choose(&mut rng).unwrap())),
+                focused: true,
+                text: sentence(&mut rng, WORDS_PER_FRAME),
+                text_json: String::new(),
+            }
+        })
+        .collect();
+    let ocr_engine = Arc::new(OcrEngine::Tesseract);
+    db.insert_video_chunk("bench.mp4", DEVICE).await?;
+    let started = Instant::now();
+    let mut frame_ids = Vec::with_capacity(frames.len());
+    if config.batch_size <= 1 {
+        for frame in &frames {
+            let id = db
+                .insert_frame(
+                    DEVICE,
+                    frame.timestamp,
+                    None,
+                    frame.app_name.as_deref(),
+                    frame.window_name.as_deref(),
+                    frame.focused,
+                )
+                .await?;
+            db.insert_ocr_text(id, &frame.text, &frame.text_json, ocr_engine.clone())
+                .await?;
+            frame_ids.push(id);
+        }
+    } else {
+        for batch in frames.chunks(config.batch_size) {
+            frame_ids.extend(
+                db.insert_frames_batch(DEVICE, batch, ocr_engine.clone())
+                    .await?,
+            );
+        }
+    }
+    inserts.push(throughput(
+        "frames with text",
+        frames.len() as u64,
+        started.elapsed(),
+    ));
+
+    let device = AudioDevice {
+        name: "bench microphone".to_string(),
+        device_type: DeviceType::Input,
+    };
+    let transcriptions: Vec<String> = (0..config.transcriptions)
+        .map(|_| sentence(&mut rng, WORDS_PER_TRANSCRIPTION))
+        .collect();
+    let started = Instant::now();
+    let mut audio_chunk_id = 0;
+    for (i, transcription) in transcriptions.iter().enumerate() {
+        // ten transcriptions per 30s chunk
+        if i % 10 == 0 {
+            audio_chunk_id = db
+                .insert_audio_chunk(&format!("bench-audio-{}.mp4", i / 10))
+                .await?;
+        }
+        db.insert_audio_transcription(
+            audio_chunk_id,
+            transcription,
+            (i % 10) as i64,
+            "bench",
+            &device,
+            None,
+            None,
+            None,
+        )
+        .await?;
+    }
+    inserts.push(throughput(
+        "transcriptions",
+        transcriptions.len() as u64,
+        started.elapsed(),
+    ));
+
+    let embeddings: Vec<(i64, String)> = frame_ids
+        .iter()
+        .take(config.embeddings as usize)
+        .map(|id| {
+            let embedding = random_embedding(&mut rng, config.embedding_dimensions);
+            (*id, serde_json::to_string(&embedding).unwrap_or_default())
+        })
+        .collect();
+    let started = Instant::now();
+    for (frame_id, embedding) in &embeddings {
+        db.insert_embeddings(*frame_id, embedding.clone()).await?;
+    }
+    inserts.push(throughput(
+        "embeddings",
+        embeddings.len() as u64,
+        started.elapsed(),
+    ));
+
+    // the same search terms for every config with the same seed
+    let terms: Vec<&str> = (0..config.searches.max(1))
+        .map(|_| *WORDS.choose(&mut rng).unwrap())
+        .collect();
+    let term = |run: u32| terms[run as usize % terms.len()];
+    let app = |run: u32| APPS[run as usize % APPS.len()];
+    let vectors: Vec<Vec<f32>> = (0..config.searches.max(1))
+        .map(|_| random_embedding(&mut rng, config.embedding_dimensions))
+        .collect();
+    let db = &db;
+    let runs = config.searches;
+    let queries = vec![
+        measure("recent frames", runs, move |_| {
+            search(db, "", ContentType::OCR, None)
+        })
+        .await?,
+        measure("ocr text", runs, move |run| {
+            search(db, term(run), ContentType::OCR, None)
+        })
+        .await?,
+        measure("ocr text in app", runs, move |run| {
+            search(db, term(run), ContentType::OCR, Some(app(run)))
+        })
+        .await?,
+        measure("audio text", runs, move |run| {
+            search(db, term(run), ContentType::Audio, None)
+        })
+        .await?,
+        measure("all content", runs, move |run| {
+            search(db, term(run), ContentType::All, None)
+        })
+        .await?,
+        measure("count ocr text", runs, move |run| {
+            db.count_search_results(
+                term(run),
+                ContentType::OCR,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                false,
+                &TagFilter::default(),
+            )
+        })
+        .await?,
+        measure("similar embeddings", runs, move |run| {
+            db.search_similar_embeddings(
+                vectors[run as usize % vectors.len()].clone(),
+                SEARCH_LIMIT,
+                1.0,
+                None,
+            )
+        })
+        .await?,
+    ];
+
+    db.pool.close().await;
+    db.read_pool.close().await;
+    let database_bytes = ["", "-wal"]
+        .iter()
+        .filter_map(|suffix| {
+            std::fs::metadata(format!("{}{}", path.to_string_lossy(), suffix)).ok()
+        })
+        .map(|metadata| metadata.len())
+        .sum();
+    Ok(BenchReport {
+        name: config.name(),
+        config: config.clone(),
+        database_bytes,
+        inserts,
+        queries,
+    })
+}
@@ -0,0 +1,146 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{BrowserContext, DatabaseManager};
+
+// words of the app names of browsers, lowercased
+const BROWSER_APPS: &[&str] = &[
+    "chrome", "chromium", "firefox", "safari", "edge", "brave", "arc", "opera", "vivaldi",
+];
+
+impl DatabaseManager {
+    /// Stores the tab the browser extension reported at `timestamp`. Returns
+    /// none when the capture blocklist keeps its url or title out.
+    pub async fn insert_browser_context(
+        &self,
+        timestamp: DateTime<Utc>,
+        url: &str,
+        title: Option<&str>,
+        selection_text: Option<&str>,
+        browser: Option<&str>,
+    ) -> Result<Option<i64>, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO browser_contexts (timestamp, url, title, selection_text, browser)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            "#,
+        )
+        .bind(timestamp)
+        .bind(url)
+        .bind(title.filter(|title| !title.is_empty()))
+        .bind(selection_text.filter(|text| !text.trim().is_empty()))
+        .bind(browser.filter(|browser| !browser.is_empty()))
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+        Ok(Some(result.last_insert_rowid()))
+    }
+
+    pub async fn get_browser_context(
+        &self,
+        id: i64,
+    ) -> Result<Option<BrowserContext>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT id, timestamp, url, title, selection_text, browser FROM browser_contexts WHERE id = ?1",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Links the context to the frames of a browser captured less than
+    /// `max_gap` from it that show its tab: the ones with its url or with its
+    /// title as window name. Frames without a url get the one of the context. A frame
+    /// already linked moves to this context only when it was captured after
+    /// it and the context it had is older, the latest tab shown wins. Returns
+    /// how many frames were linked.
+    pub async fn link_browser_context(
+        &self,
+        id: i64,
+        max_gap: Duration,
+    ) -> Result<u64, sqlx::Error> {
+        let Some(context) = self.get_browser_context(id).await? else {
+            return Ok(0);
+        };
+        let result = sqlx::query(
+            r#"
+            UPDATE frames
+            SET browser_context_id = ?1,
+                browser_url = COALESCE(NULLIF(frames.browser_url, ''), ?2)
+            WHERE frames.deleted_at IS NULL
+                AND frames.timestamp >= ?4 AND frames.timestamp <= ?5
+                AND EXISTS (
+                    SELECT 1 FROM json_each(?7) browser
+                    WHERE instr(lower(frames.app_name), browser.value) > 0
+                )
+                AND (frames.browser_url = ?2 OR frames.window_name = ?3)
+                AND (frames.browser_context_id IS NULL
+                    OR (frames.browser_context_id != ?1
+                        AND frames.timestamp >= ?6
+                        AND (
+                            SELECT linked.timestamp FROM browser_contexts linked
+                            WHERE linked.id = frames.browser_context_id
+                        ) < ?6))
+            "#,
+        )
+        .bind(id)
+        .bind(&context.url)
+        .bind(context.title.as_deref().filter(|title| !title.is_empty()))
+        .bind(context.timestamp - max_gap)
+        .bind(context.timestamp + max_gap)
+        .bind(context.timestamp)
+        .bind(serde_json::to_string(BROWSER_APPS).unwrap_or_default())
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Links the contexts pushed since `since` again, for the frames
+    /// captured after them that weren't in yet. Returns how many frames were
+    /// linked.
+    pub async fn link_recent_browser_contexts(
+        &self,
+        since: DateTime<Utc>,
+        max_gap: Duration,
+    ) -> Result<u64, sqlx::Error> {
+        let ids: Vec<i64> = sqlx::query_scalar(
+            "SELECT id FROM browser_contexts WHERE timestamp >= ?1 ORDER BY timestamp",
+        )
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut linked = 0;
+        for id in ids {
+            linked += self.link_browser_context(id, max_gap).await?;
+        }
+        Ok(linked)
+    }
+
+    /// Deletes up to `limit` contexts pushed before `before` that no frame
+    /// links to, the linked ones go with their frames.
+    pub async fn delete_unlinked_browser_contexts(
+        &self,
+        before: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<u64, sqlx::Error> {
+        Ok(sqlx::query(
+            r#"
+            DELETE FROM browser_contexts
+            WHERE id IN (
+                SELECT id FROM browser_contexts
+                WHERE timestamp < ?1
+                    AND NOT EXISTS (
+                        SELECT 1 FROM frames WHERE frames.browser_context_id = browser_contexts.id
+                    )
+                LIMIT ?2
+            )
+            "#,
+        )
+        .bind(before)
+        .bind(limit)
+        .execute(&self.pool)
+        .await?
+        .rows_affected())
+    }
+}
@@ -0,0 +1,185 @@
+use chrono::Utc;
+
+use crate::{BulkFilter, BulkResult, DatabaseManager, TagContentType};
+
+fn ids_json(ids: &[i64]) -> String {
+    serde_json::to_string(ids).unwrap_or_else(|_| "[]".to_string())
+}
+
+impl DatabaseManager {
+    /// Adds and removes tags on every frame or audio chunk matching the
+    /// filter, in one transaction. `changed` counts the links added and
+    /// removed.
+    pub async fn bulk_update_tags(
+        &self,
+        content_type: &TagContentType,
+        filter: &BulkFilter,
+        add: &[String],
+        remove: &[String],
+    ) -> Result<BulkResult, sqlx::Error> {
+        let (table, column) = match content_type {
+            TagContentType::Vision => ("vision_tags", "vision_id"),
+            TagContentType::Audio => ("audio_tags", "audio_chunk_id"),
+        };
+        let (add, remove) = (
+            self.canonical_tags(add).await?,
+            self.canonical_tags(remove).await?,
+        );
+        let mut tx = self.pool.begin().await?;
+        let ids = Self::bulk_matching_ids(&mut tx, content_type, filter).await?;
+        let mut result = BulkResult {
+            matched: ids.len() as u64,
+            changed: 0,
+        };
+        let ids = ids_json(&ids);
+
+        for tag in add {
+            let tag_id: i64 = sqlx::query_scalar(
+                "INSERT INTO tags (name) VALUES (?) ON CONFLICT(name) DO UPDATE SET name=name RETURNING id",
+            )
+            .bind(&tag)
+            .fetch_one(&mut *tx)
+            .await?;
+            result.changed += sqlx::query(&format!(
+                r#"
+                INSERT INTO {table} ({column}, tag_id)
+                SELECT value, ?2 FROM json_each(?1) WHERE true
+                ON CONFLICT DO NOTHING
+                "#
+            ))
+            .bind(&ids)
+            .bind(tag_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+        for tag in remove {
+            result.changed += sqlx::query(&format!(
+                r#"
+                DELETE FROM {table}
+                WHERE tag_id = (SELECT id FROM tags WHERE name = ?2)
+                    AND {column} IN (SELECT value FROM json_each(?1))
+                "#
+            ))
+            .bind(&ids)
+            .bind(&tag)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+
+        tx.commit().await?;
+        Ok(result)
+    }
+
+    /// Moves every frame or audio chunk matching the filter to the trash,
+    /// where it can be restored until purged.
+    pub async fn bulk_trash(
+        &self,
+        content_type: &TagContentType,
+        filter: &BulkFilter,
+    ) -> Result<BulkResult, sqlx::Error> {
+        let table = match content_type {
+            TagContentType::Vision => "frames",
+            TagContentType::Audio => "audio_chunks",
+        };
+        let mut tx = self.pool.begin().await?;
+        let ids = Self::bulk_matching_ids(&mut tx, content_type, filter).await?;
+        let changed = sqlx::query(&format!(
+            "UPDATE {table} SET deleted_at = ?1 WHERE id IN (SELECT value FROM json_each(?2))"
+        ))
+        .bind(Utc::now())
+        .bind(ids_json(&ids))
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        tx.commit().await?;
+        Ok(BulkResult {
+            matched: ids.len() as u64,
+            changed,
+        })
+    }
+
+    /// Sets the app and/or window name of every frame matching the filter,
+    /// e.g. to merge the names an app had across versions.
+    pub async fn bulk_rename_frames(
+        &self,
+        filter: &BulkFilter,
+        app_name: Option<&str>,
+        window_name: Option<&str>,
+    ) -> Result<BulkResult, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let ids = Self::bulk_matching_ids(&mut tx, &TagContentType::Vision, filter).await?;
+        let changed = sqlx::query(
+            r#"
+            UPDATE frames
+            SET app_name = COALESCE(?2, app_name), window_name = COALESCE(?3, window_name)
+            WHERE id IN (SELECT value FROM json_each(?1))
+                AND (app_name IS NOT COALESCE(?2, app_name)
+                    OR window_name IS NOT COALESCE(?3, window_name))
+            "#,
+        )
+        .bind(ids_json(&ids))
+        .bind(app_name)
+        .bind(window_name)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        tx.commit().await?;
+        Ok(BulkResult {
+            matched: ids.len() as u64,
+            changed,
+        })
+    }
+
+    async fn bulk_matching_ids(
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        content_type: &TagContentType,
+        filter: &BulkFilter,
+    ) -> Result<Vec<i64>, sqlx::Error> {
+        let query = match content_type {
+            TagContentType::Vision => {
+                r#"
+                SELECT frames.id
+                FROM frames
+                JOIN video_chunks ON frames.video_chunk_id = video_chunks.id
+                WHERE frames.deleted_at IS NULL
+                    AND (?1 IS NULL OR frames.id IN (SELECT value FROM json_each(?1)))
+                    AND (?2 IS NULL OR frames.timestamp >= ?2)
+                    AND (?3 IS NULL OR frames.timestamp <= ?3)
+                    AND (?4 IS NULL OR video_chunks.device_name = ?4)
+                    AND (?5 IS NULL OR frames.app_name = ?5)
+                    AND (?6 IS NULL OR frames.window_name = ?6)
+                ORDER BY frames.id
+                "#
+            }
+            TagContentType::Audio => {
+                r#"
+                SELECT audio_chunks.id
+                FROM audio_chunks
+                WHERE audio_chunks.deleted_at IS NULL
+                    AND (?1 IS NULL OR audio_chunks.id IN (SELECT value FROM json_each(?1)))
+                    AND (?2 IS NULL OR audio_chunks.timestamp >= ?2)
+                    AND (?3 IS NULL OR audio_chunks.timestamp <= ?3)
+                    AND (?4 IS NULL OR EXISTS (
+                        SELECT 1 FROM audio_transcriptions
+                        WHERE audio_transcriptions.audio_chunk_id = audio_chunks.id
+                            AND audio_transcriptions.device = ?4
+                    ))
+                ORDER BY audio_chunks.id
+                "#
+            }
+        };
+        let mut query = sqlx::query_scalar::<_, i64>(query)
+            .bind((!filter.ids.is_empty()).then(|| ids_json(&filter.ids)))
+            .bind(filter.start_time)
+            .bind(filter.end_time)
+            .bind(&filter.device_name);
+        if *content_type == TagContentType::Vision {
+            query = query.bind(&filter.app_name).bind(&filter.window_name);
+        }
+        query.fetch_all(&mut **tx).await
+    }
+}
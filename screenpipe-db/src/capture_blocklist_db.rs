@@ -0,0 +1,82 @@
+use chrono::Utc;
+
+use crate::{CaptureBlockRule, DatabaseManager, NewCaptureBlockRule};
+
+pub const CAPTURE_BLOCK_KINDS: &[&str] = &["app", "window", "url"];
+
+/// Window titles of private browsing windows in Firefox, Chrome and Edge.
+pub const PRIVATE_BROWSING_WINDOWS: &[&str] = &["Private Browsing", "Incognito", "InPrivate"];
+
+const CAPTURE_BLOCK_RULE_COLUMNS: &str = "id, kind, pattern, source, created_at";
+
+impl DatabaseManager {
+    /// Keeps frames and ui rows matching the rule out of the database from
+    /// now on, what was recorded before stays.
+    pub async fn insert_capture_block_rule(
+        &self,
+        rule: &NewCaptureBlockRule,
+    ) -> Result<CaptureBlockRule, sqlx::Error> {
+        sqlx::query_as::<_, CaptureBlockRule>(&format!(
+            r#"
+            INSERT INTO capture_blocklist (kind, pattern, source, created_at)
+            VALUES (?1, ?2, 'api', ?3)
+            ON CONFLICT(kind, pattern, source) DO UPDATE SET kind = excluded.kind
+            RETURNING {CAPTURE_BLOCK_RULE_COLUMNS}
+            "#
+        ))
+        .bind(&rule.kind)
+        .bind(rule.pattern.trim())
+        .bind(Utc::now())
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn list_capture_block_rules(&self) -> Result<Vec<CaptureBlockRule>, sqlx::Error> {
+        sqlx::query_as::<_, CaptureBlockRule>(&format!(
+            "SELECT {CAPTURE_BLOCK_RULE_COLUMNS} FROM capture_blocklist ORDER BY id"
+        ))
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Returns whether a rule added at the api had this id. The rules of the
+    /// command line can't be deleted, they would be back at the next start.
+    pub async fn delete_capture_block_rule(&self, id: i64) -> Result<bool, sqlx::Error> {
+        let deleted = sqlx::query("DELETE FROM capture_blocklist WHERE id = ?1 AND source = 'api'")
+            .bind(id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        Ok(deleted > 0)
+    }
+
+    /// Replaces the rules of the command line. Rules with an empty pattern,
+    /// which would block everything, are left out.
+    pub async fn set_config_capture_block_rules(
+        &self,
+        rules: &[NewCaptureBlockRule],
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM capture_blocklist WHERE source = 'config'")
+            .execute(&mut *tx)
+            .await?;
+        let now = Utc::now();
+        for rule in rules {
+            if rule.pattern.trim().is_empty() {
+                continue;
+            }
+            sqlx::query(
+                r#"
+                INSERT OR IGNORE INTO capture_blocklist (kind, pattern, source, created_at)
+                VALUES (?1, ?2, 'config', ?3)
+                "#,
+            )
+            .bind(&rule.kind)
+            .bind(rule.pattern.trim())
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await
+    }
+}
@@ -0,0 +1,154 @@
+use chrono::{DateTime, Utc};
+
+use crate::{CapturePause, CapturePauseSchedule, DatabaseManager};
+
+pub const CAPTURE_PAUSE_CONTENT_TYPES: &[&str] = &["all", "vision", "audio", "ui"];
+
+const CAPTURE_PAUSE_COLUMNS: &str =
+    "id, content_type, started_at, resume_at, ended_at, schedule_id";
+
+impl DatabaseManager {
+    pub async fn start_capture_pause(
+        &self,
+        content_type: &str,
+        started_at: DateTime<Utc>,
+        resume_at: Option<DateTime<Utc>>,
+        schedule_id: Option<i64>,
+    ) -> Result<CapturePause, sqlx::Error> {
+        sqlx::query_as::<_, CapturePause>(&format!(
+            r#"
+            INSERT INTO capture_pauses (content_type, started_at, resume_at, schedule_id)
+            VALUES (?1, ?2, ?3, ?4)
+            RETURNING {CAPTURE_PAUSE_COLUMNS}
+            "#
+        ))
+        .bind(content_type)
+        .bind(started_at)
+        .bind(resume_at)
+        .bind(schedule_id)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// Ends the running pauses of `content_type`, all of them for "all".
+    /// Returns the ones ended.
+    pub async fn end_capture_pauses(
+        &self,
+        content_type: &str,
+        ended_at: DateTime<Utc>,
+    ) -> Result<Vec<CapturePause>, sqlx::Error> {
+        sqlx::query_as::<_, CapturePause>(&format!(
+            r#"
+            UPDATE capture_pauses SET ended_at = ?2
+            WHERE ended_at IS NULL AND (?1 = 'all' OR content_type = ?1)
+            RETURNING {CAPTURE_PAUSE_COLUMNS}
+            "#
+        ))
+        .bind(content_type)
+        .bind(ended_at)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn end_capture_pause(
+        &self,
+        id: i64,
+        ended_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE capture_pauses SET ended_at = ?2 WHERE id = ?1 AND ended_at IS NULL")
+            .bind(id)
+            .bind(ended_at)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Ends the timed pauses due by `now`, at the time they were due, also
+    /// when screenpipe was not running then.
+    pub async fn end_due_capture_pauses(&self, now: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+        let ended = sqlx::query(
+            "UPDATE capture_pauses SET ended_at = resume_at WHERE ended_at IS NULL AND resume_at <= ?1",
+        )
+        .bind(now)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+        Ok(ended)
+    }
+
+    pub async fn list_running_capture_pauses(&self) -> Result<Vec<CapturePause>, sqlx::Error> {
+        sqlx::query_as::<_, CapturePause>(&format!(
+            "SELECT {CAPTURE_PAUSE_COLUMNS} FROM capture_pauses WHERE ended_at IS NULL ORDER BY id"
+        ))
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Pauses overlapping `start`..`end`, oldest first.
+    pub async fn list_capture_pauses(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<CapturePause>, sqlx::Error> {
+        sqlx::query_as::<_, CapturePause>(&format!(
+            r#"
+            SELECT {CAPTURE_PAUSE_COLUMNS} FROM capture_pauses
+            WHERE started_at < ?2 AND (ended_at IS NULL OR ended_at > ?1)
+            ORDER BY started_at
+            "#
+        ))
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn insert_capture_pause_schedule(
+        &self,
+        content_type: &str,
+        hours: &str,
+    ) -> Result<CapturePauseSchedule, sqlx::Error> {
+        sqlx::query_as::<_, CapturePauseSchedule>(
+            r#"
+            INSERT INTO capture_pause_schedules (content_type, hours, created_at)
+            VALUES (?1, ?2, ?3)
+            RETURNING id, content_type, hours, created_at
+            "#,
+        )
+        .bind(content_type)
+        .bind(hours)
+        .bind(Utc::now())
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn list_capture_pause_schedules(
+        &self,
+    ) -> Result<Vec<CapturePauseSchedule>, sqlx::Error> {
+        sqlx::query_as::<_, CapturePauseSchedule>(
+            "SELECT id, content_type, hours, created_at FROM capture_pause_schedules ORDER BY id",
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Returns whether the schedule existed. The pause it is running ends
+    /// now.
+    pub async fn delete_capture_pause_schedule(&self, id: i64) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "UPDATE capture_pauses SET ended_at = ?2 WHERE schedule_id = ?1 AND ended_at IS NULL",
+        )
+        .bind(id)
+        .bind(Utc::now())
+        .execute(&mut *tx)
+        .await?;
+        let deleted = sqlx::query("DELETE FROM capture_pause_schedules WHERE id = ?1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        tx.commit().await?;
+        Ok(deleted > 0)
+    }
+}
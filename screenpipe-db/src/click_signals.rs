@@ -0,0 +1,110 @@
+use oasgen::OaSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::SearchResult;
+
+// queries sharing less of their words than this are not alike
+pub const MIN_QUERY_SIMILARITY: f64 = 0.5;
+// time a result must stay open for its click to count fully
+pub const FULL_DWELL_SECS: f64 = 30.0;
+// how fast the boost grows with the weight of the clicks
+const CLICK_BOOST_FACTOR: f64 = 0.5;
+
+/// Kind of search result a click was on.
+#[derive(OaSchema, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClickContentType {
+    Ocr,
+    Audio,
+    Ui,
+    Clipboard,
+}
+
+impl ClickContentType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClickContentType::Ocr => "ocr",
+            ClickContentType::Audio => "audio",
+            ClickContentType::Ui => "ui",
+            ClickContentType::Clipboard => "clipboard",
+        }
+    }
+
+    /// Kind and id a click on `result` is recorded with: its frame, audio
+    /// chunk, ui monitoring or clipboard id.
+    pub fn of(result: &SearchResult) -> (ClickContentType, i64) {
+        match result {
+            SearchResult::OCR(ocr) => (ClickContentType::Ocr, ocr.frame_id),
+            SearchResult::Audio(audio) => (ClickContentType::Audio, audio.audio_chunk_id),
+            SearchResult::UI(ui) => (ClickContentType::Ui, ui.id),
+            SearchResult::Clipboard(clipboard) => (ClickContentType::Clipboard, clipboard.id),
+        }
+    }
+}
+
+impl fmt::Display for ClickContentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ClickContentType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "ocr" => Ok(ClickContentType::Ocr),
+            "audio" => Ok(ClickContentType::Audio),
+            "ui" => Ok(ClickContentType::Ui),
+            "clipboard" => Ok(ClickContentType::Clipboard),
+            _ => Err(format!(
+                "unknown content type {:?}, expected ocr, audio, ui or clipboard",
+                s
+            )),
+        }
+    }
+}
+
+/// Relevance multipliers of the results clicked for a query, by kind and id.
+/// Results missing from it keep their relevance.
+pub type ClickBoosts = HashMap<(ClickContentType, i64), f64>;
+
+/// Lowercased words of `query`, sorted and deduplicated, so that neither
+/// their case nor their order matters.
+pub fn normalize_query(query: &str) -> String {
+    let words: BTreeSet<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect();
+    words.into_iter().collect::<Vec<_>>().join(" ")
+}
+
+/// Share of the words of two normalized queries they have in common, 1 for
+/// the same query and 0 for none.
+pub fn query_similarity(a: &str, b: &str) -> f64 {
+    let a: HashSet<&str> = a.split_whitespace().collect();
+    let b: HashSet<&str> = b.split_whitespace().collect();
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+/// Weight of `clicks` on a result that stayed open `credited_dwell_secs` in
+/// total, each click counting for at most `FULL_DWELL_SECS`. Opening a
+/// result is worth half a click, staying on it the other half, so results
+/// closed right away count for little.
+pub fn click_weight(clicks: i64, credited_dwell_secs: f64) -> f64 {
+    0.5 * clicks.max(0) as f64 + 0.5 * credited_dwell_secs.max(0.0) / FULL_DWELL_SECS
+}
+
+/// Relevance multiplier of a result clicked with `weight`, 1 when never
+/// clicked, each click adding less than the one before.
+pub fn click_boost(weight: f64) -> f64 {
+    1.0 + CLICK_BOOST_FACTOR * weight.max(0.0).ln_1p()
+}
@@ -0,0 +1,141 @@
+use chrono::{DateTime, Utc};
+use sqlx::Row;
+
+use crate::click_signals::{
+    click_boost, click_weight, normalize_query, query_similarity, FULL_DWELL_SECS,
+    MIN_QUERY_SIMILARITY,
+};
+use crate::{ClickBoosts, ClickContentType, ClickSignal, DatabaseManager, NewSearchClick};
+
+impl DatabaseManager {
+    pub async fn record_search_click(&self, click: &NewSearchClick) -> Result<i64, sqlx::Error> {
+        let id = sqlx::query(
+            r#"
+            INSERT INTO search_clicks (query, content_type, content_id, dwell_secs, clicked_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            "#,
+        )
+        .bind(normalize_query(&click.q))
+        .bind(click.content_type.as_str())
+        .bind(click.content_id)
+        .bind(click.dwell_secs.max(0.0))
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+        Ok(id)
+    }
+
+    /// Clicks grouped by query and result, the most recently clicked first.
+    pub async fn list_click_signals(
+        &self,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<ClickSignal>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                query,
+                content_type,
+                content_id,
+                COUNT(*) as clicks,
+                SUM(dwell_secs) as dwell_secs,
+                SUM(MIN(dwell_secs, ?1)) as credited_dwell_secs,
+                MAX(clicked_at) as last_clicked_at
+            FROM search_clicks
+            GROUP BY query, content_type, content_id
+            ORDER BY last_clicked_at DESC, query ASC
+            LIMIT ?2 OFFSET ?3
+            "#,
+        )
+        .bind(FULL_DWELL_SECS)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                let content_type: ClickContentType =
+                    row.get::<String, _>("content_type").parse().ok()?;
+                let clicks: i64 = row.get("clicks");
+                Some(ClickSignal {
+                    query: row.get("query"),
+                    content_type,
+                    content_id: row.get("content_id"),
+                    clicks,
+                    dwell_secs: row.get("dwell_secs"),
+                    last_clicked_at: row.get::<DateTime<Utc>, _>("last_clicked_at"),
+                    boost: click_boost(click_weight(clicks, row.get("credited_dwell_secs"))),
+                })
+            })
+            .collect())
+    }
+
+    /// Boosts of the results clicked for `query` or similar queries, the
+    /// clicks of a query weighted by how much it looks like `query`.
+    pub async fn click_boosts(&self, query: &str) -> Result<ClickBoosts, sqlx::Error> {
+        let query = normalize_query(query);
+        if query.is_empty() {
+            return Ok(ClickBoosts::new());
+        }
+
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                query,
+                content_type,
+                content_id,
+                COUNT(*) as clicks,
+                SUM(MIN(dwell_secs, ?1)) as credited_dwell_secs
+            FROM search_clicks
+            GROUP BY query, content_type, content_id
+            "#,
+        )
+        .bind(FULL_DWELL_SECS)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut weights = ClickBoosts::new();
+        for row in rows {
+            let similarity = query_similarity(&query, row.get("query"));
+            if similarity < MIN_QUERY_SIMILARITY {
+                continue;
+            }
+            let Ok(content_type) = row
+                .get::<String, _>("content_type")
+                .parse::<ClickContentType>()
+            else {
+                continue;
+            };
+            let weight = click_weight(row.get("clicks"), row.get("credited_dwell_secs"));
+            *weights
+                .entry((content_type, row.get("content_id")))
+                .or_default() += similarity * weight;
+        }
+        Ok(weights
+            .into_iter()
+            .map(|(key, weight)| (key, click_boost(weight)))
+            .collect())
+    }
+
+    /// Forgets the clicks of `query`, or all of them. Returns how many were
+    /// deleted.
+    pub async fn reset_click_signals(&self, query: Option<&str>) -> Result<u64, sqlx::Error> {
+        let result = match query {
+            Some(query) => {
+                sqlx::query("DELETE FROM search_clicks WHERE query = ?1")
+                    .bind(normalize_query(query))
+                    .execute(&self.pool)
+                    .await?
+            }
+            None => {
+                sqlx::query("DELETE FROM search_clicks")
+                    .execute(&self.pool)
+                    .await?
+            }
+        };
+        Ok(result.rows_affected())
+    }
+}
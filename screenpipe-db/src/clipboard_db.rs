@@ -0,0 +1,130 @@
+use chrono::{DateTime, Utc};
+
+use crate::db::tags_filter;
+use crate::{ClipboardEntry, DatabaseManager, TagFilter};
+
+impl DatabaseManager {
+    /// Stores text copied in `app_name`. Copying the text of the last entry
+    /// again stores nothing and returns none, as does copying in an app or
+    /// window of the capture blocklist.
+    pub async fn insert_clipboard_entry(
+        &self,
+        text: &str,
+        app_name: Option<&str>,
+        window_name: Option<&str>,
+    ) -> Result<Option<i64>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let last: Option<String> =
+            sqlx::query_scalar("SELECT text FROM clipboard ORDER BY id DESC LIMIT 1")
+                .fetch_optional(&mut *tx)
+                .await?;
+        if last.as_deref() == Some(text) {
+            return Ok(None);
+        }
+        let result = sqlx::query(
+            "INSERT INTO clipboard (timestamp, text, app_name, window_name) VALUES (?1, ?2, ?3, ?4)",
+        )
+        .bind(Utc::now())
+        .bind(text)
+        .bind(app_name)
+        .bind(window_name)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+        Ok(Some(result.last_insert_rowid()))
+    }
+
+    /// Clipboard entries matching the text query, most recent first. The
+    /// clipboard isn't tagged, a tag filter leaves it out.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn search_clipboard(
+        &self,
+        query: &str,
+        app_name: Option<&str>,
+        window_name: Option<&str>,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        limit: u32,
+        offset: u32,
+        tags: &TagFilter,
+    ) -> Result<Vec<ClipboardEntry>, sqlx::Error> {
+        if tags_filter(tags).is_some() {
+            return Ok(Vec::new());
+        }
+        let (mut conn, guard) = self.read_connection().await?;
+        guard
+            .run(
+                sqlx::query_as(
+                    r#"
+                    SELECT clipboard.id, clipboard.timestamp, clipboard.text,
+                        clipboard.app_name, clipboard.window_name
+                    FROM clipboard
+                    WHERE clipboard.deleted_at IS NULL
+                        AND (?1 IS NULL OR clipboard.id IN (
+                            SELECT rowid FROM clipboard_fts WHERE clipboard_fts MATCH ?1
+                        ))
+                        AND (?2 IS NULL OR clipboard.app_name = ?2)
+                        AND (?3 IS NULL OR clipboard.window_name = ?3)
+                        AND (?4 IS NULL OR clipboard.timestamp >= ?4)
+                        AND (?5 IS NULL OR clipboard.timestamp <= ?5)
+                    ORDER BY clipboard.timestamp DESC
+                    LIMIT ?6 OFFSET ?7
+                    "#,
+                )
+                .bind((!query.trim().is_empty()).then_some(query))
+                .bind(app_name.filter(|app| !app.is_empty()))
+                .bind(window_name.filter(|window| !window.is_empty()))
+                .bind(start_time)
+                .bind(end_time)
+                .bind(limit)
+                .bind(offset)
+                .fetch_all(&mut *conn),
+            )
+            .await
+    }
+
+    /// How many clipboard entries `search_clipboard` would find without a
+    /// limit.
+    pub async fn count_clipboard(
+        &self,
+        query: &str,
+        app_name: Option<&str>,
+        window_name: Option<&str>,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        tags: &TagFilter,
+    ) -> Result<usize, sqlx::Error> {
+        if tags_filter(tags).is_some() {
+            return Ok(0);
+        }
+        let (mut conn, guard) = self.read_connection().await?;
+        let count: i64 = guard
+            .run(
+                sqlx::query_scalar(
+                    r#"
+                    SELECT COUNT(*)
+                    FROM clipboard
+                    WHERE clipboard.deleted_at IS NULL
+                        AND (?1 IS NULL OR clipboard.id IN (
+                            SELECT rowid FROM clipboard_fts WHERE clipboard_fts MATCH ?1
+                        ))
+                        AND (?2 IS NULL OR clipboard.app_name = ?2)
+                        AND (?3 IS NULL OR clipboard.window_name = ?3)
+                        AND (?4 IS NULL OR clipboard.timestamp >= ?4)
+                        AND (?5 IS NULL OR clipboard.timestamp <= ?5)
+                    "#,
+                )
+                .bind((!query.trim().is_empty()).then_some(query))
+                .bind(app_name.filter(|app| !app.is_empty()))
+                .bind(window_name.filter(|window| !window.is_empty()))
+                .bind(start_time)
+                .bind(end_time)
+                .fetch_one(&mut *conn),
+            )
+            .await?;
+        Ok(count as usize)
+    }
+}
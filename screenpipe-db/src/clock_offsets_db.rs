@@ -0,0 +1,64 @@
+use chrono::Utc;
+
+use crate::{ClockOffset, DatabaseManager};
+
+// an estimate is replaced by a noisier one once it is this old, clocks drift
+const CLOCK_OFFSET_MAX_AGE_SECS: f64 = 600.0;
+
+// whether the new exchange replaces the stored estimate, ?6 is the max age
+const REPLACES_ESTIMATE: &str = r#"(
+    session_id != excluded.session_id
+    OR excluded.delay_ms <= delay_ms
+    OR (julianday(excluded.updated_at) - julianday(updated_at)) * 86400.0 > ?6
+)"#;
+
+impl DatabaseManager {
+    /// Records one exchange of a remote agent. Within a session the exchange
+    /// with the shortest round trip wins, since the offset error is bounded by
+    /// half the delay. A new session starts over.
+    pub async fn record_clock_sample(
+        &self,
+        device_name: &str,
+        session_id: &str,
+        offset_ms: f64,
+        delay_ms: f64,
+    ) -> Result<ClockOffset, sqlx::Error> {
+        sqlx::query_as::<_, ClockOffset>(&format!(
+            r#"
+            INSERT INTO clock_offsets (device_name, session_id, offset_ms, delay_ms, samples, updated_at)
+            VALUES (?1, ?2, ?3, ?4, 1, ?5)
+            ON CONFLICT(device_name) DO UPDATE SET
+                offset_ms = CASE WHEN {REPLACES_ESTIMATE} THEN excluded.offset_ms ELSE offset_ms END,
+                delay_ms = CASE WHEN {REPLACES_ESTIMATE} THEN excluded.delay_ms ELSE delay_ms END,
+                updated_at = CASE WHEN {REPLACES_ESTIMATE} THEN excluded.updated_at ELSE updated_at END,
+                samples = CASE WHEN session_id = excluded.session_id THEN samples + 1 ELSE 1 END,
+                session_id = excluded.session_id
+            RETURNING device_name, session_id, offset_ms, delay_ms, samples, updated_at
+            "#
+        ))
+        .bind(device_name)
+        .bind(session_id)
+        .bind(offset_ms)
+        .bind(delay_ms)
+        .bind(Utc::now())
+        .bind(CLOCK_OFFSET_MAX_AGE_SECS)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn get_clock_offset(
+        &self,
+        device_name: &str,
+    ) -> Result<Option<ClockOffset>, sqlx::Error> {
+        sqlx::query_as::<_, ClockOffset>(
+            r#"
+            SELECT device_name, session_id, offset_ms, delay_ms, samples, updated_at
+            FROM clock_offsets
+            WHERE device_name = ?1
+            "#,
+        )
+        .bind(device_name)
+        .fetch_optional(&self.pool)
+        .await
+    }
+}
@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::{
+    ContentHook, DatabaseManager, HookContent, HookOutcome, NewFrame, StageProvenance,
+    TagContentType,
+};
+
+pub(crate) struct ProcessedFrame {
+    /// as stored, `None` when dropped
+    pub frame: Option<NewFrame>,
+    pub tags: Vec<String>,
+    pub stages: Vec<StageProvenance>,
+    /// the text recorded as read
+    pub raw_text: String,
+}
+
+/// Transforms, drops or tags content as it is stored, whatever records it.
+pub trait ContentProcessor: Send + Sync {
+    fn process(&self, content: &HookContent) -> HookOutcome;
+
+    /// The OCR blocks of a frame as stored, for a processor whose changes
+    /// can span blocks, `None` to run `process` on each block's text.
+    fn process_blocks(
+        &self,
+        _content: &HookContent,
+        _blocks: &[HashMap<String, String>],
+    ) -> Option<Vec<HashMap<String, String>>> {
+        None
+    }
+}
+
+impl DatabaseManager {
+    /// Runs `processor` on the OCR text and transcriptions inserted from now
+    /// on, and on the frames once inserted. Processors run in the order they
+    /// were added, each shown the text the previous ones left.
+    pub fn with_content_processor(mut self, processor: Option<Arc<dyn ContentProcessor>>) -> Self {
+        self.content_processors.extend(processor);
+        self
+    }
+
+    /// The outcome of the processors on `content`. Processors are sync and
+    /// may be slow, plugins run WASM, so they run on the blocking pool. A
+    /// processor that panics leaves the content as it was.
+    pub(crate) async fn process_content(&self, content: HookContent) -> HookOutcome {
+        if self.content_processors.is_empty() {
+            return HookOutcome::default();
+        }
+        let processors = self.content_processors.clone();
+        tokio::task::spawn_blocking(move || run_processors(&processors, content))
+            .await
+            .unwrap_or_else(|e| {
+                warn!("content processors failed: {}", e);
+                HookOutcome::default()
+            })
+    }
+
+    /// Records what the post-processing stages ran on, when some did.
+    pub(crate) async fn record_provenance(
+        &self,
+        content_type: TagContentType,
+        id: i64,
+        raw_text: &str,
+        stages: &[StageProvenance],
+    ) {
+        if stages.is_empty() || id == 0 {
+            return;
+        }
+        if let Err(e) = self
+            .set_text_provenance(content_type, id, raw_text, stages)
+            .await
+        {
+            warn!("failed to record the provenance of {}: {}", id, e);
+        }
+    }
+
+    /// The frames as `on_ocr` leaves them, `None` for the dropped ones, with
+    /// the tags asked for and the stages that ran. Runs on the blocking pool
+    /// like `process_content`.
+    pub(crate) async fn process_new_frames(&self, frames: &[NewFrame]) -> Vec<ProcessedFrame> {
+        let unprocessed = || {
+            frames
+                .iter()
+                .map(|frame| ProcessedFrame {
+                    frame: Some(frame.clone()),
+                    tags: Vec::new(),
+                    stages: Vec::new(),
+                    raw_text: frame.text.clone(),
+                })
+                .collect()
+        };
+        if self.content_processors.is_empty() {
+            return unprocessed();
+        }
+        let processors = self.content_processors.clone();
+        let owned = frames.to_vec();
+        tokio::task::spawn_blocking(move || {
+            owned
+                .iter()
+                .map(|frame| process_frame(&processors, frame))
+                .collect()
+        })
+        .await
+        .unwrap_or_else(|e| {
+            warn!("content processors failed: {}", e);
+            unprocessed()
+        })
+    }
+
+    /// The OCR text of a stored frame as `on_ocr` leaves it.
+    pub(crate) async fn process_ocr_text(
+        &self,
+        frame_id: i64,
+        text: &str,
+        text_json: &str,
+    ) -> Result<ProcessedFrame, sqlx::Error> {
+        let frame: Option<(Option<String>, Option<String>, Option<String>, bool)> = if self
+            .content_processors
+            .is_empty()
+        {
+            None
+        } else {
+            sqlx::query_as(
+                    "SELECT app_name, window_name, browser_url, COALESCE(focused, 0) FROM frames WHERE id = ?1",
+                )
+                .bind(frame_id)
+                .fetch_optional(&self.pool)
+                .await?
+        };
+        let (app_name, window_name, browser_url, focused) = frame.unwrap_or_default();
+        Ok(self
+            .process_new_frames(&[NewFrame {
+                timestamp: None,
+                browser_url,
+                app_name,
+                window_name,
+                focused,
+                text: text.to_string(),
+                text_json: text_json.to_string(),
+            }])
+            .await
+            .remove(0))
+    }
+
+    /// Shows stored frames to `on_frame` and adds the tags asked for.
+    pub(crate) async fn process_stored_frames(
+        &self,
+        frames: impl IntoIterator<Item = (i64, &NewFrame, Vec<String>)>,
+    ) {
+        if self.content_processors.is_empty() {
+            return;
+        }
+        for (id, frame, mut tags) in frames {
+            let outcome = self
+                .process_content(HookContent {
+                    hook: ContentHook::OnFrame,
+                    id: Some(id),
+                    text: frame.text.clone(),
+                    app_name: frame.app_name.clone(),
+                    window_name: frame.window_name.clone(),
+                    browser_url: frame.browser_url.clone(),
+                    device: None,
+                    speaker_id: None,
+                })
+                .await;
+            tags.extend(outcome.tags);
+            self.add_processor_tags(TagContentType::Vision, id, tags)
+                .await;
+        }
+    }
+
+    pub(crate) async fn add_processor_tags(
+        &self,
+        content_type: TagContentType,
+        id: i64,
+        mut tags: Vec<String>,
+    ) {
+        tags.retain(|tag| !tag.trim().is_empty());
+        tags.sort();
+        tags.dedup();
+        if tags.is_empty() {
+            return;
+        }
+        if let Err(e) = self.add_tags(id, content_type, tags).await {
+            warn!("failed to add content processor tags to {}: {}", id, e);
+        }
+    }
+}
+
+fn run_processors(
+    processors: &[Arc<dyn ContentProcessor>],
+    mut content: HookContent,
+) -> HookOutcome {
+    let mut outcome = HookOutcome::default();
+    for processor in processors {
+        let result = processor.process(&content);
+        // a stored frame can't be dropped, its tags are kept
+        if result.drop && content.hook != ContentHook::OnFrame {
+            return HookOutcome {
+                drop: true,
+                ..Default::default()
+            };
+        }
+        if let Some(text) = result.text {
+            content.text = text.clone();
+            outcome.text = Some(text);
+        }
+        outcome.tags.extend(result.tags);
+        outcome.stages.extend(result.stages);
+        // the later processor saw less, what it masked stays masked
+        if result.raw_text.is_some() {
+            outcome.raw_text = result.raw_text;
+        }
+    }
+    outcome
+}
+
+fn process_frame(processors: &[Arc<dyn ContentProcessor>], frame: &NewFrame) -> ProcessedFrame {
+    let outcome = run_processors(
+        processors,
+        HookContent {
+            hook: ContentHook::OnOcr,
+            id: None,
+            text: frame.text.clone(),
+            app_name: frame.app_name.clone(),
+            window_name: frame.window_name.clone(),
+            browser_url: frame.browser_url.clone(),
+            device: None,
+            speaker_id: None,
+        },
+    );
+    if outcome.drop {
+        return ProcessedFrame {
+            frame: None,
+            tags: Vec::new(),
+            stages: Vec::new(),
+            raw_text: String::new(),
+        };
+    }
+    let raw_text = outcome.raw_text.unwrap_or_else(|| frame.text.clone());
+    let mut processed = frame.clone();
+    if let Some(text) = outcome.text {
+        // the blocks hold the words replaced
+        processed.text_json = process_blocks(processors, &frame.text_json, frame);
+        processed.text = text;
+    }
+    ProcessedFrame {
+        frame: Some(processed),
+        tags: outcome.tags,
+        stages: outcome.stages,
+        raw_text,
+    }
+}
+
+/// The OCR blocks of `text_json` with their text processed, one by one
+/// unless a processor takes them all, so their positions are kept.
+/// Blocks the processors drop are left without text.
+fn process_blocks(
+    processors: &[Arc<dyn ContentProcessor>],
+    text_json: &str,
+    frame: &NewFrame,
+) -> String {
+    let Ok(mut blocks) = serde_json::from_str::<Vec<HashMap<String, String>>>(text_json) else {
+        return "[]".to_string();
+    };
+    let content = |text: String| HookContent {
+        hook: ContentHook::OnOcr,
+        id: None,
+        text,
+        app_name: frame.app_name.clone(),
+        window_name: frame.window_name.clone(),
+        browser_url: frame.browser_url.clone(),
+        device: None,
+        speaker_id: None,
+    };
+    for processor in processors {
+        if let Some(processed) = processor.process_blocks(&content(frame.text.clone()), &blocks) {
+            blocks = processed;
+            continue;
+        }
+        for block in &mut blocks {
+            let Some(text) = block.get_mut("text") else {
+                continue;
+            };
+            if text.trim().is_empty() {
+                continue;
+            }
+            let outcome = processor.process(&content(text.clone()));
+            if outcome.drop {
+                text.clear();
+            } else if let Some(processed) = outcome.text {
+                *text = processed;
+            }
+        }
+    }
+    serde_json::to_string(&blocks).unwrap_or_else(|_| "[]".to_string())
+}
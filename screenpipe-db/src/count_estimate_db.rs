@@ -0,0 +1,167 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+use crate::{ContentType, DatabaseManager, ResultCount, TagFilter};
+
+// rows of the most recent days counted exactly, the rest is extrapolated
+const ESTIMATE_SAMPLE_ROWS: f64 = 10_000.0;
+
+impl DatabaseManager {
+    /// Like `count_search_results`, but only counts the matches of the most
+    /// recent days holding about `ESTIMATE_SAMPLE_ROWS` rows and scales that
+    /// by the rows of the whole period, from the per-day counters. Exact when
+    /// the period is no bigger than the sample.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn estimate_search_results(
+        &self,
+        query: &str,
+        mut content_type: ContentType,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        app_name: Option<&str>,
+        window_name: Option<&str>,
+        min_length: Option<usize>,
+        max_length: Option<usize>,
+        speaker_ids: Option<Vec<i64>>,
+        frame_name: Option<&str>,
+        browser_url: Option<&str>,
+        focused: Option<bool>,
+        min_confidence: Option<f64>,
+        in_tables: bool,
+        tags: &TagFilter,
+    ) -> Result<ResultCount, sqlx::Error> {
+        // same narrowing as count_search_results
+        if focused.is_some() || browser_url.is_some() || in_tables {
+            content_type = ContentType::OCR;
+        }
+        let all = content_type == ContentType::All;
+        let mut content_types = match content_type {
+            ContentType::All if app_name.is_none() && window_name.is_none() => {
+                vec![ContentType::OCR, ContentType::Audio, ContentType::UI]
+            }
+            ContentType::All => vec![ContentType::OCR, ContentType::UI],
+            ContentType::OCR | ContentType::Audio | ContentType::UI => vec![content_type],
+            // no counters for these, count_search_results counts them anyway
+            _ => {
+                let count = self
+                    .count_search_results(
+                        query,
+                        content_type,
+                        start_time,
+                        end_time,
+                        app_name,
+                        window_name,
+                        min_length,
+                        max_length,
+                        speaker_ids,
+                        frame_name,
+                        browser_url,
+                        focused,
+                        min_confidence,
+                        in_tables,
+                        tags,
+                    )
+                    .await?;
+                return Ok(ResultCount { count, exact: true });
+            }
+        };
+        if all && frame_name.is_none() {
+            content_types.push(ContentType::Clipboard);
+        }
+
+        let mut total = ResultCount {
+            count: 0,
+            exact: true,
+        };
+        for content_type in content_types {
+            let (sample_start, scale) = self
+                .estimate_sample(&content_type, start_time, end_time)
+                .await?;
+            let audio = content_type == ContentType::Audio;
+            let count = self
+                .count_search_results(
+                    query,
+                    content_type,
+                    sample_start.or(start_time),
+                    end_time,
+                    if audio { None } else { app_name },
+                    if audio { None } else { window_name },
+                    min_length,
+                    max_length,
+                    if audio { speaker_ids.clone() } else { None },
+                    frame_name,
+                    browser_url,
+                    focused,
+                    min_confidence,
+                    in_tables,
+                    tags,
+                )
+                .await?;
+            total.count += (count as f64 * scale).round() as usize;
+            total.exact &= sample_start.is_none();
+        }
+        Ok(total)
+    }
+
+    /// Where the sample of `content_type` starts and how many times the
+    /// rows of the period it holds, or no start when the sample is the
+    /// whole period.
+    async fn estimate_sample(
+        &self,
+        content_type: &ContentType,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+    ) -> Result<(Option<DateTime<Utc>>, f64), sqlx::Error> {
+        let counter = match content_type {
+            ContentType::OCR => "ocr",
+            ContentType::Audio => "audio",
+            // no counter, the clipboard is small enough to count it all
+            ContentType::Clipboard => return Ok((None, 1.0)),
+            _ => "ui",
+        };
+        let (mut conn, guard) = self.read_connection().await?;
+        let days: Vec<(NaiveDate, i64)> = guard
+            .run(
+                sqlx::query_as(
+                    r#"
+            SELECT day, row_count
+            FROM content_day_counts
+            WHERE content_type = ?1
+                AND row_count > 0
+                AND (?2 IS NULL OR day >= date(?2))
+                AND (?3 IS NULL OR day <= date(?3))
+            ORDER BY day DESC
+            "#,
+                )
+                .bind(counter)
+                .bind(start_time)
+                .bind(end_time)
+                .fetch_all(&mut *conn),
+            )
+            .await?;
+
+        // rows of each day inside the period, assuming they are spread
+        // evenly over the day
+        let rows_in_period = |day: NaiveDate, rows: i64| {
+            let day_start = day.and_hms_opt(0, 0, 0).unwrap().and_utc();
+            let day_end = day_start + Duration::days(1);
+            let from = start_time.map_or(day_start, |start| start.max(day_start));
+            let to = end_time.map_or(day_end, |end| end.min(day_end));
+            let fraction = (to - from).num_seconds().max(0) as f64 / 86_400.0;
+            rows as f64 * fraction
+        };
+        let period_rows: f64 = days
+            .iter()
+            .map(|(day, rows)| rows_in_period(*day, *rows))
+            .sum();
+
+        let mut sample_rows = 0.0;
+        for (index, (day, rows)) in days.iter().enumerate() {
+            sample_rows += rows_in_period(*day, *rows);
+            if sample_rows >= ESTIMATE_SAMPLE_ROWS && index + 1 < days.len() {
+                let sample_start = day.and_hms_opt(0, 0, 0).unwrap().and_utc();
+                return Ok((Some(sample_start), period_rows / sample_rows));
+            }
+        }
+        Ok((None, 1.0))
+    }
+}
@@ -0,0 +1,114 @@
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::{CaptureCoverage, CaptureGap, CoverageReport, DatabaseManager};
+
+// every frame and transcription of the period with the gap since the previous
+// sample of the same device, in seconds. ?1 and ?2 bound the period
+const SAMPLES_CTE: &str = r#"
+    WITH samples AS (
+        SELECT video_chunks.device_name AS device_name, 'vision' AS kind, frames.timestamp AS ts
+        FROM frames
+        JOIN video_chunks ON frames.video_chunk_id = video_chunks.id
+        WHERE frames.timestamp >= ?1 AND frames.timestamp < ?2 AND frames.deleted_at IS NULL
+        UNION ALL
+        SELECT audio_transcriptions.device, 'audio', audio_transcriptions.timestamp
+        FROM audio_transcriptions
+        WHERE audio_transcriptions.timestamp >= ?1 AND audio_transcriptions.timestamp < ?2
+    ),
+    intervals AS (
+        SELECT
+            device_name,
+            kind,
+            LAG(ts) OVER (PARTITION BY kind, device_name ORDER BY ts) AS previous,
+            ts,
+            (julianday(ts) - julianday(LAG(ts) OVER (PARTITION BY kind, device_name ORDER BY ts)))
+                * 86400.0 AS gap
+        FROM samples
+    )
+"#;
+
+impl DatabaseManager {
+    /// Reports, per day and device, how much of `start..end` was recorded and
+    /// the gaps longer than `max_gap_secs` between two samples. Samples closer
+    /// than that count as continuous recording, so it should be above the
+    /// longest interval capture can idle for. Audio only has samples when
+    /// something was transcribed, silence shows up as gaps.
+    pub async fn get_coverage_report(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        max_gap_secs: f64,
+        max_gaps: u32,
+    ) -> Result<CoverageReport, sqlx::Error> {
+        let mut days: Vec<CaptureCoverage> = sqlx::query_as(&format!(
+            r#"
+            {SAMPLES_CTE}
+            SELECT
+                date(ts) AS day,
+                device_name,
+                kind,
+                COUNT(*) AS samples,
+                COALESCE(SUM(CASE WHEN gap <= ?3 THEN gap END), 0.0) AS covered_secs
+            FROM intervals
+            GROUP BY date(ts), device_name, kind
+            ORDER BY day, kind, device_name
+            "#
+        ))
+        .bind(start)
+        .bind(end)
+        .bind(max_gap_secs)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let period_end = end.min(Utc::now());
+        for coverage in &mut days {
+            let Some(day_start) = NaiveDate::parse_from_str(&coverage.day, "%Y-%m-%d")
+                .ok()
+                .and_then(|day| day.and_hms_opt(0, 0, 0))
+                .map(|day| day.and_utc())
+            else {
+                continue;
+            };
+            let day_end = day_start + chrono::Duration::days(1);
+            let period = (day_end.min(period_end) - day_start.max(start)).num_milliseconds();
+            coverage.period_secs = period.max(0) as f64 / 1000.0;
+            if coverage.period_secs > 0.0 {
+                coverage.coverage_percent =
+                    (coverage.covered_secs / coverage.period_secs * 100.0).min(100.0);
+            }
+        }
+
+        let gaps: Vec<CaptureGap> = sqlx::query_as(&format!(
+            r#"
+            {SAMPLES_CTE}
+            SELECT
+                device_name,
+                kind,
+                previous AS start,
+                ts AS "end",
+                gap AS duration_secs,
+                EXISTS (
+                    SELECT 1 FROM frames
+                    WHERE frames.timestamp > intervals.previous AND frames.timestamp < intervals.ts
+                        AND frames.deleted_at IS NULL
+                ) OR EXISTS (
+                    SELECT 1 FROM audio_transcriptions
+                    WHERE audio_transcriptions.timestamp > intervals.previous
+                        AND audio_transcriptions.timestamp < intervals.ts
+                ) AS recording_elsewhere
+            FROM intervals
+            WHERE gap > ?3
+            ORDER BY gap DESC
+            LIMIT ?4
+            "#
+        ))
+        .bind(start)
+        .bind(end)
+        .bind(max_gap_secs)
+        .bind(max_gaps)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(CoverageReport { days, gaps })
+    }
+}
@@ -1,17 +1,21 @@
 use chrono::{DateTime, Utc};
 use image::DynamicImage;
 use libsqlite3_sys::sqlite3_auto_extension;
+use regex::Regex;
 use sqlite_vec::sqlite3_vec_init;
 use sqlx::migrate::MigrateDatabase;
-use sqlx::sqlite::{SqlitePool, SqlitePoolOptions};
+use sqlx::sqlite::{SqliteAutoVacuum, SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
 use sqlx::Column;
 use sqlx::Error as SqlxError;
 use sqlx::Row;
 use sqlx::TypeInfo;
 use sqlx::ValueRef;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
+use tokio::sync::broadcast;
 use tracing::{debug, error, warn};
+use uuid::{NoContext, Timestamp, Uuid};
 
 use std::collections::BTreeMap;
 
@@ -19,15 +23,329 @@ use zerocopy::AsBytes;
 
 use futures::future::try_join_all;
 
+use crate::embedding_index_db::{prune_float_cache, OCR_TEXT_EMBEDDINGS};
+use crate::ocr_tables::tables_json;
+use crate::rule_cache::RuleCache;
+use crate::search_ranking::decayed_score;
+use crate::speaker_clustering::decode_embedding;
+use crate::tag_rules_db::TagRuleContent;
 use crate::{
-    AudioChunksResponse, AudioDevice, AudioEntry, AudioResult, AudioResultRaw, ContentType,
-    DeviceType, FrameData, FrameRow, OCREntry, OCRResult, OCRResultRaw, OcrEngine, OcrTextBlock,
-    Order, SearchMatch, SearchResult, Speaker, TagContentType, TextBounds, TextPosition,
-    TimeSeriesChunk, UiContent, VideoMetadata,
+    cosine_distance, frame_confidence, parse_ocr_blocks, AudioChunksResponse, AudioDevice,
+    AudioEntry, AudioResult, AudioResultRaw, ContentHook, ContentProcessor, ContentType,
+    DeviceActivity, DeviceOcrFailures, DeviceType, EmbeddingQuantization, FrameData,
+    FrameRegionLink, FrameRow, HookContent, InsertEvent, NewFrame, OCREntry, OCRResult,
+    OCRResultRaw, OcrBlock, OcrEngine, OcrTextBlock, Order, SearchMatch, SearchResult, Speaker,
+    TagContentType, TagFilter, TagRule, TextBounds, TextPosition, TimeSeriesChunk, UiContent,
+    UrlPattern, VideoMetadata, WebhookMatch, WebhookRule,
 };
 
+// candidates fetched per result wanted, quantized distances are rough
+const RESCORE_OVERSAMPLING: u32 = 8;
+// prepared statements kept per connection, enough for every fixed query of
+// this crate so the hot ones are never evicted
+const STATEMENT_CACHE_CAPACITY: usize = 512;
+const READ_POOL_MAX_CONNECTIONS: u32 = 16;
+
+// the tag filter of a search on `$item_id`, tagged in `$link_table`, with
+// `$param` the json of `tags_filter`. Aliases are searched as the tag they
+// point to
+macro_rules! tag_filter_sql {
+    (@wanted $param:literal, $list:literal) => {
+        concat!(
+            "(SELECT COALESCE(alias_tag.name, wanted_tag.value) AS value FROM json_each(",
+            $param,
+            ", '$.",
+            $list,
+            "') wanted_tag LEFT JOIN tag_aliases ON tag_aliases.alias = wanted_tag.value",
+            " LEFT JOIN tags alias_tag ON alias_tag.id = tag_aliases.tag_id) wanted"
+        )
+    };
+    (@tagged $link_table:literal, $link_column:literal, $item_id:literal) => {
+        concat!(
+            "EXISTS (SELECT 1 FROM ",
+            $link_table,
+            " item_tags JOIN tags tag ON tag.id = item_tags.tag_id WHERE item_tags.",
+            $link_column,
+            " = ",
+            $item_id,
+            " AND (tag.name = wanted.value",
+            " OR substr(tag.name, 1, length(wanted.value) + 1) = wanted.value || '/'))"
+        )
+    };
+    ($param:literal, $link_table:literal, $link_column:literal, $item_id:literal) => {
+        concat!(
+            "(",
+            $param,
+            " IS NULL OR (NOT EXISTS (SELECT 1 FROM ",
+            tag_filter_sql!(@wanted $param, "all"),
+            " WHERE NOT ",
+            tag_filter_sql!(@tagged $link_table, $link_column, $item_id),
+            ") AND (json_array_length(",
+            $param,
+            ", '$.any') = 0 OR EXISTS (SELECT 1 FROM ",
+            tag_filter_sql!(@wanted $param, "any"),
+            " WHERE ",
+            tag_filter_sql!(@tagged $link_table, $link_column, $item_id),
+            ")) AND NOT EXISTS (SELECT 1 FROM ",
+            tag_filter_sql!(@wanted $param, "none"),
+            " WHERE ",
+            tag_filter_sql!(@tagged $link_table, $link_column, $item_id),
+            ")))"
+        )
+    };
+}
+
+// keeps the ocr_text rows matching the text query ?6, by their own text or by
+// the tab the browser extension reported for their frame
+macro_rules! ocr_text_matches_sql {
+    () => {
+        r#"AND ocr_text.rowid IN (
+            SELECT rowid FROM ocr_text_fts WHERE ocr_text_fts MATCH ?6
+            UNION
+            SELECT context_ocr_text.rowid
+            FROM browser_contexts_fts
+            JOIN frames context_frames ON context_frames.browser_context_id = browser_contexts_fts.rowid
+            JOIN ocr_text context_ocr_text ON context_ocr_text.frame_id = context_frames.id
+            WHERE browser_contexts_fts MATCH ?6
+        )"#
+    };
+}
+
+// the `browser_url` pattern filter of a search: `$domain`, the domain of
+// the url or one it is a subdomain of, and `$path_like`, the `LIKE` pattern
+// of its path
+macro_rules! url_filter_sql {
+    ($domain:literal, $path_like:literal) => {
+        concat!(
+            "(",
+            $domain,
+            " IS NULL OR frames.url_domain = ",
+            $domain,
+            " OR substr(frames.url_domain, -length(",
+            $domain,
+            ") - 1) = '.' || ",
+            $domain,
+            ") AND (",
+            $path_like,
+            " IS NULL OR frames.url_path LIKE ",
+            $path_like,
+            " ESCAPE '\\')"
+        )
+    };
+}
+
+// the sql of `search_ocr`, one fixed string per combination of fts joins so
+// every call reuses a prepared statement. Without a text query each frame
+// joins its latest ocr row, so no GROUP BY keeps the planner from walking
+// frames in timestamp order and stopping at the limit
+macro_rules! search_ocr_sql {
+    ($ocr_join:literal, $fts_join:literal, $fts_condition:expr, $group_by:literal) => {
+        concat!(
+            r#"
+        SELECT
+            ocr_text.frame_id,
+            ocr_text.text as ocr_text,
+            ocr_text.text_json,
+            frames.timestamp,
+            frames.name as frame_name,
+            video_chunks.file_path,
+            frames.offset_index,
+            frames.app_name,
+            ocr_text.ocr_engine,
+            frames.window_name,
+            (
+                SELECT GROUP_CONCAT(tags.name, ',')
+                FROM vision_tags
+                JOIN tags ON vision_tags.tag_id = tags.id
+                WHERE vision_tags.vision_id = frames.id
+            ) as tags,
+            frames.browser_url,
+            frames.focused,
+            ocr_text.tables,
+            video_chunks.device_name,
+            browser_contexts.title as browser_title,
+            browser_contexts.selection_text as browser_selection
+        FROM frames
+        JOIN video_chunks ON frames.video_chunk_id = video_chunks.id
+        LEFT JOIN browser_contexts ON browser_contexts.id = frames.browser_context_id
+        "#,
+            $ocr_join,
+            " ",
+            $fts_join,
+            " WHERE frames.deleted_at IS NULL ",
+            $fts_condition,
+            r#"
+            AND (?2 IS NULL OR frames.timestamp >= ?2)
+            AND (?3 IS NULL OR frames.timestamp <= ?3)
+            AND (?4 IS NULL OR COALESCE(ocr_text.text_length, LENGTH(ocr_text.text)) >= ?4)
+            AND (?5 IS NULL OR COALESCE(ocr_text.text_length, LENGTH(ocr_text.text)) <= ?5)
+            AND (?9 IS NULL OR ocr_text.confidence >= ?9)
+            AND (?10 = 0 OR (ocr_text.tables IS NOT NULL AND (?6 IS NULL OR ocr_text.tables LIKE '%' || ?6 || '%')))
+            AND "#,
+            url_filter_sql!("?12", "?13"),
+            " AND ",
+            tag_filter_sql!("?11", "vision_tags", "vision_id", "frames.id"),
+            " ",
+            $group_by,
+            r#"
+        ORDER BY frames.timestamp DESC
+        LIMIT ?7 OFFSET ?8
+        "#
+        )
+    };
+}
+
+/// The sql of `search_ocr`, with or without the frame metadata and text
+/// matches.
+pub(crate) fn search_ocr_query(frame_filter: bool, text_filter: bool) -> &'static str {
+    match (frame_filter, text_filter) {
+        (false, false) => search_ocr_sql!(
+            "JOIN ocr_text ON ocr_text.rowid = (SELECT MAX(rowid) FROM ocr_text WHERE frame_id = frames.id)",
+            "",
+            "",
+            ""
+        ),
+        (true, false) => search_ocr_sql!(
+            "JOIN ocr_text ON ocr_text.rowid = (SELECT MAX(rowid) FROM ocr_text WHERE frame_id = frames.id)",
+            "JOIN frames_fts ON frames_fts.rowid = frames.id",
+            "AND frames_fts MATCH ?1",
+            ""
+        ),
+        (false, true) => search_ocr_sql!(
+            "JOIN ocr_text ON frames.id = ocr_text.frame_id",
+            "",
+            ocr_text_matches_sql!(),
+            "GROUP BY frames.id"
+        ),
+        (true, true) => search_ocr_sql!(
+            "JOIN ocr_text ON frames.id = ocr_text.frame_id",
+            "JOIN frames_fts ON frames_fts.rowid = frames.id",
+            concat!("AND frames_fts MATCH ?1 ", ocr_text_matches_sql!()),
+            "GROUP BY frames.id"
+        ),
+    }
+}
+
+// the sql of `search_audio`, with or without the fts join
+macro_rules! search_audio_sql {
+    ($fts_join:literal, $fts_condition:literal) => {
+        concat!(
+            r#"
+        SELECT
+            audio_transcriptions.audio_chunk_id,
+            audio_transcriptions.transcription,
+            audio_transcriptions.timestamp,
+            audio_chunks.file_path,
+            audio_transcriptions.offset_index,
+            audio_transcriptions.transcription_engine,
+            GROUP_CONCAT(tags.name, ',') as tags,
+            audio_transcriptions.device as device_name,
+            audio_transcriptions.is_input_device,
+            audio_transcriptions.speaker_id,
+            audio_transcriptions.start_time,
+            audio_transcriptions.end_time
+        FROM audio_transcriptions
+        JOIN audio_chunks ON audio_transcriptions.audio_chunk_id = audio_chunks.id
+        LEFT JOIN speakers ON audio_transcriptions.speaker_id = speakers.id
+        LEFT JOIN audio_tags ON audio_chunks.id = audio_tags.audio_chunk_id
+        LEFT JOIN tags ON audio_tags.tag_id = tags.id
+        "#,
+            $fts_join,
+            " WHERE audio_chunks.deleted_at IS NULL ",
+            $fts_condition,
+            r#"
+            AND (?2 IS NULL OR audio_transcriptions.timestamp >= ?2)
+            AND (?3 IS NULL OR audio_transcriptions.timestamp <= ?3)
+            AND (?4 IS NULL OR COALESCE(audio_transcriptions.text_length, LENGTH(audio_transcriptions.transcription)) >= ?4)
+            AND (?5 IS NULL OR COALESCE(audio_transcriptions.text_length, LENGTH(audio_transcriptions.transcription)) <= ?5)
+            AND (speakers.id IS NULL OR speakers.hallucination = 0)
+            AND (?6 IS NULL OR audio_transcriptions.speaker_id IN (SELECT value FROM json_each(?6)))
+            AND "#,
+            tag_filter_sql!("?9", "audio_tags", "audio_chunk_id", "audio_chunks.id"),
+            r#"
+        GROUP BY audio_transcriptions.audio_chunk_id, audio_transcriptions.offset_index
+        ORDER BY audio_transcriptions.timestamp DESC
+        LIMIT ?7 OFFSET ?8
+        "#
+        )
+    };
+}
+
+/// The sql of `search_audio`, with or without the text match.
+pub(crate) fn search_audio_query(text_filter: bool) -> &'static str {
+    if text_filter {
+        search_audio_sql!(
+            "JOIN audio_transcriptions_fts ON audio_transcriptions_fts.rowid = audio_transcriptions.id",
+            "AND audio_transcriptions_fts MATCH ?1"
+        )
+    } else {
+        search_audio_sql!("", "")
+    }
+}
+
+/// The tag filter of a search as a json object of normalized `any`, `all`
+/// and `none` arrays, none when it filters nothing.
+pub(crate) fn tags_filter(tags: &TagFilter) -> Option<String> {
+    let normalized = |tags: &[String]| -> Vec<String> {
+        tags.iter()
+            .map(|tag| normalize_tag(tag))
+            .filter(|tag| !tag.is_empty())
+            .collect()
+    };
+    let (any, all, none) = (
+        normalized(&tags.any),
+        normalized(&tags.all),
+        normalized(&tags.none),
+    );
+    if any.is_empty() && all.is_empty() && none.is_empty() {
+        return None;
+    }
+    Some(serde_json::json!({ "any": any, "all": all, "none": none }).to_string())
+}
+
+/// A tag with surrounding spaces and empty levels removed, so
+/// ` project//alpha/ ` is stored as `project/alpha`.
+pub fn normalize_tag(tag: &str) -> String {
+    tag.split('/')
+        .map(str::trim)
+        .filter(|level| !level.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// The UUIDv7 `uid` of a row written at `at`, see the `add_row_uids`
+/// migration. Times before the epoch get the current one, as in its backfill.
+pub(crate) fn row_uid(at: DateTime<Utc>) -> String {
+    let at = if at.timestamp_millis() > 0 {
+        at
+    } else {
+        Utc::now()
+    };
+    let timestamp = Timestamp::from_unix(
+        NoContext,
+        at.timestamp() as u64,
+        at.timestamp_subsec_nanos(),
+    );
+    Uuid::new_v7(timestamp).to_string()
+}
+
 pub struct DatabaseManager {
     pub pool: SqlitePool,
+    /// query_only connections for searches, counts and exports, so a slow
+    /// read never holds a connection the recorders are waiting for
+    pub read_pool: SqlitePool,
+    /// webhook rules matched by new content, see `subscribe_webhook_matches`
+    pub(crate) webhook_matches: broadcast::Sender<WebhookMatch>,
+    /// see `with_query_timeout`
+    pub(crate) query_timeout: Option<Duration>,
+    /// see `with_content_processor`
+    pub(crate) content_processors: Vec<Arc<dyn ContentProcessor>>,
+    /// content inserted, see `subscribe_insert_events`
+    pub(crate) insert_events: broadcast::Sender<InsertEvent>,
+    /// the enabled webhook rules with their patterns compiled
+    pub(crate) webhook_rules: RuleCache<(WebhookRule, Regex)>,
+    /// the enabled tag rules with their patterns compiled
+    pub(crate) tag_rules: RuleCache<(TagRule, Option<Regex>)>,
 }
 
 impl DatabaseManager {
@@ -51,11 +369,19 @@ impl DatabaseManager {
             sqlx::Sqlite::create_database(&connection_string).await?;
         }
 
+        let connect_options = SqliteConnectOptions::from_str(&connection_string)?
+            .statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
         let pool = SqlitePoolOptions::new()
             .max_connections(50)
             .min_connections(3) // Minimum number of idle connections
             .acquire_timeout(Duration::from_secs(10))
-            .connect(&connection_string)
+            // takes effect on new databases, lets maintenance give free pages
+            // back a few at a time
+            .connect_with(
+                connect_options
+                    .clone()
+                    .auto_vacuum(SqliteAutoVacuum::Incremental),
+            )
             .await?;
 
         // Enable WAL mode
@@ -73,12 +399,33 @@ impl DatabaseManager {
             .execute(&pool)
             .await?;
 
-        let db_manager = DatabaseManager { pool };
-
         // Run migrations after establishing the connection
-        Self::run_migrations(&db_manager.pool).await?;
+        Self::run_migrations(&pool).await?;
+
+        let read_pool = SqlitePoolOptions::new()
+            .max_connections(READ_POOL_MAX_CONNECTIONS)
+            .min_connections(1)
+            .acquire_timeout(Duration::from_secs(10))
+            .connect_with(
+                connect_options
+                    .pragma("cache_size", "-2000")
+                    .pragma("temp_store", "MEMORY")
+                    .pragma("query_only", "ON"),
+            )
+            .await?;
 
-        Ok(db_manager)
+        let (webhook_matches, _) = broadcast::channel(100);
+        let (insert_events, _) = broadcast::channel(1000);
+        Ok(DatabaseManager {
+            pool,
+            read_pool,
+            webhook_matches,
+            query_timeout: None,
+            content_processors: Vec::new(),
+            insert_events,
+            webhook_rules: RuleCache::default(),
+            tag_rules: RuleCache::default(),
+        })
     }
 
     async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
@@ -92,12 +439,15 @@ impl DatabaseManager {
 
     pub async fn insert_audio_chunk(&self, file_path: &str) -> Result<i64, sqlx::Error> {
         let mut tx = self.pool.begin().await?;
-        let id = sqlx::query("INSERT INTO audio_chunks (file_path, timestamp) VALUES (?1, ?2)")
-            .bind(file_path)
-            .bind(Utc::now())
-            .execute(&mut *tx)
-            .await?
-            .last_insert_rowid();
+        let now = Utc::now();
+        let id =
+            sqlx::query("INSERT INTO audio_chunks (file_path, timestamp, uid) VALUES (?1, ?2, ?3)")
+                .bind(file_path)
+                .bind(now)
+                .bind(row_uid(now))
+                .execute(&mut *tx)
+                .await?
+                .last_insert_rowid();
         tx.commit().await?;
         Ok(id)
     }
@@ -122,12 +472,16 @@ impl DatabaseManager {
         &self,
         audio_chunk_id: i64,
     ) -> Result<i64, sqlx::Error> {
-        let count = sqlx::query_scalar::<_, i64>(
-            "SELECT COUNT(*) FROM audio_transcriptions WHERE audio_chunk_id = ?1",
-        )
-        .bind(audio_chunk_id)
-        .fetch_one(&self.pool)
-        .await?;
+        let (mut conn, guard) = self.read_connection().await?;
+        let count = guard
+            .run(
+                sqlx::query_scalar::<_, i64>(
+                    "SELECT COUNT(*) FROM audio_transcriptions WHERE audio_chunk_id = ?1",
+                )
+                .bind(audio_chunk_id)
+                .fetch_one(&mut *conn),
+            )
+            .await?;
         Ok(count)
     }
 
@@ -143,17 +497,36 @@ impl DatabaseManager {
         start_time: Option<f64>,
         end_time: Option<f64>,
     ) -> Result<i64, sqlx::Error> {
+        let outcome = self
+            .process_content(HookContent {
+                hook: ContentHook::OnTranscription,
+                id: None,
+                text: transcription.to_string(),
+                app_name: None,
+                window_name: None,
+                browser_url: None,
+                device: Some(device.name.clone()),
+                speaker_id,
+            })
+            .await;
+        // dropped by the content processor
+        if outcome.drop {
+            return Ok(0);
+        }
+        let raw_transcription = outcome.raw_text.as_deref().unwrap_or(transcription);
+        let transcription = outcome.text.as_deref().unwrap_or(transcription);
         let text_length = transcription.len() as i64;
+        let now = Utc::now();
         let mut tx = self.pool.begin().await?;
 
         // Insert the full transcription
         let id = sqlx::query(
-            "INSERT INTO audio_transcriptions (audio_chunk_id, transcription, offset_index, timestamp, transcription_engine, device, is_input_device, speaker_id, start_time, end_time, text_length) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            "INSERT INTO audio_transcriptions (audio_chunk_id, transcription, offset_index, timestamp, transcription_engine, device, is_input_device, speaker_id, start_time, end_time, text_length, uid, audio_chunk_uid, speaker_uid) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, (SELECT uid FROM audio_chunks WHERE id = ?1), (SELECT uid FROM speakers WHERE id = ?8))",
         )
         .bind(audio_chunk_id)
         .bind(transcription)
         .bind(offset_index)
-        .bind(Utc::now())
+        .bind(now)
         .bind(transcription_engine)
         .bind(&device.name)
         .bind(device.device_type == DeviceType::Input)
@@ -161,6 +534,7 @@ impl DatabaseManager {
         .bind(start_time)
         .bind(end_time)
         .bind(text_length)
+        .bind(row_uid(now))
         .execute(&mut *tx)
         .await?
         .last_insert_rowid();
@@ -168,6 +542,40 @@ impl DatabaseManager {
         // Commit the transaction for the full transcription
         tx.commit().await?;
 
+        self.check_audio_webhook_rules(id, transcription, speaker_id)
+            .await;
+        self.apply_tag_rules(TagRuleContent {
+            content_type: TagContentType::Audio,
+            id: audio_chunk_id,
+            text: transcription,
+            app_name: None,
+            window_name: None,
+            browser_url: None,
+            timestamp: Utc::now(),
+        })
+        .await;
+        self.add_processor_tags(TagContentType::Audio, audio_chunk_id, outcome.tags)
+            .await;
+        self.record_provenance(
+            TagContentType::Audio,
+            id,
+            raw_transcription,
+            &outcome.stages,
+        )
+        .await;
+        self.publish_insert_event(InsertEvent {
+            content_type: "audio".to_string(),
+            id,
+            target_id: audio_chunk_id,
+            text: transcription.to_string(),
+            app_name: None,
+            window_name: None,
+            browser_url: None,
+            device_name: device.name.clone(),
+            speaker_id,
+            timestamp: Utc::now(),
+        });
+
         Ok(id)
     }
 
@@ -198,7 +606,8 @@ impl DatabaseManager {
     pub async fn insert_speaker(&self, embedding: &[f32]) -> Result<Speaker, SqlxError> {
         let mut tx = self.pool.begin().await?;
 
-        let id = sqlx::query("INSERT INTO speakers (name) VALUES (NULL)")
+        let id = sqlx::query("INSERT INTO speakers (name, uid) VALUES (NULL, ?1)")
+            .bind(row_uid(Utc::now()))
             .execute(&mut *tx)
             .await?
             .last_insert_rowid();
@@ -285,14 +694,29 @@ impl DatabaseManager {
         &self,
         file_path: &str,
         device_name: &str,
+    ) -> Result<i64, sqlx::Error> {
+        self.insert_video_chunk_with_codec(file_path, device_name, None)
+            .await
+    }
+
+    /// `insert_video_chunk` recording the codec the chunk is encoded with.
+    pub async fn insert_video_chunk_with_codec(
+        &self,
+        file_path: &str,
+        device_name: &str,
+        codec: Option<&str>,
     ) -> Result<i64, sqlx::Error> {
         let mut tx = self.pool.begin().await?;
-        let id = sqlx::query("INSERT INTO video_chunks (file_path, device_name) VALUES (?1, ?2)")
-            .bind(file_path)
-            .bind(device_name)
-            .execute(&mut *tx)
-            .await?
-            .last_insert_rowid();
+        let id = sqlx::query(
+            "INSERT INTO video_chunks (file_path, device_name, codec, uid) VALUES (?1, ?2, ?3, ?4)",
+        )
+        .bind(file_path)
+        .bind(device_name)
+        .bind(codec)
+        .bind(row_uid(Utc::now()))
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
         tx.commit().await?;
         Ok(id)
     }
@@ -306,58 +730,38 @@ impl DatabaseManager {
         window_name: Option<&str>,
         focused: bool,
     ) -> Result<i64, sqlx::Error> {
-        let mut tx = self.pool.begin().await?;
-        debug!("insert_frame Transaction started");
-
-        // Get the most recent video_chunk_id and file_path
-        let video_chunk: Option<(i64, String)> = sqlx::query_as(
-            "SELECT id, file_path FROM video_chunks WHERE device_name = ?1 ORDER BY id DESC LIMIT 1",
-        )
-        .bind(device_name)
-        .fetch_optional(&mut *tx)
-        .await?;
-        debug!("Fetched most recent video_chunk: {:?}", video_chunk);
-
-        // If no video chunk is found, return 0
-        let (video_chunk_id, file_path) = match video_chunk {
-            Some((id, path)) => (id, path),
-            None => {
-                debug!("No video chunk found, rolling back transaction");
-                tx.rollback().await?;
-                return Ok(0);
-            }
-        };
-
-        // Calculate the offset_index
-        let offset_index: i64 = sqlx::query_scalar(
-            "SELECT COALESCE(MAX(offset_index), -1) + 1 FROM frames WHERE video_chunk_id = ?1",
-        )
-        .bind(video_chunk_id)
-        .fetch_one(&mut *tx)
-        .await?;
-        debug!("insert_frame Calculated offset_index: {}", offset_index);
-
         let timestamp = timestamp.unwrap_or_else(Utc::now);
 
-        // Insert the new frame with file_path as name and app/window metadata
-        let id = sqlx::query(
-            "INSERT INTO frames (video_chunk_id, offset_index, timestamp, name, browser_url, app_name, window_name, focused) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        // one statement, so one prepared statement and one round trip: the
+        // frame goes in the device's latest video chunk, after its last frame
+        let result = sqlx::query(
+            r#"
+            INSERT INTO frames (video_chunk_id, offset_index, timestamp, name, browser_url, app_name, window_name, focused, uid, video_chunk_uid)
+            SELECT
+                vc.id,
+                COALESCE((SELECT MAX(offset_index) FROM frames WHERE video_chunk_id = vc.id), -1) + 1,
+                ?2, vc.file_path, ?3, ?4, ?5, ?6, ?7, vc.uid
+            FROM (SELECT id, file_path, uid FROM video_chunks WHERE device_name = ?1 ORDER BY id DESC LIMIT 1) vc
+            "#,
         )
-        .bind(video_chunk_id)
-        .bind(offset_index)
+        .bind(device_name)
         .bind(timestamp)
-        .bind(file_path)
         .bind(browser_url)
         .bind(app_name)
         .bind(window_name)
         .bind(focused)
-        .execute(&mut *tx)
-        .await?
-        .last_insert_rowid();
-        debug!("insert_frame Inserted new frame with id: {}", id);
+        .bind(row_uid(timestamp))
+        .execute(&self.pool)
+        .await?;
 
-        // Commit the transaction
-        tx.commit().await?;
+        // no video chunk for the device yet, or the capture blocklist keeps
+        // the frame out
+        if result.rows_affected() == 0 {
+            debug!("frame of {} not stored", device_name);
+            return Ok(0);
+        }
+        let id = result.last_insert_rowid();
+        debug!("insert_frame Inserted new frame with id: {}", id);
 
         Ok(id)
     }
@@ -369,22 +773,287 @@ impl DatabaseManager {
         text_json: &str,
         ocr_engine: Arc<OcrEngine>,
     ) -> Result<(), sqlx::Error> {
+        // the frame was not stored, see `insert_frame`
+        if frame_id == 0 {
+            debug!("no frame to insert OCR text for");
+            return Ok(());
+        }
+        let processed = self.process_ocr_text(frame_id, text, text_json).await?;
+        // dropped by the content processor
+        let Some(frame) = &processed.frame else {
+            return Ok(());
+        };
+        let (text, text_json) = (frame.text.as_str(), frame.text_json.as_str());
         let text_length = text.len() as i64;
         let mut tx = self.pool.begin().await?;
-        sqlx::query("INSERT INTO ocr_text (frame_id, text, text_json, ocr_engine, text_length) VALUES (?1, ?2, ?3, ?4, ?5)")
+        let ocr_engine = ocr_engine.column_value();
+        let blocks =
+            Self::insert_ocr_blocks(&mut tx, frame_id, text_json, &ocr_engine, true).await?;
+        sqlx::query("INSERT INTO ocr_text (frame_id, text, text_json, ocr_engine, text_length, confidence, tables) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)")
             .bind(frame_id)
             .bind(text)
             .bind(text_json)
-            .bind(format!("{:?}", *ocr_engine))
+            .bind(&ocr_engine)
             .bind(text_length)
+            .bind(frame_confidence(&blocks))
+            .bind(tables_json(&blocks))
             .execute(&mut *tx)
             .await?;
 
         tx.commit().await?;
         debug!("OCR text inserted into db successfully");
+        self.record_provenance(
+            TagContentType::Vision,
+            frame_id,
+            &processed.raw_text,
+            &processed.stages,
+        )
+        .await;
+        self.check_ocr_webhook_rules(frame_id, text).await;
+        self.apply_ocr_tag_rules(frame_id, text).await;
+        self.publish_ocr_insert(frame_id, text).await;
+        self.process_stored_frames([(frame_id, frame, processed.tags.clone())])
+            .await;
         Ok(())
     }
 
+    /// Inserts frames and their OCR text in the device's latest video chunk,
+    /// one after the other, in one transaction instead of two per frame.
+    /// Returns their ids in order, 0 for the frames the capture blocklist
+    /// keeps out, none when the device has no video chunk yet.
+    pub async fn insert_frames_batch(
+        &self,
+        device_name: &str,
+        frames: &[NewFrame],
+        ocr_engine: Arc<OcrEngine>,
+    ) -> Result<Vec<i64>, sqlx::Error> {
+        if frames.is_empty() {
+            return Ok(Vec::new());
+        }
+        let processed = self.process_new_frames(frames).await;
+        let mut tx = self.pool.begin().await?;
+        let video_chunk: Option<(i64, String, i64, Option<String>)> = sqlx::query_as(
+            r#"
+            SELECT vc.id, vc.file_path,
+                COALESCE((SELECT MAX(offset_index) FROM frames WHERE video_chunk_id = vc.id), -1) + 1,
+                vc.uid
+            FROM video_chunks vc
+            WHERE vc.device_name = ?1
+            ORDER BY vc.id DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(device_name)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some((video_chunk_id, file_path, first_offset, video_chunk_uid)) = video_chunk else {
+            debug!("No video chunk found for {}", device_name);
+            tx.rollback().await?;
+            return Ok(Vec::new());
+        };
+
+        let ocr_engine = ocr_engine.column_value();
+        let now = Utc::now();
+        let mut ids = Vec::with_capacity(frames.len());
+        for (index, processed) in processed.iter().enumerate() {
+            // dropped by the content processor
+            let Some(frame) = &processed.frame else {
+                ids.push(0);
+                continue;
+            };
+            let timestamp = frame.timestamp.unwrap_or(now);
+            let result = sqlx::query(
+                "INSERT INTO frames (video_chunk_id, offset_index, timestamp, name, browser_url, app_name, window_name, focused, uid, video_chunk_uid) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            )
+            .bind(video_chunk_id)
+            .bind(first_offset + index as i64)
+            .bind(timestamp)
+            .bind(&file_path)
+            .bind(&frame.browser_url)
+            .bind(&frame.app_name)
+            .bind(&frame.window_name)
+            .bind(frame.focused)
+            .bind(row_uid(timestamp))
+            .bind(&video_chunk_uid)
+            .execute(&mut *tx)
+            .await?;
+            // kept out by the capture blocklist, and its text with it
+            if result.rows_affected() == 0 {
+                ids.push(0);
+                continue;
+            }
+            let id = result.last_insert_rowid();
+
+            let blocks =
+                Self::insert_ocr_blocks(&mut tx, id, &frame.text_json, &ocr_engine, true).await?;
+            sqlx::query("INSERT INTO ocr_text (frame_id, text, text_json, ocr_engine, text_length, confidence, tables) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)")
+                .bind(id)
+                .bind(&frame.text)
+                .bind(&frame.text_json)
+                .bind(&ocr_engine)
+                .bind(frame.text.len() as i64)
+                .bind(frame_confidence(&blocks))
+                .bind(tables_json(&blocks))
+                .execute(&mut *tx)
+                .await?;
+            ids.push(id);
+        }
+        tx.commit().await?;
+        debug!("inserted {} frames with their OCR text", ids.len());
+
+        for (id, processed) in ids.iter().zip(&processed) {
+            if let (Some(frame), true) = (&processed.frame, *id != 0) {
+                self.record_provenance(
+                    TagContentType::Vision,
+                    *id,
+                    &processed.raw_text,
+                    &processed.stages,
+                )
+                .await;
+                self.check_ocr_webhook_rules(*id, &frame.text).await;
+                self.apply_tag_rules(TagRuleContent {
+                    content_type: TagContentType::Vision,
+                    id: *id,
+                    text: &frame.text,
+                    app_name: frame.app_name.as_deref(),
+                    window_name: frame.window_name.as_deref(),
+                    browser_url: frame.browser_url.as_deref(),
+                    timestamp: frame.timestamp.unwrap_or(now),
+                })
+                .await;
+                self.publish_insert_event(InsertEvent {
+                    content_type: "ocr".to_string(),
+                    id: *id,
+                    target_id: *id,
+                    text: frame.text.clone(),
+                    app_name: frame.app_name.clone(),
+                    window_name: frame.window_name.clone(),
+                    browser_url: frame.browser_url.clone(),
+                    device_name: device_name.to_string(),
+                    speaker_id: None,
+                    timestamp: frame.timestamp.unwrap_or(now),
+                });
+            }
+        }
+        self.process_stored_frames(ids.iter().zip(processed.iter()).filter_map(
+            |(id, processed)| match &processed.frame {
+                Some(frame) if *id != 0 => Some((*id, frame, processed.tags.clone())),
+                _ => None,
+            },
+        ))
+        .await;
+        Ok(ids)
+    }
+
+    /// Replaces the OCR output of an existing frame, e.g. after re-running a
+    /// newer engine on it. Inserts a row if the frame had no OCR text yet.
+    pub async fn replace_ocr_text(
+        &self,
+        frame_id: i64,
+        text: &str,
+        text_json: &str,
+        ocr_engine: Arc<OcrEngine>,
+    ) -> Result<(), sqlx::Error> {
+        let processed = self.process_ocr_text(frame_id, text, text_json).await?;
+        // dropped by the content processor, the old text is kept
+        let Some(frame) = &processed.frame else {
+            return Ok(());
+        };
+        let (text, text_json) = (frame.text.as_str(), frame.text_json.as_str());
+        let text_length = text.len() as i64;
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM ocr_text_blocks WHERE frame_id = ?1")
+            .bind(frame_id)
+            .execute(&mut *tx)
+            .await?;
+        let ocr_engine = ocr_engine.column_value();
+        let blocks =
+            Self::insert_ocr_blocks(&mut tx, frame_id, text_json, &ocr_engine, false).await?;
+        let confidence = frame_confidence(&blocks);
+        let tables = tables_json(&blocks);
+        let updated = sqlx::query(
+            "UPDATE ocr_text SET text = ?1, text_json = ?2, ocr_engine = ?3, text_length = ?4, confidence = ?5, tables = ?6 WHERE frame_id = ?7",
+        )
+        .bind(text)
+        .bind(text_json)
+        .bind(&ocr_engine)
+        .bind(text_length)
+        .bind(confidence)
+        .bind(&tables)
+        .bind(frame_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        if updated == 0 {
+            sqlx::query("INSERT INTO ocr_text (frame_id, text, text_json, ocr_engine, text_length, confidence, tables) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)")
+                .bind(frame_id)
+                .bind(text)
+                .bind(text_json)
+                .bind(&ocr_engine)
+                .bind(text_length)
+                .bind(confidence)
+                .bind(&tables)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        debug!("OCR text replaced for frame {}", frame_id);
+        Ok(())
+    }
+
+    /// Stores the blocks of `text_json` with their confidence and returns them.
+    /// With `track_spans`, blocks still on screen since the previous frame
+    /// only extend their span.
+    async fn insert_ocr_blocks(
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        frame_id: i64,
+        text_json: &str,
+        ocr_engine: &str,
+        track_spans: bool,
+    ) -> Result<Vec<OcrBlock>, sqlx::Error> {
+        let blocks = parse_ocr_blocks(text_json, ocr_engine);
+        let continued = if track_spans {
+            Self::track_text_spans(tx, frame_id, &blocks).await?
+        } else {
+            vec![false; blocks.len()]
+        };
+        for (index, block) in blocks.iter().enumerate() {
+            // the span already records this block as still on screen
+            if continued[index] {
+                continue;
+            }
+            sqlx::query(
+                "INSERT INTO ocr_text_blocks (frame_id, block_index, text, confidence) VALUES (?1, ?2, ?3, ?4)",
+            )
+            .bind(frame_id)
+            .bind(index as i64)
+            .bind(&block.text)
+            .bind(block.confidence)
+            .execute(&mut **tx)
+            .await?;
+        }
+        Ok(blocks)
+    }
+
+    /// Returns the ids of all frames captured between `start_time` and `end_time`.
+    pub async fn get_frame_ids_in_range(
+        &self,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<i64>, sqlx::Error> {
+        sqlx::query_scalar::<_, i64>(
+            "SELECT id FROM frames WHERE timestamp BETWEEN ?1 AND ?2 ORDER BY timestamp ASC LIMIT ?3",
+        )
+        .bind(start_time)
+        .bind(end_time)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub async fn search(
         &self,
@@ -402,11 +1071,14 @@ impl DatabaseManager {
         frame_name: Option<&str>,
         browser_url: Option<&str>,
         focused: Option<bool>,
+        min_confidence: Option<f64>,
+        in_tables: bool,
+        tags: &TagFilter,
     ) -> Result<Vec<SearchResult>, sqlx::Error> {
         let mut results = Vec::new();
 
-        // if focused or browser_url is present, we run only on OCR
-        if focused.is_some() || browser_url.is_some() {
+        // if focused, browser_url or in_tables is present, we run only on OCR
+        if focused.is_some() || browser_url.is_some() || in_tables {
             content_type = ContentType::OCR;
         }
 
@@ -429,6 +1101,9 @@ impl DatabaseManager {
                                 frame_name,
                                 browser_url,
                                 focused,
+                                min_confidence,
+                                in_tables,
+                                tags,
                             ),
                             self.search_audio(
                                 query,
@@ -438,7 +1113,8 @@ impl DatabaseManager {
                                 end_time,
                                 min_length,
                                 max_length,
-                                speaker_ids
+                                speaker_ids,
+                                tags,
                             ),
                             self.search_ui_monitoring(
                                 query,
@@ -448,6 +1124,7 @@ impl DatabaseManager {
                                 end_time,
                                 limit,
                                 offset,
+                                tags,
                             )
                         )?;
                         (ocr, Some(audio), ui)
@@ -467,6 +1144,9 @@ impl DatabaseManager {
                                 frame_name,
                                 browser_url,
                                 focused,
+                                min_confidence,
+                                in_tables,
+                                tags,
                             ),
                             self.search_ui_monitoring(
                                 query,
@@ -476,6 +1156,7 @@ impl DatabaseManager {
                                 end_time,
                                 limit,
                                 offset,
+                                tags,
                             )
                         )?;
                         (ocr, None, ui)
@@ -486,6 +1167,21 @@ impl DatabaseManager {
                     results.extend(audio.into_iter().map(SearchResult::Audio));
                 }
                 results.extend(ui_results.into_iter().map(SearchResult::UI));
+                if frame_name.is_none() {
+                    let clipboard_results = self
+                        .search_clipboard(
+                            query,
+                            app_name,
+                            window_name,
+                            start_time,
+                            end_time,
+                            limit,
+                            offset,
+                            tags,
+                        )
+                        .await?;
+                    results.extend(clipboard_results.into_iter().map(SearchResult::Clipboard));
+                }
             }
             ContentType::OCR => {
                 let ocr_results = self
@@ -502,6 +1198,9 @@ impl DatabaseManager {
                         frame_name,
                         browser_url,
                         focused,
+                        min_confidence,
+                        in_tables,
+                        tags,
                     )
                     .await?;
                 results.extend(ocr_results.into_iter().map(SearchResult::OCR));
@@ -518,6 +1217,7 @@ impl DatabaseManager {
                             min_length,
                             max_length,
                             speaker_ids,
+                            tags,
                         )
                         .await?;
                     results.extend(audio_results.into_iter().map(SearchResult::Audio));
@@ -533,6 +1233,7 @@ impl DatabaseManager {
                         end_time,
                         limit,
                         offset,
+                        tags,
                     )
                     .await?;
                 results.extend(ui_results.into_iter().map(SearchResult::UI));
@@ -548,6 +1249,7 @@ impl DatabaseManager {
                         min_length,
                         max_length,
                         speaker_ids,
+                        tags,
                     )
                     .await?;
                 let ui_results = self
@@ -559,6 +1261,7 @@ impl DatabaseManager {
                         end_time,
                         limit / 2,
                         offset,
+                        tags,
                     )
                     .await?;
 
@@ -580,6 +1283,9 @@ impl DatabaseManager {
                         frame_name,
                         browser_url,
                         focused,
+                        min_confidence,
+                        in_tables,
+                        tags,
                     )
                     .await?;
                 let ui_results = self
@@ -591,6 +1297,7 @@ impl DatabaseManager {
                         end_time,
                         limit / 2,
                         offset,
+                        tags,
                     )
                     .await?;
 
@@ -608,6 +1315,7 @@ impl DatabaseManager {
                         min_length,
                         max_length,
                         speaker_ids,
+                        tags,
                     )
                     .await?;
                 let ocr_results = self
@@ -624,12 +1332,30 @@ impl DatabaseManager {
                         frame_name,
                         browser_url,
                         focused,
+                        min_confidence,
+                        in_tables,
+                        tags,
                     )
                     .await?;
 
                 results.extend(audio_results.into_iter().map(SearchResult::Audio));
                 results.extend(ocr_results.into_iter().map(SearchResult::OCR));
             }
+            ContentType::Clipboard => {
+                let clipboard_results = self
+                    .search_clipboard(
+                        query,
+                        app_name,
+                        window_name,
+                        start_time,
+                        end_time,
+                        limit,
+                        offset,
+                        tags,
+                    )
+                    .await?;
+                results.extend(clipboard_results.into_iter().map(SearchResult::Clipboard));
+            }
         }
 
         // Sort results by timestamp in descending order
@@ -638,11 +1364,13 @@ impl DatabaseManager {
                 SearchResult::OCR(ocr) => ocr.timestamp,
                 SearchResult::Audio(audio) => audio.timestamp,
                 SearchResult::UI(ui) => ui.timestamp,
+                SearchResult::Clipboard(clipboard) => clipboard.timestamp,
             };
             let timestamp_b = match b {
                 SearchResult::OCR(ocr) => ocr.timestamp,
                 SearchResult::Audio(audio) => audio.timestamp,
                 SearchResult::UI(ui) => ui.timestamp,
+                SearchResult::Clipboard(clipboard) => clipboard.timestamp,
             };
             timestamp_b.cmp(&timestamp_a)
         });
@@ -672,6 +1400,9 @@ impl DatabaseManager {
         frame_name: Option<&str>,
         browser_url: Option<&str>,
         focused: Option<bool>,
+        min_confidence: Option<f64>,
+        in_tables: bool,
+        tags: &TagFilter,
     ) -> Result<Vec<OCRResult>, sqlx::Error> {
         let mut frame_fts_parts = Vec::new();
 
@@ -685,10 +1416,9 @@ impl DatabaseManager {
                 frame_fts_parts.push(format!("window_name:{}", window));
             }
         }
-        if let Some(browser) = browser_url {
-            if !browser.is_empty() {
-                frame_fts_parts.push(format!("browser_url:{}", browser));
-            }
+        let url_pattern = browser_url.and_then(UrlPattern::parse);
+        if let Some(url_query) = url_pattern.as_ref().and_then(UrlPattern::fts_query) {
+            frame_fts_parts.push(url_query);
         }
         if let Some(is_focused) = focused {
             frame_fts_parts.push(format!("focused:{}", if is_focused { "1" } else { "0" }));
@@ -701,105 +1431,40 @@ impl DatabaseManager {
 
         let frame_query = frame_fts_parts.join(" ");
 
-        let sql = format!(
-            r#"
-        SELECT
-            ocr_text.frame_id,
-            ocr_text.text as ocr_text,
-            ocr_text.text_json,
-            frames.timestamp,
-            frames.name as frame_name,
-            video_chunks.file_path,
-            frames.offset_index,
-            frames.app_name,
-            ocr_text.ocr_engine,
-            frames.window_name,
-            GROUP_CONCAT(tags.name, ',') as tags,
-            frames.browser_url,
-            frames.focused
-        FROM frames
-        JOIN video_chunks ON frames.video_chunk_id = video_chunks.id
-        JOIN ocr_text ON frames.id = ocr_text.frame_id
-        LEFT JOIN vision_tags ON frames.id = vision_tags.vision_id
-        LEFT JOIN tags ON vision_tags.tag_id = tags.id
-        {frame_fts_join}
-        {ocr_fts_join}
-        WHERE 1=1
-            {frame_fts_condition}
-            {ocr_fts_condition}
-            AND (?2 IS NULL OR frames.timestamp >= ?2)
-            AND (?3 IS NULL OR frames.timestamp <= ?3)
-            AND (?4 IS NULL OR COALESCE(ocr_text.text_length, LENGTH(ocr_text.text)) >= ?4)
-            AND (?5 IS NULL OR COALESCE(ocr_text.text_length, LENGTH(ocr_text.text)) <= ?5)
-        GROUP BY frames.id
-        ORDER BY frames.timestamp DESC
-        LIMIT ?7 OFFSET ?8
-        "#,
-            frame_fts_join = if frame_query.trim().is_empty() {
-                ""
-            } else {
-                "JOIN frames_fts ON frames.id = frames_fts.id"
-            },
-            ocr_fts_join = if query.trim().is_empty() {
-                ""
-            } else {
-                "JOIN ocr_text_fts ON ocr_text.frame_id = ocr_text_fts.frame_id"
-            },
-            frame_fts_condition = if frame_query.trim().is_empty() {
-                ""
-            } else {
-                "AND frames_fts MATCH ?1"
-            },
-            ocr_fts_condition = if query.trim().is_empty() {
-                ""
-            } else {
-                "AND ocr_text_fts MATCH ?6"
-            }
-        );
+        let sql = search_ocr_query(!frame_query.trim().is_empty(), !query.trim().is_empty());
 
-        let query_builder = sqlx::query_as(&sql);
+        let query_builder = sqlx::query_as(sql);
 
-        let raw_results: Vec<OCRResultRaw> = query_builder
-            .bind(if frame_query.trim().is_empty() {
-                None
-            } else {
-                Some(&frame_query)
-            })
-            .bind(start_time)
-            .bind(end_time)
-            .bind(min_length.map(|l| l as i64))
-            .bind(max_length.map(|l| l as i64))
-            .bind(if query.trim().is_empty() {
-                None
-            } else {
-                Some(query)
-            })
-            .bind(limit)
-            .bind(offset)
-            .fetch_all(&self.pool)
+        let (mut conn, guard) = self.read_connection().await?;
+        let raw_results: Vec<OCRResultRaw> = guard
+            .run(
+                query_builder
+                    .bind(if frame_query.trim().is_empty() {
+                        None
+                    } else {
+                        Some(&frame_query)
+                    })
+                    .bind(start_time)
+                    .bind(end_time)
+                    .bind(min_length.map(|l| l as i64))
+                    .bind(max_length.map(|l| l as i64))
+                    .bind(if query.trim().is_empty() {
+                        None
+                    } else {
+                        Some(query)
+                    })
+                    .bind(limit)
+                    .bind(offset)
+                    .bind(min_confidence)
+                    .bind(in_tables)
+                    .bind(tags_filter(tags))
+                    .bind(url_pattern.as_ref().and_then(UrlPattern::domain))
+                    .bind(url_pattern.as_ref().and_then(UrlPattern::path_like))
+                    .fetch_all(&mut *conn),
+            )
             .await?;
 
-        Ok(raw_results
-            .into_iter()
-            .map(|raw| OCRResult {
-                frame_id: raw.frame_id,
-                ocr_text: raw.ocr_text,
-                text_json: raw.text_json,
-                timestamp: raw.timestamp,
-                frame_name: raw.frame_name,
-                file_path: raw.file_path,
-                offset_index: raw.offset_index,
-                app_name: raw.app_name,
-                ocr_engine: raw.ocr_engine,
-                window_name: raw.window_name,
-                tags: raw
-                    .tags
-                    .map(|t| t.split(',').map(String::from).collect())
-                    .unwrap_or_default(),
-                browser_url: raw.browser_url,
-                focused: raw.focused,
-            })
-            .collect())
+        Ok(raw_results.into_iter().map(OCRResult::from).collect())
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -813,99 +1478,31 @@ impl DatabaseManager {
         min_length: Option<usize>,
         max_length: Option<usize>,
         speaker_ids: Option<Vec<i64>>,
+        tags: &TagFilter,
     ) -> Result<Vec<AudioResult>, sqlx::Error> {
-        // base query for audio search
-        let mut base_sql = String::from(
-            "SELECT
-                audio_transcriptions.audio_chunk_id,
-                audio_transcriptions.transcription,
-                audio_transcriptions.timestamp,
-                audio_chunks.file_path,
-                audio_transcriptions.offset_index,
-                audio_transcriptions.transcription_engine,
-                GROUP_CONCAT(tags.name, ',') as tags,
-                audio_transcriptions.device as device_name,
-                audio_transcriptions.is_input_device,
-                audio_transcriptions.speaker_id,
-                audio_transcriptions.start_time,
-                audio_transcriptions.end_time
-             FROM audio_transcriptions
-             JOIN audio_chunks ON audio_transcriptions.audio_chunk_id = audio_chunks.id
-             LEFT JOIN speakers ON audio_transcriptions.speaker_id = speakers.id
-             LEFT JOIN audio_tags ON audio_chunks.id = audio_tags.audio_chunk_id
-             LEFT JOIN tags ON audio_tags.tag_id = tags.id",
-        );
-        // if query is provided, join the corresponding fts table
-        if !query.is_empty() {
-            base_sql.push_str(" JOIN audio_transcriptions_fts ON audio_transcriptions_fts.audio_chunk_id = audio_transcriptions.audio_chunk_id");
-        }
-
-        // build where clause conditions in order
-        let mut conditions = Vec::new();
-        if !query.is_empty() {
-            conditions.push("audio_transcriptions_fts MATCH ?");
-        }
-        if start_time.is_some() {
-            conditions.push("audio_transcriptions.timestamp >= ?");
-        }
-        if end_time.is_some() {
-            conditions.push("audio_transcriptions.timestamp <= ?");
-        }
-        if min_length.is_some() {
-            conditions.push("COALESCE(audio_transcriptions.text_length, LENGTH(audio_transcriptions.transcription)) >= ?");
-        }
-        if max_length.is_some() {
-            conditions.push("COALESCE(audio_transcriptions.text_length, LENGTH(audio_transcriptions.transcription)) <= ?");
-        }
-        conditions.push("(speakers.id IS NULL OR speakers.hallucination = 0)");
-        if speaker_ids.is_some() {
-            conditions.push("(json_array_length(?) = 0 OR audio_transcriptions.speaker_id IN (SELECT value FROM json_each(?)))");
-        }
-
-        let where_clause = if conditions.is_empty() {
-            "WHERE 1=1".to_owned()
-        } else {
-            format!("WHERE {}", conditions.join(" AND "))
-        };
+        let sql = search_audio_query(!query.is_empty());
 
-        // complete sql with group, order, limit and offset
-        let sql = format!(
-            "{} {} GROUP BY audio_transcriptions.audio_chunk_id, audio_transcriptions.offset_index ORDER BY audio_transcriptions.timestamp DESC LIMIT ? OFFSET ?",
-            base_sql, where_clause
-        );
-
-        // prepare binding for speaker_ids (if any)
-        let speaker_ids_json = speaker_ids.as_ref().map_or_else(
-            || "[]".to_string(),
-            |ids| serde_json::to_string(&ids).unwrap_or_else(|_| "[]".to_string()),
-        );
+        // an empty list filters nothing, as no list
+        let speaker_ids_json = speaker_ids
+            .as_ref()
+            .filter(|ids| !ids.is_empty())
+            .map(|ids| serde_json::to_string(ids).unwrap_or_else(|_| "[]".to_string()));
 
-        let mut query_builder = sqlx::query_as::<_, AudioResultRaw>(&sql);
-
-        // bind parameters in the same order as added to the where clause
-        if !query.is_empty() {
-            query_builder = query_builder.bind(query);
-        }
-        if let Some(start) = start_time {
-            query_builder = query_builder.bind(start);
-        }
-        if let Some(end) = end_time {
-            query_builder = query_builder.bind(end);
-        }
-        if let Some(min) = min_length {
-            query_builder = query_builder.bind(min as i64);
-        }
-        if let Some(max) = max_length {
-            query_builder = query_builder.bind(max as i64);
-        }
-        if speaker_ids.is_some() {
-            query_builder = query_builder
-                .bind(&speaker_ids_json)
-                .bind(&speaker_ids_json);
-        }
-        query_builder = query_builder.bind(limit as i64).bind(offset as i64);
+        let query_builder = sqlx::query_as::<_, AudioResultRaw>(sql)
+            .bind(if query.is_empty() { None } else { Some(query) })
+            .bind(start_time)
+            .bind(end_time)
+            .bind(min_length.map(|l| l as i64))
+            .bind(max_length.map(|l| l as i64))
+            .bind(speaker_ids_json)
+            .bind(limit as i64)
+            .bind(offset as i64)
+            .bind(tags_filter(tags));
 
-        let results_raw: Vec<AudioResultRaw> = query_builder.fetch_all(&self.pool).await?;
+        let (mut conn, guard) = self.read_connection().await?;
+        let results_raw: Vec<AudioResultRaw> =
+            guard.run(query_builder.fetch_all(&mut *conn)).await?;
+        drop(conn);
 
         // map raw results into audio result type
         let futures: Vec<_> = results_raw
@@ -957,7 +1554,7 @@ impl DatabaseManager {
             JOIN
                 video_chunks ON frames.video_chunk_id = video_chunks.id
             WHERE
-                frames.id = ?1
+                frames.id = ?1 AND frames.deleted_at IS NULL
             "#,
         )
         .bind(frame_id)
@@ -980,13 +1577,28 @@ impl DatabaseManager {
         frame_name: Option<&str>,
         browser_url: Option<&str>,
         focused: Option<bool>,
+        min_confidence: Option<f64>,
+        in_tables: bool,
+        tags: &TagFilter,
     ) -> Result<usize, sqlx::Error> {
-        // if focused or browser_url is present, we run only on OCR
-        if focused.is_some() || browser_url.is_some() {
+        // if focused, browser_url or in_tables is present, we run only on OCR
+        if focused.is_some() || browser_url.is_some() || in_tables {
             content_type = ContentType::OCR;
         }
 
+        if content_type == ContentType::Clipboard {
+            return self
+                .count_clipboard(query, app_name, window_name, start_time, end_time, tags)
+                .await;
+        }
+
         if content_type == ContentType::All {
+            let clipboard_count = if frame_name.is_none() {
+                self.count_clipboard(query, app_name, window_name, start_time, end_time, tags)
+                    .await?
+            } else {
+                0
+            };
             // Create boxed futures to avoid infinite size issues with recursion
             let ocr_future = Box::pin(self.count_search_results(
                 query,
@@ -1001,6 +1613,9 @@ impl DatabaseManager {
                 frame_name,
                 browser_url,
                 focused,
+                min_confidence,
+                in_tables,
+                tags,
             ));
 
             let ui_future = Box::pin(self.count_search_results(
@@ -1016,6 +1631,9 @@ impl DatabaseManager {
                 None,
                 None,
                 None,
+                None,
+                false,
+                tags,
             ));
 
             if app_name.is_none() && window_name.is_none() {
@@ -1032,14 +1650,17 @@ impl DatabaseManager {
                     None,
                     None,
                     None,
+                    None,
+                    false,
+                    tags,
                 ));
 
                 let (ocr_count, audio_count, ui_count) =
                     tokio::try_join!(ocr_future, audio_future, ui_future)?;
-                return Ok(ocr_count + audio_count + ui_count);
+                return Ok(ocr_count + audio_count + ui_count + clipboard_count);
             } else {
                 let (ocr_count, ui_count) = tokio::try_join!(ocr_future, ui_future)?;
-                return Ok(ocr_count + ui_count);
+                return Ok(ocr_count + ui_count + clipboard_count);
             }
         }
 
@@ -1074,14 +1695,18 @@ impl DatabaseManager {
                 ui_fts_parts.push(format!("window:\"{}\"", window));
             }
         }
-        if let Some(browser) = browser_url {
-            if !browser.is_empty() {
-                frame_fts_parts.push(format!("browser_url:{}", browser));
-            }
+        let url_pattern = browser_url.and_then(UrlPattern::parse);
+        if let Some(url_query) = url_pattern.as_ref().and_then(UrlPattern::fts_query) {
+            frame_fts_parts.push(url_query);
         }
         if let Some(is_focused) = focused {
             frame_fts_parts.push(format!("focused:{}", if is_focused { "1" } else { "0" }));
         }
+        if let Some(frame_name) = frame_name {
+            if !frame_name.is_empty() {
+                frame_fts_parts.push(format!("name:{}", frame_name));
+            }
+        }
 
         let frame_query = frame_fts_parts.join(" ");
         let ocr_query = ocr_fts_parts.join(" ");
@@ -1090,39 +1715,58 @@ impl DatabaseManager {
         let sql = match content_type {
             ContentType::OCR => format!(
                 r#"SELECT COUNT(DISTINCT frames.id)
-                   FROM {base_table}
-                   WHERE {where_clause}
+                   FROM frames
+                   JOIN ocr_text ON frames.id = ocr_text.frame_id
+                   {frame_fts_join}
+                   WHERE frames.deleted_at IS NULL
+                       {frame_fts_condition}
+                       {ocr_fts_condition}
                        AND (?2 IS NULL OR frames.timestamp >= ?2)
                        AND (?3 IS NULL OR frames.timestamp <= ?3)
                        AND (?4 IS NULL OR COALESCE(ocr_text.text_length, LENGTH(ocr_text.text)) >= ?4)
                        AND (?5 IS NULL OR COALESCE(ocr_text.text_length, LENGTH(ocr_text.text)) <= ?5)
-                       AND (?6 IS NULL OR frames.name LIKE '%' || ?6 || '%')"#,
-                base_table = if ocr_query.is_empty() {
-                    "frames
-                     JOIN ocr_text ON frames.id = ocr_text.frame_id"
+                       AND (?7 IS NULL OR ocr_text.confidence >= ?7)
+                       AND (?8 = 0 OR (ocr_text.tables IS NOT NULL AND (?6 IS NULL OR ocr_text.tables LIKE '%' || ?6 || '%')))
+                       AND {url_filter}
+                       AND {tag_filter}"#,
+                url_filter = url_filter_sql!("?10", "?11"),
+                tag_filter = tag_filter_sql!("?9", "vision_tags", "vision_id", "frames.id"),
+                frame_fts_join = if frame_query.is_empty() {
+                    ""
                 } else {
-                    "ocr_text_fts
-                     JOIN ocr_text ON ocr_text_fts.frame_id = ocr_text.frame_id
-                     JOIN frames ON ocr_text.frame_id = frames.id"
+                    "JOIN frames_fts ON frames_fts.rowid = frames.id"
                 },
-                where_clause = if ocr_query.is_empty() {
-                    "1=1"
+                frame_fts_condition = if frame_query.is_empty() {
+                    ""
                 } else {
-                    "ocr_text_fts MATCH ?1"
+                    "AND frames_fts MATCH ?1"
+                },
+                ocr_fts_condition = if ocr_query.is_empty() {
+                    ""
+                } else {
+                    ocr_text_matches_sql!()
                 }
             ),
             ContentType::UI => format!(
                 r#"SELECT COUNT(DISTINCT ui_monitoring.id)
                    FROM {table}
                    WHERE {match_condition}
+                       AND ui_monitoring.deleted_at IS NULL
                        AND (?2 IS NULL OR timestamp >= ?2)
                        AND (?3 IS NULL OR timestamp <= ?3)
                        AND (?4 IS NULL OR COALESCE(text_length, LENGTH(ui_monitoring.text_output)) >= ?4)
-                       AND (?5 IS NULL OR COALESCE(text_length, LENGTH(ui_monitoring.text_output)) <= ?5)"#,
+                       AND (?5 IS NULL OR COALESCE(text_length, LENGTH(ui_monitoring.text_output)) <= ?5)
+                       AND {tag_filter}"#,
+                tag_filter = tag_filter_sql!(
+                    "?6",
+                    "ui_monitoring_tags",
+                    "ui_monitoring_id",
+                    "ui_monitoring.id"
+                ),
                 table = if ui_query.is_empty() {
                     "ui_monitoring"
                 } else {
-                    "ui_monitoring_fts JOIN ui_monitoring ON ui_monitoring_fts.ui_id = ui_monitoring.id"
+                    "ui_monitoring_fts JOIN ui_monitoring ON ui_monitoring_fts.rowid = ui_monitoring.id"
                 },
                 match_condition = if ui_query.is_empty() {
                     "1=1"
@@ -1139,11 +1783,19 @@ impl DatabaseManager {
                        AND (?4 IS NULL OR COALESCE(audio_transcriptions.text_length, LENGTH(audio_transcriptions.transcription)) >= ?4)
                        AND (?5 IS NULL OR COALESCE(audio_transcriptions.text_length, LENGTH(audio_transcriptions.transcription)) <= ?5)
                        AND (json_array_length(?6) = 0 OR audio_transcriptions.speaker_id IN (SELECT value FROM json_each(?6)))
+                       AND audio_transcriptions.audio_chunk_id NOT IN (SELECT id FROM audio_chunks WHERE deleted_at IS NOT NULL)
+                       AND {tag_filter}
                 "#,
+                tag_filter = tag_filter_sql!(
+                    "?7",
+                    "audio_tags",
+                    "audio_chunk_id",
+                    "audio_transcriptions.audio_chunk_id"
+                ),
                 table = if query.is_empty() {
                     "audio_transcriptions"
                 } else {
-                    "audio_transcriptions_fts JOIN audio_transcriptions ON audio_transcriptions_fts.audio_chunk_id = audio_transcriptions.audio_chunk_id"
+                    "audio_transcriptions_fts JOIN audio_transcriptions ON audio_transcriptions_fts.rowid = audio_transcriptions.id"
                 },
                 match_condition = if query.is_empty() {
                     "1=1"
@@ -1156,52 +1808,69 @@ impl DatabaseManager {
 
         let count: i64 = match content_type {
             ContentType::OCR => {
-                sqlx::query_scalar(&sql)
-                    .bind(if frame_query.is_empty() && ocr_query.is_empty() {
-                        "*".to_owned()
-                    } else if frame_query.is_empty() {
-                        ocr_query
-                    } else {
-                        frame_query
-                    })
-                    .bind(start_time)
-                    .bind(end_time)
-                    .bind(min_length.map(|l| l as i64))
-                    .bind(max_length.map(|l| l as i64))
-                    .bind(frame_name)
-                    .fetch_one(&self.pool)
+                let (mut conn, guard) = self.read_connection().await?;
+                guard
+                    .run(
+                        sqlx::query_scalar(&sql)
+                            .bind((!frame_query.is_empty()).then_some(frame_query))
+                            .bind(start_time)
+                            .bind(end_time)
+                            .bind(min_length.map(|l| l as i64))
+                            .bind(max_length.map(|l| l as i64))
+                            .bind((!ocr_query.is_empty()).then_some(ocr_query))
+                            .bind(min_confidence)
+                            .bind(in_tables)
+                            .bind(tags_filter(tags))
+                            .bind(url_pattern.as_ref().and_then(UrlPattern::domain))
+                            .bind(url_pattern.as_ref().and_then(UrlPattern::path_like))
+                            .fetch_one(&mut *conn),
+                    )
                     .await?
             }
             ContentType::UI => {
-                sqlx::query_scalar(&sql)
-                    .bind(if ui_query.is_empty() { "*" } else { &ui_query })
-                    .bind(start_time)
-                    .bind(end_time)
-                    .bind(min_length.map(|l| l as i64))
-                    .bind(max_length.map(|l| l as i64))
-                    .fetch_one(&self.pool)
+                let (mut conn, guard) = self.read_connection().await?;
+                guard
+                    .run(
+                        sqlx::query_scalar(&sql)
+                            .bind(if ui_query.is_empty() { "*" } else { &ui_query })
+                            .bind(start_time)
+                            .bind(end_time)
+                            .bind(min_length.map(|l| l as i64))
+                            .bind(max_length.map(|l| l as i64))
+                            .bind(tags_filter(tags))
+                            .fetch_one(&mut *conn),
+                    )
                     .await?
             }
             ContentType::Audio => {
-                sqlx::query_scalar(&sql)
-                    .bind(if query.is_empty() { "*" } else { query })
-                    .bind(start_time)
-                    .bind(end_time)
-                    .bind(min_length.map(|l| l as i64))
-                    .bind(max_length.map(|l| l as i64))
-                    .bind(json_array)
-                    .fetch_one(&self.pool)
+                let (mut conn, guard) = self.read_connection().await?;
+                guard
+                    .run(
+                        sqlx::query_scalar(&sql)
+                            .bind(if query.is_empty() { "*" } else { query })
+                            .bind(start_time)
+                            .bind(end_time)
+                            .bind(min_length.map(|l| l as i64))
+                            .bind(max_length.map(|l| l as i64))
+                            .bind(json_array)
+                            .bind(tags_filter(tags))
+                            .fetch_one(&mut *conn),
+                    )
                     .await?
             }
             _ => {
-                sqlx::query_scalar(&sql)
-                    .bind(query)
-                    .bind(start_time)
-                    .bind(end_time)
-                    .bind(min_length.map(|l| l as i64))
-                    .bind(max_length.map(|l| l as i64))
-                    .bind(json_array)
-                    .fetch_one(&self.pool)
+                let (mut conn, guard) = self.read_connection().await?;
+                guard
+                    .run(
+                        sqlx::query_scalar(&sql)
+                            .bind(query)
+                            .bind(start_time)
+                            .bind(end_time)
+                            .bind(min_length.map(|l| l as i64))
+                            .bind(max_length.map(|l| l as i64))
+                            .bind(json_array)
+                            .fetch_one(&mut *conn),
+                    )
                     .await?
             }
         };
@@ -1256,12 +1925,93 @@ impl DatabaseManager {
         ))
     }
 
+    /// Time of the oldest frame, transcription or ui text, `None` when
+    /// nothing was recorded yet.
+    pub async fn get_earliest_timestamp(&self) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+        let mut earliest: Option<DateTime<Utc>> = None;
+        for table in ["frames", "audio_transcriptions", "ui_monitoring"] {
+            let oldest: Option<(DateTime<Utc>,)> = sqlx::query_as(&format!(
+                "SELECT timestamp FROM {} ORDER BY timestamp ASC LIMIT 1",
+                table
+            ))
+            .fetch_optional(&self.pool)
+            .await?;
+            if let Some((timestamp,)) = oldest {
+                earliest = Some(earliest.map_or(timestamp, |e| e.min(timestamp)));
+            }
+        }
+        Ok(earliest)
+    }
+
+    /// Time of the latest frame of each screen and transcription of each
+    /// audio device, among those recorded after `since`.
+    pub async fn get_latest_timestamps_by_device(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<DeviceActivity>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT video_chunks.device_name, 'vision' AS kind, MAX(frames.timestamp) AS last_seen
+            FROM frames
+            JOIN video_chunks ON frames.video_chunk_id = video_chunks.id
+            WHERE frames.timestamp > ?1
+            GROUP BY video_chunks.device_name
+            UNION ALL
+            SELECT device, 'audio', MAX(timestamp)
+            FROM audio_transcriptions
+            WHERE timestamp > ?1
+            GROUP BY device
+            "#,
+        )
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Frames of each monitor stored between `start` and `end`, and how many
+    /// of them have no ocr text: a failed OCR stores its frame with empty
+    /// text, or with none when it never ran.
+    pub async fn get_ocr_failures_by_device(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<DeviceOcrFailures>, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        guard
+            .run(
+                sqlx::query_as(
+                    r#"
+                    SELECT video_chunks.device_name,
+                        COUNT(*) AS frames,
+                        SUM(NOT EXISTS (
+                            SELECT 1 FROM ocr_text
+                            WHERE ocr_text.frame_id = frames.id AND TRIM(ocr_text.text) != ''
+                        )) AS without_text
+                    FROM frames
+                    JOIN video_chunks ON frames.video_chunk_id = video_chunks.id
+                    WHERE frames.timestamp >= ?1 AND frames.timestamp < ?2
+                        AND frames.deleted_at IS NULL
+                    GROUP BY video_chunks.device_name
+                    ORDER BY video_chunks.device_name
+                    "#,
+                )
+                .bind(start)
+                .bind(end)
+                .fetch_all(&mut *conn),
+            )
+            .await
+    }
+
+    /// Tags a frame or an audio chunk. A `/` separates the levels of
+    /// hierarchical tags such as `project/alpha/design`, see `normalize_tag`,
+    /// and an alias tags with the tag it points to.
     pub async fn add_tags(
         &self,
         id: i64,
         content_type: TagContentType,
         tags: Vec<String>,
     ) -> Result<(), SqlxError> {
+        let tags = self.canonical_tags(&tags).await?;
         match content_type {
             TagContentType::Vision => self.add_tags_to_vision(id, tags).await,
             TagContentType::Audio => self.add_tags_to_audio(id, tags).await,
@@ -1371,6 +2121,7 @@ impl DatabaseManager {
         content_type: TagContentType,
         tags: Vec<String>,
     ) -> Result<(), SqlxError> {
+        let tags = self.canonical_tags(&tags).await?;
         match content_type {
             TagContentType::Vision => self.remove_vision_tags(id, tags).await,
             TagContentType::Audio => self.remove_audio_tags(id, tags).await,
@@ -1421,7 +2172,24 @@ impl DatabaseManager {
         Ok(())
     }
     pub async fn execute_raw_sql(&self, query: &str) -> Result<serde_json::Value, sqlx::Error> {
-        let rows = sqlx::query(query).fetch_all(&self.pool).await?;
+        Ok(self.execute_raw_sql_counted(query).await?.0)
+    }
+
+    /// Like `execute_raw_sql`, also returning how many rows the query
+    /// inserted, updated or deleted, its triggers included.
+    pub async fn execute_raw_sql_counted(
+        &self,
+        query: &str,
+    ) -> Result<(serde_json::Value, i64), sqlx::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let total_changes = "SELECT total_changes()";
+        let changes_before: i64 = sqlx::query_scalar(total_changes)
+            .fetch_one(&mut *conn)
+            .await?;
+        let rows = sqlx::query(query).fetch_all(&mut *conn).await?;
+        let changes: i64 = sqlx::query_scalar(total_changes)
+            .fetch_one(&mut *conn)
+            .await?;
 
         let result: Vec<serde_json::Map<String, serde_json::Value>> = rows
             .iter()
@@ -1453,8 +2221,9 @@ impl DatabaseManager {
             })
             .collect();
 
-        Ok(serde_json::Value::Array(
-            result.into_iter().map(serde_json::Value::Object).collect(),
+        Ok((
+            serde_json::Value::Array(result.into_iter().map(serde_json::Value::Object).collect()),
+            changes - changes_before,
         ))
     }
 
@@ -1477,7 +2246,7 @@ impl DatabaseManager {
         FROM frames f
         JOIN video_chunks vc ON f.video_chunk_id = vc.id
         LEFT JOIN ocr_text ot ON f.id = ot.frame_id
-        WHERE f.timestamp >= ?1 AND f.timestamp <= ?2
+        WHERE f.timestamp >= ?1 AND f.timestamp <= ?2 AND f.deleted_at IS NULL
         ORDER BY f.timestamp DESC, f.offset_index DESC
     "#;
 
@@ -1496,7 +2265,7 @@ impl DatabaseManager {
                  as REAL) as duration_secs
         FROM audio_transcriptions at
         JOIN audio_chunks ac ON at.audio_chunk_id = ac.id
-        WHERE at.timestamp >= ?1 AND at.timestamp <= ?2
+        WHERE at.timestamp >= ?1 AND at.timestamp <= ?2 AND ac.deleted_at IS NULL
         ORDER BY at.timestamp DESC
         "#;
 
@@ -1579,6 +2348,7 @@ impl DatabaseManager {
         end_time: Option<DateTime<Utc>>,
         limit: u32,
         offset: u32,
+        tags: &TagFilter,
     ) -> Result<Vec<UiContent>, sqlx::Error> {
         // combine search aspects into single fts query
         let mut fts_parts = Vec::new();
@@ -1596,7 +2366,7 @@ impl DatabaseManager {
         let base_sql = if combined_query.is_empty() {
             "ui_monitoring"
         } else {
-            "ui_monitoring_fts JOIN ui_monitoring ON ui_monitoring_fts.ui_id = ui_monitoring.id"
+            "ui_monitoring_fts JOIN ui_monitoring ON ui_monitoring_fts.rowid = ui_monitoring.id"
         };
 
         let where_clause = if combined_query.is_empty() {
@@ -1617,7 +2387,8 @@ impl DatabaseManager {
                 video_chunks.file_path,
                 frames.offset_index,
                 frames.name as frame_name,
-                frames.browser_url
+                frames.browser_url,
+                video_chunks.device_name
             FROM {}
             LEFT JOIN frames ON
                 frames.timestamp BETWEEN
@@ -1625,27 +2396,64 @@ impl DatabaseManager {
                     AND datetime(ui_monitoring.timestamp, '+1 seconds')
             LEFT JOIN video_chunks ON frames.video_chunk_id = video_chunks.id
             {}
+                AND ui_monitoring.deleted_at IS NULL
                 AND (?2 IS NULL OR ui_monitoring.timestamp >= ?2)
                 AND (?3 IS NULL OR ui_monitoring.timestamp <= ?3)
+                AND {}
             GROUP BY ui_monitoring.id
             ORDER BY ui_monitoring.timestamp DESC
             LIMIT ?4 OFFSET ?5
             "#,
-            base_sql, where_clause
+            base_sql,
+            where_clause,
+            tag_filter_sql!(
+                "?6",
+                "ui_monitoring_tags",
+                "ui_monitoring_id",
+                "ui_monitoring.id"
+            )
         );
 
-        sqlx::query_as(&sql)
-            .bind(if combined_query.is_empty() {
-                "*".to_owned()
-            } else {
-                combined_query
-            })
-            .bind(start_time)
-            .bind(end_time)
-            .bind(limit)
-            .bind(offset)
-            .fetch_all(&self.pool)
-            .await
+        let (mut conn, guard) = self.read_connection().await?;
+        let mut results: Vec<UiContent> = guard
+            .run(
+                sqlx::query_as(&sql)
+                    .bind(if combined_query.is_empty() {
+                        "*".to_owned()
+                    } else {
+                        combined_query
+                    })
+                    .bind(start_time)
+                    .bind(end_time)
+                    .bind(limit)
+                    .bind(offset)
+                    .bind(tags_filter(tags))
+                    .fetch_all(&mut *conn),
+            )
+            .await?;
+
+        let ids: Vec<i64> = results.iter().map(|ui| ui.id).collect();
+        let ui_tags: Vec<(i64, String)> = guard
+            .run(
+                sqlx::query_as(
+                    r#"
+                    SELECT ui_monitoring_tags.ui_monitoring_id, tags.name
+                    FROM ui_monitoring_tags
+                    JOIN tags ON tags.id = ui_monitoring_tags.tag_id
+                    WHERE ui_monitoring_tags.ui_monitoring_id IN (SELECT value FROM json_each(?1))
+                    ORDER BY tags.name
+                    "#,
+                )
+                .bind(serde_json::to_string(&ids).unwrap_or_else(|_| "[]".to_string()))
+                .fetch_all(&mut *conn),
+            )
+            .await?;
+        for (id, tag) in ui_tags {
+            if let Some(ui) = results.iter_mut().find(|ui| ui.id == id) {
+                ui.tags.push(tag);
+            }
+        }
+        Ok(results)
     }
 
     // Add tags to UI monitoring entry
@@ -1831,11 +2639,12 @@ impl DatabaseManager {
     }
 
     pub async fn search_speakers(&self, name_prefix: &str) -> Result<Vec<Speaker>, sqlx::Error> {
-        sqlx::query_as::<_, Speaker>(
+        let (mut conn, guard) = self.read_connection().await?;
+        guard.run(sqlx::query_as::<_, Speaker>(
             "SELECT DISTINCT * FROM speakers WHERE name LIKE ? || '%' AND hallucination = 0",
         )
         .bind(name_prefix)
-        .fetch_all(&self.pool)
+        .fetch_all(&mut *conn))
         .await
     }
 
@@ -1975,13 +2784,16 @@ impl DatabaseManager {
             .device_name
             .unwrap_or_else(|| "imported_files".to_string());
 
-        let video_chunk_id =
-            sqlx::query("INSERT INTO video_chunks (device_name, file_path) VALUES (?1, ?2)")
-                .bind(device_name)
-                .bind(file_path)
-                .execute(&mut *tx)
-                .await?
-                .last_insert_rowid();
+        let video_chunk_uid = row_uid(Utc::now());
+        let video_chunk_id = sqlx::query(
+            "INSERT INTO video_chunks (device_name, file_path, uid) VALUES (?1, ?2, ?3)",
+        )
+        .bind(device_name)
+        .bind(file_path)
+        .bind(&video_chunk_uid)
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
 
         // 2. Create frames with correct timestamps and default name
         let mut frame_ids = Vec::with_capacity(frames.len());
@@ -1993,12 +2805,14 @@ impl DatabaseManager {
             debug!("frame timestamp: {}", frame_timestamp);
 
             let frame_id = sqlx::query(
-                "INSERT INTO frames (video_chunk_id, offset_index, timestamp, name) VALUES (?1, ?2, ?3, ?4)",
+                "INSERT INTO frames (video_chunk_id, offset_index, timestamp, name, uid, video_chunk_uid) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
             )
             .bind(video_chunk_id)
             .bind(i as i64)
             .bind(frame_timestamp)
             .bind(metadata.name.as_deref().unwrap_or(file_path))  // Use reference instead of clone
+            .bind(row_uid(frame_timestamp))
+            .bind(&video_chunk_uid)
             .execute(&mut *tx)
             .await?
             .last_insert_rowid();
@@ -2016,37 +2830,140 @@ impl DatabaseManager {
         Ok(frame_ids)
     }
 
+    /// Stores the embedding of a frame's text, given as a JSON array, with the
+    /// quantization of the index. The float vector of a quantized embedding
+    /// goes to the float cache, used to rescore search results.
     pub async fn insert_embeddings(
         &self,
         frame_id: i64,
         embedding: String,
     ) -> Result<(), sqlx::Error> {
-        sqlx::query("INSERT INTO ocr_text_embeddings (frame_id, embedding) VALUES (?1, ?2)")
-            .bind(frame_id)
-            .bind(embedding)
-            .execute(&self.pool)
+        let settings = self
+            .get_embedding_index_settings(OCR_TEXT_EMBEDDINGS)
+            .await?;
+        let vector = match settings.quantization {
+            EmbeddingQuantization::Float32 => None,
+            _ => serde_json::from_str::<Vec<f32>>(&embedding).ok(),
+        };
+        let Some(vector) = vector else {
+            sqlx::query("INSERT INTO ocr_text_embeddings (frame_id, embedding) VALUES (?1, ?2)")
+                .bind(frame_id)
+                .bind(embedding)
+                .execute(&self.pool)
+                .await?;
+            return Ok(());
+        };
+
+        let mut tx = self.pool.begin().await?;
+        let id: i64 = sqlx::query_scalar(
+            "INSERT INTO ocr_text_embeddings (frame_id, embedding, quantization) VALUES (?1, ?2, ?3) RETURNING id",
+        )
+        .bind(frame_id)
+        .bind(settings.quantization.quantize(&vector))
+        .bind(settings.quantization.as_str())
+        .fetch_one(&mut *tx)
+        .await?;
+        if settings.float_cache_size > 0 {
+            sqlx::query(
+                "INSERT INTO ocr_text_embeddings_float_cache (embedding_id, embedding) VALUES (?1, ?2)",
+            )
+            .bind(id)
+            .bind(vector.as_bytes())
+            .execute(&mut *tx)
             .await?;
+            prune_float_cache(&mut tx, settings.float_cache_size).await?;
+        }
+        tx.commit().await?;
         Ok(())
     }
 
+    /// Frames whose text embedding is within `threshold` (cosine distance) of
+    /// `embedding`, closest first, or with the best similarity decayed by age
+    /// given a `half_life`. Quantized embeddings are matched coarsely in
+    /// sqlite, then the best candidates are rescored with their cached float
+    /// vector, or the dequantized one.
     pub async fn search_similar_embeddings(
         &self,
         embedding: Vec<f32>,
         limit: u32,
         threshold: f32,
+        half_life: Option<chrono::Duration>,
     ) -> Result<Vec<OCRResult>, sqlx::Error> {
         debug!("searching similar embeddings with threshold {}", threshold);
 
-        let sql = r#"
-            WITH embedding_matches AS (
-                SELECT
-                    frame_id,
-                    vec_distance_cosine(embedding, vec_f32(?1)) as similarity
-                FROM ocr_text_embeddings
-                WHERE vec_distance_cosine(embedding, vec_f32(?1)) < ?2
-                ORDER BY similarity ASC
-                LIMIT ?3
+        // float rows are filtered by sqlite, quantized ones after rescoring
+        let candidates_sql = r#"
+            SELECT
+                e.frame_id,
+                e.embedding,
+                e.quantization,
+                c.embedding as cached,
+                frames.timestamp,
+                CASE e.quantization
+                    WHEN 'int8' THEN vec_distance_cosine(vec_int8(e.embedding), vec_int8(?2))
+                    WHEN 'binary' THEN vec_distance_hamming(vec_bit(e.embedding), vec_bit(?3)) / ?4
+                    ELSE vec_distance_cosine(e.embedding, vec_f32(?1))
+                END as distance
+            FROM ocr_text_embeddings e
+            JOIN frames ON frames.id = e.frame_id
+            LEFT JOIN ocr_text_embeddings_float_cache c ON c.embedding_id = e.id
+            WHERE CASE e.quantization
+                WHEN 'float32' THEN vec_distance_cosine(e.embedding, vec_f32(?1)) < ?5
+                ELSE 1
+            END
+            ORDER BY distance ASC
+            LIMIT ?6
+        "#;
+
+        let (mut conn, guard) = self.read_connection().await?;
+        let candidates = guard
+            .run(
+                sqlx::query(candidates_sql)
+                    .bind(embedding.as_bytes())
+                    .bind(EmbeddingQuantization::Int8.quantize(&embedding))
+                    .bind(EmbeddingQuantization::Binary.quantize(&embedding))
+                    .bind(embedding.len() as f64)
+                    .bind(threshold)
+                    .bind(limit.saturating_mul(RESCORE_OVERSAMPLING))
+                    .fetch_all(&mut *conn),
             )
+            .await?;
+
+        let now = Utc::now();
+        // frame ids with their score, higher is better
+        let mut matches: Vec<(i64, f64)> = Vec::new();
+        for row in candidates {
+            let frame_id: i64 = row.get("frame_id");
+            let quantization = row
+                .get::<String, _>("quantization")
+                .parse::<EmbeddingQuantization>()
+                .unwrap_or_default();
+            let distance = match (quantization, row.get::<Option<Vec<u8>>, _>("cached")) {
+                (EmbeddingQuantization::Float32, _) => row.get::<f64, _>("distance") as f32,
+                (_, Some(cached)) => cosine_distance(&embedding, &decode_embedding(&cached)),
+                (quantization, None) => cosine_distance(
+                    &embedding,
+                    &quantization.dequantize(&row.get::<Vec<u8>, _>("embedding")),
+                ),
+            };
+            if distance >= threshold || matches.iter().any(|(id, _)| *id == frame_id) {
+                continue;
+            }
+            let similarity = 1.0 - distance as f64;
+            let score = match half_life {
+                Some(half_life) => decayed_score(similarity, row.get("timestamp"), now, half_life),
+                None => similarity,
+            };
+            matches.push((frame_id, score));
+        }
+        matches.sort_by(|a, b| b.1.total_cmp(&a.1));
+        matches.truncate(limit as usize);
+        if matches.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let frame_ids: Vec<i64> = matches.iter().map(|(id, _)| *id).collect();
+        let sql = r#"
             SELECT
                 ocr_text.frame_id,
                 ocr_text.text as ocr_text,
@@ -2059,47 +2976,31 @@ impl DatabaseManager {
                 ocr_text.ocr_engine,
                 frames.window_name,
                 GROUP_CONCAT(tags.name, ',') as tags,
-                frames.browser_url
-            FROM embedding_matches
-            JOIN ocr_text ON embedding_matches.frame_id = ocr_text.frame_id
+                frames.browser_url,
+                frames.focused,
+                ocr_text.tables,
+                video_chunks.device_name
+            FROM ocr_text
             JOIN frames ON ocr_text.frame_id = frames.id
             JOIN video_chunks ON frames.video_chunk_id = video_chunks.id
             LEFT JOIN vision_tags ON frames.id = vision_tags.vision_id
             LEFT JOIN tags ON vision_tags.tag_id = tags.id
+            WHERE ocr_text.frame_id IN (SELECT value FROM json_each(?1))
             GROUP BY ocr_text.frame_id
-            ORDER BY embedding_matches.similarity ASC
         "#;
 
-        let bytes = embedding.as_bytes();
-
-        let raw_results: Vec<OCRResultRaw> = sqlx::query_as(sql)
-            .bind(bytes)
-            .bind(threshold)
-            .bind(limit)
-            .fetch_all(&self.pool)
-            .await?;
-
-        Ok(raw_results
+        let mut results: Vec<OCRResult> = guard
+            .run(
+                sqlx::query_as::<_, OCRResultRaw>(sql)
+                    .bind(serde_json::to_string(&frame_ids).unwrap_or_else(|_| "[]".to_string()))
+                    .fetch_all(&mut *conn),
+            )
+            .await?
             .into_iter()
-            .map(|raw| OCRResult {
-                frame_id: raw.frame_id,
-                ocr_text: raw.ocr_text,
-                text_json: raw.text_json,
-                timestamp: raw.timestamp,
-                file_path: raw.file_path,
-                offset_index: raw.offset_index,
-                app_name: raw.app_name,
-                ocr_engine: raw.ocr_engine,
-                window_name: raw.window_name,
-                frame_name: raw.frame_name,
-                tags: raw
-                    .tags
-                    .map(|t| t.split(',').map(String::from).collect())
-                    .unwrap_or_default(),
-                browser_url: raw.browser_url,
-                focused: raw.focused,
-            })
-            .collect())
+            .map(OCRResult::from)
+            .collect();
+        results.sort_by_key(|result| frame_ids.iter().position(|id| *id == result.frame_id));
+        Ok(results)
     }
 
     // Add method to update frame names
@@ -2165,6 +3066,10 @@ impl DatabaseManager {
             ("REINDEX;", "rebuild indexes"),
             ("ANALYZE;", "update statistics"),
             ("VACUUM;", "final vacuum"), // Second vacuum after reindex
+            (
+                "INSERT INTO ocr_text_fts(ocr_text_fts) VALUES('rebuild');",
+                "rebuild ocr text search index",
+            ), // vacuum may renumber the ocr_text rows it points to
         ];
 
         for (query, step) in recovery_steps {
@@ -2225,7 +3130,7 @@ impl DatabaseManager {
         order: Order,
         app_names: Option<Vec<String>>,
     ) -> Result<Vec<SearchMatch>, sqlx::Error> {
-        let mut conditions = Vec::new();
+        let mut conditions = vec!["f.deleted_at IS NULL"];
         let mut owned_conditions = Vec::new();
 
         if start_time.is_some() {
@@ -2273,7 +3178,13 @@ SELECT
     COALESCE(f.app_name, o.app_name) as app_name,
     COALESCE(f.window_name, o.window_name) as window_name,
     o.text as ocr_text,
-    o.text_json
+    o.text_json,
+    COALESCE(o.ocr_engine, '') as ocr_engine,
+    f.offset_index,
+    COALESCE((julianday(f.timestamp) - julianday((
+        SELECT MIN(chunk_frames.timestamp) FROM frames chunk_frames
+        WHERE chunk_frames.video_chunk_id = f.video_chunk_id
+    ))) * 86400.0, 0.0) as video_offset_secs
 FROM frames f
 INNER JOIN ocr_text o ON f.id = o.frame_id
 WHERE {}
@@ -2318,18 +3229,32 @@ LIMIT ? OFFSET ?
         // Bind limit and offset
         query_builder = query_builder.bind(limit as i64).bind(offset as i64);
 
-        let rows = query_builder.fetch_all(&self.pool).await?;
+        let (mut conn, guard) = self.read_connection().await?;
+        let rows = guard.run(query_builder.fetch_all(&mut *conn)).await?;
 
         Ok(rows
             .iter()
             .map(|row| {
-                let positions = if !query.is_empty() {
+                let mut positions = if !query.is_empty() {
                     let ocr_blocks: Vec<OcrTextBlock> =
                         serde_json::from_str(&row.text_json).unwrap_or_default();
                     find_matching_positions(&ocr_blocks, query)
                 } else {
                     Vec::new()
                 };
+                for position in &mut positions {
+                    position.link =
+                        position
+                            .bounds
+                            .normalized(&row.ocr_engine)
+                            .map(|bounds| FrameRegionLink {
+                                frame_id: row.id,
+                                url: region_url(row.id, &bounds),
+                                bounds,
+                                offset_index: row.offset_index,
+                                video_offset_secs: row.video_offset_secs,
+                            });
+                }
 
                 SearchMatch {
                     frame_id: row.id,
@@ -2369,6 +3294,7 @@ pub fn find_matching_positions(blocks: &[OcrTextBlock], query: &str) -> Vec<Text
                         width: block.width.parse::<f32>().unwrap_or(0.0),
                         height: block.height.parse::<f32>().unwrap_or(0.0),
                     },
+                    link: None,
                 })
             } else {
                 None
@@ -2377,6 +3303,14 @@ pub fn find_matching_positions(blocks: &[OcrTextBlock], query: &str) -> Vec<Text
         .collect()
 }
 
+// the path of the endpoint serving `frame_id` cropped to `bounds`
+fn region_url(frame_id: i64, bounds: &TextBounds) -> String {
+    format!(
+        "/frames/{}/region?left={}&top={}&width={}&height={}",
+        frame_id, bounds.left, bounds.top, bounds.width, bounds.height
+    )
+}
+
 fn calculate_confidence(positions: &[TextPosition]) -> f32 {
     if positions.is_empty() {
         return 0.0;
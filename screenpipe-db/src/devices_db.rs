@@ -0,0 +1,69 @@
+use chrono::Utc;
+
+use crate::{DatabaseManager, Device};
+
+const DEVICE_COLUMNS: &str = "id, kind, name, host, enabled, fps, engine, created_at, last_seen_at";
+
+impl DatabaseManager {
+    /// Adds a device of this machine to the registry, or marks it seen when
+    /// it's there already. Its settings are kept.
+    pub async fn register_device(&self, kind: &str, name: &str) -> Result<Device, sqlx::Error> {
+        sqlx::query_as(&format!(
+            r#"
+            INSERT INTO devices (kind, name, host, last_seen_at)
+            VALUES (?1, ?2, '', ?3)
+            ON CONFLICT (kind, name, host) DO UPDATE SET last_seen_at = excluded.last_seen_at
+            RETURNING {DEVICE_COLUMNS}
+            "#
+        ))
+        .bind(kind)
+        .bind(name)
+        .bind(Utc::now())
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn list_devices(&self) -> Result<Vec<Device>, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        guard
+            .run(
+                sqlx::query_as(&format!(
+                    "SELECT {DEVICE_COLUMNS} FROM devices ORDER BY host, kind, name"
+                ))
+                .fetch_all(&mut *conn),
+            )
+            .await
+    }
+
+    pub async fn get_device(&self, id: i64) -> Result<Option<Device>, sqlx::Error> {
+        sqlx::query_as(&format!(
+            "SELECT {DEVICE_COLUMNS} FROM devices WHERE id = ?1"
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Replaces the settings of a device, none when there's no such device.
+    pub async fn update_device(
+        &self,
+        id: i64,
+        enabled: bool,
+        fps: Option<f64>,
+        engine: Option<&str>,
+    ) -> Result<Option<Device>, sqlx::Error> {
+        sqlx::query_as(&format!(
+            r#"
+            UPDATE devices SET enabled = ?2, fps = ?3, engine = ?4
+            WHERE id = ?1
+            RETURNING {DEVICE_COLUMNS}
+            "#
+        ))
+        .bind(id)
+        .bind(enabled)
+        .bind(fps)
+        .bind(engine)
+        .fetch_optional(&self.pool)
+        .await
+    }
+}
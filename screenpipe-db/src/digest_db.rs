@@ -0,0 +1,88 @@
+use chrono::{DateTime, Utc};
+
+use crate::{DatabaseManager, KeywordHits, SearchedQuery};
+
+impl DatabaseManager {
+    /// Queries whose results were opened between `start` and `end`, the most
+    /// opened first. Only recorded when click tracking is enabled.
+    pub async fn get_searched_queries(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<SearchedQuery>, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        guard
+            .run(
+                sqlx::query_as::<_, SearchedQuery>(
+                    r#"
+            SELECT query, COUNT(*) as clicks
+            FROM search_clicks
+            WHERE clicked_at >= ?1 AND clicked_at < ?2
+            GROUP BY query
+            ORDER BY clicks DESC, MAX(clicked_at) DESC
+            LIMIT ?3
+            "#,
+                )
+                .bind(start)
+                .bind(end)
+                .bind(limit)
+                .fetch_all(&mut *conn),
+            )
+            .await
+    }
+
+    /// Counts the frames whose text, and the transcriptions, containing
+    /// `keyword` between `start` and `end`, ignoring case.
+    pub async fn count_keyword_hits(
+        &self,
+        keyword: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<KeywordHits, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        let frames: i64 = guard
+            .run(
+                sqlx::query_scalar(
+                    r#"
+            SELECT COUNT(DISTINCT ocr_text.frame_id)
+            FROM ocr_text
+            JOIN frames f ON f.id = ocr_text.frame_id
+            WHERE f.timestamp >= ?1 AND f.timestamp < ?2
+                AND f.deleted_at IS NULL
+                AND instr(lower(ocr_text.text), lower(?3)) > 0
+            "#,
+                )
+                .bind(start)
+                .bind(end)
+                .bind(keyword)
+                .fetch_one(&mut *conn),
+            )
+            .await?;
+
+        let transcriptions: i64 = guard
+            .run(
+                sqlx::query_scalar(
+                    r#"
+            SELECT COUNT(*)
+            FROM audio_transcriptions at
+            JOIN audio_chunks ac ON at.audio_chunk_id = ac.id
+            WHERE at.timestamp >= ?1 AND at.timestamp < ?2
+                AND ac.deleted_at IS NULL
+                AND instr(lower(at.transcription), lower(?3)) > 0
+            "#,
+                )
+                .bind(start)
+                .bind(end)
+                .bind(keyword)
+                .fetch_one(&mut *conn),
+            )
+            .await?;
+
+        Ok(KeywordHits {
+            keyword: keyword.to_string(),
+            frames,
+            transcriptions,
+        })
+    }
+}
@@ -0,0 +1,194 @@
+use sqlx::Row;
+
+use crate::{DatabaseManager, EmbeddingIndexSettings, EmbeddingIndexStatus, EmbeddingQuantization};
+
+/// Index of the OCR text embeddings, the only one so far.
+pub const OCR_TEXT_EMBEDDINGS: &str = "ocr_text";
+
+pub(crate) const DEFAULT_FLOAT_CACHE_SIZE: i64 = 10_000;
+
+impl DatabaseManager {
+    pub async fn get_embedding_index_settings(
+        &self,
+        index_name: &str,
+    ) -> Result<EmbeddingIndexSettings, sqlx::Error> {
+        let row = sqlx::query(
+            "SELECT quantization, float_cache_size FROM embedding_index_settings WHERE index_name = ?1",
+        )
+        .bind(index_name)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(match row {
+            Some(row) => EmbeddingIndexSettings {
+                index_name: index_name.to_string(),
+                quantization: row
+                    .get::<String, _>("quantization")
+                    .parse()
+                    .unwrap_or_default(),
+                float_cache_size: row.get("float_cache_size"),
+            },
+            None => EmbeddingIndexSettings {
+                index_name: index_name.to_string(),
+                quantization: EmbeddingQuantization::Float32,
+                float_cache_size: DEFAULT_FLOAT_CACHE_SIZE,
+            },
+        })
+    }
+
+    /// Changes how new embeddings are stored. Existing ones keep their
+    /// quantization until `convert_ocr_text_embeddings` gets to them.
+    pub async fn set_embedding_index_settings(
+        &self,
+        index_name: &str,
+        quantization: EmbeddingQuantization,
+        float_cache_size: i64,
+    ) -> Result<EmbeddingIndexSettings, sqlx::Error> {
+        let float_cache_size = float_cache_size.max(0);
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO embedding_index_settings (index_name, quantization, float_cache_size)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT(index_name) DO UPDATE SET
+                quantization = excluded.quantization,
+                float_cache_size = excluded.float_cache_size
+            "#,
+        )
+        .bind(index_name)
+        .bind(quantization.as_str())
+        .bind(float_cache_size)
+        .execute(&mut *tx)
+        .await?;
+        if index_name == OCR_TEXT_EMBEDDINGS {
+            prune_float_cache(&mut tx, float_cache_size).await?;
+        }
+        tx.commit().await?;
+
+        Ok(EmbeddingIndexSettings {
+            index_name: index_name.to_string(),
+            quantization,
+            float_cache_size,
+        })
+    }
+
+    pub async fn get_ocr_text_embeddings_status(
+        &self,
+    ) -> Result<EmbeddingIndexStatus, sqlx::Error> {
+        let settings = self
+            .get_embedding_index_settings(OCR_TEXT_EMBEDDINGS)
+            .await?;
+        let row = sqlx::query(
+            r#"
+            SELECT
+                COUNT(*) as rows,
+                COALESCE(SUM(quantization != ?1), 0) as pending_conversion,
+                COALESCE(SUM(LENGTH(embedding)), 0) as stored_bytes,
+                (SELECT COUNT(*) FROM ocr_text_embeddings_float_cache) as cached_floats
+            FROM ocr_text_embeddings
+            "#,
+        )
+        .bind(settings.quantization.as_str())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(EmbeddingIndexStatus {
+            settings,
+            rows: row.get("rows"),
+            pending_conversion: row.get("pending_conversion"),
+            stored_bytes: row.get("stored_bytes"),
+            cached_floats: row.get("cached_floats"),
+        })
+    }
+
+    /// Converts up to `limit` OCR text embeddings to the quantization of the
+    /// index, returns how many were converted, 0 once none are left. Float
+    /// vectors are kept in the float cache as if they were new. Converting
+    /// quantized vectors back to float32 only restores the cached ones
+    /// exactly, the others keep the precision they lost.
+    pub async fn convert_ocr_text_embeddings(&self, limit: u32) -> Result<u64, sqlx::Error> {
+        let settings = self
+            .get_embedding_index_settings(OCR_TEXT_EMBEDDINGS)
+            .await?;
+        let target = settings.quantization;
+
+        let mut tx = self.pool.begin().await?;
+        let rows = sqlx::query(
+            r#"
+            SELECT e.id, e.embedding, e.quantization, c.embedding as cached
+            FROM ocr_text_embeddings e
+            LEFT JOIN ocr_text_embeddings_float_cache c ON c.embedding_id = e.id
+            WHERE e.quantization != ?1
+            ORDER BY e.id
+            LIMIT ?2
+            "#,
+        )
+        .bind(target.as_str())
+        .bind(limit)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for row in &rows {
+            let id: i64 = row.get("id");
+            let stored: Vec<u8> = row.get("embedding");
+            let current = row
+                .get::<String, _>("quantization")
+                .parse::<EmbeddingQuantization>()
+                .unwrap_or_default();
+            let cached: Option<Vec<u8>> = row.get("cached");
+            let vector = match (&cached, current) {
+                (Some(cached), _) => EmbeddingQuantization::Float32.dequantize(cached),
+                (None, current) => current.dequantize(&stored),
+            };
+
+            sqlx::query(
+                "UPDATE ocr_text_embeddings SET embedding = ?1, quantization = ?2 WHERE id = ?3",
+            )
+            .bind(target.quantize(&vector))
+            .bind(target.as_str())
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+            if target == EmbeddingQuantization::Float32 {
+                sqlx::query("DELETE FROM ocr_text_embeddings_float_cache WHERE embedding_id = ?1")
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+            } else if current == EmbeddingQuantization::Float32 {
+                sqlx::query(
+                    "INSERT OR IGNORE INTO ocr_text_embeddings_float_cache (embedding_id, embedding) VALUES (?1, ?2)",
+                )
+                .bind(id)
+                .bind(EmbeddingQuantization::Float32.quantize(&vector))
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+        prune_float_cache(&mut tx, settings.float_cache_size).await?;
+        tx.commit().await?;
+
+        Ok(rows.len() as u64)
+    }
+}
+
+/// Keeps the float copies of the `size` most recent embeddings.
+pub(crate) async fn prune_float_cache(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    size: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        DELETE FROM ocr_text_embeddings_float_cache
+        WHERE embedding_id NOT IN (
+            SELECT embedding_id FROM ocr_text_embeddings_float_cache
+            ORDER BY embedding_id DESC
+            LIMIT ?1
+        )
+        "#,
+    )
+    .bind(size)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use oasgen::OaSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::speaker_clustering::decode_embedding;
+
+/// How the vectors of an embedding index are stored. `int8` is 4x smaller
+/// than `float32`, `binary` (one bit per dimension, which must be a multiple
+/// of 8) 32x, both at the cost of some precision in search.
+#[derive(OaSchema, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddingQuantization {
+    #[default]
+    Float32,
+    Int8,
+    Binary,
+}
+
+impl EmbeddingQuantization {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EmbeddingQuantization::Float32 => "float32",
+            EmbeddingQuantization::Int8 => "int8",
+            EmbeddingQuantization::Binary => "binary",
+        }
+    }
+
+    /// Bytes stored for `embedding`. int8 is scaled by the largest component,
+    /// cosine distances don't depend on the scale.
+    pub fn quantize(&self, embedding: &[f32]) -> Vec<u8> {
+        match self {
+            EmbeddingQuantization::Float32 => {
+                embedding.iter().flat_map(|x| x.to_le_bytes()).collect()
+            }
+            EmbeddingQuantization::Int8 => {
+                let max = embedding.iter().fold(0.0f32, |max, x| max.max(x.abs()));
+                let scale = if max > 0.0 { 127.0 / max } else { 0.0 };
+                embedding
+                    .iter()
+                    .map(|x| (x * scale).round() as i8 as u8)
+                    .collect()
+            }
+            EmbeddingQuantization::Binary => {
+                let mut bits = vec![0u8; embedding.len().div_ceil(8)];
+                for (i, x) in embedding.iter().enumerate() {
+                    if *x > 0.0 {
+                        bits[i / 8] |= 1 << (i % 8);
+                    }
+                }
+                bits
+            }
+        }
+    }
+
+    /// Approximation of a stored vector, up to its scale. Float vectors are
+    /// stored as JSON by `insert_embeddings` or as little endian f32s.
+    pub fn dequantize(&self, stored: &[u8]) -> Vec<f32> {
+        match self {
+            EmbeddingQuantization::Float32 => {
+                serde_json::from_slice(stored).unwrap_or_else(|_| decode_embedding(stored))
+            }
+            EmbeddingQuantization::Int8 => stored.iter().map(|x| *x as i8 as f32).collect(),
+            EmbeddingQuantization::Binary => (0..stored.len() * 8)
+                .map(|i| {
+                    if stored[i / 8] & (1 << (i % 8)) != 0 {
+                        1.0
+                    } else {
+                        -1.0
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for EmbeddingQuantization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for EmbeddingQuantization {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "float32" => Ok(EmbeddingQuantization::Float32),
+            "int8" => Ok(EmbeddingQuantization::Int8),
+            "binary" => Ok(EmbeddingQuantization::Binary),
+            _ => Err(format!(
+                "unknown quantization {:?}, expected float32, int8 or binary",
+                s
+            )),
+        }
+    }
+}
+
+/// 1 - cosine similarity, like sqlite-vec's `vec_distance_cosine`.
+pub fn cosine_distance(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 1.0;
+    }
+    1.0 - dot / (norm_a * norm_b)
+}
@@ -0,0 +1,113 @@
+use chrono::{DateTime, Utc};
+
+use crate::{DatabaseManager, FrameExport, OcrTextExport, TranscriptionExport};
+
+impl DatabaseManager {
+    /// Frames recorded between `start` and `end`, out of the trash, oldest
+    /// first.
+    pub async fn get_frames_export(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<FrameExport>, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        guard
+            .run(
+                sqlx::query_as::<_, FrameExport>(
+                    r#"
+            SELECT
+                f.id,
+                f.video_chunk_id,
+                f.offset_index,
+                f.timestamp,
+                vc.device_name,
+                vc.file_path,
+                f.name,
+                f.app_name,
+                f.window_name,
+                f.browser_url,
+                f.focused
+            FROM frames f
+            JOIN video_chunks vc ON f.video_chunk_id = vc.id
+            WHERE f.timestamp >= ?1 AND f.timestamp < ?2 AND f.deleted_at IS NULL
+            ORDER BY f.timestamp ASC, f.id ASC
+            "#,
+                )
+                .bind(start)
+                .bind(end)
+                .fetch_all(&mut *conn),
+            )
+            .await
+    }
+
+    /// Text of the frames recorded between `start` and `end`.
+    pub async fn get_ocr_text_export(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<OcrTextExport>, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        guard
+            .run(
+                sqlx::query_as::<_, OcrTextExport>(
+                    r#"
+            SELECT
+                o.frame_id,
+                f.timestamp,
+                o.text,
+                o.ocr_engine,
+                o.text_length,
+                o.confidence
+            FROM ocr_text o
+            JOIN frames f ON o.frame_id = f.id
+            WHERE f.timestamp >= ?1 AND f.timestamp < ?2 AND f.deleted_at IS NULL
+            ORDER BY f.timestamp ASC, o.frame_id ASC
+            "#,
+                )
+                .bind(start)
+                .bind(end)
+                .fetch_all(&mut *conn),
+            )
+            .await
+    }
+
+    /// Transcriptions recorded between `start` and `end`, out of the trash,
+    /// oldest first.
+    pub async fn get_transcriptions_export(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<TranscriptionExport>, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        guard
+            .run(
+                sqlx::query_as::<_, TranscriptionExport>(
+                    r#"
+            SELECT
+                at.id,
+                at.audio_chunk_id,
+                at.offset_index,
+                at.timestamp,
+                ac.file_path,
+                at.transcription,
+                at.device,
+                at.is_input_device,
+                at.speaker_id,
+                NULLIF(s.name, '') as speaker_name,
+                at.transcription_engine,
+                at.start_time,
+                at.end_time
+            FROM audio_transcriptions at
+            JOIN audio_chunks ac ON at.audio_chunk_id = ac.id
+            LEFT JOIN speakers s ON at.speaker_id = s.id
+            WHERE at.timestamp >= ?1 AND at.timestamp < ?2 AND ac.deleted_at IS NULL
+            ORDER BY at.timestamp ASC, at.id ASC
+            "#,
+                )
+                .bind(start)
+                .bind(end)
+                .fetch_all(&mut *conn),
+            )
+            .await
+    }
+}
@@ -0,0 +1,150 @@
+use chrono::{DateTime, Utc};
+
+use crate::{DatabaseManager, FocusSession, WindowFocusTime};
+
+const FOCUS_SESSION_COLUMNS: &str = "id, app_name, window_name, started_at, ended_at, last_seen_at";
+
+impl DatabaseManager {
+    /// Records which window has the focus at `at`, none when no window has
+    /// it. The open session goes on while its window keeps the focus, and
+    /// ends at `at` otherwise. Returns the session of the focused window,
+    /// none when the capture blocklist keeps it out.
+    pub async fn record_focus(
+        &self,
+        focused: Option<(&str, &str)>,
+        at: DateTime<Utc>,
+    ) -> Result<Option<i64>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let open: Option<(i64, String, String)> = sqlx::query_as(
+            r#"
+            SELECT id, app_name, window_name FROM focus_sessions
+            WHERE ended_at IS NULL
+            ORDER BY id DESC
+            LIMIT 1
+            "#,
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if let (Some((id, app_name, window_name)), Some(focused)) = (&open, focused) {
+            if (app_name.as_str(), window_name.as_str()) == focused {
+                sqlx::query("UPDATE focus_sessions SET last_seen_at = ?2 WHERE id = ?1")
+                    .bind(id)
+                    .bind(at)
+                    .execute(&mut *tx)
+                    .await?;
+                tx.commit().await?;
+                return Ok(Some(*id));
+            }
+        }
+
+        sqlx::query(
+            "UPDATE focus_sessions SET ended_at = ?1, last_seen_at = ?1 WHERE ended_at IS NULL",
+        )
+        .bind(at)
+        .execute(&mut *tx)
+        .await?;
+        let mut id = None;
+        if let Some((app_name, window_name)) = focused {
+            let result = sqlx::query(
+                r#"
+                INSERT INTO focus_sessions (app_name, window_name, started_at, last_seen_at)
+                VALUES (?1, ?2, ?3, ?3)
+                "#,
+            )
+            .bind(app_name)
+            .bind(window_name)
+            .bind(at)
+            .execute(&mut *tx)
+            .await?;
+            if result.rows_affected() > 0 {
+                id = Some(result.last_insert_rowid());
+            }
+        }
+        tx.commit().await?;
+        Ok(id)
+    }
+
+    /// Ends the sessions left open, by a crash, when their window was last
+    /// seen focused. Returns how many were.
+    pub async fn close_stale_focus_sessions(&self) -> Result<u64, sqlx::Error> {
+        let result =
+            sqlx::query("UPDATE focus_sessions SET ended_at = last_seen_at WHERE ended_at IS NULL")
+                .execute(&self.pool)
+                .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Sessions overlapping `start..end`, of `app_name` only when set,
+    /// oldest first.
+    pub async fn get_focus_sessions(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        app_name: Option<&str>,
+    ) -> Result<Vec<FocusSession>, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        guard
+            .run(
+                sqlx::query_as(&format!(
+                    r#"
+                    SELECT {FOCUS_SESSION_COLUMNS} FROM focus_sessions
+                    WHERE started_at < ?2 AND COALESCE(ended_at, last_seen_at) > ?1
+                        AND (?3 IS NULL OR app_name = ?3)
+                    ORDER BY started_at
+                    "#
+                ))
+                .bind(start)
+                .bind(end)
+                .bind(app_name.filter(|app| !app.is_empty()))
+                .fetch_all(&mut *conn),
+            )
+            .await
+    }
+
+    /// How long each window had the focus within `start..end`, of
+    /// `app_name` only when set, longest first. An open session counts up to
+    /// when its window was last seen focused, idle intervals don't count.
+    pub async fn get_focus_time(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        app_name: Option<&str>,
+    ) -> Result<Vec<WindowFocusTime>, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        guard
+            .run(
+                sqlx::query_as(
+                    r#"
+                    SELECT app_name, window_name,
+                        COUNT(*) AS sessions,
+                        SUM(
+                            julianday(session_end) - julianday(session_start) - COALESCE((
+                                SELECT SUM(
+                                    julianday(MIN(i.ended_at, session_end))
+                                        - julianday(MAX(i.started_at, session_start))
+                                )
+                                FROM idle_intervals i
+                                WHERE i.started_at < session_end AND i.ended_at > session_start
+                            ), 0)
+                        ) * 86400.0 AS focused_secs
+                    FROM (
+                        SELECT app_name, window_name,
+                            MAX(started_at, ?1) AS session_start,
+                            MIN(COALESCE(ended_at, last_seen_at), ?2) AS session_end
+                        FROM focus_sessions
+                        WHERE started_at < ?2 AND COALESCE(ended_at, last_seen_at) > ?1
+                            AND (?3 IS NULL OR app_name = ?3)
+                    )
+                    GROUP BY app_name, window_name
+                    ORDER BY focused_secs DESC, app_name, window_name
+                    "#,
+                )
+                .bind(start)
+                .bind(end)
+                .bind(app_name.filter(|app| !app.is_empty()))
+                .fetch_all(&mut *conn),
+            )
+            .await
+    }
+}
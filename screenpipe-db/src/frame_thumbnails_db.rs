@@ -0,0 +1,45 @@
+use chrono::Utc;
+
+use crate::DatabaseManager;
+
+impl DatabaseManager {
+    /// The cached jpeg thumbnail of a frame at `width`.
+    pub async fn get_frame_thumbnail(
+        &self,
+        frame_id: i64,
+        width: u32,
+    ) -> Result<Option<Vec<u8>>, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        guard
+            .run(
+                sqlx::query_scalar::<_, Vec<u8>>(
+                    "SELECT image FROM frame_thumbnails WHERE frame_id = ?1 AND width = ?2",
+                )
+                .bind(frame_id)
+                .bind(width)
+                .fetch_optional(&mut *conn),
+            )
+            .await
+    }
+
+    pub async fn insert_frame_thumbnail(
+        &self,
+        frame_id: i64,
+        width: u32,
+        image: &[u8],
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO frame_thumbnails (frame_id, width, image, created_at)
+            VALUES (?1, ?2, ?3, ?4)
+            "#,
+        )
+        .bind(frame_id)
+        .bind(width)
+        .bind(image)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
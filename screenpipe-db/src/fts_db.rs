@@ -0,0 +1,38 @@
+use tracing::debug;
+
+use crate::DatabaseManager;
+
+/// Full text indexes over the content tables they index.
+pub(crate) const CONTENT_FTS_TABLES: [&str; 3] = [
+    "ocr_text_fts",
+    "audio_transcriptions_fts",
+    "ui_monitoring_fts",
+];
+
+impl DatabaseManager {
+    /// Merges the segments of each search index into one, which makes
+    /// searches faster after lots of inserts and deletes. With `rebuild`,
+    /// indexes every row of the content tables again first, to repair an
+    /// index that no longer matches its table.
+    pub async fn optimize_search_index(&self, rebuild: bool) -> Result<(), sqlx::Error> {
+        for table in CONTENT_FTS_TABLES {
+            if rebuild {
+                debug!("rebuilding {}", table);
+                sqlx::query(&format!(
+                    "INSERT INTO {table}({table}) VALUES('rebuild')",
+                    table = table
+                ))
+                .execute(&self.pool)
+                .await?;
+            }
+            debug!("optimizing {}", table);
+            sqlx::query(&format!(
+                "INSERT INTO {table}({table}) VALUES('optimize')",
+                table = table
+            ))
+            .execute(&self.pool)
+            .await?;
+        }
+        Ok(())
+    }
+}
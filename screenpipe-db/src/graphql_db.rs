@@ -0,0 +1,213 @@
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+
+use crate::DatabaseManager;
+
+/// A frame as the GraphQL api reads it, its relations resolved on demand.
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub struct GraphFrame {
+    pub id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub video_chunk_id: i64,
+    pub offset_index: i64,
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
+    pub browser_url: Option<String>,
+    pub focused: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub struct GraphOcr {
+    pub frame_id: i64,
+    pub text: String,
+    pub text_json: Option<String>,
+    pub ocr_engine: String,
+}
+
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub struct GraphVideoChunk {
+    pub id: i64,
+    pub file_path: String,
+    pub device_name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub struct GraphAudioChunk {
+    pub id: i64,
+    pub file_path: String,
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq, FromRow)]
+pub struct GraphTranscription {
+    pub id: i64,
+    pub audio_chunk_id: i64,
+    pub transcription: String,
+    pub timestamp: DateTime<Utc>,
+    pub device: String,
+    pub is_input_device: bool,
+    pub speaker_id: Option<i64>,
+    pub start_time: Option<f64>,
+    pub end_time: Option<f64>,
+    pub transcription_engine: String,
+}
+
+const GRAPH_FRAME_COLUMNS: &str = "f.id, f.timestamp, f.video_chunk_id, f.offset_index, \
+     f.app_name, f.window_name, f.browser_url, f.focused";
+
+const GRAPH_TRANSCRIPTION_COLUMNS: &str = "a.id, a.audio_chunk_id, a.transcription, a.timestamp, \
+     a.device, a.is_input_device, a.speaker_id, a.start_time, a.end_time, a.transcription_engine";
+
+impl DatabaseManager {
+    /// Frames between `start_time` and `end_time`, of `app_name` and tagged
+    /// `tag` when given, most recent first.
+    pub async fn list_graph_frames(
+        &self,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        app_name: Option<&str>,
+        tag: Option<&str>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<GraphFrame>, sqlx::Error> {
+        sqlx::query_as::<_, GraphFrame>(&format!(
+            r#"
+            SELECT {}
+            FROM frames f
+            WHERE f.deleted_at IS NULL
+                AND (?1 IS NULL OR f.timestamp >= ?1)
+                AND (?2 IS NULL OR f.timestamp <= ?2)
+                AND (?3 IS NULL OR f.app_name = ?3)
+                AND (?4 IS NULL OR EXISTS (
+                    SELECT 1 FROM vision_tags vt
+                    JOIN tags t ON t.id = vt.tag_id
+                    WHERE vt.vision_id = f.id AND t.name = ?4
+                ))
+            ORDER BY f.timestamp DESC
+            LIMIT ?5 OFFSET ?6
+            "#,
+            GRAPH_FRAME_COLUMNS
+        ))
+        .bind(start_time)
+        .bind(end_time)
+        .bind(app_name)
+        .bind(tag)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// The frames of a video chunk, in the order they were captured.
+    pub async fn list_graph_chunk_frames(
+        &self,
+        video_chunk_id: i64,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<GraphFrame>, sqlx::Error> {
+        sqlx::query_as::<_, GraphFrame>(&format!(
+            "SELECT {} FROM frames f WHERE f.video_chunk_id = ?1 AND f.deleted_at IS NULL ORDER BY f.offset_index LIMIT ?2 OFFSET ?3",
+            GRAPH_FRAME_COLUMNS
+        ))
+        .bind(video_chunk_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn get_graph_frame(&self, id: i64) -> Result<Option<GraphFrame>, sqlx::Error> {
+        sqlx::query_as::<_, GraphFrame>(&format!(
+            "SELECT {} FROM frames f WHERE f.id = ?1 AND f.deleted_at IS NULL",
+            GRAPH_FRAME_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    pub async fn get_graph_ocr(&self, frame_id: i64) -> Result<Option<GraphOcr>, sqlx::Error> {
+        sqlx::query_as::<_, GraphOcr>(
+            "SELECT frame_id, text, text_json, ocr_engine FROM ocr_text WHERE frame_id = ?1 LIMIT 1",
+        )
+        .bind(frame_id)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    pub async fn get_graph_video_chunk(
+        &self,
+        id: i64,
+    ) -> Result<Option<GraphVideoChunk>, sqlx::Error> {
+        sqlx::query_as::<_, GraphVideoChunk>(
+            "SELECT id, file_path, device_name FROM video_chunks WHERE id = ?1",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    pub async fn get_graph_audio_chunk(
+        &self,
+        id: i64,
+    ) -> Result<Option<GraphAudioChunk>, sqlx::Error> {
+        sqlx::query_as::<_, GraphAudioChunk>(
+            "SELECT id, file_path, timestamp FROM audio_chunks WHERE id = ?1 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// Transcriptions between `start_time` and `end_time`, of `speaker_id`
+    /// and of a chunk tagged `tag` when given, most recent first.
+    pub async fn list_graph_transcriptions(
+        &self,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        speaker_id: Option<i64>,
+        tag: Option<&str>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<GraphTranscription>, sqlx::Error> {
+        sqlx::query_as::<_, GraphTranscription>(&format!(
+            r#"
+            SELECT {}
+            FROM audio_transcriptions a
+            JOIN audio_chunks ac ON ac.id = a.audio_chunk_id AND ac.deleted_at IS NULL
+            WHERE (?1 IS NULL OR a.timestamp >= ?1)
+                AND (?2 IS NULL OR a.timestamp <= ?2)
+                AND (?3 IS NULL OR a.speaker_id = ?3)
+                AND (?4 IS NULL OR EXISTS (
+                    SELECT 1 FROM audio_tags at
+                    JOIN tags t ON t.id = at.tag_id
+                    WHERE at.audio_chunk_id = a.audio_chunk_id AND t.name = ?4
+                ))
+            ORDER BY a.timestamp DESC
+            LIMIT ?5 OFFSET ?6
+            "#,
+            GRAPH_TRANSCRIPTION_COLUMNS
+        ))
+        .bind(start_time)
+        .bind(end_time)
+        .bind(speaker_id)
+        .bind(tag)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn get_graph_transcription(
+        &self,
+        id: i64,
+    ) -> Result<Option<GraphTranscription>, sqlx::Error> {
+        sqlx::query_as::<_, GraphTranscription>(&format!(
+            "SELECT {} FROM audio_transcriptions a JOIN audio_chunks ac ON ac.id = a.audio_chunk_id WHERE a.id = ?1 AND ac.deleted_at IS NULL",
+            GRAPH_TRANSCRIPTION_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+    }
+}
@@ -0,0 +1,48 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+use crate::{DatabaseManager, HourlyCount};
+
+// how hours are written in content_hour_counts
+const HOUR_FORMAT: &str = "%Y-%m-%d %H:00:00";
+
+impl DatabaseManager {
+    /// Frames, transcriptions and ui events per hour, for the hours
+    /// overlapping `start..end` with something recorded, oldest first. Kept
+    /// by triggers, so it never scans the content tables.
+    pub async fn get_hourly_counts(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<HourlyCount>, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        let rows: Vec<(NaiveDateTime, i64, i64, i64)> = guard
+            .run(
+                sqlx::query_as(
+                    r#"
+                    SELECT
+                        hour,
+                        SUM(CASE WHEN content_type = 'vision' THEN row_count ELSE 0 END),
+                        SUM(CASE WHEN content_type = 'audio' THEN row_count ELSE 0 END),
+                        SUM(CASE WHEN content_type = 'ui' THEN row_count ELSE 0 END)
+                    FROM content_hour_counts
+                    WHERE hour >= ?1 AND hour < ?2 AND row_count > 0
+                    GROUP BY hour
+                    ORDER BY hour
+                    "#,
+                )
+                .bind(start.format(HOUR_FORMAT).to_string())
+                .bind(end.format("%Y-%m-%d %H:%M:%S").to_string())
+                .fetch_all(&mut *conn),
+            )
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(hour, frames, transcriptions, ui_events)| HourlyCount {
+                hour: hour.and_utc(),
+                frames,
+                transcriptions,
+                ui_events,
+            })
+            .collect())
+    }
+}
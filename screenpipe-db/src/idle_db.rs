@@ -0,0 +1,154 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{DatabaseManager, IdleInterval};
+
+impl DatabaseManager {
+    /// Records the stretches of at least `min_idle` between two activities
+    /// in `start..end`, a frame, a transcription or a minute with input,
+    /// counting from the last activity before `start`. One still going on at
+    /// `end` is recorded once activity comes back. Stretches overlapping one
+    /// already recorded are merged into it. Returns the ones recorded.
+    pub async fn detect_idle_intervals(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        min_idle: Duration,
+    ) -> Result<Vec<IdleInterval>, sqlx::Error> {
+        let times = self.activity_times(start, end).await?;
+        let mut recorded = Vec::new();
+        for (started_at, ended_at) in idle_gaps(&times, min_idle) {
+            recorded.push(self.insert_idle_interval(started_at, ended_at).await?);
+        }
+        Ok(recorded)
+    }
+
+    /// Idle intervals overlapping `start..end`, oldest first.
+    pub async fn get_idle_intervals(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<IdleInterval>, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        guard
+            .run(
+                sqlx::query_as(
+                    r#"
+                    SELECT id, started_at, ended_at FROM idle_intervals
+                    WHERE started_at < ?2 AND ended_at > ?1
+                    ORDER BY started_at
+                    "#,
+                )
+                .bind(start)
+                .bind(end)
+                .fetch_all(&mut *conn),
+            )
+            .await
+    }
+
+    async fn insert_idle_interval(
+        &self,
+        started_at: DateTime<Utc>,
+        ended_at: DateTime<Utc>,
+    ) -> Result<IdleInterval, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let (overlap_start, overlap_end): (Option<DateTime<Utc>>, Option<DateTime<Utc>>) =
+            sqlx::query_as(
+                r#"
+                SELECT MIN(started_at), MAX(ended_at) FROM idle_intervals
+                WHERE started_at <= ?2 AND ended_at >= ?1
+                "#,
+            )
+            .bind(started_at)
+            .bind(ended_at)
+            .fetch_one(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM idle_intervals WHERE started_at <= ?2 AND ended_at >= ?1")
+            .bind(started_at)
+            .bind(ended_at)
+            .execute(&mut *tx)
+            .await?;
+        let interval = sqlx::query_as(
+            r#"
+            INSERT INTO idle_intervals (started_at, ended_at) VALUES (?1, ?2)
+            RETURNING id, started_at, ended_at
+            "#,
+        )
+        .bind(overlap_start.map_or(started_at, |start| start.min(started_at)))
+        .bind(overlap_end.map_or(ended_at, |end| end.max(ended_at)))
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(interval)
+    }
+
+    // when frames, transcriptions and input happened in `start..end`, with
+    // the last time before `start`, oldest first. A minute with input is
+    // active until its end
+    async fn activity_times(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<DateTime<Utc>>, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        let mut times: Vec<DateTime<Utc>> = guard
+            .run(
+                sqlx::query_scalar(
+                    r#"
+                    SELECT timestamp FROM frames WHERE timestamp >= ?1 AND timestamp < ?2
+                    UNION ALL
+                    SELECT timestamp FROM audio_transcriptions WHERE timestamp >= ?1 AND timestamp < ?2
+                    "#,
+                )
+                .bind(start)
+                .bind(end)
+                .fetch_all(&mut *conn),
+            )
+            .await?;
+        let minutes: Vec<DateTime<Utc>> = guard
+            .run(
+                sqlx::query_scalar(
+                    r#"
+                    SELECT DISTINCT timestamp FROM input_activity
+                    WHERE timestamp >= ?1 AND timestamp < ?2
+                    "#,
+                )
+                .bind(start - Duration::minutes(1))
+                .bind(end)
+                .fetch_all(&mut *conn),
+            )
+            .await?;
+        times.extend(
+            minutes
+                .into_iter()
+                .flat_map(|minute| [minute, minute + Duration::minutes(1)]),
+        );
+
+        for (query, length) in [
+            ("SELECT MAX(timestamp) FROM frames WHERE timestamp < ?1", 0),
+            (
+                "SELECT MAX(timestamp) FROM audio_transcriptions WHERE timestamp < ?1",
+                0,
+            ),
+            (
+                "SELECT MAX(timestamp) FROM input_activity WHERE timestamp < ?1",
+                1,
+            ),
+        ] {
+            let last: Option<DateTime<Utc>> = guard
+                .run(sqlx::query_scalar(query).bind(start).fetch_one(&mut *conn))
+                .await?;
+            times.extend(last.map(|last| last + Duration::minutes(length)));
+        }
+        times.sort();
+        Ok(times)
+    }
+}
+
+// the stretches of at least `min_idle` between two of the sorted `times`
+fn idle_gaps(times: &[DateTime<Utc>], min_idle: Duration) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    times
+        .windows(2)
+        .filter(|pair| pair[1] - pair[0] >= min_idle)
+        .map(|pair| (pair[0], pair[1]))
+        .collect()
+}
@@ -0,0 +1,102 @@
+use chrono::{DateTime, Utc};
+
+use crate::{AppInputActivity, DatabaseManager, InputActivity, InputActivityReport};
+
+impl DatabaseManager {
+    /// Adds the counts of `activity` to the ones already stored for the same
+    /// minute and app.
+    pub async fn insert_input_activity(
+        &self,
+        activity: &[InputActivity],
+    ) -> Result<(), sqlx::Error> {
+        if activity.is_empty() {
+            return Ok(());
+        }
+        let mut tx = self.pool.begin().await?;
+        for minute in activity {
+            sqlx::query(
+                r#"
+                INSERT INTO input_activity
+                    (timestamp, app_name, keypresses, typing_bursts, clicks, scrolls)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                ON CONFLICT (timestamp, app_name) DO UPDATE SET
+                    keypresses = keypresses + excluded.keypresses,
+                    typing_bursts = typing_bursts + excluded.typing_bursts,
+                    clicks = clicks + excluded.clicks,
+                    scrolls = scrolls + excluded.scrolls
+                "#,
+            )
+            .bind(minute.timestamp)
+            .bind(&minute.app_name)
+            .bind(minute.keypresses)
+            .bind(minute.typing_bursts)
+            .bind(minute.clicks)
+            .bind(minute.scrolls)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Input activity of the minutes starting in `start..end`, of `app_name`
+    /// only when set.
+    pub async fn get_input_activity(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        app_name: Option<&str>,
+    ) -> Result<InputActivityReport, sqlx::Error> {
+        let app_name = app_name.filter(|app| !app.is_empty());
+        let (mut conn, guard) = self.read_connection().await?;
+        let minutes: Vec<InputActivity> = guard
+            .run(
+                sqlx::query_as(
+                    r#"
+                    SELECT timestamp, app_name, keypresses, typing_bursts, clicks, scrolls
+                    FROM input_activity
+                    WHERE timestamp >= ?1 AND timestamp < ?2
+                        AND (?3 IS NULL OR app_name = ?3)
+                    ORDER BY timestamp, app_name
+                    "#,
+                )
+                .bind(start)
+                .bind(end)
+                .bind(app_name)
+                .fetch_all(&mut *conn),
+            )
+            .await?;
+        let apps: Vec<AppInputActivity> = guard
+            .run(
+                sqlx::query_as(
+                    r#"
+                    SELECT app_name,
+                        COUNT(*) AS active_minutes,
+                        SUM(keypresses) AS keypresses,
+                        SUM(typing_bursts) AS typing_bursts,
+                        SUM(clicks) AS clicks,
+                        SUM(scrolls) AS scrolls
+                    FROM input_activity
+                    WHERE timestamp >= ?1 AND timestamp < ?2
+                        AND (?3 IS NULL OR app_name = ?3)
+                    GROUP BY app_name
+                    ORDER BY active_minutes DESC, keypresses + clicks DESC, app_name
+                    "#,
+                )
+                .bind(start)
+                .bind(end)
+                .bind(app_name)
+                .fetch_all(&mut *conn),
+            )
+            .await?;
+
+        let mut active_minutes: Vec<DateTime<Utc>> =
+            minutes.iter().map(|minute| minute.timestamp).collect();
+        active_minutes.dedup();
+        Ok(InputActivityReport {
+            active_minutes: active_minutes.len() as i64,
+            apps,
+            minutes,
+        })
+    }
+}
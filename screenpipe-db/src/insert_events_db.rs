@@ -0,0 +1,109 @@
+use chrono::{DateTime, Utc};
+use sqlx::Error as SqlxError;
+use tokio::sync::broadcast;
+use tracing::warn;
+
+use crate::{ContentMetadata, DatabaseManager, InsertEvent, TagContentType};
+
+// app, window, browser url, device and timestamp of a frame
+type FrameSource = (
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    String,
+    DateTime<Utc>,
+);
+
+fn metadata_table(content_type: &TagContentType) -> (&'static str, &'static str) {
+    match content_type {
+        TagContentType::Vision => ("vision_metadata", "vision_id"),
+        TagContentType::Audio => ("audio_metadata", "audio_chunk_id"),
+    }
+}
+
+impl DatabaseManager {
+    /// Receives the OCR text and transcriptions as they are inserted, with
+    /// the frame or audio chunk they belong to.
+    pub fn subscribe_insert_events(&self) -> broadcast::Receiver<InsertEvent> {
+        self.insert_events.subscribe()
+    }
+
+    pub(crate) fn publish_insert_event(&self, event: InsertEvent) {
+        if self.insert_events.receiver_count() > 0 {
+            let _ = self.insert_events.send(event);
+        }
+    }
+
+    /// Publishes the OCR text of a frame, reading where it was seen.
+    pub(crate) async fn publish_ocr_insert(&self, frame_id: i64, text: &str) {
+        if self.insert_events.receiver_count() == 0 {
+            return;
+        }
+        let frame = sqlx::query_as::<_, FrameSource>(
+            r#"
+            SELECT frames.app_name, frames.window_name, frames.browser_url,
+                video_chunks.device_name, frames.timestamp
+            FROM frames
+            JOIN video_chunks ON video_chunks.id = frames.video_chunk_id
+            WHERE frames.id = ?1
+            "#,
+        )
+        .bind(frame_id)
+        .fetch_optional(&self.pool)
+        .await;
+        match frame {
+            Ok(Some((app_name, window_name, browser_url, device_name, timestamp))) => self
+                .publish_insert_event(InsertEvent {
+                    content_type: "ocr".to_string(),
+                    id: frame_id,
+                    target_id: frame_id,
+                    text: text.to_string(),
+                    app_name,
+                    window_name,
+                    browser_url,
+                    device_name,
+                    speaker_id: None,
+                    timestamp,
+                }),
+            Ok(None) => {}
+            Err(e) => warn!(
+                "failed to read frame {} for its insert event: {}",
+                frame_id, e
+            ),
+        }
+    }
+
+    /// Sets `key` on a frame or an audio chunk, replacing its value.
+    pub async fn set_metadata(
+        &self,
+        id: i64,
+        content_type: TagContentType,
+        key: &str,
+        value: &str,
+    ) -> Result<(), SqlxError> {
+        let (table, column) = metadata_table(&content_type);
+        sqlx::query(&format!(
+            "INSERT INTO {table} ({column}, key, value) VALUES (?1, ?2, ?3) ON CONFLICT({column}, key) DO UPDATE SET value = excluded.value"
+        ))
+        .bind(id)
+        .bind(key)
+        .bind(value)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_metadata(
+        &self,
+        id: i64,
+        content_type: TagContentType,
+    ) -> Result<Vec<ContentMetadata>, SqlxError> {
+        let (table, column) = metadata_table(&content_type);
+        sqlx::query_as(&format!(
+            "SELECT key, value FROM {table} WHERE {column} = ?1 ORDER BY key"
+        ))
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await
+    }
+}
@@ -0,0 +1,193 @@
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+
+use crate::{
+    AppSession, DatabaseManager, IdleInterval, JournalDay, JournalExcerpt, JournalMeeting,
+    TagUsage, TranscriptLine,
+};
+
+// frames of an app further apart than this belong to separate sessions
+const SESSION_GAP_MINUTES: i64 = 5;
+// windows listed per session
+const MAX_SESSION_WINDOWS: usize = 3;
+const MAX_JOURNAL_MEETINGS: u32 = 100;
+
+/// Groups frames, sorted by time, into sessions per monitor. A session ends
+/// when the monitor shows another app or nothing for longer than `gap`, or
+/// when one of the `idle` intervals comes.
+fn app_sessions(
+    frames: &[(DateTime<Utc>, String, String, String)],
+    gap: Duration,
+    idle: &[IdleInterval],
+) -> Vec<AppSession> {
+    let mut sessions: Vec<AppSession> = Vec::new();
+    let mut windows: Vec<HashMap<&str, i64>> = Vec::new();
+    let mut open: HashMap<&str, usize> = HashMap::new();
+
+    for (timestamp, app_name, window_name, device_name) in frames {
+        if app_name.is_empty() {
+            continue;
+        }
+        let current = open.get(device_name.as_str()).copied().filter(|&i| {
+            sessions[i].app_name == *app_name
+                && *timestamp - sessions[i].end <= gap
+                && !idle.iter().any(|interval| {
+                    interval.started_at >= sessions[i].end && interval.ended_at <= *timestamp
+                })
+        });
+        let i = match current {
+            Some(i) => i,
+            None => {
+                sessions.push(AppSession {
+                    app_name: app_name.clone(),
+                    device_name: device_name.clone(),
+                    start: *timestamp,
+                    end: *timestamp,
+                    frame_count: 0,
+                    window_names: Vec::new(),
+                    excerpt: None,
+                });
+                windows.push(HashMap::new());
+                open.insert(device_name, sessions.len() - 1);
+                sessions.len() - 1
+            }
+        };
+        sessions[i].end = *timestamp;
+        sessions[i].frame_count += 1;
+        if !window_name.is_empty() {
+            *windows[i].entry(window_name).or_default() += 1;
+        }
+    }
+
+    for (session, windows) in sessions.iter_mut().zip(windows) {
+        let mut windows: Vec<(&str, i64)> = windows.into_iter().collect();
+        windows.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        session.window_names = windows
+            .into_iter()
+            .take(MAX_SESSION_WINDOWS)
+            .map(|(name, _)| name.to_string())
+            .collect();
+    }
+    sessions
+}
+
+impl DatabaseManager {
+    /// App sessions, meetings with their transcript, and tags used between
+    /// `start` and `end`, for the daily journal.
+    pub async fn get_journal_day(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<JournalDay, sqlx::Error> {
+        let frames = sqlx::query_as::<_, (DateTime<Utc>, String, String, String)>(
+            r#"
+            SELECT f.timestamp, COALESCE(f.app_name, ''), COALESCE(f.window_name, ''), vc.device_name
+            FROM frames f
+            JOIN video_chunks vc ON f.video_chunk_id = vc.id
+            WHERE f.timestamp >= ?1 AND f.timestamp < ?2 AND f.deleted_at IS NULL
+            ORDER BY f.timestamp ASC, f.id ASC
+            "#,
+        )
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let idle = self.get_idle_intervals(start, end).await?;
+        let mut sessions = app_sessions(&frames, Duration::minutes(SESSION_GAP_MINUTES), &idle);
+        for session in &mut sessions {
+            session.excerpt = sqlx::query_as::<_, (i64, String)>(
+                r#"
+                SELECT ocr_text.frame_id, ocr_text.text
+                FROM ocr_text
+                JOIN frames f ON f.id = ocr_text.frame_id
+                JOIN video_chunks vc ON f.video_chunk_id = vc.id
+                WHERE vc.device_name = ?1 AND f.app_name = ?2
+                    AND f.timestamp >= ?3 AND f.timestamp <= ?4
+                    AND f.deleted_at IS NULL
+                ORDER BY COALESCE(ocr_text.text_length, LENGTH(ocr_text.text)) DESC
+                LIMIT 1
+                "#,
+            )
+            .bind(&session.device_name)
+            .bind(&session.app_name)
+            .bind(session.start)
+            .bind(session.end)
+            .fetch_optional(&self.pool)
+            .await?
+            .filter(|(_, text)| !text.trim().is_empty())
+            .map(|(frame_id, text)| JournalExcerpt { frame_id, text });
+        }
+
+        let mut meetings = Vec::new();
+        let mut listed = self
+            .list_meetings(Some(start), Some(end), MAX_JOURNAL_MEETINGS, 0)
+            .await?;
+        listed.reverse();
+        for meeting in listed {
+            let participants = self.get_meeting_participants(meeting.id).await?;
+            let transcript = sqlx::query_as::<_, TranscriptLine>(
+                r#"
+                SELECT
+                    at.timestamp,
+                    NULLIF(s.name, '') as speaker_name,
+                    at.device as device_name,
+                    at.transcription as text,
+                    ac.file_path,
+                    at.start_time
+                FROM audio_transcriptions at
+                JOIN audio_chunks ac ON at.audio_chunk_id = ac.id
+                LEFT JOIN speakers s ON at.speaker_id = s.id
+                WHERE at.timestamp >= ?1 AND at.timestamp <= ?2
+                    AND ac.deleted_at IS NULL
+                    AND (s.id IS NULL OR s.hallucination = 0)
+                ORDER BY at.timestamp ASC, at.start_time ASC
+                "#,
+            )
+            .bind(meeting.start_time)
+            .bind(meeting.end_time.unwrap_or(end))
+            .fetch_all(&self.pool)
+            .await?;
+            meetings.push(JournalMeeting {
+                meeting,
+                participants,
+                transcript,
+            });
+        }
+
+        let tags = sqlx::query_as::<_, TagUsage>(
+            r#"
+            SELECT
+                tags.name,
+                SUM(tagged.kind = 'frame') as frames,
+                SUM(tagged.kind = 'audio') as audio_chunks
+            FROM (
+                SELECT vt.tag_id, 'frame' as kind
+                FROM vision_tags vt
+                JOIN frames f ON f.id = vt.vision_id
+                WHERE f.timestamp >= ?1 AND f.timestamp < ?2
+                UNION ALL
+                SELECT at.tag_id, 'audio' as kind
+                FROM audio_tags at
+                JOIN audio_chunks ac ON ac.id = at.audio_chunk_id
+                WHERE ac.timestamp >= ?1 AND ac.timestamp < ?2
+            ) tagged
+            JOIN tags ON tags.id = tagged.tag_id
+            GROUP BY tags.name
+            ORDER BY COUNT(*) DESC, tags.name ASC
+            "#,
+        )
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(JournalDay {
+            start,
+            end,
+            sessions,
+            meetings,
+            tags,
+        })
+    }
+}
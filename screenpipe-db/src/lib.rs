@@ -1,11 +1,114 @@
+mod api_tokens_db;
+mod app_categories_db;
+mod audit_log_db;
+mod bench;
+mod browser_context_db;
+mod bulk_db;
+mod capture_blocklist_db;
+mod capture_pauses_db;
+mod click_signals;
+mod click_signals_db;
+mod clipboard_db;
+mod clock_offsets_db;
+mod content_processor_db;
+mod count_estimate_db;
+mod coverage_db;
 mod db;
+mod devices_db;
+mod digest_db;
+mod embedding_index_db;
+mod embedding_quantization;
+mod export_db;
+mod focus_sessions_db;
+mod frame_thumbnails_db;
+mod fts_db;
+mod graphql_db;
+mod hour_counts_db;
+mod idle_db;
+mod input_activity_db;
+mod insert_events_db;
+mod journal_db;
+mod maintenance_db;
+mod media_encryption_db;
+mod media_evictions_db;
+mod media_gc_db;
+mod media_integrity_db;
+mod meeting_detection_db;
+mod meetings_db;
+mod merge_db;
 mod migration_worker;
+mod monitor_settings_db;
+mod ocr_confidence;
+mod ocr_tables;
+mod push_db;
+mod query_guard;
+mod query_plan_db;
+mod receipts_db;
+mod redaction_db;
+mod retention_db;
+mod rule_cache;
+mod search_delete_db;
+mod search_ranking;
+mod search_ranking_db;
+mod search_tag_db;
+mod speaker_clustering;
+mod speaker_compaction_db;
+mod subject_db;
+mod summaries_db;
+mod sync_db;
+mod tag_facets_db;
+mod tag_names_db;
+mod tag_rules_db;
+mod tag_suggestions_db;
+mod text_provenance_db;
+mod text_spans;
+mod text_spans_db;
+mod timeline_db;
+mod topic_terms;
+mod topics_db;
+mod trash_db;
 mod types;
+mod ui_diff;
+mod ui_secret_scan_db;
+mod ui_traversals_db;
+mod url_pattern;
+mod usage_db;
+mod video_compaction_db;
 mod video_db;
+mod webhook_rules_db;
+mod whiteboard_db;
+mod work_sessions_db;
 
-pub use db::DatabaseManager;
+pub use app_categories_db::{APP_CATEGORIES, APP_CATEGORY_KINDS};
+pub use bench::{
+    percentile_ms, run_bench, BenchConfig, BenchReport, InsertThroughput, QueryLatency,
+};
+pub use capture_blocklist_db::{CAPTURE_BLOCK_KINDS, PRIVATE_BROWSING_WINDOWS};
+pub use capture_pauses_db::CAPTURE_PAUSE_CONTENT_TYPES;
+pub use click_signals::{
+    click_boost, click_weight, normalize_query, query_similarity, ClickBoosts, ClickContentType,
+};
+pub use content_processor_db::ContentProcessor;
+pub use db::{normalize_tag, DatabaseManager};
+pub use embedding_index_db::OCR_TEXT_EMBEDDINGS;
+pub use embedding_quantization::{cosine_distance, EmbeddingQuantization};
+pub use graphql_db::{GraphAudioChunk, GraphFrame, GraphOcr, GraphTranscription, GraphVideoChunk};
+pub use media_encryption_db::MediaChunkKind;
 pub use migration_worker::{
     create_migration_worker, MigrationCommand, MigrationConfig, MigrationResponse, MigrationStatus,
     MigrationWorker,
 };
+pub use ocr_confidence::{block_confidence, frame_confidence, parse_ocr_blocks, OcrBlock};
+pub use ocr_tables::{extract_tables, OcrTable};
+pub use query_guard::QueryTimedOut;
+pub use retention_db::{PrunedData, RetentionRule, RetentionScope, TagRetention};
+pub use search_ranking::{decayed_score, recency_weight, SearchRanking};
+pub use speaker_clustering::representative_embeddings;
+pub use sync_db::{SyncWatermarks, SYNC_DELTA_TABLES};
+pub use tag_rules_db::tag_rule_time;
+pub use topic_terms::document_terms;
 pub use types::*;
+pub use ui_diff::{apply_line_edits, diff_lines, LineEdit};
+pub use ui_traversals_db::UI_KEYFRAME_INTERVAL;
+pub use url_pattern::UrlPattern;
+pub use webhook_rules_db::webhook_rule_matcher;
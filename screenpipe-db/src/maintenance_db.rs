@@ -0,0 +1,137 @@
+use std::time::Instant;
+
+use sqlx::pool::PoolConnection;
+use sqlx::Sqlite;
+use tracing::{debug, warn};
+
+use crate::fts_db::CONTENT_FTS_TABLES;
+use crate::{DatabaseManager, MaintenanceStep};
+
+/// Pages of each search index merged per run, `merge` stops after about this
+/// much work so a run stays short.
+const FTS_MERGE_PAGES: i64 = 500;
+/// Free pages given back to the file system per run.
+const INCREMENTAL_VACUUM_PAGES: i64 = 10_000;
+/// Rows of each index `ANALYZE` looks at, enough for the planner.
+const ANALYSIS_LIMIT: i64 = 1000;
+
+enum StepOutcome {
+    Done,
+    Skipped,
+}
+
+impl DatabaseManager {
+    /// Keeps the database fast without taking it offline: merges the search
+    /// index segments, gives free pages back, refreshes the planner
+    /// statistics and truncates the WAL. Each step is bounded in the work it
+    /// does and a failing step doesn't stop the next ones. Meant to run while
+    /// nothing is being captured, unlike `repair_database`.
+    pub async fn run_maintenance(&self) -> Result<Vec<MaintenanceStep>, sqlx::Error> {
+        let mut conn = self.pool.acquire().await?;
+        let mut steps = Vec::new();
+
+        for table in CONTENT_FTS_TABLES {
+            let sql = format!(
+                "INSERT INTO {table}({table}, rank) VALUES('merge', {pages})",
+                table = table,
+                pages = FTS_MERGE_PAGES
+            );
+            steps.push(
+                run_step(&format!("merge {}", table), async {
+                    sqlx::query(&sql).execute(&mut *conn).await?;
+                    Ok(StepOutcome::Done)
+                })
+                .await,
+            );
+        }
+
+        steps.push(run_step("incremental vacuum", incremental_vacuum(&mut conn)).await);
+
+        steps.push(
+            run_step("analyze", async {
+                sqlx::query(&format!("PRAGMA analysis_limit = {}", ANALYSIS_LIMIT))
+                    .execute(&mut *conn)
+                    .await?;
+                sqlx::query("ANALYZE").execute(&mut *conn).await?;
+                Ok(StepOutcome::Done)
+            })
+            .await,
+        );
+
+        steps.push(
+            run_step("optimize", async {
+                sqlx::query("PRAGMA optimize").execute(&mut *conn).await?;
+                Ok(StepOutcome::Done)
+            })
+            .await,
+        );
+
+        // last, so the WAL written by the steps above goes too
+        steps.push(
+            run_step("wal checkpoint", async {
+                let (busy, _, _): (i64, i64, i64) =
+                    sqlx::query_as("PRAGMA wal_checkpoint(TRUNCATE)")
+                        .fetch_one(&mut *conn)
+                        .await?;
+                if busy != 0 {
+                    return Err(sqlx::Error::Protocol(
+                        "checkpoint blocked by open readers".to_string(),
+                    ));
+                }
+                Ok(StepOutcome::Done)
+            })
+            .await,
+        );
+
+        Ok(steps)
+    }
+}
+
+/// Only databases created with `auto_vacuum = INCREMENTAL` can give pages back
+/// without a full `VACUUM`, older ones skip this step.
+async fn incremental_vacuum(conn: &mut PoolConnection<Sqlite>) -> Result<StepOutcome, sqlx::Error> {
+    let auto_vacuum: i64 = sqlx::query_scalar("PRAGMA auto_vacuum")
+        .fetch_one(&mut **conn)
+        .await?;
+    let free_pages: i64 = sqlx::query_scalar("PRAGMA freelist_count")
+        .fetch_one(&mut **conn)
+        .await?;
+    // 2 is INCREMENTAL
+    if auto_vacuum != 2 || free_pages == 0 {
+        return Ok(StepOutcome::Skipped);
+    }
+    sqlx::query(&format!(
+        "PRAGMA incremental_vacuum({})",
+        INCREMENTAL_VACUUM_PAGES
+    ))
+    .execute(&mut **conn)
+    .await?;
+    Ok(StepOutcome::Done)
+}
+
+async fn run_step(
+    name: &str,
+    step: impl std::future::Future<Output = Result<StepOutcome, sqlx::Error>>,
+) -> MaintenanceStep {
+    debug!("maintenance: {}", name);
+    let started = Instant::now();
+    let outcome = step.await;
+    let duration_ms = started.elapsed().as_millis() as u64;
+    match outcome {
+        Ok(outcome) => MaintenanceStep {
+            name: name.to_string(),
+            duration_ms,
+            skipped: matches!(outcome, StepOutcome::Skipped),
+            error: None,
+        },
+        Err(e) => {
+            warn!("maintenance: {} failed: {}", name, e);
+            MaintenanceStep {
+                name: name.to_string(),
+                duration_ms,
+                skipped: false,
+                error: Some(e.to_string()),
+            }
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use chrono::{DateTime, Utc};
+
+use crate::DatabaseManager;
+
+/// The chunks whose files are encrypted on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaChunkKind {
+    Video,
+    Audio,
+}
+
+impl MediaChunkKind {
+    pub(crate) fn table(self) -> &'static str {
+        match self {
+            MediaChunkKind::Video => "video_chunks",
+            MediaChunkKind::Audio => "audio_chunks",
+        }
+    }
+}
+
+impl DatabaseManager {
+    /// Video chunks not encrypted yet that ffmpeg is done writing: a newer
+    /// chunk of the same monitor was started since. Oldest first.
+    pub async fn list_unencrypted_video_chunks(
+        &self,
+        limit: u32,
+    ) -> Result<Vec<(i64, String)>, sqlx::Error> {
+        sqlx::query_as::<_, (i64, String)>(
+            r#"
+            SELECT id, file_path
+            FROM video_chunks
+            WHERE encrypted_at IS NULL
+                AND file_path != ''
+                AND EXISTS (
+                    SELECT 1 FROM video_chunks newer
+                    WHERE newer.device_name = video_chunks.device_name
+                        AND newer.id > video_chunks.id
+                )
+            ORDER BY id
+            LIMIT ?1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Audio chunks not encrypted yet recorded before `before`, transcription
+    /// is done with them by then. Oldest first.
+    pub async fn list_unencrypted_audio_chunks(
+        &self,
+        before: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<(i64, String)>, sqlx::Error> {
+        sqlx::query_as::<_, (i64, String)>(
+            r#"
+            SELECT id, file_path
+            FROM audio_chunks
+            WHERE encrypted_at IS NULL
+                AND file_path != ''
+                AND (timestamp IS NULL OR timestamp < ?1)
+            ORDER BY id
+            LIMIT ?2
+            "#,
+        )
+        .bind(before)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn mark_chunk_encrypted(
+        &self,
+        kind: MediaChunkKind,
+        id: i64,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(&format!(
+            "UPDATE {} SET encrypted_at = ?1 WHERE id = ?2",
+            kind.table()
+        ))
+        .bind(Utc::now())
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_audio_chunk_path(&self, id: i64) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar::<_, String>("SELECT file_path FROM audio_chunks WHERE id = ?1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+}
@@ -0,0 +1,165 @@
+use chrono::{DateTime, Utc};
+
+use crate::{DatabaseManager, MediaChunkKind, MediaEviction};
+
+const MEDIA_EVICTION_COLUMNS: &str = "id, kind, chunk_id, file_path, bytes, reason, evicted_at";
+
+fn kind_name(kind: MediaChunkKind) -> &'static str {
+    match kind {
+        MediaChunkKind::Video => "video",
+        MediaChunkKind::Audio => "audio",
+    }
+}
+
+impl DatabaseManager {
+    /// Video and audio chunks whose file can be evicted, as (kind, id, file
+    /// path): not tagged, with nothing recorded after `before` and, for
+    /// video, not the chunk a monitor is still writing. Oldest first, or
+    /// the ones with the least text first when `least_text_first` is set.
+    pub async fn list_eviction_candidates(
+        &self,
+        before: DateTime<Utc>,
+        least_text_first: bool,
+        limit: u32,
+    ) -> Result<Vec<(String, i64, String)>, sqlx::Error> {
+        let order = if least_text_first {
+            "text_length, recorded_at"
+        } else {
+            "recorded_at"
+        };
+        sqlx::query_as::<_, (String, i64, String)>(&format!(
+            r#"
+            SELECT kind, id, file_path FROM (
+                SELECT 'video' AS kind, v.id, v.file_path,
+                    MIN(f.timestamp) AS recorded_at,
+                    COALESCE(SUM(COALESCE(o.text_length, LENGTH(o.text))), 0) AS text_length
+                FROM video_chunks v
+                JOIN frames f ON f.video_chunk_id = v.id
+                LEFT JOIN ocr_text o ON o.frame_id = f.id
+                WHERE v.file_path != ''
+                    AND EXISTS (
+                        SELECT 1 FROM video_chunks newer
+                        WHERE newer.device_name = v.device_name AND newer.id > v.id
+                    )
+                    AND NOT EXISTS (
+                        SELECT 1 FROM frames tagged
+                        JOIN vision_tags vt ON vt.vision_id = tagged.id
+                        WHERE tagged.video_chunk_id = v.id
+                    )
+                GROUP BY v.id
+                HAVING MAX(f.timestamp) < ?1
+                UNION ALL
+                SELECT 'audio' AS kind, a.id, a.file_path,
+                    a.timestamp AS recorded_at,
+                    COALESCE(SUM(COALESCE(t.text_length, LENGTH(t.transcription))), 0) AS text_length
+                FROM audio_chunks a
+                LEFT JOIN audio_transcriptions t ON t.audio_chunk_id = a.id
+                WHERE a.file_path != ''
+                    AND a.timestamp < ?1
+                    AND NOT EXISTS (SELECT 1 FROM audio_tags WHERE audio_chunk_id = a.id)
+                GROUP BY a.id
+            )
+            ORDER BY {order}
+            LIMIT ?2
+            "#
+        ))
+        .bind(before)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Records the file of a chunk was evicted, `bytes` freed, and clears
+    /// the chunk's file path in the same transaction. Its frames or
+    /// transcriptions stay.
+    pub async fn record_media_eviction(
+        &self,
+        kind: MediaChunkKind,
+        chunk_id: i64,
+        file_path: &str,
+        bytes: u64,
+        reason: &str,
+    ) -> Result<MediaEviction, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let eviction = sqlx::query_as::<_, MediaEviction>(&format!(
+            r#"
+            INSERT INTO media_evictions (kind, chunk_id, file_path, bytes, reason, evicted_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT (kind, chunk_id) DO UPDATE SET
+                file_path = excluded.file_path,
+                bytes = excluded.bytes,
+                reason = excluded.reason,
+                evicted_at = excluded.evicted_at
+            RETURNING {MEDIA_EVICTION_COLUMNS}
+            "#
+        ))
+        .bind(kind_name(kind))
+        .bind(chunk_id)
+        .bind(file_path)
+        .bind(bytes as i64)
+        .bind(reason)
+        .bind(Utc::now())
+        .fetch_one(&mut *tx)
+        .await?;
+        sqlx::query(&format!(
+            "UPDATE {} SET file_path = '' WHERE id = ?1",
+            kind.table()
+        ))
+        .bind(chunk_id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(eviction)
+    }
+
+    /// The eviction of the video chunk a frame is in, none when its media
+    /// wasn't evicted.
+    pub async fn get_frame_eviction(
+        &self,
+        frame_id: i64,
+    ) -> Result<Option<MediaEviction>, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        guard
+            .run(
+                sqlx::query_as::<_, MediaEviction>(&format!(
+                    r#"
+                    SELECT {MEDIA_EVICTION_COLUMNS} FROM media_evictions
+                    WHERE kind = 'video'
+                        AND chunk_id = (SELECT video_chunk_id FROM frames WHERE id = ?1)
+                    "#
+                ))
+                .bind(frame_id)
+                .fetch_optional(&mut *conn),
+            )
+            .await
+    }
+
+    pub async fn get_audio_chunk_eviction(
+        &self,
+        chunk_id: i64,
+    ) -> Result<Option<MediaEviction>, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        guard
+            .run(
+                sqlx::query_as::<_, MediaEviction>(&format!(
+                    "SELECT {MEDIA_EVICTION_COLUMNS} FROM media_evictions WHERE kind = 'audio' AND chunk_id = ?1"
+                ))
+                .bind(chunk_id)
+                .fetch_optional(&mut *conn),
+            )
+            .await
+    }
+
+    /// How many chunks were evicted and how many bytes that freed.
+    pub async fn media_eviction_totals(&self) -> Result<(i64, i64), sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        guard
+            .run(
+                sqlx::query_as::<_, (i64, i64)>(
+                    "SELECT COUNT(*), COALESCE(SUM(bytes), 0) FROM media_evictions",
+                )
+                .fetch_one(&mut *conn),
+            )
+            .await
+    }
+}
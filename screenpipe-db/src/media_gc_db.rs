@@ -0,0 +1,76 @@
+use chrono::Utc;
+
+use crate::DatabaseManager;
+
+impl DatabaseManager {
+    /// Every video and audio chunk with a file, as (kind, id, file path,
+    /// whether it was synced).
+    pub async fn list_media_references(
+        &self,
+    ) -> Result<Vec<(String, i64, String, bool)>, sqlx::Error> {
+        sqlx::query_as::<_, (String, i64, String, bool)>(
+            r#"
+            SELECT 'video', id, file_path, synced_at IS NOT NULL
+            FROM video_chunks WHERE file_path != ''
+            UNION ALL
+            SELECT 'audio', id, file_path, synced_at IS NOT NULL
+            FROM audio_chunks WHERE file_path != ''
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Clears the file path of the video and audio chunks whose file is
+    /// gone, recording it as an eviction, in one transaction. Their frames
+    /// and transcriptions stay, like for an evicted file. Returns how many
+    /// chunks were cleared.
+    pub async fn clear_missing_media(
+        &self,
+        video_chunk_ids: &[i64],
+        audio_chunk_ids: &[i64],
+    ) -> Result<u64, sqlx::Error> {
+        let mut cleared = 0;
+        let mut tx = self.pool.begin().await?;
+        for (kind, table, ids) in [
+            ("video", "video_chunks", video_chunk_ids),
+            ("audio", "audio_chunks", audio_chunk_ids),
+        ] {
+            for chunk_id in ids {
+                let file_path: Option<String> = sqlx::query_scalar(&format!(
+                    "SELECT file_path FROM {table} WHERE id = ?1 AND file_path != ''"
+                ))
+                .bind(chunk_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+                let Some(file_path) = file_path else {
+                    continue;
+                };
+                sqlx::query(&format!("UPDATE {table} SET file_path = '' WHERE id = ?1"))
+                    .bind(chunk_id)
+                    .execute(&mut *tx)
+                    .await?;
+                sqlx::query(
+                    r#"
+                    INSERT INTO media_evictions (kind, chunk_id, file_path, bytes, reason, evicted_at)
+                    VALUES (?1, ?2, ?3, 0, 'missing', ?4)
+                    ON CONFLICT (kind, chunk_id) DO UPDATE SET
+                        file_path = excluded.file_path,
+                        bytes = excluded.bytes,
+                        reason = excluded.reason,
+                        evicted_at = excluded.evicted_at
+                    "#,
+                )
+                .bind(kind)
+                .bind(chunk_id)
+                .bind(&file_path)
+                .bind(Utc::now())
+                .execute(&mut *tx)
+                .await?;
+                cleared += 1;
+            }
+        }
+        tx.commit().await?;
+        Ok(cleared)
+    }
+}
@@ -0,0 +1,171 @@
+use chrono::{DateTime, Utc};
+
+use crate::{DatabaseManager, MediaChunkKind, MediaIssue, PrunedData};
+
+impl DatabaseManager {
+    /// Video chunks not verified since `verified_before`, never verified ones
+    /// first, with the last frame offset they hold (-1 without frames). The
+    /// chunk a monitor still writes isn't listed.
+    pub async fn list_unverified_video_chunks(
+        &self,
+        verified_before: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<(i64, String, i64)>, sqlx::Error> {
+        sqlx::query_as::<_, (i64, String, i64)>(
+            r#"
+            SELECT v.id, v.file_path, COALESCE(MAX(f.offset_index), -1)
+            FROM video_chunks v
+            LEFT JOIN frames f ON f.video_chunk_id = v.id
+            WHERE v.file_path != ''
+                AND (v.verified_at IS NULL OR v.verified_at < ?1)
+                AND EXISTS (
+                    SELECT 1 FROM video_chunks newer
+                    WHERE newer.device_name = v.device_name AND newer.id > v.id
+                )
+            GROUP BY v.id
+            ORDER BY v.verified_at IS NOT NULL, v.verified_at, v.id
+            LIMIT ?2
+            "#,
+        )
+        .bind(verified_before)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Audio chunks recorded before `recorded_before` and not verified since
+    /// `verified_before`, never verified ones first.
+    pub async fn list_unverified_audio_chunks(
+        &self,
+        recorded_before: DateTime<Utc>,
+        verified_before: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<(i64, String)>, sqlx::Error> {
+        sqlx::query_as::<_, (i64, String)>(
+            r#"
+            SELECT id, file_path
+            FROM audio_chunks
+            WHERE file_path != ''
+                AND (timestamp IS NULL OR timestamp < ?1)
+                AND (verified_at IS NULL OR verified_at < ?2)
+            ORDER BY verified_at IS NOT NULL, verified_at, id
+            LIMIT ?3
+            "#,
+        )
+        .bind(recorded_before)
+        .bind(verified_before)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Records a chunk was verified, with what was wrong with its file, none
+    /// when it was fine.
+    pub async fn set_chunk_verified(
+        &self,
+        kind: MediaChunkKind,
+        id: i64,
+        issue: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(&format!(
+            "UPDATE {} SET verified_at = ?1, media_issue = ?2 WHERE id = ?3",
+            kind.table()
+        ))
+        .bind(Utc::now())
+        .bind(issue)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Points a chunk, and the frames naming it, to its file found at
+    /// `file_path`, in one transaction.
+    pub async fn relocate_chunk(
+        &self,
+        kind: MediaChunkKind,
+        id: i64,
+        file_path: &str,
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let previous: Option<String> = sqlx::query_scalar(&format!(
+            "SELECT file_path FROM {} WHERE id = ?1",
+            kind.table()
+        ))
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(previous) = previous else {
+            return Ok(());
+        };
+        sqlx::query(&format!(
+            "UPDATE {} SET file_path = ?1 WHERE id = ?2",
+            kind.table()
+        ))
+        .bind(file_path)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        if kind == MediaChunkKind::Video {
+            sqlx::query("UPDATE frames SET name = ?1 WHERE video_chunk_id = ?2 AND name = ?3")
+                .bind(file_path)
+                .bind(id)
+                .bind(&previous)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Deletes the frames of a video chunk at `offset_index` or past it,
+    /// with everything read from them, for frames its file doesn't hold.
+    /// The chunk goes too when that leaves it without frames, its file left
+    /// to the caller.
+    pub async fn delete_frames_past_end(
+        &self,
+        video_chunk_id: i64,
+        offset_index: i64,
+    ) -> Result<PrunedData, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let frames: Vec<(i64, i64)> = sqlx::query_as(
+            "SELECT id, video_chunk_id FROM frames WHERE video_chunk_id = ?1 AND offset_index >= ?2",
+        )
+        .bind(video_chunk_id)
+        .bind(offset_index)
+        .fetch_all(&mut *tx)
+        .await?;
+        let mut pruned = PrunedData::default();
+        Self::delete_frames(&mut tx, &frames, &mut pruned).await?;
+        tx.commit().await?;
+        Ok(pruned)
+    }
+
+    /// The chunks whose file failed its last check, most recently checked
+    /// first.
+    pub async fn list_media_issues(
+        &self,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<MediaIssue>, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        guard
+            .run(
+                sqlx::query_as::<_, MediaIssue>(
+                    r#"
+                    SELECT 'video' AS kind, id AS chunk_id, file_path, media_issue AS issue, verified_at
+                    FROM video_chunks WHERE media_issue IS NOT NULL
+                    UNION ALL
+                    SELECT 'audio' AS kind, id AS chunk_id, file_path, media_issue AS issue, verified_at
+                    FROM audio_chunks WHERE media_issue IS NOT NULL
+                    ORDER BY verified_at DESC
+                    LIMIT ?1 OFFSET ?2
+                    "#,
+                )
+                .bind(limit)
+                .bind(offset)
+                .fetch_all(&mut *conn),
+            )
+            .await
+    }
+}
@@ -0,0 +1,449 @@
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use crate::{CalendarHint, DatabaseManager, Meeting, NewCalendarHint, TranscriptLine};
+
+// in lowercase window titles and urls while a call is going on
+const CALL_WINDOW_PATTERNS: &[&str] = &[
+    "zoom meeting",
+    "zoom webinar",
+    "meet.google.com",
+    "meet - ",
+    "teams meeting",
+    "webex meeting",
+    "facetime",
+    "huddle",
+];
+// a minute has a conversation when another voice talks this many minutes
+// before or after
+const CONVERSATION_WINDOW_MINUTES: i64 = 2;
+// minutes with no sign of a call that still leave it going on
+const MAX_GAP_MINUTES: i64 = 3;
+// shorter stretches are meetings only over a calendar hint
+const MIN_MEETING_MINUTES: i64 = 5;
+
+// id, title, start time, end time, attendees as a json array, created at
+type CalendarHintRow = (
+    i64,
+    String,
+    DateTime<Utc>,
+    DateTime<Utc>,
+    String,
+    DateTime<Utc>,
+);
+
+fn calendar_hint(
+    (id, title, start_time, end_time, attendees, created_at): CalendarHintRow,
+) -> CalendarHint {
+    CalendarHint {
+        id,
+        title,
+        start_time,
+        end_time,
+        attendees: serde_json::from_str(&attendees).unwrap_or_default(),
+        created_at,
+    }
+}
+
+impl DatabaseManager {
+    pub async fn insert_calendar_hint(
+        &self,
+        hint: &NewCalendarHint,
+    ) -> Result<CalendarHint, sqlx::Error> {
+        let attendees: Vec<&str> = hint
+            .attendees
+            .iter()
+            .map(|name| name.trim())
+            .filter(|name| !name.is_empty())
+            .collect();
+        let row: CalendarHintRow = sqlx::query_as(
+            r#"
+            INSERT INTO calendar_hints (title, start_time, end_time, attendees)
+            VALUES (?1, ?2, ?3, ?4)
+            RETURNING id, title, start_time, end_time, attendees, created_at
+            "#,
+        )
+        .bind(hint.title.trim())
+        .bind(hint.start_time)
+        .bind(hint.end_time)
+        .bind(serde_json::to_string(&attendees).unwrap_or_else(|_| "[]".to_string()))
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(calendar_hint(row))
+    }
+
+    /// Calendar hints overlapping `start..end`, oldest first.
+    pub async fn list_calendar_hints(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<CalendarHint>, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        let rows: Vec<CalendarHintRow> = guard
+            .run(
+                sqlx::query_as(
+                    r#"
+                    SELECT id, title, start_time, end_time, attendees, created_at
+                    FROM calendar_hints
+                    WHERE start_time < ?2 AND end_time > ?1
+                    ORDER BY start_time
+                    "#,
+                )
+                .bind(start)
+                .bind(end)
+                .fetch_all(&mut *conn),
+            )
+            .await?;
+        Ok(rows.into_iter().map(calendar_hint).collect())
+    }
+
+    /// Returns false when there was no such hint.
+    pub async fn delete_calendar_hint(&self, id: i64) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM calendar_hints WHERE id = ?1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Finds the meetings in `start..end` from what was recorded: minutes
+    /// with a call window on screen or with several voices talking, a few
+    /// minutes apart at most. A stretch of at least five minutes, or one
+    /// over a calendar hint, extends the meeting it overlaps, or is stored
+    /// as a new "detected" one titled after the hint. Named speakers and
+    /// hint attendees become participants, and the transcriptions of every
+    /// meeting in the range are linked to it. Returns the meetings detected
+    /// or extended.
+    pub async fn detect_meetings(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<Meeting>, sqlx::Error> {
+        let hints = self.list_calendar_hints(start, end).await?;
+        let (mut conn, guard) = self.read_connection().await?;
+        let call_frames: Vec<(DateTime<Utc>, String)> = guard
+            .run(
+                sqlx::query_as(
+                    r#"
+                    SELECT f.timestamp, COALESCE(f.app_name, '')
+                    FROM frames f
+                    WHERE f.timestamp >= ?1 AND f.timestamp < ?2
+                        AND f.deleted_at IS NULL
+                        AND EXISTS (
+                            SELECT 1 FROM json_each(?3) p
+                            WHERE instr(
+                                lower(COALESCE(f.window_name, '') || ' ' || COALESCE(f.browser_url, '')),
+                                p.value
+                            ) > 0
+                        )
+                    "#,
+                )
+                .bind(start)
+                .bind(end)
+                .bind(serde_json::to_string(CALL_WINDOW_PATTERNS).unwrap_or_default())
+                .fetch_all(&mut *conn),
+            )
+            .await?;
+        let voices: Vec<(DateTime<Utc>, i64)> = guard
+            .run(
+                sqlx::query_as(
+                    r#"
+                    SELECT at.timestamp, at.speaker_id
+                    FROM audio_transcriptions at
+                    JOIN audio_chunks ac ON at.audio_chunk_id = ac.id
+                    JOIN speakers s ON at.speaker_id = s.id
+                    WHERE at.timestamp >= ?1 AND at.timestamp < ?2
+                        AND ac.deleted_at IS NULL
+                        AND s.hallucination = 0
+                    "#,
+                )
+                .bind(start)
+                .bind(end)
+                .fetch_all(&mut *conn),
+            )
+            .await?;
+
+        let mut minutes: BTreeSet<DateTime<Utc>> = call_frames
+            .iter()
+            .map(|(timestamp, _)| minute_of(*timestamp))
+            .collect();
+        minutes.extend(conversation_minutes(&voices));
+
+        let mut detected = Vec::new();
+        for (segment_start, segment_end) in segments(&minutes) {
+            let hint = hints
+                .iter()
+                .filter(|hint| hint.start_time < segment_end && hint.end_time > segment_start)
+                .max_by_key(|hint| {
+                    hint.end_time.min(segment_end) - hint.start_time.max(segment_start)
+                });
+            if segment_end - segment_start < Duration::minutes(MIN_MEETING_MINUTES)
+                && hint.is_none()
+            {
+                continue;
+            }
+            let app = most_common_app(&call_frames, segment_start, segment_end);
+            detected.push(
+                self.store_detected_meeting(segment_start, segment_end, &app, hint)
+                    .await?,
+            );
+        }
+
+        for meeting in self
+            .list_meetings(Some(start), Some(end), u32::MAX, 0)
+            .await?
+        {
+            sqlx::query(
+                r#"
+                INSERT OR IGNORE INTO meeting_transcriptions (meeting_id, transcription_id)
+                SELECT ?1, id FROM audio_transcriptions
+                WHERE timestamp >= ?2 AND timestamp <= ?3
+                "#,
+            )
+            .bind(meeting.id)
+            .bind(meeting.start_time)
+            .bind(meeting.end_time.unwrap_or(end))
+            .execute(&self.pool)
+            .await?;
+        }
+        Ok(detected)
+    }
+
+    /// What was said in a meeting, as linked by `detect_meetings`, or
+    /// between its start and end when nothing was linked yet.
+    pub async fn get_meeting_transcript(
+        &self,
+        meeting_id: i64,
+    ) -> Result<Vec<TranscriptLine>, sqlx::Error> {
+        let meeting = self.get_meeting(meeting_id).await?;
+        let (mut conn, guard) = self.read_connection().await?;
+        let linked: Vec<TranscriptLine> = guard
+            .run(
+                sqlx::query_as(
+                    r#"
+                    SELECT
+                        at.timestamp,
+                        NULLIF(s.name, '') as speaker_name,
+                        at.device as device_name,
+                        at.transcription as text,
+                        ac.file_path,
+                        at.start_time
+                    FROM meeting_transcriptions mt
+                    JOIN audio_transcriptions at ON at.id = mt.transcription_id
+                    JOIN audio_chunks ac ON at.audio_chunk_id = ac.id
+                    LEFT JOIN speakers s ON at.speaker_id = s.id
+                    WHERE mt.meeting_id = ?1
+                        AND ac.deleted_at IS NULL
+                        AND (s.id IS NULL OR s.hallucination = 0)
+                    ORDER BY at.timestamp ASC, at.start_time ASC
+                    "#,
+                )
+                .bind(meeting_id)
+                .fetch_all(&mut *conn),
+            )
+            .await?;
+        if !linked.is_empty() {
+            return Ok(linked);
+        }
+        guard
+            .run(
+                sqlx::query_as(
+                    r#"
+                    SELECT
+                        at.timestamp,
+                        NULLIF(s.name, '') as speaker_name,
+                        at.device as device_name,
+                        at.transcription as text,
+                        ac.file_path,
+                        at.start_time
+                    FROM audio_transcriptions at
+                    JOIN audio_chunks ac ON at.audio_chunk_id = ac.id
+                    LEFT JOIN speakers s ON at.speaker_id = s.id
+                    WHERE at.timestamp >= ?1 AND at.timestamp <= ?2
+                        AND ac.deleted_at IS NULL
+                        AND (s.id IS NULL OR s.hallucination = 0)
+                    ORDER BY at.timestamp ASC, at.start_time ASC
+                    "#,
+                )
+                .bind(meeting.start_time)
+                .bind(meeting.end_time.unwrap_or_else(Utc::now))
+                .fetch_all(&mut *conn),
+            )
+            .await
+    }
+
+    // extends the meeting within a gap of `start..end`, a live one only
+    // takes the title, or stores a new one
+    async fn store_detected_meeting(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        app: &str,
+        hint: Option<&CalendarHint>,
+    ) -> Result<Meeting, sqlx::Error> {
+        let gap = Duration::minutes(MAX_GAP_MINUTES);
+        let title = hint.map(|hint| hint.title.as_str());
+        let mut tx = self.pool.begin().await?;
+        let existing: Option<i64> = sqlx::query_scalar(
+            r#"
+            SELECT id FROM meetings
+            WHERE start_time <= ?2 AND COALESCE(end_time, ?2) >= ?1
+            ORDER BY start_time
+            LIMIT 1
+            "#,
+        )
+        .bind(start - gap)
+        .bind(end + gap)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let meeting: Meeting = match existing {
+            Some(id) => {
+                sqlx::query_as(
+                    r#"
+                    UPDATE meetings SET
+                        start_time = CASE WHEN source = 'detected' THEN MIN(start_time, ?2) ELSE start_time END,
+                        end_time = CASE WHEN source = 'detected' THEN MAX(end_time, ?3) ELSE end_time END,
+                        meeting_app = CASE WHEN meeting_app = '' THEN ?4 ELSE meeting_app END,
+                        title = COALESCE(title, ?5)
+                    WHERE id = ?1
+                    RETURNING id, meeting_app, start_time, end_time, source, title
+                    "#,
+                )
+                .bind(id)
+                .bind(start)
+                .bind(end)
+                .bind(app)
+                .bind(title)
+                .fetch_one(&mut *tx)
+                .await?
+            }
+            None => {
+                sqlx::query_as(
+                    r#"
+                    INSERT INTO meetings (meeting_app, start_time, end_time, source, title)
+                    VALUES (?1, ?2, ?3, 'detected', ?4)
+                    RETURNING id, meeting_app, start_time, end_time, source, title
+                    "#,
+                )
+                .bind(app)
+                .bind(start)
+                .bind(end)
+                .bind(title)
+                .fetch_one(&mut *tx)
+                .await?
+            }
+        };
+
+        // name, local, speaker, first and last time said or seen
+        let mut participants: Vec<(String, bool, Option<i64>, DateTime<Utc>, DateTime<Utc>)> =
+            sqlx::query_as(
+                r#"
+                SELECT s.name, MAX(at.is_input_device), s.id, MIN(at.timestamp), MAX(at.timestamp)
+                FROM audio_transcriptions at
+                JOIN speakers s ON at.speaker_id = s.id
+                WHERE at.timestamp >= ?1 AND at.timestamp < ?2
+                    AND s.hallucination = 0
+                    AND COALESCE(s.name, '') != ''
+                GROUP BY s.id
+                "#,
+            )
+            .bind(start)
+            .bind(end)
+            .fetch_all(&mut *tx)
+            .await?;
+        participants.extend(
+            hint.into_iter()
+                .flat_map(|hint| &hint.attendees)
+                .map(|name| (name.clone(), false, None, start, end)),
+        );
+        for (name, is_local, speaker_id, first_seen, last_seen) in participants {
+            sqlx::query(
+                r#"
+                INSERT INTO meeting_participants
+                    (meeting_id, name, is_local, speaker_id, first_seen, last_seen)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                ON CONFLICT(meeting_id, name) DO UPDATE SET
+                    is_local = is_local OR excluded.is_local,
+                    speaker_id = COALESCE(speaker_id, excluded.speaker_id),
+                    first_seen = MIN(first_seen, excluded.first_seen),
+                    last_seen = MAX(last_seen, excluded.last_seen)
+                "#,
+            )
+            .bind(meeting.id)
+            .bind(name)
+            .bind(is_local)
+            .bind(speaker_id)
+            .bind(first_seen)
+            .bind(last_seen)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(meeting)
+    }
+}
+
+fn minute_of(timestamp: DateTime<Utc>) -> DateTime<Utc> {
+    timestamp
+        .duration_trunc(Duration::minutes(1))
+        .unwrap_or(timestamp)
+}
+
+// the minutes someone talked with another voice close by
+fn conversation_minutes(voices: &[(DateTime<Utc>, i64)]) -> Vec<DateTime<Utc>> {
+    let mut speakers: BTreeMap<DateTime<Utc>, HashSet<i64>> = BTreeMap::new();
+    for (timestamp, speaker_id) in voices {
+        speakers
+            .entry(minute_of(*timestamp))
+            .or_default()
+            .insert(*speaker_id);
+    }
+    let window = Duration::minutes(CONVERSATION_WINDOW_MINUTES);
+    speakers
+        .keys()
+        .filter(|minute| {
+            let around: HashSet<i64> = speakers
+                .range(**minute - window..=**minute + window)
+                .flat_map(|(_, ids)| ids.iter().copied())
+                .collect();
+            around.len() >= 2
+        })
+        .copied()
+        .collect()
+}
+
+// the sorted minutes as stretches from the first to the end of the last,
+// split where more than the allowed gap has no minute
+fn segments(minutes: &BTreeSet<DateTime<Utc>>) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let mut segments: Vec<(DateTime<Utc>, DateTime<Utc>)> = Vec::new();
+    for minute in minutes {
+        let end = *minute + Duration::minutes(1);
+        match segments.last_mut() {
+            Some((_, last_end)) if *minute - *last_end <= Duration::minutes(MAX_GAP_MINUTES) => {
+                *last_end = end;
+            }
+            _ => segments.push((*minute, end)),
+        }
+    }
+    segments
+}
+
+// the app showing the call most in `start..end`, empty when only voices
+// were heard
+fn most_common_app(
+    call_frames: &[(DateTime<Utc>, String)],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> String {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for (timestamp, app) in call_frames {
+        if *timestamp >= start && *timestamp < end && !app.is_empty() {
+            *counts.entry(app.as_str()).or_default() += 1;
+        }
+    }
+    counts
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(a.0)))
+        .map(|(app, _)| app.to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,323 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{DatabaseManager, Meeting, MeetingParticipant, MeetingSlide};
+
+// a meeting nobody closed (missed end event, crash) stops absorbing new
+// participants after this long
+const MAX_OPEN_MEETING_HOURS: i64 = 6;
+
+const OPEN_MEETING: &str = r#"
+    SELECT id FROM meetings
+    WHERE end_time IS NULL AND start_time >= ?1
+    ORDER BY start_time DESC
+    LIMIT 1
+"#;
+
+impl DatabaseManager {
+    /// Returns the id of the meeting currently in progress, starting a new one
+    /// at `start_time` if there is none.
+    pub async fn start_meeting(
+        &self,
+        meeting_app: &str,
+        start_time: DateTime<Utc>,
+    ) -> Result<i64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let open = sqlx::query_scalar::<_, i64>(OPEN_MEETING)
+            .bind(start_time - Duration::hours(MAX_OPEN_MEETING_HOURS))
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        let id = match open {
+            Some(id) => id,
+            None => sqlx::query("INSERT INTO meetings (meeting_app, start_time) VALUES (?1, ?2)")
+                .bind(meeting_app)
+                .bind(start_time)
+                .execute(&mut *tx)
+                .await?
+                .last_insert_rowid(),
+        };
+        tx.commit().await?;
+        Ok(id)
+    }
+
+    /// The id of the meeting in progress at `at`, none outside of a meeting.
+    pub async fn current_meeting(&self, at: DateTime<Utc>) -> Result<Option<i64>, sqlx::Error> {
+        sqlx::query_scalar(OPEN_MEETING)
+            .bind(at - Duration::hours(MAX_OPEN_MEETING_HOURS))
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    /// Closes every meeting still in progress.
+    pub async fn end_meetings(&self, end_time: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("UPDATE meetings SET end_time = ?1 WHERE end_time IS NULL")
+            .bind(end_time)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    pub async fn get_meeting(&self, meeting_id: i64) -> Result<Meeting, sqlx::Error> {
+        sqlx::query_as::<_, Meeting>(
+            r#"
+            SELECT id, meeting_app, start_time, end_time, source, title
+            FROM meetings WHERE id = ?1
+            "#,
+        )
+        .bind(meeting_id)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn list_meetings(
+        &self,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<Meeting>, sqlx::Error> {
+        sqlx::query_as::<_, Meeting>(
+            r#"
+            SELECT id, meeting_app, start_time, end_time, source, title
+            FROM meetings
+            WHERE (?1 IS NULL OR COALESCE(end_time, start_time) >= ?1)
+                AND (?2 IS NULL OR start_time <= ?2)
+            ORDER BY start_time DESC
+            LIMIT ?3 OFFSET ?4
+            "#,
+        )
+        .bind(start_time)
+        .bind(end_time)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Time of the first and last transcriptions of a meeting, `None` if
+    /// nothing was said.
+    pub async fn get_meeting_speech_span(
+        &self,
+        meeting: &Meeting,
+    ) -> Result<Option<(DateTime<Utc>, DateTime<Utc>)>, sqlx::Error> {
+        let (first, last) = sqlx::query_as::<_, (Option<DateTime<Utc>>, Option<DateTime<Utc>>)>(
+            r#"
+            SELECT MIN(at.timestamp), MAX(at.timestamp)
+            FROM audio_transcriptions at
+            JOIN audio_chunks ac ON at.audio_chunk_id = ac.id
+            LEFT JOIN speakers s ON at.speaker_id = s.id
+            WHERE at.timestamp >= ?1
+                AND (?2 IS NULL OR at.timestamp <= ?2)
+                AND ac.deleted_at IS NULL
+                AND (s.id IS NULL OR s.hallucination = 0)
+            "#,
+        )
+        .bind(meeting.start_time)
+        .bind(meeting.end_time)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(first.zip(last))
+    }
+
+    /// Records the participant labels seen on a frame. `participants` holds the
+    /// label and whether it is the local user.
+    pub async fn upsert_meeting_participants(
+        &self,
+        meeting_id: i64,
+        participants: &[(String, bool)],
+        frame_id: i64,
+        seen_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        for (name, is_local) in participants {
+            sqlx::query(
+                r#"
+                INSERT INTO meeting_participants
+                    (meeting_id, name, is_local, frame_id, first_seen, last_seen)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?5)
+                ON CONFLICT(meeting_id, name) DO UPDATE SET
+                    is_local = is_local OR excluded.is_local,
+                    frame_id = excluded.frame_id,
+                    last_seen = excluded.last_seen
+                "#,
+            )
+            .bind(meeting_id)
+            .bind(name)
+            .bind(is_local)
+            .bind(frame_id)
+            .bind(seen_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn get_meeting_participants(
+        &self,
+        meeting_id: i64,
+    ) -> Result<Vec<MeetingParticipant>, sqlx::Error> {
+        sqlx::query_as::<_, MeetingParticipant>(
+            r#"
+            SELECT id, meeting_id, name, is_local, speaker_id, frame_id, first_seen, last_seen
+            FROM meeting_participants
+            WHERE meeting_id = ?1
+            ORDER BY first_seen ASC, name ASC
+            "#,
+        )
+        .bind(meeting_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Links a participant to a speaker. An unnamed speaker takes the
+    /// participant's on-screen name.
+    pub async fn link_participant_speaker(
+        &self,
+        participant_id: i64,
+        speaker_id: i64,
+    ) -> Result<MeetingParticipant, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let participant = sqlx::query_as::<_, MeetingParticipant>(
+            r#"
+            UPDATE meeting_participants SET speaker_id = ?1 WHERE id = ?2
+            RETURNING id, meeting_id, name, is_local, speaker_id, frame_id, first_seen, last_seen
+            "#,
+        )
+        .bind(speaker_id)
+        .bind(participant_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query("UPDATE speakers SET name = ?1 WHERE id = ?2 AND (name = '' OR name IS NULL)")
+            .bind(&participant.name)
+            .bind(speaker_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(participant)
+    }
+
+    /// Links participants to speakers where the screen leaves no ambiguity: a
+    /// single local participant and a single voice on input devices, or a
+    /// single remote participant and a single voice on output devices, over
+    /// the duration of the meeting. Returns the participants that were linked.
+    pub async fn auto_link_meeting_speakers(
+        &self,
+        meeting_id: i64,
+    ) -> Result<Vec<MeetingParticipant>, sqlx::Error> {
+        let meeting = self.get_meeting(meeting_id).await?;
+        let participants = self.get_meeting_participants(meeting_id).await?;
+        let mut linked = Vec::new();
+
+        for is_local in [true, false] {
+            let side: Vec<&MeetingParticipant> = participants
+                .iter()
+                .filter(|p| p.is_local == is_local)
+                .collect();
+            let [participant] = side.as_slice() else {
+                continue;
+            };
+            if participant.speaker_id.is_some() {
+                continue;
+            }
+
+            let speaker_ids = sqlx::query_scalar::<_, i64>(
+                r#"
+                SELECT DISTINCT at.speaker_id
+                FROM audio_transcriptions at
+                JOIN speakers s ON at.speaker_id = s.id
+                WHERE at.timestamp >= ?1
+                    AND (?2 IS NULL OR at.timestamp <= ?2)
+                    AND at.is_input_device = ?3
+                    AND s.hallucination = 0
+                "#,
+            )
+            .bind(meeting.start_time)
+            .bind(meeting.end_time)
+            .bind(is_local)
+            .fetch_all(&self.pool)
+            .await?;
+
+            if let [speaker_id] = speaker_ids.as_slice() {
+                linked.push(
+                    self.link_participant_speaker(participant.id, *speaker_id)
+                        .await?,
+                );
+            }
+        }
+
+        Ok(linked)
+    }
+
+    pub async fn get_meeting_slides(
+        &self,
+        meeting_id: i64,
+    ) -> Result<Vec<MeetingSlide>, sqlx::Error> {
+        sqlx::query_as::<_, MeetingSlide>(
+            r#"
+            SELECT id, meeting_id, slide_index, frame_id, text, first_seen, last_seen
+            FROM meeting_slides
+            WHERE meeting_id = ?1
+            ORDER BY slide_index ASC
+            "#,
+        )
+        .bind(meeting_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Appends a slide to the end of the meeting's deck.
+    pub async fn insert_meeting_slide(
+        &self,
+        meeting_id: i64,
+        frame_id: i64,
+        text: &str,
+        seen_at: DateTime<Utc>,
+    ) -> Result<i64, sqlx::Error> {
+        let id = sqlx::query(
+            r#"
+            INSERT INTO meeting_slides (meeting_id, slide_index, frame_id, text, first_seen, last_seen)
+            SELECT ?1, COALESCE(MAX(slide_index) + 1, 0), ?2, ?3, ?4, ?4
+            FROM meeting_slides
+            WHERE meeting_id = ?1
+            "#,
+        )
+        .bind(meeting_id)
+        .bind(frame_id)
+        .bind(text)
+        .bind(seen_at)
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+        Ok(id)
+    }
+
+    /// Marks a slide as shown again. When `frame` is set, the slide's frame
+    /// and text are replaced, e.g. by a later step of a build animation.
+    pub async fn update_meeting_slide(
+        &self,
+        slide_id: i64,
+        frame: Option<(i64, &str)>,
+        seen_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        let (frame_id, text) = frame.unzip();
+        sqlx::query(
+            r#"
+            UPDATE meeting_slides SET
+                frame_id = COALESCE(?1, frame_id),
+                text = COALESCE(?2, text),
+                last_seen = ?3
+            WHERE id = ?4
+            "#,
+        )
+        .bind(frame_id)
+        .bind(text)
+        .bind(seen_at)
+        .bind(slide_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,401 @@
+use std::path::Path;
+
+use chrono::Utc;
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{Connection, SqliteConnection};
+
+use crate::{DatabaseManager, MergeReport};
+
+// cosine distance under which two voices are the same speaker, as when a new
+// voice is matched
+const SPEAKER_MATCH_DISTANCE: f64 = 0.5;
+
+impl DatabaseManager {
+    /// Merges the database at `source_path`, e.g. the one of another
+    /// computer, into this one: its video and audio chunks, frames with their
+    /// text, metadata and tags, transcriptions, ui text and speakers. The rows
+    /// get ids after the ones here and `source_device` set to
+    /// `source_device`, unless they came from another merge before. Speakers
+    /// whose voice matches one here become that speaker, tags are shared by
+    /// name. The source is copied and migrated to this version first and
+    /// isn't changed. Rows keep their uid: the ones already here, and chunks
+    /// whose file is, are left out, so merging the same database again adds
+    /// nothing.
+    pub async fn merge_database(
+        &self,
+        source_path: &Path,
+        source_device: &str,
+    ) -> Result<MergeReport, sqlx::Error> {
+        let copy = std::env::temp_dir().join(format!(
+            "screenpipe-merge-{}-{}.sqlite",
+            std::process::id(),
+            Utc::now().timestamp_millis()
+        ));
+        let result = self.merge_copy(source_path, &copy, source_device).await;
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", copy.display(), suffix));
+        }
+        result
+    }
+
+    async fn merge_copy(
+        &self,
+        source_path: &Path,
+        copy: &Path,
+        source_device: &str,
+    ) -> Result<MergeReport, sqlx::Error> {
+        let copy_path = copy.to_string_lossy().to_string();
+        // a consistent snapshot, even of a database still being recorded to
+        let mut source = SqliteConnection::connect_with(
+            &SqliteConnectOptions::new()
+                .filename(source_path)
+                .read_only(true),
+        )
+        .await?;
+        sqlx::query("VACUUM INTO ?1")
+            .bind(&copy_path)
+            .execute(&mut source)
+            .await?;
+        source.close().await?;
+
+        let migrated = DatabaseManager::new(&copy_path).await?;
+        migrated.pool.close().await;
+        migrated.read_pool.close().await;
+
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query("ATTACH DATABASE ?1 AS merge_source")
+            .bind(&copy_path)
+            .execute(&mut *conn)
+            .await?;
+        let result = merge_attached(&mut *conn, source_device).await;
+        sqlx::query("DETACH DATABASE merge_source")
+            .execute(&mut *conn)
+            .await?;
+        result
+    }
+}
+
+async fn max_id(conn: &mut SqliteConnection, table: &str) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar(&format!("SELECT COALESCE(MAX(id), 0) FROM main.{}", table))
+        .fetch_one(conn)
+        .await
+}
+
+/// Copies the rows of the attached `merge_source` in a single transaction.
+async fn merge_attached(
+    conn: &mut SqliteConnection,
+    source_device: &str,
+) -> Result<MergeReport, sqlx::Error> {
+    let mut tx = conn.begin().await?;
+    let mut report = MergeReport::default();
+
+    // source ids are shifted past the ones here
+    let video_offset = max_id(&mut *tx, "video_chunks").await?;
+    let frame_offset = max_id(&mut *tx, "frames").await?;
+    let audio_offset = max_id(&mut *tx, "audio_chunks").await?;
+    let transcription_offset = max_id(&mut *tx, "audio_transcriptions").await?;
+    let ui_offset = max_id(&mut *tx, "ui_monitoring").await?;
+    let browser_context_offset = max_id(&mut *tx, "browser_contexts").await?;
+    let last_speaker = max_id(&mut *tx, "speakers").await?;
+
+    report.added_tags = sqlx::query(
+        "INSERT OR IGNORE INTO main.tags (name, created_at) SELECT name, created_at FROM merge_source.tags",
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    sqlx::query(
+        "CREATE TEMP TABLE merge_speakers (source_id INTEGER PRIMARY KEY, target_id INTEGER NOT NULL)",
+    )
+    .execute(&mut *tx)
+    .await?;
+    type SpeakerRow = (
+        i64,
+        Option<String>,
+        Option<String>,
+        Option<bool>,
+        Option<String>,
+        Option<String>,
+    );
+    let speakers: Vec<SpeakerRow> = sqlx::query_as(
+        "SELECT id, name, metadata, hallucination, source_device, uid FROM merge_source.speakers ORDER BY id",
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    for (source_id, name, metadata, hallucination, device, uid) in speakers {
+        // the same speaker, merged or synced from here before
+        let mut matched: Option<i64> =
+            sqlx::query_scalar("SELECT id FROM main.speakers WHERE uid = ?1")
+                .bind(&uid)
+                .fetch_optional(&mut *tx)
+                .await?;
+        if matched.is_none() {
+            // only speakers that were here before, two of the source stay apart
+            matched = sqlx::query_scalar(
+                r#"
+                SELECT t.speaker_id
+                FROM merge_source.speaker_embeddings s
+                JOIN main.speaker_embeddings t
+                WHERE s.speaker_id = ?1 AND t.speaker_id <= ?2
+                  AND vec_distance_cosine(t.embedding, s.embedding) < ?3
+                ORDER BY vec_distance_cosine(t.embedding, s.embedding)
+                LIMIT 1
+                "#,
+            )
+            .bind(source_id)
+            .bind(last_speaker)
+            .bind(SPEAKER_MATCH_DISTANCE)
+            .fetch_optional(&mut *tx)
+            .await?;
+        }
+        let target_id = match matched {
+            Some(target_id) => {
+                sqlx::query(
+                    "UPDATE main.speakers SET name = ?1 WHERE id = ?2 AND COALESCE(name, '') = ''",
+                )
+                .bind(name.filter(|name| !name.is_empty()))
+                .bind(target_id)
+                .execute(&mut *tx)
+                .await?;
+                report.matched_speakers += 1;
+                target_id
+            }
+            None => {
+                report.added_speakers += 1;
+                sqlx::query(
+                    "INSERT INTO main.speakers (name, metadata, hallucination, source_device, uid) VALUES (?1, ?2, ?3, ?4, ?5)",
+                )
+                .bind(name)
+                .bind(metadata)
+                .bind(hallucination.unwrap_or(false))
+                .bind(device.as_deref().unwrap_or(source_device))
+                .bind(uid)
+                .execute(&mut *tx)
+                .await?
+                .last_insert_rowid()
+            }
+        };
+        // the voice as recorded on the other device helps match it here too
+        sqlx::query(
+            r#"
+            INSERT INTO main.speaker_embeddings (embedding, speaker_id)
+            SELECT embedding, ?2 FROM merge_source.speaker_embeddings
+            WHERE speaker_id = ?1
+              AND embedding NOT IN (SELECT embedding FROM main.speaker_embeddings WHERE speaker_id = ?2)
+            "#,
+        )
+        .bind(source_id)
+        .bind(target_id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("INSERT INTO temp.merge_speakers (source_id, target_id) VALUES (?1, ?2)")
+            .bind(source_id)
+            .bind(target_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    // the capture blocklist applies, as it did when recording: blocked rows
+    // are left out with the ones that depend on them
+    sqlx::query(
+        r#"
+        INSERT INTO main.browser_contexts (id, timestamp, url, title, selection_text, browser)
+        SELECT b.id + ?1, b.timestamp, b.url, b.title, b.selection_text, b.browser
+        FROM merge_source.browser_contexts b
+        WHERE NOT EXISTS (
+            SELECT 1 FROM main.browser_contexts m
+            WHERE m.timestamp = b.timestamp AND m.url = b.url
+        )
+        "#,
+    )
+    .bind(browser_context_offset)
+    .execute(&mut *tx)
+    .await?;
+
+    report.video_chunks = sqlx::query(
+        r#"
+        INSERT INTO main.video_chunks (
+            id, file_path, device_name, encrypted_at, source_device, uid, codec
+        )
+        SELECT id + ?1, file_path, device_name, encrypted_at, COALESCE(source_device, ?2), uid, codec
+        FROM merge_source.video_chunks s
+        WHERE file_path NOT IN (SELECT file_path FROM main.video_chunks)
+            AND NOT EXISTS (SELECT 1 FROM main.video_chunks m WHERE m.uid = s.uid)
+        "#,
+    )
+    .bind(video_offset)
+    .bind(source_device)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    report.frames = sqlx::query(
+        r#"
+        INSERT INTO main.frames (
+            id, video_chunk_id, offset_index, timestamp, name, browser_url, app_name,
+            window_name, focused, deleted_at, restored_at, trashed_by, browser_context_id,
+            source_device, uid, video_chunk_uid
+        )
+        SELECT f.id + ?1, v.id, f.offset_index, f.timestamp, f.name, f.browser_url, f.app_name,
+            f.window_name, f.focused, f.deleted_at, f.restored_at, f.trashed_by,
+            (SELECT b.id FROM main.browser_contexts b WHERE b.id = f.browser_context_id + ?3),
+            COALESCE(f.source_device, ?4), f.uid, v.uid
+        FROM merge_source.frames f
+        JOIN main.video_chunks v ON v.id = f.video_chunk_id + ?2
+        WHERE NOT EXISTS (SELECT 1 FROM main.frames m WHERE m.uid = f.uid)
+        "#,
+    )
+    .bind(frame_offset)
+    .bind(video_offset)
+    .bind(browser_context_offset)
+    .bind(source_device)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    for sql in [
+        r#"
+        INSERT INTO main.ocr_text (
+            frame_id, text, text_json, app_name, ocr_engine, window_name, focused, text_length,
+            confidence, tables
+        )
+        SELECT f.id, o.text, o.text_json, o.app_name, o.ocr_engine, o.window_name, o.focused,
+            o.text_length, o.confidence, o.tables
+        FROM merge_source.ocr_text o
+        JOIN main.frames f ON f.id = o.frame_id + ?1
+        "#,
+        r#"
+        INSERT INTO main.ocr_text_blocks (frame_id, block_index, text, confidence)
+        SELECT f.id, o.block_index, o.text, o.confidence
+        FROM merge_source.ocr_text_blocks o
+        JOIN main.frames f ON f.id = o.frame_id + ?1
+        "#,
+        r#"
+        INSERT INTO main.vision_metadata (vision_id, key, value)
+        SELECT f.id, m.key, m.value
+        FROM merge_source.vision_metadata m
+        JOIN main.frames f ON f.id = m.vision_id + ?1
+        "#,
+        r#"
+        INSERT OR IGNORE INTO main.vision_tags (vision_id, tag_id)
+        SELECT f.id, t.id
+        FROM merge_source.vision_tags vt
+        JOIN main.frames f ON f.id = vt.vision_id + ?1
+        JOIN merge_source.tags st ON st.id = vt.tag_id
+        JOIN main.tags t ON t.name = st.name
+        "#,
+    ] {
+        sqlx::query(sql)
+            .bind(frame_offset)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    report.audio_chunks = sqlx::query(
+        r#"
+        INSERT INTO main.audio_chunks (
+            id, file_path, timestamp, deleted_at, restored_at, trashed_by, encrypted_at,
+            source_device, uid
+        )
+        SELECT id + ?1, file_path, timestamp, deleted_at, restored_at, trashed_by, encrypted_at,
+            COALESCE(source_device, ?2), uid
+        FROM merge_source.audio_chunks s
+        WHERE file_path NOT IN (SELECT file_path FROM main.audio_chunks)
+            AND NOT EXISTS (SELECT 1 FROM main.audio_chunks m WHERE m.uid = s.uid)
+        "#,
+    )
+    .bind(audio_offset)
+    .bind(source_device)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    report.audio_transcriptions = sqlx::query(
+        r#"
+        INSERT INTO main.audio_transcriptions (
+            id, audio_chunk_id, offset_index, timestamp, transcription, device, is_input_device,
+            speaker_id, transcription_engine, start_time, end_time, text_length, source_device,
+            uid, audio_chunk_uid, speaker_uid
+        )
+        SELECT t.id + ?1, c.id, t.offset_index, t.timestamp, t.transcription, t.device,
+            t.is_input_device, s.id, t.transcription_engine, t.start_time, t.end_time,
+            t.text_length, COALESCE(t.source_device, ?3), t.uid, c.uid, s.uid
+        FROM merge_source.audio_transcriptions t
+        JOIN main.audio_chunks c ON c.id = t.audio_chunk_id + ?2
+        LEFT JOIN temp.merge_speakers ms ON ms.source_id = t.speaker_id
+        LEFT JOIN main.speakers s ON s.id = ms.target_id
+        WHERE NOT EXISTS (SELECT 1 FROM main.audio_transcriptions m WHERE m.uid = t.uid)
+        "#,
+    )
+    .bind(transcription_offset)
+    .bind(audio_offset)
+    .bind(source_device)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    for sql in [
+        r#"
+        INSERT INTO main.audio_metadata (audio_chunk_id, key, value)
+        SELECT c.id, m.key, m.value
+        FROM merge_source.audio_metadata m
+        JOIN main.audio_chunks c ON c.id = m.audio_chunk_id + ?1
+        "#,
+        r#"
+        INSERT OR IGNORE INTO main.audio_tags (audio_chunk_id, tag_id)
+        SELECT c.id, t.id
+        FROM merge_source.audio_tags at
+        JOIN main.audio_chunks c ON c.id = at.audio_chunk_id + ?1
+        JOIN merge_source.tags st ON st.id = at.tag_id
+        JOIN main.tags t ON t.name = st.name
+        "#,
+    ] {
+        sqlx::query(sql)
+            .bind(audio_offset)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    // ui text has no file, rows seen here at the same time are left out
+    report.ui_monitoring = sqlx::query(
+        r#"
+        INSERT INTO main.ui_monitoring (
+            id, text_output, timestamp, app, window, initial_traversal_at, text_length,
+            source_device
+        )
+        SELECT u.id + ?1, u.text_output, u.timestamp, u.app, u.window, u.initial_traversal_at,
+            u.text_length, COALESCE(u.source_device, ?2)
+        FROM merge_source.ui_monitoring u
+        WHERE NOT EXISTS (
+            SELECT 1 FROM main.ui_monitoring m
+            WHERE m.timestamp = u.timestamp AND m.app = u.app AND m.window = u.window
+        )
+        "#,
+    )
+    .bind(ui_offset)
+    .bind(source_device)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    sqlx::query(
+        r#"
+        INSERT OR IGNORE INTO main.ui_monitoring_tags (ui_monitoring_id, tag_id)
+        SELECT u.id, t.id
+        FROM merge_source.ui_monitoring_tags ut
+        JOIN main.ui_monitoring u ON u.id = ut.ui_monitoring_id + ?1
+        JOIN merge_source.tags st ON st.id = ut.tag_id
+        JOIN main.tags t ON t.name = st.name
+        "#,
+    )
+    .bind(ui_offset)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("DROP TABLE temp.merge_speakers")
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(report)
+}
@@ -0,0 +1,14 @@
+-- Structured fields extracted from frames that look like receipts or invoices
+CREATE TABLE IF NOT EXISTS receipts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    frame_id INTEGER NOT NULL,
+    vendor TEXT,
+    receipt_date TEXT,
+    total REAL,
+    currency TEXT,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (frame_id) REFERENCES frames(id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_receipts_frame_id ON receipts(frame_id);
+CREATE INDEX IF NOT EXISTS idx_receipts_vendor ON receipts(vendor);
@@ -0,0 +1,8 @@
+-- Per-display capture settings, adjustable at runtime through the API
+CREATE TABLE IF NOT EXISTS monitor_settings (
+    monitor_id INTEGER PRIMARY KEY,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    fps REAL DEFAULT NULL,
+    downscale_factor REAL NOT NULL DEFAULT 1.0,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,16 @@
+-- Per-frame OCR confidence (mean of block confidences, normalized to 0..1)
+ALTER TABLE ocr_text ADD COLUMN confidence REAL DEFAULT NULL;
+CREATE INDEX IF NOT EXISTS idx_ocr_text_confidence ON ocr_text(confidence);
+
+-- Per-block OCR confidence, extracted from text_json at insert time
+CREATE TABLE IF NOT EXISTS ocr_text_blocks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    frame_id INTEGER NOT NULL,
+    block_index INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    confidence REAL,
+    FOREIGN KEY (frame_id) REFERENCES frames(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_ocr_text_blocks_frame_id ON ocr_text_blocks(frame_id);
+CREATE INDEX IF NOT EXISTS idx_ocr_text_blocks_confidence ON ocr_text_blocks(confidence);
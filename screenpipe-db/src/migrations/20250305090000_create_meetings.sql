@@ -0,0 +1,28 @@
+-- Meetings detected from screen and audio activity
+CREATE TABLE IF NOT EXISTS meetings (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    meeting_app TEXT NOT NULL,
+    start_time TIMESTAMP NOT NULL,
+    end_time TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_meetings_start_time ON meetings(start_time);
+CREATE INDEX IF NOT EXISTS idx_meetings_end_time ON meetings(end_time);
+
+-- Participant name labels read from video call grids
+CREATE TABLE IF NOT EXISTS meeting_participants (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    meeting_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    is_local BOOLEAN NOT NULL DEFAULT FALSE,
+    speaker_id INTEGER,
+    frame_id INTEGER,
+    first_seen TIMESTAMP NOT NULL,
+    last_seen TIMESTAMP NOT NULL,
+    FOREIGN KEY (meeting_id) REFERENCES meetings(id) ON DELETE CASCADE,
+    FOREIGN KEY (speaker_id) REFERENCES speakers(id) ON DELETE SET NULL,
+    UNIQUE (meeting_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_meeting_participants_meeting_id ON meeting_participants(meeting_id);
+CREATE INDEX IF NOT EXISTS idx_meeting_participants_speaker_id ON meeting_participants(speaker_id);
@@ -0,0 +1,15 @@
+-- Slides shown during a meeting, one row per distinct slide in order of first appearance
+CREATE TABLE IF NOT EXISTS meeting_slides (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    meeting_id INTEGER NOT NULL,
+    slide_index INTEGER NOT NULL,
+    frame_id INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    first_seen TIMESTAMP NOT NULL,
+    last_seen TIMESTAMP NOT NULL,
+    FOREIGN KEY (meeting_id) REFERENCES meetings(id) ON DELETE CASCADE,
+    FOREIGN KEY (frame_id) REFERENCES frames(id) ON DELETE CASCADE,
+    UNIQUE (meeting_id, slide_index)
+);
+
+CREATE INDEX IF NOT EXISTS idx_meeting_slides_meeting_id ON meeting_slides(meeting_id);
@@ -0,0 +1,9 @@
+-- Whiteboard photos ingested through /add: the photo as received and the
+-- straightened, contrast-cleaned copy that was run through OCR
+CREATE TABLE IF NOT EXISTS whiteboard_images (
+    frame_id INTEGER PRIMARY KEY,
+    original_path TEXT NOT NULL,
+    enhanced_path TEXT NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (frame_id) REFERENCES frames(id) ON DELETE CASCADE
+);
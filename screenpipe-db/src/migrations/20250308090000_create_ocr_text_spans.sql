@@ -0,0 +1,20 @@
+-- Text blocks that stay at the same place across consecutive frames of a
+-- device are stored once, as a span, instead of one ocr_text_blocks row per frame
+CREATE TABLE IF NOT EXISTS ocr_text_spans (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    device_name TEXT NOT NULL,
+    text TEXT NOT NULL,
+    x REAL NOT NULL,
+    y REAL NOT NULL,
+    width REAL NOT NULL,
+    height REAL NOT NULL,
+    first_frame_id INTEGER NOT NULL,
+    last_frame_id INTEGER NOT NULL,
+    first_seen TIMESTAMP NOT NULL,
+    last_seen TIMESTAMP NOT NULL,
+    frame_count INTEGER NOT NULL DEFAULT 1
+);
+
+CREATE INDEX IF NOT EXISTS idx_ocr_text_spans_device_last_frame ON ocr_text_spans(device_name, last_frame_id);
+CREATE INDEX IF NOT EXISTS idx_ocr_text_spans_first_seen ON ocr_text_spans(first_seen);
+CREATE INDEX IF NOT EXISTS idx_ocr_text_spans_last_seen ON ocr_text_spans(last_seen);
@@ -0,0 +1,3 @@
+-- Tables detected in the text_json layout, as a JSON array of {"rows": [[cell, ...], ...]}
+ALTER TABLE ocr_text ADD COLUMN tables TEXT DEFAULT NULL;
+CREATE INDEX IF NOT EXISTS idx_ocr_text_has_tables ON ocr_text(frame_id) WHERE tables IS NOT NULL;
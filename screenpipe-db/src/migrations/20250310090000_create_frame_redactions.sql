@@ -0,0 +1,16 @@
+-- Tombstones of frames redacted after capture. The frame row stays so the
+-- timeline keeps its shape, its text is gone and its image blacked out.
+-- region_* is null when the whole frame was redacted.
+CREATE TABLE IF NOT EXISTS frame_redactions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    frame_id INTEGER NOT NULL,
+    region_left REAL,
+    region_top REAL,
+    region_width REAL,
+    region_height REAL,
+    reason TEXT,
+    redacted_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (frame_id) REFERENCES frames(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_frame_redactions_frame_id ON frame_redactions(frame_id);
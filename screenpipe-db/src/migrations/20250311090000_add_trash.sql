@@ -0,0 +1,10 @@
+-- Retention moves expired recordings to the trash before purging them for
+-- good: deleted_at is when they were trashed, restored_at is set when they
+-- were taken out of the trash and keeps them from being trashed again.
+ALTER TABLE frames ADD COLUMN deleted_at TIMESTAMP DEFAULT NULL;
+ALTER TABLE frames ADD COLUMN restored_at TIMESTAMP DEFAULT NULL;
+ALTER TABLE audio_chunks ADD COLUMN deleted_at TIMESTAMP DEFAULT NULL;
+ALTER TABLE audio_chunks ADD COLUMN restored_at TIMESTAMP DEFAULT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_frames_deleted_at ON frames(deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_audio_chunks_deleted_at ON audio_chunks(deleted_at) WHERE deleted_at IS NOT NULL;
@@ -0,0 +1,20 @@
+-- Rules checked against every OCR text and transcription as it is inserted.
+-- A match posts the OCR/audio result to webhook_url, at most once per
+-- cooldown_secs per rule. The filters are optional, app_name and window_name
+-- only apply to OCR and speaker_id only to audio.
+CREATE TABLE IF NOT EXISTS webhook_rules (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    pattern TEXT NOT NULL,
+    is_regex BOOLEAN NOT NULL DEFAULT FALSE,
+    -- 'ocr', 'audio' or null for both
+    content_type TEXT,
+    app_name TEXT,
+    window_name TEXT,
+    speaker_id INTEGER,
+    webhook_url TEXT NOT NULL,
+    cooldown_secs INTEGER NOT NULL DEFAULT 300,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    last_fired_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL
+);
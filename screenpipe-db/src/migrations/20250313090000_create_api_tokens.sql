@@ -0,0 +1,10 @@
+-- Tokens accepted by the api when it runs with --api-auth. Only a sha256 of
+-- the token is stored, scopes is a comma separated list.
+CREATE TABLE IF NOT EXISTS api_tokens (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    scopes TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    last_used_at TIMESTAMP
+);
@@ -0,0 +1,11 @@
+-- Clock offset of remote capture agents, estimated NTP-style from their
+-- /clock/sync exchanges. offset_ms is added to the timestamps they send to get
+-- server time. A new agent session starts a new estimate.
+CREATE TABLE IF NOT EXISTS clock_offsets (
+    device_name TEXT PRIMARY KEY,
+    session_id TEXT NOT NULL,
+    offset_ms REAL NOT NULL,
+    delay_ms REAL NOT NULL,
+    samples INTEGER NOT NULL,
+    updated_at TIMESTAMP NOT NULL
+);
@@ -0,0 +1,18 @@
+-- Embeddings can be stored quantized (int8 or binary) to save space, the
+-- quantization of each row is kept so an index can be converted gradually.
+ALTER TABLE ocr_text_embeddings ADD COLUMN quantization TEXT NOT NULL DEFAULT 'float32';
+
+-- How new embeddings of each index are stored. The float copies of the most
+-- recent float_cache_size quantized embeddings are kept to rescore results.
+CREATE TABLE IF NOT EXISTS embedding_index_settings (
+    index_name TEXT PRIMARY KEY,
+    quantization TEXT NOT NULL DEFAULT 'float32',
+    float_cache_size INTEGER NOT NULL DEFAULT 10000
+);
+
+CREATE TABLE IF NOT EXISTS ocr_text_embeddings_float_cache (
+    embedding_id INTEGER PRIMARY KEY REFERENCES ocr_text_embeddings(id) ON DELETE CASCADE,
+    embedding BLOB NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_ocr_text_embeddings_quantization ON ocr_text_embeddings(quantization);
@@ -0,0 +1,17 @@
+-- Search results opened by the user, only recorded when click tracking is
+-- enabled. Clicks on a result for a query and similar ones rank it higher.
+CREATE TABLE IF NOT EXISTS search_clicks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    -- lowercased words of the query, sorted
+    query TEXT NOT NULL,
+    -- 'ocr', 'audio' or 'ui'
+    content_type TEXT NOT NULL,
+    -- frame, audio chunk or ui monitoring id
+    content_id INTEGER NOT NULL,
+    -- seconds the result stayed open
+    dwell_secs REAL NOT NULL DEFAULT 0,
+    clicked_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_search_clicks_query ON search_clicks(query);
+CREATE INDEX IF NOT EXISTS idx_search_clicks_content ON search_clicks(content_type, content_id);
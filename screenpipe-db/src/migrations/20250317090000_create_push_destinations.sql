@@ -0,0 +1,28 @@
+-- Notion databases and Readwise accounts that tagged frames and audio are
+-- pushed to. The api key stays on this machine, the api never returns it.
+CREATE TABLE IF NOT EXISTS push_destinations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    -- 'notion' or 'readwise'
+    kind TEXT NOT NULL,
+    api_key TEXT NOT NULL,
+    -- database the notion pages are created in
+    notion_database_id TEXT,
+    -- comma separated, items with any of these tags are pushed
+    tags TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    last_pushed_at TIMESTAMP,
+    -- why the last push failed, cleared by the next successful one
+    last_error TEXT,
+    created_at TIMESTAMP NOT NULL
+);
+
+-- Frames and audio chunks pushed to a destination, never pushed again.
+CREATE TABLE IF NOT EXISTS pushed_items (
+    destination_id INTEGER NOT NULL,
+    -- 'vision' or 'audio'
+    content_type TEXT NOT NULL,
+    content_id INTEGER NOT NULL,
+    pushed_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (destination_id, content_type, content_id)
+);
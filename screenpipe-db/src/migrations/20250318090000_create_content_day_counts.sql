@@ -0,0 +1,70 @@
+-- rows of searchable content per utc day, kept by triggers so approximate
+-- search counts never scan the content tables
+CREATE TABLE IF NOT EXISTS content_day_counts (
+    day TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    row_count INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (day, content_type)
+);
+
+INSERT OR REPLACE INTO content_day_counts (day, content_type, row_count)
+SELECT date(frames.timestamp), 'ocr', COUNT(*)
+FROM ocr_text
+JOIN frames ON frames.id = ocr_text.frame_id
+GROUP BY date(frames.timestamp);
+
+INSERT OR REPLACE INTO content_day_counts (day, content_type, row_count)
+SELECT date(timestamp), 'audio', COUNT(*)
+FROM audio_transcriptions
+GROUP BY date(timestamp);
+
+INSERT OR REPLACE INTO content_day_counts (day, content_type, row_count)
+SELECT date(timestamp), 'ui', COUNT(*)
+FROM ui_monitoring
+GROUP BY date(timestamp);
+
+CREATE TRIGGER IF NOT EXISTS content_day_counts_ocr_insert
+AFTER INSERT ON ocr_text
+BEGIN
+    INSERT INTO content_day_counts (day, content_type, row_count)
+    SELECT date(timestamp), 'ocr', 1 FROM frames WHERE id = NEW.frame_id
+    ON CONFLICT (day, content_type) DO UPDATE SET row_count = row_count + 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS content_day_counts_ocr_delete
+AFTER DELETE ON ocr_text
+BEGIN
+    UPDATE content_day_counts SET row_count = row_count - 1
+    WHERE content_type = 'ocr'
+        AND day = (SELECT date(timestamp) FROM frames WHERE id = OLD.frame_id);
+END;
+
+CREATE TRIGGER IF NOT EXISTS content_day_counts_audio_insert
+AFTER INSERT ON audio_transcriptions
+BEGIN
+    INSERT INTO content_day_counts (day, content_type, row_count)
+    VALUES (date(NEW.timestamp), 'audio', 1)
+    ON CONFLICT (day, content_type) DO UPDATE SET row_count = row_count + 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS content_day_counts_audio_delete
+AFTER DELETE ON audio_transcriptions
+BEGIN
+    UPDATE content_day_counts SET row_count = row_count - 1
+    WHERE content_type = 'audio' AND day = date(OLD.timestamp);
+END;
+
+CREATE TRIGGER IF NOT EXISTS content_day_counts_ui_insert
+AFTER INSERT ON ui_monitoring
+BEGIN
+    INSERT INTO content_day_counts (day, content_type, row_count)
+    VALUES (date(NEW.timestamp), 'ui', 1)
+    ON CONFLICT (day, content_type) DO UPDATE SET row_count = row_count + 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS content_day_counts_ui_delete
+AFTER DELETE ON ui_monitoring
+BEGIN
+    UPDATE content_day_counts SET row_count = row_count - 1
+    WHERE content_type = 'ui' AND day = date(OLD.timestamp);
+END;
@@ -0,0 +1,117 @@
+-- the fts tables index the rows of their content table instead of keeping a
+-- copy of the text, which roughly doubled the size of the database. The
+-- triggers keep every row indexed, empty ones index no terms
+
+-- ocr text
+DROP TRIGGER IF EXISTS ocr_text_ai;
+DROP TRIGGER IF EXISTS ocr_text_update;
+DROP TRIGGER IF EXISTS ocr_text_delete;
+DROP TABLE IF EXISTS ocr_text_fts;
+
+CREATE VIRTUAL TABLE ocr_text_fts USING fts5(
+    text,
+    app_name,
+    window_name,
+    frame_id UNINDEXED,
+    content='ocr_text',
+    tokenize='unicode61'
+);
+
+INSERT INTO ocr_text_fts(ocr_text_fts) VALUES('rebuild');
+
+CREATE TRIGGER ocr_text_ai AFTER INSERT ON ocr_text
+BEGIN
+    INSERT INTO ocr_text_fts(rowid, text, app_name, window_name, frame_id)
+    VALUES (NEW.rowid, NEW.text, NEW.app_name, NEW.window_name, NEW.frame_id);
+END;
+
+CREATE TRIGGER ocr_text_update AFTER UPDATE ON ocr_text
+BEGIN
+    INSERT INTO ocr_text_fts(ocr_text_fts, rowid, text, app_name, window_name, frame_id)
+    VALUES ('delete', OLD.rowid, OLD.text, OLD.app_name, OLD.window_name, OLD.frame_id);
+    INSERT INTO ocr_text_fts(rowid, text, app_name, window_name, frame_id)
+    VALUES (NEW.rowid, NEW.text, NEW.app_name, NEW.window_name, NEW.frame_id);
+END;
+
+CREATE TRIGGER ocr_text_delete AFTER DELETE ON ocr_text
+BEGIN
+    INSERT INTO ocr_text_fts(ocr_text_fts, rowid, text, app_name, window_name, frame_id)
+    VALUES ('delete', OLD.rowid, OLD.text, OLD.app_name, OLD.window_name, OLD.frame_id);
+END;
+
+-- audio transcriptions
+DROP TRIGGER IF EXISTS audio_transcriptions_ai;
+DROP TRIGGER IF EXISTS audio_transcriptions_update;
+DROP TRIGGER IF EXISTS audio_transcriptions_delete;
+DROP TABLE IF EXISTS audio_transcriptions_fts;
+
+CREATE VIRTUAL TABLE audio_transcriptions_fts USING fts5(
+    transcription,
+    device,
+    audio_chunk_id UNINDEXED,
+    speaker_id,
+    start_time UNINDEXED,
+    end_time UNINDEXED,
+    content='audio_transcriptions',
+    content_rowid='id',
+    tokenize='unicode61'
+);
+
+INSERT INTO audio_transcriptions_fts(audio_transcriptions_fts) VALUES('rebuild');
+
+CREATE TRIGGER audio_transcriptions_ai AFTER INSERT ON audio_transcriptions
+BEGIN
+    INSERT INTO audio_transcriptions_fts(rowid, transcription, device, audio_chunk_id, speaker_id, start_time, end_time)
+    VALUES (NEW.id, NEW.transcription, NEW.device, NEW.audio_chunk_id, NEW.speaker_id, NEW.start_time, NEW.end_time);
+END;
+
+CREATE TRIGGER audio_transcriptions_update AFTER UPDATE ON audio_transcriptions
+BEGIN
+    INSERT INTO audio_transcriptions_fts(audio_transcriptions_fts, rowid, transcription, device, audio_chunk_id, speaker_id, start_time, end_time)
+    VALUES ('delete', OLD.id, OLD.transcription, OLD.device, OLD.audio_chunk_id, OLD.speaker_id, OLD.start_time, OLD.end_time);
+    INSERT INTO audio_transcriptions_fts(rowid, transcription, device, audio_chunk_id, speaker_id, start_time, end_time)
+    VALUES (NEW.id, NEW.transcription, NEW.device, NEW.audio_chunk_id, NEW.speaker_id, NEW.start_time, NEW.end_time);
+END;
+
+CREATE TRIGGER audio_transcriptions_delete AFTER DELETE ON audio_transcriptions
+BEGIN
+    INSERT INTO audio_transcriptions_fts(audio_transcriptions_fts, rowid, transcription, device, audio_chunk_id, speaker_id, start_time, end_time)
+    VALUES ('delete', OLD.id, OLD.transcription, OLD.device, OLD.audio_chunk_id, OLD.speaker_id, OLD.start_time, OLD.end_time);
+END;
+
+-- ui monitoring, its id is now the rowid of the index
+DROP TRIGGER IF EXISTS ui_monitoring_ai;
+DROP TRIGGER IF EXISTS ui_monitoring_update;
+DROP TRIGGER IF EXISTS ui_monitoring_delete;
+DROP TABLE IF EXISTS ui_monitoring_fts;
+
+CREATE VIRTUAL TABLE ui_monitoring_fts USING fts5(
+    text_output,
+    app,
+    window,
+    content='ui_monitoring',
+    content_rowid='id',
+    tokenize='unicode61'
+);
+
+INSERT INTO ui_monitoring_fts(ui_monitoring_fts) VALUES('rebuild');
+
+CREATE TRIGGER ui_monitoring_ai AFTER INSERT ON ui_monitoring
+BEGIN
+    INSERT INTO ui_monitoring_fts(rowid, text_output, app, window)
+    VALUES (NEW.id, NEW.text_output, NEW.app, NEW.window);
+END;
+
+CREATE TRIGGER ui_monitoring_update AFTER UPDATE ON ui_monitoring
+BEGIN
+    INSERT INTO ui_monitoring_fts(ui_monitoring_fts, rowid, text_output, app, window)
+    VALUES ('delete', OLD.id, OLD.text_output, OLD.app, OLD.window);
+    INSERT INTO ui_monitoring_fts(rowid, text_output, app, window)
+    VALUES (NEW.id, NEW.text_output, NEW.app, NEW.window);
+END;
+
+CREATE TRIGGER ui_monitoring_delete AFTER DELETE ON ui_monitoring
+BEGIN
+    INSERT INTO ui_monitoring_fts(ui_monitoring_fts, rowid, text_output, app, window)
+    VALUES ('delete', OLD.id, OLD.text_output, OLD.app, OLD.window);
+END;
@@ -0,0 +1,76 @@
+-- frames_fts rows get the id of their frame as rowid, so the searches join
+-- it to frames through the primary key and deleting a frame finds its row
+-- without scanning the whole index
+DROP TRIGGER IF EXISTS frames_ai;
+DROP TRIGGER IF EXISTS frames_au;
+DROP TRIGGER IF EXISTS frames_ad;
+DROP TABLE IF EXISTS frames_fts;
+
+CREATE VIRTUAL TABLE frames_fts USING fts5(
+    name,
+    browser_url,
+    app_name,
+    window_name,
+    focused,
+    id UNINDEXED,
+    tokenize='unicode61'
+);
+
+INSERT INTO frames_fts(rowid, id, name, browser_url, app_name, window_name, focused)
+SELECT
+    id,
+    id,
+    COALESCE(name, ''),
+    COALESCE(browser_url, ''),
+    COALESCE(app_name, ''),
+    COALESCE(window_name, ''),
+    COALESCE(focused, 0)
+FROM frames;
+
+CREATE TRIGGER frames_ai AFTER INSERT ON frames
+BEGIN
+    INSERT INTO frames_fts(rowid, id, name, browser_url, app_name, window_name, focused)
+    VALUES (
+        NEW.id,
+        NEW.id,
+        COALESCE(NEW.name, ''),
+        COALESCE(NEW.browser_url, ''),
+        COALESCE(NEW.app_name, ''),
+        COALESCE(NEW.window_name, ''),
+        COALESCE(NEW.focused, 0)
+    );
+END;
+
+CREATE TRIGGER frames_au AFTER UPDATE ON frames
+WHEN (NEW.name IS NOT NULL AND NEW.name != '')
+   OR (NEW.browser_url IS NOT NULL AND NEW.browser_url != '')
+   OR (NEW.app_name IS NOT NULL AND NEW.app_name != '')
+   OR (NEW.window_name IS NOT NULL AND NEW.window_name != '')
+   OR (NEW.focused IS NOT NULL)
+BEGIN
+    INSERT OR REPLACE INTO frames_fts(rowid, id, name, browser_url, app_name, window_name, focused)
+    VALUES (
+        NEW.id,
+        NEW.id,
+        COALESCE(NEW.name, ''),
+        COALESCE(NEW.browser_url, ''),
+        COALESCE(NEW.app_name, ''),
+        COALESCE(NEW.window_name, ''),
+        COALESCE(NEW.focused, 0)
+    );
+END;
+
+CREATE TRIGGER frames_ad AFTER DELETE ON frames
+BEGIN
+    DELETE FROM frames_fts WHERE rowid = OLD.id;
+END;
+
+-- filtered searches walk these in timestamp order and stop at the limit
+CREATE INDEX IF NOT EXISTS idx_frames_app_name_timestamp ON frames(app_name, timestamp);
+CREATE INDEX IF NOT EXISTS idx_frames_window_name_timestamp ON frames(window_name, timestamp);
+-- prefixes of the ones above
+DROP INDEX IF EXISTS idx_frames_app_name;
+DROP INDEX IF EXISTS idx_frames_window_name;
+
+-- the length filters read the text length of a frame from the index alone
+CREATE INDEX IF NOT EXISTS idx_ocr_text_frame_id_text_length ON ocr_text(frame_id, text_length);
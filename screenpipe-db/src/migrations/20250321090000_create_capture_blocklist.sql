@@ -0,0 +1,36 @@
+-- Apps, window titles and browser urls that are never recorded. Frames and
+-- ui rows matching a rule are dropped by the triggers below, whichever
+-- process writes them.
+CREATE TABLE IF NOT EXISTS capture_blocklist (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    -- 'app', 'window' or 'url'
+    kind TEXT NOT NULL,
+    -- found case insensitively anywhere in the app name, window title or url
+    pattern TEXT NOT NULL,
+    -- 'api' for the rules added at /capture/blocklist, 'config' for the ones
+    -- of the command line, replaced at each start
+    source TEXT NOT NULL DEFAULT 'api',
+    created_at TIMESTAMP NOT NULL,
+    UNIQUE (kind, pattern, source)
+);
+
+CREATE TRIGGER IF NOT EXISTS capture_blocklist_frames BEFORE INSERT ON frames
+WHEN EXISTS (
+    SELECT 1 FROM capture_blocklist b
+    WHERE (b.kind = 'app' AND instr(lower(COALESCE(NEW.app_name, '')), lower(b.pattern)) > 0)
+       OR (b.kind = 'window' AND instr(lower(COALESCE(NEW.window_name, '')), lower(b.pattern)) > 0)
+       OR (b.kind = 'url' AND instr(lower(COALESCE(NEW.browser_url, '')), lower(b.pattern)) > 0)
+)
+BEGIN
+    SELECT RAISE(IGNORE);
+END;
+
+CREATE TRIGGER IF NOT EXISTS capture_blocklist_ui_monitoring BEFORE INSERT ON ui_monitoring
+WHEN EXISTS (
+    SELECT 1 FROM capture_blocklist b
+    WHERE (b.kind = 'app' AND instr(lower(NEW.app), lower(b.pattern)) > 0)
+       OR (b.kind = 'window' AND instr(lower(NEW.window), lower(b.pattern)) > 0)
+)
+BEGIN
+    SELECT RAISE(IGNORE);
+END;
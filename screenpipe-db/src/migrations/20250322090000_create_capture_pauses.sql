@@ -0,0 +1,34 @@
+-- When capture was paused, from the api or a schedule, so the timeline can
+-- tell a pause from a gap nothing explains. A pause still running has no
+-- ended_at.
+CREATE TABLE IF NOT EXISTS capture_pauses (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    -- 'all', 'vision', 'audio' or 'ui'
+    content_type TEXT NOT NULL,
+    started_at TIMESTAMP NOT NULL,
+    -- when a timed pause ends by itself
+    resume_at TIMESTAMP,
+    ended_at TIMESTAMP,
+    -- the schedule that started it, which also ends it
+    schedule_id INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS idx_capture_pauses_started_at ON capture_pauses(started_at);
+
+-- Recurring pauses, in local time, e.g. 'mon-fri 18:00-09:00'.
+CREATE TABLE IF NOT EXISTS capture_pause_schedules (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    content_type TEXT NOT NULL,
+    hours TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL
+);
+
+-- the ui recorder runs in another process, it writes on through a pause
+CREATE TRIGGER IF NOT EXISTS capture_pauses_ui_monitoring BEFORE INSERT ON ui_monitoring
+WHEN EXISTS (
+    SELECT 1 FROM capture_pauses
+    WHERE ended_at IS NULL AND content_type IN ('all', 'ui')
+)
+BEGIN
+    SELECT RAISE(IGNORE);
+END;
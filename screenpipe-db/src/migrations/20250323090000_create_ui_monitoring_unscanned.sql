@@ -0,0 +1,6 @@
+-- Ui text written or changed since secrets were last masked in it. The ui
+-- recorder writes in another process, so its text is masked after the fact.
+-- Filled only while secret masking is on, see set_ui_secret_scanning.
+CREATE TABLE IF NOT EXISTS ui_monitoring_unscanned (
+    ui_monitoring_id INTEGER PRIMARY KEY
+);
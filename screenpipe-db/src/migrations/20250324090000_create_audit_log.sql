@@ -0,0 +1,15 @@
+-- Raw sql queries and operations deleting or changing recorded data, who
+-- ran them and how many rows they changed.
+CREATE TABLE IF NOT EXISTS audit_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp TIMESTAMP NOT NULL,
+    -- 'token:<name>' or 'ip:<address>' for the api, 'screenpipe' for itself
+    actor TEXT NOT NULL,
+    action TEXT NOT NULL,
+    -- the query, or what the operation applied to
+    target TEXT NOT NULL,
+    -- null when not known
+    rows_affected INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_timestamp ON audit_log(timestamp);
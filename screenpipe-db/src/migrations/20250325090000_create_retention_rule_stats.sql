@@ -0,0 +1,17 @@
+-- The retention rule that moved a recording to the trash, so its purge is
+-- counted for that rule. Items trashed at the api have none.
+ALTER TABLE frames ADD COLUMN trashed_by TEXT DEFAULT NULL;
+ALTER TABLE audio_chunks ADD COLUMN trashed_by TEXT DEFAULT NULL;
+
+-- What each retention rule did so far, by rule name ("app:Chase", "audio",
+-- "tag:receipts", "default"). keep_days is NULL for rules keeping forever.
+CREATE TABLE IF NOT EXISTS retention_rule_stats (
+    rule TEXT PRIMARY KEY,
+    keep_days INTEGER,
+    trashed_frames INTEGER NOT NULL DEFAULT 0,
+    trashed_audio_chunks INTEGER NOT NULL DEFAULT 0,
+    purged_frames INTEGER NOT NULL DEFAULT 0,
+    purged_audio_chunks INTEGER NOT NULL DEFAULT 0,
+    deleted_ui_rows INTEGER NOT NULL DEFAULT 0,
+    last_applied_at TIMESTAMP
+);
@@ -0,0 +1,3 @@
+-- When the file of a chunk was encrypted on disk, NULL for plaintext files.
+ALTER TABLE video_chunks ADD COLUMN encrypted_at TIMESTAMP DEFAULT NULL;
+ALTER TABLE audio_chunks ADD COLUMN encrypted_at TIMESTAMP DEFAULT NULL;
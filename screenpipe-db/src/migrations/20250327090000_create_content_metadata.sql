@@ -0,0 +1,16 @@
+-- Key-value metadata set on frames and audio chunks, e.g. by insert scripts.
+CREATE TABLE IF NOT EXISTS vision_metadata (
+    vision_id INTEGER NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (vision_id, key),
+    FOREIGN KEY (vision_id) REFERENCES frames(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS audio_metadata (
+    audio_chunk_id INTEGER NOT NULL,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (audio_chunk_id, key),
+    FOREIGN KEY (audio_chunk_id) REFERENCES audio_chunks(id) ON DELETE CASCADE
+);
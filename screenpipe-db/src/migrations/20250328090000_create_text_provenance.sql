@@ -0,0 +1,24 @@
+-- The text OCR and transcriptions were read as before post-processing, and
+-- the stages that ran on it, so stored text can be traced and re-processed.
+CREATE TABLE IF NOT EXISTS text_provenance (
+    -- 'vision' for the OCR text of a frame, 'audio' for a transcription
+    content_type TEXT NOT NULL,
+    content_id INTEGER NOT NULL,
+    raw_text TEXT NOT NULL,
+    -- json array of {stage, version, changed}
+    stages TEXT NOT NULL,
+    processed_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (content_type, content_id)
+);
+
+CREATE TRIGGER IF NOT EXISTS text_provenance_frames_delete
+AFTER DELETE ON frames
+BEGIN
+    DELETE FROM text_provenance WHERE content_type = 'vision' AND content_id = OLD.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS text_provenance_audio_transcriptions_delete
+AFTER DELETE ON audio_transcriptions
+BEGIN
+    DELETE FROM text_provenance WHERE content_type = 'audio' AND content_id = OLD.id;
+END;
@@ -0,0 +1,20 @@
+-- Rules checked against every OCR text and transcription as it is inserted.
+-- Content matching every condition a rule sets gets its tag. app_name,
+-- window_name and browser_url only apply to OCR, start_time and end_time are
+-- local times of day ('HH:MM').
+CREATE TABLE IF NOT EXISTS tag_rules (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    -- 'ocr', 'audio' or null for both
+    content_type TEXT,
+    app_name TEXT,
+    window_name TEXT,
+    browser_url TEXT,
+    pattern TEXT,
+    is_regex BOOLEAN NOT NULL DEFAULT FALSE,
+    start_time TEXT,
+    end_time TEXT,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP NOT NULL
+);
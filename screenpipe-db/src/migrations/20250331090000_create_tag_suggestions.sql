@@ -0,0 +1,27 @@
+-- Tags an llm suggested for untagged frames and audio chunks, from the
+-- existing tags. They are only applied once accepted.
+CREATE TABLE IF NOT EXISTS tag_suggestions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    -- 'vision' or 'audio'
+    content_type TEXT NOT NULL,
+    -- the frame or the audio chunk
+    content_id INTEGER NOT NULL,
+    tag TEXT NOT NULL,
+    -- 'pending', 'accepted' or 'rejected'
+    status TEXT NOT NULL DEFAULT 'pending',
+    model TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    decided_at TIMESTAMP,
+    UNIQUE (content_type, content_id, tag)
+);
+
+CREATE INDEX IF NOT EXISTS idx_tag_suggestions_status ON tag_suggestions(status, id);
+
+-- Content already sent to the llm, suggested tags or not, so it isn't sent
+-- again.
+CREATE TABLE IF NOT EXISTS tag_suggestion_samples (
+    content_type TEXT NOT NULL,
+    content_id INTEGER NOT NULL,
+    sampled_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (content_type, content_id)
+);
@@ -0,0 +1,40 @@
+-- Text copied to the clipboard, with the app it was copied in.
+CREATE TABLE IF NOT EXISTS clipboard (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp TIMESTAMP NOT NULL,
+    text TEXT NOT NULL,
+    -- the focused app and window when the text was copied
+    app_name TEXT,
+    window_name TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_clipboard_timestamp ON clipboard(timestamp);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS clipboard_fts USING fts5(
+    text,
+    app_name,
+    window_name,
+    content='clipboard',
+    content_rowid='id',
+    tokenize='unicode61'
+);
+
+CREATE TRIGGER IF NOT EXISTS clipboard_ai AFTER INSERT ON clipboard
+BEGIN
+    INSERT INTO clipboard_fts(rowid, text, app_name, window_name)
+    VALUES (NEW.id, NEW.text, NEW.app_name, NEW.window_name);
+END;
+
+CREATE TRIGGER IF NOT EXISTS clipboard_update AFTER UPDATE ON clipboard
+BEGIN
+    INSERT INTO clipboard_fts(clipboard_fts, rowid, text, app_name, window_name)
+    VALUES ('delete', OLD.id, OLD.text, OLD.app_name, OLD.window_name);
+    INSERT INTO clipboard_fts(rowid, text, app_name, window_name)
+    VALUES (NEW.id, NEW.text, NEW.app_name, NEW.window_name);
+END;
+
+CREATE TRIGGER IF NOT EXISTS clipboard_delete AFTER DELETE ON clipboard
+BEGIN
+    INSERT INTO clipboard_fts(clipboard_fts, rowid, text, app_name, window_name)
+    VALUES ('delete', OLD.id, OLD.text, OLD.app_name, OLD.window_name);
+END;
@@ -0,0 +1,15 @@
+-- Keyboard and mouse activity counted per minute and focused app. Which keys
+-- were pressed or where the mouse clicked is never stored.
+CREATE TABLE IF NOT EXISTS input_activity (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    -- start of the minute
+    timestamp TIMESTAMP NOT NULL,
+    -- empty when no app had focus
+    app_name TEXT NOT NULL DEFAULT '',
+    keypresses INTEGER NOT NULL DEFAULT 0,
+    -- runs of keypresses with no pause longer than a few seconds
+    typing_bursts INTEGER NOT NULL DEFAULT 0,
+    clicks INTEGER NOT NULL DEFAULT 0,
+    scrolls INTEGER NOT NULL DEFAULT 0,
+    UNIQUE (timestamp, app_name)
+);
@@ -0,0 +1,57 @@
+-- The active tab pushed by the companion browser extension, with the text
+-- selected in it. Frames captured around a push link to it.
+CREATE TABLE IF NOT EXISTS browser_contexts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp TIMESTAMP NOT NULL,
+    url TEXT NOT NULL,
+    title TEXT,
+    selection_text TEXT,
+    -- as the extension names it, e.g. "chrome"
+    browser TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_browser_contexts_timestamp ON browser_contexts(timestamp);
+
+ALTER TABLE frames ADD COLUMN browser_context_id INTEGER DEFAULT NULL
+    REFERENCES browser_contexts(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_frames_browser_context_id ON frames(browser_context_id);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS browser_contexts_fts USING fts5(
+    url,
+    title,
+    selection_text,
+    content='browser_contexts',
+    content_rowid='id',
+    tokenize='unicode61'
+);
+
+CREATE TRIGGER IF NOT EXISTS browser_contexts_ai AFTER INSERT ON browser_contexts
+BEGIN
+    INSERT INTO browser_contexts_fts(rowid, url, title, selection_text)
+    VALUES (NEW.id, NEW.url, NEW.title, NEW.selection_text);
+END;
+
+CREATE TRIGGER IF NOT EXISTS browser_contexts_update AFTER UPDATE ON browser_contexts
+BEGIN
+    INSERT INTO browser_contexts_fts(browser_contexts_fts, rowid, url, title, selection_text)
+    VALUES ('delete', OLD.id, OLD.url, OLD.title, OLD.selection_text);
+    INSERT INTO browser_contexts_fts(rowid, url, title, selection_text)
+    VALUES (NEW.id, NEW.url, NEW.title, NEW.selection_text);
+END;
+
+CREATE TRIGGER IF NOT EXISTS browser_contexts_delete AFTER DELETE ON browser_contexts
+BEGIN
+    INSERT INTO browser_contexts_fts(browser_contexts_fts, rowid, url, title, selection_text)
+    VALUES ('delete', OLD.id, OLD.url, OLD.title, OLD.selection_text);
+END;
+
+CREATE TRIGGER IF NOT EXISTS capture_blocklist_browser_contexts BEFORE INSERT ON browser_contexts
+WHEN EXISTS (
+    SELECT 1 FROM capture_blocklist b
+    WHERE (b.kind = 'url' AND instr(lower(NEW.url), lower(b.pattern)) > 0)
+       OR (b.kind = 'window' AND instr(lower(COALESCE(NEW.title, '')), lower(b.pattern)) > 0)
+)
+BEGIN
+    SELECT RAISE(IGNORE);
+END;
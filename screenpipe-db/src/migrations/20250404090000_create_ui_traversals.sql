@@ -0,0 +1,35 @@
+-- Accessibility tree traversals of a window, each stored as the lines that
+-- changed since the previous traversal of the same window. Every so often a
+-- keyframe holds the whole text again so reads replay a few diffs at most.
+CREATE TABLE IF NOT EXISTS ui_traversals (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp TIMESTAMP NOT NULL,
+    app TEXT NOT NULL,
+    window TEXT NOT NULL,
+    keyframe BOOLEAN NOT NULL DEFAULT 0,
+    -- the text when a keyframe, the json line edits from the previous
+    -- traversal otherwise
+    content TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_ui_traversals_app_window_id ON ui_traversals(app, window, id);
+CREATE INDEX IF NOT EXISTS idx_ui_traversals_timestamp ON ui_traversals(timestamp);
+
+CREATE TRIGGER IF NOT EXISTS capture_blocklist_ui_traversals BEFORE INSERT ON ui_traversals
+WHEN EXISTS (
+    SELECT 1 FROM capture_blocklist b
+    WHERE (b.kind = 'app' AND instr(lower(NEW.app), lower(b.pattern)) > 0)
+       OR (b.kind = 'window' AND instr(lower(NEW.window), lower(b.pattern)) > 0)
+)
+BEGIN
+    SELECT RAISE(IGNORE);
+END;
+
+CREATE TRIGGER IF NOT EXISTS capture_pauses_ui_traversals BEFORE INSERT ON ui_traversals
+WHEN EXISTS (
+    SELECT 1 FROM capture_pauses
+    WHERE ended_at IS NULL AND content_type IN ('all', 'ui')
+)
+BEGIN
+    SELECT RAISE(IGNORE);
+END;
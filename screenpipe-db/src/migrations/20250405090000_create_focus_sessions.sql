@@ -0,0 +1,26 @@
+-- How long each window had the focus, recorded as the focus changes rather
+-- than derived from the frames sampled.
+CREATE TABLE IF NOT EXISTS focus_sessions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    app_name TEXT NOT NULL,
+    window_name TEXT NOT NULL,
+    started_at TIMESTAMP NOT NULL,
+    -- null while the window has the focus
+    ended_at TIMESTAMP,
+    -- the last time the window was seen focused, where a session left open
+    -- by a crash ends
+    last_seen_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_focus_sessions_started_at ON focus_sessions(started_at);
+CREATE INDEX IF NOT EXISTS idx_focus_sessions_open ON focus_sessions(ended_at) WHERE ended_at IS NULL;
+
+CREATE TRIGGER IF NOT EXISTS capture_blocklist_focus_sessions BEFORE INSERT ON focus_sessions
+WHEN EXISTS (
+    SELECT 1 FROM capture_blocklist b
+    WHERE (b.kind = 'app' AND instr(lower(NEW.app_name), lower(b.pattern)) > 0)
+       OR (b.kind = 'window' AND instr(lower(NEW.window_name), lower(b.pattern)) > 0)
+)
+BEGIN
+    SELECT RAISE(IGNORE);
+END;
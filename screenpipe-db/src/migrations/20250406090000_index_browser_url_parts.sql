@@ -0,0 +1,109 @@
+-- The host and the path of the browser url, to filter the frames by site:
+-- "docs.rs" matches https://docs.rs/tokio and https://www.docs.rs but not
+-- https://github.com/docs/rs
+ALTER TABLE frames ADD COLUMN url_domain TEXT GENERATED ALWAYS AS (
+    NULLIF(lower(
+        CASE
+            WHEN instr(browser_url, '://') > 0 THEN
+                CASE
+                    WHEN instr(substr(browser_url, instr(browser_url, '://') + 3), '/') > 0 THEN
+                        substr(
+                            substr(browser_url, instr(browser_url, '://') + 3),
+                            1,
+                            instr(substr(browser_url, instr(browser_url, '://') + 3), '/') - 1
+                        )
+                    ELSE substr(browser_url, instr(browser_url, '://') + 3)
+                END
+            WHEN instr(browser_url, '/') > 0 THEN substr(browser_url, 1, instr(browser_url, '/') - 1)
+            ELSE browser_url
+        END
+    ), '')
+) VIRTUAL;
+
+ALTER TABLE frames ADD COLUMN url_path TEXT GENERATED ALWAYS AS (
+    CASE
+        WHEN instr(browser_url, '://') > 0 THEN
+            CASE
+                WHEN instr(substr(browser_url, instr(browser_url, '://') + 3), '/') > 0 THEN
+                    substr(
+                        substr(browser_url, instr(browser_url, '://') + 3),
+                        instr(substr(browser_url, instr(browser_url, '://') + 3), '/')
+                    )
+            END
+        WHEN instr(browser_url, '/') > 0 THEN substr(browser_url, instr(browser_url, '/'))
+    END
+) VIRTUAL;
+
+-- frames_fts gets the host and the path as columns of their own, so the
+-- words of a site don't match the same words in the path of another
+DROP TRIGGER IF EXISTS frames_ai;
+DROP TRIGGER IF EXISTS frames_au;
+DROP TRIGGER IF EXISTS frames_ad;
+DROP TABLE IF EXISTS frames_fts;
+
+CREATE VIRTUAL TABLE frames_fts USING fts5(
+    name,
+    browser_url,
+    app_name,
+    window_name,
+    focused,
+    url_domain,
+    url_path,
+    id UNINDEXED,
+    tokenize='unicode61'
+);
+
+INSERT INTO frames_fts(rowid, id, name, browser_url, app_name, window_name, focused, url_domain, url_path)
+SELECT
+    id,
+    id,
+    COALESCE(name, ''),
+    COALESCE(browser_url, ''),
+    COALESCE(app_name, ''),
+    COALESCE(window_name, ''),
+    COALESCE(focused, 0),
+    COALESCE(url_domain, ''),
+    COALESCE(url_path, '')
+FROM frames;
+
+CREATE TRIGGER frames_ai AFTER INSERT ON frames
+BEGIN
+    INSERT INTO frames_fts(rowid, id, name, browser_url, app_name, window_name, focused, url_domain, url_path)
+    VALUES (
+        NEW.id,
+        NEW.id,
+        COALESCE(NEW.name, ''),
+        COALESCE(NEW.browser_url, ''),
+        COALESCE(NEW.app_name, ''),
+        COALESCE(NEW.window_name, ''),
+        COALESCE(NEW.focused, 0),
+        COALESCE(NEW.url_domain, ''),
+        COALESCE(NEW.url_path, '')
+    );
+END;
+
+CREATE TRIGGER frames_au AFTER UPDATE ON frames
+WHEN (NEW.name IS NOT NULL AND NEW.name != '')
+   OR (NEW.browser_url IS NOT NULL AND NEW.browser_url != '')
+   OR (NEW.app_name IS NOT NULL AND NEW.app_name != '')
+   OR (NEW.window_name IS NOT NULL AND NEW.window_name != '')
+   OR (NEW.focused IS NOT NULL)
+BEGIN
+    INSERT OR REPLACE INTO frames_fts(rowid, id, name, browser_url, app_name, window_name, focused, url_domain, url_path)
+    VALUES (
+        NEW.id,
+        NEW.id,
+        COALESCE(NEW.name, ''),
+        COALESCE(NEW.browser_url, ''),
+        COALESCE(NEW.app_name, ''),
+        COALESCE(NEW.window_name, ''),
+        COALESCE(NEW.focused, 0),
+        COALESCE(NEW.url_domain, ''),
+        COALESCE(NEW.url_path, '')
+    );
+END;
+
+CREATE TRIGGER frames_ad AFTER DELETE ON frames
+BEGIN
+    DELETE FROM frames_fts WHERE rowid = OLD.id;
+END;
@@ -0,0 +1,15 @@
+-- Summaries of a day or a week of activity, kept once the period is over so
+-- they are not generated again on every request.
+CREATE TABLE IF NOT EXISTS summaries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    -- 'day' or 'week'
+    period TEXT NOT NULL,
+    -- local date the period starts on, a monday for weeks
+    start_date TEXT NOT NULL,
+    -- 'template', or the model of the llm that wrote it
+    generator TEXT NOT NULL,
+    -- the summary as json
+    content TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (period, start_date)
+);
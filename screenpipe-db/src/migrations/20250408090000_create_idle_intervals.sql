@@ -0,0 +1,10 @@
+-- Stretches with no screen change, no transcription and no input, from the
+-- last activity before to the first one after. They never overlap, and are
+-- left out of focus time, usage and summaries.
+CREATE TABLE IF NOT EXISTS idle_intervals (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    started_at TIMESTAMP NOT NULL,
+    ended_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_idle_intervals_started_at ON idle_intervals(started_at);
@@ -0,0 +1,29 @@
+-- Meetings are either reported live by the ui events ('live') or detected
+-- afterwards from what was recorded ('detected'): call windows on screen,
+-- several voices talking, calendar hints.
+ALTER TABLE meetings ADD COLUMN source TEXT NOT NULL DEFAULT 'live';
+ALTER TABLE meetings ADD COLUMN title TEXT;
+
+-- Events from a calendar, naming the meetings detected over them
+CREATE TABLE IF NOT EXISTS calendar_hints (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title TEXT NOT NULL,
+    start_time TIMESTAMP NOT NULL,
+    end_time TIMESTAMP NOT NULL,
+    -- json array of names
+    attendees TEXT NOT NULL DEFAULT '[]',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_calendar_hints_start_time ON calendar_hints(start_time);
+
+-- The transcriptions said during a meeting
+CREATE TABLE IF NOT EXISTS meeting_transcriptions (
+    meeting_id INTEGER NOT NULL,
+    transcription_id INTEGER NOT NULL,
+    PRIMARY KEY (meeting_id, transcription_id),
+    FOREIGN KEY (meeting_id) REFERENCES meetings(id) ON DELETE CASCADE,
+    FOREIGN KEY (transcription_id) REFERENCES audio_transcriptions(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_meeting_transcriptions_transcription_id ON meeting_transcriptions(transcription_id);
@@ -0,0 +1,71 @@
+-- frames, transcriptions and ui events per utc hour, kept by triggers so the
+-- activity heatmap of months reads a few thousand rows instead of scanning
+-- the content tables
+CREATE TABLE IF NOT EXISTS content_hour_counts (
+    -- YYYY-MM-DD HH:00:00, utc
+    hour TEXT NOT NULL,
+    -- 'vision', 'audio' or 'ui'
+    content_type TEXT NOT NULL,
+    row_count INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (hour, content_type)
+);
+
+INSERT OR REPLACE INTO content_hour_counts (hour, content_type, row_count)
+SELECT strftime('%Y-%m-%d %H:00:00', timestamp), 'vision', COUNT(*)
+FROM frames
+GROUP BY strftime('%Y-%m-%d %H:00:00', timestamp);
+
+INSERT OR REPLACE INTO content_hour_counts (hour, content_type, row_count)
+SELECT strftime('%Y-%m-%d %H:00:00', timestamp), 'audio', COUNT(*)
+FROM audio_transcriptions
+GROUP BY strftime('%Y-%m-%d %H:00:00', timestamp);
+
+INSERT OR REPLACE INTO content_hour_counts (hour, content_type, row_count)
+SELECT strftime('%Y-%m-%d %H:00:00', timestamp), 'ui', COUNT(*)
+FROM ui_monitoring
+GROUP BY strftime('%Y-%m-%d %H:00:00', timestamp);
+
+CREATE TRIGGER IF NOT EXISTS content_hour_counts_vision_insert
+AFTER INSERT ON frames
+BEGIN
+    INSERT INTO content_hour_counts (hour, content_type, row_count)
+    VALUES (strftime('%Y-%m-%d %H:00:00', NEW.timestamp), 'vision', 1)
+    ON CONFLICT (hour, content_type) DO UPDATE SET row_count = row_count + 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS content_hour_counts_vision_delete
+AFTER DELETE ON frames
+BEGIN
+    UPDATE content_hour_counts SET row_count = row_count - 1
+    WHERE content_type = 'vision' AND hour = strftime('%Y-%m-%d %H:00:00', OLD.timestamp);
+END;
+
+CREATE TRIGGER IF NOT EXISTS content_hour_counts_audio_insert
+AFTER INSERT ON audio_transcriptions
+BEGIN
+    INSERT INTO content_hour_counts (hour, content_type, row_count)
+    VALUES (strftime('%Y-%m-%d %H:00:00', NEW.timestamp), 'audio', 1)
+    ON CONFLICT (hour, content_type) DO UPDATE SET row_count = row_count + 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS content_hour_counts_audio_delete
+AFTER DELETE ON audio_transcriptions
+BEGIN
+    UPDATE content_hour_counts SET row_count = row_count - 1
+    WHERE content_type = 'audio' AND hour = strftime('%Y-%m-%d %H:00:00', OLD.timestamp);
+END;
+
+CREATE TRIGGER IF NOT EXISTS content_hour_counts_ui_insert
+AFTER INSERT ON ui_monitoring
+BEGIN
+    INSERT INTO content_hour_counts (hour, content_type, row_count)
+    VALUES (strftime('%Y-%m-%d %H:00:00', NEW.timestamp), 'ui', 1)
+    ON CONFLICT (hour, content_type) DO UPDATE SET row_count = row_count + 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS content_hour_counts_ui_delete
+AFTER DELETE ON ui_monitoring
+BEGIN
+    UPDATE content_hour_counts SET row_count = row_count - 1
+    WHERE content_type = 'ui' AND hour = strftime('%Y-%m-%d %H:00:00', OLD.timestamp);
+END;
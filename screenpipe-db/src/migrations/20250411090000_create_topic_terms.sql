@@ -0,0 +1,22 @@
+-- The terms standing out on each day, in the screen text and transcriptions,
+-- to follow topics over time
+CREATE TABLE IF NOT EXISTS topic_terms (
+    day DATE NOT NULL,
+    term TEXT NOT NULL,
+    -- distinct texts of the day holding the term
+    documents INTEGER NOT NULL,
+    -- documents over the texts of the day
+    share REAL NOT NULL,
+    -- share weighed down for terms found on most days before
+    score REAL NOT NULL,
+    PRIMARY KEY (day, term)
+);
+
+CREATE INDEX IF NOT EXISTS idx_topic_terms_term_day ON topic_terms(term, day);
+
+-- The days whose terms were extracted, a day with no text has none
+CREATE TABLE IF NOT EXISTS topic_days (
+    day DATE PRIMARY KEY,
+    documents INTEGER NOT NULL,
+    computed_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,15 @@
+-- How apps, window titles and browser urls count in the productivity score.
+CREATE TABLE IF NOT EXISTS app_categories (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    -- 'app', 'window' or 'url'
+    kind TEXT NOT NULL,
+    -- found case insensitively anywhere in the app name, window title or url
+    pattern TEXT NOT NULL,
+    -- 'deep_work', 'communication' or 'distraction'
+    category TEXT NOT NULL,
+    -- 'api' for the rules added at /productivity/categories, 'config' for
+    -- the ones of the command line, replaced at each start
+    source TEXT NOT NULL DEFAULT 'api',
+    created_at TIMESTAMP NOT NULL,
+    UNIQUE (kind, pattern, source)
+);
@@ -0,0 +1,18 @@
+-- Stretches of activity on related apps between two breaks, detected from
+-- the frames and transcriptions and replaced when detected again.
+CREATE TABLE IF NOT EXISTS work_sessions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    started_at TIMESTAMP NOT NULL,
+    ended_at TIMESTAMP NOT NULL,
+    -- the app and window title with the most frames
+    dominant_app TEXT,
+    dominant_window TEXT,
+    -- the word or pair of words in the window titles of the most frames
+    topic TEXT,
+    -- json array of the apps, the one with the most frames first
+    apps TEXT NOT NULL DEFAULT '[]',
+    frames INTEGER NOT NULL DEFAULT 0,
+    transcriptions INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS idx_work_sessions_started_at ON work_sessions(started_at);
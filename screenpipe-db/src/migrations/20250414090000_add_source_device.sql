@@ -0,0 +1,8 @@
+-- The device rows merged from another database were recorded on, e.g.
+-- "desktop". Null for the ones recorded here.
+ALTER TABLE video_chunks ADD COLUMN source_device TEXT DEFAULT NULL;
+ALTER TABLE frames ADD COLUMN source_device TEXT DEFAULT NULL;
+ALTER TABLE audio_chunks ADD COLUMN source_device TEXT DEFAULT NULL;
+ALTER TABLE audio_transcriptions ADD COLUMN source_device TEXT DEFAULT NULL;
+ALTER TABLE ui_monitoring ADD COLUMN source_device TEXT DEFAULT NULL;
+ALTER TABLE speakers ADD COLUMN source_device TEXT DEFAULT NULL;
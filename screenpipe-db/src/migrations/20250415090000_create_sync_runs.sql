@@ -0,0 +1,17 @@
+-- Uploads of the database to object storage: snapshots of the whole
+-- database and deltas of the rows added since the upload before.
+CREATE TABLE IF NOT EXISTS sync_runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    -- 'snapshot' or 'delta'
+    kind TEXT NOT NULL,
+    object_key TEXT NOT NULL,
+    -- json object of the last rowid of each synced table the upload holds
+    watermarks TEXT NOT NULL,
+    rows INTEGER NOT NULL DEFAULT 0,
+    bytes INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL
+);
+
+-- when the file of the chunk was uploaded
+ALTER TABLE video_chunks ADD COLUMN synced_at TIMESTAMP DEFAULT NULL;
+ALTER TABLE audio_chunks ADD COLUMN synced_at TIMESTAMP DEFAULT NULL;
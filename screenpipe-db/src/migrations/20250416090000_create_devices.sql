@@ -0,0 +1,104 @@
+-- Capture sources: the machines, their monitors and audio devices, with
+-- the settings recording applies to each.
+CREATE TABLE IF NOT EXISTS devices (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    -- 'host', 'monitor', 'audio_input' or 'audio_output'
+    kind TEXT NOT NULL,
+    -- as recorded: 'monitor_1', the name of the audio device, the host name
+    name TEXT NOT NULL,
+    -- machine of the device: '' for this one, the source device of merged rows
+    host TEXT NOT NULL DEFAULT '',
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    -- capture fps of a monitor, the global one when NULL
+    fps REAL DEFAULT NULL,
+    -- ocr engine of a monitor or transcription engine of an audio device
+    engine TEXT DEFAULT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_seen_at TIMESTAMP DEFAULT NULL,
+    UNIQUE (kind, name, host)
+);
+
+ALTER TABLE video_chunks ADD COLUMN device_id INTEGER DEFAULT NULL REFERENCES devices(id);
+ALTER TABLE audio_transcriptions ADD COLUMN device_id INTEGER DEFAULT NULL REFERENCES devices(id);
+
+-- the search index only holds the text columns, setting the device id
+-- mustn't rewrite it
+DROP TRIGGER IF EXISTS audio_transcriptions_update;
+CREATE TRIGGER audio_transcriptions_update
+AFTER UPDATE OF transcription, device, audio_chunk_id, speaker_id, start_time, end_time
+ON audio_transcriptions
+BEGIN
+    INSERT INTO audio_transcriptions_fts(audio_transcriptions_fts, rowid, transcription, device, audio_chunk_id, speaker_id, start_time, end_time)
+    VALUES ('delete', OLD.id, OLD.transcription, OLD.device, OLD.audio_chunk_id, OLD.speaker_id, OLD.start_time, OLD.end_time);
+    INSERT INTO audio_transcriptions_fts(rowid, transcription, device, audio_chunk_id, speaker_id, start_time, end_time)
+    VALUES (NEW.id, NEW.transcription, NEW.device, NEW.audio_chunk_id, NEW.speaker_id, NEW.start_time, NEW.end_time);
+END;
+
+INSERT OR IGNORE INTO devices (kind, name, host)
+SELECT DISTINCT 'monitor', device_name, COALESCE(source_device, '')
+FROM video_chunks
+WHERE device_name != '';
+
+INSERT OR IGNORE INTO devices (kind, name, host)
+SELECT DISTINCT
+    CASE WHEN is_input_device THEN 'audio_input' ELSE 'audio_output' END,
+    device,
+    COALESCE(source_device, '')
+FROM audio_transcriptions
+WHERE device != '';
+
+UPDATE video_chunks SET device_id = (
+    SELECT d.id FROM devices d
+    WHERE d.kind = 'monitor'
+        AND d.name = video_chunks.device_name
+        AND d.host = COALESCE(video_chunks.source_device, '')
+);
+
+UPDATE audio_transcriptions SET device_id = (
+    SELECT d.id FROM devices d
+    WHERE d.kind = CASE WHEN audio_transcriptions.is_input_device THEN 'audio_input' ELSE 'audio_output' END
+        AND d.name = audio_transcriptions.device
+        AND d.host = COALESCE(audio_transcriptions.source_device, '')
+);
+
+CREATE INDEX IF NOT EXISTS idx_video_chunks_device_id ON video_chunks(device_id);
+CREATE INDEX IF NOT EXISTS idx_audio_transcriptions_device_id ON audio_transcriptions(device_id);
+
+-- every way rows are written (capture, imports, merges) registers the device
+-- they come from
+CREATE TRIGGER IF NOT EXISTS video_chunks_device
+AFTER INSERT ON video_chunks
+WHEN NEW.device_id IS NULL AND NEW.device_name != ''
+BEGIN
+    INSERT INTO devices (kind, name, host, last_seen_at)
+    VALUES ('monitor', NEW.device_name, COALESCE(NEW.source_device, ''), CURRENT_TIMESTAMP)
+    ON CONFLICT (kind, name, host) DO UPDATE SET last_seen_at = excluded.last_seen_at;
+    UPDATE video_chunks SET device_id = (
+        SELECT id FROM devices
+        WHERE kind = 'monitor'
+            AND name = NEW.device_name
+            AND host = COALESCE(NEW.source_device, '')
+    )
+    WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS audio_transcriptions_device
+AFTER INSERT ON audio_transcriptions
+WHEN NEW.device_id IS NULL AND NEW.device != ''
+BEGIN
+    INSERT INTO devices (kind, name, host, last_seen_at)
+    VALUES (
+        CASE WHEN NEW.is_input_device THEN 'audio_input' ELSE 'audio_output' END,
+        NEW.device,
+        COALESCE(NEW.source_device, ''),
+        CURRENT_TIMESTAMP
+    )
+    ON CONFLICT (kind, name, host) DO UPDATE SET last_seen_at = excluded.last_seen_at;
+    UPDATE audio_transcriptions SET device_id = (
+        SELECT id FROM devices
+        WHERE kind = CASE WHEN NEW.is_input_device THEN 'audio_input' ELSE 'audio_output' END
+            AND name = NEW.device
+            AND host = COALESCE(NEW.source_device, '')
+    )
+    WHERE id = NEW.id;
+END;
@@ -0,0 +1,173 @@
+-- UUIDv7 ids of the rows sync and merge move between databases: unlike
+-- the integer ids, they're the same in every copy of a row and never
+-- collide with the ones of another machine. Their first 48 bits are the
+-- unix time in milliseconds of the row, so they sort by time.
+ALTER TABLE video_chunks ADD COLUMN uid TEXT;
+ALTER TABLE frames ADD COLUMN uid TEXT;
+ALTER TABLE audio_chunks ADD COLUMN uid TEXT;
+ALTER TABLE audio_transcriptions ADD COLUMN uid TEXT;
+ALTER TABLE speakers ADD COLUMN uid TEXT;
+ALTER TABLE frames ADD COLUMN video_chunk_uid TEXT;
+ALTER TABLE audio_transcriptions ADD COLUMN audio_chunk_uid TEXT;
+ALTER TABLE audio_transcriptions ADD COLUMN speaker_uid TEXT;
+
+-- frames_fts only holds these, setting a uid mustn't rewrite it
+DROP TRIGGER IF EXISTS frames_au;
+CREATE TRIGGER frames_au AFTER UPDATE OF name, browser_url, app_name, window_name, focused ON frames
+WHEN (NEW.name IS NOT NULL AND NEW.name != '')
+   OR (NEW.browser_url IS NOT NULL AND NEW.browser_url != '')
+   OR (NEW.app_name IS NOT NULL AND NEW.app_name != '')
+   OR (NEW.window_name IS NOT NULL AND NEW.window_name != '')
+   OR (NEW.focused IS NOT NULL)
+BEGIN
+    INSERT OR REPLACE INTO frames_fts(rowid, id, name, browser_url, app_name, window_name, focused, url_domain, url_path)
+    VALUES (
+        NEW.id,
+        NEW.id,
+        COALESCE(NEW.name, ''),
+        COALESCE(NEW.browser_url, ''),
+        COALESCE(NEW.app_name, ''),
+        COALESCE(NEW.window_name, ''),
+        COALESCE(NEW.focused, 0),
+        COALESCE(NEW.url_domain, ''),
+        COALESCE(NEW.url_path, '')
+    );
+END;
+
+UPDATE video_chunks SET uid = lower(
+        substr(printf('%012x', CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER)), 1, 8) || '-' ||
+        substr(printf('%012x', CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER)), 9, 4) || '-7' ||
+        substr(hex(randomblob(2)), 2) || '-' ||
+        substr('89ab', 1 + abs(random() % 4), 1) || substr(hex(randomblob(2)), 2) || '-' ||
+        hex(randomblob(6))
+    );
+
+UPDATE frames SET uid = lower(
+        substr(printf('%012x', CAST((CASE WHEN julianday(timestamp) > 2440587.5 THEN julianday(timestamp) ELSE julianday('now') END - 2440587.5) * 86400000 AS INTEGER)), 1, 8) || '-' ||
+        substr(printf('%012x', CAST((CASE WHEN julianday(timestamp) > 2440587.5 THEN julianday(timestamp) ELSE julianday('now') END - 2440587.5) * 86400000 AS INTEGER)), 9, 4) || '-7' ||
+        substr(hex(randomblob(2)), 2) || '-' ||
+        substr('89ab', 1 + abs(random() % 4), 1) || substr(hex(randomblob(2)), 2) || '-' ||
+        hex(randomblob(6))
+    );
+
+UPDATE audio_chunks SET uid = lower(
+        substr(printf('%012x', CAST((CASE WHEN julianday(timestamp) > 2440587.5 THEN julianday(timestamp) ELSE julianday('now') END - 2440587.5) * 86400000 AS INTEGER)), 1, 8) || '-' ||
+        substr(printf('%012x', CAST((CASE WHEN julianday(timestamp) > 2440587.5 THEN julianday(timestamp) ELSE julianday('now') END - 2440587.5) * 86400000 AS INTEGER)), 9, 4) || '-7' ||
+        substr(hex(randomblob(2)), 2) || '-' ||
+        substr('89ab', 1 + abs(random() % 4), 1) || substr(hex(randomblob(2)), 2) || '-' ||
+        hex(randomblob(6))
+    );
+
+UPDATE audio_transcriptions SET uid = lower(
+        substr(printf('%012x', CAST((CASE WHEN julianday(timestamp) > 2440587.5 THEN julianday(timestamp) ELSE julianday('now') END - 2440587.5) * 86400000 AS INTEGER)), 1, 8) || '-' ||
+        substr(printf('%012x', CAST((CASE WHEN julianday(timestamp) > 2440587.5 THEN julianday(timestamp) ELSE julianday('now') END - 2440587.5) * 86400000 AS INTEGER)), 9, 4) || '-7' ||
+        substr(hex(randomblob(2)), 2) || '-' ||
+        substr('89ab', 1 + abs(random() % 4), 1) || substr(hex(randomblob(2)), 2) || '-' ||
+        hex(randomblob(6))
+    );
+
+UPDATE speakers SET uid = lower(
+        substr(printf('%012x', CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER)), 1, 8) || '-' ||
+        substr(printf('%012x', CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER)), 9, 4) || '-7' ||
+        substr(hex(randomblob(2)), 2) || '-' ||
+        substr('89ab', 1 + abs(random() % 4), 1) || substr(hex(randomblob(2)), 2) || '-' ||
+        hex(randomblob(6))
+    );
+
+UPDATE frames SET video_chunk_uid = (SELECT uid FROM video_chunks WHERE id = frames.video_chunk_id);
+UPDATE audio_transcriptions SET
+    audio_chunk_uid = (SELECT uid FROM audio_chunks WHERE id = audio_transcriptions.audio_chunk_id),
+    speaker_uid = (SELECT uid FROM speakers WHERE id = audio_transcriptions.speaker_id);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_video_chunks_uid ON video_chunks(uid);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_frames_uid ON frames(uid);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_audio_chunks_uid ON audio_chunks(uid);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_audio_transcriptions_uid ON audio_transcriptions(uid);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_speakers_uid ON speakers(uid);
+CREATE INDEX IF NOT EXISTS idx_frames_video_chunk_uid ON frames(video_chunk_uid);
+CREATE INDEX IF NOT EXISTS idx_audio_transcriptions_audio_chunk_uid ON audio_transcriptions(audio_chunk_uid);
+CREATE INDEX IF NOT EXISTS idx_audio_transcriptions_speaker_uid ON audio_transcriptions(speaker_uid);
+
+-- rows inserted without a uid get one, e.g. new captures; merged and synced
+-- rows keep the one they have
+
+CREATE TRIGGER IF NOT EXISTS video_chunks_uid
+AFTER INSERT ON video_chunks
+WHEN NEW.uid IS NULL
+BEGIN
+    UPDATE video_chunks SET uid = COALESCE(NEW.uid, lower(
+        substr(printf('%012x', CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER)), 1, 8) || '-' ||
+        substr(printf('%012x', CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER)), 9, 4) || '-7' ||
+        substr(hex(randomblob(2)), 2) || '-' ||
+        substr('89ab', 1 + abs(random() % 4), 1) || substr(hex(randomblob(2)), 2) || '-' ||
+        hex(randomblob(6))
+    ))
+    WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS frames_uid
+AFTER INSERT ON frames
+WHEN NEW.uid IS NULL OR NEW.video_chunk_uid IS NULL
+BEGIN
+    UPDATE frames SET uid = COALESCE(NEW.uid, lower(
+        substr(printf('%012x', CAST((CASE WHEN julianday(NEW.timestamp) > 2440587.5 THEN julianday(NEW.timestamp) ELSE julianday('now') END - 2440587.5) * 86400000 AS INTEGER)), 1, 8) || '-' ||
+        substr(printf('%012x', CAST((CASE WHEN julianday(NEW.timestamp) > 2440587.5 THEN julianday(NEW.timestamp) ELSE julianday('now') END - 2440587.5) * 86400000 AS INTEGER)), 9, 4) || '-7' ||
+        substr(hex(randomblob(2)), 2) || '-' ||
+        substr('89ab', 1 + abs(random() % 4), 1) || substr(hex(randomblob(2)), 2) || '-' ||
+        hex(randomblob(6))
+    )),
+        video_chunk_uid = (SELECT uid FROM video_chunks WHERE id = NEW.video_chunk_id)
+    WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS audio_chunks_uid
+AFTER INSERT ON audio_chunks
+WHEN NEW.uid IS NULL
+BEGIN
+    UPDATE audio_chunks SET uid = COALESCE(NEW.uid, lower(
+        substr(printf('%012x', CAST((CASE WHEN julianday(NEW.timestamp) > 2440587.5 THEN julianday(NEW.timestamp) ELSE julianday('now') END - 2440587.5) * 86400000 AS INTEGER)), 1, 8) || '-' ||
+        substr(printf('%012x', CAST((CASE WHEN julianday(NEW.timestamp) > 2440587.5 THEN julianday(NEW.timestamp) ELSE julianday('now') END - 2440587.5) * 86400000 AS INTEGER)), 9, 4) || '-7' ||
+        substr(hex(randomblob(2)), 2) || '-' ||
+        substr('89ab', 1 + abs(random() % 4), 1) || substr(hex(randomblob(2)), 2) || '-' ||
+        hex(randomblob(6))
+    ))
+    WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS audio_transcriptions_uid
+AFTER INSERT ON audio_transcriptions
+WHEN NEW.uid IS NULL OR NEW.audio_chunk_uid IS NULL OR (NEW.speaker_uid IS NULL AND NEW.speaker_id IS NOT NULL)
+BEGIN
+    UPDATE audio_transcriptions SET uid = COALESCE(NEW.uid, lower(
+        substr(printf('%012x', CAST((CASE WHEN julianday(NEW.timestamp) > 2440587.5 THEN julianday(NEW.timestamp) ELSE julianday('now') END - 2440587.5) * 86400000 AS INTEGER)), 1, 8) || '-' ||
+        substr(printf('%012x', CAST((CASE WHEN julianday(NEW.timestamp) > 2440587.5 THEN julianday(NEW.timestamp) ELSE julianday('now') END - 2440587.5) * 86400000 AS INTEGER)), 9, 4) || '-7' ||
+        substr(hex(randomblob(2)), 2) || '-' ||
+        substr('89ab', 1 + abs(random() % 4), 1) || substr(hex(randomblob(2)), 2) || '-' ||
+        hex(randomblob(6))
+    )),
+        audio_chunk_uid = (SELECT uid FROM audio_chunks WHERE id = NEW.audio_chunk_id),
+        speaker_uid = (SELECT uid FROM speakers WHERE id = NEW.speaker_id)
+    WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS speakers_uid
+AFTER INSERT ON speakers
+WHEN NEW.uid IS NULL
+BEGIN
+    UPDATE speakers SET uid = COALESCE(NEW.uid, lower(
+        substr(printf('%012x', CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER)), 1, 8) || '-' ||
+        substr(printf('%012x', CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER)), 9, 4) || '-7' ||
+        substr(hex(randomblob(2)), 2) || '-' ||
+        substr('89ab', 1 + abs(random() % 4), 1) || substr(hex(randomblob(2)), 2) || '-' ||
+        hex(randomblob(6))
+    ))
+    WHERE id = NEW.id;
+END;
+
+-- speakers get merged and reassigned
+CREATE TRIGGER IF NOT EXISTS audio_transcriptions_speaker_uid
+AFTER UPDATE OF speaker_id ON audio_transcriptions
+BEGIN
+    UPDATE audio_transcriptions SET speaker_uid = (SELECT uid FROM speakers WHERE id = NEW.speaker_id)
+    WHERE id = NEW.id;
+END;
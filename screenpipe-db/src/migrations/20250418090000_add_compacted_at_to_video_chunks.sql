@@ -0,0 +1,3 @@
+-- When the file of a video chunk was re-encoded to take less space, NULL
+-- while it's the one recorded.
+ALTER TABLE video_chunks ADD COLUMN compacted_at TIMESTAMP DEFAULT NULL;
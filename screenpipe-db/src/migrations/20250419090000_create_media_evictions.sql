@@ -0,0 +1,14 @@
+-- Video and audio chunks whose file was deleted to stay under the storage
+-- budget. Their frames and transcriptions are kept.
+CREATE TABLE IF NOT EXISTS media_evictions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    -- "video" or "audio"
+    kind TEXT NOT NULL,
+    chunk_id INTEGER NOT NULL,
+    file_path TEXT NOT NULL,
+    bytes INTEGER NOT NULL DEFAULT 0,
+    -- the eviction policy that picked the chunk
+    reason TEXT NOT NULL,
+    evicted_at TIMESTAMP NOT NULL,
+    UNIQUE (kind, chunk_id)
+);
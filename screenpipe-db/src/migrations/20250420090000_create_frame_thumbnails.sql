@@ -0,0 +1,9 @@
+-- Small jpegs of frames, made on first request so timelines don't decode
+-- video chunks. Gone with their frame.
+CREATE TABLE IF NOT EXISTS frame_thumbnails (
+    frame_id INTEGER NOT NULL REFERENCES frames(id) ON DELETE CASCADE,
+    width INTEGER NOT NULL,
+    image BLOB NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (frame_id, width)
+);
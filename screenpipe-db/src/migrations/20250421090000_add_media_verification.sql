@@ -0,0 +1,7 @@
+-- When the file of a chunk was last checked, and what was wrong with it
+-- then: "missing", "undecodable" or "missing_frames". NULL when it was
+-- fine.
+ALTER TABLE video_chunks ADD COLUMN verified_at TIMESTAMP DEFAULT NULL;
+ALTER TABLE video_chunks ADD COLUMN media_issue TEXT DEFAULT NULL;
+ALTER TABLE audio_chunks ADD COLUMN verified_at TIMESTAMP DEFAULT NULL;
+ALTER TABLE audio_chunks ADD COLUMN media_issue TEXT DEFAULT NULL;
@@ -0,0 +1,3 @@
+-- The codec a video chunk is encoded with, as ffprobe names it: "h264",
+-- "hevc" or "av1". NULL for chunks recorded before, all hevc.
+ALTER TABLE video_chunks ADD COLUMN codec TEXT DEFAULT NULL;
@@ -0,0 +1,24 @@
+-- Ui text the recorder writes while secret masking is on. The recorder runs
+-- in another process, so its writes are held here and only reach
+-- ui_monitoring once masked, see set_ui_secret_scanning.
+DROP TRIGGER IF EXISTS ui_monitoring_unscanned_insert;
+DROP TRIGGER IF EXISTS ui_monitoring_unscanned_update;
+DROP TABLE IF EXISTS ui_monitoring_unscanned;
+
+CREATE TABLE IF NOT EXISTS ui_monitoring_held (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    -- the row the text replaces, null for a new row
+    ui_monitoring_id INTEGER,
+    timestamp DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    initial_traversal_at DATETIME,
+    app TEXT NOT NULL,
+    window TEXT NOT NULL,
+    text_output TEXT NOT NULL,
+    UNIQUE (app, window)
+);
+
+-- Holds a row while held text is released, so the release itself is let
+-- through.
+CREATE TABLE IF NOT EXISTS ui_monitoring_releasing (
+    id INTEGER PRIMARY KEY
+);
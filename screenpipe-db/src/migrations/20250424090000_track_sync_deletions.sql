@@ -0,0 +1,105 @@
+-- Deletions and text masked or redacted since the last sync snapshot. Deltas
+-- only carry new rows, so these make the next upload a snapshot, and the
+-- objects before it are pruned from the bucket.
+CREATE TABLE IF NOT EXISTS sync_state (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    snapshot_needed BOOLEAN NOT NULL DEFAULT FALSE
+);
+INSERT OR IGNORE INTO sync_state (id) VALUES (1);
+
+-- Media files uploaded then deleted here, to delete from the bucket.
+CREATE TABLE IF NOT EXISTS sync_deleted_media (
+    file_path TEXT PRIMARY KEY
+);
+
+CREATE TRIGGER IF NOT EXISTS sync_snapshot_frames_delete AFTER DELETE ON frames
+WHEN NOT (SELECT snapshot_needed FROM sync_state WHERE id = 1)
+BEGIN
+    UPDATE sync_state SET snapshot_needed = TRUE WHERE id = 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS sync_snapshot_ocr_text_delete AFTER DELETE ON ocr_text
+WHEN NOT (SELECT snapshot_needed FROM sync_state WHERE id = 1)
+BEGIN
+    UPDATE sync_state SET snapshot_needed = TRUE WHERE id = 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS sync_snapshot_audio_transcriptions_delete AFTER DELETE ON audio_transcriptions
+WHEN NOT (SELECT snapshot_needed FROM sync_state WHERE id = 1)
+BEGIN
+    UPDATE sync_state SET snapshot_needed = TRUE WHERE id = 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS sync_snapshot_ui_monitoring_delete AFTER DELETE ON ui_monitoring
+WHEN NOT (SELECT snapshot_needed FROM sync_state WHERE id = 1)
+BEGIN
+    UPDATE sync_state SET snapshot_needed = TRUE WHERE id = 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS sync_snapshot_ui_traversals_delete AFTER DELETE ON ui_traversals
+WHEN NOT (SELECT snapshot_needed FROM sync_state WHERE id = 1)
+BEGIN
+    UPDATE sync_state SET snapshot_needed = TRUE WHERE id = 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS sync_snapshot_clipboard_delete AFTER DELETE ON clipboard
+WHEN NOT (SELECT snapshot_needed FROM sync_state WHERE id = 1)
+BEGIN
+    UPDATE sync_state SET snapshot_needed = TRUE WHERE id = 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS sync_snapshot_browser_contexts_delete AFTER DELETE ON browser_contexts
+WHEN NOT (SELECT snapshot_needed FROM sync_state WHERE id = 1)
+BEGIN
+    UPDATE sync_state SET snapshot_needed = TRUE WHERE id = 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS sync_snapshot_speakers_delete AFTER DELETE ON speakers
+WHEN NOT (SELECT snapshot_needed FROM sync_state WHERE id = 1)
+BEGIN
+    UPDATE sync_state SET snapshot_needed = TRUE WHERE id = 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS sync_snapshot_ocr_text_update AFTER UPDATE OF text ON ocr_text
+WHEN NEW.text IS NOT OLD.text
+    AND NOT (SELECT snapshot_needed FROM sync_state WHERE id = 1)
+BEGIN
+    UPDATE sync_state SET snapshot_needed = TRUE WHERE id = 1;
+END;
+
+CREATE TRIGGER IF NOT EXISTS sync_snapshot_audio_transcriptions_update AFTER UPDATE OF transcription ON audio_transcriptions
+WHEN NEW.transcription IS NOT OLD.transcription
+    AND NOT (SELECT snapshot_needed FROM sync_state WHERE id = 1)
+BEGIN
+    UPDATE sync_state SET snapshot_needed = TRUE WHERE id = 1;
+END;
+
+-- an evicted chunk is named by its eviction
+CREATE TRIGGER IF NOT EXISTS sync_deleted_video_chunks AFTER DELETE ON video_chunks
+WHEN OLD.synced_at IS NOT NULL
+BEGIN
+    INSERT OR IGNORE INTO sync_deleted_media (file_path)
+    SELECT path FROM (
+        SELECT COALESCE(
+            NULLIF(OLD.file_path, ''),
+            (SELECT file_path FROM media_evictions WHERE kind = 'video' AND chunk_id = OLD.id)
+        ) AS path
+    )
+    WHERE path IS NOT NULL AND path != '';
+    UPDATE sync_state SET snapshot_needed = TRUE WHERE id = 1;
+END;
+
+-- an evicted chunk is named by its eviction
+CREATE TRIGGER IF NOT EXISTS sync_deleted_audio_chunks AFTER DELETE ON audio_chunks
+WHEN OLD.synced_at IS NOT NULL
+BEGIN
+    INSERT OR IGNORE INTO sync_deleted_media (file_path)
+    SELECT path FROM (
+        SELECT COALESCE(
+            NULLIF(OLD.file_path, ''),
+            (SELECT file_path FROM media_evictions WHERE kind = 'audio' AND chunk_id = OLD.id)
+        ) AS path
+    )
+    WHERE path IS NOT NULL AND path != '';
+    UPDATE sync_state SET snapshot_needed = TRUE WHERE id = 1;
+END;
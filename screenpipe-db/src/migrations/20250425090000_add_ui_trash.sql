@@ -0,0 +1,13 @@
+-- Ui text and ui traversals past their retention go to the trash like frames
+-- and audio, and are purged with them.
+ALTER TABLE ui_monitoring ADD COLUMN deleted_at TIMESTAMP DEFAULT NULL;
+ALTER TABLE ui_monitoring ADD COLUMN restored_at TIMESTAMP DEFAULT NULL;
+ALTER TABLE ui_monitoring ADD COLUMN trashed_by TEXT DEFAULT NULL;
+ALTER TABLE ui_traversals ADD COLUMN deleted_at TIMESTAMP DEFAULT NULL;
+ALTER TABLE ui_traversals ADD COLUMN restored_at TIMESTAMP DEFAULT NULL;
+ALTER TABLE ui_traversals ADD COLUMN trashed_by TEXT DEFAULT NULL;
+CREATE INDEX IF NOT EXISTS idx_ui_monitoring_deleted_at ON ui_monitoring(deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_ui_traversals_deleted_at ON ui_traversals(deleted_at) WHERE deleted_at IS NOT NULL;
+
+-- deleted_ui_rows counts the ui rows purged from then on
+ALTER TABLE retention_rule_stats ADD COLUMN trashed_ui_rows INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,97 @@
+-- ocr_text only had an implicit rowid, which VACUUM and VACUUM INTO are free
+-- to renumber, leaving ocr_text_fts and the sync watermarks pointing at other
+-- rows. The rowids are kept as the new id.
+PRAGMA foreign_keys=off;
+
+DROP TRIGGER IF EXISTS ocr_text_ai;
+DROP TRIGGER IF EXISTS ocr_text_update;
+DROP TRIGGER IF EXISTS ocr_text_delete;
+DROP TRIGGER IF EXISTS content_day_counts_ocr_insert;
+DROP TRIGGER IF EXISTS content_day_counts_ocr_delete;
+DROP TRIGGER IF EXISTS sync_snapshot_ocr_text_delete;
+DROP TRIGGER IF EXISTS sync_snapshot_ocr_text_update;
+
+CREATE TABLE ocr_text_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    frame_id INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    text_json TEXT,
+    app_name TEXT NOT NULL DEFAULT '',
+    ocr_engine TEXT NOT NULL DEFAULT 'unknown',
+    window_name TEXT,
+    focused BOOLEAN DEFAULT FALSE,
+    text_length INTEGER,
+    confidence REAL DEFAULT NULL,
+    tables TEXT DEFAULT NULL
+);
+
+INSERT INTO ocr_text_new (
+    id, frame_id, text, text_json, app_name, ocr_engine, window_name, focused,
+    text_length, confidence, tables
+)
+SELECT
+    rowid, frame_id, text, text_json, app_name, ocr_engine, window_name, focused,
+    text_length, confidence, tables
+FROM ocr_text;
+
+DROP TABLE ocr_text;
+ALTER TABLE ocr_text_new RENAME TO ocr_text;
+
+CREATE INDEX IF NOT EXISTS idx_ocr_text_frame_id ON ocr_text(frame_id);
+CREATE INDEX IF NOT EXISTS idx_ocr_text_frame_app_window ON ocr_text(frame_id, app_name, window_name);
+CREATE INDEX IF NOT EXISTS idx_ocr_text_length ON ocr_text(text_length);
+CREATE INDEX IF NOT EXISTS idx_ocr_text_confidence ON ocr_text(confidence);
+CREATE INDEX IF NOT EXISTS idx_ocr_text_has_tables ON ocr_text(frame_id) WHERE tables IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_ocr_text_frame_id_text_length ON ocr_text(frame_id, text_length);
+
+-- the index already holds the rows under these ids
+CREATE TRIGGER ocr_text_ai AFTER INSERT ON ocr_text
+BEGIN
+    INSERT INTO ocr_text_fts(rowid, text, app_name, window_name, frame_id)
+    VALUES (NEW.id, NEW.text, NEW.app_name, NEW.window_name, NEW.frame_id);
+END;
+
+CREATE TRIGGER ocr_text_update AFTER UPDATE ON ocr_text
+BEGIN
+    INSERT INTO ocr_text_fts(ocr_text_fts, rowid, text, app_name, window_name, frame_id)
+    VALUES ('delete', OLD.id, OLD.text, OLD.app_name, OLD.window_name, OLD.frame_id);
+    INSERT INTO ocr_text_fts(rowid, text, app_name, window_name, frame_id)
+    VALUES (NEW.id, NEW.text, NEW.app_name, NEW.window_name, NEW.frame_id);
+END;
+
+CREATE TRIGGER ocr_text_delete AFTER DELETE ON ocr_text
+BEGIN
+    INSERT INTO ocr_text_fts(ocr_text_fts, rowid, text, app_name, window_name, frame_id)
+    VALUES ('delete', OLD.id, OLD.text, OLD.app_name, OLD.window_name, OLD.frame_id);
+END;
+
+CREATE TRIGGER content_day_counts_ocr_insert
+AFTER INSERT ON ocr_text
+BEGIN
+    INSERT INTO content_day_counts (day, content_type, row_count)
+    SELECT date(timestamp), 'ocr', 1 FROM frames WHERE id = NEW.frame_id
+    ON CONFLICT (day, content_type) DO UPDATE SET row_count = row_count + 1;
+END;
+
+CREATE TRIGGER content_day_counts_ocr_delete
+AFTER DELETE ON ocr_text
+BEGIN
+    UPDATE content_day_counts SET row_count = row_count - 1
+    WHERE content_type = 'ocr'
+        AND day = (SELECT date(timestamp) FROM frames WHERE id = OLD.frame_id);
+END;
+
+CREATE TRIGGER sync_snapshot_ocr_text_delete AFTER DELETE ON ocr_text
+WHEN NOT (SELECT snapshot_needed FROM sync_state WHERE id = 1)
+BEGIN
+    UPDATE sync_state SET snapshot_needed = TRUE WHERE id = 1;
+END;
+
+CREATE TRIGGER sync_snapshot_ocr_text_update AFTER UPDATE OF text ON ocr_text
+WHEN NEW.text IS NOT OLD.text
+    AND NOT (SELECT snapshot_needed FROM sync_state WHERE id = 1)
+BEGIN
+    UPDATE sync_state SET snapshot_needed = TRUE WHERE id = 1;
+END;
+
+PRAGMA foreign_keys=on;
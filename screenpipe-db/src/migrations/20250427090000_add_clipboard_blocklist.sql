@@ -0,0 +1,10 @@
+-- Text copied in a blocklisted app or window is kept out like its frames.
+CREATE TRIGGER IF NOT EXISTS capture_blocklist_clipboard BEFORE INSERT ON clipboard
+WHEN EXISTS (
+    SELECT 1 FROM capture_blocklist b
+    WHERE (b.kind = 'app' AND instr(lower(COALESCE(NEW.app_name, '')), lower(b.pattern)) > 0)
+       OR (b.kind = 'window' AND instr(lower(COALESCE(NEW.window_name, '')), lower(b.pattern)) > 0)
+)
+BEGIN
+    SELECT RAISE(IGNORE);
+END;
@@ -0,0 +1,6 @@
+-- Clipboard entries past their retention go to the trash like ui text, and
+-- are purged with it.
+ALTER TABLE clipboard ADD COLUMN deleted_at TIMESTAMP DEFAULT NULL;
+ALTER TABLE clipboard ADD COLUMN restored_at TIMESTAMP DEFAULT NULL;
+ALTER TABLE clipboard ADD COLUMN trashed_by TEXT DEFAULT NULL;
+CREATE INDEX IF NOT EXISTS idx_clipboard_deleted_at ON clipboard(deleted_at) WHERE deleted_at IS NOT NULL;
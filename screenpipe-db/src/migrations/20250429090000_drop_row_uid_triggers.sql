@@ -0,0 +1,8 @@
+-- rows get their uid in the INSERT itself now, the triggers only cost every
+-- new frame, chunk and transcription a second write. The backfill of
+-- add_row_uids stays, and the speaker uid still follows reassignments.
+DROP TRIGGER IF EXISTS video_chunks_uid;
+DROP TRIGGER IF EXISTS frames_uid;
+DROP TRIGGER IF EXISTS audio_chunks_uid;
+DROP TRIGGER IF EXISTS audio_transcriptions_uid;
+DROP TRIGGER IF EXISTS speakers_uid;
@@ -0,0 +1,50 @@
+use crate::{DatabaseManager, MonitorSettings};
+
+impl DatabaseManager {
+    pub async fn get_monitor_settings(&self) -> Result<Vec<MonitorSettings>, sqlx::Error> {
+        sqlx::query_as::<_, MonitorSettings>(
+            "SELECT monitor_id, enabled, fps, downscale_factor, updated_at FROM monitor_settings ORDER BY monitor_id",
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn get_monitor_settings_by_id(
+        &self,
+        monitor_id: i64,
+    ) -> Result<Option<MonitorSettings>, sqlx::Error> {
+        sqlx::query_as::<_, MonitorSettings>(
+            "SELECT monitor_id, enabled, fps, downscale_factor, updated_at FROM monitor_settings WHERE monitor_id = ?1",
+        )
+        .bind(monitor_id)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    pub async fn upsert_monitor_settings(
+        &self,
+        monitor_id: i64,
+        enabled: bool,
+        fps: Option<f64>,
+        downscale_factor: f64,
+    ) -> Result<MonitorSettings, sqlx::Error> {
+        sqlx::query_as::<_, MonitorSettings>(
+            r#"
+            INSERT INTO monitor_settings (monitor_id, enabled, fps, downscale_factor, updated_at)
+            VALUES (?1, ?2, ?3, ?4, CURRENT_TIMESTAMP)
+            ON CONFLICT(monitor_id) DO UPDATE SET
+                enabled = excluded.enabled,
+                fps = excluded.fps,
+                downscale_factor = excluded.downscale_factor,
+                updated_at = CURRENT_TIMESTAMP
+            RETURNING monitor_id, enabled, fps, downscale_factor, updated_at
+            "#,
+        )
+        .bind(monitor_id)
+        .bind(enabled)
+        .bind(fps)
+        .bind(downscale_factor)
+        .fetch_one(&self.pool)
+        .await
+    }
+}
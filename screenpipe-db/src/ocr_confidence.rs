@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+
+use crate::TextBounds;
+
+/// A single text block of an engine's `text_json` output with its confidence
+#[derive(Debug, Clone, PartialEq)]
+pub struct OcrBlock {
+    pub text: String,
+    /// Normalized to 0..1, `None` when the engine didn't report one
+    pub confidence: Option<f64>,
+    /// `None` when the engine didn't report block positions
+    pub bounds: Option<TextBounds>,
+}
+
+// the highest confidence `ocr_engine`, as the ocr_engine column records it,
+// reports. Tesseract reports 0..100, the other engines 0..1
+fn confidence_scale(ocr_engine: &str) -> f64 {
+    if ocr_engine == "Tesseract" {
+        100.0
+    } else {
+        1.0
+    }
+}
+
+/// Reads the confidence of a text_json block written by `ocr_engine`, as the
+/// ocr_engine column records it. Engines disagree on both the key (`conf` vs
+/// `confidence`) and the scale, so normalize here. A value off the engine's
+/// scale is no confidence.
+pub fn block_confidence(block: &HashMap<String, String>, ocr_engine: &str) -> Option<f64> {
+    let raw = block
+        .get("confidence")
+        .or_else(|| block.get("conf"))?
+        .trim()
+        .parse::<f64>()
+        .ok()?;
+    let confidence = raw / confidence_scale(ocr_engine);
+    (0.0..=1.0).contains(&confidence).then_some(confidence)
+}
+
+pub fn parse_ocr_blocks(text_json: &str, ocr_engine: &str) -> Vec<OcrBlock> {
+    serde_json::from_str::<Vec<HashMap<String, String>>>(text_json)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|block| {
+            let text = block.get("text")?.trim().to_string();
+            if text.is_empty() {
+                return None;
+            }
+            Some(OcrBlock {
+                confidence: block_confidence(&block, ocr_engine),
+                bounds: TextBounds::from_block(&block),
+                text,
+            })
+        })
+        .collect()
+}
+
+/// Mean confidence of the blocks that report one
+pub fn frame_confidence(blocks: &[OcrBlock]) -> Option<f64> {
+    let confidences: Vec<f64> = blocks.iter().filter_map(|b| b.confidence).collect();
+    if confidences.is_empty() {
+        None
+    } else {
+        Some(confidences.iter().sum::<f64>() / confidences.len() as f64)
+    }
+}
@@ -0,0 +1,151 @@
+use oasgen::OaSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{OcrBlock, TextBounds};
+
+// blocks whose vertical centers are closer than this share of the line height
+// sit on the same row
+const ROW_TOLERANCE: f32 = 0.5;
+// column gutters are at least this many line heights wide, word spacing is far less
+const MIN_COLUMN_GAP: f32 = 1.0;
+// rows further apart than this many line heights belong to different tables
+const MAX_ROW_GAP: f32 = 2.5;
+const MIN_TABLE_ROWS: usize = 3;
+
+/// A table read out of the OCR blocks of a frame, cells in reading order
+#[derive(OaSchema, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OcrTable {
+    pub rows: Vec<Vec<String>>,
+}
+
+struct Row<'a> {
+    center_y: f32,
+    height: f32,
+    /// sorted left to right
+    cells: Vec<(&'a str, TextBounds)>,
+}
+
+impl Row<'_> {
+    /// Whether some pair of neighbouring blocks is separated by a gutter
+    /// rather than a space.
+    fn has_gutter(&self, min_gap: f32) -> bool {
+        self.cells
+            .windows(2)
+            .any(|pair| pair[1].1.left - (pair[0].1.left + pair[0].1.width) >= min_gap)
+    }
+}
+
+/// Finds tables in the OCR blocks of a frame: runs of at least three
+/// consecutive rows split by gutters that line up into shared columns.
+/// Blocks without positions are ignored.
+pub fn extract_tables(blocks: &[OcrBlock]) -> Vec<OcrTable> {
+    let mut positioned: Vec<(&str, TextBounds)> = blocks
+        .iter()
+        .filter_map(|block| Some((block.text.as_str(), block.bounds?)))
+        .collect();
+    if positioned.len() < MIN_TABLE_ROWS * 2 {
+        return Vec::new();
+    }
+
+    let mut heights: Vec<f32> = positioned.iter().map(|(_, b)| b.height).collect();
+    heights.sort_by(f32::total_cmp);
+    let line_height = heights[heights.len() / 2];
+
+    positioned.sort_by(|a, b| center_y(&a.1).total_cmp(&center_y(&b.1)));
+    let mut rows: Vec<Row> = Vec::new();
+    for (text, bounds) in positioned {
+        match rows.last_mut() {
+            Some(row)
+                if (center_y(&bounds) - row.center_y).abs() <= ROW_TOLERANCE * line_height =>
+            {
+                row.cells.push((text, bounds));
+                row.height = row.height.max(bounds.height);
+            }
+            _ => rows.push(Row {
+                center_y: center_y(&bounds),
+                height: bounds.height,
+                cells: vec![(text, bounds)],
+            }),
+        }
+    }
+    for row in &mut rows {
+        row.cells.sort_by(|a, b| a.1.left.total_cmp(&b.1.left));
+    }
+
+    let min_gap = MIN_COLUMN_GAP * line_height;
+    let mut tables = Vec::new();
+    let mut run: Vec<&Row> = Vec::new();
+    for row in &rows {
+        let continues = run.last().is_some_and(|last| {
+            row.center_y - last.center_y <= MAX_ROW_GAP * line_height.max(last.height)
+        });
+        if !row.has_gutter(min_gap) || !continues {
+            tables.extend(table_from_rows(&run, min_gap));
+            run.clear();
+        }
+        if row.has_gutter(min_gap) {
+            run.push(row);
+        }
+    }
+    tables.extend(table_from_rows(&run, min_gap));
+    tables
+}
+
+/// Splits a run of rows into columns at the gutters shared by every row.
+fn table_from_rows(rows: &[&Row], min_gap: f32) -> Option<OcrTable> {
+    if rows.len() < MIN_TABLE_ROWS {
+        return None;
+    }
+
+    // merge the horizontal extents of all cells; the holes left are the gutters
+    let mut extents: Vec<(f32, f32)> = rows
+        .iter()
+        .flat_map(|row| row.cells.iter().map(|(_, b)| (b.left, b.left + b.width)))
+        .collect();
+    extents.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut columns: Vec<(f32, f32)> = Vec::new();
+    for (start, end) in extents {
+        match columns.last_mut() {
+            Some(column) if start - column.1 < min_gap => column.1 = column.1.max(end),
+            _ => columns.push((start, end)),
+        }
+    }
+    if columns.len() < 2 {
+        return None;
+    }
+
+    let table_rows: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            let mut cells = vec![Vec::new(); columns.len()];
+            for (text, bounds) in &row.cells {
+                let column = columns
+                    .iter()
+                    .position(|(_, end)| bounds.left < *end)
+                    .unwrap_or(columns.len() - 1);
+                cells[column].push(*text);
+            }
+            cells.into_iter().map(|words| words.join(" ")).collect()
+        })
+        .collect();
+
+    // a table has at least two columns actually filled on most rows
+    let filled_rows = table_rows
+        .iter()
+        .filter(|row| row.iter().filter(|cell| !cell.is_empty()).count() >= 2)
+        .count();
+    (filled_rows * 2 > table_rows.len()).then_some(OcrTable { rows: table_rows })
+}
+
+fn center_y(bounds: &TextBounds) -> f32 {
+    bounds.top + bounds.height / 2.0
+}
+
+/// JSON stored in `ocr_text.tables`, `None` when the blocks hold no table.
+pub(crate) fn tables_json(blocks: &[OcrBlock]) -> Option<String> {
+    let tables = extract_tables(blocks);
+    if tables.is_empty() {
+        return None;
+    }
+    serde_json::to_string(&tables).ok()
+}
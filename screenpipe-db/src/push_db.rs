@@ -0,0 +1,169 @@
+use chrono::Utc;
+
+use crate::{DatabaseManager, NewPushDestination, PushDestination, PushItem};
+
+const PUSH_DESTINATION_COLUMNS: &str = "id, name, kind, api_key, notion_database_id, tags, enabled, last_pushed_at, last_error, created_at";
+
+impl DatabaseManager {
+    pub async fn insert_push_destination(
+        &self,
+        destination: &NewPushDestination,
+    ) -> Result<PushDestination, sqlx::Error> {
+        let tags: Vec<&str> = destination
+            .tags
+            .iter()
+            .map(|tag| tag.trim())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        sqlx::query_as::<_, PushDestination>(&format!(
+            r#"
+            INSERT INTO push_destinations (name, kind, api_key, notion_database_id, tags, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            RETURNING {PUSH_DESTINATION_COLUMNS}
+            "#
+        ))
+        .bind(&destination.name)
+        .bind(&destination.kind)
+        .bind(&destination.api_key)
+        .bind(&destination.notion_database_id)
+        .bind(tags.join(","))
+        .bind(Utc::now())
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn list_push_destinations(&self) -> Result<Vec<PushDestination>, sqlx::Error> {
+        sqlx::query_as::<_, PushDestination>(&format!(
+            "SELECT {PUSH_DESTINATION_COLUMNS} FROM push_destinations ORDER BY id"
+        ))
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Returns whether the destination existed. What was pushed to it is
+    /// forgotten.
+    pub async fn delete_push_destination(&self, id: i64) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM pushed_items WHERE destination_id = ?1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        let deleted = sqlx::query("DELETE FROM push_destinations WHERE id = ?1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        tx.commit().await?;
+        Ok(deleted > 0)
+    }
+
+    /// Frames and audio chunks tagged with any of the destination's tags and
+    /// not pushed to it yet, oldest first.
+    pub async fn get_unpushed_items(
+        &self,
+        destination: &PushDestination,
+        limit: u32,
+    ) -> Result<Vec<PushItem>, sqlx::Error> {
+        let tags = serde_json::to_string(&destination.tag_list()).unwrap_or_default();
+        sqlx::query_as::<_, PushItem>(
+            r#"
+            SELECT * FROM (
+                SELECT
+                    'vision' as content_type,
+                    f.id as content_id,
+                    f.timestamp,
+                    vc.device_name,
+                    COALESCE(f.app_name, '') as app_name,
+                    COALESCE(f.window_name, '') as window_name,
+                    COALESCE((SELECT text FROM ocr_text WHERE frame_id = f.id LIMIT 1), '') as text,
+                    GROUP_CONCAT(t.name, ',') as tags
+                FROM vision_tags vt
+                JOIN tags t ON t.id = vt.tag_id
+                JOIN frames f ON f.id = vt.vision_id
+                JOIN video_chunks vc ON vc.id = f.video_chunk_id
+                WHERE t.name IN (SELECT value FROM json_each(?2))
+                    AND f.deleted_at IS NULL
+                    AND NOT EXISTS (
+                        SELECT 1 FROM pushed_items p
+                        WHERE p.destination_id = ?1 AND p.content_type = 'vision' AND p.content_id = f.id
+                    )
+                GROUP BY f.id
+                UNION ALL
+                SELECT
+                    'audio' as content_type,
+                    ac.id as content_id,
+                    (SELECT MIN(timestamp) FROM audio_transcriptions WHERE audio_chunk_id = ac.id) as timestamp,
+                    COALESCE((SELECT device FROM audio_transcriptions WHERE audio_chunk_id = ac.id LIMIT 1), '') as device_name,
+                    '' as app_name,
+                    '' as window_name,
+                    COALESCE((SELECT GROUP_CONCAT(transcription, ' ') FROM audio_transcriptions WHERE audio_chunk_id = ac.id), '') as text,
+                    GROUP_CONCAT(t.name, ',') as tags
+                FROM audio_tags atg
+                JOIN tags t ON t.id = atg.tag_id
+                JOIN audio_chunks ac ON ac.id = atg.audio_chunk_id
+                WHERE t.name IN (SELECT value FROM json_each(?2))
+                    AND ac.deleted_at IS NULL
+                    AND NOT EXISTS (
+                        SELECT 1 FROM pushed_items p
+                        WHERE p.destination_id = ?1 AND p.content_type = 'audio' AND p.content_id = ac.id
+                    )
+                GROUP BY ac.id
+            )
+            -- audio chunks without transcription have nothing to push
+            WHERE timestamp IS NOT NULL
+            ORDER BY timestamp ASC, content_id ASC
+            LIMIT ?3
+            "#,
+        )
+        .bind(destination.id)
+        .bind(tags)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn mark_items_pushed(
+        &self,
+        destination_id: i64,
+        items: &[PushItem],
+    ) -> Result<(), sqlx::Error> {
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+        for item in items {
+            sqlx::query(
+                r#"
+                INSERT INTO pushed_items (destination_id, content_type, content_id, pushed_at)
+                VALUES (?1, ?2, ?3, ?4)
+                ON CONFLICT DO NOTHING
+                "#,
+            )
+            .bind(destination_id)
+            .bind(&item.content_type)
+            .bind(item.content_id)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+        }
+        sqlx::query(
+            "UPDATE push_destinations SET last_pushed_at = ?2, last_error = NULL WHERE id = ?1",
+        )
+        .bind(destination_id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await
+    }
+
+    pub async fn set_push_error(
+        &self,
+        destination_id: i64,
+        error: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE push_destinations SET last_error = ?2 WHERE id = ?1")
+            .bind(destination_id)
+            .bind(error)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+use std::fmt;
+use std::future::Future;
+use std::ptr::NonNull;
+use std::time::Duration;
+
+use libsqlite3_sys::{sqlite3, sqlite3_interrupt};
+use sqlx::pool::PoolConnection;
+use sqlx::Sqlite;
+use tracing::warn;
+
+use crate::DatabaseManager;
+
+/// A read query interrupted because it ran longer than the query timeout of
+/// the `DatabaseManager`, see `with_query_timeout`. Returned inside
+/// `sqlx::Error::Io`, `QueryTimedOut::find` gets it back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueryTimedOut {
+    pub after: Duration,
+}
+
+impl QueryTimedOut {
+    /// The timeout behind `error`, if it is one.
+    pub fn find(error: &sqlx::Error) -> Option<QueryTimedOut> {
+        match error {
+            sqlx::Error::Io(e) => e
+                .get_ref()
+                .and_then(|e| e.downcast_ref::<QueryTimedOut>())
+                .copied(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for QueryTimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "query interrupted after {}s", self.after.as_secs_f64())
+    }
+}
+
+impl std::error::Error for QueryTimedOut {}
+
+impl From<QueryTimedOut> for sqlx::Error {
+    fn from(e: QueryTimedOut) -> Self {
+        sqlx::Error::Io(std::io::Error::new(std::io::ErrorKind::TimedOut, e))
+    }
+}
+
+/// The sqlite connection a guarded query runs on.
+#[derive(Clone, Copy)]
+struct InterruptHandle(NonNull<sqlite3>);
+
+// sqlite3_interrupt is the one call meant to be made from other threads
+unsafe impl Send for InterruptHandle {}
+unsafe impl Sync for InterruptHandle {}
+
+impl InterruptHandle {
+    fn interrupt(self) {
+        unsafe { sqlite3_interrupt(self.0.as_ptr()) }
+    }
+}
+
+// interrupts the query when dropped before being disarmed, which is how a
+// dropped http request stops the query it was waiting for
+struct InterruptOnDrop(Option<InterruptHandle>);
+
+impl Drop for InterruptOnDrop {
+    fn drop(&mut self) {
+        if let Some(handle) = self.0.take() {
+            handle.interrupt();
+        }
+    }
+}
+
+/// Runs queries of a read connection with the query timeout, and stops them
+/// as soon as the future awaiting them is dropped. Only valid while the
+/// connection it came with is held.
+#[derive(Clone, Copy)]
+pub(crate) struct QueryGuard {
+    handle: InterruptHandle,
+    timeout: Option<Duration>,
+}
+
+impl QueryGuard {
+    pub(crate) async fn run<T>(
+        self,
+        query: impl Future<Output = Result<T, sqlx::Error>>,
+    ) -> Result<T, sqlx::Error> {
+        let mut interrupt = InterruptOnDrop(Some(self.handle));
+        let result = match self.timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, query).await {
+                Ok(result) => result,
+                Err(_) => {
+                    warn!("read query interrupted after {:?}", timeout);
+                    // interrupt stays armed and stops what still runs
+                    return Err(QueryTimedOut { after: timeout }.into());
+                }
+            },
+            None => query.await,
+        };
+        interrupt.0 = None;
+        result
+    }
+}
+
+impl DatabaseManager {
+    /// Stops read queries running longer than `timeout`, with a
+    /// `QueryTimedOut` error. No timeout by default.
+    pub fn with_query_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.query_timeout = timeout;
+        self
+    }
+
+    /// A connection of the read pool and the guard its queries run through.
+    pub(crate) async fn read_connection(
+        &self,
+    ) -> Result<(PoolConnection<Sqlite>, QueryGuard), sqlx::Error> {
+        let mut conn = self.read_pool.acquire().await?;
+        let handle = InterruptHandle(conn.lock_handle().await?.as_raw_handle());
+        Ok((
+            conn,
+            QueryGuard {
+                handle,
+                timeout: self.query_timeout,
+            },
+        ))
+    }
+}
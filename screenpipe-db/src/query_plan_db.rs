@@ -0,0 +1,58 @@
+use sqlx::Row;
+use tracing::{debug, warn};
+
+use crate::db::{search_audio_query, search_ocr_query};
+use crate::DatabaseManager;
+
+/// Whether a line of `EXPLAIN QUERY PLAN` reads a whole table, rather than
+/// going through an index, a primary key or a full text index.
+fn is_full_scan(detail: &str) -> bool {
+    detail.starts_with("SCAN ") && !detail.contains(" USING ") && !detail.contains("VIRTUAL TABLE")
+}
+
+// highest ?N of the sql, every parameter is bound to NULL to plan it
+fn parameter_count(sql: &str) -> usize {
+    sql.split('?')
+        .skip(1)
+        .filter_map(|rest| {
+            let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+            digits.parse().ok()
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+impl DatabaseManager {
+    /// Plans each variant of the search queries and logs the ones that read
+    /// a whole table, e.g. because an index they rely on is missing. Returns
+    /// the full scans found, as `query: plan line`.
+    pub async fn check_search_query_plans(&self) -> Result<Vec<String>, sqlx::Error> {
+        let queries = [
+            ("ocr search", search_ocr_query(false, false)),
+            ("ocr search by frame", search_ocr_query(true, false)),
+            ("ocr search by text", search_ocr_query(false, true)),
+            ("ocr search by frame and text", search_ocr_query(true, true)),
+            ("audio search", search_audio_query(false)),
+            ("audio search by text", search_audio_query(true)),
+        ];
+
+        let mut full_scans = Vec::new();
+        for (name, sql) in queries {
+            let explain = format!("EXPLAIN QUERY PLAN {}", sql);
+            let mut query = sqlx::query(&explain);
+            for _ in 0..parameter_count(sql) {
+                query = query.bind(None::<String>);
+            }
+            let (mut conn, guard) = self.read_connection().await?;
+            for row in guard.run(query.fetch_all(&mut *conn)).await? {
+                let detail: String = row.try_get("detail")?;
+                debug!("query plan of {}: {}", name, detail);
+                if is_full_scan(&detail) {
+                    warn!("{} falls back to a full scan: {}", name, detail);
+                    full_scans.push(format!("{}: {}", name, detail));
+                }
+            }
+        }
+        Ok(full_scans)
+    }
+}
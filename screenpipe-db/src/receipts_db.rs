@@ -0,0 +1,83 @@
+use chrono::{DateTime, Utc};
+
+use crate::{DatabaseManager, Receipt, TagContentType};
+
+impl DatabaseManager {
+    /// Stores the fields extracted from a receipt-like frame and tags the frame
+    /// with `receipt`. Re-detecting the same frame overwrites the previous row.
+    pub async fn insert_receipt(
+        &self,
+        frame_id: i64,
+        vendor: Option<&str>,
+        receipt_date: Option<&str>,
+        total: Option<f64>,
+        currency: Option<&str>,
+    ) -> Result<i64, sqlx::Error> {
+        let id = sqlx::query_scalar::<_, i64>(
+            r#"
+            INSERT INTO receipts (frame_id, vendor, receipt_date, total, currency)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT(frame_id) DO UPDATE SET
+                vendor = excluded.vendor,
+                receipt_date = excluded.receipt_date,
+                total = excluded.total,
+                currency = excluded.currency
+            RETURNING id
+            "#,
+        )
+        .bind(frame_id)
+        .bind(vendor)
+        .bind(receipt_date)
+        .bind(total)
+        .bind(currency)
+        .fetch_one(&self.pool)
+        .await?;
+
+        self.add_tags(
+            frame_id,
+            TagContentType::Vision,
+            vec!["receipt".to_string()],
+        )
+        .await?;
+
+        Ok(id)
+    }
+
+    pub async fn list_receipts(
+        &self,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        vendor: Option<&str>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<Receipt>, sqlx::Error> {
+        sqlx::query_as::<_, Receipt>(
+            r#"
+            SELECT
+                receipts.id,
+                receipts.frame_id,
+                receipts.vendor,
+                receipts.receipt_date,
+                receipts.total,
+                receipts.currency,
+                frames.timestamp,
+                frames.app_name,
+                frames.window_name
+            FROM receipts
+            JOIN frames ON receipts.frame_id = frames.id
+            WHERE (?1 IS NULL OR frames.timestamp >= ?1)
+                AND (?2 IS NULL OR frames.timestamp <= ?2)
+                AND (?3 IS NULL OR receipts.vendor LIKE '%' || ?3 || '%' COLLATE NOCASE)
+            ORDER BY frames.timestamp DESC
+            LIMIT ?4 OFFSET ?5
+            "#,
+        )
+        .bind(start_time)
+        .bind(end_time)
+        .bind(vendor)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+}
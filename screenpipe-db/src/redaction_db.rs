@@ -0,0 +1,118 @@
+use chrono::Utc;
+
+use crate::{DatabaseManager, FrameRedaction, TextBounds};
+
+impl DatabaseManager {
+    /// Deletes everything read out of a frame (OCR text and the text read
+    /// before post-processing, blocks, embeddings, text spans, tables,
+    /// receipts, slides, whiteboard copies, thumbnails)
+    /// and records a tombstone. The text of a frame can't be split by region,
+    /// so it is deleted even when only a region of the image is redacted.
+    /// Blacking out the image itself is left to the caller.
+    pub async fn redact_frame(
+        &self,
+        frame_id: i64,
+        region: Option<TextBounds>,
+        reason: Option<&str>,
+    ) -> Result<FrameRedaction, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("SELECT 1 FROM frames WHERE id = ?1")
+            .bind(frame_id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        for query in [
+            "DELETE FROM ocr_text WHERE frame_id = ?1",
+            "DELETE FROM ocr_text_blocks WHERE frame_id = ?1",
+            "DELETE FROM ocr_text_embeddings WHERE frame_id = ?1",
+            "DELETE FROM chunked_text_entries WHERE frame_id = ?1",
+            "DELETE FROM text_provenance WHERE content_type = 'vision' AND content_id = ?1",
+            // the spans of the frame's window shown across it, a span whose
+            // first frame is gone counts as one
+            r#"DELETE FROM ocr_text_spans WHERE id IN (
+                SELECT s.id FROM ocr_text_spans s
+                JOIN frames redacted ON redacted.id = ?1
+                JOIN video_chunks vc ON vc.id = redacted.video_chunk_id
+                LEFT JOIN frames first ON first.id = s.first_frame_id
+                WHERE s.device_name = vc.device_name
+                    AND s.first_frame_id <= ?1 AND s.last_frame_id >= ?1
+                    AND (first.id IS NULL OR (
+                        first.app_name IS redacted.app_name
+                        AND first.window_name IS redacted.window_name
+                    ))
+            )"#,
+            "DELETE FROM receipts WHERE frame_id = ?1",
+            "DELETE FROM meeting_slides WHERE frame_id = ?1",
+            "DELETE FROM whiteboard_images WHERE frame_id = ?1",
+            // the windows of a capture share its image, and their thumbnails
+            r#"DELETE FROM frame_thumbnails WHERE frame_id IN (
+                SELECT f.id FROM frames f
+                JOIN frames redacted ON redacted.video_chunk_id = f.video_chunk_id
+                    AND redacted.offset_index = f.offset_index
+                WHERE redacted.id = ?1
+            )"#,
+        ] {
+            sqlx::query(query).bind(frame_id).execute(&mut *tx).await?;
+        }
+
+        let redaction = sqlx::query_as::<_, FrameRedaction>(
+            r#"
+            INSERT INTO frame_redactions (
+                frame_id, region_left, region_top, region_width, region_height, reason, redacted_at
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            RETURNING id, frame_id, region_left, region_top, region_width, region_height, reason, redacted_at
+            "#,
+        )
+        .bind(frame_id)
+        .bind(region.map(|r| r.left))
+        .bind(region.map(|r| r.top))
+        .bind(region.map(|r| r.width))
+        .bind(region.map(|r| r.height))
+        .bind(reason)
+        .bind(Utc::now())
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(redaction)
+    }
+
+    /// Whether the frame is in the last video chunk of its monitor, the one
+    /// that may still be written to and can't be re-encoded yet.
+    pub async fn is_frame_recording(&self, frame_id: i64) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            SELECT NOT EXISTS (
+                SELECT 1 FROM video_chunks newer
+                WHERE newer.device_name = v.device_name AND newer.id > v.id
+            )
+            FROM frames
+            JOIN video_chunks v ON v.id = frames.video_chunk_id
+            WHERE frames.id = ?1
+            "#,
+        )
+        .bind(frame_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map(|recording| recording.unwrap_or(false))
+    }
+
+    pub async fn get_frame_redactions(
+        &self,
+        frame_id: i64,
+    ) -> Result<Vec<FrameRedaction>, sqlx::Error> {
+        sqlx::query_as::<_, FrameRedaction>(
+            r#"
+            SELECT id, frame_id, region_left, region_top, region_width, region_height, reason, redacted_at
+            FROM frame_redactions
+            WHERE frame_id = ?1
+            ORDER BY redacted_at
+            "#,
+        )
+        .bind(frame_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+}
@@ -0,0 +1,561 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{DatabaseManager, RetentionRuleStats};
+
+/// What a retention rule applies to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RetentionScope {
+    /// everything carrying the tag
+    Tag(String),
+    /// frames, ui text and clipboard entries of the app, its name compared
+    /// case-insensitively
+    App(String),
+    Vision,
+    Audio,
+    Ui,
+    Clipboard,
+}
+
+impl RetentionScope {
+    fn kind(&self) -> &'static str {
+        match self {
+            RetentionScope::Tag(_) => "tag",
+            RetentionScope::App(_) => "app",
+            RetentionScope::Vision => "vision",
+            RetentionScope::Audio => "audio",
+            RetentionScope::Ui => "ui",
+            RetentionScope::Clipboard => "clipboard",
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            RetentionScope::Tag(name) | RetentionScope::App(name) => name,
+            _ => "",
+        }
+    }
+}
+
+impl fmt::Display for RetentionScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RetentionScope::Tag(_) | RetentionScope::App(_) => {
+                write!(f, "{}:{}", self.kind(), self.name())
+            }
+            _ => f.write_str(self.kind()),
+        }
+    }
+}
+
+/// How long some of the data is kept, written `tag:receipts=forever`,
+/// `app:Chase=0d`, `audio=30d`, `vision=365d`, `ui=90d` or `clipboard=7d`.
+/// Overrides the default retention for what it applies to: tag rules come
+/// first, then app rules, then content type rules.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetentionRule {
+    pub scope: RetentionScope,
+    /// `None` keeps the data forever
+    pub keep_days: Option<u32>,
+}
+
+impl FromStr for RetentionRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid retention rule {:?}, expected e.g. \"tag:receipts=forever\", \"app:Chase=0d\" or \"audio=30d\"",
+                s
+            )
+        };
+        let (key, keep) = s.split_once('=').ok_or_else(invalid)?;
+        let key = key.trim();
+        let scope = if let Some(tag) = key.strip_prefix("tag:") {
+            RetentionScope::Tag(tag.trim().to_string())
+        } else if let Some(app) = key.strip_prefix("app:") {
+            RetentionScope::App(app.trim().to_string())
+        } else {
+            match key {
+                "vision" => RetentionScope::Vision,
+                "audio" => RetentionScope::Audio,
+                "ui" => RetentionScope::Ui,
+                "clipboard" => RetentionScope::Clipboard,
+                _ => return Err(invalid()),
+            }
+        };
+        if matches!(&scope, RetentionScope::Tag(name) | RetentionScope::App(name) if name.is_empty())
+        {
+            return Err(invalid());
+        }
+        let keep = keep.trim();
+        let keep_days = if keep.eq_ignore_ascii_case("forever") {
+            None
+        } else {
+            Some(
+                keep.strip_suffix('d')
+                    .unwrap_or(keep)
+                    .parse::<u32>()
+                    .map_err(|_| invalid())?,
+            )
+        };
+
+        Ok(RetentionRule { scope, keep_days })
+    }
+}
+
+/// Tag rules were the only retention rules before app and content type
+/// ones, `tag:receipts=forever` still parses the same.
+pub type TagRetention = RetentionRule;
+
+/// What a retention pass removed from the database. The files are left to the
+/// caller.
+#[derive(Debug, Default, Clone)]
+pub struct PrunedData {
+    pub frames: u64,
+    pub audio_chunks: u64,
+    /// rows of ui text, ui traversals and clipboard entries
+    pub ui_rows: u64,
+    /// video chunks left without any frame
+    pub video_files: Vec<String>,
+    pub audio_files: Vec<String>,
+}
+
+// rules keeping data forever get a retention no timestamp is older than
+const FOREVER_DAYS: u32 = 1_000_000_000;
+
+// ?1 is the rules as json [[kind, name, days], ...], ?2 now. When several tag
+// rules or app rules apply to an item, the longest one does
+const RULES_CTE: &str = r#"
+    WITH rules(kind, name, keep_days) AS (
+        SELECT json_extract(value, '$[0]'), json_extract(value, '$[1]'), json_extract(value, '$[2]')
+        FROM json_each(?1)
+    ),
+    app_rules AS (
+        SELECT lower(name) AS app, name, MAX(keep_days) AS keep_days
+        FROM rules WHERE kind = 'app' GROUP BY lower(name)
+    )
+"#;
+
+fn rules_json(rules: &[RetentionRule]) -> String {
+    serde_json::to_string(
+        &rules
+            .iter()
+            .map(|rule| {
+                (
+                    rule.scope.kind(),
+                    rule.scope.name(),
+                    rule.keep_days.unwrap_or(FOREVER_DAYS),
+                )
+            })
+            .collect::<Vec<_>>(),
+    )
+    .unwrap_or_else(|_| "[]".to_string())
+}
+
+/// Items a retention pass found expired, by the rule expiring them.
+#[derive(Debug, Default)]
+pub(crate) struct ExpiredItems {
+    /// (frame id, video chunk id, rule)
+    pub frames: Vec<(i64, i64, String)>,
+    /// (audio chunk id, rule)
+    pub audio_chunks: Vec<(i64, String)>,
+}
+
+/// Counts of a retention pass, to add to `retention_rule_stats`.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct RuleCounts {
+    pub trashed_frames: u64,
+    pub trashed_audio_chunks: u64,
+    pub purged_frames: u64,
+    pub purged_audio_chunks: u64,
+    pub trashed_ui_rows: u64,
+    pub deleted_ui_rows: u64,
+}
+
+impl DatabaseManager {
+    /// Deletes up to `limit` frames and `limit` audio chunks that outlived
+    /// their retention, for good. See `RetentionRule` for which rule applies
+    /// to an item, `default_days` applies to the items no rule does.
+    pub async fn prune_expired(
+        &self,
+        now: DateTime<Utc>,
+        default_days: Option<u32>,
+        rules: &[RetentionRule],
+        limit: u32,
+    ) -> Result<PrunedData, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let expired = Self::find_expired(&mut tx, now, default_days, rules, limit).await?;
+
+        let mut counts: HashMap<&str, RuleCounts> = HashMap::new();
+        for (_, _, rule) in &expired.frames {
+            counts.entry(rule).or_default().purged_frames += 1;
+        }
+        for (_, rule) in &expired.audio_chunks {
+            counts.entry(rule).or_default().purged_audio_chunks += 1;
+        }
+        for (rule, counts) in counts {
+            Self::add_retention_stats(&mut tx, rule, counts, now).await?;
+        }
+
+        let frames: Vec<(i64, i64)> = expired
+            .frames
+            .iter()
+            .map(|(id, chunk_id, _)| (*id, *chunk_id))
+            .collect();
+        let audio_chunk_ids: Vec<i64> = expired.audio_chunks.iter().map(|(id, _)| *id).collect();
+        let mut pruned = PrunedData::default();
+        Self::delete_frames(&mut tx, &frames, &mut pruned).await?;
+        Self::delete_audio_chunks(&mut tx, &audio_chunk_ids, &mut pruned).await?;
+
+        tx.commit().await?;
+        Ok(pruned)
+    }
+
+    /// Moves up to `limit` rows of ui text, `limit` ui traversals and `limit`
+    /// clipboard entries past the retention of their app rule, or of the ui
+    /// rule or clipboard rule, to the trash, where they are purged with the
+    /// frames and audio. `--retention-days` doesn't apply to them.
+    pub async fn trash_expired_ui(
+        &self,
+        now: DateTime<Utc>,
+        rules: &[RetentionRule],
+        limit: u32,
+    ) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let mut counts: HashMap<String, RuleCounts> = HashMap::new();
+        let mut trashed = 0;
+        for (table, app, kind) in [
+            ("ui_monitoring", "app", "ui"),
+            ("ui_traversals", "app", "ui"),
+            ("clipboard", "app_name", "clipboard"),
+        ] {
+            let expired: Vec<(i64, String)> = sqlx::query_as(&format!(
+                r#"
+                {RULES_CTE}
+                SELECT id, rule FROM (
+                    SELECT
+                        {table}.id,
+                        {table}.timestamp,
+                        COALESCE(
+                            app_rules.keep_days,
+                            (SELECT MAX(keep_days) FROM rules WHERE kind = '{kind}')
+                        ) AS keep_days,
+                        CASE
+                            WHEN app_rules.keep_days IS NOT NULL THEN 'app:' || app_rules.name
+                            ELSE '{kind}'
+                        END AS rule
+                    FROM {table}
+                    LEFT JOIN app_rules ON app_rules.app = lower({table}.{app})
+                    WHERE {table}.deleted_at IS NULL AND {table}.restored_at IS NULL
+                )
+                WHERE julianday(timestamp) < julianday(?2) - keep_days
+                ORDER BY timestamp
+                LIMIT ?3
+                "#
+            ))
+            .bind(rules_json(rules))
+            .bind(now)
+            .bind(limit)
+            .fetch_all(&mut *tx)
+            .await?;
+
+            for (id, rule) in expired {
+                sqlx::query(&format!(
+                    "UPDATE {table} SET deleted_at = ?1, trashed_by = ?3 WHERE id = ?2"
+                ))
+                .bind(now)
+                .bind(id)
+                .bind(&rule)
+                .execute(&mut *tx)
+                .await?;
+                counts.entry(rule).or_default().trashed_ui_rows += 1;
+                trashed += 1;
+            }
+        }
+        for (rule, counts) in counts {
+            Self::add_retention_stats(&mut tx, &rule, counts, now).await?;
+        }
+
+        tx.commit().await?;
+        Ok(trashed)
+    }
+
+    /// Frames (with their video chunk) and audio chunks past their retention
+    /// that are neither in the trash nor restored from it, with the name of
+    /// the rule that expired them, "default" for `default_days`.
+    pub(crate) async fn find_expired(
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        now: DateTime<Utc>,
+        default_days: Option<u32>,
+        rules: &[RetentionRule],
+        limit: u32,
+    ) -> Result<ExpiredItems, sqlx::Error> {
+        let rules = rules_json(rules);
+
+        let frames: Vec<(i64, i64, String)> = sqlx::query_as(&format!(
+            r#"
+            {RULES_CTE}
+            SELECT id, video_chunk_id, rule FROM (
+                SELECT
+                    frames.id,
+                    frames.video_chunk_id,
+                    frames.timestamp,
+                    CASE
+                        WHEN tagged.vision_id IS NOT NULL THEN tagged.keep_days
+                        WHEN app_rules.keep_days IS NOT NULL THEN app_rules.keep_days
+                        ELSE COALESCE((SELECT MAX(keep_days) FROM rules WHERE kind = 'vision'), ?3)
+                    END AS keep_days,
+                    CASE
+                        WHEN tagged.vision_id IS NOT NULL THEN 'tag:' || tagged.name
+                        WHEN app_rules.keep_days IS NOT NULL THEN 'app:' || app_rules.name
+                        WHEN EXISTS (SELECT 1 FROM rules WHERE kind = 'vision') THEN 'vision'
+                        ELSE 'default'
+                    END AS rule
+                FROM frames
+                LEFT JOIN (
+                    SELECT vision_tags.vision_id, rules.name, MAX(rules.keep_days) AS keep_days
+                    FROM vision_tags
+                    JOIN tags ON tags.id = vision_tags.tag_id
+                    JOIN rules ON rules.kind = 'tag' AND rules.name = tags.name
+                    GROUP BY vision_tags.vision_id
+                ) tagged ON tagged.vision_id = frames.id
+                LEFT JOIN app_rules ON app_rules.app = lower(frames.app_name)
+                WHERE frames.deleted_at IS NULL AND frames.restored_at IS NULL
+            )
+            WHERE julianday(timestamp) < julianday(?2) - keep_days
+            ORDER BY timestamp
+            LIMIT ?4
+            "#
+        ))
+        .bind(&rules)
+        .bind(now)
+        .bind(default_days)
+        .bind(limit)
+        .fetch_all(&mut **tx)
+        .await?;
+
+        let audio_chunks: Vec<(i64, String)> = sqlx::query_as(&format!(
+            r#"
+            {RULES_CTE}
+            SELECT id, rule FROM (
+                SELECT
+                    audio_chunks.id,
+                    audio_chunks.timestamp,
+                    CASE
+                        WHEN tagged.audio_chunk_id IS NOT NULL THEN tagged.keep_days
+                        ELSE COALESCE((SELECT MAX(keep_days) FROM rules WHERE kind = 'audio'), ?3)
+                    END AS keep_days,
+                    CASE
+                        WHEN tagged.audio_chunk_id IS NOT NULL THEN 'tag:' || tagged.name
+                        WHEN EXISTS (SELECT 1 FROM rules WHERE kind = 'audio') THEN 'audio'
+                        ELSE 'default'
+                    END AS rule
+                FROM audio_chunks
+                LEFT JOIN (
+                    SELECT audio_tags.audio_chunk_id, rules.name, MAX(rules.keep_days) AS keep_days
+                    FROM audio_tags
+                    JOIN tags ON tags.id = audio_tags.tag_id
+                    JOIN rules ON rules.kind = 'tag' AND rules.name = tags.name
+                    GROUP BY audio_tags.audio_chunk_id
+                ) tagged ON tagged.audio_chunk_id = audio_chunks.id
+                WHERE audio_chunks.timestamp IS NOT NULL
+                    AND audio_chunks.deleted_at IS NULL
+                    AND audio_chunks.restored_at IS NULL
+            )
+            WHERE julianday(timestamp) < julianday(?2) - keep_days
+            ORDER BY timestamp
+            LIMIT ?4
+            "#
+        ))
+        .bind(&rules)
+        .bind(now)
+        .bind(default_days)
+        .bind(limit)
+        .fetch_all(&mut **tx)
+        .await?;
+
+        Ok(ExpiredItems {
+            frames,
+            audio_chunks,
+        })
+    }
+
+    /// Records the rules in use and how long they keep data, so their stats
+    /// list them before they apply to anything. `default_days` is the
+    /// "default" rule.
+    pub async fn register_retention_rules(
+        &self,
+        default_days: Option<u32>,
+        rules: &[RetentionRule],
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let default = default_days.map(|days| ("default".to_string(), Some(days)));
+        for (rule, keep_days) in rules
+            .iter()
+            .map(|rule| (rule.scope.to_string(), rule.keep_days))
+            .chain(default)
+        {
+            sqlx::query(
+                r#"
+                INSERT INTO retention_rule_stats (rule, keep_days) VALUES (?1, ?2)
+                ON CONFLICT(rule) DO UPDATE SET keep_days = excluded.keep_days
+                "#,
+            )
+            .bind(rule)
+            .bind(keep_days)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await
+    }
+
+    pub async fn list_retention_rule_stats(&self) -> Result<Vec<RetentionRuleStats>, sqlx::Error> {
+        sqlx::query_as::<_, RetentionRuleStats>(
+            r#"
+            SELECT
+                rule, keep_days, trashed_frames, trashed_audio_chunks, purged_frames,
+                purged_audio_chunks, trashed_ui_rows, deleted_ui_rows, last_applied_at
+            FROM retention_rule_stats
+            ORDER BY rule
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub(crate) async fn add_retention_stats(
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        rule: &str,
+        counts: RuleCounts,
+        now: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO retention_rule_stats (
+                rule, trashed_frames, trashed_audio_chunks, purged_frames,
+                purged_audio_chunks, deleted_ui_rows, last_applied_at, trashed_ui_rows
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            ON CONFLICT(rule) DO UPDATE SET
+                trashed_frames = trashed_frames + excluded.trashed_frames,
+                trashed_audio_chunks = trashed_audio_chunks + excluded.trashed_audio_chunks,
+                purged_frames = purged_frames + excluded.purged_frames,
+                purged_audio_chunks = purged_audio_chunks + excluded.purged_audio_chunks,
+                deleted_ui_rows = deleted_ui_rows + excluded.deleted_ui_rows,
+                trashed_ui_rows = trashed_ui_rows + excluded.trashed_ui_rows,
+                last_applied_at = excluded.last_applied_at
+            "#,
+        )
+        .bind(rule)
+        .bind(counts.trashed_frames as i64)
+        .bind(counts.trashed_audio_chunks as i64)
+        .bind(counts.purged_frames as i64)
+        .bind(counts.purged_audio_chunks as i64)
+        .bind(counts.deleted_ui_rows as i64)
+        .bind(now)
+        .bind(counts.trashed_ui_rows as i64)
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    /// Deletes frames with everything read from them, and the video chunks
+    /// and browser contexts they leave unused.
+    pub(crate) async fn delete_frames(
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        frames: &[(i64, i64)],
+        pruned: &mut PrunedData,
+    ) -> Result<(), sqlx::Error> {
+        let frame_ids =
+            serde_json::to_string(&frames.iter().map(|(id, _)| *id).collect::<Vec<_>>())
+                .unwrap_or_else(|_| "[]".to_string());
+        let browser_context_ids: Vec<i64> = sqlx::query_scalar(
+            r#"
+            SELECT DISTINCT browser_context_id FROM frames
+            WHERE id IN (SELECT value FROM json_each(?1)) AND browser_context_id IS NOT NULL
+            "#,
+        )
+        .bind(&frame_ids)
+        .fetch_all(&mut **tx)
+        .await?;
+        for (frame_id, _) in frames {
+            for query in [
+                "DELETE FROM ocr_text WHERE frame_id = ?1",
+                "DELETE FROM chunked_text_entries WHERE frame_id = ?1",
+                "DELETE FROM frames WHERE id = ?1",
+            ] {
+                sqlx::query(query).bind(frame_id).execute(&mut **tx).await?;
+            }
+        }
+        pruned.frames += frames.len() as u64;
+
+        // only the chunks we just emptied, the one being recorded may not have
+        // frames yet, and never the last chunk of a device, it may still be
+        // written to
+        let mut video_chunk_ids: Vec<i64> = frames.iter().map(|(_, chunk_id)| *chunk_id).collect();
+        video_chunk_ids.sort_unstable();
+        video_chunk_ids.dedup();
+        for video_chunk_id in video_chunk_ids {
+            let file_path: Option<String> = sqlx::query_scalar(
+                r#"
+                DELETE FROM video_chunks
+                WHERE id = ?1
+                    AND NOT EXISTS (SELECT 1 FROM frames WHERE frames.video_chunk_id = ?1)
+                    AND EXISTS (
+                        SELECT 1 FROM video_chunks newer
+                        WHERE newer.device_name = video_chunks.device_name
+                            AND newer.id > video_chunks.id
+                    )
+                RETURNING file_path
+                "#,
+            )
+            .bind(video_chunk_id)
+            .fetch_optional(&mut **tx)
+            .await?;
+            pruned.video_files.extend(file_path);
+        }
+        sqlx::query(
+            r#"
+            DELETE FROM browser_contexts
+            WHERE id IN (SELECT value FROM json_each(?1))
+                AND NOT EXISTS (
+                    SELECT 1 FROM frames WHERE frames.browser_context_id = browser_contexts.id
+                )
+            "#,
+        )
+        .bind(serde_json::to_string(&browser_context_ids).unwrap_or_else(|_| "[]".to_string()))
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    pub(crate) async fn delete_audio_chunks(
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        audio_chunk_ids: &[i64],
+        pruned: &mut PrunedData,
+    ) -> Result<(), sqlx::Error> {
+        for audio_chunk_id in audio_chunk_ids {
+            for query in [
+                "DELETE FROM audio_transcriptions WHERE audio_chunk_id = ?1",
+                "DELETE FROM chunked_text_entries WHERE audio_chunk_id = ?1",
+            ] {
+                sqlx::query(query)
+                    .bind(audio_chunk_id)
+                    .execute(&mut **tx)
+                    .await?;
+            }
+            let file_path: String =
+                sqlx::query_scalar("DELETE FROM audio_chunks WHERE id = ?1 RETURNING file_path")
+                    .bind(audio_chunk_id)
+                    .fetch_one(&mut **tx)
+                    .await?;
+            pruned.audio_files.push(file_path);
+        }
+        pruned.audio_chunks += audio_chunk_ids.len() as u64;
+        Ok(())
+    }
+}
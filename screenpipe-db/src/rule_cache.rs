@@ -0,0 +1,43 @@
+use std::sync::{Arc, Mutex};
+
+/// Rules evaluated on every insert, read from the database and compiled once
+/// then kept until they change.
+pub(crate) struct RuleCache<T> {
+    // bumped on every change, so rules read before it aren't kept
+    state: Mutex<(u64, Option<Arc<Vec<T>>>)>,
+}
+
+impl<T> Default for RuleCache<T> {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new((0, None)),
+        }
+    }
+}
+
+impl<T> RuleCache<T> {
+    /// The cached rules, or the generation to pass to `set` once loaded.
+    pub(crate) fn get(&self) -> Result<Arc<Vec<T>>, u64> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        match &state.1 {
+            Some(rules) => Ok(Arc::clone(rules)),
+            None => Err(state.0),
+        }
+    }
+
+    /// Keeps the rules loaded at `generation`, unless they changed since.
+    pub(crate) fn set(&self, generation: u64, rules: Vec<T>) -> Arc<Vec<T>> {
+        let rules = Arc::new(rules);
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.0 == generation {
+            state.1 = Some(Arc::clone(&rules));
+        }
+        rules
+    }
+
+    pub(crate) fn invalidate(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.0 += 1;
+        state.1 = None;
+    }
+}
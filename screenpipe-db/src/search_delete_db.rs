@@ -0,0 +1,291 @@
+use crate::{ContentType, DatabaseManager, PrunedData, SearchDeleteFilter, SearchDeletion};
+
+fn ids_json(ids: &[i64]) -> String {
+    serde_json::to_string(ids).unwrap_or_else(|_| "[]".to_string())
+}
+
+pub(crate) fn non_empty(value: &Option<String>) -> Option<&str> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+// the words of a full text query, for text that has no index to match it on
+fn query_terms(q: &str) -> Vec<String> {
+    q.split_whitespace()
+        .filter(|word| !matches!(*word, "AND" | "OR" | "NOT"))
+        .map(|word| word.trim_matches(|c| matches!(c, '"' | '*' | '(' | ')')))
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+impl DatabaseManager {
+    /// Deletes for good every frame, audio chunk, ui row, ui traversal and
+    /// clipboard entry the filter matches, trashed or not, with everything
+    /// read out of them: text, full text index entries, embeddings and tags.
+    /// A frame matches when its text does, an audio chunk when one of its
+    /// transcriptions does, a ui traversal when its text holds every word of
+    /// the query. With `dry_run` nothing is deleted and the counts are what would
+    /// be.
+    ///
+    /// Video chunks still holding other frames are kept, the deleted frames
+    /// stay visible in them until redacted, and so is the chunk a monitor is
+    /// still recording.
+    pub async fn delete_search_results(
+        &self,
+        filter: &SearchDeleteFilter,
+        dry_run: bool,
+    ) -> Result<SearchDeletion, sqlx::Error> {
+        let q = non_empty(&filter.q);
+        let app_name = non_empty(&filter.app_name);
+        let window_name = non_empty(&filter.window_name);
+        let (ocr, audio, ui, clipboard) = match filter.content_type {
+            ContentType::All => (true, true, true, true),
+            ContentType::OCR => (true, false, false, false),
+            ContentType::Audio => (false, true, false, false),
+            ContentType::UI => (false, false, true, false),
+            ContentType::AudioAndUi => (false, true, true, false),
+            ContentType::OcrAndUi => (true, false, true, false),
+            ContentType::AudioAndOcr => (true, true, false, false),
+            ContentType::Clipboard => (false, false, false, true),
+        };
+        let audio = audio && app_name.is_none() && window_name.is_none();
+
+        let mut tx = self.pool.begin().await?;
+        let mut deletion = SearchDeletion {
+            dry_run,
+            ..Default::default()
+        };
+        let mut pruned = PrunedData::default();
+
+        if ocr {
+            let frames: Vec<(i64, i64)> = sqlx::query_as(
+                r#"
+                SELECT frames.id, frames.video_chunk_id
+                FROM frames
+                WHERE (?1 IS NULL OR frames.id IN (
+                        SELECT frame_id FROM ocr_text_fts WHERE ocr_text_fts MATCH ?1
+                    ))
+                    AND (?2 IS NULL OR frames.timestamp >= ?2)
+                    AND (?3 IS NULL OR frames.timestamp <= ?3)
+                    AND (?4 IS NULL OR frames.app_name = ?4)
+                    AND (?5 IS NULL OR frames.window_name = ?5)
+                ORDER BY frames.id
+                "#,
+            )
+            .bind(q)
+            .bind(filter.start_time)
+            .bind(filter.end_time)
+            .bind(app_name)
+            .bind(window_name)
+            .fetch_all(&mut *tx)
+            .await?;
+            Self::delete_matched_frames(&mut tx, &frames, &mut deletion, &mut pruned).await?;
+        }
+
+        if audio {
+            let audio_chunk_ids: Vec<i64> = sqlx::query_scalar(
+                r#"
+                SELECT audio_chunks.id
+                FROM audio_chunks
+                WHERE (?1 IS NULL OR audio_chunks.id IN (
+                        SELECT audio_chunk_id FROM audio_transcriptions_fts
+                        WHERE audio_transcriptions_fts MATCH ?1
+                    ))
+                    AND (?2 IS NULL OR audio_chunks.timestamp >= ?2)
+                    AND (?3 IS NULL OR audio_chunks.timestamp <= ?3)
+                ORDER BY audio_chunks.id
+                "#,
+            )
+            .bind(q)
+            .bind(filter.start_time)
+            .bind(filter.end_time)
+            .fetch_all(&mut *tx)
+            .await?;
+            Self::delete_matched_audio_chunks(
+                &mut tx,
+                &audio_chunk_ids,
+                &mut deletion,
+                &mut pruned,
+            )
+            .await?;
+        }
+
+        if ui {
+            let ui_ids: Vec<i64> = sqlx::query_scalar(
+                r#"
+                SELECT ui_monitoring.id
+                FROM ui_monitoring
+                WHERE (?1 IS NULL OR ui_monitoring.id IN (
+                        SELECT rowid FROM ui_monitoring_fts WHERE ui_monitoring_fts MATCH ?1
+                    ))
+                    AND (?2 IS NULL OR ui_monitoring.timestamp >= ?2)
+                    AND (?3 IS NULL OR ui_monitoring.timestamp <= ?3)
+                    AND (?4 IS NULL OR ui_monitoring.app = ?4)
+                    AND (?5 IS NULL OR ui_monitoring.window = ?5)
+                "#,
+            )
+            .bind(q)
+            .bind(filter.start_time)
+            .bind(filter.end_time)
+            .bind(app_name)
+            .bind(window_name)
+            .fetch_all(&mut *tx)
+            .await?;
+            Self::delete_matched_ui_rows(&mut tx, &ui_ids, &mut deletion).await?;
+
+            let traversal_ids = Self::matching_ui_traversals(
+                &mut tx,
+                &q.map(query_terms).unwrap_or_default(),
+                filter.start_time,
+                filter.end_time,
+                app_name,
+                window_name,
+            )
+            .await?;
+            deletion.ui_traversals += Self::delete_ui_traversals(&mut tx, &traversal_ids).await?;
+        }
+
+        if clipboard {
+            deletion.clipboard_entries = sqlx::query(
+                r#"
+                DELETE FROM clipboard
+                WHERE (?1 IS NULL OR clipboard.id IN (
+                        SELECT rowid FROM clipboard_fts WHERE clipboard_fts MATCH ?1
+                    ))
+                    AND (?2 IS NULL OR clipboard.timestamp >= ?2)
+                    AND (?3 IS NULL OR clipboard.timestamp <= ?3)
+                    AND (?4 IS NULL OR clipboard.app_name = ?4)
+                    AND (?5 IS NULL OR clipboard.window_name = ?5)
+                "#,
+            )
+            .bind(q)
+            .bind(filter.start_time)
+            .bind(filter.end_time)
+            .bind(app_name)
+            .bind(window_name)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+
+        deletion.frames = pruned.frames;
+        deletion.audio_chunks = pruned.audio_chunks;
+        deletion.video_files = pruned.video_files;
+        deletion.audio_files = pruned.audio_files;
+        if dry_run {
+            tx.rollback().await?;
+        } else {
+            tx.commit().await?;
+        }
+        Ok(deletion)
+    }
+
+    /// Deletes frames with their text, embeddings, tags, text spans and
+    /// whiteboard photos, counting them in `deletion`.
+    pub(crate) async fn delete_matched_frames(
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        frames: &[(i64, i64)],
+        deletion: &mut SearchDeletion,
+        pruned: &mut PrunedData,
+    ) -> Result<(), sqlx::Error> {
+        let ids = ids_json(&frames.iter().map(|(id, _)| *id).collect::<Vec<_>>());
+
+        let (ocr_rows, embeddings, tags): (i64, i64, i64) = sqlx::query_as(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM ocr_text WHERE frame_id IN (SELECT value FROM json_each(?1))),
+                (SELECT COUNT(*) FROM ocr_text_embeddings WHERE frame_id IN (SELECT value FROM json_each(?1))),
+                (SELECT COUNT(*) FROM vision_tags WHERE vision_id IN (SELECT value FROM json_each(?1)))
+            "#,
+        )
+        .bind(&ids)
+        .fetch_one(&mut **tx)
+        .await?;
+        deletion.ocr_rows += ocr_rows as u64;
+        deletion.embeddings += embeddings as u64;
+        deletion.tags += tags as u64;
+
+        let photos: Vec<(String, String)> = sqlx::query_as(
+            r#"
+            SELECT original_path, enhanced_path FROM whiteboard_images
+            WHERE frame_id IN (SELECT value FROM json_each(?1))
+            "#,
+        )
+        .bind(&ids)
+        .fetch_all(&mut **tx)
+        .await?;
+        deletion.image_files.extend(
+            photos
+                .into_iter()
+                .flat_map(|(original, enhanced)| [original, enhanced]),
+        );
+
+        // what isn't deleted along with the frames
+        for query in [
+            "DELETE FROM ocr_text_blocks WHERE frame_id IN (SELECT value FROM json_each(?1))",
+            "DELETE FROM ocr_text_embeddings WHERE frame_id IN (SELECT value FROM json_each(?1))",
+            "DELETE FROM vision_tags WHERE vision_id IN (SELECT value FROM json_each(?1))",
+            r#"
+            DELETE FROM ocr_text_spans
+            WHERE first_frame_id IN (SELECT value FROM json_each(?1))
+                OR last_frame_id IN (SELECT value FROM json_each(?1))
+            "#,
+        ] {
+            sqlx::query(query).bind(&ids).execute(&mut **tx).await?;
+        }
+        Self::delete_frames(tx, frames, pruned).await?;
+        Ok(())
+    }
+
+    /// Deletes audio chunks with their transcriptions and tags, counting
+    /// them in `deletion`.
+    pub(crate) async fn delete_matched_audio_chunks(
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        audio_chunk_ids: &[i64],
+        deletion: &mut SearchDeletion,
+        pruned: &mut PrunedData,
+    ) -> Result<(), sqlx::Error> {
+        let ids = ids_json(audio_chunk_ids);
+
+        let (transcriptions, tags): (i64, i64) = sqlx::query_as(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM audio_transcriptions WHERE audio_chunk_id IN (SELECT value FROM json_each(?1))),
+                (SELECT COUNT(*) FROM audio_tags WHERE audio_chunk_id IN (SELECT value FROM json_each(?1)))
+            "#,
+        )
+        .bind(&ids)
+        .fetch_one(&mut **tx)
+        .await?;
+        deletion.audio_transcriptions += transcriptions as u64;
+        deletion.tags += tags as u64;
+
+        sqlx::query(
+            "DELETE FROM audio_tags WHERE audio_chunk_id IN (SELECT value FROM json_each(?1))",
+        )
+        .bind(&ids)
+        .execute(&mut **tx)
+        .await?;
+        Self::delete_audio_chunks(tx, audio_chunk_ids, pruned).await?;
+        Ok(())
+    }
+
+    pub(crate) async fn delete_matched_ui_rows(
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        ui_ids: &[i64],
+        deletion: &mut SearchDeletion,
+    ) -> Result<(), sqlx::Error> {
+        let ids = ids_json(ui_ids);
+        for query in [
+            "DELETE FROM ui_monitoring_held WHERE ui_monitoring_id IN (SELECT value FROM json_each(?1))",
+            "DELETE FROM ui_monitoring WHERE id IN (SELECT value FROM json_each(?1))",
+        ] {
+            sqlx::query(query).bind(&ids).execute(&mut **tx).await?;
+        }
+        deletion.ui_rows += ui_ids.len() as u64;
+        Ok(())
+    }
+}
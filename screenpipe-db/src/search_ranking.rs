@@ -0,0 +1,37 @@
+use chrono::{DateTime, Duration, Utc};
+use oasgen::OaSchema;
+use serde::{Deserialize, Serialize};
+
+/// Order of search results.
+#[derive(OaSchema, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchRanking {
+    /// the usual order of each search: most recent first for full text
+    /// search, closest first for semantic search
+    #[default]
+    Standard,
+    /// best match first, with the relevance of a result halved every
+    /// half-life of its age, and raised by the clicks on it for similar
+    /// queries when click tracking is enabled
+    Decayed,
+}
+
+/// Weight of a result `age` old, 1 for a new one and halved every
+/// `half_life`.
+pub fn recency_weight(age: Duration, half_life: Duration) -> f64 {
+    let half_life = half_life.num_milliseconds();
+    if half_life <= 0 {
+        return 1.0;
+    }
+    let age = age.num_milliseconds().max(0) as f64;
+    0.5f64.powf(age / half_life as f64)
+}
+
+pub fn decayed_score(
+    relevance: f64,
+    timestamp: DateTime<Utc>,
+    now: DateTime<Utc>,
+    half_life: Duration,
+) -> f64 {
+    relevance * recency_weight(now - timestamp, half_life)
+}
@@ -0,0 +1,118 @@
+use chrono::{Duration, Utc};
+use std::collections::HashMap;
+
+use crate::search_ranking::decayed_score;
+use crate::{ClickBoosts, ClickContentType, DatabaseManager, SearchResult};
+
+impl DatabaseManager {
+    /// Orders results of `query` by their full text relevance (bm25) decayed
+    /// by their age. Only the results given are ranked, so they should be a
+    /// wide enough window of the most recent matches: an older match only
+    /// comes first when it is that much more relevant. The relevance of the
+    /// results in `click_boosts` is multiplied by their boost.
+    pub async fn rank_by_decayed_relevance(
+        &self,
+        query: &str,
+        results: Vec<SearchResult>,
+        half_life: Duration,
+        click_boosts: &ClickBoosts,
+    ) -> Result<Vec<SearchResult>, sqlx::Error> {
+        if query.trim().is_empty() {
+            // every result is as relevant, the most recent come first
+            return Ok(results);
+        }
+
+        let ids = |content: fn(&SearchResult) -> Option<i64>| {
+            let ids: Vec<i64> = results.iter().filter_map(content).collect();
+            serde_json::to_string(&ids).unwrap_or_else(|_| "[]".to_string())
+        };
+        let ocr_ids = ids(|result| match result {
+            SearchResult::OCR(ocr) => Some(ocr.frame_id),
+            _ => None,
+        });
+        let audio_ids = ids(|result| match result {
+            SearchResult::Audio(audio) => Some(audio.audio_chunk_id),
+            _ => None,
+        });
+        let ui_ids = ids(|result| match result {
+            SearchResult::UI(ui) => Some(ui.id),
+            _ => None,
+        });
+        let clipboard_ids = ids(|result| match result {
+            SearchResult::Clipboard(clipboard) => Some(clipboard.id),
+            _ => None,
+        });
+
+        let relevance = |sql: &'static str, ids: String| async move {
+            // bm25 is negative, lower for better matches
+            sqlx::query_as::<_, (i64, f64)>(sql)
+                .bind(query)
+                .bind(ids)
+                .fetch_all(&self.pool)
+                .await
+                .map(|rows| {
+                    rows.into_iter()
+                        .map(|(id, rank)| (id, -rank))
+                        .collect::<HashMap<_, _>>()
+                })
+        };
+        let (ocr, audio, ui, clipboard) = tokio::try_join!(
+            relevance(
+                r#"
+                SELECT frame_id, MIN(rank) FROM ocr_text_fts
+                WHERE ocr_text_fts MATCH ?1 AND frame_id IN (SELECT value FROM json_each(?2))
+                GROUP BY frame_id
+                "#,
+                ocr_ids,
+            ),
+            relevance(
+                r#"
+                SELECT audio_chunk_id, MIN(rank) FROM audio_transcriptions_fts
+                WHERE audio_transcriptions_fts MATCH ?1
+                    AND audio_chunk_id IN (SELECT value FROM json_each(?2))
+                GROUP BY audio_chunk_id
+                "#,
+                audio_ids,
+            ),
+            relevance(
+                r#"
+                SELECT rowid, MIN(rank) FROM ui_monitoring_fts
+                WHERE ui_monitoring_fts MATCH ?1 AND rowid IN (SELECT value FROM json_each(?2))
+                GROUP BY rowid
+                "#,
+                ui_ids,
+            ),
+            relevance(
+                r#"
+                SELECT rowid, MIN(rank) FROM clipboard_fts
+                WHERE clipboard_fts MATCH ?1 AND rowid IN (SELECT value FROM json_each(?2))
+                GROUP BY rowid
+                "#,
+                clipboard_ids,
+            ),
+        )?;
+
+        let now = Utc::now();
+        let mut scored: Vec<(f64, SearchResult)> = results
+            .into_iter()
+            .map(|result| {
+                let (relevance, timestamp) = match &result {
+                    SearchResult::OCR(r) => (ocr.get(&r.frame_id), r.timestamp),
+                    SearchResult::Audio(r) => (audio.get(&r.audio_chunk_id), r.timestamp),
+                    SearchResult::UI(r) => (ui.get(&r.id), r.timestamp),
+                    SearchResult::Clipboard(r) => (clipboard.get(&r.id), r.timestamp),
+                };
+                let boost = click_boosts
+                    .get(&ClickContentType::of(&result))
+                    .copied()
+                    .unwrap_or(1.0);
+                let score =
+                    decayed_score(relevance.unwrap_or(&0.0) * boost, timestamp, now, half_life);
+                (score, result)
+            })
+            .collect();
+        // stable, ties stay most recent first
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        Ok(scored.into_iter().map(|(_, result)| result).collect())
+    }
+}
@@ -0,0 +1,162 @@
+use crate::search_delete_db::non_empty;
+use crate::{ContentType, DatabaseManager, SearchDeleteFilter, SearchTagging};
+
+// items tagged per statement, progress is reported after each
+const TAG_BATCH: usize = 1_000;
+
+fn ids_json(ids: &[i64]) -> String {
+    serde_json::to_string(ids).unwrap_or_else(|_| "[]".to_string())
+}
+
+impl DatabaseManager {
+    /// Tags every frame, audio chunk and ui row the filter matches, left out
+    /// of the trash, in one transaction: a frame matches when its text does,
+    /// an audio chunk when one of its transcriptions does. `progress` is
+    /// called once the matches are known and after each batch tagged.
+    pub async fn tag_search_results(
+        &self,
+        filter: &SearchDeleteFilter,
+        tag: &str,
+        mut progress: impl FnMut(&SearchTagging) + Send,
+    ) -> Result<SearchTagging, sqlx::Error> {
+        let mut tagging = SearchTagging::default();
+        let Some(tag) = self
+            .canonical_tags(&[tag.to_string()])
+            .await?
+            .into_iter()
+            .next()
+        else {
+            return Ok(tagging);
+        };
+        let q = non_empty(&filter.q);
+        let app_name = non_empty(&filter.app_name);
+        let window_name = non_empty(&filter.window_name);
+        let (ocr, audio, ui) = match filter.content_type {
+            ContentType::All => (true, true, true),
+            ContentType::OCR => (true, false, false),
+            ContentType::Audio => (false, true, false),
+            ContentType::UI => (false, false, true),
+            ContentType::AudioAndUi => (false, true, true),
+            ContentType::OcrAndUi => (true, false, true),
+            ContentType::AudioAndOcr => (true, true, false),
+            // the clipboard isn't tagged
+            ContentType::Clipboard => (false, false, false),
+        };
+        let audio = audio && app_name.is_none() && window_name.is_none();
+
+        let mut tx = self.pool.begin().await?;
+        let frame_ids: Vec<i64> = if ocr {
+            sqlx::query_scalar(
+                r#"
+                SELECT frames.id
+                FROM frames
+                WHERE frames.deleted_at IS NULL
+                    AND (?1 IS NULL OR frames.id IN (
+                        SELECT frame_id FROM ocr_text_fts WHERE ocr_text_fts MATCH ?1
+                    ))
+                    AND (?2 IS NULL OR frames.timestamp >= ?2)
+                    AND (?3 IS NULL OR frames.timestamp <= ?3)
+                    AND (?4 IS NULL OR frames.app_name = ?4)
+                    AND (?5 IS NULL OR frames.window_name = ?5)
+                ORDER BY frames.id
+                "#,
+            )
+            .bind(q)
+            .bind(filter.start_time)
+            .bind(filter.end_time)
+            .bind(app_name)
+            .bind(window_name)
+            .fetch_all(&mut *tx)
+            .await?
+        } else {
+            Vec::new()
+        };
+        let audio_chunk_ids: Vec<i64> = if audio {
+            sqlx::query_scalar(
+                r#"
+                SELECT audio_chunks.id
+                FROM audio_chunks
+                WHERE audio_chunks.deleted_at IS NULL
+                    AND (?1 IS NULL OR audio_chunks.id IN (
+                        SELECT audio_chunk_id FROM audio_transcriptions_fts
+                        WHERE audio_transcriptions_fts MATCH ?1
+                    ))
+                    AND (?2 IS NULL OR audio_chunks.timestamp >= ?2)
+                    AND (?3 IS NULL OR audio_chunks.timestamp <= ?3)
+                ORDER BY audio_chunks.id
+                "#,
+            )
+            .bind(q)
+            .bind(filter.start_time)
+            .bind(filter.end_time)
+            .fetch_all(&mut *tx)
+            .await?
+        } else {
+            Vec::new()
+        };
+        let ui_ids: Vec<i64> = if ui {
+            sqlx::query_scalar(
+                r#"
+                SELECT ui_monitoring.id
+                FROM ui_monitoring
+                WHERE ui_monitoring.deleted_at IS NULL
+                    AND (?1 IS NULL OR ui_monitoring.id IN (
+                        SELECT rowid FROM ui_monitoring_fts WHERE ui_monitoring_fts MATCH ?1
+                    ))
+                    AND (?2 IS NULL OR ui_monitoring.timestamp >= ?2)
+                    AND (?3 IS NULL OR ui_monitoring.timestamp <= ?3)
+                    AND (?4 IS NULL OR ui_monitoring.app = ?4)
+                    AND (?5 IS NULL OR ui_monitoring.window = ?5)
+                ORDER BY ui_monitoring.id
+                "#,
+            )
+            .bind(q)
+            .bind(filter.start_time)
+            .bind(filter.end_time)
+            .bind(app_name)
+            .bind(window_name)
+            .fetch_all(&mut *tx)
+            .await?
+        } else {
+            Vec::new()
+        };
+        tagging.matched = (frame_ids.len() + audio_chunk_ids.len() + ui_ids.len()) as u64;
+        progress(&tagging);
+        if tagging.matched == 0 {
+            tx.rollback().await?;
+            return Ok(tagging);
+        }
+
+        let tag_id: i64 = sqlx::query_scalar(
+            "INSERT INTO tags (name) VALUES (?) ON CONFLICT(name) DO UPDATE SET name=name RETURNING id",
+        )
+        .bind(&tag)
+        .fetch_one(&mut *tx)
+        .await?;
+        for (table, column, ids) in [
+            ("vision_tags", "vision_id", &frame_ids),
+            ("audio_tags", "audio_chunk_id", &audio_chunk_ids),
+            ("ui_monitoring_tags", "ui_monitoring_id", &ui_ids),
+        ] {
+            for batch in ids.chunks(TAG_BATCH) {
+                tagging.tagged += sqlx::query(&format!(
+                    r#"
+                    INSERT INTO {table} ({column}, tag_id)
+                    SELECT value, ?2 FROM json_each(?1) WHERE true
+                    ON CONFLICT DO NOTHING
+                    "#
+                ))
+                .bind(ids_json(batch))
+                .bind(tag_id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+                tagging.processed += batch.len() as u64;
+                progress(&tagging);
+            }
+        }
+
+        tx.commit().await?;
+        Ok(tagging)
+    }
+}
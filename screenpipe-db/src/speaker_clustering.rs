@@ -0,0 +1,102 @@
+// k-means rounds, embeddings of one speaker converge well before
+const KMEANS_ITERATIONS: usize = 10;
+
+/// Reads an embedding stored with `vec_f32`, little endian f32s.
+pub fn decode_embedding(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect()
+}
+
+fn normalized(embedding: &[f32]) -> Vec<f32> {
+    let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        return embedding.to_vec();
+    }
+    embedding.iter().map(|x| x / norm).collect()
+}
+
+fn cosine_distance(a: &[f32], b: &[f32]) -> f32 {
+    1.0 - a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>()
+}
+
+/// Picks at most `k` embeddings that cover the voice of a speaker: the
+/// embeddings are clustered by cosine distance and the one closest to the
+/// center of each cluster is kept. Returns their indices, sorted. Matching
+/// uses the nearest embedding, so the kept ones still match every variant
+/// of the voice (microphones, rooms) the dropped ones did.
+pub fn representative_embeddings(embeddings: &[Vec<f32>], k: usize) -> Vec<usize> {
+    if embeddings.len() <= k {
+        return (0..embeddings.len()).collect();
+    }
+    if k == 0 {
+        return Vec::new();
+    }
+    let points: Vec<Vec<f32>> = embeddings.iter().map(|e| normalized(e)).collect();
+    let nearest = |centers: &[Vec<f32>], point: &[f32]| {
+        centers
+            .iter()
+            .enumerate()
+            .map(|(i, center)| (i, cosine_distance(center, point)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map_or(0, |(i, _)| i)
+    };
+
+    // farthest point init, deterministic and spread over the variants
+    let mut centers = vec![points[0].clone()];
+    while centers.len() < k {
+        let farthest = points
+            .iter()
+            .max_by(|a, b| {
+                let da = cosine_distance(&centers[nearest(&centers, a)], a);
+                let db = cosine_distance(&centers[nearest(&centers, b)], b);
+                da.total_cmp(&db)
+            })
+            .unwrap();
+        centers.push(farthest.clone());
+    }
+
+    let mut assignment = vec![0; points.len()];
+    for _ in 0..KMEANS_ITERATIONS {
+        let next: Vec<usize> = points.iter().map(|p| nearest(&centers, p)).collect();
+        let changed = next != assignment;
+        assignment = next;
+        for (i, center) in centers.iter_mut().enumerate() {
+            let members: Vec<&Vec<f32>> = points
+                .iter()
+                .zip(&assignment)
+                .filter(|(_, cluster)| **cluster == i)
+                .map(|(point, _)| point)
+                .collect();
+            if members.is_empty() {
+                continue;
+            }
+            let mut sum = vec![0.0; center.len()];
+            for member in &members {
+                for (s, x) in sum.iter_mut().zip(member.iter()) {
+                    *s += x;
+                }
+            }
+            *center = normalized(&sum);
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    let mut kept: Vec<usize> = (0..centers.len())
+        .filter_map(|cluster| {
+            points
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| assignment[*i] == cluster)
+                .min_by(|(_, a), (_, b)| {
+                    cosine_distance(&centers[cluster], a)
+                        .total_cmp(&cosine_distance(&centers[cluster], b))
+                })
+                .map(|(i, _)| i)
+        })
+        .collect();
+    kept.sort_unstable();
+    kept
+}
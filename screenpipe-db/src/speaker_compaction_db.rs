@@ -0,0 +1,89 @@
+use std::collections::HashSet;
+
+use crate::speaker_clustering::{decode_embedding, representative_embeddings};
+use crate::{DatabaseManager, SpeakerCompaction};
+
+impl DatabaseManager {
+    /// Keeps speaker matching fast as audio piles up: drops the embeddings of
+    /// speakers that no longer exist and caps the others to the
+    /// `max_embeddings_per_speaker` most representative ones. Merging moves
+    /// every embedding to the kept speaker, so merged speakers grow the most.
+    pub async fn compact_speakers(
+        &self,
+        max_embeddings_per_speaker: usize,
+    ) -> Result<SpeakerCompaction, sqlx::Error> {
+        let mut result = SpeakerCompaction {
+            orphaned_embeddings: sqlx::query(
+                r#"
+                DELETE FROM speaker_embeddings
+                WHERE speaker_id IS NULL OR speaker_id NOT IN (SELECT id FROM speakers)
+                "#,
+            )
+            .execute(&self.pool)
+            .await?
+            .rows_affected(),
+            ..Default::default()
+        };
+
+        let speaker_ids: Vec<i64> = sqlx::query_scalar(
+            r#"
+            SELECT speaker_id FROM speaker_embeddings
+            GROUP BY speaker_id
+            HAVING COUNT(*) > ?1
+            "#,
+        )
+        .bind(max_embeddings_per_speaker as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        // one transaction per speaker so capture can keep writing in between
+        for speaker_id in speaker_ids {
+            let mut tx = self.pool.begin().await?;
+            let rows: Vec<(i64, Vec<u8>)> = sqlx::query_as(
+                "SELECT id, embedding FROM speaker_embeddings WHERE speaker_id = ?1 ORDER BY id",
+            )
+            .bind(speaker_id)
+            .fetch_all(&mut *tx)
+            .await?;
+            let embeddings: Vec<Vec<f32>> = rows
+                .iter()
+                .map(|(_, blob)| decode_embedding(blob))
+                .collect();
+            let kept: HashSet<usize> =
+                representative_embeddings(&embeddings, max_embeddings_per_speaker)
+                    .into_iter()
+                    .collect();
+            let pruned: Vec<i64> = rows
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| !kept.contains(i))
+                .map(|(_, (id, _))| *id)
+                .collect();
+
+            result.pruned_embeddings += sqlx::query(
+                "DELETE FROM speaker_embeddings WHERE id IN (SELECT value FROM json_each(?1))",
+            )
+            .bind(serde_json::to_string(&pruned).unwrap_or_else(|_| "[]".to_string()))
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            tx.commit().await?;
+            result.compacted_speakers += 1;
+        }
+
+        Ok(result)
+    }
+
+    /// Rebuilds the database file to give the space of deleted rows back to
+    /// the disk. It copies the whole database, so it is slow on large ones
+    /// and blocks writes meanwhile.
+    pub async fn vacuum(&self) -> Result<(), sqlx::Error> {
+        sqlx::query("VACUUM").execute(&self.pool).await?;
+        // ocr_text has no integer primary key, so vacuum may renumber the
+        // rows its search index points to
+        sqlx::query("INSERT INTO ocr_text_fts(ocr_text_fts) VALUES('rebuild')")
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
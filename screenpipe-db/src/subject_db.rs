@@ -0,0 +1,438 @@
+use std::collections::HashSet;
+
+use sqlx::SqliteConnection;
+
+use crate::{
+    ClipboardEntry, DatabaseManager, PrunedData, SearchDeletion, Speaker, Subject,
+    SubjectAudioChunk, SubjectErasure, SubjectExport, SubjectOcrHit, SubjectParticipant,
+    SubjectTranscript, SubjectUiHit,
+};
+
+fn ids_json(ids: &[i64]) -> String {
+    serde_json::to_string(ids).unwrap_or_else(|_| "[]".to_string())
+}
+
+// the name as a phrase in one column of a full text index, quotes escaped
+fn fts_phrase(column: &str, name: &str) -> String {
+    format!("{} : \"{}\"", column, name.replace('"', "\"\""))
+}
+
+fn subject_name(subject: &Subject) -> Option<&str> {
+    subject
+        .name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+}
+
+async fn collect_subject(
+    conn: &mut SqliteConnection,
+    subject: &Subject,
+) -> Result<SubjectExport, sqlx::Error> {
+    let name = subject_name(subject);
+    let mut export = SubjectExport {
+        subject: subject.clone(),
+        ..Default::default()
+    };
+
+    export.speakers = sqlx::query_as::<_, Speaker>(
+        r#"
+        SELECT id, COALESCE(name, '') AS name, COALESCE(metadata, '') AS metadata
+        FROM speakers
+        WHERE id = ?1 OR name = ?2 COLLATE NOCASE
+        ORDER BY id
+        "#,
+    )
+    .bind(subject.speaker_id)
+    .bind(name)
+    .fetch_all(&mut *conn)
+    .await?;
+    let mut speaker_ids: Vec<i64> = export.speakers.iter().map(|speaker| speaker.id).collect();
+    speaker_ids.extend(subject.speaker_id);
+    speaker_ids.sort_unstable();
+    speaker_ids.dedup();
+    let speaker_ids = ids_json(&speaker_ids);
+
+    // what they said, and what was said about them
+    let mentions = if name.is_some() {
+        r#"
+        OR id IN (
+            SELECT rowid FROM audio_transcriptions_fts WHERE audio_transcriptions_fts MATCH ?2
+        )
+        "#
+    } else {
+        ""
+    };
+    let sql = format!(
+        r#"
+        SELECT id, audio_chunk_id, transcription, timestamp, device, speaker_id
+        FROM audio_transcriptions
+        WHERE speaker_id IN (SELECT value FROM json_each(?1)) {mentions}
+        ORDER BY timestamp, id
+        "#
+    );
+    let mut transcripts = sqlx::query_as::<_, SubjectTranscript>(&sql).bind(&speaker_ids);
+    if let Some(name) = name {
+        transcripts = transcripts.bind(fts_phrase("transcription", name));
+    }
+    export.transcripts = transcripts.fetch_all(&mut *conn).await?;
+
+    let mut audio_chunk_ids: Vec<i64> = export
+        .transcripts
+        .iter()
+        .map(|transcript| transcript.audio_chunk_id)
+        .collect();
+    audio_chunk_ids.sort_unstable();
+    audio_chunk_ids.dedup();
+    export.audio_chunks = sqlx::query_as::<_, SubjectAudioChunk>(
+        r#"
+        SELECT id, file_path, timestamp
+        FROM audio_chunks
+        WHERE id IN (SELECT value FROM json_each(?1))
+        ORDER BY id
+        "#,
+    )
+    .bind(ids_json(&audio_chunk_ids))
+    .fetch_all(&mut *conn)
+    .await?;
+
+    export.meeting_participants = sqlx::query_as::<_, SubjectParticipant>(
+        r#"
+        SELECT id, meeting_id, name, speaker_id
+        FROM meeting_participants
+        WHERE speaker_id IN (SELECT value FROM json_each(?1)) OR name = ?2 COLLATE NOCASE
+        ORDER BY id
+        "#,
+    )
+    .bind(&speaker_ids)
+    .bind(name)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    // a speaker id alone is never read on screen
+    let Some(name) = name else {
+        return Ok(export);
+    };
+    export.ocr_hits = sqlx::query_as::<_, SubjectOcrHit>(
+        r#"
+        SELECT
+            frames.id AS frame_id,
+            frames.timestamp,
+            frames.app_name,
+            frames.window_name,
+            ocr_text.text,
+            video_chunks.file_path,
+            frames.offset_index
+        FROM frames
+        JOIN ocr_text ON ocr_text.frame_id = frames.id
+        JOIN video_chunks ON video_chunks.id = frames.video_chunk_id
+        WHERE frames.id IN (SELECT frame_id FROM ocr_text_fts WHERE ocr_text_fts MATCH ?1)
+        GROUP BY frames.id
+        ORDER BY frames.timestamp, frames.id
+        "#,
+    )
+    .bind(fts_phrase("text", name))
+    .fetch_all(&mut *conn)
+    .await?;
+
+    export.ui_hits = sqlx::query_as::<_, SubjectUiHit>(
+        r#"
+        SELECT id, timestamp, app, window, text_output AS text
+        FROM ui_monitoring
+        WHERE id IN (SELECT rowid FROM ui_monitoring_fts WHERE ui_monitoring_fts MATCH ?1)
+        ORDER BY timestamp, id
+        "#,
+    )
+    .bind(fts_phrase("text_output", name))
+    .fetch_all(&mut *conn)
+    .await?;
+
+    export.clipboard_hits = sqlx::query_as::<_, ClipboardEntry>(
+        r#"
+        SELECT id, timestamp, text, app_name, window_name
+        FROM clipboard
+        WHERE id IN (SELECT rowid FROM clipboard_fts WHERE clipboard_fts MATCH ?1)
+        ORDER BY timestamp, id
+        "#,
+    )
+    .bind(fts_phrase("text", name))
+    .fetch_all(&mut *conn)
+    .await?;
+    Ok(export)
+}
+
+impl DatabaseManager {
+    /// Every transcription, audio chunk, screen text, ui text and clipboard
+    /// hit, speaker and meeting participant referencing the subject.
+    pub async fn export_subject(&self, subject: &Subject) -> Result<SubjectExport, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        guard.run(collect_subject(&mut conn, subject)).await
+    }
+
+    /// Deletes for good, in one transaction, what `export_subject` finds:
+    /// the frames, ui rows, ui traversals, clipboard entries and browser tabs
+    /// mentioning the subject, the transcriptions
+    /// they speak or are spoken of in, their speakers with the voice
+    /// embeddings, the meeting participants, and the search clicks, tag
+    /// suggestions and audit entries pointing at any of it or naming them.
+    /// Audio chunks are removed once no transcription of them is left.
+    ///
+    /// Matches in the video and audio chunks still being recorded are left
+    /// as they are and counted in `skipped_live`, the returned export holds
+    /// what was erased only. Video chunks still holding other frames are
+    /// kept, the caller blacks the deleted frames out of them with the
+    /// returned offsets.
+    pub async fn erase_subject(&self, subject: &Subject) -> Result<SubjectErasure, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let mut export = collect_subject(&mut tx, subject).await?;
+        let mut deletion = SearchDeletion::default();
+        let mut pruned = PrunedData::default();
+        let mut skipped_live = 0;
+
+        let frame_ids: Vec<i64> = export.ocr_hits.iter().map(|hit| hit.frame_id).collect();
+        let frames: Vec<(i64, i64)> = sqlx::query_as(
+            r#"
+            SELECT frames.id, frames.video_chunk_id
+            FROM frames
+            JOIN video_chunks v ON v.id = frames.video_chunk_id
+            WHERE frames.id IN (SELECT value FROM json_each(?1))
+                AND EXISTS (
+                    SELECT 1 FROM video_chunks newer
+                    WHERE newer.device_name = v.device_name AND newer.id > v.id
+                )
+            "#,
+        )
+        .bind(ids_json(&frame_ids))
+        .fetch_all(&mut *tx)
+        .await?;
+        let erased_frames: HashSet<i64> = frames.iter().map(|(id, _)| *id).collect();
+        skipped_live += (frame_ids.len() - erased_frames.len()) as u64;
+        export
+            .ocr_hits
+            .retain(|hit| erased_frames.contains(&hit.frame_id));
+        Self::delete_matched_frames(&mut tx, &frames, &mut deletion, &mut pruned).await?;
+
+        // the chunk still being recorded is the last one of its device
+        let transcript_ids: Vec<i64> = export.transcripts.iter().map(|t| t.id).collect();
+        let transcripts: Vec<(i64, i64)> = sqlx::query_as(
+            r#"
+            SELECT t.id, t.audio_chunk_id
+            FROM audio_transcriptions t
+            WHERE t.id IN (SELECT value FROM json_each(?1))
+                AND EXISTS (
+                    SELECT 1 FROM audio_transcriptions newer
+                    WHERE newer.device = t.device AND newer.audio_chunk_id > t.audio_chunk_id
+                )
+            "#,
+        )
+        .bind(ids_json(&transcript_ids))
+        .fetch_all(&mut *tx)
+        .await?;
+        let erased_transcripts: HashSet<i64> = transcripts.iter().map(|(id, _)| *id).collect();
+        skipped_live += (transcript_ids.len() - erased_transcripts.len()) as u64;
+        export
+            .transcripts
+            .retain(|transcript| erased_transcripts.contains(&transcript.id));
+        let mut touched_chunks: Vec<i64> = transcripts.iter().map(|(_, chunk)| *chunk).collect();
+        touched_chunks.sort_unstable();
+        touched_chunks.dedup();
+        export
+            .audio_chunks
+            .retain(|chunk| touched_chunks.binary_search(&chunk.id).is_ok());
+
+        deletion.audio_transcriptions += sqlx::query(
+            "DELETE FROM audio_transcriptions WHERE id IN (SELECT value FROM json_each(?1))",
+        )
+        .bind(ids_json(
+            &transcripts.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        ))
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        // the legacy word index of a chunk mixes every transcription of it
+        sqlx::query(
+            "DELETE FROM chunked_text_entries WHERE audio_chunk_id IN (SELECT value FROM json_each(?1))",
+        )
+        .bind(ids_json(&touched_chunks))
+        .execute(&mut *tx)
+        .await?;
+        let emptied_chunks: Vec<i64> = sqlx::query_scalar(
+            r#"
+            SELECT value FROM json_each(?1)
+            WHERE NOT EXISTS (
+                SELECT 1 FROM audio_transcriptions WHERE audio_chunk_id = value
+            )
+            "#,
+        )
+        .bind(ids_json(&touched_chunks))
+        .fetch_all(&mut *tx)
+        .await?;
+        Self::delete_matched_audio_chunks(&mut tx, &emptied_chunks, &mut deletion, &mut pruned)
+            .await?;
+
+        let ui_ids: Vec<i64> = export.ui_hits.iter().map(|hit| hit.id).collect();
+        Self::delete_matched_ui_rows(&mut tx, &ui_ids, &mut deletion).await?;
+        if let Some(name) = subject_name(subject) {
+            let traversal_ids =
+                Self::matching_ui_traversals(&mut tx, &[name.to_string()], None, None, None, None)
+                    .await?;
+            deletion.ui_traversals += Self::delete_ui_traversals(&mut tx, &traversal_ids).await?;
+        }
+        let clipboard_ids: Vec<i64> = export.clipboard_hits.iter().map(|hit| hit.id).collect();
+        deletion.clipboard_entries =
+            sqlx::query("DELETE FROM clipboard WHERE id IN (SELECT value FROM json_each(?1))")
+                .bind(ids_json(&clipboard_ids))
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+        let browser_contexts = match subject_name(subject) {
+            Some(name) => sqlx::query(
+                r#"
+                DELETE FROM browser_contexts
+                WHERE id IN (
+                    SELECT rowid FROM browser_contexts_fts WHERE browser_contexts_fts MATCH ?1
+                )
+                "#,
+            )
+            .bind(format!("\"{}\"", name.replace('"', "\"\"")))
+            .execute(&mut *tx)
+            .await?
+            .rows_affected(),
+            None => 0,
+        };
+
+        let participant_ids: Vec<i64> = export
+            .meeting_participants
+            .iter()
+            .map(|participant| participant.id)
+            .collect();
+        let meeting_participants = sqlx::query(
+            "DELETE FROM meeting_participants WHERE id IN (SELECT value FROM json_each(?1))",
+        )
+        .bind(ids_json(&participant_ids))
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        // speakers still heard in a live chunk are kept to be found again
+        let still_heard: Vec<i64> = sqlx::query_scalar(
+            r#"
+            SELECT DISTINCT speaker_id FROM audio_transcriptions
+            WHERE speaker_id IN (SELECT value FROM json_each(?1))
+            "#,
+        )
+        .bind(ids_json(
+            &export
+                .speakers
+                .iter()
+                .map(|speaker| speaker.id)
+                .collect::<Vec<_>>(),
+        ))
+        .fetch_all(&mut *tx)
+        .await?;
+        export
+            .speakers
+            .retain(|speaker| !still_heard.contains(&speaker.id));
+        let speaker_ids = ids_json(
+            &export
+                .speakers
+                .iter()
+                .map(|speaker| speaker.id)
+                .collect::<Vec<_>>(),
+        );
+        let speaker_embeddings = sqlx::query(
+            "DELETE FROM speaker_embeddings WHERE speaker_id IN (SELECT value FROM json_each(?1))",
+        )
+        .bind(&speaker_ids)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        let speakers =
+            sqlx::query("DELETE FROM speakers WHERE id IN (SELECT value FROM json_each(?1))")
+                .bind(&speaker_ids)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+
+        let ui_ids_json = ids_json(&ui_ids);
+        let frame_ids_json = ids_json(&erased_frames.iter().copied().collect::<Vec<_>>());
+        let chunk_ids_json = ids_json(&touched_chunks);
+        let mut search_clicks = sqlx::query(
+            r#"
+            DELETE FROM search_clicks
+            WHERE (content_type = 'ocr' AND content_id IN (SELECT value FROM json_each(?1)))
+                OR (content_type = 'audio' AND content_id IN (SELECT value FROM json_each(?2)))
+                OR (content_type = 'ui' AND content_id IN (SELECT value FROM json_each(?3)))
+                OR (content_type = 'clipboard' AND content_id IN (SELECT value FROM json_each(?4)))
+            "#,
+        )
+        .bind(&frame_ids_json)
+        .bind(&chunk_ids_json)
+        .bind(&ui_ids_json)
+        .bind(ids_json(&clipboard_ids))
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        let tag_suggestions = sqlx::query(
+            r#"
+            DELETE FROM tag_suggestions
+            WHERE (content_type = 'vision' AND content_id IN (SELECT value FROM json_each(?1)))
+                OR (content_type = 'audio' AND content_id IN (SELECT value FROM json_each(?2)))
+            "#,
+        )
+        .bind(&frame_ids_json)
+        .bind(&chunk_ids_json)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        let mut audit_entries = 0;
+        if let Some(name) = subject_name(subject) {
+            // clicks keep the lowercased words of the query, sorted
+            let words: Vec<String> = name.split_whitespace().map(str::to_lowercase).collect();
+            let clicks: Vec<(i64, String)> =
+                sqlx::query_as("SELECT id, query FROM search_clicks WHERE instr(query, ?1) > 0")
+                    .bind(&words[0])
+                    .fetch_all(&mut *tx)
+                    .await?;
+            let naming: Vec<i64> = clicks
+                .into_iter()
+                .filter(|(_, query)| {
+                    let query: HashSet<&str> = query.split_whitespace().collect();
+                    words.iter().all(|word| query.contains(word.as_str()))
+                })
+                .map(|(id, _)| id)
+                .collect();
+            search_clicks += sqlx::query(
+                "DELETE FROM search_clicks WHERE id IN (SELECT value FROM json_each(?1))",
+            )
+            .bind(ids_json(&naming))
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            // searches for them and the like
+            audit_entries = sqlx::query("DELETE FROM audit_log WHERE instr(lower(target), ?1) > 0")
+                .bind(name.to_lowercase())
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+        }
+
+        deletion.frames = pruned.frames;
+        deletion.audio_chunks = pruned.audio_chunks;
+        deletion.video_files = pruned.video_files;
+        deletion.audio_files = pruned.audio_files;
+        tx.commit().await?;
+        Ok(SubjectErasure {
+            export,
+            deletion,
+            speakers,
+            speaker_embeddings,
+            meeting_participants,
+            search_clicks,
+            tag_suggestions,
+            audit_entries,
+            browser_contexts,
+            skipped_live,
+        })
+    }
+}
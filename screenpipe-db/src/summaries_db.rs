@@ -0,0 +1,93 @@
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::{DatabaseManager, StoredSummary, TranscriptLine};
+
+impl DatabaseManager {
+    /// The summary kept of the `period` starting on `start_date`, if any.
+    pub async fn get_summary(
+        &self,
+        period: &str,
+        start_date: NaiveDate,
+    ) -> Result<Option<StoredSummary>, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        guard
+            .run(
+                sqlx::query_as(
+                    r#"
+                    SELECT id, period, start_date, generator, content, created_at
+                    FROM summaries
+                    WHERE period = ?1 AND start_date = ?2
+                    "#,
+                )
+                .bind(period)
+                .bind(start_date)
+                .fetch_optional(&mut *conn),
+            )
+            .await
+    }
+
+    /// Keeps `content`, replacing the summary of the same period if any.
+    pub async fn save_summary(
+        &self,
+        period: &str,
+        start_date: NaiveDate,
+        generator: &str,
+        content: &str,
+    ) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            INSERT INTO summaries (period, start_date, generator, content)
+            VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT (period, start_date) DO UPDATE SET
+                generator = excluded.generator,
+                content = excluded.content,
+                created_at = CURRENT_TIMESTAMP
+            RETURNING id
+            "#,
+        )
+        .bind(period)
+        .bind(start_date)
+        .bind(generator)
+        .bind(content)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    /// The longest transcriptions between `start` and `end`, longest first.
+    pub async fn get_longest_transcriptions(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<TranscriptLine>, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        guard
+            .run(
+                sqlx::query_as(
+                    r#"
+                    SELECT
+                        at.timestamp,
+                        NULLIF(s.name, '') as speaker_name,
+                        at.device as device_name,
+                        at.transcription as text,
+                        ac.file_path,
+                        at.start_time
+                    FROM audio_transcriptions at
+                    JOIN audio_chunks ac ON at.audio_chunk_id = ac.id
+                    LEFT JOIN speakers s ON at.speaker_id = s.id
+                    WHERE at.timestamp >= ?1 AND at.timestamp < ?2
+                        AND ac.deleted_at IS NULL
+                        AND (s.id IS NULL OR s.hallucination = 0)
+                        AND trim(at.transcription) != ''
+                    ORDER BY LENGTH(at.transcription) DESC, at.timestamp ASC
+                    LIMIT ?3
+                    "#,
+                )
+                .bind(start)
+                .bind(end)
+                .bind(limit)
+                .fetch_all(&mut *conn),
+            )
+            .await
+    }
+}
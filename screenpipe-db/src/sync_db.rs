@@ -0,0 +1,402 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use sqlx::pool::PoolConnection;
+use sqlx::{Connection, Sqlite, SqliteConnection};
+
+use crate::{DatabaseManager, MediaChunkKind, SyncRun};
+
+/// Tables whose new rows go in sync deltas, parents first. The rows of the
+/// other tables, and changes to rows synced before, reach the copy with the
+/// next snapshot, which a deletion or a text masked makes the next upload.
+pub const SYNC_DELTA_TABLES: &[&str] = &[
+    "devices",
+    "video_chunks",
+    "browser_contexts",
+    "frames",
+    "ocr_text",
+    "ocr_text_blocks",
+    "vision_metadata",
+    "tags",
+    "vision_tags",
+    "audio_chunks",
+    "speakers",
+    "speaker_embeddings",
+    "audio_transcriptions",
+    "audio_tags",
+    "audio_metadata",
+    "ui_monitoring",
+    "ui_monitoring_tags",
+];
+
+/// The last rowid of each table of `SYNC_DELTA_TABLES` an upload holds.
+pub type SyncWatermarks = BTreeMap<String, i64>;
+
+const SYNC_RUN_COLUMNS: &str = "id, kind, object_key, watermarks, rows, bytes, created_at";
+
+type SyncRunRow = (i64, String, String, String, i64, i64, DateTime<Utc>);
+
+fn sync_run((id, kind, object_key, watermarks, rows, bytes, created_at): SyncRunRow) -> SyncRun {
+    SyncRun {
+        id,
+        kind,
+        object_key,
+        watermarks: serde_json::from_str(&watermarks).unwrap_or_default(),
+        rows,
+        bytes,
+        created_at,
+    }
+}
+
+async fn watermarks(
+    conn: &mut SqliteConnection,
+    schema: &str,
+) -> Result<SyncWatermarks, sqlx::Error> {
+    let mut watermarks = SyncWatermarks::new();
+    for table in SYNC_DELTA_TABLES {
+        let last: i64 = sqlx::query_scalar(&format!(
+            "SELECT COALESCE(MAX(rowid), 0) FROM {}.{}",
+            schema, table
+        ))
+        .fetch_one(&mut *conn)
+        .await?;
+        watermarks.insert(table.to_string(), last);
+    }
+    Ok(watermarks)
+}
+
+// generated columns can't be inserted
+async fn insertable_columns(
+    conn: &mut SqliteConnection,
+    schema: &str,
+    table: &str,
+) -> Result<Vec<String>, sqlx::Error> {
+    let columns: Vec<(String, i64)> = sqlx::query_as(&format!(
+        "SELECT name, hidden FROM pragma_table_xinfo('{}', '{}')",
+        table, schema
+    ))
+    .fetch_all(&mut *conn)
+    .await?;
+    Ok(columns
+        .into_iter()
+        .filter(|(_, hidden)| *hidden == 0)
+        .map(|(name, _)| format!("\"{}\"", name))
+        .collect())
+}
+
+async fn detach_delta(mut conn: PoolConnection<Sqlite>) -> Result<(), sqlx::Error> {
+    sqlx::query("DETACH DATABASE sync_delta")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("PRAGMA foreign_keys = ON")
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Copies the rows of `SYNC_DELTA_TABLES` in `from` added after `since` to
+/// `to`, two schemas of `conn`, in one transaction. Returns the watermarks
+/// of what was copied and how many rows were.
+async fn copy_rows(
+    conn: &mut SqliteConnection,
+    from: &str,
+    to: &str,
+    since: &SyncWatermarks,
+) -> Result<(SyncWatermarks, u64), sqlx::Error> {
+    let mut tx = conn.begin().await?;
+    let upto = watermarks(&mut tx, from).await?;
+    let mut rows = 0;
+    for table in SYNC_DELTA_TABLES {
+        let columns = insertable_columns(&mut tx, to, table).await?.join(", ");
+        rows += sqlx::query(&format!(
+            r#"
+            INSERT OR IGNORE INTO {to}.{table} ({columns})
+            SELECT {columns} FROM {from}.{table}
+            WHERE rowid > ?1 AND rowid <= ?2
+            "#
+        ))
+        .bind(since.get(*table).copied().unwrap_or(0))
+        .bind(upto[*table])
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    }
+    tx.commit().await?;
+    Ok((upto, rows))
+}
+
+impl DatabaseManager {
+    /// Writes a copy of the whole database to `path`, which must not exist,
+    /// for a sync snapshot. Returns the watermarks the copy holds at least.
+    pub async fn create_sync_snapshot(&self, path: &Path) -> Result<SyncWatermarks, sqlx::Error> {
+        // read here, as the copy may number the rows of tables without an id
+        // differently. What is added meanwhile is in both the copy and the
+        // next delta, which skips it
+        let mut conn = self.pool.acquire().await?;
+        let watermarks = watermarks(&mut conn, "main").await?;
+        sqlx::query("VACUUM INTO ?1")
+            .bind(path.to_string_lossy().to_string())
+            .execute(&mut *conn)
+            .await?;
+        Ok(watermarks)
+    }
+
+    /// Writes the rows of `SYNC_DELTA_TABLES` added after `since` to a new
+    /// database at `path`, for a sync delta. Returns the watermarks it goes
+    /// up to and how many rows it holds.
+    pub async fn create_sync_delta(
+        &self,
+        path: &Path,
+        since: &SyncWatermarks,
+    ) -> Result<(SyncWatermarks, u64), sqlx::Error> {
+        // the delta gets the schema of this database, and the rows copied go
+        // straight to its file rather than to a WAL
+        let delta = DatabaseManager::new(&path.to_string_lossy()).await?;
+        sqlx::query("PRAGMA journal_mode = DELETE")
+            .execute(&delta.pool)
+            .await?;
+        delta.pool.close().await;
+        delta.read_pool.close().await;
+        let mut conn = self.attach_delta(path).await?;
+        let result = copy_rows(&mut conn, "main", "sync_delta", since).await;
+        detach_delta(conn).await?;
+        result
+    }
+
+    /// Adds the rows of the sync delta at `path` with their ids, e.g. to a
+    /// database restored from a snapshot. The delta is migrated to this
+    /// version first. Returns how many rows were added.
+    pub async fn apply_sync_delta(&self, path: &Path) -> Result<u64, sqlx::Error> {
+        let delta = DatabaseManager::new(&path.to_string_lossy()).await?;
+        delta.pool.close().await;
+        delta.read_pool.close().await;
+        let mut conn = self.attach_delta(path).await?;
+        let result = copy_rows(&mut conn, "sync_delta", "main", &SyncWatermarks::new()).await;
+        detach_delta(conn).await?;
+        Ok(result?.1)
+    }
+
+    /// A connection with the database at `path` attached as `sync_delta`.
+    /// Foreign keys are off until `detach_delta`, as the rows of a delta
+    /// refer to parents of earlier ones.
+    async fn attach_delta(&self, path: &Path) -> Result<PoolConnection<Sqlite>, sqlx::Error> {
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query("PRAGMA foreign_keys = OFF")
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query("ATTACH DATABASE ?1 AS sync_delta")
+            .bind(path.to_string_lossy().to_string())
+            .execute(&mut *conn)
+            .await?;
+        Ok(conn)
+    }
+
+    pub async fn insert_sync_run(
+        &self,
+        kind: &str,
+        object_key: &str,
+        watermarks: &SyncWatermarks,
+        rows: u64,
+        bytes: u64,
+    ) -> Result<SyncRun, sqlx::Error> {
+        let row: SyncRunRow = sqlx::query_as(&format!(
+            r#"
+            INSERT INTO sync_runs (kind, object_key, watermarks, rows, bytes, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            RETURNING {SYNC_RUN_COLUMNS}
+            "#
+        ))
+        .bind(kind)
+        .bind(object_key)
+        .bind(serde_json::to_string(watermarks).unwrap_or_else(|_| "{}".to_string()))
+        .bind(rows as i64)
+        .bind(bytes as i64)
+        .bind(Utc::now())
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(sync_run(row))
+    }
+
+    /// The last `limit` sync runs, newest first.
+    pub async fn list_sync_runs(&self, limit: u32) -> Result<Vec<SyncRun>, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        let rows: Vec<SyncRunRow> = guard
+            .run(
+                sqlx::query_as(&format!(
+                    "SELECT {SYNC_RUN_COLUMNS} FROM sync_runs ORDER BY id DESC LIMIT ?1"
+                ))
+                .bind(limit)
+                .fetch_all(&mut *conn),
+            )
+            .await?;
+        Ok(rows.into_iter().map(sync_run).collect())
+    }
+
+    /// Forgets the runs before `id`, whose objects were pruned from the
+    /// bucket.
+    pub async fn delete_sync_runs_before(&self, id: i64) -> Result<u64, sqlx::Error> {
+        Ok(sqlx::query("DELETE FROM sync_runs WHERE id < ?1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected())
+    }
+
+    /// Whether rows were deleted, or text masked or redacted, since the last
+    /// call, which the next upload must then carry as a snapshot.
+    pub async fn take_sync_snapshot_needed(&self) -> Result<bool, sqlx::Error> {
+        let taken = sqlx::query(
+            "UPDATE sync_state SET snapshot_needed = FALSE WHERE id = 1 AND snapshot_needed",
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+        Ok(taken > 0)
+    }
+
+    /// Makes the next upload a snapshot, e.g. again after one failed.
+    pub async fn request_sync_snapshot(&self) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE sync_state SET snapshot_needed = TRUE WHERE id = 1")
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Media files uploaded and deleted since, to delete from the bucket.
+    pub async fn list_deleted_sync_media(&self, limit: u32) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar("SELECT file_path FROM sync_deleted_media ORDER BY file_path LIMIT ?1")
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    pub async fn forget_deleted_sync_media(&self, file_path: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM sync_deleted_media WHERE file_path = ?1")
+            .bind(file_path)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Uploads the video chunk at `file_path` again, after it was changed in
+    /// place, e.g. redacted.
+    pub async fn reset_video_chunk_sync(&self, file_path: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE video_chunks SET synced_at = NULL WHERE file_path = ?1")
+            .bind(file_path)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Video chunks not uploaded yet that ffmpeg is done writing, oldest
+    /// first.
+    pub async fn list_unsynced_video_chunks(
+        &self,
+        limit: u32,
+    ) -> Result<Vec<(i64, String)>, sqlx::Error> {
+        sqlx::query_as::<_, (i64, String)>(
+            r#"
+            SELECT id, file_path
+            FROM video_chunks
+            WHERE synced_at IS NULL
+                AND file_path != ''
+                AND EXISTS (
+                    SELECT 1 FROM video_chunks newer
+                    WHERE newer.device_name = video_chunks.device_name
+                        AND newer.id > video_chunks.id
+                )
+            ORDER BY id
+            LIMIT ?1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Audio chunks not uploaded yet recorded before `before`, oldest first.
+    pub async fn list_unsynced_audio_chunks(
+        &self,
+        before: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<(i64, String)>, sqlx::Error> {
+        sqlx::query_as::<_, (i64, String)>(
+            r#"
+            SELECT id, file_path
+            FROM audio_chunks
+            WHERE synced_at IS NULL
+                AND file_path != ''
+                AND (timestamp IS NULL OR timestamp < ?1)
+            ORDER BY id
+            LIMIT ?2
+            "#,
+        )
+        .bind(before)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    pub async fn mark_chunk_synced(
+        &self,
+        kind: MediaChunkKind,
+        id: i64,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(&format!(
+            "UPDATE {} SET synced_at = ?1 WHERE id = ?2",
+            kind.table()
+        ))
+        .bind(Utc::now())
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Moves the video and audio chunks, and the frames naming them, to
+    /// files of the same name in `dir`, as after a restore on another
+    /// machine. Returns how many chunks moved.
+    pub async fn relocate_media(&self, dir: &Path) -> Result<u64, sqlx::Error> {
+        let mut moved = 0;
+        let mut tx = self.pool.begin().await?;
+        for kind in [MediaChunkKind::Video, MediaChunkKind::Audio] {
+            let chunks: Vec<(i64, String)> = sqlx::query_as(&format!(
+                "SELECT id, file_path FROM {} WHERE file_path != ''",
+                kind.table()
+            ))
+            .fetch_all(&mut *tx)
+            .await?;
+            for (id, file_path) in chunks {
+                let Some(file_name) = Path::new(&file_path).file_name() else {
+                    continue;
+                };
+                let relocated = dir.join(file_name).to_string_lossy().to_string();
+                if relocated == file_path {
+                    continue;
+                }
+                sqlx::query(&format!(
+                    "UPDATE {} SET file_path = ?1 WHERE id = ?2",
+                    kind.table()
+                ))
+                .bind(&relocated)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+                if kind == MediaChunkKind::Video {
+                    sqlx::query(
+                        "UPDATE frames SET name = ?1 WHERE video_chunk_id = ?2 AND name = ?3",
+                    )
+                    .bind(&relocated)
+                    .bind(id)
+                    .bind(&file_path)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                moved += 1;
+            }
+        }
+        tx.commit().await?;
+        Ok(moved)
+    }
+}
@@ -0,0 +1,75 @@
+use chrono::{DateTime, Utc};
+
+use crate::db::normalize_tag;
+use crate::{DatabaseManager, TagFacet};
+
+impl DatabaseManager {
+    /// The tags one level below `parent`, or the top level tags without one,
+    /// with the frames and audio chunks tagged with each or a tag below it.
+    /// Content tagged twice under a facet counts once.
+    pub async fn tag_facets(
+        &self,
+        parent: Option<&str>,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+    ) -> Result<Vec<TagFacet>, sqlx::Error> {
+        let parent = parent
+            .map(normalize_tag)
+            .filter(|parent| !parent.is_empty());
+        let (mut conn, guard) = self.read_connection().await?;
+        let facets: Vec<(String, i64, bool)> = guard
+            .run(
+                sqlx::query_as(
+                    r#"
+            WITH tagged AS (
+                SELECT 'vision:' || vision_tags.vision_id AS item, tags.name
+                FROM vision_tags
+                JOIN tags ON tags.id = vision_tags.tag_id
+                JOIN frames ON frames.id = vision_tags.vision_id
+                WHERE frames.deleted_at IS NULL
+                    AND (?2 IS NULL OR frames.timestamp >= ?2)
+                    AND (?3 IS NULL OR frames.timestamp <= ?3)
+                UNION ALL
+                SELECT 'audio:' || audio_tags.audio_chunk_id, tags.name
+                FROM audio_tags
+                JOIN tags ON tags.id = audio_tags.tag_id
+                JOIN audio_chunks ON audio_chunks.id = audio_tags.audio_chunk_id
+                WHERE audio_chunks.deleted_at IS NULL
+                    AND (?2 IS NULL OR audio_chunks.timestamp >= ?2)
+                    AND (?3 IS NULL OR audio_chunks.timestamp <= ?3)
+            ),
+            below AS (
+                SELECT item,
+                    CASE WHEN ?1 IS NULL THEN name ELSE substr(name, length(?1) + 2) END AS rest
+                FROM tagged
+                WHERE ?1 IS NULL OR substr(name, 1, length(?1) + 1) = ?1 || '/'
+            )
+            SELECT
+                CASE WHEN instr(rest, '/') > 0 THEN substr(rest, 1, instr(rest, '/') - 1) ELSE rest END AS level,
+                COUNT(DISTINCT item),
+                MAX(instr(rest, '/') > 0)
+            FROM below
+            GROUP BY level
+            ORDER BY COUNT(DISTINCT item) DESC, level ASC
+            "#,
+                )
+                .bind(&parent)
+                .bind(start_time)
+                .bind(end_time)
+                .fetch_all(&mut *conn),
+            )
+            .await?;
+
+        Ok(facets
+            .into_iter()
+            .map(|(level, count, has_children)| TagFacet {
+                tag: match &parent {
+                    Some(parent) => format!("{}/{}", parent, level),
+                    None => level,
+                },
+                count,
+                has_children,
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,269 @@
+use chrono::Utc;
+
+use crate::db::normalize_tag;
+use crate::{DatabaseManager, TagSummary, TagUpdate};
+
+const TAG_SUMMARY_SQL: &str = r#"
+    SELECT
+        tags.name,
+        (SELECT COUNT(*) FROM vision_tags WHERE vision_tags.tag_id = tags.id),
+        (SELECT COUNT(*) FROM audio_tags WHERE audio_tags.tag_id = tags.id),
+        (SELECT COUNT(*) FROM ui_monitoring_tags WHERE ui_monitoring_tags.tag_id = tags.id),
+        (
+            SELECT json_group_array(alias)
+            FROM (SELECT alias FROM tag_aliases WHERE tag_aliases.tag_id = tags.id ORDER BY alias)
+        )
+    FROM tags
+    WHERE ?1 IS NULL OR tags.name = ?1
+    ORDER BY tags.name
+"#;
+
+// the tables linking content to tags
+const TAG_LINKS: [(&str, &str); 3] = [
+    ("vision_tags", "vision_id"),
+    ("audio_tags", "audio_chunk_id"),
+    ("ui_monitoring_tags", "ui_monitoring_id"),
+];
+
+// name, vision, audio and ui counts, aliases as a json array
+type TagSummaryRow = (String, i64, i64, i64, String);
+
+fn tag_summary((name, vision_count, audio_count, ui_count, aliases): TagSummaryRow) -> TagSummary {
+    TagSummary {
+        name,
+        vision_count,
+        audio_count,
+        ui_count,
+        aliases: serde_json::from_str(&aliases).unwrap_or_default(),
+    }
+}
+
+async fn summary_in(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    name: &str,
+) -> Result<Option<TagSummary>, sqlx::Error> {
+    let row = sqlx::query_as::<_, TagSummaryRow>(TAG_SUMMARY_SQL)
+        .bind(name)
+        .fetch_optional(&mut **tx)
+        .await?;
+    Ok(row.map(tag_summary))
+}
+
+async fn tag_id_in(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    name: &str,
+) -> Result<Option<i64>, sqlx::Error> {
+    sqlx::query_scalar("SELECT id FROM tags WHERE name = ?1")
+        .bind(name)
+        .fetch_optional(&mut **tx)
+        .await
+}
+
+// whether `name` is already a tag or an alias
+async fn name_taken_in(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    name: &str,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        SELECT EXISTS (SELECT 1 FROM tags WHERE name = ?1)
+            OR EXISTS (SELECT 1 FROM tag_aliases WHERE alias = ?1)
+        "#,
+    )
+    .bind(name)
+    .fetch_one(&mut **tx)
+    .await
+}
+
+impl DatabaseManager {
+    /// Every tag with the frames, audio chunks and ui elements it is on.
+    pub async fn list_tags(&self) -> Result<Vec<TagSummary>, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        let rows: Vec<TagSummaryRow> = guard
+            .run(
+                sqlx::query_as(TAG_SUMMARY_SQL)
+                    .bind(None::<String>)
+                    .fetch_all(&mut *conn),
+            )
+            .await?;
+        Ok(rows.into_iter().map(tag_summary).collect())
+    }
+
+    /// The tags as stored, normalized and with aliases replaced by the tag
+    /// they point to. Empty tags are dropped.
+    pub(crate) async fn canonical_tags(&self, tags: &[String]) -> Result<Vec<String>, sqlx::Error> {
+        let tags: Vec<String> = tags
+            .iter()
+            .map(|tag| normalize_tag(tag))
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        if tags.is_empty() {
+            return Ok(tags);
+        }
+        sqlx::query_scalar(
+            r#"
+            SELECT COALESCE(tags.name, wanted.value)
+            FROM json_each(?1) wanted
+            LEFT JOIN tag_aliases ON tag_aliases.alias = wanted.value
+            LEFT JOIN tags ON tags.id = tag_aliases.tag_id
+            ORDER BY wanted.key
+            "#,
+        )
+        .bind(serde_json::to_string(&tags).unwrap_or_else(|_| "[]".to_string()))
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Renames the tag on all the content it is on, its aliases and the tag
+    /// rules adding it follow.
+    pub async fn rename_tag(&self, from: &str, to: &str) -> Result<TagUpdate, sqlx::Error> {
+        let (from, to) = (normalize_tag(from), normalize_tag(to));
+        let mut tx = self.pool.begin().await?;
+        if tag_id_in(&mut tx, &from).await?.is_none() {
+            return Ok(TagUpdate::NotFound);
+        }
+        if from != to {
+            if to.is_empty() || name_taken_in(&mut tx, &to).await? {
+                return Ok(TagUpdate::NameTaken);
+            }
+            sqlx::query("UPDATE tags SET name = ?2 WHERE name = ?1")
+                .bind(&from)
+                .bind(&to)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("UPDATE tag_rules SET tag = ?2 WHERE tag = ?1")
+                .bind(&from)
+                .bind(&to)
+                .execute(&mut *tx)
+                .await?;
+        }
+        let summary = summary_in(&mut tx, &to).await?;
+        tx.commit().await?;
+        self.tag_rules.invalidate();
+        Ok(summary.map_or(TagUpdate::NotFound, TagUpdate::Updated))
+    }
+
+    /// Moves all the content tagged `source` to `target` and removes
+    /// `source`, which becomes an alias of `target` so it keeps working for
+    /// tagging and searches.
+    pub async fn merge_tags(&self, source: &str, target: &str) -> Result<TagUpdate, sqlx::Error> {
+        let (source, target) = (normalize_tag(source), normalize_tag(target));
+        let mut tx = self.pool.begin().await?;
+        let (Some(source_id), Some(target_id)) = (
+            tag_id_in(&mut tx, &source).await?,
+            tag_id_in(&mut tx, &target).await?,
+        ) else {
+            return Ok(TagUpdate::NotFound);
+        };
+        if source_id != target_id {
+            for (table, column) in TAG_LINKS {
+                sqlx::query(&format!(
+                    r#"
+                    INSERT OR IGNORE INTO {table} ({column}, tag_id)
+                    SELECT {column}, ?2 FROM {table} WHERE tag_id = ?1
+                    "#
+                ))
+                .bind(source_id)
+                .bind(target_id)
+                .execute(&mut *tx)
+                .await?;
+                sqlx::query(&format!("DELETE FROM {table} WHERE tag_id = ?1"))
+                    .bind(source_id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            sqlx::query("UPDATE tag_aliases SET tag_id = ?2 WHERE tag_id = ?1")
+                .bind(source_id)
+                .bind(target_id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("DELETE FROM tags WHERE id = ?1")
+                .bind(source_id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("INSERT INTO tag_aliases (alias, tag_id, created_at) VALUES (?1, ?2, ?3)")
+                .bind(&source)
+                .bind(target_id)
+                .bind(Utc::now())
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("UPDATE tag_rules SET tag = ?2 WHERE tag = ?1")
+                .bind(&source)
+                .bind(&target)
+                .execute(&mut *tx)
+                .await?;
+        }
+        let summary = summary_in(&mut tx, &target).await?;
+        tx.commit().await?;
+        self.tag_rules.invalidate();
+        Ok(summary.map_or(TagUpdate::NotFound, TagUpdate::Updated))
+    }
+
+    /// Makes `alias` another name of `tag`. Adding an alias the tag already
+    /// has changes nothing.
+    pub async fn add_tag_alias(&self, alias: &str, tag: &str) -> Result<TagUpdate, sqlx::Error> {
+        let (alias, tag) = (normalize_tag(alias), normalize_tag(tag));
+        let mut tx = self.pool.begin().await?;
+        let Some(tag_id) = tag_id_in(&mut tx, &tag).await? else {
+            return Ok(TagUpdate::NotFound);
+        };
+        let current: Option<i64> =
+            sqlx::query_scalar("SELECT tag_id FROM tag_aliases WHERE alias = ?1")
+                .bind(&alias)
+                .fetch_optional(&mut *tx)
+                .await?;
+        if current != Some(tag_id) {
+            if alias.is_empty() || name_taken_in(&mut tx, &alias).await? {
+                return Ok(TagUpdate::NameTaken);
+            }
+            sqlx::query("INSERT INTO tag_aliases (alias, tag_id, created_at) VALUES (?1, ?2, ?3)")
+                .bind(&alias)
+                .bind(tag_id)
+                .bind(Utc::now())
+                .execute(&mut *tx)
+                .await?;
+        }
+        let summary = summary_in(&mut tx, &tag).await?;
+        tx.commit().await?;
+        Ok(summary.map_or(TagUpdate::NotFound, TagUpdate::Updated))
+    }
+
+    /// Returns whether the alias existed.
+    pub async fn remove_tag_alias(&self, alias: &str) -> Result<bool, sqlx::Error> {
+        let deleted = sqlx::query("DELETE FROM tag_aliases WHERE alias = ?1")
+            .bind(normalize_tag(alias))
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        Ok(deleted > 0)
+    }
+
+    /// Deletes the tag, removing it from all the content it is on, with its
+    /// aliases. Returns the tag as it was, none when it doesn't exist. Tag
+    /// rules adding it are left alone and add it again on new content.
+    pub async fn delete_tag(&self, tag: &str) -> Result<Option<TagSummary>, sqlx::Error> {
+        let tag = normalize_tag(tag);
+        let mut tx = self.pool.begin().await?;
+        let Some(summary) = summary_in(&mut tx, &tag).await? else {
+            return Ok(None);
+        };
+        for (table, _) in TAG_LINKS {
+            sqlx::query(&format!(
+                "DELETE FROM {table} WHERE tag_id = (SELECT id FROM tags WHERE name = ?1)"
+            ))
+            .bind(&tag)
+            .execute(&mut *tx)
+            .await?;
+        }
+        sqlx::query("DELETE FROM tag_aliases WHERE tag_id = (SELECT id FROM tags WHERE name = ?1)")
+            .bind(&tag)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM tags WHERE name = ?1")
+            .bind(&tag)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(Some(summary))
+    }
+}
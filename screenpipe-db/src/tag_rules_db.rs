@@ -0,0 +1,239 @@
+use chrono::{DateTime, Local, NaiveTime, Utc};
+use regex::Regex;
+use tracing::warn;
+
+use crate::{webhook_rule_matcher, DatabaseManager, NewTagRule, TagContentType, TagRule};
+
+const TAG_RULE_COLUMNS: &str = "id, name, tag, content_type, app_name, window_name, browser_url, pattern, is_regex, start_time, end_time, enabled, created_at";
+
+// app, window, browser url and timestamp of a frame
+type FrameSource = (
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    DateTime<Utc>,
+);
+
+/// Parses the `start_time` or `end_time` of a tag rule, HH:MM.
+pub fn tag_rule_time(time: &str) -> Result<NaiveTime, chrono::ParseError> {
+    NaiveTime::parse_from_str(time, "%H:%M")
+}
+
+/// What the tag rules are checked against.
+pub(crate) struct TagRuleContent<'a> {
+    pub content_type: TagContentType,
+    /// the frame or the audio chunk tagged
+    pub id: i64,
+    pub text: &'a str,
+    pub app_name: Option<&'a str>,
+    pub window_name: Option<&'a str>,
+    pub browser_url: Option<&'a str>,
+    pub timestamp: DateTime<Utc>,
+}
+
+fn contains_ignore_case(value: Option<&str>, filter: &Option<String>) -> bool {
+    filter.as_ref().map_or(true, |f| {
+        value.is_some_and(|value| value.to_lowercase().contains(&f.to_lowercase()))
+    })
+}
+
+fn within_time_of_day(rule: &TagRule, timestamp: DateTime<Utc>) -> bool {
+    let parse = |time: &Option<String>| match time.as_deref().map(tag_rule_time) {
+        Some(Ok(time)) => Ok(Some(time)),
+        Some(Err(e)) => Err(e),
+        None => Ok(None),
+    };
+    let (start, end) = match (parse(&rule.start_time), parse(&rule.end_time)) {
+        (Ok(start), Ok(end)) => (start, end),
+        _ => {
+            warn!("tag rule {} has an invalid time of day", rule.id);
+            return false;
+        }
+    };
+    let time = timestamp.with_timezone(&Local).time();
+    match (start, end) {
+        (Some(start), Some(end)) if start <= end => start <= time && time < end,
+        // e.g. 22:00 to 06:00
+        (Some(start), Some(end)) => start <= time || time < end,
+        (Some(start), None) => start <= time,
+        (None, Some(end)) => time < end,
+        (None, None) => true,
+    }
+}
+
+fn rule_matches(rule: &TagRule, matcher: Option<&Regex>, content: &TagRuleContent) -> bool {
+    if matcher.is_some_and(|matcher| !matcher.is_match(content.text)) {
+        return false;
+    }
+    contains_ignore_case(content.app_name, &rule.app_name)
+        && contains_ignore_case(content.window_name, &rule.window_name)
+        && contains_ignore_case(content.browser_url, &rule.browser_url)
+        && within_time_of_day(rule, content.timestamp)
+}
+
+impl DatabaseManager {
+    pub async fn insert_tag_rule(&self, rule: &NewTagRule) -> Result<TagRule, sqlx::Error> {
+        let rule = sqlx::query_as::<_, TagRule>(&format!(
+            r#"
+            INSERT INTO tag_rules (
+                name, tag, content_type, app_name, window_name, browser_url, pattern,
+                is_regex, start_time, end_time, created_at
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+            RETURNING {TAG_RULE_COLUMNS}
+            "#
+        ))
+        .bind(&rule.name)
+        .bind(&rule.tag)
+        .bind(&rule.content_type)
+        .bind(&rule.app_name)
+        .bind(&rule.window_name)
+        .bind(&rule.browser_url)
+        .bind(&rule.pattern)
+        .bind(rule.is_regex)
+        .bind(&rule.start_time)
+        .bind(&rule.end_time)
+        .bind(Utc::now())
+        .fetch_one(&self.pool)
+        .await;
+        self.tag_rules.invalidate();
+        rule
+    }
+
+    pub async fn list_tag_rules(&self) -> Result<Vec<TagRule>, sqlx::Error> {
+        sqlx::query_as::<_, TagRule>(&format!(
+            "SELECT {TAG_RULE_COLUMNS} FROM tag_rules ORDER BY id"
+        ))
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Returns the rule, none when it doesn't exist.
+    pub async fn set_tag_rule_enabled(
+        &self,
+        id: i64,
+        enabled: bool,
+    ) -> Result<Option<TagRule>, sqlx::Error> {
+        let rule = sqlx::query_as::<_, TagRule>(&format!(
+            "UPDATE tag_rules SET enabled = ?2 WHERE id = ?1 RETURNING {TAG_RULE_COLUMNS}"
+        ))
+        .bind(id)
+        .bind(enabled)
+        .fetch_optional(&self.pool)
+        .await;
+        self.tag_rules.invalidate();
+        rule
+    }
+
+    /// Returns whether the rule existed.
+    pub async fn delete_tag_rule(&self, id: i64) -> Result<bool, sqlx::Error> {
+        let deleted = sqlx::query("DELETE FROM tag_rules WHERE id = ?1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        self.tag_rules.invalidate();
+        Ok(deleted > 0)
+    }
+
+    /// The tags of the enabled rules `content` matches, each once.
+    pub(crate) async fn matching_tag_rules(
+        &self,
+        content: &TagRuleContent<'_>,
+    ) -> Result<Vec<String>, sqlx::Error> {
+        let content_type = match content.content_type {
+            TagContentType::Vision => "ocr",
+            TagContentType::Audio => "audio",
+        };
+        let rules = match self.tag_rules.get() {
+            Ok(rules) => rules,
+            Err(generation) => {
+                let rules = self.load_tag_rules().await?;
+                self.tag_rules.set(generation, rules)
+            }
+        };
+
+        let mut tags: Vec<String> = Vec::new();
+        for (rule, matcher) in rules.iter() {
+            if rule
+                .content_type
+                .as_deref()
+                .map_or(true, |rule_type| rule_type == content_type)
+                && rule_matches(rule, matcher.as_ref(), content)
+                && !tags.contains(&rule.tag)
+            {
+                tags.push(rule.tag.clone());
+            }
+        }
+        Ok(tags)
+    }
+
+    /// The enabled rules with their patterns compiled, those that don't
+    /// compile left out.
+    async fn load_tag_rules(&self) -> Result<Vec<(TagRule, Option<Regex>)>, sqlx::Error> {
+        let rules = sqlx::query_as::<_, TagRule>(&format!(
+            "SELECT {TAG_RULE_COLUMNS} FROM tag_rules WHERE enabled = 1 ORDER BY id"
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rules
+            .into_iter()
+            .filter_map(|rule| {
+                let matcher = match &rule.pattern {
+                    Some(pattern) => match webhook_rule_matcher(pattern, rule.is_regex) {
+                        Ok(matcher) => Some(matcher),
+                        Err(e) => {
+                            warn!("tag rule {} has an invalid pattern: {}", rule.id, e);
+                            return None;
+                        }
+                    },
+                    None => None,
+                };
+                Some((rule, matcher))
+            })
+            .collect())
+    }
+
+    /// Tags the frame or audio chunk of content just inserted with the tags
+    /// of the rules it matches.
+    pub(crate) async fn apply_tag_rules(&self, content: TagRuleContent<'_>) {
+        let result = match self.matching_tag_rules(&content).await {
+            Ok(tags) if tags.is_empty() => Ok(()),
+            Ok(tags) => self.add_tags(content.id, content.content_type, tags).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            warn!(
+                "failed to apply tag rules to {:?} {}: {}",
+                content.content_type, content.id, e
+            );
+        }
+    }
+
+    /// Applies the tag rules to the OCR text of a frame, reading where it was
+    /// seen.
+    pub(crate) async fn apply_ocr_tag_rules(&self, frame_id: i64, text: &str) {
+        let frame = sqlx::query_as::<_, FrameSource>(
+            "SELECT app_name, window_name, browser_url, timestamp FROM frames WHERE id = ?1",
+        )
+        .bind(frame_id)
+        .fetch_optional(&self.pool)
+        .await;
+        match frame {
+            Ok(Some((app_name, window_name, browser_url, timestamp))) => {
+                self.apply_tag_rules(TagRuleContent {
+                    content_type: TagContentType::Vision,
+                    id: frame_id,
+                    text,
+                    app_name: app_name.as_deref(),
+                    window_name: window_name.as_deref(),
+                    browser_url: browser_url.as_deref(),
+                    timestamp,
+                })
+                .await
+            }
+            Ok(None) => {}
+            Err(e) => warn!("failed to read frame {} for its tag rules: {}", frame_id, e),
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use chrono::Utc;
+
+use crate::{DatabaseManager, TagContentType, TagSuggestion, TagSuggestionSample};
+
+const TAG_SUGGESTION_COLUMNS: &str =
+    "id, content_type, content_id, tag, status, model, created_at, decided_at";
+
+fn content_type_name(content_type: TagContentType) -> &'static str {
+    match content_type {
+        TagContentType::Vision => "vision",
+        TagContentType::Audio => "audio",
+    }
+}
+
+impl DatabaseManager {
+    /// Up to `limit` frames and `limit` audio chunks picked at random among
+    /// the ones without tags, out of the trash and never sampled before.
+    pub async fn tag_suggestion_samples(
+        &self,
+        limit: u32,
+    ) -> Result<Vec<TagSuggestionSample>, sqlx::Error> {
+        let frames: Vec<(i64, String)> = sqlx::query_as(
+            r#"
+            SELECT frames.id, group_concat(ocr_text.text, ' ')
+            FROM frames
+            JOIN ocr_text ON ocr_text.frame_id = frames.id
+            WHERE frames.deleted_at IS NULL
+                AND length(trim(ocr_text.text)) > 0
+                AND NOT EXISTS (SELECT 1 FROM vision_tags WHERE vision_tags.vision_id = frames.id)
+                AND NOT EXISTS (
+                    SELECT 1 FROM tag_suggestion_samples
+                    WHERE content_type = 'vision' AND content_id = frames.id
+                )
+            GROUP BY frames.id
+            ORDER BY RANDOM()
+            LIMIT ?1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        let audio_chunks: Vec<(i64, String)> = sqlx::query_as(
+            r#"
+            SELECT audio_chunks.id, group_concat(audio_transcriptions.transcription, ' ')
+            FROM audio_chunks
+            JOIN audio_transcriptions ON audio_transcriptions.audio_chunk_id = audio_chunks.id
+            WHERE audio_chunks.deleted_at IS NULL
+                AND length(trim(audio_transcriptions.transcription)) > 0
+                AND NOT EXISTS (
+                    SELECT 1 FROM audio_tags WHERE audio_tags.audio_chunk_id = audio_chunks.id
+                )
+                AND NOT EXISTS (
+                    SELECT 1 FROM tag_suggestion_samples
+                    WHERE content_type = 'audio' AND content_id = audio_chunks.id
+                )
+            GROUP BY audio_chunks.id
+            ORDER BY RANDOM()
+            LIMIT ?1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let sample = |content_type: TagContentType| {
+            move |(content_id, text): (i64, String)| TagSuggestionSample {
+                content_type,
+                content_id,
+                text,
+            }
+        };
+        Ok(frames
+            .into_iter()
+            .map(sample(TagContentType::Vision))
+            .chain(audio_chunks.into_iter().map(sample(TagContentType::Audio)))
+            .collect())
+    }
+
+    /// Stores the tags suggested for a sample as pending and marks it
+    /// sampled, with no tags too. A tag already suggested for it is skipped.
+    pub async fn insert_tag_suggestions(
+        &self,
+        sample: &TagSuggestionSample,
+        tags: &[String],
+        model: &str,
+    ) -> Result<(), sqlx::Error> {
+        let content_type = content_type_name(sample.content_type);
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "INSERT OR IGNORE INTO tag_suggestion_samples (content_type, content_id, sampled_at) VALUES (?1, ?2, ?3)",
+        )
+        .bind(content_type)
+        .bind(sample.content_id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+        for tag in tags {
+            sqlx::query(
+                r#"
+                INSERT OR IGNORE INTO tag_suggestions (content_type, content_id, tag, model, created_at)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+                "#,
+            )
+            .bind(content_type)
+            .bind(sample.content_id)
+            .bind(tag)
+            .bind(model)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await
+    }
+
+    /// Newest first, of any status without one.
+    pub async fn list_tag_suggestions(
+        &self,
+        status: Option<&str>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<TagSuggestion>, sqlx::Error> {
+        sqlx::query_as::<_, TagSuggestion>(&format!(
+            r#"
+            SELECT {TAG_SUGGESTION_COLUMNS}
+            FROM tag_suggestions
+            WHERE ?1 IS NULL OR status = ?1
+            ORDER BY id DESC
+            LIMIT ?2 OFFSET ?3
+            "#
+        ))
+        .bind(status)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Accepts a pending suggestion, tagging its content, or rejects it.
+    /// Returns the suggestion, none when there is no pending one with this
+    /// id.
+    pub async fn decide_tag_suggestion(
+        &self,
+        id: i64,
+        accept: bool,
+    ) -> Result<Option<TagSuggestion>, sqlx::Error> {
+        let pending = sqlx::query_as::<_, TagSuggestion>(&format!(
+            "SELECT {TAG_SUGGESTION_COLUMNS} FROM tag_suggestions WHERE id = ?1 AND status = 'pending'"
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some(pending) = pending else {
+            return Ok(None);
+        };
+        if accept {
+            let content_type = match pending.content_type.as_str() {
+                "audio" => TagContentType::Audio,
+                _ => TagContentType::Vision,
+            };
+            self.add_tags(pending.content_id, content_type, vec![pending.tag])
+                .await?;
+        }
+        let suggestion = sqlx::query_as::<_, TagSuggestion>(&format!(
+            r#"
+            UPDATE tag_suggestions SET status = ?2, decided_at = ?3
+            WHERE id = ?1 AND status = 'pending'
+            RETURNING {TAG_SUGGESTION_COLUMNS}
+            "#
+        ))
+        .bind(id)
+        .bind(if accept { "accepted" } else { "rejected" })
+        .bind(Utc::now())
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(suggestion)
+    }
+}
@@ -0,0 +1,147 @@
+use chrono::{DateTime, Utc};
+use sqlx::Error as SqlxError;
+
+use crate::{DatabaseManager, StageProvenance, TagContentType, TextProvenance};
+
+fn content_type_name(content_type: &TagContentType) -> &'static str {
+    match content_type {
+        TagContentType::Vision => "vision",
+        TagContentType::Audio => "audio",
+    }
+}
+
+// the table holding the text and its key column
+fn text_column(content_type: &TagContentType) -> (&'static str, &'static str, &'static str) {
+    match content_type {
+        TagContentType::Vision => ("ocr_text", "text", "frame_id"),
+        TagContentType::Audio => ("audio_transcriptions", "transcription", "id"),
+    }
+}
+
+// the frame or the audio chunk of the transcription is in the trash
+fn trashed(content_type: &TagContentType) -> &'static str {
+    match content_type {
+        TagContentType::Vision => {
+            "EXISTS (SELECT 1 FROM frames WHERE frames.id = p.content_id AND frames.deleted_at IS NOT NULL)"
+        }
+        TagContentType::Audio => {
+            r#"EXISTS (
+                SELECT 1 FROM audio_transcriptions
+                JOIN audio_chunks ON audio_chunks.id = audio_transcriptions.audio_chunk_id
+                WHERE audio_transcriptions.id = p.content_id
+                    AND audio_chunks.deleted_at IS NOT NULL
+            )"#
+        }
+    }
+}
+
+const SET_PROVENANCE: &str = "INSERT OR REPLACE INTO text_provenance (content_type, content_id, raw_text, stages, processed_at) VALUES (?1, ?2, ?3, ?4, ?5)";
+
+fn stages_json(stages: &[StageProvenance]) -> String {
+    serde_json::to_string(stages).unwrap_or_else(|_| "[]".to_string())
+}
+
+impl DatabaseManager {
+    /// Records the text of a frame or a transcription as read, before the
+    /// post-processing `stages` ran on it, replacing what was recorded.
+    pub async fn set_text_provenance(
+        &self,
+        content_type: TagContentType,
+        id: i64,
+        raw_text: &str,
+        stages: &[StageProvenance],
+    ) -> Result<(), SqlxError> {
+        sqlx::query(SET_PROVENANCE)
+            .bind(content_type_name(&content_type))
+            .bind(id)
+            .bind(raw_text)
+            .bind(stages_json(stages))
+            .bind(Utc::now())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// The provenance of the OCR text of a frame or of a transcription, none
+    /// when no post-processing ran on it or it is in the trash.
+    pub async fn get_text_provenance(
+        &self,
+        content_type: TagContentType,
+        id: i64,
+    ) -> Result<Option<TextProvenance>, SqlxError> {
+        let (table, column, key) = text_column(&content_type);
+        let trashed = trashed(&content_type);
+        let row: Option<(String, String, DateTime<Utc>, Option<String>)> =
+            sqlx::query_as(&format!(
+                r#"
+                SELECT p.raw_text, p.stages, p.processed_at, t.{column}
+                FROM text_provenance p
+                LEFT JOIN {table} t ON t.{key} = p.content_id
+                WHERE p.content_type = ?1 AND p.content_id = ?2 AND NOT {trashed}
+                "#
+            ))
+            .bind(content_type_name(&content_type))
+            .bind(id)
+            .fetch_optional(&self.read_pool)
+            .await?;
+        let provenance = row.map(|(raw_text, stages, processed_at, text)| TextProvenance {
+            content_type: content_type_name(&content_type).to_string(),
+            content_id: id,
+            raw_text,
+            stages: serde_json::from_str(&stages).unwrap_or_default(),
+            processed_at,
+            text: text.unwrap_or_default(),
+        });
+        Ok(provenance)
+    }
+
+    /// The stored OCR text of a frame or text of a transcription.
+    pub async fn get_stored_text(
+        &self,
+        content_type: TagContentType,
+        id: i64,
+    ) -> Result<Option<String>, SqlxError> {
+        let (table, column, key) = text_column(&content_type);
+        sqlx::query_scalar(&format!("SELECT {column} FROM {table} WHERE {key} = ?1"))
+            .bind(id)
+            .fetch_optional(&self.read_pool)
+            .await
+    }
+
+    /// Stores `text` re-processed from `raw_text` by `stages`, with its
+    /// provenance. Returns false when the frame or transcription is gone.
+    pub async fn update_processed_text(
+        &self,
+        content_type: TagContentType,
+        id: i64,
+        raw_text: &str,
+        text: &str,
+        stages: &[StageProvenance],
+    ) -> Result<bool, SqlxError> {
+        let (table, column, key) = text_column(&content_type);
+        let mut tx = self.pool.begin().await?;
+        let updated = sqlx::query(&format!(
+            "UPDATE {table} SET {column} = ?1, text_length = ?2 WHERE {key} = ?3"
+        ))
+        .bind(text)
+        .bind(text.len() as i64)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if updated == 0 {
+            tx.rollback().await?;
+            return Ok(false);
+        }
+        sqlx::query(SET_PROVENANCE)
+            .bind(content_type_name(&content_type))
+            .bind(id)
+            .bind(raw_text)
+            .bind(stages_json(stages))
+            .bind(Utc::now())
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+
+use crate::TextBounds;
+
+// engines report a pixel or two of jitter for the same text on identical frames
+const BOUNDS_TOLERANCE: f32 = 2.0;
+// normalized bounds overshoot the image by a hair at its edges
+const NORMALIZED_TOLERANCE: f32 = 0.01;
+
+impl TextBounds {
+    /// Reads the `left`/`top`/`width`/`height` keys of a text_json block.
+    pub fn from_block(block: &HashMap<String, String>) -> Option<Self> {
+        let coord = |key: &str| {
+            block
+                .get(key)?
+                .trim()
+                .parse::<f32>()
+                .ok()
+                .filter(|v| v.is_finite())
+        };
+        let bounds = TextBounds {
+            left: coord("left")?,
+            top: coord("top")?,
+            width: coord("width")?,
+            height: coord("height")?,
+        };
+        (bounds.width > 0.0 && bounds.height > 0.0).then_some(bounds)
+    }
+
+    /// The bounds in fractions of the image the text was read from, from its
+    /// top left corner. Apple's engine reports them from the bottom left
+    /// corner. None for bounds in pixels, the size of the image isn't kept.
+    pub fn normalized(&self, ocr_engine: &str) -> Option<Self> {
+        let fits = |start: f32, size: f32| {
+            start >= -NORMALIZED_TOLERANCE && start + size <= 1.0 + NORMALIZED_TOLERANCE
+        };
+        if !fits(self.left, self.width) || !fits(self.top, self.height) {
+            return None;
+        }
+        let top = if ocr_engine == "AppleNative" {
+            1.0 - self.top - self.height
+        } else {
+            self.top
+        };
+        Some(TextBounds {
+            left: self.left.clamp(0.0, 1.0),
+            top: top.clamp(0.0, 1.0),
+            width: self.width.min(1.0),
+            height: self.height.min(1.0),
+        })
+    }
+
+    /// Whether two blocks occupy the same place on screen.
+    pub fn matches(&self, other: &TextBounds) -> bool {
+        (self.left - other.left).abs() <= BOUNDS_TOLERANCE
+            && (self.top - other.top).abs() <= BOUNDS_TOLERANCE
+            && (self.width - other.width).abs() <= BOUNDS_TOLERANCE
+            && (self.height - other.height).abs() <= BOUNDS_TOLERANCE
+    }
+}
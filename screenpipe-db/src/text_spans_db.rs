@@ -0,0 +1,170 @@
+use chrono::{DateTime, Utc};
+
+use crate::{DatabaseManager, OcrBlock, TextBounds, TextSpan};
+
+impl DatabaseManager {
+    /// Extends the span of every block that was already shown at the same
+    /// place in the previous frame of the same window and opens a span for
+    /// the others. Frames are stored per window, so the previous frame of the
+    /// device is usually another window of the same capture. Returns, per block, whether it continued an existing span.
+    pub(crate) async fn track_text_spans(
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        frame_id: i64,
+        blocks: &[OcrBlock],
+    ) -> Result<Vec<bool>, sqlx::Error> {
+        let frame = sqlx::query_as::<_, (String, DateTime<Utc>, Option<String>, Option<String>)>(
+            r#"
+            SELECT vc.device_name, f.timestamp, f.app_name, f.window_name
+            FROM frames f
+            JOIN video_chunks vc ON f.video_chunk_id = vc.id
+            WHERE f.id = ?1
+            "#,
+        )
+        .bind(frame_id)
+        .fetch_optional(&mut **tx)
+        .await?;
+        let Some((device_name, timestamp, app_name, window_name)) = frame else {
+            return Ok(vec![false; blocks.len()]);
+        };
+
+        let previous_frame_id = sqlx::query_scalar::<_, Option<i64>>(
+            r#"
+            SELECT MAX(f.id)
+            FROM frames f
+            JOIN video_chunks vc ON f.video_chunk_id = vc.id
+            WHERE vc.device_name = ?1 AND f.id < ?2
+                AND f.app_name IS ?3 AND f.window_name IS ?4
+            "#,
+        )
+        .bind(&device_name)
+        .bind(frame_id)
+        .bind(&app_name)
+        .bind(&window_name)
+        .fetch_one(&mut **tx)
+        .await?;
+
+        let mut open_spans: Vec<(i64, String, TextBounds)> = match previous_frame_id {
+            Some(previous_frame_id) => sqlx::query_as::<_, (i64, String, f32, f32, f32, f32)>(
+                r#"
+                SELECT id, text, x, y, width, height
+                FROM ocr_text_spans
+                WHERE device_name = ?1 AND last_frame_id = ?2
+                "#,
+            )
+            .bind(&device_name)
+            .bind(previous_frame_id)
+            .fetch_all(&mut **tx)
+            .await?
+            .into_iter()
+            .map(|(id, text, left, top, width, height)| {
+                (
+                    id,
+                    text,
+                    TextBounds {
+                        left,
+                        top,
+                        width,
+                        height,
+                    },
+                )
+            })
+            .collect(),
+            None => Vec::new(),
+        };
+
+        let mut continued = Vec::with_capacity(blocks.len());
+        for block in blocks {
+            let Some(bounds) = block.bounds else {
+                continued.push(false);
+                continue;
+            };
+
+            let span = open_spans.iter().position(|(_, text, span_bounds)| {
+                *text == block.text && span_bounds.matches(&bounds)
+            });
+            match span {
+                Some(index) => {
+                    let (span_id, _, _) = open_spans.swap_remove(index);
+                    sqlx::query(
+                        r#"
+                        UPDATE ocr_text_spans
+                        SET last_frame_id = ?1, last_seen = ?2, frame_count = frame_count + 1
+                        WHERE id = ?3
+                        "#,
+                    )
+                    .bind(frame_id)
+                    .bind(timestamp)
+                    .bind(span_id)
+                    .execute(&mut **tx)
+                    .await?;
+                    continued.push(true);
+                }
+                None => {
+                    sqlx::query(
+                        r#"
+                        INSERT INTO ocr_text_spans
+                            (device_name, text, x, y, width, height, first_frame_id, last_frame_id, first_seen, last_seen)
+                        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7, ?8, ?8)
+                        "#,
+                    )
+                    .bind(&device_name)
+                    .bind(&block.text)
+                    .bind(bounds.left)
+                    .bind(bounds.top)
+                    .bind(bounds.width)
+                    .bind(bounds.height)
+                    .bind(frame_id)
+                    .bind(timestamp)
+                    .execute(&mut **tx)
+                    .await?;
+                    continued.push(false);
+                }
+            }
+        }
+
+        Ok(continued)
+    }
+
+    /// Lists text spans overlapping the time range, optionally only those
+    /// containing `query` and shown for at least `min_duration_secs`.
+    pub async fn search_text_spans(
+        &self,
+        query: Option<&str>,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        min_duration_secs: f64,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<TextSpan>, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        guard
+            .run(
+                sqlx::query_as::<_, TextSpan>(
+                    r#"
+            SELECT *
+            FROM (
+                SELECT
+                    id, device_name, text, x, y, width, height,
+                    first_frame_id, last_frame_id, first_seen, last_seen, frame_count,
+                    (julianday(last_seen) - julianday(first_seen)) * 86400.0 AS duration_secs
+                FROM ocr_text_spans
+                WHERE (?1 IS NULL OR text LIKE '%' || ?1 || '%')
+                    AND (?2 IS NULL OR last_seen >= ?2)
+                    AND (?3 IS NULL OR first_seen <= ?3)
+            )
+            WHERE duration_secs >= ?4
+            ORDER BY first_seen DESC
+            LIMIT ?5 OFFSET ?6
+            "#,
+                )
+                .bind(query)
+                .bind(start_time)
+                .bind(end_time)
+                .bind(min_duration_secs)
+                .bind(limit)
+                .bind(offset)
+                .fetch_all(&mut *conn),
+            )
+            .await
+    }
+}
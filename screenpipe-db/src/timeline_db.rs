@@ -0,0 +1,129 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{DatabaseManager, Timeline, TimelineGap, TimelineSegment};
+
+impl DatabaseManager {
+    /// The segments of `start..end` with frames or transcriptions no further
+    /// apart than `max_gap`, and the gaps between them with why nothing was
+    /// recorded: a capture pause or idle time covering most of the gap, else
+    /// no reason. The period stops now at most.
+    pub async fn get_timeline(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        max_gap: Duration,
+    ) -> Result<Timeline, sqlx::Error> {
+        let end = end.min(Utc::now()).max(start);
+        let (mut conn, guard) = self.read_connection().await?;
+        // first and last sample, frames and transcriptions of each minute
+        let mut minutes: Vec<(DateTime<Utc>, DateTime<Utc>, i64, i64)> = guard
+            .run(
+                sqlx::query_as(
+                    r#"
+                    WITH samples AS (
+                        SELECT timestamp AS ts, 1 AS frames, 0 AS transcriptions
+                        FROM frames
+                        WHERE timestamp >= ?1 AND timestamp < ?2 AND deleted_at IS NULL
+                        UNION ALL
+                        SELECT timestamp, 0, 1
+                        FROM audio_transcriptions
+                        WHERE timestamp >= ?1 AND timestamp < ?2
+                    )
+                    SELECT MIN(ts), MAX(ts), SUM(frames), SUM(transcriptions)
+                    FROM samples
+                    GROUP BY strftime('%Y-%m-%d %H:%M', ts)
+                    "#,
+                )
+                .bind(start)
+                .bind(end)
+                .fetch_all(&mut *conn),
+            )
+            .await?;
+        minutes.sort_by_key(|(first, ..)| *first);
+
+        let mut segments: Vec<TimelineSegment> = Vec::new();
+        for (first, last, frames, transcriptions) in minutes {
+            match segments.last_mut() {
+                Some(segment) if first - segment.end <= max_gap => {
+                    segment.end = segment.end.max(last);
+                    segment.frames += frames;
+                    segment.transcriptions += transcriptions;
+                }
+                _ => segments.push(TimelineSegment {
+                    start: first,
+                    end: last,
+                    frames,
+                    transcriptions,
+                    frames_per_minute: 0.0,
+                }),
+            }
+        }
+        for segment in &mut segments {
+            // a segment of a single frame still lasts a minute
+            let minutes = ((segment.end - segment.start).num_seconds() as f64 / 60.0).max(1.0);
+            segment.frames_per_minute = segment.frames as f64 / minutes;
+        }
+
+        let mut bounds = vec![start];
+        for segment in &segments {
+            bounds.extend([segment.start, segment.end]);
+        }
+        bounds.push(end);
+        let pauses: Vec<(DateTime<Utc>, DateTime<Utc>)> = self
+            .list_capture_pauses(start, end)
+            .await?
+            .into_iter()
+            .filter(|pause| matches!(pause.content_type.as_str(), "all" | "vision"))
+            .map(|pause| (pause.started_at, pause.ended_at.unwrap_or(end)))
+            .collect();
+        let idle: Vec<(DateTime<Utc>, DateTime<Utc>)> = self
+            .get_idle_intervals(start, end)
+            .await?
+            .into_iter()
+            .map(|interval| (interval.started_at, interval.ended_at))
+            .collect();
+        let gaps = bounds
+            .chunks(2)
+            .filter_map(|pair| match pair {
+                [gap_start, gap_end] if *gap_end - *gap_start > max_gap => {
+                    let duration = *gap_end - *gap_start;
+                    let reason = if overlap(&pauses, *gap_start, *gap_end) * 2 >= duration {
+                        "paused"
+                    } else if overlap(&idle, *gap_start, *gap_end) * 2 >= duration {
+                        "idle"
+                    } else {
+                        "not_recording"
+                    };
+                    Some(TimelineGap {
+                        start: *gap_start,
+                        end: *gap_end,
+                        duration_secs: duration.num_milliseconds() as f64 / 1000.0,
+                        reason: reason.to_string(),
+                    })
+                }
+                _ => None,
+            })
+            .collect();
+
+        Ok(Timeline {
+            start,
+            end,
+            segments,
+            gaps,
+        })
+    }
+}
+
+// how much of `start..end` the `intervals` cover, counted twice where two
+// of them overlap
+fn overlap(
+    intervals: &[(DateTime<Utc>, DateTime<Utc>)],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Duration {
+    intervals
+        .iter()
+        .map(|(from, to)| (*to).min(end) - (*from).max(start))
+        .filter(|covered| *covered > Duration::zero())
+        .sum()
+}
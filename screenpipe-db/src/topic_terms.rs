@@ -0,0 +1,118 @@
+use std::collections::HashSet;
+
+// shorter words are rarely a topic
+const MIN_TERM_CHARS: usize = 4;
+// longer ones are mostly ocr noise, hashes and urls run together
+const MAX_TERM_CHARS: usize = 30;
+
+const STOPWORDS: &[&str] = &[
+    "about",
+    "after",
+    "again",
+    "also",
+    "been",
+    "before",
+    "being",
+    "both",
+    "came",
+    "come",
+    "could",
+    "does",
+    "done",
+    "down",
+    "each",
+    "even",
+    "every",
+    "from",
+    "gets",
+    "going",
+    "gonna",
+    "good",
+    "have",
+    "here",
+    "into",
+    "just",
+    "know",
+    "like",
+    "look",
+    "made",
+    "make",
+    "many",
+    "maybe",
+    "more",
+    "most",
+    "much",
+    "must",
+    "need",
+    "only",
+    "other",
+    "over",
+    "really",
+    "right",
+    "said",
+    "same",
+    "should",
+    "some",
+    "something",
+    "still",
+    "such",
+    "sure",
+    "take",
+    "than",
+    "that",
+    "their",
+    "them",
+    "then",
+    "there",
+    "these",
+    "they",
+    "thing",
+    "things",
+    "think",
+    "this",
+    "those",
+    "through",
+    "time",
+    "today",
+    "very",
+    "want",
+    "well",
+    "were",
+    "what",
+    "when",
+    "where",
+    "which",
+    "while",
+    "will",
+    "with",
+    "would",
+    "yeah",
+    "your",
+];
+
+/// The terms of a text: its words, lowercase, with short, long and common
+/// ones left out, and the pairs of such words next to each other, e.g.
+/// "kubernetes migration".
+pub fn document_terms(text: &str) -> HashSet<String> {
+    let mut terms = HashSet::new();
+    let mut previous: Option<String> = None;
+    for word in text.split(|c: char| !c.is_alphanumeric()) {
+        let chars = word.chars().count();
+        let word = word.to_lowercase();
+        let keep = (MIN_TERM_CHARS..=MAX_TERM_CHARS).contains(&chars)
+            && word.chars().all(char::is_alphabetic)
+            && !STOPWORDS.contains(&word.as_str());
+        if !keep {
+            previous = None;
+            continue;
+        }
+        if let Some(previous) = &previous {
+            if *previous != word {
+                terms.insert(format!("{} {}", previous, word));
+            }
+        }
+        terms.insert(word.clone());
+        previous = Some(word);
+    }
+    terms
+}
@@ -0,0 +1,209 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use futures::TryStreamExt;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use crate::topic_terms::document_terms;
+use crate::{DatabaseManager, PeriodTopic, TopicTerm};
+
+// terms in fewer texts of a day are left out
+const MIN_TERM_DOCUMENTS: i64 = 3;
+// days before the one extracted a term found on most of them weighs less
+const HISTORY_DAYS: i64 = 30;
+
+impl DatabaseManager {
+    /// Extracts the `limit` terms standing out on `day`, from `start` to
+    /// `end`, in the screen text and transcriptions, and keeps them in
+    /// place of the ones extracted before. A term scores the share of the
+    /// day's distinct texts holding it, times its inverse frequency over the
+    /// days extracted before.
+    pub async fn extract_day_terms(
+        &self,
+        day: NaiveDate,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<TopicTerm>, sqlx::Error> {
+        let mut seen: HashSet<u64> = HashSet::new();
+        let mut counts: HashMap<String, i64> = HashMap::new();
+        let mut texts = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT ocr_text.text
+            FROM ocr_text
+            JOIN frames f ON f.id = ocr_text.frame_id
+            WHERE f.timestamp >= ?1 AND f.timestamp < ?2 AND f.deleted_at IS NULL
+            UNION ALL
+            SELECT transcription FROM audio_transcriptions
+            WHERE timestamp >= ?1 AND timestamp < ?2
+            "#,
+        )
+        .bind(start)
+        .bind(end)
+        .fetch(&self.pool);
+        while let Some(text) = texts.try_next().await? {
+            // the same screen is read again and again
+            let mut hasher = DefaultHasher::new();
+            text.trim().hash(&mut hasher);
+            if !seen.insert(hasher.finish()) {
+                continue;
+            }
+            for term in document_terms(&text) {
+                *counts.entry(term).or_default() += 1;
+            }
+        }
+        drop(texts);
+        let documents = seen.len() as i64;
+
+        let history_start = day - Duration::days(HISTORY_DAYS);
+        let history_days: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM topic_days WHERE day >= ?1 AND day < ?2")
+                .bind(history_start)
+                .bind(day)
+                .fetch_one(&self.pool)
+                .await?;
+        let term_days: HashMap<String, i64> = sqlx::query_as::<_, (String, i64)>(
+            r#"
+            SELECT term, COUNT(*) FROM topic_terms
+            WHERE day >= ?1 AND day < ?2
+            GROUP BY term
+            "#,
+        )
+        .bind(history_start)
+        .bind(day)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .collect();
+
+        let mut terms: Vec<TopicTerm> = counts
+            .into_iter()
+            .filter(|(_, count)| *count >= MIN_TERM_DOCUMENTS)
+            .map(|(term, count)| {
+                let share = count as f64 / documents as f64;
+                let days = term_days.get(&term).copied().unwrap_or(0);
+                let idf = ((1 + history_days) as f64 / (1 + days) as f64).ln() + 1.0;
+                TopicTerm {
+                    day,
+                    term,
+                    documents: count,
+                    share,
+                    score: share * idf,
+                }
+            })
+            .collect();
+        terms.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.term.cmp(&b.term)));
+        terms.truncate(limit);
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM topic_terms WHERE day = ?1")
+            .bind(day)
+            .execute(&mut *tx)
+            .await?;
+        for term in &terms {
+            sqlx::query(
+                r#"
+                INSERT INTO topic_terms (day, term, documents, share, score)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+                "#,
+            )
+            .bind(day)
+            .bind(&term.term)
+            .bind(term.documents)
+            .bind(term.share)
+            .bind(term.score)
+            .execute(&mut *tx)
+            .await?;
+        }
+        sqlx::query(
+            r#"
+            INSERT INTO topic_days (day, documents) VALUES (?1, ?2)
+            ON CONFLICT (day) DO UPDATE SET
+                documents = excluded.documents,
+                computed_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(day)
+        .bind(documents)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(terms)
+    }
+
+    /// The days from `first` to `last` whose terms were extracted.
+    pub async fn get_topic_days(
+        &self,
+        first: NaiveDate,
+        last: NaiveDate,
+    ) -> Result<Vec<NaiveDate>, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        guard
+            .run(
+                sqlx::query_scalar(
+                    "SELECT day FROM topic_days WHERE day >= ?1 AND day <= ?2 ORDER BY day",
+                )
+                .bind(first)
+                .bind(last)
+                .fetch_all(&mut *conn),
+            )
+            .await
+    }
+
+    /// The terms standing out the most from `first` to `last`, over all
+    /// their days.
+    pub async fn get_top_topics(
+        &self,
+        first: NaiveDate,
+        last: NaiveDate,
+        limit: u32,
+    ) -> Result<Vec<PeriodTopic>, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        guard
+            .run(
+                sqlx::query_as(
+                    r#"
+                    SELECT term, COUNT(*) AS days, SUM(documents) AS documents, SUM(score) AS score
+                    FROM topic_terms
+                    WHERE day >= ?1 AND day <= ?2
+                    GROUP BY term
+                    ORDER BY score DESC, term
+                    LIMIT ?3
+                    "#,
+                )
+                .bind(first)
+                .bind(last)
+                .bind(limit)
+                .fetch_all(&mut *conn),
+            )
+            .await
+    }
+
+    /// How `terms`, lowercase words one space apart, stood out each day from `first` to `last`,
+    /// the days they didn't left out, oldest first.
+    pub async fn get_topic_trend(
+        &self,
+        terms: &[String],
+        first: NaiveDate,
+        last: NaiveDate,
+    ) -> Result<Vec<TopicTerm>, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        guard
+            .run(
+                sqlx::query_as(
+                    r#"
+                    SELECT day, term, documents, share, score
+                    FROM topic_terms
+                    WHERE term IN (SELECT value FROM json_each(?1))
+                        AND day >= ?2 AND day <= ?3
+                    ORDER BY day, term
+                    "#,
+                )
+                .bind(serde_json::to_string(terms).unwrap_or_else(|_| "[]".to_string()))
+                .bind(first)
+                .bind(last)
+                .fetch_all(&mut *conn),
+            )
+            .await
+    }
+}
@@ -0,0 +1,350 @@
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+
+use crate::retention_db::RuleCounts;
+use crate::{DatabaseManager, PrunedData, RetentionRule, TrashCount, TrashGroup, TrashItem};
+
+impl DatabaseManager {
+    /// Moves up to `limit` frames and `limit` audio chunks past their
+    /// retention to the trash. They disappear from search but stay on disk
+    /// until `purge_trash`.
+    pub async fn trash_expired(
+        &self,
+        now: DateTime<Utc>,
+        default_days: Option<u32>,
+        rules: &[RetentionRule],
+        limit: u32,
+    ) -> Result<TrashCount, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let expired = Self::find_expired(&mut tx, now, default_days, rules, limit).await?;
+
+        let mut counts: HashMap<&str, RuleCounts> = HashMap::new();
+        for (frame_id, _, rule) in &expired.frames {
+            sqlx::query("UPDATE frames SET deleted_at = ?1, trashed_by = ?3 WHERE id = ?2")
+                .bind(now)
+                .bind(frame_id)
+                .bind(rule)
+                .execute(&mut *tx)
+                .await?;
+            counts.entry(rule).or_default().trashed_frames += 1;
+        }
+        for (audio_chunk_id, rule) in &expired.audio_chunks {
+            sqlx::query("UPDATE audio_chunks SET deleted_at = ?1, trashed_by = ?3 WHERE id = ?2")
+                .bind(now)
+                .bind(audio_chunk_id)
+                .bind(rule)
+                .execute(&mut *tx)
+                .await?;
+            counts.entry(rule).or_default().trashed_audio_chunks += 1;
+        }
+        for (rule, counts) in counts {
+            Self::add_retention_stats(&mut tx, rule, counts, now).await?;
+        }
+
+        tx.commit().await?;
+        Ok(TrashCount {
+            frames: expired.frames.len() as u64,
+            audio_chunks: expired.audio_chunks.len() as u64,
+            ..Default::default()
+        })
+    }
+
+    /// How much of the trash `purge_trash` would delete.
+    pub async fn count_purgeable(
+        &self,
+        trashed_before: DateTime<Utc>,
+        purge_now: &[String],
+    ) -> Result<TrashCount, sqlx::Error> {
+        let (frames, audio_chunks, ui_rows): (i64, i64, i64) = sqlx::query_as(&format!(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM frames WHERE {PURGEABLE}),
+                (SELECT COUNT(*) FROM audio_chunks WHERE {PURGEABLE}),
+                (SELECT COUNT(*) FROM ui_monitoring WHERE {PURGEABLE})
+                    + (SELECT COUNT(*) FROM ui_traversals WHERE {PURGEABLE})
+                    + (SELECT COUNT(*) FROM clipboard WHERE {PURGEABLE})
+            "#
+        ))
+        .bind(trashed_before)
+        .bind(names_json(purge_now))
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(TrashCount {
+            frames: frames as u64,
+            audio_chunks: audio_chunks as u64,
+            ui_rows: ui_rows as u64,
+        })
+    }
+
+    /// Deletes up to `limit` frames, `limit` audio chunks, `limit` rows of
+    /// each kind of ui text and `limit` clipboard entries trashed before `trashed_before`, or by one of the
+    /// `purge_now` rules whenever it was, for good.
+    pub async fn purge_trash(
+        &self,
+        trashed_before: DateTime<Utc>,
+        purge_now: &[String],
+        limit: u32,
+    ) -> Result<PrunedData, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let purge_now = names_json(purge_now);
+        let purgeable = |table: &str, columns: &str| {
+            format!("SELECT {columns} FROM {table} WHERE {PURGEABLE} ORDER BY deleted_at LIMIT ?3")
+        };
+
+        let frames: Vec<(i64, i64, Option<String>)> =
+            sqlx::query_as(&purgeable("frames", "id, video_chunk_id, trashed_by"))
+                .bind(trashed_before)
+                .bind(&purge_now)
+                .bind(limit)
+                .fetch_all(&mut *tx)
+                .await?;
+        let mut trashed = HashMap::new();
+        for table in [
+            "audio_chunks",
+            "ui_monitoring",
+            "ui_traversals",
+            "clipboard",
+        ] {
+            let rows: Vec<(i64, Option<String>)> =
+                sqlx::query_as(&purgeable(table, "id, trashed_by"))
+                    .bind(trashed_before)
+                    .bind(&purge_now)
+                    .bind(limit)
+                    .fetch_all(&mut *tx)
+                    .await?;
+            trashed.insert(table, rows);
+        }
+        let ids = |table: &str| -> Vec<i64> { trashed[table].iter().map(|(id, _)| *id).collect() };
+
+        // what was trashed at the api isn't counted for any rule
+        let mut counts: HashMap<&str, RuleCounts> = HashMap::new();
+        for rule in frames.iter().filter_map(|(_, _, rule)| rule.as_deref()) {
+            counts.entry(rule).or_default().purged_frames += 1;
+        }
+        for (table, rows) in &trashed {
+            for rule in rows.iter().filter_map(|(_, rule)| rule.as_deref()) {
+                let counts = counts.entry(rule).or_default();
+                match *table {
+                    "audio_chunks" => counts.purged_audio_chunks += 1,
+                    _ => counts.deleted_ui_rows += 1,
+                }
+            }
+        }
+        let now = Utc::now();
+        for (rule, counts) in counts {
+            Self::add_retention_stats(&mut tx, rule, counts, now).await?;
+        }
+
+        let frames: Vec<(i64, i64)> = frames
+            .iter()
+            .map(|(id, chunk_id, _)| (*id, *chunk_id))
+            .collect();
+        let mut pruned = PrunedData::default();
+        Self::delete_frames(&mut tx, &frames, &mut pruned).await?;
+        Self::delete_audio_chunks(&mut tx, &ids("audio_chunks"), &mut pruned).await?;
+        let ui_ids = names_json(&ids("ui_monitoring"));
+        for query in [
+            "DELETE FROM ui_monitoring_held WHERE ui_monitoring_id IN (SELECT value FROM json_each(?1))",
+            "DELETE FROM ui_monitoring WHERE id IN (SELECT value FROM json_each(?1))",
+        ] {
+            sqlx::query(query).bind(&ui_ids).execute(&mut *tx).await?;
+        }
+        pruned.ui_rows = trashed["ui_monitoring"].len() as u64
+            + Self::delete_ui_traversals(&mut tx, &ids("ui_traversals")).await?;
+        pruned.ui_rows +=
+            sqlx::query("DELETE FROM clipboard WHERE id IN (SELECT value FROM json_each(?1))")
+                .bind(names_json(&ids("clipboard")))
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+
+        tx.commit().await?;
+        Ok(pruned)
+    }
+
+    /// What the trash holds, by day and app. `purge_delay` is how long items
+    /// stay in the trash.
+    pub async fn list_trash(&self, purge_delay: Duration) -> Result<Vec<TrashGroup>, sqlx::Error> {
+        let mut groups = sqlx::query_as::<_, TrashGroup>(
+            r#"
+            SELECT
+                day,
+                app_name,
+                SUM(frame_count) AS frame_count,
+                SUM(audio_chunk_count) AS audio_chunk_count,
+                SUM(ui_row_count) AS ui_row_count,
+                MIN(deleted_at) AS deleted_at
+            FROM (
+                SELECT
+                    date(timestamp) AS day,
+                    app_name,
+                    1 AS frame_count,
+                    0 AS audio_chunk_count,
+                    0 AS ui_row_count,
+                    deleted_at
+                FROM frames
+                WHERE deleted_at IS NOT NULL
+                UNION ALL
+                SELECT date(timestamp), NULL, 0, 1, 0, deleted_at
+                FROM audio_chunks
+                WHERE deleted_at IS NOT NULL
+                UNION ALL
+                SELECT date(timestamp), app, 0, 0, 1, deleted_at
+                FROM ui_monitoring
+                WHERE deleted_at IS NOT NULL
+                UNION ALL
+                SELECT date(timestamp), app, 0, 0, 1, deleted_at
+                FROM ui_traversals
+                WHERE deleted_at IS NOT NULL
+                UNION ALL
+                SELECT date(timestamp), app_name, 0, 0, 1, deleted_at
+                FROM clipboard
+                WHERE deleted_at IS NOT NULL
+            )
+            GROUP BY day, app_name
+            ORDER BY day, app_name
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        for group in &mut groups {
+            group.purge_at = group.deleted_at + purge_delay;
+        }
+        Ok(groups)
+    }
+
+    /// Items in the trash, optionally only those of one day (YYYY-MM-DD) and
+    /// app. Audio has no app and is left out when `app_name` is set.
+    pub async fn list_trash_items(
+        &self,
+        purge_delay: Duration,
+        day: Option<&str>,
+        app_name: Option<&str>,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<TrashItem>, sqlx::Error> {
+        let mut items = sqlx::query_as::<_, TrashItem>(
+            r#"
+            SELECT * FROM (
+                SELECT
+                    'frame' AS kind,
+                    id,
+                    timestamp,
+                    app_name,
+                    window_name,
+                    deleted_at
+                FROM frames
+                WHERE deleted_at IS NOT NULL
+                    AND (?1 IS NULL OR date(timestamp) = ?1)
+                    AND (?2 IS NULL OR app_name = ?2)
+                UNION ALL
+                SELECT
+                    'audio' AS kind,
+                    id,
+                    timestamp,
+                    NULL AS app_name,
+                    NULL AS window_name,
+                    deleted_at
+                FROM audio_chunks
+                WHERE deleted_at IS NOT NULL
+                    AND (?1 IS NULL OR date(timestamp) = ?1)
+                    AND ?2 IS NULL
+                UNION ALL
+                SELECT 'ui' AS kind, id, timestamp, app AS app_name, window AS window_name, deleted_at
+                FROM ui_monitoring
+                WHERE deleted_at IS NOT NULL
+                    AND (?1 IS NULL OR date(timestamp) = ?1)
+                    AND (?2 IS NULL OR app = ?2)
+                UNION ALL
+                SELECT 'ui_traversal' AS kind, id, timestamp, app AS app_name, window AS window_name, deleted_at
+                FROM ui_traversals
+                WHERE deleted_at IS NOT NULL
+                    AND (?1 IS NULL OR date(timestamp) = ?1)
+                    AND (?2 IS NULL OR app = ?2)
+                UNION ALL
+                SELECT 'clipboard' AS kind, id, timestamp, app_name, window_name, deleted_at
+                FROM clipboard
+                WHERE deleted_at IS NOT NULL
+                    AND (?1 IS NULL OR date(timestamp) = ?1)
+                    AND (?2 IS NULL OR app_name = ?2)
+            )
+            ORDER BY timestamp
+            LIMIT ?3 OFFSET ?4
+            "#,
+        )
+        .bind(day)
+        .bind(app_name)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        for item in &mut items {
+            item.purge_at = item.deleted_at + purge_delay;
+        }
+        Ok(items)
+    }
+
+    /// Takes items out of the trash, by id or by day (YYYY-MM-DD) and app.
+    /// Restored items are exempt from retention from then on.
+    pub async fn restore_trash(
+        &self,
+        frame_ids: &[i64],
+        audio_chunk_ids: &[i64],
+        ui_ids: &[i64],
+        ui_traversal_ids: &[i64],
+        clipboard_ids: &[i64],
+        day: Option<&str>,
+        app_name: Option<&str>,
+    ) -> Result<TrashCount, sqlx::Error> {
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+        let mut restored = TrashCount::default();
+        for (table, ids, app_column) in [
+            ("frames", frame_ids, Some("app_name")),
+            ("audio_chunks", audio_chunk_ids, None),
+            ("ui_monitoring", ui_ids, Some("app")),
+            ("ui_traversals", ui_traversal_ids, Some("app")),
+            ("clipboard", clipboard_ids, Some("app_name")),
+        ] {
+            // audio has no app, a day of an app leaves it out
+            let app_filter = match app_column {
+                Some(column) => format!("(?4 IS NULL OR {column} = ?4)"),
+                None => "?4 IS NULL".to_string(),
+            };
+            let rows = sqlx::query(&format!(
+                r#"
+                UPDATE {table} SET deleted_at = NULL, trashed_by = NULL, restored_at = ?1
+                WHERE deleted_at IS NOT NULL
+                    AND (
+                        id IN (SELECT value FROM json_each(?2))
+                        OR (?3 IS NOT NULL AND date(timestamp) = ?3 AND {app_filter})
+                    )
+                "#
+            ))
+            .bind(now)
+            .bind(names_json(ids))
+            .bind(day)
+            .bind(app_name)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            match table {
+                "frames" => restored.frames = rows,
+                "audio_chunks" => restored.audio_chunks = rows,
+                _ => restored.ui_rows += rows,
+            }
+        }
+
+        tx.commit().await?;
+        Ok(restored)
+    }
+}
+
+// trashed before ?1, or by one of the rules in the json array ?2
+const PURGEABLE: &str = "(deleted_at < ?1 OR trashed_by IN (SELECT value FROM json_each(?2)))";
+
+fn names_json<T: serde::Serialize>(values: &[T]) -> String {
+    serde_json::to_string(values).unwrap_or_else(|_| "[]".to_string())
+}
@@ -1,7 +1,9 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use oasgen::OaSchema;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+
+use crate::{ClickContentType, EmbeddingQuantization, OcrTable};
 use std::error::Error as StdError;
 use std::fmt::{self, Display};
 
@@ -21,6 +23,7 @@ pub enum SearchResult {
     OCR(OCRResult),
     Audio(AudioResult),
     UI(UiContent),
+    Clipboard(ClipboardEntry),
 }
 
 #[derive(FromRow, Debug)]
@@ -46,6 +49,14 @@ pub struct OCRResultRaw {
     pub tags: Option<String>,
     pub browser_url: Option<String>,
     pub focused: Option<bool>,
+    #[sqlx(default)]
+    pub tables: Option<String>,
+    #[sqlx(default)]
+    pub device_name: Option<String>,
+    #[sqlx(default)]
+    pub browser_title: Option<String>,
+    #[sqlx(default)]
+    pub browser_selection: Option<String>,
 }
 
 #[derive(OaSchema, Debug, Serialize, Deserialize)]
@@ -63,6 +74,48 @@ pub struct OCRResult {
     pub tags: Vec<String>,
     pub browser_url: Option<String>,
     pub focused: Option<bool>,
+    /// tables detected in the frame's layout
+    #[serde(default)]
+    pub tables: Vec<OcrTable>,
+    /// monitor the frame was captured from
+    #[serde(default)]
+    pub device_name: Option<String>,
+    /// title of the tab the browser extension reported around the frame
+    #[serde(default)]
+    pub browser_title: Option<String>,
+    /// text selected in that tab
+    #[serde(default)]
+    pub browser_selection: Option<String>,
+}
+
+impl From<OCRResultRaw> for OCRResult {
+    fn from(raw: OCRResultRaw) -> Self {
+        OCRResult {
+            frame_id: raw.frame_id,
+            ocr_text: raw.ocr_text,
+            text_json: raw.text_json,
+            timestamp: raw.timestamp,
+            frame_name: raw.frame_name,
+            file_path: raw.file_path,
+            offset_index: raw.offset_index,
+            app_name: raw.app_name,
+            ocr_engine: raw.ocr_engine,
+            window_name: raw.window_name,
+            tags: raw
+                .tags
+                .map(|t| t.split(',').map(String::from).collect())
+                .unwrap_or_default(),
+            browser_url: raw.browser_url,
+            focused: raw.focused,
+            tables: raw
+                .tables
+                .and_then(|t| serde_json::from_str(&t).ok())
+                .unwrap_or_default(),
+            device_name: raw.device_name,
+            browser_title: raw.browser_title,
+            browser_selection: raw.browser_selection,
+        }
+    }
 }
 
 #[derive(OaSchema, Debug, Deserialize, PartialEq, Default, Clone)]
@@ -82,6 +135,7 @@ pub enum ContentType {
     #[serde(rename = "audio+ocr")]
     #[serde(alias = "audio ocr")]
     AudioAndOcr,
+    Clipboard,
 }
 
 #[derive(FromRow)]
@@ -100,7 +154,7 @@ pub struct AudioResultRaw {
     pub end_time: Option<f64>,
 }
 
-#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone)]
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
 pub struct Speaker {
     pub id: i64,
     pub name: String,
@@ -129,13 +183,29 @@ pub struct AudioResult {
     pub end_time: Option<f64>,
 }
 
-#[derive(OaSchema, Debug, Deserialize, PartialEq)]
+#[derive(OaSchema, Debug, Clone, Copy, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum TagContentType {
     Vision,
     Audio,
 }
 
+/// Tags search results are filtered by. Each tag matches itself and the tags
+/// below it, `project/alpha` matches `project/alpha/design`, and an alias
+/// matches as the tag it points to.
+#[derive(OaSchema, Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct TagFilter {
+    /// content with at least one of these tags
+    #[serde(default)]
+    pub any: Vec<String>,
+    /// content with every one of these tags
+    #[serde(default)]
+    pub all: Vec<String>,
+    /// content with none of these tags
+    #[serde(default)]
+    pub none: Vec<String>,
+}
+
 #[derive(OaSchema, Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub struct UiContent {
     pub id: i64,
@@ -151,6 +221,24 @@ pub struct UiContent {
     pub offset_index: i64,
     pub frame_name: Option<String>,
     pub browser_url: Option<String>,
+    /// monitor of the frame captured with the UI text
+    #[sqlx(default)]
+    #[serde(default)]
+    pub device_name: Option<String>,
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Text copied to the clipboard.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, FromRow, PartialEq)]
+pub struct ClipboardEntry {
+    pub id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub text: String,
+    /// the focused app and window when the text was copied
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
 }
 
 #[derive(OaSchema, Debug, Clone)]
@@ -239,9 +327,28 @@ pub struct TextPosition {
     pub text: String,
     pub confidence: f32,
     pub bounds: TextBounds,
+    /// none when the engine reports the bounds in pixels
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<FrameRegionLink>,
 }
 
-#[derive(OaSchema, Debug, Serialize, Clone)]
+/// A stable link to where a text sits in a frame.
+#[derive(OaSchema, Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FrameRegionLink {
+    pub frame_id: i64,
+    /// in fractions of the image the text was read from, from its top left
+    /// corner
+    pub bounds: TextBounds,
+    /// the frame's place in its video chunk
+    pub offset_index: i64,
+    /// seconds from the first frame of the video chunk
+    pub video_offset_secs: f64,
+    /// the frame cropped to the region, `&mode=annotate` for the whole frame
+    /// with the region outlined
+    pub url: String,
+}
+
+#[derive(OaSchema, Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
 pub struct TextBounds {
     pub left: f32,
     pub top: f32,
@@ -271,6 +378,9 @@ pub struct FrameRow {
     pub window_name: String,
     pub ocr_text: String,
     pub text_json: String,
+    pub ocr_engine: String,
+    pub offset_index: i64,
+    pub video_offset_secs: f64,
 }
 
 #[derive(Deserialize, OaSchema, PartialEq, Default)]
@@ -305,6 +415,18 @@ pub enum OcrEngine {
     WindowsNative,
     AppleNative,
     Custom(CustomOcrConfig),
+    /// a provider registered at runtime, as `<name>/<version>`
+    Provider(String),
+}
+
+impl OcrEngine {
+    /// What the ocr_engine column records for the text the engine reads.
+    pub fn column_value(&self) -> String {
+        match self {
+            OcrEngine::Provider(id) => id.clone(),
+            engine => format!("{:?}", engine),
+        }
+    }
 }
 
 #[derive(OaSchema, Debug, Clone, Serialize, Deserialize)]
@@ -323,3 +445,1389 @@ impl Default for CustomOcrConfig {
         }
     }
 }
+
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct Receipt {
+    pub id: i64,
+    pub frame_id: i64,
+    pub vendor: Option<String>,
+    pub receipt_date: Option<String>,
+    pub total: Option<f64>,
+    pub currency: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
+}
+
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct MonitorSettings {
+    pub monitor_id: i64,
+    pub enabled: bool,
+    /// overrides the global capture fps when set
+    pub fps: Option<f64>,
+    /// 1.0 keeps the native resolution, 2.0 halves width and height
+    pub downscale_factor: f64,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct Meeting {
+    pub id: i64,
+    pub meeting_app: String,
+    pub start_time: DateTime<Utc>,
+    pub end_time: Option<DateTime<Utc>>,
+    /// "live" when reported by the ui events, "detected" when found
+    /// afterwards in what was recorded
+    pub source: String,
+    /// from the calendar hint it was detected over
+    pub title: Option<String>,
+}
+
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct MeetingParticipant {
+    pub id: i64,
+    pub meeting_id: i64,
+    pub name: String,
+    /// the label carried a "(You)"/"(Me)" marker, i.e. it is the local user
+    pub is_local: bool,
+    pub speaker_id: Option<i64>,
+    /// last frame the label was seen on
+    pub frame_id: Option<i64>,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct MeetingSlide {
+    pub id: i64,
+    pub meeting_id: i64,
+    /// position in the reconstructed deck, by first appearance
+    pub slide_index: i64,
+    /// frame holding the most complete rendering of the slide
+    pub frame_id: i64,
+    pub text: String,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct WhiteboardImage {
+    pub frame_id: i64,
+    /// the photo as it was ingested
+    pub original_path: String,
+    /// the straightened and contrast-cleaned copy used for OCR
+    pub enhanced_path: String,
+}
+
+#[derive(OaSchema, Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq)]
+pub struct TrashCount {
+    pub frames: u64,
+    pub audio_chunks: u64,
+    /// rows of ui text, ui traversals and clipboard entries
+    pub ui_rows: u64,
+}
+
+/// Trashed recordings of one day, frames and ui text grouped by app and audio
+/// on its own.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct TrashGroup {
+    /// YYYY-MM-DD, in UTC
+    pub day: String,
+    /// none for audio
+    pub app_name: Option<String>,
+    pub frame_count: i64,
+    pub audio_chunk_count: i64,
+    /// rows of ui text, ui traversals and clipboard entries
+    pub ui_row_count: i64,
+    /// when the first item of the group was trashed
+    pub deleted_at: DateTime<Utc>,
+    /// when the group starts being purged for good
+    #[sqlx(skip)]
+    pub purge_at: DateTime<Utc>,
+}
+
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct TrashItem {
+    /// "frame", "audio", "ui", "ui_traversal" or "clipboard"
+    pub kind: String,
+    /// frame id, audio chunk id, ui row id, ui traversal id or clipboard id
+    pub id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
+    pub deleted_at: DateTime<Utc>,
+    #[sqlx(skip)]
+    pub purge_at: DateTime<Utc>,
+}
+
+/// Tombstone left when a frame is redacted after capture.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct FrameRedaction {
+    pub id: i64,
+    pub frame_id: i64,
+    /// redacted area in pixels, none when the whole frame was redacted
+    pub region_left: Option<f32>,
+    pub region_top: Option<f32>,
+    pub region_width: Option<f32>,
+    pub region_height: Option<f32>,
+    pub reason: Option<String>,
+    pub redacted_at: DateTime<Utc>,
+}
+
+/// A text block that stayed on screen, unchanged and in place, over a run of
+/// consecutive frames
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct TextSpan {
+    pub id: i64,
+    pub device_name: String,
+    pub text: String,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub first_frame_id: i64,
+    pub last_frame_id: i64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub frame_count: i64,
+    /// seconds between the first and last frame showing the text
+    pub duration_secs: f64,
+}
+
+/// Keyword or regex watched in new OCR text and transcriptions. A match is
+/// posted to `webhook_url`.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct WebhookRule {
+    pub id: i64,
+    pub name: String,
+    pub pattern: String,
+    pub is_regex: bool,
+    /// "ocr", "audio" or none for both
+    pub content_type: Option<String>,
+    /// only for ocr, matches part of the app name
+    pub app_name: Option<String>,
+    /// only for ocr, matches part of the window name
+    pub window_name: Option<String>,
+    /// only for audio
+    pub speaker_id: Option<i64>,
+    pub webhook_url: String,
+    /// minimum time between two posts of the rule
+    pub cooldown_secs: i64,
+    pub enabled: bool,
+    pub last_fired_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(OaSchema, Debug, Serialize, Deserialize, Clone)]
+pub struct NewWebhookRule {
+    pub name: String,
+    pub pattern: String,
+    #[serde(default)]
+    pub is_regex: bool,
+    pub content_type: Option<String>,
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
+    pub speaker_id: Option<i64>,
+    pub webhook_url: String,
+    #[serde(default = "default_webhook_cooldown_secs")]
+    pub cooldown_secs: i64,
+}
+
+fn default_webhook_cooldown_secs() -> i64 {
+    300
+}
+
+/// A level of the tag hierarchy, see `tag_facets`.
+#[derive(OaSchema, Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TagFacet {
+    /// the full tag, e.g. `project/alpha`
+    pub tag: String,
+    /// frames and audio chunks tagged with it or a tag below it
+    pub count: i64,
+    /// whether there are tags below it
+    pub has_children: bool,
+}
+
+/// A tag with the content it is on and its aliases.
+#[derive(OaSchema, Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TagSummary {
+    pub name: String,
+    pub vision_count: i64,
+    pub audio_count: i64,
+    pub ui_count: i64,
+    pub aliases: Vec<String>,
+}
+
+/// A tag an llm suggested for a frame or an audio chunk, applied once
+/// accepted.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct TagSuggestion {
+    pub id: i64,
+    /// "vision" or "audio"
+    pub content_type: String,
+    /// the frame or the audio chunk
+    pub content_id: i64,
+    pub tag: String,
+    /// "pending", "accepted" or "rejected"
+    pub status: String,
+    /// the model that suggested it
+    pub model: String,
+    pub created_at: DateTime<Utc>,
+    pub decided_at: Option<DateTime<Utc>>,
+}
+
+/// Untagged content to ask tag suggestions for, with its text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagSuggestionSample {
+    pub content_type: TagContentType,
+    pub content_id: i64,
+    pub text: String,
+}
+
+/// What became of renaming, merging or aliasing a tag.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TagUpdate {
+    /// the tag as it is now
+    Updated(TagSummary),
+    NotFound,
+    /// the new name is already a tag or an alias
+    NameTaken,
+}
+
+/// Tags new OCR text and transcriptions matching every condition it sets,
+/// e.g. "meeting" for zoom or "research" for arxiv.org urls.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct TagRule {
+    pub id: i64,
+    pub name: String,
+    pub tag: String,
+    /// "ocr", "audio" or none for both
+    pub content_type: Option<String>,
+    /// only for ocr, matches part of the app name
+    pub app_name: Option<String>,
+    /// only for ocr, matches part of the window name
+    pub window_name: Option<String>,
+    /// only for ocr, matches part of the browser url
+    pub browser_url: Option<String>,
+    /// keyword or regex found in the text
+    pub pattern: Option<String>,
+    pub is_regex: bool,
+    /// local time of day, HH:MM, from which the rule applies
+    pub start_time: Option<String>,
+    /// local time of day, HH:MM, until which the rule applies, before
+    /// start_time for a range past midnight
+    pub end_time: Option<String>,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(OaSchema, Debug, Serialize, Deserialize, Clone, Default)]
+pub struct NewTagRule {
+    pub name: String,
+    pub tag: String,
+    #[serde(default)]
+    pub content_type: Option<String>,
+    #[serde(default)]
+    pub app_name: Option<String>,
+    #[serde(default)]
+    pub window_name: Option<String>,
+    #[serde(default)]
+    pub browser_url: Option<String>,
+    #[serde(default)]
+    pub pattern: Option<String>,
+    #[serde(default)]
+    pub is_regex: bool,
+    #[serde(default)]
+    pub start_time: Option<String>,
+    #[serde(default)]
+    pub end_time: Option<String>,
+}
+
+/// A rule that matched content just inserted, to be posted to its webhook.
+#[derive(Debug, Serialize, Clone)]
+pub struct WebhookMatch {
+    pub rule: WebhookRule,
+    /// "ocr" or "audio"
+    pub content_type: String,
+    /// the matching `OCRResult` or `AudioResult`
+    pub content: serde_json::Value,
+    /// the matched text
+    pub matched: String,
+}
+
+/// How much of a day a capture device actually recorded.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct CaptureCoverage {
+    /// YYYY-MM-DD, in UTC
+    pub day: String,
+    pub device_name: String,
+    /// "vision" or "audio"
+    pub kind: String,
+    pub samples: i64,
+    /// time between consecutive samples no further apart than the max gap
+    pub covered_secs: f64,
+    /// part of the day inside the requested period, up to now
+    #[sqlx(skip)]
+    pub period_secs: f64,
+    #[sqlx(skip)]
+    pub coverage_percent: f64,
+}
+
+/// A stretch without any sample from a device, longer than the max gap.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct CaptureGap {
+    pub device_name: String,
+    /// "vision" or "audio"
+    pub kind: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub duration_secs: f64,
+    /// other devices kept recording, so only this one stopped (idle screen,
+    /// silence, device unplugged). When false nothing was recorded at all:
+    /// capture was paused, crashed or the machine was asleep.
+    pub recording_elsewhere: bool,
+}
+
+#[derive(OaSchema, Debug, Serialize, Deserialize, Clone)]
+pub struct CoverageReport {
+    pub days: Vec<CaptureCoverage>,
+    pub gaps: Vec<CaptureGap>,
+}
+
+/// Token accepted by the api. The token itself is only shown on creation.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct ApiToken {
+    pub id: i64,
+    pub name: String,
+    /// comma separated, e.g. "read-search,write-tags"
+    pub scopes: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+/// When a capture device last stored something.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct DeviceActivity {
+    pub device_name: String,
+    /// "vision" or "audio"
+    pub kind: String,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// Frames of a monitor over a period, and the ones OCR stored no text for.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct DeviceOcrFailures {
+    pub device_name: String,
+    pub frames: i64,
+    pub without_text: i64,
+}
+
+/// Clock offset of a remote capture agent, the best estimate of its session.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct ClockOffset {
+    pub device_name: String,
+    pub session_id: String,
+    /// server time minus agent time
+    pub offset_ms: f64,
+    /// round trip of the exchange the offset comes from
+    pub delay_ms: f64,
+    pub samples: i64,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Frames or audio chunks a bulk change applies to. Every field given must
+/// match; `app_name` and `window_name` only apply to frames, `device_name` to
+/// both. Trashed items never match.
+#[derive(OaSchema, Debug, Serialize, Deserialize, Default, Clone)]
+pub struct BulkFilter {
+    #[serde(default)]
+    pub ids: Vec<i64>,
+    #[serde(default)]
+    pub start_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub end_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub app_name: Option<String>,
+    #[serde(default)]
+    pub window_name: Option<String>,
+    #[serde(default)]
+    pub device_name: Option<String>,
+}
+
+impl BulkFilter {
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+            && self.start_time.is_none()
+            && self.end_time.is_none()
+            && self.app_name.is_none()
+            && self.window_name.is_none()
+            && self.device_name.is_none()
+    }
+}
+
+/// Outcome of a bulk change: items matching the filter, and rows it changed.
+#[derive(OaSchema, Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq)]
+pub struct BulkResult {
+    pub matched: u64,
+    pub changed: u64,
+}
+
+#[derive(OaSchema, Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq)]
+pub struct SpeakerCompaction {
+    /// embeddings of deleted speakers
+    pub orphaned_embeddings: u64,
+    /// embeddings dropped to cap speakers with too many
+    pub pruned_embeddings: u64,
+    pub compacted_speakers: u64,
+}
+
+/// How the vectors of an embedding index are stored.
+#[derive(OaSchema, Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct EmbeddingIndexSettings {
+    pub index_name: String,
+    pub quantization: EmbeddingQuantization,
+    /// float copies kept of the most recent quantized vectors, to rescore
+    /// search results
+    pub float_cache_size: i64,
+}
+
+#[derive(OaSchema, Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct EmbeddingIndexStatus {
+    #[serde(flatten)]
+    pub settings: EmbeddingIndexSettings,
+    pub rows: i64,
+    /// rows not stored with the index's quantization yet
+    pub pending_conversion: i64,
+    pub stored_bytes: i64,
+    pub cached_floats: i64,
+}
+
+/// Time spent in one app on one monitor, frames less than a few minutes
+/// apart.
+#[derive(OaSchema, Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AppSession {
+    pub app_name: String,
+    pub device_name: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub frame_count: i64,
+    /// most shown windows first
+    pub window_names: Vec<String>,
+    /// longest text read in the session
+    pub excerpt: Option<JournalExcerpt>,
+}
+
+#[derive(OaSchema, Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct JournalExcerpt {
+    pub frame_id: i64,
+    pub text: String,
+}
+
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct TranscriptLine {
+    pub timestamp: DateTime<Utc>,
+    pub speaker_name: Option<String>,
+    pub device_name: String,
+    pub text: String,
+    pub file_path: String,
+    /// seconds into the audio chunk
+    pub start_time: Option<f64>,
+}
+
+#[derive(OaSchema, Debug, Serialize, Deserialize, Clone)]
+pub struct JournalMeeting {
+    pub meeting: Meeting,
+    pub participants: Vec<MeetingParticipant>,
+    pub transcript: Vec<TranscriptLine>,
+}
+
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct TagUsage {
+    pub name: String,
+    pub frames: i64,
+    pub audio_chunks: i64,
+}
+
+/// Everything recorded over a period, for the daily journal.
+#[derive(OaSchema, Debug, Serialize, Deserialize, Clone)]
+pub struct JournalDay {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub sessions: Vec<AppSession>,
+    pub meetings: Vec<JournalMeeting>,
+    pub tags: Vec<TagUsage>,
+}
+
+/// A search result the user opened.
+#[derive(OaSchema, Debug, Serialize, Deserialize, Clone)]
+pub struct NewSearchClick {
+    /// the query the result was found with
+    pub q: String,
+    pub content_type: ClickContentType,
+    /// frame, audio chunk or ui monitoring id of the result
+    pub content_id: i64,
+    /// seconds the result stayed open
+    #[serde(default)]
+    pub dwell_secs: f64,
+}
+
+/// What the clicks on one result for one query taught.
+#[derive(OaSchema, Debug, Serialize, Deserialize, Clone)]
+pub struct ClickSignal {
+    /// normalized query, its lowercased words sorted
+    pub query: String,
+    pub content_type: ClickContentType,
+    pub content_id: i64,
+    pub clicks: i64,
+    pub dwell_secs: f64,
+    pub last_clicked_at: DateTime<Utc>,
+    /// multiplier of the relevance of the result for this query
+    pub boost: f64,
+}
+
+/// Metadata of a frame or a transcription, a row of the usage export.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct UsageRecord {
+    /// `frame` or `audio`
+    pub kind: String,
+    /// frame or transcription id
+    pub id: i64,
+    pub timestamp: DateTime<Utc>,
+    /// monitor or audio device
+    pub device_name: String,
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
+    pub browser_url: Option<String>,
+    /// seconds until the next frame of the monitor, or spoken in the
+    /// transcription
+    pub duration_secs: Option<f64>,
+    pub speaker_name: Option<String>,
+    /// characters of ocr text or transcription
+    pub text_length: Option<i64>,
+}
+
+/// A row of the frames table, for exports.
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct FrameExport {
+    pub id: i64,
+    pub video_chunk_id: i64,
+    pub offset_index: i64,
+    pub timestamp: DateTime<Utc>,
+    pub device_name: String,
+    pub file_path: String,
+    pub name: Option<String>,
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
+    pub browser_url: Option<String>,
+    pub focused: Option<bool>,
+}
+
+/// A row of the ocr_text table, with the time of its frame, for exports.
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct OcrTextExport {
+    pub frame_id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub text: String,
+    pub ocr_engine: String,
+    pub text_length: Option<i64>,
+    pub confidence: Option<f64>,
+}
+
+/// A row of the audio_transcriptions table, for exports.
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct TranscriptionExport {
+    pub id: i64,
+    pub audio_chunk_id: i64,
+    pub offset_index: i64,
+    pub timestamp: DateTime<Utc>,
+    pub file_path: String,
+    pub transcription: String,
+    pub device: String,
+    pub is_input_device: bool,
+    pub speaker_id: Option<i64>,
+    pub speaker_name: Option<String>,
+    pub transcription_engine: String,
+    pub start_time: Option<f64>,
+    pub end_time: Option<f64>,
+}
+
+/// A query searched over a period, with how many of its results were opened.
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct SearchedQuery {
+    pub query: String,
+    pub clicks: i64,
+}
+
+/// How often a keyword was read or heard over a period.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct KeywordHits {
+    pub keyword: String,
+    pub frames: i64,
+    pub transcriptions: i64,
+}
+
+/// Notion database or Readwise account the tagged frames and audio are
+/// pushed to.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct PushDestination {
+    pub id: i64,
+    pub name: String,
+    /// "notion" or "readwise"
+    pub kind: String,
+    /// never returned by the api
+    #[serde(skip_serializing, default)]
+    pub api_key: String,
+    pub notion_database_id: Option<String>,
+    /// comma separated, items with any of these tags are pushed
+    pub tags: String,
+    pub enabled: bool,
+    pub last_pushed_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl PushDestination {
+    pub fn tag_list(&self) -> Vec<String> {
+        self.tags
+            .split(',')
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect()
+    }
+}
+
+#[derive(OaSchema, Debug, Serialize, Deserialize, Clone)]
+pub struct NewPushDestination {
+    pub name: String,
+    pub kind: String,
+    pub api_key: String,
+    /// required for notion
+    pub notion_database_id: Option<String>,
+    #[serde(default = "default_push_tags")]
+    pub tags: Vec<String>,
+}
+
+fn default_push_tags() -> Vec<String> {
+    vec!["bookmark".to_string()]
+}
+
+/// A tagged frame, or audio chunk with its transcript, to push.
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct PushItem {
+    /// "vision" or "audio"
+    pub content_type: String,
+    /// frame or audio chunk id
+    pub content_id: i64,
+    pub timestamp: DateTime<Utc>,
+    /// monitor or audio device
+    pub device_name: String,
+    /// empty for audio
+    pub app_name: String,
+    /// empty for audio
+    pub window_name: String,
+    pub text: String,
+    /// comma separated, the tags it was pushed for
+    pub tags: String,
+}
+
+/// How many results a search has, estimated when `exact` is false.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct ResultCount {
+    pub count: usize,
+    pub exact: bool,
+}
+
+/// A frame and the OCR of it, see `insert_frames_batch`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct NewFrame {
+    /// default to now
+    pub timestamp: Option<DateTime<Utc>>,
+    pub browser_url: Option<String>,
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
+    pub focused: bool,
+    pub text: String,
+    pub text_json: String,
+}
+
+/// One step of `run_maintenance`.
+#[derive(OaSchema, Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MaintenanceStep {
+    pub name: String,
+    pub duration_ms: u64,
+    /// not needed or not possible on this database
+    pub skipped: bool,
+    pub error: Option<String>,
+}
+
+/// An app, window title or browser url never recorded, see
+/// `insert_capture_block_rule`.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct CaptureBlockRule {
+    pub id: i64,
+    /// "app", "window" or "url"
+    pub kind: String,
+    /// found case insensitively anywhere in the app name, window title or url
+    pub pattern: String,
+    /// "api", or "config" for the rules of the command line
+    pub source: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(OaSchema, Debug, Serialize, Deserialize, Clone)]
+pub struct NewCaptureBlockRule {
+    pub kind: String,
+    pub pattern: String,
+}
+
+/// A pause of capture, from the api or a schedule. Kept once ended, as a gap
+/// in the timeline.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct CapturePause {
+    pub id: i64,
+    /// "all", "vision", "audio" or "ui"
+    pub content_type: String,
+    pub started_at: DateTime<Utc>,
+    /// when a timed pause ends by itself
+    pub resume_at: Option<DateTime<Utc>>,
+    /// none while it lasts
+    pub ended_at: Option<DateTime<Utc>>,
+    /// the schedule that started it
+    pub schedule_id: Option<i64>,
+}
+
+/// Hours capture pauses every day or week, see `CapturePause`.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct CapturePauseSchedule {
+    pub id: i64,
+    /// "all", "vision", "audio" or "ui"
+    pub content_type: String,
+    /// local time, e.g. "18:00-09:00" or "sat-sun 00:00-23:59"
+    pub hours: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A raw sql query or destructive operation, see `insert_audit_entry`.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub timestamp: DateTime<Utc>,
+    /// "token:<name>" or "ip:<address>" for the api, "screenpipe" for itself
+    pub actor: String,
+    /// e.g. "raw_sql", "delete_speaker", "add_tags"
+    pub action: String,
+    /// the query, or what the operation applied to
+    pub target: String,
+    pub rows_affected: Option<i64>,
+}
+
+/// What a search with the same query, content type, time range and app or
+/// window finds, to delete or tag. `app_name` and `window_name` leave audio
+/// out.
+#[derive(OaSchema, Debug, Deserialize, Default, Clone)]
+pub struct SearchDeleteFilter {
+    /// full text query, as for `/search`
+    #[serde(default)]
+    pub q: Option<String>,
+    #[serde(default)]
+    pub content_type: ContentType,
+    #[serde(default)]
+    pub start_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub end_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub app_name: Option<String>,
+    #[serde(default)]
+    pub window_name: Option<String>,
+}
+
+impl SearchDeleteFilter {
+    pub fn is_empty(&self) -> bool {
+        self.q.as_deref().map_or(true, |q| q.trim().is_empty())
+            && self.start_time.is_none()
+            && self.end_time.is_none()
+            && self.app_name.is_none()
+            && self.window_name.is_none()
+    }
+}
+
+/// How far tagging what a search finds got, see `tag_search_results`.
+#[derive(OaSchema, Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct SearchTagging {
+    /// frames, audio chunks and ui rows found
+    pub matched: u64,
+    /// of those, the ones gone through so far
+    pub processed: u64,
+    /// the ones that didn't have the tag yet
+    pub tagged: u64,
+}
+
+/// What a search deletion removed, or would remove on a dry run.
+#[derive(OaSchema, Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct SearchDeletion {
+    pub dry_run: bool,
+    pub frames: u64,
+    pub ocr_rows: u64,
+    pub embeddings: u64,
+    /// tag links of the frames and audio chunks
+    pub tags: u64,
+    pub audio_chunks: u64,
+    pub audio_transcriptions: u64,
+    pub ui_rows: u64,
+    #[serde(default)]
+    pub ui_traversals: u64,
+    #[serde(default)]
+    pub clipboard_entries: u64,
+    /// video chunks left without frames, audio chunks and whiteboard photos,
+    /// removed from disk by the caller
+    pub video_files: Vec<String>,
+    pub audio_files: Vec<String>,
+    pub image_files: Vec<String>,
+}
+
+/// What a retention rule deleted so far.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct RetentionRuleStats {
+    /// e.g. "app:Chase", "audio", "tag:receipts", "default" for --retention-days
+    pub rule: String,
+    /// `None` keeps forever
+    pub keep_days: Option<i64>,
+    pub trashed_frames: i64,
+    pub trashed_audio_chunks: i64,
+    pub purged_frames: i64,
+    pub purged_audio_chunks: i64,
+    /// rows of ui text and ui traversals
+    pub trashed_ui_rows: i64,
+    pub deleted_ui_rows: i64,
+    pub last_applied_at: Option<DateTime<Utc>>,
+}
+
+/// Who a subject export or erasure is about: a speaker, a name, or both.
+/// A name matches the speakers and meeting participants with that name, and
+/// the transcriptions, screen text and ui text mentioning it.
+#[derive(OaSchema, Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct Subject {
+    pub speaker_id: Option<i64>,
+    pub name: Option<String>,
+}
+
+impl Subject {
+    pub fn is_empty(&self) -> bool {
+        self.speaker_id.is_none()
+            && self
+                .name
+                .as_deref()
+                .map_or(true, |name| name.trim().is_empty())
+    }
+}
+
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct SubjectTranscript {
+    pub id: i64,
+    pub audio_chunk_id: i64,
+    pub transcription: String,
+    pub timestamp: DateTime<Utc>,
+    pub device: String,
+    pub speaker_id: Option<i64>,
+}
+
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct SubjectAudioChunk {
+    pub id: i64,
+    pub file_path: String,
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct SubjectOcrHit {
+    pub frame_id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
+    pub text: String,
+    /// the video chunk holding the frame
+    pub file_path: String,
+    pub offset_index: i64,
+}
+
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct SubjectUiHit {
+    pub id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub app: String,
+    pub window: String,
+    pub text: String,
+}
+
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct SubjectParticipant {
+    pub id: i64,
+    pub meeting_id: i64,
+    pub name: String,
+    pub speaker_id: Option<i64>,
+}
+
+/// Every artifact referencing a subject.
+#[derive(OaSchema, Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct SubjectExport {
+    pub subject: Subject,
+    pub speakers: Vec<Speaker>,
+    pub transcripts: Vec<SubjectTranscript>,
+    /// the chunks of the transcripts, they hold the subject's voice
+    pub audio_chunks: Vec<SubjectAudioChunk>,
+    pub ocr_hits: Vec<SubjectOcrHit>,
+    pub ui_hits: Vec<SubjectUiHit>,
+    pub meeting_participants: Vec<SubjectParticipant>,
+    #[serde(default)]
+    pub clipboard_hits: Vec<ClipboardEntry>,
+}
+
+/// What erasing a subject removed from the database.
+#[derive(OaSchema, Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct SubjectErasure {
+    /// what was found and removed
+    pub export: SubjectExport,
+    pub deletion: SearchDeletion,
+    pub speakers: u64,
+    pub speaker_embeddings: u64,
+    pub meeting_participants: u64,
+    #[serde(default)]
+    pub search_clicks: u64,
+    #[serde(default)]
+    pub tag_suggestions: u64,
+    /// audit entries naming the subject
+    #[serde(default)]
+    pub audit_entries: u64,
+    /// tabs from the browser extension naming the subject
+    #[serde(default)]
+    pub browser_contexts: u64,
+    /// frames and transcriptions in chunks still being recorded, left for a
+    /// later erasure
+    #[serde(default)]
+    pub skipped_live: u64,
+}
+
+/// Where a content processor is called: on the OCR text of a window and on
+/// a transcription before they are stored, on a frame once it was stored.
+#[derive(OaSchema, Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentHook {
+    OnOcr,
+    OnTranscription,
+    OnFrame,
+}
+
+/// What a content processor is shown, see `ContentProcessor`.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HookContent {
+    pub hook: ContentHook,
+    /// the frame, for `on_frame` only
+    pub id: Option<i64>,
+    pub text: String,
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
+    pub browser_url: Option<String>,
+    /// the audio device of a transcription
+    pub device: Option<String>,
+    pub speaker_id: Option<i64>,
+}
+
+/// What to do with content, nothing by default.
+#[derive(OaSchema, Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct HookOutcome {
+    /// not stored at all, ignored after insertion
+    #[serde(default)]
+    pub drop: bool,
+    /// stored instead of the text, ignored after insertion
+    #[serde(default)]
+    pub text: Option<String>,
+    /// tags added to the frame or audio chunk
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// the post-processing stages that ran, recorded with the text
+    #[serde(default, skip_deserializing)]
+    pub stages: Vec<StageProvenance>,
+    /// recorded as the text read when a stage masked secrets, so the
+    /// provenance doesn't keep what was masked
+    #[serde(default, skip_deserializing)]
+    pub raw_text: Option<String>,
+}
+
+/// Content just inserted, see `subscribe_insert_events`.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InsertEvent {
+    /// "ocr" or "audio"
+    pub content_type: String,
+    /// the frame or the transcription
+    pub id: i64,
+    /// what tags and metadata are added to: the frame, or the audio chunk of
+    /// the transcription
+    pub target_id: i64,
+    pub text: String,
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
+    pub browser_url: Option<String>,
+    /// the monitor or the audio device
+    pub device_name: String,
+    pub speaker_id: Option<i64>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// A key set on a frame or an audio chunk.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, FromRow, PartialEq)]
+pub struct ContentMetadata {
+    pub key: String,
+    pub value: String,
+}
+
+/// A post-processing stage stored text went through.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StageProvenance {
+    pub stage: String,
+    pub version: String,
+    /// whether it changed the text
+    pub changed: bool,
+}
+
+/// How the OCR text of a frame or a transcription came to be what is stored.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TextProvenance {
+    /// "vision" or "audio"
+    pub content_type: String,
+    /// the frame or the transcription
+    pub content_id: i64,
+    /// as the OCR engine or the transcription engine read it, with the
+    /// secrets a stage masked still masked
+    pub raw_text: String,
+    /// in the order they ran
+    pub stages: Vec<StageProvenance>,
+    pub processed_at: DateTime<Utc>,
+    /// as stored
+    pub text: String,
+}
+
+/// Keyboard and mouse activity in a minute while `app_name` had focus. Only
+/// counts, never which keys were pressed.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, FromRow, PartialEq)]
+pub struct InputActivity {
+    /// start of the minute
+    pub timestamp: DateTime<Utc>,
+    /// empty when no app had focus
+    pub app_name: String,
+    pub keypresses: i64,
+    /// runs of keypresses with no pause longer than a few seconds
+    pub typing_bursts: i64,
+    pub clicks: i64,
+    pub scrolls: i64,
+}
+
+/// Input activity of an app over a period.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, FromRow, PartialEq)]
+pub struct AppInputActivity {
+    pub app_name: String,
+    /// minutes with any input while the app had focus
+    pub active_minutes: i64,
+    pub keypresses: i64,
+    pub typing_bursts: i64,
+    pub clicks: i64,
+    pub scrolls: i64,
+}
+
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct InputActivityReport {
+    /// minutes with any input, whatever app had focus. Screen time outside
+    /// of them is passive
+    pub active_minutes: i64,
+    /// most active first
+    pub apps: Vec<AppInputActivity>,
+    /// oldest first
+    pub minutes: Vec<InputActivity>,
+}
+
+/// The active tab as the browser extension reported it.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, FromRow, PartialEq)]
+pub struct BrowserContext {
+    pub id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub url: String,
+    pub title: Option<String>,
+    pub selection_text: Option<String>,
+    pub browser: Option<String>,
+}
+
+/// A traversal of a window's accessibility tree, rebuilt from the diffs it
+/// is stored as.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UiTraversal {
+    pub id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub app: String,
+    pub window: String,
+    pub text: String,
+}
+
+/// The lines a traversal added to and removed from the previous one of its
+/// window.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UiChange {
+    pub id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub app: String,
+    pub window: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// A stretch of time a window had the focus.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, FromRow, PartialEq)]
+pub struct FocusSession {
+    pub id: i64,
+    pub app_name: String,
+    pub window_name: String,
+    pub started_at: DateTime<Utc>,
+    /// none while the window has the focus
+    pub ended_at: Option<DateTime<Utc>>,
+    pub last_seen_at: DateTime<Utc>,
+}
+
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, FromRow, PartialEq)]
+pub struct WindowFocusTime {
+    pub app_name: String,
+    pub window_name: String,
+    pub sessions: i64,
+    /// of the sessions within the range asked for
+    pub focused_secs: f64,
+}
+
+/// A summary of a day or a week kept in the database.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, FromRow, PartialEq)]
+pub struct StoredSummary {
+    pub id: i64,
+    /// "day" or "week"
+    pub period: String,
+    pub start_date: NaiveDate,
+    /// "template", or the model of the llm that wrote it
+    pub generator: String,
+    /// the summary as json
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A stretch of time with no screen change, transcription or input.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, FromRow, PartialEq)]
+pub struct IdleInterval {
+    pub id: i64,
+    /// the last activity before
+    pub started_at: DateTime<Utc>,
+    /// the first activity after
+    pub ended_at: DateTime<Utc>,
+}
+
+/// An event from a calendar. Meetings detected over it take its title and
+/// attendees.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CalendarHint {
+    pub id: i64,
+    pub title: String,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub attendees: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize)]
+pub struct NewCalendarHint {
+    pub title: String,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    #[serde(default)]
+    pub attendees: Vec<String>,
+}
+
+/// A stretch of the timeline where something was recorded, frames or
+/// transcriptions no further apart than the max gap.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TimelineSegment {
+    /// the first sample
+    pub start: DateTime<Utc>,
+    /// the last sample
+    pub end: DateTime<Utc>,
+    pub frames: i64,
+    pub transcriptions: i64,
+    /// frames per minute, how dense the scrubber can be
+    pub frames_per_minute: f64,
+}
+
+/// A stretch of the timeline with nothing recorded.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TimelineGap {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub duration_secs: f64,
+    /// "paused" when capture was paused, "idle" when nobody was at the
+    /// machine or it was asleep, "not_recording" when nothing explains it,
+    /// screenpipe stopped or the machine was off
+    pub reason: String,
+}
+
+/// What was recorded over a period and what was not, oldest first.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Timeline {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub segments: Vec<TimelineSegment>,
+    pub gaps: Vec<TimelineGap>,
+}
+
+/// What was recorded over an hour.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HourlyCount {
+    /// start of the hour
+    pub hour: DateTime<Utc>,
+    pub frames: i64,
+    pub transcriptions: i64,
+    pub ui_events: i64,
+}
+
+/// A term standing out on a day.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, FromRow, PartialEq)]
+pub struct TopicTerm {
+    pub day: NaiveDate,
+    pub term: String,
+    /// distinct texts of the day holding the term
+    pub documents: i64,
+    /// documents over the texts of the day
+    pub share: f64,
+    /// share weighed down for terms found on most of the days before
+    pub score: f64,
+}
+
+/// A term over a period, from the days it stood out on.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, FromRow, PartialEq)]
+pub struct PeriodTopic {
+    pub term: String,
+    pub days: i64,
+    pub documents: i64,
+    /// the scores of its days added up
+    pub score: f64,
+}
+
+/// Counts an app, window title or browser url in a category of the
+/// productivity score.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, FromRow, PartialEq)]
+pub struct AppCategoryRule {
+    pub id: i64,
+    /// "app", "window" or "url"
+    pub kind: String,
+    /// found case insensitively anywhere in the app name, window title or url
+    pub pattern: String,
+    /// "deep_work", "communication" or "distraction"
+    pub category: String,
+    /// "api", or "config" for the rules of the command line
+    pub source: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NewAppCategoryRule {
+    pub kind: String,
+    pub pattern: String,
+    pub category: String,
+}
+
+/// A stretch of activity on related apps between two breaks.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WorkSession {
+    pub id: i64,
+    /// the first and the last frame or transcription
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    /// the app and window title with the most frames
+    pub dominant_app: Option<String>,
+    pub dominant_window: Option<String>,
+    /// the word or pair of words in the window titles of the most frames
+    pub topic: Option<String>,
+    /// the one with the most frames first
+    pub apps: Vec<String>,
+    pub frames: i64,
+    pub transcriptions: i64,
+}
+
+/// Rows a database merge added, see `merge_database`.
+#[derive(OaSchema, Debug, Serialize, Deserialize, Default, Clone, Copy, PartialEq)]
+pub struct MergeReport {
+    pub video_chunks: u64,
+    pub frames: u64,
+    pub audio_chunks: u64,
+    pub audio_transcriptions: u64,
+    pub ui_monitoring: u64,
+    /// speakers of the other database whose voice matched one of this one
+    pub matched_speakers: u64,
+    pub added_speakers: u64,
+    /// tags this database didn't have, the others are shared by name
+    pub added_tags: u64,
+}
+
+/// An upload of the database to object storage by the sync.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SyncRun {
+    pub id: i64,
+    /// "snapshot" of the whole database, or "delta" of the rows added since
+    /// the run before
+    pub kind: String,
+    pub object_key: String,
+    /// the last rowid of each table of `SYNC_DELTA_TABLES` the upload holds
+    pub watermarks: std::collections::BTreeMap<String, i64>,
+    /// rows a delta holds, 0 for snapshots
+    pub rows: i64,
+    pub bytes: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A capture source: a machine, one of its monitors or audio devices.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct Device {
+    pub id: i64,
+    /// "host", "monitor", "audio_input" or "audio_output"
+    pub kind: String,
+    /// as recorded: "monitor_1", the name of the audio device, the host name
+    pub name: String,
+    /// machine of the device: empty for this one, the source device of merged
+    /// rows
+    pub host: String,
+    pub enabled: bool,
+    /// capture fps of a monitor, the global one when unset
+    pub fps: Option<f64>,
+    /// OCR engine or provider of a monitor, transcription provider of an
+    /// audio device, the global one when unset
+    pub engine: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: Option<DateTime<Utc>>,
+}
+
+/// A video or audio chunk whose file was deleted to stay under the storage
+/// budget, its text kept.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct MediaEviction {
+    pub id: i64,
+    /// "video" or "audio"
+    pub kind: String,
+    pub chunk_id: i64,
+    pub file_path: String,
+    pub bytes: i64,
+    /// the eviction policy that picked the chunk
+    pub reason: String,
+    pub evicted_at: DateTime<Utc>,
+}
+
+/// A video or audio chunk whose file failed its last integrity check.
+#[derive(OaSchema, Debug, Serialize, Deserialize, FromRow, Clone, PartialEq)]
+pub struct MediaIssue {
+    /// "video" or "audio"
+    pub kind: String,
+    pub chunk_id: i64,
+    pub file_path: String,
+    /// "missing", "undecodable" or "missing_frames"
+    pub issue: String,
+    pub verified_at: DateTime<Utc>,
+}
@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+
+// above this many cells the middle of two traversals is replaced whole
+// instead of being diffed line by line
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// A step turning the lines of a traversal into the lines of the next one,
+/// applied from the first line on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineEdit {
+    /// the next lines are unchanged
+    Keep(usize),
+    /// the next lines are gone
+    Delete(usize),
+    Insert(Vec<String>),
+}
+
+/// The edits turning `old` into `new`, keeping as many lines as it can.
+pub fn diff_lines<S: AsRef<str>>(old: &[S], new: &[S]) -> Vec<LineEdit> {
+    let prefix = old
+        .iter()
+        .zip(new)
+        .take_while(|(a, b)| a.as_ref() == b.as_ref())
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a.as_ref() == b.as_ref())
+        .count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    let mut edits = Vec::new();
+    push_edit(&mut edits, LineEdit::Keep(prefix));
+    if (old_middle.len() + 1) * (new_middle.len() + 1) > MAX_DIFF_CELLS {
+        push_edit(&mut edits, LineEdit::Delete(old_middle.len()));
+        push_edit(&mut edits, insert(new_middle));
+    } else {
+        diff_middle(old_middle, new_middle, &mut edits);
+    }
+    push_edit(&mut edits, LineEdit::Keep(suffix));
+    edits
+}
+
+/// The lines `edits` turn `old` into, none when they were made for other
+/// lines.
+pub fn apply_line_edits<S: AsRef<str>>(old: &[S], edits: &[LineEdit]) -> Option<Vec<String>> {
+    let mut lines = Vec::with_capacity(old.len());
+    let mut position = 0;
+    for edit in edits {
+        match edit {
+            LineEdit::Keep(count) => {
+                let kept = old.get(position..position + count)?;
+                lines.extend(kept.iter().map(|line| line.as_ref().to_string()));
+                position += count;
+            }
+            LineEdit::Delete(count) => {
+                old.get(position..position + count)?;
+                position += count;
+            }
+            LineEdit::Insert(inserted) => lines.extend(inserted.iter().cloned()),
+        }
+    }
+    (position == old.len()).then_some(lines)
+}
+
+// longest common subsequence of the lines, walked back into edits
+fn diff_middle<S: AsRef<str>>(old: &[S], new: &[S], edits: &mut Vec<LineEdit>) {
+    let width = new.len() + 1;
+    // common[i * width + j]: longest common subsequence of old[i..] and new[j..]
+    let mut common = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i * width + j] = if old[i].as_ref() == new[j].as_ref() {
+                common[(i + 1) * width + j + 1] + 1
+            } else {
+                common[(i + 1) * width + j].max(common[i * width + j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i].as_ref() == new[j].as_ref() {
+            push_edit(edits, LineEdit::Keep(1));
+            i += 1;
+            j += 1;
+        } else if j < new.len()
+            && (i == old.len() || common[i * width + j + 1] >= common[(i + 1) * width + j])
+        {
+            push_edit(edits, insert(&new[j..j + 1]));
+            j += 1;
+        } else {
+            push_edit(edits, LineEdit::Delete(1));
+            i += 1;
+        }
+    }
+}
+
+fn insert<S: AsRef<str>>(lines: &[S]) -> LineEdit {
+    LineEdit::Insert(lines.iter().map(|line| line.as_ref().to_string()).collect())
+}
+
+// appends `edit`, merged into the last edit when they are of the same kind
+fn push_edit(edits: &mut Vec<LineEdit>, edit: LineEdit) {
+    match (edits.last_mut(), edit) {
+        (_, LineEdit::Keep(0) | LineEdit::Delete(0)) => {}
+        (_, LineEdit::Insert(lines)) if lines.is_empty() => {}
+        (Some(LineEdit::Keep(count)), LineEdit::Keep(more)) => *count += more,
+        (Some(LineEdit::Delete(count)), LineEdit::Delete(more)) => *count += more,
+        (Some(LineEdit::Insert(lines)), LineEdit::Insert(more)) => lines.extend(more),
+        (_, edit) => edits.push(edit),
+    }
+}
@@ -0,0 +1,138 @@
+use sqlx::Row;
+
+use crate::DatabaseManager;
+
+impl DatabaseManager {
+    /// Starts or stops holding back the ui text written, until its secrets
+    /// are masked by `release_held_ui_text`. Held text never reaches
+    /// ui_monitoring or its search index unmasked. Turned on, the text
+    /// written before is held for masking too.
+    pub async fn set_ui_secret_scanning(&self, enabled: bool) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        if enabled {
+            let scanning: bool = sqlx::query_scalar(
+                "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'trigger' AND name = 'ui_monitoring_hold_insert')",
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            if !scanning {
+                sqlx::query(
+                    r#"
+                    CREATE TRIGGER ui_monitoring_hold_insert BEFORE INSERT ON ui_monitoring
+                    WHEN NOT EXISTS (SELECT 1 FROM ui_monitoring_releasing)
+                    BEGIN
+                        INSERT OR REPLACE INTO ui_monitoring_held (timestamp, initial_traversal_at, app, window, text_output)
+                        VALUES (COALESCE(NEW.timestamp, CURRENT_TIMESTAMP), NEW.initial_traversal_at, NEW.app, NEW.window, NEW.text_output);
+                        SELECT RAISE(IGNORE);
+                    END
+                    "#,
+                )
+                .execute(&mut *tx)
+                .await?;
+                sqlx::query(
+                    r#"
+                    CREATE TRIGGER ui_monitoring_hold_update BEFORE UPDATE OF text_output ON ui_monitoring
+                    WHEN NEW.text_output IS NOT OLD.text_output
+                        AND NOT EXISTS (SELECT 1 FROM ui_monitoring_releasing)
+                    BEGIN
+                        INSERT OR REPLACE INTO ui_monitoring_held (ui_monitoring_id, timestamp, initial_traversal_at, app, window, text_output)
+                        VALUES (OLD.id, NEW.timestamp, OLD.initial_traversal_at, NEW.app, NEW.window, NEW.text_output);
+                        SELECT RAISE(IGNORE);
+                    END
+                    "#,
+                )
+                .execute(&mut *tx)
+                .await?;
+                sqlx::query(
+                    r#"
+                    INSERT OR REPLACE INTO ui_monitoring_held (ui_monitoring_id, timestamp, initial_traversal_at, app, window, text_output)
+                    SELECT id, timestamp, initial_traversal_at, app, window, text_output FROM ui_monitoring
+                    "#,
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+        } else {
+            sqlx::query("DROP TRIGGER IF EXISTS ui_monitoring_hold_insert")
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("DROP TRIGGER IF EXISTS ui_monitoring_hold_update")
+                .execute(&mut *tx)
+                .await?;
+            // the recorder writes the whole window text again on its next pass
+            sqlx::query("DELETE FROM ui_monitoring_held")
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await
+    }
+
+    /// Writes up to `limit` held ui texts to ui_monitoring through `mask`,
+    /// returns how many held texts there were and how many `mask` changed.
+    /// A held text for a row deleted meanwhile is dropped.
+    pub async fn release_held_ui_text(
+        &self,
+        limit: u32,
+        mask: impl Fn(&str) -> String,
+    ) -> Result<(usize, usize), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let held = sqlx::query(
+            r#"
+            DELETE FROM ui_monitoring_held
+            WHERE id IN (SELECT id FROM ui_monitoring_held ORDER BY id LIMIT ?1)
+            RETURNING id, ui_monitoring_id, timestamp, initial_traversal_at, app, window, text_output
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&mut *tx)
+        .await?;
+        sqlx::query("INSERT OR IGNORE INTO ui_monitoring_releasing (id) VALUES (1)")
+            .execute(&mut *tx)
+            .await?;
+        let mut masked = 0;
+        for row in &held {
+            let text: String = row.try_get("text_output")?;
+            let masked_text = mask(&text);
+            if masked_text != text {
+                masked += 1;
+            }
+            let ui_monitoring_id: Option<i64> = row.try_get("ui_monitoring_id")?;
+            let timestamp: Option<String> = row.try_get("timestamp")?;
+            let initial_traversal_at: Option<String> = row.try_get("initial_traversal_at")?;
+            let app: String = row.try_get("app")?;
+            let window: String = row.try_get("window")?;
+            match ui_monitoring_id {
+                Some(id) => {
+                    sqlx::query(
+                        "UPDATE ui_monitoring SET timestamp = COALESCE(?2, timestamp), text_output = ?3 WHERE id = ?1",
+                    )
+                    .bind(id)
+                    .bind(timestamp)
+                    .bind(&masked_text)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                None => {
+                    sqlx::query(
+                        r#"
+                        INSERT INTO ui_monitoring (timestamp, initial_traversal_at, app, window, text_output)
+                        VALUES (COALESCE(?1, CURRENT_TIMESTAMP), ?2, ?3, ?4, ?5)
+                        "#,
+                    )
+                    .bind(timestamp)
+                    .bind(initial_traversal_at)
+                    .bind(app)
+                    .bind(window)
+                    .bind(&masked_text)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+            }
+        }
+        sqlx::query("DELETE FROM ui_monitoring_releasing")
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok((held.len(), masked))
+    }
+}
@@ -0,0 +1,464 @@
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, SqliteConnection};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::{apply_line_edits, diff_lines, DatabaseManager, LineEdit, UiChange, UiTraversal};
+
+/// Traversals of a window stored between two keyframes, at most.
+pub const UI_KEYFRAME_INTERVAL: usize = 20;
+
+// traversals read at once looking for changes matching a query, and at most
+const UI_CHANGES_PAGE: u32 = 500;
+const MAX_SCANNED_UI_CHANGES: usize = 20_000;
+
+#[derive(Debug, FromRow)]
+struct TraversalRow {
+    id: i64,
+    timestamp: DateTime<Utc>,
+    app: String,
+    window: String,
+    keyframe: bool,
+    content: String,
+}
+
+// a traversal rebuilt from its keyframe, with the lines it changed
+struct Replayed {
+    lines: Vec<String>,
+    added: Vec<String>,
+    removed: Vec<String>,
+}
+
+impl DatabaseManager {
+    /// Stores a traversal of `window` as the lines it changed since the
+    /// previous one, or whole as a keyframe when it is the first of the
+    /// window, the previous keyframe is `UI_KEYFRAME_INTERVAL` traversals
+    /// old, or the diff is not smaller than the text. Returns none when the
+    /// text did not change or the capture blocklist keeps the window out.
+    pub async fn insert_ui_traversal(
+        &self,
+        app: &str,
+        window: &str,
+        timestamp: DateTime<Utc>,
+        text: &str,
+    ) -> Result<Option<i64>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let chain: Vec<TraversalRow> = sqlx::query_as(
+            r#"
+            SELECT id, timestamp, app, window, keyframe, content
+            FROM ui_traversals
+            WHERE app = ?1 AND window = ?2
+                AND id >= COALESCE((
+                    SELECT MAX(id) FROM ui_traversals
+                    WHERE app = ?1 AND window = ?2 AND keyframe = 1
+                ), 0)
+            ORDER BY id
+            "#,
+        )
+        .bind(app)
+        .bind(window)
+        .fetch_all(&mut *tx)
+        .await?;
+        let previous = replay_chain(&chain)
+            .pop()
+            .map(|(_, replayed)| replayed.lines);
+
+        let lines = split_lines(text);
+        let content = match &previous {
+            Some(previous) if *previous == lines => return Ok(None),
+            Some(previous) if chain.len() < UI_KEYFRAME_INTERVAL => {
+                serde_json::to_string(&diff_lines(previous, &lines))
+                    .ok()
+                    .filter(|edits| edits.len() < text.len())
+            }
+            _ => None,
+        };
+        let keyframe = content.is_none();
+        let result = sqlx::query(
+            r#"
+            INSERT INTO ui_traversals (timestamp, app, window, keyframe, content)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            "#,
+        )
+        .bind(timestamp)
+        .bind(app)
+        .bind(window)
+        .bind(keyframe)
+        .bind(content.as_deref().unwrap_or(text))
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+        Ok(Some(result.last_insert_rowid()))
+    }
+
+    /// Traversals in `start..=end`, of `app` and `window` only when set,
+    /// latest first.
+    pub async fn get_ui_traversals(
+        &self,
+        app: Option<&str>,
+        window: Option<&str>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<UiTraversal>, sqlx::Error> {
+        let rows = self
+            .ui_traversal_rows(app, window, start, end, None, limit)
+            .await?;
+        let mut replayed = self.replay_ui_traversals(&rows).await?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| {
+                let replayed = replayed.remove(&row.id)?;
+                Some(UiTraversal {
+                    id: row.id,
+                    timestamp: row.timestamp,
+                    app: row.app,
+                    window: row.window,
+                    text: replayed.lines.join("\n"),
+                })
+            })
+            .collect())
+    }
+
+    /// What traversals in `start..=end` changed from the previous one of
+    /// their window, latest first. With `query`, only the changes adding or
+    /// removing a line containing it, ignoring case: when a field got or
+    /// lost a value, among the latest 20000 traversals of the range.
+    pub async fn get_ui_changes(
+        &self,
+        app: Option<&str>,
+        window: Option<&str>,
+        query: Option<&str>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<UiChange>, sqlx::Error> {
+        let query = query
+            .map(|query| query.trim().to_lowercase())
+            .filter(|query| !query.is_empty());
+        let matches = |line: &String| {
+            query
+                .as_ref()
+                .is_none_or(|query| line.to_lowercase().contains(query))
+        };
+        // the query is matched on the rebuilt lines, so pages of traversals
+        // are read until enough matched
+        let page = limit.max(UI_CHANGES_PAGE);
+        let mut changes = Vec::new();
+        let mut before = None;
+        let mut scanned = 0;
+        while changes.len() < limit as usize && scanned < MAX_SCANNED_UI_CHANGES {
+            let rows = self
+                .ui_traversal_rows(app, window, start, end, before, page)
+                .await?;
+            let Some(last) = rows.last() else {
+                break;
+            };
+            before = Some((last.timestamp, last.id));
+            scanned += rows.len();
+            let exhausted = rows.len() < page as usize;
+            let mut replayed = self.replay_ui_traversals(&rows).await?;
+            changes.extend(rows.into_iter().filter_map(|row| {
+                let replayed = replayed.remove(&row.id)?;
+                if replayed.added.is_empty() && replayed.removed.is_empty() {
+                    return None;
+                }
+                if !replayed.added.iter().chain(&replayed.removed).any(matches) {
+                    return None;
+                }
+                Some(UiChange {
+                    id: row.id,
+                    timestamp: row.timestamp,
+                    app: row.app,
+                    window: row.window,
+                    added: replayed.added,
+                    removed: replayed.removed,
+                })
+            }));
+            if exhausted {
+                break;
+            }
+        }
+        changes.truncate(limit as usize);
+        Ok(changes)
+    }
+
+    // latest first, older than `before` when set
+    async fn ui_traversal_rows(
+        &self,
+        app: Option<&str>,
+        window: Option<&str>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        before: Option<(DateTime<Utc>, i64)>,
+        limit: u32,
+    ) -> Result<Vec<TraversalRow>, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        guard
+            .run(
+                sqlx::query_as(
+                    r#"
+                    SELECT id, timestamp, app, window, keyframe, content
+                    FROM ui_traversals
+                    WHERE timestamp >= ?1 AND timestamp <= ?2
+                        AND deleted_at IS NULL
+                        AND (?3 IS NULL OR app = ?3)
+                        AND (?4 IS NULL OR window = ?4)
+                        AND (?5 IS NULL OR (timestamp, id) < (?5, ?6))
+                    ORDER BY timestamp DESC, id DESC
+                    LIMIT ?7
+                    "#,
+                )
+                .bind(start)
+                .bind(end)
+                .bind(app.filter(|app| !app.is_empty()))
+                .bind(window.filter(|window| !window.is_empty()))
+                .bind(before.map(|(timestamp, _)| timestamp))
+                .bind(before.map(|(_, id)| id))
+                .bind(limit)
+                .fetch_all(&mut *conn),
+            )
+            .await
+    }
+
+    // rebuilds `rows` from the keyframe before the oldest of each window
+    async fn replay_ui_traversals(
+        &self,
+        rows: &[TraversalRow],
+    ) -> Result<HashMap<i64, Replayed>, sqlx::Error> {
+        let mut windows: BTreeMap<(&str, &str), (i64, i64)> = BTreeMap::new();
+        for row in rows {
+            let ids = windows
+                .entry((row.app.as_str(), row.window.as_str()))
+                .or_insert((row.id, row.id));
+            ids.0 = ids.0.min(row.id);
+            ids.1 = ids.1.max(row.id);
+        }
+
+        let (mut conn, guard) = self.read_connection().await?;
+        let mut replayed = HashMap::new();
+        for ((app, window), (first, last)) in windows {
+            let chain = guard
+                .run(window_chain(&mut conn, app, window, first, last))
+                .await?;
+            replayed.extend(
+                replay_chain(&chain)
+                    .into_iter()
+                    .filter(|(id, _)| *id >= first),
+            );
+        }
+        Ok(replayed)
+    }
+
+    /// Ids of the traversals, in `start..=end` and of `app` and `window`
+    /// when set, whose rebuilt text contains every one of `terms`, ignoring
+    /// case.
+    pub(crate) async fn matching_ui_traversals(
+        conn: &mut SqliteConnection,
+        terms: &[String],
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+        app: Option<&str>,
+        window: Option<&str>,
+    ) -> Result<Vec<i64>, sqlx::Error> {
+        if terms.is_empty() {
+            return sqlx::query_scalar(
+                r#"
+                SELECT id FROM ui_traversals
+                WHERE (?1 IS NULL OR timestamp >= ?1)
+                    AND (?2 IS NULL OR timestamp <= ?2)
+                    AND (?3 IS NULL OR app = ?3)
+                    AND (?4 IS NULL OR window = ?4)
+                "#,
+            )
+            .bind(start)
+            .bind(end)
+            .bind(app)
+            .bind(window)
+            .fetch_all(&mut *conn)
+            .await;
+        }
+        let windows: Vec<(String, String, i64, i64)> = sqlx::query_as(
+            r#"
+            SELECT app, window, MIN(id), MAX(id)
+            FROM ui_traversals
+            WHERE (?1 IS NULL OR timestamp >= ?1)
+                AND (?2 IS NULL OR timestamp <= ?2)
+                AND (?3 IS NULL OR app = ?3)
+                AND (?4 IS NULL OR window = ?4)
+            GROUP BY app, window
+            "#,
+        )
+        .bind(start)
+        .bind(end)
+        .bind(app)
+        .bind(window)
+        .fetch_all(&mut *conn)
+        .await?;
+        let terms: Vec<String> = terms.iter().map(|term| term.to_lowercase()).collect();
+        let mut ids = Vec::new();
+        for (app, window, first, last) in windows {
+            let chain = window_chain(conn, &app, &window, first, last).await?;
+            for (id, replayed) in replay_chain(&chain) {
+                let row = &chain[chain.partition_point(|row| row.id < id)];
+                let in_range = start.is_none_or(|start| row.timestamp >= start)
+                    && end.is_none_or(|end| row.timestamp <= end);
+                if !in_range {
+                    continue;
+                }
+                let text = replayed.lines.join("\n").to_lowercase();
+                if terms.iter().all(|term| text.contains(term)) {
+                    ids.push(id);
+                }
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Deletes the traversals in `ids`. The first one kept after those
+    /// deleted of a window is stored whole as a keyframe, so the traversals
+    /// after it still rebuild.
+    pub(crate) async fn delete_ui_traversals(
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        ids: &[i64],
+    ) -> Result<u64, sqlx::Error> {
+        let ids_json = serde_json::to_string(ids).unwrap_or_else(|_| "[]".to_string());
+        let windows: Vec<(String, String, i64, i64)> = sqlx::query_as(
+            r#"
+            SELECT app, window, MIN(id), MAX(id)
+            FROM ui_traversals
+            WHERE id IN (SELECT value FROM json_each(?1))
+            GROUP BY app, window
+            "#,
+        )
+        .bind(&ids_json)
+        .fetch_all(&mut **tx)
+        .await?;
+        let deleted: HashSet<i64> = ids.iter().copied().collect();
+        for (app, window, first, last) in windows {
+            let next: Option<i64> = sqlx::query_scalar(
+                "SELECT MIN(id) FROM ui_traversals WHERE app = ?1 AND window = ?2 AND id > ?3",
+            )
+            .bind(&app)
+            .bind(&window)
+            .bind(last)
+            .fetch_one(&mut **tx)
+            .await?;
+            let chain = window_chain(&mut **tx, &app, &window, first, next.unwrap_or(last)).await?;
+            let mut replayed: HashMap<i64, Replayed> = replay_chain(&chain).into_iter().collect();
+            for pair in chain.windows(2) {
+                let (previous, row) = (&pair[0], &pair[1]);
+                if row.keyframe || deleted.contains(&row.id) || !deleted.contains(&previous.id) {
+                    continue;
+                }
+                // a diff that didn't apply anyway waits for the next keyframe
+                let Some(rebuilt) = replayed.remove(&row.id) else {
+                    continue;
+                };
+                sqlx::query("UPDATE ui_traversals SET keyframe = 1, content = ?2 WHERE id = ?1")
+                    .bind(row.id)
+                    .bind(rebuilt.lines.join("\n"))
+                    .execute(&mut **tx)
+                    .await?;
+            }
+        }
+        Ok(
+            sqlx::query("DELETE FROM ui_traversals WHERE id IN (SELECT value FROM json_each(?1))")
+                .bind(&ids_json)
+                .execute(&mut **tx)
+                .await?
+                .rows_affected(),
+        )
+    }
+}
+
+// the traversals of a window from the keyframe at or before `first` up to
+// `last`, in id order
+async fn window_chain(
+    conn: &mut SqliteConnection,
+    app: &str,
+    window: &str,
+    first: i64,
+    last: i64,
+) -> Result<Vec<TraversalRow>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT id, timestamp, app, window, keyframe, content
+        FROM ui_traversals
+        WHERE app = ?1 AND window = ?2 AND id <= ?4
+            AND id >= COALESCE((
+                SELECT MAX(id) FROM ui_traversals
+                WHERE app = ?1 AND window = ?2 AND keyframe = 1 AND id <= ?3
+            ), 0)
+        ORDER BY id
+        "#,
+    )
+    .bind(app)
+    .bind(window)
+    .bind(first)
+    .bind(last)
+    .fetch_all(&mut *conn)
+    .await
+}
+
+// the traversals of a window in id order, rebuilt by applying each diff to
+// the one before. A diff that does not apply drops the traversals up to the
+// next keyframe
+fn replay_chain(chain: &[TraversalRow]) -> Vec<(i64, Replayed)> {
+    let mut replayed: Vec<(i64, Replayed)> = Vec::with_capacity(chain.len());
+    let mut previous: Option<Vec<String>> = None;
+    for row in chain {
+        let (lines, edits) = if row.keyframe {
+            let lines = split_lines(&row.content);
+            let edits = diff_lines(previous.as_deref().unwrap_or_default(), &lines);
+            (lines, edits)
+        } else {
+            let rebuilt = previous.as_ref().and_then(|previous| {
+                let edits: Vec<LineEdit> = serde_json::from_str(&row.content).ok()?;
+                Some((apply_line_edits(previous, &edits)?, edits))
+            });
+            match rebuilt {
+                Some(rebuilt) => rebuilt,
+                None => {
+                    previous = None;
+                    continue;
+                }
+            }
+        };
+        let (added, removed) = changed_lines(previous.as_deref().unwrap_or_default(), &edits);
+        previous = Some(lines.clone());
+        replayed.push((
+            row.id,
+            Replayed {
+                lines,
+                added,
+                removed,
+            },
+        ));
+    }
+    replayed
+}
+
+// the lines `edits` insert, and the ones of `old` they delete
+fn changed_lines(old: &[String], edits: &[LineEdit]) -> (Vec<String>, Vec<String>) {
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    let mut position = 0;
+    for edit in edits {
+        match edit {
+            LineEdit::Keep(count) => position += count,
+            LineEdit::Delete(count) => {
+                removed.extend(old.iter().skip(position).take(*count).cloned());
+                position += count;
+            }
+            LineEdit::Insert(lines) => added.extend(lines.iter().cloned()),
+        }
+    }
+    (added, removed)
+}
+
+fn split_lines(text: &str) -> Vec<String> {
+    text.split('\n').map(str::to_string).collect()
+}
@@ -0,0 +1,107 @@
+/// A `browser_url` search filter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UrlPattern {
+    /// a word of the url, e.g. "github"
+    Word(String),
+    /// a site, its subdomains included, and a path the url starts with,
+    /// e.g. "docs.rs", "*.rust-lang.org" or "docs.rs/tokio/*/sync". `*`
+    /// in the path matches anything
+    Site {
+        domain: Option<String>,
+        path: Option<String>,
+    },
+}
+
+impl UrlPattern {
+    /// None when the pattern filters nothing.
+    pub fn parse(pattern: &str) -> Option<Self> {
+        let pattern = pattern.trim();
+        if !pattern.contains(['.', '/', ':']) {
+            if tokens(pattern).is_empty() {
+                return None;
+            }
+            return Some(Self::Word(pattern.to_lowercase()));
+        }
+        let rest = pattern.split_once("://").map_or(pattern, |(_, rest)| rest);
+        let (domain, path) = match rest.find('/') {
+            Some(slash) => (&rest[..slash], Some(&rest[slash..])),
+            None => (rest, None),
+        };
+        let domain = domain.trim_start_matches('*').trim_start_matches('.');
+        let domain = (!domain.is_empty()).then(|| domain.to_lowercase());
+        let path = path
+            .map(|path| path.trim_end_matches('*'))
+            .filter(|path| !path.is_empty() && *path != "/")
+            .map(str::to_string);
+        if domain.is_none() && path.is_none() {
+            return None;
+        }
+        Some(Self::Site { domain, path })
+    }
+
+    /// The `frames_fts` query narrowing the frames down to the ones that may
+    /// match, none when it can't.
+    pub(crate) fn fts_query(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        match self {
+            Self::Word(word) => parts.push(format!("browser_url:\"{}\"", tokens(word).join(" "))),
+            Self::Site { domain, path } => {
+                if let Some(domain) = domain {
+                    let domain = tokens(domain);
+                    if !domain.is_empty() {
+                        parts.push(format!("url_domain:\"{}\"", domain.join(" ")));
+                    }
+                }
+                for (i, segment) in path.iter().flat_map(|path| path.split('*')).enumerate() {
+                    let mut words = tokens(segment);
+                    // a word right after a `*` may be the end of a longer one
+                    if i > 0 && segment.starts_with(char::is_alphanumeric) && !words.is_empty() {
+                        words.remove(0);
+                    }
+                    if words.is_empty() {
+                        continue;
+                    }
+                    // and the last one may go on in the url
+                    let prefix = if segment.ends_with(char::is_alphanumeric) {
+                        " *"
+                    } else {
+                        ""
+                    };
+                    parts.push(format!("url_path:\"{}\"{}", words.join(" "), prefix));
+                }
+            }
+        }
+        (!parts.is_empty()).then(|| parts.join(" "))
+    }
+
+    /// The domain `frames.url_domain` is, or is a subdomain of.
+    pub(crate) fn domain(&self) -> Option<&str> {
+        match self {
+            Self::Site { domain, .. } => domain.as_deref(),
+            Self::Word(_) => None,
+        }
+    }
+
+    /// The `LIKE` pattern of `frames.url_path`, escaped with `\`.
+    pub(crate) fn path_like(&self) -> Option<String> {
+        let Self::Site {
+            path: Some(path), ..
+        } = self
+        else {
+            return None;
+        };
+        let escaped = path
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+            .replace('*', "%");
+        Some(format!("{}%", escaped))
+    }
+}
+
+// the words fts5 splits `text` into
+fn tokens(text: &str) -> Vec<&str> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect()
+}
@@ -0,0 +1,91 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{DatabaseManager, UsageRecord};
+
+// a frame lasts until the next one of its monitor, unless nothing was
+// captured for longer than this or an idle interval starts before
+const MAX_FRAME_SECS: i64 = 300;
+
+impl DatabaseManager {
+    /// Frames and transcriptions recorded between `start` and `end`, oldest
+    /// first, for the usage export.
+    pub async fn get_usage_records(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        frames: bool,
+        audio: bool,
+    ) -> Result<Vec<UsageRecord>, sqlx::Error> {
+        // frames are read past `end` so the last ones of the period have a
+        // duration too
+        let (mut conn, guard) = self.read_connection().await?;
+        let mut records = guard.run(sqlx::query_as::<_, UsageRecord>(
+            r#"
+            SELECT * FROM (
+                SELECT
+                    'frame' as kind,
+                    f.id,
+                    f.timestamp,
+                    vc.device_name,
+                    NULLIF(f.app_name, '') as app_name,
+                    NULLIF(f.window_name, '') as window_name,
+                    f.browser_url,
+                    (julianday(LEAD(f.timestamp) OVER (PARTITION BY vc.device_name ORDER BY f.timestamp, f.id))
+                        - julianday(f.timestamp)) * 86400.0 as duration_secs,
+                    NULL as speaker_name,
+                    (SELECT COALESCE(o.text_length, LENGTH(o.text)) FROM ocr_text o WHERE o.frame_id = f.id LIMIT 1)
+                        as text_length
+                FROM frames f
+                JOIN video_chunks vc ON f.video_chunk_id = vc.id
+                WHERE ?3 AND f.timestamp >= ?1 AND f.timestamp < ?5 AND f.deleted_at IS NULL
+            )
+            WHERE timestamp < ?2
+            UNION ALL
+            SELECT
+                'audio' as kind,
+                at.id,
+                at.timestamp,
+                at.device as device_name,
+                NULL as app_name,
+                NULL as window_name,
+                NULL as browser_url,
+                at.end_time - at.start_time as duration_secs,
+                NULLIF(s.name, '') as speaker_name,
+                COALESCE(at.text_length, LENGTH(at.transcription)) as text_length
+            FROM audio_transcriptions at
+            JOIN audio_chunks ac ON at.audio_chunk_id = ac.id
+            LEFT JOIN speakers s ON at.speaker_id = s.id
+            WHERE ?4 AND at.timestamp >= ?1 AND at.timestamp < ?2 AND ac.deleted_at IS NULL
+            ORDER BY timestamp ASC, kind ASC, id ASC
+            "#,
+        )
+        .bind(start)
+        .bind(end)
+        .bind(frames)
+        .bind(audio)
+        .bind(end + Duration::seconds(MAX_FRAME_SECS))
+        .fetch_all(&mut *conn))
+        .await?;
+
+        let idle = self
+            .get_idle_intervals(start, end + Duration::seconds(MAX_FRAME_SECS))
+            .await?;
+        for record in records.iter_mut().filter(|record| record.kind == "frame") {
+            let Some(secs) = record.duration_secs else {
+                continue;
+            };
+            if secs > MAX_FRAME_SECS as f64 {
+                record.duration_secs = None;
+                continue;
+            }
+            let frame_end = record.timestamp + Duration::milliseconds((secs * 1000.0) as i64);
+            if let Some(interval) = idle.iter().find(|interval| {
+                interval.started_at < frame_end && interval.ended_at > record.timestamp
+            }) {
+                let active = (interval.started_at - record.timestamp).num_milliseconds();
+                record.duration_secs = Some(active.max(0) as f64 / 1000.0);
+            }
+        }
+        Ok(records)
+    }
+}
@@ -0,0 +1,86 @@
+use chrono::{DateTime, Utc};
+
+use crate::DatabaseManager;
+
+impl DatabaseManager {
+    /// Video chunks not compacted yet whose last frame was captured before
+    /// `before`, oldest first, with the ids of their first and last frames.
+    pub async fn list_compactable_video_chunks(
+        &self,
+        before: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<(i64, String, i64, i64)>, sqlx::Error> {
+        sqlx::query_as::<_, (i64, String, i64, i64)>(
+            r#"
+            SELECT v.id, v.file_path, MIN(f.id), MAX(f.id)
+            FROM video_chunks v
+            JOIN frames f ON f.video_chunk_id = v.id AND f.deleted_at IS NULL
+            WHERE v.compacted_at IS NULL
+                AND v.file_path != ''
+            GROUP BY v.id
+            HAVING MAX(f.timestamp) < ?1
+            ORDER BY v.id
+            LIMIT ?2
+            "#,
+        )
+        .bind(before)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Points a video chunk, and the frames naming it, to `file_path` in one
+    /// transaction, with `compacted_at` set to `compacted_at`. Returns the
+    /// file it pointed to, none when there's no such chunk.
+    pub async fn set_video_chunk_file(
+        &self,
+        id: i64,
+        file_path: &str,
+        compacted_at: Option<DateTime<Utc>>,
+    ) -> Result<Option<String>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let Some(previous) =
+            sqlx::query_scalar::<_, String>("SELECT file_path FROM video_chunks WHERE id = ?1")
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await?
+        else {
+            return Ok(None);
+        };
+        sqlx::query("UPDATE video_chunks SET file_path = ?1, compacted_at = ?2 WHERE id = ?3")
+            .bind(file_path)
+            .bind(compacted_at)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE frames SET name = ?1 WHERE video_chunk_id = ?2 AND name = ?3")
+            .bind(file_path)
+            .bind(id)
+            .bind(&previous)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(Some(previous))
+    }
+
+    /// Records the codec a video chunk is now encoded with.
+    pub async fn set_video_chunk_codec(&self, id: i64, codec: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE video_chunks SET codec = ?1 WHERE id = ?2")
+            .bind(codec)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Marks a video chunk compacted without changing its file, e.g. when
+    /// re-encoding it saves nothing.
+    pub async fn mark_video_chunk_compacted(&self, id: i64) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE video_chunks SET compacted_at = ?1 WHERE id = ?2")
+            .bind(Utc::now())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
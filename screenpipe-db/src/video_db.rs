@@ -11,6 +11,15 @@ impl DatabaseManager {
         .fetch_one(&self.pool)
         .await
     }
+    /// Whether frames were recorded under `name`, e.g. the path of an
+    /// imported file.
+    pub async fn has_frames_named(&self, name: &str) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM frames WHERE name = ?1)")
+            .bind(name)
+            .fetch_one(&self.pool)
+            .await
+    }
+
     /// Retrieves a list of videos ordered by their start time.
     pub async fn get_ordered_videos(&self) -> Result<Vec<String>, sqlx::Error> {
         sqlx::query_scalar(
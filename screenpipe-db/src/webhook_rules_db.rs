@@ -0,0 +1,344 @@
+use chrono::Utc;
+use regex::{Regex, RegexBuilder};
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+
+use crate::{
+    AudioResult, AudioResultRaw, DatabaseManager, DeviceType, NewWebhookRule, OCRResult,
+    OCRResultRaw, WebhookMatch, WebhookRule,
+};
+
+const WEBHOOK_RULE_COLUMNS: &str = "id, name, pattern, is_regex, content_type, app_name, window_name, speaker_id, webhook_url, cooldown_secs, enabled, last_fired_at, created_at";
+
+/// Case-insensitive matcher for a rule pattern. Keywords match literally,
+/// anywhere in the text.
+pub fn webhook_rule_matcher(pattern: &str, is_regex: bool) -> Result<Regex, regex::Error> {
+    let pattern = if is_regex {
+        pattern.to_string()
+    } else {
+        regex::escape(pattern)
+    };
+    RegexBuilder::new(&pattern).case_insensitive(true).build()
+}
+
+fn contains_ignore_case(value: &str, filter: &Option<String>) -> bool {
+    filter
+        .as_ref()
+        .map_or(true, |f| value.to_lowercase().contains(&f.to_lowercase()))
+}
+
+impl DatabaseManager {
+    /// Receives the webhook rules matched by OCR text and transcriptions as
+    /// they are inserted. Rules are only evaluated while someone listens.
+    pub fn subscribe_webhook_matches(&self) -> broadcast::Receiver<WebhookMatch> {
+        self.webhook_matches.subscribe()
+    }
+
+    pub async fn insert_webhook_rule(
+        &self,
+        rule: &NewWebhookRule,
+    ) -> Result<WebhookRule, sqlx::Error> {
+        let rule = sqlx::query_as::<_, WebhookRule>(&format!(
+            r#"
+            INSERT INTO webhook_rules (
+                name, pattern, is_regex, content_type, app_name, window_name, speaker_id,
+                webhook_url, cooldown_secs, created_at
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            RETURNING {WEBHOOK_RULE_COLUMNS}
+            "#
+        ))
+        .bind(&rule.name)
+        .bind(&rule.pattern)
+        .bind(rule.is_regex)
+        .bind(&rule.content_type)
+        .bind(&rule.app_name)
+        .bind(&rule.window_name)
+        .bind(rule.speaker_id)
+        .bind(&rule.webhook_url)
+        .bind(rule.cooldown_secs)
+        .bind(Utc::now())
+        .fetch_one(&self.pool)
+        .await;
+        self.webhook_rules.invalidate();
+        rule
+    }
+
+    pub async fn list_webhook_rules(&self) -> Result<Vec<WebhookRule>, sqlx::Error> {
+        sqlx::query_as::<_, WebhookRule>(&format!(
+            "SELECT {WEBHOOK_RULE_COLUMNS} FROM webhook_rules ORDER BY id"
+        ))
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    /// Returns whether the rule existed.
+    pub async fn delete_webhook_rule(&self, id: i64) -> Result<bool, sqlx::Error> {
+        let deleted = sqlx::query("DELETE FROM webhook_rules WHERE id = ?1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        self.webhook_rules.invalidate();
+        Ok(deleted > 0)
+    }
+
+    pub(crate) async fn check_ocr_webhook_rules(&self, frame_id: i64, text: &str) {
+        if let Err(e) = self.try_check_ocr_webhook_rules(frame_id, text).await {
+            warn!(
+                "failed to check webhook rules for frame {}: {}",
+                frame_id, e
+            );
+        }
+    }
+
+    pub(crate) async fn check_audio_webhook_rules(
+        &self,
+        transcription_id: i64,
+        transcription: &str,
+        speaker_id: Option<i64>,
+    ) {
+        if let Err(e) = self
+            .try_check_audio_webhook_rules(transcription_id, transcription, speaker_id)
+            .await
+        {
+            warn!(
+                "failed to check webhook rules for transcription {}: {}",
+                transcription_id, e
+            );
+        }
+    }
+
+    async fn try_check_ocr_webhook_rules(
+        &self,
+        frame_id: i64,
+        text: &str,
+    ) -> Result<(), sqlx::Error> {
+        let rules: Vec<_> = self
+            .matching_webhook_rules("ocr", text)
+            .await?
+            .into_iter()
+            .filter(|(rule, _)| rule.speaker_id.is_none())
+            .collect();
+        if rules.is_empty() {
+            return Ok(());
+        }
+
+        let raw = sqlx::query_as::<_, OCRResultRaw>(
+            r#"
+            SELECT
+                ocr_text.frame_id,
+                ocr_text.text as ocr_text,
+                ocr_text.text_json,
+                frames.timestamp,
+                frames.name as frame_name,
+                video_chunks.file_path,
+                frames.offset_index,
+                COALESCE(frames.app_name, '') as app_name,
+                ocr_text.ocr_engine,
+                COALESCE(frames.window_name, '') as window_name,
+                GROUP_CONCAT(tags.name, ',') as tags,
+                frames.browser_url,
+                frames.focused,
+                ocr_text.tables
+            FROM frames
+            JOIN video_chunks ON frames.video_chunk_id = video_chunks.id
+            JOIN ocr_text ON frames.id = ocr_text.frame_id
+            LEFT JOIN vision_tags ON frames.id = vision_tags.vision_id
+            LEFT JOIN tags ON vision_tags.tag_id = tags.id
+            WHERE frames.id = ?1
+            GROUP BY frames.id
+            "#,
+        )
+        .bind(frame_id)
+        .fetch_one(&self.pool)
+        .await?;
+        let result = OCRResult::from(raw);
+
+        for (rule, matched) in rules {
+            if contains_ignore_case(&result.app_name, &rule.app_name)
+                && contains_ignore_case(&result.window_name, &rule.window_name)
+            {
+                self.fire_webhook_rule(rule, "ocr", &result, matched)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn try_check_audio_webhook_rules(
+        &self,
+        transcription_id: i64,
+        transcription: &str,
+        speaker_id: Option<i64>,
+    ) -> Result<(), sqlx::Error> {
+        let rules: Vec<_> = self
+            .matching_webhook_rules("audio", transcription)
+            .await?
+            .into_iter()
+            .filter(|(rule, _)| {
+                rule.app_name.is_none()
+                    && rule.window_name.is_none()
+                    && rule.speaker_id.map_or(true, |id| Some(id) == speaker_id)
+            })
+            .collect();
+        if rules.is_empty() {
+            return Ok(());
+        }
+
+        let raw = sqlx::query_as::<_, AudioResultRaw>(
+            r#"
+            SELECT
+                audio_transcriptions.audio_chunk_id,
+                audio_transcriptions.transcription,
+                audio_transcriptions.timestamp,
+                audio_chunks.file_path,
+                audio_transcriptions.offset_index,
+                audio_transcriptions.transcription_engine,
+                GROUP_CONCAT(tags.name, ',') as tags,
+                audio_transcriptions.device as device_name,
+                audio_transcriptions.is_input_device,
+                audio_transcriptions.speaker_id,
+                audio_transcriptions.start_time,
+                audio_transcriptions.end_time
+            FROM audio_transcriptions
+            JOIN audio_chunks ON audio_transcriptions.audio_chunk_id = audio_chunks.id
+            LEFT JOIN audio_tags ON audio_chunks.id = audio_tags.audio_chunk_id
+            LEFT JOIN tags ON audio_tags.tag_id = tags.id
+            WHERE audio_transcriptions.id = ?1
+            GROUP BY audio_transcriptions.id
+            "#,
+        )
+        .bind(transcription_id)
+        .fetch_one(&self.pool)
+        .await?;
+        let speaker = match raw.speaker_id {
+            Some(id) => self.get_speaker_by_id(id).await.ok(),
+            None => None,
+        };
+        let result = AudioResult {
+            audio_chunk_id: raw.audio_chunk_id,
+            transcription: raw.transcription,
+            timestamp: raw.timestamp,
+            file_path: raw.file_path,
+            offset_index: raw.offset_index,
+            transcription_engine: raw.transcription_engine,
+            tags: raw
+                .tags
+                .map(|s| s.split(',').map(|s| s.to_owned()).collect())
+                .unwrap_or_default(),
+            device_name: raw.device_name,
+            device_type: if raw.is_input_device {
+                DeviceType::Input
+            } else {
+                DeviceType::Output
+            },
+            speaker,
+            start_time: raw.start_time,
+            end_time: raw.end_time,
+        };
+
+        for (rule, matched) in rules {
+            self.fire_webhook_rule(rule, "audio", &result, matched)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Enabled rules for `content_type` whose pattern is found in `text`,
+    /// with the matched text. The app, window and speaker filters are left to
+    /// the caller.
+    async fn matching_webhook_rules(
+        &self,
+        content_type: &str,
+        text: &str,
+    ) -> Result<Vec<(WebhookRule, String)>, sqlx::Error> {
+        if self.webhook_matches.receiver_count() == 0 {
+            return Ok(Vec::new());
+        }
+
+        let rules = match self.webhook_rules.get() {
+            Ok(rules) => rules,
+            Err(generation) => {
+                let rules = self.load_webhook_rules().await?;
+                self.webhook_rules.set(generation, rules)
+            }
+        };
+
+        Ok(rules
+            .iter()
+            .filter(|(rule, _)| {
+                rule.content_type
+                    .as_deref()
+                    .map_or(true, |rule_type| rule_type == content_type)
+            })
+            .filter_map(|(rule, matcher)| {
+                let matched = matcher.find(text)?.as_str().to_string();
+                Some((rule.clone(), matched))
+            })
+            .collect())
+    }
+
+    /// The enabled rules with their patterns compiled, those that don't
+    /// compile left out.
+    async fn load_webhook_rules(&self) -> Result<Vec<(WebhookRule, Regex)>, sqlx::Error> {
+        let rules = sqlx::query_as::<_, WebhookRule>(&format!(
+            "SELECT {WEBHOOK_RULE_COLUMNS} FROM webhook_rules WHERE enabled = 1 ORDER BY id"
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rules
+            .into_iter()
+            .filter_map(
+                |rule| match webhook_rule_matcher(&rule.pattern, rule.is_regex) {
+                    Ok(matcher) => Some((rule, matcher)),
+                    Err(e) => {
+                        warn!("webhook rule {} has an invalid pattern: {}", rule.id, e);
+                        None
+                    }
+                },
+            )
+            .collect())
+    }
+
+    /// Sends the match unless the rule already fired within its cooldown.
+    async fn fire_webhook_rule<T: serde::Serialize>(
+        &self,
+        rule: WebhookRule,
+        content_type: &str,
+        content: &T,
+        matched: String,
+    ) -> Result<(), sqlx::Error> {
+        let now = Utc::now();
+        let claimed = sqlx::query(
+            r#"
+            UPDATE webhook_rules SET last_fired_at = ?2
+            WHERE id = ?1
+                AND (last_fired_at IS NULL
+                    OR julianday(?2) - julianday(last_fired_at) >= cooldown_secs / 86400.0)
+            "#,
+        )
+        .bind(rule.id)
+        .bind(now)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+        if claimed == 0 {
+            debug!("webhook rule {} matched during its cooldown", rule.id);
+            return Ok(());
+        }
+
+        let content = serde_json::to_value(content)
+            .map_err(|e| sqlx::Error::Protocol(format!("failed to serialize match: {}", e)))?;
+        let _ = self.webhook_matches.send(WebhookMatch {
+            rule: WebhookRule {
+                last_fired_at: Some(now),
+                ..rule
+            },
+            content_type: content_type.to_string(),
+            content,
+            matched,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+use crate::{DatabaseManager, WhiteboardImage};
+
+impl DatabaseManager {
+    /// Records where the original and enhanced versions of a whiteboard photo
+    /// are stored. Enhancing the same frame again overwrites the paths.
+    pub async fn insert_whiteboard_image(
+        &self,
+        frame_id: i64,
+        original_path: &str,
+        enhanced_path: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO whiteboard_images (frame_id, original_path, enhanced_path)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT(frame_id) DO UPDATE SET
+                original_path = excluded.original_path,
+                enhanced_path = excluded.enhanced_path
+            "#,
+        )
+        .bind(frame_id)
+        .bind(original_path)
+        .bind(enhanced_path)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_whiteboard_image(
+        &self,
+        frame_id: i64,
+    ) -> Result<Option<WhiteboardImage>, sqlx::Error> {
+        sqlx::query_as::<_, WhiteboardImage>(
+            "SELECT frame_id, original_path, enhanced_path FROM whiteboard_images WHERE frame_id = ?1",
+        )
+        .bind(frame_id)
+        .fetch_optional(&self.pool)
+        .await
+    }
+}
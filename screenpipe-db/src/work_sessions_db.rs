@@ -0,0 +1,327 @@
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use crate::topic_terms::document_terms;
+use crate::{DatabaseManager, IdleInterval, WorkSession};
+
+// the apps used this long before and after a time are compared, a session
+// ends where they have none in common
+const CONTEXT_MINUTES: i64 = 15;
+// shorter sessions are left out
+const MIN_SESSION_MINUTES: i64 = 5;
+// apps listed per session
+const MAX_SESSION_APPS: usize = 5;
+
+const WORK_SESSION_COLUMNS: &str =
+    "id, started_at, ended_at, dominant_app, dominant_window, topic, apps, frames, transcriptions";
+
+type WorkSessionRow = (
+    i64,
+    DateTime<Utc>,
+    DateTime<Utc>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    String,
+    i64,
+    i64,
+);
+
+fn work_session(
+    (id, started_at, ended_at, dominant_app, dominant_window, topic, apps, frames, transcriptions): WorkSessionRow,
+) -> WorkSession {
+    WorkSession {
+        id,
+        started_at,
+        ended_at,
+        dominant_app,
+        dominant_window,
+        topic,
+        apps: serde_json::from_str(&apps).unwrap_or_default(),
+        frames,
+        transcriptions,
+    }
+}
+
+// what was recorded at a time: the app and window title of a frame, none
+// for a transcription
+type Activity = (DateTime<Utc>, Option<(String, String)>);
+
+/// Splits activity, sorted by time, into sessions: at a break of at least
+/// `min_break` or an idle interval, then where the apps change to unrelated
+/// ones.
+fn session_bounds(
+    activity: &[Activity],
+    min_break: Duration,
+    idle: &[IdleInterval],
+) -> Vec<Range<usize>> {
+    let mut segments = Vec::new();
+    let mut first = 0;
+    for i in 1..activity.len() {
+        let (before, at) = (activity[i - 1].0, activity[i].0);
+        let is_break = at - before >= min_break
+            || idle
+                .iter()
+                .any(|interval| interval.started_at >= before && interval.ended_at <= at);
+        if is_break {
+            segments.push(first..i);
+            first = i;
+        }
+    }
+    if !activity.is_empty() {
+        segments.push(first..activity.len());
+    }
+    segments
+        .into_iter()
+        .flat_map(|segment| split_at_switches(activity, segment))
+        .collect()
+}
+
+/// Splits `segment` before the activity where the apps of the
+/// `CONTEXT_MINUTES` before share none with the ones of the
+/// `CONTEXT_MINUTES` after. Transcriptions have no app, a call in between
+/// doesn't split.
+fn split_at_switches(activity: &[Activity], segment: Range<usize>) -> Vec<Range<usize>> {
+    let context = Duration::minutes(CONTEXT_MINUTES);
+    let app = |i: usize| {
+        activity[i]
+            .1
+            .as_ref()
+            .map(|(app, _)| app.as_str())
+            .filter(|app| !app.is_empty())
+    };
+
+    // the apps of tail..i and i..head
+    let (mut before, mut after): (HashMap<&str, usize>, HashMap<&str, usize>) =
+        (HashMap::new(), HashMap::new());
+    let (mut tail, mut head) = (segment.start, segment.start);
+    let mut bounds = Vec::new();
+    let mut first = segment.start;
+    for i in segment.clone() {
+        let at = activity[i].0;
+        while head < segment.end && activity[head].0 < at + context {
+            count_app(&mut after, app(head));
+            head += 1;
+        }
+        while tail < i && activity[tail].0 < at - context {
+            uncount_app(&mut before, app(tail));
+            tail += 1;
+        }
+        if i > first
+            && !before.is_empty()
+            && !after.is_empty()
+            && before.keys().all(|app| !after.contains_key(app))
+        {
+            bounds.push(first..i);
+            first = i;
+        }
+        uncount_app(&mut after, app(i));
+        count_app(&mut before, app(i));
+    }
+    bounds.push(first..segment.end);
+    bounds
+}
+
+fn count_app<'a>(counts: &mut HashMap<&'a str, usize>, app: Option<&'a str>) {
+    if let Some(app) = app {
+        *counts.entry(app).or_default() += 1;
+    }
+}
+
+fn uncount_app(counts: &mut HashMap<&str, usize>, app: Option<&str>) {
+    let Some(app) = app else {
+        return;
+    };
+    if let Some(count) = counts.get_mut(app) {
+        *count -= 1;
+        if *count == 0 {
+            counts.remove(app);
+        }
+    }
+}
+
+/// The session of `activity`, without its id.
+fn describe_session(activity: &[Activity]) -> WorkSession {
+    let mut apps: HashMap<&str, i64> = HashMap::new();
+    let mut windows: HashMap<(&str, &str), i64> = HashMap::new();
+    let (mut frames, mut transcriptions) = (0, 0);
+    for (_, frame) in activity {
+        let Some((app, window)) = frame else {
+            transcriptions += 1;
+            continue;
+        };
+        frames += 1;
+        if !app.is_empty() {
+            *apps.entry(app.as_str()).or_default() += 1;
+        }
+        if !window.is_empty() {
+            *windows.entry((app.as_str(), window.as_str())).or_default() += 1;
+        }
+    }
+    let mut apps: Vec<(&str, i64)> = apps.into_iter().collect();
+    apps.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    let dominant_window = windows
+        .iter()
+        .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))
+        .map(|((_, window), _)| window.to_string());
+
+    // terms of the app names say nothing of the work
+    let app_terms: HashSet<String> = apps
+        .iter()
+        .flat_map(|(app, _)| document_terms(app))
+        .collect();
+    let mut terms: HashMap<String, i64> = HashMap::new();
+    for ((_, window), count) in &windows {
+        for term in document_terms(window) {
+            if !term.split(' ').any(|word| app_terms.contains(word)) {
+                *terms.entry(term).or_default() += count;
+            }
+        }
+    }
+    // a pair of words as frequent as its words says more
+    let topic = terms
+        .into_iter()
+        .filter(|(_, count)| *count > 1)
+        .max_by(|a, b| {
+            a.1.cmp(&b.1)
+                .then(a.0.contains(' ').cmp(&b.0.contains(' ')))
+                .then(b.0.cmp(&a.0))
+        })
+        .map(|(term, _)| term);
+
+    WorkSession {
+        id: 0,
+        started_at: activity.first().map_or_else(Utc::now, |(at, _)| *at),
+        ended_at: activity.last().map_or_else(Utc::now, |(at, _)| *at),
+        dominant_app: apps.first().map(|(app, _)| app.to_string()),
+        dominant_window,
+        topic,
+        apps: apps
+            .iter()
+            .take(MAX_SESSION_APPS)
+            .map(|(app, _)| app.to_string())
+            .collect(),
+        frames,
+        transcriptions,
+    }
+}
+
+impl DatabaseManager {
+    /// Detects the work sessions of `start..end`, split at breaks of at
+    /// least `min_break`, idle intervals and changes to unrelated apps, in
+    /// place of the ones detected before. A session going on at `start` is
+    /// detected again from its beginning, one going on at `end` is stored
+    /// and extended by the next detection. Returns the ones stored.
+    pub async fn detect_work_sessions(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        min_break: Duration,
+    ) -> Result<Vec<WorkSession>, sqlx::Error> {
+        let ongoing: Option<DateTime<Utc>> = sqlx::query_scalar(
+            "SELECT MIN(started_at) FROM work_sessions WHERE started_at < ?1 AND ended_at > ?2",
+        )
+        .bind(start)
+        .bind(start - min_break)
+        .fetch_one(&self.pool)
+        .await?;
+        let start = ongoing.map_or(start, |ongoing| ongoing.min(start));
+
+        let rows = sqlx::query_as::<_, (DateTime<Utc>, Option<String>, Option<String>)>(
+            r#"
+            SELECT f.timestamp, COALESCE(f.app_name, ''), COALESCE(f.window_name, '')
+            FROM frames f
+            WHERE f.timestamp >= ?1 AND f.timestamp < ?2 AND f.deleted_at IS NULL
+            UNION ALL
+            SELECT at.timestamp, NULL, NULL
+            FROM audio_transcriptions at
+            JOIN audio_chunks ac ON at.audio_chunk_id = ac.id
+            WHERE at.timestamp >= ?1 AND at.timestamp < ?2 AND ac.deleted_at IS NULL
+            "#,
+        )
+        .bind(start)
+        .bind(end)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut activity: Vec<Activity> = rows
+            .into_iter()
+            .map(|(at, app, window)| (at, app.zip(window)))
+            .collect();
+        activity.sort_by_key(|(at, _)| *at);
+        let idle = self.get_idle_intervals(start, end).await?;
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM work_sessions WHERE started_at >= ?1 AND started_at < ?2")
+            .bind(start)
+            .bind(end)
+            .execute(&mut *tx)
+            .await?;
+        let mut stored = Vec::new();
+        for bounds in session_bounds(&activity, min_break, &idle) {
+            let session = describe_session(&activity[bounds]);
+            if session.ended_at - session.started_at < Duration::minutes(MIN_SESSION_MINUTES) {
+                continue;
+            }
+            let row: WorkSessionRow = sqlx::query_as(&format!(
+                r#"
+                INSERT INTO work_sessions
+                    (started_at, ended_at, dominant_app, dominant_window, topic, apps, frames, transcriptions)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                RETURNING {WORK_SESSION_COLUMNS}
+                "#
+            ))
+            .bind(session.started_at)
+            .bind(session.ended_at)
+            .bind(&session.dominant_app)
+            .bind(&session.dominant_window)
+            .bind(&session.topic)
+            .bind(serde_json::to_string(&session.apps).unwrap_or_else(|_| "[]".to_string()))
+            .bind(session.frames)
+            .bind(session.transcriptions)
+            .fetch_one(&mut *tx)
+            .await?;
+            stored.push(work_session(row));
+        }
+        tx.commit().await?;
+        Ok(stored)
+    }
+
+    /// Work sessions overlapping `start..end`, oldest first.
+    pub async fn get_work_sessions(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<WorkSession>, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        let rows: Vec<WorkSessionRow> = guard
+            .run(
+                sqlx::query_as(&format!(
+                    r#"
+                    SELECT {WORK_SESSION_COLUMNS} FROM work_sessions
+                    WHERE started_at < ?2 AND ended_at >= ?1
+                    ORDER BY started_at
+                    "#
+                ))
+                .bind(start)
+                .bind(end)
+                .fetch_all(&mut *conn),
+            )
+            .await?;
+        Ok(rows.into_iter().map(work_session).collect())
+    }
+
+    pub async fn get_work_session(&self, id: i64) -> Result<Option<WorkSession>, sqlx::Error> {
+        let (mut conn, guard) = self.read_connection().await?;
+        let row: Option<WorkSessionRow> = guard
+            .run(
+                sqlx::query_as(&format!(
+                    "SELECT {WORK_SESSION_COLUMNS} FROM work_sessions WHERE id = ?1"
+                ))
+                .bind(id)
+                .fetch_optional(&mut *conn),
+            )
+            .await?;
+        Ok(row.map(work_session))
+    }
+}
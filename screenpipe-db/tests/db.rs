@@ -4,7 +4,16 @@ mod tests {
 
     use chrono::Utc;
     use screenpipe_db::{
-        AudioDevice, ContentType, DatabaseManager, DeviceType, Frame, OcrEngine, SearchResult,
+        apply_line_edits, block_confidence, click_boost, click_weight, diff_lines, extract_tables,
+        normalize_query, normalize_tag, parse_ocr_blocks, recency_weight,
+        representative_embeddings, AudioDevice, BulkFilter, ClickBoosts, ClickContentType,
+        ContentHook, ContentMetadata, ContentProcessor, ContentType, DatabaseManager, DeviceType,
+        EmbeddingQuantization, Frame, HookContent, HookOutcome, InputActivity, MediaChunkKind,
+        NewAppCategoryRule, NewCalendarHint, NewCaptureBlockRule, NewFrame, NewPushDestination,
+        NewSearchClick, NewTagRule, NewWebhookRule, OcrEngine, Order, ResultCount, RetentionRule,
+        SearchDeleteFilter, SearchResult, SearchTagging, Subject, SyncWatermarks, TagContentType,
+        TagFacet, TagFilter, TagSummary, TagUpdate, TextBounds, UrlPattern, OCR_TEXT_EMBEDDINGS,
+        UI_KEYFRAME_INTERVAL,
     };
 
     async fn setup_test_db() -> DatabaseManager {
@@ -65,6 +74,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -113,6 +125,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -134,6 +149,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -189,6 +207,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -210,6 +231,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -297,6 +321,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -318,6 +345,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -458,14 +488,34 @@ mod tests {
 
         // After inserting both audio transcriptions, let's check all audio entries
         let all_audio = db
-            .search_audio("", 100, 0, None, None, None, None, None)
+            .search_audio(
+                "",
+                100,
+                0,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &TagFilter::default(),
+            )
             .await
             .unwrap();
         println!("All audio entries: {:?}", all_audio);
 
         // Then try specific search
         let audio_results = db
-            .search_audio("2", 100, 0, None, None, None, None, None)
+            .search_audio(
+                "2",
+                100,
+                0,
+                None,
+                None,
+                None,
+                None,
+                None,
+                &TagFilter::default(),
+            )
             .await
             .unwrap();
         println!("Audio results for '2': {:?}", audio_results);
@@ -489,6 +539,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -513,6 +566,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -536,6 +592,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -563,6 +622,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -585,6 +647,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -690,6 +755,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -716,6 +784,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -821,7 +892,7 @@ mod tests {
         }
 
         // insert a speaker with a name
-        let speaker = db.insert_speaker(&vec![0.1; 512]).await.unwrap();
+        let speaker = db.insert_speaker(&[0.1; 512]).await.unwrap();
         db.update_speaker_name(speaker.id, "test name")
             .await
             .unwrap();
@@ -888,7 +959,7 @@ mod tests {
         }
 
         // insert a speaker with a name
-        let speaker = db.insert_speaker(&vec![0.1; 512]).await.unwrap();
+        let speaker = db.insert_speaker(&[0.1; 512]).await.unwrap();
         db.update_speaker_name(speaker.id, "test name")
             .await
             .unwrap();
@@ -910,11 +981,11 @@ mod tests {
     async fn test_merge_speakers() {
         let db = setup_test_db().await;
 
-        let speaker_1 = db.insert_speaker(&vec![0.1; 512]).await.unwrap();
+        let speaker_1 = db.insert_speaker(&[0.1; 512]).await.unwrap();
         db.update_speaker_name(speaker_1.id, "speaker 1")
             .await
             .unwrap();
-        let speaker_2 = db.insert_speaker(&vec![0.2; 512]).await.unwrap();
+        let speaker_2 = db.insert_speaker(&[0.2; 512]).await.unwrap();
         db.update_speaker_name(speaker_2.id, "speaker 2")
             .await
             .unwrap();
@@ -957,7 +1028,7 @@ mod tests {
     async fn test_search_speakers() {
         let db = setup_test_db().await;
 
-        let speaker = db.insert_speaker(&vec![0.1; 512]).await.unwrap();
+        let speaker = db.insert_speaker(&[0.1; 512]).await.unwrap();
         db.update_speaker_name(speaker.id, "test name")
             .await
             .unwrap();
@@ -971,7 +1042,7 @@ mod tests {
     async fn test_delete_speaker() {
         let db = setup_test_db().await;
 
-        let speaker = db.insert_speaker(&vec![0.1; 512]).await.unwrap();
+        let speaker = db.insert_speaker(&[0.1; 512]).await.unwrap();
 
         let audio_chunk_id = db.insert_audio_chunk("test_audio.mp4").await.unwrap();
         db.insert_audio_transcription(
@@ -1004,7 +1075,7 @@ mod tests {
     async fn test_mark_speaker_as_hallucination() {
         let db = setup_test_db().await;
 
-        let speaker = db.insert_speaker(&vec![0.1; 512]).await.unwrap();
+        let speaker = db.insert_speaker(&[0.1; 512]).await.unwrap();
         db.mark_speaker_as_hallucination(speaker.id).await.unwrap();
 
         let speakers = db.search_speakers("").await.unwrap();
@@ -1016,7 +1087,7 @@ mod tests {
         let db = setup_test_db().await;
 
         // Create first speaker with audio data
-        let speaker = db.insert_speaker(&vec![0.1; 512]).await.unwrap();
+        let speaker = db.insert_speaker(&[0.1; 512]).await.unwrap();
         db.update_speaker_name(speaker.id, "test name")
             .await
             .unwrap();
@@ -1038,7 +1109,7 @@ mod tests {
         .unwrap();
 
         // Create second speaker with audio data
-        let speaker2 = db.insert_speaker(&vec![0.2; 512]).await.unwrap();
+        let speaker2 = db.insert_speaker(&[0.2; 512]).await.unwrap();
         db.update_speaker_name(speaker2.id, "name").await.unwrap();
         let audio_chunk_id2 = db.insert_audio_chunk("test_audio2.mp4").await.unwrap();
         db.insert_audio_transcription(
@@ -1117,6 +1188,9 @@ mod tests {
                 Some("test_video"),
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -1144,6 +1218,9 @@ mod tests {
                 Some("non_existent"),
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -1171,6 +1248,9 @@ mod tests {
                 Some("test_video"),
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -1196,6 +1276,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -1245,6 +1328,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -1274,6 +1360,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -1296,6 +1385,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -1318,6 +1410,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -1400,6 +1495,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -1420,6 +1518,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -1440,9 +1541,6605 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
         assert_eq!(count, 0, "Should count zero results for non-matching query");
     }
+
+    #[tokio::test]
+    async fn test_replace_ocr_text() {
+        let db = setup_test_db().await;
+        let _ = db
+            .insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+        let frame_id = db
+            .insert_frame("test_device", None, None, Some("test"), Some(""), false)
+            .await
+            .unwrap();
+        db.insert_ocr_text(frame_id, "h3llo w0rld", "", Arc::new(OcrEngine::Tesseract))
+            .await
+            .unwrap();
+
+        db.replace_ocr_text(
+            frame_id,
+            "hello world",
+            "[]",
+            Arc::new(OcrEngine::AppleNative),
+        )
+        .await
+        .unwrap();
+
+        let results = db
+            .search(
+                "hello",
+                ContentType::OCR,
+                100,
+                0,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                false,
+                &TagFilter::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        if let SearchResult::OCR(ocr_result) = &results[0] {
+            assert_eq!(ocr_result.ocr_text, "hello world");
+            assert_eq!(ocr_result.frame_id, frame_id);
+        } else {
+            panic!("Expected OCR result");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upsert_monitor_settings() {
+        let db = setup_test_db().await;
+
+        let settings = db
+            .upsert_monitor_settings(1, true, Some(0.2), 2.0)
+            .await
+            .unwrap();
+        assert!(settings.enabled);
+        assert_eq!(settings.fps, Some(0.2));
+        assert_eq!(settings.downscale_factor, 2.0);
+
+        db.upsert_monitor_settings(1, false, None, 1.0)
+            .await
+            .unwrap();
+        db.upsert_monitor_settings(2, true, None, 1.5)
+            .await
+            .unwrap();
+
+        let all = db.get_monitor_settings().await.unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].monitor_id, 1);
+        assert!(!all[0].enabled);
+        assert_eq!(all[0].fps, None);
+
+        let second = db.get_monitor_settings_by_id(2).await.unwrap().unwrap();
+        assert_eq!(second.downscale_factor, 1.5);
+        assert!(db.get_monitor_settings_by_id(3).await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_block_confidence_per_engine() {
+        let block = |confidence: &str| {
+            std::collections::HashMap::from([("confidence".to_string(), confidence.to_string())])
+        };
+        // tesseract reports 0..100, even a confidence of 1 is a low one
+        assert_eq!(block_confidence(&block("1"), "Tesseract"), Some(0.01));
+        assert_eq!(block_confidence(&block("0.5"), "Tesseract"), Some(0.005));
+        assert_eq!(block_confidence(&block("96"), "Tesseract"), Some(0.96));
+        assert_eq!(block_confidence(&block("1"), "AppleNative"), Some(1.0));
+        assert_eq!(block_confidence(&block("0.5"), "AppleNative"), Some(0.5));
+        // off the engine's scale
+        assert_eq!(block_confidence(&block("90"), "AppleNative"), None);
+        assert_eq!(block_confidence(&block("-1"), "Tesseract"), None);
+    }
+
+    #[tokio::test]
+    async fn test_search_ocr_min_confidence() {
+        let db = setup_test_db().await;
+        let _ = db
+            .insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+
+        let clear_frame = db
+            .insert_frame("test_device", None, None, Some("test"), Some(""), false)
+            .await
+            .unwrap();
+        db.insert_ocr_text(
+            clear_frame,
+            "quarterly report",
+            r#"[{"text":"quarterly","confidence":"96.0"},{"text":"report","confidence":"92.0"}]"#,
+            Arc::new(OcrEngine::Tesseract),
+        )
+        .await
+        .unwrap();
+
+        let noisy_frame = db
+            .insert_frame("test_device", None, None, Some("test"), Some(""), false)
+            .await
+            .unwrap();
+        db.insert_ocr_text(
+            noisy_frame,
+            "qu4rt3rly rep0rt",
+            r#"[{"text":"qu4rt3rly","conf":"0.2"},{"text":"rep0rt","conf":"0.4"}]"#,
+            Arc::new(OcrEngine::AppleNative),
+        )
+        .await
+        .unwrap();
+
+        let results = db
+            .search(
+                "",
+                ContentType::OCR,
+                100,
+                0,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(0.5),
+                false,
+                &TagFilter::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        if let SearchResult::OCR(ocr_result) = &results[0] {
+            assert_eq!(ocr_result.frame_id, clear_frame);
+        } else {
+            panic!("Expected OCR result");
+        }
+
+        let count = db
+            .count_search_results(
+                "",
+                ContentType::OCR,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(0.5),
+                false,
+                &TagFilter::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+
+        let count = db
+            .count_search_results(
+                "",
+                ContentType::OCR,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                false,
+                &TagFilter::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+    }
+
+    #[tokio::test]
+    async fn test_meeting_participants_auto_link() {
+        let db = setup_test_db().await;
+        let started = Utc::now() - chrono::Duration::minutes(1);
+        let meeting_id = db.start_meeting("zoom.us", started).await.unwrap();
+        assert_eq!(
+            db.start_meeting("zoom.us", Utc::now()).await.unwrap(),
+            meeting_id
+        );
+
+        let _ = db
+            .insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+        let frame_id = db
+            .insert_frame("test_device", None, None, Some("zoom.us"), Some(""), true)
+            .await
+            .unwrap();
+        db.upsert_meeting_participants(
+            meeting_id,
+            &[
+                ("Alice Martin".to_string(), false),
+                ("Dan O'Neil".to_string(), true),
+            ],
+            frame_id,
+            Utc::now(),
+        )
+        .await
+        .unwrap();
+
+        let remote = db.insert_speaker(&[0.1; 512]).await.unwrap();
+        let local = db.insert_speaker(&[0.2; 512]).await.unwrap();
+        let audio_chunk_id = db.insert_audio_chunk("test_audio.mp4").await.unwrap();
+        for (speaker_id, device_type) in [
+            (remote.id, DeviceType::Output),
+            (local.id, DeviceType::Input),
+        ] {
+            db.insert_audio_transcription(
+                audio_chunk_id,
+                "hello everyone",
+                0,
+                "",
+                &AudioDevice {
+                    name: "test".to_string(),
+                    device_type,
+                },
+                Some(speaker_id),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+
+        let linked = db.auto_link_meeting_speakers(meeting_id).await.unwrap();
+        assert_eq!(linked.len(), 2);
+
+        let participants = db.get_meeting_participants(meeting_id).await.unwrap();
+        let alice = participants
+            .iter()
+            .find(|p| p.name == "Alice Martin")
+            .unwrap();
+        assert_eq!(alice.speaker_id, Some(remote.id));
+        assert_eq!(
+            db.get_speaker_by_id(remote.id).await.unwrap().name,
+            "Alice Martin"
+        );
+        assert_eq!(
+            db.get_speaker_by_id(local.id).await.unwrap().name,
+            "Dan O'Neil"
+        );
+
+        assert_eq!(db.end_meetings(Utc::now()).await.unwrap(), 1);
+        assert!(db.get_meeting(meeting_id).await.unwrap().end_time.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_meeting_slides() {
+        let db = setup_test_db().await;
+        // a deck shown outside of a call has no meeting to go to
+        assert_eq!(db.current_meeting(Utc::now()).await.unwrap(), None);
+        let meeting_id = db.start_meeting("zoom.us", Utc::now()).await.unwrap();
+        assert_eq!(
+            db.current_meeting(Utc::now()).await.unwrap(),
+            Some(meeting_id)
+        );
+        let _ = db
+            .insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+        let mut frame_ids = Vec::new();
+        for _ in 0..3 {
+            frame_ids.push(
+                db.insert_frame("test_device", None, None, Some("zoom.us"), Some(""), true)
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        let first = db
+            .insert_meeting_slide(meeting_id, frame_ids[0], "Agenda", Utc::now())
+            .await
+            .unwrap();
+        db.insert_meeting_slide(meeting_id, frame_ids[1], "Results", Utc::now())
+            .await
+            .unwrap();
+        db.update_meeting_slide(first, Some((frame_ids[2], "Agenda\nIntro")), Utc::now())
+            .await
+            .unwrap();
+
+        let slides = db.get_meeting_slides(meeting_id).await.unwrap();
+        assert_eq!(slides.len(), 2);
+        assert_eq!(slides[0].slide_index, 0);
+        assert_eq!(slides[0].frame_id, frame_ids[2]);
+        assert_eq!(slides[0].text, "Agenda\nIntro");
+        assert_eq!(slides[1].slide_index, 1);
+        assert_eq!(slides[1].text, "Results");
+    }
+
+    #[tokio::test]
+    async fn test_text_spans_across_frames() {
+        let db = setup_test_db().await;
+        let _ = db
+            .insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+
+        let block = |text: &str, top: u32| {
+            format!(
+                r#"{{"text":"{}","left":"10","top":"{}","width":"200","height":"30"}}"#,
+                text, top
+            )
+        };
+        let frames = [
+            format!("[{},{}]", block("Build failed", 20), block("line 1", 80)),
+            // the banner jitters by a pixel, the editor line changes
+            format!("[{},{}]", block("Build failed", 21), block("line 2", 80)),
+            format!("[{}]", block("Build failed", 20)),
+        ];
+        for text_json in &frames {
+            let frame_id = db
+                .insert_frame("test_device", None, None, None, None, true)
+                .await
+                .unwrap();
+            db.insert_ocr_text(frame_id, "", text_json, Arc::new(OcrEngine::Tesseract))
+                .await
+                .unwrap();
+        }
+
+        let spans = db
+            .search_text_spans(Some("build"), None, None, 0.0, 100, 0)
+            .await
+            .unwrap();
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].frame_count, 3);
+        assert!(spans[0].last_seen >= spans[0].first_seen);
+
+        let lines = db
+            .search_text_spans(Some("line"), None, None, 0.0, 100, 0)
+            .await
+            .unwrap();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|span| span.frame_count == 1));
+
+        let long_lived = db
+            .search_text_spans(None, None, None, 3600.0, 100, 0)
+            .await
+            .unwrap();
+        assert!(long_lived.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_text_spans_with_interleaved_windows() {
+        let db = setup_test_db().await;
+        let _ = db
+            .insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+
+        let block = |text: &str| {
+            format!(
+                r#"[{{"text":"{}","left":"10","top":"20","width":"200","height":"30"}}]"#,
+                text
+            )
+        };
+        // one capture stores a frame per window, so the windows alternate
+        for _ in 0..3 {
+            for (window_name, text) in [("editor", "fn main()"), ("terminal", "cargo build")] {
+                let frame_id = db
+                    .insert_frame(
+                        "test_device",
+                        None,
+                        None,
+                        Some("app"),
+                        Some(window_name),
+                        true,
+                    )
+                    .await
+                    .unwrap();
+                db.insert_ocr_text(frame_id, "", &block(text), Arc::new(OcrEngine::Tesseract))
+                    .await
+                    .unwrap();
+            }
+        }
+
+        for query in ["main", "cargo"] {
+            let spans = db
+                .search_text_spans(Some(query), None, None, 0.0, 100, 0)
+                .await
+                .unwrap();
+            assert_eq!(spans.len(), 1, "{}", query);
+            assert_eq!(spans[0].frame_count, 3, "{}", query);
+        }
+    }
+
+    fn table_json(rows: &[&[&str]]) -> String {
+        let blocks: Vec<String> = rows
+            .iter()
+            .enumerate()
+            .flat_map(|(row, cells)| {
+                cells.iter().enumerate().map(move |(column, text)| {
+                    format!(
+                        r#"{{"text":"{}","left":"{}","top":"{}","width":"80","height":"20"}}"#,
+                        text,
+                        10 + column * 150,
+                        100 + row * 30
+                    )
+                })
+            })
+            .collect();
+        format!("[{}]", blocks.join(","))
+    }
+
+    #[test]
+    fn test_extract_tables_from_ocr_blocks() {
+        let text_json = table_json(&[
+            &["Region", "Revenue", "Growth"],
+            &["EMEA", "1.2M", "4%"],
+            &["APAC", "0.9M", "11%"],
+        ]);
+        let tables = extract_tables(&parse_ocr_blocks(&text_json, "Tesseract"));
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].rows.len(), 3);
+        assert_eq!(tables[0].rows[2], vec!["APAC", "0.9M", "11%"]);
+
+        // a paragraph: words on consecutive lines with no gutter between them
+        let paragraph = (0..3)
+            .flat_map(|line| {
+                (0..5).map(move |word| {
+                    format!(
+                        r#"{{"text":"word","left":"{}","top":"{}","width":"40","height":"20"}}"#,
+                        10 + word * 45 + line * 7,
+                        100 + line * 30
+                    )
+                })
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        assert!(
+            extract_tables(&parse_ocr_blocks(&format!("[{}]", paragraph), "Tesseract")).is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_in_tables() {
+        let db = setup_test_db().await;
+        let _ = db
+            .insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+
+        let table_frame = db
+            .insert_frame("test_device", None, None, None, None, true)
+            .await
+            .unwrap();
+        db.insert_ocr_text(
+            table_frame,
+            "Region Revenue EMEA 1.2M APAC 0.9M",
+            &table_json(&[&["Region", "Revenue"], &["EMEA", "1.2M"], &["APAC", "0.9M"]]),
+            Arc::new(OcrEngine::Tesseract),
+        )
+        .await
+        .unwrap();
+        let text_frame = db
+            .insert_frame("test_device", None, None, None, None, true)
+            .await
+            .unwrap();
+        db.insert_ocr_text(
+            text_frame,
+            "EMEA revenue grew this quarter",
+            "",
+            Arc::new(OcrEngine::Tesseract),
+        )
+        .await
+        .unwrap();
+
+        let results = db
+            .search(
+                "EMEA",
+                ContentType::OCR,
+                100,
+                0,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                true,
+                &TagFilter::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        if let SearchResult::OCR(ocr) = &results[0] {
+            assert_eq!(ocr.frame_id, table_frame);
+            assert_eq!(ocr.tables[0].rows[1], vec!["EMEA", "1.2M"]);
+        } else {
+            panic!("expected an OCR result");
+        }
+
+        let count = db
+            .count_search_results(
+                "EMEA",
+                ContentType::OCR,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                true,
+                &TagFilter::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_prune_expired_with_tag_rules() {
+        let db = setup_test_db().await;
+        let _ = db
+            .insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+
+        let now = Utc::now();
+        let mut frames = Vec::new();
+        for (days_ago, tag) in [
+            (30, Some("receipt")),
+            (10, Some("youtube")),
+            (3, Some("youtube")),
+            (30, None),
+            (1, None),
+        ] {
+            let frame_id = db
+                .insert_frame(
+                    "test_device",
+                    Some(now - chrono::Duration::days(days_ago)),
+                    None,
+                    None,
+                    None,
+                    true,
+                )
+                .await
+                .unwrap();
+            if let Some(tag) = tag {
+                db.add_tags(frame_id, TagContentType::Vision, vec![tag.to_string()])
+                    .await
+                    .unwrap();
+            }
+            frames.push(frame_id);
+        }
+
+        let rules: Vec<RetentionRule> = ["tag:receipt=forever", "tag:youtube=7d"]
+            .iter()
+            .map(|rule| rule.parse().unwrap())
+            .collect();
+        let pruned = db.prune_expired(now, Some(14), &rules, 100).await.unwrap();
+        assert_eq!(pruned.frames, 2);
+        // the chunk still holds frames
+        assert!(pruned.video_files.is_empty());
+
+        let remaining: Vec<i64> = sqlx::query_scalar("SELECT id FROM frames ORDER BY id")
+            .fetch_all(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(remaining, vec![frames[0], frames[2], frames[4]]);
+
+        // without a default, only the tag rules delete anything
+        let pruned = db
+            .prune_expired(now + chrono::Duration::days(60), None, &rules, 100)
+            .await
+            .unwrap();
+        assert_eq!(pruned.frames, 1);
+        assert!("youtube=7d".parse::<RetentionRule>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_graph_queries() {
+        let db = setup_test_db().await;
+        let chunk_id = db
+            .insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+
+        let mut frames = Vec::new();
+        for (app_name, text) in [("firefox", "invoice 42"), ("slack", "standup notes")] {
+            let frame_id = db
+                .insert_frame("test_device", None, None, Some(app_name), None, true)
+                .await
+                .unwrap();
+            db.insert_ocr_text(frame_id, text, "", Arc::new(OcrEngine::Tesseract))
+                .await
+                .unwrap();
+            frames.push(frame_id);
+        }
+        db.add_tags(frames[0], TagContentType::Vision, vec!["receipt".to_string()])
+            .await
+            .unwrap();
+
+        let tagged = db
+            .list_graph_frames(None, None, None, Some("receipt"), 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(tagged.len(), 1);
+        assert_eq!(tagged[0].id, frames[0]);
+        assert_eq!(tagged[0].app_name.as_deref(), Some("firefox"));
+
+        let slack = db
+            .list_graph_frames(None, None, Some("slack"), None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(slack.len(), 1);
+        assert_eq!(slack[0].id, frames[1]);
+
+        let ocr = db.get_graph_ocr(frames[0]).await.unwrap().unwrap();
+        assert_eq!(ocr.text, "invoice 42");
+        let chunk = db
+            .get_graph_video_chunk(tagged[0].video_chunk_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(chunk.id, chunk_id);
+        assert_eq!(chunk.file_path, "test_video.mp4");
+
+        let chunk_frames = db.list_graph_chunk_frames(chunk_id, 10, 0).await.unwrap();
+        assert_eq!(
+            chunk_frames.iter().map(|frame| frame.id).collect::<Vec<_>>(),
+            frames
+        );
+        assert!(db.get_graph_frame(frames[1] + 1).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_redact_frame() {
+        let db = setup_test_db().await;
+        let _ = db
+            .insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+        let frame_id = db
+            .insert_frame("test_device", None, None, None, None, true)
+            .await
+            .unwrap();
+        db.insert_ocr_text(
+            frame_id,
+            "my bank password is hunter2",
+            "",
+            Arc::new(OcrEngine::Tesseract),
+        )
+        .await
+        .unwrap();
+        // a span shown on across the frame, and one of another monitor
+        sqlx::query(
+            r#"
+            INSERT INTO ocr_text_spans
+                (device_name, text, x, y, width, height, first_frame_id, last_frame_id, first_seen, last_seen)
+            VALUES
+                ('test_device', 'hunter2', 0, 0, 10, 10, ?1, ?1 + 5, ?2, ?2),
+                ('other_device', 'inbox', 0, 0, 10, 10, ?1, ?1 + 5, ?2, ?2)
+            "#,
+        )
+        .bind(frame_id)
+        .bind(Utc::now())
+        .execute(&db.pool)
+        .await
+        .unwrap();
+
+        let redaction = db
+            .redact_frame(
+                frame_id,
+                Some(TextBounds {
+                    left: 10.0,
+                    top: 20.0,
+                    width: 300.0,
+                    height: 40.0,
+                }),
+                Some("password on screen"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(redaction.frame_id, frame_id);
+        assert_eq!(redaction.region_width, Some(300.0));
+
+        let results = db
+            .search(
+                "hunter2",
+                ContentType::OCR,
+                100,
+                0,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                false,
+                &TagFilter::default(),
+            )
+            .await
+            .unwrap();
+        assert!(results.is_empty());
+        let spans: Vec<String> = sqlx::query_scalar("SELECT device_name FROM ocr_text_spans")
+            .fetch_all(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(spans, vec!["other_device".to_string()]);
+
+        // the frame stays in the timeline, with its tombstone
+        assert!(db.get_frame(frame_id).await.unwrap().is_some());
+        let redactions = db.get_frame_redactions(frame_id).await.unwrap();
+        assert_eq!(redactions.len(), 1);
+        assert_eq!(redactions[0].reason.as_deref(), Some("password on screen"));
+
+        assert!(matches!(
+            db.redact_frame(frame_id + 1, None, None).await,
+            Err(sqlx::Error::RowNotFound)
+        ));
+
+        // its chunk can be re-encoded once the monitor moved to the next one
+        assert!(db.is_frame_recording(frame_id).await.unwrap());
+        db.insert_video_chunk("next_video.mp4", "test_device")
+            .await
+            .unwrap();
+        assert!(!db.is_frame_recording(frame_id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_trash_restore_and_purge() {
+        let db = setup_test_db().await;
+        let _ = db
+            .insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+
+        let now = Utc::now();
+        let mut frames = Vec::new();
+        for (days_ago, app_name) in [(30, "Chrome"), (30, "Slack"), (1, "Slack")] {
+            let frame_id = db
+                .insert_frame(
+                    "test_device",
+                    Some(now - chrono::Duration::days(days_ago)),
+                    None,
+                    Some(app_name),
+                    None,
+                    true,
+                )
+                .await
+                .unwrap();
+            db.insert_ocr_text(
+                frame_id,
+                "quarterly report",
+                "",
+                Arc::new(OcrEngine::Tesseract),
+            )
+            .await
+            .unwrap();
+            frames.push(frame_id);
+        }
+
+        let trashed = db.trash_expired(now, Some(14), &[], 100).await.unwrap();
+        assert_eq!(trashed.frames, 2);
+        assert!(db.get_frame(frames[0]).await.unwrap().is_none());
+        assert!(db
+            .search_with_text_positions(
+                "quarterly",
+                10,
+                0,
+                None,
+                None,
+                false,
+                Order::Descending,
+                None
+            )
+            .await
+            .unwrap()
+            .iter()
+            .all(|found| found.frame_id == frames[2]));
+        // trashed frames don't show up in search anymore
+        let results = db
+            .search(
+                "quarterly",
+                ContentType::OCR,
+                100,
+                0,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                false,
+                &TagFilter::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+
+        let groups = db.list_trash(chrono::Duration::days(7)).await.unwrap();
+        assert_eq!(groups.len(), 2);
+        assert!(groups.iter().all(|group| group.frame_count == 1));
+        assert_eq!(
+            groups[0].purge_at - groups[0].deleted_at,
+            chrono::Duration::days(7)
+        );
+
+        let day = (now - chrono::Duration::days(30))
+            .format("%Y-%m-%d")
+            .to_string();
+        let restored = db
+            .restore_trash(&[], &[], &[], &[], &[], Some(&day), Some("Slack"))
+            .await
+            .unwrap();
+        assert_eq!(restored.frames, 1);
+        // restored frames aren't trashed again
+        let trashed = db.trash_expired(now, Some(14), &[], 100).await.unwrap();
+        assert_eq!(trashed.frames, 0);
+
+        let items = db
+            .list_trash_items(chrono::Duration::days(7), None, None, 100, 0)
+            .await
+            .unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].id, frames[0]);
+
+        // nothing is old enough in the trash yet
+        let pruned = db
+            .purge_trash(now - chrono::Duration::days(7), &[], 100)
+            .await
+            .unwrap();
+        assert_eq!(pruned.frames, 0);
+        assert_eq!(
+            db.count_purgeable(now + chrono::Duration::seconds(1), &[])
+                .await
+                .unwrap()
+                .frames,
+            1
+        );
+        let pruned = db
+            .purge_trash(now + chrono::Duration::seconds(1), &[], 100)
+            .await
+            .unwrap();
+        assert_eq!(pruned.frames, 1);
+        assert!(db.get_frame(frames[0]).await.unwrap().is_none());
+        assert!(db.get_frame(frames[1]).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_webhook_rules_fire_on_insert() {
+        let db = setup_test_db().await;
+        let mut matches = db.subscribe_webhook_matches();
+        let rule = |name: &str, pattern: &str, app_name: Option<&str>| NewWebhookRule {
+            name: name.to_string(),
+            pattern: pattern.to_string(),
+            is_regex: false,
+            content_type: None,
+            app_name: app_name.map(String::from),
+            window_name: None,
+            speaker_id: None,
+            webhook_url: "https://hooks.slack.com/services/test".to_string(),
+            cooldown_secs: 300,
+        };
+        let incident = db
+            .insert_webhook_rule(&rule("incident", "production incident", None))
+            .await
+            .unwrap();
+        db.insert_webhook_rule(&rule("slack only", "deploy", Some("slack")))
+            .await
+            .unwrap();
+
+        let _ = db
+            .insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+        for (app_name, text) in [
+            ("Chrome", "PRODUCTION INCIDENT: api down"),
+            // same rule, still within its cooldown
+            ("Chrome", "production incident update"),
+            ("Chrome", "deploy finished"),
+        ] {
+            let frame_id = db
+                .insert_frame("test_device", None, None, Some(app_name), None, true)
+                .await
+                .unwrap();
+            db.insert_ocr_text(frame_id, text, "", Arc::new(OcrEngine::Tesseract))
+                .await
+                .unwrap();
+        }
+
+        let ocr_match = matches.try_recv().unwrap();
+        assert_eq!(ocr_match.rule.id, incident.id);
+        assert_eq!(ocr_match.content_type, "ocr");
+        assert_eq!(ocr_match.matched, "PRODUCTION INCIDENT");
+        assert_eq!(ocr_match.content["app_name"], "Chrome");
+        assert!(matches.try_recv().is_err());
+
+        // the cooldown is per rule, audio goes through the same rules
+        sqlx::query("UPDATE webhook_rules SET last_fired_at = NULL")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        let audio_chunk_id = db.insert_audio_chunk("test_audio.mp4").await.unwrap();
+        db.insert_audio_transcription(
+            audio_chunk_id,
+            "we have a production incident on the payments api",
+            0,
+            "",
+            &AudioDevice {
+                name: "test".to_string(),
+                device_type: DeviceType::Input,
+            },
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let audio_match = matches.try_recv().unwrap();
+        assert_eq!(audio_match.rule.id, incident.id);
+        assert_eq!(audio_match.content_type, "audio");
+        assert_eq!(audio_match.content["audio_chunk_id"], audio_chunk_id);
+        assert!(matches.try_recv().is_err());
+
+        assert!(db.delete_webhook_rule(incident.id).await.unwrap());
+        assert_eq!(db.list_webhook_rules().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_coverage_report() {
+        let db = setup_test_db().await;
+        let _ = db
+            .insert_video_chunk("test_video.mp4", "monitor_1")
+            .await
+            .unwrap();
+
+        let day = chrono::NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc();
+        // two hours recorded with a 10 minute hole in the middle
+        for minute in (0..60).chain(70..130) {
+            db.insert_frame(
+                "monitor_1",
+                Some(day + chrono::Duration::hours(9) + chrono::Duration::minutes(minute)),
+                None,
+                None,
+                None,
+                true,
+            )
+            .await
+            .unwrap();
+        }
+
+        let report = db
+            .get_coverage_report(day, day + chrono::Duration::days(1), 120.0, 10)
+            .await
+            .unwrap();
+        assert_eq!(report.days.len(), 1);
+        let coverage = &report.days[0];
+        assert_eq!(coverage.day, "2025-01-01");
+        assert_eq!(coverage.device_name, "monitor_1");
+        assert_eq!(coverage.kind, "vision");
+        assert_eq!(coverage.samples, 120);
+        // 118 one minute intervals, the 11 minute one is a gap
+        assert!((coverage.covered_secs - 118.0 * 60.0).abs() < 0.1);
+        assert_eq!(coverage.period_secs, 86400.0);
+        assert!((coverage.coverage_percent - 118.0 * 60.0 / 864.0).abs() < 0.01);
+
+        assert_eq!(report.gaps.len(), 1);
+        let gap = &report.gaps[0];
+        assert_eq!(
+            gap.start,
+            day + chrono::Duration::hours(9) + chrono::Duration::minutes(59)
+        );
+        assert!((gap.duration_secs - 11.0 * 60.0).abs() < 0.1);
+        assert!(!gap.recording_elsewhere);
+    }
+
+    #[tokio::test]
+    async fn test_clock_offset_keeps_shortest_round_trip() {
+        let db = setup_test_db().await;
+        assert!(db.get_clock_offset("laptop").await.unwrap().is_none());
+
+        db.record_clock_sample("laptop", "s1", 5_000.0, 80.0)
+            .await
+            .unwrap();
+        // a slower exchange is noisier, the estimate stays
+        let offset = db
+            .record_clock_sample("laptop", "s1", 5_300.0, 400.0)
+            .await
+            .unwrap();
+        assert_eq!(offset.offset_ms, 5_000.0);
+        assert_eq!(offset.samples, 2);
+        let offset = db
+            .record_clock_sample("laptop", "s1", 4_990.0, 20.0)
+            .await
+            .unwrap();
+        assert_eq!(offset.offset_ms, 4_990.0);
+        assert_eq!(offset.delay_ms, 20.0);
+
+        // the agent restarted, its clock may have been set meanwhile
+        let offset = db
+            .record_clock_sample("laptop", "s2", -120.0, 300.0)
+            .await
+            .unwrap();
+        assert_eq!(offset.offset_ms, -120.0);
+        assert_eq!(offset.session_id, "s2");
+        assert_eq!(offset.samples, 1);
+
+        let stored = db.get_clock_offset("laptop").await.unwrap().unwrap();
+        assert_eq!(stored.offset_ms, -120.0);
+        assert!(db.get_clock_offset("phone").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_bulk_mutations() {
+        let db = setup_test_db().await;
+        let _ = db
+            .insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+        let mut frame_ids = Vec::new();
+        for app_name in ["Chrome", "Chrome", "chrome-beta", "Slack"] {
+            frame_ids.push(
+                db.insert_frame("test_device", None, None, Some(app_name), Some(""), false)
+                    .await
+                    .unwrap(),
+            );
+        }
+        let chrome = BulkFilter {
+            app_name: Some("Chrome".to_string()),
+            ..Default::default()
+        };
+
+        let result = db
+            .bulk_update_tags(
+                &TagContentType::Vision,
+                &chrome,
+                &["browser".to_string(), "work".to_string()],
+                &[],
+            )
+            .await
+            .unwrap();
+        assert_eq!((result.matched, result.changed), (2, 4));
+        assert_eq!(
+            db.get_tags(frame_ids[1], TagContentType::Vision)
+                .await
+                .unwrap(),
+            vec!["browser", "work"]
+        );
+        let result = db
+            .bulk_update_tags(&TagContentType::Vision, &chrome, &[], &["work".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(result.changed, 2);
+
+        let result = db
+            .bulk_rename_frames(
+                &BulkFilter {
+                    app_name: Some("chrome-beta".to_string()),
+                    ..Default::default()
+                },
+                Some("Chrome"),
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!((result.matched, result.changed), (1, 1));
+
+        let result = db
+            .bulk_trash(&TagContentType::Vision, &chrome)
+            .await
+            .unwrap();
+        assert_eq!((result.matched, result.changed), (3, 3));
+        // trashed frames don't match anymore
+        let result = db
+            .bulk_trash(
+                &TagContentType::Vision,
+                &BulkFilter {
+                    ids: frame_ids.clone(),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!((result.matched, result.changed), (1, 1));
+    }
+
+    #[test]
+    fn test_representative_embeddings() {
+        // two variants of a voice, e.g. laptop mic and headset
+        let variant = |first_half: bool, noise: f32| -> Vec<f32> {
+            (0..8)
+                .map(|i| {
+                    if (i < 4) == first_half {
+                        1.0 + noise
+                    } else {
+                        noise
+                    }
+                })
+                .collect()
+        };
+        let embeddings = vec![
+            variant(true, 0.0),
+            variant(true, 0.05),
+            variant(false, 0.0),
+            variant(true, 0.1),
+            variant(false, 0.05),
+        ];
+
+        let kept = representative_embeddings(&embeddings, 2);
+        assert_eq!(kept.len(), 2);
+        assert!(kept.iter().any(|i| [0, 1, 3].contains(i)));
+        assert!(kept.iter().any(|i| [2, 4].contains(i)));
+        assert_eq!(
+            representative_embeddings(&embeddings, 10),
+            vec![0, 1, 2, 3, 4]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compact_speakers() {
+        let db = setup_test_db().await;
+        let mut embedding = [0.0; 512];
+        let mut speakers = Vec::new();
+        for n in 0..4 {
+            // a headset variant and a laptop variant
+            embedding[(n % 2) * 256] = 1.0 + n as f32 * 0.01;
+            embedding[((n + 1) % 2) * 256] = 0.0;
+            speakers.push(db.insert_speaker(&embedding).await.unwrap());
+        }
+        for speaker in &speakers[1..] {
+            db.merge_speakers(speakers[0].id, speaker.id).await.unwrap();
+        }
+        let deleted = db.insert_speaker(&[0.3; 512]).await.unwrap();
+        sqlx::query("DELETE FROM speakers WHERE id = ?1")
+            .bind(deleted.id)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+
+        let compaction = db.compact_speakers(2).await.unwrap();
+        assert_eq!(compaction.orphaned_embeddings, 1);
+        assert_eq!(compaction.pruned_embeddings, 2);
+        assert_eq!(compaction.compacted_speakers, 1);
+        let remaining: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM speaker_embeddings WHERE speaker_id = ?1")
+                .bind(speakers[0].id)
+                .fetch_one(&db.pool)
+                .await
+                .unwrap();
+        assert_eq!(remaining, 2);
+
+        // nothing left to do
+        let compaction = db.compact_speakers(2).await.unwrap();
+        assert_eq!(
+            compaction.pruned_embeddings + compaction.orphaned_embeddings,
+            0
+        );
+        db.vacuum().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_quantized_embeddings() {
+        assert_eq!(
+            EmbeddingQuantization::Int8.quantize(&[0.5, -1.0]),
+            vec![64, 129]
+        );
+        assert_eq!(EmbeddingQuantization::Binary.quantize(&[1.0; 16]).len(), 2);
+
+        let db = setup_test_db().await;
+        let _ = db
+            .insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+        // sign patterns far apart from each other
+        let vectors: Vec<Vec<f32>> = (0..3)
+            .map(|n| {
+                (0..16)
+                    .map(|i| {
+                        if (i >> n) & 1 == 1 {
+                            1.0 + i as f32 * 0.1
+                        } else {
+                            -1.0
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let mut frame_ids = Vec::new();
+        for (n, vector) in vectors.iter().enumerate() {
+            let frame_id = db
+                .insert_frame("test_device", None, None, Some("test"), Some(""), false)
+                .await
+                .unwrap();
+            db.insert_ocr_text(
+                frame_id,
+                &format!("text {}", n),
+                "",
+                Arc::new(OcrEngine::Tesseract),
+            )
+            .await
+            .unwrap();
+            if n == 1 {
+                db.set_embedding_index_settings(
+                    OCR_TEXT_EMBEDDINGS,
+                    EmbeddingQuantization::Int8,
+                    1,
+                )
+                .await
+                .unwrap();
+            }
+            db.insert_embeddings(frame_id, serde_json::to_string(vector).unwrap())
+                .await
+                .unwrap();
+            frame_ids.push(frame_id);
+        }
+        let closest = |results: Vec<screenpipe_db::OCRResult>| {
+            results.iter().map(|r| r.frame_id).collect::<Vec<_>>()
+        };
+
+        // the first is float, the second int8 without a cached float
+        let status = db.get_ocr_text_embeddings_status().await.unwrap();
+        assert_eq!(
+            (status.rows, status.pending_conversion, status.cached_floats),
+            (3, 1, 1)
+        );
+        for n in 0..3 {
+            let results = db
+                .search_similar_embeddings(vectors[n].clone(), 1, 0.3, None)
+                .await
+                .unwrap();
+            assert_eq!(closest(results), vec![frame_ids[n]]);
+        }
+
+        assert_eq!(db.convert_ocr_text_embeddings(500).await.unwrap(), 1);
+        assert_eq!(db.convert_ocr_text_embeddings(500).await.unwrap(), 0);
+        let status = db.get_ocr_text_embeddings_status().await.unwrap();
+        assert_eq!(status.pending_conversion, 0);
+
+        db.set_embedding_index_settings(OCR_TEXT_EMBEDDINGS, EmbeddingQuantization::Binary, 0)
+            .await
+            .unwrap();
+        assert_eq!(db.convert_ocr_text_embeddings(500).await.unwrap(), 3);
+        let status = db.get_ocr_text_embeddings_status().await.unwrap();
+        assert_eq!((status.stored_bytes, status.cached_floats), (6, 0));
+        for n in 0..3 {
+            let results = db
+                .search_similar_embeddings(vectors[n].clone(), 3, 0.3, None)
+                .await
+                .unwrap();
+            assert_eq!(closest(results), vec![frame_ids[n]]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_decayed_ranking() {
+        let week = chrono::Duration::days(7);
+        assert_eq!(recency_weight(week * 2, week), 0.25);
+
+        let db = setup_test_db().await;
+        let _ = db
+            .insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+        let mut frame_ids = Vec::new();
+        for (days_ago, text) in [
+            (30, "invoice invoice invoice"),
+            (
+                1,
+                "lunch with the team, then the invoice of the new office chairs",
+            ),
+        ] {
+            let frame_id = db
+                .insert_frame(
+                    "test_device",
+                    Some(Utc::now() - chrono::Duration::days(days_ago)),
+                    None,
+                    Some("test"),
+                    Some(""),
+                    false,
+                )
+                .await
+                .unwrap();
+            db.insert_ocr_text(frame_id, text, "", Arc::new(OcrEngine::Tesseract))
+                .await
+                .unwrap();
+            frame_ids.push(frame_id);
+        }
+        let search = || {
+            db.search(
+                "invoice",
+                ContentType::OCR,
+                100,
+                0,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                false,
+                &TagFilter::default(),
+            )
+        };
+        let ranked = |results: Vec<SearchResult>| {
+            results
+                .iter()
+                .map(|result| match result {
+                    SearchResult::OCR(ocr) => ocr.frame_id,
+                    _ => panic!("expected ocr results"),
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            ranked(search().await.unwrap()),
+            vec![frame_ids[1], frame_ids[0]]
+        );
+
+        // a long half-life favors the better match, a short one the recent one
+        for (half_life, expected) in [
+            (
+                chrono::Duration::days(365),
+                vec![frame_ids[0], frame_ids[1]],
+            ),
+            (chrono::Duration::days(1), vec![frame_ids[1], frame_ids[0]]),
+        ] {
+            let results = db
+                .rank_by_decayed_relevance(
+                    "invoice",
+                    search().await.unwrap(),
+                    half_life,
+                    &ClickBoosts::new(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(ranked(results), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_journal_day() {
+        let db = setup_test_db().await;
+        let now = Utc::now();
+        let _ = db
+            .insert_video_chunk("test_video.mp4", "monitor_1")
+            .await
+            .unwrap();
+        // two minutes of code, a long pause, then slack
+        let mut frame_ids = Vec::new();
+        for (minutes_ago, app, window) in [
+            (30, "Code", "main.rs"),
+            (29, "Code", "main.rs"),
+            (28, "Code", "lib.rs"),
+            (10, "Slack", "general"),
+        ] {
+            frame_ids.push(
+                db.insert_frame(
+                    "monitor_1",
+                    Some(now - chrono::Duration::minutes(minutes_ago)),
+                    None,
+                    Some(app),
+                    Some(window),
+                    true,
+                )
+                .await
+                .unwrap(),
+            );
+        }
+        db.insert_ocr_text(frame_ids[0], "fn main", "", Arc::new(OcrEngine::Tesseract))
+            .await
+            .unwrap();
+        db.insert_ocr_text(
+            frame_ids[1],
+            "fn main() { println!(\"hello\") }",
+            "",
+            Arc::new(OcrEngine::Tesseract),
+        )
+        .await
+        .unwrap();
+        db.add_tags(
+            frame_ids[1],
+            TagContentType::Vision,
+            vec!["rust".to_string()],
+        )
+        .await
+        .unwrap();
+
+        let meeting_id = db
+            .start_meeting("zoom.us", now - chrono::Duration::minutes(5))
+            .await
+            .unwrap();
+        db.upsert_meeting_participants(
+            meeting_id,
+            &[("Alice".to_string(), false)],
+            frame_ids[3],
+            now,
+        )
+        .await
+        .unwrap();
+        let speaker = db.insert_speaker(&[0.1; 512]).await.unwrap();
+        db.update_speaker_name(speaker.id, "Alice").await.unwrap();
+        let audio_chunk_id = db.insert_audio_chunk("meeting.mp4").await.unwrap();
+        db.insert_audio_transcription(
+            audio_chunk_id,
+            "let's ship it",
+            0,
+            "",
+            &AudioDevice {
+                name: "speaker".to_string(),
+                device_type: DeviceType::Output,
+            },
+            Some(speaker.id),
+            Some(2.0),
+            Some(4.0),
+        )
+        .await
+        .unwrap();
+
+        let day = db
+            .get_journal_day(
+                now - chrono::Duration::hours(1),
+                now + chrono::Duration::hours(1),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(day.sessions.len(), 2);
+        assert_eq!(day.sessions[0].app_name, "Code");
+        assert_eq!(day.sessions[0].frame_count, 3);
+        assert_eq!(day.sessions[0].window_names, vec!["main.rs", "lib.rs"]);
+        let excerpt = day.sessions[0].excerpt.as_ref().unwrap();
+        assert_eq!(excerpt.frame_id, frame_ids[1]);
+        assert_eq!(day.sessions[1].app_name, "Slack");
+        assert!(day.sessions[1].excerpt.is_none());
+
+        assert_eq!(day.meetings.len(), 1);
+        assert_eq!(day.meetings[0].participants[0].name, "Alice");
+        assert_eq!(day.meetings[0].transcript.len(), 1);
+        assert_eq!(
+            day.meetings[0].transcript[0].speaker_name.as_deref(),
+            Some("Alice")
+        );
+        assert_eq!(day.meetings[0].transcript[0].file_path, "meeting.mp4");
+
+        assert_eq!(day.tags.len(), 1);
+        assert_eq!(day.tags[0].name, "rust");
+        assert_eq!(day.tags[0].frames, 1);
+        assert_eq!(day.tags[0].audio_chunks, 0);
+    }
+
+    #[tokio::test]
+    async fn test_click_signals() {
+        assert_eq!(normalize_query("Office, chairs  office"), "chairs office");
+        assert_eq!(click_boost(0.0), 1.0);
+
+        let db = setup_test_db().await;
+        for (q, content_type, content_id, dwell_secs) in [
+            ("office Chairs", ClickContentType::Ocr, 7, 60.0),
+            ("chairs office", ClickContentType::Ocr, 7, 0.0),
+            ("lunch", ClickContentType::Audio, 3, 10.0),
+        ] {
+            db.record_search_click(&NewSearchClick {
+                q: q.to_string(),
+                content_type,
+                content_id,
+                dwell_secs,
+            })
+            .await
+            .unwrap();
+        }
+
+        // a full click and a bounce
+        let boosts = db.click_boosts("Office chairs").await.unwrap();
+        assert_eq!(boosts.len(), 1);
+        assert_eq!(
+            boosts[&(ClickContentType::Ocr, 7)],
+            click_boost(click_weight(2, 30.0))
+        );
+        // similar queries count for less
+        let boosts = db.click_boosts("office chairs invoice").await.unwrap();
+        assert!(boosts[&(ClickContentType::Ocr, 7)] > 1.0);
+        assert!(boosts[&(ClickContentType::Ocr, 7)] < click_boost(click_weight(2, 30.0)));
+        let boosts = db.click_boosts("lunch plans").await.unwrap();
+        assert!(boosts.contains_key(&(ClickContentType::Audio, 3)));
+        assert!(!boosts.contains_key(&(ClickContentType::Ocr, 7)));
+        assert!(db.click_boosts("weather").await.unwrap().is_empty());
+
+        let signals = db.list_click_signals(10, 0).await.unwrap();
+        assert_eq!(signals.len(), 2);
+        assert_eq!(signals[0].query, "lunch");
+        assert_eq!(signals[1].query, "chairs office");
+        assert_eq!(signals[1].clicks, 2);
+        assert_eq!(signals[1].dwell_secs, 60.0);
+
+        assert_eq!(db.reset_click_signals(Some("Lunch")).await.unwrap(), 1);
+        assert_eq!(db.reset_click_signals(None).await.unwrap(), 2);
+        assert!(db.list_click_signals(10, 0).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_usage_records() {
+        let db = setup_test_db().await;
+        let now = Utc::now();
+        let _ = db
+            .insert_video_chunk("test_video.mp4", "monitor_1")
+            .await
+            .unwrap();
+        // the last frame comes after a long pause
+        let mut frame_ids = Vec::new();
+        for secs_ago in [600, 598, 10] {
+            frame_ids.push(
+                db.insert_frame(
+                    "monitor_1",
+                    Some(now - chrono::Duration::seconds(secs_ago)),
+                    None,
+                    Some("Code"),
+                    Some("main.rs"),
+                    true,
+                )
+                .await
+                .unwrap(),
+            );
+        }
+        db.insert_ocr_text(frame_ids[0], "fn main", "", Arc::new(OcrEngine::Tesseract))
+            .await
+            .unwrap();
+        let audio_chunk_id = db.insert_audio_chunk("audio.mp4").await.unwrap();
+        db.insert_audio_transcription(
+            audio_chunk_id,
+            "hello there",
+            0,
+            "",
+            &AudioDevice {
+                name: "mic".to_string(),
+                device_type: DeviceType::Input,
+            },
+            None,
+            Some(1.0),
+            Some(3.5),
+        )
+        .await
+        .unwrap();
+
+        let start = now - chrono::Duration::hours(1);
+        let records = db
+            .get_usage_records(start, now + chrono::Duration::hours(1), true, true)
+            .await
+            .unwrap();
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[0].kind, "frame");
+        assert_eq!(records[0].app_name.as_deref(), Some("Code"));
+        assert_eq!(records[0].text_length, Some(7));
+        assert!((records[0].duration_secs.unwrap() - 2.0).abs() < 0.01);
+        assert_eq!(records[1].duration_secs, None);
+        assert_eq!(records[1].text_length, None);
+        assert_eq!(records[3].kind, "audio");
+        assert_eq!(records[3].device_name, "mic");
+        assert_eq!(records[3].duration_secs, Some(2.5));
+        assert_eq!(records[3].text_length, Some(11));
+
+        // the frame before the end of the period lasts past it
+        let records = db
+            .get_usage_records(start, now - chrono::Duration::seconds(599), true, false)
+            .await
+            .unwrap();
+        assert_eq!(records.len(), 1);
+        assert!(records[0].duration_secs.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_meeting_speech_span() {
+        let db = setup_test_db().await;
+        let meeting_id = db
+            .start_meeting("zoom.us", Utc::now() - chrono::Duration::minutes(10))
+            .await
+            .unwrap();
+        let meeting = db.get_meeting(meeting_id).await.unwrap();
+        assert_eq!(db.get_meeting_speech_span(&meeting).await.unwrap(), None);
+
+        let audio_chunk_id = db.insert_audio_chunk("call.mp4").await.unwrap();
+        let device = AudioDevice {
+            name: "speaker".to_string(),
+            device_type: DeviceType::Output,
+        };
+        for (offset, text) in ["hi all", "see you"].iter().enumerate() {
+            db.insert_audio_transcription(
+                audio_chunk_id,
+                text,
+                offset as i64,
+                "",
+                &device,
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+        // hallucinated speech doesn't count
+        let ghost = db.insert_speaker(&[0.2; 512]).await.unwrap();
+        db.mark_speaker_as_hallucination(ghost.id).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        db.insert_audio_transcription(
+            audio_chunk_id,
+            "thank you",
+            2,
+            "",
+            &device,
+            Some(ghost.id),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let (first, last) = db.get_meeting_speech_span(&meeting).await.unwrap().unwrap();
+        assert!(first >= meeting.start_time);
+        assert!(first <= last);
+        let ghost_said: chrono::DateTime<Utc> =
+            sqlx::query_scalar("SELECT timestamp FROM audio_transcriptions WHERE speaker_id = ?1")
+                .bind(ghost.id)
+                .fetch_one(&db.pool)
+                .await
+                .unwrap();
+        assert!(last < ghost_said);
+
+        // nothing said after the meeting ended belongs to it
+        db.end_meetings(first - chrono::Duration::seconds(1))
+            .await
+            .unwrap();
+        let meeting = db.get_meeting(meeting_id).await.unwrap();
+        assert_eq!(db.get_meeting_speech_span(&meeting).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_digest_queries() {
+        let db = setup_test_db().await;
+        let start = Utc::now() - chrono::Duration::hours(1);
+        let end = Utc::now() + chrono::Duration::hours(1);
+
+        for (q, content_id) in [("Budget", 1), ("budget", 2), ("lunch", 3)] {
+            db.record_search_click(&NewSearchClick {
+                q: q.to_string(),
+                content_type: ClickContentType::Ocr,
+                content_id,
+                dwell_secs: 5.0,
+            })
+            .await
+            .unwrap();
+        }
+        let searches = db.get_searched_queries(start, end, 5).await.unwrap();
+        assert_eq!(searches.len(), 2);
+        assert_eq!(searches[0].query, "budget");
+        assert_eq!(searches[0].clicks, 2);
+        assert!(db
+            .get_searched_queries(end, end + chrono::Duration::hours(1), 5)
+            .await
+            .unwrap()
+            .is_empty());
+
+        db.insert_video_chunk("video.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let frame_id = db
+            .insert_frame("monitor_1", None, None, None, None, true)
+            .await
+            .unwrap();
+        db.insert_ocr_text(
+            frame_id,
+            "Invoice #42 due friday",
+            "",
+            Arc::new(OcrEngine::Tesseract),
+        )
+        .await
+        .unwrap();
+        let audio_chunk_id = db.insert_audio_chunk("audio.mp4").await.unwrap();
+        db.insert_audio_transcription(
+            audio_chunk_id,
+            "did you send the invoice",
+            0,
+            "",
+            &AudioDevice {
+                name: "mic".to_string(),
+                device_type: DeviceType::Input,
+            },
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let hits = db.count_keyword_hits("INVOICE", start, end).await.unwrap();
+        assert_eq!(hits.keyword, "INVOICE");
+        assert_eq!(hits.frames, 1);
+        assert_eq!(hits.transcriptions, 1);
+        let hits = db.count_keyword_hits("receipt", start, end).await.unwrap();
+        assert_eq!((hits.frames, hits.transcriptions), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_unpushed_items() {
+        let db = setup_test_db().await;
+        let destination = db
+            .insert_push_destination(&NewPushDestination {
+                name: "highlights".to_string(),
+                kind: "readwise".to_string(),
+                api_key: "key".to_string(),
+                notion_database_id: None,
+                tags: vec![
+                    " bookmark".to_string(),
+                    "".to_string(),
+                    "receipt".to_string(),
+                ],
+            })
+            .await
+            .unwrap();
+        assert_eq!(destination.tags, "bookmark,receipt");
+
+        db.insert_video_chunk("video.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let frame_id = db
+            .insert_frame("monitor_1", None, None, Some("Arc"), Some("Docs"), true)
+            .await
+            .unwrap();
+        db.insert_ocr_text(
+            frame_id,
+            "worth keeping",
+            "",
+            Arc::new(OcrEngine::Tesseract),
+        )
+        .await
+        .unwrap();
+        let other_frame_id = db
+            .insert_frame("monitor_1", None, None, Some("Arc"), Some("News"), true)
+            .await
+            .unwrap();
+        db.add_tags(
+            frame_id,
+            TagContentType::Vision,
+            vec!["bookmark".to_string()],
+        )
+        .await
+        .unwrap();
+        db.add_tags(
+            other_frame_id,
+            TagContentType::Vision,
+            vec!["later".to_string()],
+        )
+        .await
+        .unwrap();
+
+        let audio_chunk_id = db.insert_audio_chunk("audio.mp4").await.unwrap();
+        let device = AudioDevice {
+            name: "mic".to_string(),
+            device_type: DeviceType::Input,
+        };
+        for (offset, text) in ["the receipt", "is in the drawer"].iter().enumerate() {
+            db.insert_audio_transcription(
+                audio_chunk_id,
+                text,
+                offset as i64,
+                "",
+                &device,
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+        db.add_tags(
+            audio_chunk_id,
+            TagContentType::Audio,
+            vec!["receipt".to_string()],
+        )
+        .await
+        .unwrap();
+
+        let items = db.get_unpushed_items(&destination, 10).await.unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].content_type, "vision");
+        assert_eq!(items[0].content_id, frame_id);
+        assert_eq!(items[0].text, "worth keeping");
+        assert_eq!(items[0].window_name, "Docs");
+        assert_eq!(items[1].content_type, "audio");
+        assert_eq!(items[1].device_name, "mic");
+        assert_eq!(items[1].text, "the receipt is in the drawer");
+        assert_eq!(items[1].tags, "receipt");
+
+        db.mark_items_pushed(destination.id, &items[..1])
+            .await
+            .unwrap();
+        let items = db.get_unpushed_items(&destination, 10).await.unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].content_type, "audio");
+
+        db.set_push_error(destination.id, "401 unauthorized")
+            .await
+            .unwrap();
+        let listed = db.list_push_destinations().await.unwrap();
+        assert_eq!(listed[0].last_error.as_deref(), Some("401 unauthorized"));
+        assert!(listed[0].last_pushed_at.is_some());
+
+        assert!(db.delete_push_destination(destination.id).await.unwrap());
+        assert!(!db.delete_push_destination(destination.id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_insert_frame_offsets() {
+        let db = setup_test_db().await;
+        // no video chunk to put it in yet
+        assert_eq!(
+            db.insert_frame("monitor_1", None, None, None, None, true)
+                .await
+                .unwrap(),
+            0
+        );
+
+        db.insert_video_chunk("first.mp4", "monitor_1")
+            .await
+            .unwrap();
+        db.insert_video_chunk("other.mp4", "monitor_2")
+            .await
+            .unwrap();
+        let first = db
+            .insert_frame("monitor_1", None, None, Some("Code"), None, true)
+            .await
+            .unwrap();
+        let second = db
+            .insert_frame("monitor_1", None, None, Some("Code"), None, true)
+            .await
+            .unwrap();
+        db.insert_video_chunk("second.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let third = db
+            .insert_frame("monitor_1", None, None, Some("Code"), None, true)
+            .await
+            .unwrap();
+
+        let frames: Vec<(i64, i64, String)> =
+            sqlx::query_as("SELECT id, offset_index, name FROM frames ORDER BY id")
+                .fetch_all(&db.pool)
+                .await
+                .unwrap();
+        assert_eq!(
+            frames,
+            vec![
+                (first, 0, "first.mp4".to_string()),
+                (second, 1, "first.mp4".to_string()),
+                (third, 0, "second.mp4".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_audio_filters() {
+        let db = setup_test_db().await;
+        let device = AudioDevice {
+            name: "mic".to_string(),
+            device_type: DeviceType::Input,
+        };
+        for text in ["short", "a much longer sentence"] {
+            let audio_chunk_id = db.insert_audio_chunk("audio.mp4").await.unwrap();
+            db.insert_audio_transcription(audio_chunk_id, text, 0, "", &device, None, None, None)
+                .await
+                .unwrap();
+        }
+
+        let search = |query: &'static str, min_length, speaker_ids| {
+            let db = &db;
+            async move {
+                db.search_audio(
+                    query,
+                    10,
+                    0,
+                    None,
+                    None,
+                    min_length,
+                    None,
+                    speaker_ids,
+                    &TagFilter::default(),
+                )
+                .await
+                .unwrap()
+            }
+        };
+        assert_eq!(search("", None, None).await.len(), 2);
+        assert_eq!(search("", Some(10), None).await.len(), 1);
+        assert_eq!(search("sentence", None, None).await.len(), 1);
+        // an empty list of speakers filters nothing
+        assert_eq!(search("", None, Some(vec![])).await.len(), 2);
+        assert_eq!(search("", None, Some(vec![42])).await.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_read_pool_is_query_only() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("video.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let frame_id = db
+            .insert_frame("monitor_1", None, None, Some("Code"), None, true)
+            .await
+            .unwrap();
+        db.insert_ocr_text(
+            frame_id,
+            "written on the write pool",
+            "",
+            Arc::new(OcrEngine::Tesseract),
+        )
+        .await
+        .unwrap();
+
+        // searches see what the recorders just wrote
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM ocr_text")
+            .fetch_one(&db.read_pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+        let results = db
+            .search(
+                "written",
+                ContentType::OCR,
+                10,
+                0,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                false,
+                &TagFilter::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+
+        assert!(sqlx::query("DELETE FROM ocr_text")
+            .execute(&db.read_pool)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_estimate_search_results() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("video.mp4", "monitor_1")
+            .await
+            .unwrap();
+        for i in 0..6 {
+            let frame_id = db
+                .insert_frame("monitor_1", None, None, Some("Code"), None, true)
+                .await
+                .unwrap();
+            let text = if i % 2 == 0 {
+                "quarterly budget"
+            } else {
+                "lunch"
+            };
+            db.insert_ocr_text(frame_id, text, "", Arc::new(OcrEngine::Tesseract))
+                .await
+                .unwrap();
+        }
+
+        let estimate = |query: &'static str| {
+            let db = &db;
+            async move {
+                db.estimate_search_results(
+                    query,
+                    ContentType::OCR,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    false,
+                    &TagFilter::default(),
+                )
+                .await
+                .unwrap()
+            }
+        };
+        // small enough to count it all
+        assert_eq!(
+            estimate("budget").await,
+            ResultCount {
+                count: 3,
+                exact: true
+            }
+        );
+
+        // today alone fills the sample, and an older day as big doubles it
+        let today: String = sqlx::query_scalar("SELECT day FROM content_day_counts")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(today, Utc::now().date_naive().to_string());
+        sqlx::query("UPDATE content_day_counts SET row_count = 10000")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO content_day_counts (day, content_type, row_count) VALUES (date('now', '-3 days'), 'ocr', 10000)",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            estimate("budget").await,
+            ResultCount {
+                count: 6,
+                exact: false
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_external_content_fts() {
+        let db = setup_test_db().await;
+        // the indexes keep no copy of the text
+        let copies: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM sqlite_master WHERE name IN ('ocr_text_fts_content', 'audio_transcriptions_fts_content', 'ui_monitoring_fts_content')",
+        )
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+        assert_eq!(copies, 0);
+
+        db.insert_video_chunk("video.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let frame_id = db
+            .insert_frame("monitor_1", None, None, Some("Code"), None, true)
+            .await
+            .unwrap();
+        db.insert_ocr_text(frame_id, "first draft", "", Arc::new(OcrEngine::Tesseract))
+            .await
+            .unwrap();
+        db.replace_ocr_text(
+            frame_id,
+            "final version",
+            "",
+            Arc::new(OcrEngine::Tesseract),
+        )
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO ui_monitoring (text_output, app, window) VALUES ('settings panel', 'Finder', 'Prefs')")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+
+        let matches = |table: &'static str, query: &'static str| {
+            let db = &db;
+            async move {
+                sqlx::query_scalar::<_, i64>(&format!(
+                    "SELECT COUNT(*) FROM {table} WHERE {table} MATCH ?1",
+                    table = table
+                ))
+                .bind(query)
+                .fetch_one(&db.pool)
+                .await
+                .unwrap()
+            }
+        };
+        assert_eq!(matches("ocr_text_fts", "draft").await, 0);
+        assert_eq!(matches("ocr_text_fts", "final").await, 1);
+        assert_eq!(matches("ui_monitoring_fts", "settings").await, 1);
+        let ui_id: i64 = sqlx::query_scalar(
+            "SELECT rowid FROM ui_monitoring_fts WHERE ui_monitoring_fts MATCH 'panel'",
+        )
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+        let id: i64 = sqlx::query_scalar("SELECT id FROM ui_monitoring")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(ui_id, id);
+
+        sqlx::query("DELETE FROM ocr_text")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(matches("ocr_text_fts", "final").await, 0);
+
+        db.optimize_search_index(true).await.unwrap();
+        for table in [
+            "ocr_text_fts",
+            "audio_transcriptions_fts",
+            "ui_monitoring_fts",
+        ] {
+            sqlx::query(&format!(
+                "INSERT INTO {table}({table}) VALUES('integrity-check')",
+                table = table
+            ))
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        }
+        assert_eq!(matches("ui_monitoring_fts", "settings").await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_insert_frames_batch() {
+        let db = setup_test_db().await;
+        let frames = vec![
+            NewFrame {
+                app_name: Some("Code".to_string()),
+                window_name: Some("main.rs".to_string()),
+                focused: true,
+                text: "fn main()".to_string(),
+                ..Default::default()
+            },
+            NewFrame {
+                app_name: Some("Slack".to_string()),
+                window_name: Some("general".to_string()),
+                text: "standup at ten".to_string(),
+                ..Default::default()
+            },
+        ];
+        // no video chunk to put them in yet
+        assert!(db
+            .insert_frames_batch("monitor_1", &frames, Arc::new(OcrEngine::Tesseract))
+            .await
+            .unwrap()
+            .is_empty());
+
+        db.insert_video_chunk("video.mp4", "monitor_1")
+            .await
+            .unwrap();
+        db.insert_frame("monitor_1", None, None, Some("Code"), None, true)
+            .await
+            .unwrap();
+        let ids = db
+            .insert_frames_batch("monitor_1", &frames, Arc::new(OcrEngine::Tesseract))
+            .await
+            .unwrap();
+        assert_eq!(ids.len(), 2);
+
+        let rows: Vec<(i64, i64, String, String)> = sqlx::query_as(
+            "SELECT f.id, f.offset_index, f.app_name, o.text FROM frames f JOIN ocr_text o ON o.frame_id = f.id ORDER BY f.id",
+        )
+        .fetch_all(&db.pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                (ids[0], 1, "Code".to_string(), "fn main()".to_string()),
+                (ids[1], 2, "Slack".to_string(), "standup at ten".to_string()),
+            ]
+        );
+
+        let results = db
+            .search(
+                "standup",
+                ContentType::OCR,
+                10,
+                0,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                false,
+                &TagFilter::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_run_maintenance() {
+        let path = std::env::temp_dir().join(format!(
+            "screenpipe-maintenance-{}.sqlite",
+            std::process::id()
+        ));
+        let db = DatabaseManager::new(&path.to_string_lossy()).await.unwrap();
+        let auto_vacuum: i64 = sqlx::query_scalar("PRAGMA auto_vacuum")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(auto_vacuum, 2);
+
+        db.insert_video_chunk("video.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let frame_id = db
+            .insert_frame("monitor_1", None, None, Some("Code"), None, true)
+            .await
+            .unwrap();
+        let text = "lorem ipsum ".repeat(2000);
+        for _ in 0..20 {
+            db.insert_ocr_text(frame_id, &text, "", Arc::new(OcrEngine::Tesseract))
+                .await
+                .unwrap();
+        }
+        sqlx::query("DELETE FROM ocr_text")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        let pool = &db.pool;
+        let free_pages = move || async move {
+            sqlx::query_scalar::<_, i64>("PRAGMA freelist_count")
+                .fetch_one(pool)
+                .await
+                .unwrap()
+        };
+        let freed = free_pages().await;
+        assert!(freed > 0);
+
+        let steps = db.run_maintenance().await.unwrap();
+        let names: Vec<&str> = steps.iter().map(|step| step.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "merge ocr_text_fts",
+                "merge audio_transcriptions_fts",
+                "merge ui_monitoring_fts",
+                "incremental vacuum",
+                "analyze",
+                "optimize",
+                "wal checkpoint",
+            ]
+        );
+        assert!(steps.iter().all(|step| step.error.is_none()), "{:?}", steps);
+        assert!(!steps[3].skipped);
+        assert!(free_pages().await < freed);
+
+        db.pool.close().await;
+        db.read_pool.close().await;
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.to_string_lossy(), suffix));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_query_plans() {
+        let db = setup_test_db().await;
+        assert_eq!(
+            db.check_search_query_plans().await.unwrap(),
+            Vec::<String>::new()
+        );
+
+        db.insert_video_chunk("video.mp4", "monitor_1")
+            .await
+            .unwrap();
+        for app in ["Google Chrome", "Slack"] {
+            let frame_id = db
+                .insert_frame("monitor_1", None, None, Some(app), Some("inbox"), true)
+                .await
+                .unwrap();
+            for text in ["first pass", "second pass"] {
+                db.insert_ocr_text(frame_id, text, "", Arc::new(OcrEngine::Tesseract))
+                    .await
+                    .unwrap();
+            }
+        }
+
+        let search = |query: &'static str, app_name: Option<&'static str>| {
+            let db = &db;
+            async move {
+                db.search(
+                    query,
+                    ContentType::OCR,
+                    10,
+                    0,
+                    None,
+                    None,
+                    app_name,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    false,
+                    &TagFilter::default(),
+                )
+                .await
+                .unwrap()
+            }
+        };
+        let count = |query: &'static str, app_name: Option<&'static str>| {
+            let db = &db;
+            async move {
+                db.count_search_results(
+                    query,
+                    ContentType::OCR,
+                    None,
+                    None,
+                    app_name,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    false,
+                    &TagFilter::default(),
+                )
+                .await
+                .unwrap()
+            }
+        };
+
+        // a frame once, with its latest text
+        let results = search("", None).await;
+        assert_eq!(results.len(), 2);
+        for result in &results {
+            let SearchResult::OCR(ocr) = result else {
+                panic!("expected ocr");
+            };
+            assert_eq!(ocr.ocr_text, "second pass");
+        }
+        assert_eq!(search("", Some("chrome")).await.len(), 1);
+        assert_eq!(search("first", Some("chrome")).await.len(), 1);
+        assert_eq!(count("", None).await, 2);
+        assert_eq!(count("", Some("chrome")).await, 1);
+        assert_eq!(count("first", Some("chrome")).await, 1);
+        assert_eq!(count("first", Some("teams")).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_query_timeout() {
+        let db = setup_test_db()
+            .await
+            .with_query_timeout(Some(std::time::Duration::from_millis(100)));
+        assert_eq!(db.count_audio_transcriptions(1).await.unwrap(), 0);
+
+        // a write transaction on the shared in-memory database holds the
+        // table, so the read waits until it is interrupted
+        let mut tx = db.pool.begin().await.unwrap();
+        sqlx::query("DELETE FROM audio_transcriptions")
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        let err = db.count_audio_transcriptions(1).await.unwrap_err();
+        assert_eq!(
+            screenpipe_db::QueryTimedOut::find(&err),
+            Some(screenpipe_db::QueryTimedOut {
+                after: std::time::Duration::from_millis(100)
+            })
+        );
+        tx.rollback().await.unwrap();
+
+        assert_eq!(db.count_audio_transcriptions(1).await.unwrap(), 0);
+        let other = sqlx::Error::RowNotFound;
+        assert!(screenpipe_db::QueryTimedOut::find(&other).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_run_bench() {
+        let config = screenpipe_db::BenchConfig {
+            frames: 60,
+            transcriptions: 15,
+            embeddings: 10,
+            embedding_dimensions: 16,
+            quantization: EmbeddingQuantization::Int8,
+            batch_size: 25,
+            searches: 5,
+            seed: 7,
+        };
+        let report = screenpipe_db::run_bench(&std::env::temp_dir(), &config)
+            .await
+            .unwrap();
+
+        let rows: Vec<u64> = report.inserts.iter().map(|insert| insert.rows).collect();
+        assert_eq!(rows, vec![60, 15, 10]);
+        assert!(report.database_bytes > 0);
+        assert_eq!(report.queries.len(), 7);
+        for query in &report.queries {
+            assert_eq!(query.runs, 5);
+            assert!(query.p50_ms <= query.p95_ms && query.p95_ms <= query.max_ms);
+        }
+    }
+
+    #[test]
+    fn test_percentile_ms() {
+        let durations: Vec<std::time::Duration> =
+            (1..=20).map(std::time::Duration::from_millis).collect();
+        assert_eq!(screenpipe_db::percentile_ms(&durations, 50.0), 10.0);
+        assert_eq!(screenpipe_db::percentile_ms(&durations, 95.0), 19.0);
+        assert_eq!(screenpipe_db::percentile_ms(&durations, 100.0), 20.0);
+        assert_eq!(screenpipe_db::percentile_ms(&[], 95.0), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_capture_blocklist() {
+        let db = setup_test_db().await;
+        db.set_config_capture_block_rules(&[
+            NewCaptureBlockRule {
+                kind: "app".to_string(),
+                pattern: "1password".to_string(),
+            },
+            NewCaptureBlockRule {
+                kind: "window".to_string(),
+                pattern: " ".to_string(),
+            },
+        ])
+        .await
+        .unwrap();
+        let bank = db
+            .insert_capture_block_rule(&NewCaptureBlockRule {
+                kind: "url".to_string(),
+                pattern: "mybank.com".to_string(),
+            })
+            .await
+            .unwrap();
+        let rules = db.list_capture_block_rules().await.unwrap();
+        // the empty pattern would block everything
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].source, "config");
+
+        db.insert_video_chunk("video.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let blocked = db
+            .insert_frame(
+                "monitor_1",
+                None,
+                Some("https://www.MyBank.com/accounts"),
+                Some("Arc"),
+                Some("Accounts"),
+                true,
+            )
+            .await
+            .unwrap();
+        assert_eq!(blocked, 0);
+        db.insert_ocr_text(blocked, "balance", "", Arc::new(OcrEngine::Tesseract))
+            .await
+            .unwrap();
+
+        let frames = vec![
+            NewFrame {
+                app_name: Some("1Password 8".to_string()),
+                text: "hunter2".to_string(),
+                ..Default::default()
+            },
+            NewFrame {
+                app_name: Some("Code".to_string()),
+                text: "fn main()".to_string(),
+                ..Default::default()
+            },
+        ];
+        let ids = db
+            .insert_frames_batch("monitor_1", &frames, Arc::new(OcrEngine::Tesseract))
+            .await
+            .unwrap();
+        assert_eq!(ids[0], 0);
+        assert_ne!(ids[1], 0);
+        let texts: Vec<String> = sqlx::query_scalar("SELECT text FROM ocr_text")
+            .fetch_all(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(texts, vec!["fn main()".to_string()]);
+
+        // rows written by other processes too
+        sqlx::query("INSERT INTO ui_monitoring (text_output, app, window) VALUES ('hunter2', '1Password', 'Vault')")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        let ui_rows: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM ui_monitoring")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(ui_rows, 0);
+
+        // the rules of the command line stay
+        assert!(!db.delete_capture_block_rule(rules[0].id).await.unwrap());
+        assert!(db.delete_capture_block_rule(bank.id).await.unwrap());
+        let stored = db
+            .insert_frame(
+                "monitor_1",
+                None,
+                Some("https://www.mybank.com/accounts"),
+                Some("Arc"),
+                Some("Accounts"),
+                true,
+            )
+            .await
+            .unwrap();
+        assert_ne!(stored, 0);
+    }
+
+    #[tokio::test]
+    async fn test_capture_pauses() {
+        let db = setup_test_db().await;
+        let now = Utc::now();
+        let insert_ui = || {
+            sqlx::query(
+                "INSERT INTO ui_monitoring (text_output, app, window) VALUES ('inbox', 'Mail', 'Inbox')",
+            )
+            .execute(&db.pool)
+        };
+
+        let timed = db
+            .start_capture_pause("ui", now, Some(now + chrono::Duration::minutes(10)), None)
+            .await
+            .unwrap();
+        let vision = db
+            .start_capture_pause("vision", now, None, None)
+            .await
+            .unwrap();
+        // the ui recorder writes on through a pause, the database drops it
+        assert_eq!(insert_ui().await.unwrap().rows_affected(), 0);
+
+        // ended at the time it was due, even when checked later
+        let later = now + chrono::Duration::minutes(30);
+        assert_eq!(db.end_due_capture_pauses(later).await.unwrap(), 1);
+        assert_eq!(insert_ui().await.unwrap().rows_affected(), 1);
+        let running = db.list_running_capture_pauses().await.unwrap();
+        assert_eq!(running, vec![vision.clone()]);
+
+        // resuming audio leaves the vision pause running
+        assert!(db
+            .end_capture_pauses("audio", later)
+            .await
+            .unwrap()
+            .is_empty());
+        let ended = db.end_capture_pauses("all", later).await.unwrap();
+        assert_eq!(ended.len(), 1);
+        assert_eq!(ended[0].id, vision.id);
+
+        let gaps = db
+            .list_capture_pauses(
+                now - chrono::Duration::hours(1),
+                now + chrono::Duration::hours(1),
+            )
+            .await
+            .unwrap();
+        assert_eq!(gaps.len(), 2);
+        assert_eq!(gaps[0].id, timed.id);
+        assert_eq!(gaps[0].ended_at, timed.resume_at);
+        let gaps = db
+            .list_capture_pauses(
+                later + chrono::Duration::minutes(1),
+                later + chrono::Duration::hours(1),
+            )
+            .await
+            .unwrap();
+        assert!(gaps.is_empty());
+
+        let schedule = db
+            .insert_capture_pause_schedule("all", "18:00-09:00")
+            .await
+            .unwrap();
+        db.start_capture_pause("all", later, None, Some(schedule.id))
+            .await
+            .unwrap();
+        assert!(db.delete_capture_pause_schedule(schedule.id).await.unwrap());
+        assert!(!db.delete_capture_pause_schedule(schedule.id).await.unwrap());
+        assert!(db.list_running_capture_pauses().await.unwrap().is_empty());
+        assert!(db.list_capture_pause_schedules().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_ui_secret_scanning() {
+        let db = setup_test_db().await;
+        let insert_ui = |text: &'static str, window: &'static str| {
+            sqlx::query(
+                "INSERT INTO ui_monitoring (text_output, app, window) VALUES (?1, 'Terminal', ?2)",
+            )
+            .bind(text)
+            .bind(window)
+            .execute(&db.pool)
+        };
+        let ui_texts = || {
+            sqlx::query_scalar::<_, String>("SELECT text_output FROM ui_monitoring ORDER BY id")
+                .fetch_all(&db.pool)
+        };
+        let fts_hits = |word: &'static str| {
+            sqlx::query_scalar::<_, i64>(
+                "SELECT COUNT(*) FROM ui_monitoring_fts WHERE ui_monitoring_fts MATCH ?1",
+            )
+            .bind(word)
+            .fetch_one(&db.pool)
+        };
+        insert_ui("before", "zsh").await.unwrap();
+        assert_eq!(
+            db.release_held_ui_text(10, str::to_uppercase)
+                .await
+                .unwrap(),
+            (0, 0)
+        );
+
+        // the text written before is held for masking when scanning starts
+        db.set_ui_secret_scanning(true).await.unwrap();
+        db.set_ui_secret_scanning(true).await.unwrap();
+        insert_ui("after", "bash").await.unwrap();
+        sqlx::query("UPDATE ui_monitoring SET text_output = 'changed' WHERE window = 'zsh'")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        // held text reaches neither the table nor its index
+        assert_eq!(ui_texts().await.unwrap(), vec!["before"]);
+        assert_eq!(fts_hits("after").await.unwrap(), 0);
+
+        assert_eq!(
+            db.release_held_ui_text(10, str::to_uppercase)
+                .await
+                .unwrap(),
+            (2, 2)
+        );
+        assert_eq!(ui_texts().await.unwrap(), vec!["CHANGED", "AFTER"]);
+        assert_eq!(fts_hits("changed").await.unwrap(), 1);
+        assert_eq!(
+            db.release_held_ui_text(10, str::to_uppercase)
+                .await
+                .unwrap(),
+            (0, 0)
+        );
+
+        // held text for a row deleted meanwhile is dropped
+        sqlx::query("UPDATE ui_monitoring SET text_output = 'again' WHERE window = 'bash'")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM ui_monitoring WHERE window = 'bash'")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        db.release_held_ui_text(10, str::to_uppercase)
+            .await
+            .unwrap();
+        assert_eq!(ui_texts().await.unwrap(), vec!["CHANGED"]);
+
+        db.set_ui_secret_scanning(false).await.unwrap();
+        insert_ui("off", "fish").await.unwrap();
+        assert_eq!(
+            db.release_held_ui_text(10, str::to_uppercase)
+                .await
+                .unwrap(),
+            (0, 0)
+        );
+        assert_eq!(ui_texts().await.unwrap(), vec!["CHANGED", "off"]);
+    }
+
+    #[tokio::test]
+    async fn test_audit_log() {
+        let db = setup_test_db().await;
+
+        let (_, changed) = db
+            .execute_raw_sql_counted("INSERT INTO tags (name) VALUES ('a'), ('b') RETURNING id")
+            .await
+            .unwrap();
+        assert_eq!(changed, 2);
+        let (rows, changed) = db
+            .execute_raw_sql_counted("SELECT name FROM tags")
+            .await
+            .unwrap();
+        assert_eq!(rows.as_array().unwrap().len(), 2);
+        assert_eq!(changed, 0);
+
+        let start = Utc::now();
+        db.insert_audit_entry("token:admin", "raw_sql", "DELETE FROM tags", Some(2))
+            .await
+            .unwrap();
+        db.insert_audit_entry(
+            "ip:127.0.0.1",
+            "merge_speakers",
+            "speaker:2 into speaker:1",
+            None,
+        )
+        .await
+        .unwrap();
+
+        let entries = db.list_audit_log(None, None, None, 10, 0).await.unwrap();
+        assert_eq!(entries.len(), 2);
+        // newest first
+        assert_eq!(entries[0].action, "merge_speakers");
+        assert_eq!(entries[1].actor, "token:admin");
+        assert_eq!(entries[1].rows_affected, Some(2));
+
+        let raw_sql = db
+            .list_audit_log(Some("raw_sql"), Some(start), None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(raw_sql.len(), 1);
+        assert_eq!(raw_sql[0].target, "DELETE FROM tags");
+        assert!(db
+            .list_audit_log(
+                None,
+                None,
+                Some(start - chrono::Duration::seconds(1)),
+                10,
+                0
+            )
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_delete_search_results() {
+        let db = setup_test_db().await;
+        let engine = Arc::new(OcrEngine::Tesseract);
+        let device = AudioDevice {
+            name: "mic".to_string(),
+            device_type: DeviceType::Input,
+        };
+
+        db.insert_video_chunk("a.mp4", "monitor").await.unwrap();
+        let payroll = db
+            .insert_frame(
+                "monitor",
+                None,
+                None,
+                Some("Excel"),
+                Some("payroll.xlsx"),
+                true,
+            )
+            .await
+            .unwrap();
+        db.insert_ocr_text(payroll, "payroll export march", "", engine.clone())
+            .await
+            .unwrap();
+        db.add_tags(payroll, TagContentType::Vision, vec!["finance".to_string()])
+            .await
+            .unwrap();
+        db.insert_video_chunk("b.mp4", "monitor").await.unwrap();
+        for text in ["payroll totals", "weather today"] {
+            let frame_id = db
+                .insert_frame("monitor", None, None, Some("Chrome"), None, true)
+                .await
+                .unwrap();
+            db.insert_ocr_text(frame_id, text, "", engine.clone())
+                .await
+                .unwrap();
+        }
+        for (path, text) in [
+            ("meeting.wav", "the payroll meeting"),
+            ("lunch.wav", "lunch"),
+        ] {
+            let audio_chunk_id = db.insert_audio_chunk(path).await.unwrap();
+            db.insert_audio_transcription(audio_chunk_id, text, 0, "", &device, None, None, None)
+                .await
+                .unwrap();
+        }
+        sqlx::query(
+            "INSERT INTO ui_monitoring (text_output, app, window) VALUES ('payroll', 'Excel', 'payroll.xlsx'), ('inbox', 'Mail', 'Inbox')",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+
+        let filter = SearchDeleteFilter {
+            q: Some("payroll".to_string()),
+            ..Default::default()
+        };
+        let db = &db;
+        let counts = || async move {
+            db.execute_raw_sql(
+                r#"SELECT
+                    (SELECT COUNT(*) FROM frames) AS frames,
+                    (SELECT COUNT(*) FROM ocr_text_fts WHERE ocr_text_fts MATCH 'payroll') AS ocr,
+                    (SELECT COUNT(*) FROM audio_chunks) AS audio,
+                    (SELECT COUNT(*) FROM ui_monitoring) AS ui,
+                    (SELECT COUNT(*) FROM vision_tags) AS tags"#,
+            )
+            .await
+            .unwrap()[0]
+                .clone()
+        };
+
+        let dry_run = db.delete_search_results(&filter, true).await.unwrap();
+        assert!(dry_run.dry_run);
+        assert_eq!(dry_run.frames, 2);
+        assert_eq!(dry_run.ocr_rows, 2);
+        assert_eq!(dry_run.tags, 1);
+        assert_eq!(dry_run.audio_chunks, 1);
+        assert_eq!(dry_run.audio_transcriptions, 1);
+        assert_eq!(dry_run.ui_rows, 1);
+        // b.mp4 still holds the weather frame
+        assert_eq!(dry_run.video_files, vec!["a.mp4".to_string()]);
+        assert_eq!(dry_run.audio_files, vec!["meeting.wav".to_string()]);
+        assert_eq!(
+            counts().await,
+            serde_json::json!({"frames": 3, "ocr": 2, "audio": 2, "ui": 2, "tags": 1})
+        );
+
+        let deleted = db.delete_search_results(&filter, false).await.unwrap();
+        assert_eq!(
+            deleted,
+            screenpipe_db::SearchDeletion {
+                dry_run: false,
+                ..dry_run
+            }
+        );
+        assert_eq!(
+            counts().await,
+            serde_json::json!({"frames": 1, "ocr": 0, "audio": 1, "ui": 1, "tags": 0})
+        );
+
+        // app and window filters leave audio out
+        let chrome = SearchDeleteFilter {
+            app_name: Some("Chrome".to_string()),
+            ..Default::default()
+        };
+        let deleted = db.delete_search_results(&chrome, false).await.unwrap();
+        assert_eq!(deleted.frames, 1);
+        assert_eq!(deleted.audio_chunks, 0);
+        // b.mp4 is still being recorded
+        assert!(deleted.video_files.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_retention_rules_by_app_and_content_type() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+        let now = Utc::now();
+        for (days_ago, app) in [(2, "Chase"), (40, "Code"), (400, "Code")] {
+            db.insert_frame(
+                "test_device",
+                Some(now - chrono::Duration::days(days_ago)),
+                None,
+                Some(app),
+                None,
+                true,
+            )
+            .await
+            .unwrap();
+        }
+        for (path, days_ago) in [("old.mp4", 40), ("new.mp4", 10)] {
+            let audio_chunk_id = db.insert_audio_chunk(path).await.unwrap();
+            sqlx::query("UPDATE audio_chunks SET timestamp = ?1 WHERE id = ?2")
+                .bind(now - chrono::Duration::days(days_ago))
+                .bind(audio_chunk_id)
+                .execute(&db.pool)
+                .await
+                .unwrap();
+        }
+        sqlx::query(
+            "INSERT INTO ui_monitoring (text_output, app, window, timestamp) VALUES ('balance', 'Chase', 'Accounts', ?1), ('main.rs', 'Code', 'main.rs', ?1)",
+        )
+        .bind(now - chrono::Duration::days(1))
+        .execute(&db.pool)
+        .await
+        .unwrap();
+
+        let rules: Vec<RetentionRule> = ["app:chase=0d", "vision=365d", "audio=30d", "ui=90d"]
+            .iter()
+            .map(|rule| rule.parse().unwrap())
+            .collect();
+        assert!("frames=1d".parse::<RetentionRule>().is_err());
+        assert!("app:=1d".parse::<RetentionRule>().is_err());
+        db.register_retention_rules(Some(14), &rules).await.unwrap();
+
+        // the 40 days old frame is kept by the vision rule, not the default
+        let trashed = db.trash_expired(now, Some(14), &rules, 100).await.unwrap();
+        assert_eq!(trashed.frames, 2);
+        assert_eq!(trashed.audio_chunks, 1);
+        let trashed = db.trash_expired_ui(now, &rules, 100).await.unwrap();
+        assert_eq!(trashed, 1);
+        let ui = db
+            .search_ui_monitoring("", None, None, None, None, 10, 0, &TagFilter::default())
+            .await
+            .unwrap();
+        assert_eq!(ui.len(), 1);
+        assert_eq!(ui[0].app_name, "Code");
+
+        // what the 0 days rule trashed doesn't wait in the trash
+        let purge_now = ["app:chase".to_string()];
+        let purged = db
+            .purge_trash(now - chrono::Duration::days(7), &purge_now, 100)
+            .await
+            .unwrap();
+        assert_eq!(purged.frames, 1);
+        assert_eq!(purged.audio_chunks, 0);
+        assert_eq!(purged.ui_rows, 1);
+        let purged = db
+            .purge_trash(now + chrono::Duration::minutes(1), &purge_now, 100)
+            .await
+            .unwrap();
+        assert_eq!(purged.frames, 1);
+        assert_eq!(purged.audio_chunks, 1);
+
+        let stats: Vec<(String, Option<i64>, i64, i64, i64, i64, i64, i64)> = db
+            .list_retention_rule_stats()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|stats| {
+                (
+                    stats.rule,
+                    stats.keep_days,
+                    stats.trashed_frames,
+                    stats.trashed_audio_chunks,
+                    stats.purged_frames,
+                    stats.purged_audio_chunks,
+                    stats.trashed_ui_rows,
+                    stats.deleted_ui_rows,
+                )
+            })
+            .collect();
+        assert_eq!(
+            stats,
+            vec![
+                ("app:chase".to_string(), Some(0), 1, 0, 1, 0, 1, 1),
+                ("audio".to_string(), Some(30), 0, 1, 0, 1, 0, 0),
+                ("default".to_string(), Some(14), 0, 0, 0, 0, 0, 0),
+                ("ui".to_string(), Some(90), 0, 0, 0, 0, 0, 0),
+                ("vision".to_string(), Some(365), 1, 0, 1, 0, 0, 0),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_clipboard_retention_and_trash() {
+        let db = setup_test_db().await;
+        let now = Utc::now();
+        for (text, app, days_ago) in [
+            ("4111 1111", "Chase", 1),
+            ("old snippet", "Code", 10),
+            ("new snippet", "Code", 1),
+        ] {
+            let id = db
+                .insert_clipboard_entry(text, Some(app), None)
+                .await
+                .unwrap()
+                .unwrap();
+            sqlx::query("UPDATE clipboard SET timestamp = ?1 WHERE id = ?2")
+                .bind(now - chrono::Duration::days(days_ago))
+                .bind(id)
+                .execute(&db.pool)
+                .await
+                .unwrap();
+        }
+
+        let rules: Vec<RetentionRule> = ["app:chase=0d", "clipboard=7d"]
+            .iter()
+            .map(|rule| rule.parse().unwrap())
+            .collect();
+        assert_eq!(db.trash_expired_ui(now, &rules, 100).await.unwrap(), 2);
+        let search =
+            || db.search_clipboard("", None, None, None, None, 10, 0, &TagFilter::default());
+        let left: Vec<String> = search()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.text)
+            .collect();
+        assert_eq!(left, vec!["new snippet"]);
+
+        let items = db
+            .list_trash_items(chrono::Duration::days(7), None, Some("Code"), 100, 0)
+            .await
+            .unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].kind, "clipboard");
+        let restored = db
+            .restore_trash(&[], &[], &[], &[], &[items[0].id], None, None)
+            .await
+            .unwrap();
+        assert_eq!(restored.ui_rows, 1);
+        assert_eq!(search().await.unwrap().len(), 2);
+        // restored entries aren't trashed again
+        assert_eq!(db.trash_expired_ui(now, &rules, 100).await.unwrap(), 0);
+
+        // what the 0 days rule trashed doesn't wait in the trash
+        let purge_now = ["app:chase".to_string()];
+        assert_eq!(
+            db.count_purgeable(now - chrono::Duration::days(7), &purge_now)
+                .await
+                .unwrap()
+                .ui_rows,
+            1
+        );
+        let purged = db
+            .purge_trash(now - chrono::Duration::days(7), &purge_now, 100)
+            .await
+            .unwrap();
+        assert_eq!(purged.ui_rows, 1);
+        let texts: Vec<String> = sqlx::query_scalar("SELECT text FROM clipboard ORDER BY id")
+            .fetch_all(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(texts, vec!["old snippet", "new snippet"]);
+        let indexed: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM clipboard_fts WHERE clipboard_fts MATCH '4111'",
+        )
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+        assert_eq!(indexed, 0);
+    }
+
+    #[tokio::test]
+    async fn test_unencrypted_media_chunks() {
+        let db = setup_test_db().await;
+        let first = db
+            .insert_video_chunk("monitor_1_a.mp4", "monitor_1")
+            .await
+            .unwrap();
+        db.insert_video_chunk("monitor_2_a.mp4", "monitor_2")
+            .await
+            .unwrap();
+        db.insert_video_chunk("monitor_1_b.mp4", "monitor_1")
+            .await
+            .unwrap();
+
+        // the latest chunk of each monitor is still being written
+        assert_eq!(
+            db.list_unencrypted_video_chunks(10).await.unwrap(),
+            vec![(first, "monitor_1_a.mp4".to_string())]
+        );
+        db.mark_chunk_encrypted(MediaChunkKind::Video, first)
+            .await
+            .unwrap();
+        assert!(db
+            .list_unencrypted_video_chunks(10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let audio = db.insert_audio_chunk("input_a.mp4").await.unwrap();
+        let before = Utc::now() - chrono::Duration::minutes(5);
+        assert!(db
+            .list_unencrypted_audio_chunks(before, 10)
+            .await
+            .unwrap()
+            .is_empty());
+        let later = Utc::now() + chrono::Duration::minutes(1);
+        assert_eq!(
+            db.list_unencrypted_audio_chunks(later, 10).await.unwrap(),
+            vec![(audio, "input_a.mp4".to_string())]
+        );
+        db.mark_chunk_encrypted(MediaChunkKind::Audio, audio)
+            .await
+            .unwrap();
+        assert!(db
+            .list_unencrypted_audio_chunks(later, 10)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            db.get_audio_chunk_path(audio).await.unwrap().as_deref(),
+            Some("input_a.mp4")
+        );
+        assert_eq!(db.get_audio_chunk_path(audio + 1).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_export_and_erase_subject() {
+        let db = setup_test_db().await;
+        let engine = Arc::new(OcrEngine::Tesseract);
+        let device = AudioDevice {
+            name: "mic".to_string(),
+            device_type: DeviceType::Input,
+        };
+
+        let jane = db.insert_speaker(&[0.1; 512]).await.unwrap();
+        db.update_speaker_name(jane.id, "Jane Doe").await.unwrap();
+        let bob = db.insert_speaker(&[0.2; 512]).await.unwrap();
+        let mut audio_chunks = Vec::new();
+        for (path, texts) in [
+            ("jane.mp4", vec![("see you tomorrow", jane.id)]),
+            (
+                "bob.mp4",
+                vec![("I sent it to jane doe", bob.id), ("lunch at noon", bob.id)],
+            ),
+            ("other.mp4", vec![("nothing to see", bob.id)]),
+            // still being recorded
+            ("live.mp4", vec![("jane doe is here", bob.id)]),
+        ] {
+            let audio_chunk_id = db.insert_audio_chunk(path).await.unwrap();
+            audio_chunks.push(audio_chunk_id);
+            for (text, speaker_id) in texts {
+                db.insert_audio_transcription(
+                    audio_chunk_id,
+                    text,
+                    0,
+                    "",
+                    &device,
+                    Some(speaker_id),
+                    None,
+                    None,
+                )
+                .await
+                .unwrap();
+            }
+        }
+
+        db.insert_video_chunk("screen.mp4", "monitor")
+            .await
+            .unwrap();
+        let mut frame_ids = Vec::new();
+        for text in ["Message from Jane Doe", "weather today"] {
+            let frame_id = db
+                .insert_frame("monitor", None, None, Some("Slack"), None, true)
+                .await
+                .unwrap();
+            db.insert_ocr_text(frame_id, text, "", engine.clone())
+                .await
+                .unwrap();
+            frame_ids.push(frame_id);
+        }
+        db.insert_video_chunk("live_screen.mp4", "monitor")
+            .await
+            .unwrap();
+        let live_frame = db
+            .insert_frame("monitor", None, None, Some("Slack"), None, true)
+            .await
+            .unwrap();
+        db.insert_ocr_text(live_frame, "Jane Doe joined", "", engine.clone())
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO ui_monitoring (text_output, app, window) VALUES ('Jane Doe is typing', 'Slack', 'general'), ('inbox', 'Mail', 'Inbox')",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        let copied = db
+            .insert_clipboard_entry("jane.doe@example.com", Some("Mail"), None)
+            .await
+            .unwrap()
+            .unwrap();
+        db.insert_clipboard_entry("groceries", Some("Notes"), None)
+            .await
+            .unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO search_clicks (query, content_type, content_id, clicked_at) VALUES
+                ('report', 'ocr', ?1, '2024-01-01'),
+                ('doe jane', 'ui', 99, '2024-01-01'),
+                ('weather', 'ocr', ?2, '2024-01-01'),
+                ('email', 'clipboard', ?3, '2024-01-01')
+            "#,
+        )
+        .bind(frame_ids[0])
+        .bind(frame_ids[1])
+        .bind(copied)
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO tag_suggestions (content_type, content_id, tag, model, created_at) VALUES
+                ('vision', ?1, 'chat', 'm', '2024-01-01'),
+                ('audio', ?2, 'chat', 'm', '2024-01-01')
+            "#,
+        )
+        .bind(frame_ids[0])
+        .bind(audio_chunks[0])
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        for target in ["q=Jane Doe", "q=weather"] {
+            db.insert_audit_entry("admin", "search", target, None)
+                .await
+                .unwrap();
+        }
+
+        let subject = Subject {
+            speaker_id: None,
+            name: Some("jane doe".to_string()),
+        };
+        let export = db.export_subject(&subject).await.unwrap();
+        assert_eq!(
+            export
+                .speakers
+                .iter()
+                .map(|speaker| (speaker.id, speaker.name.as_str()))
+                .collect::<Vec<_>>(),
+            vec![(jane.id, "Jane Doe")]
+        );
+        assert_eq!(
+            export
+                .transcripts
+                .iter()
+                .map(|transcript| transcript.transcription.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "see you tomorrow",
+                "I sent it to jane doe",
+                "jane doe is here"
+            ]
+        );
+        assert_eq!(
+            export
+                .audio_chunks
+                .iter()
+                .map(|chunk| chunk.file_path.as_str())
+                .collect::<Vec<_>>(),
+            vec!["jane.mp4", "bob.mp4", "live.mp4"]
+        );
+        assert_eq!(export.ocr_hits.len(), 2);
+        assert_eq!(export.ocr_hits[0].file_path, "screen.mp4");
+        assert_eq!(export.ui_hits.len(), 1);
+        assert_eq!(
+            export
+                .clipboard_hits
+                .iter()
+                .map(|hit| hit.id)
+                .collect::<Vec<_>>(),
+            vec![copied]
+        );
+
+        // a speaker id alone finds what they said only
+        let by_speaker = db
+            .export_subject(&Subject {
+                speaker_id: Some(jane.id),
+                name: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(by_speaker.transcripts.len(), 1);
+        assert!(by_speaker.ocr_hits.is_empty());
+
+        let erasure = db.erase_subject(&subject).await.unwrap();
+        assert_eq!(erasure.export.ocr_hits, export.ocr_hits[..1]);
+        assert_eq!(erasure.export.transcripts, export.transcripts[..2]);
+        assert_eq!(erasure.export.audio_chunks, export.audio_chunks[..2]);
+        assert_eq!(erasure.skipped_live, 2);
+        assert_eq!(erasure.deletion.frames, 1);
+        // bob.mp4 keeps the transcription not mentioning them
+        assert_eq!(erasure.deletion.audio_transcriptions, 2);
+        assert_eq!(erasure.deletion.audio_chunks, 1);
+        assert_eq!(erasure.deletion.audio_files, vec!["jane.mp4"]);
+        assert_eq!(erasure.deletion.ui_rows, 1);
+        assert_eq!(erasure.deletion.clipboard_entries, 1);
+        // the video chunk still holds the other frame
+        assert!(erasure.deletion.video_files.is_empty());
+        assert_eq!(erasure.speakers, 1);
+        assert_eq!(erasure.speaker_embeddings, 1);
+        assert_eq!(erasure.search_clicks, 3);
+        assert_eq!(erasure.tag_suggestions, 2);
+        assert_eq!(erasure.audit_entries, 1);
+
+        let after = db.export_subject(&subject).await.unwrap();
+        assert!(after.speakers.is_empty());
+        // left in the live chunks for a later erasure
+        assert_eq!(after.transcripts.len(), 1);
+        assert_eq!(after.ocr_hits.len(), 1);
+        assert!(after.ui_hits.is_empty());
+        assert!(after.clipboard_hits.is_empty());
+        let left = db
+            .execute_raw_sql(
+                r#"SELECT
+                    (SELECT COUNT(*) FROM frames) AS frames,
+                    (SELECT COUNT(*) FROM audio_chunks) AS audio,
+                    (SELECT COUNT(*) FROM audio_transcriptions) AS transcriptions,
+                    (SELECT COUNT(*) FROM ui_monitoring) AS ui,
+                    (SELECT COUNT(*) FROM clipboard) AS clipboard,
+                    (SELECT COUNT(*) FROM speakers) AS speakers,
+                    (SELECT COUNT(*) FROM search_clicks) AS clicks,
+                    (SELECT COUNT(*) FROM tag_suggestions) AS suggestions,
+                    (SELECT COUNT(*) FROM audit_log) AS audit"#,
+            )
+            .await
+            .unwrap()[0]
+            .clone();
+        assert_eq!(
+            left,
+            serde_json::json!({
+                "frames": 2,
+                "audio": 3,
+                "transcriptions": 3,
+                "ui": 1,
+                "clipboard": 1,
+                "speakers": 1,
+                "clicks": 1,
+                "suggestions": 0,
+                "audit": 1
+            })
+        );
+    }
+
+    struct RedactingProcessor;
+
+    impl ContentProcessor for RedactingProcessor {
+        fn process(&self, content: &HookContent) -> HookOutcome {
+            match content.hook {
+                _ if content.text.contains("password") => HookOutcome {
+                    drop: true,
+                    ..Default::default()
+                },
+                ContentHook::OnOcr | ContentHook::OnTranscription => HookOutcome {
+                    text: Some(content.text.replace("secret", "[redacted]")),
+                    tags: vec![format!("{:?}", content.hook)],
+                    ..Default::default()
+                },
+                ContentHook::OnFrame => HookOutcome {
+                    // ignored after insertion
+                    drop: true,
+                    tags: vec![content.app_name.clone().unwrap_or_default()],
+                    ..Default::default()
+                },
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_content_processor() {
+        let db = setup_test_db()
+            .await
+            .with_content_processor(Some(Arc::new(RedactingProcessor)));
+        db.insert_video_chunk("video.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let frames = vec![
+            NewFrame {
+                app_name: Some("Code".to_string()),
+                text: "the secret plan".to_string(),
+                ..Default::default()
+            },
+            NewFrame {
+                app_name: Some("1Password".to_string()),
+                text: "password hunter2".to_string(),
+                ..Default::default()
+            },
+        ];
+        let ids = db
+            .insert_frames_batch("monitor_1", &frames, Arc::new(OcrEngine::Tesseract))
+            .await
+            .unwrap();
+        assert_eq!(ids[1], 0);
+        let texts: Vec<String> = sqlx::query_scalar("SELECT text FROM ocr_text")
+            .fetch_all(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(texts, vec!["the [redacted] plan".to_string()]);
+        let mut tags = db.get_tags(ids[0], TagContentType::Vision).await.unwrap();
+        tags.sort();
+        assert_eq!(tags, vec!["Code".to_string(), "OnOcr".to_string()]);
+
+        // the text of frames inserted one by one goes through it too
+        for text in ["another secret", "password hunter2"] {
+            let frame_id = db
+                .insert_frame("monitor_1", None, None, Some("Code"), None, true)
+                .await
+                .unwrap();
+            db.insert_ocr_text(frame_id, text, "[]", Arc::new(OcrEngine::Tesseract))
+                .await
+                .unwrap();
+        }
+        let texts: Vec<String> = sqlx::query_scalar("SELECT text FROM ocr_text ORDER BY frame_id")
+            .fetch_all(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(
+            texts,
+            vec![
+                "the [redacted] plan".to_string(),
+                "another [redacted]".to_string()
+            ]
+        );
+
+        let audio_chunk_id = db.insert_audio_chunk("audio.mp4").await.unwrap();
+        let device = AudioDevice {
+            name: "mic".to_string(),
+            device_type: DeviceType::Input,
+        };
+        let dropped = db
+            .insert_audio_transcription(
+                audio_chunk_id,
+                "my password is hunter2",
+                0,
+                "",
+                &device,
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(dropped, 0);
+        db.insert_audio_transcription(
+            audio_chunk_id,
+            "a secret meeting",
+            1,
+            "",
+            &device,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        let transcriptions: Vec<String> =
+            sqlx::query_scalar("SELECT transcription FROM audio_transcriptions")
+                .fetch_all(&db.pool)
+                .await
+                .unwrap();
+        assert_eq!(transcriptions, vec!["a [redacted] meeting".to_string()]);
+        assert_eq!(
+            db.get_tags(audio_chunk_id, TagContentType::Audio)
+                .await
+                .unwrap(),
+            vec!["OnTranscription".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_insert_events_and_metadata() {
+        let db = setup_test_db().await;
+        let mut events = db.subscribe_insert_events();
+        db.insert_video_chunk("video.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let frame_id = db
+            .insert_frame("monitor_1", None, None, Some("Jira"), Some("PROJ-42"), true)
+            .await
+            .unwrap();
+        db.insert_ocr_text(frame_id, "fix PROJ-42", "", Arc::new(OcrEngine::Tesseract))
+            .await
+            .unwrap();
+        let event = events.try_recv().unwrap();
+        assert_eq!(
+            (
+                event.content_type.as_str(),
+                event.target_id,
+                event.text.as_str(),
+                event.app_name.as_deref(),
+                event.device_name.as_str()
+            ),
+            ("ocr", frame_id, "fix PROJ-42", Some("Jira"), "monitor_1")
+        );
+
+        let audio_chunk_id = db.insert_audio_chunk("audio.mp4").await.unwrap();
+        let transcription_id = db
+            .insert_audio_transcription(
+                audio_chunk_id,
+                "let's look at PROJ-42",
+                0,
+                "",
+                &AudioDevice {
+                    name: "mic".to_string(),
+                    device_type: DeviceType::Input,
+                },
+                Some(3),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        let event = events.try_recv().unwrap();
+        assert_eq!(
+            (
+                event.content_type.as_str(),
+                event.id,
+                event.target_id,
+                event.speaker_id
+            ),
+            ("audio", transcription_id, audio_chunk_id, Some(3))
+        );
+
+        db.set_metadata(frame_id, TagContentType::Vision, "jira", "PROJ-42")
+            .await
+            .unwrap();
+        db.set_metadata(frame_id, TagContentType::Vision, "jira", "PROJ-43")
+            .await
+            .unwrap();
+        db.set_metadata(audio_chunk_id, TagContentType::Audio, "jira", "PROJ-42")
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_metadata(frame_id, TagContentType::Vision)
+                .await
+                .unwrap(),
+            vec![ContentMetadata {
+                key: "jira".to_string(),
+                value: "PROJ-43".to_string()
+            }]
+        );
+        assert_eq!(
+            db.get_metadata(audio_chunk_id, TagContentType::Audio)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    struct UppercaseStage;
+
+    impl ContentProcessor for UppercaseStage {
+        fn process(&self, content: &HookContent) -> HookOutcome {
+            HookOutcome {
+                text: Some(content.text.to_uppercase()),
+                stages: vec![screenpipe_db::StageProvenance {
+                    stage: "uppercase".to_string(),
+                    version: "1".to_string(),
+                    changed: true,
+                }],
+                ..Default::default()
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_text_provenance() {
+        // the second processor sees what the first left
+        let db = setup_test_db()
+            .await
+            .with_content_processor(Some(Arc::new(UppercaseStage)))
+            .with_content_processor(Some(Arc::new(RedactingProcessor)));
+        db.insert_video_chunk("video.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let frames = vec![NewFrame {
+            text: "the secret plan".to_string(),
+            ..Default::default()
+        }];
+        let ids = db
+            .insert_frames_batch("monitor_1", &frames, Arc::new(OcrEngine::Tesseract))
+            .await
+            .unwrap();
+        let provenance = db
+            .get_text_provenance(TagContentType::Vision, ids[0])
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(provenance.raw_text, "the secret plan");
+        assert_eq!(provenance.text, "THE SECRET PLAN");
+        assert_eq!(provenance.stages[0].stage, "uppercase");
+
+        // hidden with the frame in the trash
+        sqlx::query("UPDATE frames SET deleted_at = ?1 WHERE id = ?2")
+            .bind(Utc::now())
+            .bind(ids[0])
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        assert!(db
+            .get_text_provenance(TagContentType::Vision, ids[0])
+            .await
+            .unwrap()
+            .is_none());
+
+        let audio_chunk_id = db.insert_audio_chunk("audio.mp4").await.unwrap();
+        let id = db
+            .insert_audio_transcription(
+                audio_chunk_id,
+                "hello there",
+                0,
+                "",
+                &AudioDevice {
+                    name: "mic".to_string(),
+                    device_type: DeviceType::Input,
+                },
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(db
+            .update_processed_text(
+                TagContentType::Audio,
+                id,
+                "hello there",
+                "Hello there.",
+                &[]
+            )
+            .await
+            .unwrap());
+        let provenance = db
+            .get_text_provenance(TagContentType::Audio, id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            (provenance.raw_text.as_str(), provenance.text.as_str()),
+            ("hello there", "Hello there.")
+        );
+        assert!(provenance.stages.is_empty());
+
+        // goes with the transcription
+        sqlx::query("DELETE FROM audio_transcriptions WHERE id = ?1")
+            .bind(id)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        assert!(db
+            .get_text_provenance(TagContentType::Audio, id)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_tag_rules_tag_on_insert() {
+        let db = setup_test_db().await;
+        let rule = |tag: &str| NewTagRule {
+            name: tag.to_string(),
+            tag: tag.to_string(),
+            ..Default::default()
+        };
+        let now = chrono::Local::now();
+        let time_of_day = |hours: i64| {
+            (now + chrono::Duration::hours(hours))
+                .format("%H:%M")
+                .to_string()
+        };
+        db.insert_tag_rule(&NewTagRule {
+            app_name: Some("zoom".to_string()),
+            ..rule("meeting")
+        })
+        .await
+        .unwrap();
+        db.insert_tag_rule(&NewTagRule {
+            browser_url: Some("arxiv.org".to_string()),
+            ..rule("research")
+        })
+        .await
+        .unwrap();
+        db.insert_tag_rule(&NewTagRule {
+            content_type: Some("ocr".to_string()),
+            start_time: Some(time_of_day(-1)),
+            end_time: Some(time_of_day(1)),
+            ..rule("work")
+        })
+        .await
+        .unwrap();
+        db.insert_tag_rule(&NewTagRule {
+            start_time: Some(time_of_day(2)),
+            end_time: Some(time_of_day(3)),
+            ..rule("night")
+        })
+        .await
+        .unwrap();
+        let incident = db
+            .insert_tag_rule(&NewTagRule {
+                pattern: Some(r"\bincident\b".to_string()),
+                is_regex: true,
+                ..rule("incident")
+            })
+            .await
+            .unwrap();
+
+        let _ = db
+            .insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+        let frame_id = db
+            .insert_frame("test_device", None, None, Some("Zoom"), None, true)
+            .await
+            .unwrap();
+        db.insert_ocr_text(
+            frame_id,
+            "standup notes",
+            "",
+            Arc::new(OcrEngine::Tesseract),
+        )
+        .await
+        .unwrap();
+        let mut tags = db.get_tags(frame_id, TagContentType::Vision).await.unwrap();
+        tags.sort();
+        assert_eq!(tags, vec!["meeting", "work"]);
+
+        let ids = db
+            .insert_frames_batch(
+                "test_device",
+                &[NewFrame {
+                    timestamp: None,
+                    browser_url: Some("https://arxiv.org/abs/1706.03762".to_string()),
+                    app_name: Some("Firefox".to_string()),
+                    window_name: None,
+                    focused: true,
+                    text: "incident report".to_string(),
+                    text_json: String::new(),
+                }],
+                Arc::new(OcrEngine::Tesseract),
+            )
+            .await
+            .unwrap();
+        let mut tags = db.get_tags(ids[0], TagContentType::Vision).await.unwrap();
+        tags.sort();
+        assert_eq!(tags, vec!["incident", "research", "work"]);
+
+        // disabled rules and rules on where content was seen don't apply
+        db.set_tag_rule_enabled(incident.id, false).await.unwrap();
+        let audio_chunk_id = db.insert_audio_chunk("test_audio.mp4").await.unwrap();
+        db.insert_audio_transcription(
+            audio_chunk_id,
+            "the zoom incident call",
+            0,
+            "",
+            &AudioDevice {
+                name: "test".to_string(),
+                device_type: DeviceType::Input,
+            },
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(db
+            .get_tags(audio_chunk_id, TagContentType::Audio)
+            .await
+            .unwrap()
+            .is_empty());
+
+        assert!(db.delete_tag_rule(incident.id).await.unwrap());
+        assert_eq!(db.list_tag_rules().await.unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_hierarchical_tags() {
+        let db = setup_test_db().await;
+        assert_eq!(
+            normalize_tag(" project / alpha//design/ "),
+            "project/alpha/design"
+        );
+
+        let _ = db
+            .insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+        let mut frames = Vec::new();
+        for (text, tags) in [
+            ("alpha design review", vec!["project/alpha/design"]),
+            ("alpha budget", vec!["project/alpha", "finance"]),
+            ("beta planning", vec!["project/beta"]),
+            ("projector manual", vec!["projector"]),
+        ] {
+            let frame_id = db
+                .insert_frame("test_device", None, None, None, None, true)
+                .await
+                .unwrap();
+            db.insert_ocr_text(frame_id, text, "", Arc::new(OcrEngine::Tesseract))
+                .await
+                .unwrap();
+            db.add_tags(
+                frame_id,
+                TagContentType::Vision,
+                tags.into_iter().map(String::from).collect(),
+            )
+            .await
+            .unwrap();
+            frames.push(frame_id);
+        }
+
+        let search = |tags: &'static [&'static str]| {
+            let db = &db;
+            async move {
+                let tags = TagFilter {
+                    all: tags.iter().map(|tag| tag.to_string()).collect(),
+                    ..Default::default()
+                };
+                let results = db
+                    .search(
+                        "",
+                        ContentType::All,
+                        10,
+                        0,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        false,
+                        &tags,
+                    )
+                    .await
+                    .unwrap();
+                let count = db
+                    .count_search_results(
+                        "",
+                        ContentType::All,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        false,
+                        &tags,
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(results.len(), count);
+                let mut ids: Vec<i64> = results
+                    .into_iter()
+                    .map(|result| match result {
+                        SearchResult::OCR(ocr) => ocr.frame_id,
+                        other => panic!("unexpected result {:?}", other),
+                    })
+                    .collect();
+                ids.sort();
+                ids
+            }
+        };
+
+        // a parent matches its descendants, not tags it is only a prefix of
+        assert_eq!(search(&["project"]).await, frames[..3].to_vec());
+        assert_eq!(search(&["project/alpha/"]).await, frames[..2].to_vec());
+        assert_eq!(search(&["project/alpha/design"]).await, vec![frames[0]]);
+        assert_eq!(search(&["project", "finance"]).await, vec![frames[1]]);
+        assert_eq!(search(&["project/gamma"]).await, Vec::<i64>::new());
+
+        let top = db.tag_facets(None, None, None).await.unwrap();
+        assert_eq!(
+            top,
+            vec![
+                TagFacet {
+                    tag: "project".to_string(),
+                    count: 3,
+                    has_children: true,
+                },
+                TagFacet {
+                    tag: "finance".to_string(),
+                    count: 1,
+                    has_children: false,
+                },
+                TagFacet {
+                    tag: "projector".to_string(),
+                    count: 1,
+                    has_children: false,
+                },
+            ]
+        );
+        let project = db.tag_facets(Some("project"), None, None).await.unwrap();
+        assert_eq!(
+            project,
+            vec![
+                TagFacet {
+                    tag: "project/alpha".to_string(),
+                    count: 2,
+                    has_children: true,
+                },
+                TagFacet {
+                    tag: "project/beta".to_string(),
+                    count: 1,
+                    has_children: false,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tag_management() {
+        let db = setup_test_db().await;
+        let _ = db
+            .insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+        let mut frames = Vec::new();
+        for tags in [
+            vec!["ml"],
+            vec!["machine-learning"],
+            vec!["ml", "machine-learning"],
+        ] {
+            let frame_id = db
+                .insert_frame("test_device", None, None, None, None, true)
+                .await
+                .unwrap();
+            db.insert_ocr_text(frame_id, "notes", "", Arc::new(OcrEngine::Tesseract))
+                .await
+                .unwrap();
+            db.add_tags(
+                frame_id,
+                TagContentType::Vision,
+                tags.into_iter().map(String::from).collect(),
+            )
+            .await
+            .unwrap();
+            frames.push(frame_id);
+        }
+        db.insert_tag_rule(&NewTagRule {
+            name: "papers".to_string(),
+            tag: "ml".to_string(),
+            app_name: Some("zotero".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        let tags = |frame_id: i64| db.get_tags(frame_id, TagContentType::Vision);
+        let summary = |name: &str, vision_count: i64, aliases: &[&str]| TagSummary {
+            name: name.to_string(),
+            vision_count,
+            audio_count: 0,
+            ui_count: 0,
+            aliases: aliases.iter().map(|alias| alias.to_string()).collect(),
+        };
+
+        assert_eq!(
+            db.rename_tag("ml", "ml-papers").await.unwrap(),
+            TagUpdate::Updated(summary("ml-papers", 2, &[]))
+        );
+        assert_eq!(tags(frames[0]).await.unwrap(), vec!["ml-papers"]);
+        assert_eq!(db.list_tag_rules().await.unwrap()[0].tag, "ml-papers");
+        assert_eq!(
+            db.rename_tag("ml-papers", "machine-learning")
+                .await
+                .unwrap(),
+            TagUpdate::NameTaken
+        );
+        assert_eq!(
+            db.rename_tag("missing", "other").await.unwrap(),
+            TagUpdate::NotFound
+        );
+
+        // the merged tag lives on as an alias
+        assert_eq!(
+            db.merge_tags("ml-papers", "machine-learning")
+                .await
+                .unwrap(),
+            TagUpdate::Updated(summary("machine-learning", 3, &["ml-papers"]))
+        );
+        assert_eq!(tags(frames[2]).await.unwrap(), vec!["machine-learning"]);
+        assert_eq!(
+            db.list_tag_rules().await.unwrap()[0].tag,
+            "machine-learning"
+        );
+        db.add_tags(
+            frames[0],
+            TagContentType::Vision,
+            vec!["ml-papers".to_string()],
+        )
+        .await
+        .unwrap();
+        assert_eq!(tags(frames[0]).await.unwrap(), vec!["machine-learning"]);
+
+        assert_eq!(
+            db.add_tag_alias("ai", "machine-learning").await.unwrap(),
+            TagUpdate::Updated(summary("machine-learning", 3, &["ai", "ml-papers"]))
+        );
+        assert_eq!(
+            db.add_tag_alias("machine-learning", "machine-learning")
+                .await
+                .unwrap(),
+            TagUpdate::NameTaken
+        );
+        let count = db
+            .count_search_results(
+                "",
+                ContentType::OCR,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                false,
+                &TagFilter {
+                    all: vec!["ai".to_string()],
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(count, 3);
+        assert!(db.remove_tag_alias("ai").await.unwrap());
+        assert!(!db.remove_tag_alias("ai").await.unwrap());
+
+        assert_eq!(
+            db.delete_tag("machine-learning").await.unwrap(),
+            Some(summary("machine-learning", 3, &["ml-papers"]))
+        );
+        assert!(tags(frames[2]).await.unwrap().is_empty());
+        assert_eq!(db.delete_tag("machine-learning").await.unwrap(), None);
+        // the alias went with the tag
+        db.add_tags(
+            frames[0],
+            TagContentType::Vision,
+            vec!["ml-papers".to_string()],
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            db.list_tags().await.unwrap(),
+            vec![summary("ml-papers", 1, &[])]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tag_search_results() {
+        let db = setup_test_db().await;
+        let engine = Arc::new(OcrEngine::Tesseract);
+        let device = AudioDevice {
+            name: "mic".to_string(),
+            device_type: DeviceType::Input,
+        };
+
+        db.insert_video_chunk("a.mp4", "monitor").await.unwrap();
+        let mut frames = Vec::new();
+        for text in ["interview with ada", "interview notes", "weather today"] {
+            let frame_id = db
+                .insert_frame("monitor", None, None, Some("Zoom"), None, true)
+                .await
+                .unwrap();
+            db.insert_ocr_text(frame_id, text, "", engine.clone())
+                .await
+                .unwrap();
+            frames.push(frame_id);
+        }
+        db.add_tags(
+            frames[1],
+            TagContentType::Vision,
+            vec!["hiring/interview".to_string()],
+        )
+        .await
+        .unwrap();
+        let audio_chunk_id = db.insert_audio_chunk("call.wav").await.unwrap();
+        db.insert_audio_transcription(
+            audio_chunk_id,
+            "the interview went well",
+            0,
+            "",
+            &device,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO ui_monitoring (text_output, app, window) VALUES ('interview', 'Zoom', 'Call')")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+
+        let filter = SearchDeleteFilter {
+            q: Some("interview".to_string()),
+            ..Default::default()
+        };
+        let mut reported = Vec::new();
+        let tagging = db
+            .tag_search_results(&filter, " hiring / interview ", |tagging| {
+                reported.push(tagging.clone())
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            tagging,
+            SearchTagging {
+                matched: 4,
+                processed: 4,
+                tagged: 3,
+            }
+        );
+        assert_eq!(reported.first().unwrap().processed, 0);
+        assert_eq!(reported.last().unwrap(), &tagging);
+        for frame_id in &frames[..2] {
+            assert_eq!(
+                db.get_tags(*frame_id, TagContentType::Vision)
+                    .await
+                    .unwrap(),
+                vec!["hiring/interview"]
+            );
+        }
+        assert!(db
+            .get_tags(frames[2], TagContentType::Vision)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            db.get_tags(audio_chunk_id, TagContentType::Audio)
+                .await
+                .unwrap(),
+            vec!["hiring/interview"]
+        );
+
+        // app_name leaves audio out
+        let filter = SearchDeleteFilter {
+            app_name: Some("Zoom".to_string()),
+            ..filter
+        };
+        let tagging = db
+            .tag_search_results(&filter, "zoom", |_| {})
+            .await
+            .unwrap();
+        assert_eq!(tagging.matched, 3);
+        assert_eq!(tagging.tagged, 3);
+    }
+
+    #[tokio::test]
+    async fn test_tag_filter_any_all_none() {
+        let db = setup_test_db().await;
+        let _ = db
+            .insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+        for (text, tags) in [
+            ("alpha notes", vec!["project/alpha", "urgent"]),
+            ("beta notes", vec!["project/beta"]),
+            ("gamma notes", vec![]),
+        ] {
+            let frame_id = db
+                .insert_frame("test_device", None, None, None, None, true)
+                .await
+                .unwrap();
+            db.insert_ocr_text(frame_id, text, "", Arc::new(OcrEngine::Tesseract))
+                .await
+                .unwrap();
+            db.add_tags(
+                frame_id,
+                TagContentType::Vision,
+                tags.into_iter().map(String::from).collect(),
+            )
+            .await
+            .unwrap();
+        }
+        let audio_chunk_id = db.insert_audio_chunk("test_audio.mp4").await.unwrap();
+        db.insert_audio_transcription(
+            audio_chunk_id,
+            "beta call",
+            0,
+            "",
+            &AudioDevice {
+                name: "test".to_string(),
+                device_type: DeviceType::Input,
+            },
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        db.add_tags(
+            audio_chunk_id,
+            TagContentType::Audio,
+            vec!["project/beta".to_string(), "urgent".to_string()],
+        )
+        .await
+        .unwrap();
+        let ui_id: i64 = sqlx::query_scalar(
+            "INSERT INTO ui_monitoring (text_output, timestamp, app, window) VALUES (?1, ?2, 'app', 'window') RETURNING id",
+        )
+        .bind("alpha ui")
+        .bind(Utc::now())
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+        let alpha_id: i64 = sqlx::query_scalar("SELECT id FROM tags WHERE name = 'project/alpha'")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        db.add_tags_to_ui_monitoring(ui_id, &[alpha_id])
+            .await
+            .unwrap();
+
+        let search = |tags: TagFilter| {
+            let db = &db;
+            async move {
+                let results = db
+                    .search(
+                        "",
+                        ContentType::All,
+                        10,
+                        0,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        false,
+                        &tags,
+                    )
+                    .await
+                    .unwrap();
+                let count = db
+                    .count_search_results(
+                        "",
+                        ContentType::All,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        false,
+                        &tags,
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(count, results.len());
+                let mut texts: Vec<String> = results
+                    .into_iter()
+                    .map(|result| match result {
+                        SearchResult::OCR(ocr) => ocr.ocr_text,
+                        SearchResult::Audio(audio) => audio.transcription,
+                        SearchResult::UI(ui) => {
+                            assert_eq!(ui.tags, vec!["project/alpha"]);
+                            ui.text
+                        }
+                        SearchResult::Clipboard(clipboard) => clipboard.text,
+                    })
+                    .collect();
+                texts.sort();
+                texts
+            }
+        };
+        let tags = |tags: &[&str]| tags.iter().map(|tag| tag.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            search(TagFilter {
+                any: tags(&["project"]),
+                ..Default::default()
+            })
+            .await,
+            vec!["alpha notes", "alpha ui", "beta call", "beta notes"]
+        );
+        assert_eq!(
+            search(TagFilter {
+                all: tags(&["project", "urgent"]),
+                ..Default::default()
+            })
+            .await,
+            vec!["alpha notes", "beta call"]
+        );
+        assert_eq!(
+            search(TagFilter {
+                none: tags(&["urgent"]),
+                ..Default::default()
+            })
+            .await,
+            vec!["alpha ui", "beta notes", "gamma notes"]
+        );
+        assert_eq!(
+            search(TagFilter {
+                any: tags(&["project/alpha", "finance"]),
+                none: tags(&["urgent"]),
+                ..Default::default()
+            })
+            .await,
+            vec!["alpha ui"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_clipboard_search_and_delete() {
+        let db = setup_test_db().await;
+        let first = db
+            .insert_clipboard_entry("git rebase -i main", Some("Terminal"), Some("zsh"))
+            .await
+            .unwrap();
+        assert!(first.is_some());
+        // copying the same text again stores nothing
+        assert_eq!(
+            db.insert_clipboard_entry("git rebase -i main", Some("Terminal"), Some("zsh"))
+                .await
+                .unwrap(),
+            None
+        );
+        db.insert_clipboard_entry("quarterly rebase plan", Some("Notes"), None)
+            .await
+            .unwrap();
+
+        let search = |query: &'static str, content_type: ContentType, app: Option<&'static str>| {
+            let db = &db;
+            async move {
+                db.search(
+                    query,
+                    content_type,
+                    10,
+                    0,
+                    None,
+                    None,
+                    app,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    false,
+                    &TagFilter::default(),
+                )
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|result| match result {
+                    SearchResult::Clipboard(clipboard) => clipboard.text,
+                    _ => panic!("expected clipboard results"),
+                })
+                .collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(
+            search("rebase", ContentType::Clipboard, None).await,
+            vec!["quarterly rebase plan", "git rebase -i main"]
+        );
+        assert_eq!(
+            search("rebase", ContentType::All, Some("Terminal")).await,
+            vec!["git rebase -i main"]
+        );
+        assert_eq!(
+            db.count_search_results(
+                "rebase",
+                ContentType::All,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                false,
+                &TagFilter::default(),
+            )
+            .await
+            .unwrap(),
+            2
+        );
+        // the clipboard isn't tagged
+        let tagged = TagFilter {
+            any: vec!["work".to_string()],
+            ..Default::default()
+        };
+        assert!(db
+            .search_clipboard("rebase", None, None, None, None, 10, 0, &tagged)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let filter = SearchDeleteFilter {
+            q: Some("quarterly".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            db.delete_search_results(&filter, true)
+                .await
+                .unwrap()
+                .clipboard_entries,
+            1
+        );
+        assert_eq!(
+            db.delete_search_results(&filter, false)
+                .await
+                .unwrap()
+                .clipboard_entries,
+            1
+        );
+        assert_eq!(
+            search("rebase", ContentType::Clipboard, None).await,
+            vec!["git rebase -i main"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_clipboard_capture_blocklist() {
+        let db = setup_test_db().await;
+        for (kind, pattern) in [("app", "1password"), ("window", "online banking")] {
+            db.insert_capture_block_rule(&NewCaptureBlockRule {
+                kind: kind.to_string(),
+                pattern: pattern.to_string(),
+            })
+            .await
+            .unwrap();
+        }
+
+        assert_eq!(
+            db.insert_clipboard_entry("hunter2", Some("1Password 8"), Some("Vault"))
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            db.insert_clipboard_entry("DE89 3704", Some("Arc"), Some("Online Banking - Arc"))
+                .await
+                .unwrap(),
+            None
+        );
+        assert!(db
+            .insert_clipboard_entry("fn main()", Some("Code"), Some("main.rs"))
+            .await
+            .unwrap()
+            .is_some());
+
+        let texts: Vec<String> = sqlx::query_scalar("SELECT text FROM clipboard")
+            .fetch_all(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(texts, vec!["fn main()".to_string()]);
+        let indexed: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM clipboard_fts WHERE clipboard_fts MATCH 'hunter2'",
+        )
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+        assert_eq!(indexed, 0);
+    }
+
+    #[tokio::test]
+    async fn test_input_activity_report() {
+        let db = setup_test_db().await;
+        let minute = |minute: i64| {
+            chrono::DateTime::parse_from_rfc3339("2025-04-02T09:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc)
+                + chrono::Duration::minutes(minute)
+        };
+        let activity =
+            |minute_offset: i64, app: &str, keypresses: i64, clicks: i64| InputActivity {
+                timestamp: minute(minute_offset),
+                app_name: app.to_string(),
+                keypresses,
+                typing_bursts: (keypresses > 0) as i64,
+                clicks,
+                scrolls: 0,
+            };
+        db.insert_input_activity(&[
+            activity(0, "Code", 40, 0),
+            activity(0, "Slack", 5, 2),
+            activity(1, "Code", 10, 1),
+            activity(5, "Slack", 0, 3),
+        ])
+        .await
+        .unwrap();
+        // the same minute and app again adds up
+        db.insert_input_activity(&[activity(1, "Code", 10, 0)])
+            .await
+            .unwrap();
+
+        let report = db
+            .get_input_activity(minute(0), minute(10), None)
+            .await
+            .unwrap();
+        assert_eq!(report.active_minutes, 3);
+        assert_eq!(report.minutes.len(), 4);
+        assert_eq!(report.minutes[2], {
+            let mut code = activity(1, "Code", 20, 1);
+            code.typing_bursts = 2;
+            code
+        });
+        let apps: Vec<_> = report
+            .apps
+            .iter()
+            .map(|app| (app.app_name.as_str(), app.active_minutes, app.keypresses))
+            .collect();
+        assert_eq!(apps, vec![("Code", 2, 60), ("Slack", 2, 5)]);
+
+        let slack = db
+            .get_input_activity(minute(1), minute(10), Some("Slack"))
+            .await
+            .unwrap();
+        assert_eq!(slack.active_minutes, 1);
+        assert_eq!(slack.apps[0].clicks, 3);
+    }
+
+    #[tokio::test]
+    async fn test_browser_context_links_frames() {
+        let db = setup_test_db().await;
+        let engine = Arc::new(OcrEngine::Tesseract);
+        db.insert_video_chunk("a.mp4", "monitor").await.unwrap();
+        let shown_at = Utc::now();
+        let mut frame_ids = Vec::new();
+        for (seconds, app, window) in [
+            (-3, "Google Chrome", "Rust Book"),
+            (2, "Google Chrome", "Rust Book"),
+            (4, "Mail", "Inbox"),
+            // not a browser, or another tab
+            (5, "Preview", "Rust Book"),
+            (6, "Google Chrome", "Rust Book notes"),
+            (60, "Google Chrome", "Rust Book"),
+        ] {
+            let frame_id = db
+                .insert_frame(
+                    "monitor",
+                    Some(shown_at + chrono::Duration::seconds(seconds)),
+                    None,
+                    Some(app),
+                    Some(window),
+                    true,
+                )
+                .await
+                .unwrap();
+            db.insert_ocr_text(frame_id, "chapter four", "", engine.clone())
+                .await
+                .unwrap();
+            frame_ids.push(frame_id);
+        }
+
+        let id = db
+            .insert_browser_context(
+                shown_at,
+                "https://doc.rust-lang.org/book/ch04-01-what-is-ownership.html",
+                Some("Rust Book"),
+                Some("borrowing rules"),
+                Some("chrome"),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        let max_gap = chrono::Duration::seconds(10);
+        assert_eq!(db.link_browser_context(id, max_gap).await.unwrap(), 2);
+        // linking again finds nothing new
+        assert_eq!(db.link_browser_context(id, max_gap).await.unwrap(), 0);
+
+        let search = |query: &'static str| {
+            let db = &db;
+            async move {
+                db.search(
+                    query,
+                    ContentType::OCR,
+                    10,
+                    0,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    false,
+                    &TagFilter::default(),
+                )
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|result| match result {
+                    SearchResult::OCR(ocr) => ocr,
+                    _ => panic!("expected ocr results"),
+                })
+                .collect::<Vec<_>>()
+            }
+        };
+        // the selection is searched with the text of the frames showing it
+        let mut found = search("borrowing").await;
+        found.sort_by_key(|ocr| ocr.frame_id);
+        assert_eq!(
+            found.iter().map(|ocr| ocr.frame_id).collect::<Vec<_>>(),
+            vec![frame_ids[0], frame_ids[1]]
+        );
+        assert_eq!(found[0].browser_title.as_deref(), Some("Rust Book"));
+        assert_eq!(
+            found[0].browser_selection.as_deref(),
+            Some("borrowing rules")
+        );
+        assert_eq!(
+            found[0].browser_url.as_deref(),
+            Some("https://doc.rust-lang.org/book/ch04-01-what-is-ownership.html")
+        );
+        assert_eq!(search("chapter").await.len(), 6);
+        assert_eq!(
+            db.count_search_results(
+                "borrowing",
+                ContentType::OCR,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                false,
+                &TagFilter::default(),
+            )
+            .await
+            .unwrap(),
+            2
+        );
+
+        // a later tab takes the frames captured after it
+        let later = db
+            .insert_browser_context(
+                shown_at + chrono::Duration::seconds(1),
+                "https://doc.rust-lang.org/book/ch04-02-references-and-borrowing.html",
+                Some("Rust Book"),
+                None,
+                None,
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(db.link_browser_context(later, max_gap).await.unwrap(), 1);
+
+        db.insert_capture_block_rule(&NewCaptureBlockRule {
+            kind: "url".to_string(),
+            pattern: "bank.example".to_string(),
+        })
+        .await
+        .unwrap();
+        assert_eq!(
+            db.insert_browser_context(shown_at, "https://bank.example/accounts", None, None, None)
+                .await
+                .unwrap(),
+            None
+        );
+
+        // contexts go with the frames linking to them, the others once old
+        db.insert_browser_context(shown_at, "https://example.com", None, None, None)
+            .await
+            .unwrap();
+        db.delete_search_results(
+            &SearchDeleteFilter {
+                content_type: ContentType::OCR,
+                ..Default::default()
+            },
+            false,
+        )
+        .await
+        .unwrap();
+        let contexts = || async {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM browser_contexts")
+                .fetch_one(&db.pool)
+                .await
+                .unwrap()
+        };
+        assert_eq!(contexts().await, 1);
+        assert_eq!(
+            db.delete_unlinked_browser_contexts(shown_at + chrono::Duration::seconds(1), 100)
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(contexts().await, 0);
+    }
+
+    #[test]
+    fn test_diff_lines_round_trip() {
+        let old = ["title", "name: alice", "email: a@x.io", "save", "cancel"];
+        let new = ["title", "name: bob", "email: a@x.io", "phone: 123", "save"];
+        let edits = diff_lines(&old, &new);
+        assert_eq!(apply_line_edits(&old, &edits).unwrap(), new);
+        // edits made for other lines don't apply
+        assert_eq!(apply_line_edits(&["title"], &edits), None);
+
+        let empty: [&str; 0] = [];
+        assert_eq!(
+            apply_line_edits(&empty, &diff_lines(&empty, &new)).unwrap(),
+            new
+        );
+        assert!(diff_lines(&new, &new)
+            .iter()
+            .all(|edit| matches!(edit, screenpipe_db::LineEdit::Keep(_))));
+    }
+
+    #[tokio::test]
+    async fn test_ui_traversals_diffs_and_changes() {
+        let db = setup_test_db().await;
+        let start = Utc::now();
+        let form = |value: usize| {
+            let mut lines: Vec<String> = (0..30).map(|i| format!("label {}", i)).collect();
+            lines[10] = format!("amount: {}", value);
+            lines.join("\n")
+        };
+
+        let mut ids = Vec::new();
+        for value in 0..UI_KEYFRAME_INTERVAL + 5 {
+            let id = db
+                .insert_ui_traversal("Bank", "Transfer", Utc::now(), &form(value))
+                .await
+                .unwrap();
+            ids.push(id.unwrap());
+        }
+        // the same text again is not stored
+        assert_eq!(
+            db.insert_ui_traversal(
+                "Bank",
+                "Transfer",
+                Utc::now(),
+                &form(UI_KEYFRAME_INTERVAL + 4)
+            )
+            .await
+            .unwrap(),
+            None
+        );
+
+        let rows: Vec<(bool, String)> =
+            sqlx::query_as("SELECT keyframe, content FROM ui_traversals ORDER BY id")
+                .fetch_all(&db.pool)
+                .await
+                .unwrap();
+        let keyframes: Vec<usize> = rows
+            .iter()
+            .enumerate()
+            .filter(|(_, (keyframe, _))| *keyframe)
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(keyframes, vec![0, UI_KEYFRAME_INTERVAL]);
+        assert!(rows[1].1.len() < form(1).len() / 2);
+
+        let end = Utc::now();
+        let traversals = db
+            .get_ui_traversals(Some("Bank"), None, start, end, 100)
+            .await
+            .unwrap();
+        assert_eq!(traversals.len(), UI_KEYFRAME_INTERVAL + 5);
+        for (traversal, value) in traversals.iter().zip((0..UI_KEYFRAME_INTERVAL + 5).rev()) {
+            assert_eq!(traversal.text, form(value));
+        }
+        // a diff far from its keyframe is rebuilt on its own too
+        let latest = db
+            .get_ui_traversals(None, Some("Transfer"), start, end, 1)
+            .await
+            .unwrap();
+        assert_eq!(latest[0].id, *ids.last().unwrap());
+        assert_eq!(latest[0].text, form(UI_KEYFRAME_INTERVAL + 4));
+
+        let changes = db
+            .get_ui_changes(Some("Bank"), None, Some("AMOUNT: 7"), start, end, 10)
+            .await
+            .unwrap();
+        let changes: Vec<_> = changes
+            .iter()
+            .map(|change| (change.id, change.added.clone(), change.removed.clone()))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                (
+                    ids[8],
+                    vec!["amount: 8".to_string()],
+                    vec!["amount: 7".to_string()]
+                ),
+                (
+                    ids[7],
+                    vec!["amount: 7".to_string()],
+                    vec!["amount: 6".to_string()]
+                ),
+            ]
+        );
+        // the keyframe replacing a diff still tells what changed
+        let keyframe_change = db
+            .get_ui_changes(None, None, Some("amount: 19"), start, end, 1)
+            .await
+            .unwrap();
+        assert_eq!(keyframe_change[0].id, ids[UI_KEYFRAME_INTERVAL]);
+        assert_eq!(keyframe_change[0].removed, vec!["amount: 19".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_delete_search_results_in_ui_traversals() {
+        let db = setup_test_db().await;
+        let start = Utc::now();
+        let form = |value: usize| {
+            let mut lines: Vec<String> = (0..10).map(|i| format!("label {}", i)).collect();
+            lines[3] = format!("amount: {}", value);
+            if value == 2 {
+                lines.push("password hunter2".to_string());
+            }
+            lines.join("\n")
+        };
+        for value in 0..5 {
+            db.insert_ui_traversal("Bank", "Transfer", Utc::now(), &form(value))
+                .await
+                .unwrap();
+        }
+
+        let deletion = db
+            .delete_search_results(
+                &SearchDeleteFilter {
+                    q: Some("hunter2".to_string()),
+                    content_type: ContentType::UI,
+                    ..Default::default()
+                },
+                false,
+            )
+            .await
+            .unwrap();
+        assert_eq!(deletion.ui_traversals, 1);
+
+        // the traversals after the deleted one still rebuild
+        let texts: Vec<String> = db
+            .get_ui_traversals(None, None, start, Utc::now(), 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|traversal| traversal.text)
+            .collect();
+        assert_eq!(texts, vec![form(4), form(3), form(1), form(0)]);
+    }
+
+    #[tokio::test]
+    async fn test_ui_changes_matching_past_the_first_page() {
+        let db = setup_test_db().await;
+        let start = Utc::now();
+        for value in 0..1200 {
+            let text = match value {
+                3 => "needle".to_string(),
+                _ => format!("step {}", value),
+            };
+            db.insert_ui_traversal("Bank", "Transfer", Utc::now(), &text)
+                .await
+                .unwrap();
+        }
+        let changes = db
+            .get_ui_changes(None, None, Some("needle"), start, Utc::now(), 10)
+            .await
+            .unwrap();
+        let changes: Vec<_> = changes
+            .iter()
+            .map(|change| (change.added.clone(), change.removed.clone()))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                (vec!["step 4".to_string()], vec!["needle".to_string()]),
+                (vec!["needle".to_string()], vec!["step 2".to_string()]),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_by_browser_url_pattern() {
+        let db = setup_test_db().await;
+        let engine = Arc::new(OcrEngine::Tesseract);
+        db.insert_video_chunk("a.mp4", "monitor").await.unwrap();
+        for url in [
+            "https://docs.rs/tokio/latest/tokio/sync/index.html",
+            "https://www.docs.rs/serde",
+            "https://github.com/docs/rs",
+            "https://notdocs.rs/tokio",
+        ] {
+            let frame_id = db
+                .insert_frame("monitor", None, Some(url), Some("Firefox"), Some(""), true)
+                .await
+                .unwrap();
+            db.insert_ocr_text(frame_id, "reading the docs", "", engine.clone())
+                .await
+                .unwrap();
+        }
+
+        let search = |browser_url: &'static str| {
+            let db = &db;
+            async move {
+                let results = db
+                    .search(
+                        "docs",
+                        ContentType::All,
+                        10,
+                        0,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        Some(browser_url),
+                        None,
+                        None,
+                        false,
+                        &TagFilter::default(),
+                    )
+                    .await
+                    .unwrap();
+                let count = db
+                    .count_search_results(
+                        "docs",
+                        ContentType::All,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        None,
+                        Some(browser_url),
+                        None,
+                        None,
+                        false,
+                        &TagFilter::default(),
+                    )
+                    .await
+                    .unwrap();
+                let mut urls: Vec<String> = results
+                    .into_iter()
+                    .map(|result| match result {
+                        SearchResult::OCR(ocr) => ocr.browser_url.unwrap(),
+                        _ => panic!("expected ocr results"),
+                    })
+                    .collect();
+                assert_eq!(urls.len(), count);
+                urls.sort();
+                urls
+            }
+        };
+
+        assert_eq!(
+            search("docs.rs").await,
+            vec![
+                "https://docs.rs/tokio/latest/tokio/sync/index.html",
+                "https://www.docs.rs/serde"
+            ]
+        );
+        assert_eq!(
+            search("https://docs.rs/tokio/*/sync").await,
+            vec!["https://docs.rs/tokio/latest/tokio/sync/index.html"]
+        );
+        assert_eq!(
+            search("*/tok").await,
+            vec![
+                "https://docs.rs/tokio/latest/tokio/sync/index.html",
+                "https://notdocs.rs/tokio"
+            ]
+        );
+        // a word matches anywhere in the url
+        assert_eq!(search("github").await, vec!["https://github.com/docs/rs"]);
+
+        assert_eq!(
+            UrlPattern::parse("*.Rust-Lang.org/book/*"),
+            Some(UrlPattern::Site {
+                domain: Some("rust-lang.org".to_string()),
+                path: Some("/book/".to_string()),
+            })
+        );
+        assert_eq!(UrlPattern::parse(" * "), None);
+    }
+
+    #[tokio::test]
+    async fn test_search_text_positions_link_frame_regions() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+        let start = Utc::now() - chrono::Duration::seconds(10);
+        db.insert_frame(
+            "test_device",
+            Some(start),
+            None,
+            Some("app"),
+            Some("w"),
+            false,
+        )
+        .await
+        .unwrap();
+        let frame_id = db
+            .insert_frame(
+                "test_device",
+                Some(start + chrono::Duration::seconds(5)),
+                None,
+                Some("app"),
+                Some("w"),
+                false,
+            )
+            .await
+            .unwrap();
+
+        let block = |text: &str, left: &str, top: &str, width: &str, height: &str| {
+            serde_json::json!({
+                "block_num": "0", "conf": "90", "page_num": "1", "level": "5",
+                "par_num": "0", "word_num": "0", "line_num": "0", "text": text,
+                "left": left, "top": top, "width": width, "height": height,
+            })
+        };
+        // apple's engine measures from the bottom left corner
+        let text_json = serde_json::json!([block("invoice", "0.25", "0.75", "0.5", "0.125")]);
+        db.insert_ocr_text(
+            frame_id,
+            "invoice",
+            &text_json.to_string(),
+            Arc::new(OcrEngine::AppleNative),
+        )
+        .await
+        .unwrap();
+
+        let matches = db
+            .search_with_text_positions(
+                "invoice",
+                10,
+                0,
+                None,
+                None,
+                false,
+                Order::Descending,
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+        let link = matches[0].text_positions[0].link.as_ref().unwrap();
+        assert_eq!(link.frame_id, frame_id);
+        assert_eq!(link.offset_index, 1);
+        assert!((link.video_offset_secs - 5.0).abs() < 0.01);
+        assert_eq!(link.bounds.top, 0.125);
+        assert_eq!(
+            link.url,
+            format!(
+                "/frames/{}/region?left=0.25&top=0.125&width=0.5&height=0.125",
+                frame_id
+            )
+        );
+
+        // bounds in pixels can't be placed without the size of the image
+        let text_json = serde_json::json!([block("receipt", "120", "40", "300", "20")]);
+        db.insert_ocr_text(
+            frame_id,
+            "receipt",
+            &text_json.to_string(),
+            Arc::new(OcrEngine::Tesseract),
+        )
+        .await
+        .unwrap();
+        let matches = db
+            .search_with_text_positions(
+                "receipt",
+                10,
+                0,
+                None,
+                None,
+                false,
+                Order::Descending,
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(matches[0].text_positions[0].link.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_idle_intervals_left_out_of_focus_time() {
+        let db = setup_test_db().await;
+        let start = Utc::now() - chrono::Duration::hours(3);
+        let at = |minutes: i64| start + chrono::Duration::minutes(minutes);
+
+        db.insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+        for minutes in [0, 1, 60, 61] {
+            db.insert_frame(
+                "test_device",
+                Some(at(minutes)),
+                None,
+                Some("Code"),
+                None,
+                true,
+            )
+            .await
+            .unwrap();
+        }
+        // typing in the minute after the last frame, still there until its end
+        db.insert_input_activity(&[InputActivity {
+            timestamp: at(2),
+            app_name: "Code".to_string(),
+            keypresses: 10,
+            typing_bursts: 1,
+            clicks: 0,
+            scrolls: 0,
+        }])
+        .await
+        .unwrap();
+
+        // the last activity before the range starts the interval
+        let detected = db
+            .detect_idle_intervals(at(50), at(70), chrono::Duration::minutes(10))
+            .await
+            .unwrap();
+        assert_eq!(detected.len(), 1);
+        assert_eq!(
+            (detected[0].started_at, detected[0].ended_at),
+            (at(3), at(60))
+        );
+        // detecting again merges into the same interval
+        db.detect_idle_intervals(at(0), at(70), chrono::Duration::minutes(10))
+            .await
+            .unwrap();
+        let idle = db.get_idle_intervals(at(0), at(120)).await.unwrap();
+        assert_eq!(idle.len(), 1);
+        assert_eq!((idle[0].started_at, idle[0].ended_at), (at(3), at(60)));
+        assert!(db
+            .get_idle_intervals(at(61), at(120))
+            .await
+            .unwrap()
+            .is_empty());
+
+        // the editor kept the focus over lunch
+        db.record_focus(Some(("Code", "main.rs")), at(0))
+            .await
+            .unwrap();
+        db.record_focus(Some(("Code", "main.rs")), at(61))
+            .await
+            .unwrap();
+        db.record_focus(None, at(61)).await.unwrap();
+        let focus = db.get_focus_time(at(0), at(120), None).await.unwrap();
+        assert_eq!(focus.len(), 1);
+        assert!((focus[0].focused_secs - 4.0 * 60.0).abs() < 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_detect_meetings() {
+        use chrono::DurationRound;
+
+        let db = setup_test_db().await;
+        let start = (Utc::now() - chrono::Duration::hours(5))
+            .duration_trunc(chrono::Duration::minutes(1))
+            .unwrap();
+        let at = |minutes: i64| start + chrono::Duration::minutes(minutes);
+
+        db.insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+        // a call on screen for nine minutes, and a glimpse at another later
+        for minutes in (0..9).chain([120]) {
+            db.insert_frame(
+                "test_device",
+                Some(at(minutes)),
+                None,
+                Some("zoom.us"),
+                Some("Zoom Meeting"),
+                true,
+            )
+            .await
+            .unwrap();
+        }
+        db.insert_calendar_hint(&NewCalendarHint {
+            title: "Design review".to_string(),
+            start_time: at(-5),
+            end_time: at(30),
+            attendees: vec!["Carol".to_string(), " ".to_string()],
+        })
+        .await
+        .unwrap();
+
+        // two voices talking for seven minutes, then one alone
+        let alice = db.insert_speaker(&[0.1; 512]).await.unwrap();
+        db.update_speaker_name(alice.id, "Alice").await.unwrap();
+        let bob = db.insert_speaker(&[0.2; 512]).await.unwrap();
+        db.update_speaker_name(bob.id, "Bob").await.unwrap();
+        let audio_chunk_id = db.insert_audio_chunk("call.mp4").await.unwrap();
+        let talk = |minutes: i64, speaker_id: i64, device_type: DeviceType| {
+            let db = &db;
+            async move {
+                let id = db
+                    .insert_audio_transcription(
+                        audio_chunk_id,
+                        &format!("said at {}", minutes),
+                        minutes,
+                        "",
+                        &AudioDevice {
+                            name: "device".to_string(),
+                            device_type,
+                        },
+                        Some(speaker_id),
+                        None,
+                        None,
+                    )
+                    .await
+                    .unwrap();
+                sqlx::query("UPDATE audio_transcriptions SET timestamp = ?1 WHERE id = ?2")
+                    .bind(at(minutes))
+                    .bind(id)
+                    .execute(&db.pool)
+                    .await
+                    .unwrap();
+            }
+        };
+        for minutes in 60..67 {
+            if minutes % 2 == 0 {
+                talk(minutes, alice.id, DeviceType::Input).await;
+            } else {
+                talk(minutes, bob.id, DeviceType::Output).await;
+            }
+        }
+        for minutes in 180..190 {
+            talk(minutes, alice.id, DeviceType::Input).await;
+        }
+
+        let detected = db.detect_meetings(at(-60), at(240)).await.unwrap();
+        assert_eq!(detected.len(), 2);
+        let call = &detected[0];
+        assert_eq!(call.source, "detected");
+        assert_eq!(call.meeting_app, "zoom.us");
+        assert_eq!(call.title.as_deref(), Some("Design review"));
+        assert_eq!((call.start_time, call.end_time), (at(0), Some(at(9))));
+        let participants = db.get_meeting_participants(call.id).await.unwrap();
+        assert_eq!(participants.len(), 1);
+        assert_eq!(participants[0].name, "Carol");
+
+        let conversation = &detected[1];
+        assert_eq!(conversation.meeting_app, "");
+        assert_eq!(conversation.title, None);
+        assert_eq!(
+            (conversation.start_time, conversation.end_time),
+            (at(60), Some(at(67)))
+        );
+        let participants = db.get_meeting_participants(conversation.id).await.unwrap();
+        let names: Vec<(&str, bool, Option<i64>)> = participants
+            .iter()
+            .map(|p| (p.name.as_str(), p.is_local, p.speaker_id))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("Alice", true, Some(alice.id)),
+                ("Bob", false, Some(bob.id))
+            ]
+        );
+        let transcript = db.get_meeting_transcript(conversation.id).await.unwrap();
+        assert_eq!(transcript.len(), 7);
+        assert_eq!(transcript[0].speaker_name.as_deref(), Some("Alice"));
+        assert!(db.get_meeting_transcript(call.id).await.unwrap().is_empty());
+
+        // running again finds the same meetings, a call going on extends
+        for minutes in 10..13 {
+            db.insert_frame(
+                "test_device",
+                Some(at(minutes)),
+                None,
+                Some("zoom.us"),
+                Some("Zoom Meeting"),
+                true,
+            )
+            .await
+            .unwrap();
+        }
+        let detected = db.detect_meetings(at(5), at(30)).await.unwrap();
+        assert_eq!(detected.len(), 1);
+        assert_eq!(detected[0].id, call.id);
+        assert_eq!(
+            (detected[0].start_time, detected[0].end_time),
+            (at(0), Some(at(13)))
+        );
+        db.detect_meetings(at(-60), at(240)).await.unwrap();
+        assert_eq!(db.list_meetings(None, None, 10, 0).await.unwrap().len(), 2);
+
+        // a live meeting only takes the title of the hint
+        let live_id = db.start_meeting("teams", at(118)).await.unwrap();
+        db.end_meetings(at(125)).await.unwrap();
+        db.insert_calendar_hint(&NewCalendarHint {
+            title: "1:1".to_string(),
+            start_time: at(115),
+            end_time: at(130),
+            attendees: vec![],
+        })
+        .await
+        .unwrap();
+        let detected = db.detect_meetings(at(100), at(140)).await.unwrap();
+        assert_eq!(detected.len(), 1);
+        assert_eq!(detected[0].id, live_id);
+        assert_eq!(detected[0].source, "live");
+        assert_eq!(detected[0].title.as_deref(), Some("1:1"));
+        assert_eq!(
+            (detected[0].start_time, detected[0].end_time),
+            (at(118), Some(at(125)))
+        );
+
+        let hints = db.list_calendar_hints(at(0), at(240)).await.unwrap();
+        assert_eq!(hints.len(), 2);
+        assert_eq!(hints[0].attendees, vec!["Carol"]);
+        assert!(db.delete_calendar_hint(hints[0].id).await.unwrap());
+        assert!(!db.delete_calendar_hint(hints[0].id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_timeline_gaps_with_reasons() {
+        use chrono::DurationRound;
+
+        let db = setup_test_db().await;
+        let start = (Utc::now() - chrono::Duration::hours(3))
+            .duration_trunc(chrono::Duration::minutes(1))
+            .unwrap();
+        let at = |minutes: i64| start + chrono::Duration::minutes(minutes);
+
+        db.insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+        for minutes in [0, 1, 2, 30, 31, 60] {
+            db.insert_frame(
+                "test_device",
+                Some(at(minutes)),
+                None,
+                Some("Code"),
+                None,
+                true,
+            )
+            .await
+            .unwrap();
+        }
+        let audio_chunk_id = db.insert_audio_chunk("audio.mp4").await.unwrap();
+        let id = db
+            .insert_audio_transcription(
+                audio_chunk_id,
+                "hello",
+                0,
+                "",
+                &AudioDevice {
+                    name: "mic".to_string(),
+                    device_type: DeviceType::Input,
+                },
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        sqlx::query("UPDATE audio_transcriptions SET timestamp = ?1 WHERE id = ?2")
+            .bind(at(3))
+            .bind(id)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        db.start_capture_pause("all", at(4), None, None)
+            .await
+            .unwrap();
+        db.end_capture_pauses("all", at(29)).await.unwrap();
+        db.detect_idle_intervals(at(-10), at(100), chrono::Duration::minutes(10))
+            .await
+            .unwrap();
+
+        let timeline = db
+            .get_timeline(at(-10), at(100), chrono::Duration::minutes(2))
+            .await
+            .unwrap();
+        let segments: Vec<_> = timeline
+            .segments
+            .iter()
+            .map(|s| (s.start, s.end, s.frames, s.transcriptions))
+            .collect();
+        assert_eq!(
+            segments,
+            vec![
+                (at(0), at(3), 3, 1),
+                (at(30), at(31), 2, 0),
+                (at(60), at(60), 1, 0),
+            ]
+        );
+        assert_eq!(timeline.segments[0].frames_per_minute, 1.0);
+        assert_eq!(timeline.segments[2].frames_per_minute, 1.0);
+        let gaps: Vec<_> = timeline
+            .gaps
+            .iter()
+            .map(|g| (g.start, g.end, g.reason.as_str()))
+            .collect();
+        assert_eq!(
+            gaps,
+            vec![
+                (at(-10), at(0), "not_recording"),
+                (at(3), at(30), "paused"),
+                (at(31), at(60), "idle"),
+                (at(60), at(100), "not_recording"),
+            ]
+        );
+        assert_eq!(timeline.gaps[1].duration_secs, 27.0 * 60.0);
+
+        // nothing recorded at all is one gap
+        let empty = db
+            .get_timeline(at(-100), at(-50), chrono::Duration::minutes(2))
+            .await
+            .unwrap();
+        assert!(empty.segments.is_empty());
+        assert_eq!(empty.gaps.len(), 1);
+        assert_eq!(empty.gaps[0].reason, "not_recording");
+    }
+
+    #[tokio::test]
+    async fn test_app_category_rules() {
+        let db = setup_test_db().await;
+        let rule = |kind: &str, pattern: &str, category: &str| NewAppCategoryRule {
+            kind: kind.to_string(),
+            pattern: pattern.to_string(),
+            category: category.to_string(),
+        };
+        db.set_config_app_category_rules(&[
+            rule("app", "Code", "deep_work"),
+            rule("app", "Code", "distraction"),
+            rule("url", " ", "distraction"),
+        ])
+        .await
+        .unwrap();
+        let slack = db
+            .insert_app_category_rule(&rule("app", "Slack", "deep_work"))
+            .await
+            .unwrap();
+        // added again, its category changes
+        let again = db
+            .insert_app_category_rule(&rule("app", " Slack", "communication"))
+            .await
+            .unwrap();
+        assert_eq!(again.id, slack.id);
+
+        let rules = db.list_app_category_rules().await.unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(
+            (rules[0].category.as_str(), rules[0].source.as_str()),
+            ("distraction", "config")
+        );
+        assert_eq!(rules[1].category, "communication");
+
+        // the ones of the command line stay
+        assert!(!db.delete_app_category_rule(rules[0].id).await.unwrap());
+        assert!(db.delete_app_category_rule(slack.id).await.unwrap());
+        db.set_config_app_category_rules(&[]).await.unwrap();
+        assert!(db.list_app_category_rules().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_work_sessions() {
+        use chrono::DurationRound;
+
+        let db = setup_test_db().await;
+        let start = (Utc::now() - chrono::Duration::hours(3))
+            .duration_trunc(chrono::Duration::minutes(1))
+            .unwrap();
+        let at = |minutes: i64| start + chrono::Duration::minutes(minutes);
+
+        db.insert_video_chunk("test_video.mp4", "test_device")
+            .await
+            .unwrap();
+        // a spreadsheet, a break, then chat until a switch to the browser
+        let frames = (0..=20)
+            .step_by(2)
+            .map(|minutes| (minutes, "Numbers", "Budget forecast"))
+            .chain(
+                (50..=70)
+                    .step_by(2)
+                    .map(|minutes| (minutes, "Slack", "general")),
+            )
+            .chain(
+                (72..=100)
+                    .step_by(2)
+                    .map(|minutes| (minutes, "Arc", "Hacker News")),
+            );
+        for (minutes, app, window) in frames {
+            db.insert_frame(
+                "test_device",
+                Some(at(minutes)),
+                None,
+                Some(app),
+                Some(window),
+                true,
+            )
+            .await
+            .unwrap();
+        }
+        let audio_chunk_id = db.insert_audio_chunk("audio.mp4").await.unwrap();
+        let id = db
+            .insert_audio_transcription(
+                audio_chunk_id,
+                "let's go over the forecast",
+                0,
+                "",
+                &AudioDevice {
+                    name: "mic".to_string(),
+                    device_type: DeviceType::Input,
+                },
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        sqlx::query("UPDATE audio_transcriptions SET timestamp = ?1 WHERE id = ?2")
+            .bind(at(11))
+            .bind(id)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+
+        let break_length = chrono::Duration::minutes(15);
+        let first = db
+            .detect_work_sessions(at(-5), at(60), break_length)
+            .await
+            .unwrap();
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[0].dominant_app.as_deref(), Some("Numbers"));
+        assert_eq!(first[0].topic.as_deref(), Some("budget forecast"));
+        assert_eq!((first[0].frames, first[0].transcriptions), (11, 1));
+        assert_eq!(first[1].ended_at, at(58));
+
+        // the chat going on is detected again from its beginning
+        let second = db
+            .detect_work_sessions(at(55), at(120), break_length)
+            .await
+            .unwrap();
+        assert_eq!(second.len(), 2);
+        assert_eq!((second[0].started_at, second[0].ended_at), (at(50), at(70)));
+        assert_eq!(second[0].apps, vec!["Slack"]);
+        assert_eq!(
+            (second[1].started_at, second[1].ended_at),
+            (at(72), at(100))
+        );
+
+        let sessions = db.get_work_sessions(at(0), at(120)).await.unwrap();
+        assert_eq!(sessions.len(), 3);
+        assert_eq!(sessions[0], first[0]);
+        assert_eq!(
+            db.get_work_session(second[1].id).await.unwrap(),
+            Some(second[1].clone())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_ocr_failures_by_device() {
+        let db = setup_test_db().await;
+        let start = Utc::now() - chrono::Duration::hours(1);
+        let at = |minutes: i64| start + chrono::Duration::minutes(minutes);
+
+        db.insert_video_chunk("video_1.mp4", "monitor_1")
+            .await
+            .unwrap();
+        db.insert_video_chunk("video_2.mp4", "monitor_2")
+            .await
+            .unwrap();
+        for (device, minutes, text) in [
+            ("monitor_1", 1, Some("hello")),
+            // how recording stores a frame its OCR failed on
+            ("monitor_1", 2, Some("")),
+            ("monitor_1", 3, None),
+            ("monitor_1", 4, Some(" \n")),
+            ("monitor_2", 1, Some("world")),
+            // out of the range
+            ("monitor_2", 30, None),
+        ] {
+            let frame_id = db
+                .insert_frame(device, Some(at(minutes)), None, Some("Code"), None, true)
+                .await
+                .unwrap();
+            if let Some(text) = text {
+                db.insert_ocr_text(frame_id, text, "", Arc::new(OcrEngine::Tesseract))
+                    .await
+                    .unwrap();
+            }
+        }
+
+        let failures = db.get_ocr_failures_by_device(at(0), at(10)).await.unwrap();
+        let counts: Vec<(&str, i64, i64)> = failures
+            .iter()
+            .map(|device| {
+                (
+                    device.device_name.as_str(),
+                    device.frames,
+                    device.without_text,
+                )
+            })
+            .collect();
+        assert_eq!(counts, vec![("monitor_1", 4, 3), ("monitor_2", 1, 0)]);
+    }
+
+    #[tokio::test]
+    async fn test_merge_database() {
+        let path = |name: &str| {
+            std::env::temp_dir().join(format!(
+                "screenpipe-merge-{}-{}.sqlite",
+                name,
+                std::process::id()
+            ))
+        };
+        let (laptop_path, desktop_path) = (path("laptop"), path("desktop"));
+        let laptop = DatabaseManager::new(&laptop_path.to_string_lossy())
+            .await
+            .unwrap();
+        let desktop = DatabaseManager::new(&desktop_path.to_string_lossy())
+            .await
+            .unwrap();
+        let voice = |i: usize| {
+            let mut embedding = vec![0.0f32; 512];
+            embedding[i] = 1.0;
+            embedding
+        };
+        let device = AudioDevice {
+            name: "microphone".to_string(),
+            device_type: DeviceType::Input,
+        };
+
+        laptop
+            .insert_video_chunk("laptop.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let laptop_frame = laptop
+            .insert_frame("monitor_1", None, None, Some("Code"), None, true)
+            .await
+            .unwrap();
+        laptop
+            .insert_ocr_text(
+                laptop_frame,
+                "laptop notes",
+                "",
+                Arc::new(OcrEngine::Tesseract),
+            )
+            .await
+            .unwrap();
+        laptop
+            .add_tags(
+                laptop_frame,
+                TagContentType::Vision,
+                vec!["work".to_string()],
+            )
+            .await
+            .unwrap();
+        let laptop_speaker = laptop.insert_speaker(&voice(0)).await.unwrap();
+
+        desktop
+            .insert_video_chunk("desktop.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let desktop_frame = desktop
+            .insert_frame("monitor_1", None, None, Some("Figma"), None, true)
+            .await
+            .unwrap();
+        desktop
+            .insert_ocr_text(
+                desktop_frame,
+                "desktop mockups",
+                "",
+                Arc::new(OcrEngine::Tesseract),
+            )
+            .await
+            .unwrap();
+        desktop
+            .add_tags(
+                desktop_frame,
+                TagContentType::Vision,
+                vec!["work".to_string(), "design".to_string()],
+            )
+            .await
+            .unwrap();
+        // the same voice as on the laptop, and one it never heard
+        let alice = desktop.insert_speaker(&voice(0)).await.unwrap();
+        desktop
+            .update_speaker_name(alice.id, "alice")
+            .await
+            .unwrap();
+        let bob = desktop.insert_speaker(&voice(1)).await.unwrap();
+        let audio_chunk = desktop.insert_audio_chunk("desktop.mp4.wav").await.unwrap();
+        for (offset, speaker) in [(0, alice.id), (1, bob.id)] {
+            desktop
+                .insert_audio_transcription(
+                    audio_chunk,
+                    "the desktop standup",
+                    offset,
+                    "",
+                    &device,
+                    Some(speaker),
+                    None,
+                    None,
+                )
+                .await
+                .unwrap();
+        }
+
+        let report = laptop
+            .merge_database(&desktop_path, "desktop")
+            .await
+            .unwrap();
+        assert_eq!(report.video_chunks, 1);
+        assert_eq!(report.frames, 1);
+        assert_eq!(report.audio_chunks, 1);
+        assert_eq!(report.audio_transcriptions, 2);
+        assert_eq!(report.matched_speakers, 1);
+        assert_eq!(report.added_speakers, 1);
+        assert_eq!(report.added_tags, 1);
+
+        let results = laptop
+            .search(
+                "mockups",
+                ContentType::OCR,
+                10,
+                0,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                false,
+                &TagFilter::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+
+        let (frame_id, source_device): (i64, Option<String>) =
+            sqlx::query_as("SELECT id, source_device FROM frames WHERE app_name = 'Figma'")
+                .fetch_one(&laptop.pool)
+                .await
+                .unwrap();
+        assert!(frame_id > laptop_frame);
+        assert_eq!(source_device.as_deref(), Some("desktop"));
+        let mut tags: Vec<String> = sqlx::query_scalar(
+            "SELECT t.name FROM vision_tags vt JOIN tags t ON t.id = vt.tag_id WHERE vt.vision_id = ?1",
+        )
+        .bind(frame_id)
+        .fetch_all(&laptop.pool)
+        .await
+        .unwrap();
+        tags.sort();
+        assert_eq!(tags, vec!["design", "work"]);
+
+        // alice is the speaker the laptop heard, bob a new one
+        let speakers: Vec<(Option<i64>, Option<String>)> = sqlx::query_as(
+            r#"
+            SELECT at.speaker_id, s.name FROM audio_transcriptions at
+            LEFT JOIN speakers s ON s.id = at.speaker_id
+            ORDER BY at.offset_index
+            "#,
+        )
+        .fetch_all(&laptop.pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            speakers[0],
+            (Some(laptop_speaker.id), Some("alice".to_string()))
+        );
+        assert_ne!(speakers[1].0, Some(laptop_speaker.id));
+        assert!(speakers[1].0.is_some());
+
+        // merging again adds nothing
+        let again = laptop
+            .merge_database(&desktop_path, "desktop")
+            .await
+            .unwrap();
+        assert_eq!(again.frames, 0);
+        assert_eq!(again.audio_transcriptions, 0);
+
+        // the desktop database is left as it was
+        let desktop_frames: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM frames")
+            .fetch_one(&desktop.pool)
+            .await
+            .unwrap();
+        assert_eq!(desktop_frames, 1);
+
+        for path in [laptop_path, desktop_path] {
+            for suffix in ["", "-wal", "-shm"] {
+                let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sync_delta() {
+        let path = |name: &str| {
+            let path = std::env::temp_dir().join(format!(
+                "screenpipe-sync-{}-{}.sqlite",
+                name,
+                std::process::id()
+            ));
+            let _ = std::fs::remove_file(&path);
+            path
+        };
+        let (source_path, snapshot_path, delta_path) =
+            (path("source"), path("snapshot"), path("delta"));
+        let source = DatabaseManager::new(&source_path.to_string_lossy())
+            .await
+            .unwrap();
+        source
+            .insert_video_chunk("chunk.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let first = source
+            .insert_frame("monitor_1", None, None, Some("Code"), None, true)
+            .await
+            .unwrap();
+        source
+            .insert_ocr_text(
+                first,
+                "before the snapshot",
+                "",
+                Arc::new(OcrEngine::Tesseract),
+            )
+            .await
+            .unwrap();
+
+        let snapshot = source.create_sync_snapshot(&snapshot_path).await.unwrap();
+        assert_eq!(snapshot["frames"], first);
+        source
+            .insert_sync_run("snapshot", "db/1-snapshot.sqlite.enc", &snapshot, 0, 100)
+            .await
+            .unwrap();
+
+        let second = source
+            .insert_frame("monitor_1", None, None, Some("Code"), None, true)
+            .await
+            .unwrap();
+        source
+            .insert_ocr_text(
+                second,
+                "after the snapshot",
+                "",
+                Arc::new(OcrEngine::Tesseract),
+            )
+            .await
+            .unwrap();
+        let (delta, rows) = source
+            .create_sync_delta(&delta_path, &snapshot)
+            .await
+            .unwrap();
+        assert_eq!(delta["frames"], second);
+        assert_eq!(rows, 2);
+        let run = source
+            .insert_sync_run("delta", "db/2-delta.sqlite.enc", &delta, rows, 50)
+            .await
+            .unwrap();
+        let runs = source.list_sync_runs(10).await.unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0], run);
+        assert_eq!(runs[0].watermarks, delta);
+
+        // nothing was added since
+        let empty_path = path("empty");
+        let (_, rows) = source.create_sync_delta(&empty_path, &delta).await.unwrap();
+        assert_eq!(rows, 0);
+
+        let restored = DatabaseManager::new(&snapshot_path.to_string_lossy())
+            .await
+            .unwrap();
+        assert_eq!(restored.apply_sync_delta(&delta_path).await.unwrap(), 2);
+        let results = restored
+            .search(
+                "after",
+                ContentType::OCR,
+                10,
+                0,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                false,
+                &TagFilter::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+
+        assert_eq!(
+            restored
+                .relocate_media(std::path::Path::new("/restored"))
+                .await
+                .unwrap(),
+            1
+        );
+        let names: Vec<String> = sqlx::query_scalar("SELECT name FROM frames")
+            .fetch_all(&restored.pool)
+            .await
+            .unwrap();
+        assert!(names.iter().all(|name| name == "/restored/chunk.mp4"));
+    }
+
+    #[tokio::test]
+    async fn test_sync_deletions() {
+        let db = setup_test_db().await;
+        assert!(!db.take_sync_snapshot_needed().await.unwrap());
+
+        let old = db
+            .insert_video_chunk("/data/old.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let frame_id = db
+            .insert_frame("monitor_1", None, None, Some("Code"), None, true)
+            .await
+            .unwrap();
+        db.insert_ocr_text(frame_id, "hello", "", Arc::new(OcrEngine::Tesseract))
+            .await
+            .unwrap();
+        db.insert_video_chunk("/data/new.mp4", "monitor_1")
+            .await
+            .unwrap();
+        // adding rows is left to deltas
+        assert!(!db.take_sync_snapshot_needed().await.unwrap());
+
+        // masked text is only in a snapshot
+        sqlx::query("UPDATE ocr_text SET text = 'masked' WHERE frame_id = ?1")
+            .bind(frame_id)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        assert!(db.take_sync_snapshot_needed().await.unwrap());
+        assert!(!db.take_sync_snapshot_needed().await.unwrap());
+        db.request_sync_snapshot().await.unwrap();
+        assert!(db.take_sync_snapshot_needed().await.unwrap());
+
+        // a chunk changed in place is uploaded again
+        db.mark_chunk_synced(MediaChunkKind::Video, old)
+            .await
+            .unwrap();
+        assert!(db.list_unsynced_video_chunks(10).await.unwrap().is_empty());
+        db.reset_video_chunk_sync("/data/old.mp4").await.unwrap();
+        assert_eq!(
+            db.list_unsynced_video_chunks(10).await.unwrap(),
+            vec![(old, "/data/old.mp4".to_string())]
+        );
+
+        // only deleted chunks that were uploaded are deleted from the bucket
+        db.mark_chunk_synced(MediaChunkKind::Video, old)
+            .await
+            .unwrap();
+        let unsynced = db.insert_audio_chunk("/data/unsynced.wav").await.unwrap();
+        sqlx::query("DELETE FROM frames WHERE id = ?1")
+            .bind(frame_id)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        for (table, id) in [("video_chunks", old), ("audio_chunks", unsynced)] {
+            sqlx::query(&format!("DELETE FROM {} WHERE id = ?1", table))
+                .bind(id)
+                .execute(&db.pool)
+                .await
+                .unwrap();
+        }
+        assert!(db.take_sync_snapshot_needed().await.unwrap());
+        assert_eq!(
+            db.list_deleted_sync_media(10).await.unwrap(),
+            vec!["/data/old.mp4".to_string()]
+        );
+        db.forget_deleted_sync_media("/data/old.mp4").await.unwrap();
+        assert!(db.list_deleted_sync_media(10).await.unwrap().is_empty());
+
+        for kind in ["snapshot", "delta", "snapshot"] {
+            db.insert_sync_run(kind, kind, &SyncWatermarks::new(), 0, 0)
+                .await
+                .unwrap();
+        }
+        let newest = db.list_sync_runs(10).await.unwrap()[0].id;
+        assert_eq!(db.delete_sync_runs_before(newest).await.unwrap(), 2);
+        assert_eq!(db.list_sync_runs(10).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_device_registry() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("chunk.mp4", "monitor_1").await.unwrap();
+        let audio_chunk_id = db.insert_audio_chunk("audio.mp4").await.unwrap();
+        db.insert_audio_transcription(
+            audio_chunk_id,
+            "hello",
+            0,
+            "",
+            &AudioDevice {
+                name: "mic".to_string(),
+                device_type: DeviceType::Input,
+            },
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+
+        // capture registers the devices it writes rows for
+        let devices = db.list_devices().await.unwrap();
+        let kinds: Vec<(&str, &str)> = devices
+            .iter()
+            .map(|device| (device.kind.as_str(), device.name.as_str()))
+            .collect();
+        assert_eq!(kinds, vec![("audio_input", "mic"), ("monitor", "monitor_1")]);
+        let monitor = devices[1].clone();
+        let device_ids: Vec<Option<i64>> = sqlx::query_scalar(
+            "SELECT device_id FROM video_chunks UNION ALL SELECT device_id FROM audio_transcriptions",
+        )
+        .fetch_all(&db.pool)
+        .await
+        .unwrap();
+        assert_eq!(device_ids, vec![Some(monitor.id), Some(devices[0].id)]);
+
+        let registered = db.register_device("monitor", "monitor_1").await.unwrap();
+        assert_eq!(registered.id, monitor.id);
+        assert!(registered.enabled);
+
+        let updated = db
+            .update_device(monitor.id, false, Some(0.5), Some("tesseract"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!updated.enabled);
+        assert_eq!(updated.fps, Some(0.5));
+        assert_eq!(updated.engine.as_deref(), Some("tesseract"));
+        // registering again keeps the settings
+        let registered = db.register_device("monitor", "monitor_1").await.unwrap();
+        assert!(!registered.enabled);
+        assert!(db
+            .update_device(1000, true, None, None)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_row_uids() {
+        let path = |name: &str| {
+            std::env::temp_dir().join(format!(
+                "screenpipe-uids-{}-{}.sqlite",
+                name,
+                std::process::id()
+            ))
+        };
+        let (laptop_path, desktop_path) = (path("laptop"), path("desktop"));
+        let laptop = DatabaseManager::new(&laptop_path.to_string_lossy())
+            .await
+            .unwrap();
+        let desktop = DatabaseManager::new(&desktop_path.to_string_lossy())
+            .await
+            .unwrap();
+        let mut voice = vec![0.0f32; 512];
+        voice[3] = 1.0;
+
+        for (db, file) in [(&laptop, "laptop.mp4"), (&desktop, "desktop.mp4")] {
+            db.insert_video_chunk(file, "monitor_1").await.unwrap();
+            db.insert_frame("monitor_1", None, None, Some("Code"), None, true)
+                .await
+                .unwrap();
+        }
+        let speaker = desktop.insert_speaker(&voice).await.unwrap();
+        let audio_chunk_id = desktop.insert_audio_chunk("desktop.wav").await.unwrap();
+        desktop
+            .insert_audio_transcription(
+                audio_chunk_id,
+                "standup notes",
+                0,
+                "",
+                &AudioDevice {
+                    name: "microphone".to_string(),
+                    device_type: DeviceType::Input,
+                },
+                Some(speaker.id),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let (frame_uid, chunk_uid): (String, String) =
+            sqlx::query_as("SELECT uid, video_chunk_uid FROM frames")
+                .fetch_one(&desktop.pool)
+                .await
+                .unwrap();
+        // UUIDv7
+        assert_eq!(frame_uid.len(), 36);
+        assert_eq!(&frame_uid[14..15], "7");
+        let video_chunk_uid: String = sqlx::query_scalar("SELECT uid FROM video_chunks")
+            .fetch_one(&desktop.pool)
+            .await
+            .unwrap();
+        assert_eq!(chunk_uid, video_chunk_uid);
+
+        // set by the INSERTs, no trigger writes them afterwards
+        let triggers: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'trigger' AND name IN ('video_chunks_uid', 'frames_uid', 'audio_chunks_uid', 'audio_transcriptions_uid', 'speakers_uid')",
+        )
+        .fetch_one(&desktop.pool)
+        .await
+        .unwrap();
+        assert_eq!(triggers, 0);
+        let transcription: (String, String, String) = sqlx::query_as(
+            "SELECT t.uid, t.audio_chunk_uid, t.speaker_uid FROM audio_transcriptions t",
+        )
+        .fetch_one(&desktop.pool)
+        .await
+        .unwrap();
+        let audio_chunk_uid: String = sqlx::query_scalar("SELECT uid FROM audio_chunks")
+            .fetch_one(&desktop.pool)
+            .await
+            .unwrap();
+        let speaker_uid: String = sqlx::query_scalar("SELECT uid FROM speakers")
+            .fetch_one(&desktop.pool)
+            .await
+            .unwrap();
+        assert_eq!(&transcription.0[14..15], "7");
+        assert_eq!(
+            (&transcription.1, &transcription.2),
+            (&audio_chunk_uid, &speaker_uid)
+        );
+
+        // merged rows keep their uid
+        laptop
+            .merge_database(&desktop_path, "desktop")
+            .await
+            .unwrap();
+        let merged: (String, String) = sqlx::query_as(
+            "SELECT uid, video_chunk_uid FROM frames WHERE source_device = 'desktop'",
+        )
+        .fetch_one(&laptop.pool)
+        .await
+        .unwrap();
+        assert_eq!(merged, (frame_uid, chunk_uid));
+        let merged: (String, String, String) = sqlx::query_as(
+            "SELECT uid, audio_chunk_uid, speaker_uid FROM audio_transcriptions WHERE source_device = 'desktop'",
+        )
+        .fetch_one(&laptop.pool)
+        .await
+        .unwrap();
+        assert_eq!(merged, transcription);
+
+        // merging back only adds the rows of the laptop
+        let report = desktop
+            .merge_database(&laptop_path, "laptop")
+            .await
+            .unwrap();
+        assert_eq!(report.frames, 1);
+        assert_eq!(report.matched_speakers, 1);
+        assert_eq!(report.added_speakers, 0);
+        let frames: i64 = sqlx::query_scalar("SELECT COUNT(DISTINCT uid) FROM frames")
+            .fetch_one(&desktop.pool)
+            .await
+            .unwrap();
+        assert_eq!(frames, 2);
+    }
+
+    #[tokio::test]
+    async fn test_video_chunk_compaction() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("/data/old.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let first = db
+            .insert_frame("monitor_1", None, None, Some("Code"), None, true)
+            .await
+            .unwrap();
+        let last = db
+            .insert_frame("monitor_1", None, None, Some("Code"), None, true)
+            .await
+            .unwrap();
+
+        let later = Utc::now() + chrono::Duration::minutes(1);
+        assert!(db
+            .list_compactable_video_chunks(Utc::now() - chrono::Duration::days(1), 10)
+            .await
+            .unwrap()
+            .is_empty());
+        let chunks = db.list_compactable_video_chunks(later, 10).await.unwrap();
+        assert_eq!(chunks, vec![(1, "/data/old.mp4".to_string(), first, last)]);
+
+        let previous = db
+            .set_video_chunk_file(1, "/data/old_compacted.mp4", Some(Utc::now()))
+            .await
+            .unwrap();
+        assert_eq!(previous.as_deref(), Some("/data/old.mp4"));
+        assert_eq!(
+            db.get_frame(last).await.unwrap(),
+            Some(("/data/old_compacted.mp4".to_string(), 1))
+        );
+        let names: Vec<String> = sqlx::query_scalar("SELECT name FROM frames")
+            .fetch_all(&db.pool)
+            .await
+            .unwrap();
+        assert!(names.iter().all(|name| name == "/data/old_compacted.mp4"));
+        assert!(db
+            .list_compactable_video_chunks(later, 10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_media_eviction() {
+        let db = setup_test_db().await;
+        let mut frames = Vec::new();
+        for (chunk, text) in [
+            ("/data/wordy.mp4", "a screen full of words"),
+            ("/data/blank.mp4", ""),
+            ("/data/bookmarked.mp4", ""),
+            ("/data/current.mp4", ""),
+        ] {
+            db.insert_video_chunk(chunk, "monitor_1").await.unwrap();
+            let frame_id = db
+                .insert_frame("monitor_1", None, None, Some("Code"), None, true)
+                .await
+                .unwrap();
+            if !text.is_empty() {
+                db.insert_ocr_text(frame_id, text, "", Arc::new(OcrEngine::Tesseract))
+                    .await
+                    .unwrap();
+            }
+            frames.push(frame_id);
+        }
+        db.add_tags(
+            frames[2],
+            TagContentType::Vision,
+            vec!["bookmark".to_string()],
+        )
+        .await
+        .unwrap();
+        let audio = db.insert_audio_chunk("/data/quiet.mp4").await.unwrap();
+        let tagged_audio = db.insert_audio_chunk("/data/keep.mp4").await.unwrap();
+        db.add_tags(
+            tagged_audio,
+            TagContentType::Audio,
+            vec!["bookmark".to_string()],
+        )
+        .await
+        .unwrap();
+
+        let later = Utc::now() + chrono::Duration::minutes(1);
+        // tagged chunks and the chunk a monitor still writes are kept
+        let oldest = db.list_eviction_candidates(later, false, 10).await.unwrap();
+        assert_eq!(
+            oldest,
+            vec![
+                ("video".to_string(), 1, "/data/wordy.mp4".to_string()),
+                ("video".to_string(), 2, "/data/blank.mp4".to_string()),
+                ("audio".to_string(), audio, "/data/quiet.mp4".to_string()),
+            ]
+        );
+        let least_text = db.list_eviction_candidates(later, true, 10).await.unwrap();
+        assert_eq!(
+            least_text
+                .iter()
+                .map(|(_, _, path)| path.as_str())
+                .collect::<Vec<_>>(),
+            vec!["/data/blank.mp4", "/data/quiet.mp4", "/data/wordy.mp4"]
+        );
+        assert!(db
+            .list_eviction_candidates(Utc::now() - chrono::Duration::days(1), false, 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        assert_eq!(db.get_frame_eviction(frames[0]).await.unwrap(), None);
+        let eviction = db
+            .record_media_eviction(MediaChunkKind::Video, 1, "/data/wordy.mp4", 1000, "oldest")
+            .await
+            .unwrap();
+        db.record_media_eviction(MediaChunkKind::Audio, audio, "/data/quiet.mp4", 0, "oldest")
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_frame_eviction(frames[0]).await.unwrap(),
+            Some(eviction)
+        );
+        assert_eq!(db.get_frame_eviction(frames[1]).await.unwrap(), None);
+        assert_eq!(
+            db.get_audio_chunk_eviction(audio)
+                .await
+                .unwrap()
+                .map(|eviction| eviction.file_path),
+            Some("/data/quiet.mp4".to_string())
+        );
+        // the frame and its text stay, without a file
+        assert_eq!(
+            db.get_frame(frames[0]).await.unwrap(),
+            Some((String::new(), 0))
+        );
+        let texts: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM ocr_text")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(texts, 1);
+        assert_eq!(
+            db.list_eviction_candidates(later, false, 10).await.unwrap(),
+            vec![("video".to_string(), 2, "/data/blank.mp4".to_string())]
+        );
+        assert_eq!(db.media_eviction_totals().await.unwrap(), (2, 1000));
+    }
+
+    #[tokio::test]
+    async fn test_frame_thumbnails() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("/data/chunk.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let frame_id = db
+            .insert_frame("monitor_1", None, None, Some("Code"), None, true)
+            .await
+            .unwrap();
+        let other = db
+            .insert_frame("monitor_1", None, None, Some("Slack"), None, true)
+            .await
+            .unwrap();
+
+        assert_eq!(db.get_frame_thumbnail(frame_id, 160).await.unwrap(), None);
+        db.insert_frame_thumbnail(frame_id, 160, b"small")
+            .await
+            .unwrap();
+        db.insert_frame_thumbnail(frame_id, 320, b"large")
+            .await
+            .unwrap();
+        db.insert_frame_thumbnail(other, 160, b"other")
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_frame_thumbnail(frame_id, 160).await.unwrap(),
+            Some(b"small".to_vec())
+        );
+        assert_eq!(
+            db.get_frame_thumbnail(frame_id, 320).await.unwrap(),
+            Some(b"large".to_vec())
+        );
+
+        // a redacted frame loses its thumbnails
+        db.redact_frame(frame_id, None, None).await.unwrap();
+        assert_eq!(db.get_frame_thumbnail(frame_id, 160).await.unwrap(), None);
+        assert_eq!(db.get_frame_thumbnail(frame_id, 320).await.unwrap(), None);
+        assert!(db.get_frame_thumbnail(other, 160).await.unwrap().is_some());
+
+        // and so does a deleted one
+        sqlx::query("DELETE FROM frames WHERE id = ?1")
+            .bind(other)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(db.get_frame_thumbnail(other, 160).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_media_verification() {
+        let db = setup_test_db().await;
+        db.insert_video_chunk("/data/old.mp4", "monitor_1")
+            .await
+            .unwrap();
+        for _ in 0..3 {
+            db.insert_frame("monitor_1", None, None, Some("Code"), None, true)
+                .await
+                .unwrap();
+        }
+        db.insert_video_chunk("/data/current.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let audio = db.insert_audio_chunk("/data/audio.mp4").await.unwrap();
+
+        let later = Utc::now() + chrono::Duration::minutes(1);
+        // the chunk still written isn't checked
+        assert_eq!(
+            db.list_unverified_video_chunks(later, 10).await.unwrap(),
+            vec![(1, "/data/old.mp4".to_string(), 2)]
+        );
+        assert_eq!(
+            db.list_unverified_audio_chunks(later, later, 10)
+                .await
+                .unwrap(),
+            vec![(audio, "/data/audio.mp4".to_string())]
+        );
+
+        db.set_chunk_verified(MediaChunkKind::Video, 1, Some("missing_frames"))
+            .await
+            .unwrap();
+        db.set_chunk_verified(MediaChunkKind::Audio, audio, None)
+            .await
+            .unwrap();
+        let earlier = Utc::now() - chrono::Duration::days(1);
+        assert!(db
+            .list_unverified_video_chunks(earlier, 10)
+            .await
+            .unwrap()
+            .is_empty());
+        let issues = db.list_media_issues(10, 0).await.unwrap();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(
+            (
+                issues[0].kind.as_str(),
+                issues[0].chunk_id,
+                issues[0].issue.as_str()
+            ),
+            ("video", 1, "missing_frames")
+        );
+
+        db.relocate_chunk(MediaChunkKind::Video, 1, "/moved/old.mp4")
+            .await
+            .unwrap();
+        let pruned = db.delete_frames_past_end(1, 2).await.unwrap();
+        assert_eq!(pruned.frames, 1);
+        assert!(pruned.video_files.is_empty());
+        let names: Vec<(String, i64)> =
+            sqlx::query_as("SELECT name, offset_index FROM frames ORDER BY offset_index")
+                .fetch_all(&db.pool)
+                .await
+                .unwrap();
+        assert_eq!(
+            names,
+            vec![
+                ("/moved/old.mp4".to_string(), 0),
+                ("/moved/old.mp4".to_string(), 1)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_media_gc() {
+        let db = setup_test_db().await;
+        let video = db
+            .insert_video_chunk("/data/gone.mp4", "monitor_1")
+            .await
+            .unwrap();
+        for _ in 0..2 {
+            db.insert_frame("monitor_1", None, None, Some("Code"), None, true)
+                .await
+                .unwrap();
+        }
+        let empty_video = db
+            .insert_video_chunk("/data/empty.mp4", "monitor_2")
+            .await
+            .unwrap();
+        let audio = db.insert_audio_chunk("/data/gone.wav").await.unwrap();
+        db.insert_audio_transcription(
+            audio,
+            "Hello from audio",
+            0,
+            "",
+            &AudioDevice {
+                name: "test".to_string(),
+                device_type: DeviceType::Output,
+            },
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        db.mark_chunk_synced(MediaChunkKind::Audio, audio)
+            .await
+            .unwrap();
+
+        let mut references = db.list_media_references().await.unwrap();
+        references.sort();
+        assert_eq!(
+            references,
+            vec![
+                (
+                    "audio".to_string(),
+                    audio,
+                    "/data/gone.wav".to_string(),
+                    true
+                ),
+                (
+                    "video".to_string(),
+                    video,
+                    "/data/gone.mp4".to_string(),
+                    false
+                ),
+                (
+                    "video".to_string(),
+                    empty_video,
+                    "/data/empty.mp4".to_string(),
+                    false
+                ),
+            ]
+        );
+
+        let cleared = db
+            .clear_missing_media(&[video, empty_video], &[audio])
+            .await
+            .unwrap();
+        assert_eq!(cleared, 3);
+        assert!(db.list_media_references().await.unwrap().is_empty());
+        // the recordings stay, only their file is forgotten
+        let (frames, transcriptions): (i64, i64) = sqlx::query_as(
+            "SELECT (SELECT COUNT(*) FROM frames), (SELECT COUNT(*) FROM audio_transcriptions)",
+        )
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+        assert_eq!((frames, transcriptions), (2, 1));
+        let eviction = db.get_frame_eviction(1).await.unwrap().unwrap();
+        assert_eq!(
+            (eviction.file_path.as_str(), eviction.reason.as_str()),
+            ("/data/gone.mp4", "missing")
+        );
+        // already cleared
+        assert_eq!(db.clear_missing_media(&[video], &[]).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_video_chunk_codec() {
+        let db = setup_test_db().await;
+        let legacy = db
+            .insert_video_chunk("/data/legacy.mp4", "monitor_1")
+            .await
+            .unwrap();
+        let recorded = db
+            .insert_video_chunk_with_codec("/data/recorded.mkv", "monitor_1", Some("h264"))
+            .await
+            .unwrap();
+        db.set_video_chunk_codec(legacy, "av1").await.unwrap();
+
+        let codecs: Vec<(i64, Option<String>)> =
+            sqlx::query_as("SELECT id, codec FROM video_chunks ORDER BY id")
+                .fetch_all(&db.pool)
+                .await
+                .unwrap();
+        assert_eq!(
+            codecs,
+            vec![
+                (legacy, Some("av1".to_string())),
+                (recorded, Some("h264".to_string()))
+            ]
+        );
+    }
 }
@@ -7,7 +7,7 @@ use serde_json;
 use std::collections::{HashMap, HashSet};
 use std::time::{Duration, Instant};
 
-const MEETING_APPS: &[&str] = &["zoom", "teams", "meet", "webex", "skype", "slack"];
+pub const MEETING_APPS: &[&str] = &["zoom", "teams", "meet", "webex", "skype", "slack"];
 const MEETING_KEYWORDS: &[&str] = &[
     "meeting",
     "call",
@@ -204,9 +204,9 @@ pub async fn poll_meetings_events() -> Result<()> {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct MeetingEvent {
-    app: String,
-    timestamp: DateTime<Utc>,
+pub struct MeetingEvent {
+    pub app: String,
+    pub timestamp: DateTime<Utc>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
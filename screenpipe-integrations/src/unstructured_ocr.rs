@@ -2,8 +2,7 @@ use anyhow::{anyhow, Result};
 use image::{codecs::png::PngEncoder, DynamicImage, ImageEncoder};
 use log::error;
 use reqwest::multipart::{Form, Part};
-use reqwest::Client;
-use screenpipe_core::{Language, TESSERACT_LANGUAGES};
+use screenpipe_core::{egress, Language, TESSERACT_LANGUAGES};
 use serde_json;
 use serde_json::Value;
 use std::collections::HashMap;
@@ -27,6 +26,7 @@ pub async fn perform_ocr_cloud(
         }
     };
     let api_url = "https://api.unstructuredapp.io/general/v0/general".to_string();
+    egress::check_egress(&api_url)?;
 
     let mut buffer = Vec::new();
     let mut cursor = Cursor::new(&mut buffer);
@@ -66,7 +66,7 @@ pub async fn perform_ocr_cloud(
         );
     }
 
-    let client = reqwest::Client::new();
+    let client = egress::http_client();
     let response = match timeout(
         Duration::from_secs(180),
         client
@@ -118,7 +118,7 @@ fn calculate_overall_confidence(parsed_response: &[HashMap<String, serde_json::V
 }
 
 pub async fn unstructured_chunking(text: &str) -> Result<Vec<String>> {
-    let client = Client::new();
+    let client = egress::http_client();
     let api_key = match env::var("UNSTRUCTURED_API_KEY") {
         Ok(key) => key,
         Err(_) => {
@@ -162,6 +162,7 @@ pub async fn unstructured_chunking(text: &str) -> Result<Vec<String>> {
         .text("output_format", "application/json");
 
     // Send request
+    egress::check_egress("https://api.unstructuredapp.io/general/v0/general")?;
     let response = client
         .post("https://api.unstructuredapp.io/general/v0/general")
         .header("accept", "application/json")
@@ -8,4 +8,7 @@ fn main() {
     {
         link_onnx();
     }
+
+    #[cfg(feature = "grpc")]
+    tonic_build::compile_protos("proto/screenpipe.proto").unwrap();
 }
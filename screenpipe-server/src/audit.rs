@@ -0,0 +1,56 @@
+use axum::{
+    extract::{ConnectInfo, Request},
+    middleware::Next,
+    response::Response,
+};
+use screenpipe_db::DatabaseManager;
+use std::net::SocketAddr;
+use tracing::warn;
+
+use crate::auth::ApiCaller;
+
+tokio::task_local! {
+    static ACTOR: String;
+}
+
+/// Who the request being handled comes from: "token:<name>" with api auth,
+/// "ip:<address>" without, "screenpipe" outside of requests.
+pub fn current_actor() -> String {
+    ACTOR
+        .try_with(|actor| actor.clone())
+        .unwrap_or_else(|_| "screenpipe".to_string())
+}
+
+/// Runs the request with its caller as the actor of what it audits.
+pub(crate) async fn scope_audit_actor(request: Request, next: Next) -> Response {
+    let actor = if let Some(ApiCaller(name)) = request.extensions().get::<ApiCaller>() {
+        format!("token:{}", name)
+    } else if let Some(ConnectInfo(addr)) = request.extensions().get::<ConnectInfo<SocketAddr>>() {
+        format!("ip:{}", addr.ip())
+    } else {
+        "local".to_string()
+    };
+    ACTOR.scope(actor, next.run(request)).await
+}
+
+/// Records an operation of the current actor in the audit log.
+pub async fn audit(db: &DatabaseManager, action: &str, target: &str, rows_affected: Option<i64>) {
+    audit_as(db, &current_actor(), action, target, rows_affected).await
+}
+
+/// Records an operation in the audit log. A failure to record is logged, the
+/// operation went through anyway.
+pub async fn audit_as(
+    db: &DatabaseManager,
+    actor: &str,
+    action: &str,
+    target: &str,
+    rows_affected: Option<i64>,
+) {
+    if let Err(e) = db
+        .insert_audit_entry(actor, action, target, rows_affected)
+        .await
+    {
+        warn!("failed to record {} in the audit log: {}", action, e);
+    }
+}
@@ -0,0 +1,271 @@
+use axum::{
+    extract::{Request, State},
+    http::{header::AUTHORIZATION, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use screenpipe_db::DatabaseManager;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::{fmt, path::Path, str::FromStr, sync::Arc};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::server::AppState;
+
+/// What a token may do. `admin` allows everything, including managing tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ApiScope {
+    /// every read: search, frames, meetings, events, ...
+    ReadSearch,
+    WriteTags,
+    /// other changes: adding content, pipes, speakers, capture control
+    Write,
+    /// deleting or redacting data
+    Delete,
+    AdminSql,
+    Admin,
+}
+
+impl ApiScope {
+    pub const ALL: [ApiScope; 6] = [
+        ApiScope::ReadSearch,
+        ApiScope::WriteTags,
+        ApiScope::Write,
+        ApiScope::Delete,
+        ApiScope::AdminSql,
+        ApiScope::Admin,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiScope::ReadSearch => "read-search",
+            ApiScope::WriteTags => "write-tags",
+            ApiScope::Write => "write",
+            ApiScope::Delete => "delete",
+            ApiScope::AdminSql => "admin-sql",
+            ApiScope::Admin => "admin",
+        }
+    }
+}
+
+impl fmt::Display for ApiScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ApiScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ApiScope::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == s.trim())
+            .ok_or_else(|| format!("unknown scope {:?}", s))
+    }
+}
+
+/// Scope a request needs, `None` for the few routes left open (health
+/// checks, the api spec, CORS preflights). The grpc api, see `crate::grpc`,
+/// states the scope of each of its calls itself.
+pub fn required_scope(method: &Method, path: &str) -> Option<ApiScope> {
+    if *method == Method::OPTIONS
+        || path == "/health"
+        || path == "/ws/health"
+        || path.starts_with("/openapi.")
+    {
+        return None;
+    }
+    // push destinations and webhook rules hold api keys and send data out,
+    // the audit log shows every query run, pipes (their config too) and input
+    // control run code or drive the machine
+    let admin_paths = [
+        "/audit",
+        "/pipes/download",
+        "/pipes/download-private",
+        "/pipes/enable",
+        "/pipes/update",
+        "/pipes/update-version",
+    ];
+    if path.starts_with("/auth/")
+        || path.starts_with("/push/")
+        || path.starts_with("/webhook-rules")
+        || path.starts_with("/experimental/")
+        || admin_paths.contains(&path)
+    {
+        return Some(ApiScope::Admin);
+    }
+    if path == "/raw_sql" {
+        return Some(ApiScope::AdminSql);
+    }
+    // extensions run programs on the machine
+    if path.starts_with("/extensions") && *method != Method::GET && *method != Method::HEAD {
+        return Some(ApiScope::Admin);
+    }
+    // deleting a tag removes it from everything it was on
+    if path == "/tag-names/delete" {
+        return Some(ApiScope::Delete);
+    }
+    if path.starts_with("/tags/")
+        || path.starts_with("/tag-names/")
+        || path.starts_with("/tag-suggestions/")
+        || path == "/bulk/tags"
+        || path == "/search/tag"
+    {
+        return Some(ApiScope::WriteTags);
+    }
+    // tag rules only tag what is recorded from then on
+    if (path == "/tag-rules" || path.starts_with("/tag-rules/"))
+        && *method != Method::GET
+        && *method != Method::HEAD
+    {
+        return Some(ApiScope::WriteTags);
+    }
+    // graphql has no mutations, its queries are posted
+    if *method == Method::GET || *method == Method::HEAD || path == "/graphql" {
+        return Some(ApiScope::ReadSearch);
+    }
+    let deletes = [
+        "/delete", "/purge", "/merge", "/redact", "/compact", "/erase",
+    ];
+    if *method == Method::DELETE || deletes.iter().any(|suffix| path.ends_with(suffix)) {
+        return Some(ApiScope::Delete);
+    }
+    Some(ApiScope::Write)
+}
+
+pub fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+/// Stores a new token and returns it, the only time it is readable.
+pub async fn create_token(
+    db: &DatabaseManager,
+    name: &str,
+    scopes: &[ApiScope],
+) -> Result<(String, screenpipe_db::ApiToken), sqlx::Error> {
+    let token = format!("sp_{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let scopes = scopes
+        .iter()
+        .map(ApiScope::as_str)
+        .collect::<Vec<_>>()
+        .join(",");
+    let stored = db
+        .insert_api_token(name, &hash_token(&token), &scopes)
+        .await?;
+    Ok((token, stored))
+}
+
+/// Creates an admin token when there is none yet, written to `api_token` in
+/// the data dir, so the api isn't locked for good once auth is turned on.
+pub async fn ensure_admin_token(db: &DatabaseManager, data_dir: &Path) -> anyhow::Result<()> {
+    if !db.list_api_tokens().await?.is_empty() {
+        return Ok(());
+    }
+    let (token, _) = create_token(db, "admin", &[ApiScope::Admin]).await?;
+    let path = data_dir.join("api_token");
+    tokio::fs::write(&path, &token).await?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        tokio::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).await?;
+    }
+    info!("created an admin api token, stored in {}", path.display());
+    Ok(())
+}
+
+pub(crate) fn bearer_token(request: &Request) -> Option<String> {
+    if let Some(value) = request.headers().get(AUTHORIZATION) {
+        return value
+            .to_str()
+            .ok()?
+            .strip_prefix("Bearer ")
+            .map(|token| token.trim().to_string());
+    }
+    // browsers can't set headers on websockets and event sources
+    request
+        .uri()
+        .query()?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("token="))
+        .map(String::from)
+}
+
+/// Name of the token a request was let in with.
+#[derive(Debug, Clone)]
+pub(crate) struct ApiCaller(pub String);
+
+/// Why a token was turned away.
+#[derive(Debug)]
+pub enum TokenRejection {
+    Missing,
+    Invalid,
+    /// the token, by name, lacks the scope
+    Forbidden(String),
+    Failed(sqlx::Error),
+}
+
+/// Name of the token if it grants `scope`. The http and grpc apis both check
+/// their callers with it.
+pub async fn check_token(
+    db: &DatabaseManager,
+    token: Option<&str>,
+    scope: ApiScope,
+) -> Result<String, TokenRejection> {
+    let token = token.ok_or(TokenRejection::Missing)?;
+    let stored = db
+        .use_api_token(&hash_token(token))
+        .await
+        .map_err(TokenRejection::Failed)?
+        .ok_or(TokenRejection::Invalid)?;
+
+    let granted = stored
+        .scopes
+        .split(',')
+        .filter_map(|s| s.parse::<ApiScope>().ok())
+        .any(|granted| granted == scope || granted == ApiScope::Admin);
+    if !granted {
+        return Err(TokenRejection::Forbidden(stored.name));
+    }
+    Ok(stored.name)
+}
+
+/// Rejects requests without a token granting the scope of the route.
+pub(crate) async fn require_token(
+    State(state): State<Arc<AppState>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let Some(scope) = required_scope(request.method(), request.uri().path()) else {
+        return next.run(request).await;
+    };
+    let error = |status: StatusCode, message: String| {
+        (status, Json(json!({ "error": message }))).into_response()
+    };
+
+    let token = bearer_token(&request);
+    match check_token(&state.db, token.as_deref(), scope).await {
+        Ok(name) => {
+            request.extensions_mut().insert(ApiCaller(name));
+            next.run(request).await
+        }
+        Err(TokenRejection::Missing) => {
+            error(StatusCode::UNAUTHORIZED, "missing api token".to_string())
+        }
+        Err(TokenRejection::Invalid) => {
+            error(StatusCode::UNAUTHORIZED, "invalid api token".to_string())
+        }
+        Err(TokenRejection::Forbidden(name)) => error(
+            StatusCode::FORBIDDEN,
+            format!("token {:?} lacks the {} scope", name, scope),
+        ),
+        Err(TokenRejection::Failed(e)) => {
+            warn!("failed to check api token: {}", e);
+            error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        }
+    }
+}
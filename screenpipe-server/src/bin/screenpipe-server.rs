@@ -10,19 +10,67 @@ use screenpipe_audio::{
         default_input_device, default_output_device, list_audio_devices, parse_audio_device,
     },
 };
-use screenpipe_core::find_ffmpeg_path;
+use screenpipe_core::{find_ffmpeg_path, SecretMasker};
 use screenpipe_db::{
-    create_migration_worker, DatabaseManager, MigrationCommand, MigrationConfig, MigrationStatus,
+    create_migration_worker, run_bench, ContentProcessor, DatabaseManager, MigrationCommand,
+    MigrationConfig, MigrationStatus,
 };
 use screenpipe_server::{
+    auth::ensure_admin_token,
     cli::{
         AudioCommand, Cli, CliAudioTranscriptionEngine, CliOcrEngine, Command, MigrationSubCommand,
         OutputFormat, PipeCommand, VisionCommand,
     },
+    clipboard::ClipboardMonitor,
+    db_bench::{bench_configs, render_bench_report},
+    devices::register_devices,
+    digest::{DigestMailer, DigestScheduler},
+    extensions::ExtensionManager,
+    focus_sessions::FocusSessionMonitor,
     handle_index_command,
+    html_export::export_html,
+    idle::IdleMonitor,
+    import::import,
+    input_activity::InputActivityMonitor,
+    journal::{export_journal, local_day},
+    local_only,
+    maintenance::MaintenanceScheduler,
+    media_encryption::{
+        load_media_key, load_or_create_media_key, media_key, set_media_key, MediaEncryptor,
+        MEDIA_KEY_FILE,
+    },
+    media_gc::{collect_garbage, GcOptions},
+    media_integrity::MediaVerifier,
+    meeting_detection::MeetingDetector,
+    obsidian::ObsidianSync,
+    ocr_providers::register_providers,
+    openapi_spec,
+    outage_monitor::{Notifier, OutageMonitor},
+    parquet_export::export_parquet,
+    pause::CapturePauses,
+    peers::Peers,
     pipe_manager::PipeInfo,
-    start_continuous_recording, watch_pid, PipeManager, ResourceMonitor, SCServer,
+    plugins::PluginHost,
+    push::PushSync,
+    rate_limit::RateLimits,
+    scripts::{InsertScripts, ScriptRunner},
+    secret_masking::{SecretMaskingProcessor, UiSecretScrubber},
+    shadow::ShadowConfig,
+    start_continuous_recording,
+    storage_quota::StorageQuota,
+    summary::SummaryLlm,
+    sync::{restore, set_sync_store, S3Store, SyncEngine},
+    tag_suggestions::TagSuggester,
+    text_pipeline::TextPipeline,
+    topics::TopicExtractor,
+    transcription_providers::register_providers as register_transcription_providers,
+    video_compaction::{CompactionConfig, VideoCompactor},
+    watch_pid,
+    watchdog::WatchdogConfig,
+    work_sessions::WorkSessionDetector,
+    PipeManager, ResourceMonitor, RetentionManager, SCServer, WebhookDispatcher,
 };
+use screenpipe_vision::capture_settings::set_monitor_settings;
 use screenpipe_vision::monitor::list_monitors;
 #[cfg(target_os = "macos")]
 use screenpipe_vision::run_ui;
@@ -30,6 +78,7 @@ use serde_json::{json, Value};
 use std::{
     env, fs, io::Write, net::SocketAddr, ops::Deref, path::PathBuf, sync::Arc, time::Duration,
 };
+use sysinfo::{System, SystemExt};
 use tokio::{runtime::Runtime, signal, sync::broadcast};
 use tracing::{debug, error, info, warn};
 use tracing_appender::non_blocking::WorkerGuard;
@@ -156,7 +205,15 @@ fn setup_logging(local_data_dir: &PathBuf, cli: &Cli) -> anyhow::Result<WorkerGu
 #[tracing::instrument]
 async fn main() -> anyhow::Result<()> {
     debug!("starting screenpipe server");
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
+    // before telemetry starts, local-only mode turns it off
+    local_only::enforce(&mut cli)?;
+    register_providers(&cli.remote_ocr_provider, cli.ocr_provider.as_deref())?;
+    let mut device_transcription_providers = register_transcription_providers(
+        &cli.remote_transcription_provider,
+        &cli.device_transcription_provider,
+        cli.deepgram_api_key.clone(),
+    )?;
 
     // Initialize Sentry only if telemetry is enabled
     let _sentry_guard = if !cli.disable_telemetry {
@@ -210,6 +267,17 @@ async fn main() -> anyhow::Result<()> {
             output: OutputFormat::Text,
             ..
         }) => true,
+        // the spec goes to stdout
+        Some(Command::Openapi { output: None }) => false,
+        Some(Command::Journal { output: None, .. }) => false,
+        Some(Command::Bench {
+            output: OutputFormat::Json,
+            ..
+        }) => false,
+        Some(Command::Gc {
+            output: OutputFormat::Json,
+            ..
+        }) => false,
         _ => true,
     };
 
@@ -286,6 +354,274 @@ async fn main() -> anyhow::Result<()> {
                 cli.handle_completions(*shell)?;
                 return Ok(());
             }
+            Command::Openapi { output } => {
+                let spec = serde_json::to_string_pretty(&openapi_spec())?;
+                match output {
+                    Some(path) => std::fs::write(path, spec)?,
+                    None => println!("{}", spec),
+                }
+                return Ok(());
+            }
+            Command::Journal {
+                date,
+                data_dir,
+                output,
+            } => {
+                let local_data_dir = get_base_dir(data_dir)?;
+                let db = DatabaseManager::new(&format!(
+                    "{}/db.sqlite",
+                    local_data_dir.to_string_lossy()
+                ))
+                .await?;
+                let date = date.unwrap_or_else(|| chrono::Local::now().date_naive());
+                let server_url = format!("http://localhost:{}", cli.port);
+                let markdown = export_journal(&db, date, &server_url).await?;
+                match output {
+                    Some(path) => std::fs::write(path, markdown)?,
+                    None => print!("{}", markdown),
+                }
+                return Ok(());
+            }
+            Command::ExportParquet {
+                output,
+                from,
+                to,
+                overwrite,
+                data_dir,
+            } => {
+                let local_data_dir = get_base_dir(data_dir)?;
+                let db = DatabaseManager::new(&format!(
+                    "{}/db.sqlite",
+                    local_data_dir.to_string_lossy()
+                ))
+                .await?;
+                let today = chrono::Local::now().date_naive();
+                let from = match from {
+                    Some(from) => *from,
+                    None => match db.get_earliest_timestamp().await? {
+                        Some(earliest) => earliest.with_timezone(&chrono::Local).date_naive(),
+                        None => today,
+                    },
+                };
+                let summary =
+                    export_parquet(&db, output, from, to.unwrap_or(today), *overwrite).await?;
+                println!(
+                    "exported {} rows to {} files, {} exported before",
+                    summary.rows, summary.files, summary.skipped
+                );
+                return Ok(());
+            }
+            Command::ExportHtml {
+                output,
+                from,
+                to,
+                no_thumbnails,
+                data_dir,
+            } => {
+                let local_data_dir = get_base_dir(data_dir)?;
+                let db = DatabaseManager::new(&format!(
+                    "{}/db.sqlite",
+                    local_data_dir.to_string_lossy()
+                ))
+                .await?;
+                let start = match from {
+                    Some(from) => from.start(),
+                    None => local_day(chrono::Local::now().date_naive()).0,
+                };
+                if let Some(key) = load_media_key(&local_data_dir.join(MEDIA_KEY_FILE)).await? {
+                    set_media_key(key);
+                }
+                let end = to.map(|to| to.end()).unwrap_or_else(chrono::Utc::now);
+                let entries = export_html(&db, output, start, end, !*no_thumbnails).await?;
+                println!("exported {} entries to {}", entries, output.display());
+                return Ok(());
+            }
+            Command::Import {
+                path,
+                from,
+                data_dir,
+                ocr_engine,
+            } => {
+                let local_data_dir = get_base_dir(data_dir)?;
+                let db = Arc::new(
+                    DatabaseManager::new(&format!(
+                        "{}/db.sqlite",
+                        local_data_dir.to_string_lossy()
+                    ))
+                    .await?,
+                );
+                let ocr_engine = ocr_engine.clone().unwrap_or(cli.ocr_engine.clone());
+                let summary = import(
+                    db,
+                    &local_data_dir,
+                    path,
+                    *from,
+                    ocr_engine.into(),
+                    cli.unique_languages().map_err(anyhow::Error::msg)?,
+                )
+                .await?;
+                println!(
+                    "imported {} frames from {} files, {} imported before, {} failed",
+                    summary.frames,
+                    summary.files - summary.skipped - summary.failed,
+                    summary.skipped,
+                    summary.failed
+                );
+                return Ok(());
+            }
+            Command::OptimizeSearchIndex { rebuild, data_dir } => {
+                let local_data_dir = get_base_dir(data_dir)?;
+                let db = DatabaseManager::new(&format!(
+                    "{}/db.sqlite",
+                    local_data_dir.to_string_lossy()
+                ))
+                .await?;
+                db.optimize_search_index(*rebuild).await?;
+                println!("search index optimized");
+                return Ok(());
+            }
+            Command::MergeDb {
+                source,
+                source_device,
+                data_dir,
+            } => {
+                let local_data_dir = get_base_dir(data_dir)?;
+                let db = DatabaseManager::new(&format!(
+                    "{}/db.sqlite",
+                    local_data_dir.to_string_lossy()
+                ))
+                .await?;
+                let report = db.merge_database(source, source_device).await?;
+                println!(
+                    "merged {} frames, {} transcriptions and {} ui texts from {} video and {} audio chunks, {} speakers matched, {} added, {} new tags",
+                    report.frames,
+                    report.audio_transcriptions,
+                    report.ui_monitoring,
+                    report.video_chunks,
+                    report.audio_chunks,
+                    report.matched_speakers,
+                    report.added_speakers,
+                    report.added_tags
+                );
+                return Ok(());
+            }
+            Command::SyncRestore { data_dir, media } => {
+                let local_data_dir = get_base_dir(data_dir)?;
+                let config = cli
+                    .sync_config()
+                    .map_err(anyhow::Error::msg)?
+                    .ok_or_else(|| anyhow::anyhow!("sync-restore needs --sync-bucket"))?;
+                let key_path = cli
+                    .media_key_file
+                    .as_ref()
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|| local_data_dir.join(MEDIA_KEY_FILE));
+                let key = load_media_key(&key_path).await?.ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "no media key in {}, the synced data can't be decrypted without the key of the machine synced",
+                        key_path.display()
+                    )
+                })?;
+                let report = restore(&S3Store::new(config), &key, &local_data_dir, *media).await?;
+                // recording here reads the restored chunks with it
+                let data_dir_key = local_data_dir.join(MEDIA_KEY_FILE);
+                if key_path != data_dir_key && !tokio::fs::try_exists(&data_dir_key).await? {
+                    tokio::fs::copy(&key_path, &data_dir_key).await?;
+                }
+                println!(
+                    "restored {} and {} deltas ({} rows), {} chunks moved to {}, {} downloaded",
+                    report.snapshot,
+                    report.deltas,
+                    report.delta_rows,
+                    report.relocated_chunks,
+                    local_data_dir.join("data").display(),
+                    report.downloaded_media
+                );
+                return Ok(());
+            }
+            Command::Bench {
+                frames,
+                transcriptions,
+                embeddings,
+                embedding_dimensions,
+                quantization,
+                batch_size,
+                searches,
+                seed,
+                dir,
+                output,
+            } => {
+                let dir = dir.clone().unwrap_or_else(std::env::temp_dir);
+                let configs = bench_configs(
+                    frames,
+                    *transcriptions,
+                    *embeddings,
+                    *embedding_dimensions,
+                    quantization,
+                    batch_size,
+                    *searches,
+                    *seed,
+                );
+                let mut reports = Vec::new();
+                for config in &configs {
+                    let report = run_bench(&dir, config).await?;
+                    if *output == OutputFormat::Text {
+                        println!("{}", render_bench_report(&report));
+                    }
+                    reports.push(report);
+                }
+                if *output == OutputFormat::Json {
+                    println!("{}", serde_json::to_string_pretty(&reports)?);
+                }
+                return Ok(());
+            }
+            Command::Gc {
+                delete_files,
+                clear_missing,
+                min_age_hours,
+                data_dir,
+                output,
+            } => {
+                let local_data_dir = get_base_dir(data_dir)?;
+                let db = DatabaseManager::new(&format!(
+                    "{}/db.sqlite",
+                    local_data_dir.to_string_lossy()
+                ))
+                .await?;
+                let options = GcOptions {
+                    delete_files: *delete_files,
+                    clear_missing: *clear_missing,
+                    min_age: Duration::from_secs(*min_age_hours as u64 * 60 * 60),
+                };
+                let report = collect_garbage(&db, &local_data_dir, options).await?;
+                match output {
+                    OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+                    OutputFormat::Text => {
+                        for file in &report.orphaned_files {
+                            println!("orphaned file: {}", file);
+                        }
+                        for chunk in &report.dangling_chunks {
+                            println!(
+                                "{} chunk {} without its file: {}",
+                                chunk.kind, chunk.id, chunk.file_path
+                            );
+                        }
+                        println!(
+                            "{} orphaned files ({:.1} MB), {} chunks without their file",
+                            report.orphaned_files.len(),
+                            report.orphaned_bytes as f64 / 1_000_000.0,
+                            report.dangling_chunks.len()
+                        );
+                        if *delete_files || *clear_missing {
+                            println!(
+                                "deleted {} files, cleared {} chunks",
+                                report.deleted_files, report.cleared_chunks
+                            );
+                        }
+                    }
+                }
+                return Ok(());
+            }
             Command::Pipe { subcommand } => {
                 handle_pipe_command(subcommand, &pipe_manager).await?;
                 return Ok(());
@@ -596,21 +932,267 @@ async fn main() -> anyhow::Result<()> {
         }
     }
 
-    let audio_devices_clone = audio_devices.clone();
     let resource_monitor = ResourceMonitor::new(!cli.disable_telemetry);
     resource_monitor.start_monitoring(Duration::from_secs(30), Some(Duration::from_secs(60)));
 
+    let text_pipeline = if cli.ocr_pipeline.is_empty() && cli.transcription_pipeline.is_empty() {
+        None
+    } else {
+        Some(Arc::new(TextPipeline::from_config(
+            &cli.ocr_pipeline,
+            &cli.transcription_pipeline,
+            cli.spell_corrections.as_deref(),
+            SecretMasker::new(cli.allow_secret.clone()),
+        )?))
+    };
+
+    let plugins = if cli.enable_plugins {
+        Some(PluginHost::load(&local_data_dir.join("plugins"))?)
+    } else {
+        None
+    };
+
+    let secret_masker = cli
+        .mask_secrets
+        .then(|| Arc::new(SecretMasker::new(cli.allow_secret.clone())));
+
     let db = Arc::new(
         DatabaseManager::new(&format!("{}/db.sqlite", local_data_dir.to_string_lossy()))
             .await
             .map_err(|e| {
                 eprintln!("failed to initialize database: {:?}", e);
                 e
-            })?,
+            })?
+            .with_query_timeout(
+                (cli.db_query_timeout_secs > 0)
+                    .then(|| Duration::from_secs(cli.db_query_timeout_secs)),
+            )
+            // secrets are masked whatever records them, before the stages
+            // and plugins see the text, which they leave
+            .with_content_processor(
+                secret_masker
+                    .clone()
+                    .map(|masker| SecretMaskingProcessor::new(masker) as Arc<dyn ContentProcessor>),
+            )
+            .with_content_processor(
+                text_pipeline
+                    .clone()
+                    .map(|pipeline| pipeline as Arc<dyn ContentProcessor>),
+            )
+            .with_content_processor(
+                plugins
+                    .clone()
+                    .map(|plugins| plugins as Arc<dyn ContentProcessor>),
+            ),
     );
 
     let db_server = db.clone();
 
+    // enforced by the database before anything is recorded
+    db.set_config_capture_block_rules(&cli.capture_block_rules())
+        .await?;
+    db.set_config_app_category_rules(&cli.app_category).await?;
+
+    // ui text is written by another process, held back until it is masked
+    db.set_ui_secret_scanning(secret_masker.is_some() && cli.enable_ui_monitoring)
+        .await?;
+    if let Some(masker) = &secret_masker {
+        if cli.enable_ui_monitoring {
+            UiSecretScrubber::new(db.clone(), masker.clone()).start(Duration::from_secs(5));
+        }
+    }
+
+    // full scans are logged as warnings, an index the searches rely on is missing
+    if let Err(e) = db.check_search_query_plans().await {
+        warn!("failed to check the search query plans: {}", e);
+    }
+
+    let media_key_path = cli
+        .media_key_file
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| local_data_dir.join(MEDIA_KEY_FILE));
+    if cli.encrypt_media {
+        let key = load_or_create_media_key(&media_key_path).await?;
+        MediaEncryptor::new(db.clone(), key).start(Duration::from_secs(60));
+    } else if let Some(key) = load_media_key(&media_key_path).await? {
+        // chunks encrypted before stay readable
+        set_media_key(key);
+    }
+
+    if cli.compact_video_after_days > 0 {
+        VideoCompactor::new(
+            db.clone(),
+            CompactionConfig {
+                after: chrono::Duration::days(cli.compact_video_after_days as i64),
+                codec: cli.compact_video_codec,
+                crf: cli.compact_video_crf,
+                scale: cli.compact_video_scale,
+            },
+        )
+        .start(Duration::from_secs(60 * 60));
+    }
+
+    let storage_quota = match cli.storage_budget_gb {
+        Some(budget_gb) if budget_gb > 0.0 => {
+            let quota = StorageQuota::new(
+                db.clone(),
+                local_data_dir.clone(),
+                (budget_gb * 1e9) as u64,
+                cli.eviction_policy,
+            );
+            quota.start(Duration::from_secs(10 * 60));
+            Some(quota)
+        }
+        _ => None,
+    };
+
+    if cli.verify_media_every_days > 0 {
+        MediaVerifier::new(
+            db.clone(),
+            local_data_dir.clone(),
+            chrono::Duration::days(cli.verify_media_every_days as i64),
+            cli.heal_media,
+        )
+        .start(Duration::from_secs(30 * 60));
+    }
+
+    let sync_config = cli.sync_config().map_err(anyhow::Error::msg)?;
+    if cli.enable_sync && sync_config.is_none() {
+        return Err(anyhow::anyhow!("--enable-sync needs --sync-bucket"));
+    }
+    if let Some(config) = sync_config {
+        let store = Arc::new(S3Store::new(config));
+        set_sync_store(store.clone());
+        if cli.enable_sync {
+            let key = match media_key() {
+                Some(key) => key.clone(),
+                None => {
+                    let key = load_or_create_media_key(&media_key_path).await?;
+                    set_media_key(key.clone());
+                    key
+                }
+            };
+            SyncEngine::new(db.clone(), store, key)
+                .start(Duration::from_secs(cli.sync_interval_minutes * 60));
+        }
+    }
+
+    if cli.retention_days.is_some() || !cli.retention_rule.is_empty() {
+        let retention_manager = RetentionManager::new(
+            db.clone(),
+            cli.retention_days,
+            cli.retention_rule.clone(),
+            cli.trash_days,
+            cli.trash_purge_webhook.clone(),
+        );
+        retention_manager.start(Duration::from_secs(60 * 60));
+    }
+
+    WebhookDispatcher::new(db.clone()).start();
+    if cli.enable_scripts {
+        ScriptRunner::new(
+            db.clone(),
+            InsertScripts::load(&local_data_dir.join("scripts"))?,
+        )
+        .start();
+    }
+    let extensions = cli.enable_extensions.then(|| {
+        ExtensionManager::new(
+            local_data_dir.join("extensions"),
+            db.clone(),
+            format!("http://localhost:{}", cli.port),
+            cli.api_auth,
+        )
+    });
+
+    if cli.outage_alert_minutes.is_some() || cli.ocr_failure_alert_percent.is_some() {
+        let notifiers = if cli.outage_notifier.is_empty() {
+            vec![Notifier::Desktop]
+        } else {
+            cli.outage_notifier.clone()
+        };
+        let outage_monitor = OutageMonitor::new(
+            db.clone(),
+            cli.outage_alert_minutes
+                .map(|minutes| chrono::Duration::minutes(minutes as i64)),
+            cli.recording_hours.clone(),
+            cli.ocr_failure_alert_percent,
+            notifiers,
+        );
+        outage_monitor.start(Duration::from_secs(60));
+    }
+
+    if let Some(vault) = &cli.obsidian_vault {
+        let obsidian_sync = ObsidianSync::new(
+            db.clone(),
+            vault.clone(),
+            cli.obsidian_folder.clone(),
+            cli.obsidian_notes,
+            format!("http://localhost:{}", cli.port),
+        );
+        obsidian_sync.start(Duration::from_secs(5 * 60));
+    }
+
+    PushSync::new(db.clone(), format!("http://localhost:{}", cli.port))
+        .start(Duration::from_secs(cli.push_interval_minutes.max(1) * 60));
+
+    if let Some(url) = &cli.tag_suggestion_url {
+        TagSuggester::new(
+            db.clone(),
+            url.clone(),
+            cli.tag_suggestion_model.clone(),
+            std::env::var("SCREENPIPE_TAG_SUGGESTION_API_KEY").ok(),
+            cli.tag_suggestion_samples,
+        )
+        .start(Duration::from_secs(
+            cli.tag_suggestion_interval_minutes.max(1) * 60,
+        ));
+    }
+
+    let maintenance = (cli.db_maintenance_hours > 0).then(|| {
+        let maintenance = MaintenanceScheduler::new(
+            db.clone(),
+            chrono::Duration::hours(cli.db_maintenance_hours as i64),
+            chrono::Duration::minutes(cli.db_maintenance_idle_minutes as i64),
+        );
+        maintenance.start(Duration::from_secs(5 * 60));
+        maintenance
+    });
+
+    if !cli.digest_email.is_empty() {
+        match &cli.smtp_url {
+            Some(smtp_url) => {
+                let from = cli
+                    .digest_from
+                    .clone()
+                    .unwrap_or_else(|| cli.digest_email[0].clone());
+                match DigestMailer::new(smtp_url, from, cli.digest_email.clone()) {
+                    Ok(mailer) => DigestScheduler::new(
+                        db.clone(),
+                        mailer,
+                        cli.digest_time,
+                        cli.digest_keyword.clone(),
+                        format!("http://localhost:{}", cli.port),
+                    )
+                    .start(),
+                    Err(e) => error!("invalid smtp url, no digest will be sent: {}", e),
+                }
+            }
+            None => warn!("--digest-email is set without --smtp-url, no digest will be sent"),
+        }
+    }
+
+    // restore per-monitor capture settings changed through the api in previous runs
+    match db.get_monitor_settings().await {
+        Ok(settings) => {
+            for settings in settings {
+                set_monitor_settings(settings.monitor_id as u32, settings.into());
+            }
+        }
+        Err(e) => warn!("failed to load monitor settings: {}", e),
+    }
+
     let warning_ocr_engine_clone = cli.ocr_engine.clone();
     let warning_audio_transcription_engine_clone = cli.audio_transcription_engine.clone();
     let monitor_ids = if cli.monitor_id.is_empty() {
@@ -619,10 +1201,26 @@ async fn main() -> anyhow::Result<()> {
         cli.monitor_id.clone()
     };
 
+    // the settings of the device registry win over the ones above
+    let monitor_ocr_engines = register_devices(
+        &db,
+        &System::new()
+            .host_name()
+            .unwrap_or_else(|| "localhost".to_string()),
+        &monitor_ids,
+        &mut audio_devices,
+        &mut device_transcription_providers,
+    )
+    .await?;
+    let audio_devices_clone = audio_devices.clone();
+
     let languages = cli.unique_languages().unwrap();
     let languages_clone = languages.clone();
 
-    let ocr_engine_clone = cli.ocr_engine.clone();
+    let ocr_engine_clone = match &cli.ocr_provider {
+        Some(name) => format!("provider {}", name),
+        None => format!("{:?}", cli.ocr_engine),
+    };
     let vad_engine = cli.vad_engine.clone();
     let vad_engine_clone = vad_engine.clone();
     let vad_sensitivity_clone = cli.vad_sensitivity.clone();
@@ -660,6 +1258,7 @@ async fn main() -> anyhow::Result<()> {
         .realtime(cli.enable_realtime_audio_transcription)
         .enabled_devices(audio_devices)
         .deepgram_api_key(cli.deepgram_api_key.clone())
+        .device_transcription_providers(device_transcription_providers)
         .output_path(PathBuf::from(output_path_clone.clone().to_string()));
 
     let audio_manager = match audio_manager_builder.build(db.clone()).await {
@@ -670,6 +1269,60 @@ async fn main() -> anyhow::Result<()> {
         }
     };
 
+    let capture_pauses = CapturePauses::new(
+        db.clone(),
+        (!cli.disable_audio).then(|| audio_manager.clone()),
+    );
+    capture_pauses.start(Duration::from_secs(15));
+    if cli.enable_clipboard {
+        ClipboardMonitor::new(
+            db.clone(),
+            cli.ignored_windows.clone(),
+            secret_masker.clone(),
+            Some(capture_pauses.clone()),
+        )
+        .start(Duration::from_secs(2));
+    }
+    if cli.enable_input_activity {
+        InputActivityMonitor::new(
+            db.clone(),
+            cli.ignored_windows.clone(),
+            Some(capture_pauses.clone()),
+        )
+        .start(Duration::from_secs(5));
+    }
+    if cli.enable_focus_sessions {
+        FocusSessionMonitor::new(
+            db.clone(),
+            cli.ignored_windows.clone(),
+            Some(capture_pauses.clone()),
+        )
+        .start(Duration::from_secs(2));
+    }
+    if cli.idle_minutes > 0 {
+        IdleMonitor::new(
+            db.clone(),
+            chrono::Duration::minutes(cli.idle_minutes as i64),
+        )
+        .start(Duration::from_secs(5 * 60));
+    }
+    if cli.enable_meeting_detection {
+        MeetingDetector::new(db.clone()).start(Duration::from_secs(5 * 60));
+    }
+    if cli.enable_topic_trends {
+        TopicExtractor::new(db.clone()).start(Duration::from_secs(60 * 60));
+    }
+    if cli.enable_work_sessions {
+        WorkSessionDetector::new(
+            db.clone(),
+            chrono::Duration::minutes(cli.work_session_break_minutes.max(1) as i64),
+        )
+        .start(Duration::from_secs(10 * 60));
+    }
+    let capture_pauses_recording = capture_pauses.clone();
+    let secret_masker_recording = secret_masker.clone();
+    let video_encoding = cli.video_encoding().resolve().await;
+
     let handle = {
         let runtime = &tokio::runtime::Handle::current();
         runtime.spawn(async move {
@@ -680,7 +1333,8 @@ async fn main() -> anyhow::Result<()> {
                     output_path_clone.clone(),
                     fps,
                     Duration::from_secs(cli.video_chunk_duration),
-                    Arc::new(cli.ocr_engine.clone().into()),
+                    Arc::new(cli.selected_ocr_engine()),
+                    monitor_ocr_engines.clone(),
                     monitor_ids_clone.clone(),
                     cli.use_pii_removal,
                     cli.disable_vision,
@@ -690,6 +1344,20 @@ async fn main() -> anyhow::Result<()> {
                     languages_clone.clone(),
                     cli.capture_unfocused_windows,
                     cli.enable_realtime_audio_transcription,
+                    cli.enable_receipt_detection,
+                    cli.min_ocr_confidence,
+                    cli.enable_participant_detection,
+                    cli.enable_slide_detection,
+                    cli.adaptive_fps
+                        .then(|| Duration::from_secs(cli.adaptive_fps_max_interval)),
+                    (!cli.watch_window.is_empty()).then(|| WatchdogConfig {
+                        windows: cli.watch_window.clone(),
+                        keywords: cli.watch_keyword.clone(),
+                        thresholds: cli.watch_threshold.clone(),
+                    }),
+                    Some(capture_pauses_recording.clone()),
+                    secret_masker_recording.clone(),
+                    video_encoding,
                 );
 
                 let result = tokio::select! {
@@ -724,6 +1392,27 @@ async fn main() -> anyhow::Result<()> {
     #[cfg(feature = "llm")]
     debug!("LLM initialized");
 
+    let peers = if cli.peer.is_empty() && !cli.enable_peer_discovery {
+        None
+    } else {
+        let secret = env::var("SCREENPIPE_PEER_SECRET").unwrap_or_default();
+        if secret.len() < 16 {
+            return Err(anyhow::anyhow!(
+                "peers need SCREENPIPE_PEER_SECRET set to 16 characters at least, the same on every machine"
+            ));
+        }
+        let name = cli
+            .peer_name
+            .clone()
+            .or_else(|| System::new().host_name())
+            .unwrap_or_else(|| "screenpipe".to_string());
+        let peers = Peers::new(name, secret, cli.peer.clone())?;
+        if cli.enable_peer_discovery {
+            peers.start_discovery(cli.peer_port)?;
+        }
+        Some(peers)
+    };
+
     let server = SCServer::new(
         db_server,
         SocketAddr::from(([127, 0, 0, 1], cli.port)),
@@ -733,7 +1422,58 @@ async fn main() -> anyhow::Result<()> {
         cli.disable_audio,
         cli.enable_ui_monitoring,
         audio_manager.clone(),
-    );
+    )
+    .with_ocr_engine(Arc::new(cli.selected_ocr_engine()), languages.clone())
+    .with_window_filters(cli.ignored_windows.clone(), cli.included_windows.clone())
+    .with_trash_days(cli.trash_days)
+    .with_click_tracking(cli.enable_click_tracking)
+    .with_shadow_search(cli.shadow_search.map(|candidate| ShadowConfig {
+        candidate,
+        sample_rate: cli.shadow_sample_rate,
+    }))
+    .with_api_auth(cli.api_auth)
+    .with_peers(peers, SocketAddr::from(([0, 0, 0, 0], cli.peer_port)))
+    .with_rate_limits(RateLimits {
+        search: cli.rate_limit_search,
+        read: cli.rate_limit_read,
+        write: cli.rate_limit_write,
+    })
+    .with_maintenance(maintenance)
+    .with_storage_quota(storage_quota)
+    .with_capture_pauses(capture_pauses.clone())
+    .with_secret_masker(secret_masker.clone())
+    .with_plugins(plugins.clone())
+    .with_text_pipeline(text_pipeline.clone())
+    .with_extensions(extensions.clone())
+    .with_summary_llm(cli.summary_llm_url.as_ref().map(|url| {
+        Arc::new(SummaryLlm::new(
+            url.clone(),
+            cli.summary_llm_model.clone(),
+            std::env::var("SCREENPIPE_SUMMARY_LLM_API_KEY").ok(),
+        ))
+    }));
+
+    if cli.api_auth {
+        if let Err(e) = ensure_admin_token(&db, &local_data_dir).await {
+            error!("failed to create the admin api token: {}", e);
+        }
+    }
+    // after the admin token, which is only created while there are no tokens
+    if let Some(extensions) = &extensions {
+        extensions.start()?;
+    }
+
+    #[cfg(feature = "grpc")]
+    if let Some(grpc_port) = cli.grpc_port {
+        let db = db.clone();
+        let api_auth = cli.api_auth;
+        tokio::spawn(async move {
+            let addr = SocketAddr::from(([127, 0, 0, 1], grpc_port));
+            if let Err(e) = screenpipe_server::grpc::serve(db, addr, api_auth).await {
+                error!("grpc server failed: {}", e);
+            }
+        });
+    }
 
     // print screenpipe in gradient
     println!("\n\n{}", DISPLAY.truecolor(147, 112, 219).bold());
@@ -771,10 +1511,7 @@ async fn main() -> anyhow::Result<()> {
         "│ audio engine           │ {:<34} │",
         format!("{:?}", warning_audio_transcription_engine_clone)
     );
-    println!(
-        "│ ocr engine             │ {:<34} │",
-        format!("{:?}", ocr_engine_clone)
-    );
+    println!("│ ocr engine             │ {:<34} │", ocr_engine_clone);
     println!(
         "│ vad engine             │ {:<34} │",
         format!("{:?}", vad_engine_clone)
@@ -793,8 +1530,37 @@ async fn main() -> anyhow::Result<()> {
         !cli.disable_telemetry
     );
     println!("│ local llm              │ {:<34} │", cli.enable_llm);
+    println!("│ local only             │ {:<34} │", cli.local_only);
+    println!(
+        "│ plugins                │ {:<34} │",
+        plugins
+            .as_ref()
+            .map_or("disabled".to_string(), |plugins| plugins
+                .status()
+                .len()
+                .to_string())
+    );
+    println!(
+        "│ extensions             │ {:<34} │",
+        extensions
+            .as_ref()
+            .map_or("disabled".to_string(), |extensions| extensions
+                .list()
+                .len()
+                .to_string())
+    );
+    let (ocr_stages, transcription_stages) = text_pipeline
+        .as_ref()
+        .map(|pipeline| pipeline.describe())
+        .unwrap_or_default();
+    println!("│ ocr pipeline           │ {:<34} │", ocr_stages.join(", "));
+    println!(
+        "│ transcription pipeline │ {:<34} │",
+        transcription_stages.join(", ")
+    );
 
     println!("│ use pii removal        │ {:<34} │", cli.use_pii_removal);
+    println!("│ mask secrets           │ {:<34} │", cli.mask_secrets);
     println!(
         "│ ignored windows        │ {:<34} │",
         format_cell(&format!("{:?}", &ignored_windows_clone), VALUE_WIDTH)
@@ -997,6 +1763,14 @@ async fn main() -> anyhow::Result<()> {
         );
     }
 
+    if cli.local_only {
+        println!(
+            "{}",
+            "local-only mode: every call outside this machine is refused, see /health.\n"
+                .bright_green()
+        );
+    }
+
     // Add warning for telemetry
     if !cli.disable_telemetry {
         println!(
@@ -1023,15 +1797,26 @@ async fn main() -> anyhow::Result<()> {
     // start recording after all this text
     if !cli.disable_audio {
         let audio_manager_clone = audio_manager.clone();
+        let capture_pauses = capture_pauses.clone();
         tokio::spawn(async move {
             tokio::time::sleep(Duration::from_secs(10)).await;
-            audio_manager_clone.start().await.unwrap();
+            // a pause running since before the start keeps audio off
+            if capture_pauses.audio_paused() {
+                capture_pauses.start_audio_on_resume();
+            } else {
+                audio_manager_clone.start().await.unwrap();
+            }
         });
     }
 
     // Start pipes
     info!("starting pipes");
-    let pipes = pipe_manager.list_pipes().await;
+    let pipes = if cli.local_only {
+        info!("local-only mode: pipes are not started");
+        Vec::new()
+    } else {
+        pipe_manager.list_pipes().await
+    };
     for pipe in pipes {
         debug!("pipe: {:?}", pipe.id);
         if !pipe.enabled {
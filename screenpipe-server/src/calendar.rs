@@ -0,0 +1,152 @@
+use chrono::{DateTime, Duration, Utc};
+use screenpipe_db::{DatabaseManager, Meeting, MeetingParticipant};
+use std::fmt::Write;
+
+// remote participants named in the title of an event
+const MAX_TITLE_NAMES: usize = 3;
+// content lines longer than this many bytes are folded
+const MAX_LINE_BYTES: usize = 75;
+
+/// A meeting as a calendar event.
+#[derive(Debug, Clone)]
+pub struct CalendarMeeting {
+    pub meeting: Meeting,
+    pub participants: Vec<MeetingParticipant>,
+    /// first and last transcriptions, when something was said
+    pub speech: Option<(DateTime<Utc>, DateTime<Utc>)>,
+}
+
+/// Meetings that started in the last `days`, as an iCalendar feed.
+pub async fn export_calendar(
+    db: &DatabaseManager,
+    days: i64,
+    limit: u32,
+    server_url: &str,
+) -> Result<String, sqlx::Error> {
+    let start = Utc::now() - Duration::days(days);
+    let mut meetings = Vec::new();
+    for meeting in db.list_meetings(Some(start), None, limit, 0).await? {
+        meetings.push(CalendarMeeting {
+            participants: db.get_meeting_participants(meeting.id).await?,
+            speech: db.get_meeting_speech_span(&meeting).await?,
+            meeting,
+        });
+    }
+    Ok(render_calendar(&meetings, server_url))
+}
+
+/// The title from the calendar, else "Call with Alice, Bob" after the
+/// remote participants, or the app when none was seen.
+pub fn event_title(meeting: &CalendarMeeting) -> String {
+    if let Some(title) = &meeting.meeting.title {
+        return title.clone();
+    }
+    let names: Vec<&str> = meeting
+        .participants
+        .iter()
+        .filter(|p| !p.is_local)
+        .map(|p| p.name.as_str())
+        .collect();
+    match names.len() {
+        0 => format!("Call on {}", meeting.meeting.meeting_app),
+        n if n <= MAX_TITLE_NAMES => format!("Call with {}", names.join(", ")),
+        n => format!(
+            "Call with {} and {} others",
+            names[..MAX_TITLE_NAMES].join(", "),
+            n - MAX_TITLE_NAMES
+        ),
+    }
+}
+
+/// Escapes a TEXT value (RFC 5545 3.3.11).
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Writes a content line, folded so no line is longer than 75 bytes
+/// (RFC 5545 3.1).
+fn write_line(ics: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > MAX_LINE_BYTES {
+            ics.push_str("\r\n ");
+            // the leading space counts
+            width = 1;
+        }
+        ics.push(c);
+        width += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+fn ics_time(t: &DateTime<Utc>) -> String {
+    t.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Renders meetings as an iCalendar feed, an event each. Events are timed
+/// from the first to the last thing said, or from the start to the end of
+/// the meeting when nothing was. Each keeps its uid across renders, so
+/// calendars subscribed to the feed update events rather than adding them.
+pub fn render_calendar(meetings: &[CalendarMeeting], server_url: &str) -> String {
+    let server_url = server_url.trim_end_matches('/');
+    let mut ics = String::new();
+    write_line(&mut ics, "BEGIN:VCALENDAR");
+    write_line(&mut ics, "VERSION:2.0");
+    write_line(&mut ics, "PRODID:-//screenpipe//meetings//EN");
+    write_line(&mut ics, "CALSCALE:GREGORIAN");
+    write_line(&mut ics, "X-WR-CALNAME:screenpipe meetings");
+
+    for meeting in meetings {
+        let (start, end) = match meeting.speech {
+            Some((first, last)) => (first, Some(last)),
+            None => (meeting.meeting.start_time, meeting.meeting.end_time),
+        };
+        let mut description = format!("Recorded on {}", meeting.meeting.meeting_app);
+        if !meeting.participants.is_empty() {
+            let names: Vec<&str> = meeting
+                .participants
+                .iter()
+                .map(|p| p.name.as_str())
+                .collect();
+            let _ = write!(description, "\nParticipants: {}", names.join(", "));
+        }
+        if meeting.meeting.end_time.is_none() {
+            description.push_str("\nStill in progress");
+        }
+
+        write_line(&mut ics, "BEGIN:VEVENT");
+        write_line(
+            &mut ics,
+            &format!("UID:meeting-{}@screenpipe", meeting.meeting.id),
+        );
+        write_line(
+            &mut ics,
+            &format!("DTSTAMP:{}", ics_time(&meeting.meeting.start_time)),
+        );
+        write_line(&mut ics, &format!("DTSTART:{}", ics_time(&start)));
+        // an event without an end lasts no time
+        if let Some(end) = end.filter(|end| *end > start) {
+            write_line(&mut ics, &format!("DTEND:{}", ics_time(&end)));
+        }
+        write_line(
+            &mut ics,
+            &format!("SUMMARY:{}", escape_text(&event_title(meeting))),
+        );
+        write_line(
+            &mut ics,
+            &format!("DESCRIPTION:{}", escape_text(&description)),
+        );
+        write_line(
+            &mut ics,
+            &format!("URL:{}/meetings/{}", server_url, meeting.meeting.id),
+        );
+        write_line(&mut ics, "END:VEVENT");
+    }
+
+    write_line(&mut ics, "END:VCALENDAR");
+    ics
+}
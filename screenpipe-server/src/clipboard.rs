@@ -0,0 +1,125 @@
+use anyhow::Result;
+use screenpipe_core::SecretMasker;
+use screenpipe_db::DatabaseManager;
+use screenpipe_vision::capture_screenshot_by_window::focused_window;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, error};
+
+use crate::pause::{is_paused, CapturePauses};
+
+// copies longer than this, in characters, are cut
+const MAX_CLIPBOARD_CHARS: usize = 100_000;
+
+/// Whether text copied in `app_name`/`window_name` is left out, as screen
+/// capture leaves out the windows of --ignored-windows.
+pub fn clipboard_ignored(ignored_windows: &[String], app_name: &str, window_name: &str) -> bool {
+    let (app_name, window_name) = (app_name.to_lowercase(), window_name.to_lowercase());
+    ignored_windows.iter().any(|ignored| {
+        let ignored = ignored.to_lowercase();
+        !ignored.is_empty() && (app_name.contains(&ignored) || window_name.contains(&ignored))
+    })
+}
+
+/// Records the text copied to the clipboard with the app focused when it
+/// was, checking the clipboard every few seconds. Images and files copied
+/// are not recorded.
+pub struct ClipboardMonitor {
+    db: Arc<DatabaseManager>,
+    ignored_windows: Vec<String>,
+    masker: Option<Arc<SecretMasker>>,
+    pauses: Option<Arc<CapturePauses>>,
+    // the clipboard text seen last, recorded or not
+    last: Mutex<Option<String>>,
+}
+
+impl ClipboardMonitor {
+    pub fn new(
+        db: Arc<DatabaseManager>,
+        ignored_windows: Vec<String>,
+        masker: Option<Arc<SecretMasker>>,
+        pauses: Option<Arc<CapturePauses>>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            db,
+            ignored_windows,
+            masker,
+            pauses,
+            last: Mutex::new(None),
+        })
+    }
+
+    pub fn start(self: &Arc<Self>, interval: Duration) {
+        let monitor = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let text = tokio::task::spawn_blocking(|| {
+                    arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text())
+                })
+                .await;
+                let text = match text {
+                    Ok(Ok(text)) => text,
+                    // no text on the clipboard
+                    Ok(Err(arboard::Error::ContentNotAvailable)) => continue,
+                    Ok(Err(e)) => {
+                        debug!("clipboard: failed to read: {}", e);
+                        continue;
+                    }
+                    Err(e) => {
+                        error!("clipboard: {}", e);
+                        continue;
+                    }
+                };
+                if let Err(e) = monitor.record(&text, focused_window).await {
+                    error!("clipboard: failed to record: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Stores `text` when it isn't the clipboard text seen last, with the
+    /// focused window `focused` returns. Returns the id of the new entry.
+    /// Nothing is stored while all capture is paused or for ignored
+    /// windows, and secrets are masked with --mask-secrets.
+    pub async fn record(
+        &self,
+        text: &str,
+        focused: impl FnOnce() -> Option<(String, String)> + Send + 'static,
+    ) -> Result<Option<i64>> {
+        {
+            let mut last = self.last.lock().unwrap();
+            if last.as_deref() == Some(text) {
+                return Ok(None);
+            }
+            *last = Some(text.to_string());
+        }
+        if text.trim().is_empty() {
+            return Ok(None);
+        }
+        if let Some(pauses) = &self.pauses {
+            if is_paused(&pauses.status().pauses, "clipboard") {
+                return Ok(None);
+            }
+        }
+
+        let (app_name, window_name) = tokio::task::spawn_blocking(focused).await?.unzip();
+        if clipboard_ignored(
+            &self.ignored_windows,
+            app_name.as_deref().unwrap_or_default(),
+            window_name.as_deref().unwrap_or_default(),
+        ) {
+            return Ok(None);
+        }
+
+        let text: String = text.chars().take(MAX_CLIPBOARD_CHARS).collect();
+        let text = match &self.masker {
+            Some(masker) => masker.mask(&text),
+            None => text,
+        };
+        Ok(self
+            .db
+            .insert_clipboard_entry(&text, app_name.as_deref(), window_name.as_deref())
+            .await?)
+    }
+}
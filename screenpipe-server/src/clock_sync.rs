@@ -0,0 +1,44 @@
+use chrono::{DateTime, Utc};
+use oasgen::OaSchema;
+use serde::{Deserialize, Serialize};
+
+/// One request/response between a remote agent and `/clock/sync`, as in
+/// NTP: `t0` the agent sent the request, `t1` the server received it, `t2`
+/// the server answered and `t3` the agent received the answer. `t0` and `t3`
+/// are read on the agent's clock, `t1` and `t2` on the server's.
+#[derive(OaSchema, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ClockExchange {
+    pub t0: DateTime<Utc>,
+    pub t1: DateTime<Utc>,
+    pub t2: DateTime<Utc>,
+    pub t3: DateTime<Utc>,
+}
+
+impl ClockExchange {
+    /// Server time minus agent time, assuming both legs took as long.
+    pub fn offset_ms(&self) -> f64 {
+        ((self.t1 - self.t0) + (self.t2 - self.t3))
+            .num_microseconds()
+            .unwrap_or(0) as f64
+            / 2000.0
+    }
+
+    /// Round trip without the time spent on the server. The offset is off by
+    /// at most half of it.
+    pub fn delay_ms(&self) -> f64 {
+        ((self.t3 - self.t0) - (self.t2 - self.t1))
+            .num_microseconds()
+            .unwrap_or(0) as f64
+            / 1000.0
+    }
+
+    /// Exchanges with times going backwards on either side can't be used.
+    pub fn is_valid(&self) -> bool {
+        self.t0 <= self.t3 && self.t1 <= self.t2 && self.delay_ms() >= 0.0
+    }
+}
+
+/// Moves a timestamp read on an agent's clock to server time.
+pub fn to_server_time(timestamp: DateTime<Utc>, offset_ms: f64) -> DateTime<Utc> {
+    timestamp + chrono::Duration::microseconds((offset_ms * 1000.0).round() as i64)
+}
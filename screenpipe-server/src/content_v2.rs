@@ -0,0 +1,194 @@
+use chrono::{DateTime, Utc};
+use oasgen::OaSchema;
+use screenpipe_db::{SearchResult, Speaker};
+use serde::{Deserialize, Serialize};
+
+#[derive(OaSchema, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentKind {
+    Ocr,
+    Audio,
+    Ui,
+    Clipboard,
+}
+
+/// Where the media of an item is stored: a frame of a video, or a span of an
+/// audio chunk.
+#[derive(OaSchema, Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MediaRef {
+    pub file_path: String,
+    pub offset_index: i64,
+    /// seconds into the audio chunk
+    pub start_time: Option<f64>,
+    pub end_time: Option<f64>,
+}
+
+/// A search result of the v2 api, the same fields whatever its kind.
+#[derive(OaSchema, Serialize, Deserialize, Debug, Clone)]
+pub struct ContentItemV2 {
+    /// `ocr:<frame id>`, `audio:<chunk id>:<offset>` or `ui:<id>`, stable
+    /// across requests
+    pub id: String,
+    pub kind: ContentKind,
+    pub timestamp: DateTime<Utc>,
+    pub text: String,
+    /// monitor or audio device the content was captured from
+    pub device_name: Option<String>,
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
+    pub browser_url: Option<String>,
+    pub media: MediaRef,
+    pub tags: Vec<String>,
+    pub speaker: Option<Speaker>,
+}
+
+#[derive(OaSchema, Serialize, Deserialize, Debug)]
+pub struct PaginationV2 {
+    pub limit: u32,
+    pub offset: u32,
+    pub total: i64,
+    /// offset of the next page, `None` on the last one
+    pub next_offset: Option<u32>,
+}
+
+#[derive(OaSchema, Serialize, Deserialize, Debug)]
+pub struct SearchResponseV2 {
+    pub data: Vec<ContentItemV2>,
+    pub pagination: PaginationV2,
+}
+
+fn non_empty(s: String) -> Option<String> {
+    if s.is_empty() {
+        None
+    } else {
+        Some(s)
+    }
+}
+
+/// Id of the item of `result`.
+pub fn item_id(result: &SearchResult) -> String {
+    match result {
+        SearchResult::OCR(ocr) => format!("ocr:{}", ocr.frame_id),
+        SearchResult::Audio(audio) => {
+            format!("audio:{}:{}", audio.audio_chunk_id, audio.offset_index)
+        }
+        SearchResult::UI(ui) => format!("ui:{}", ui.id),
+        SearchResult::Clipboard(clipboard) => format!("clipboard:{}", clipboard.id),
+    }
+}
+
+impl From<SearchResult> for ContentItemV2 {
+    fn from(result: SearchResult) -> Self {
+        let id = item_id(&result);
+        match result {
+            SearchResult::OCR(ocr) => ContentItemV2 {
+                id,
+                kind: ContentKind::Ocr,
+                timestamp: ocr.timestamp,
+                text: ocr.ocr_text,
+                device_name: ocr.device_name.and_then(non_empty),
+                app_name: non_empty(ocr.app_name),
+                window_name: non_empty(ocr.window_name),
+                browser_url: ocr.browser_url,
+                media: MediaRef {
+                    file_path: ocr.file_path,
+                    offset_index: ocr.offset_index,
+                    start_time: None,
+                    end_time: None,
+                },
+                tags: ocr.tags,
+                speaker: None,
+            },
+            SearchResult::Audio(audio) => ContentItemV2 {
+                id,
+                kind: ContentKind::Audio,
+                timestamp: audio.timestamp,
+                text: audio.transcription,
+                device_name: non_empty(audio.device_name),
+                app_name: None,
+                window_name: None,
+                browser_url: None,
+                media: MediaRef {
+                    file_path: audio.file_path,
+                    offset_index: audio.offset_index,
+                    start_time: audio.start_time,
+                    end_time: audio.end_time,
+                },
+                tags: audio.tags,
+                speaker: audio.speaker,
+            },
+            SearchResult::UI(ui) => ContentItemV2 {
+                id,
+                kind: ContentKind::Ui,
+                timestamp: ui.timestamp,
+                text: ui.text,
+                device_name: ui.device_name.and_then(non_empty),
+                app_name: non_empty(ui.app_name),
+                window_name: non_empty(ui.window_name),
+                browser_url: ui.browser_url,
+                media: MediaRef {
+                    file_path: ui.file_path,
+                    offset_index: ui.offset_index,
+                    start_time: None,
+                    end_time: None,
+                },
+                tags: ui.tags,
+                speaker: None,
+            },
+            SearchResult::Clipboard(clipboard) => ContentItemV2 {
+                id,
+                kind: ContentKind::Clipboard,
+                timestamp: clipboard.timestamp,
+                text: clipboard.text,
+                device_name: None,
+                app_name: clipboard.app_name.and_then(non_empty),
+                window_name: clipboard.window_name.and_then(non_empty),
+                browser_url: None,
+                // copied text has no media
+                media: MediaRef {
+                    file_path: String::new(),
+                    offset_index: 0,
+                    start_time: None,
+                    end_time: None,
+                },
+                tags: Vec::new(),
+                speaker: None,
+            },
+        }
+    }
+}
+
+/// Most recent first, ties broken by kind and id so pages never overlap or
+/// skip items with the same timestamp.
+pub fn sort_items(items: &mut [ContentItemV2]) {
+    items.sort_by(|a, b| {
+        b.timestamp
+            .cmp(&a.timestamp)
+            .then(a.kind.cmp(&b.kind))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+}
+
+/// The page at `offset` of `items`, sorted as a whole.
+pub fn paginate(
+    items: Vec<ContentItemV2>,
+    limit: u32,
+    offset: u32,
+    total: i64,
+) -> SearchResponseV2 {
+    let data: Vec<ContentItemV2> = items
+        .into_iter()
+        .skip(offset as usize)
+        .take(limit as usize)
+        .collect();
+    let end = offset as i64 + data.len() as i64;
+    SearchResponseV2 {
+        pagination: PaginationV2 {
+            limit,
+            offset,
+            total,
+            next_offset: (!data.is_empty() && end < total).then_some(end as u32),
+        },
+        data,
+    }
+}
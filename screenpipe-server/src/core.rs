@@ -1,12 +1,22 @@
+use crate::participants::{detect_participant_labels, ParticipantLabel};
+use crate::pause::CapturePauses;
+use crate::receipts::detect_receipt;
+use crate::secret_masking::mask_window_text;
+use crate::slides::{compare_slides, is_presentation_frame, SlideMatch};
+use crate::watchdog::{Watchdog, WatchdogConfig, WatchdogEvent};
 use crate::VideoCapture;
 use anyhow::Result;
+use chrono::Utc;
 use futures::future::join_all;
+use futures::StreamExt;
 use screenpipe_core::pii_removal::remove_pii;
 use screenpipe_core::Language;
-use screenpipe_db::{DatabaseManager, Speaker};
-use screenpipe_events::{poll_meetings_events, send_event};
+use screenpipe_core::SecretMasker;
+use screenpipe_db::{block_confidence, DatabaseManager, NewFrame, Speaker};
+use screenpipe_events::{poll_meetings_events, send_event, subscribe_to_all_events, MeetingEvent};
 use screenpipe_vision::core::WindowOcr;
-use screenpipe_vision::OcrEngine;
+use screenpipe_vision::{OcrEngine, UIFrame};
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::runtime::Handle;
@@ -19,6 +29,8 @@ pub async fn start_continuous_recording(
     fps: f64,
     video_chunk_duration: Duration,
     ocr_engine: Arc<OcrEngine>,
+    // overrides `ocr_engine` for some monitors
+    monitor_ocr_engines: HashMap<u32, Arc<OcrEngine>>,
     monitor_ids: Vec<u32>,
     use_pii_removal: bool,
     vision_disabled: bool,
@@ -28,6 +40,15 @@ pub async fn start_continuous_recording(
     languages: Vec<Language>,
     capture_unfocused_windows: bool,
     realtime_vision: bool,
+    detect_receipts: bool,
+    min_ocr_confidence: Option<f64>,
+    detect_participants: bool,
+    detect_slides: bool,
+    max_idle_interval: Option<Duration>,
+    watchdog: Option<WatchdogConfig>,
+    capture_pauses: Option<Arc<CapturePauses>>,
+    secret_masker: Option<Arc<SecretMasker>>,
+    video_encoding: VideoEncoding,
 ) -> Result<()> {
     info!("Starting video recording for monitors {:?}", monitor_ids);
     let video_tasks = if !vision_disabled {
@@ -36,11 +57,17 @@ pub async fn start_continuous_recording(
             .map(|&monitor_id| {
                 let db_manager_video = Arc::clone(&db);
                 let output_path_video = Arc::clone(&output_path);
-                let ocr_engine = Arc::clone(&ocr_engine);
+                let ocr_engine = monitor_ocr_engines
+                    .get(&monitor_id)
+                    .cloned()
+                    .unwrap_or_else(|| Arc::clone(&ocr_engine));
                 let ignored_windows_video = ignored_windows.to_vec();
                 let include_windows_video = include_windows.to_vec();
 
                 let languages = languages.clone();
+                let watchdog = watchdog.clone();
+                let capture_pauses = capture_pauses.clone();
+                let secret_masker = secret_masker.clone();
 
                 info!("Starting video recording for monitor {}", monitor_id);
                 vision_handle.spawn(async move {
@@ -60,6 +87,15 @@ pub async fn start_continuous_recording(
                             languages.clone(),
                             capture_unfocused_windows,
                             realtime_vision,
+                            detect_receipts,
+                            min_ocr_confidence,
+                            detect_participants,
+                            detect_slides,
+                            max_idle_interval,
+                            watchdog.clone(),
+                            capture_pauses.clone(),
+                            secret_masker.clone(),
+                            video_encoding,
                         )
                         .await
                         {
@@ -86,6 +122,11 @@ pub async fn start_continuous_recording(
     };
 
     if !vision_disabled {
+        vision_handle.spawn(persist_meetings(Arc::clone(&db)));
+        vision_handle.spawn(persist_ui_traversals(
+            Arc::clone(&db),
+            secret_masker.clone(),
+        ));
         vision_handle.spawn(async move {
             info!("Starting meeting events polling");
             match poll_meetings_events().await {
@@ -124,9 +165,19 @@ async fn record_video(
     languages: Vec<Language>,
     capture_unfocused_windows: bool,
     realtime_vision: bool,
+    detect_receipts: bool,
+    min_ocr_confidence: Option<f64>,
+    detect_participants: bool,
+    detect_slides: bool,
+    max_idle_interval: Option<Duration>,
+    watchdog: Option<WatchdogConfig>,
+    capture_pauses: Option<Arc<CapturePauses>>,
+    secret_masker: Option<Arc<SecretMasker>>,
+    video_encoding: VideoEncoding,
 ) -> Result<()> {
     info!("record_video: Starting for monitor {}", monitor_id);
     let device_name = Arc::new(format!("monitor_{}", monitor_id));
+    let mut watchdog = watchdog.map(Watchdog::new);
 
     // Add heartbeat counter
     let mut heartbeat_counter: u64 = 0;
@@ -149,7 +200,14 @@ async fn record_video(
             // Just spawn the task directly
             tokio::spawn(async move {
                 debug!("Inserting new video chunk: {}", file_path);
-                if let Err(e) = db.insert_video_chunk(&file_path, &device_name).await {
+                if let Err(e) = db
+                    .insert_video_chunk_with_codec(
+                        &file_path,
+                        &device_name,
+                        Some(video_encoding.codec.codec_name()),
+                    )
+                    .await
+                {
                     error!("Failed to insert new video chunk: {}", e);
                 } else {
                     debug!("Successfully inserted video chunk: {}", file_path);
@@ -170,6 +228,9 @@ async fn record_video(
         include_windows,
         languages,
         capture_unfocused_windows,
+        max_idle_interval,
+        capture_pauses,
+        video_encoding,
     );
 
     info!(
@@ -178,6 +239,9 @@ async fn record_video(
     );
     let mut last_frame_time = std::time::Instant::now();
     let mut frames_processed = 0;
+    // the engine as the database records it
+    let db_ocr_engine: Arc<screenpipe_db::OcrEngine> = Arc::new((*ocr_engine).clone().into());
+    let ocr_engine_name = db_ocr_engine.column_value();
 
     // Keep count of consecutive errors to detect unhealthy state
     let mut consecutive_db_errors = 0;
@@ -235,109 +299,173 @@ async fn record_video(
                 time_since_last_frame.as_millis()
             );
 
-            for window_result in &frame.window_ocr_results {
-                let insert_frame_start = std::time::Instant::now();
-                let result = db
-                    .insert_frame(
-                        &device_name,
-                        None,
-                        window_result.browser_url.as_deref(),
-                        Some(window_result.app_name.as_str()),
-                        Some(window_result.window_name.as_str()),
-                        window_result.focused,
-                    )
-                    .await;
+            // the text stored for each window, filtered and cleaned
+            let windows: Vec<_> = frame
+                .window_ocr_results
+                .iter()
+                .map(|window_result| {
+                    let (raw_text, blocks) = match min_ocr_confidence {
+                        Some(min_confidence) => drop_low_confidence_blocks(
+                            &window_result.text_json,
+                            &ocr_engine_name,
+                            min_confidence,
+                        ),
+                        None => (window_result.text.clone(), window_result.text_json.clone()),
+                    };
+                    let text = if use_pii_removal {
+                        remove_pii(&raw_text)
+                    } else {
+                        raw_text
+                    };
+                    let (text, blocks) = match &secret_masker {
+                        Some(masker) => mask_window_text(masker, &text, &blocks),
+                        None => (text, blocks),
+                    };
+                    (window_result, text, blocks)
+                })
+                .collect();
+            let new_frames: Vec<NewFrame> = windows
+                .iter()
+                .map(|(window_result, text, blocks)| NewFrame {
+                    timestamp: None,
+                    browser_url: window_result.browser_url.clone(),
+                    app_name: Some(window_result.app_name.clone()),
+                    window_name: Some(window_result.window_name.clone()),
+                    focused: window_result.focused,
+                    text: text.clone(),
+                    text_json: serde_json::to_string(blocks).unwrap_or_default(),
+                })
+                .collect();
 
-                let insert_duration = insert_frame_start.elapsed();
-                if insert_duration.as_millis() > 100 {
-                    warn!(
-                        "Slow DB insert_frame operation: {}ms",
+            // every window of the frame in one transaction
+            let insert_start = std::time::Instant::now();
+            let frame_ids = match db
+                .insert_frames_batch(&device_name, &new_frames, db_ocr_engine.clone())
+                .await
+            {
+                Ok(frame_ids) => {
+                    let insert_duration = insert_start.elapsed();
+                    if insert_duration.as_millis() > 100 {
+                        warn!(
+                            "Slow DB insert_frames_batch operation: {}ms for {} windows",
+                            insert_duration.as_millis(),
+                            new_frames.len()
+                        );
+                    }
+                    debug!(
+                        "Inserted {} frames with their OCR text in {}ms",
+                        frame_ids.len(),
                         insert_duration.as_millis()
                     );
+                    consecutive_db_errors = 0;
+                    frame_ids
+                }
+                Err(e) => {
+                    warn!("Failed to insert frames: {}", e);
+                    consecutive_db_errors += 1;
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    Vec::new()
                 }
+            };
 
-                match result {
-                    Ok(frame_id) => {
-                        debug!(
-                            "Successfully inserted frame {} in {}ms",
-                            frame_id,
-                            insert_duration.as_millis()
-                        );
-                        let text_json =
-                            serde_json::to_string(&window_result.text_json).unwrap_or_default();
-
-                        let text = if use_pii_removal {
-                            &remove_pii(&window_result.text)
-                        } else {
-                            &window_result.text
-                        };
-
-                        if realtime_vision {
-                            let send_event_start = std::time::Instant::now();
-                            match send_event(
-                                "ocr_result",
-                                WindowOcr {
-                                    image: Some(frame.image.clone()),
-                                    text: text.clone(),
-                                    text_json: window_result.text_json.clone(),
-                                    app_name: window_result.app_name.clone(),
-                                    window_name: window_result.window_name.clone(),
-                                    focused: window_result.focused,
-                                    confidence: window_result.confidence,
-                                    timestamp: frame.timestamp,
-                                    browser_url: window_result.browser_url.clone(),
-                                },
-                            ) {
-                                Ok(_) => {
-                                    let event_duration = send_event_start.elapsed();
-                                    if event_duration.as_millis() > 100 {
-                                        warn!(
-                                            "Slow event sending: {}ms",
-                                            event_duration.as_millis()
-                                        );
-                                    }
-                                }
-                                Err(e) => error!("Failed to send OCR event: {}", e),
+            for ((window_result, text, blocks), frame_id) in windows.iter().zip(frame_ids) {
+                // kept out by the capture blocklist, nothing about it leaves
+                if frame_id == 0 {
+                    debug!("window {} not recorded", window_result.app_name);
+                    continue;
+                }
+                if realtime_vision {
+                    let send_event_start = std::time::Instant::now();
+                    match send_event(
+                        "ocr_result",
+                        WindowOcr {
+                            image: Some(frame.image.clone()),
+                            text: text.clone(),
+                            text_json: blocks.clone(),
+                            app_name: window_result.app_name.clone(),
+                            window_name: window_result.window_name.clone(),
+                            focused: window_result.focused,
+                            confidence: window_result.confidence,
+                            timestamp: frame.timestamp,
+                            browser_url: window_result.browser_url.clone(),
+                        },
+                    ) {
+                        Ok(_) => {
+                            let event_duration = send_event_start.elapsed();
+                            if event_duration.as_millis() > 100 {
+                                warn!("Slow event sending: {}ms", event_duration.as_millis());
                             }
                         }
+                        Err(e) => error!("Failed to send OCR event: {}", e),
+                    }
+                }
 
-                        let insert_ocr_start = std::time::Instant::now();
+                if detect_receipts {
+                    if let Some(receipt) = detect_receipt(text) {
+                        debug!("receipt detected in frame {}: {:?}", frame_id, receipt);
                         if let Err(e) = db
-                            .insert_ocr_text(
+                            .insert_receipt(
                                 frame_id,
-                                text,
-                                &text_json,
-                                Arc::new((*ocr_engine).clone().into()),
+                                receipt.vendor.as_deref(),
+                                receipt.date.as_deref(),
+                                receipt.total,
+                                receipt.currency.as_deref(),
                             )
                             .await
                         {
+                            error!("Failed to insert receipt for frame {}: {}", frame_id, e);
+                        }
+                    }
+                }
+
+                if detect_participants {
+                    let labels = detect_participant_labels(
+                        &window_result.app_name,
+                        &window_result.window_name,
+                        blocks,
+                    );
+                    if !labels.is_empty() {
+                        if let Err(e) = record_participants(&db, frame_id, labels).await {
                             error!(
-                                "Failed to insert OCR text: {}, skipping window {} of frame {}",
-                                e, window_result.window_name, frame_id
-                            );
-                            consecutive_db_errors += 1;
-                            continue;
-                        } else {
-                            let ocr_insert_duration = insert_ocr_start.elapsed();
-                            if ocr_insert_duration.as_millis() > 100 {
-                                warn!(
-                                    "Slow DB insert_ocr_text operation: {}ms",
-                                    ocr_insert_duration.as_millis()
-                                );
-                            }
-                            consecutive_db_errors = 0; // Reset on success
-                            debug!(
-                                "OCR text inserted for frame {} in {}ms",
-                                frame_id,
-                                ocr_insert_duration.as_millis()
+                                "Failed to record meeting participants for frame {}: {}",
+                                frame_id, e
                             );
                         }
                     }
-                    Err(e) => {
-                        warn!("Failed to insert frame: {}", e);
-                        consecutive_db_errors += 1;
-                        tokio::time::sleep(Duration::from_millis(100)).await;
-                        continue;
+                }
+
+                if detect_slides
+                    && is_presentation_frame(
+                        &window_result.app_name,
+                        &window_result.window_name,
+                        text,
+                    )
+                {
+                    if let Err(e) = record_slide(&db, frame_id, text).await {
+                        error!("Failed to record slide for frame {}: {}", frame_id, e);
+                    }
+                }
+
+                if let Some(watchdog) = watchdog.as_mut() {
+                    for change in
+                        watchdog.observe(&window_result.app_name, &window_result.window_name, text)
+                    {
+                        info!(
+                            "watchdog: {:?} in {} ({})",
+                            change, window_result.window_name, window_result.app_name
+                        );
+                        if let Err(e) = send_event(
+                            "watchdog_triggered",
+                            WatchdogEvent {
+                                app_name: window_result.app_name.clone(),
+                                window_name: window_result.window_name.clone(),
+                                frame_id,
+                                timestamp: Utc::now(),
+                                change,
+                            },
+                        ) {
+                            error!("Failed to send watchdog event: {}", e);
+                        }
                     }
                 }
             }
@@ -366,6 +494,155 @@ async fn record_video(
     }
 }
 
+/// Adds the participants read on a call window to the meeting the meeting
+/// detector started. Labels read outside of a meeting are dropped, nothing
+/// would ever end a meeting they opened.
+async fn record_participants(
+    db: &DatabaseManager,
+    frame_id: i64,
+    labels: Vec<ParticipantLabel>,
+) -> Result<()> {
+    let now = Utc::now();
+    let Some(meeting_id) = db.current_meeting(now).await? else {
+        debug!(
+            "participants of frame {} read outside of a meeting",
+            frame_id
+        );
+        return Ok(());
+    };
+    let participants: Vec<(String, bool)> = labels
+        .into_iter()
+        .map(|label| (label.name, label.is_local))
+        .collect();
+    db.upsert_meeting_participants(meeting_id, &participants, frame_id, now)
+        .await?;
+
+    for participant in db.auto_link_meeting_speakers(meeting_id).await? {
+        info!(
+            "linked meeting participant {} to speaker {:?}",
+            participant.name, participant.speaker_id
+        );
+    }
+    Ok(())
+}
+
+/// Adds a presentation frame to the slide deck of the current meeting, unless
+/// it shows a slide that is already in the deck. A deck shown outside of a
+/// meeting isn't recorded, nothing would ever end the meeting it opened.
+async fn record_slide(db: &DatabaseManager, frame_id: i64, text: &str) -> Result<()> {
+    let now = Utc::now();
+    let Some(meeting_id) = db.current_meeting(now).await? else {
+        debug!("slide of frame {} shown outside of a meeting", frame_id);
+        return Ok(());
+    };
+    let slides = db.get_meeting_slides(meeting_id).await?;
+
+    if let Some(slide) = slides
+        .iter()
+        .find(|slide| compare_slides(&slide.text, text) == SlideMatch::Same)
+    {
+        db.update_meeting_slide(slide.id, None, now).await?;
+        return Ok(());
+    }
+
+    // keep the fullest step of a build animation rather than one slide per step
+    match slides.last() {
+        Some(last) if compare_slides(&last.text, text) == SlideMatch::Extends => {
+            db.update_meeting_slide(last.id, Some((frame_id, text)), now)
+                .await?;
+        }
+        _ => {
+            let slide_id = db
+                .insert_meeting_slide(meeting_id, frame_id, text, now)
+                .await?;
+            debug!("new slide {} in meeting {}", slide_id, meeting_id);
+        }
+    }
+    Ok(())
+}
+
+/// Stores meeting boundaries reported by the meeting detector so participants
+/// and speakers can be attached to them.
+async fn persist_meetings(db: Arc<DatabaseManager>) {
+    let mut subscription = subscribe_to_all_events();
+    while let Some(event) = subscription.next().await {
+        let result = match event.name.as_str() {
+            "meeting_started" => match serde_json::from_value::<MeetingEvent>(event.data) {
+                Ok(meeting) => db
+                    .start_meeting(&meeting.app, meeting.timestamp)
+                    .await
+                    .map(|_| ()),
+                Err(e) => {
+                    warn!("invalid meeting_started event: {}", e);
+                    continue;
+                }
+            },
+            "meeting_ended" => match serde_json::from_value::<MeetingEvent>(event.data) {
+                Ok(meeting) => db.end_meetings(meeting.timestamp).await.map(|_| ()),
+                Err(e) => {
+                    warn!("invalid meeting_ended event: {}", e);
+                    continue;
+                }
+            },
+            _ => continue,
+        };
+        if let Err(e) = result {
+            error!("failed to persist meeting event: {}", e);
+        }
+    }
+}
+
+/// Stores the traversals of the ui monitor as diffs from the previous one of
+/// their window, with secrets masked as in the OCR text.
+async fn persist_ui_traversals(db: Arc<DatabaseManager>, secret_masker: Option<Arc<SecretMasker>>) {
+    let mut subscription = subscribe_to_all_events();
+    while let Some(event) = subscription.next().await {
+        if event.name != "ui_frame" {
+            continue;
+        }
+        let frame = match serde_json::from_value::<UIFrame>(event.data) {
+            Ok(frame) => frame,
+            Err(e) => {
+                warn!("invalid ui_frame event: {}", e);
+                continue;
+            }
+        };
+        let text = match &secret_masker {
+            Some(masker) => masker.mask(&frame.text_output),
+            None => frame.text_output,
+        };
+        if let Err(e) = db
+            .insert_ui_traversal(&frame.app, &frame.window, Utc::now(), &text)
+            .await
+        {
+            error!("failed to persist ui traversal: {}", e);
+        }
+    }
+}
+
+/// Removes OCR blocks `ocr_engine` read with less than `min_confidence` and
+/// rebuilds the text from the remaining ones. Blocks without a confidence are
+/// kept.
+fn drop_low_confidence_blocks(
+    blocks: &[HashMap<String, String>],
+    ocr_engine: &str,
+    min_confidence: f64,
+) -> (String, Vec<HashMap<String, String>>) {
+    let kept: Vec<HashMap<String, String>> = blocks
+        .iter()
+        .filter(|block| block_confidence(block, ocr_engine).is_none_or(|c| c >= min_confidence))
+        .cloned()
+        .collect();
+    let text = kept
+        .iter()
+        .filter_map(|block| block.get("text"))
+        .map(|text| text.trim())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    (text, kept)
+}
+
 pub async fn merge_speakers(
     db: &DatabaseManager,
     speaker_to_keep_id: i64,
@@ -0,0 +1,61 @@
+use screenpipe_db::{BenchConfig, BenchReport, EmbeddingQuantization};
+use std::fmt::Write;
+
+/// Every combination of the sizes, quantizations and batch sizes asked for.
+/// Transcriptions and embeddings default to a tenth of the frames.
+#[allow(clippy::too_many_arguments)]
+pub fn bench_configs(
+    frames: &[u32],
+    transcriptions: Option<u32>,
+    embeddings: Option<u32>,
+    embedding_dimensions: usize,
+    quantizations: &[EmbeddingQuantization],
+    batch_sizes: &[usize],
+    searches: u32,
+    seed: u64,
+) -> Vec<BenchConfig> {
+    let mut configs = Vec::new();
+    for &frame_count in frames {
+        for &quantization in quantizations {
+            for &batch_size in batch_sizes {
+                configs.push(BenchConfig {
+                    frames: frame_count,
+                    transcriptions: transcriptions.unwrap_or(frame_count / 10),
+                    embeddings: embeddings.unwrap_or(frame_count / 10).min(frame_count),
+                    embedding_dimensions,
+                    quantization,
+                    batch_size: batch_size.max(1),
+                    searches,
+                    seed,
+                });
+            }
+        }
+    }
+    configs
+}
+
+/// The report as a few aligned lines, to compare runs in a terminal.
+pub fn render_bench_report(report: &BenchReport) -> String {
+    let mut text = String::new();
+    let _ = writeln!(
+        text,
+        "{} ({:.1} MB)",
+        report.name,
+        report.database_bytes as f64 / 1_000_000.0
+    );
+    for insert in &report.inserts {
+        let _ = writeln!(
+            text,
+            "  insert {:<22} {:>8} rows {:>10.0} rows/s",
+            insert.name, insert.rows, insert.rows_per_sec
+        );
+    }
+    for query in &report.queries {
+        let _ = writeln!(
+            text,
+            "  {:<29} p50 {:>8.2} ms  p95 {:>8.2} ms  max {:>8.2} ms",
+            query.name, query.p50_ms, query.p95_ms, query.max_ms
+        );
+    }
+    text
+}
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use screenpipe_audio::core::device::{parse_audio_device, DeviceType};
+use screenpipe_audio::transcription::transcription_provider;
+use screenpipe_db::{DatabaseManager, Device};
+use screenpipe_vision::capture_settings::{get_monitor_settings, set_monitor_settings};
+use screenpipe_vision::ocr_provider::ocr_provider;
+use screenpipe_vision::OcrEngine;
+use tracing::{info, warn};
+
+use crate::cli::CliOcrEngine;
+
+/// The id of a monitor of this machine, from its device name, e.g.
+/// "monitor_1".
+pub fn monitor_id(device: &Device) -> Option<u32> {
+    if device.kind != "monitor" || !device.host.is_empty() {
+        return None;
+    }
+    device.name.strip_prefix("monitor_")?.parse().ok()
+}
+
+/// The name audio recording knows an audio device of this machine by, e.g.
+/// "MacBook Pro Microphone (input)".
+pub fn audio_device_name(device: &Device) -> Option<String> {
+    if !device.host.is_empty() {
+        return None;
+    }
+    match device.kind.as_str() {
+        "audio_input" => Some(format!("{} (input)", device.name)),
+        "audio_output" => Some(format!("{} (output)", device.name)),
+        _ => None,
+    }
+}
+
+/// The OCR engine a monitor's `engine` names: a built-in one, e.g.
+/// "tesseract", or a registered OCR provider.
+pub fn monitor_ocr_engine(engine: &str) -> Result<OcrEngine, String> {
+    if let Ok(engine) = CliOcrEngine::from_str(engine, true) {
+        return Ok(engine.into());
+    }
+    match ocr_provider(engine) {
+        Some(_) => Ok(OcrEngine::Provider(engine.to_string())),
+        None => Err(format!("no ocr engine or provider named {}", engine)),
+    }
+}
+
+/// Checks the settings of a device fit its kind.
+pub fn validate_device_settings(
+    device: &Device,
+    fps: Option<f64>,
+    engine: Option<&str>,
+) -> Result<(), String> {
+    if fps.is_some() && device.kind != "monitor" {
+        return Err("only monitors have an fps".to_string());
+    }
+    match (engine, device.kind.as_str()) {
+        (None, _) => Ok(()),
+        (Some(engine), "monitor") => monitor_ocr_engine(engine).map(|_| ()),
+        (Some(engine), "audio_input" | "audio_output") => transcription_provider(engine)
+            .map(|_| ())
+            .ok_or_else(|| format!("no transcription provider named {}", engine)),
+        (Some(_), _) => Err(format!("a {} device has no engine", device.kind)),
+    }
+}
+
+/// Applies the enabled and fps settings of a monitor to its capture loop,
+/// on top of the ones of `/vision/settings`: a monitor disabled by either
+/// isn't captured.
+fn apply_monitor_settings(device: &Device) {
+    if let Some(id) = monitor_id(device) {
+        let mut settings = get_monitor_settings(id);
+        settings.enabled &= device.enabled;
+        if device.fps.is_some() {
+            settings.fps = device.fps;
+        }
+        set_monitor_settings(id, settings);
+    }
+}
+
+/// Registers this machine and the monitors and audio devices recording is
+/// about to capture, then applies their settings: monitors get theirs right
+/// away, disabled audio devices are taken out of `audio_devices` and audio
+/// engines go in `transcription_providers`. Returns the OCR engines of the
+/// monitors that have one.
+pub async fn register_devices(
+    db: &DatabaseManager,
+    host_name: &str,
+    monitor_ids: &[u32],
+    audio_devices: &mut Vec<String>,
+    transcription_providers: &mut HashMap<String, String>,
+) -> Result<HashMap<u32, Arc<OcrEngine>>> {
+    db.register_device("host", host_name).await?;
+
+    let mut ocr_engines = HashMap::new();
+    for monitor_id in monitor_ids {
+        let device = db
+            .register_device("monitor", &format!("monitor_{}", monitor_id))
+            .await?;
+        apply_monitor_settings(&device);
+        if let Some(engine) = &device.engine {
+            match monitor_ocr_engine(engine) {
+                Ok(engine) => {
+                    ocr_engines.insert(*monitor_id, Arc::new(engine));
+                }
+                Err(e) => warn!("monitor {} keeps the default ocr engine: {}", monitor_id, e),
+            }
+        }
+    }
+
+    let mut enabled = Vec::new();
+    for name in audio_devices.drain(..) {
+        let audio_device = parse_audio_device(&name)?;
+        let kind = match audio_device.device_type {
+            DeviceType::Input => "audio_input",
+            DeviceType::Output => "audio_output",
+        };
+        let device = db.register_device(kind, &audio_device.name).await?;
+        if !device.enabled {
+            info!("audio device {} is disabled in the device registry", name);
+            continue;
+        }
+        if let Some(engine) = &device.engine {
+            if transcription_provider(engine).is_some() {
+                transcription_providers.insert(name.clone(), engine.clone());
+            } else {
+                warn!("no transcription provider named {} for {}", engine, name);
+            }
+        }
+        enabled.push(name);
+    }
+    *audio_devices = enabled;
+    Ok(ocr_engines)
+}
@@ -0,0 +1,504 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use oasgen::OaSchema;
+use screenpipe_db::DatabaseManager;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+use walkdir::WalkDir;
+
+use crate::auth::{create_token, ApiScope};
+
+const MANIFEST: &str = "extension.json";
+/// Which extensions are enabled, in the extensions directory.
+const ENABLED_FILE: &str = "enabled.json";
+const LOG_FILE: &str = "extension.log";
+/// A service running this long is considered healthy again.
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+fn default_max_restarts() -> u32 {
+    5
+}
+
+/// `extension.json`, in `<data dir>/extensions/<name>/`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ExtensionManifest {
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    /// the program and its arguments, run in the extension's directory
+    pub entry: Vec<String>,
+    /// the api scopes of the token it is given when api auth is on
+    #[serde(default)]
+    pub permissions: Vec<ApiScope>,
+    /// a cron expression with seconds, e.g. "0 0 9 * * *", to run it at
+    /// times; without one it runs as a service, restarted when it exits
+    #[serde(default)]
+    pub schedule: Option<String>,
+    /// restarts in a row before a failing service is given up on
+    #[serde(default = "default_max_restarts")]
+    pub max_restarts: u32,
+}
+
+impl ExtensionManifest {
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(MANIFEST);
+        let manifest: Self = serde_json::from_str(
+            &std::fs::read_to_string(&path)
+                .with_context(|| format!("failed to read {}", path.display()))?,
+        )
+        .with_context(|| format!("invalid {}", path.display()))?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.name.is_empty()
+            || !self
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            anyhow::bail!("invalid extension name {:?}", self.name);
+        }
+        if self.entry.is_empty() {
+            anyhow::bail!("extension {} has no entry", self.name);
+        }
+        if let Some(scope) = self
+            .permissions
+            .iter()
+            .find(|scope| matches!(scope, ApiScope::Admin | ApiScope::AdminSql))
+        {
+            anyhow::bail!("extensions can't be granted the {} scope", scope);
+        }
+        if let Some(schedule) = &self.schedule {
+            Schedule::from_str(schedule)
+                .with_context(|| format!("invalid schedule {:?}", schedule))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(OaSchema, Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtensionState {
+    Stopped,
+    Running,
+    /// waiting to restart after it exited
+    Restarting,
+    /// waiting for its next scheduled run
+    Scheduled,
+    /// exited too many times in a row, until enabled again
+    Failed,
+}
+
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ExtensionStatus {
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub permissions: Vec<String>,
+    pub schedule: Option<String>,
+    pub enabled: bool,
+    pub state: ExtensionState,
+    pub pid: Option<u32>,
+    /// since it was enabled
+    pub restarts: u32,
+    pub last_started_at: Option<DateTime<Utc>>,
+    pub last_exit: Option<String>,
+    pub next_run_at: Option<DateTime<Utc>>,
+}
+
+struct Extension {
+    manifest: ExtensionManifest,
+    dir: PathBuf,
+    status: Mutex<ExtensionStatus>,
+    supervisor: Mutex<Option<(watch::Sender<bool>, JoinHandle<()>)>>,
+}
+
+impl Extension {
+    fn new(manifest: ExtensionManifest, dir: PathBuf, enabled: bool) -> Self {
+        let status = ExtensionStatus {
+            name: manifest.name.clone(),
+            version: manifest.version.clone(),
+            description: manifest.description.clone(),
+            permissions: manifest
+                .permissions
+                .iter()
+                .map(|scope| scope.to_string())
+                .collect(),
+            schedule: manifest.schedule.clone(),
+            enabled,
+            state: ExtensionState::Stopped,
+            pid: None,
+            restarts: 0,
+            last_started_at: None,
+            last_exit: None,
+            next_run_at: None,
+        };
+        Self {
+            manifest,
+            dir,
+            status: Mutex::new(status),
+            supervisor: Mutex::new(None),
+        }
+    }
+
+    fn update(&self, update: impl FnOnce(&mut ExtensionStatus)) {
+        update(&mut self.status.lock().unwrap());
+    }
+
+    fn token_name(&self) -> String {
+        format!("extension:{}", self.manifest.name)
+    }
+}
+
+// true once asked to stop, or when the manager is gone
+async fn wait_or_stop(stop: &mut watch::Receiver<bool>, duration: Duration) -> bool {
+    tokio::select! {
+        _ = tokio::time::sleep(duration) => false,
+        _ = stop.changed() => true,
+    }
+}
+
+/// Discovers the extensions installed in a directory, each a program with a
+/// manifest, and runs the enabled ones: services are restarted with a
+/// growing delay when they exit, scheduled ones run at their times. An
+/// extension reaches screenpipe through its api, with a token limited to the
+/// permissions of its manifest when api auth is on.
+pub struct ExtensionManager {
+    dir: PathBuf,
+    db: Arc<DatabaseManager>,
+    api_url: String,
+    api_auth: bool,
+    extensions: RwLock<BTreeMap<String, Arc<Extension>>>,
+}
+
+impl ExtensionManager {
+    pub fn new(
+        dir: PathBuf,
+        db: Arc<DatabaseManager>,
+        api_url: String,
+        api_auth: bool,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            dir,
+            db,
+            api_url,
+            api_auth,
+            extensions: RwLock::new(BTreeMap::new()),
+        })
+    }
+
+    fn enabled_names(&self) -> Vec<String> {
+        std::fs::read_to_string(self.dir.join(ENABLED_FILE))
+            .ok()
+            .and_then(|enabled| serde_json::from_str(&enabled).ok())
+            .unwrap_or_default()
+    }
+
+    fn save_enabled(&self) -> Result<()> {
+        let enabled: Vec<String> = self
+            .list()
+            .into_iter()
+            .filter(|status| status.enabled)
+            .map(|status| status.name)
+            .collect();
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(
+            self.dir.join(ENABLED_FILE),
+            serde_json::to_string_pretty(&enabled)?,
+        )?;
+        Ok(())
+    }
+
+    /// Loads the extensions of the directory and starts the enabled ones.
+    /// Those with an invalid manifest are logged and skipped.
+    pub fn start(self: &Arc<Self>) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        let enabled = self.enabled_names();
+        let mut dirs = std::fs::read_dir(&self.dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.join(MANIFEST).exists())
+            .collect::<Vec<_>>();
+        dirs.sort();
+        for dir in dirs {
+            match ExtensionManifest::load(&dir) {
+                Ok(manifest) => {
+                    let name = manifest.name.clone();
+                    let extension =
+                        Arc::new(Extension::new(manifest, dir, enabled.contains(&name)));
+                    self.extensions.write().unwrap().insert(name, extension);
+                }
+                Err(e) => warn!("skipping extension {}: {:#}", dir.display(), e),
+            }
+        }
+        for extension in self.extensions.read().unwrap().values() {
+            if extension.status.lock().unwrap().enabled {
+                self.spawn(extension.clone());
+            }
+        }
+        Ok(())
+    }
+
+    fn get(&self, name: &str) -> Option<Arc<Extension>> {
+        self.extensions.read().unwrap().get(name).cloned()
+    }
+
+    pub fn list(&self) -> Vec<ExtensionStatus> {
+        self.extensions
+            .read()
+            .unwrap()
+            .values()
+            .map(|extension| extension.status.lock().unwrap().clone())
+            .collect()
+    }
+
+    pub fn status(&self, name: &str) -> Option<ExtensionStatus> {
+        self.get(name)
+            .map(|extension| extension.status.lock().unwrap().clone())
+    }
+
+    /// Copies the extension in `source` to the directory, disabled.
+    pub fn install(&self, source: &Path) -> Result<ExtensionStatus> {
+        let manifest = ExtensionManifest::load(source)?;
+        let target = self.dir.join(&manifest.name);
+        if self.get(&manifest.name).is_some() || target.exists() {
+            anyhow::bail!("extension {} is already installed", manifest.name);
+        }
+        for entry in WalkDir::new(source) {
+            let entry = entry?;
+            let path = target.join(entry.path().strip_prefix(source)?);
+            if entry.file_type().is_dir() {
+                std::fs::create_dir_all(&path)?;
+            } else {
+                std::fs::copy(entry.path(), &path)?;
+            }
+        }
+        info!("installed extension {} {}", manifest.name, manifest.version);
+        let extension = Arc::new(Extension::new(manifest, target, false));
+        let status = extension.status.lock().unwrap().clone();
+        self.extensions
+            .write()
+            .unwrap()
+            .insert(status.name.clone(), extension);
+        Ok(status)
+    }
+
+    /// Starts an extension and keeps it enabled across restarts, none when
+    /// there is no such extension.
+    pub fn enable(self: &Arc<Self>, name: &str) -> Result<Option<ExtensionStatus>> {
+        let Some(extension) = self.get(name) else {
+            return Ok(None);
+        };
+        extension.update(|status| status.enabled = true);
+        self.save_enabled()?;
+        self.spawn(extension.clone());
+        Ok(self.status(name))
+    }
+
+    /// Stops an extension and keeps it disabled.
+    pub fn disable(&self, name: &str) -> Result<Option<ExtensionStatus>> {
+        let Some(extension) = self.get(name) else {
+            return Ok(None);
+        };
+        extension.update(|status| status.enabled = false);
+        self.save_enabled()?;
+        if let Some((stop, _)) = extension.supervisor.lock().unwrap().take() {
+            let _ = stop.send(true);
+        }
+        Ok(self.status(name))
+    }
+
+    fn spawn(self: &Arc<Self>, extension: Arc<Extension>) {
+        let mut supervisor = extension.supervisor.lock().unwrap();
+        if supervisor
+            .as_ref()
+            .is_some_and(|(_, handle)| !handle.is_finished())
+        {
+            return;
+        }
+        let (stop, stopped) = watch::channel(false);
+        extension.update(|status| status.restarts = 0);
+        let handle = tokio::spawn(Arc::clone(self).supervise(extension.clone(), stopped));
+        *supervisor = Some((stop, handle));
+    }
+
+    async fn supervise(
+        self: Arc<Self>,
+        extension: Arc<Extension>,
+        mut stop: watch::Receiver<bool>,
+    ) {
+        let schedule = extension
+            .manifest
+            .schedule
+            .as_deref()
+            .and_then(|schedule| Schedule::from_str(schedule).ok());
+        let mut failures = 0;
+        loop {
+            if let Some(schedule) = &schedule {
+                let Some(next) = schedule.upcoming(Utc).next() else {
+                    break;
+                };
+                extension.update(|status| {
+                    status.state = ExtensionState::Scheduled;
+                    status.next_run_at = Some(next);
+                });
+                let wait = (next - Utc::now()).to_std().unwrap_or_default();
+                if wait_or_stop(&mut stop, wait).await {
+                    break;
+                }
+            }
+
+            let started = Instant::now();
+            let exit = match self.run(&extension, &mut stop).await {
+                Ok(Some(exit)) => exit,
+                Ok(None) => break,
+                Err(e) => format!("failed to start: {:#}", e),
+            };
+            warn!("extension {} exited: {}", extension.manifest.name, exit);
+            extension.update(|status| {
+                status.pid = None;
+                status.last_exit = Some(exit);
+            });
+            // a scheduled run that fails waits for the next one
+            if schedule.is_some() {
+                continue;
+            }
+
+            if started.elapsed() >= STABLE_AFTER {
+                failures = 0;
+            }
+            failures += 1;
+            if failures > extension.manifest.max_restarts {
+                error!(
+                    "extension {} exited {} times in a row, giving up",
+                    extension.manifest.name, failures
+                );
+                extension.update(|status| status.state = ExtensionState::Failed);
+                return;
+            }
+            let backoff = Duration::from_secs(1 << (failures - 1).min(10)).min(MAX_BACKOFF);
+            extension.update(|status| {
+                status.state = ExtensionState::Restarting;
+                status.restarts += 1;
+            });
+            if wait_or_stop(&mut stop, backoff).await {
+                break;
+            }
+        }
+        extension.update(|status| {
+            status.state = ExtensionState::Stopped;
+            status.pid = None;
+            status.next_run_at = None;
+        });
+    }
+
+    // none when stopped, else how it exited
+    async fn run(
+        &self,
+        extension: &Extension,
+        stop: &mut watch::Receiver<bool>,
+    ) -> Result<Option<String>> {
+        let manifest = &extension.manifest;
+        let mut program = PathBuf::from(&manifest.entry[0]);
+        // e.g. ./run.sh, relative to the extension
+        if program.is_relative() && program.components().count() > 1 {
+            program = extension.dir.join(program);
+        }
+        let log = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(extension.dir.join(LOG_FILE))?;
+        let mut command = Command::new(program);
+        command
+            .args(&manifest.entry[1..])
+            .current_dir(&extension.dir)
+            .env("SCREENPIPE_API_URL", &self.api_url)
+            .env(
+                "SCREENPIPE_PERMISSIONS",
+                extension.status.lock().unwrap().permissions.join(","),
+            )
+            .stdin(Stdio::null())
+            .stdout(log.try_clone()?)
+            .stderr(log)
+            .kill_on_drop(true);
+        if self.api_auth {
+            command.env("SCREENPIPE_API_TOKEN", self.create_token(extension).await?);
+        }
+
+        let mut child = command.spawn()?;
+        info!(
+            "started extension {} ({:?})",
+            manifest.name,
+            child.id().unwrap_or_default()
+        );
+        extension.update(|status| {
+            status.state = ExtensionState::Running;
+            status.pid = child.id();
+            status.last_started_at = Some(Utc::now());
+            status.next_run_at = None;
+        });
+        let exit = tokio::select! {
+            exit = child.wait() => Some(match exit {
+                Ok(exit) => exit.to_string(),
+                Err(e) => e.to_string(),
+            }),
+            _ = stop.changed() => {
+                let _ = child.kill().await;
+                None
+            }
+        };
+        if self.api_auth {
+            self.revoke_tokens(extension).await;
+        }
+        Ok(exit)
+    }
+
+    // one token per run, with the scopes of the manifest
+    async fn create_token(&self, extension: &Extension) -> Result<String> {
+        self.revoke_tokens(extension).await;
+        let (token, _) = create_token(
+            &self.db,
+            &extension.token_name(),
+            &extension.manifest.permissions,
+        )
+        .await?;
+        Ok(token)
+    }
+
+    async fn revoke_tokens(&self, extension: &Extension) {
+        let tokens = match self.db.list_api_tokens().await {
+            Ok(tokens) => tokens,
+            Err(e) => {
+                warn!(
+                    "failed to list the tokens of {}: {}",
+                    extension.manifest.name, e
+                );
+                return;
+            }
+        };
+        for token in tokens {
+            if token.name == extension.token_name() {
+                if let Err(e) = self.db.delete_api_token(token.id).await {
+                    warn!(
+                        "failed to revoke the token of {}: {}",
+                        extension.manifest.name, e
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use screenpipe_db::DatabaseManager;
+use screenpipe_vision::capture_screenshot_by_window::focused_window;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::clipboard::clipboard_ignored;
+use crate::pause::{is_paused, CapturePauses};
+
+/// Records how long each window has the focus, checking the focused window
+/// every few seconds. Time in ignored windows, without a focused window or
+/// while all capture is paused is left out.
+pub struct FocusSessionMonitor {
+    db: Arc<DatabaseManager>,
+    ignored_windows: Vec<String>,
+    pauses: Option<Arc<CapturePauses>>,
+}
+
+impl FocusSessionMonitor {
+    pub fn new(
+        db: Arc<DatabaseManager>,
+        ignored_windows: Vec<String>,
+        pauses: Option<Arc<CapturePauses>>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            db,
+            ignored_windows,
+            pauses,
+        })
+    }
+
+    pub fn start(self: &Arc<Self>, interval: Duration) {
+        let monitor = Arc::clone(self);
+        tokio::spawn(async move {
+            match monitor.db.close_stale_focus_sessions().await {
+                Ok(0) => {}
+                Ok(closed) => info!("focus sessions: closed {} left open", closed),
+                Err(e) => error!("focus sessions: failed to close the open ones: {}", e),
+            }
+            loop {
+                let focused = match tokio::task::spawn_blocking(focused_window).await {
+                    Ok(focused) => focused,
+                    Err(e) => {
+                        error!("focus sessions: {}", e);
+                        None
+                    }
+                };
+                if let Err(e) = monitor.record(focused, Utc::now()).await {
+                    error!("focus sessions: failed to record the focus: {}", e);
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+
+    /// Records `focused`, the (app, window) with the focus at `at`. Returns
+    /// the session it goes on or starts, none when it is left out.
+    pub async fn record(
+        &self,
+        focused: Option<(String, String)>,
+        at: DateTime<Utc>,
+    ) -> Result<Option<i64>> {
+        let paused = self
+            .pauses
+            .as_ref()
+            .is_some_and(|pauses| is_paused(&pauses.status().pauses, "focus"));
+        let focused = focused.filter(|(app_name, window_name)| {
+            !paused && !clipboard_ignored(&self.ignored_windows, app_name, window_name)
+        });
+        Ok(self
+            .db
+            .record_focus(
+                focused
+                    .as_ref()
+                    .map(|(app_name, window_name)| (app_name.as_str(), window_name.as_str())),
+                at,
+            )
+            .await?)
+    }
+}
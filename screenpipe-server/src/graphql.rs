@@ -0,0 +1,378 @@
+use async_graphql::http::GraphiQLSource;
+use async_graphql::{Context, EmptyMutation, EmptySubscription, Object, Result, Schema};
+use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use axum::extract::State;
+use axum::response::{Html, IntoResponse};
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use screenpipe_db::{
+    parse_ocr_blocks, DatabaseManager, GraphAudioChunk, GraphFrame, GraphOcr, GraphTranscription,
+    GraphVideoChunk, OcrBlock, TagContentType,
+};
+use std::sync::Arc;
+
+use crate::server::AppState;
+
+const MAX_LIMIT: u32 = 100;
+// frame -> video chunk and back is as deep as a sensible query goes
+const MAX_DEPTH: usize = 8;
+
+pub type ScreenpipeSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+static SCHEMA: Lazy<ScreenpipeSchema> = Lazy::new(schema);
+
+/// The GraphQL schema over frames, OCR, transcriptions, speakers and tags.
+/// Queries run with the database as data.
+pub fn schema() -> ScreenpipeSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .limit_depth(MAX_DEPTH)
+        .finish()
+}
+
+pub(crate) async fn graphql_handler(
+    State(state): State<Arc<AppState>>,
+    request: GraphQLRequest,
+) -> GraphQLResponse {
+    SCHEMA
+        .execute(request.into_inner().data(state.db.clone()))
+        .await
+        .into()
+}
+
+pub(crate) async fn graphiql_handler() -> impl IntoResponse {
+    Html(GraphiQLSource::build().endpoint("/graphql").finish())
+}
+
+fn db<'a>(ctx: &Context<'a>) -> Result<&'a Arc<DatabaseManager>> {
+    ctx.data::<Arc<DatabaseManager>>()
+}
+
+async fn find_speaker(db: &DatabaseManager, id: i64) -> Result<Option<Speaker>> {
+    match db.get_speaker_by_id(id).await {
+        Ok(speaker) => Ok(Some(Speaker(speaker))),
+        Err(sqlx::Error::RowNotFound) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    async fn frame(&self, ctx: &Context<'_>, id: i64) -> Result<Option<Frame>> {
+        Ok(db(ctx)?.get_graph_frame(id).await?.map(Frame))
+    }
+
+    /// Frames between `startTime` and `endTime`, most recent first.
+    async fn frames(
+        &self,
+        ctx: &Context<'_>,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        app_name: Option<String>,
+        tag: Option<String>,
+        #[graphql(default = 20)] limit: u32,
+        #[graphql(default = 0)] offset: u32,
+    ) -> Result<Vec<Frame>> {
+        let frames = db(ctx)?
+            .list_graph_frames(
+                start_time,
+                end_time,
+                app_name.as_deref(),
+                tag.as_deref(),
+                limit.min(MAX_LIMIT),
+                offset,
+            )
+            .await?;
+        Ok(frames.into_iter().map(Frame).collect())
+    }
+
+    async fn transcription(&self, ctx: &Context<'_>, id: i64) -> Result<Option<Transcription>> {
+        Ok(db(ctx)?
+            .get_graph_transcription(id)
+            .await?
+            .map(Transcription))
+    }
+
+    /// Transcriptions between `startTime` and `endTime`, most recent first.
+    async fn transcriptions(
+        &self,
+        ctx: &Context<'_>,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        speaker_id: Option<i64>,
+        tag: Option<String>,
+        #[graphql(default = 20)] limit: u32,
+        #[graphql(default = 0)] offset: u32,
+    ) -> Result<Vec<Transcription>> {
+        let transcriptions = db(ctx)?
+            .list_graph_transcriptions(
+                start_time,
+                end_time,
+                speaker_id,
+                tag.as_deref(),
+                limit.min(MAX_LIMIT),
+                offset,
+            )
+            .await?;
+        Ok(transcriptions.into_iter().map(Transcription).collect())
+    }
+
+    async fn speaker(&self, ctx: &Context<'_>, id: i64) -> Result<Option<Speaker>> {
+        find_speaker(db(ctx)?, id).await
+    }
+
+    /// Speakers whose name starts with `name`, all of them without it.
+    async fn speakers(&self, ctx: &Context<'_>, name: Option<String>) -> Result<Vec<Speaker>> {
+        let speakers = db(ctx)?
+            .search_speakers(name.as_deref().unwrap_or_default())
+            .await?;
+        Ok(speakers.into_iter().map(Speaker).collect())
+    }
+}
+
+pub struct Frame(GraphFrame);
+
+#[Object]
+impl Frame {
+    async fn id(&self) -> i64 {
+        self.0.id
+    }
+
+    async fn timestamp(&self) -> DateTime<Utc> {
+        self.0.timestamp
+    }
+
+    async fn app_name(&self) -> Option<&str> {
+        self.0.app_name.as_deref()
+    }
+
+    async fn window_name(&self) -> Option<&str> {
+        self.0.window_name.as_deref()
+    }
+
+    async fn browser_url(&self) -> Option<&str> {
+        self.0.browser_url.as_deref()
+    }
+
+    async fn focused(&self) -> Option<bool> {
+        self.0.focused
+    }
+
+    /// Index of the frame in its video chunk.
+    async fn offset_index(&self) -> i64 {
+        self.0.offset_index
+    }
+
+    async fn ocr(&self, ctx: &Context<'_>) -> Result<Option<OcrText>> {
+        Ok(db(ctx)?.get_graph_ocr(self.0.id).await?.map(OcrText))
+    }
+
+    async fn tags(&self, ctx: &Context<'_>) -> Result<Vec<String>> {
+        Ok(db(ctx)?.get_tags(self.0.id, TagContentType::Vision).await?)
+    }
+
+    async fn video_chunk(&self, ctx: &Context<'_>) -> Result<Option<VideoChunk>> {
+        Ok(db(ctx)?
+            .get_graph_video_chunk(self.0.video_chunk_id)
+            .await?
+            .map(VideoChunk))
+    }
+}
+
+pub struct OcrText(GraphOcr);
+
+#[Object]
+impl OcrText {
+    async fn text(&self) -> &str {
+        &self.0.text
+    }
+
+    async fn engine(&self) -> &str {
+        &self.0.ocr_engine
+    }
+
+    /// The blocks of text the engine read, with their position when it
+    /// reported one.
+    async fn blocks(&self) -> Vec<TextBlock> {
+        self.0
+            .text_json
+            .as_deref()
+            .map(|text_json| parse_ocr_blocks(text_json, &self.0.ocr_engine))
+            .unwrap_or_default()
+            .into_iter()
+            .map(TextBlock)
+            .collect()
+    }
+}
+
+pub struct TextBlock(OcrBlock);
+
+#[Object]
+impl TextBlock {
+    async fn text(&self) -> &str {
+        &self.0.text
+    }
+
+    /// 0 to 1.
+    async fn confidence(&self) -> Option<f64> {
+        self.0.confidence
+    }
+
+    async fn left(&self) -> Option<f32> {
+        self.0.bounds.map(|bounds| bounds.left)
+    }
+
+    async fn top(&self) -> Option<f32> {
+        self.0.bounds.map(|bounds| bounds.top)
+    }
+
+    async fn width(&self) -> Option<f32> {
+        self.0.bounds.map(|bounds| bounds.width)
+    }
+
+    async fn height(&self) -> Option<f32> {
+        self.0.bounds.map(|bounds| bounds.height)
+    }
+}
+
+pub struct VideoChunk(GraphVideoChunk);
+
+#[Object]
+impl VideoChunk {
+    async fn id(&self) -> i64 {
+        self.0.id
+    }
+
+    async fn file_path(&self) -> &str {
+        &self.0.file_path
+    }
+
+    async fn device_name(&self) -> &str {
+        &self.0.device_name
+    }
+
+    async fn frames(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 20)] limit: u32,
+        #[graphql(default = 0)] offset: u32,
+    ) -> Result<Vec<Frame>> {
+        let frames = db(ctx)?
+            .list_graph_chunk_frames(self.0.id, limit.min(MAX_LIMIT), offset)
+            .await?;
+        Ok(frames.into_iter().map(Frame).collect())
+    }
+}
+
+pub struct Transcription(GraphTranscription);
+
+#[Object]
+impl Transcription {
+    async fn id(&self) -> i64 {
+        self.0.id
+    }
+
+    async fn transcription(&self) -> &str {
+        &self.0.transcription
+    }
+
+    async fn timestamp(&self) -> DateTime<Utc> {
+        self.0.timestamp
+    }
+
+    async fn device(&self) -> &str {
+        &self.0.device
+    }
+
+    async fn is_input_device(&self) -> bool {
+        self.0.is_input_device
+    }
+
+    /// Seconds into its audio chunk.
+    async fn start_time(&self) -> Option<f64> {
+        self.0.start_time
+    }
+
+    async fn end_time(&self) -> Option<f64> {
+        self.0.end_time
+    }
+
+    async fn engine(&self) -> &str {
+        &self.0.transcription_engine
+    }
+
+    async fn speaker(&self, ctx: &Context<'_>) -> Result<Option<Speaker>> {
+        let Some(speaker_id) = self.0.speaker_id else {
+            return Ok(None);
+        };
+        find_speaker(db(ctx)?, speaker_id).await
+    }
+
+    /// The tags of its audio chunk.
+    async fn tags(&self, ctx: &Context<'_>) -> Result<Vec<String>> {
+        Ok(db(ctx)?
+            .get_tags(self.0.audio_chunk_id, TagContentType::Audio)
+            .await?)
+    }
+
+    async fn audio_chunk(&self, ctx: &Context<'_>) -> Result<Option<AudioChunk>> {
+        Ok(db(ctx)?
+            .get_graph_audio_chunk(self.0.audio_chunk_id)
+            .await?
+            .map(AudioChunk))
+    }
+}
+
+pub struct AudioChunk(GraphAudioChunk);
+
+#[Object]
+impl AudioChunk {
+    async fn id(&self) -> i64 {
+        self.0.id
+    }
+
+    async fn file_path(&self) -> &str {
+        &self.0.file_path
+    }
+
+    async fn timestamp(&self) -> Option<DateTime<Utc>> {
+        self.0.timestamp
+    }
+}
+
+pub struct Speaker(screenpipe_db::Speaker);
+
+#[Object]
+impl Speaker {
+    async fn id(&self) -> i64 {
+        self.0.id
+    }
+
+    async fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    async fn metadata(&self) -> &str {
+        &self.0.metadata
+    }
+
+    async fn transcriptions(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 20)] limit: u32,
+        #[graphql(default = 0)] offset: u32,
+    ) -> Result<Vec<Transcription>> {
+        let transcriptions = db(ctx)?
+            .list_graph_transcriptions(
+                None,
+                None,
+                Some(self.0.id),
+                None,
+                limit.min(MAX_LIMIT),
+                offset,
+            )
+            .await?;
+        Ok(transcriptions.into_iter().map(Transcription).collect())
+    }
+}
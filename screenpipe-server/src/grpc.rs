@@ -0,0 +1,522 @@
+use chrono::{DateTime, TimeZone, Utc};
+use futures::{Stream, StreamExt};
+use screenpipe_db::{
+    AudioDevice, ContentType, DatabaseManager, DeviceType, OcrEngine, SearchResult, TagContentType,
+    TagFilter,
+};
+use screenpipe_events::subscribe_to_all_events;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+use tracing::{error, info};
+
+use crate::audit::audit_as;
+use crate::auth::{check_token, ApiScope, TokenRejection};
+use crate::clock_sync::to_server_time;
+
+pub mod proto {
+    tonic::include_proto!("screenpipe.v1");
+}
+
+use proto::screenpipe_server::{Screenpipe, ScreenpipeServer};
+
+const DEFAULT_LIMIT: u32 = 20;
+
+// who a call comes from, for the audit log
+fn grpc_actor<T>(request: &Request<T>) -> String {
+    request
+        .remote_addr()
+        .map(|addr| format!("grpc:{}", addr.ip()))
+        .unwrap_or_else(|| "grpc".to_string())
+}
+
+/// gRPC api served next to the HTTP one, on the same database.
+pub struct GrpcService {
+    db: Arc<DatabaseManager>,
+    api_auth: bool,
+}
+
+impl GrpcService {
+    pub fn new(db: Arc<DatabaseManager>) -> Self {
+        Self {
+            db,
+            api_auth: false,
+        }
+    }
+
+    /// Require the api token of the HTTP api, in the `authorization`
+    /// metadata, with the scope the same HTTP route would need.
+    pub fn with_api_auth(mut self, api_auth: bool) -> Self {
+        self.api_auth = api_auth;
+        self
+    }
+
+    /// Checks the caller may make a call needing `scope`, returns who it is
+    /// for the audit log.
+    async fn authorize<T>(&self, request: &Request<T>, scope: ApiScope) -> Result<String, Status> {
+        if !self.api_auth {
+            return Ok(grpc_actor(request));
+        }
+        let token = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::trim);
+        match check_token(&self.db, token, scope).await {
+            Ok(name) => Ok(format!("token:{}", name)),
+            Err(TokenRejection::Missing) => Err(Status::unauthenticated("missing api token")),
+            Err(TokenRejection::Invalid) => Err(Status::unauthenticated("invalid api token")),
+            Err(TokenRejection::Forbidden(name)) => Err(Status::permission_denied(format!(
+                "token {:?} lacks the {} scope",
+                name, scope
+            ))),
+            Err(TokenRejection::Failed(e)) => Err(db_error(e)),
+        }
+    }
+}
+
+/// Serves the gRPC api on `addr` until the server fails.
+pub async fn serve(
+    db: Arc<DatabaseManager>,
+    addr: SocketAddr,
+    api_auth: bool,
+) -> Result<(), anyhow::Error> {
+    info!("grpc server listening on {}", addr);
+    tonic::transport::Server::builder()
+        .add_service(ScreenpipeServer::new(
+            GrpcService::new(db).with_api_auth(api_auth),
+        ))
+        .serve(addr)
+        .await?;
+    Ok(())
+}
+
+fn db_error(e: sqlx::Error) -> Status {
+    match e {
+        sqlx::Error::RowNotFound => Status::not_found("not found"),
+        e => {
+            error!("grpc: database error: {}", e);
+            Status::internal(e.to_string())
+        }
+    }
+}
+
+fn to_timestamp(time: DateTime<Utc>) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: time.timestamp(),
+        nanos: time.timestamp_subsec_nanos() as i32,
+    }
+}
+
+fn from_timestamp(timestamp: Option<prost_types::Timestamp>) -> Option<DateTime<Utc>> {
+    let timestamp = timestamp?;
+    Utc.timestamp_opt(timestamp.seconds, timestamp.nanos.max(0) as u32)
+        .single()
+}
+
+impl From<proto::ContentType> for ContentType {
+    fn from(content_type: proto::ContentType) -> Self {
+        match content_type {
+            proto::ContentType::All => ContentType::All,
+            proto::ContentType::Ocr => ContentType::OCR,
+            proto::ContentType::Audio => ContentType::Audio,
+            proto::ContentType::Ui => ContentType::UI,
+            proto::ContentType::AudioAndUi => ContentType::AudioAndUi,
+            proto::ContentType::OcrAndUi => ContentType::OcrAndUi,
+            proto::ContentType::AudioAndOcr => ContentType::AudioAndOcr,
+            proto::ContentType::Clipboard => ContentType::Clipboard,
+        }
+    }
+}
+
+impl From<proto::TagContentType> for TagContentType {
+    fn from(content_type: proto::TagContentType) -> Self {
+        match content_type {
+            proto::TagContentType::Vision => TagContentType::Vision,
+            proto::TagContentType::Audio => TagContentType::Audio,
+        }
+    }
+}
+
+impl From<screenpipe_db::Speaker> for proto::Speaker {
+    fn from(speaker: screenpipe_db::Speaker) -> Self {
+        Self {
+            id: speaker.id,
+            name: speaker.name,
+            metadata: speaker.metadata,
+        }
+    }
+}
+
+impl From<SearchResult> for proto::SearchResult {
+    fn from(result: SearchResult) -> Self {
+        use proto::search_result::Content;
+
+        let content = match result {
+            SearchResult::OCR(ocr) => Content::Ocr(proto::OcrContent {
+                frame_id: ocr.frame_id,
+                text: ocr.ocr_text,
+                timestamp: Some(to_timestamp(ocr.timestamp)),
+                file_path: ocr.file_path,
+                offset_index: ocr.offset_index,
+                app_name: ocr.app_name,
+                window_name: ocr.window_name,
+                tags: ocr.tags,
+                browser_url: ocr.browser_url,
+                focused: ocr.focused,
+                browser_title: ocr.browser_title,
+                browser_selection: ocr.browser_selection,
+            }),
+            SearchResult::Audio(audio) => Content::Audio(proto::AudioContent {
+                chunk_id: audio.audio_chunk_id,
+                transcription: audio.transcription,
+                timestamp: Some(to_timestamp(audio.timestamp)),
+                file_path: audio.file_path,
+                offset_index: audio.offset_index,
+                tags: audio.tags,
+                device_name: audio.device_name,
+                device_type: match audio.device_type {
+                    DeviceType::Input => "input",
+                    DeviceType::Output => "output",
+                }
+                .to_string(),
+                speaker: audio.speaker.map(Into::into),
+                start_time: audio.start_time,
+                end_time: audio.end_time,
+            }),
+            SearchResult::UI(ui) => Content::Ui(proto::UiContent {
+                id: ui.id,
+                text: ui.text,
+                timestamp: Some(to_timestamp(ui.timestamp)),
+                app_name: ui.app_name,
+                window_name: ui.window_name,
+                file_path: ui.file_path,
+                offset_index: ui.offset_index,
+                browser_url: ui.browser_url,
+                tags: ui.tags,
+            }),
+            SearchResult::Clipboard(clipboard) => Content::Clipboard(proto::ClipboardContent {
+                id: clipboard.id,
+                text: clipboard.text,
+                timestamp: Some(to_timestamp(clipboard.timestamp)),
+                app_name: clipboard.app_name,
+                window_name: clipboard.window_name,
+            }),
+        };
+        Self {
+            content: Some(content),
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Screenpipe for GrpcService {
+    async fn search(
+        &self,
+        request: Request<proto::SearchRequest>,
+    ) -> Result<Response<proto::SearchResponse>, Status> {
+        self.authorize(&request, ApiScope::ReadSearch).await?;
+        let request = request.into_inner();
+        let speaker_ids = (!request.speaker_ids.is_empty()).then(|| request.speaker_ids.clone());
+        let tags = TagFilter {
+            any: request.tags_any.clone(),
+            all: request.tags_all.clone(),
+            none: request.tags_none.clone(),
+        };
+        let results = self
+            .db
+            .search(
+                &request.q,
+                request.content_type().into(),
+                if request.limit == 0 {
+                    DEFAULT_LIMIT
+                } else {
+                    request.limit
+                },
+                request.offset,
+                from_timestamp(request.start_time),
+                from_timestamp(request.end_time),
+                request.app_name.as_deref(),
+                request.window_name.as_deref(),
+                request.min_length.map(|length| length as usize),
+                request.max_length.map(|length| length as usize),
+                speaker_ids,
+                request.frame_name.as_deref(),
+                request.browser_url.as_deref(),
+                request.focused,
+                request.min_confidence,
+                request.in_tables,
+                &tags,
+            )
+            .await
+            .map_err(db_error)?;
+
+        Ok(Response::new(proto::SearchResponse {
+            results: results.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn insert_frame(
+        &self,
+        request: Request<proto::InsertFrameRequest>,
+    ) -> Result<Response<proto::InsertResponse>, Status> {
+        self.authorize(&request, ApiScope::Write).await?;
+        let request = request.into_inner();
+        if request.device_name.is_empty() {
+            return Err(Status::invalid_argument("device_name is required"));
+        }
+
+        // the timestamp comes from the agent's clock, see /clock/sync
+        let clock_offset_ms = self
+            .db
+            .get_clock_offset(&request.device_name)
+            .await
+            .map_err(db_error)?
+            .map_or(0.0, |offset| offset.offset_ms);
+        let timestamp = from_timestamp(request.timestamp)
+            .map(|timestamp| to_server_time(timestamp, clock_offset_ms))
+            .unwrap_or_else(Utc::now);
+
+        // no video behind these frames, like transcriptions added without audio
+        self.db
+            .insert_video_chunk("", &request.device_name)
+            .await
+            .map_err(db_error)?;
+        let frame_id = self
+            .db
+            .insert_frame(
+                &request.device_name,
+                Some(timestamp),
+                request.browser_url.as_deref(),
+                request.app_name.as_deref(),
+                request.window_name.as_deref(),
+                request.focused,
+            )
+            .await
+            .map_err(db_error)?;
+        self.db
+            .insert_ocr_text(
+                frame_id,
+                &request.text,
+                request.text_json.as_deref().unwrap_or(""),
+                Arc::new(OcrEngine::default()),
+            )
+            .await
+            .map_err(db_error)?;
+        if !request.tags.is_empty() {
+            self.db
+                .add_tags(frame_id, TagContentType::Vision, request.tags)
+                .await
+                .map_err(db_error)?;
+        }
+
+        Ok(Response::new(proto::InsertResponse { id: frame_id }))
+    }
+
+    async fn insert_transcription(
+        &self,
+        request: Request<proto::InsertTranscriptionRequest>,
+    ) -> Result<Response<proto::InsertResponse>, Status> {
+        self.authorize(&request, ApiScope::Write).await?;
+        let request = request.into_inner();
+        if request.device_name.is_empty() {
+            return Err(Status::invalid_argument("device_name is required"));
+        }
+
+        let audio_chunk_id = self.db.insert_audio_chunk("").await.map_err(db_error)?;
+        self.db
+            .insert_audio_transcription(
+                audio_chunk_id,
+                &request.transcription,
+                -1,
+                &request.transcription_engine,
+                &AudioDevice {
+                    name: request.device_name,
+                    device_type: if request.is_input_device {
+                        DeviceType::Input
+                    } else {
+                        DeviceType::Output
+                    },
+                },
+                request.speaker_id,
+                None,
+                None,
+            )
+            .await
+            .map_err(db_error)?;
+
+        Ok(Response::new(proto::InsertResponse { id: audio_chunk_id }))
+    }
+
+    async fn get_tags(
+        &self,
+        request: Request<proto::GetTagsRequest>,
+    ) -> Result<Response<proto::TagsResponse>, Status> {
+        self.authorize(&request, ApiScope::ReadSearch).await?;
+        let request = request.into_inner();
+        let tags = self
+            .db
+            .get_tags(request.id, request.content_type().into())
+            .await
+            .map_err(db_error)?;
+        Ok(Response::new(proto::TagsResponse { tags }))
+    }
+
+    async fn add_tags(
+        &self,
+        request: Request<proto::TagsRequest>,
+    ) -> Result<Response<proto::TagsResponse>, Status> {
+        let actor = self.authorize(&request, ApiScope::WriteTags).await?;
+        let request = request.into_inner();
+        let content_type = request.content_type();
+        let target = format!(
+            "{}:{} {}",
+            format!("{:?}", content_type).to_lowercase(),
+            request.id,
+            request.tags.join(",")
+        );
+        self.db
+            .add_tags(request.id, content_type.into(), request.tags)
+            .await
+            .map_err(db_error)?;
+        audit_as(&self.db, &actor, "add_tags", &target, None).await;
+        let tags = self
+            .db
+            .get_tags(request.id, content_type.into())
+            .await
+            .map_err(db_error)?;
+        Ok(Response::new(proto::TagsResponse { tags }))
+    }
+
+    async fn remove_tags(
+        &self,
+        request: Request<proto::TagsRequest>,
+    ) -> Result<Response<proto::TagsResponse>, Status> {
+        let actor = self.authorize(&request, ApiScope::WriteTags).await?;
+        let request = request.into_inner();
+        let content_type = request.content_type();
+        let target = format!(
+            "{}:{} {}",
+            format!("{:?}", content_type).to_lowercase(),
+            request.id,
+            request.tags.join(",")
+        );
+        self.db
+            .remove_tags(request.id, content_type.into(), request.tags)
+            .await
+            .map_err(db_error)?;
+        audit_as(&self.db, &actor, "remove_tags", &target, None).await;
+        let tags = self
+            .db
+            .get_tags(request.id, content_type.into())
+            .await
+            .map_err(db_error)?;
+        Ok(Response::new(proto::TagsResponse { tags }))
+    }
+
+    async fn get_speaker(
+        &self,
+        request: Request<proto::GetSpeakerRequest>,
+    ) -> Result<Response<proto::Speaker>, Status> {
+        self.authorize(&request, ApiScope::ReadSearch).await?;
+        let speaker = self
+            .db
+            .get_speaker_by_id(request.into_inner().id)
+            .await
+            .map_err(db_error)?;
+        Ok(Response::new(speaker.into()))
+    }
+
+    async fn search_speakers(
+        &self,
+        request: Request<proto::SearchSpeakersRequest>,
+    ) -> Result<Response<proto::SpeakersResponse>, Status> {
+        self.authorize(&request, ApiScope::ReadSearch).await?;
+        let speakers = self
+            .db
+            .search_speakers(&request.into_inner().name_prefix)
+            .await
+            .map_err(db_error)?;
+        Ok(Response::new(proto::SpeakersResponse {
+            speakers: speakers.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn list_unnamed_speakers(
+        &self,
+        request: Request<proto::ListUnnamedSpeakersRequest>,
+    ) -> Result<Response<proto::SpeakersResponse>, Status> {
+        self.authorize(&request, ApiScope::ReadSearch).await?;
+        let request = request.into_inner();
+        let speakers = self
+            .db
+            .get_unnamed_speakers(
+                if request.limit == 0 {
+                    DEFAULT_LIMIT
+                } else {
+                    request.limit
+                },
+                request.offset,
+                (!request.speaker_ids.is_empty()).then_some(request.speaker_ids),
+            )
+            .await
+            .map_err(db_error)?;
+        Ok(Response::new(proto::SpeakersResponse {
+            speakers: speakers.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn update_speaker(
+        &self,
+        request: Request<proto::UpdateSpeakerRequest>,
+    ) -> Result<Response<proto::Speaker>, Status> {
+        self.authorize(&request, ApiScope::Write).await?;
+        let request = request.into_inner();
+        if let Some(name) = &request.name {
+            self.db
+                .update_speaker_name(request.id, name)
+                .await
+                .map_err(db_error)?;
+        }
+        if let Some(metadata) = &request.metadata {
+            self.db
+                .update_speaker_metadata(request.id, metadata)
+                .await
+                .map_err(db_error)?;
+        }
+        let speaker = self
+            .db
+            .get_speaker_by_id(request.id)
+            .await
+            .map_err(db_error)?;
+        Ok(Response::new(speaker.into()))
+    }
+
+    type StreamEventsStream = Pin<Box<dyn Stream<Item = Result<proto::Event, Status>> + Send>>;
+
+    async fn stream_events(
+        &self,
+        request: Request<proto::StreamEventsRequest>,
+    ) -> Result<Response<Self::StreamEventsStream>, Status> {
+        self.authorize(&request, ApiScope::ReadSearch).await?;
+        let request = request.into_inner();
+        let stream = subscribe_to_all_events().filter_map(move |mut event| {
+            let wanted = request.names.is_empty() || request.names.contains(&event.name);
+            if wanted && !request.images && (event.name == "ocr_result" || event.name == "ui_frame")
+            {
+                if let Some(data) = event.data.as_object_mut() {
+                    data.remove("image");
+                }
+            }
+            futures::future::ready(wanted.then(|| {
+                Ok(proto::Event {
+                    data: serde_json::to_string(&event.data).unwrap_or_default(),
+                    name: event.name,
+                })
+            }))
+        });
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
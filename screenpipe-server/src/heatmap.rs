@@ -0,0 +1,61 @@
+use chrono::{DateTime, Datelike, Local, TimeZone, Timelike, Utc};
+use oasgen::OaSchema;
+use screenpipe_db::{DatabaseManager, HourlyCount};
+use serde::{Deserialize, Serialize};
+
+/// What was recorded at an hour of a day of the week, over every week of
+/// the period.
+#[derive(OaSchema, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HeatmapCell {
+    /// 0 for monday to 6 for sunday
+    pub weekday: u32,
+    /// 0 to 23
+    pub hour: u32,
+    pub frames: i64,
+    pub transcriptions: i64,
+    pub ui_events: i64,
+}
+
+#[derive(OaSchema, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActivityHeatmap {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// the 168 hours of the week, monday 0:00 first, in local time
+    pub cells: Vec<HeatmapCell>,
+}
+
+/// The activity heatmap of `start..end` in the local timezone.
+pub async fn activity_heatmap(
+    db: &DatabaseManager,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<ActivityHeatmap, sqlx::Error> {
+    let counts = db.get_hourly_counts(start, end).await?;
+    Ok(ActivityHeatmap {
+        start,
+        end,
+        cells: build_heatmap(&counts, &Local),
+    })
+}
+
+/// Adds up the hourly counts by day of the week and hour in `tz`. Counts
+/// are kept per utc hour, in a timezone off by half an hour they go to the
+/// hour they start in.
+pub fn build_heatmap<Tz: TimeZone>(counts: &[HourlyCount], tz: &Tz) -> Vec<HeatmapCell> {
+    let mut cells: Vec<HeatmapCell> = (0..7 * 24)
+        .map(|i| HeatmapCell {
+            weekday: i / 24,
+            hour: i % 24,
+            ..Default::default()
+        })
+        .collect();
+    for count in counts {
+        let local = count.hour.with_timezone(tz);
+        let cell =
+            &mut cells[(local.weekday().num_days_from_monday() * 24 + local.hour()) as usize];
+        cell.frames += count.frames;
+        cell.transcriptions += count.transcriptions;
+        cell.ui_events += count.ui_events;
+    }
+    cells
+}
@@ -0,0 +1,377 @@
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use screenpipe_db::{AppSession, DatabaseManager, JournalMeeting, TranscriptionExport};
+use std::fmt::Write;
+use std::path::Path;
+use std::str::FromStr;
+use tracing::{debug, info};
+
+use crate::journal::local_day;
+use crate::thumbnails::frame_thumbnail;
+
+// transcriptions of a device further apart than this start a new block
+const TRANSCRIPT_GAP_MINUTES: i64 = 2;
+const MAX_BLOCK_LINES: usize = 20;
+const MAX_SNIPPET_CHARS: usize = 600;
+const THUMBNAIL_WIDTH: u32 = 480;
+
+const STYLE: &str = r#"
+body { font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", sans-serif; margin: 2rem auto; max-width: 56rem; padding: 0 1rem; color: #1f2328; }
+h1 { font-size: 1.5rem; }
+h2 { font-size: 1.1rem; margin-top: 2rem; border-bottom: 1px solid #d0d7de; }
+article { margin: 1rem 0; padding: 0.75rem 1rem; border-left: 3px solid #d0d7de; }
+article.meeting { border-color: #8250df; }
+article.audio { border-color: #1a7f37; }
+.time { color: #656d76; font-variant-numeric: tabular-nums; }
+.windows { color: #656d76; font-size: 0.9rem; }
+img { display: block; max-width: 100%; margin: 0.5rem 0; border: 1px solid #d0d7de; }
+blockquote { margin: 0.5rem 0; padding-left: 0.75rem; border-left: 2px solid #eaeef2; color: #424a53; white-space: pre-wrap; }
+ul { margin: 0.5rem 0; padding-left: 1.25rem; }
+footer { margin-top: 3rem; color: #656d76; font-size: 0.8rem; }
+"#;
+
+/// A bound of the exported range: a local date (`2025-03-14`), a local time
+/// (`2025-03-14 09:30`) or an RFC 3339 time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeBound {
+    Date(NaiveDate),
+    Time(DateTime<Utc>),
+}
+
+impl TimeBound {
+    /// The range starts at the beginning of a date.
+    pub fn start(&self) -> DateTime<Utc> {
+        match self {
+            TimeBound::Date(date) => local_day(*date).0,
+            TimeBound::Time(time) => *time,
+        }
+    }
+
+    /// The range ends with the end of a date.
+    pub fn end(&self) -> DateTime<Utc> {
+        match self {
+            TimeBound::Date(date) => local_day(*date).1,
+            TimeBound::Time(time) => *time,
+        }
+    }
+}
+
+impl FromStr for TimeBound {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+            return Ok(TimeBound::Date(date));
+        }
+        if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+            return Ok(TimeBound::Time(time.with_timezone(&Utc)));
+        }
+        ["%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S"]
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+            .and_then(|naive| Local.from_local_datetime(&naive).earliest())
+            .map(|time| TimeBound::Time(time.with_timezone(&Utc)))
+            .ok_or_else(|| {
+                format!(
+                    "invalid time {:?}, expected e.g. \"2025-03-14\" or \"2025-03-14 09:30\"",
+                    s
+                )
+            })
+    }
+}
+
+/// Transcriptions of a device close to each other.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranscriptBlock {
+    pub device: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub lines: Vec<TranscriptionExport>,
+}
+
+/// Groups transcriptions, sorted by time, into blocks per device.
+pub fn transcript_blocks(rows: Vec<TranscriptionExport>) -> Vec<TranscriptBlock> {
+    let gap = Duration::minutes(TRANSCRIPT_GAP_MINUTES);
+    let mut blocks: Vec<TranscriptBlock> = Vec::new();
+    for row in rows {
+        if row.transcription.trim().is_empty() {
+            continue;
+        }
+        let open = blocks
+            .iter_mut()
+            .rev()
+            .find(|b| b.device == row.device)
+            .filter(|b| row.timestamp - b.end <= gap);
+        match open {
+            Some(block) => {
+                block.end = row.timestamp;
+                block.lines.push(row);
+            }
+            None => blocks.push(TranscriptBlock {
+                device: row.device.clone(),
+                start: row.timestamp,
+                end: row.timestamp,
+                lines: vec![row],
+            }),
+        }
+    }
+    blocks
+}
+
+#[derive(Debug, Clone)]
+pub enum TimelineEntry {
+    Session {
+        session: AppSession,
+        /// jpeg of the excerpt frame, base64 encoded
+        thumbnail: Option<String>,
+    },
+    Meeting(JournalMeeting),
+    Speech(TranscriptBlock),
+}
+
+impl TimelineEntry {
+    pub fn start(&self) -> DateTime<Utc> {
+        match self {
+            TimelineEntry::Session { session, .. } => session.start,
+            TimelineEntry::Meeting(meeting) => meeting.meeting.start_time,
+            TimelineEntry::Speech(block) => block.start,
+        }
+    }
+}
+
+/// A small jpeg of the frame, base64 encoded.
+async fn thumbnail(db: &DatabaseManager, frame_id: i64) -> Result<String> {
+    let jpeg = frame_thumbnail(db, frame_id, THUMBNAIL_WIDTH)
+        .await?
+        .ok_or_else(|| anyhow!("frame {} or its media not found", frame_id))?;
+    Ok(general_purpose::STANDARD.encode(jpeg))
+}
+
+/// App sessions, meetings and speech between `start` and `end`, in order.
+/// Sessions showing text get a thumbnail of the frame it was read on unless
+/// `thumbnails` is off.
+pub async fn build_timeline(
+    db: &DatabaseManager,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    thumbnails: bool,
+) -> Result<Vec<TimelineEntry>> {
+    let day = db.get_journal_day(start, end).await?;
+    let mut entries = Vec::new();
+    for session in day.sessions {
+        let thumbnail = match (&session.excerpt, thumbnails) {
+            (Some(excerpt), true) => match thumbnail(db, excerpt.frame_id).await {
+                Ok(thumbnail) => Some(thumbnail),
+                Err(e) => {
+                    debug!("no thumbnail for frame {}: {}", excerpt.frame_id, e);
+                    None
+                }
+            },
+            _ => None,
+        };
+        entries.push(TimelineEntry::Session { session, thumbnail });
+    }
+    entries.extend(day.meetings.into_iter().map(TimelineEntry::Meeting));
+    entries.extend(
+        transcript_blocks(db.get_transcriptions_export(start, end).await?)
+            .into_iter()
+            .map(TimelineEntry::Speech),
+    );
+    entries.sort_by_key(|entry| entry.start());
+    Ok(entries)
+}
+
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn snippet(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= MAX_SNIPPET_CHARS {
+        return text;
+    }
+    let cut: String = text.chars().take(MAX_SNIPPET_CHARS).collect();
+    format!("{}…", cut.trim_end())
+}
+
+/// Renders the timeline as a single HTML page, images embedded, that opens
+/// without the server.
+pub fn render_timeline<Tz: TimeZone>(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    entries: &[TimelineEntry],
+    tz: &Tz,
+) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let local = |t: &DateTime<Utc>| t.with_timezone(tz);
+    let time = |t: &DateTime<Utc>| local(t).format("%H:%M").to_string();
+    let title = format!(
+        "screenpipe timeline, {} – {}",
+        local(&start).format("%Y-%m-%d %H:%M"),
+        local(&end).format("%Y-%m-%d %H:%M")
+    );
+
+    let mut html = String::new();
+    let _ = writeln!(html, "<!DOCTYPE html>");
+    let _ = writeln!(html, "<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">");
+    let _ = writeln!(
+        html,
+        "<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">"
+    );
+    let _ = writeln!(html, "<title>{}</title>", escape_html(&title));
+    let _ = writeln!(html, "<style>{}</style>\n</head>\n<body>", STYLE);
+    let _ = writeln!(html, "<h1>{}</h1>", escape_html(&title));
+    if entries.is_empty() {
+        let _ = writeln!(html, "<p>Nothing recorded in this period.</p>");
+    }
+
+    let mut current_day = None;
+    for entry in entries {
+        let day = local(&entry.start()).date_naive();
+        if current_day != Some(day) {
+            let _ = writeln!(html, "<h2>{}</h2>", day.format("%A %Y-%m-%d"));
+            current_day = Some(day);
+        }
+        match entry {
+            TimelineEntry::Session { session, thumbnail } => {
+                let _ = writeln!(
+                    html,
+                    "<article class=\"session\">\n<div><span class=\"time\">{}–{}</span> <strong>{}</strong> ({})</div>",
+                    time(&session.start),
+                    time(&session.end),
+                    escape_html(&session.app_name),
+                    escape_html(&session.device_name)
+                );
+                if !session.window_names.is_empty() {
+                    let _ = writeln!(
+                        html,
+                        "<div class=\"windows\">{}</div>",
+                        escape_html(&session.window_names.join(" · "))
+                    );
+                }
+                if let Some(thumbnail) = thumbnail {
+                    let _ = writeln!(
+                        html,
+                        "<img src=\"data:image/jpeg;base64,{}\" alt=\"{}\" loading=\"lazy\">",
+                        thumbnail,
+                        escape_html(&session.app_name)
+                    );
+                }
+                if let Some(excerpt) = &session.excerpt {
+                    let _ = writeln!(
+                        html,
+                        "<blockquote>{}</blockquote>",
+                        escape_html(&snippet(&excerpt.text))
+                    );
+                }
+                let _ = writeln!(html, "</article>");
+            }
+            TimelineEntry::Meeting(meeting) => {
+                let end = meeting
+                    .meeting
+                    .end_time
+                    .map(|t| time(&t))
+                    .unwrap_or_else(|| "…".to_string());
+                let _ = writeln!(
+                    html,
+                    "<article class=\"meeting\">\n<div><span class=\"time\">{}–{}</span> <strong>Meeting on {}</strong></div>",
+                    time(&meeting.meeting.start_time),
+                    end,
+                    escape_html(&meeting.meeting.meeting_app)
+                );
+                if !meeting.participants.is_empty() {
+                    let participants: Vec<&str> = meeting
+                        .participants
+                        .iter()
+                        .map(|p| p.name.as_str())
+                        .collect();
+                    let _ = writeln!(
+                        html,
+                        "<div class=\"windows\">with {}</div>",
+                        escape_html(&participants.join(", "))
+                    );
+                }
+                let _ = writeln!(html, "</article>");
+            }
+            TimelineEntry::Speech(block) => {
+                let _ = writeln!(
+                    html,
+                    "<article class=\"audio\">\n<div><span class=\"time\">{}–{}</span> <strong>{}</strong></div>\n<ul>",
+                    time(&block.start),
+                    time(&block.end),
+                    escape_html(&block.device)
+                );
+                for line in block.lines.iter().take(MAX_BLOCK_LINES) {
+                    let speaker = line
+                        .speaker_name
+                        .as_deref()
+                        .map(|name| format!("<strong>{}</strong>: ", escape_html(name)))
+                        .unwrap_or_default();
+                    let _ = writeln!(
+                        html,
+                        "<li><span class=\"time\">{}</span> {}{}</li>",
+                        time(&line.timestamp),
+                        speaker,
+                        escape_html(&snippet(&line.transcription))
+                    );
+                }
+                if block.lines.len() > MAX_BLOCK_LINES {
+                    let _ = writeln!(
+                        html,
+                        "<li>… {} more</li>",
+                        block.lines.len() - MAX_BLOCK_LINES
+                    );
+                }
+                let _ = writeln!(html, "</ul>\n</article>");
+            }
+        }
+    }
+
+    let _ = writeln!(
+        html,
+        "<footer>Exported from screenpipe on {}.</footer>\n</body>\n</html>",
+        Utc::now().with_timezone(tz).format("%Y-%m-%d %H:%M")
+    );
+    html
+}
+
+/// Writes the timeline between `start` and `end` to `output` as a single
+/// HTML file, times in the local timezone. Returns how many entries it has.
+pub async fn export_html(
+    db: &DatabaseManager,
+    output: &Path,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    thumbnails: bool,
+) -> Result<usize> {
+    if end <= start {
+        return Err(anyhow!("the range ends before it starts"));
+    }
+    let entries = build_timeline(db, start, end, thumbnails).await?;
+    let html = render_timeline(start, end, &entries, &Local);
+    if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(output, html).await?;
+    info!(
+        "exported {} timeline entries to {}",
+        entries.len(),
+        output.display()
+    );
+    Ok(entries.len())
+}
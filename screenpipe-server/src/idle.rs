@@ -0,0 +1,47 @@
+use chrono::Utc;
+use screenpipe_db::DatabaseManager;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error};
+
+/// Records as idle the stretches of at least `min_idle` with no screen
+/// change, no transcription and no input, looking at what was stored since
+/// the run before.
+pub struct IdleMonitor {
+    db: Arc<DatabaseManager>,
+    min_idle: chrono::Duration,
+}
+
+impl IdleMonitor {
+    pub fn new(db: Arc<DatabaseManager>, min_idle: chrono::Duration) -> Arc<Self> {
+        Arc::new(Self { db, min_idle })
+    }
+
+    pub fn start(self: &Arc<Self>, interval: Duration) {
+        let monitor = Arc::clone(self);
+        // runs overlap, a slow one leaves nothing out
+        let lookback =
+            chrono::Duration::from_std(interval * 2).unwrap_or_else(|_| chrono::Duration::hours(1));
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let now = Utc::now();
+                match monitor
+                    .db
+                    .detect_idle_intervals(now - lookback, now, monitor.min_idle)
+                    .await
+                {
+                    Ok(intervals) => {
+                        for interval in intervals {
+                            debug!(
+                                "idle: from {} to {}",
+                                interval.started_at, interval.ended_at
+                            );
+                        }
+                    }
+                    Err(e) => error!("idle: failed to detect idle intervals: {}", e),
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,280 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Utc};
+use clap::ValueEnum;
+use image::DynamicImage;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use screenpipe_core::Language;
+use screenpipe_db::{DatabaseManager, VideoMetadata};
+use screenpipe_vision::utils::compare_with_previous_image;
+use screenpipe_vision::{perform_ocr_with_engine, OcrEngine};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+use crate::video_utils::{extract_frames_from_video, extracted_fps, get_video_metadata};
+
+// frames closer than this to the previous one aren't ocr'd again, as when
+// recording
+const MIN_FRAME_DIFFERENCE: f64 = 0.006;
+
+const IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "webp", "bmp"];
+const VIDEO_EXTENSIONS: [&str; 5] = ["mp4", "mov", "mkv", "avi", "webm"];
+
+// "Screenshot 2024-03-14 at 10.22.31 PM", "Screenshot_20240314_102231",
+// "monitor_1_2024-03-14_10-22-31"
+static DATE_TIME_IN_NAME: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(\d{4})[-_.]?(\d{2})[-_.]?(\d{2})(?:[ _T-]+|[ _]at[ _])(\d{1,2})[.:_-]?(\d{2})[.:_-]?(\d{2})(?:\s*([AaPp][Mm]))?",
+    )
+    .unwrap()
+});
+// seconds or milliseconds since the epoch, "1710411751.png"
+static UNIX_TIME_IN_NAME: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?:^|\D)(1\d{9})(\d{3})?(?:\D|$)").unwrap());
+
+/// What is being imported.
+#[derive(Clone, Copy, Debug, ValueEnum, PartialEq)]
+pub enum ImportSource {
+    /// a folder of screenshots
+    Screenshots,
+    /// a folder of screen recordings
+    Recordings,
+    /// the `chunks` folder of Rewind. App and window names stay in its
+    /// encrypted database and aren't imported
+    Rewind,
+}
+
+impl ImportSource {
+    /// Device the imported frames are recorded under.
+    pub fn device_name(&self) -> &'static str {
+        match self {
+            ImportSource::Screenshots => "imported_screenshots",
+            ImportSource::Recordings => "imported_recordings",
+            ImportSource::Rewind => "imported_rewind",
+        }
+    }
+
+    fn accepts(&self, path: &Path) -> bool {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase());
+        match (self, extension.as_deref()) {
+            (ImportSource::Screenshots, Some(e)) => IMAGE_EXTENSIONS.contains(&e),
+            (ImportSource::Recordings, Some(e)) => VIDEO_EXTENSIONS.contains(&e),
+            // rewind chunks are mp4 files without an extension
+            (ImportSource::Rewind, e) => e.is_none() || e == Some("mp4"),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ImportSummary {
+    pub files: usize,
+    /// files imported before, left alone
+    pub skipped: usize,
+    pub failed: usize,
+    pub frames: usize,
+    pub text_chars: usize,
+}
+
+/// When a file was captured according to its name, read in the local
+/// timezone, as screenshot tools name them.
+pub fn timestamp_from_name(name: &str) -> Option<DateTime<Utc>> {
+    if let Some(captures) = DATE_TIME_IN_NAME.captures(name) {
+        let number = |i: usize| captures[i].parse::<u32>().ok();
+        let mut hour = number(4)?;
+        match captures
+            .get(7)
+            .map(|m| m.as_str().to_lowercase())
+            .as_deref()
+        {
+            Some("pm") if hour < 12 => hour += 12,
+            Some("am") if hour == 12 => hour = 0,
+            _ => {}
+        }
+        let naive = NaiveDate::from_ymd_opt(number(1)? as i32, number(2)?, number(3)?)?
+            .and_hms_opt(hour, number(5)?, number(6)?)?;
+        return Local
+            .from_local_datetime(&naive)
+            .earliest()
+            .map(|t| t.with_timezone(&Utc));
+    }
+
+    let captures = UNIX_TIME_IN_NAME.captures(name)?;
+    let secs = captures[1].parse::<i64>().ok()?;
+    let millis = captures
+        .get(2)
+        .and_then(|m| m.as_str().parse::<i64>().ok())
+        .unwrap_or(0);
+    Utc.timestamp_millis_opt(secs * 1000 + millis).single()
+}
+
+fn modified_time(path: &Path) -> Option<DateTime<Utc>> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(DateTime::<Utc>::from(modified))
+}
+
+fn find_import_files(root: &Path, source: ImportSource) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = WalkDir::new(root)
+        .follow_links(true)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .map(|e| e.into_path())
+        .filter(|path| path.is_file() && source.accepts(path))
+        // .DS_Store and the like
+        .filter(|path| {
+            !path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with('.'))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Imports the screenshots or recordings under `path` as frames recorded at
+/// the time they were captured, their text ocr'd with `ocr_engine`. Files are
+/// copied into `data_dir`, the originals are left alone. Files imported
+/// before are skipped, so an interrupted import can be run again.
+pub async fn import(
+    db: Arc<DatabaseManager>,
+    data_dir: &Path,
+    path: &Path,
+    source: ImportSource,
+    ocr_engine: OcrEngine,
+    languages: Vec<Language>,
+) -> Result<ImportSummary> {
+    // files are known by their absolute path, wherever the import is run from
+    let path = path
+        .canonicalize()
+        .map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+    let target_dir = data_dir.join("data");
+    tokio::fs::create_dir_all(&target_dir).await?;
+
+    let files = find_import_files(&path, source);
+    info!(
+        "found {} files to import from {}",
+        files.len(),
+        path.display()
+    );
+
+    let mut summary = ImportSummary::default();
+    for file in files {
+        summary.files += 1;
+        let name = file.to_string_lossy().to_string();
+        if db.has_frames_named(&name).await? {
+            debug!("{} was imported before, skipping", name);
+            summary.skipped += 1;
+            continue;
+        }
+        match import_file(&db, &target_dir, &file, source, &ocr_engine, &languages).await {
+            Ok((frames, text_chars)) => {
+                info!("imported {} frames from {}", frames, name);
+                summary.frames += frames;
+                summary.text_chars += text_chars;
+            }
+            Err(e) => {
+                warn!("failed to import {}: {}", name, e);
+                summary.failed += 1;
+            }
+        }
+    }
+    Ok(summary)
+}
+
+/// Imports one file, returns how many frames and characters of text it had.
+async fn import_file(
+    db: &DatabaseManager,
+    target_dir: &Path,
+    file: &Path,
+    source: ImportSource,
+    ocr_engine: &OcrEngine,
+    languages: &[Language],
+) -> Result<(usize, usize)> {
+    let name = file.to_string_lossy().to_string();
+    let file_name = file.file_name().and_then(|n| n.to_str()).unwrap_or("");
+
+    let (frames, creation_time, fps, duration) = match source {
+        ImportSource::Screenshots => {
+            let image = image::open(file)?;
+            let creation_time = timestamp_from_name(file_name)
+                .or_else(|| modified_time(file))
+                .unwrap_or_else(Utc::now);
+            (vec![image], creation_time, 1.0, 0.0)
+        }
+        ImportSource::Recordings | ImportSource::Rewind => {
+            let metadata = get_video_metadata(&name).await?;
+            let creation_time = match source {
+                // chunks are written once recorded, and carry no time
+                ImportSource::Rewind => modified_time(file)
+                    .map(|t| t - Duration::milliseconds((metadata.duration * 1000.0) as i64))
+                    .unwrap_or(metadata.creation_time),
+                _ => timestamp_from_name(file_name).unwrap_or(metadata.creation_time),
+            };
+            let frames = extract_frames_from_video(file, None).await?;
+            (
+                frames,
+                creation_time,
+                extracted_fps(metadata.fps),
+                metadata.duration,
+            )
+        }
+    };
+    if frames.is_empty() {
+        return Err(anyhow!("no frames found"));
+    }
+
+    let extension = match file.extension().and_then(|e| e.to_str()) {
+        Some(extension) => extension.to_lowercase(),
+        None => "mp4".to_string(),
+    };
+    let target = target_dir.join(format!("{}.{}", Uuid::new_v4(), extension));
+    tokio::fs::copy(file, &target).await?;
+
+    let frame_ids = db
+        .create_video_with_frames(
+            &target.to_string_lossy(),
+            frames.clone(),
+            VideoMetadata {
+                creation_time,
+                fps,
+                duration,
+                device_name: Some(source.device_name().to_string()),
+                name: Some(name),
+            },
+        )
+        .await?;
+
+    let mut text_chars = 0;
+    let mut previous: Option<&DynamicImage> = None;
+    for (i, (frame, frame_id)) in frames.iter().zip(frame_ids).enumerate() {
+        if let Some(previous) = previous {
+            let difference =
+                compare_with_previous_image(Some(previous), frame, &mut None, i as u64, &mut 0.0)
+                    .await?;
+            if difference < MIN_FRAME_DIFFERENCE {
+                continue;
+            }
+        }
+        previous = Some(frame);
+
+        let (text, text_json, _) = perform_ocr_with_engine(ocr_engine, frame, languages.to_vec())
+            .await
+            .map_err(|e| anyhow!(e.to_string()))?;
+        text_chars += text.len();
+        db.insert_ocr_text(
+            frame_id,
+            &text,
+            &text_json,
+            Arc::new(ocr_engine.clone().into()),
+        )
+        .await?;
+    }
+    Ok((frames.len(), text_chars))
+}
@@ -0,0 +1,179 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration as ChronoDuration, DurationRound, Utc};
+use screenpipe_db::{DatabaseManager, InputActivity};
+use screenpipe_vision::adaptive_rate::record_input;
+use screenpipe_vision::capture_screenshot_by_window::focused_window;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, error};
+
+use crate::clipboard::clipboard_ignored;
+use crate::pause::{is_paused, CapturePauses};
+
+// a keypress after a longer pause starts a new typing burst
+const TYPING_PAUSE_SECS: i64 = 3;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputKind {
+    KeyPress,
+    Click,
+    Scroll,
+}
+
+/// Counts input per minute and focused app until it is taken.
+#[derive(Debug, Default)]
+pub struct InputCounter {
+    minutes: BTreeMap<(DateTime<Utc>, String), InputActivity>,
+    last_keypress: Option<DateTime<Utc>>,
+}
+
+impl InputCounter {
+    pub fn record(&mut self, kind: InputKind, at: DateTime<Utc>, app_name: &str) {
+        let timestamp = at.duration_trunc(ChronoDuration::minutes(1)).unwrap_or(at);
+        let minute = self
+            .minutes
+            .entry((timestamp, app_name.to_string()))
+            .or_insert_with(|| InputActivity {
+                timestamp,
+                app_name: app_name.to_string(),
+                keypresses: 0,
+                typing_bursts: 0,
+                clicks: 0,
+                scrolls: 0,
+            });
+        match kind {
+            InputKind::KeyPress => {
+                minute.keypresses += 1;
+                if self.last_keypress.map_or(true, |last| {
+                    at - last > ChronoDuration::seconds(TYPING_PAUSE_SECS)
+                }) {
+                    minute.typing_bursts += 1;
+                }
+                self.last_keypress = Some(at);
+            }
+            InputKind::Click => minute.clicks += 1,
+            InputKind::Scroll => minute.scrolls += 1,
+        }
+    }
+
+    /// The counts so far, oldest minute first, starting over from none.
+    pub fn take(&mut self) -> Vec<InputActivity> {
+        std::mem::take(&mut self.minutes).into_values().collect()
+    }
+}
+
+/// Counts keypresses, typing bursts, clicks and scrolls per minute and
+/// focused app, never which keys were pressed, and tells the adaptive
+/// capture rate the user is active. Input in ignored windows is counted
+/// without the app, nothing is counted while all capture is paused.
+pub struct InputActivityMonitor {
+    db: Arc<DatabaseManager>,
+    ignored_windows: Vec<String>,
+    pauses: Option<Arc<CapturePauses>>,
+    counter: Mutex<InputCounter>,
+    focused_app: Mutex<String>,
+    paused: AtomicBool,
+}
+
+impl InputActivityMonitor {
+    pub fn new(
+        db: Arc<DatabaseManager>,
+        ignored_windows: Vec<String>,
+        pauses: Option<Arc<CapturePauses>>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            db,
+            ignored_windows,
+            pauses,
+            counter: Mutex::new(InputCounter::default()),
+            focused_app: Mutex::new(String::new()),
+            paused: AtomicBool::new(false),
+        })
+    }
+
+    /// Listens to input on a thread of its own, checking the focused app
+    /// every `interval` and storing the counts every minute.
+    pub fn start(self: &Arc<Self>, interval: Duration) {
+        let listener = Arc::clone(self);
+        std::thread::spawn(move || {
+            let result = rdev::listen(move |event| {
+                let kind = match event.event_type {
+                    rdev::EventType::KeyPress(_) => InputKind::KeyPress,
+                    rdev::EventType::ButtonPress(_) => InputKind::Click,
+                    rdev::EventType::Wheel { .. } => InputKind::Scroll,
+                    rdev::EventType::MouseMove { .. } => {
+                        if !listener.paused.load(Ordering::Relaxed) {
+                            record_input();
+                        }
+                        return;
+                    }
+                    _ => return,
+                };
+                listener.record(kind, Utc::now());
+            });
+            if let Err(e) = result {
+                error!("input activity: failed to listen to input: {:?}", e);
+            }
+        });
+
+        let monitor = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut last_flush = Instant::now();
+            loop {
+                tokio::time::sleep(interval).await;
+                match tokio::task::spawn_blocking(focused_window).await {
+                    Ok(focused) => monitor.refresh(focused),
+                    Err(e) => error!("input activity: {}", e),
+                }
+                if last_flush.elapsed() < FLUSH_INTERVAL {
+                    continue;
+                }
+                last_flush = Instant::now();
+                match monitor.flush().await {
+                    Ok(minutes) => debug!("input activity: stored {} minutes", minutes),
+                    Err(e) => error!("input activity: failed to store: {}", e),
+                }
+            }
+        });
+    }
+
+    /// Takes `focused`, the (app, window) with focus, as the app input is
+    /// counted for, and whether capture is paused.
+    pub fn refresh(&self, focused: Option<(String, String)>) {
+        let app_name = match focused {
+            Some((app_name, window_name))
+                if !clipboard_ignored(&self.ignored_windows, &app_name, &window_name) =>
+            {
+                app_name
+            }
+            _ => String::new(),
+        };
+        *self.focused_app.lock().unwrap() = app_name;
+        if let Some(pauses) = &self.pauses {
+            self.paused.store(
+                is_paused(&pauses.status().pauses, "input"),
+                Ordering::Relaxed,
+            );
+        }
+    }
+
+    pub fn record(&self, kind: InputKind, at: DateTime<Utc>) {
+        if self.paused.load(Ordering::Relaxed) {
+            return;
+        }
+        record_input();
+        let app_name = self.focused_app.lock().unwrap().clone();
+        self.counter.lock().unwrap().record(kind, at, &app_name);
+    }
+
+    /// Stores the counts so far. Returns how many minutes of an app were
+    /// stored.
+    pub async fn flush(&self) -> Result<usize> {
+        let minutes = self.counter.lock().unwrap().take();
+        self.db.insert_input_activity(&minutes).await?;
+        Ok(minutes.len())
+    }
+}
@@ -0,0 +1,214 @@
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use screenpipe_db::{DatabaseManager, JournalDay, JournalMeeting};
+use std::fmt::Write;
+
+// sessions shorter than this are left out of the timeline
+const MIN_SESSION_SECS: i64 = 60;
+const MAX_EXCERPT_CHARS: usize = 280;
+
+/// Start and end of `date` in the local timezone.
+pub fn local_day(date: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+    let bound = |date: NaiveDate| {
+        let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+        Local
+            .from_local_datetime(&midnight)
+            .earliest()
+            .map(|t| t.with_timezone(&Utc))
+            .unwrap_or_else(|| Utc.from_utc_datetime(&midnight))
+    };
+    (bound(date), bound(date.succ_opt().unwrap_or(date)))
+}
+
+/// The journal of `date`, in the local timezone.
+pub async fn export_journal(
+    db: &DatabaseManager,
+    date: NaiveDate,
+    server_url: &str,
+) -> Result<String, sqlx::Error> {
+    let (start, end) = local_day(date);
+    let day = db.get_journal_day(start, end).await?;
+    Ok(render_journal(date, &day, &Local, server_url))
+}
+
+/// A meeting with its participants and transcript, in the local timezone.
+pub async fn export_meeting(db: &DatabaseManager, meeting_id: i64) -> Result<String, sqlx::Error> {
+    let meeting = JournalMeeting {
+        meeting: db.get_meeting(meeting_id).await?,
+        participants: db.get_meeting_participants(meeting_id).await?,
+        transcript: db.get_meeting_transcript(meeting_id).await?,
+    };
+    Ok(render_meeting_export(&meeting, &Local))
+}
+
+/// Renders a meeting on its own as Markdown, titled after the calendar or
+/// the app it was on.
+pub fn render_meeting_export<Tz: TimeZone>(meeting: &JournalMeeting, tz: &Tz) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let start = meeting.meeting.start_time.with_timezone(tz);
+    let title = meeting
+        .meeting
+        .title
+        .clone()
+        .unwrap_or_else(|| format!("Meeting on {}", meeting.meeting.meeting_app));
+    let mut md = String::new();
+    let _ = writeln!(
+        md,
+        "# {}
+",
+        title
+    );
+    match meeting.meeting.end_time {
+        Some(end) => {
+            let _ = writeln!(
+                md,
+                "{}–{} ({} min)\n",
+                start.format("%Y-%m-%d %H:%M"),
+                end.with_timezone(tz).format("%H:%M"),
+                (end - meeting.meeting.start_time).num_minutes()
+            );
+        }
+        None => {
+            let _ = writeln!(md, "{}–…\n", start.format("%Y-%m-%d %H:%M"));
+        }
+    }
+    render_meeting(&mut md, meeting, tz);
+    md
+}
+
+/// `text` on one line, cut to the length of an excerpt.
+pub(crate) fn shorten(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= MAX_EXCERPT_CHARS {
+        return text;
+    }
+    let cut: String = text.chars().take(MAX_EXCERPT_CHARS).collect();
+    format!("{}…", cut.trim_end())
+}
+
+/// Renders a day as Markdown: a timeline of app sessions with an excerpt of
+/// what was read, the meetings with their transcript, and the tags used.
+/// Frames link to `server_url`, audio to the recorded files.
+pub fn render_journal<Tz: TimeZone>(
+    date: NaiveDate,
+    day: &JournalDay,
+    tz: &Tz,
+    server_url: &str,
+) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let time = |t: &DateTime<Utc>| t.with_timezone(tz).format("%H:%M").to_string();
+    let server_url = server_url.trim_end_matches('/');
+    let mut md = String::new();
+
+    let _ = writeln!(md, "# Journal {}", date.format("%Y-%m-%d"));
+
+    let sessions: Vec<_> = day
+        .sessions
+        .iter()
+        .filter(|s| (s.end - s.start).num_seconds() >= MIN_SESSION_SECS)
+        .collect();
+    if !sessions.is_empty() {
+        let _ = writeln!(md, "\n## Timeline\n");
+        for session in sessions {
+            let _ = writeln!(
+                md,
+                "- **{}–{}** {} ({} min, {})",
+                time(&session.start),
+                time(&session.end),
+                session.app_name,
+                (session.end - session.start).num_minutes(),
+                session.device_name
+            );
+            if !session.window_names.is_empty() {
+                let _ = writeln!(md, "  - windows: {}", session.window_names.join(", "));
+            }
+            if let Some(excerpt) = &session.excerpt {
+                let _ = writeln!(
+                    md,
+                    "  - > {} [frame]({}/frames/{})",
+                    shorten(&excerpt.text),
+                    server_url,
+                    excerpt.frame_id
+                );
+            }
+        }
+    }
+
+    if !day.meetings.is_empty() {
+        let _ = writeln!(md, "\n## Meetings");
+        for meeting in &day.meetings {
+            let end = meeting
+                .meeting
+                .end_time
+                .map(|t| time(&t))
+                .unwrap_or_else(|| "…".to_string());
+            let _ = writeln!(
+                md,
+                "\n### {}–{} {}\n",
+                time(&meeting.meeting.start_time),
+                end,
+                meeting.meeting.meeting_app
+            );
+            render_meeting(&mut md, meeting, tz);
+        }
+    }
+
+    if !day.tags.is_empty() {
+        let _ = writeln!(md, "\n## Tags\n");
+        for tag in &day.tags {
+            let _ = writeln!(
+                md,
+                "- `{}`: {} frames, {} audio chunks",
+                tag.name, tag.frames, tag.audio_chunks
+            );
+        }
+    }
+
+    if day.sessions.is_empty() && day.meetings.is_empty() {
+        let _ = writeln!(md, "\nNothing recorded this day.");
+    }
+    md
+}
+
+/// Appends the participants and transcript of `meeting` to `md`.
+pub fn render_meeting<Tz: TimeZone>(md: &mut String, meeting: &JournalMeeting, tz: &Tz)
+where
+    Tz::Offset: std::fmt::Display,
+{
+    if !meeting.participants.is_empty() {
+        let participants: Vec<String> = meeting
+            .participants
+            .iter()
+            .map(|p| {
+                if p.is_local {
+                    format!("{} (me)", p.name)
+                } else {
+                    p.name.clone()
+                }
+            })
+            .collect();
+        let _ = writeln!(md, "Participants: {}\n", participants.join(", "));
+    }
+    if meeting.transcript.is_empty() {
+        let _ = writeln!(md, "_no transcript_");
+    }
+    for line in &meeting.transcript {
+        let speaker = line.speaker_name.as_deref().unwrap_or(&line.device_name);
+        let offset = line
+            .start_time
+            .map(|s| format!(" at {:.0}s", s))
+            .unwrap_or_default();
+        let _ = writeln!(
+            md,
+            "- **{}** {}: {} ([audio{}](<{}>))",
+            line.timestamp.with_timezone(tz).format("%H:%M"),
+            speaker,
+            line.text.trim(),
+            offset,
+            line.file_path
+        );
+    }
+}
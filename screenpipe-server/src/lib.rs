@@ -1,16 +1,74 @@
 mod add;
+pub mod audit;
+pub mod auth;
 mod auto_destruct;
+pub mod calendar;
 pub mod chunking;
 pub mod cli;
+pub mod clipboard;
+pub mod clock_sync;
+pub mod content_v2;
 pub mod core;
+pub mod db_bench;
+pub mod devices;
+pub mod digest;
+pub mod extensions;
 pub mod filtering;
+pub mod focus_sessions;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod heatmap;
+pub mod html_export;
+pub mod idle;
+pub mod import;
+pub mod input_activity;
+pub mod journal;
+pub mod local_only;
+pub mod maintenance;
+pub mod media_encryption;
+pub mod media_gc;
+pub mod media_integrity;
+pub mod meeting_detection;
+pub mod obsidian;
+pub mod ocr_providers;
+pub mod outage_monitor;
+pub mod parquet_export;
+pub mod participants;
+pub mod pause;
+pub mod peers;
 pub mod pipe_manager;
+pub mod plugins;
+pub mod productivity;
+pub mod push;
+pub mod rate_limit;
+pub mod receipts;
 mod resource_monitor;
+mod retention;
+pub mod scripts;
+pub mod secret_masking;
 mod server;
+pub mod shadow;
+pub mod slides;
+pub mod storage_quota;
+pub mod subject_erasure;
+pub mod summary;
+pub mod sync;
+pub mod tag_suggestions;
 pub mod text_embeds;
+pub mod text_pipeline;
+pub mod thumbnails;
+pub mod topics;
+pub mod transcription_providers;
+pub mod usage_csv;
 mod video;
 pub mod video_cache;
+pub mod video_compaction;
 pub mod video_utils;
+pub mod watchdog;
+mod webhook_rules;
+pub mod work_sessions;
 pub use add::handle_index_command;
 pub use auto_destruct::watch_pid;
 pub use axum::Json as JsonResponse;
@@ -18,13 +76,16 @@ pub use cli::Cli;
 pub use core::start_continuous_recording;
 pub use pipe_manager::PipeManager;
 pub use resource_monitor::{ResourceMonitor, RestartSignal};
+pub use retention::RetentionManager;
 pub use screenpipe_core::Language;
 pub use server::health_check;
+pub use server::openapi_spec;
 pub use server::AppState;
 pub use server::ContentItem;
 pub use server::HealthCheckResponse;
 pub use server::PaginatedResponse;
 pub use server::SCServer;
 pub use server::{api_list_monitors, MonitorInfo};
-pub use video::VideoCapture;
+pub use video::{VideoCapture, VideoCodec, VideoContainer, VideoEncoder, VideoEncoding};
+pub use webhook_rules::WebhookDispatcher;
 pub mod embedding;
@@ -0,0 +1,138 @@
+use anyhow::Result;
+use oasgen::OaSchema;
+use screenpipe_core::egress;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::cli::{Cli, CliAudioTranscriptionEngine, CliOcrEngine};
+use crate::outage_monitor::Notifier;
+use crate::sync::S3Config;
+
+static TELEMETRY: AtomicBool = AtomicBool::new(false);
+
+/// Local-only mode as `/health` reports it, to check nothing leaves the
+/// machine.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LocalOnlyStatus {
+    pub enabled: bool,
+    /// built with the `local-only` feature, the mode can't be turned off
+    pub enforced_by_build: bool,
+    pub telemetry: bool,
+    /// outbound calls refused since the start
+    pub blocked_requests: u64,
+    /// scheme and host of the last call refused
+    pub last_blocked: Option<String>,
+}
+
+pub fn status() -> LocalOnlyStatus {
+    LocalOnlyStatus {
+        enabled: egress::local_only(),
+        enforced_by_build: egress::BUILD_LOCAL_ONLY,
+        telemetry: TELEMETRY.load(Ordering::SeqCst),
+        blocked_requests: egress::blocked_requests(),
+        last_blocked: egress::last_blocked(),
+    }
+}
+
+// deepgram is used through a local proxy when its url is set to one
+fn env_url_is_local(var: &str) -> bool {
+    std::env::var(var).is_ok_and(|url| egress::is_local_url(&url))
+}
+
+/// The flags asking for something that reaches another machine.
+pub fn violations(cli: &Cli) -> Vec<String> {
+    let mut violations = Vec::new();
+    if cli.audio_transcription_engine == CliAudioTranscriptionEngine::Deepgram
+        && !env_url_is_local("DEEPGRAM_API_URL")
+    {
+        violations.push("deepgram transcription is a cloud service".to_string());
+    }
+    if cli.enable_realtime_audio_transcription && !env_url_is_local("DEEPGRAM_WEBSOCKET_URL") {
+        violations.push("realtime transcription streams to deepgram".to_string());
+    }
+    if cli.ocr_engine == CliOcrEngine::Unstructured {
+        violations.push("unstructured ocr is a cloud service".to_string());
+    }
+    for provider in &cli.remote_ocr_provider {
+        if !egress::is_local_url(&provider.url) {
+            violations.push(format!("ocr provider {} is not local", provider.name));
+        }
+    }
+    if cli
+        .device_transcription_provider
+        .iter()
+        .any(|device| device.provider == "deepgram")
+        && !env_url_is_local("DEEPGRAM_API_URL")
+    {
+        violations.push("deepgram transcription is a cloud service".to_string());
+    }
+    for provider in &cli.remote_transcription_provider {
+        if !egress::is_local_url(&provider.url) {
+            violations.push(format!(
+                "transcription provider {} is not local",
+                provider.name
+            ));
+        }
+    }
+    if cli.enable_llm {
+        violations.push("the local llm downloads its model on start".to_string());
+    }
+    if let Some(smtp_url) = &cli.smtp_url {
+        if !egress::is_local_url(smtp_url) {
+            violations.push("the digest smtp server is not on this machine".to_string());
+        }
+    }
+    if let Some(webhook) = &cli.trash_purge_webhook {
+        if !egress::is_local_url(webhook) {
+            violations.push(format!("trash purge webhook {} is not local", webhook));
+        }
+    }
+    if let Some(url) = &cli.tag_suggestion_url {
+        if !egress::is_local_url(url) {
+            violations.push(format!("tag suggestion llm {} is not local", url));
+        }
+    }
+    for notifier in &cli.outage_notifier {
+        if let Notifier::Webhook(url) = notifier {
+            if !egress::is_local_url(url) {
+                violations.push(format!("outage webhook {} is not local", url));
+            }
+        }
+    }
+    if cli.enable_peer_discovery {
+        violations.push("peer discovery searches the other machines of the lan".to_string());
+    }
+    for peer in &cli.peer {
+        if !egress::is_local_url(&peer.url) {
+            violations.push(format!("peer {} is not local", peer.name));
+        }
+    }
+    if cli.sync_bucket.is_some() {
+        let endpoint = cli
+            .sync_endpoint
+            .clone()
+            .unwrap_or_else(|| S3Config::aws_endpoint(&cli.sync_region));
+        if !egress::is_local_url(&endpoint) {
+            violations.push(format!("sync bucket {} is not local", endpoint));
+        }
+    }
+    violations
+}
+
+/// Turns local-only mode on for `--local-only` and local-only builds:
+/// telemetry is turned off and the flags that would reach the network fail
+/// the start. Every outbound call made afterwards is checked, by the
+/// clients of `egress::http_client` built from then on.
+pub fn enforce(cli: &mut Cli) -> Result<()> {
+    egress::set_local_only(cli.local_only);
+    if egress::local_only() {
+        cli.local_only = true;
+        cli.disable_telemetry = true;
+        let violations = violations(cli);
+        if !violations.is_empty() {
+            anyhow::bail!("local-only mode: {}", violations.join(", "));
+        }
+    }
+    TELEMETRY.store(!cli.disable_telemetry, Ordering::SeqCst);
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use oasgen::OaSchema;
+use screenpipe_db::{DatabaseManager, MaintenanceStep};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, error, info};
+
+/// Past this many `min_interval`s without maintenance it runs even while
+/// capturing, for machines that are never idle.
+const MAX_OVERDUE_FACTOR: i32 = 4;
+
+#[derive(OaSchema, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MaintenanceStatus {
+    pub running: bool,
+    /// no frame, audio or ui event stored lately, at the last check
+    pub capture_idle: bool,
+    pub last_started: Option<DateTime<Utc>>,
+    pub last_finished: Option<DateTime<Utc>>,
+    pub last_steps: Vec<MaintenanceStep>,
+    /// why the last run could not start at all
+    pub last_error: Option<String>,
+}
+
+/// Whether nothing was captured since `idle_after` before `now`, from the
+/// latest frame, audio and ui timestamps.
+pub fn is_capture_idle(
+    latest: &[Option<DateTime<Utc>>],
+    now: DateTime<Utc>,
+    idle_after: ChronoDuration,
+) -> bool {
+    latest
+        .iter()
+        .flatten()
+        .all(|timestamp| now - *timestamp >= idle_after)
+}
+
+/// Runs `DatabaseManager::run_maintenance` at most every `min_interval`,
+/// waiting for capture to be idle unless the last run is long overdue.
+pub struct MaintenanceScheduler {
+    db: Arc<DatabaseManager>,
+    min_interval: ChronoDuration,
+    idle_after: ChronoDuration,
+    created: DateTime<Utc>,
+    status: Mutex<MaintenanceStatus>,
+}
+
+impl MaintenanceScheduler {
+    pub fn new(
+        db: Arc<DatabaseManager>,
+        min_interval: ChronoDuration,
+        idle_after: ChronoDuration,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            db,
+            min_interval,
+            idle_after,
+            created: Utc::now(),
+            status: Mutex::new(MaintenanceStatus::default()),
+        })
+    }
+
+    pub fn start(self: &Arc<Self>, interval: Duration) {
+        let scheduler = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                match scheduler.run_if_due(Utc::now()).await {
+                    Ok(true) => info!("maintenance: database maintenance done"),
+                    Ok(false) => debug!("maintenance: not due or capture not idle"),
+                    Err(e) => error!("maintenance: failed to check capture activity: {}", e),
+                }
+            }
+        });
+    }
+
+    pub fn status(&self) -> MaintenanceStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// Runs maintenance if it is due at `now`. Returns whether it ran.
+    pub async fn run_if_due(&self, now: DateTime<Utc>) -> Result<bool> {
+        let last_started = self.status.lock().unwrap().last_started;
+        if last_started.map_or(false, |last| now - last < self.min_interval) {
+            return Ok(false);
+        }
+
+        let (frame, audio, ui) = self.db.get_latest_timestamps().await?;
+        let idle = is_capture_idle(&[frame, audio, ui], now, self.idle_after);
+        self.status.lock().unwrap().capture_idle = idle;
+        let overdue =
+            now - last_started.unwrap_or(self.created) >= self.min_interval * MAX_OVERDUE_FACTOR;
+        if !idle && !overdue {
+            return Ok(false);
+        }
+
+        self.run(now).await;
+        Ok(true)
+    }
+
+    /// Runs maintenance now, whatever capture is doing.
+    pub async fn run(&self, now: DateTime<Utc>) {
+        {
+            let mut status = self.status.lock().unwrap();
+            status.running = true;
+            status.last_started = Some(now);
+        }
+        let result = self.db.run_maintenance().await;
+        let mut status = self.status.lock().unwrap();
+        status.running = false;
+        status.last_finished = Some(Utc::now());
+        match result {
+            Ok(steps) => {
+                status.last_steps = steps;
+                status.last_error = None;
+            }
+            Err(e) => {
+                error!("maintenance: failed to start: {}", e);
+                status.last_error = Some(e.to_string());
+            }
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use anyhow::Result;
+use chrono::{Duration as ChronoDuration, Utc};
+use screenpipe_core::media_crypto::{self, MediaKey};
+use screenpipe_db::{DatabaseManager, MediaChunkKind};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tempfile::TempPath;
+use tracing::{debug, error, info, warn};
+
+// chunks encrypted per table at each pass
+const ENCRYPT_BATCH_SIZE: u32 = 50;
+// audio chunks younger than this may still be read by transcription
+const AUDIO_SETTLE_MINUTES: i64 = 5;
+
+/// Name of the key file in the data dir, unless `--media-key-file` says
+/// otherwise.
+pub const MEDIA_KEY_FILE: &str = "media.key";
+
+static MEDIA_KEY: OnceLock<MediaKey> = OnceLock::new();
+
+/// The key media files are decrypted with, once it was loaded.
+pub fn media_key() -> Option<&'static MediaKey> {
+    MEDIA_KEY.get()
+}
+
+pub fn set_media_key(key: MediaKey) {
+    if MEDIA_KEY.set(key).is_err() {
+        warn!("media encryption key was set already, keeping the first one");
+    }
+}
+
+/// Reads the key at `path`, `None` when there's no key file.
+pub async fn load_media_key(path: &Path) -> Result<Option<MediaKey>> {
+    if !tokio::fs::try_exists(path).await? {
+        return Ok(None);
+    }
+    let key = tokio::fs::read_to_string(path).await?;
+    key.parse()
+        .map(Some)
+        .map_err(|e| anyhow::anyhow!("invalid media key in {}: {}", path.display(), e))
+}
+
+/// Reads the key at `path`, creating a random one readable by the user only
+/// when there's none. Losing the key loses every encrypted recording.
+pub async fn load_or_create_media_key(path: &Path) -> Result<MediaKey> {
+    if let Some(key) = load_media_key(path).await? {
+        return Ok(key);
+    }
+    let key = MediaKey::generate();
+    tokio::fs::write(path, key.to_hex()).await?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        tokio::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600)).await?;
+    }
+    info!(
+        "created a media encryption key, stored in {}",
+        path.display()
+    );
+    Ok(key)
+}
+
+/// A media file ffmpeg can read: the file itself when it's plaintext, a
+/// temporary decrypted copy, removed on drop, when it's encrypted.
+pub struct PlainMedia {
+    path: String,
+    decrypted: Option<TempPath>,
+}
+
+impl PlainMedia {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn was_encrypted(&self) -> bool {
+        self.decrypted.is_some()
+    }
+}
+
+/// Decrypts `file_path` to a temporary file when it's encrypted, kept for as
+/// long as the returned value lives.
+pub async fn plain_media(file_path: &str) -> Result<PlainMedia> {
+    let path = PathBuf::from(file_path);
+    if let Err(e) = crate::sync::hydrate_media(&path).await {
+        warn!(
+            "failed to download {} from the sync bucket: {}",
+            file_path, e
+        );
+    }
+    let encrypted = {
+        let path = path.clone();
+        tokio::task::spawn_blocking(move || media_crypto::is_encrypted_file(&path)).await?
+    };
+    // missing files are reported by whatever reads them
+    if !matches!(encrypted, Ok(true)) {
+        return Ok(PlainMedia {
+            path: file_path.to_string(),
+            decrypted: None,
+        });
+    }
+    let key = media_key()
+        .ok_or_else(|| anyhow::anyhow!("{} is encrypted and there's no media key", file_path))?
+        .clone();
+    // ffmpeg guesses the container from the extension
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+    let decrypted = tokio::task::spawn_blocking(move || -> std::io::Result<TempPath> {
+        let output = tempfile::Builder::new()
+            .prefix("screenpipe-media-")
+            .suffix(&extension)
+            .tempfile()?
+            .into_temp_path();
+        media_crypto::decrypt_file(&key, &path, &output)?;
+        Ok(output)
+    })
+    .await??;
+    debug!("decrypted {} to {}", file_path, decrypted.display());
+    Ok(PlainMedia {
+        path: decrypted.to_string_lossy().into_owned(),
+        decrypted: Some(decrypted),
+    })
+}
+
+/// Encrypts `path` in place with the media key. Returns false when there's
+/// no key or the file is encrypted already.
+pub async fn encrypt_media_file(path: &Path) -> Result<bool> {
+    let Some(key) = media_key().cloned() else {
+        return Ok(false);
+    };
+    let path = path.to_path_buf();
+    Ok(tokio::task::spawn_blocking(move || media_crypto::encrypt_file(&key, &path)).await??)
+}
+
+/// Encrypts video and audio chunks with AES-256-GCM once they are written,
+/// video chunks when the next chunk of their monitor starts and audio chunks
+/// a few minutes after they were recorded. The frame and audio endpoints, the
+/// frame cache and the html export decrypt them to temporary files to read
+/// them.
+pub struct MediaEncryptor {
+    db: Arc<DatabaseManager>,
+}
+
+impl MediaEncryptor {
+    pub fn new(db: Arc<DatabaseManager>, key: MediaKey) -> Arc<Self> {
+        set_media_key(key);
+        Arc::new(Self { db })
+    }
+
+    pub fn start(self: &Arc<Self>, interval: Duration) {
+        let encryptor = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                match encryptor.encrypt_pending().await {
+                    Ok(0) => {}
+                    Ok(encrypted) => info!("media encryption: encrypted {} chunks", encrypted),
+                    Err(e) => error!("media encryption: failed: {}", e),
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+
+    /// Encrypts the chunks done being written. Returns how many files were
+    /// encrypted.
+    pub async fn encrypt_pending(&self) -> Result<u64> {
+        let mut encrypted = 0;
+        let videos = self
+            .db
+            .list_unencrypted_video_chunks(ENCRYPT_BATCH_SIZE)
+            .await?;
+        for (id, file_path) in videos {
+            encrypted += self.encrypt(MediaChunkKind::Video, id, &file_path).await?;
+        }
+
+        let before = Utc::now() - ChronoDuration::minutes(AUDIO_SETTLE_MINUTES);
+        let audio = self
+            .db
+            .list_unencrypted_audio_chunks(before, ENCRYPT_BATCH_SIZE)
+            .await?;
+        for (id, file_path) in audio {
+            encrypted += self.encrypt(MediaChunkKind::Audio, id, &file_path).await?;
+        }
+        Ok(encrypted)
+    }
+
+    async fn encrypt(&self, kind: MediaChunkKind, id: i64, file_path: &str) -> Result<u64> {
+        let path = Path::new(file_path);
+        let encrypted = if tokio::fs::try_exists(path).await? {
+            match encrypt_media_file(path).await {
+                Ok(encrypted) => encrypted,
+                Err(e) => {
+                    // left for the next pass
+                    warn!("media encryption: failed to encrypt {}: {}", file_path, e);
+                    return Ok(0);
+                }
+            }
+        } else {
+            // deleted files have nothing left to encrypt
+            debug!("media encryption: {} is gone", file_path);
+            false
+        };
+        self.db.mark_chunk_encrypted(kind, id).await?;
+        Ok(encrypted as u64)
+    }
+}
@@ -0,0 +1,168 @@
+use anyhow::{bail, Result};
+use screenpipe_db::DatabaseManager;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::{info, warn};
+use walkdir::WalkDir;
+
+// only these are taken for recordings, whiteboard photos and the like stay
+const MEDIA_EXTENSIONS: [&str; 9] = [
+    "mp4", "mov", "mkv", "webm", "wav", "mp3", "m4a", "flac", "ogg",
+];
+
+/// A chunk whose file is gone.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DanglingChunk {
+    /// "video" or "audio"
+    pub kind: String,
+    pub id: i64,
+    pub file_path: String,
+}
+
+/// What a garbage collection found, and deleted when asked to.
+#[derive(Debug, Default, Serialize)]
+pub struct GcReport {
+    /// media files of the data directory no chunk refers to
+    pub orphaned_files: Vec<String>,
+    pub orphaned_bytes: u64,
+    /// chunks whose file is gone, and that weren't synced
+    pub dangling_chunks: Vec<DanglingChunk>,
+    pub deleted_files: u64,
+    /// dangling chunks whose file path was cleared, their frames and
+    /// transcriptions stay
+    pub cleared_chunks: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GcOptions {
+    pub delete_files: bool,
+    pub clear_missing: bool,
+    /// files modified more recently may be a chunk being created
+    pub min_age: Duration,
+}
+
+fn is_media(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| MEDIA_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+}
+
+/// The media files under `media_dir` older than `min_age` whose canonical
+/// path isn't in `referenced`, with their size.
+fn orphaned_files(
+    media_dir: &Path,
+    referenced: &HashSet<PathBuf>,
+    min_age: Duration,
+) -> Vec<(PathBuf, u64)> {
+    let now = SystemTime::now();
+    WalkDir::new(media_dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file() && is_media(entry.path()))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            let age = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .unwrap_or_default();
+            if age < min_age {
+                return None;
+            }
+            let path = entry.path().canonicalize().ok()?;
+            if referenced.contains(&path) {
+                None
+            } else {
+                Some((path, metadata.len()))
+            }
+        })
+        .collect()
+}
+
+/// Finds the media files of `data_dir` no chunk refers to, and the chunks
+/// whose file is gone, then deletes the files and clears the chunks' file
+/// path as `options` asks. Paths are compared once canonical, so a data
+/// directory reached through another path doesn't look orphaned. Chunks
+/// synced to a bucket aren't dangling, their file can be downloaded again.
+///
+/// Refuses to run when the media directory is missing or most chunks
+/// point at files that are gone, both look like a data directory that was
+/// moved or isn't mounted rather than garbage.
+pub async fn collect_garbage(
+    db: &DatabaseManager,
+    data_dir: &Path,
+    options: GcOptions,
+) -> Result<GcReport> {
+    let media_dir = data_dir.join("data");
+    if !media_dir.is_dir() {
+        bail!(
+            "{} doesn't exist, is the data directory right and mounted?",
+            media_dir.display()
+        );
+    }
+
+    let mut report = GcReport::default();
+    let mut referenced = HashSet::new();
+    let (mut video_ids, mut audio_ids) = (Vec::new(), Vec::new());
+    let references = db.list_media_references().await?;
+    let total = references.len();
+    for (kind, id, file_path, synced) in references {
+        match Path::new(&file_path).canonicalize() {
+            Ok(path) => {
+                referenced.insert(path);
+            }
+            Err(_) if synced => {}
+            Err(_) => {
+                match kind.as_str() {
+                    "video" => video_ids.push(id),
+                    _ => audio_ids.push(id),
+                }
+                report.dangling_chunks.push(DanglingChunk {
+                    kind,
+                    id,
+                    file_path,
+                });
+            }
+        }
+    }
+
+    if report.dangling_chunks.len() * 2 > total {
+        bail!(
+            "{} of {} recordings point at files that are gone, the media may have been moved: \
+             refusing to collect garbage",
+            report.dangling_chunks.len(),
+            total
+        );
+    }
+
+    let min_age = options.min_age;
+    let orphans =
+        tokio::task::spawn_blocking(move || orphaned_files(&media_dir, &referenced, min_age))
+            .await?;
+    for (path, bytes) in orphans {
+        if options.delete_files {
+            match tokio::fs::remove_file(&path).await {
+                Ok(()) => report.deleted_files += 1,
+                Err(e) => warn!("gc: failed to remove {}: {}", path.display(), e),
+            }
+        }
+        report.orphaned_bytes += bytes;
+        report
+            .orphaned_files
+            .push(path.to_string_lossy().into_owned());
+    }
+
+    if options.clear_missing && !report.dangling_chunks.is_empty() {
+        report.cleared_chunks = db.clear_missing_media(&video_ids, &audio_ids).await?;
+    }
+    info!(
+        "gc: {} orphaned files, {} dangling chunks, {} files deleted and {} chunks cleared",
+        report.orphaned_files.len(),
+        report.dangling_chunks.len(),
+        report.deleted_files,
+        report.cleared_chunks
+    );
+    Ok(report)
+}
@@ -0,0 +1,220 @@
+use anyhow::Result;
+use chrono::{Duration as ChronoDuration, Utc};
+use oasgen::OaSchema;
+use screenpipe_db::{DatabaseManager, MediaChunkKind};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+
+use crate::video_utils::{count_video_frames, validate_media};
+
+// chunks checked at each pass, one after the other
+const VERIFY_BATCH_SIZE: u32 = 50;
+// audio chunks this recent may still be written
+const AUDIO_SETTLE_MINUTES: i64 = 5;
+
+/// What a verification pass found.
+#[derive(OaSchema, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VerificationReport {
+    pub checked: u64,
+    /// chunks left with an issue, see /media/issues
+    pub broken: u64,
+    /// chunks whose issue was fixed
+    pub healed: u64,
+}
+
+impl VerificationReport {
+    fn record(&mut self, issue: Option<&str>, healed: bool) {
+        self.checked += 1;
+        self.broken += issue.is_some() as u64;
+        self.healed += healed as u64;
+    }
+}
+
+/// Checks the file of every video and audio chunk exists and decodes, and
+/// that a video holds the frames pointing to it, again every `recheck_after`.
+/// Broken chunks get their issue recorded. With `heal`, chunks whose file
+/// moved within the screenpipe directory are pointed to it, and the frames
+/// past the end of a truncated video are deleted.
+pub struct MediaVerifier {
+    db: Arc<DatabaseManager>,
+    data_dir: PathBuf,
+    recheck_after: ChronoDuration,
+    heal: bool,
+}
+
+impl MediaVerifier {
+    pub fn new(
+        db: Arc<DatabaseManager>,
+        data_dir: PathBuf,
+        recheck_after: ChronoDuration,
+        heal: bool,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            db,
+            data_dir,
+            recheck_after,
+            heal,
+        })
+    }
+
+    pub fn start(self: &Arc<Self>, interval: Duration) {
+        let verifier = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                match verifier.verify_pending().await {
+                    Ok(report) if report.checked == 0 => {}
+                    Ok(report) => info!(
+                        "media verification: checked {} chunks, {} broken, {} healed",
+                        report.checked, report.broken, report.healed
+                    ),
+                    Err(e) => error!("media verification: failed: {}", e),
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+
+    /// Checks the chunks due for it.
+    pub async fn verify_pending(&self) -> Result<VerificationReport> {
+        let now = Utc::now();
+        let verified_before = now - self.recheck_after;
+        let mut report = VerificationReport::default();
+
+        let videos = self
+            .db
+            .list_unverified_video_chunks(verified_before, VERIFY_BATCH_SIZE)
+            .await?;
+        for (id, file_path, last_offset) in videos {
+            let (issue, healed) = self.verify_video(id, &file_path, last_offset).await?;
+            report.record(issue, healed);
+        }
+
+        let audio = self
+            .db
+            .list_unverified_audio_chunks(
+                now - ChronoDuration::minutes(AUDIO_SETTLE_MINUTES),
+                verified_before,
+                VERIFY_BATCH_SIZE,
+            )
+            .await?;
+        for (id, file_path) in audio {
+            let (issue, healed) = self.verify_audio(id, &file_path).await?;
+            report.record(issue, healed);
+        }
+        Ok(report)
+    }
+
+    /// Returns the issue left and whether one was fixed.
+    async fn verify_video(
+        &self,
+        id: i64,
+        file_path: &str,
+        last_offset: i64,
+    ) -> Result<(Option<&'static str>, bool)> {
+        let Some((file_path, mut healed)) =
+            self.locate(MediaChunkKind::Video, id, file_path).await?
+        else {
+            self.db
+                .set_chunk_verified(MediaChunkKind::Video, id, Some("missing"))
+                .await?;
+            return Ok((Some("missing"), false));
+        };
+
+        let issue = match count_video_frames(&file_path).await {
+            Err(e) => {
+                debug!("media verification: {} doesn't decode: {}", file_path, e);
+                Some("undecodable")
+            }
+            Ok(frames) if last_offset >= frames as i64 => {
+                if self.heal && frames > 0 {
+                    let pruned = self.db.delete_frames_past_end(id, frames as i64).await?;
+                    info!(
+                        "media verification: deleted {} frames past the end of {}",
+                        pruned.frames, file_path
+                    );
+                    // none of its frames were left, nor the chunk
+                    for path in pruned.video_files {
+                        if let Err(e) = tokio::fs::remove_file(&path).await {
+                            warn!("media verification: failed to remove {}: {}", path, e);
+                        }
+                    }
+                    healed = true;
+                    None
+                } else {
+                    Some("missing_frames")
+                }
+            }
+            Ok(_) => None,
+        };
+        self.db
+            .set_chunk_verified(MediaChunkKind::Video, id, issue)
+            .await?;
+        Ok((issue, healed))
+    }
+
+    async fn verify_audio(&self, id: i64, file_path: &str) -> Result<(Option<&'static str>, bool)> {
+        let Some((file_path, healed)) = self.locate(MediaChunkKind::Audio, id, file_path).await?
+        else {
+            self.db
+                .set_chunk_verified(MediaChunkKind::Audio, id, Some("missing"))
+                .await?;
+            return Ok((Some("missing"), false));
+        };
+        let issue = match validate_media(&file_path).await {
+            Ok(()) => None,
+            Err(e) => {
+                debug!("media verification: {} doesn't decode: {}", file_path, e);
+                Some("undecodable")
+            }
+        };
+        self.db
+            .set_chunk_verified(MediaChunkKind::Audio, id, issue)
+            .await?;
+        Ok((issue, healed))
+    }
+
+    /// The file of a chunk, and whether it had moved and the chunk was
+    /// pointed to it. None when it's nowhere to be found.
+    async fn locate(
+        &self,
+        kind: MediaChunkKind,
+        id: i64,
+        file_path: &str,
+    ) -> Result<Option<(String, bool)>> {
+        if tokio::fs::try_exists(file_path).await? {
+            return Ok(Some((file_path.to_string(), false)));
+        }
+        if !self.heal {
+            return Ok(None);
+        }
+        let Some(moved) = moved_file(&self.data_dir, file_path).await else {
+            return Ok(None);
+        };
+        warn!(
+            "media verification: {} moved to {}",
+            file_path,
+            moved.display()
+        );
+        let moved = moved.to_string_lossy().into_owned();
+        self.db.relocate_chunk(kind, id, &moved).await?;
+        Ok(Some((moved, true)))
+    }
+}
+
+/// A file of the same name as the missing `file_path` in the media directory
+/// of `data_dir`, or in `data_dir` itself.
+pub async fn moved_file(data_dir: &Path, file_path: &str) -> Option<PathBuf> {
+    let file_name = Path::new(file_path).file_name()?;
+    for dir in [data_dir.join("data"), data_dir.to_path_buf()] {
+        let candidate = dir.join(file_name);
+        if candidate != Path::new(file_path)
+            && tokio::fs::try_exists(&candidate).await.unwrap_or(false)
+        {
+            return Some(candidate);
+        }
+    }
+    None
+}
@@ -0,0 +1,47 @@
+use chrono::Utc;
+use screenpipe_db::DatabaseManager;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error};
+
+// a meeting is followed across runs this far back, so one going on is
+// extended rather than split
+const LOOKBACK_MINUTES: i64 = 30;
+
+/// Detects meetings from call windows, voices and calendar hints stored
+/// since the runs before, and links them to what was said.
+pub struct MeetingDetector {
+    db: Arc<DatabaseManager>,
+}
+
+impl MeetingDetector {
+    pub fn new(db: Arc<DatabaseManager>) -> Arc<Self> {
+        Arc::new(Self { db })
+    }
+
+    pub fn start(self: &Arc<Self>, interval: Duration) {
+        let detector = Arc::clone(self);
+        let lookback = chrono::Duration::minutes(LOOKBACK_MINUTES)
+            .max(chrono::Duration::from_std(interval * 2).unwrap_or_default());
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let now = Utc::now();
+                match detector.db.detect_meetings(now - lookback, now).await {
+                    Ok(meetings) => {
+                        for meeting in meetings {
+                            debug!(
+                                "meetings: {} on '{}' from {} to {:?}",
+                                meeting.id,
+                                meeting.meeting_app,
+                                meeting.start_time,
+                                meeting.end_time
+                            );
+                        }
+                    }
+                    Err(e) => error!("meetings: failed to detect meetings: {}", e),
+                }
+            }
+        });
+    }
+}
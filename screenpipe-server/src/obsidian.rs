@@ -0,0 +1,299 @@
+use anyhow::bail;
+use chrono::{Local, NaiveDate, TimeZone};
+use clap::ValueEnum;
+use screenpipe_db::{DatabaseManager, JournalMeeting};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error, info};
+
+use crate::journal::{local_day, render_journal, render_meeting};
+
+const GENERATED_START: &str = "%% screenpipe:start %%";
+const GENERATED_END: &str = "%% screenpipe:end %%";
+const MEETINGS_FOLDER: &str = "meetings";
+
+/// Notes kept in the vault.
+#[derive(Clone, Copy, Debug, ValueEnum, PartialEq)]
+pub enum ObsidianNotes {
+    /// a note per day
+    Daily,
+    /// a note per meeting
+    Meetings,
+    All,
+}
+
+impl ObsidianNotes {
+    fn daily(&self) -> bool {
+        matches!(self, ObsidianNotes::Daily | ObsidianNotes::All)
+    }
+
+    fn meetings(&self) -> bool {
+        matches!(self, ObsidianNotes::Meetings | ObsidianNotes::All)
+    }
+}
+
+/// A note as screenpipe writes it. `id` goes in the front matter and finds
+/// the note again even once renamed, `file_name` is only used to create it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Note {
+    pub id: String,
+    pub file_name: String,
+    /// front matter lines besides the id
+    pub front_matter: Vec<String>,
+    pub body: String,
+}
+
+pub fn daily_note(date: NaiveDate, journal: String) -> Note {
+    let date = date.format("%Y-%m-%d").to_string();
+    Note {
+        id: format!("day-{}", date),
+        file_name: format!("{}.md", date),
+        front_matter: vec![
+            format!("date: {}", date),
+            "tags: [screenpipe, journal]".to_string(),
+        ],
+        body: journal,
+    }
+}
+
+pub fn meeting_note<Tz: TimeZone>(meeting: &JournalMeeting, tz: &Tz) -> Note
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let start = meeting.meeting.start_time.with_timezone(tz);
+    let end = meeting.meeting.end_time.map(|t| t.with_timezone(tz));
+    let app = &meeting.meeting.meeting_app;
+    let participants: Vec<&str> = meeting
+        .participants
+        .iter()
+        .map(|p| p.name.as_str())
+        .collect();
+
+    let mut front_matter = vec![
+        format!("date: {}", start.format("%Y-%m-%d")),
+        format!("start: {}", start.to_rfc3339()),
+    ];
+    if let Some(end) = &end {
+        front_matter.push(format!("end: {}", end.to_rfc3339()));
+    }
+    // json strings and arrays are valid yaml, and quote whatever names hold
+    front_matter.push(format!(
+        "app: {}",
+        serde_json::to_string(app).unwrap_or_default()
+    ));
+    front_matter.push(format!(
+        "participants: {}",
+        serde_json::to_string(&participants).unwrap_or_default()
+    ));
+    front_matter.push("tags: [screenpipe, meeting]".to_string());
+
+    let mut body = format!(
+        "# {} {}–{} {}\n\n",
+        start.format("%Y-%m-%d"),
+        start.format("%H:%M"),
+        end.map(|t| t.format("%H:%M").to_string())
+            .unwrap_or_else(|| "…".to_string()),
+        app
+    );
+    render_meeting(&mut body, meeting, tz);
+
+    Note {
+        id: format!("meeting-{}", meeting.meeting.id),
+        file_name: format!(
+            "{}/{} {}.md",
+            MEETINGS_FOLDER,
+            start.format("%Y-%m-%d %H.%M"),
+            sanitize_file_name(app)
+        ),
+        front_matter,
+        body,
+    }
+}
+
+/// `name` without the characters vaults can't have in file names.
+fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' | '#' | '^' | '[' | ']' => '-',
+            c => c,
+        })
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// The `screenpipe_id` in the front matter of a note, if it has one.
+pub fn note_id(content: &str) -> Option<String> {
+    let front_matter = content.strip_prefix("---\n")?;
+    front_matter
+        .lines()
+        .take_while(|line| line.trim_end() != "---")
+        .find_map(|line| line.strip_prefix("screenpipe_id:"))
+        .map(|id| id.trim().trim_matches('"').to_string())
+        .filter(|id| !id.is_empty())
+}
+
+/// Content of `note`, keeping what was written after the generated part of
+/// the `existing` one. The front matter and the generated part are
+/// rewritten.
+pub fn merge_note(note: &Note, existing: Option<&str>) -> String {
+    let kept = existing
+        .and_then(|existing| existing.split_once(GENERATED_END))
+        .map(|(_, after)| after.strip_prefix('\n').unwrap_or(after))
+        .unwrap_or("");
+
+    let mut content = format!("---\nscreenpipe_id: {}\n", note.id);
+    for line in &note.front_matter {
+        let _ = writeln!(content, "{}", line);
+    }
+    let _ = write!(
+        content,
+        "---\n{}\n{}\n{}\n{}",
+        GENERATED_START,
+        note.body.trim_end(),
+        GENERATED_END,
+        kept
+    );
+    content
+}
+
+/// Keeps a note per day and per meeting up to date in an Obsidian vault.
+/// Each note carries a stable id in its front matter, so every pass updates
+/// the note it wrote before, even renamed or moved within the folder,
+/// rather than adding another one. What is written below the generated part
+/// of a note is kept.
+pub struct ObsidianSync {
+    db: Arc<DatabaseManager>,
+    vault: PathBuf,
+    folder: String,
+    notes: ObsidianNotes,
+    /// where frame links point
+    server_url: String,
+}
+
+impl ObsidianSync {
+    pub fn new(
+        db: Arc<DatabaseManager>,
+        vault: PathBuf,
+        folder: String,
+        notes: ObsidianNotes,
+        server_url: String,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            db,
+            vault,
+            folder,
+            notes,
+            server_url,
+        })
+    }
+
+    pub fn start(self: &Arc<Self>, interval: Duration) {
+        let sync = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                match sync.sync().await {
+                    Ok(0) => debug!("obsidian: notes up to date"),
+                    Ok(written) => info!("obsidian: updated {} notes", written),
+                    Err(e) => error!("obsidian: failed to sync notes: {}", e),
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+
+    /// Writes the notes of yesterday, which may still have changed after
+    /// midnight, and today. Returns how many notes changed.
+    pub async fn sync(&self) -> anyhow::Result<usize> {
+        if !self.vault.is_dir() {
+            bail!("vault {} not found", self.vault.display());
+        }
+        let dir = self.vault.join(&self.folder);
+        let mut index = index_notes(&dir);
+
+        let today = Local::now().date_naive();
+        let mut written = 0;
+        for date in [today.pred_opt().unwrap_or(today), today] {
+            let (start, end) = local_day(date);
+            let day = self.db.get_journal_day(start, end).await?;
+
+            let mut notes = Vec::new();
+            if self.notes.daily() && !(day.sessions.is_empty() && day.meetings.is_empty()) {
+                notes.push(daily_note(
+                    date,
+                    render_journal(date, &day, &Local, &self.server_url),
+                ));
+            }
+            if self.notes.meetings() {
+                notes.extend(day.meetings.iter().map(|m| meeting_note(m, &Local)));
+            }
+
+            for note in notes {
+                let (path, changed) = write_note(&dir, &note, index.get(&note.id))?;
+                index.insert(note.id, path);
+                if changed {
+                    written += 1;
+                }
+            }
+        }
+        Ok(written)
+    }
+}
+
+/// Notes written before in `dir`, by id.
+fn index_notes(dir: &Path) -> HashMap<String, PathBuf> {
+    let mut index = HashMap::new();
+    for dir in [dir.to_path_buf(), dir.join(MEETINGS_FOLDER)] {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.extension().and_then(|e| e.to_str()) != Some("md") {
+                continue;
+            }
+            if let Some(id) = fs::read_to_string(&path).ok().and_then(|c| note_id(&c)) {
+                index.insert(id, path);
+            }
+        }
+    }
+    index
+}
+
+/// Writes `note` over its previous version at `existing`, or to a new file.
+/// Returns where it is and whether its content changed.
+fn write_note(
+    dir: &Path,
+    note: &Note,
+    existing: Option<&PathBuf>,
+) -> std::io::Result<(PathBuf, bool)> {
+    let mut path = match existing {
+        Some(path) => path.clone(),
+        None => dir.join(&note.file_name),
+    };
+    // never overwrite a note of the user that happens to have the name
+    if existing.is_none() && path.exists() {
+        path.set_extension("");
+        let mut name = path.into_os_string();
+        name.push(" (screenpipe).md");
+        path = PathBuf::from(name);
+    }
+
+    let previous = fs::read_to_string(&path).ok();
+    let content = merge_note(note, previous.as_deref());
+    if previous.as_deref() == Some(content.as_str()) {
+        return Ok((path, false));
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // the vault never sees a half written note
+    let tmp = path.with_extension("md.tmp");
+    fs::write(&tmp, content)?;
+    fs::rename(&tmp, &path)?;
+    Ok((path, true))
+}
@@ -0,0 +1,77 @@
+use anyhow::Result;
+use screenpipe_vision::{
+    custom_ocr::CustomOcrConfig, ocr_provider, ocr_providers, register_ocr_provider,
+    RemoteOcrProvider,
+};
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::info;
+
+/// An OCR service to register as a provider, `<name>[@<version>]=<url>`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteOcrProviderArg {
+    pub name: String,
+    pub version: String,
+    pub url: String,
+}
+
+impl FromStr for RemoteOcrProviderArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid ocr provider {:?}, expected name[@version]=url", s);
+        let (id, url) = s.split_once('=').ok_or_else(invalid)?;
+        let (name, version) = id.split_once('@').unwrap_or((id, "unversioned"));
+        let (name, version, url) = (name.trim(), version.trim(), url.trim());
+        if name.is_empty() || name.contains('/') || version.is_empty() {
+            return Err(invalid());
+        }
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(invalid());
+        }
+        Ok(Self {
+            name: name.to_string(),
+            version: version.to_string(),
+            url: url.to_string(),
+        })
+    }
+}
+
+/// Registers the OCR services of `--remote-ocr-provider`, then checks the
+/// provider selected with `--ocr-provider` was registered, by them or by
+/// the program embedding screenpipe.
+pub fn register_providers(remote: &[RemoteOcrProviderArg], selected: Option<&str>) -> Result<()> {
+    for provider in remote {
+        register_ocr_provider(Arc::new(RemoteOcrProvider::new(
+            &provider.name,
+            &provider.version,
+            CustomOcrConfig {
+                api_url: provider.url.clone(),
+                api_key: std::env::var("SCREENPIPE_REMOTE_OCR_API_KEY").unwrap_or_default(),
+                ..Default::default()
+            },
+        )));
+        info!(
+            "registered ocr provider {}@{}",
+            provider.name, provider.version
+        );
+    }
+    if let Some(name) = selected {
+        if ocr_provider(name).is_none() {
+            let registered: Vec<String> = ocr_providers()
+                .iter()
+                .map(|provider| provider.name().to_string())
+                .collect();
+            anyhow::bail!(
+                "no ocr provider named {}, registered: {}",
+                name,
+                if registered.is_empty() {
+                    "none".to_string()
+                } else {
+                    registered.join(", ")
+                }
+            );
+        }
+    }
+    Ok(())
+}
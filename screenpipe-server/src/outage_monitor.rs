@@ -0,0 +1,410 @@
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, NaiveTime, Utc, Weekday};
+use reqwest::Client;
+use screenpipe_core::egress;
+use screenpipe_db::{DatabaseManager, DeviceActivity, DeviceOcrFailures};
+use screenpipe_events::send_event;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, warn};
+
+const NOTIFY_TIMEOUT: Duration = Duration::from_secs(10);
+// the frames of this many minutes are looked at for OCR failures, once
+// their text had time to be stored
+const OCR_WINDOW_MINUTES: i64 = 10;
+const OCR_GRACE_SECS: i64 = 60;
+// fewer frames say too little about OCR
+const MIN_OCR_FRAMES: i64 = 10;
+// notification server of the desktop app
+const DESKTOP_NOTIFY_URL: &str = "http://localhost:11435/notify";
+
+/// Hours capture is expected to run, in local time, written `09:00-18:00` or
+/// `mon-fri 09:00-18:00`. A window can go past midnight (`22:00-02:00`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordingHours {
+    /// first and last day, `None` for every day
+    pub days: Option<(Weekday, Weekday)>,
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl RecordingHours {
+    pub fn contains(&self, at: NaiveDateTime) -> bool {
+        let time = at.time();
+        let (in_window, day) = if self.start <= self.end {
+            (time >= self.start && time < self.end, at.weekday())
+        } else if time >= self.start {
+            (true, at.weekday())
+        } else {
+            // past midnight, the window started the day before
+            (time < self.end, at.weekday().pred())
+        };
+        in_window
+            && self.days.map_or(true, |(first, last)| {
+                let (day, first, last) = (
+                    day.num_days_from_monday(),
+                    first.num_days_from_monday(),
+                    last.num_days_from_monday(),
+                );
+                if first <= last {
+                    first <= day && day <= last
+                } else {
+                    day >= first || day <= last
+                }
+            })
+    }
+}
+
+impl FromStr for RecordingHours {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid recording hours {:?}, expected e.g. \"09:00-18:00\" or \"mon-fri 09:00-18:00\"",
+                s
+            )
+        };
+        let parts: Vec<&str> = s.split_whitespace().collect();
+        let (days, hours) = match parts.as_slice() {
+            [hours] => (None, *hours),
+            [days, hours] => {
+                let (first, last) = days.split_once('-').unwrap_or((days, days));
+                let first = first.parse::<Weekday>().map_err(|_| invalid())?;
+                let last = last.parse::<Weekday>().map_err(|_| invalid())?;
+                (Some((first, last)), *hours)
+            }
+            _ => return Err(invalid()),
+        };
+        let (start, end) = hours.split_once('-').ok_or_else(invalid)?;
+        let time = |t: &str| NaiveTime::parse_from_str(t.trim(), "%H:%M").map_err(|_| invalid());
+        Ok(RecordingHours {
+            days,
+            start: time(start)?,
+            end: time(end)?,
+        })
+    }
+}
+
+/// Where outage alerts go: `desktop` for a notification from the app, or a
+/// webhook url.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Notifier {
+    Desktop,
+    Webhook(String),
+}
+
+impl FromStr for Notifier {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("desktop") {
+            Ok(Notifier::Desktop)
+        } else if s.starts_with("http://") || s.starts_with("https://") {
+            Ok(Notifier::Webhook(s.to_string()))
+        } else {
+            Err(format!(
+                "invalid notifier {:?}, expected \"desktop\" or a webhook url",
+                s
+            ))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OutageAlert {
+    /// the device stored nothing for longer than allowed
+    Outage {
+        device_name: String,
+        kind: String,
+        last_seen: DateTime<Utc>,
+        silent_minutes: i64,
+    },
+    /// the device records again after an outage
+    Recovered {
+        device_name: String,
+        kind: String,
+        down_minutes: i64,
+    },
+    /// OCR stored no text for too many of the recent frames of the monitor
+    OcrFailures {
+        device_name: String,
+        frames: i64,
+        without_text: i64,
+    },
+    /// OCR stores the text of the monitor again
+    OcrRecovered { device_name: String },
+}
+
+impl OutageAlert {
+    pub fn message(&self) -> String {
+        let content = |kind: &str| if kind == "audio" { "audio" } else { "frames" };
+        match self {
+            OutageAlert::Outage {
+                device_name,
+                kind,
+                silent_minutes,
+                ..
+            } => format!(
+                "no {} recorded from {} for {} minutes",
+                content(kind),
+                device_name,
+                silent_minutes
+            ),
+            OutageAlert::Recovered {
+                device_name,
+                down_minutes,
+                ..
+            } => format!(
+                "{} is recording again after {} minutes",
+                device_name, down_minutes
+            ),
+            OutageAlert::OcrFailures {
+                device_name,
+                frames,
+                without_text,
+            } => format!(
+                "no text read from {} of the last {} frames of {}",
+                without_text, frames, device_name
+            ),
+            OutageAlert::OcrRecovered { device_name } => {
+                format!("text is read from {} again", device_name)
+            }
+        }
+    }
+}
+
+/// Keeps when each device last stored something and decides when to alert.
+/// Each outage is reported once, and once more when the device recovers.
+pub struct OutageTracker {
+    max_silence: chrono::Duration,
+    hours: Vec<RecordingHours>,
+    last_seen: HashMap<(String, String), DateTime<Utc>>,
+    /// devices in an outage, with their last sample when it was reported
+    alerted: HashMap<(String, String), DateTime<Utc>>,
+    /// start of the current recording window, silence before it doesn't count
+    in_hours_since: Option<DateTime<Utc>>,
+}
+
+impl OutageTracker {
+    /// No `hours` means capture should run around the clock.
+    pub fn new(max_silence: chrono::Duration, hours: Vec<RecordingHours>) -> Self {
+        Self {
+            max_silence,
+            hours,
+            last_seen: HashMap::new(),
+            alerted: HashMap::new(),
+            in_hours_since: None,
+        }
+    }
+
+    pub fn check(
+        &mut self,
+        now: DateTime<Utc>,
+        local_now: NaiveDateTime,
+        activity: Vec<DeviceActivity>,
+    ) -> Vec<OutageAlert> {
+        let mut alerts = Vec::new();
+        for device in activity {
+            let key = (device.kind, device.device_name);
+            let last_seen = self
+                .last_seen
+                .entry(key.clone())
+                .or_insert(device.last_seen);
+            *last_seen = (*last_seen).max(device.last_seen);
+
+            if let Some(reported) = self.alerted.get(&key) {
+                if *last_seen > *reported {
+                    alerts.push(OutageAlert::Recovered {
+                        device_name: key.1.clone(),
+                        kind: key.0.clone(),
+                        down_minutes: (*last_seen - *reported).num_minutes(),
+                    });
+                    self.alerted.remove(&key);
+                }
+            }
+        }
+
+        let in_hours = self.hours.is_empty() || self.hours.iter().any(|h| h.contains(local_now));
+        if !in_hours {
+            self.in_hours_since = None;
+            return alerts;
+        }
+        let in_hours_since = *self.in_hours_since.get_or_insert(now);
+
+        let mut silent: Vec<_> = self
+            .last_seen
+            .iter()
+            .filter(|(key, last_seen)| {
+                !self.alerted.contains_key(*key)
+                    && now - (**last_seen).max(in_hours_since) > self.max_silence
+            })
+            .map(|(key, last_seen)| (key.clone(), *last_seen))
+            .collect();
+        silent.sort();
+        for ((kind, device_name), last_seen) in silent {
+            alerts.push(OutageAlert::Outage {
+                device_name: device_name.clone(),
+                kind: kind.clone(),
+                last_seen,
+                silent_minutes: (now - last_seen).num_minutes(),
+            });
+            self.alerted.insert((kind, device_name), last_seen);
+        }
+        alerts
+    }
+}
+
+/// Decides when the share of frames without ocr text of a monitor is worth
+/// an alert. Each spike is reported once, and once more when it's over.
+pub struct OcrFailureTracker {
+    max_percent: u8,
+    failing: HashSet<String>,
+}
+
+impl OcrFailureTracker {
+    pub fn new(max_percent: u8) -> Self {
+        Self {
+            max_percent,
+            failing: HashSet::new(),
+        }
+    }
+
+    pub fn check(&mut self, failures: Vec<DeviceOcrFailures>) -> Vec<OutageAlert> {
+        let mut alerts = Vec::new();
+        for device in failures {
+            if device.frames < MIN_OCR_FRAMES {
+                continue;
+            }
+            let failing = device.without_text * 100 > device.frames * self.max_percent as i64;
+            if failing && self.failing.insert(device.device_name.clone()) {
+                alerts.push(OutageAlert::OcrFailures {
+                    device_name: device.device_name,
+                    frames: device.frames,
+                    without_text: device.without_text,
+                });
+            } else if !failing && self.failing.remove(&device.device_name) {
+                alerts.push(OutageAlert::OcrRecovered {
+                    device_name: device.device_name,
+                });
+            }
+        }
+        alerts
+    }
+}
+
+/// Watches that every device keeps storing frames or audio during the
+/// recording hours, and that OCR reads the frames, and alerts the notifiers
+/// when that stops.
+pub struct OutageMonitor {
+    db: Arc<DatabaseManager>,
+    /// none when silent devices aren't watched
+    tracker: Option<tokio::sync::Mutex<OutageTracker>>,
+    ocr_tracker: Option<tokio::sync::Mutex<OcrFailureTracker>>,
+    notifiers: Vec<Notifier>,
+    client: Client,
+}
+
+impl OutageMonitor {
+    pub fn new(
+        db: Arc<DatabaseManager>,
+        max_silence: Option<chrono::Duration>,
+        hours: Vec<RecordingHours>,
+        max_ocr_failure_percent: Option<u8>,
+        notifiers: Vec<Notifier>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            db,
+            tracker: max_silence
+                .map(|max_silence| tokio::sync::Mutex::new(OutageTracker::new(max_silence, hours))),
+            ocr_tracker: max_ocr_failure_percent
+                .map(|percent| tokio::sync::Mutex::new(OcrFailureTracker::new(percent))),
+            notifiers,
+            client: egress::http_client(),
+        })
+    }
+
+    pub fn start(self: &Arc<Self>, interval: Duration) {
+        let monitor = Arc::clone(self);
+        tokio::spawn(async move {
+            // devices that stored nothing for a week aren't watched
+            let mut since = Utc::now() - chrono::Duration::days(7);
+            loop {
+                let now = Utc::now();
+                let mut alerts = Vec::new();
+                if let Some(tracker) = &monitor.tracker {
+                    match monitor.db.get_latest_timestamps_by_device(since).await {
+                        Ok(activity) => {
+                            // a bit of overlap for rows committed late
+                            since = now - chrono::Duration::minutes(1);
+                            alerts.extend(tracker.lock().await.check(
+                                now,
+                                Local::now().naive_local(),
+                                activity,
+                            ));
+                        }
+                        Err(e) => {
+                            error!("outage monitor: failed to read latest timestamps: {}", e)
+                        }
+                    }
+                }
+                if let Some(ocr_tracker) = &monitor.ocr_tracker {
+                    let end = now - chrono::Duration::seconds(OCR_GRACE_SECS);
+                    match monitor
+                        .db
+                        .get_ocr_failures_by_device(
+                            end - chrono::Duration::minutes(OCR_WINDOW_MINUTES),
+                            end,
+                        )
+                        .await
+                    {
+                        Ok(failures) => alerts.extend(ocr_tracker.lock().await.check(failures)),
+                        Err(e) => error!("outage monitor: failed to read ocr failures: {}", e),
+                    }
+                }
+                for alert in alerts {
+                    monitor.notify(&alert).await;
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+
+    async fn notify(&self, alert: &OutageAlert) {
+        warn!("outage monitor: {}", alert.message());
+        let _ = send_event("capture_outage", alert.clone());
+
+        for notifier in &self.notifiers {
+            if let Notifier::Webhook(url) = notifier {
+                if let Err(e) = egress::check_egress(url) {
+                    error!("outage monitor: {}", e);
+                    continue;
+                }
+            }
+            let request = match notifier {
+                Notifier::Desktop => self.client.post(DESKTOP_NOTIFY_URL).json(&json!({
+                    "title": "screenpipe",
+                    "body": alert.message(),
+                })),
+                Notifier::Webhook(url) => self.client.post(url).json(&json!({
+                    "event": "capture_outage",
+                    "text": alert.message(),
+                    "alert": alert,
+                })),
+            };
+            if let Err(e) = request
+                .timeout(NOTIFY_TIMEOUT)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+            {
+                error!("outage monitor: failed to notify {:?}: {}", notifier, e);
+            }
+        }
+    }
+}
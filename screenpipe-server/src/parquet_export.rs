@@ -0,0 +1,273 @@
+use anyhow::Result;
+use arrow_array::{
+    ArrayRef, BooleanArray, Float64Array, Int64Array, RecordBatch, StringArray,
+    TimestampMillisecondArray,
+};
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{Compression, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use screenpipe_db::{DatabaseManager, FrameExport, OcrTextExport, TranscriptionExport};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{debug, info};
+
+use crate::journal::local_day;
+
+/// Tables exported, a folder each.
+pub const PARQUET_TABLES: [&str; 3] = ["frames", "ocr_text", "audio_transcriptions"];
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ParquetSummary {
+    pub files: usize,
+    /// partitions exported before, left alone
+    pub skipped: usize,
+    pub rows: usize,
+}
+
+/// Where the partition of `table` for `date` goes: hive style, so DuckDB,
+/// pandas or polars read the folder of a table as one table with a `date`
+/// column.
+pub fn partition_path(out_dir: &Path, table: &str, date: NaiveDate) -> PathBuf {
+    out_dir
+        .join(table)
+        .join(format!("date={}", date.format("%Y-%m-%d")))
+        .join(format!("{}.parquet", table))
+}
+
+fn timestamps(values: impl Iterator<Item = DateTime<Utc>>) -> ArrayRef {
+    Arc::new(
+        TimestampMillisecondArray::from_iter_values(values.map(|t| t.timestamp_millis()))
+            .with_timezone("UTC"),
+    )
+}
+
+fn strings<'a>(values: impl Iterator<Item = &'a str>) -> ArrayRef {
+    Arc::new(StringArray::from_iter_values(values))
+}
+
+fn optional_strings<'a>(values: impl Iterator<Item = Option<&'a str>>) -> ArrayRef {
+    Arc::new(values.collect::<StringArray>())
+}
+
+fn ints(values: impl Iterator<Item = i64>) -> ArrayRef {
+    Arc::new(Int64Array::from_iter_values(values))
+}
+
+fn frames_batch(rows: &[FrameExport]) -> Result<RecordBatch> {
+    Ok(RecordBatch::try_from_iter_with_nullable([
+        ("id", ints(rows.iter().map(|r| r.id)), false),
+        (
+            "video_chunk_id",
+            ints(rows.iter().map(|r| r.video_chunk_id)),
+            false,
+        ),
+        (
+            "offset_index",
+            ints(rows.iter().map(|r| r.offset_index)),
+            false,
+        ),
+        (
+            "timestamp",
+            timestamps(rows.iter().map(|r| r.timestamp)),
+            false,
+        ),
+        (
+            "device_name",
+            strings(rows.iter().map(|r| r.device_name.as_str())),
+            false,
+        ),
+        (
+            "file_path",
+            strings(rows.iter().map(|r| r.file_path.as_str())),
+            false,
+        ),
+        (
+            "name",
+            optional_strings(rows.iter().map(|r| r.name.as_deref())),
+            true,
+        ),
+        (
+            "app_name",
+            optional_strings(rows.iter().map(|r| r.app_name.as_deref())),
+            true,
+        ),
+        (
+            "window_name",
+            optional_strings(rows.iter().map(|r| r.window_name.as_deref())),
+            true,
+        ),
+        (
+            "browser_url",
+            optional_strings(rows.iter().map(|r| r.browser_url.as_deref())),
+            true,
+        ),
+        (
+            "focused",
+            Arc::new(rows.iter().map(|r| r.focused).collect::<BooleanArray>()) as ArrayRef,
+            true,
+        ),
+    ])?)
+}
+
+fn ocr_text_batch(rows: &[OcrTextExport]) -> Result<RecordBatch> {
+    Ok(RecordBatch::try_from_iter_with_nullable([
+        ("frame_id", ints(rows.iter().map(|r| r.frame_id)), false),
+        (
+            "timestamp",
+            timestamps(rows.iter().map(|r| r.timestamp)),
+            false,
+        ),
+        ("text", strings(rows.iter().map(|r| r.text.as_str())), false),
+        (
+            "ocr_engine",
+            strings(rows.iter().map(|r| r.ocr_engine.as_str())),
+            false,
+        ),
+        (
+            "text_length",
+            Arc::new(rows.iter().map(|r| r.text_length).collect::<Int64Array>()) as ArrayRef,
+            true,
+        ),
+        (
+            "confidence",
+            Arc::new(rows.iter().map(|r| r.confidence).collect::<Float64Array>()) as ArrayRef,
+            true,
+        ),
+    ])?)
+}
+
+fn transcriptions_batch(rows: &[TranscriptionExport]) -> Result<RecordBatch> {
+    Ok(RecordBatch::try_from_iter_with_nullable([
+        ("id", ints(rows.iter().map(|r| r.id)), false),
+        (
+            "audio_chunk_id",
+            ints(rows.iter().map(|r| r.audio_chunk_id)),
+            false,
+        ),
+        (
+            "offset_index",
+            ints(rows.iter().map(|r| r.offset_index)),
+            false,
+        ),
+        (
+            "timestamp",
+            timestamps(rows.iter().map(|r| r.timestamp)),
+            false,
+        ),
+        (
+            "file_path",
+            strings(rows.iter().map(|r| r.file_path.as_str())),
+            false,
+        ),
+        (
+            "transcription",
+            strings(rows.iter().map(|r| r.transcription.as_str())),
+            false,
+        ),
+        (
+            "device",
+            strings(rows.iter().map(|r| r.device.as_str())),
+            false,
+        ),
+        (
+            "is_input_device",
+            Arc::new(
+                rows.iter()
+                    .map(|r| Some(r.is_input_device))
+                    .collect::<BooleanArray>(),
+            ) as ArrayRef,
+            false,
+        ),
+        (
+            "speaker_id",
+            Arc::new(rows.iter().map(|r| r.speaker_id).collect::<Int64Array>()) as ArrayRef,
+            true,
+        ),
+        (
+            "speaker_name",
+            optional_strings(rows.iter().map(|r| r.speaker_name.as_deref())),
+            true,
+        ),
+        (
+            "transcription_engine",
+            strings(rows.iter().map(|r| r.transcription_engine.as_str())),
+            false,
+        ),
+        (
+            "start_time",
+            Arc::new(rows.iter().map(|r| r.start_time).collect::<Float64Array>()) as ArrayRef,
+            true,
+        ),
+        (
+            "end_time",
+            Arc::new(rows.iter().map(|r| r.end_time).collect::<Float64Array>()) as ArrayRef,
+            true,
+        ),
+    ])?)
+}
+
+/// Writes `batch` to `path`, through a temporary file so readers never see
+/// a partial one.
+fn write_parquet(path: &Path, batch: &RecordBatch) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("parquet.tmp");
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::ZSTD(ZstdLevel::default()))
+        .build();
+    let mut writer = ArrowWriter::try_new(File::create(&tmp)?, batch.schema(), Some(properties))?;
+    writer.write(batch)?;
+    writer.close()?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Exports frames, their text and transcriptions from `from` to `to`, local
+/// dates, a parquet file per table and day. The database is read a day at a
+/// time. Days before yesterday, which won't change anymore, are only
+/// exported once unless `overwrite` is set.
+pub async fn export_parquet(
+    db: &DatabaseManager,
+    out_dir: &Path,
+    from: NaiveDate,
+    to: NaiveDate,
+    overwrite: bool,
+) -> Result<ParquetSummary> {
+    let yesterday = Local::now().date_naive().pred_opt().unwrap_or(to);
+    let mut summary = ParquetSummary::default();
+
+    for date in from.iter_days().take_while(|date| *date <= to) {
+        let (start, end) = local_day(date);
+        for table in PARQUET_TABLES {
+            let path = partition_path(out_dir, table, date);
+            if !overwrite && date < yesterday && path.exists() {
+                summary.skipped += 1;
+                continue;
+            }
+
+            let batch = match table {
+                "frames" => frames_batch(&db.get_frames_export(start, end).await?)?,
+                "ocr_text" => ocr_text_batch(&db.get_ocr_text_export(start, end).await?)?,
+                _ => transcriptions_batch(&db.get_transcriptions_export(start, end).await?)?,
+            };
+            if batch.num_rows() == 0 {
+                continue;
+            }
+            write_parquet(&path, &batch)?;
+            debug!("exported {} rows to {}", batch.num_rows(), path.display());
+            summary.files += 1;
+            summary.rows += batch.num_rows();
+        }
+    }
+
+    info!(
+        "exported {} rows to {} parquet files in {}",
+        summary.rows,
+        summary.files,
+        out_dir.display()
+    );
+    Ok(summary)
+}
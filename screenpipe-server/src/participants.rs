@@ -0,0 +1,183 @@
+use screenpipe_events::MEETING_APPS;
+use std::collections::HashMap;
+
+/// A participant name label read from a video call grid.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParticipantLabel {
+    pub name: String,
+    /// the label carried a "(You)"/"(Me)" marker
+    pub is_local: bool,
+}
+
+// call controls and panel titles that look like names to the layout heuristic
+const UI_LABELS: &[&str] = &[
+    "mute",
+    "unmute",
+    "start video",
+    "stop video",
+    "camera",
+    "mic",
+    "participants",
+    "people",
+    "chat",
+    "share",
+    "share screen",
+    "present now",
+    "record",
+    "recording",
+    "react",
+    "reactions",
+    "raise hand",
+    "leave",
+    "end",
+    "more",
+    "apps",
+    "security",
+    "settings",
+    "captions",
+    "whiteboard",
+    "whiteboards",
+    "summary",
+    "ai companion",
+    "host tools",
+    "breakout rooms",
+    "polls",
+    "notes",
+    "invite",
+    "view",
+    "gallery view",
+    "speaker view",
+    "activity",
+    "meeting details",
+];
+
+const LOCAL_MARKERS: &[&str] = &["you", "me", "host, me", "me, host"];
+const ROLE_MARKERS: &[&str] = &["host", "co-host", "guest", "presenter", "external"];
+
+const MAX_NAME_WORDS: usize = 4;
+const MAX_NAME_LEN: usize = 40;
+
+#[derive(Debug)]
+struct Candidate {
+    label: ParticipantLabel,
+    left: f64,
+    top: f64,
+    width: f64,
+    height: f64,
+}
+
+/// Reads participant name labels out of the OCR blocks of a video call window.
+///
+/// Call apps print each participant's name in a corner of their tile, so the
+/// labels form a grid: a block counts as a participant only if it reads like a
+/// name and lines up with at least one other such block, horizontally or
+/// vertically, at tile distance. This needs block positions, so engines that
+/// don't report them (left/top/width/height) yield nothing.
+pub fn detect_participant_labels(
+    app_name: &str,
+    window_name: &str,
+    text_json: &[HashMap<String, String>],
+) -> Vec<ParticipantLabel> {
+    let app_name = app_name.to_lowercase();
+    let window_name = window_name.to_lowercase();
+    if !MEETING_APPS
+        .iter()
+        .any(|app| app_name.contains(app) || window_name.contains(app))
+    {
+        return Vec::new();
+    }
+
+    let candidates: Vec<Candidate> = text_json
+        .iter()
+        .filter_map(|block| {
+            let label = parse_label(block.get("text")?)?;
+            let coord = |key: &str| block.get(key)?.parse::<f64>().ok();
+            let (left, top, width, height) = (
+                coord("left")?,
+                coord("top")?,
+                coord("width")?,
+                coord("height")?,
+            );
+            (width > 0.0 && height > 0.0).then_some(Candidate {
+                label,
+                left,
+                top,
+                width,
+                height,
+            })
+        })
+        .collect();
+
+    let mut labels: Vec<ParticipantLabel> = Vec::new();
+    for (i, candidate) in candidates.iter().enumerate() {
+        let in_grid = candidates
+            .iter()
+            .enumerate()
+            .any(|(j, other)| i != j && aligned(candidate, other));
+        if in_grid && !labels.iter().any(|l| l.name == candidate.label.name) {
+            labels.push(candidate.label.clone());
+        }
+    }
+    labels
+}
+
+/// Two labels belong to different tiles of the same grid row or column.
+fn aligned(a: &Candidate, b: &Candidate) -> bool {
+    let line_height = a.height.max(b.height);
+    // same row: tops match, and far enough apart not to be words of one line
+    let same_row =
+        (a.top - b.top).abs() <= line_height / 2.0 && horizontal_gap(a, b) > 2.0 * line_height;
+    // same column: left edges match, and far enough apart not to be lines of
+    // one paragraph
+    let same_column =
+        (a.left - b.left).abs() <= line_height && (a.top - b.top).abs() > 3.0 * line_height;
+    same_row || same_column
+}
+
+fn horizontal_gap(a: &Candidate, b: &Candidate) -> f64 {
+    let (first, second) = if a.left <= b.left { (a, b) } else { (b, a) };
+    second.left - (first.left + first.width)
+}
+
+/// Strips role markers ("(Host)", "(You)") and returns the label if what is
+/// left reads like a person's name.
+fn parse_label(text: &str) -> Option<ParticipantLabel> {
+    let mut name = text.trim();
+    let mut is_local = false;
+
+    while let Some(open) = name.rfind('(') {
+        if !name.ends_with(')') {
+            break;
+        }
+        let marker = name[open + 1..name.len() - 1].trim().to_lowercase();
+        if LOCAL_MARKERS.contains(&marker.as_str()) {
+            is_local = true;
+        } else if !ROLE_MARKERS.contains(&marker.as_str()) {
+            return None;
+        }
+        name = name[..open].trim_end();
+    }
+
+    if name.len() < 2 || name.len() > MAX_NAME_LEN {
+        return None;
+    }
+    if UI_LABELS.contains(&name.to_lowercase().as_str()) {
+        return None;
+    }
+
+    let words: Vec<&str> = name.split_whitespace().collect();
+    if words.is_empty() || words.len() > MAX_NAME_WORDS {
+        return None;
+    }
+    let looks_like_name = words.iter().all(|word| {
+        word.chars().next().is_some_and(char::is_uppercase)
+            && word
+                .chars()
+                .all(|c| c.is_alphabetic() || matches!(c, '-' | '\'' | '.'))
+    });
+
+    looks_like_name.then(|| ParticipantLabel {
+        name: words.join(" "),
+        is_local,
+    })
+}
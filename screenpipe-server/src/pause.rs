@@ -0,0 +1,199 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration as ChronoDuration, Local, NaiveDateTime, Utc};
+use oasgen::OaSchema;
+use screenpipe_audio::audio_manager::{AudioManager, AudioManagerStatus};
+use screenpipe_db::{CapturePause, DatabaseManager};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::outage_monitor::RecordingHours;
+
+#[derive(OaSchema, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CapturePauseStatus {
+    pub vision_paused: bool,
+    pub audio_paused: bool,
+    pub ui_paused: bool,
+    /// the pauses running now
+    pub pauses: Vec<CapturePause>,
+}
+
+/// Whether the running pauses pause `content_type`: its own pauses and the
+/// ones of "all".
+pub fn is_paused(pauses: &[CapturePause], content_type: &str) -> bool {
+    pauses
+        .iter()
+        .any(|pause| pause.content_type == "all" || pause.content_type == content_type)
+}
+
+/// Pauses capture from the api and during the hours of the pause schedules,
+/// keeping every pause in the database as a gap of the timeline. Vision
+/// drops what it captures while paused, audio is stopped and the ui rows are
+/// ignored by the database.
+pub struct CapturePauses {
+    db: Arc<DatabaseManager>,
+    audio_manager: Option<Arc<AudioManager>>,
+    vision_paused: AtomicBool,
+    audio_paused: AtomicBool,
+    // audio was running when a pause stopped it, it starts again on resume
+    audio_stopped: AtomicBool,
+    // schedules resumed at the api during their hours, they don't pause again
+    // until their hours are over
+    resumed_schedules: Mutex<HashSet<i64>>,
+    status: Mutex<CapturePauseStatus>,
+    refreshing: tokio::sync::Mutex<()>,
+}
+
+impl CapturePauses {
+    pub fn new(db: Arc<DatabaseManager>, audio_manager: Option<Arc<AudioManager>>) -> Arc<Self> {
+        Arc::new(Self {
+            db,
+            audio_manager,
+            vision_paused: AtomicBool::new(false),
+            audio_paused: AtomicBool::new(false),
+            audio_stopped: AtomicBool::new(false),
+            resumed_schedules: Mutex::new(HashSet::new()),
+            status: Mutex::new(CapturePauseStatus::default()),
+            refreshing: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    pub fn start(self: &Arc<Self>, interval: Duration) {
+        let pauses = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = pauses.refresh(Utc::now(), Local::now().naive_local()).await {
+                    error!("capture pauses: failed to refresh: {}", e);
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+
+    pub fn vision_paused(&self) -> bool {
+        self.vision_paused.load(Ordering::Relaxed)
+    }
+
+    pub fn audio_paused(&self) -> bool {
+        self.audio_paused.load(Ordering::Relaxed)
+    }
+
+    /// Starts audio when the audio pauses end, for audio not started yet
+    /// because of them.
+    pub fn start_audio_on_resume(&self) {
+        self.audio_stopped.store(true, Ordering::Relaxed);
+    }
+
+    pub fn status(&self) -> CapturePauseStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// Pauses `content_type` until resumed, or for `minutes`.
+    pub async fn pause(&self, content_type: &str, minutes: Option<i64>) -> Result<CapturePause> {
+        let now = Utc::now();
+        let resume_at = minutes.map(|minutes| now + ChronoDuration::minutes(minutes));
+        let pause = self
+            .db
+            .start_capture_pause(content_type, now, resume_at, None)
+            .await?;
+        info!("capture pauses: {} paused", content_type);
+        self.refresh(now, Local::now().naive_local()).await?;
+        Ok(pause)
+    }
+
+    /// Ends the pauses of `content_type`, every pause for "all", including
+    /// the ones of schedules. Returns the pauses ended.
+    pub async fn resume(&self, content_type: &str) -> Result<Vec<CapturePause>> {
+        let now = Utc::now();
+        let ended = self.db.end_capture_pauses(content_type, now).await?;
+        self.resumed_schedules
+            .lock()
+            .unwrap()
+            .extend(ended.iter().filter_map(|pause| pause.schedule_id));
+        info!("capture pauses: {} resumed", content_type);
+        self.refresh(now, Local::now().naive_local()).await?;
+        Ok(ended)
+    }
+
+    /// Ends the timed pauses due, starts and ends the pauses of the schedules
+    /// at `local`, then pauses or resumes capture to match.
+    pub async fn refresh(&self, now: DateTime<Utc>, local: NaiveDateTime) -> Result<()> {
+        let _refreshing = self.refreshing.lock().await;
+        self.db.end_due_capture_pauses(now).await?;
+
+        let running = self.db.list_running_capture_pauses().await?;
+        for schedule in self.db.list_capture_pause_schedules().await? {
+            let hours = match schedule.hours.parse::<RecordingHours>() {
+                Ok(hours) => hours,
+                Err(e) => {
+                    warn!("capture pauses: skipping schedule {}: {}", schedule.id, e);
+                    continue;
+                }
+            };
+            let pause = running
+                .iter()
+                .find(|pause| pause.schedule_id == Some(schedule.id));
+            if hours.contains(local) {
+                let resumed = self
+                    .resumed_schedules
+                    .lock()
+                    .unwrap()
+                    .contains(&schedule.id);
+                if pause.is_none() && !resumed {
+                    self.db
+                        .start_capture_pause(&schedule.content_type, now, None, Some(schedule.id))
+                        .await?;
+                    info!(
+                        "capture pauses: {} paused by schedule {}",
+                        schedule.content_type, schedule.hours
+                    );
+                }
+            } else {
+                self.resumed_schedules.lock().unwrap().remove(&schedule.id);
+                if let Some(pause) = pause {
+                    self.db.end_capture_pause(pause.id, now).await?;
+                    info!(
+                        "capture pauses: {} resumed by schedule {}",
+                        schedule.content_type, schedule.hours
+                    );
+                }
+            }
+        }
+
+        let running = self.db.list_running_capture_pauses().await?;
+        let status = CapturePauseStatus {
+            vision_paused: is_paused(&running, "vision"),
+            audio_paused: is_paused(&running, "audio"),
+            ui_paused: is_paused(&running, "ui"),
+            pauses: running,
+        };
+        self.vision_paused
+            .store(status.vision_paused, Ordering::Relaxed);
+        self.audio_paused
+            .store(status.audio_paused, Ordering::Relaxed);
+        self.apply_audio(status.audio_paused).await;
+        *self.status.lock().unwrap() = status;
+        Ok(())
+    }
+
+    async fn apply_audio(&self, paused: bool) {
+        let Some(audio_manager) = &self.audio_manager else {
+            return;
+        };
+        if paused {
+            if audio_manager.status().await == AudioManagerStatus::Running {
+                match audio_manager.stop().await {
+                    Ok(()) => self.audio_stopped.store(true, Ordering::Relaxed),
+                    Err(e) => error!("capture pauses: failed to stop audio: {}", e),
+                }
+            }
+        } else if self.audio_stopped.swap(false, Ordering::Relaxed) {
+            if let Err(e) = audio_manager.start().await {
+                error!("capture pauses: failed to start audio again: {}", e);
+            }
+        }
+    }
+}
@@ -0,0 +1,491 @@
+use anyhow::{anyhow, Result};
+use axum::http::HeaderMap;
+use chrono::Utc;
+use futures::future::join_all;
+use hmac::{Hmac, Mac};
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use oasgen::OaSchema;
+use reqwest::{Client, Url};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, SignatureScheme};
+use screenpipe_core::egress;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::server::{ContentItem, SearchResponse};
+
+/// mDNS service type screenpipe instances advertise themselves as.
+pub const PEER_SERVICE_TYPE: &str = "_screenpipe._tcp.local.";
+pub const PEER_TIME_HEADER: &str = "x-screenpipe-peer-time";
+pub const PEER_NONCE_HEADER: &str = "x-screenpipe-peer-nonce";
+pub const PEER_SIGNATURE_HEADER: &str = "x-screenpipe-peer-signature";
+
+const PEER_TIMEOUT: Duration = Duration::from_secs(10);
+// requests signed longer ago are refused, so the nonces of the ones signed
+// since are enough to refuse replays
+const MAX_CLOCK_SKEW_SECS: i64 = 60;
+// an ed25519 private key in pkcs8, before its 32 bytes
+const ED25519_PKCS8_PREFIX: [u8; 16] = [
+    0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22, 0x04, 0x20,
+];
+// the subject public key info of an ed25519 key, before its 32 bytes
+const ED25519_SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+const PEER_TLS_NAME: &str = "screenpipe-peer";
+
+/// Another screenpipe instance searched along this one.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Peer {
+    pub name: String,
+    /// base url of its peer listener, e.g. "https://192.168.1.20:3035"
+    pub url: String,
+    /// found on the lan over mDNS rather than set with `--peer`
+    pub discovered: bool,
+}
+
+impl FromStr for Peer {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid peer {:?}, expected name=https://host:port", s);
+        let (name, url) = s.split_once('=').ok_or_else(invalid)?;
+        let (name, url) = (name.trim(), url.trim());
+        // peers only answer over tls
+        if name.is_empty() || !url.starts_with("https://") {
+            return Err(invalid());
+        }
+        Ok(Self {
+            name: name.to_string(),
+            url: url.trim_end_matches('/').to_string(),
+            discovered: false,
+        })
+    }
+}
+
+/// The results of a search on a peer, or why there are none.
+#[derive(OaSchema, Debug, Serialize, Deserialize)]
+pub struct PeerSearchResults {
+    pub peer: String,
+    pub data: Vec<ContentItem>,
+    pub total: i64,
+    pub error: Option<String>,
+}
+
+fn hmac_hex(secret: &str, message: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac takes keys of any length");
+    mac.update(message.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Signature of a request to a peer, over when it was sent, a nonce the
+/// response is signed with, and its path and query.
+pub fn sign_request(secret: &str, time: i64, nonce: &str, path_and_query: &str) -> String {
+    hmac_hex(
+        secret,
+        &format!("request\n{}\n{}\n{}", time, nonce, path_and_query),
+    )
+}
+
+/// Signature of the response to a peer's request, which tells it the
+/// response comes from an instance with the same secret.
+pub fn sign_response(secret: &str, nonce: &str, body: &[u8]) -> String {
+    hmac_hex(
+        secret,
+        &format!("response\n{}\n{:x}", nonce, Sha256::digest(body)),
+    )
+}
+
+/// Checks a request was signed with `secret` less than a minute before
+/// `now`, a unix timestamp. Returns the nonce to sign the response with.
+pub fn verify_request(
+    secret: &str,
+    time: Option<&str>,
+    nonce: Option<&str>,
+    signature: Option<&str>,
+    path_and_query: &str,
+    now: i64,
+) -> Result<String, String> {
+    let (Some(time), Some(nonce), Some(signature)) = (time, nonce, signature) else {
+        return Err("the request isn't signed by a peer".to_string());
+    };
+    let time: i64 = time
+        .parse()
+        .map_err(|_| format!("invalid {}", PEER_TIME_HEADER))?;
+    if (now - time).abs() > MAX_CLOCK_SKEW_SECS {
+        return Err("the request was signed too long ago, or the clocks differ".to_string());
+    }
+    if !constant_time_eq(
+        signature,
+        &sign_request(secret, time, nonce, path_and_query),
+    ) {
+        return Err("the request isn't signed with the peer secret".to_string());
+    }
+    Ok(nonce.to_string())
+}
+
+// tells the instances sharing the secret apart from the other ones of the
+// lan, without giving the secret away
+fn group_id(secret: &str) -> String {
+    hmac_hex(secret, "screenpipe peer group")[..16].to_string()
+}
+
+fn tls_provider() -> Arc<CryptoProvider> {
+    Arc::new(rustls::crypto::ring::default_provider())
+}
+
+/// The key pair of the peer listener, derived from the secret: every
+/// instance with the secret serves the same key, and pins it in the peers
+/// it connects to.
+fn peer_tls_key(secret: &str) -> Result<(rcgen::KeyPair, PrivatePkcs8KeyDer<'static>)> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac takes keys of any length");
+    mac.update(b"screenpipe peer tls key");
+    let mut pkcs8 = ED25519_PKCS8_PREFIX.to_vec();
+    pkcs8.extend_from_slice(&mac.finalize().into_bytes());
+    let der = PrivatePkcs8KeyDer::from(pkcs8);
+    let key_pair = rcgen::KeyPair::from_pkcs8_der_and_sign_algo(&der, &rcgen::PKCS_ED25519)?;
+    Ok((key_pair, der))
+}
+
+/// The tls config of the peer listener: a certificate of the key derived
+/// from the secret, which the peers check instead of a certificate
+/// authority.
+pub fn peer_tls_server_config(secret: &str) -> Result<rustls::ServerConfig> {
+    let (key_pair, der) = peer_tls_key(secret)?;
+    let cert =
+        rcgen::CertificateParams::new(vec![PEER_TLS_NAME.to_string()])?.self_signed(&key_pair)?;
+    Ok(rustls::ServerConfig::builder_with_provider(tls_provider())
+        .with_safe_default_protocol_versions()?
+        .with_no_client_auth()
+        .with_single_cert(vec![cert.der().clone()], PrivateKeyDer::Pkcs8(der))?)
+}
+
+/// Accepts the peer listeners serving the key derived from the secret,
+/// whatever their address, as discovered peers have no name to check.
+#[derive(Debug)]
+struct PinnedPeerKey {
+    spki: Vec<u8>,
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinnedPeerKey {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        // the handshake signature proves the peer holds the key
+        if end_entity
+            .windows(self.spki.len())
+            .any(|window| window == self.spki)
+        {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(
+                "the peer doesn't serve the key of the peer secret".to_string(),
+            ))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+/// A client of the peer listeners, see `PinnedPeerKey`.
+fn peer_client(secret: &str) -> Result<Client> {
+    let (key_pair, _) = peer_tls_key(secret)?;
+    let mut spki = ED25519_SPKI_PREFIX.to_vec();
+    spki.extend_from_slice(&key_pair.public_key_raw());
+    let provider = tls_provider();
+    let config = rustls::ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(PinnedPeerKey { spki, provider }))
+        .with_no_client_auth();
+    Ok(egress::http_client_builder()
+        .use_preconfigured_tls(config)
+        .build()?)
+}
+
+/// The peers of this instance: the ones of `--peer` and, with discovery on,
+/// the ones advertising themselves on the lan with the same secret. They
+/// answer the searches of each other over tls, every request and response
+/// signed with the secret, so both sides know the other one holds it.
+pub struct Peers {
+    client: Client,
+    name: String,
+    secret: String,
+    // tells this instance apart from the others it browses
+    id: String,
+    configured: Vec<Peer>,
+    // by mDNS full name
+    discovered: Mutex<HashMap<String, Peer>>,
+    // of the requests verified lately, with when they were
+    seen_nonces: Mutex<HashMap<String, i64>>,
+}
+
+impl Peers {
+    pub fn new(name: String, secret: String, configured: Vec<Peer>) -> Result<Arc<Self>> {
+        Ok(Arc::new(Self {
+            client: peer_client(&secret)?,
+            name,
+            secret,
+            id: Uuid::new_v4().simple().to_string(),
+            configured,
+            discovered: Mutex::new(HashMap::new()),
+            seen_nonces: Mutex::new(HashMap::new()),
+        }))
+    }
+
+    /// Serves `router` to the peers on `listener`, over tls.
+    pub async fn serve(&self, listener: tokio::net::TcpListener, router: Router) -> Result<()> {
+        let config = RustlsConfig::from_config(Arc::new(peer_tls_server_config(&self.secret)?));
+        axum_server::from_tcp_rustls(listener.into_std()?, config)
+            .serve(router.into_make_service())
+            .await?;
+        Ok(())
+    }
+
+    pub fn list(&self) -> Vec<Peer> {
+        let discovered = self.discovered.lock().unwrap();
+        let mut peers = self.configured.clone();
+        peers.extend(
+            discovered
+                .values()
+                .filter(|peer| !self.configured.iter().any(|known| known.url == peer.url))
+                .cloned(),
+        );
+        peers
+    }
+
+    /// Advertises this instance, its api on `port`, over mDNS and adds the
+    /// instances advertised with the same secret to the peers until they
+    /// leave.
+    pub fn start_discovery(self: &Arc<Self>, port: u16) -> Result<()> {
+        let daemon = ServiceDaemon::new()?;
+        let host: String = self
+            .name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect();
+        let group = group_id(&self.secret);
+        let properties = [
+            ("name", self.name.as_str()),
+            ("id", self.id.as_str()),
+            ("group", group.as_str()),
+        ];
+        let service = ServiceInfo::new(
+            PEER_SERVICE_TYPE,
+            &self.id,
+            &format!("{}.local.", host),
+            "",
+            port,
+            &properties[..],
+        )?
+        .enable_addr_auto();
+        daemon.register(service)?;
+        let receiver = daemon.browse(PEER_SERVICE_TYPE)?;
+        info!("advertising this instance to peers on the lan");
+
+        let peers = Arc::clone(self);
+        tokio::spawn(async move {
+            // stops advertising when dropped
+            let _daemon = daemon;
+            while let Ok(event) = receiver.recv_async().await {
+                match event {
+                    ServiceEvent::ServiceResolved(service) => peers.resolved(&service, &group),
+                    ServiceEvent::ServiceRemoved(_, fullname) => {
+                        if let Some(peer) = peers.discovered.lock().unwrap().remove(&fullname) {
+                            info!("peer {} left", peer.name);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        });
+        Ok(())
+    }
+
+    fn resolved(&self, service: &ServiceInfo, group: &str) {
+        if service.get_property_val_str("id") == Some(self.id.as_str())
+            || service.get_property_val_str("group") != Some(group)
+        {
+            return;
+        }
+        let addresses = service.get_addresses();
+        let Some(address) = addresses
+            .iter()
+            .find(|address| address.is_ipv4())
+            .or_else(|| addresses.iter().next())
+        else {
+            return;
+        };
+        let peer = Peer {
+            name: service
+                .get_property_val_str("name")
+                .unwrap_or("unnamed")
+                .to_string(),
+            url: format!("https://{}", SocketAddr::new(*address, service.get_port())),
+            discovered: true,
+        };
+        let mut discovered = self.discovered.lock().unwrap();
+        if discovered.get(service.get_fullname()) != Some(&peer) {
+            info!("found peer {} at {}", peer.name, peer.url);
+            discovered.insert(service.get_fullname().to_string(), peer);
+        }
+    }
+
+    /// Checks a request to `path_and_query` comes from a peer, see
+    /// `verify_request`, and wasn't seen before.
+    pub fn verify(&self, headers: &HeaderMap, path_and_query: &str) -> Result<String, String> {
+        let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+        let now = Utc::now().timestamp();
+        let nonce = verify_request(
+            &self.secret,
+            header(PEER_TIME_HEADER),
+            header(PEER_NONCE_HEADER),
+            header(PEER_SIGNATURE_HEADER),
+            path_and_query,
+            now,
+        )?;
+        let mut seen = self.seen_nonces.lock().unwrap();
+        // older requests are refused for their time already
+        seen.retain(|_, at| now - *at <= 2 * MAX_CLOCK_SKEW_SECS);
+        if seen.insert(nonce.clone(), now).is_some() {
+            return Err("the request was replayed".to_string());
+        }
+        Ok(nonce)
+    }
+
+    pub fn sign(&self, nonce: &str, body: &[u8]) -> String {
+        sign_response(&self.secret, nonce, body)
+    }
+
+    /// Runs the search of `query`, the query string of a `/search` request,
+    /// on every peer at once. A peer failing doesn't fail the others.
+    pub async fn search(&self, query: &str) -> Vec<PeerSearchResults> {
+        let peers = self.list();
+        join_all(peers.into_iter().map(|peer| async move {
+            match self.search_peer(&peer, query).await {
+                Ok(response) => PeerSearchResults {
+                    peer: peer.name,
+                    data: response.data,
+                    total: response.pagination.total,
+                    error: None,
+                },
+                Err(e) => {
+                    warn!("search on peer {} failed: {}", peer.name, e);
+                    PeerSearchResults {
+                        peer: peer.name,
+                        data: Vec::new(),
+                        total: 0,
+                        error: Some(e.to_string()),
+                    }
+                }
+            }
+        }))
+        .await
+    }
+
+    async fn search_peer(&self, peer: &Peer, query: &str) -> Result<SearchResponse> {
+        let mut url = Url::parse(&format!("{}/peers/search", peer.url))?;
+        if !query.is_empty() {
+            url.set_query(Some(query));
+        }
+        egress::check_egress(url.as_str())?;
+        // signed as the peer will read it, once the url was normalized
+        let path_and_query = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        let time = Utc::now().timestamp();
+        let nonce = Uuid::new_v4().simple().to_string();
+        let response = self
+            .client
+            .get(url)
+            .header(PEER_TIME_HEADER, time.to_string())
+            .header(PEER_NONCE_HEADER, &nonce)
+            .header(
+                PEER_SIGNATURE_HEADER,
+                sign_request(&self.secret, time, &nonce, &path_and_query),
+            )
+            .timeout(PEER_TIMEOUT)
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("{}: {}", status, body));
+        }
+        let signature = response
+            .headers()
+            .get(PEER_SIGNATURE_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let body = response.bytes().await?;
+        if !signature
+            .is_some_and(|signature| constant_time_eq(&signature, &self.sign(&nonce, &body)))
+        {
+            return Err(anyhow!("the response isn't signed with the peer secret"));
+        }
+        debug!("searched peer {}", peer.name);
+        Ok(serde_json::from_slice(&body)?)
+    }
+}
@@ -0,0 +1,320 @@
+use anyhow::{Context, Result};
+use oasgen::OaSchema;
+use screenpipe_db::{ContentHook, ContentProcessor, HookContent, HookOutcome};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+use wasmtime::{
+    Caller, Config, Engine, Instance, Linker, Module, Store, StoreLimits, StoreLimitsBuilder,
+};
+
+/// Fuel a plugin gets per call, a few hundred million instructions at most.
+const FUEL_PER_CALL: u64 = 500_000_000;
+/// Memory a plugin may grow to.
+const MEMORY_LIMIT: usize = 64 << 20;
+
+/// What a plugin may do with the content it is shown. What it asks for
+/// without the capability is ignored and counted as denied.
+#[derive(OaSchema, Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginCapability {
+    /// replace the text stored
+    Modify,
+    /// keep content from being stored
+    Drop,
+    /// tag frames and audio chunks
+    Tag,
+    /// write to the screenpipe log with `screenpipe.log`
+    Log,
+}
+
+/// `plugin.json`, next to the module in `<data dir>/plugins/<plugin>/`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PluginManifest {
+    pub name: String,
+    /// the module, `.wasm` or `.wat`, relative to the manifest
+    #[serde(default = "default_wasm")]
+    pub wasm: String,
+    pub hooks: Vec<ContentHook>,
+    #[serde(default)]
+    pub capabilities: Vec<PluginCapability>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_wasm() -> String {
+    "plugin.wasm".to_string()
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// What a plugin did since the start.
+#[derive(OaSchema, Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct PluginStatus {
+    pub name: String,
+    pub hooks: Vec<ContentHook>,
+    pub capabilities: Vec<PluginCapability>,
+    pub calls: u64,
+    pub dropped: u64,
+    pub modified: u64,
+    pub tagged: u64,
+    /// outcomes ignored for a missing capability
+    pub denied: u64,
+    /// traps, fuel or memory exhausted and invalid outcomes
+    pub errors: u64,
+    pub last_error: Option<String>,
+}
+
+struct PluginState {
+    name: String,
+    limits: StoreLimits,
+}
+
+struct Plugin {
+    manifest: PluginManifest,
+    module: Module,
+    // dropped after a trap, the next call starts from a fresh instance
+    instance: Mutex<Option<(Store<PluginState>, Instance)>>,
+    status: Mutex<PluginStatus>,
+}
+
+fn export_name(hook: ContentHook) -> &'static str {
+    match hook {
+        ContentHook::OnOcr => "on_ocr",
+        ContentHook::OnTranscription => "on_transcription",
+        ContentHook::OnFrame => "on_frame",
+    }
+}
+
+impl Plugin {
+    fn can(&self, capability: PluginCapability) -> bool {
+        self.manifest.capabilities.contains(&capability)
+    }
+
+    // nothing is linked but what the capabilities allow, no wasi
+    fn instantiate(&self, engine: &Engine) -> Result<(Store<PluginState>, Instance)> {
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(MEMORY_LIMIT)
+            .instances(1)
+            .build();
+        let mut store = Store::new(
+            engine,
+            PluginState {
+                name: self.manifest.name.clone(),
+                limits,
+            },
+        );
+        store.limiter(|state| &mut state.limits);
+        let mut linker = Linker::new(engine);
+        if self.can(PluginCapability::Log) {
+            linker.func_wrap(
+                "screenpipe",
+                "log",
+                |mut caller: Caller<'_, PluginState>, ptr: i32, len: i32| {
+                    let Some(memory) = caller
+                        .get_export("memory")
+                        .and_then(|export| export.into_memory())
+                    else {
+                        return;
+                    };
+                    let mut message = vec![0; (len as u32 as usize).min(4096)];
+                    if memory
+                        .read(&caller, ptr as u32 as usize, &mut message)
+                        .is_ok()
+                    {
+                        info!(
+                            "plugin {}: {}",
+                            caller.data().name,
+                            String::from_utf8_lossy(&message)
+                        );
+                    }
+                },
+            )?;
+        }
+        let instance = linker.instantiate(&mut store, &self.module)?;
+        Ok((store, instance))
+    }
+
+    // the content goes in as json at a buffer from `alloc`, the hook returns 0
+    // to keep it as is or the pointer and length of a json outcome
+    fn call(
+        store: &mut Store<PluginState>,
+        instance: &Instance,
+        content: &HookContent,
+    ) -> Result<HookOutcome> {
+        store.set_fuel(FUEL_PER_CALL)?;
+        let memory = instance
+            .get_memory(&mut *store, "memory")
+            .context("no memory exported")?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut *store, "alloc")?;
+        let hook =
+            instance.get_typed_func::<(i32, i32), i64>(&mut *store, export_name(content.hook))?;
+
+        let input = serde_json::to_vec(content)?;
+        let ptr = alloc.call(&mut *store, input.len() as i32)?;
+        memory.write(&mut *store, ptr as u32 as usize, &input)?;
+        let result = hook.call(&mut *store, (ptr, input.len() as i32))?;
+        if result == 0 {
+            return Ok(HookOutcome::default());
+        }
+
+        let (ptr, len) = ((result >> 32) as u32 as usize, result as u32 as usize);
+        if len > memory.data_size(&*store) {
+            anyhow::bail!("outcome of {} bytes out of memory", len);
+        }
+        let mut output = vec![0; len];
+        memory.read(&*store, ptr, &mut output)?;
+        Ok(serde_json::from_slice(&output)?)
+    }
+
+    fn run(&self, engine: &Engine, content: &HookContent) -> Result<HookOutcome> {
+        let mut instance = self.instance.lock().unwrap();
+        if instance.is_none() {
+            *instance = Some(self.instantiate(engine)?);
+        }
+        let (store, wasm) = instance.as_mut().unwrap();
+        let outcome = Self::call(store, wasm, content);
+        if outcome.is_err() {
+            *instance = None;
+        }
+        outcome
+    }
+}
+
+/// Runs WASM plugins on what is recorded, see `ContentProcessor`. Plugins
+/// run one after the other in the order of their directory names, each shown
+/// the text the previous ones left. A failing plugin leaves the content as
+/// it was.
+pub struct PluginHost {
+    engine: Engine,
+    plugins: Vec<Plugin>,
+}
+
+impl PluginHost {
+    /// Loads the enabled plugins of `dir`, those that fail to load are logged
+    /// and skipped.
+    pub fn load(dir: &Path) -> Result<Arc<Self>> {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config)?;
+
+        let mut plugins = Vec::new();
+        if dir.exists() {
+            let mut entries = std::fs::read_dir(dir)?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.join("plugin.json").exists())
+                .collect::<Vec<_>>();
+            entries.sort();
+            for path in entries {
+                match Self::load_plugin(&engine, &path) {
+                    Ok(Some(plugin)) => {
+                        info!(
+                            "loaded plugin {} for {:?} with {:?}",
+                            plugin.manifest.name,
+                            plugin.manifest.hooks,
+                            plugin.manifest.capabilities
+                        );
+                        plugins.push(plugin);
+                    }
+                    Ok(None) => {}
+                    Err(e) => warn!("failed to load plugin {}: {:#}", path.display(), e),
+                }
+            }
+        }
+        Ok(Arc::new(Self { engine, plugins }))
+    }
+
+    fn load_plugin(engine: &Engine, path: &Path) -> Result<Option<Plugin>> {
+        let manifest: PluginManifest =
+            serde_json::from_str(&std::fs::read_to_string(path.join("plugin.json"))?)
+                .context("invalid plugin.json")?;
+        if !manifest.enabled {
+            return Ok(None);
+        }
+        let module = Module::from_file(engine, path.join(&manifest.wasm))?;
+        for hook in &manifest.hooks {
+            if module.get_export(export_name(*hook)).is_none() {
+                anyhow::bail!("{} is not exported", export_name(*hook));
+            }
+        }
+        let status = PluginStatus {
+            name: manifest.name.clone(),
+            hooks: manifest.hooks.clone(),
+            capabilities: manifest.capabilities.clone(),
+            ..Default::default()
+        };
+        let plugin = Plugin {
+            manifest,
+            module,
+            instance: Mutex::new(None),
+            status: Mutex::new(status),
+        };
+        // fails on imports the capabilities don't allow
+        let instance = plugin
+            .instantiate(engine)
+            .context("failed to instantiate")?;
+        *plugin.instance.lock().unwrap() = Some(instance);
+        Ok(Some(plugin))
+    }
+
+    pub fn status(&self) -> Vec<PluginStatus> {
+        self.plugins
+            .iter()
+            .map(|plugin| plugin.status.lock().unwrap().clone())
+            .collect()
+    }
+}
+
+impl ContentProcessor for PluginHost {
+    fn process(&self, content: &HookContent) -> HookOutcome {
+        let mut content = content.clone();
+        let mut outcome = HookOutcome::default();
+        for plugin in &self.plugins {
+            if !plugin.manifest.hooks.contains(&content.hook) {
+                continue;
+            }
+            let result = plugin.run(&self.engine, &content);
+            let mut status = plugin.status.lock().unwrap();
+            status.calls += 1;
+            let result = match result {
+                Ok(result) => result,
+                Err(e) => {
+                    warn!("plugin {} failed: {:#}", plugin.manifest.name, e);
+                    status.errors += 1;
+                    status.last_error = Some(format!("{:#}", e));
+                    continue;
+                }
+            };
+
+            if result.drop {
+                if plugin.can(PluginCapability::Drop) {
+                    status.dropped += 1;
+                    outcome.drop = true;
+                    return outcome;
+                }
+                status.denied += 1;
+            }
+            if let Some(text) = result.text.filter(|text| *text != content.text) {
+                if plugin.can(PluginCapability::Modify) {
+                    status.modified += 1;
+                    content.text = text.clone();
+                    outcome.text = Some(text);
+                } else {
+                    status.denied += 1;
+                }
+            }
+            if !result.tags.is_empty() {
+                if plugin.can(PluginCapability::Tag) {
+                    status.tagged += 1;
+                    outcome.tags.extend(result.tags);
+                } else {
+                    status.denied += 1;
+                }
+            }
+        }
+        outcome
+    }
+}
@@ -0,0 +1,249 @@
+use chrono::NaiveDate;
+use oasgen::OaSchema;
+use screenpipe_db::{
+    AppCategoryRule, DatabaseManager, NewAppCategoryRule, UsageRecord, WindowFocusTime,
+    APP_CATEGORIES, APP_CATEGORY_KINDS,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::digest::AppTime;
+use crate::journal::local_day;
+
+// apps listed under each category
+const APPS_PER_CATEGORY: usize = 5;
+
+#[derive(OaSchema, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CategoryTime {
+    /// "deep_work", "communication" or "distraction"
+    pub category: String,
+    pub minutes: i64,
+    /// the apps the time was spent in, the longest first
+    pub apps: Vec<AppTime>,
+}
+
+/// How a day was spent according to the category rules.
+#[derive(OaSchema, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DayProductivity {
+    pub date: NaiveDate,
+    /// 0 to 100, deep work counting fully, communication half and
+    /// distraction not at all, over the time in a category. None when no
+    /// time was
+    pub score: Option<i64>,
+    /// every category, even without time
+    pub categories: Vec<CategoryTime>,
+    /// time in apps no rule matched
+    pub uncategorized_minutes: i64,
+    /// "focus" when the time comes from focus sessions, "frames" when there
+    /// were none and it comes from the frames
+    pub source: String,
+}
+
+/// The days from `first` to `last`, in the local timezone, scored with the
+/// category rules of the database.
+pub async fn productivity(
+    db: &DatabaseManager,
+    first: NaiveDate,
+    last: NaiveDate,
+) -> Result<Vec<DayProductivity>, sqlx::Error> {
+    let rules = db.list_app_category_rules().await?;
+    let mut days = Vec::new();
+    for date in first.iter_days().take_while(|date| *date <= last) {
+        let (start, end) = local_day(date);
+        let focus = db.get_focus_time(start, end, None).await?;
+        let frames = db.get_usage_records(start, end, true, false).await?;
+        days.push(day_productivity(date, &rules, &focus, &frames));
+    }
+    Ok(days)
+}
+
+/// Scores a day. The time comes from the focus sessions, or from the frames
+/// when there are none. Frames on urls of a category move that share of the
+/// focus time of their browser to it, as focus sessions don't know the url.
+pub fn day_productivity(
+    date: NaiveDate,
+    rules: &[AppCategoryRule],
+    focus: &[WindowFocusTime],
+    frames: &[UsageRecord],
+) -> DayProductivity {
+    // (app, category, seconds), no category when no rule matched
+    let mut spent: Vec<(&str, Option<&str>, f64)> = Vec::new();
+    if focus.is_empty() {
+        for frame in frames {
+            let Some(secs) = frame.duration_secs else {
+                continue;
+            };
+            let app = frame.app_name.as_deref().unwrap_or("");
+            let category = categorize(
+                rules,
+                app,
+                frame.window_name.as_deref().unwrap_or(""),
+                frame.browser_url.as_deref(),
+            );
+            spent.push((app, category, secs));
+        }
+    } else {
+        let mut app_secs: HashMap<&str, f64> = HashMap::new();
+        let mut url_secs: HashMap<(&str, &str), f64> = HashMap::new();
+        for frame in frames {
+            let Some(secs) = frame.duration_secs else {
+                continue;
+            };
+            let app = frame.app_name.as_deref().unwrap_or("");
+            *app_secs.entry(app).or_default() += secs;
+            if let Some(category) = frame
+                .browser_url
+                .as_deref()
+                .and_then(|url| matching_category(rules, "url", url))
+            {
+                *url_secs.entry((app, category)).or_default() += secs;
+            }
+        }
+        for window in focus {
+            let app = window.app_name.as_str();
+            let mut rest = window.focused_secs;
+            let total = app_secs.get(app).copied().unwrap_or(0.0);
+            if total > 0.0 {
+                for category in APP_CATEGORIES {
+                    if let Some(secs) = url_secs.get(&(app, *category)) {
+                        let moved = window.focused_secs * secs / total;
+                        spent.push((app, Some(*category), moved));
+                        rest -= moved;
+                    }
+                }
+            }
+            spent.push((app, categorize(rules, app, &window.window_name, None), rest));
+        }
+    }
+
+    let mut uncategorized = 0.0;
+    let mut by_category: HashMap<&str, HashMap<&str, f64>> = HashMap::new();
+    for (app, category, secs) in spent {
+        match category {
+            Some(category) => {
+                *by_category
+                    .entry(category)
+                    .or_default()
+                    .entry(app)
+                    .or_default() += secs
+            }
+            None => uncategorized += secs,
+        }
+    }
+    let categories: Vec<CategoryTime> = APP_CATEGORIES
+        .iter()
+        .map(|category| {
+            let apps = by_category.remove(category).unwrap_or_default();
+            let secs: f64 = apps.values().sum();
+            let mut apps: Vec<AppTime> = apps
+                .into_iter()
+                .filter(|(app, _)| !app.is_empty())
+                .map(|(app, secs)| AppTime {
+                    app_name: app.to_string(),
+                    minutes: minutes(secs),
+                })
+                .filter(|app| app.minutes > 0)
+                .collect();
+            apps.sort_by(|a, b| b.minutes.cmp(&a.minutes).then(a.app_name.cmp(&b.app_name)));
+            apps.truncate(APPS_PER_CATEGORY);
+            CategoryTime {
+                category: category.to_string(),
+                minutes: minutes(secs),
+                apps,
+            }
+        })
+        .collect();
+
+    DayProductivity {
+        date,
+        score: productivity_score(&categories),
+        categories,
+        uncategorized_minutes: minutes(uncategorized),
+        source: if focus.is_empty() { "frames" } else { "focus" }.to_string(),
+    }
+}
+
+/// The category of an app, window or url: the url rules first, then the
+/// window and the app ones, the longest pattern of a kind winning.
+pub fn categorize<'a>(
+    rules: &'a [AppCategoryRule],
+    app: &str,
+    window: &str,
+    url: Option<&str>,
+) -> Option<&'a str> {
+    url.and_then(|url| matching_category(rules, "url", url))
+        .or_else(|| matching_category(rules, "window", window))
+        .or_else(|| matching_category(rules, "app", app))
+}
+
+fn matching_category<'a>(rules: &'a [AppCategoryRule], kind: &str, text: &str) -> Option<&'a str> {
+    let text = text.to_lowercase();
+    rules
+        .iter()
+        .filter(|rule| rule.kind == kind && !rule.pattern.is_empty())
+        .filter(|rule| text.contains(&rule.pattern.to_lowercase()))
+        // rules of the api come before the ones of the command line
+        .max_by_key(|rule| (rule.pattern.len(), rule.source == "api"))
+        .map(|rule| rule.category.as_str())
+}
+
+fn productivity_score(categories: &[CategoryTime]) -> Option<i64> {
+    let minutes_in = |category: &str| {
+        categories
+            .iter()
+            .find(|time| time.category == category)
+            .map_or(0, |time| time.minutes)
+    };
+    let (deep_work, communication, distraction) = (
+        minutes_in("deep_work"),
+        minutes_in("communication"),
+        minutes_in("distraction"),
+    );
+    let total = deep_work + communication + distraction;
+    (total > 0).then(|| {
+        (100.0 * (deep_work as f64 + communication as f64 / 2.0) / total as f64).round() as i64
+    })
+}
+
+fn minutes(secs: f64) -> i64 {
+    (secs / 60.0).round() as i64
+}
+
+/// Parses a category rule of the command line, written
+/// `[kind:]pattern=category` with the app kind by default, e.g.
+/// `url:youtube.com=distraction` or `Slack=communication`.
+pub fn parse_app_category(s: &str) -> Result<NewAppCategoryRule, String> {
+    let (rule, category) = s.rsplit_once('=').ok_or_else(|| {
+        format!(
+            "invalid category rule {:?}, expected e.g. \"url:youtube.com=distraction\"",
+            s
+        )
+    })?;
+    let category = category.trim();
+    if !APP_CATEGORIES.contains(&category) {
+        return Err(format!(
+            "invalid category {:?}, expected one of {}",
+            category,
+            APP_CATEGORIES.join(", ")
+        ));
+    }
+    let (kind, pattern) = APP_CATEGORY_KINDS
+        .iter()
+        .find_map(|kind| {
+            rule.strip_prefix(kind)
+                .and_then(|rest| rest.strip_prefix(':'))
+                .map(|pattern| (*kind, pattern))
+        })
+        .unwrap_or(("app", rule));
+    if pattern.trim().is_empty() {
+        return Err(format!(
+            "invalid category rule {:?}, the pattern is empty",
+            s
+        ));
+    }
+    Ok(NewAppCategoryRule {
+        kind: kind.to_string(),
+        pattern: pattern.trim().to_string(),
+        category: category.to_string(),
+    })
+}
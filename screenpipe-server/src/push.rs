@@ -0,0 +1,352 @@
+use anyhow::{anyhow, Result};
+use chrono::{Duration as ChronoDuration, SecondsFormat};
+use reqwest::{Client, RequestBuilder};
+use screenpipe_core::egress;
+use screenpipe_db::{DatabaseManager, PushDestination, PushItem};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error, info};
+
+const NOTION_API: &str = "https://api.notion.com/v1";
+const NOTION_VERSION: &str = "2022-06-28";
+const READWISE_API: &str = "https://readwise.io/api/v2";
+const PUSH_TIMEOUT: Duration = Duration::from_secs(30);
+const PUSH_BATCH: u32 = 50;
+// limits of the apis
+const NOTION_TEXT_CHARS: usize = 2000;
+const NOTION_MAX_BLOCKS: usize = 100;
+const READWISE_TEXT_CHARS: usize = 8191;
+
+/// Where the item opens on this server: the frame, or the audio searched
+/// around the time it was said.
+pub fn item_link(item: &PushItem, server_url: &str) -> String {
+    let server_url = server_url.trim_end_matches('/');
+    if item.content_type == "audio" {
+        format!(
+            "{}/search?content_type=audio&start_time={}&end_time={}",
+            server_url,
+            item.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true),
+            (item.timestamp + ChronoDuration::minutes(1))
+                .to_rfc3339_opts(SecondsFormat::Secs, true)
+        )
+    } else {
+        format!("{}/frames/{}", server_url, item.content_id)
+    }
+}
+
+pub fn item_title(item: &PushItem) -> String {
+    if item.content_type == "audio" {
+        format!("Audio from {}", item.device_name)
+    } else if item.window_name.is_empty() {
+        item.app_name.clone()
+    } else if item.app_name.is_empty() {
+        item.window_name.clone()
+    } else {
+        format!("{} – {}", item.app_name, item.window_name)
+    }
+}
+
+fn item_tags(item: &PushItem) -> Vec<&str> {
+    item.tags
+        .split(',')
+        .map(|tag| tag.trim())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(max_chars - 1).collect();
+    format!("{}…", cut)
+}
+
+/// A highlight of the Readwise api. Tags go in the note, as Readwise reads
+/// `.tag` there.
+pub fn readwise_highlight(item: &PushItem, server_url: &str) -> Value {
+    let title = item_title(item);
+    let text = if item.text.trim().is_empty() {
+        title.clone()
+    } else {
+        item.text.trim().to_string()
+    };
+    let note = item_tags(item)
+        .iter()
+        .map(|tag| format!(".{}", tag.replace(' ', "_")))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let link = item_link(item, server_url);
+    json!({
+        "text": truncate(&text, READWISE_TEXT_CHARS),
+        "title": title,
+        "author": "screenpipe",
+        "source_type": "screenpipe",
+        "category": "articles",
+        "source_url": link,
+        "highlight_url": link,
+        "highlighted_at": item.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true),
+        "note": note,
+    })
+}
+
+fn rich_text(content: &str, link: Option<&str>) -> Value {
+    let mut text = json!({ "content": content });
+    if let Some(link) = link {
+        text["link"] = json!({ "url": link });
+    }
+    json!([{ "type": "text", "text": text }])
+}
+
+fn paragraph(content: &str, link: Option<&str>) -> Value {
+    json!({
+        "object": "block",
+        "type": "paragraph",
+        "paragraph": { "rich_text": rich_text(content, link) },
+    })
+}
+
+/// A page of the Notion database described by `schema`, as returned by its
+/// api. The title goes in the title property, whatever its name, and the
+/// date, link, tags and app in the `Date`, `URL`, `Tags` and `App`
+/// properties when the database has them.
+pub fn notion_page(database_id: &str, schema: &Value, item: &PushItem, server_url: &str) -> Value {
+    let link = item_link(item, server_url);
+    let title = item_title(item);
+    let empty = serde_json::Map::new();
+    let columns = schema["properties"].as_object().unwrap_or(&empty);
+    let column_type = |name: &str| columns.get(name).and_then(|c| c["type"].as_str());
+
+    let mut properties = serde_json::Map::new();
+    let title_column = columns
+        .iter()
+        .find(|(_, column)| column["type"] == "title")
+        .map(|(name, _)| name.clone())
+        .unwrap_or_else(|| "Name".to_string());
+    properties.insert(
+        title_column,
+        json!({ "title": rich_text(&truncate(&title, NOTION_TEXT_CHARS), None) }),
+    );
+    if column_type("Date") == Some("date") {
+        let start = item.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true);
+        properties.insert("Date".to_string(), json!({ "date": { "start": start } }));
+    }
+    if column_type("URL") == Some("url") {
+        properties.insert("URL".to_string(), json!({ "url": link }));
+    }
+    if column_type("Tags") == Some("multi_select") {
+        let tags: Vec<Value> = item_tags(item)
+            .iter()
+            .map(|tag| json!({ "name": tag }))
+            .collect();
+        properties.insert("Tags".to_string(), json!({ "multi_select": tags }));
+    }
+    match column_type("App") {
+        Some("rich_text") if !item.app_name.is_empty() => {
+            properties.insert(
+                "App".to_string(),
+                json!({ "rich_text": rich_text(&item.app_name, None) }),
+            );
+        }
+        Some("select") if !item.app_name.is_empty() => {
+            properties.insert(
+                "App".to_string(),
+                json!({ "select": { "name": item.app_name.replace(',', " ") } }),
+            );
+        }
+        _ => {}
+    }
+
+    // the text in paragraphs of the longest rich text notion takes, with the
+    // link last
+    let chars: Vec<char> = item.text.trim().chars().collect();
+    let mut children: Vec<Value> = chars
+        .chunks(NOTION_TEXT_CHARS)
+        .take(NOTION_MAX_BLOCKS - 1)
+        .map(|chunk| paragraph(&chunk.iter().collect::<String>(), None))
+        .collect();
+    children.push(paragraph("Open in screenpipe", Some(&link)));
+
+    json!({
+        "parent": { "database_id": database_id },
+        "properties": properties,
+        "children": children,
+    })
+}
+
+/// Pushes tagged frames and audio to the Notion databases and Readwise
+/// accounts configured through the api, each item once per destination.
+pub struct PushSync {
+    db: Arc<DatabaseManager>,
+    client: Client,
+    server_url: String,
+    notion_api: String,
+    readwise_api: String,
+}
+
+impl PushSync {
+    pub fn new(db: Arc<DatabaseManager>, server_url: String) -> Arc<Self> {
+        Self::with_api_urls(
+            db,
+            server_url,
+            NOTION_API.to_string(),
+            READWISE_API.to_string(),
+        )
+    }
+
+    /// Talks to these urls instead of the Notion and Readwise apis, e.g.
+    /// through a proxy.
+    pub fn with_api_urls(
+        db: Arc<DatabaseManager>,
+        server_url: String,
+        notion_api: String,
+        readwise_api: String,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            db,
+            client: egress::http_client(),
+            server_url,
+            notion_api: notion_api.trim_end_matches('/').to_string(),
+            readwise_api: readwise_api.trim_end_matches('/').to_string(),
+        })
+    }
+
+    pub fn start(self: &Arc<Self>, interval: Duration) {
+        let sync = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                match sync.push_all().await {
+                    Ok(0) => debug!("push: nothing new to push"),
+                    Ok(pushed) => info!("push: pushed {} items", pushed),
+                    Err(e) => error!("push: failed to list destinations: {}", e),
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+
+    /// Pushes what is new to every enabled destination. A failing
+    /// destination keeps its error and is tried again next time. Returns how
+    /// many items were pushed.
+    pub async fn push_all(&self) -> Result<usize> {
+        let mut pushed = 0;
+        for destination in self.db.list_push_destinations().await? {
+            if !destination.enabled || destination.tag_list().is_empty() {
+                continue;
+            }
+            match self.push(&destination).await {
+                Ok(count) => pushed += count,
+                Err(e) => {
+                    error!("push: failed to push to {}: {}", destination.name, e);
+                    self.db
+                        .set_push_error(destination.id, &e.to_string())
+                        .await?;
+                }
+            }
+        }
+        Ok(pushed)
+    }
+
+    async fn push(&self, destination: &PushDestination) -> Result<usize> {
+        let notion_schema = match destination.kind.as_str() {
+            "notion" => Some(self.notion_schema(destination).await?),
+            "readwise" => None,
+            kind => return Err(anyhow!("unknown destination kind {:?}", kind)),
+        };
+
+        let mut pushed = 0;
+        loop {
+            let items = self.db.get_unpushed_items(destination, PUSH_BATCH).await?;
+            if items.is_empty() {
+                break;
+            }
+            match &notion_schema {
+                Some(schema) => {
+                    // a page per item, kept as pushed as soon as it exists
+                    for item in &items {
+                        self.push_notion_page(destination, schema, item).await?;
+                        self.db
+                            .mark_items_pushed(destination.id, std::slice::from_ref(item))
+                            .await?;
+                    }
+                }
+                None => {
+                    self.push_readwise(destination, &items).await?;
+                    self.db.mark_items_pushed(destination.id, &items).await?;
+                }
+            }
+            pushed += items.len();
+            if items.len() < PUSH_BATCH as usize {
+                break;
+            }
+        }
+        Ok(pushed)
+    }
+
+    async fn send(&self, request: RequestBuilder) -> Result<Value> {
+        let request = request.timeout(PUSH_TIMEOUT).build()?;
+        egress::check_egress(request.url().as_str())?;
+        let response = self.client.execute(request).await?;
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            return Err(anyhow!("{}: {}", status, body));
+        }
+        Ok(serde_json::from_str(&body).unwrap_or(Value::Null))
+    }
+
+    fn notion_request(
+        &self,
+        request: RequestBuilder,
+        destination: &PushDestination,
+    ) -> RequestBuilder {
+        request
+            .bearer_auth(&destination.api_key)
+            .header("Notion-Version", NOTION_VERSION)
+    }
+
+    async fn notion_schema(&self, destination: &PushDestination) -> Result<Value> {
+        let database_id = destination
+            .notion_database_id
+            .as_deref()
+            .ok_or_else(|| anyhow!("no notion database id"))?;
+        let url = format!("{}/databases/{}", self.notion_api, database_id);
+        self.send(self.notion_request(self.client.get(url), destination))
+            .await
+    }
+
+    async fn push_notion_page(
+        &self,
+        destination: &PushDestination,
+        schema: &Value,
+        item: &PushItem,
+    ) -> Result<()> {
+        let database_id = destination
+            .notion_database_id
+            .as_deref()
+            .unwrap_or_default();
+        let page = notion_page(database_id, schema, item, &self.server_url);
+        let url = format!("{}/pages", self.notion_api);
+        self.send(
+            self.notion_request(self.client.post(url), destination)
+                .json(&page),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn push_readwise(&self, destination: &PushDestination, items: &[PushItem]) -> Result<()> {
+        let highlights: Vec<Value> = items
+            .iter()
+            .map(|item| readwise_highlight(item, &self.server_url))
+            .collect();
+        let request = self
+            .client
+            .post(format!("{}/highlights/", self.readwise_api))
+            .header("Authorization", format!("Token {}", destination.api_key))
+            .json(&json!({ "highlights": highlights }));
+        self.send(request).await?;
+        Ok(())
+    }
+}
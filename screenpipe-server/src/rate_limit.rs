@@ -0,0 +1,265 @@
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header::RETRY_AFTER, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fmt;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+use crate::auth::ApiCaller;
+
+// past this many clients the least recently seen are forgotten
+const MAX_TRACKED_BUCKETS: usize = 10_000;
+// how often buckets refilled to the full are dropped
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Routes sharing a limit. Searches are limited apart so a client hammering
+/// them can't keep the database from the capture pipeline's writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EndpointClass {
+    /// search and raw sql queries
+    Search,
+    /// every other read
+    Read,
+    Write,
+}
+
+impl EndpointClass {
+    /// Class of a request, `None` for the routes never limited (health
+    /// checks, CORS preflights).
+    pub fn of(method: &Method, path: &str) -> Option<EndpointClass> {
+        if *method == Method::OPTIONS || path == "/health" || path == "/ws/health" {
+            return None;
+        }
+        let read = *method == Method::GET || *method == Method::HEAD;
+        let search = path == "/search"
+            || path.starts_with("/search/")
+            || path == "/v2/search"
+            || path == "/semantic-search"
+            || path == "/speakers/search";
+        // raw sql queries are posted, posts and deletes under /search/ (tagging
+        // or deleting results, recording clicks) are writes
+        if path == "/raw_sql" || (search && read) {
+            return Some(EndpointClass::Search);
+        }
+        if read {
+            return Some(EndpointClass::Read);
+        }
+        Some(EndpointClass::Write)
+    }
+}
+
+impl fmt::Display for EndpointClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            EndpointClass::Search => "search",
+            EndpointClass::Read => "read",
+            EndpointClass::Write => "write",
+        })
+    }
+}
+
+/// Requests allowed per period, written `60/min`, `5/s` or `1000/hour`. A
+/// client can use them in a burst, they come back evenly over the period.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub per: Duration,
+}
+
+impl FromStr for RateLimit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "invalid rate limit {:?}, expected e.g. \"60/min\" or \"5/s\"",
+                s
+            )
+        };
+        let (requests, period) = s.trim().split_once('/').ok_or_else(invalid)?;
+        let requests = requests.trim().parse::<u32>().map_err(|_| invalid())?;
+        let per = match period.trim() {
+            "s" | "sec" | "second" => Duration::from_secs(1),
+            "m" | "min" | "minute" => Duration::from_secs(60),
+            "h" | "hour" => Duration::from_secs(3600),
+            _ => return Err(invalid()),
+        };
+        if requests == 0 {
+            return Err(invalid());
+        }
+        Ok(RateLimit { requests, per })
+    }
+}
+
+/// Limit of each endpoint class, `None` for unlimited.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RateLimits {
+    pub search: Option<RateLimit>,
+    pub read: Option<RateLimit>,
+    pub write: Option<RateLimit>,
+}
+
+impl RateLimits {
+    pub fn is_empty(&self) -> bool {
+        self.search.is_none() && self.read.is_none() && self.write.is_none()
+    }
+
+    fn get(&self, class: EndpointClass) -> Option<RateLimit> {
+        match class {
+            EndpointClass::Search => self.search,
+            EndpointClass::Read => self.read,
+            EndpointClass::Write => self.write,
+        }
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    fn refilled(&self, limit: RateLimit, now: Instant) -> f64 {
+        let capacity = limit.requests as f64;
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        (self.tokens + elapsed * capacity / limit.per.as_secs_f64()).min(capacity)
+    }
+}
+
+struct Buckets {
+    buckets: HashMap<(String, EndpointClass), Bucket>,
+    swept_at: Instant,
+}
+
+/// Token buckets per client and endpoint class. Clients are told apart by
+/// the api token they were let in with, or by their address without one.
+pub struct RateLimiter {
+    limits: RateLimits,
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: RateLimits) -> Arc<Self> {
+        Arc::new(Self {
+            limits,
+            buckets: Mutex::new(Buckets {
+                buckets: HashMap::new(),
+                swept_at: Instant::now(),
+            }),
+        })
+    }
+
+    /// Clients tracked, for tests.
+    pub fn tracked(&self) -> usize {
+        self.buckets.lock().unwrap().buckets.len()
+    }
+
+    // a full bucket is the same as a new one, and past the cap the clients
+    // seen longest ago go too
+    fn sweep(&self, buckets: &mut HashMap<(String, EndpointClass), Bucket>, now: Instant) {
+        buckets.retain(|(_, class), bucket| {
+            self.limits
+                .get(*class)
+                .is_some_and(|limit| bucket.refilled(limit, now) < limit.requests as f64)
+        });
+        if buckets.len() >= MAX_TRACKED_BUCKETS {
+            let mut seen: Vec<_> = buckets
+                .iter()
+                .map(|(key, bucket)| (bucket.updated_at, key.clone()))
+                .collect();
+            seen.sort_unstable_by_key(|(updated_at, _)| *updated_at);
+            for (_, key) in seen.iter().take(buckets.len() - MAX_TRACKED_BUCKETS / 2) {
+                buckets.remove(key);
+            }
+        }
+    }
+
+    /// Takes a request from the client's bucket, or returns how long until
+    /// one is available.
+    pub fn check(&self, client: &str, class: EndpointClass, now: Instant) -> Result<(), Duration> {
+        let Some(limit) = self.limits.get(class) else {
+            return Ok(());
+        };
+        let mut state = self.buckets.lock().unwrap();
+        let Buckets { buckets, swept_at } = &mut *state;
+        if buckets.len() >= MAX_TRACKED_BUCKETS
+            || now.saturating_duration_since(*swept_at) >= SWEEP_INTERVAL
+        {
+            self.sweep(buckets, now);
+            *swept_at = now;
+        }
+
+        let bucket = buckets
+            .entry((client.to_string(), class))
+            .or_insert(Bucket {
+                tokens: limit.requests as f64,
+                updated_at: now,
+            });
+        bucket.tokens = bucket.refilled(limit, now);
+        bucket.updated_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            let per_sec = limit.requests as f64 / limit.per.as_secs_f64();
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_sec))
+        }
+    }
+}
+
+// a token not verified yet could be made up per request, so it doesn't get
+// a budget of its own
+fn client_key(request: &Request) -> String {
+    if let Some(ApiCaller(name)) = request.extensions().get::<ApiCaller>() {
+        return format!("token:{}", name);
+    }
+    request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| format!("ip:{}", addr.ip()))
+        .unwrap_or_else(|| "ip:unknown".to_string())
+}
+
+/// Answers 429 with a `Retry-After` header once a client used up the limit of
+/// the route's class.
+pub(crate) async fn rate_limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(class) = EndpointClass::of(request.method(), request.uri().path()) else {
+        return next.run(request).await;
+    };
+    let client = client_key(&request);
+    let Err(retry_after) = limiter.check(&client, class, Instant::now()) else {
+        return next.run(request).await;
+    };
+
+    let retry_after_secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+    debug!(
+        "rate limited {} request of {}, retry in {}s",
+        class, client, retry_after_secs
+    );
+    let mut response = (
+        StatusCode::TOO_MANY_REQUESTS,
+        Json(json!({
+            "error": format!("too many {} requests", class),
+            "retry_after_secs": retry_after_secs,
+        })),
+    )
+        .into_response();
+    response
+        .headers_mut()
+        .insert(RETRY_AFTER, HeaderValue::from(retry_after_secs));
+    response
+}
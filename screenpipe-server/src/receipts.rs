@@ -0,0 +1,213 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// Fields pulled out of a frame whose OCR text looks like a receipt or invoice.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReceiptFields {
+    pub vendor: Option<String>,
+    pub date: Option<String>,
+    pub total: Option<f64>,
+    pub currency: Option<String>,
+}
+
+const RECEIPT_KEYWORDS: &[&str] = &[
+    "receipt",
+    "invoice",
+    "subtotal",
+    "sub total",
+    "tax",
+    "vat",
+    "amount due",
+    "balance due",
+    "bill to",
+    "order #",
+    "order number",
+    "invoice #",
+    "invoice number",
+    "payment method",
+    "paid",
+    "qty",
+    "thank you for your",
+];
+
+// the keywords as words, so "tax" isn't found in "syntax" nor "paid" in
+// "unpaid"
+static KEYWORD_WORDS: Lazy<Vec<Vec<String>>> =
+    Lazy::new(|| RECEIPT_KEYWORDS.iter().copied().map(words).collect());
+
+// minimum number of distinct keywords and of lines ending in an amount
+const MIN_KEYWORD_HITS: usize = 2;
+const MIN_AMOUNT_LINES: usize = 3;
+
+// amounts are ascii digits only, `\d` would also take arabic-indic or
+// fullwidth ones
+static AMOUNT_AT_END: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?:[$€£¥]\s?)?[0-9]{1,3}(?:[,.\s][0-9]{3})*[.,][0-9]{2}\s*$").unwrap()
+});
+
+static TOTAL_LINE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)\b(grand total|total due|amount due|balance due|amount paid|total)\b[^0-9$€£¥]*([$€£¥])?\s?([0-9]{1,3}(?:[,.\s][0-9]{3})*[.,][0-9]{2})",
+    )
+    .unwrap()
+});
+
+static SUBTOTAL: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)\bsub[\s-]?total\b").unwrap());
+
+static CURRENCY_CODE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b(USD|EUR|GBP|JPY|CAD|AUD|CHF|INR)\b").unwrap());
+
+static DATE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)\b(\d{4}-\d{2}-\d{2}|\d{1,2}[/.]\d{1,2}[/.]\d{2,4}|(?:jan|feb|mar|apr|may|jun|jul|aug|sep|oct|nov|dec)[a-z]*\.? \d{1,2},? \d{4}|\d{1,2} (?:jan|feb|mar|apr|may|jun|jul|aug|sep|oct|nov|dec)[a-z]* \d{4})\b",
+    )
+    .unwrap()
+});
+
+static VENDOR_PREFIX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)^(?:from|sold by|merchant|vendor|seller)\s*:\s*(.+)$").unwrap());
+
+/// Returns the extracted fields when `text` looks like a receipt or invoice.
+///
+/// Detection combines vocabulary (total, tax, invoice #, ...) with layout: a
+/// receipt has several lines that end in a monetary amount.
+pub fn detect_receipt(text: &str) -> Option<ReceiptFields> {
+    let text_words = words(text);
+    let keyword_hits = KEYWORD_WORDS
+        .iter()
+        .filter(|keyword| has_phrase(&text_words, keyword))
+        .count();
+    if keyword_hits < MIN_KEYWORD_HITS {
+        return None;
+    }
+
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    let amount_lines = lines
+        .iter()
+        .filter(|line| AMOUNT_AT_END.is_match(line))
+        .count();
+    if amount_lines < MIN_AMOUNT_LINES {
+        return None;
+    }
+
+    let (total, symbol) = extract_total(&lines);
+    // a receipt without any recognizable total is most likely a price list
+    if total.is_none() {
+        return None;
+    }
+
+    let currency = symbol
+        .and_then(currency_from_symbol)
+        .or_else(|| CURRENCY_CODE.find(text).map(|m| m.as_str().to_string()));
+
+    Some(ReceiptFields {
+        vendor: extract_vendor(&lines),
+        date: DATE.find(text).map(|m| m.as_str().to_string()),
+        total,
+        currency,
+    })
+}
+
+fn extract_total(lines: &[&str]) -> (Option<f64>, Option<char>) {
+    // receipts usually print the final total last, so the last match wins
+    // unless a more specific label ("amount due", "grand total") shows up
+    let mut best: Option<(bool, f64, Option<char>)> = None;
+    for line in lines {
+        if SUBTOTAL.is_match(line) {
+            continue;
+        }
+        if let Some(caps) = TOTAL_LINE.captures(line) {
+            let specific = !caps[1].eq_ignore_ascii_case("total");
+            let Some(amount) = parse_amount(&caps[3]) else {
+                continue;
+            };
+            let symbol = caps.get(2).and_then(|m| m.as_str().chars().next());
+            match best {
+                Some((true, _, _)) if !specific => {}
+                _ => best = Some((specific, amount, symbol)),
+            }
+        }
+    }
+    match best {
+        Some((_, amount, symbol)) => (Some(amount), symbol),
+        None => (None, None),
+    }
+}
+
+fn extract_vendor(lines: &[&str]) -> Option<String> {
+    if let Some(vendor) = lines.iter().find_map(|line| {
+        VENDOR_PREFIX
+            .captures(line)
+            .map(|c| c[1].trim().to_string())
+    }) {
+        return Some(vendor);
+    }
+
+    // otherwise the merchant name is typically the first line of the header
+    lines
+        .iter()
+        .take(5)
+        .find(|line| {
+            let line_words = words(line);
+            line.len() >= 2
+                && line.len() <= 60
+                && line.chars().any(char::is_alphabetic)
+                && !AMOUNT_AT_END.is_match(line)
+                && !DATE.is_match(line)
+                && !KEYWORD_WORDS
+                    .iter()
+                    .any(|keyword| has_phrase(&line_words, keyword))
+        })
+        .map(|line| line.to_string())
+}
+
+// lowercased runs of letters and digits, "#" kept as a word of its own
+fn words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            word.extend(c.to_lowercase());
+            continue;
+        }
+        if !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        if c == '#' {
+            words.push("#".to_string());
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+fn has_phrase(words: &[String], phrase: &[String]) -> bool {
+    !phrase.is_empty() && words.windows(phrase.len()).any(|window| window == phrase)
+}
+
+fn parse_amount(raw: &str) -> Option<f64> {
+    let chars: Vec<char> = raw.chars().filter(|c| !c.is_whitespace()).collect();
+    // the separator two digits from the end is the decimal one, whatever the locale
+    let (int_part, frac_part) = chars.split_at(chars.len().checked_sub(3)?);
+    let int_part: String = int_part.iter().filter(|c| c.is_ascii_digit()).collect();
+    let frac_part: String = frac_part[1..].iter().collect();
+    format!("{}.{}", int_part, frac_part).parse().ok()
+}
+
+fn currency_from_symbol(symbol: char) -> Option<String> {
+    let code = match symbol {
+        '$' => "USD",
+        '€' => "EUR",
+        '£' => "GBP",
+        '¥' => "JPY",
+        _ => return None,
+    };
+    Some(code.to_string())
+}
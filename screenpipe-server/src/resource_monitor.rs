@@ -1,5 +1,6 @@
 use chrono::Local;
 use reqwest::Client;
+use screenpipe_core::egress;
 use serde_json::json;
 use serde_json::Value;
 use std::env;
@@ -106,6 +107,9 @@ impl ResourceMonitor {
         trace!(target: "resource_monitor", "Sending resource usage to PostHog: {:?}", payload);
 
         // Send the event to PostHog
+        if egress::check_egress("https://eu.i.posthog.com/capture/").is_err() {
+            return;
+        }
         if let Err(e) = client
             .post("https://eu.i.posthog.com/capture/")
             .json(&payload)
@@ -0,0 +1,261 @@
+use chrono::Utc;
+use reqwest::Client;
+use screenpipe_core::egress;
+use screenpipe_db::{DatabaseManager, PrunedData, RetentionRule, RetentionScope, TrashCount};
+use serde_json::json;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+
+use crate::audit::audit;
+
+// deletions run in small transactions so capture can keep writing in between
+const PRUNE_BATCH_SIZE: u32 = 500;
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Deletes recordings once they are older than the retention period. Rules
+/// are evaluated before the age-based default: tag rules
+/// (`tag:receipts=forever`, `tag:youtube=7d`) first, then app rules
+/// (`app:Chase=0d`), then content type rules (`audio=30d`, `vision=365d`,
+/// `ui=90d`, `clipboard=7d`), so some data can be kept longer or dropped
+/// sooner. What each rule deleted is counted in `retention_rule_stats`.
+///
+/// Expired recordings, ui text and clipboard entries first go to the trash,
+/// where they can be previewed and restored through the api, and are purged
+/// for good `trash_days` later. What a rule keeping 0 days expires is purged
+/// right away, the purge webhook still asked first.
+pub struct RetentionManager {
+    db: Arc<DatabaseManager>,
+    /// `None` keeps data no rule applies to forever
+    default_days: Option<u32>,
+    rules: Vec<RetentionRule>,
+    trash_days: u32,
+    /// asked before each purge, which only goes ahead on a 2xx answer
+    purge_webhook: Option<String>,
+    client: Client,
+}
+
+impl RetentionManager {
+    pub fn new(
+        db: Arc<DatabaseManager>,
+        default_days: Option<u32>,
+        rules: Vec<RetentionRule>,
+        trash_days: u32,
+        purge_webhook: Option<String>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            db,
+            default_days,
+            rules,
+            trash_days,
+            purge_webhook,
+            client: egress::http_client(),
+        })
+    }
+
+    pub fn start(self: &Arc<Self>, interval: Duration) {
+        let manager = Arc::clone(self);
+        tokio::spawn(async move {
+            if let Err(e) = manager
+                .db
+                .register_retention_rules(manager.default_days, &manager.rules)
+                .await
+            {
+                error!("retention: failed to record the rules: {}", e);
+            }
+            loop {
+                match manager.trash().await {
+                    Ok(trashed) if trashed.frames > 0 || trashed.audio_chunks > 0 => info!(
+                        "retention: moved {} frames and {} audio chunks to the trash",
+                        trashed.frames, trashed.audio_chunks
+                    ),
+                    Ok(_) => debug!("retention: nothing expired"),
+                    Err(e) => error!("retention: failed to trash old data: {}", e),
+                }
+                match manager.trash_ui().await {
+                    Ok(0) => {}
+                    Ok(trashed) => {
+                        info!(
+                            "retention: moved {} rows of ui text and clipboard entries to the trash",
+                            trashed
+                        )
+                    }
+                    Err(e) => error!("retention: failed to trash old ui text: {}", e),
+                }
+                match manager.delete_browser_contexts().await {
+                    Ok(0) => {}
+                    Ok(deleted) => info!("retention: deleted {} browser contexts", deleted),
+                    Err(e) => error!("retention: failed to delete old browser contexts: {}", e),
+                }
+                match manager.purge().await {
+                    Ok(pruned) if pruned.frames + pruned.audio_chunks + pruned.ui_rows > 0 => {
+                        info!(
+                            "retention: deleted {} frames, {} audio chunks and {} rows of ui text and clipboard entries",
+                            pruned.frames, pruned.audio_chunks, pruned.ui_rows
+                        )
+                    }
+                    Ok(_) => debug!("retention: nothing to delete"),
+                    Err(e) => error!("retention: failed to purge the trash: {}", e),
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+
+    /// Moves everything past its retention to the trash.
+    pub async fn trash(&self) -> Result<TrashCount, sqlx::Error> {
+        let now = Utc::now();
+        let mut total = TrashCount::default();
+        loop {
+            let trashed = self
+                .db
+                .trash_expired(now, self.default_days, &self.rules, PRUNE_BATCH_SIZE)
+                .await?;
+            total.frames += trashed.frames;
+            total.audio_chunks += trashed.audio_chunks;
+            if trashed.frames < PRUNE_BATCH_SIZE as u64
+                && trashed.audio_chunks < PRUNE_BATCH_SIZE as u64
+            {
+                return Ok(total);
+            }
+            tokio::task::yield_now().await;
+        }
+    }
+
+    /// Moves the ui text and clipboard entries past their retention to the
+    /// trash.
+    pub async fn trash_ui(&self) -> Result<u64, sqlx::Error> {
+        let now = Utc::now();
+        let mut total = 0;
+        loop {
+            let trashed = self
+                .db
+                .trash_expired_ui(now, &self.rules, PRUNE_BATCH_SIZE)
+                .await?;
+            total += trashed;
+            // a batch per table
+            if trashed < PRUNE_BATCH_SIZE as u64 {
+                return Ok(total);
+            }
+            tokio::task::yield_now().await;
+        }
+    }
+
+    // the rules whose data isn't kept at all skip the wait in the trash
+    fn purge_now(&self) -> Vec<String> {
+        let default = (self.default_days == Some(0)).then(|| "default".to_string());
+        self.rules
+            .iter()
+            .filter(|rule| rule.keep_days == Some(0))
+            .map(|rule| rule.scope.to_string())
+            .chain(default)
+            .collect()
+    }
+
+    /// Deletes the browser contexts no frame links to once past the vision
+    /// retention, the linked ones go with their frames.
+    pub async fn delete_browser_contexts(&self) -> Result<u64, sqlx::Error> {
+        let keep_days = self
+            .rules
+            .iter()
+            .find(|rule| rule.scope == RetentionScope::Vision)
+            .map_or(self.default_days, |rule| rule.keep_days);
+        let Some(keep_days) = keep_days else {
+            return Ok(0);
+        };
+        let before = Utc::now() - chrono::Duration::days(keep_days as i64);
+        let mut total = 0;
+        loop {
+            let deleted = self
+                .db
+                .delete_unlinked_browser_contexts(before, PRUNE_BATCH_SIZE)
+                .await?;
+            total += deleted;
+            if deleted < PRUNE_BATCH_SIZE as u64 {
+                return Ok(total);
+            }
+            tokio::task::yield_now().await;
+        }
+    }
+
+    /// Deletes what has been in the trash for `trash_days`, batch by batch,
+    /// and removes the media files no longer referenced. Nothing is deleted
+    /// when the purge webhook doesn't confirm.
+    pub async fn purge(&self) -> Result<PrunedData, sqlx::Error> {
+        let trashed_before = Utc::now() - chrono::Duration::days(self.trash_days as i64);
+        let purge_now = self.purge_now();
+        let mut total = PrunedData::default();
+
+        if let Some(webhook) = &self.purge_webhook {
+            let due = self.db.count_purgeable(trashed_before, &purge_now).await?;
+            if due.frames == 0 && due.audio_chunks == 0 && due.ui_rows == 0 {
+                return Ok(total);
+            }
+            if let Err(e) = self.confirm_purge(webhook, due).await {
+                warn!("retention: purge not confirmed, keeping the trash: {}", e);
+                return Ok(total);
+            }
+        }
+
+        loop {
+            let pruned = self
+                .db
+                .purge_trash(trashed_before, &purge_now, PRUNE_BATCH_SIZE)
+                .await?;
+            for file in pruned.video_files.iter().chain(&pruned.audio_files) {
+                remove_file(file).await;
+            }
+
+            // up to a batch of each kind of ui text and of clipboard entries
+            let done = pruned.frames < PRUNE_BATCH_SIZE as u64
+                && pruned.audio_chunks < PRUNE_BATCH_SIZE as u64
+                && pruned.ui_rows < PRUNE_BATCH_SIZE as u64;
+            total.frames += pruned.frames;
+            total.audio_chunks += pruned.audio_chunks;
+            total.ui_rows += pruned.ui_rows;
+            total.video_files.extend(pruned.video_files);
+            total.audio_files.extend(pruned.audio_files);
+            if done {
+                let purged = total.frames + total.audio_chunks + total.ui_rows;
+                if purged > 0 {
+                    audit(
+                        &self.db,
+                        "purge_trash",
+                        &format!("trashed before {}", trashed_before.to_rfc3339()),
+                        Some(purged as i64),
+                    )
+                    .await;
+                }
+                return Ok(total);
+            }
+            tokio::task::yield_now().await;
+        }
+    }
+
+    async fn confirm_purge(&self, webhook: &str, due: TrashCount) -> anyhow::Result<()> {
+        egress::check_egress(webhook)?;
+        self.client
+            .post(webhook)
+            .timeout(WEBHOOK_TIMEOUT)
+            .json(&json!({
+                "event": "trash_purge",
+                "frames": due.frames,
+                "audio_chunks": due.audio_chunks,
+                "ui_rows": due.ui_rows,
+            }))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+async fn remove_file(path: &str) {
+    if !Path::new(path).exists() {
+        return;
+    }
+    if let Err(e) = tokio::fs::remove_file(path).await {
+        warn!("retention: failed to remove {}: {}", path, e);
+    }
+}
@@ -0,0 +1,296 @@
+use anyhow::Result;
+use regex::Regex;
+use reqwest::Client;
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Scope, AST};
+use screenpipe_core::egress;
+use screenpipe_db::{DatabaseManager, InsertEvent, TagContentType};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, error, info, warn};
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+/// Operations a script may run per event, loops included.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// What a script asked for while handling an event, done once it returned.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptAction {
+    AddTag(String),
+    SetMetadata { key: String, value: String },
+    CallWebhook { url: String, body: Value },
+}
+
+/// Rhai scripts run on each insert event. A script sees the event as
+/// `event` and has `add_tag(tag)`, `set_metadata(key, value)` and
+/// `call_webhook(url, body)`, plus `matches(text, pattern)` and
+/// `find_all(text, pattern)` for regexes. Nothing else is reachable: no
+/// files, no processes, no modules.
+pub struct InsertScripts {
+    engine: Engine,
+    scripts: Vec<(String, AST)>,
+    actions: Arc<Mutex<Vec<ScriptAction>>>,
+    // one event at a time, the actions are collected per run
+    running: Mutex<()>,
+}
+
+fn regex(
+    cache: &Mutex<HashMap<String, Regex>>,
+    pattern: &str,
+) -> Result<Regex, Box<EvalAltResult>> {
+    let mut cache = cache.lock().unwrap();
+    if let Some(regex) = cache.get(pattern) {
+        return Ok(regex.clone());
+    }
+    let regex = Regex::new(pattern).map_err(|e| e.to_string())?;
+    cache.insert(pattern.to_string(), regex.clone());
+    Ok(regex)
+}
+
+impl InsertScripts {
+    /// Compiles the `.rhai` files of `dir` in the order of their names, those
+    /// that don't compile are logged and skipped.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let mut sources = Vec::new();
+        if dir.exists() {
+            let mut paths = std::fs::read_dir(dir)?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "rhai"))
+                .collect::<Vec<_>>();
+            paths.sort();
+            for path in paths {
+                let name = path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                sources.push((name, std::fs::read_to_string(&path)?));
+            }
+        }
+        Ok(Self::new(sources))
+    }
+
+    /// Compiles named script sources.
+    pub fn new(sources: Vec<(String, String)>) -> Self {
+        let actions = Arc::new(Mutex::new(Vec::new()));
+        let regexes = Arc::new(Mutex::new(HashMap::new()));
+        let mut engine = Engine::new();
+        // `import` would read other files
+        engine.set_module_resolver(DummyModuleResolver::new());
+        engine
+            .set_max_operations(MAX_OPERATIONS)
+            .set_max_call_levels(32)
+            .set_max_string_size(1 << 20)
+            .set_max_array_size(10_000)
+            .set_max_map_size(10_000)
+            .on_print(|text| info!("script: {}", text))
+            .on_debug(|text, _, _| debug!("script: {}", text));
+
+        let add_tag = actions.clone();
+        engine.register_fn("add_tag", move |tag: &str| {
+            add_tag
+                .lock()
+                .unwrap()
+                .push(ScriptAction::AddTag(tag.to_string()));
+        });
+        let set_metadata = actions.clone();
+        engine.register_fn("set_metadata", move |key: &str, value: Dynamic| {
+            set_metadata
+                .lock()
+                .unwrap()
+                .push(ScriptAction::SetMetadata {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                });
+        });
+        let call_webhook = actions.clone();
+        engine.register_fn(
+            "call_webhook",
+            move |url: &str, body: Dynamic| -> Result<(), Box<EvalAltResult>> {
+                let body = rhai::serde::from_dynamic::<Value>(&body)?;
+                call_webhook
+                    .lock()
+                    .unwrap()
+                    .push(ScriptAction::CallWebhook {
+                        url: url.to_string(),
+                        body,
+                    });
+                Ok(())
+            },
+        );
+        let cache = regexes.clone();
+        engine.register_fn(
+            "matches",
+            move |text: &str, pattern: &str| -> Result<bool, Box<EvalAltResult>> {
+                Ok(regex(&cache, pattern)?.is_match(text))
+            },
+        );
+        let cache = regexes;
+        engine.register_fn(
+            "find_all",
+            move |text: &str, pattern: &str| -> Result<Array, Box<EvalAltResult>> {
+                Ok(regex(&cache, pattern)?
+                    .find_iter(text)
+                    .map(|found| Dynamic::from(found.as_str().to_string()))
+                    .collect())
+            },
+        );
+
+        let scripts = sources
+            .into_iter()
+            .filter_map(|(name, source)| match engine.compile(&source) {
+                Ok(ast) => {
+                    info!("loaded insert script {}", name);
+                    Some((name, ast))
+                }
+                Err(e) => {
+                    warn!("failed to compile insert script {}: {}", name, e);
+                    None
+                }
+            })
+            .collect();
+        Self {
+            engine,
+            scripts,
+            actions,
+            running: Mutex::new(()),
+        }
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.scripts.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    /// Runs every script on `event`, returning what each asked for. A script
+    /// that fails asks for nothing.
+    pub fn run(&self, event: &InsertEvent) -> Vec<(String, ScriptAction)> {
+        let _running = self.running.lock().unwrap();
+        let event = match rhai::serde::to_dynamic(event) {
+            Ok(event) => event,
+            Err(e) => {
+                error!("failed to pass insert event {} to scripts: {}", event.id, e);
+                return Vec::new();
+            }
+        };
+        let mut requested = Vec::new();
+        for (name, ast) in &self.scripts {
+            self.actions.lock().unwrap().clear();
+            let mut scope = Scope::new();
+            scope.push_constant("event", event.clone());
+            let result = self.engine.run_ast_with_scope(&mut scope, ast);
+            let actions = std::mem::take(&mut *self.actions.lock().unwrap());
+            match result {
+                Ok(()) => requested.extend(actions.into_iter().map(|a| (name.clone(), a))),
+                Err(e) => warn!("insert script {} failed: {}", name, e),
+            }
+        }
+        requested
+    }
+}
+
+/// Runs the insert scripts on the OCR text and transcriptions the database
+/// publishes, and does what they ask for.
+pub struct ScriptRunner {
+    db: Arc<DatabaseManager>,
+    scripts: Arc<InsertScripts>,
+    client: Client,
+}
+
+fn content_type(event: &InsertEvent) -> TagContentType {
+    if event.content_type == "audio" {
+        TagContentType::Audio
+    } else {
+        TagContentType::Vision
+    }
+}
+
+impl ScriptRunner {
+    pub fn new(db: Arc<DatabaseManager>, scripts: InsertScripts) -> Arc<Self> {
+        Arc::new(Self {
+            db,
+            scripts: Arc::new(scripts),
+            client: egress::http_client(),
+        })
+    }
+
+    pub fn start(self: &Arc<Self>) {
+        let runner = Arc::clone(self);
+        let mut events = runner.db.subscribe_insert_events();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => runner.handle(event).await,
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("insert scripts: dropped {} events", skipped)
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    async fn handle(&self, event: InsertEvent) {
+        let scripts = Arc::clone(&self.scripts);
+        let script_event = event.clone();
+        let actions = match tokio::task::spawn_blocking(move || scripts.run(&script_event)).await {
+            Ok(actions) => actions,
+            Err(e) => {
+                error!("insert scripts panicked: {}", e);
+                return;
+            }
+        };
+
+        let mut tags = Vec::new();
+        for (name, action) in actions {
+            match action {
+                ScriptAction::AddTag(tag) => tags.push(tag),
+                ScriptAction::SetMetadata { key, value } => {
+                    if let Err(e) = self
+                        .db
+                        .set_metadata(event.target_id, content_type(&event), &key, &value)
+                        .await
+                    {
+                        error!("insert script {}: failed to set {}: {}", name, key, e);
+                    }
+                }
+                ScriptAction::CallWebhook { url, body } => {
+                    // a slow webhook shouldn't hold back the next events
+                    let client = self.client.clone();
+                    tokio::spawn(async move { post(&client, &name, &url, &body).await });
+                }
+            }
+        }
+        tags.retain(|tag| !tag.trim().is_empty());
+        tags.sort();
+        tags.dedup();
+        if !tags.is_empty() {
+            if let Err(e) = self
+                .db
+                .add_tags(event.target_id, content_type(&event), tags)
+                .await
+            {
+                error!("insert scripts: failed to tag {}: {}", event.target_id, e);
+            }
+        }
+    }
+}
+
+async fn post(client: &Client, script: &str, url: &str, body: &Value) {
+    if let Err(e) = egress::check_egress(url) {
+        error!("insert script {}: {}", script, e);
+        return;
+    }
+    let result = client
+        .post(url)
+        .timeout(WEBHOOK_TIMEOUT)
+        .json(body)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+    if let Err(e) = result {
+        error!("insert script {}: failed to call {}: {}", script, url, e);
+    }
+}
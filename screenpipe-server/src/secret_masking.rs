@@ -0,0 +1,130 @@
+use anyhow::Result;
+use screenpipe_core::SecretMasker;
+use screenpipe_db::{ContentHook, ContentProcessor, DatabaseManager, HookContent, HookOutcome};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error};
+
+const SCAN_BATCH: u32 = 200;
+
+/// Masks secrets in the ocr text of a window and in its text blocks, which
+/// hold the same words. A secret can be split across blocks, so the blocks
+/// are masked where a secret found in their joined text overlaps them.
+pub fn mask_window_text(
+    masker: &SecretMasker,
+    text: &str,
+    blocks: &[HashMap<String, String>],
+) -> (String, Vec<HashMap<String, String>>) {
+    let mut joined = String::new();
+    let mut spans = Vec::with_capacity(blocks.len());
+    for block in blocks {
+        if !joined.is_empty() {
+            joined.push(' ');
+        }
+        let start = joined.len();
+        joined.push_str(block.get("text").map(String::as_str).unwrap_or_default());
+        spans.push((start, joined.len()));
+    }
+    let secrets = masker.find(&joined);
+    let blocks = blocks
+        .iter()
+        .zip(spans)
+        .map(|(block, (start, end))| {
+            let mut block = block.clone();
+            if let Some(text) = block.get_mut("text") {
+                // back to front, so the earlier offsets still hold
+                for secret in secrets.iter().rev() {
+                    if secret.end <= start || secret.start >= end {
+                        continue;
+                    }
+                    let range = secret.start.max(start) - start..secret.end.min(end) - start;
+                    text.replace_range(range, &format!("[REDACTED:{}]", secret.rule));
+                }
+            }
+            block
+        })
+        .collect();
+    (masker.mask(text), blocks)
+}
+
+/// Masks secrets in the OCR text and transcriptions the database stores,
+/// whatever records them, before the other content processors see them.
+pub struct SecretMaskingProcessor {
+    masker: Arc<SecretMasker>,
+}
+
+impl SecretMaskingProcessor {
+    pub fn new(masker: Arc<SecretMasker>) -> Arc<Self> {
+        Arc::new(Self { masker })
+    }
+}
+
+impl ContentProcessor for SecretMaskingProcessor {
+    fn process(&self, content: &HookContent) -> HookOutcome {
+        if content.hook == ContentHook::OnFrame {
+            return HookOutcome::default();
+        }
+        let masked = self.masker.mask(&content.text);
+        if masked == content.text {
+            return HookOutcome::default();
+        }
+        HookOutcome {
+            text: Some(masked.clone()),
+            // what was masked isn't kept as read either
+            raw_text: Some(masked),
+            ..Default::default()
+        }
+    }
+
+    fn process_blocks(
+        &self,
+        content: &HookContent,
+        blocks: &[HashMap<String, String>],
+    ) -> Option<Vec<HashMap<String, String>>> {
+        Some(mask_window_text(&self.masker, &content.text, blocks).1)
+    }
+}
+
+/// Masks secrets in the text the ui recorder writes. It runs in another
+/// process, so while masking is on its writes are held back by the database
+/// and only reach ui_monitoring once masked here.
+pub struct UiSecretScrubber {
+    db: Arc<DatabaseManager>,
+    masker: Arc<SecretMasker>,
+}
+
+impl UiSecretScrubber {
+    pub fn new(db: Arc<DatabaseManager>, masker: Arc<SecretMasker>) -> Arc<Self> {
+        Arc::new(Self { db, masker })
+    }
+
+    pub fn start(self: &Arc<Self>, interval: Duration) {
+        let scrubber = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                match scrubber.scrub().await {
+                    Ok(0) => {}
+                    Ok(masked) => debug!("masked secrets in {} ui rows", masked),
+                    Err(e) => error!("failed to mask secrets in ui text: {}", e),
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+
+    /// Writes the held ui text masked, returns how many rows had secrets.
+    pub async fn scrub(&self) -> Result<usize> {
+        let mut masked = 0;
+        loop {
+            let (held, changed) = self
+                .db
+                .release_held_ui_text(SCAN_BATCH, |text| self.masker.mask(text))
+                .await?;
+            masked += changed;
+            if held == 0 {
+                return Ok(masked);
+            }
+        }
+    }
+}
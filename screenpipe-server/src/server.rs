@@ -2,21 +2,40 @@ use axum::{
     body::Body,
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
-        Json, Path, Query, State,
+        Json, OriginalUri, Path, Query, RawQuery, State,
     },
-    http::StatusCode,
-    response::{IntoResponse, Json as JsonResponse, Response},
-    routing::get,
+    http::{
+        header::{CONTENT_TYPE, HOST},
+        HeaderMap, StatusCode,
+    },
+    middleware,
+    response::{
+        sse::{Event as SseEvent, KeepAlive, Sse},
+        IntoResponse, Json as JsonResponse, Response,
+    },
+    routing::{get, post},
     serve, Router,
 };
 use oasgen::{oasgen, OaSchema, Server};
 
-use screenpipe_core::Desktop;
+use screenpipe_core::{Desktop, Language, SecretMasker};
 
+use base64::{engine::general_purpose, Engine as _};
 use chrono::TimeZone;
 use screenpipe_db::{
-    ContentType, DatabaseManager, FrameData, Order, SearchMatch, SearchResult, Speaker,
-    TagContentType,
+    normalize_query, normalize_tag, ApiToken, AppCategoryRule, AuditEntry, BulkFilter, BulkResult,
+    CalendarHint, CaptureBlockRule, CapturePause, CapturePauseSchedule, ClickBoosts, ClickSignal,
+    ClipboardEntry, ClockOffset, ContentMetadata, ContentType, CoverageReport, DatabaseManager,
+    Device, EmbeddingIndexStatus, EmbeddingQuantization, FocusSession, FrameData, FrameRedaction,
+    IdleInterval, InputActivityReport, MediaChunkKind, MediaIssue, Meeting, MeetingParticipant,
+    MeetingSlide, NewCalendarHint, NewCaptureBlockRule, NewPushDestination, NewSearchClick,
+    NewTagRule, NewWebhookRule, OcrTable, Order, PeriodTopic, PushDestination, QueryTimedOut,
+    Receipt, ResultCount, RetentionRuleStats, SearchDeleteFilter, SearchDeletion, SearchMatch,
+    SearchRanking, SearchResult, Speaker, SpeakerCompaction, Subject, SubjectExport,
+    TagContentType, TagFacet, TagFilter, TagRule, TagSuggestion, TagSummary, TagUpdate, TextBounds,
+    TextProvenance, TextSpan, Timeline, TranscriptLine, TrashCount, TrashGroup, TrashItem,
+    UiChange, UiTraversal, WebhookRule, WindowFocusTime, WorkSession, CAPTURE_BLOCK_KINDS,
+    CAPTURE_PAUSE_CONTENT_TYPES, OCR_TEXT_EMBEDDINGS,
 };
 
 use tokio_util::io::ReaderStream;
@@ -25,35 +44,76 @@ use tokio::fs::File;
 
 use futures::{
     future::{try_join, try_join_all},
-    SinkExt, StreamExt,
+    SinkExt, Stream, StreamExt,
 };
 use image::ImageFormat::{self};
 use screenpipe_events::{send_event, subscribe_to_all_events, Event as ScreenpipeEvent};
 
 use crate::{
+    audit::{audit, scope_audit_actor},
+    auth::{create_token, require_token, ApiScope},
+    calendar::export_calendar,
+    clock_sync::{to_server_time, ClockExchange},
+    content_v2::{item_id, paginate, sort_items, ContentItemV2, SearchResponseV2},
+    devices::{audio_device_name, monitor_id, validate_device_settings},
     embedding::embedding_endpoint::create_embeddings,
-    video::{finish_ffmpeg_process, start_ffmpeg_process, write_frame_to_ffmpeg, MAX_FPS},
+    extensions::{ExtensionManager, ExtensionStatus},
+    heatmap::{activity_heatmap, ActivityHeatmap},
+    journal::{export_journal, export_meeting},
+    local_only::{self, LocalOnlyStatus},
+    maintenance::{MaintenanceScheduler, MaintenanceStatus},
+    media_encryption::plain_media,
+    outage_monitor::RecordingHours,
+    pause::{CapturePauseStatus, CapturePauses},
+    peers::{Peer, PeerSearchResults, Peers, PEER_SIGNATURE_HEADER},
+    plugins::{PluginHost, PluginStatus},
+    productivity::{productivity, DayProductivity},
+    rate_limit::{rate_limit, RateLimiter, RateLimits},
+    shadow::{compare_pages, ShadowCandidate, ShadowConfig, ShadowReport, ShadowRunner},
+    storage_quota::{StorageQuota, StorageStatus},
+    subject_erasure::{
+        erase_subject, load_or_create_signing_key, verify_report, ErasureReport, SIGNING_KEY_FILE,
+    },
+    summary::{summarize, Summary, SummaryLlm, SummaryPeriod},
+    text_pipeline::TextPipeline,
+    thumbnails::{
+        frame_thumbnail, DEFAULT_THUMBNAIL_WIDTH, MAX_THUMBNAIL_WIDTH, MIN_THUMBNAIL_WIDTH,
+    },
+    topics::{extract_day, normalize_term, topic_trends, TopicTrend},
+    usage_csv::{csv_header, csv_row, parse_columns, UsageColumn},
+    video::{
+        finish_ffmpeg_process, start_ffmpeg_process, write_frame_to_ffmpeg, VideoEncoding, MAX_FPS,
+    },
     video_cache::{AudioEntry, DeviceFrame, FrameCache, FrameMetadata, TimeSeriesFrame},
     video_utils::{
         extract_frame, extract_frame_from_video, extract_high_quality_frame, merge_videos,
-        validate_media, MergeVideosRequest, MergeVideosResponse, ValidateMediaParams,
+        redact_video_frame, validate_media, MergeVideosRequest, MergeVideosResponse,
+        ValidateMediaParams,
     },
+    work_sessions::{group_by_session, SessionGroup, DEFAULT_BREAK_MINUTES},
     PipeManager,
 };
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Local, NaiveDate, Utc};
 use screenpipe_audio::{
     audio_manager::AudioManager,
     core::device::{
         default_input_device, default_output_device, list_audio_devices, AudioDevice, DeviceType,
     },
 };
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
-use screenpipe_vision::monitor::{get_monitor_by_id, list_monitors};
-use screenpipe_vision::OcrEngine;
+use screenpipe_vision::capture_screenshot_by_window::WindowFilters;
+use screenpipe_vision::capture_settings::{
+    get_monitor_settings, set_monitor_settings, MonitorCaptureSettings,
+};
+use screenpipe_vision::monitor::{get_monitor_by_id, list_monitors, SafeMonitor};
+use screenpipe_vision::whiteboard::enhance_whiteboard;
+use screenpipe_vision::{capture_now, perform_ocr_with_engine, OcrEngine};
 use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::{json, Value};
 use std::{
+    collections::{HashSet, VecDeque},
+    convert::Infallible,
     net::SocketAddr,
     num::NonZeroUsize,
     path::PathBuf,
@@ -92,10 +152,26 @@ pub struct AppState {
     pub ui_monitoring_enabled: bool,
     pub frame_cache: Option<Arc<FrameCache>>,
     pub frame_image_cache: Option<Arc<Mutex<FrameImageCache>>>,
+    pub ocr_engine: Arc<OcrEngine>,
+    pub languages: Vec<Language>,
+    pub ignored_windows: Vec<String>,
+    pub included_windows: Vec<String>,
+    pub trash_days: u32,
+    pub click_tracking: bool,
+    pub shadow: Option<Arc<ShadowRunner>>,
+    pub maintenance: Option<Arc<MaintenanceScheduler>>,
+    pub storage_quota: Option<Arc<StorageQuota>>,
+    pub capture_pauses: Option<Arc<CapturePauses>>,
+    pub secret_masker: Option<Arc<SecretMasker>>,
+    pub plugins: Option<Arc<PluginHost>>,
+    pub text_pipeline: Option<Arc<TextPipeline>>,
+    pub extensions: Option<Arc<ExtensionManager>>,
+    pub summary_llm: Option<Arc<SummaryLlm>>,
+    pub peers: Option<Arc<Peers>>,
 }
 
 // Update the SearchQuery struct
-#[derive(OaSchema, Deserialize)]
+#[derive(OaSchema, Deserialize, Clone)]
 pub(crate) struct SearchQuery {
     q: Option<String>,
     #[serde(flatten)]
@@ -125,11 +201,58 @@ pub(crate) struct SearchQuery {
     speaker_ids: Option<Vec<i64>>,
     #[serde(default)]
     focused: Option<bool>,
+    /// Only return OCR results of frames showing the url: a site with its
+    /// subdomains and an optional path prefix, `*` matching anything in it,
+    /// e.g. "docs.rs/tokio/*/sync", or a word of the url
     #[serde(default)]
     browser_url: Option<String>,
+    /// Only return OCR results whose frame confidence (0..1) is at least this
+    #[serde(default)]
+    min_confidence: Option<f64>,
+    /// Only return OCR results with a detected table, matching `q` inside the table
+    #[serde(default)]
+    in_tables: bool,
+    #[serde(default)]
+    ranking: SearchRanking,
+    /// Age at which a result counts half as much, with the `decayed` ranking
+    #[serde(default = "default_half_life_days")]
+    half_life_days: f64,
+    /// Estimate the total from the most recent matches instead of counting
+    /// them all, much faster on a big database
+    #[serde(default)]
+    approximate_count: bool,
+    /// Comma separated tags results must all have, a tag matching the tags
+    /// below it too: `project/alpha` matches `project/alpha/design`. Same as
+    /// `tags_all`
+    #[serde(default, deserialize_with = "from_comma_separated_string")]
+    tags: Option<Vec<String>>,
+    /// Comma separated tags results must have at least one of
+    #[serde(default, deserialize_with = "from_comma_separated_string")]
+    tags_any: Option<Vec<String>>,
+    /// Comma separated tags results must all have
+    #[serde(default, deserialize_with = "from_comma_separated_string")]
+    tags_all: Option<Vec<String>>,
+    /// Comma separated tags results must have none of
+    #[serde(default, deserialize_with = "from_comma_separated_string")]
+    tags_none: Option<Vec<String>>,
+    /// Also group the results of the page by the work session they fall in,
+    /// see /work-sessions
+    #[serde(default)]
+    group_by_session: bool,
 }
 
-#[derive(OaSchema, Deserialize)]
+impl SearchQuery {
+    fn tag_filter(&self) -> TagFilter {
+        let tags = |tags: &Option<Vec<String>>| tags.clone().unwrap_or_default();
+        TagFilter {
+            any: tags(&self.tags_any),
+            all: [tags(&self.tags), tags(&self.tags_all)].concat(),
+            none: tags(&self.tags_none),
+        }
+    }
+}
+
+#[derive(OaSchema, Deserialize, Clone)]
 pub(crate) struct PaginationQuery {
     #[serde(default = "default_limit")]
     #[serde(deserialize_with = "deserialize_number_from_string")]
@@ -159,6 +282,9 @@ pub struct PaginationInfo {
     pub limit: u32,
     pub offset: u32,
     pub total: i64,
+    /// the total is an estimate, see `approximate_count`
+    #[serde(default)]
+    pub total_is_approximate: bool,
 }
 
 #[derive(OaSchema, Serialize, Deserialize, Debug)]
@@ -189,6 +315,7 @@ pub enum ContentItem {
     OCR(OCRContent),
     Audio(AudioContent),
     UI(UiContent),
+    Clipboard(ClipboardEntry),
 }
 
 #[derive(OaSchema, Serialize, Deserialize, Debug)]
@@ -205,6 +332,13 @@ pub struct OCRContent {
     pub frame_name: Option<String>,
     pub browser_url: Option<String>,
     pub focused: Option<bool>,
+    #[serde(default)]
+    pub tables: Vec<OcrTable>,
+    /// title of the tab the browser extension reported around the frame
+    #[serde(default)]
+    pub browser_title: Option<String>,
+    #[serde(default)]
+    pub browser_selection: Option<String>,
 }
 
 #[derive(OaSchema, Serialize, Deserialize, Debug)]
@@ -234,6 +368,7 @@ pub struct UiContent {
     pub offset_index: i64,
     pub frame_name: Option<String>,
     pub browser_url: Option<String>,
+    pub tags: Vec<String>,
 }
 
 #[derive(OaSchema, Serialize)]
@@ -276,6 +411,20 @@ fn default_limit() -> u32 {
     20
 }
 
+fn default_half_life_days() -> f64 {
+    7.0
+}
+
+fn half_life(days: f64) -> Result<chrono::Duration, (StatusCode, JsonResponse<Value>)> {
+    if !days.is_finite() || days <= 0.0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "half_life_days must be positive"})),
+        ));
+    }
+    Ok(chrono::Duration::milliseconds((days * 86_400_000.0) as i64))
+}
+
 #[derive(Serialize, OaSchema, Deserialize)]
 pub struct HealthCheckResponse {
     pub status: String,
@@ -289,14 +438,30 @@ pub struct HealthCheckResponse {
     pub message: String,
     pub verbose_instructions: Option<String>,
     pub device_status_details: Option<String>,
+    /// scheduled database maintenance, when enabled
+    pub maintenance: Option<MaintenanceStatus>,
+    /// the storage budget and what was evicted to meet it, when set
+    pub storage: Option<StorageStatus>,
+    /// what is paused, see /capture/pause
+    pub capture_pauses: Option<CapturePauseStatus>,
+    /// whether outbound calls are refused, and how many were
+    pub local_only: LocalOnlyStatus,
 }
 
 #[derive(OaSchema, Serialize, Deserialize)]
 pub struct SearchResponse {
     pub data: Vec<ContentItem>,
     pub pagination: PaginationInfo,
+    /// with `group_by_session`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sessions: Vec<SessionGroup>,
 }
 
+// decayed ranking reorders this many times the results asked for, at least
+// the minimum
+const DECAYED_RANKING_WINDOW_FACTOR: u32 = 5;
+const MIN_DECAYED_RANKING_WINDOW: u32 = 200;
+
 // Update the search function
 #[oasgen]
 pub(crate) async fn search(
@@ -321,95 +486,64 @@ pub(crate) async fn search(
         query.focused,
     );
 
+    let started = Instant::now();
     let query_str = query.q.as_deref().unwrap_or("");
-
-    let content_type = query.content_type.clone();
-
-    let (results, total) = try_join(
-        state.db.search(
-            query_str,
-            content_type.clone(),
+    // decayed ranking reorders a window of the most recent matches
+    let decayed = query.ranking == SearchRanking::Decayed && !query_str.trim().is_empty();
+    let approximate = query.approximate_count;
+    let (results, total) = if decayed {
+        let window = decayed_ranking_window(&query);
+        let (results, total) = search_results(&state, &query, window, 0, approximate).await?;
+        let results = rank_by_decayed_relevance(&state, &query, results)
+            .await?
+            .into_iter()
+            .skip(query.pagination.offset as usize)
+            .take(query.pagination.limit as usize)
+            .collect();
+        (results, total)
+    } else {
+        search_results(
+            &state,
+            &query,
             query.pagination.limit,
             query.pagination.offset,
-            query.start_time,
-            query.end_time,
-            query.app_name.as_deref(),
-            query.window_name.as_deref(),
-            query.min_length,
-            query.max_length,
-            query.speaker_ids.clone(),
-            query.frame_name.as_deref(),
-            query.browser_url.as_deref(),
-            query.focused,
-        ),
-        state.db.count_search_results(
-            query_str,
-            content_type,
-            query.start_time,
-            query.end_time,
-            query.app_name.as_deref(),
-            query.window_name.as_deref(),
-            query.min_length,
-            query.max_length,
-            query.speaker_ids.clone(),
-            query.frame_name.as_deref(),
-            query.browser_url.as_deref(),
-            query.focused,
-        ),
-    )
-    .await
-    .map_err(|e| {
-        error!("failed to perform search operations: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            JsonResponse(json!({"error": format!("failed to perform search operations: {}", e)})),
+            approximate,
         )
-    })?;
+        .await?
+    };
 
-    let mut content_items: Vec<ContentItem> = results
-        .iter()
-        .map(|result| match result {
-            SearchResult::OCR(ocr) => ContentItem::OCR(OCRContent {
-                frame_id: ocr.frame_id,
-                text: ocr.ocr_text.clone(),
-                timestamp: ocr.timestamp,
-                file_path: ocr.file_path.clone(),
-                offset_index: ocr.offset_index,
-                app_name: ocr.app_name.clone(),
-                window_name: ocr.window_name.clone(),
-                tags: ocr.tags.clone(),
-                frame: None,
-                frame_name: Some(ocr.frame_name.clone()),
-                browser_url: ocr.browser_url.clone(),
-                focused: ocr.focused,
-            }),
-            SearchResult::Audio(audio) => ContentItem::Audio(AudioContent {
-                chunk_id: audio.audio_chunk_id,
-                transcription: audio.transcription.clone(),
-                timestamp: audio.timestamp,
-                file_path: audio.file_path.clone(),
-                offset_index: audio.offset_index,
-                tags: audio.tags.clone(),
-                device_name: audio.device_name.clone(),
-                device_type: audio.device_type.clone().into(),
-                speaker: audio.speaker.clone(),
-                start_time: audio.start_time,
-                end_time: audio.end_time,
-            }),
-            SearchResult::UI(ui) => ContentItem::UI(UiContent {
-                id: ui.id,
-                text: ui.text.clone(),
-                timestamp: ui.timestamp,
-                app_name: ui.app_name.clone(),
-                window_name: ui.window_name.clone(),
-                initial_traversal_at: ui.initial_traversal_at,
-                file_path: ui.file_path.clone(),
-                offset_index: ui.offset_index,
-                frame_name: ui.frame_name.clone(),
-                browser_url: ui.browser_url.clone(),
-            }),
-        })
-        .collect();
+    if let Some(shadow) = state.shadow.as_ref() {
+        if shadows(shadow.candidate(), &query) && shadow.sample() {
+            let primary = results.iter().map(item_id).collect();
+            spawn_shadow_search(
+                state.clone(),
+                shadow.clone(),
+                query.clone(),
+                primary,
+                started.elapsed(),
+            );
+        }
+    }
+
+    let mut sessions = Vec::new();
+    if query.group_by_session {
+        let timestamps: Vec<DateTime<Utc>> = results.iter().map(result_timestamp).collect();
+        if let (Some(first), Some(last)) = (timestamps.iter().min(), timestamps.iter().max()) {
+            let work_sessions = state
+                .db
+                .get_work_sessions(*first, *last + chrono::Duration::seconds(1))
+                .await
+                .map_err(|e| {
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        JsonResponse(json!({"error": e.to_string()})),
+                    )
+                })?;
+            sessions = group_by_session(&timestamps, &work_sessions);
+        }
+    }
+
+    let mut content_items: Vec<ContentItem> = results.iter().map(content_item).collect();
 
     if query.include_frames {
         debug!("extracting frames for ocr content");
@@ -436,2369 +570,7953 @@ pub(crate) async fn search(
         }
     }
 
-    info!("search completed: found {} results", total);
+    info!("search completed: found {} results", total.count);
     Ok(JsonResponse(SearchResponse {
         data: content_items,
         pagination: PaginationInfo {
             limit: query.pagination.limit,
             offset: query.pagination.offset,
-            total: total as i64,
+            total: total.count as i64,
+            total_is_approximate: !total.exact,
         },
+        sessions,
     }))
 }
 
-#[oasgen]
-pub(crate) async fn api_list_audio_devices(
-    State(_state): State<Arc<AppState>>,
-) -> Result<JsonResponse<Vec<ListDeviceResponse>>, (StatusCode, JsonResponse<serde_json::Value>)> {
-    let default_input_device = default_input_device().map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            JsonResponse(json!({"error": format!("Failed to get default input device: {}", e)})),
-        )
-    })?;
-
-    let default_output_device = default_output_device().await.map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            JsonResponse(json!({"error": format!("Failed to get default output device: {}", e)})),
-        )
-    })?;
-
-    let devices = list_audio_devices().await.map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            JsonResponse(json!({"error": format!("Failed to list audio devices: {}", e)})),
-        )
-    })?;
-
-    let response: Vec<ListDeviceResponse> = devices
-        .into_iter()
-        .map(|device| {
-            let is_default = device == default_input_device || device == default_output_device;
-            ListDeviceResponse {
-                name: device.to_string(),
-                is_default,
-            }
-        })
-        .collect();
-
-    if response.is_empty() {
-        Err((
-            StatusCode::NOT_FOUND,
-            JsonResponse(json!({"error": "No audio devices found"})),
-        ))
-    } else {
-        Ok(JsonResponse(response))
-    }
+#[derive(Serialize)]
+pub struct PeerSearchResponse {
+    #[serde(flatten)]
+    pub local: SearchResponse,
+    /// results of each peer, see /peers
+    pub peers: Vec<PeerSearchResults>,
 }
 
-#[oasgen]
-pub async fn api_list_monitors(
-) -> Result<JsonResponse<Vec<MonitorInfo>>, (StatusCode, JsonResponse<serde_json::Value>)> {
-    let monitors = list_monitors().await;
-    let monitor_info = futures::future::join_all(monitors.into_iter().map(|monitor| async move {
-        let monitor_id = monitor.id();
-        match get_monitor_by_id(monitor_id).await {
-            Some(monitor) => MonitorInfo {
-                id: monitor.id(),
-                name: monitor.name().to_string(),
-                width: monitor.width(),
-                height: monitor.height(),
-                is_default: monitor.is_primary(),
-            },
-            None => MonitorInfo {
-                id: monitor_id,
-                name: "Unknown".to_string(),
-                width: 0,
-                height: 0,
-                is_default: false,
-            },
-        }
-    }))
-    .await;
-
-    if monitor_info.is_empty() {
-        Err((
-            StatusCode::NOT_FOUND,
-            JsonResponse(json!({"error": "No monitors found"})),
-        ))
-    } else {
-        Ok(JsonResponse(monitor_info))
-    }
+/// `/search` on this machine and on each peer, with the same parameters.
+/// Pages are cut on each machine apart.
+async fn peer_search_handler(
+    State(state): State<Arc<AppState>>,
+    RawQuery(raw_query): RawQuery,
+    query: Query<SearchQuery>,
+) -> Result<JsonResponse<PeerSearchResponse>, (StatusCode, JsonResponse<Value>)> {
+    let local = search(query, State(state.clone())).await?.0;
+    let peers = match state.peers.as_ref() {
+        Some(peers) => peers.search(raw_query.as_deref().unwrap_or("")).await,
+        None => Vec::new(),
+    };
+    Ok(JsonResponse(PeerSearchResponse { local, peers }))
 }
 
-#[oasgen]
-pub(crate) async fn add_tags(
+/// `/search` for the peers. Their request is signed with the peer secret,
+/// and the response is signed back for them to check.
+async fn signed_search_handler(
     State(state): State<Arc<AppState>>,
-    Path((content_type, id)): Path<(String, i64)>,
-    JsonResponse(payload): JsonResponse<AddTagsRequest>,
-) -> Result<Json<AddTagsResponse>, (StatusCode, JsonResponse<Value>)> {
-    let content_type = match content_type.as_str() {
-        "vision" => TagContentType::Vision,
-        "audio" => TagContentType::Audio,
-        _ => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                JsonResponse(json!({"error": "Invalid content type"})),
-            ))
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+    query: Query<SearchQuery>,
+) -> Response {
+    let Some(peers) = state.peers.clone() else {
+        return (
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "peers are not enabled"})),
+        )
+            .into_response();
+    };
+    let path_and_query = uri
+        .path_and_query()
+        .map(|path_and_query| path_and_query.as_str())
+        .unwrap_or_else(|| uri.path());
+    let nonce = match peers.verify(&headers, path_and_query) {
+        Ok(nonce) => nonce,
+        Err(e) => {
+            warn!("refused a peer search: {}", e);
+            return (StatusCode::UNAUTHORIZED, JsonResponse(json!({"error": e}))).into_response();
         }
     };
-
-    match state.db.add_tags(id, content_type, payload.tags).await {
-        Ok(_) => Ok(JsonResponse(AddTagsResponse { success: true })),
+    let response = match search(query, State(state)).await {
+        Ok(JsonResponse(response)) => response,
+        Err(e) => return e.into_response(),
+    };
+    let body = match serde_json::to_vec(&response) {
+        Ok(body) => body,
         Err(e) => {
-            error!("Failed to add tags: {}", e);
-            Err((
+            return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 JsonResponse(json!({"error": e.to_string()})),
-            ))
+            )
+                .into_response()
         }
-    }
+    };
+    let signature = peers.sign(&nonce, &body);
+    (
+        [
+            (CONTENT_TYPE.as_str(), "application/json".to_string()),
+            (PEER_SIGNATURE_HEADER, signature),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+/// The routes of the peer listener: only `/peers/search`, the requests
+/// signed with the peer secret rather than carrying a token.
+fn peer_router(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/peers/search", get(signed_search_handler))
+        .with_state(state)
+        .layer(TraceLayer::new_for_http().make_span_with(DefaultMakeSpan::default()))
 }
 
+/// The peers searched by /search/peers, discovered ones included.
 #[oasgen]
-pub(crate) async fn remove_tags(
-    State(state): State<Arc<AppState>>,
-    Path((content_type, id)): Path<(String, i64)>,
-    JsonResponse(payload): JsonResponse<RemoveTagsRequest>,
-) -> Result<Json<RemoveTagsResponse>, (StatusCode, JsonResponse<Value>)> {
-    let content_type = match content_type.as_str() {
-        "vision" => TagContentType::Vision,
-        "audio" => TagContentType::Audio,
-        _ => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                JsonResponse(json!({"error": "Invalid content type"})),
-            ))
-        }
-    };
+async fn list_peers_handler(State(state): State<Arc<AppState>>) -> JsonResponse<Vec<Peer>> {
+    JsonResponse(
+        state
+            .peers
+            .as_ref()
+            .map(|peers| peers.list())
+            .unwrap_or_default(),
+    )
+}
 
-    match state.db.remove_tags(id, content_type, payload.tags).await {
-        Ok(_) => Ok(JsonResponse(RemoveTagsResponse { success: true })),
-        Err(e) => {
-            error!("Failed to remove tag: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                JsonResponse(json!({"error": e.to_string()})),
-            ))
+/// Results of the search and the count of all its matches, estimated when
+/// `approximate`.
+async fn search_results(
+    state: &AppState,
+    query: &SearchQuery,
+    limit: u32,
+    offset: u32,
+    approximate: bool,
+) -> Result<(Vec<SearchResult>, ResultCount), (StatusCode, JsonResponse<Value>)> {
+    let query_str = query.q.as_deref().unwrap_or("");
+    let count = async {
+        if approximate {
+            return state
+                .db
+                .estimate_search_results(
+                    query_str,
+                    query.content_type.clone(),
+                    query.start_time,
+                    query.end_time,
+                    query.app_name.as_deref(),
+                    query.window_name.as_deref(),
+                    query.min_length,
+                    query.max_length,
+                    query.speaker_ids.clone(),
+                    query.frame_name.as_deref(),
+                    query.browser_url.as_deref(),
+                    query.focused,
+                    query.min_confidence,
+                    query.in_tables,
+                    &query.tag_filter(),
+                )
+                .await;
         }
-    }
+        let count = state
+            .db
+            .count_search_results(
+                query_str,
+                query.content_type.clone(),
+                query.start_time,
+                query.end_time,
+                query.app_name.as_deref(),
+                query.window_name.as_deref(),
+                query.min_length,
+                query.max_length,
+                query.speaker_ids.clone(),
+                query.frame_name.as_deref(),
+                query.browser_url.as_deref(),
+                query.focused,
+                query.min_confidence,
+                query.in_tables,
+                &query.tag_filter(),
+            )
+            .await?;
+        Ok(ResultCount { count, exact: true })
+    };
+    try_join(
+        state.db.search(
+            query_str,
+            query.content_type.clone(),
+            limit,
+            offset,
+            query.start_time,
+            query.end_time,
+            query.app_name.as_deref(),
+            query.window_name.as_deref(),
+            query.min_length,
+            query.max_length,
+            query.speaker_ids.clone(),
+            query.frame_name.as_deref(),
+            query.browser_url.as_deref(),
+            query.focused,
+            query.min_confidence,
+            query.in_tables,
+            &query.tag_filter(),
+        ),
+        count,
+    )
+    .await
+    .map_err(|e| {
+        error!("failed to perform search operations: {}", e);
+        read_query_error("failed to perform search operations", e)
+    })
 }
 
-#[oasgen]
-pub async fn health_check(State(state): State<Arc<AppState>>) -> JsonResponse<HealthCheckResponse> {
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+/// 503 for a query interrupted by the query timeout, which a narrower time
+/// range usually avoids, 500 otherwise.
+fn read_query_error(context: &str, e: sqlx::Error) -> (StatusCode, JsonResponse<Value>) {
+    match QueryTimedOut::find(&e) {
+        Some(timed_out) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            JsonResponse(json!({
+                "error": format!("{}: {}, try a narrower time range", context, timed_out)
+            })),
+        ),
+        None => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": format!("{}: {}", context, e)})),
+        ),
+    }
+}
 
-    let app_uptime = (now as i64) - (state.app_start_time.timestamp());
-    let grace_period = 120; // 2 minutes in seconds
+fn decayed_ranking_window(query: &SearchQuery) -> u32 {
+    query
+        .pagination
+        .offset
+        .saturating_add(query.pagination.limit)
+        .saturating_mul(DECAYED_RANKING_WINDOW_FACTOR)
+        .max(MIN_DECAYED_RANKING_WINDOW)
+}
 
-    // Get the status of all devices
-    let audio_devices = state.audio_manager.current_devices();
-    let mut device_statuses = Vec::new();
-    let mut global_audio_active = false;
-    let mut most_recent_audio_timestamp = 0; // Track the most recent timestamp
+async fn rank_by_decayed_relevance(
+    state: &AppState,
+    query: &SearchQuery,
+    results: Vec<SearchResult>,
+) -> Result<Vec<SearchResult>, (StatusCode, JsonResponse<Value>)> {
+    let half_life = half_life(query.half_life_days)?;
+    let query_str = query.q.as_deref().unwrap_or("");
+    let ranking_error = |e: sqlx::Error| {
+        error!("failed to rank search results: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": format!("failed to rank search results: {}", e)})),
+        )
+    };
+    let click_boosts = if state.click_tracking {
+        state
+            .db
+            .click_boosts(query_str)
+            .await
+            .map_err(ranking_error)?
+    } else {
+        ClickBoosts::new()
+    };
+    state
+        .db
+        .rank_by_decayed_relevance(query_str, results, half_life, &click_boosts)
+        .await
+        .map_err(ranking_error)
+}
 
-    // Check each device
-    for device in &audio_devices {
-        let device_name = device.to_string();
-        let last_capture = screenpipe_audio::core::get_device_capture_time(&device_name);
+/// Search returning every kind of content as the same item. Pages are cut
+/// from one ordering of all the matches, so consecutive pages never overlap
+/// nor skip items, unlike `/search` which pages each kind apart.
+#[oasgen]
+pub(crate) async fn search_v2(
+    Query(query): Query<SearchQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<SearchResponseV2>, (StatusCode, JsonResponse<Value>)> {
+    search_v2_page(&state, &query).await.map(JsonResponse)
+}
 
-        // Update the most recent timestamp
-        most_recent_audio_timestamp = most_recent_audio_timestamp.max(last_capture);
+async fn search_v2_page(
+    state: &AppState,
+    query: &SearchQuery,
+) -> Result<SearchResponseV2, (StatusCode, JsonResponse<Value>)> {
+    let query_str = query.q.as_deref().unwrap_or("");
+    let decayed = query.ranking == SearchRanking::Decayed && !query_str.trim().is_empty();
+    let window = if decayed {
+        decayed_ranking_window(query)
+    } else {
+        query
+            .pagination
+            .offset
+            .saturating_add(query.pagination.limit)
+    };
+    // combined content types get half of the limit each
+    let per_kind = match query.content_type {
+        ContentType::AudioAndUi | ContentType::OcrAndUi | ContentType::AudioAndOcr => 2,
+        _ => 1,
+    };
 
-        let device_active = if app_uptime < grace_period {
-            true // Consider active during grace period
-        } else {
-            now - last_capture < 5 // Consider active if captured in last 5 seconds
-        };
+    let (results, total) =
+        search_results(state, query, window.saturating_mul(per_kind), 0, false).await?;
+    let results = if decayed {
+        rank_by_decayed_relevance(state, query, results).await?
+    } else {
+        results
+    };
+    let mut items: Vec<ContentItemV2> = results.into_iter().map(ContentItemV2::from).collect();
+    if !decayed {
+        sort_items(&mut items);
+    }
 
-        // Track if any device is active
-        if device_active {
-            global_audio_active = true;
-        }
-        debug!(target: "server", "device status: {} {}", device_name, device_active);
+    Ok(paginate(
+        items,
+        query.pagination.limit,
+        query.pagination.offset,
+        total.count as i64,
+    ))
+}
 
-        device_statuses.push((device_name, device_active, last_capture));
+/// Whether `candidate` would run differently from the search of `query`.
+fn shadows(candidate: ShadowCandidate, query: &SearchQuery) -> bool {
+    match candidate {
+        ShadowCandidate::Decayed => {
+            query.ranking == SearchRanking::Standard
+                && !query.q.as_deref().unwrap_or("").trim().is_empty()
+        }
+        ShadowCandidate::V2 => true,
     }
+}
 
-    // Fallback to global timestamp if no devices are detected
-    if audio_devices.is_empty() {
-        let last_capture = screenpipe_audio::core::LAST_AUDIO_CAPTURE.load(Ordering::Relaxed);
-        global_audio_active = if app_uptime < grace_period {
-            true // Consider active during grace period
-        } else {
-            now - last_capture < 5 // Consider active if captured in last 5 seconds
-        };
-    }
+/// Runs the candidate on a sampled search in the background, so it never
+/// slows the request down, and records how its page compares with the one
+/// returned.
+fn spawn_shadow_search(
+    state: Arc<AppState>,
+    shadow: Arc<ShadowRunner>,
+    query: SearchQuery,
+    primary: Vec<String>,
+    primary_time: Duration,
+) {
+    tokio::spawn(async move {
+        let started = Instant::now();
+        let candidate = shadow_page(&state, &query, shadow.candidate())
+            .await
+            .map_err(|(_, JsonResponse(error))| error["error"].as_str().unwrap_or("").to_string());
+        shadow.record(compare_pages(
+            query.q.as_deref().unwrap_or(""),
+            &primary,
+            candidate,
+            primary_time,
+            started.elapsed(),
+        ));
+    });
+}
 
-    let (last_frame, audio, last_ui) = match state.db.get_latest_timestamps().await {
-        Ok((frame, audio, ui)) => (frame, audio, ui),
-        Err(e) => {
-            error!("failed to get latest timestamps: {}", e);
-            (None, None, None)
+/// Ids of the page the candidate finds for `query`.
+async fn shadow_page(
+    state: &AppState,
+    query: &SearchQuery,
+    candidate: ShadowCandidate,
+) -> Result<Vec<String>, (StatusCode, JsonResponse<Value>)> {
+    match candidate {
+        ShadowCandidate::Decayed => {
+            let (results, _) =
+                search_results(state, query, decayed_ranking_window(query), 0, false).await?;
+            Ok(rank_by_decayed_relevance(state, query, results)
+                .await?
+                .iter()
+                .skip(query.pagination.offset as usize)
+                .take(query.pagination.limit as usize)
+                .map(item_id)
+                .collect())
         }
-    };
+        ShadowCandidate::V2 => Ok(search_v2_page(state, query)
+            .await?
+            .data
+            .into_iter()
+            .map(|item| item.id)
+            .collect()),
+    }
+}
 
-    let now = Utc::now();
-    let threshold = Duration::from_secs(1800); // 30 minutes
+fn content_item(result: &SearchResult) -> ContentItem {
+    match result {
+        SearchResult::OCR(ocr) => ContentItem::OCR(OCRContent {
+            frame_id: ocr.frame_id,
+            text: ocr.ocr_text.clone(),
+            timestamp: ocr.timestamp,
+            file_path: ocr.file_path.clone(),
+            offset_index: ocr.offset_index,
+            app_name: ocr.app_name.clone(),
+            window_name: ocr.window_name.clone(),
+            tags: ocr.tags.clone(),
+            frame: None,
+            frame_name: Some(ocr.frame_name.clone()),
+            browser_url: ocr.browser_url.clone(),
+            focused: ocr.focused,
+            tables: ocr.tables.clone(),
+            browser_title: ocr.browser_title.clone(),
+            browser_selection: ocr.browser_selection.clone(),
+        }),
+        SearchResult::Audio(audio) => ContentItem::Audio(AudioContent {
+            chunk_id: audio.audio_chunk_id,
+            transcription: audio.transcription.clone(),
+            timestamp: audio.timestamp,
+            file_path: audio.file_path.clone(),
+            offset_index: audio.offset_index,
+            tags: audio.tags.clone(),
+            device_name: audio.device_name.clone(),
+            device_type: audio.device_type.clone().into(),
+            speaker: audio.speaker.clone(),
+            start_time: audio.start_time,
+            end_time: audio.end_time,
+        }),
+        SearchResult::UI(ui) => ContentItem::UI(UiContent {
+            id: ui.id,
+            text: ui.text.clone(),
+            timestamp: ui.timestamp,
+            app_name: ui.app_name.clone(),
+            window_name: ui.window_name.clone(),
+            initial_traversal_at: ui.initial_traversal_at,
+            file_path: ui.file_path.clone(),
+            offset_index: ui.offset_index,
+            frame_name: ui.frame_name.clone(),
+            browser_url: ui.browser_url.clone(),
+            tags: ui.tags.clone(),
+        }),
+        SearchResult::Clipboard(clipboard) => ContentItem::Clipboard(clipboard.clone()),
+    }
+}
 
-    let frame_status = if state.vision_disabled {
-        "disabled"
-    } else {
-        match last_frame {
-            Some(timestamp)
-                if now.signed_duration_since(timestamp)
-                    < chrono::Duration::from_std(threshold).unwrap() =>
-            {
-                "ok"
-            }
-            Some(_) => "stale",
-            None => "not_started",
-        }
-    };
+fn result_timestamp(result: &SearchResult) -> DateTime<Utc> {
+    match result {
+        SearchResult::OCR(ocr) => ocr.timestamp,
+        SearchResult::Audio(audio) => audio.timestamp,
+        SearchResult::UI(ui) => ui.timestamp,
+        SearchResult::Clipboard(clipboard) => clipboard.timestamp,
+    }
+}
 
-    let audio_status = if state.audio_disabled {
-        "disabled".to_string()
-    } else if global_audio_active {
-        "ok".to_string()
-    } else {
-        match audio {
-            Some(timestamp)
-                if now.signed_duration_since(timestamp)
-                    < chrono::Duration::from_std(threshold).unwrap() =>
-            {
-                "stale".to_string()
+// how often a live search looks for new content
+const LIVE_SEARCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A standing search, re-run on what was inserted since the last look.
+struct LiveSearch {
+    state: Arc<AppState>,
+    query: SearchQuery,
+    /// timestamp of the newest result sent
+    cursor: DateTime<Utc>,
+    /// results already sent at `cursor`, the next look starts there again
+    sent_at_cursor: HashSet<String>,
+    pending: VecDeque<ContentItem>,
+    polled: bool,
+    /// whether the first look, on what matched since `start_time`, was made
+    caught_up: bool,
+}
+
+impl LiveSearch {
+    async fn poll(&mut self) -> Result<(), sqlx::Error> {
+        let query = &self.query;
+        let page = query.pagination.limit.max(1);
+        let mut results = Vec::new();
+        let mut offset = 0;
+        // the first look only takes the newest `limit` results, a
+        // `start_time` far back doesn't load the whole history. The next ones
+        // take all that came in since the look before
+        loop {
+            let found = self
+                .state
+                .db
+                .search(
+                    query.q.as_deref().unwrap_or(""),
+                    query.content_type.clone(),
+                    page,
+                    offset,
+                    Some(self.cursor),
+                    query.end_time,
+                    query.app_name.as_deref(),
+                    query.window_name.as_deref(),
+                    query.min_length,
+                    query.max_length,
+                    query.speaker_ids.clone(),
+                    query.frame_name.as_deref(),
+                    query.browser_url.as_deref(),
+                    query.focused,
+                    query.min_confidence,
+                    query.in_tables,
+                    &query.tag_filter(),
+                )
+                .await?;
+            let done = found.len() < page as usize || !self.caught_up;
+            results.extend(found);
+            if done {
+                break;
             }
-            Some(_) => "stale".to_string(),
-            None => "not_started".to_string(),
+            offset += page;
         }
-    };
+        self.caught_up = true;
 
-    // Format device statuses as a string for a more detailed view
-    let device_status_details = if !device_statuses.is_empty() {
-        let now_secs = now.timestamp() as u64;
-        let device_details: Vec<String> = device_statuses
+        let mut items: Vec<(DateTime<Utc>, String, ContentItem)> = results
             .iter()
-            .map(|(name, active, last_capture)| {
-                format!(
-                    "{}: {} (last activity: {}s ago)",
-                    name,
-                    if *active { "active" } else { "inactive" },
-                    now_secs.saturating_sub(*last_capture)
-                )
+            .map(|result| {
+                let (timestamp, key) = match result {
+                    SearchResult::OCR(ocr) => (ocr.timestamp, format!("ocr:{}", ocr.frame_id)),
+                    SearchResult::Audio(audio) => (
+                        audio.timestamp,
+                        format!("audio:{}:{}", audio.audio_chunk_id, audio.offset_index),
+                    ),
+                    SearchResult::UI(ui) => (ui.timestamp, format!("ui:{}", ui.id)),
+                    SearchResult::Clipboard(clipboard) => {
+                        (clipboard.timestamp, format!("clipboard:{}", clipboard.id))
+                    }
+                };
+                (timestamp, key, content_item(result))
             })
             .collect();
+        items.sort_by_key(|(timestamp, _, _)| *timestamp);
 
-        Some(device_details.join(", "))
-    } else {
-        None
-    };
-
-    let ui_status = if !state.ui_monitoring_enabled {
-        "disabled"
-    } else {
-        match last_ui {
-            Some(timestamp)
-                if now.signed_duration_since(timestamp)
-                    < chrono::Duration::from_std(threshold).unwrap() =>
+        // pages can overlap when content comes in between them
+        let mut seen = HashSet::new();
+        for (timestamp, key, item) in items {
+            if !seen.insert(key.clone())
+                || (timestamp == self.cursor && self.sent_at_cursor.contains(&key))
             {
-                "ok"
+                continue;
             }
-            Some(_) => "stale",
-            None => "not_started",
-        }
-    };
-
-    let (overall_status, message, verbose_instructions, status_code) = if (frame_status == "ok"
-        || frame_status == "disabled")
-        && (audio_status == "ok" || audio_status == "disabled")
-        && (ui_status == "ok" || ui_status == "disabled")
-    {
-        (
-            "healthy",
-            "all systems are functioning normally.".to_string(),
-            None,
-            200,
-        )
-    } else {
-        let mut unhealthy_systems = Vec::new();
-        if frame_status != "ok" && frame_status != "disabled" {
-            unhealthy_systems.push("vision");
-        }
-        if audio_status != "ok" && audio_status != "disabled" {
-            unhealthy_systems.push("audio");
-        }
-        if ui_status != "ok" && ui_status != "disabled" {
-            unhealthy_systems.push("ui");
+            if timestamp > self.cursor {
+                self.cursor = timestamp;
+                self.sent_at_cursor.clear();
+            }
+            self.sent_at_cursor.insert(key);
+            self.pending.push_back(item);
         }
-
-        let systems_str = unhealthy_systems.join(", ");
-        (
-            "degraded",
-            format!("some systems are not healthy: {}", systems_str),
-            Some(get_verbose_instructions(&unhealthy_systems)),
-            503,
-        )
-    };
-
-    JsonResponse(HealthCheckResponse {
-        status: overall_status.to_string(),
-        status_code,
-        last_frame_timestamp: last_frame,
-        last_audio_timestamp: if most_recent_audio_timestamp > 0 {
-            Some(
-                Utc.timestamp_opt(most_recent_audio_timestamp as i64, 0)
-                    .unwrap(),
-            )
-        } else {
-            None
-        },
-        last_ui_timestamp: last_ui,
-        frame_status: frame_status.to_string(),
-        audio_status,
-        ui_status: ui_status.to_string(),
-        message,
-        verbose_instructions,
-        device_status_details,
-    })
-}
-
-fn get_verbose_instructions(unhealthy_systems: &[&str]) -> String {
-    let mut instructions = String::new();
-
-    if unhealthy_systems.contains(&"vision") {
-        instructions.push_str("Vision system is not working properly. Check if screen recording permissions are enabled.\n");
-    }
-
-    if unhealthy_systems.contains(&"audio") {
-        instructions.push_str("Audio system is not working properly. Check if microphone permissions are enabled and devices are connected.\n");
-    }
-
-    if unhealthy_systems.contains(&"ui") {
-        instructions.push_str("UI monitoring is not working properly. Check if accessibility permissions are enabled.\n");
+        Ok(())
     }
 
-    if instructions.is_empty() {
-        instructions =
-            "If you're experiencing issues, please try contacting us on Discord.".to_string();
+    fn finished(&self) -> bool {
+        self.pending.is_empty() && self.query.end_time.is_some_and(|end| end < Utc::now())
     }
-
-    instructions
 }
 
-// Request and response structs
-#[derive(OaSchema, Deserialize)]
-struct DownloadPipeRequest {
-    url: String,
+#[derive(Deserialize)]
+pub(crate) struct JournalQuery {
+    /// local date, today by default
+    date: Option<NaiveDate>,
+    /// where frame links point, this server by default
+    server_url: Option<String>,
+}
+
+/// `server_url`, or this server as the request reached it.
+fn server_url_or_host(server_url: Option<String>, headers: &HeaderMap) -> String {
+    server_url.unwrap_or_else(|| {
+        let host = headers
+            .get(HOST)
+            .and_then(|host| host.to_str().ok())
+            .unwrap_or("localhost:3030");
+        format!("http://{}", host)
+    })
 }
 
-#[derive(OaSchema, Deserialize)]
-struct DownloadPipePrivateRequest {
-    url: String,
-    pipe_name: String,
-    pipe_id: String,
+/// The day's activity as a Markdown journal: app sessions with excerpts of
+/// their text, meetings with their transcript, and tags.
+async fn journal_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<JournalQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let date = query.date.unwrap_or_else(|| Local::now().date_naive());
+    let server_url = server_url_or_host(query.server_url, &headers);
+
+    match export_journal(&state.db, date, &server_url).await {
+        Ok(markdown) => {
+            ([(CONTENT_TYPE, "text/markdown; charset=utf-8")], markdown).into_response()
+        }
+        Err(e) => {
+            error!("failed to export journal of {}: {}", date, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+                .into_response()
+        }
+    }
 }
 
-#[derive(OaSchema, Deserialize)]
-struct RunPipeRequest {
-    pipe_id: String,
+#[derive(Deserialize)]
+pub(crate) struct CalendarQuery {
+    /// how far back meetings go
+    #[serde(default = "default_calendar_days")]
+    days: i64,
+    /// where event links point, this server by default
+    server_url: Option<String>,
 }
 
-#[derive(OaSchema, Deserialize)]
-struct UpdatePipeConfigRequest {
-    pipe_id: String,
-    config: serde_json::Value,
+fn default_calendar_days() -> i64 {
+    90
 }
 
-#[derive(OaSchema, Deserialize)]
-struct UpdatePipeVersionRequest {
-    pipe_id: String,
-    source: String,
-}
+// events in the feed, the most recent meetings
+const MAX_CALENDAR_MEETINGS: u32 = 1000;
 
-#[oasgen]
-async fn download_pipe_handler(
+/// Detected meetings as an iCalendar feed calendar apps can subscribe to,
+/// titled after their participants and timed from their transcriptions.
+async fn calendar_handler(
     State(state): State<Arc<AppState>>,
-    JsonResponse(payload): JsonResponse<DownloadPipeRequest>,
-) -> Result<JsonResponse<serde_json::Value>, (StatusCode, JsonResponse<Value>)> {
-    debug!("Downloading pipe: {}", payload.url);
-    match state.pipe_manager.download_pipe(&payload.url).await {
-        Ok(pipe_dir) => Ok(JsonResponse(json!({
-            "data": {
-                "pipe_id": pipe_dir,
-                "message": "pipe downloaded successfully"
-            },
-            "success": true
-        }))),
+    Query(query): Query<CalendarQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let server_url = server_url_or_host(query.server_url, &headers);
+
+    match export_calendar(
+        &state.db,
+        query.days.max(0),
+        MAX_CALENDAR_MEETINGS,
+        &server_url,
+    )
+    .await
+    {
+        Ok(ics) => ([(CONTENT_TYPE, "text/calendar; charset=utf-8")], ics).into_response(),
         Err(e) => {
-            error!("Failed to download pipe: {}", e);
-            Err((
+            error!("failed to export meetings calendar: {}", e);
+            (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                JsonResponse(json!({
-                    "error": format!("failed to download pipe: {}", e),
-                    "success": false
-                })),
-            ))
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+                .into_response()
         }
     }
 }
 
-#[oasgen]
-async fn download_pipe_private_handler(
+/// Streams the results of a standing search as server-sent events, as
+/// matching content is inserted. Takes the same filters as /search, results
+/// are sent once each, oldest first, from `start_time` (now by default) until
+/// `end_time` if set, the newest `limit` only of what matched before the
+/// request. Frames aren't included.
+async fn live_search_handler(
     State(state): State<Arc<AppState>>,
-    JsonResponse(payload): JsonResponse<DownloadPipePrivateRequest>,
-) -> Result<JsonResponse<serde_json::Value>, (StatusCode, JsonResponse<Value>)> {
-    match state
-        .pipe_manager
-        .download_pipe_private(&payload.url, &payload.pipe_name, &payload.pipe_id)
-        .await
-    {
-        Ok(pipe_dir) => Ok(JsonResponse(json!({
-            "data": {
-                "pipe_id": pipe_dir,
-                "message": "pipe downloaded successfully"
-            },
-            "success": true
-        }))),
-        Err(e) => {
-            error!("Failed to download pipe: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                JsonResponse(json!({
-                    "error": format!("failed to download pipe: {}", e),
-                    "success": false
-                })),
-            ))
+    Query(query): Query<SearchQuery>,
+) -> Sse<impl Stream<Item = Result<SseEvent, Infallible>>> {
+    let live = LiveSearch {
+        state,
+        cursor: query.start_time.unwrap_or_else(Utc::now),
+        query,
+        sent_at_cursor: HashSet::new(),
+        pending: VecDeque::new(),
+        polled: false,
+        caught_up: false,
+    };
+
+    let events = futures::stream::unfold(live, |mut live| async move {
+        loop {
+            if let Some(item) = live.pending.pop_front() {
+                let event = SseEvent::default()
+                    .event("result")
+                    .json_data(&item)
+                    .unwrap_or_else(|e| SseEvent::default().event("error").data(e.to_string()));
+                return Some((Ok(event), live));
+            }
+            if live.finished() {
+                return None;
+            }
+            if live.polled {
+                tokio::time::sleep(LIVE_SEARCH_INTERVAL).await;
+            }
+            live.polled = true;
+            if let Err(e) = live.poll().await {
+                error!("live search failed: {}", e);
+                let event = SseEvent::default().event("error").data(e.to_string());
+                return Some((Ok(event), live));
+            }
         }
-    }
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
 }
 
-#[oasgen]
-async fn run_pipe_handler(
-    State(state): State<Arc<AppState>>,
-    JsonResponse(payload): JsonResponse<RunPipeRequest>,
-) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
-    debug!("starting pipe: {}", payload.pipe_id);
+// span of time read from the database at once by an export
+const EXPORT_WINDOW_MINUTES: i64 = 10;
+// results read per query, the export pages within a window
+const EXPORT_BATCH: u32 = 500;
 
-    match state
-        .pipe_manager
-        .update_config(
-            &payload.pipe_id,
-            serde_json::json!({
-                "enabled": true,
-            }),
-        )
-        .await
-    {
-        Ok(_) => Ok(JsonResponse(json!({
-            "data": {
-                "pipe_id": payload.pipe_id,
-                "message": "pipe started"
-            },
-            "success": true
-        }))),
-        Err(e) => Err((
-            StatusCode::BAD_REQUEST,
-            JsonResponse(json!({
-                "error": format!("failed to start pipe: {}", e),
-                "success": false
-            })),
-        )),
-    }
-}
+struct JsonlExport {
+    state: Arc<AppState>,
+    query: SearchQuery,
+    /// start of the next window to read
+    cursor: DateTime<Utc>,
+    end: DateTime<Utc>,
+    pending: VecDeque<ContentItemV2>,
+}
+
+impl JsonlExport {
+    /// Reads the next window, from `cursor` on.
+    async fn read_window(&mut self) -> Result<(), sqlx::Error> {
+        let query = &self.query;
+        let window_end =
+            (self.cursor + chrono::Duration::minutes(EXPORT_WINDOW_MINUTES)).min(self.end);
+        let last = window_end == self.end;
+        // each kind alone, combined content types split the limit between
+        // kinds and can't be paged through
+        let kinds = match query.content_type {
+            ContentType::All => vec![
+                ContentType::OCR,
+                ContentType::Audio,
+                ContentType::UI,
+                ContentType::Clipboard,
+            ],
+            ContentType::AudioAndUi => vec![ContentType::Audio, ContentType::UI],
+            ContentType::OcrAndUi => vec![ContentType::OCR, ContentType::UI],
+            ContentType::AudioAndOcr => vec![ContentType::Audio, ContentType::OCR],
+            ref kind => vec![kind.clone()],
+        };
 
-#[oasgen]
-async fn stop_pipe_handler(
-    State(state): State<Arc<AppState>>,
-    JsonResponse(payload): JsonResponse<RunPipeRequest>,
-) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
-    debug!("Stopping pipe: {}", payload.pipe_id);
-    match state
-        .pipe_manager
-        .update_config(
-            &payload.pipe_id,
-            serde_json::json!({
-                "enabled": false,
-            }),
-        )
-        .await
-    {
-        Ok(_) => Ok(JsonResponse(json!({
-            "data": {
-                "pipe_id": payload.pipe_id,
-                "message": "pipe stopped"
-            },
-            "success": true
-        }))),
-        Err(e) => Err((
-            StatusCode::BAD_REQUEST,
-            JsonResponse(json!({
-                "error": format!("failed to stop pipe: {}", e),
-                "success": false
-            })),
-        )),
-    }
-}
+        let mut items = Vec::new();
+        for kind in kinds {
+            let mut offset = 0;
+            loop {
+                let found = self
+                    .state
+                    .db
+                    .search(
+                        query.q.as_deref().unwrap_or(""),
+                        kind.clone(),
+                        EXPORT_BATCH,
+                        offset,
+                        Some(self.cursor),
+                        Some(window_end),
+                        query.app_name.as_deref(),
+                        query.window_name.as_deref(),
+                        query.min_length,
+                        query.max_length,
+                        query.speaker_ids.clone(),
+                        query.frame_name.as_deref(),
+                        query.browser_url.as_deref(),
+                        query.focused,
+                        query.min_confidence,
+                        query.in_tables,
+                        &query.tag_filter(),
+                    )
+                    .await?;
+                let done = found.len() < EXPORT_BATCH as usize;
+                // the end of a window is the start of the next one
+                items.extend(
+                    found
+                        .into_iter()
+                        .map(ContentItemV2::from)
+                        .filter(|item| last || item.timestamp < window_end),
+                );
+                if done {
+                    break;
+                }
+                offset += EXPORT_BATCH;
+            }
+        }
 
-#[oasgen]
-async fn update_pipe_config_handler(
-    State(state): State<Arc<AppState>>,
-    JsonResponse(payload): JsonResponse<UpdatePipeConfigRequest>,
-) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
-    debug!("Updating pipe config for: {}", payload.pipe_id);
-    match state
-        .pipe_manager
-        .update_config(&payload.pipe_id, payload.config)
-        .await
-    {
-        Ok(_) => Ok(JsonResponse(json!({
-            "data": {
-                "pipe_id": payload.pipe_id,
-                "message": "pipe config updated"
-            },
-            "success": true
-        }))),
-        Err(e) => Err((
-            StatusCode::BAD_REQUEST,
-            JsonResponse(json!({
-                "error": format!("failed to update pipe config: {}", e),
-                "success": false
-            })),
-        )),
+        // oldest first, ties broken by kind and id
+        items.sort_by(|a, b| {
+            a.timestamp
+                .cmp(&b.timestamp)
+                .then(a.kind.cmp(&b.kind))
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        self.pending.extend(items);
+        self.cursor = window_end;
+        Ok(())
     }
-}
 
-#[oasgen]
-async fn update_pipe_version_handler(
-    State(state): State<Arc<AppState>>,
-    JsonResponse(payload): JsonResponse<UpdatePipeVersionRequest>,
-) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
-    debug!("Updating pipe version for: {}", payload.pipe_id);
-    match state
-        .pipe_manager
-        .update_pipe_version(&payload.pipe_id, &payload.source)
-        .await
-    {
-        Ok(_) => Ok(JsonResponse(json!({
-            "data": {
-                "pipe_id": payload.pipe_id,
-                "message": "pipe version updated"
-            },
-            "success": true
-        }))),
-        Err(e) => Err((
-            StatusCode::BAD_REQUEST,
-            JsonResponse(json!({
-                "error": format!("failed to update pipe version: {}", e),
-                "success": false
-            })),
-        )),
+    fn finished(&self) -> bool {
+        self.pending.is_empty() && self.cursor >= self.end
     }
 }
 
-#[oasgen]
-async fn get_pipe_info_handler(
+/// Streams everything matching the filters of /search as newline-delimited
+/// json, a `/v2/search` item per line, oldest first, from `start_time` (the
+/// oldest content by default) to `end_time` (now by default). The database
+/// is read a window of time at a time as the client reads, so a slow reader
+/// holds back the export rather than piling it up in memory. Pagination and
+/// ranking are ignored.
+async fn export_jsonl_handler(
     State(state): State<Arc<AppState>>,
-    Path(pipe_id): Path<String>,
-) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
-    debug!("Getting pipe info for: {}", pipe_id);
-    match state.pipe_manager.get_pipe_info(&pipe_id).await {
-        Some(info) => Ok(JsonResponse(json!({
-            "data": info,
-            "success": true
-        }))),
-        None => Err((
-            StatusCode::NOT_FOUND,
-            JsonResponse(json!({
-                "error": "pipe not found",
-                "success": false
-            })),
-        )),
-    }
-}
+    Query(query): Query<SearchQuery>,
+) -> Response {
+    let end = query.end_time.unwrap_or_else(Utc::now);
+    let start = match query.start_time {
+        Some(start) => Some(start),
+        None => match state.db.get_earliest_timestamp().await {
+            Ok(earliest) => earliest,
+            Err(e) => {
+                error!("failed to export jsonl: {}", e);
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    JsonResponse(json!({"error": e.to_string()})),
+                )
+                    .into_response();
+            }
+        },
+    };
+    let export = JsonlExport {
+        state,
+        // nothing recorded yet, nothing to read
+        cursor: start.unwrap_or(end),
+        end,
+        query,
+        pending: VecDeque::new(),
+    };
 
-#[oasgen]
-async fn list_pipes_handler(State(state): State<Arc<AppState>>) -> JsonResponse<Value> {
-    let pipes = state.pipe_manager.list_pipes().await;
-    JsonResponse(json!({
-        "data": pipes,
-        "success": true
-    }))
+    let lines = futures::stream::unfold(export, |mut export| async move {
+        loop {
+            if let Some(item) = export.pending.pop_front() {
+                let line = match serde_json::to_string(&item) {
+                    Ok(json) => json + "\n",
+                    Err(e) => {
+                        error!("failed to serialize {}: {}", item.id, e);
+                        continue;
+                    }
+                };
+                return Some((Ok(line), export));
+            }
+            if export.finished() {
+                return None;
+            }
+            if let Err(e) = export.read_window().await {
+                // the response is cut short, rather than looking complete
+                error!("jsonl export failed at {}: {}", export.cursor, e);
+                export.cursor = export.end;
+                return Some((Err(e), export));
+            }
+        }
+    });
+
+    (
+        [(CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(lines),
+    )
+        .into_response()
 }
 
-pub struct SCServer {
-    db: Arc<DatabaseManager>,
-    addr: SocketAddr,
-    audio_manager: Arc<AudioManager>,
-    screenpipe_dir: PathBuf,
-    pipe_manager: Arc<PipeManager>,
-    vision_disabled: bool,
-    audio_disabled: bool,
-    ui_monitoring_enabled: bool,
+// span of time read from the database at once by the usage export
+const USAGE_EXPORT_WINDOW_HOURS: i64 = 1;
+
+#[derive(Deserialize)]
+pub(crate) struct UsageExportQuery {
+    /// the oldest content by default
+    start_time: Option<DateTime<Utc>>,
+    /// now by default
+    end_time: Option<DateTime<Utc>>,
+    /// frames for `ocr`, transcriptions for `audio`, both by default
+    #[serde(default)]
+    content_type: ContentType,
+    /// comma separated, all of them by default
+    columns: Option<String>,
 }
 
-impl SCServer {
-    #[allow(clippy::too_many_arguments)]
-    pub fn new(
-        db: Arc<DatabaseManager>,
-        addr: SocketAddr,
-        screenpipe_dir: PathBuf,
-        pipe_manager: Arc<PipeManager>,
-        vision_disabled: bool,
-        audio_disabled: bool,
-        ui_monitoring_enabled: bool,
-        audio_manager: Arc<AudioManager>,
-    ) -> Self {
-        SCServer {
-            db,
-            addr,
-            screenpipe_dir,
-            pipe_manager,
-            vision_disabled,
-            audio_disabled,
-            ui_monitoring_enabled,
-            audio_manager,
+struct UsageExport {
+    state: Arc<AppState>,
+    columns: Vec<UsageColumn>,
+    frames: bool,
+    audio: bool,
+    /// start of the next window to read
+    cursor: DateTime<Utc>,
+    end: DateTime<Utc>,
+    header_sent: bool,
+}
+
+/// Metadata of frames and transcriptions as csv, a row each, oldest first:
+/// when, app, window, how long, speaker and text length, without having to
+/// query the database. Streamed like /search/export.
+async fn usage_export_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<UsageExportQuery>,
+) -> Response {
+    let columns = match parse_columns(query.columns.as_deref()) {
+        Ok(columns) => columns,
+        Err(e) => {
+            return (StatusCode::BAD_REQUEST, JsonResponse(json!({"error": e}))).into_response();
         }
-    }
-
-    pub async fn start(self, enable_frame_cache: bool) -> Result<(), std::io::Error> {
-        // Create the OpenAPI server
-        let app = self.create_router(enable_frame_cache).await;
+    };
+    let (frames, audio) = match query.content_type {
+        ContentType::All | ContentType::AudioAndOcr => (true, true),
+        ContentType::OCR => (true, false),
+        ContentType::Audio => (false, true),
+        _ => {
+            return (
+                StatusCode::BAD_REQUEST,
+                JsonResponse(json!({"error": "only frames and audio can be exported, content_type must be ocr, audio or all"})),
+            )
+                .into_response();
+        }
+    };
+    let end = query.end_time.unwrap_or_else(Utc::now);
+    let start = match query.start_time {
+        Some(start) => Some(start),
+        None => match state.db.get_earliest_timestamp().await {
+            Ok(earliest) => earliest,
+            Err(e) => {
+                error!("failed to export usage: {}", e);
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    JsonResponse(json!({"error": e.to_string()})),
+                )
+                    .into_response();
+            }
+        },
+    };
+    let export = UsageExport {
+        state,
+        columns,
+        frames,
+        audio,
+        cursor: start.unwrap_or(end),
+        end,
+        header_sent: false,
+    };
 
-        #[cfg(feature = "experimental")]
-        let app = app.route("/experimental/input_control", post(input_control_handler));
+    let chunks = futures::stream::unfold(export, |mut export| async move {
+        if !export.header_sent {
+            export.header_sent = true;
+            return Some((Ok(csv_header(&export.columns)), export));
+        }
+        while export.cursor < export.end {
+            let window_end = (export.cursor + chrono::Duration::hours(USAGE_EXPORT_WINDOW_HOURS))
+                .min(export.end);
+            let records = export
+                .state
+                .db
+                .get_usage_records(export.cursor, window_end, export.frames, export.audio)
+                .await;
+            let start = export.cursor;
+            export.cursor = window_end;
+            match records {
+                Ok(records) if records.is_empty() => continue,
+                Ok(records) => {
+                    let rows: String = records
+                        .iter()
+                        .map(|record| csv_row(record, &export.columns))
+                        .collect();
+                    return Some((Ok(rows), export));
+                }
+                Err(e) => {
+                    // the response is cut short, rather than looking complete
+                    error!("usage export failed at {}: {}", start, e);
+                    export.cursor = export.end;
+                    return Some((Err(e), export));
+                }
+            }
+        }
+        None
+    });
 
-        // Create the listener
-        let listener = TcpListener::bind(&self.addr).await?;
-        info!("Server listening on {}", self.addr);
+    (
+        [(CONTENT_TYPE, "text/csv; charset=utf-8")],
+        Body::from_stream(chunks),
+    )
+        .into_response()
+}
 
-        // Start serving
-        serve(
-            listener,
-            app.into_make_service_with_connect_info::<SocketAddr>(),
+#[oasgen]
+pub(crate) async fn api_list_audio_devices(
+    State(_state): State<Arc<AppState>>,
+) -> Result<JsonResponse<Vec<ListDeviceResponse>>, (StatusCode, JsonResponse<serde_json::Value>)> {
+    let default_input_device = default_input_device().map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": format!("Failed to get default input device: {}", e)})),
         )
-        .await
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+    })?;
 
-        Ok(())
-    }
+    let default_output_device = default_output_device().await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": format!("Failed to get default output device: {}", e)})),
+        )
+    })?;
 
-    pub async fn create_router(&self, enable_frame_cache: bool) -> Router {
-        let app_state = Arc::new(AppState {
-            db: self.db.clone(),
-            audio_manager: self.audio_manager.clone(),
-            app_start_time: Utc::now(),
-            screenpipe_dir: self.screenpipe_dir.clone(),
-            pipe_manager: self.pipe_manager.clone(),
-            vision_disabled: self.vision_disabled,
-            audio_disabled: self.audio_disabled,
-            ui_monitoring_enabled: self.ui_monitoring_enabled,
-            frame_cache: if enable_frame_cache {
-                Some(Arc::new(
-                    FrameCache::new(self.screenpipe_dir.clone().join("data"), self.db.clone())
-                        .await
-                        .unwrap(),
-                ))
-            } else {
-                None
-            },
-            frame_image_cache: if enable_frame_cache {
-                Some(Arc::new(Mutex::new(LruCache::new(
-                    NonZeroUsize::new(100).unwrap(),
-                ))))
-            } else {
-                None
-            },
-        });
+    let devices = list_audio_devices().await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": format!("Failed to list audio devices: {}", e)})),
+        )
+    })?;
 
-        let cors = CorsLayer::new()
-            .allow_origin(Any)
-            .allow_methods(Any)
-            .allow_headers(Any)
-            .expose_headers([
-                axum::http::header::CONTENT_TYPE,
-                axum::http::header::CACHE_CONTROL,
-            ]);
-        let server = Server::axum()
-            .get("/search", search)
-            .get("/audio/list", api_list_audio_devices)
-            .get("/vision/list", api_list_monitors)
-            .post("/tags/:content_type/:id", add_tags)
-            .delete("/tags/:content_type/:id", remove_tags)
-            .get("/pipes/info/:pipe_id", get_pipe_info_handler)
-            .get("/pipes/list", list_pipes_handler)
-            .post("/pipes/download", download_pipe_handler)
-            .post("/pipes/download-private", download_pipe_private_handler)
-            .post("/pipes/enable", run_pipe_handler)
-            .post("/pipes/disable", stop_pipe_handler)
-            .post("/pipes/update", update_pipe_config_handler)
-            .post("/pipes/update-version", update_pipe_version_handler)
-            .post("/pipes/delete", delete_pipe_handler)
-            .post("/pipes/purge", purge_pipe_handler)
-            .get("/frames/:frame_id", get_frame_data)
-            .get("/health", health_check)
-            .post("/raw_sql", execute_raw_sql)
-            .post("/add", add_to_database)
-            .get("/speakers/unnamed", get_unnamed_speakers_handler)
-            .post("/speakers/update", update_speaker_handler)
-            .get("/speakers/search", search_speakers_handler)
-            .post("/speakers/delete", delete_speaker_handler)
-            .post("/speakers/hallucination", mark_as_hallucination_handler)
-            .post("/speakers/merge", merge_speakers_handler)
-            .get("/speakers/similar", get_similar_speakers_handler)
-            .post("/experimental/frames/merge", merge_frames_handler)
-            .get("/experimental/validate/media", validate_media_handler)
-            .post("/experimental/operator", find_elements_handler)
-            .post("/experimental/operator/click", click_element_handler)
-            .post("/experimental/operator/type", type_text_handler)
-            .post("/audio/start", start_audio)
-            .post("/audio/stop", stop_audio)
-            .get("/semantic-search", semantic_search_handler)
-            .get("/pipes/build-status/:pipe_id", get_pipe_build_status)
-            .get("/search/keyword", keyword_search_handler)
-            .post("/v1/embeddings", create_embeddings)
-            .post("/audio/device/start", start_audio_device)
-            .post("/audio/device/stop", stop_audio_device)
-            .route_yaml_spec("/openapi.yaml")
-            .route_json_spec("/openapi.json")
-            .freeze();
+    let response: Vec<ListDeviceResponse> = devices
+        .into_iter()
+        .map(|device| {
+            let is_default = device == default_input_device || device == default_output_device;
+            ListDeviceResponse {
+                name: device.to_string(),
+                is_default,
+            }
+        })
+        .collect();
 
-        // Build the main router with all routes
-        Router::new()
-            .merge(server.into_router())
-            // NOTE: websockerts and sse is not supported by openapi so we move it down here
-            .route("/stream/frames", get(stream_frames_handler))
-            .route("/ws/events", get(ws_events_handler))
-            .route("/ws/health", get(ws_health_handler))
-            .route("/frames/export", get(handle_video_export_ws))
-            .with_state(app_state)
-            .layer(cors)
-            .layer(TraceLayer::new_for_http().make_span_with(DefaultMakeSpan::default()))
+    if response.is_empty() {
+        Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "No audio devices found"})),
+        ))
+    } else {
+        Ok(JsonResponse(response))
     }
 }
 
 #[oasgen]
-async fn merge_frames_handler(
-    State(state): State<Arc<AppState>>,
-    JsonResponse(payload): JsonResponse<MergeVideosRequest>,
-) -> Result<JsonResponse<MergeVideosResponse>, (StatusCode, JsonResponse<Value>)> {
-    let output_dir = state.screenpipe_dir.join("videos");
-
-    match merge_videos(payload, output_dir).await {
-        Ok(response) => Ok(JsonResponse(response)),
-        Err(e) => {
-            error!("Failed to merge frames: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                JsonResponse(json!({"error": e.to_string()})),
-            ))
+pub async fn api_list_monitors(
+) -> Result<JsonResponse<Vec<MonitorInfo>>, (StatusCode, JsonResponse<serde_json::Value>)> {
+    let monitors = list_monitors().await;
+    let monitor_info = futures::future::join_all(monitors.into_iter().map(|monitor| async move {
+        let monitor_id = monitor.id();
+        match get_monitor_by_id(monitor_id).await {
+            Some(monitor) => MonitorInfo {
+                id: monitor.id(),
+                name: monitor.name().to_string(),
+                width: monitor.width(),
+                height: monitor.height(),
+                is_default: monitor.is_primary(),
+            },
+            None => MonitorInfo {
+                id: monitor_id,
+                name: "Unknown".to_string(),
+                width: 0,
+                height: 0,
+                is_default: false,
+            },
         }
+    }))
+    .await;
+
+    if monitor_info.is_empty() {
+        Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "No monitors found"})),
+        ))
+    } else {
+        Ok(JsonResponse(monitor_info))
     }
 }
 
-#[oasgen]
-async fn validate_media_handler(
-    State(_state): State<Arc<AppState>>,
-    Query(params): Query<ValidateMediaParams>,
-) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
-    match validate_media(&params.file_path).await {
-        Ok(_) => Ok(Json(json!({"status": "valid media file"}))),
-        Err(e) => Err((
-            StatusCode::EXPECTATION_FAILED,
-            Json(json!({"status": e.to_string()})),
-        )),
-    }
+#[derive(OaSchema, Serialize)]
+pub struct MonitorSettingsInfo {
+    pub monitor_id: u32,
+    pub name: String,
+    pub enabled: bool,
+    pub fps: Option<f64>,
+    pub downscale_factor: f64,
 }
 
 #[derive(OaSchema, Deserialize)]
-struct RawSqlQuery {
-    query: String,
+pub struct UpdateMonitorSettingsRequest {
+    pub enabled: Option<bool>,
+    /// capture fps for this monitor, 0 removes the override
+    pub fps: Option<f64>,
+    pub downscale_factor: Option<f64>,
 }
 
 #[oasgen]
-async fn execute_raw_sql(
+async fn get_monitor_settings_handler(
+) -> Result<JsonResponse<Vec<MonitorSettingsInfo>>, (StatusCode, JsonResponse<Value>)> {
+    let settings = list_monitors()
+        .await
+        .into_iter()
+        .map(|monitor| {
+            let settings = get_monitor_settings(monitor.id());
+            MonitorSettingsInfo {
+                monitor_id: monitor.id(),
+                name: monitor.name().to_string(),
+                enabled: settings.enabled,
+                fps: settings.fps,
+                downscale_factor: settings.downscale_factor,
+            }
+        })
+        .collect();
+
+    Ok(JsonResponse(settings))
+}
+
+#[oasgen]
+async fn update_monitor_settings_handler(
     State(state): State<Arc<AppState>>,
-    JsonResponse(payload): JsonResponse<RawSqlQuery>,
-) -> Result<JsonResponse<serde_json::Value>, (StatusCode, JsonResponse<serde_json::Value>)> {
-    match state.db.execute_raw_sql(&payload.query).await {
-        Ok(result) => Ok(JsonResponse(result)),
-        Err(e) => {
-            error!("Failed to execute raw SQL query: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                JsonResponse(json!({"error": e.to_string()})),
+    Path(monitor_id): Path<u32>,
+    JsonResponse(payload): JsonResponse<UpdateMonitorSettingsRequest>,
+) -> Result<JsonResponse<MonitorSettingsInfo>, (StatusCode, JsonResponse<Value>)> {
+    let current = get_monitor_settings(monitor_id);
+
+    let fps = match payload.fps {
+        Some(fps) if fps <= 0.0 => None,
+        Some(fps) if fps > MAX_FPS => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                JsonResponse(json!({"error": format!("fps must be at most {}", MAX_FPS)})),
             ))
         }
+        Some(fps) => Some(fps),
+        None => current.fps,
+    };
+    let downscale_factor = payload.downscale_factor.unwrap_or(current.downscale_factor);
+    if !(1.0..=8.0).contains(&downscale_factor) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "downscale_factor must be between 1 and 8"})),
+        ));
     }
-}
 
-#[derive(OaSchema, Deserialize)]
-pub struct AddContentRequest {
-    pub device_name: String,     // Moved device_name to the top level
-    pub content: AddContentData, // The actual content (either Frame or Transcription)
-}
+    let settings = state
+        .db
+        .upsert_monitor_settings(
+            monitor_id as i64,
+            payload.enabled.unwrap_or(current.enabled),
+            fps,
+            downscale_factor,
+        )
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("failed to save monitor settings: {}", e)})),
+            )
+        })?;
 
-#[derive(OaSchema, Deserialize)]
-pub struct AddContentData {
-    pub content_type: String,
-    pub data: ContentData,
+    let settings = MonitorCaptureSettings::from(settings);
+    set_monitor_settings(monitor_id, settings.clone());
+    info!(
+        "updated capture settings for monitor {}: {:?}",
+        monitor_id, settings
+    );
+
+    let name = get_monitor_by_id(monitor_id)
+        .await
+        .map(|monitor| monitor.name().to_string())
+        .unwrap_or_else(|| "Unknown".to_string());
+
+    Ok(JsonResponse(MonitorSettingsInfo {
+        monitor_id,
+        name,
+        enabled: settings.enabled,
+        fps: settings.fps,
+        downscale_factor: settings.downscale_factor,
+    }))
 }
 
 #[derive(OaSchema, Deserialize)]
-#[serde(untagged)]
-pub enum ContentData {
-    Frames(Vec<FrameContent>),
-    Transcription(AudioTranscription),
+pub struct UpdateDeviceRequest {
+    pub enabled: Option<bool>,
+    /// capture fps of a monitor, 0 removes the override
+    pub fps: Option<f64>,
+    /// OCR engine or provider of a monitor, transcription provider of an
+    /// audio device, empty removes the override
+    pub engine: Option<String>,
 }
 
-#[derive(OaSchema, Deserialize)]
-pub struct FrameContent {
-    pub file_path: String,
-    pub timestamp: Option<DateTime<Utc>>,
-    pub app_name: Option<String>,
-    pub window_name: Option<String>,
-    pub ocr_results: Option<Vec<OCRResult>>,
-    pub tags: Option<Vec<String>>,
+#[oasgen]
+async fn list_devices_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<Vec<Device>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .list_devices()
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("failed to list devices: {}", e)})),
+            )
+        })
 }
 
-#[derive(Serialize, OaSchema, Deserialize, Debug)]
-pub struct OCRResult {
-    pub text: String,
-    pub text_json: Option<String>,
-    pub ocr_engine: Option<String>,
-    pub focused: Option<bool>,
+/// Changes the settings of a device. Enabling and disabling a device and
+/// the fps of a monitor apply right away, a new engine from the next start.
+#[oasgen]
+async fn update_device_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+    JsonResponse(payload): JsonResponse<UpdateDeviceRequest>,
+) -> Result<JsonResponse<Device>, (StatusCode, JsonResponse<Value>)> {
+    let internal_error = |e: sqlx::Error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": format!("failed to update device: {}", e)})),
+        )
+    };
+    let not_found = || {
+        (
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": format!("no device with id {}", id)})),
+        )
+    };
+    let current = state
+        .db
+        .get_device(id)
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(not_found)?;
+
+    let fps = match payload.fps {
+        Some(fps) if fps <= 0.0 => None,
+        Some(fps) if fps > MAX_FPS => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                JsonResponse(json!({"error": format!("fps must be at most {}", MAX_FPS)})),
+            ))
+        }
+        Some(fps) => Some(fps),
+        None => current.fps,
+    };
+    let engine = match payload.engine {
+        Some(engine) if engine.is_empty() => None,
+        Some(engine) => Some(engine),
+        None => current.engine.clone(),
+    };
+    validate_device_settings(&current, fps, engine.as_deref())
+        .map_err(|e| (StatusCode::BAD_REQUEST, JsonResponse(json!({"error": e}))))?;
+
+    let device = state
+        .db
+        .update_device(
+            id,
+            payload.enabled.unwrap_or(current.enabled),
+            fps,
+            engine.as_deref(),
+        )
+        .await
+        .map_err(internal_error)?
+        .ok_or_else(not_found)?;
+
+    if let Some(monitor_id) = monitor_id(&device) {
+        let mut settings = get_monitor_settings(monitor_id);
+        settings.enabled = device.enabled;
+        settings.fps = match device.fps {
+            Some(fps) => Some(fps),
+            // back to the fps of /vision/settings
+            None => state
+                .db
+                .get_monitor_settings()
+                .await
+                .map_err(internal_error)?
+                .into_iter()
+                .find(|settings| settings.monitor_id == monitor_id as i64)
+                .and_then(|settings| settings.fps),
+        };
+        set_monitor_settings(monitor_id, settings);
+    } else if let Some(name) = audio_device_name(&device) {
+        if device.enabled != current.enabled {
+            let result = match device.enabled {
+                true => match AudioDevice::from_name(&name) {
+                    Ok(audio_device) => state.audio_manager.start_device(&audio_device).await,
+                    Err(e) => Err(e),
+                },
+                false => state.audio_manager.stop_device(&name).await,
+            };
+            if let Err(e) = result {
+                warn!("failed to apply the settings of {}: {}", name, e);
+            }
+        }
+    }
+    info!(
+        "updated device {} {}: {:?}",
+        device.kind, device.name, device
+    );
+
+    Ok(JsonResponse(device))
 }
 
 #[derive(OaSchema, Deserialize)]
-pub struct AudioTranscription {
-    pub transcription: String,
-    pub transcription_engine: String,
+pub(crate) struct TagFacetsQuery {
+    /// the tag whose children are counted, e.g. `project/alpha`, the top
+    /// level tags without one
+    #[serde(default)]
+    parent: Option<String>,
+    #[serde(default)]
+    start_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    end_time: Option<DateTime<Utc>>,
 }
 
-#[derive(OaSchema, Serialize)]
-pub struct AddContentResponse {
-    pub success: bool,
-    pub message: Option<String>,
+/// The tags one level below `parent` with how many frames and audio chunks
+/// have them or a tag below them, to browse tags as a tree and narrow a
+/// search with its `tags` filter.
+#[oasgen]
+async fn tag_facets_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TagFacetsQuery>,
+) -> Result<JsonResponse<Vec<TagFacet>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .tag_facets(query.parent.as_deref(), query.start_time, query.end_time)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| read_query_error("failed to count tags", e))
 }
 
-async fn add_frame_to_db(
-    state: &AppState,
-    frame: &FrameContent,
-    device_name: &str,
-) -> Result<(), anyhow::Error> {
-    let db = &state.db;
-
-    let frame_id = db
-        .insert_frame(
-            device_name,
-            Some(frame.timestamp.unwrap_or_else(Utc::now)),
-            None,
-            frame.app_name.as_deref(),
-            frame.window_name.as_deref(),
-            false,
-        )
-        .await?;
-
-    if let Some(ocr_results) = &frame.ocr_results {
-        for ocr in ocr_results {
-            db.insert_ocr_text(
-                frame_id,
-                &ocr.text,
-                ocr.text_json.as_deref().unwrap_or(""),
-                Arc::new(OcrEngine::default().into()), // Ideally could pass any str as ocr_engine since can be run outside of screenpipe
-            )
-            .await?;
+#[oasgen]
+pub(crate) async fn add_tags(
+    State(state): State<Arc<AppState>>,
+    Path((content_type, id)): Path<(String, i64)>,
+    JsonResponse(payload): JsonResponse<AddTagsRequest>,
+) -> Result<Json<AddTagsResponse>, (StatusCode, JsonResponse<Value>)> {
+    let target = format!("{}:{} {}", content_type, id, payload.tags.join(","));
+    let content_type = match content_type.as_str() {
+        "vision" => TagContentType::Vision,
+        "audio" => TagContentType::Audio,
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                JsonResponse(json!({"error": "Invalid content type"})),
+            ))
         }
-    }
+    };
 
-    if let Some(tags) = &frame.tags {
-        db.add_tags(frame_id, TagContentType::Vision, tags.clone())
-            .await?;
+    match state.db.add_tags(id, content_type, payload.tags).await {
+        Ok(_) => {
+            audit(&state.db, "add_tags", &target, None).await;
+            Ok(JsonResponse(AddTagsResponse { success: true }))
+        }
+        Err(e) => {
+            error!("Failed to add tags: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
     }
-
-    Ok(())
 }
 
-fn encode_frame_from_file_path(file_path: &str) -> Result<Vec<u8>, anyhow::Error> {
-    let image = image::open(file_path)?;
-    let mut buffer = Vec::new();
-    image.write_to(&mut std::io::Cursor::new(&mut buffer), ImageFormat::Png)?;
-    Ok(buffer)
+/// The metadata set on a frame or an audio chunk, e.g. by insert scripts.
+#[oasgen]
+pub(crate) async fn get_metadata_handler(
+    State(state): State<Arc<AppState>>,
+    Path((content_type, id)): Path<(String, i64)>,
+) -> Result<JsonResponse<Vec<ContentMetadata>>, (StatusCode, JsonResponse<Value>)> {
+    let content_type = match content_type.as_str() {
+        "vision" => TagContentType::Vision,
+        "audio" => TagContentType::Audio,
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                JsonResponse(json!({"error": "Invalid content type"})),
+            ))
+        }
+    };
+    state
+        .db
+        .get_metadata(id, content_type)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
 }
 
-async fn write_frames_to_video(
-    frames: &Vec<FrameContent>,
-    video_file_path: &str,
-    fps: f64,
-) -> Result<(), anyhow::Error> {
-    let mut ffmpeg_child = start_ffmpeg_process(video_file_path, fps).await?;
-    let mut ffmpeg_stdin = ffmpeg_child
-        .stdin
-        .take()
-        .expect("Failed to open stdin for FFmpeg");
+#[oasgen]
+pub(crate) async fn remove_tags(
+    State(state): State<Arc<AppState>>,
+    Path((content_type, id)): Path<(String, i64)>,
+    JsonResponse(payload): JsonResponse<RemoveTagsRequest>,
+) -> Result<Json<RemoveTagsResponse>, (StatusCode, JsonResponse<Value>)> {
+    let target = format!("{}:{} {}", content_type, id, payload.tags.join(","));
+    let content_type = match content_type.as_str() {
+        "vision" => TagContentType::Vision,
+        "audio" => TagContentType::Audio,
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                JsonResponse(json!({"error": "Invalid content type"})),
+            ))
+        }
+    };
 
-    for frame in frames {
-        let encoded_frame = encode_frame_from_file_path(&frame.file_path)?;
-        if let Err(e) = write_frame_to_ffmpeg(&mut ffmpeg_stdin, &encoded_frame).await {
-            error!("Failed to write frame to FFmpeg: {}", e);
-            return Err(e);
+    match state.db.remove_tags(id, content_type, payload.tags).await {
+        Ok(_) => {
+            audit(&state.db, "remove_tags", &target, None).await;
+            Ok(JsonResponse(RemoveTagsResponse { success: true }))
+        }
+        Err(e) => {
+            error!("Failed to remove tag: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
         }
     }
+}
 
-    finish_ffmpeg_process(ffmpeg_child, Some(ffmpeg_stdin)).await;
-    Ok(())
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct BulkTagsRequest {
+    content_type: TagContentType,
+    filter: BulkFilter,
+    #[serde(default)]
+    add: Vec<String>,
+    #[serde(default)]
+    remove: Vec<String>,
 }
 
-async fn add_transcription_to_db(
-    state: &AppState,
-    transcription: &AudioTranscription,
-    device_name: &str,
-) -> Result<(), anyhow::Error> {
-    let db = &state.db;
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct BulkDeleteRequest {
+    content_type: TagContentType,
+    filter: BulkFilter,
+}
 
-    let dummy_audio_chunk_id = db.insert_audio_chunk("").await?;
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct BulkRenameRequest {
+    filter: BulkFilter,
+    #[serde(default)]
+    app_name: Option<String>,
+    #[serde(default)]
+    window_name: Option<String>,
+}
 
-    db.insert_audio_transcription(
-        dummy_audio_chunk_id, // No associated audio chunk
-        &transcription.transcription,
-        -1,
-        &transcription.transcription_engine,
-        &screenpipe_db::AudioDevice {
-            name: device_name.to_string(),
-            device_type: DeviceType::Input.into(),
-        },
-        None,
-        None,
-        None,
-    )
-    .await?;
+fn check_bulk_filter(
+    content_type: &TagContentType,
+    filter: &BulkFilter,
+) -> Result<(), (StatusCode, JsonResponse<Value>)> {
+    let error = if filter.is_empty() {
+        // an empty filter would change everything
+        Some("filter needs at least one field")
+    } else if *content_type == TagContentType::Audio
+        && (filter.app_name.is_some() || filter.window_name.is_some())
+    {
+        Some("app_name and window_name only filter frames")
+    } else {
+        None
+    };
+    match error {
+        Some(error) => Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": error})),
+        )),
+        None => Ok(()),
+    }
+}
 
-    Ok(())
+// what a bulk operation applied to, for the audit log
+fn bulk_target(content_type: &TagContentType, filter: &BulkFilter) -> String {
+    let content_type = match content_type {
+        TagContentType::Vision => "vision",
+        TagContentType::Audio => "audio",
+    };
+    format!(
+        "{} {}",
+        content_type,
+        serde_json::to_string(filter).unwrap_or_default()
+    )
 }
 
+/// Adds and removes tags on every frame or audio chunk matching a filter, in
+/// one transaction.
 #[oasgen]
-pub(crate) async fn add_to_database(
+async fn bulk_tags_handler(
     State(state): State<Arc<AppState>>,
-    Json(payload): Json<AddContentRequest>,
-) -> Result<Json<AddContentResponse>, (StatusCode, Json<serde_json::Value>)> {
-    let device_name = payload.device_name.clone();
-    let mut success_messages = Vec::new();
+    Json(request): Json<BulkTagsRequest>,
+) -> Result<JsonResponse<BulkResult>, (StatusCode, JsonResponse<Value>)> {
+    check_bulk_filter(&request.content_type, &request.filter)?;
+    if request.add.is_empty() && request.remove.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "no tags to add or remove"})),
+        ));
+    }
 
-    match payload.content.content_type.as_str() {
-        "frames" => {
-            if let ContentData::Frames(frames) = &payload.content.data {
-                if !frames.is_empty() {
-                    let output_dir = state.screenpipe_dir.join("data");
-                    let time = Utc::now();
-                    let formatted_time = time.format("%Y-%m-%d_%H-%M-%S").to_string();
-                    let video_file_path = output_dir
-                        .join(format!("{}_{}.mp4", device_name, formatted_time))
-                        .to_str()
-                        .expect("Failed to create valid path")
-                        .to_string();
-
-                    if let Err(e) = state
-                        .db
-                        .insert_video_chunk(&video_file_path, &device_name)
-                        .await
-                    {
-                        error!(
-                            "Failed to insert video chunk for device {}: {}",
-                            device_name, e
-                        );
-                        return Err((
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            JsonResponse(
-                                json!({"error": format!("Failed to insert video chunk: {}", e)}),
-                            ),
-                        ));
-                    }
-
-                    if let Err(e) = write_frames_to_video(frames, &video_file_path, MAX_FPS).await {
-                        error!(
-                            "Failed to write frames to video file {}: {}",
-                            video_file_path, e
-                        );
-                        return Err((
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            JsonResponse(
-                                json!({"error": format!("Failed to write frames to video: {}", e)}),
-                            ),
-                        ));
-                    }
+    let result = state
+        .db
+        .bulk_update_tags(
+            &request.content_type,
+            &request.filter,
+            &request.add,
+            &request.remove,
+        )
+        .await
+        .map_err(|e| {
+            error!("failed to update tags in bulk: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })?;
+    audit(
+        &state.db,
+        "bulk_tags",
+        &bulk_target(&request.content_type, &request.filter),
+        Some(result.changed as i64),
+    )
+    .await;
+    Ok(JsonResponse(result))
+}
 
-                    for frame in frames {
-                        if let Err(e) = add_frame_to_db(&state, frame, &device_name).await {
-                            error!(
-                                "Failed to add frame content for device {}: {}",
-                                device_name, e
-                            );
-                        }
-                    }
+/// Moves every frame or audio chunk matching a filter to the trash.
+#[oasgen]
+async fn bulk_delete_handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<BulkDeleteRequest>,
+) -> Result<JsonResponse<BulkResult>, (StatusCode, JsonResponse<Value>)> {
+    check_bulk_filter(&request.content_type, &request.filter)?;
 
-                    success_messages.push("Frames added successfully".to_string());
-                }
-            }
-        }
-        "transcription" => {
-            if let ContentData::Transcription(transcription) = &payload.content.data {
-                if let Err(e) = add_transcription_to_db(&state, transcription, &device_name).await {
-                    error!(
-                        "Failed to add transcription for device {}: {}",
-                        device_name, e
-                    );
-                    return Err((
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        JsonResponse(
-                            json!({"error": format!("Failed to add transcription: {}", e)}),
-                        ),
-                    ));
-                }
+    let result = state
+        .db
+        .bulk_trash(&request.content_type, &request.filter)
+        .await
+        .map_err(|e| {
+            error!("failed to delete in bulk: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })?;
+    audit(
+        &state.db,
+        "bulk_delete",
+        &bulk_target(&request.content_type, &request.filter),
+        Some(result.changed as i64),
+    )
+    .await;
+    Ok(JsonResponse(result))
+}
 
-                success_messages.push("Transcription added successfully".to_string());
-            }
-        }
-        _ => {
-            error!("Unknown content type: {}", payload.content.content_type);
-            return Err((
-                StatusCode::BAD_REQUEST,
-                JsonResponse(json!({"error": "Unsupported content type"})),
-            ));
-        }
+/// Renames the app and/or window of every frame matching a filter.
+#[oasgen]
+async fn bulk_rename_handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<BulkRenameRequest>,
+) -> Result<JsonResponse<BulkResult>, (StatusCode, JsonResponse<Value>)> {
+    check_bulk_filter(&TagContentType::Vision, &request.filter)?;
+    if request.app_name.is_none() && request.window_name.is_none() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "app_name or window_name is required"})),
+        ));
     }
 
-    Ok(JsonResponse(AddContentResponse {
-        success: true,
-        message: Some(success_messages.join(", ")),
-    }))
+    state
+        .db
+        .bulk_rename_frames(
+            &request.filter,
+            request.app_name.as_deref(),
+            request.window_name.as_deref(),
+        )
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            error!("failed to rename frames in bulk: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
 }
 
-#[cfg(feature = "experimental")]
-async fn input_control_handler(
-    JsonResponse(payload): JsonResponse<InputControlRequest>,
-) -> Result<JsonResponse<InputControlResponse>, (StatusCode, JsonResponse<Value>)> {
-    use enigo::{Keyboard, Mouse};
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct SearchDeleteRequest {
+    #[serde(flatten)]
+    filter: SearchDeleteFilter,
+    /// only count what would be deleted
+    #[serde(default)]
+    dry_run: bool,
+}
 
-    info!("input control handler {:?}", payload);
-    let mut enigo = Enigo::new(&Settings::default()).map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            JsonResponse(json!({"error": format!("failed to initialize enigo: {}", e)})),
-        )
-    })?;
+/// Deletes for good everything a search finds, e.g. a session recorded by
+/// mistake: frames, audio and ui text with their text, index entries,
+/// embeddings, tags and the media files left unused. Try it with `dry_run`
+/// first.
+#[oasgen]
+async fn delete_search_results_handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<SearchDeleteRequest>,
+) -> Result<JsonResponse<SearchDeletion>, (StatusCode, JsonResponse<Value>)> {
+    if request.filter.is_empty() {
+        // an empty filter would delete everything
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(
+                json!({"error": "q, start_time, end_time, app_name or window_name is required"}),
+            ),
+        ));
+    }
 
-    match payload.action {
-        InputAction::KeyPress(key) => {
-            let _ = enigo.key(key_from_string(&key).unwrap(), enigo::Direction::Press);
-        }
-        InputAction::MouseMove { x, y } => {
-            let _ = enigo.move_mouse(x, y, enigo::Coordinate::Abs);
-        }
-        InputAction::MouseClick(button) => {
-            let _ = enigo.button(
-                mouse_button_from_string(&button).unwrap(),
-                enigo::Direction::Press,
-            );
-        }
-        InputAction::WriteText(text) => {
-            let _ = enigo.text(&text);
+    let deletion = state
+        .db
+        .delete_search_results(&request.filter, request.dry_run)
+        .await
+        .map_err(|e| {
+            error!("failed to delete search results: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })?;
+    if request.dry_run {
+        return Ok(JsonResponse(deletion));
+    }
+
+    for path in deletion
+        .video_files
+        .iter()
+        .chain(&deletion.audio_files)
+        .chain(&deletion.image_files)
+    {
+        if let Err(e) = tokio::fs::remove_file(path).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                error!("failed to remove {}: {}", path, e);
+            }
         }
     }
+    audit(
+        &state.db,
+        "delete_search_results",
+        &format!("{:?}", request.filter),
+        Some((deletion.frames + deletion.audio_chunks + deletion.ui_rows) as i64),
+    )
+    .await;
+    Ok(JsonResponse(deletion))
+}
 
-    Ok(JsonResponse(InputControlResponse { success: true }))
+#[derive(Deserialize)]
+pub(crate) struct SearchTagRequest {
+    #[serde(flatten)]
+    filter: SearchDeleteFilter,
+    tag: String,
 }
 
-#[cfg(feature = "experimental")]
-fn key_from_string(key: &str) -> Result<Key, (StatusCode, JsonResponse<Value>)> {
-    match key {
-        "enter" => Ok(Key::Return),
-        "space" => Ok(Key::Space),
-        // Add more key mappings as needed
-        _ => Err((
+/// Tags everything a search finds in one transaction, e.g. months of
+/// interviews at once. Streams `progress` events as server-sent events while
+/// it goes, then a `done` event with the final counts or an `error` event.
+async fn tag_search_results_handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<SearchTagRequest>,
+) -> Result<Sse<impl Stream<Item = Result<SseEvent, Infallible>>>, (StatusCode, JsonResponse<Value>)>
+{
+    if normalize_tag(&request.tag).is_empty() {
+        return Err((
             StatusCode::BAD_REQUEST,
-            JsonResponse(json!({"error": format!("Unsupported key: {}", key)})),
-        )),
+            JsonResponse(json!({"error": "tag is required"})),
+        ));
     }
+
+    let (events_tx, events_rx) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let progress = events_tx.clone();
+        let result = state
+            .db
+            .tag_search_results(&request.filter, &request.tag, |tagging| {
+                let _ = progress.send(SseEvent::default().event("progress").json_data(tagging));
+            })
+            .await;
+        let event = match result {
+            Ok(tagging) => {
+                audit(
+                    &state.db,
+                    "tag_search_results",
+                    &format!("{} {:?}", request.tag, request.filter),
+                    Some(tagging.tagged as i64),
+                )
+                .await;
+                SseEvent::default().event("done").json_data(&tagging)
+            }
+            Err(e) => {
+                error!("failed to tag search results: {}", e);
+                Ok(SseEvent::default().event("error").data(e.to_string()))
+            }
+        };
+        let _ = events_tx.send(event);
+    });
+
+    let events = futures::stream::unfold(events_rx, |mut events_rx| async move {
+        let event = events_rx
+            .recv()
+            .await?
+            .unwrap_or_else(|e| SseEvent::default().event("error").data(e.to_string()));
+        Some((Ok(event), events_rx))
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
 }
 
-#[cfg(feature = "experimental")]
-fn mouse_button_from_string(
-    button: &str,
-) -> Result<enigo::Button, (StatusCode, JsonResponse<Value>)> {
-    match button {
-        "left" => Ok(enigo::Button::Left),
-        "right" => Ok(enigo::Button::Right),
-        // Add more button mappings as needed
-        _ => Err((
+fn subject_required(subject: &Subject) -> Result<(), (StatusCode, JsonResponse<Value>)> {
+    if subject.is_empty() {
+        return Err((
             StatusCode::BAD_REQUEST,
-            JsonResponse(json!({"error": format!("Unsupported mouse button: {}", button)})),
-        )),
+            JsonResponse(json!({"error": "speaker_id or name is required"})),
+        ));
     }
+    Ok(())
 }
 
-// Add these new structs:
-#[cfg(feature = "experimental")]
-#[derive(Deserialize, Debug)]
-struct InputControlRequest {
-    action: InputAction,
+/// Everything referencing a speaker or a name, e.g. for a data subject
+/// access request: what they said and what was said about them, the audio
+/// chunks holding it, served by /audio/chunks/:chunk_id/file, and the screen
+/// and ui text mentioning them.
+#[oasgen]
+async fn export_subject_handler(
+    State(state): State<Arc<AppState>>,
+    Query(subject): Query<Subject>,
+) -> Result<JsonResponse<SubjectExport>, (StatusCode, JsonResponse<Value>)> {
+    subject_required(&subject)?;
+    state
+        .db
+        .export_subject(&subject)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            error!("failed to export subject: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
 }
 
-#[cfg(feature = "experimental")]
-#[derive(Deserialize, Debug)]
-#[serde(tag = "type", content = "data")]
-enum InputAction {
-    KeyPress(String),
-    MouseMove { x: i32, y: i32 },
-    MouseClick(String),
-    WriteText(String),
+/// Deletes for good what /subjects/export finds, blacks the frames
+/// mentioning the subject out of the videos and removes the media files left
+/// unused. The report holds counts and hashes of what was removed, sealed
+/// with the key in erasure_signing.key in the data dir, /subjects/verify
+/// checks the seal. Anyone reading the data dir can seal a report, so the
+/// seal is no proof to a third party.
+#[oasgen]
+async fn erase_subject_handler(
+    State(state): State<Arc<AppState>>,
+    Json(subject): Json<Subject>,
+) -> Result<JsonResponse<ErasureReport>, (StatusCode, JsonResponse<Value>)> {
+    subject_required(&subject)?;
+    let internal_error = |e: anyhow::Error| {
+        error!("failed to erase subject: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )
+    };
+    let key = load_or_create_signing_key(&state.screenpipe_dir.join(SIGNING_KEY_FILE))
+        .await
+        .map_err(internal_error)?;
+    erase_subject(&state.db, &subject, &key)
+        .await
+        .map(JsonResponse)
+        .map_err(internal_error)
 }
 
-#[cfg(feature = "experimental")]
-#[derive(Serialize)]
-struct InputControlResponse {
-    success: bool,
+#[oasgen]
+async fn verify_erasure_report_handler(
+    State(state): State<Arc<AppState>>,
+    Json(report): Json<ErasureReport>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    let key = load_or_create_signing_key(&state.screenpipe_dir.join(SIGNING_KEY_FILE))
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })?;
+    Ok(JsonResponse(json!({"valid": verify_report(&key, &report)})))
 }
 
-// Add this new struct
-#[derive(Deserialize)]
-pub struct StreamFramesRequest {
-    start_time: DateTime<Utc>,
-    end_time: DateTime<Utc>,
-    #[serde(rename = "order")]
-    #[serde(default = "Order::default")]
-    order: Order,
-}
+#[oasgen]
+pub async fn health_check(State(state): State<Arc<AppState>>) -> JsonResponse<HealthCheckResponse> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
 
-#[derive(Debug, Serialize)]
-pub struct StreamTimeSeriesResponse {
-    pub timestamp: DateTime<Utc>,
-    pub devices: Vec<DeviceFrameResponse>,
-}
+    let app_uptime = (now as i64) - (state.app_start_time.timestamp());
+    let grace_period = 120; // 2 minutes in seconds
 
-#[derive(Debug, Serialize)]
-pub struct DeviceFrameResponse {
-    pub device_id: String,
-    // pub frame: String, // base64 encoded image
-    pub frame_id: i64,
-    pub metadata: DeviceMetadata,
-    pub audio: Vec<AudioData>,
-}
+    // Get the status of all devices
+    let audio_devices = state.audio_manager.current_devices();
+    let mut device_statuses = Vec::new();
+    let mut global_audio_active = false;
+    let mut most_recent_audio_timestamp = 0; // Track the most recent timestamp
 
-#[derive(Debug, Serialize)]
-pub struct DeviceMetadata {
-    pub file_path: String,
-    pub app_name: String,
-    pub window_name: String,
-    pub ocr_text: String,
-}
+    // Check each device
+    for device in &audio_devices {
+        let device_name = device.to_string();
+        let last_capture = screenpipe_audio::core::get_device_capture_time(&device_name);
 
-#[derive(Debug, Serialize)]
-pub struct AudioData {
-    pub device_name: String,
-    pub is_input: bool,
-    pub transcription: String,
-    pub audio_file_path: String,
-    pub duration_secs: f64,
-    pub start_offset: f64, // offset from frame timestamp
-}
+        // Update the most recent timestamp
+        most_recent_audio_timestamp = most_recent_audio_timestamp.max(last_capture);
 
-impl From<TimeSeriesFrame> for StreamTimeSeriesResponse {
-    fn from(frame: TimeSeriesFrame) -> Self {
-        StreamTimeSeriesResponse {
-            timestamp: frame.timestamp,
-            devices: frame
-                .frame_data
-                .into_iter()
-                .map(|device_frame| {
-                    DeviceFrameResponse {
-                        device_id: device_frame.device_id,
-                        // frame: BASE64_STANDARD.encode(&device_frame.image_data),
-                        frame_id: device_frame.frame_id,
-                        metadata: DeviceMetadata {
-                            file_path: device_frame.metadata.file_path,
-                            app_name: device_frame.metadata.app_name,
-                            window_name: device_frame.metadata.window_name,
-                            ocr_text: device_frame.metadata.ocr_text,
-                        },
-                        audio: device_frame
-                            .audio_entries
-                            .into_iter()
-                            .map(|audio| {
-                                AudioData {
-                                    device_name: audio.device_name,
-                                    is_input: audio.is_input,
-                                    transcription: audio.transcription,
-                                    audio_file_path: audio.audio_file_path,
-                                    duration_secs: audio.duration_secs,
-                                    start_offset: 0.0, // calculate based on audio timestamp vs frame timestamp
-                                }
-                            })
-                            .collect(),
-                    }
-                })
-                .collect(),
-        }
-    }
-}
+        let device_active = if app_uptime < grace_period {
+            true // Consider active during grace period
+        } else {
+            now - last_capture < 5 // Consider active if captured in last 5 seconds
+        };
 
-#[derive(OaSchema, Deserialize, Debug)]
-pub struct GetUnnamedSpeakersRequest {
-    limit: u32,
-    offset: u32,
-    // comma separated list of speaker ids to include
-    #[serde(
-        deserialize_with = "from_comma_separated_array",
-        default = "default_speaker_ids"
-    )]
-    speaker_ids: Option<Vec<i64>>,
-}
+        // Track if any device is active
+        if device_active {
+            global_audio_active = true;
+        }
+        debug!(target: "server", "device status: {} {}", device_name, device_active);
 
-fn default_speaker_ids() -> Option<Vec<i64>> {
-    None
-}
+        device_statuses.push((device_name, device_active, last_capture));
+    }
 
-#[derive(OaSchema, Deserialize, Debug)]
-pub struct GetSimilarSpeakersRequest {
-    speaker_id: i64,
-    limit: u32,
-}
+    // Fallback to global timestamp if no devices are detected
+    if audio_devices.is_empty() {
+        let last_capture = screenpipe_audio::core::LAST_AUDIO_CAPTURE.load(Ordering::Relaxed);
+        global_audio_active = if app_uptime < grace_period {
+            true // Consider active during grace period
+        } else {
+            now - last_capture < 5 // Consider active if captured in last 5 seconds
+        };
+    }
 
-fn from_comma_separated_array<'de, D>(deserializer: D) -> Result<Option<Vec<i64>>, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let s = Option::<String>::deserialize(deserializer).unwrap_or(None);
-    let s = match s {
-        None => return Ok(None),
-        Some(s) => s,
+    let (last_frame, audio, last_ui) = match state.db.get_latest_timestamps().await {
+        Ok((frame, audio, ui)) => (frame, audio, ui),
+        Err(e) => {
+            error!("failed to get latest timestamps: {}", e);
+            (None, None, None)
+        }
     };
-    s.split(',')
-        .map(|i| i64::from_str(i).map_err(serde::de::Error::custom))
-        .collect::<Result<Vec<_>, _>>()
-        .map(Some)
-}
 
-#[oasgen]
-async fn get_unnamed_speakers_handler(
-    State(state): State<Arc<AppState>>,
-    Query(request): Query<GetUnnamedSpeakersRequest>,
-) -> Result<JsonResponse<Vec<Speaker>>, (StatusCode, JsonResponse<Value>)> {
-    let speakers = state
-        .db
-        .get_unnamed_speakers(request.limit, request.offset, request.speaker_ids)
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                JsonResponse(json!({"error": e.to_string()})),
-            )
-        })?;
+    let now = Utc::now();
+    let threshold = Duration::from_secs(1800); // 30 minutes
+    let pauses = state.capture_pauses.as_ref().map(|p| p.status());
 
-    // convert metadata to json
-    let speakers = speakers
-        .into_iter()
-        .map(|speaker| {
-            let mut metadata: Value = serde_json::from_str(&speaker.metadata).unwrap();
-            if let Some(audio_samples) = metadata.get("audio_samples").and_then(|v| v.as_array()) {
-                metadata["audio_samples"] = serde_json::to_value(audio_samples).unwrap();
+    let frame_status = if state.vision_disabled {
+        "disabled"
+    } else if pauses.as_ref().is_some_and(|p| p.vision_paused) {
+        "paused"
+    } else {
+        match last_frame {
+            Some(timestamp)
+                if now.signed_duration_since(timestamp)
+                    < chrono::Duration::from_std(threshold).unwrap() =>
+            {
+                "ok"
             }
-            Speaker {
-                metadata: metadata.to_string(),
-                ..speaker
+            Some(_) => "stale",
+            None => "not_started",
+        }
+    };
+
+    let audio_status = if state.audio_disabled {
+        "disabled".to_string()
+    } else if pauses.as_ref().is_some_and(|p| p.audio_paused) {
+        "paused".to_string()
+    } else if global_audio_active {
+        "ok".to_string()
+    } else {
+        match audio {
+            Some(timestamp)
+                if now.signed_duration_since(timestamp)
+                    < chrono::Duration::from_std(threshold).unwrap() =>
+            {
+                "stale".to_string()
             }
-        })
-        .collect();
+            Some(_) => "stale".to_string(),
+            None => "not_started".to_string(),
+        }
+    };
 
-    Ok(JsonResponse(speakers))
-}
+    // Format device statuses as a string for a more detailed view
+    let device_status_details = if !device_statuses.is_empty() {
+        let now_secs = now.timestamp() as u64;
+        let device_details: Vec<String> = device_statuses
+            .iter()
+            .map(|(name, active, last_capture)| {
+                format!(
+                    "{}: {} (last activity: {}s ago)",
+                    name,
+                    if *active { "active" } else { "inactive" },
+                    now_secs.saturating_sub(*last_capture)
+                )
+            })
+            .collect();
 
-#[oasgen]
-async fn update_speaker_handler(
-    State(state): State<Arc<AppState>>,
-    Json(payload): Json<UpdateSpeakerRequest>,
-) -> Result<JsonResponse<Speaker>, (StatusCode, JsonResponse<Value>)> {
-    let speaker_id = payload.id;
+        Some(device_details.join(", "))
+    } else {
+        None
+    };
 
-    if let Some(name) = payload.name {
-        if let Err(e) = state.db.update_speaker_name(speaker_id, &name).await {
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                JsonResponse(json!({"error": e.to_string()})),
-            ));
+    let ui_status = if !state.ui_monitoring_enabled {
+        "disabled"
+    } else if pauses.as_ref().is_some_and(|p| p.ui_paused) {
+        "paused"
+    } else {
+        match last_ui {
+            Some(timestamp)
+                if now.signed_duration_since(timestamp)
+                    < chrono::Duration::from_std(threshold).unwrap() =>
+            {
+                "ok"
+            }
+            Some(_) => "stale",
+            None => "not_started",
         }
-    }
+    };
 
-    if let Some(metadata) = payload.metadata {
-        if let Err(e) = state
-            .db
-            .update_speaker_metadata(speaker_id, &metadata)
-            .await
-        {
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                JsonResponse(json!({"error": e.to_string()})),
-            ));
+    // a paused system is off on purpose, like a disabled one
+    let healthy = |status: &str| matches!(status, "ok" | "disabled" | "paused");
+    let all_healthy = healthy(frame_status) && healthy(&audio_status) && healthy(ui_status);
+    let (overall_status, message, verbose_instructions, status_code) = if all_healthy {
+        (
+            "healthy",
+            "all systems are functioning normally.".to_string(),
+            None,
+            200,
+        )
+    } else {
+        let mut unhealthy_systems = Vec::new();
+        if !healthy(frame_status) {
+            unhealthy_systems.push("vision");
+        }
+        if !healthy(&audio_status) {
+            unhealthy_systems.push("audio");
+        }
+        if !healthy(ui_status) {
+            unhealthy_systems.push("ui");
         }
+
+        let systems_str = unhealthy_systems.join(", ");
+        (
+            "degraded",
+            format!("some systems are not healthy: {}", systems_str),
+            Some(get_verbose_instructions(&unhealthy_systems)),
+            503,
+        )
+    };
+
+    JsonResponse(HealthCheckResponse {
+        status: overall_status.to_string(),
+        status_code,
+        last_frame_timestamp: last_frame,
+        last_audio_timestamp: if most_recent_audio_timestamp > 0 {
+            Some(
+                Utc.timestamp_opt(most_recent_audio_timestamp as i64, 0)
+                    .unwrap(),
+            )
+        } else {
+            None
+        },
+        last_ui_timestamp: last_ui,
+        frame_status: frame_status.to_string(),
+        audio_status,
+        ui_status: ui_status.to_string(),
+        message,
+        verbose_instructions,
+        device_status_details,
+        maintenance: state.maintenance.as_ref().map(|m| m.status()),
+        storage: state.storage_quota.as_ref().map(|q| q.status()),
+        capture_pauses: pauses,
+        local_only: local_only::status(),
+    })
+}
+
+fn get_verbose_instructions(unhealthy_systems: &[&str]) -> String {
+    let mut instructions = String::new();
+
+    if unhealthy_systems.contains(&"vision") {
+        instructions.push_str("Vision system is not working properly. Check if screen recording permissions are enabled.\n");
     }
 
-    Ok(JsonResponse(
-        state.db.get_speaker_by_id(speaker_id).await.unwrap(),
-    ))
+    if unhealthy_systems.contains(&"audio") {
+        instructions.push_str("Audio system is not working properly. Check if microphone permissions are enabled and devices are connected.\n");
+    }
+
+    if unhealthy_systems.contains(&"ui") {
+        instructions.push_str("UI monitoring is not working properly. Check if accessibility permissions are enabled.\n");
+    }
+
+    if instructions.is_empty() {
+        instructions =
+            "If you're experiencing issues, please try contacting us on Discord.".to_string();
+    }
+
+    instructions
 }
 
-#[oasgen]
-async fn search_speakers_handler(
-    State(state): State<Arc<AppState>>,
-    Query(request): Query<SearchSpeakersRequest>,
-) -> Result<JsonResponse<Vec<Speaker>>, (StatusCode, JsonResponse<Value>)> {
-    let search_prefix = request.name.unwrap_or_default();
-    Ok(JsonResponse(
-        state.db.search_speakers(&search_prefix).await.unwrap(),
-    ))
+// Request and response structs
+#[derive(OaSchema, Deserialize)]
+struct DownloadPipeRequest {
+    url: String,
+}
+
+#[derive(OaSchema, Deserialize)]
+struct DownloadPipePrivateRequest {
+    url: String,
+    pipe_name: String,
+    pipe_id: String,
+}
+
+#[derive(OaSchema, Deserialize)]
+struct RunPipeRequest {
+    pipe_id: String,
+}
+
+#[derive(OaSchema, Deserialize)]
+struct UpdatePipeConfigRequest {
+    pipe_id: String,
+    config: serde_json::Value,
+}
+
+#[derive(OaSchema, Deserialize)]
+struct UpdatePipeVersionRequest {
+    pipe_id: String,
+    source: String,
 }
 
 #[oasgen]
-async fn delete_speaker_handler(
+async fn download_pipe_handler(
     State(state): State<Arc<AppState>>,
-    Json(payload): Json<DeleteSpeakerRequest>,
-) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
-    // get audio_chunks for this speaker
-    let audio_chunks = state
-        .db
-        .get_audio_chunks_for_speaker(payload.id)
-        .await
-        .map_err(|e| {
-            (
+    JsonResponse(payload): JsonResponse<DownloadPipeRequest>,
+) -> Result<JsonResponse<serde_json::Value>, (StatusCode, JsonResponse<Value>)> {
+    debug!("Downloading pipe: {}", payload.url);
+    match state.pipe_manager.download_pipe(&payload.url).await {
+        Ok(pipe_dir) => Ok(JsonResponse(json!({
+            "data": {
+                "pipe_id": pipe_dir,
+                "message": "pipe downloaded successfully"
+            },
+            "success": true
+        }))),
+        Err(e) => {
+            error!("Failed to download pipe: {}", e);
+            Err((
                 StatusCode::INTERNAL_SERVER_ERROR,
-                JsonResponse(json!({"error": e.to_string()})),
-            )
-        })?;
-
-    state.db.delete_speaker(payload.id).await.map_err(|e| {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            JsonResponse(json!({"error": e.to_string()})),
-        )
-    })?;
-
-    // delete all audio chunks from the file system
-    for audio_chunk in audio_chunks {
-        if audio_chunk.start_time.is_some() && audio_chunk.end_time.is_some() {
-            std::fs::remove_file(audio_chunk.file_path).map_err(|e| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    JsonResponse(json!({"error": e.to_string()})),
-                )
-            })?;
+                JsonResponse(json!({
+                    "error": format!("failed to download pipe: {}", e),
+                    "success": false
+                })),
+            ))
         }
     }
-
-    Ok(JsonResponse(json!({"success": true})))
 }
 
 #[oasgen]
-async fn mark_as_hallucination_handler(
+async fn download_pipe_private_handler(
     State(state): State<Arc<AppState>>,
-    Json(payload): Json<MarkAsHallucinationRequest>,
-) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
-    let speaker_id = payload.speaker_id;
-
-    state
-        .db
-        .mark_speaker_as_hallucination(speaker_id)
+    JsonResponse(payload): JsonResponse<DownloadPipePrivateRequest>,
+) -> Result<JsonResponse<serde_json::Value>, (StatusCode, JsonResponse<Value>)> {
+    match state
+        .pipe_manager
+        .download_pipe_private(&payload.url, &payload.pipe_name, &payload.pipe_id)
         .await
-        .unwrap();
-
-    Ok(JsonResponse(json!({"success": true})))
+    {
+        Ok(pipe_dir) => Ok(JsonResponse(json!({
+            "data": {
+                "pipe_id": pipe_dir,
+                "message": "pipe downloaded successfully"
+            },
+            "success": true
+        }))),
+        Err(e) => {
+            error!("Failed to download pipe: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({
+                    "error": format!("failed to download pipe: {}", e),
+                    "success": false
+                })),
+            ))
+        }
+    }
 }
 
 #[oasgen]
-async fn merge_speakers_handler(
+async fn run_pipe_handler(
     State(state): State<Arc<AppState>>,
-    Json(payload): Json<MergeSpeakersRequest>,
+    JsonResponse(payload): JsonResponse<RunPipeRequest>,
 ) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
-    let speaker_to_keep_id = payload.speaker_to_keep_id;
-    let speaker_to_merge_id = payload.speaker_to_merge_id;
+    debug!("starting pipe: {}", payload.pipe_id);
 
-    state
-        .db
-        .merge_speakers(speaker_to_keep_id, speaker_to_merge_id)
+    match state
+        .pipe_manager
+        .update_config(
+            &payload.pipe_id,
+            serde_json::json!({
+                "enabled": true,
+            }),
+        )
         .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                JsonResponse(json!({"error": e.to_string(), "speaker_to_keep_id": speaker_to_keep_id, "speaker_to_merge_id": speaker_to_merge_id})),
-            )
-        })?;
-
-    Ok(JsonResponse(json!({"success": true})))
+    {
+        Ok(_) => Ok(JsonResponse(json!({
+            "data": {
+                "pipe_id": payload.pipe_id,
+                "message": "pipe started"
+            },
+            "success": true
+        }))),
+        Err(e) => Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({
+                "error": format!("failed to start pipe: {}", e),
+                "success": false
+            })),
+        )),
+    }
 }
 
 #[oasgen]
-async fn get_similar_speakers_handler(
+async fn stop_pipe_handler(
     State(state): State<Arc<AppState>>,
-    Query(request): Query<GetSimilarSpeakersRequest>,
-) -> Result<JsonResponse<Vec<Speaker>>, (StatusCode, JsonResponse<Value>)> {
-    let speaker_id = request.speaker_id;
-    let limit = request.limit;
-
-    let similar_speakers = state
-        .db
-        .get_similar_speakers(speaker_id, limit)
+    JsonResponse(payload): JsonResponse<RunPipeRequest>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    debug!("Stopping pipe: {}", payload.pipe_id);
+    match state
+        .pipe_manager
+        .update_config(
+            &payload.pipe_id,
+            serde_json::json!({
+                "enabled": false,
+            }),
+        )
+        .await
+    {
+        Ok(_) => Ok(JsonResponse(json!({
+            "data": {
+                "pipe_id": payload.pipe_id,
+                "message": "pipe stopped"
+            },
+            "success": true
+        }))),
+        Err(e) => Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({
+                "error": format!("failed to stop pipe: {}", e),
+                "success": false
+            })),
+        )),
+    }
+}
+
+#[oasgen]
+async fn update_pipe_config_handler(
+    State(state): State<Arc<AppState>>,
+    JsonResponse(payload): JsonResponse<UpdatePipeConfigRequest>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    debug!("Updating pipe config for: {}", payload.pipe_id);
+    match state
+        .pipe_manager
+        .update_config(&payload.pipe_id, payload.config)
+        .await
+    {
+        Ok(_) => Ok(JsonResponse(json!({
+            "data": {
+                "pipe_id": payload.pipe_id,
+                "message": "pipe config updated"
+            },
+            "success": true
+        }))),
+        Err(e) => Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({
+                "error": format!("failed to update pipe config: {}", e),
+                "success": false
+            })),
+        )),
+    }
+}
+
+#[oasgen]
+async fn update_pipe_version_handler(
+    State(state): State<Arc<AppState>>,
+    JsonResponse(payload): JsonResponse<UpdatePipeVersionRequest>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    debug!("Updating pipe version for: {}", payload.pipe_id);
+    match state
+        .pipe_manager
+        .update_pipe_version(&payload.pipe_id, &payload.source)
+        .await
+    {
+        Ok(_) => Ok(JsonResponse(json!({
+            "data": {
+                "pipe_id": payload.pipe_id,
+                "message": "pipe version updated"
+            },
+            "success": true
+        }))),
+        Err(e) => Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({
+                "error": format!("failed to update pipe version: {}", e),
+                "success": false
+            })),
+        )),
+    }
+}
+
+#[oasgen]
+async fn get_pipe_info_handler(
+    State(state): State<Arc<AppState>>,
+    Path(pipe_id): Path<String>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    debug!("Getting pipe info for: {}", pipe_id);
+    match state.pipe_manager.get_pipe_info(&pipe_id).await {
+        Some(info) => Ok(JsonResponse(json!({
+            "data": info,
+            "success": true
+        }))),
+        None => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({
+                "error": "pipe not found",
+                "success": false
+            })),
+        )),
+    }
+}
+
+#[oasgen]
+async fn list_pipes_handler(State(state): State<Arc<AppState>>) -> JsonResponse<Value> {
+    let pipes = state.pipe_manager.list_pipes().await;
+    JsonResponse(json!({
+        "data": pipes,
+        "success": true
+    }))
+}
+
+/// Routes documented in the OpenAPI spec served at `/openapi.json` and
+/// `/openapi.yaml`. Websockets and server-sent events can't be described by
+/// it and are added in `SCServer::create_router`.
+fn api_server() -> Server<Router<Arc<AppState>>> {
+    let mut server = Server::axum()
+        .get("/search", search)
+        .get("/v2/search", search_v2)
+        .post("/search/clicks", record_search_click_handler)
+        .get("/search/clicks", list_click_signals_handler)
+        .delete("/search/clicks", reset_click_signals_handler)
+        .get("/shadow/report", shadow_report_handler)
+        .get("/peers", list_peers_handler)
+        .get("/audio/list", api_list_audio_devices)
+        .get("/audio/chunks/:chunk_id/file", get_audio_chunk_file_handler)
+        .get("/vision/list", api_list_monitors)
+        .get("/vision/settings", get_monitor_settings_handler)
+        .post(
+            "/vision/settings/:monitor_id",
+            update_monitor_settings_handler,
+        )
+        .get("/devices", list_devices_handler)
+        .post("/devices/:id", update_device_handler)
+        .post("/vision/screenshot", capture_screenshot_handler)
+        .get("/tags", tag_facets_handler)
+        .post("/tags/:content_type/:id", add_tags)
+        .delete("/tags/:content_type/:id", remove_tags)
+        .get("/metadata/:content_type/:id", get_metadata_handler)
+        .post("/bulk/tags", bulk_tags_handler)
+        .post("/bulk/delete", bulk_delete_handler)
+        .post("/bulk/rename", bulk_rename_handler)
+        .post("/search/delete", delete_search_results_handler)
+        .get("/subjects/export", export_subject_handler)
+        .post("/subjects/erase", erase_subject_handler)
+        .post("/subjects/verify", verify_erasure_report_handler)
+        .get("/pipes/info/:pipe_id", get_pipe_info_handler)
+        .get("/pipes/list", list_pipes_handler)
+        .post("/pipes/download", download_pipe_handler)
+        .post("/pipes/download-private", download_pipe_private_handler)
+        .post("/pipes/enable", run_pipe_handler)
+        .post("/pipes/disable", stop_pipe_handler)
+        .post("/pipes/update", update_pipe_config_handler)
+        .post("/pipes/update-version", update_pipe_version_handler)
+        .post("/pipes/delete", delete_pipe_handler)
+        .post("/pipes/purge", purge_pipe_handler)
+        .get("/frames/:frame_id", get_frame_data)
+        .post("/frames/reocr", reocr_frames_handler)
+        .get("/frames/:frame_id/thumbnail", get_frame_thumbnail_handler)
+        .get("/frames/:frame_id/whiteboard", get_whiteboard_image_handler)
+        .get("/frames/:frame_id/region", get_frame_region_handler)
+        .post("/frames/:frame_id/redact", redact_frame_handler)
+        .get("/receipts", list_receipts_handler)
+        .get("/media/issues", list_media_issues_handler)
+        .get("/trash", list_trash_handler)
+        .get("/retention/rules", list_retention_rules_handler)
+        .get("/trash/items", list_trash_items_handler)
+        .post("/trash/restore", restore_trash_handler)
+        .get("/coverage", coverage_report_handler)
+        .get("/timeline", timeline_handler)
+        .get("/work-sessions", list_work_sessions_handler)
+        .get("/work-sessions/:id", get_work_session_handler)
+        .post("/work-sessions/detect", detect_work_sessions_handler)
+        .get("/activity/heatmap", activity_heatmap_handler)
+        .get("/input-activity", input_activity_handler)
+        .get("/ui/traversals", ui_traversals_handler)
+        .get("/ui/changes", ui_changes_handler)
+        .get("/focus/sessions", focus_sessions_handler)
+        .get("/focus/time", focus_time_handler)
+        .get("/idle", idle_intervals_handler)
+        .get("/summary", summary_handler)
+        .get("/topics", top_topics_handler)
+        .get("/topics/trend", topic_trend_handler)
+        .post("/topics/extract", extract_topics_handler)
+        .get("/productivity", productivity_handler)
+        .get("/productivity/categories", list_app_category_rules_handler)
+        .post("/productivity/categories", create_app_category_rule_handler)
+        .delete(
+            "/productivity/categories/:id",
+            delete_app_category_rule_handler,
+        )
+        .post("/clock/sync", clock_sync_handler)
+        .get("/auth/tokens", list_api_tokens_handler)
+        .post("/auth/tokens", create_api_token_handler)
+        .delete("/auth/tokens/:id", delete_api_token_handler)
+        .get("/webhook-rules", list_webhook_rules_handler)
+        .post("/webhook-rules", create_webhook_rule_handler)
+        .delete("/webhook-rules/:id", delete_webhook_rule_handler)
+        .get("/tag-rules", list_tag_rules_handler)
+        .post("/tag-rules", create_tag_rule_handler)
+        .post("/tag-rules/:id/enable", enable_tag_rule_handler)
+        .post("/tag-rules/:id/disable", disable_tag_rule_handler)
+        .delete("/tag-rules/:id", delete_tag_rule_handler)
+        .get("/tag-names", list_tag_names_handler)
+        .post("/tag-names/rename", rename_tag_handler)
+        .post("/tag-names/merge", merge_tags_handler)
+        .post("/tag-names/aliases", add_tag_alias_handler)
+        .post("/tag-names/aliases/delete", remove_tag_alias_handler)
+        .post("/tag-names/delete", delete_tag_handler)
+        .get("/tag-suggestions", list_tag_suggestions_handler)
+        .post("/tag-suggestions/:id/accept", accept_tag_suggestion_handler)
+        .post("/tag-suggestions/:id/reject", reject_tag_suggestion_handler)
+        .get("/push/destinations", list_push_destinations_handler)
+        .post("/push/destinations", create_push_destination_handler)
+        .delete("/push/destinations/:id", delete_push_destination_handler)
+        .get("/audit", list_audit_log_handler)
+        .get("/capture/blocklist", list_capture_block_rules_handler)
+        .post("/capture/blocklist", create_capture_block_rule_handler)
+        .delete("/capture/blocklist/:id", delete_capture_block_rule_handler)
+        .get("/capture/pause", capture_pause_status_handler)
+        .post("/capture/pause", pause_capture_handler)
+        .post("/capture/resume", resume_capture_handler)
+        .get("/capture/pauses", list_capture_pauses_handler)
+        .get(
+            "/capture/pause/schedules",
+            list_capture_pause_schedules_handler,
+        )
+        .post(
+            "/capture/pause/schedules",
+            create_capture_pause_schedule_handler,
+        )
+        .delete(
+            "/capture/pause/schedules/:id",
+            delete_capture_pause_schedule_handler,
+        )
+        .get("/plugins", list_plugins_handler)
+        .get("/extensions", list_extensions_handler)
+        .post("/extensions/install", install_extension_handler)
+        .get("/extensions/:name", get_extension_handler)
+        .post("/extensions/:name/enable", enable_extension_handler)
+        .post("/extensions/:name/disable", disable_extension_handler)
+        .get("/provenance/:content_type/:id", get_provenance_handler)
+        .post(
+            "/provenance/:content_type/:id/rerun",
+            rerun_pipeline_handler,
+        )
+        .get("/text-spans", list_text_spans_handler)
+        .get("/meetings", list_meetings_handler)
+        .get("/meetings/:meeting_id", get_meeting_handler)
+        .get("/meetings/:meeting_id/slides", get_meeting_slides_handler)
+        .get(
+            "/meetings/:meeting_id/transcript",
+            get_meeting_transcript_handler,
+        )
+        .post("/meetings/detect", detect_meetings_handler)
+        .get("/calendar-hints", list_calendar_hints_handler)
+        .post("/calendar-hints", add_calendar_hint_handler)
+        .delete("/calendar-hints/:id", delete_calendar_hint_handler)
+        .post(
+            "/meetings/:meeting_id/participants/:participant_id/speaker",
+            link_participant_speaker_handler,
+        )
+        .get("/health", health_check)
+        .post("/raw_sql", execute_raw_sql)
+        .post("/add", add_to_database)
+        .post("/browser/context", browser_context_handler)
+        .get("/speakers/unnamed", get_unnamed_speakers_handler)
+        .post("/speakers/update", update_speaker_handler)
+        .get("/speakers/search", search_speakers_handler)
+        .post("/speakers/delete", delete_speaker_handler)
+        .post("/speakers/hallucination", mark_as_hallucination_handler)
+        .post("/speakers/merge", merge_speakers_handler)
+        .post("/speakers/compact", compact_speakers_handler)
+        .get("/speakers/similar", get_similar_speakers_handler)
+        .post("/experimental/frames/merge", merge_frames_handler)
+        .get("/experimental/validate/media", validate_media_handler)
+        .post("/experimental/operator", find_elements_handler)
+        .post("/experimental/operator/click", click_element_handler)
+        .post("/experimental/operator/type", type_text_handler)
+        .post("/audio/start", start_audio)
+        .post("/audio/stop", stop_audio)
+        .get("/semantic-search", semantic_search_handler)
+        .get(
+            "/embeddings/indexes/:index_name",
+            get_embedding_index_handler,
+        )
+        .post(
+            "/embeddings/indexes/:index_name",
+            update_embedding_index_handler,
+        )
+        .get("/pipes/build-status/:pipe_id", get_pipe_build_status)
+        .get("/search/keyword", keyword_search_handler)
+        .post("/v1/embeddings", create_embeddings)
+        .post("/audio/device/start", start_audio_device)
+        .post("/audio/device/stop", stop_audio_device);
+    server.openapi.info.title = "screenpipe".to_string();
+    server.openapi.info.version = env!("CARGO_PKG_VERSION").to_string();
+    server.openapi.info.description =
+        Some("Search and manage what screenpipe recorded from the screen and audio.".to_string());
+    server
+}
+
+/// OpenAPI document of the HTTP api, to generate client SDKs from.
+pub fn openapi_spec() -> Value {
+    serde_json::to_value(&api_server().openapi).unwrap_or_default()
+}
+
+pub struct SCServer {
+    db: Arc<DatabaseManager>,
+    addr: SocketAddr,
+    audio_manager: Arc<AudioManager>,
+    screenpipe_dir: PathBuf,
+    pipe_manager: Arc<PipeManager>,
+    vision_disabled: bool,
+    audio_disabled: bool,
+    ui_monitoring_enabled: bool,
+    ocr_engine: Arc<OcrEngine>,
+    languages: Vec<Language>,
+    ignored_windows: Vec<String>,
+    included_windows: Vec<String>,
+    trash_days: u32,
+    click_tracking: bool,
+    shadow_search: Option<ShadowConfig>,
+    api_auth: bool,
+    rate_limits: RateLimits,
+    maintenance: Option<Arc<MaintenanceScheduler>>,
+    storage_quota: Option<Arc<StorageQuota>>,
+    capture_pauses: Option<Arc<CapturePauses>>,
+    secret_masker: Option<Arc<SecretMasker>>,
+    plugins: Option<Arc<PluginHost>>,
+    text_pipeline: Option<Arc<TextPipeline>>,
+    extensions: Option<Arc<ExtensionManager>>,
+    summary_llm: Option<Arc<SummaryLlm>>,
+    peers: Option<Arc<Peers>>,
+    peer_addr: Option<SocketAddr>,
+}
+
+impl SCServer {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        db: Arc<DatabaseManager>,
+        addr: SocketAddr,
+        screenpipe_dir: PathBuf,
+        pipe_manager: Arc<PipeManager>,
+        vision_disabled: bool,
+        audio_disabled: bool,
+        ui_monitoring_enabled: bool,
+        audio_manager: Arc<AudioManager>,
+    ) -> Self {
+        SCServer {
+            db,
+            addr,
+            screenpipe_dir,
+            pipe_manager,
+            vision_disabled,
+            audio_disabled,
+            ui_monitoring_enabled,
+            audio_manager,
+            ocr_engine: Arc::new(OcrEngine::default()),
+            languages: Vec::new(),
+            ignored_windows: Vec::new(),
+            included_windows: Vec::new(),
+            trash_days: 0,
+            click_tracking: false,
+            shadow_search: None,
+            api_auth: false,
+            rate_limits: RateLimits::default(),
+            maintenance: None,
+            storage_quota: None,
+            capture_pauses: None,
+            secret_masker: None,
+            plugins: None,
+            text_pipeline: None,
+            extensions: None,
+            summary_llm: None,
+            peers: None,
+            peer_addr: None,
+        }
+    }
+
+    /// Sets the OCR engine and languages used when the server runs OCR itself,
+    /// e.g. when re-processing stored frames.
+    pub fn with_ocr_engine(mut self, ocr_engine: Arc<OcrEngine>, languages: Vec<Language>) -> Self {
+        self.ocr_engine = ocr_engine;
+        self.languages = languages;
+        self
+    }
+
+    /// Sets the window filters applied to captures the server takes itself.
+    pub fn with_window_filters(
+        mut self,
+        ignored_windows: Vec<String>,
+        included_windows: Vec<String>,
+    ) -> Self {
+        self.ignored_windows = ignored_windows;
+        self.included_windows = included_windows;
+        self
+    }
+
+    /// Sets how long expired recordings stay in the trash, to tell when they
+    /// get purged.
+    pub fn with_trash_days(mut self, trash_days: u32) -> Self {
+        self.trash_days = trash_days;
+        self
+    }
+
+    /// Records the search results opened and ranks them higher for similar
+    /// queries.
+    pub fn with_click_tracking(mut self, click_tracking: bool) -> Self {
+        self.click_tracking = click_tracking;
+        self
+    }
+
+    /// Runs a candidate search implementation alongside `/search` on a
+    /// sample of the requests and reports how its results compare.
+    pub fn with_shadow_search(mut self, shadow_search: Option<ShadowConfig>) -> Self {
+        self.shadow_search = shadow_search;
+        self
+    }
+
+    /// Requires a token with the right scope on every route but the health
+    /// checks.
+    pub fn with_api_auth(mut self, api_auth: bool) -> Self {
+        self.api_auth = api_auth;
+        self
+    }
+
+    /// Limits how often each client may call each class of routes.
+    pub fn with_rate_limits(mut self, rate_limits: RateLimits) -> Self {
+        self.rate_limits = rate_limits;
+        self
+    }
+
+    /// Reports the status of this database maintenance in /health.
+    pub fn with_maintenance(mut self, maintenance: Option<Arc<MaintenanceScheduler>>) -> Self {
+        self.maintenance = maintenance;
+        self
+    }
+
+    /// Reports the storage budget and evictions in /health.
+    pub fn with_storage_quota(mut self, storage_quota: Option<Arc<StorageQuota>>) -> Self {
+        self.storage_quota = storage_quota;
+        self
+    }
+
+    /// Pauses and resumes capture at /capture/pause and /capture/resume.
+    pub fn with_capture_pauses(mut self, capture_pauses: Arc<CapturePauses>) -> Self {
+        self.capture_pauses = Some(capture_pauses);
+        self
+    }
+
+    /// Masks secrets in what is pushed through the api, as `--mask-secrets`
+    /// does in captured text.
+    pub fn with_secret_masker(mut self, secret_masker: Option<Arc<SecretMasker>>) -> Self {
+        self.secret_masker = secret_masker;
+        self
+    }
+
+    /// Reports what the content processor plugins did at /plugins.
+    pub fn with_plugins(mut self, plugins: Option<Arc<PluginHost>>) -> Self {
+        self.plugins = plugins;
+        self
+    }
+
+    /// Re-processes stored text at /provenance/:content_type/:id/rerun.
+    pub fn with_text_pipeline(mut self, text_pipeline: Option<Arc<TextPipeline>>) -> Self {
+        self.text_pipeline = text_pipeline;
+        self
+    }
+
+    /// Manages the extensions at /extensions.
+    pub fn with_extensions(mut self, extensions: Option<Arc<ExtensionManager>>) -> Self {
+        self.extensions = extensions;
+        self
+    }
+
+    /// Has the llm write the overview of the summaries at /summary, a
+    /// template is used without it.
+    pub fn with_summary_llm(mut self, summary_llm: Option<Arc<SummaryLlm>>) -> Self {
+        self.summary_llm = summary_llm;
+        self
+    }
+
+    /// Searches the peers too at /search/peers, and answers their searches
+    /// on `peer_addr`, which serves nothing else.
+    pub fn with_peers(mut self, peers: Option<Arc<Peers>>, peer_addr: SocketAddr) -> Self {
+        self.peers = peers;
+        self.peer_addr = Some(peer_addr);
+        self
+    }
+
+    pub async fn start(self, enable_frame_cache: bool) -> Result<(), std::io::Error> {
+        let app_state = self.app_state(enable_frame_cache).await;
+        if let (Some(peers), Some(peer_addr)) = (self.peers.clone(), self.peer_addr) {
+            let listener = TcpListener::bind(&peer_addr).await?;
+            info!("Answering peers on {}", peer_addr);
+            let peer_router = peer_router(app_state.clone());
+            tokio::spawn(async move {
+                if let Err(e) = peers.serve(listener, peer_router).await {
+                    error!("peer listener stopped: {}", e);
+                }
+            });
+        }
+
+        // frames captured after a tab was pushed are linked once they are in
+        let db = self.db.clone();
+        tokio::spawn(async move {
+            let max_gap = chrono::Duration::seconds(BROWSER_CONTEXT_MAX_GAP_SECS);
+            loop {
+                tokio::time::sleep(Duration::from_secs(BROWSER_CONTEXT_MAX_GAP_SECS as u64)).await;
+                if let Err(e) = db
+                    .link_recent_browser_contexts(Utc::now() - max_gap * 3, max_gap)
+                    .await
+                {
+                    error!("failed to link browser contexts to frames: {}", e);
+                }
+            }
+        });
+
+        // Create the OpenAPI server
+        let app = self.router(app_state);
+
+        // Create the listener
+        let listener = TcpListener::bind(&self.addr).await?;
+        info!("Server listening on {}", self.addr);
+
+        // Start serving
+        serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+
+        Ok(())
+    }
+
+    pub async fn create_router(&self, enable_frame_cache: bool) -> Router {
+        let app_state = self.app_state(enable_frame_cache).await;
+        self.router(app_state)
+    }
+
+    async fn app_state(&self, enable_frame_cache: bool) -> Arc<AppState> {
+        Arc::new(AppState {
+            db: self.db.clone(),
+            audio_manager: self.audio_manager.clone(),
+            app_start_time: Utc::now(),
+            screenpipe_dir: self.screenpipe_dir.clone(),
+            pipe_manager: self.pipe_manager.clone(),
+            vision_disabled: self.vision_disabled,
+            audio_disabled: self.audio_disabled,
+            ui_monitoring_enabled: self.ui_monitoring_enabled,
+            frame_cache: if enable_frame_cache {
+                Some(Arc::new(
+                    FrameCache::new(self.screenpipe_dir.clone().join("data"), self.db.clone())
+                        .await
+                        .unwrap(),
+                ))
+            } else {
+                None
+            },
+            frame_image_cache: if enable_frame_cache {
+                Some(Arc::new(Mutex::new(LruCache::new(
+                    NonZeroUsize::new(100).unwrap(),
+                ))))
+            } else {
+                None
+            },
+            ocr_engine: self.ocr_engine.clone(),
+            languages: self.languages.clone(),
+            ignored_windows: self.ignored_windows.clone(),
+            included_windows: self.included_windows.clone(),
+            trash_days: self.trash_days,
+            click_tracking: self.click_tracking,
+            shadow: self
+                .shadow_search
+                .map(|config| Arc::new(ShadowRunner::new(config))),
+            maintenance: self.maintenance.clone(),
+            storage_quota: self.storage_quota.clone(),
+            capture_pauses: self.capture_pauses.clone(),
+            secret_masker: self.secret_masker.clone(),
+            plugins: self.plugins.clone(),
+            text_pipeline: self.text_pipeline.clone(),
+            extensions: self.extensions.clone(),
+            summary_llm: self.summary_llm.clone(),
+            peers: self.peers.clone(),
+        })
+    }
+
+    /// The router of the peer listener, see `with_peers`.
+    pub async fn create_peer_router(&self) -> Router {
+        peer_router(self.app_state(false).await)
+    }
+
+    fn router(&self, app_state: Arc<AppState>) -> Router {
+        let cors = CorsLayer::new()
+            .allow_origin(Any)
+            .allow_methods(Any)
+            .allow_headers(Any)
+            .expose_headers([
+                axum::http::header::CONTENT_TYPE,
+                axum::http::header::CACHE_CONTROL,
+            ]);
+        let server = api_server()
+            .route_yaml_spec("/openapi.yaml")
+            .route_json_spec("/openapi.json")
+            .freeze();
+
+        // Build the main router with all routes
+        let router = Router::new()
+            .merge(server.into_router())
+            // NOTE: websockerts and sse is not supported by openapi so we move it down here
+            .route("/stream/frames", get(stream_frames_handler))
+            .route("/ws/events", get(ws_events_handler))
+            .route("/search/live", get(live_search_handler))
+            .route("/search/tag", post(tag_search_results_handler))
+            .route("/search/peers", get(peer_search_handler))
+            .route("/ws/health", get(ws_health_handler))
+            .route("/frames/export", get(handle_video_export_ws))
+            // streamed, one json item per line
+            .route("/search/export", get(export_jsonl_handler))
+            .route("/usage/export", get(usage_export_handler))
+            // markdown, not json
+            .route("/journal", get(journal_handler))
+            // icalendar, for calendar apps to subscribe to
+            .route("/calendar/meetings.ics", get(calendar_handler))
+            // markdown, not json
+            .route("/meetings/:meeting_id/export", get(meeting_export_handler))
+            // inside auth, which tells who the caller is
+            .layer(middleware::from_fn(scope_audit_actor));
+        // graphql has its own schema, at /graphql rather than in the openapi spec
+        #[cfg(feature = "graphql")]
+        let router = router.route(
+            "/graphql",
+            get(crate::graphql::graphiql_handler).post(crate::graphql::graphql_handler),
+        );
+        // inside auth, it drives the keyboard and mouse
+        #[cfg(feature = "experimental")]
+        let router = router.route("/experimental/input_control", post(input_control_handler));
+        // inside auth, a token gets a budget of its own once verified
+        let router = if self.rate_limits.is_empty() {
+            router
+        } else {
+            router.layer(middleware::from_fn_with_state(
+                RateLimiter::new(self.rate_limits.clone()),
+                rate_limit,
+            ))
+        };
+        let router = if self.api_auth {
+            router.layer(middleware::from_fn_with_state(
+                app_state.clone(),
+                require_token,
+            ))
+        } else {
+            router
+        };
+
+        router
+            .with_state(app_state)
+            .layer(cors)
+            .layer(TraceLayer::new_for_http().make_span_with(DefaultMakeSpan::default()))
+    }
+}
+
+#[oasgen]
+async fn merge_frames_handler(
+    State(state): State<Arc<AppState>>,
+    JsonResponse(payload): JsonResponse<MergeVideosRequest>,
+) -> Result<JsonResponse<MergeVideosResponse>, (StatusCode, JsonResponse<Value>)> {
+    let output_dir = state.screenpipe_dir.join("videos");
+
+    match merge_videos(payload, output_dir).await {
+        Ok(response) => Ok(JsonResponse(response)),
+        Err(e) => {
+            error!("Failed to merge frames: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    }
+}
+
+#[oasgen]
+async fn validate_media_handler(
+    State(_state): State<Arc<AppState>>,
+    Query(params): Query<ValidateMediaParams>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    match validate_media(&params.file_path).await {
+        Ok(_) => Ok(Json(json!({"status": "valid media file"}))),
+        Err(e) => Err((
+            StatusCode::EXPECTATION_FAILED,
+            Json(json!({"status": e.to_string()})),
+        )),
+    }
+}
+
+#[derive(OaSchema, Deserialize)]
+struct RawSqlQuery {
+    query: String,
+}
+
+#[oasgen]
+async fn execute_raw_sql(
+    State(state): State<Arc<AppState>>,
+    JsonResponse(payload): JsonResponse<RawSqlQuery>,
+) -> Result<JsonResponse<serde_json::Value>, (StatusCode, JsonResponse<serde_json::Value>)> {
+    match state.db.execute_raw_sql_counted(&payload.query).await {
+        Ok((result, rows_affected)) => {
+            audit(&state.db, "raw_sql", &payload.query, Some(rows_affected)).await;
+            Ok(JsonResponse(result))
+        }
+        Err(e) => {
+            error!("Failed to execute raw SQL query: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    }
+}
+
+#[derive(OaSchema, Deserialize)]
+pub struct AddContentRequest {
+    pub device_name: String,     // Moved device_name to the top level
+    pub content: AddContentData, // The actual content (either Frame or Transcription)
+}
+
+#[derive(OaSchema, Deserialize)]
+pub struct AddContentData {
+    pub content_type: String,
+    pub data: ContentData,
+}
+
+#[derive(OaSchema, Deserialize)]
+#[serde(untagged)]
+pub enum ContentData {
+    Frames(Vec<FrameContent>),
+    Transcription(AudioTranscription),
+}
+
+#[derive(OaSchema, Deserialize)]
+pub struct FrameContent {
+    pub file_path: String,
+    pub timestamp: Option<DateTime<Utc>>,
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
+    pub ocr_results: Option<Vec<OCRResult>>,
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Serialize, OaSchema, Deserialize, Debug)]
+pub struct OCRResult {
+    pub text: String,
+    pub text_json: Option<String>,
+    pub ocr_engine: Option<String>,
+    pub focused: Option<bool>,
+}
+
+#[derive(OaSchema, Deserialize)]
+pub struct AudioTranscription {
+    pub transcription: String,
+    pub transcription_engine: String,
+}
+
+#[derive(OaSchema, Serialize)]
+pub struct AddContentResponse {
+    pub success: bool,
+    pub message: Option<String>,
+}
+
+// ingested frames carrying this tag are photos of a whiteboard and get
+// enhanced before OCR
+const WHITEBOARD_TAG: &str = "whiteboard";
+
+async fn add_frame_to_db(
+    state: &AppState,
+    frame: &FrameContent,
+    device_name: &str,
+    clock_offset_ms: f64,
+) -> Result<(), anyhow::Error> {
+    let db = &state.db;
+
+    let timestamp = frame
+        .timestamp
+        .map(|timestamp| to_server_time(timestamp, clock_offset_ms))
+        .unwrap_or_else(Utc::now);
+    let frame_id = db
+        .insert_frame(
+            device_name,
+            Some(timestamp),
+            None,
+            frame.app_name.as_deref(),
+            frame.window_name.as_deref(),
+            false,
+        )
+        .await?;
+    // the capture blocklist keeps the frame out, its text too
+    if frame_id == 0 {
+        debug!("frame {} not stored", frame.file_path);
+        return Ok(());
+    }
+
+    let is_whiteboard = frame.tags.as_ref().is_some_and(|tags| {
+        tags.iter()
+            .any(|tag| tag.eq_ignore_ascii_case(WHITEBOARD_TAG))
+    });
+    let enhanced = if is_whiteboard {
+        match store_whiteboard_versions(state, frame_id, &frame.file_path).await {
+            Ok(image) => Some(image),
+            Err(e) => {
+                error!(
+                    "failed to enhance whiteboard photo {}: {}",
+                    frame.file_path, e
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    if let Some(ocr_results) = &frame.ocr_results {
+        for ocr in ocr_results {
+            db.insert_ocr_text(
+                frame_id,
+                &ocr.text,
+                ocr.text_json.as_deref().unwrap_or(""),
+                Arc::new(OcrEngine::default().into()), // Ideally could pass any str as ocr_engine since can be run outside of screenpipe
+            )
+            .await?;
+        }
+    } else if let Some(image) = enhanced {
+        let (text, text_json, _) =
+            perform_ocr_with_engine(&state.ocr_engine, &image, state.languages.clone())
+                .await
+                .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        db.insert_ocr_text(
+            frame_id,
+            &text,
+            &text_json,
+            Arc::new((*state.ocr_engine).clone().into()),
+        )
+        .await?;
+    }
+
+    if let Some(tags) = &frame.tags {
+        db.add_tags(frame_id, TagContentType::Vision, tags.clone())
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Copies an ingested whiteboard photo into the data dir next to a
+/// straightened, contrast-cleaned version and returns the latter for OCR.
+async fn store_whiteboard_versions(
+    state: &AppState,
+    frame_id: i64,
+    file_path: &str,
+) -> Result<image::DynamicImage, anyhow::Error> {
+    let output_dir = state.screenpipe_dir.join("data");
+    let extension = std::path::Path::new(file_path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("png");
+    let original_path = output_dir.join(format!("whiteboard_{}_original.{}", frame_id, extension));
+    let enhanced_path = output_dir.join(format!("whiteboard_{}_enhanced.png", frame_id));
+    tokio::fs::copy(file_path, &original_path).await?;
+
+    let source = original_path.clone();
+    let destination = enhanced_path.clone();
+    let enhanced = tokio::task::spawn_blocking(move || -> Result<_, anyhow::Error> {
+        let enhanced = enhance_whiteboard(&image::open(&source)?);
+        enhanced.save_with_format(&destination, ImageFormat::Png)?;
+        Ok(enhanced)
+    })
+    .await??;
+
+    state
+        .db
+        .insert_whiteboard_image(
+            frame_id,
+            &original_path.to_string_lossy(),
+            &enhanced_path.to_string_lossy(),
+        )
+        .await?;
+    Ok(enhanced)
+}
+
+fn encode_frame_from_file_path(file_path: &str) -> Result<Vec<u8>, anyhow::Error> {
+    let image = image::open(file_path)?;
+    let mut buffer = Vec::new();
+    image.write_to(&mut std::io::Cursor::new(&mut buffer), ImageFormat::Png)?;
+    Ok(buffer)
+}
+
+async fn write_frames_to_video(
+    frames: &Vec<FrameContent>,
+    video_file_path: &str,
+    fps: f64,
+) -> Result<(), anyhow::Error> {
+    let mut ffmpeg_child =
+        start_ffmpeg_process(video_file_path, fps, &VideoEncoding::default()).await?;
+    let mut ffmpeg_stdin = ffmpeg_child
+        .stdin
+        .take()
+        .expect("Failed to open stdin for FFmpeg");
+
+    for frame in frames {
+        let encoded_frame = encode_frame_from_file_path(&frame.file_path)?;
+        if let Err(e) = write_frame_to_ffmpeg(&mut ffmpeg_stdin, &encoded_frame).await {
+            error!("Failed to write frame to FFmpeg: {}", e);
+            return Err(e);
+        }
+    }
+
+    finish_ffmpeg_process(ffmpeg_child, Some(ffmpeg_stdin)).await;
+    Ok(())
+}
+
+async fn add_transcription_to_db(
+    state: &AppState,
+    transcription: &AudioTranscription,
+    device_name: &str,
+) -> Result<(), anyhow::Error> {
+    let db = &state.db;
+
+    let dummy_audio_chunk_id = db.insert_audio_chunk("").await?;
+
+    db.insert_audio_transcription(
+        dummy_audio_chunk_id, // No associated audio chunk
+        &transcription.transcription,
+        -1,
+        &transcription.transcription_engine,
+        &screenpipe_db::AudioDevice {
+            name: device_name.to_string(),
+            device_type: DeviceType::Input.into(),
+        },
+        None,
+        None,
+        None,
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[oasgen]
+pub(crate) async fn add_to_database(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<AddContentRequest>,
+) -> Result<Json<AddContentResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let device_name = payload.device_name.clone();
+    let mut success_messages = Vec::new();
+
+    match payload.content.content_type.as_str() {
+        "frames" => {
+            if let ContentData::Frames(frames) = &payload.content.data {
+                if !frames.is_empty() {
+                    let output_dir = state.screenpipe_dir.join("data");
+                    let time = Utc::now();
+                    let formatted_time = time.format("%Y-%m-%d_%H-%M-%S").to_string();
+                    let video_file_path = output_dir
+                        .join(format!("{}_{}.mp4", device_name, formatted_time))
+                        .to_str()
+                        .expect("Failed to create valid path")
+                        .to_string();
+
+                    if let Err(e) = state
+                        .db
+                        .insert_video_chunk_with_codec(
+                            &video_file_path,
+                            &device_name,
+                            Some(VideoEncoding::default().codec.codec_name()),
+                        )
+                        .await
+                    {
+                        error!(
+                            "Failed to insert video chunk for device {}: {}",
+                            device_name, e
+                        );
+                        return Err((
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            JsonResponse(
+                                json!({"error": format!("Failed to insert video chunk: {}", e)}),
+                            ),
+                        ));
+                    }
+
+                    if let Err(e) = write_frames_to_video(frames, &video_file_path, MAX_FPS).await {
+                        error!(
+                            "Failed to write frames to video file {}: {}",
+                            video_file_path, e
+                        );
+                        return Err((
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            JsonResponse(
+                                json!({"error": format!("Failed to write frames to video: {}", e)}),
+                            ),
+                        ));
+                    }
+
+                    // timestamps come from the agent's clock
+                    let clock_offset_ms = match state.db.get_clock_offset(&device_name).await {
+                        Ok(offset) => offset.map_or(0.0, |offset| offset.offset_ms),
+                        Err(e) => {
+                            error!("failed to get clock offset of {}: {}", device_name, e);
+                            0.0
+                        }
+                    };
+                    for frame in frames {
+                        if let Err(e) =
+                            add_frame_to_db(&state, frame, &device_name, clock_offset_ms).await
+                        {
+                            error!(
+                                "Failed to add frame content for device {}: {}",
+                                device_name, e
+                            );
+                        }
+                    }
+
+                    success_messages.push("Frames added successfully".to_string());
+                }
+            }
+        }
+        "transcription" => {
+            if let ContentData::Transcription(transcription) = &payload.content.data {
+                if let Err(e) = add_transcription_to_db(&state, transcription, &device_name).await {
+                    error!(
+                        "Failed to add transcription for device {}: {}",
+                        device_name, e
+                    );
+                    return Err((
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        JsonResponse(
+                            json!({"error": format!("Failed to add transcription: {}", e)}),
+                        ),
+                    ));
+                }
+
+                success_messages.push("Transcription added successfully".to_string());
+            }
+        }
+        _ => {
+            error!("Unknown content type: {}", payload.content.content_type);
+            return Err((
+                StatusCode::BAD_REQUEST,
+                JsonResponse(json!({"error": "Unsupported content type"})),
+            ));
+        }
+    }
+
+    Ok(JsonResponse(AddContentResponse {
+        success: true,
+        message: Some(success_messages.join(", ")),
+    }))
+}
+
+// frames this close to a tab reported by the browser extension may show it
+const BROWSER_CONTEXT_MAX_GAP_SECS: i64 = 10;
+// selections longer than this, in characters, are cut
+const MAX_BROWSER_SELECTION_CHARS: usize = 10_000;
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct BrowserContextRequest {
+    url: String,
+    #[serde(default)]
+    title: Option<String>,
+    /// text selected in the tab
+    #[serde(default)]
+    selection_text: Option<String>,
+    /// e.g. "chrome"
+    #[serde(default)]
+    browser: Option<String>,
+    /// when the tab was shown, defaults to now
+    #[serde(default)]
+    timestamp: Option<DateTime<Utc>>,
+}
+
+#[derive(OaSchema, Serialize)]
+pub(crate) struct BrowserContextResponse {
+    /// none when the capture blocklist keeps the tab out
+    id: Option<i64>,
+    /// frames linked to the tab so far
+    frames: u64,
+}
+
+/// Takes the active tab from the companion browser extension, on each
+/// switch of tab or selection. It is linked to the frames of the browser
+/// captured around it, its title and selection are searched with their OCR
+/// text and the frames without a url get the tab's. Frames captured after
+/// the request are linked once they are in. Tabs are dropped while vision is
+/// paused or when their url or title is in the ignored windows, and secrets
+/// in the url and selection are masked with `--mask-secrets`.
+#[oasgen]
+async fn browser_context_handler(
+    State(state): State<Arc<AppState>>,
+    JsonResponse(request): JsonResponse<BrowserContextRequest>,
+) -> Result<JsonResponse<BrowserContextResponse>, (StatusCode, JsonResponse<Value>)> {
+    let url = request.url.trim();
+    if url.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "url is required"})),
+        ));
+    }
+    let internal_error = |e: sqlx::Error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )
+    };
+
+    let kept_out = || {
+        JsonResponse(BrowserContextResponse {
+            id: None,
+            frames: 0,
+        })
+    };
+    if state
+        .capture_pauses
+        .as_ref()
+        .is_some_and(|pauses| pauses.vision_paused())
+    {
+        return Ok(kept_out());
+    }
+    let title = request.title.as_deref().map(str::trim);
+    let ignored = |text: &str| {
+        let text = text.to_lowercase();
+        state
+            .ignored_windows
+            .iter()
+            .any(|ignored| text.contains(&ignored.to_lowercase()))
+    };
+    if ignored(url) || title.is_some_and(ignored) {
+        return Ok(kept_out());
+    }
+
+    let mask = |text: &str| match &state.secret_masker {
+        Some(masker) => masker.mask(text),
+        None => text.to_string(),
+    };
+    let url = mask(url);
+    let selection_text: Option<String> = request.selection_text.map(|text| {
+        mask(
+            &text
+                .chars()
+                .take(MAX_BROWSER_SELECTION_CHARS)
+                .collect::<String>(),
+        )
+    });
+    let id = state
+        .db
+        .insert_browser_context(
+            request.timestamp.unwrap_or_else(Utc::now),
+            &url,
+            title,
+            selection_text.as_deref(),
+            request.browser.as_deref(),
+        )
+        .await
+        .map_err(internal_error)?;
+    let Some(id) = id else {
+        return Ok(kept_out());
+    };
+
+    let max_gap = chrono::Duration::seconds(BROWSER_CONTEXT_MAX_GAP_SECS);
+    let frames = state
+        .db
+        .link_browser_context(id, max_gap)
+        .await
+        .map_err(internal_error)?;
+
+    Ok(JsonResponse(BrowserContextResponse {
+        id: Some(id),
+        frames,
+    }))
+}
+
+#[cfg(feature = "experimental")]
+async fn input_control_handler(
+    JsonResponse(payload): JsonResponse<InputControlRequest>,
+) -> Result<JsonResponse<InputControlResponse>, (StatusCode, JsonResponse<Value>)> {
+    use enigo::{Keyboard, Mouse};
+
+    info!("input control handler {:?}", payload);
+    let mut enigo = Enigo::new(&Settings::default()).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": format!("failed to initialize enigo: {}", e)})),
+        )
+    })?;
+
+    match payload.action {
+        InputAction::KeyPress(key) => {
+            let _ = enigo.key(key_from_string(&key).unwrap(), enigo::Direction::Press);
+        }
+        InputAction::MouseMove { x, y } => {
+            let _ = enigo.move_mouse(x, y, enigo::Coordinate::Abs);
+        }
+        InputAction::MouseClick(button) => {
+            let _ = enigo.button(
+                mouse_button_from_string(&button).unwrap(),
+                enigo::Direction::Press,
+            );
+        }
+        InputAction::WriteText(text) => {
+            let _ = enigo.text(&text);
+        }
+    }
+
+    Ok(JsonResponse(InputControlResponse { success: true }))
+}
+
+#[cfg(feature = "experimental")]
+fn key_from_string(key: &str) -> Result<Key, (StatusCode, JsonResponse<Value>)> {
+    match key {
+        "enter" => Ok(Key::Return),
+        "space" => Ok(Key::Space),
+        // Add more key mappings as needed
+        _ => Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": format!("Unsupported key: {}", key)})),
+        )),
+    }
+}
+
+#[cfg(feature = "experimental")]
+fn mouse_button_from_string(
+    button: &str,
+) -> Result<enigo::Button, (StatusCode, JsonResponse<Value>)> {
+    match button {
+        "left" => Ok(enigo::Button::Left),
+        "right" => Ok(enigo::Button::Right),
+        // Add more button mappings as needed
+        _ => Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": format!("Unsupported mouse button: {}", button)})),
+        )),
+    }
+}
+
+// Add these new structs:
+#[cfg(feature = "experimental")]
+#[derive(Deserialize, Debug)]
+struct InputControlRequest {
+    action: InputAction,
+}
+
+#[cfg(feature = "experimental")]
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", content = "data")]
+enum InputAction {
+    KeyPress(String),
+    MouseMove { x: i32, y: i32 },
+    MouseClick(String),
+    WriteText(String),
+}
+
+#[cfg(feature = "experimental")]
+#[derive(Serialize)]
+struct InputControlResponse {
+    success: bool,
+}
+
+// Add this new struct
+#[derive(Deserialize)]
+pub struct StreamFramesRequest {
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+    #[serde(rename = "order")]
+    #[serde(default = "Order::default")]
+    order: Order,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StreamTimeSeriesResponse {
+    pub timestamp: DateTime<Utc>,
+    pub devices: Vec<DeviceFrameResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeviceFrameResponse {
+    pub device_id: String,
+    // pub frame: String, // base64 encoded image
+    pub frame_id: i64,
+    pub metadata: DeviceMetadata,
+    pub audio: Vec<AudioData>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeviceMetadata {
+    pub file_path: String,
+    pub app_name: String,
+    pub window_name: String,
+    pub ocr_text: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AudioData {
+    pub device_name: String,
+    pub is_input: bool,
+    pub transcription: String,
+    pub audio_file_path: String,
+    pub duration_secs: f64,
+    pub start_offset: f64, // offset from frame timestamp
+}
+
+impl From<TimeSeriesFrame> for StreamTimeSeriesResponse {
+    fn from(frame: TimeSeriesFrame) -> Self {
+        StreamTimeSeriesResponse {
+            timestamp: frame.timestamp,
+            devices: frame
+                .frame_data
+                .into_iter()
+                .map(|device_frame| {
+                    DeviceFrameResponse {
+                        device_id: device_frame.device_id,
+                        // frame: BASE64_STANDARD.encode(&device_frame.image_data),
+                        frame_id: device_frame.frame_id,
+                        metadata: DeviceMetadata {
+                            file_path: device_frame.metadata.file_path,
+                            app_name: device_frame.metadata.app_name,
+                            window_name: device_frame.metadata.window_name,
+                            ocr_text: device_frame.metadata.ocr_text,
+                        },
+                        audio: device_frame
+                            .audio_entries
+                            .into_iter()
+                            .map(|audio| {
+                                AudioData {
+                                    device_name: audio.device_name,
+                                    is_input: audio.is_input,
+                                    transcription: audio.transcription,
+                                    audio_file_path: audio.audio_file_path,
+                                    duration_secs: audio.duration_secs,
+                                    start_offset: 0.0, // calculate based on audio timestamp vs frame timestamp
+                                }
+                            })
+                            .collect(),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(OaSchema, Deserialize, Debug)]
+pub struct GetUnnamedSpeakersRequest {
+    limit: u32,
+    offset: u32,
+    // comma separated list of speaker ids to include
+    #[serde(
+        deserialize_with = "from_comma_separated_array",
+        default = "default_speaker_ids"
+    )]
+    speaker_ids: Option<Vec<i64>>,
+}
+
+fn default_speaker_ids() -> Option<Vec<i64>> {
+    None
+}
+
+#[derive(OaSchema, Deserialize, Debug)]
+pub struct GetSimilarSpeakersRequest {
+    speaker_id: i64,
+    limit: u32,
+}
+
+fn from_comma_separated_array<'de, D>(deserializer: D) -> Result<Option<Vec<i64>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = Option::<String>::deserialize(deserializer).unwrap_or(None);
+    let s = match s {
+        None => return Ok(None),
+        Some(s) => s,
+    };
+    s.split(',')
+        .map(|i| i64::from_str(i).map_err(serde::de::Error::custom))
+        .collect::<Result<Vec<_>, _>>()
+        .map(Some)
+}
+
+#[oasgen]
+async fn get_unnamed_speakers_handler(
+    State(state): State<Arc<AppState>>,
+    Query(request): Query<GetUnnamedSpeakersRequest>,
+) -> Result<JsonResponse<Vec<Speaker>>, (StatusCode, JsonResponse<Value>)> {
+    let speakers = state
+        .db
+        .get_unnamed_speakers(request.limit, request.offset, request.speaker_ids)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })?;
+
+    // convert metadata to json
+    let speakers = speakers
+        .into_iter()
+        .map(|speaker| {
+            let mut metadata: Value = serde_json::from_str(&speaker.metadata).unwrap();
+            if let Some(audio_samples) = metadata.get("audio_samples").and_then(|v| v.as_array()) {
+                metadata["audio_samples"] = serde_json::to_value(audio_samples).unwrap();
+            }
+            Speaker {
+                metadata: metadata.to_string(),
+                ..speaker
+            }
+        })
+        .collect();
+
+    Ok(JsonResponse(speakers))
+}
+
+#[oasgen]
+async fn update_speaker_handler(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<UpdateSpeakerRequest>,
+) -> Result<JsonResponse<Speaker>, (StatusCode, JsonResponse<Value>)> {
+    let speaker_id = payload.id;
+
+    if let Some(name) = payload.name {
+        if let Err(e) = state.db.update_speaker_name(speaker_id, &name).await {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ));
+        }
+    }
+
+    if let Some(metadata) = payload.metadata {
+        if let Err(e) = state
+            .db
+            .update_speaker_metadata(speaker_id, &metadata)
+            .await
+        {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ));
+        }
+    }
+
+    Ok(JsonResponse(
+        state.db.get_speaker_by_id(speaker_id).await.unwrap(),
+    ))
+}
+
+#[oasgen]
+async fn search_speakers_handler(
+    State(state): State<Arc<AppState>>,
+    Query(request): Query<SearchSpeakersRequest>,
+) -> Result<JsonResponse<Vec<Speaker>>, (StatusCode, JsonResponse<Value>)> {
+    let search_prefix = request.name.unwrap_or_default();
+    Ok(JsonResponse(
+        state.db.search_speakers(&search_prefix).await.unwrap(),
+    ))
+}
+
+#[oasgen]
+async fn delete_speaker_handler(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<DeleteSpeakerRequest>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    // get audio_chunks for this speaker
+    let audio_chunks = state
+        .db
+        .get_audio_chunks_for_speaker(payload.id)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })?;
+
+    state.db.delete_speaker(payload.id).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )
+    })?;
+    audit(
+        &state.db,
+        "delete_speaker",
+        &format!("speaker:{}", payload.id),
+        None,
+    )
+    .await;
+
+    // delete all audio chunks from the file system
+    for audio_chunk in audio_chunks {
+        if audio_chunk.start_time.is_some() && audio_chunk.end_time.is_some() {
+            std::fs::remove_file(audio_chunk.file_path).map_err(|e| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    JsonResponse(json!({"error": e.to_string()})),
+                )
+            })?;
+        }
+    }
+
+    Ok(JsonResponse(json!({"success": true})))
+}
+
+#[oasgen]
+async fn mark_as_hallucination_handler(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<MarkAsHallucinationRequest>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    let speaker_id = payload.speaker_id;
+
+    state
+        .db
+        .mark_speaker_as_hallucination(speaker_id)
+        .await
+        .unwrap();
+
+    Ok(JsonResponse(json!({"success": true})))
+}
+
+#[oasgen]
+async fn merge_speakers_handler(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<MergeSpeakersRequest>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    let speaker_to_keep_id = payload.speaker_to_keep_id;
+    let speaker_to_merge_id = payload.speaker_to_merge_id;
+
+    state
+        .db
+        .merge_speakers(speaker_to_keep_id, speaker_to_merge_id)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string(), "speaker_to_keep_id": speaker_to_keep_id, "speaker_to_merge_id": speaker_to_merge_id})),
+            )
+        })?;
+    audit(
+        &state.db,
+        "merge_speakers",
+        &format!(
+            "speaker:{} into speaker:{}",
+            speaker_to_merge_id, speaker_to_keep_id
+        ),
+        None,
+    )
+    .await;
+
+    Ok(JsonResponse(json!({"success": true})))
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct CompactSpeakersRequest {
+    #[serde(default = "default_max_embeddings_per_speaker")]
+    max_embeddings_per_speaker: usize,
+    /// also give the freed space back to the disk, slow on large databases
+    #[serde(default)]
+    vacuum: bool,
+}
+
+fn default_max_embeddings_per_speaker() -> usize {
+    20
+}
+
+/// Drops the embeddings of deleted speakers and caps the others to their most
+/// representative ones, so matching new audio to speakers stays fast.
+#[oasgen]
+async fn compact_speakers_handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<CompactSpeakersRequest>,
+) -> Result<JsonResponse<SpeakerCompaction>, (StatusCode, JsonResponse<Value>)> {
+    if request.max_embeddings_per_speaker == 0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "max_embeddings_per_speaker must be at least 1"})),
+        ));
+    }
+    let internal_error = |e: sqlx::Error| {
+        error!("failed to compact speakers: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )
+    };
+
+    let compaction = state
+        .db
+        .compact_speakers(request.max_embeddings_per_speaker)
+        .await
+        .map_err(internal_error)?;
+    if request.vacuum {
+        state.db.vacuum().await.map_err(internal_error)?;
+    }
+    Ok(JsonResponse(compaction))
+}
+
+#[oasgen]
+async fn get_similar_speakers_handler(
+    State(state): State<Arc<AppState>>,
+    Query(request): Query<GetSimilarSpeakersRequest>,
+) -> Result<JsonResponse<Vec<Speaker>>, (StatusCode, JsonResponse<Value>)> {
+    let speaker_id = request.speaker_id;
+    let limit = request.limit;
+
+    let similar_speakers = state
+        .db
+        .get_similar_speakers(speaker_id, limit)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })?;
+
+    Ok(JsonResponse(similar_speakers))
+}
+// #[derive(OaSchema, Deserialize)]
+// pub struct AudioDeviceControlRequest {
+//     device_name: String,
+//     #[serde(default)]
+//     device_type: Option<DeviceType>,
+// }
+
+#[derive(Debug, OaSchema, Serialize)]
+pub struct AudioDeviceControlResponse {
+    success: bool,
+    message: String,
+}
+
+// Add these new handler functions before create_router()
+// async fn start_audio_device(
+//     State(state): State<Arc<AppState>>,
+//     Json(payload): Json<AudioDeviceControlRequest>,
+// ) -> Result<JsonResponse<AudioDeviceControlResponse>, (StatusCode, JsonResponse<Value>)> {
+//     let device = AudioDevice {
+//         name: payload.device_name.clone(),
+//         device_type: payload.device_type.unwrap_or(AudioDeviceType::Input),
+//     };
+
+//     // Validate device exists
+//     let available_devices = list_audio_devices().await.map_err(|e| {
+//         (
+//             StatusCode::INTERNAL_SERVER_ERROR,
+//             JsonResponse(json!({
+//                 "error": format!("failed to list audio devices: {}", e),
+//                 "success": false
+//             })),
+//         )
+//     })?;
+
+//     if !available_devices.contains(&device) {
+//         return Err((
+//             StatusCode::BAD_REQUEST,
+//             JsonResponse(json!({
+//                 "error": format!("device not found: {}", device.name),
+//                 "success": false
+//             })),
+//         ));
+//     }
+
+//     let control = DeviceControl {
+//         device: screenpipe_core::DeviceType::Audio(device.clone()),
+//         is_running: true,
+//         is_paused: false,
+//     };
+
+//     let _ = state.device_manager.update_device(control).await;
+
+//     Ok(JsonResponse(AudioDeviceControlResponse {
+//         success: true,
+//         message: format!("started audio device: {}", device.name),
+//     }))
+// }
+
+// async fn stop_audio_device(
+//     State(state): State<Arc<AppState>>,
+//     Json(payload): Json<AudioDeviceControlRequest>,
+// ) -> Result<JsonResponse<AudioDeviceControlResponse>, (StatusCode, JsonResponse<Value>)> {
+//     let device = AudioDevice {
+//         name: payload.device_name.clone(),
+//         device_type: payload.device_type.unwrap_or(AudioDeviceType::Input),
+//     };
+
+//     // Validate device exists
+//     let available_devices = list_audio_devices().await.map_err(|e| {
+//         (
+//             StatusCode::INTERNAL_SERVER_ERROR,
+//             JsonResponse(json!({
+//                 "error": format!("failed to list audio devices: {}", e),
+//                 "success": false
+//             })),
+//         )
+//     })?;
+
+//     if !available_devices.contains(&device) {
+//         return Err((
+//             StatusCode::BAD_REQUEST,
+//             JsonResponse(json!({
+//                 "error": format!("device not found: {}", device.name),
+//                 "success": false
+//             })),
+//         ));
+//     }
+
+//     let _ = state
+//         .device_manager
+//         .update_device(DeviceControl {
+//             device: screenpipe_core::DeviceType::Audio(device.clone()),
+//             is_running: false,
+//             is_paused: false,
+//         })
+//         .await;
+
+//     Ok(JsonResponse(AudioDeviceControlResponse {
+//         success: true,
+//         message: format!("stopped audio device: {}", device.name),
+//     }))
+// }
+
+#[derive(OaSchema, Deserialize)]
+struct EventsQuery {
+    images: Option<bool>,
+}
+
+#[derive(Debug, OaSchema, Deserialize)]
+struct SemanticSearchQuery {
+    text: String,
+    limit: Option<u32>,
+    threshold: Option<f32>,
+    #[serde(default)]
+    ranking: SearchRanking,
+    #[serde(default = "default_half_life_days")]
+    half_life_days: f64,
+}
+
+#[oasgen]
+async fn semantic_search_handler(
+    Query(query): Query<SemanticSearchQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<Vec<screenpipe_db::OCRResult>>, (StatusCode, JsonResponse<Value>)> {
+    let limit = query.limit.unwrap_or(10);
+    let threshold = query.threshold.unwrap_or(0.3);
+    let half_life = match query.ranking {
+        SearchRanking::Decayed => Some(half_life(query.half_life_days)?),
+        SearchRanking::Standard => None,
+    };
+
+    debug!(
+        "semantic search for '{}' with limit {} and threshold {}",
+        query.text, limit, threshold
+    );
+
+    // Generate embedding for search text
+    let embedding = match generate_embedding(&query.text, 0).await {
+        Ok(emb) => emb,
+        Err(e) => {
+            error!("failed to generate embedding: {}", e);
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("failed to generate embedding: {}", e)})),
+            ));
+        }
+    };
+
+    // Search database for similar embeddings
+    match state
+        .db
+        .search_similar_embeddings(embedding, limit, threshold, half_life)
+        .await
+    {
+        Ok(results) => {
+            debug!("found {} similar results", results.len());
+            Ok(JsonResponse(results))
+        }
+        Err(e) => {
+            error!("failed to search embeddings: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": format!("failed to search embeddings: {}", e)})),
+            ))
+        }
+    }
+}
+
+// embeddings converted per transaction by the background conversion
+const EMBEDDING_CONVERSION_BATCH: u32 = 500;
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct UpdateEmbeddingIndexRequest {
+    quantization: EmbeddingQuantization,
+    #[serde(default = "default_float_cache_size")]
+    float_cache_size: i64,
+}
+
+fn default_float_cache_size() -> i64 {
+    10_000
+}
+
+fn embedding_index_error(e: sqlx::Error) -> (StatusCode, JsonResponse<Value>) {
+    error!("failed to access embedding index: {}", e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        JsonResponse(json!({"error": e.to_string()})),
+    )
+}
+
+fn check_embedding_index(index_name: &str) -> Result<(), (StatusCode, JsonResponse<Value>)> {
+    if index_name != OCR_TEXT_EMBEDDINGS {
+        return Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": format!("unknown embedding index {:?}", index_name)})),
+        ));
+    }
+    Ok(())
+}
+
+#[oasgen]
+async fn get_embedding_index_handler(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+) -> Result<JsonResponse<EmbeddingIndexStatus>, (StatusCode, JsonResponse<Value>)> {
+    check_embedding_index(&index_name)?;
+    let status = state
+        .db
+        .get_ocr_text_embeddings_status()
+        .await
+        .map_err(embedding_index_error)?;
+    Ok(JsonResponse(status))
+}
+
+/// Changes how the index stores its vectors. New embeddings use the new
+/// quantization right away, existing ones are converted in the background.
+#[oasgen]
+async fn update_embedding_index_handler(
+    State(state): State<Arc<AppState>>,
+    Path(index_name): Path<String>,
+    Json(request): Json<UpdateEmbeddingIndexRequest>,
+) -> Result<JsonResponse<EmbeddingIndexStatus>, (StatusCode, JsonResponse<Value>)> {
+    check_embedding_index(&index_name)?;
+    state
+        .db
+        .set_embedding_index_settings(&index_name, request.quantization, request.float_cache_size)
+        .await
+        .map_err(embedding_index_error)?;
+
+    let db = state.db.clone();
+    tokio::spawn(async move {
+        let mut converted = 0;
+        loop {
+            match db
+                .convert_ocr_text_embeddings(EMBEDDING_CONVERSION_BATCH)
+                .await
+            {
+                Ok(0) => break,
+                Ok(n) => converted += n,
+                Err(e) => {
+                    error!("failed to convert embeddings: {}", e);
+                    break;
+                }
+            }
+        }
+        if converted > 0 {
+            info!(
+                "converted {} embeddings to {}",
+                converted, request.quantization
+            );
+        }
+    });
+
+    let status = state
+        .db
+        .get_ocr_text_embeddings_status()
+        .await
+        .map_err(embedding_index_error)?;
+    Ok(JsonResponse(status))
+}
+
+#[derive(Serialize, OaSchema, Deserialize)]
+pub struct VisionDeviceControlRequest {
+    device_id: u32,
+}
+
+// impl VisionDeviceControlRequest {
+//     pub fn new(device_id: u32) -> Self {
+//         Self { device_id }
+//     }
+// }
+
+#[derive(Serialize)]
+pub struct VisionDeviceControlResponse {
+    success: bool,
+    message: String,
+}
+
+// async fn start_vision_device(
+//     State(state): State<Arc<AppState>>,
+//     Json(payload): Json<VisionDeviceControlRequest>,
+// ) -> Result<JsonResponse<VisionDeviceControlResponse>, (StatusCode, JsonResponse<Value>)> {
+//     debug!("starting vision device: {}", payload.device_id);
+//     // Validate device exists
+//     let monitors = list_monitors().await;
+//     if !monitors.iter().any(|m| m.id() == payload.device_id) {
+//         return Err((
+//             StatusCode::BAD_REQUEST,
+//             JsonResponse(json!({
+//                 "error": format!("monitor not found: {}", payload.device_id),
+//                 "success": false
+//             })),
+//         ));
+//     }
+
+//     debug!("starting vision device: {}", payload.device_id);
+//     let _ = state
+//         .device_manager
+//         .update_device(DeviceControl {
+//             device: screenpipe_core::DeviceType::Vision(payload.device_id),
+//             is_running: true,
+//             is_paused: false,
+//         })
+//         .await;
+
+//     Ok(JsonResponse(VisionDeviceControlResponse {
+//         success: true,
+//         message: format!("started vision device: {}", payload.device_id),
+//     }))
+// }
+
+// async fn stop_vision_device(
+//     State(state): State<Arc<AppState>>,
+//     Json(payload): Json<VisionDeviceControlRequest>,
+// ) -> Result<JsonResponse<VisionDeviceControlResponse>, (StatusCode, JsonResponse<Value>)> {
+//     debug!("stopping vision device: {}", payload.device_id);
+//     // Validate device exists
+//     let monitors = list_monitors().await;
+//     if !monitors.iter().any(|m| m.id() == payload.device_id) {
+//         return Err((
+//             StatusCode::BAD_REQUEST,
+//             JsonResponse(json!({
+//                 "error": format!("monitor not found: {}", payload.device_id),
+//                 "success": false
+//             })),
+//         ));
+//     }
+
+//     debug!("stopping vision device: {}", payload.device_id);
+
+//     let _ = state
+//         .device_manager
+//         .update_device(DeviceControl {
+//             device: screenpipe_core::DeviceType::Vision(payload.device_id),
+//             is_running: false,
+//             is_paused: false,
+//         })
+//         .await;
+
+//     Ok(JsonResponse(VisionDeviceControlResponse {
+//         success: true,
+//         message: format!("stopped vision device: {}", payload.device_id),
+//     }))
+// }
+
+// websocket events handler
+async fn ws_events_handler(ws: WebSocketUpgrade, query: Query<EventsQuery>) -> Response {
+    ws.on_upgrade(|socket| handle_socket(socket, query))
+}
+
+async fn handle_socket(socket: WebSocket, query: Query<EventsQuery>) {
+    let (mut sender, mut receiver) = socket.split();
+
+    let incoming = tokio::spawn(async move {
+        while let Some(Ok(msg)) = receiver.next().await {
+            if let Message::Text(t) = msg {
+                if let Ok(event) = serde_json::from_str::<ScreenpipeEvent>(&t) {
+                    let _ = send_event(&event.name, event.data);
+                }
+            }
+        }
+    });
+    // Handle the WebSocket connection here
+    // You can add your logic to handle messages, upgrades, etc.
+
+    let outgoing = tokio::spawn(async move {
+        let mut stream = subscribe_to_all_events();
+        loop {
+            tokio::select! {
+                event = stream.next() => {
+                    if let Some(mut event) = event {
+                        if !query.images.unwrap_or(false) && (event.name == "ocr_result" || event.name == "ui_frame") {
+                            if let Some(data) = event.data.as_object_mut() {
+                                data.remove("image");
+                            }
+                        }
+                        if let Err(e) = sender
+                            .send(Message::Text(
+                                serde_json::to_string(&event).unwrap_or_default(),
+                            ))
+                            .await
+                        {
+                            tracing::error!("Failed to send websocket message: {}", e);
+                            break;
+                        }
+                    }
+                }
+                _ = tokio::time::sleep(Duration::from_secs(1)) => {
+                    let _ = sender.send(Message::Ping(vec![])).await;
+                }
+            }
+        }
+    });
+
+    // Wait for either task to finish
+    tokio::select! {
+        _ = incoming => {}
+        _ = outgoing => {}
+    }
+
+    debug!("WebSocket connection closed");
+}
+
+async fn ws_health_handler(ws: WebSocketUpgrade, State(state): State<Arc<AppState>>) -> Response {
+    ws.on_upgrade(move |socket| handle_health_socket(socket, state))
+}
+
+async fn handle_health_socket(mut socket: WebSocket, state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(Duration::from_secs(5));
+
+    loop {
+        tokio::select! {
+        _ = interval.tick() => {
+            let health_response = health_check(State(state.clone())).await;
+            let health_status = serde_json::to_string(&health_response.0).unwrap_or_default();
+            if let Err(e) = socket.send(Message::Text(health_status)).await {
+                error!("Failed to send health status: {}", e);
+                break;
+            }
+        }
+            result = socket.recv() => {
+                if result.is_none() {
+                    break;
+                }
+            }
+        }
+    }
+
+    debug!("WebSocket connection closed gracefully");
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct VideoExportRequest {
+    #[serde(deserialize_with = "deserialize_frame_ids")]
+    frame_ids: Vec<i64>,
+    fps: f64,
+}
+
+#[derive(OaSchema, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+struct AudioDeviceControlRequest {
+    device_name: String,
+}
+
+#[oasgen]
+async fn start_audio_device(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<AudioDeviceControlRequest>,
+) -> Result<Json<AudioDeviceControlResponse>, (StatusCode, JsonResponse<Value>)> {
+    let device_name = payload.device_name.clone();
+    let device: AudioDevice;
+    // todo Better handling error
+    match AudioDevice::from_name(&payload.device_name) {
+        Ok(audio_device) => device = audio_device,
+        Err(e) => {
+            return Err((
+                StatusCode::UNPROCESSABLE_ENTITY,
+                JsonResponse(
+                    json!({"success": false, "message": format!("device {} not found: {}", device_name.clone(), e)}),
+                ),
+            ))
+        }
+    };
+
+    if let Err(e) = state.audio_manager.start_device(&device).await {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({
+                "success": false,
+                "message": format!("Failed to start recording device {}: {}", device_name.clone(), e)
+            })),
+        ));
+    }
+
+    Ok(Json(AudioDeviceControlResponse {
+        success: true,
+        message: format!("started device: {}", device_name),
+    }))
+}
+
+#[oasgen]
+async fn stop_audio_device(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<AudioDeviceControlRequest>,
+) -> Result<Json<AudioDeviceControlResponse>, (StatusCode, JsonResponse<Value>)> {
+    let device_name = payload.device_name.clone();
+
+    if let Err(e) = state.audio_manager.stop_device(&device_name).await {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({
+                "success": false,
+                "message": format!("Failed to stop recording device {}: {}", device_name.clone(), e)
+            })),
+        ));
+    }
+
+    Ok(Json(AudioDeviceControlResponse {
+        success: true,
+        message: format!("stopped recording audio device: {}", device_name),
+    }))
+}
+
+fn deserialize_frame_ids<'de, D>(deserializer: D) -> Result<Vec<i64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let s: String = String::deserialize(deserializer)?;
+    Ok(s.split(',').filter_map(|id| id.parse().ok()).collect())
+}
+
+#[derive(Debug, Serialize)]
+struct ExportProgress {
+    status: String,
+    progress: f32,
+    video_data: Option<Vec<u8>>,
+    error: Option<String>,
+}
+
+#[oasgen]
+async fn start_audio(
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, (StatusCode, JsonResponse<Value>)> {
+    match state.audio_manager.start().await {
+        Ok(_) => Ok(Response::builder().status(200).body(Body::empty()).unwrap()),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({
+                "success": false,
+                "message": format!("Failed to start audio processing: {}", e),
+            })),
+        )),
+    }
+}
+
+#[oasgen]
+async fn stop_audio(
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, (StatusCode, JsonResponse<Value>)> {
+    match state.audio_manager.stop().await {
+        Ok(_) => Ok(Response::builder().status(200).body(Body::empty()).unwrap()),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({
+                "success": false,
+                "message": format!("Failed to start audio processing: {}", e),
+            })),
+        )),
+    }
+}
+
+pub async fn handle_video_export_ws(
+    ws: WebSocketUpgrade,
+    State(state): State<Arc<AppState>>,
+    Query(payload): Query<VideoExportRequest>,
+) -> impl IntoResponse {
+    if payload.frame_ids.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "No valid frame IDs provided" })),
+        )
+            .into_response();
+    }
+
+    ws.on_upgrade(move |socket| async move { handle_video_export(socket, state, payload).await })
+}
+
+async fn handle_video_export(
+    mut socket: WebSocket,
+    state: Arc<AppState>,
+    payload: VideoExportRequest,
+) {
+    let temp_dir = match tempfile::tempdir() {
+        Ok(dir) => dir,
+        Err(e) => {
+            let _ = socket
+                .send(Message::Text(
+                    serde_json::to_string(&ExportProgress {
+                        status: "error".to_string(),
+                        progress: 0.0,
+                        video_data: None,
+                        error: Some(format!("Failed to create temp directory: {}", e)),
+                    })
+                    .unwrap(),
+                ))
+                .await;
+            return;
+        }
+    };
+
+    let frames_dir = temp_dir.path().join("frames");
+    if let Err(e) = tokio::fs::create_dir_all(&frames_dir).await {
+        let _ = socket
+            .send(Message::Text(
+                serde_json::to_string(&ExportProgress {
+                    status: "error".to_string(),
+                    progress: 0.0,
+                    video_data: None,
+                    error: Some(format!("Failed to create frames directory: {}", e)),
+                })
+                .unwrap(),
+            ))
+            .await;
+        return;
+    }
+
+    let output_filename = format!(
+        "screenpipe_export_{}.mp4",
+        chrono::Utc::now().format("%Y%m%d_%H%M%S")
+    );
+    let output_path = temp_dir.path().join(&output_filename);
+
+    let mut frames = Vec::new();
+    let mut skipped_frames = Vec::new();
+
+    // Send initial status
+    let _ = socket
+        .send(Message::Text(
+            serde_json::to_string(&ExportProgress {
+                status: "extracting".to_string(),
+                progress: 0.0,
+                video_data: None,
+                error: None,
+            })
+            .unwrap(),
+        ))
+        .await;
+
+    // Process frames
+    for (index, frame_id) in payload.frame_ids.iter().enumerate() {
+        let progress = (index as f32 / payload.frame_ids.len() as f32) * 0.5;
+        let _ = socket
+            .send(Message::Text(
+                serde_json::to_string(&ExportProgress {
+                    status: "extracting".to_string(),
+                    progress,
+                    video_data: None,
+                    error: None,
+                })
+                .unwrap(),
+            ))
+            .await;
+
+        match state.db.get_frame(*frame_id).await {
+            Ok(Some((file_path, offset_index))) => {
+                match extract_high_quality_frame(&file_path, offset_index, &frames_dir).await {
+                    Ok(frame_path) => {
+                        frames.push(FrameContent {
+                            file_path: frame_path,
+                            timestamp: Some(chrono::Utc::now()),
+                            window_name: None,
+                            app_name: None,
+                            ocr_results: None,
+                            tags: None,
+                        });
+                    }
+                    Err(e) => {
+                        error!("Failed to extract frame {}: {}", frame_id, e);
+                        skipped_frames.push(*frame_id);
+                    }
+                }
+            }
+            Ok(None) => {
+                error!("Frame {} not found in database", frame_id);
+                skipped_frames.push(*frame_id);
+            }
+            Err(e) => {
+                error!("Database error for frame {}: {}", frame_id, e);
+                skipped_frames.push(*frame_id);
+            }
+        }
+    }
+
+    if frames.is_empty() {
+        let _ = socket
+            .send(Message::Text(
+                serde_json::to_string(&ExportProgress {
+                    status: "error".to_string(),
+                    progress: 0.0,
+                    video_data: None,
+                    error: Some("No valid frames to process".to_string()),
+                })
+                .unwrap(),
+            ))
+            .await;
+        return;
+    }
+
+    // Send encoding status
+    let _ = socket
+        .send(Message::Text(
+            serde_json::to_string(&ExportProgress {
+                status: "encoding".to_string(),
+                progress: 0.5,
+                video_data: None,
+                error: None,
+            })
+            .unwrap(),
+        ))
+        .await;
+
+    // Create video
+    match write_frames_to_video(&frames, output_path.to_str().unwrap(), payload.fps).await {
+        Ok(_) => match tokio::fs::read(&output_path).await {
+            Ok(video_data) => {
+                let _ = socket
+                    .send(Message::Text(
+                        serde_json::to_string(&ExportProgress {
+                            status: "completed".to_string(),
+                            progress: 1.0,
+                            video_data: Some(video_data),
+                            error: None,
+                        })
+                        .unwrap(),
+                    ))
+                    .await;
+            }
+            Err(e) => {
+                let _ = socket
+                    .send(Message::Text(
+                        serde_json::to_string(&ExportProgress {
+                            status: "error".to_string(),
+                            progress: 1.0,
+                            video_data: None,
+                            error: Some(format!("Failed to read video file: {}", e)),
+                        })
+                        .unwrap(),
+                    ))
+                    .await;
+            }
+        },
+        Err(e) => {
+            let _ = socket
+                .send(Message::Text(
+                    serde_json::to_string(&ExportProgress {
+                        status: "error".to_string(),
+                        progress: 1.0,
+                        video_data: None,
+                        error: Some(format!("Failed to create video: {}", e)),
+                    })
+                    .unwrap(),
+                ))
+                .await;
+        }
+    }
+
+    // Cleanup
+    if let Err(e) = tokio::fs::remove_dir_all(&temp_dir).await {
+        error!("Failed to clean up temp directory: {}", e);
+    }
+}
+
+#[oasgen]
+async fn get_pipe_build_status(
+    Path(pipe_id): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    let pipe_dir = state.screenpipe_dir.join("pipes").join(&pipe_id);
+    let update_temp_dir = std::env::temp_dir().join(format!("{}_update", pipe_id));
+    let temp_dir = pipe_dir.with_extension("_temp");
+    
+    // 1. First check if the update temp directory exists
+    if update_temp_dir.exists() {
+        debug!("Update temp directory exists for pipe: {}", pipe_id);
+        
+        // Check if there's a pipe.json in the update temp directory
+        let update_pipe_json_path = update_temp_dir.join("pipe.json");
+        if update_pipe_json_path.exists() {
+            let pipe_json = tokio::fs::read_to_string(&update_pipe_json_path)
+                .await
+                .map_err(|e| {
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        JsonResponse(json!({"error": format!("Failed to read update temp pipe config: {}", e)})),
+                    )
+                })?;
+
+            let pipe_config: Value = serde_json::from_str(&pipe_json).map_err(|e| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    JsonResponse(json!({"error": format!("Failed to parse update temp pipe config: {}", e)})),
+                )
+            })?;
+
+            // Return the buildStatus if it exists
+            if let Some(build_status) = pipe_config.get("buildStatus") {
+                debug!("Found build status in update temp directory for pipe: {}", pipe_id);
+                return Ok(JsonResponse(build_status.clone()));
+            }
+        }
+        
+        // If no buildStatus found in update temp directory, return a default in_progress status
+        return Ok(JsonResponse(json!({
+            "status": "in_progress",
+            "step": "downloading",
+            "message": "Update in progress"
+        })));
+    }
+
+    // 2. Check if the pipe directory exists
+    if pipe_dir.exists() {
+        // Then check if there's a pipe.json file
+        let pipe_json_path = pipe_dir.join("pipe.json");
+        if pipe_json_path.exists() {
+            let pipe_json = tokio::fs::read_to_string(&pipe_json_path)
+                .await
+                .map_err(|e| {
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        JsonResponse(json!({"error": format!("Failed to read pipe config: {}", e)})),
+                    )
+                })?;
+
+            let pipe_config: Value = serde_json::from_str(&pipe_json).map_err(|e| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    JsonResponse(json!({"error": format!("Failed to parse pipe config: {}", e)})),
+                )
+            })?;
+
+            // Check if there's a buildStatus field
+            if let Some(build_status) = pipe_config.get("buildStatus") {
+                // Return the build status directly
+                return Ok(JsonResponse(build_status.clone()));
+            }
+        } else {
+            // Pipe directory exists but pipe.json doesn't exist yet
+            // This likely means the pipe is still being created
+            debug!("Pipe directory exists but pipe.json not found for pipe: {}", pipe_id);
+            return Ok(JsonResponse(json!({
+                "status": "in_progress",
+                "step": "creating_config",
+                "message": "Creating pipe configuration"
+            })));
+        }
+    } else {
+        // If pipe directory doesn't exist, check temp directory
+        if temp_dir.exists() {
+            let temp_pipe_json = temp_dir.join("pipe.json");
+            if temp_pipe_json.exists() {
+                let pipe_json = tokio::fs::read_to_string(&temp_pipe_json)
+                    .await
+                    .map_err(|e| {
+                        (
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            JsonResponse(
+                                json!({"error": format!("Failed to read temp pipe config: {}", e)}),
+                            ),
+                        )
+                    })?;
+
+                let pipe_config: Value = serde_json::from_str(&pipe_json).map_err(|e| {
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        JsonResponse(
+                            json!({"error": format!("Failed to parse temp pipe config: {}", e)}),
+                        ),
+                    )
+                })?;
+
+                debug!("Found build status in temp directory for pipe: {}", pipe_id);
+                if let Some(build_status) = pipe_config.get("buildStatus") {
+                    return Ok(JsonResponse(build_status.clone()));
+                }
+            }
+            
+            // Temp directory exists but no pipe.json or no buildStatus
+            return Ok(JsonResponse(json!({
+                "status": "in_progress",
+                "step": "initializing",
+                "message": "Initializing pipe"
+            })));
+        }
+        
+        // If neither pipe directory nor temp directory exists, return not found
+        return Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "Pipe not found"})),
+        ));
+    }
+
+    // If we get here, there's a pipe.json but no buildStatus field
+    Ok(JsonResponse(json!(null)))
+}
+
+#[oasgen]
+async fn keyword_search_handler(
+    Query(query): Query<KeywordSearchRequest>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<SearchMatch>>, (StatusCode, JsonResponse<Value>)> {
+    let matches = state
+        .db
+        .search_with_text_positions(
+            &query.query,
+            query.limit,
+            query.offset,
+            query.start_time,
+            query.end_time,
+            query.fuzzy_match,
+            query.order,
+            query.app_names,
+        )
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })?;
+
+    Ok(JsonResponse(matches))
+}
+
+fn from_comma_separated_string<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s: Option<String> = Option::deserialize(deserializer)?;
+    Ok(s.map(|s| s.split(',').map(String::from).collect()))
+}
+
+#[derive(OaSchema, Deserialize)]
+pub struct KeywordSearchRequest {
+    query: String,
+    #[serde(default = "default_limit")]
+    limit: u32,
+    #[serde(default)]
+    offset: u32,
+    #[serde(default)]
+    start_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    end_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    fuzzy_match: bool,
+    #[serde(default)]
+    order: Order,
+    #[serde(default)]
+    #[serde(deserialize_with = "from_comma_separated_string")]
+    app_names: Option<Vec<String>>,
+}
+
+#[oasgen]
+pub async fn get_frame_data(
+    State(state): State<Arc<AppState>>,
+    Path(frame_id): Path<i64>,
+) -> Result<Response<Body>, (StatusCode, JsonResponse<Value>)> {
+    let start_time = Instant::now();
+
+    match timeout(Duration::from_secs(5), async {
+        // Try to get frame from cache if enabled
+        if let Some(cache) = &state.frame_image_cache {
+            let cache_result = cache.try_lock();
+            match cache_result {
+                Ok(mut cache) => {
+                    if let Some((file_path, timestamp)) = cache.get(&frame_id) {
+                        if timestamp.elapsed() < Duration::from_secs(300) {
+                            debug!(
+                                "Cache hit for frame_id: {}. Retrieved in {:?}",
+                                frame_id,
+                                start_time.elapsed()
+                            );
+                            return serve_file(file_path).await;
+                        }
+                        cache.pop(&frame_id);
+                    }
+                }
+                Err(_) => {
+                    debug!("Cache lock contention for frame_id: {}", frame_id);
+                }
+            }
+        }
+
+        // If not in cache or cache disabled, get from database
+        match state.db.get_frame(frame_id).await {
+            Ok(Some((file_path, _))) if file_path.is_empty() => {
+                Err(media_gone(&state, MediaChunkKind::Video, frame_id).await)
+            }
+            // a frame captured on demand is a png of its own, see /vision/screenshot
+            Ok(Some((file_path, _))) if file_path.ends_with(".png") => {
+                if tokio::fs::metadata(&file_path).await.is_err() {
+                    return Err(media_gone(&state, MediaChunkKind::Video, frame_id).await);
+                }
+                serve_file_as(&file_path, "image/png").await
+            }
+            Ok(Some((file_path, offset_index))) => {
+                match extract_frame_from_video(&file_path, offset_index).await {
+                    Ok(frame_path) => {
+                        // Store in cache if enabled and we can get the lock
+                        if let Some(cache) = &state.frame_image_cache {
+                            if let Ok(mut cache) = cache.try_lock() {
+                                cache.put(frame_id, (frame_path.clone(), Instant::now()));
+                            }
+                        }
+
+                        debug!("Frame {} extracted in {:?}", frame_id, start_time.elapsed());
+                        serve_file(&frame_path).await
+                    }
+                    Err(e) => {
+                        error!("Failed to extract frame {}: {}", frame_id, e);
+                        Err((
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            JsonResponse(json!({
+                                "error": format!("Failed to extract frame: {}", e),
+                                "frame_id": frame_id,
+                                "file_path": file_path
+                            })),
+                        ))
+                    }
+                }
+            }
+            Ok(None) => Err((
+                StatusCode::NOT_FOUND,
+                JsonResponse(json!({
+                    "error": "Frame not found",
+                    "frame_id": frame_id
+                })),
+            )),
+            Err(e) => Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({
+                    "error": format!("Database error: {}", e),
+                    "frame_id": frame_id
+                })),
+            )),
+        }
+    })
+    .await
+    {
+        Ok(result) => result,
+        Err(_) => {
+            error!("Request timeout for frame_id: {}", frame_id);
+            Err((
+                StatusCode::REQUEST_TIMEOUT,
+                JsonResponse(json!({
+                    "error": "Request timed out",
+                    "frame_id": frame_id
+                })),
+            ))
+        }
+    }
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct FrameThumbnailQuery {
+    /// in pixels, 160 by default, the height follows
+    #[serde(default)]
+    width: Option<u32>,
+}
+
+/// A small jpeg of a frame for timelines, made on first request and cached,
+/// still served once the frame's media was evicted.
+#[oasgen]
+pub(crate) async fn get_frame_thumbnail_handler(
+    State(state): State<Arc<AppState>>,
+    Path(frame_id): Path<i64>,
+    Query(query): Query<FrameThumbnailQuery>,
+) -> Result<Response<Body>, (StatusCode, JsonResponse<Value>)> {
+    let width = query.width.unwrap_or(DEFAULT_THUMBNAIL_WIDTH);
+    if !(MIN_THUMBNAIL_WIDTH..=MAX_THUMBNAIL_WIDTH).contains(&width) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({
+                "error": format!(
+                    "width must be between {} and {}",
+                    MIN_THUMBNAIL_WIDTH, MAX_THUMBNAIL_WIDTH
+                )
+            })),
+        ));
+    }
+    let thumbnail = match frame_thumbnail(&state.db, frame_id, width).await {
+        Ok(Some(thumbnail)) => thumbnail,
+        Ok(None) => return Err(media_gone(&state, MediaChunkKind::Video, frame_id).await),
+        Err(e) => {
+            error!("failed to make the thumbnail of frame {}: {}", frame_id, e);
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string(), "frame_id": frame_id})),
+            ));
+        }
+    };
+    Response::builder()
+        .header("content-type", "image/jpeg")
+        .header("cache-control", "public, max-age=604800")
+        .body(Body::from(thumbnail))
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+/// The response for a frame or an audio chunk without a file: 410 with when
+/// and why it was evicted, or 404 when it wasn't.
+async fn media_gone(
+    state: &AppState,
+    kind: MediaChunkKind,
+    id: i64,
+) -> (StatusCode, JsonResponse<Value>) {
+    let eviction = match kind {
+        MediaChunkKind::Video => state.db.get_frame_eviction(id).await,
+        MediaChunkKind::Audio => state.db.get_audio_chunk_eviction(id).await,
+    };
+    match eviction {
+        Ok(Some(eviction)) => (
+            StatusCode::GONE,
+            JsonResponse(json!({
+                "error": "media purged",
+                "id": id,
+                "evicted_at": eviction.evicted_at,
+                "reason": eviction.reason,
+            })),
+        ),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "media not found", "id": id})),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        ),
+    }
+}
+
+/// Serves the audio file of a chunk, decrypted when it's encrypted on disk.
+#[oasgen]
+pub(crate) async fn get_audio_chunk_file_handler(
+    State(state): State<Arc<AppState>>,
+    Path(chunk_id): Path<i64>,
+) -> Result<Response<Body>, (StatusCode, JsonResponse<Value>)> {
+    let file_path = match state.db.get_audio_chunk_path(chunk_id).await {
+        Ok(Some(file_path)) if !file_path.is_empty() => file_path,
+        Ok(Some(_)) => return Err(media_gone(&state, MediaChunkKind::Audio, chunk_id).await),
+        Ok(None) => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                JsonResponse(json!({"error": "audio chunk not found"})),
+            ))
+        }
+        Err(e) => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            ))
+        }
+    };
+    let media = plain_media(&file_path).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": format!("failed to decrypt audio: {}", e)})),
+        )
+    })?;
+    let audio = tokio::fs::read(media.path()).await.map_err(|e| {
+        (
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": format!("failed to read audio: {}", e)})),
+        )
+    })?;
+    let content_type = if file_path.ends_with(".wav") {
+        "audio/wav"
+    } else {
+        "audio/mp4"
+    };
+    Response::builder()
+        .header("content-type", content_type)
+        .body(Body::from(audio))
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct WhiteboardImageQuery {
+    /// serve the photo as ingested instead of the enhanced version
+    #[serde(default)]
+    original: bool,
+}
+
+#[oasgen]
+pub(crate) async fn get_whiteboard_image_handler(
+    State(state): State<Arc<AppState>>,
+    Path(frame_id): Path<i64>,
+    Query(query): Query<WhiteboardImageQuery>,
+) -> Result<Response<Body>, (StatusCode, JsonResponse<Value>)> {
+    let image = state
+        .db
+        .get_whiteboard_image(frame_id)
+        .await
+        .map_err(|e| {
+            error!(
+                "failed to get whiteboard image for frame {}: {}",
+                frame_id, e
+            );
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                JsonResponse(json!({"error": "no whiteboard image for this frame"})),
+            )
+        })?;
+
+    if query.original {
+        serve_file(&image.original_path).await
+    } else {
+        serve_file(&image.enhanced_path).await
+    }
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct FrameRegionQuery {
+    /// region in fractions of the frame from its top left corner, as in the
+    /// `link` of a search text position
+    left: f32,
+    top: f32,
+    width: f32,
+    height: f32,
+    /// "crop" (default) serves the region alone, "annotate" the whole frame
+    /// with the region outlined
+    #[serde(default)]
+    mode: Option<String>,
+    /// context kept around a crop, in fractions of the frame
+    #[serde(default)]
+    padding: Option<f32>,
+}
+
+/// A region of a frame as png, the target of the deep links search gives
+/// text positions.
+#[oasgen]
+pub(crate) async fn get_frame_region_handler(
+    State(state): State<Arc<AppState>>,
+    Path(frame_id): Path<i64>,
+    Query(query): Query<FrameRegionQuery>,
+) -> Result<Response<Body>, (StatusCode, JsonResponse<Value>)> {
+    let internal_error = |e: String| {
+        error!("failed to get region of frame {}: {}", frame_id, e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e})),
+        )
+    };
+    let annotate = match query.mode.as_deref() {
+        None | Some("crop") => false,
+        Some("annotate") => true,
+        Some(mode) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                JsonResponse(json!({"error": format!("unknown mode: {}", mode)})),
+            ))
+        }
+    };
+
+    let (file_path, offset_index) = state
+        .db
+        .get_frame(frame_id)
+        .await
+        .map_err(|e| internal_error(e.to_string()))?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                JsonResponse(json!({"error": "frame not found"})),
+            )
+        })?;
+    let frame_path = extract_frame_from_video(&file_path, offset_index)
+        .await
+        .map_err(|e| internal_error(e.to_string()))?;
+
+    let region = TextBounds {
+        left: query.left,
+        top: query.top,
+        width: query.width,
+        height: query.height,
+    };
+    let padding = query.padding.unwrap_or(0.02);
+    let png = tokio::task::spawn_blocking(move || -> Result<Option<Vec<u8>>, anyhow::Error> {
+        let image = image::open(&frame_path)?;
+        let region_image = if annotate {
+            image::DynamicImage::ImageRgba8(annotate_region(&image, &region, 3))
+        } else {
+            match crop_region(&image, &region, padding) {
+                Some(cropped) => cropped,
+                None => return Ok(None),
+            }
+        };
+        let mut png = Vec::new();
+        region_image.write_to(&mut std::io::Cursor::new(&mut png), ImageFormat::Png)?;
+        Ok(Some(png))
+    })
+    .await
+    .map_err(|e| internal_error(e.to_string()))?
+    .map_err(|e| internal_error(e.to_string()))?
+    .ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "region is outside of the frame"})),
+        )
+    })?;
+
+    Response::builder()
+        .header("content-type", "image/png")
+        .body(Body::from(png))
+        .map_err(|e| internal_error(e.to_string()))
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct RedactFrameRequest {
+    /// area to black out in pixels, the whole frame when missing
+    #[serde(default)]
+    region: Option<TextBounds>,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+/// Removes a frame from history after the fact: the image (or a region of it)
+/// is blacked out in its video chunk, the text read from it is deleted and a
+/// tombstone is recorded.
+#[oasgen]
+pub(crate) async fn redact_frame_handler(
+    State(state): State<Arc<AppState>>,
+    Path(frame_id): Path<i64>,
+    Json(request): Json<RedactFrameRequest>,
+) -> Result<JsonResponse<FrameRedaction>, (StatusCode, JsonResponse<Value>)> {
+    let internal_error = |e: String| {
+        error!("failed to redact frame {}: {}", frame_id, e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e})),
+        )
+    };
+
+    let (file_path, offset_index) = state
+        .db
+        .get_frame(frame_id)
+        .await
+        .map_err(|e| internal_error(e.to_string()))?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                JsonResponse(json!({"error": "frame not found"})),
+            )
+        })?;
+    let whiteboard = state
+        .db
+        .get_whiteboard_image(frame_id)
+        .await
+        .map_err(|e| internal_error(e.to_string()))?;
+
+    // the chunk still being recorded can't be re-encoded under the recorder
+    if state
+        .db
+        .is_frame_recording(frame_id)
+        .await
+        .map_err(|e| internal_error(e.to_string()))?
+    {
+        return Err((
+            StatusCode::CONFLICT,
+            JsonResponse(json!({
+                "error": "the frame's video is still being recorded, retry once its chunk is finished"
+            })),
+        ));
+    }
+
+    // the image goes first, the text is only deleted once nothing can be
+    // recovered from the video
+    if !file_path.is_empty() && std::path::Path::new(&file_path).exists() {
+        redact_video_frame(&file_path, offset_index, request.region)
+            .await
+            .map_err(|e| internal_error(e.to_string()))?;
+        // the synced copy is replaced by the redacted one
+        state
+            .db
+            .reset_video_chunk_sync(&file_path)
+            .await
+            .map_err(|e| internal_error(e.to_string()))?;
+    }
+
+    let redaction = state
+        .db
+        .redact_frame(frame_id, request.region, request.reason.as_deref())
+        .await
+        .map_err(|e| internal_error(e.to_string()))?;
+    audit(
+        &state.db,
+        "redact_frame",
+        &format!("frame:{}", frame_id),
+        None,
+    )
+    .await;
+
+    if let Some(whiteboard) = whiteboard {
+        for path in [&whiteboard.original_path, &whiteboard.enhanced_path] {
+            if let Err(e) = tokio::fs::remove_file(path).await {
+                error!("failed to remove whiteboard photo {}: {}", path, e);
+            }
+        }
+    }
+
+    Ok(JsonResponse(redaction))
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct AuditLogQuery {
+    #[serde(flatten)]
+    pagination: PaginationQuery,
+    /// e.g. "raw_sql", "bulk_delete", "merge_speakers"
+    #[serde(default)]
+    action: Option<String>,
+    #[serde(default)]
+    start_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    end_time: Option<DateTime<Utc>>,
+}
+
+/// Raw sql queries, deletions, speaker merges and tag changes, newest first,
+/// with who made them and how many rows they changed.
+#[oasgen]
+async fn list_audit_log_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AuditLogQuery>,
+) -> Result<JsonResponse<Vec<AuditEntry>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .list_audit_log(
+            query.action.as_deref(),
+            query.start_time,
+            query.end_time,
+            query.pagination.limit,
+            query.pagination.offset,
+        )
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct ListReceiptsQuery {
+    #[serde(flatten)]
+    pagination: PaginationQuery,
+    #[serde(default)]
+    start_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    end_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    vendor: Option<String>,
+}
+
+#[oasgen]
+async fn list_receipts_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListReceiptsQuery>,
+) -> Result<JsonResponse<Vec<Receipt>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .list_receipts(
+            query.start_time,
+            query.end_time,
+            query.vendor.as_deref(),
+            query.pagination.limit,
+            query.pagination.offset,
+        )
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+/// The video and audio chunks whose file was missing, didn't decode or
+/// lacked frames at their last check, see `--verify-media-every-days`.
+#[oasgen]
+async fn list_media_issues_handler(
+    State(state): State<Arc<AppState>>,
+    Query(pagination): Query<PaginationQuery>,
+) -> Result<JsonResponse<Vec<MediaIssue>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .list_media_issues(pagination.limit, pagination.offset)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+/// The retention rules in use or used before, with what each trashed,
+/// purged and deleted so far.
+#[oasgen]
+async fn list_retention_rules_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<Vec<RetentionRuleStats>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .list_retention_rule_stats()
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+/// Lists what retention moved to the trash, by day and app, with when each
+/// group gets purged for good.
+#[oasgen]
+async fn list_trash_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<Vec<TrashGroup>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .list_trash(chrono::Duration::days(state.trash_days as i64))
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct TrashItemsQuery {
+    #[serde(flatten)]
+    pagination: PaginationQuery,
+    /// YYYY-MM-DD
+    #[serde(default)]
+    day: Option<String>,
+    #[serde(default)]
+    app_name: Option<String>,
+}
+
+#[oasgen]
+async fn list_trash_items_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TrashItemsQuery>,
+) -> Result<JsonResponse<Vec<TrashItem>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .list_trash_items(
+            chrono::Duration::days(state.trash_days as i64),
+            query.day.as_deref(),
+            query.app_name.as_deref(),
+            query.pagination.limit,
+            query.pagination.offset,
+        )
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct RestoreTrashRequest {
+    #[serde(default)]
+    frame_ids: Vec<i64>,
+    #[serde(default)]
+    audio_chunk_ids: Vec<i64>,
+    #[serde(default)]
+    ui_ids: Vec<i64>,
+    #[serde(default)]
+    ui_traversal_ids: Vec<i64>,
+    #[serde(default)]
+    clipboard_ids: Vec<i64>,
+    /// restores a whole day (YYYY-MM-DD), only the items of `app_name` when set
+    #[serde(default)]
+    day: Option<String>,
+    #[serde(default)]
+    app_name: Option<String>,
+}
+
+/// Takes items out of the trash. Restored items are kept from then on, even
+/// past their retention.
+#[oasgen]
+async fn restore_trash_handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<RestoreTrashRequest>,
+) -> Result<JsonResponse<TrashCount>, (StatusCode, JsonResponse<Value>)> {
+    if request.frame_ids.is_empty()
+        && request.audio_chunk_ids.is_empty()
+        && request.ui_ids.is_empty()
+        && request.ui_traversal_ids.is_empty()
+        && request.clipboard_ids.is_empty()
+        && request.day.is_none()
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({
+                "error": "frame_ids, audio_chunk_ids, ui_ids, ui_traversal_ids, clipboard_ids or day is required"
+            })),
+        ));
+    }
+
+    state
+        .db
+        .restore_trash(
+            &request.frame_ids,
+            &request.audio_chunk_ids,
+            &request.ui_ids,
+            &request.ui_traversal_ids,
+            &request.clipboard_ids,
+            request.day.as_deref(),
+            request.app_name.as_deref(),
+        )
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct CoverageQuery {
+    start_time: DateTime<Utc>,
+    /// defaults to now
+    #[serde(default)]
+    end_time: Option<DateTime<Utc>>,
+    /// samples further apart than this are reported as a gap
+    #[serde(default = "default_max_gap_secs")]
+    max_gap_secs: f64,
+    /// longest gaps to return
+    #[serde(default = "default_max_gaps")]
+    max_gaps: u32,
+}
+
+fn default_max_gap_secs() -> f64 {
+    // above the longest interval of --adaptive-fps by default
+    120.0
+}
+
+fn default_max_gaps() -> u32 {
+    100
+}
+
+/// Reports, per day and device, the share of time covered by frames and
+/// transcriptions, and the longest gaps, to tell how complete the archive of
+/// a period is.
+#[oasgen]
+async fn coverage_report_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<CoverageQuery>,
+) -> Result<JsonResponse<CoverageReport>, (StatusCode, JsonResponse<Value>)> {
+    let end_time = query.end_time.unwrap_or_else(Utc::now);
+    if end_time <= query.start_time || query.max_gap_secs <= 0.0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({
+                "error": "end_time must be after start_time and max_gap_secs positive"
+            })),
+        ));
+    }
+
+    state
+        .db
+        .get_coverage_report(
+            query.start_time,
+            end_time,
+            query.max_gap_secs,
+            query.max_gaps,
+        )
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct TimelineQuery {
+    start_time: DateTime<Utc>,
+    /// defaults to now
+    #[serde(default)]
+    end_time: Option<DateTime<Utc>>,
+    /// samples further apart than this split the timeline with a gap
+    #[serde(default = "default_max_gap_secs")]
+    max_gap_secs: f64,
+}
+
+/// The stretches with frames or transcriptions and the gaps between them,
+/// with why nothing was recorded, for scrubbers to draw without asking for
+/// every frame.
+#[oasgen]
+async fn timeline_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TimelineQuery>,
+) -> Result<JsonResponse<Timeline>, (StatusCode, JsonResponse<Value>)> {
+    let end_time = query.end_time.unwrap_or_else(Utc::now);
+    if end_time <= query.start_time || query.max_gap_secs <= 0.0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({
+                "error": "end_time must be after start_time and max_gap_secs positive"
+            })),
+        ));
+    }
+
+    state
+        .db
+        .get_timeline(
+            query.start_time,
+            end_time,
+            chrono::Duration::milliseconds((query.max_gap_secs * 1000.0) as i64),
+        )
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct HeatmapQuery {
+    start_time: DateTime<Utc>,
+    /// defaults to now
+    #[serde(default)]
+    end_time: Option<DateTime<Utc>>,
+}
+
+/// Frames, transcriptions and ui events by day of the week and hour of the
+/// day, in local time, over the whole hours of the range.
+#[oasgen]
+async fn activity_heatmap_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<HeatmapQuery>,
+) -> Result<JsonResponse<ActivityHeatmap>, (StatusCode, JsonResponse<Value>)> {
+    let end_time = query.end_time.unwrap_or_else(Utc::now);
+    if end_time <= query.start_time {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "end_time must be after start_time"})),
+        ));
+    }
+    activity_heatmap(&state.db, query.start_time, end_time)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct InputActivityQuery {
+    start_time: DateTime<Utc>,
+    /// defaults to now
+    #[serde(default)]
+    end_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    app_name: Option<String>,
+}
+
+/// Keyboard and mouse activity counted per minute and app with
+/// --enable-input-activity, telling the minutes of active work from passive
+/// screen time.
+#[oasgen]
+async fn input_activity_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<InputActivityQuery>,
+) -> Result<JsonResponse<InputActivityReport>, (StatusCode, JsonResponse<Value>)> {
+    let end_time = query.end_time.unwrap_or_else(Utc::now);
+    if end_time <= query.start_time {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "end_time must be after start_time"})),
+        ));
+    }
+
+    state
+        .db
+        .get_input_activity(query.start_time, end_time, query.app_name.as_deref())
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct UiTraversalsQuery {
+    start_time: DateTime<Utc>,
+    /// defaults to now
+    #[serde(default)]
+    end_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    app_name: Option<String>,
+    #[serde(default)]
+    window_name: Option<String>,
+    /// for /ui/changes, only the changes adding or removing a line with it
+    #[serde(default)]
+    q: Option<String>,
+    #[serde(default = "default_limit")]
+    limit: u32,
+}
+
+/// Accessibility tree traversals of the windows, rebuilt from the diffs
+/// they are stored as, latest first.
+#[oasgen]
+async fn ui_traversals_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<UiTraversalsQuery>,
+) -> Result<JsonResponse<Vec<UiTraversal>>, (StatusCode, JsonResponse<Value>)> {
+    let end_time = query.end_time.unwrap_or_else(Utc::now);
+    state
+        .db
+        .get_ui_traversals(
+            query.app_name.as_deref(),
+            query.window_name.as_deref(),
+            query.start_time,
+            end_time,
+            query.limit,
+        )
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+/// The lines each traversal added and removed, e.g. when the value of a
+/// field changed, latest first.
+#[oasgen]
+async fn ui_changes_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<UiTraversalsQuery>,
+) -> Result<JsonResponse<Vec<UiChange>>, (StatusCode, JsonResponse<Value>)> {
+    let end_time = query.end_time.unwrap_or_else(Utc::now);
+    state
+        .db
+        .get_ui_changes(
+            query.app_name.as_deref(),
+            query.window_name.as_deref(),
+            query.q.as_deref(),
+            query.start_time,
+            end_time,
+            query.limit,
+        )
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct FocusQuery {
+    start_time: DateTime<Utc>,
+    /// defaults to now
+    #[serde(default)]
+    end_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    app_name: Option<String>,
+}
+
+impl FocusQuery {
+    fn range(&self) -> Result<(DateTime<Utc>, DateTime<Utc>), (StatusCode, JsonResponse<Value>)> {
+        let end_time = self.end_time.unwrap_or_else(Utc::now);
+        if end_time <= self.start_time {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                JsonResponse(json!({"error": "end_time must be after start_time"})),
+            ));
+        }
+        Ok((self.start_time, end_time))
+    }
+}
+
+/// The windows focused within the range, recorded with
+/// --enable-focus-sessions, oldest first.
+#[oasgen]
+async fn focus_sessions_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<FocusQuery>,
+) -> Result<JsonResponse<Vec<FocusSession>>, (StatusCode, JsonResponse<Value>)> {
+    let (start_time, end_time) = query.range()?;
+    state
+        .db
+        .get_focus_sessions(start_time, end_time, query.app_name.as_deref())
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+/// How long each window had the focus within the range, longest first.
+#[oasgen]
+async fn focus_time_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<FocusQuery>,
+) -> Result<JsonResponse<Vec<WindowFocusTime>>, (StatusCode, JsonResponse<Value>)> {
+    let (start_time, end_time) = query.range()?;
+    state
+        .db
+        .get_focus_time(start_time, end_time, query.app_name.as_deref())
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct IdleQuery {
+    start_time: DateTime<Utc>,
+    /// defaults to now
+    #[serde(default)]
+    end_time: Option<DateTime<Utc>>,
+}
+
+/// The stretches with no screen change, transcription or input overlapping
+/// the range, oldest first.
+#[oasgen]
+async fn idle_intervals_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<IdleQuery>,
+) -> Result<JsonResponse<Vec<IdleInterval>>, (StatusCode, JsonResponse<Value>)> {
+    let end_time = query.end_time.unwrap_or_else(Utc::now);
+    if end_time <= query.start_time {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "end_time must be after start_time"})),
+        ));
+    }
+    state
+        .db
+        .get_idle_intervals(query.start_time, end_time)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct SummaryQuery {
+    /// "day" (default) or "week", weeks starting on monday
+    #[serde(default)]
+    period: SummaryPeriod,
+    /// local date within the period, today by default
+    #[serde(default)]
+    date: Option<NaiveDate>,
+    /// generate it again instead of serving the one kept
+    #[serde(default)]
+    refresh: bool,
+}
+
+/// Top apps, meetings, key topics and notable excerpts of a day or a week,
+/// with an overview when an llm is configured.
+#[oasgen]
+async fn summary_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<SummaryQuery>,
+) -> Result<JsonResponse<Summary>, (StatusCode, JsonResponse<Value>)> {
+    let date = query.date.unwrap_or_else(|| Local::now().date_naive());
+    summarize(
+        &state.db,
+        state.summary_llm.as_deref(),
+        query.period,
+        date,
+        query.refresh,
+    )
+    .await
+    .map(JsonResponse)
+    .map_err(|e| {
+        error!(
+            "failed to summarize the {} of {}: {}",
+            query.period.as_str(),
+            date,
+            e
+        );
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )
+    })
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct TopicsQuery {
+    /// local dates, the 30 days up to today by default
+    #[serde(default)]
+    start_date: Option<NaiveDate>,
+    #[serde(default)]
+    end_date: Option<NaiveDate>,
+    #[serde(default = "default_limit")]
+    limit: u32,
+}
+
+// the days topics are looked at over by default
+const DEFAULT_TOPIC_DAYS: i64 = 30;
+
+fn topic_days(
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+) -> Result<(NaiveDate, NaiveDate), (StatusCode, JsonResponse<Value>)> {
+    let end_date = end_date.unwrap_or_else(|| Local::now().date_naive());
+    let start_date =
+        start_date.unwrap_or(end_date - chrono::Duration::days(DEFAULT_TOPIC_DAYS - 1));
+    if end_date < start_date {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "end_date must not be before start_date"})),
+        ));
+    }
+    Ok((start_date, end_date))
+}
+
+/// The terms standing out the most over the days, from the screen text and
+/// transcriptions of the days extracted.
+#[oasgen]
+async fn top_topics_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TopicsQuery>,
+) -> Result<JsonResponse<Vec<PeriodTopic>>, (StatusCode, JsonResponse<Value>)> {
+    let (start_date, end_date) = topic_days(query.start_date, query.end_date)?;
+    state
+        .db
+        .get_top_topics(start_date, end_date, query.limit)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct TopicTrendQuery {
+    /// comma separated, e.g. "kubernetes migration,hiring"
+    #[serde(default, deserialize_with = "from_comma_separated_string")]
+    terms: Option<Vec<String>>,
+    #[serde(default)]
+    start_date: Option<NaiveDate>,
+    #[serde(default)]
+    end_date: Option<NaiveDate>,
+    /// "day" (default) or "week"
+    #[serde(default)]
+    period: SummaryPeriod,
+}
+
+/// How terms stood out each day or week, to see when a topic took over.
+#[oasgen]
+async fn topic_trend_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TopicTrendQuery>,
+) -> Result<JsonResponse<Vec<TopicTrend>>, (StatusCode, JsonResponse<Value>)> {
+    let (start_date, end_date) = topic_days(query.start_date, query.end_date)?;
+    let terms: Vec<String> = query
+        .terms
+        .unwrap_or_default()
+        .iter()
+        .map(|term| normalize_term(term))
+        .filter(|term| !term.is_empty())
+        .collect();
+    if terms.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "terms must not be empty"})),
+        ));
+    }
+    let internal = |e: sqlx::Error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )
+    };
+    let extracted = state
+        .db
+        .get_topic_trend(&terms, start_date, end_date)
+        .await
+        .map_err(internal)?;
+    let days = state
+        .db
+        .get_topic_days(start_date, end_date)
+        .await
+        .map_err(internal)?;
+    Ok(JsonResponse(topic_trends(
+        &terms,
+        &extracted,
+        &days,
+        query.period,
+    )))
+}
+
+#[derive(OaSchema, Deserialize)]
+pub struct ExtractTopicsRequest {
+    /// local date, today by default
+    #[serde(default)]
+    pub date: Option<NaiveDate>,
+}
+
+/// Extracts the terms of a day again, e.g. today so far, which the job
+/// leaves until it is over.
+#[oasgen]
+async fn extract_topics_handler(
+    State(state): State<Arc<AppState>>,
+    JsonResponse(request): JsonResponse<ExtractTopicsRequest>,
+) -> Result<JsonResponse<Vec<TopicTerm>>, (StatusCode, JsonResponse<Value>)> {
+    let date = request.date.unwrap_or_else(|| Local::now().date_naive());
+    extract_day(&state.db, date)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            error!("failed to extract the topics of {}: {}", date, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct ProductivityQuery {
+    /// local dates, the 7 days up to today by default
+    #[serde(default)]
+    start_date: Option<NaiveDate>,
+    #[serde(default)]
+    end_date: Option<NaiveDate>,
+}
+
+// the days scored by default, and at most
+const DEFAULT_PRODUCTIVITY_DAYS: i64 = 7;
+const MAX_PRODUCTIVITY_DAYS: i64 = 92;
+
+/// The score of each day, from the time spent in the apps, windows and urls
+/// of each category of /productivity/categories.
+#[oasgen]
+async fn productivity_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ProductivityQuery>,
+) -> Result<JsonResponse<Vec<DayProductivity>>, (StatusCode, JsonResponse<Value>)> {
+    let end_date = query.end_date.unwrap_or_else(|| Local::now().date_naive());
+    let start_date = query
+        .start_date
+        .unwrap_or(end_date - chrono::Duration::days(DEFAULT_PRODUCTIVITY_DAYS - 1));
+    if end_date < start_date {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "end_date must not be before start_date"})),
+        ));
+    }
+    if (end_date - start_date).num_days() >= MAX_PRODUCTIVITY_DAYS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({
+                "error": format!("at most {} days can be scored at once", MAX_PRODUCTIVITY_DAYS)
+            })),
+        ));
+    }
+
+    productivity(&state.db, start_date, end_date)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+/// The apps, window titles and browser urls counted as deep work,
+/// communication or distraction, from the command line and added here.
+#[oasgen]
+async fn list_app_category_rules_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<Vec<AppCategoryRule>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .list_app_category_rules()
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+/// Counts an app, window title or browser url in a category, the days
+/// scored before included.
+#[oasgen]
+async fn create_app_category_rule_handler(
+    State(state): State<Arc<AppState>>,
+    Json(rule): Json<NewAppCategoryRule>,
+) -> Result<JsonResponse<AppCategoryRule>, (StatusCode, JsonResponse<Value>)> {
+    let bad_request = |error: &str| {
+        (
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": error})),
+        )
+    };
+    if !APP_CATEGORY_KINDS.contains(&rule.kind.as_str()) {
+        return Err(bad_request("kind must be \"app\", \"window\" or \"url\""));
+    }
+    if !APP_CATEGORIES.contains(&rule.category.as_str()) {
+        return Err(bad_request(
+            "category must be \"deep_work\", \"communication\" or \"distraction\"",
+        ));
+    }
+    if rule.pattern.trim().is_empty() {
+        return Err(bad_request("pattern is required"));
+    }
+
+    state
+        .db
+        .insert_app_category_rule(&rule)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+#[oasgen]
+async fn delete_app_category_rule_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    match state.db.delete_app_category_rule(id).await {
+        Ok(true) => Ok(JsonResponse(json!({"success": true}))),
+        Ok(false) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({
+                "error": format!("no category rule {} added at the api, the ones of the command line can't be deleted", id)
+            })),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )),
+    }
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct ClockSyncRequest {
+    device_name: String,
+    /// changes when the agent restarts, its clock may have been reset
+    session_id: String,
+    /// the agent's previous exchange, completed with the time it received
+    /// the answer
+    #[serde(default)]
+    previous: Option<ClockExchange>,
+}
+
+#[derive(OaSchema, Serialize)]
+pub(crate) struct ClockSyncResponse {
+    server_received_at: DateTime<Utc>,
+    server_sent_at: DateTime<Utc>,
+    /// current estimate for the device, once an exchange was sent back
+    offset: Option<ClockOffset>,
+}
+
+/// NTP-style clock sync for remote capture agents. An agent calls it
+/// periodically and sends back the times of its previous exchange; the
+/// estimated offset is then applied to the timestamps it sends to `/add`,
+/// so its frames line up with the other devices.
+#[oasgen]
+async fn clock_sync_handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ClockSyncRequest>,
+) -> Result<JsonResponse<ClockSyncResponse>, (StatusCode, JsonResponse<Value>)> {
+    let server_received_at = Utc::now();
+    if request.device_name.is_empty() || request.session_id.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "device_name and session_id are required"})),
+        ));
+    }
+
+    let offset = match request.previous {
+        Some(exchange) if !exchange.is_valid() => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                JsonResponse(json!({"error": "previous exchange has times going backwards"})),
+            ));
+        }
+        Some(exchange) => state
+            .db
+            .record_clock_sample(
+                &request.device_name,
+                &request.session_id,
+                exchange.offset_ms(),
+                exchange.delay_ms(),
+            )
+            .await
+            .map(Some),
+        None => state.db.get_clock_offset(&request.device_name).await,
+    }
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )
+    })?
+    // an estimate of a previous session doesn't hold anymore
+    .filter(|offset| offset.session_id == request.session_id);
+
+    Ok(JsonResponse(ClockSyncResponse {
+        server_received_at,
+        server_sent_at: Utc::now(),
+        offset,
+    }))
+}
+
+#[oasgen]
+async fn list_api_tokens_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<Vec<ApiToken>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .list_api_tokens()
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct CreateApiTokenRequest {
+    name: String,
+    /// read-search, write-tags, write, delete, admin-sql or admin
+    scopes: Vec<String>,
+}
+
+#[derive(OaSchema, Serialize)]
+pub(crate) struct CreateApiTokenResponse {
+    /// shown only once, only its hash is stored
+    token: String,
+    #[serde(flatten)]
+    info: ApiToken,
+}
+
+#[oasgen]
+async fn create_api_token_handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<CreateApiTokenRequest>,
+) -> Result<JsonResponse<CreateApiTokenResponse>, (StatusCode, JsonResponse<Value>)> {
+    let scopes = request
+        .scopes
+        .iter()
+        .map(|scope| scope.parse::<ApiScope>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| (StatusCode::BAD_REQUEST, JsonResponse(json!({"error": e}))))?;
+    if scopes.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "at least one scope is required"})),
+        ));
+    }
+
+    let (token, info) = create_token(&state.db, &request.name, &scopes)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })?;
+    Ok(JsonResponse(CreateApiTokenResponse { token, info }))
+}
+
+#[oasgen]
+async fn delete_api_token_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    match state.db.delete_api_token(id).await {
+        Ok(true) => Ok(JsonResponse(json!({"success": true}))),
+        Ok(false) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": format!("api token {} not found", id)})),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )),
+    }
+}
+
+/// How the shadow search compared with `/search` since the server started.
+#[oasgen]
+async fn shadow_report_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<ShadowReport>, (StatusCode, JsonResponse<Value>)> {
+    match &state.shadow {
+        Some(shadow) => Ok(JsonResponse(shadow.report())),
+        None => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({
+                "error": "shadow search is disabled, start screenpipe with --shadow-search"
+            })),
+        )),
+    }
+}
+
+/// Records that a search result was opened, and for how long, to rank it
+/// higher for similar queries. Refused unless click tracking is enabled.
+#[oasgen]
+async fn record_search_click_handler(
+    State(state): State<Arc<AppState>>,
+    Json(click): Json<NewSearchClick>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    if !state.click_tracking {
+        return Err((
+            StatusCode::FORBIDDEN,
+            JsonResponse(json!({
+                "error": "click tracking is disabled, start screenpipe with --enable-click-tracking"
+            })),
+        ));
+    }
+    if normalize_query(&click.q).is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "q is required"})),
+        ));
+    }
+    if !click.dwell_secs.is_finite() || click.dwell_secs < 0.0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "dwell_secs can't be negative"})),
+        ));
+    }
+
+    state
+        .db
+        .record_search_click(&click)
+        .await
+        .map(|id| JsonResponse(json!({"id": id})))
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+/// The clicks learned from, grouped by query and result.
+#[oasgen]
+async fn list_click_signals_handler(
+    State(state): State<Arc<AppState>>,
+    Query(pagination): Query<PaginationQuery>,
+) -> Result<JsonResponse<Vec<ClickSignal>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .list_click_signals(pagination.limit, pagination.offset)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct ResetClickSignalsQuery {
+    /// only forget the clicks of this query
+    q: Option<String>,
+}
+
+/// Forgets the clicks learned from, all of them or those of one query.
+#[oasgen]
+async fn reset_click_signals_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ResetClickSignalsQuery>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .reset_click_signals(query.q.as_deref())
+        .await
+        .map(|deleted| JsonResponse(json!({"deleted": deleted})))
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+#[oasgen]
+async fn list_webhook_rules_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<Vec<WebhookRule>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .list_webhook_rules()
         .await
+        .map(JsonResponse)
         .map_err(|e| {
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 JsonResponse(json!({"error": e.to_string()})),
             )
-        })?;
+        })
+}
 
-    Ok(JsonResponse(similar_speakers))
+/// Adds a keyword or regex rule. New OCR text and transcriptions matching it
+/// are posted to its webhook.
+#[oasgen]
+async fn create_webhook_rule_handler(
+    State(state): State<Arc<AppState>>,
+    Json(rule): Json<NewWebhookRule>,
+) -> Result<JsonResponse<WebhookRule>, (StatusCode, JsonResponse<Value>)> {
+    let bad_request = |error: String| {
+        (
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": error})),
+        )
+    };
+    if rule.pattern.trim().is_empty() {
+        return Err(bad_request("pattern is required".to_string()));
+    }
+    if let Err(e) = screenpipe_db::webhook_rule_matcher(&rule.pattern, rule.is_regex) {
+        return Err(bad_request(format!("invalid pattern: {}", e)));
+    }
+    if !matches!(
+        rule.content_type.as_deref(),
+        None | Some("ocr") | Some("audio")
+    ) {
+        return Err(bad_request(
+            "content_type must be \"ocr\" or \"audio\"".to_string(),
+        ));
+    }
+    if reqwest::Url::parse(&rule.webhook_url).is_err() {
+        return Err(bad_request("invalid webhook_url".to_string()));
+    }
+    if rule.cooldown_secs < 0 {
+        return Err(bad_request("cooldown_secs can't be negative".to_string()));
+    }
+
+    state
+        .db
+        .insert_webhook_rule(&rule)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
 }
-// #[derive(OaSchema, Deserialize)]
-// pub struct AudioDeviceControlRequest {
-//     device_name: String,
-//     #[serde(default)]
-//     device_type: Option<DeviceType>,
-// }
 
-#[derive(Debug, OaSchema, Serialize)]
-pub struct AudioDeviceControlResponse {
-    success: bool,
-    message: String,
+#[oasgen]
+async fn delete_webhook_rule_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    match state.db.delete_webhook_rule(id).await {
+        Ok(true) => Ok(JsonResponse(json!({"success": true}))),
+        Ok(false) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": format!("webhook rule {} not found", id)})),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )),
+    }
 }
 
-// Add these new handler functions before create_router()
-// async fn start_audio_device(
-//     State(state): State<Arc<AppState>>,
-//     Json(payload): Json<AudioDeviceControlRequest>,
-// ) -> Result<JsonResponse<AudioDeviceControlResponse>, (StatusCode, JsonResponse<Value>)> {
-//     let device = AudioDevice {
-//         name: payload.device_name.clone(),
-//         device_type: payload.device_type.unwrap_or(AudioDeviceType::Input),
-//     };
+#[oasgen]
+async fn list_tag_rules_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<Vec<TagRule>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .list_tag_rules()
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
 
-//     // Validate device exists
-//     let available_devices = list_audio_devices().await.map_err(|e| {
-//         (
-//             StatusCode::INTERNAL_SERVER_ERROR,
-//             JsonResponse(json!({
-//                 "error": format!("failed to list audio devices: {}", e),
-//                 "success": false
-//             })),
-//         )
-//     })?;
+/// Adds a rule tagging new OCR text and transcriptions that match all of its
+/// conditions: app, window, browser url, keyword or regex, time of day.
+#[oasgen]
+async fn create_tag_rule_handler(
+    State(state): State<Arc<AppState>>,
+    Json(rule): Json<NewTagRule>,
+) -> Result<JsonResponse<TagRule>, (StatusCode, JsonResponse<Value>)> {
+    let bad_request = |error: String| {
+        (
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": error})),
+        )
+    };
+    if rule.tag.trim().is_empty() {
+        return Err(bad_request("tag is required".to_string()));
+    }
+    if let Some(pattern) = &rule.pattern {
+        if let Err(e) = screenpipe_db::webhook_rule_matcher(pattern, rule.is_regex) {
+            return Err(bad_request(format!("invalid pattern: {}", e)));
+        }
+    }
+    if !matches!(
+        rule.content_type.as_deref(),
+        None | Some("ocr") | Some("audio")
+    ) {
+        return Err(bad_request(
+            "content_type must be \"ocr\" or \"audio\"".to_string(),
+        ));
+    }
+    for time in [&rule.start_time, &rule.end_time].into_iter().flatten() {
+        if screenpipe_db::tag_rule_time(time).is_err() {
+            return Err(bad_request(format!(
+                "invalid time of day {}, expected HH:MM",
+                time
+            )));
+        }
+    }
+    // a rule without conditions would tag everything
+    if rule.app_name.is_none()
+        && rule.window_name.is_none()
+        && rule.browser_url.is_none()
+        && rule.pattern.is_none()
+        && rule.start_time.is_none()
+        && rule.end_time.is_none()
+    {
+        return Err(bad_request(
+            "a rule needs at least one condition".to_string(),
+        ));
+    }
 
-//     if !available_devices.contains(&device) {
-//         return Err((
-//             StatusCode::BAD_REQUEST,
-//             JsonResponse(json!({
-//                 "error": format!("device not found: {}", device.name),
-//                 "success": false
-//             })),
-//         ));
-//     }
+    state
+        .db
+        .insert_tag_rule(&rule)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
 
-//     let control = DeviceControl {
-//         device: screenpipe_core::DeviceType::Audio(device.clone()),
-//         is_running: true,
-//         is_paused: false,
-//     };
+async fn set_tag_rule_enabled(
+    state: &AppState,
+    id: i64,
+    enabled: bool,
+) -> Result<JsonResponse<TagRule>, (StatusCode, JsonResponse<Value>)> {
+    match state.db.set_tag_rule_enabled(id, enabled).await {
+        Ok(Some(rule)) => Ok(JsonResponse(rule)),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": format!("tag rule {} not found", id)})),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )),
+    }
+}
 
-//     let _ = state.device_manager.update_device(control).await;
+#[oasgen]
+async fn enable_tag_rule_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<JsonResponse<TagRule>, (StatusCode, JsonResponse<Value>)> {
+    set_tag_rule_enabled(&state, id, true).await
+}
 
-//     Ok(JsonResponse(AudioDeviceControlResponse {
-//         success: true,
-//         message: format!("started audio device: {}", device.name),
-//     }))
-// }
+#[oasgen]
+async fn disable_tag_rule_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<JsonResponse<TagRule>, (StatusCode, JsonResponse<Value>)> {
+    set_tag_rule_enabled(&state, id, false).await
+}
 
-// async fn stop_audio_device(
-//     State(state): State<Arc<AppState>>,
-//     Json(payload): Json<AudioDeviceControlRequest>,
-// ) -> Result<JsonResponse<AudioDeviceControlResponse>, (StatusCode, JsonResponse<Value>)> {
-//     let device = AudioDevice {
-//         name: payload.device_name.clone(),
-//         device_type: payload.device_type.unwrap_or(AudioDeviceType::Input),
-//     };
+#[oasgen]
+async fn delete_tag_rule_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    match state.db.delete_tag_rule(id).await {
+        Ok(true) => Ok(JsonResponse(json!({"success": true}))),
+        Ok(false) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": format!("tag rule {} not found", id)})),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )),
+    }
+}
 
-//     // Validate device exists
-//     let available_devices = list_audio_devices().await.map_err(|e| {
-//         (
-//             StatusCode::INTERNAL_SERVER_ERROR,
-//             JsonResponse(json!({
-//                 "error": format!("failed to list audio devices: {}", e),
-//                 "success": false
-//             })),
-//         )
-//     })?;
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct TagSuggestionsQuery {
+    /// "pending", "accepted" or "rejected", all without one
+    #[serde(default)]
+    status: Option<String>,
+    #[serde(default = "default_limit")]
+    limit: u32,
+    #[serde(default)]
+    offset: u32,
+}
 
-//     if !available_devices.contains(&device) {
-//         return Err((
-//             StatusCode::BAD_REQUEST,
-//             JsonResponse(json!({
-//                 "error": format!("device not found: {}", device.name),
-//                 "success": false
-//             })),
-//         ));
-//     }
+/// Tags the llm of --tag-suggestion-url suggested for untagged content,
+/// newest first.
+#[oasgen]
+async fn list_tag_suggestions_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TagSuggestionsQuery>,
+) -> Result<JsonResponse<Vec<TagSuggestion>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .list_tag_suggestions(query.status.as_deref(), query.limit, query.offset)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
 
-//     let _ = state
-//         .device_manager
-//         .update_device(DeviceControl {
-//             device: screenpipe_core::DeviceType::Audio(device.clone()),
-//             is_running: false,
-//             is_paused: false,
-//         })
-//         .await;
+async fn decide_tag_suggestion(
+    state: &AppState,
+    id: i64,
+    accept: bool,
+) -> Result<JsonResponse<TagSuggestion>, (StatusCode, JsonResponse<Value>)> {
+    match state.db.decide_tag_suggestion(id, accept).await {
+        Ok(Some(suggestion)) => {
+            if accept {
+                let target = format!(
+                    "{}:{} {}",
+                    suggestion.content_type, suggestion.content_id, suggestion.tag
+                );
+                audit(&state.db, "accept_tag_suggestion", &target, None).await;
+            }
+            Ok(JsonResponse(suggestion))
+        }
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": format!("pending tag suggestion {} not found", id)})),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )),
+    }
+}
+
+/// Tags the content of the suggestion with it.
+#[oasgen]
+async fn accept_tag_suggestion_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<JsonResponse<TagSuggestion>, (StatusCode, JsonResponse<Value>)> {
+    decide_tag_suggestion(&state, id, true).await
+}
+
+#[oasgen]
+async fn reject_tag_suggestion_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<JsonResponse<TagSuggestion>, (StatusCode, JsonResponse<Value>)> {
+    decide_tag_suggestion(&state, id, false).await
+}
+
+/// Every tag with how many frames, audio chunks and ui elements have it and
+/// its aliases.
+#[oasgen]
+async fn list_tag_names_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<Vec<TagSummary>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .list_tags()
+        .await
+        .map(JsonResponse)
+        .map_err(|e| read_query_error("failed to list tags", e))
+}
+
+fn tag_update_response(
+    update: Result<TagUpdate, sqlx::Error>,
+    tag: &str,
+    name: &str,
+) -> Result<JsonResponse<TagSummary>, (StatusCode, JsonResponse<Value>)> {
+    match update {
+        Ok(TagUpdate::Updated(summary)) => Ok(JsonResponse(summary)),
+        Ok(TagUpdate::NotFound) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": format!("tag {} not found", tag)})),
+        )),
+        Ok(TagUpdate::NameTaken) => Err((
+            StatusCode::CONFLICT,
+            JsonResponse(json!({"error": format!("{:?} is already a tag or an alias", name)})),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )),
+    }
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct RenameTagRequest {
+    from: String,
+    to: String,
+}
+
+/// Renames a tag on all the content it is on, tag rules adding it included.
+#[oasgen]
+async fn rename_tag_handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<RenameTagRequest>,
+) -> Result<JsonResponse<TagSummary>, (StatusCode, JsonResponse<Value>)> {
+    let update = state.db.rename_tag(&request.from, &request.to).await;
+    if matches!(update, Ok(TagUpdate::Updated(_))) {
+        let target = format!("{} -> {}", request.from, request.to);
+        audit(&state.db, "rename_tag", &target, None).await;
+    }
+    tag_update_response(update, &request.from, &request.to)
+}
 
-//     Ok(JsonResponse(AudioDeviceControlResponse {
-//         success: true,
-//         message: format!("stopped audio device: {}", device.name),
-//     }))
-// }
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct MergeTagsRequest {
+    source: String,
+    target: String,
+}
+
+/// Moves the content tagged `source` to `target`, `source` is kept as an
+/// alias of `target`.
+#[oasgen]
+async fn merge_tags_handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<MergeTagsRequest>,
+) -> Result<JsonResponse<TagSummary>, (StatusCode, JsonResponse<Value>)> {
+    let update = state.db.merge_tags(&request.source, &request.target).await;
+    let target = format!("{} -> {}", request.source, request.target);
+    if matches!(update, Ok(TagUpdate::Updated(_))) {
+        audit(&state.db, "merge_tags", &target, None).await;
+    }
+    // either could be missing
+    let missing = format!("{} or {}", request.source, request.target);
+    tag_update_response(update, &missing, &request.source)
+}
 
 #[derive(OaSchema, Deserialize)]
-struct EventsQuery {
-    images: Option<bool>,
+pub(crate) struct TagAliasRequest {
+    alias: String,
+    tag: String,
 }
 
-#[derive(Debug, OaSchema, Deserialize)]
-struct SemanticSearchQuery {
-    text: String,
-    limit: Option<u32>,
-    threshold: Option<f32>,
+/// Adds another name to a tag, tagging or searching with it uses the tag.
+#[oasgen]
+async fn add_tag_alias_handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<TagAliasRequest>,
+) -> Result<JsonResponse<TagSummary>, (StatusCode, JsonResponse<Value>)> {
+    let update = state.db.add_tag_alias(&request.alias, &request.tag).await;
+    tag_update_response(update, &request.tag, &request.alias)
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct RemoveTagAliasRequest {
+    alias: String,
 }
 
 #[oasgen]
-async fn semantic_search_handler(
-    Query(query): Query<SemanticSearchQuery>,
+async fn remove_tag_alias_handler(
     State(state): State<Arc<AppState>>,
-) -> Result<JsonResponse<Vec<screenpipe_db::OCRResult>>, (StatusCode, JsonResponse<Value>)> {
-    let limit = query.limit.unwrap_or(10);
-    let threshold = query.threshold.unwrap_or(0.3);
+    Json(request): Json<RemoveTagAliasRequest>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    match state.db.remove_tag_alias(&request.alias).await {
+        Ok(true) => Ok(JsonResponse(json!({"success": true}))),
+        Ok(false) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": format!("alias {} not found", request.alias)})),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )),
+    }
+}
 
-    debug!(
-        "semantic search for '{}' with limit {} and threshold {}",
-        query.text, limit, threshold
-    );
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct DeleteTagRequest {
+    tag: String,
+}
 
-    // Generate embedding for search text
-    let embedding = match generate_embedding(&query.text, 0).await {
-        Ok(emb) => emb,
-        Err(e) => {
-            error!("failed to generate embedding: {}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                JsonResponse(json!({"error": format!("failed to generate embedding: {}", e)})),
-            ));
+/// Deletes a tag from all the content it is on, with its aliases. Returns the
+/// tag as it was, so the counts removed.
+#[oasgen]
+async fn delete_tag_handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<DeleteTagRequest>,
+) -> Result<JsonResponse<TagSummary>, (StatusCode, JsonResponse<Value>)> {
+    match state.db.delete_tag(&request.tag).await {
+        Ok(Some(summary)) => {
+            audit(&state.db, "delete_tag", &request.tag, None).await;
+            Ok(JsonResponse(summary))
         }
-    };
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": format!("tag {} not found", request.tag)})),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )),
+    }
+}
 
-    // Search database for similar embeddings
-    match state
+/// Notion databases and Readwise accounts tagged items are pushed to,
+/// without their api keys.
+#[oasgen]
+async fn list_push_destinations_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<Vec<PushDestination>>, (StatusCode, JsonResponse<Value>)> {
+    state
         .db
-        .search_similar_embeddings(embedding, limit, threshold)
+        .list_push_destinations()
         .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+/// Adds a Notion database or Readwise account. Frames and audio tagged with
+/// one of its tags are pushed to it every few minutes, each once.
+#[oasgen]
+async fn create_push_destination_handler(
+    State(state): State<Arc<AppState>>,
+    Json(destination): Json<NewPushDestination>,
+) -> Result<JsonResponse<PushDestination>, (StatusCode, JsonResponse<Value>)> {
+    let bad_request = |error: &str| {
+        (
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": error})),
+        )
+    };
+    if !matches!(destination.kind.as_str(), "notion" | "readwise") {
+        return Err(bad_request("kind must be \"notion\" or \"readwise\""));
+    }
+    if destination.api_key.trim().is_empty() {
+        return Err(bad_request("api_key is required"));
+    }
+    if destination.kind == "notion"
+        && destination
+            .notion_database_id
+            .as_deref()
+            .map_or(true, |id| id.trim().is_empty())
     {
-        Ok(results) => {
-            debug!("found {} similar results", results.len());
-            Ok(JsonResponse(results))
-        }
-        Err(e) => {
-            error!("failed to search embeddings: {}", e);
-            Err((
+        return Err(bad_request("notion_database_id is required for notion"));
+    }
+    if destination.tags.iter().all(|tag| tag.trim().is_empty()) {
+        return Err(bad_request("at least one tag is required"));
+    }
+
+    state
+        .db
+        .insert_push_destination(&destination)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                JsonResponse(json!({"error": format!("failed to search embeddings: {}", e)})),
-            ))
-        }
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+#[oasgen]
+async fn delete_push_destination_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    match state.db.delete_push_destination(id).await {
+        Ok(true) => Ok(JsonResponse(json!({"success": true}))),
+        Ok(false) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": format!("push destination {} not found", id)})),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )),
     }
 }
 
-#[derive(Serialize, OaSchema, Deserialize)]
-pub struct VisionDeviceControlRequest {
-    device_id: u32,
+/// Apps, window titles and browser urls never recorded, from the command
+/// line and added here.
+#[oasgen]
+async fn list_capture_block_rules_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<Vec<CaptureBlockRule>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .list_capture_block_rules()
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
 }
 
-// impl VisionDeviceControlRequest {
-//     pub fn new(device_id: u32) -> Self {
-//         Self { device_id }
-//     }
-// }
+/// Stops recording an app, window title or browser url. Frames and ui text
+/// matching it are dropped by the database from now on, whoever records them.
+#[oasgen]
+async fn create_capture_block_rule_handler(
+    State(state): State<Arc<AppState>>,
+    Json(rule): Json<NewCaptureBlockRule>,
+) -> Result<JsonResponse<CaptureBlockRule>, (StatusCode, JsonResponse<Value>)> {
+    let bad_request = |error: &str| {
+        (
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": error})),
+        )
+    };
+    if !CAPTURE_BLOCK_KINDS.contains(&rule.kind.as_str()) {
+        return Err(bad_request("kind must be \"app\", \"window\" or \"url\""));
+    }
+    if rule.pattern.trim().is_empty() {
+        return Err(bad_request("pattern is required"));
+    }
 
-#[derive(Serialize)]
-pub struct VisionDeviceControlResponse {
-    success: bool,
-    message: String,
+    state
+        .db
+        .insert_capture_block_rule(&rule)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
 }
 
-// async fn start_vision_device(
-//     State(state): State<Arc<AppState>>,
-//     Json(payload): Json<VisionDeviceControlRequest>,
-// ) -> Result<JsonResponse<VisionDeviceControlResponse>, (StatusCode, JsonResponse<Value>)> {
-//     debug!("starting vision device: {}", payload.device_id);
-//     // Validate device exists
-//     let monitors = list_monitors().await;
-//     if !monitors.iter().any(|m| m.id() == payload.device_id) {
-//         return Err((
-//             StatusCode::BAD_REQUEST,
-//             JsonResponse(json!({
-//                 "error": format!("monitor not found: {}", payload.device_id),
-//                 "success": false
-//             })),
-//         ));
-//     }
+#[oasgen]
+async fn delete_capture_block_rule_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    match state.db.delete_capture_block_rule(id).await {
+        Ok(true) => Ok(JsonResponse(json!({"success": true}))),
+        Ok(false) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({
+                "error": format!("no blocklist rule {} added at the api, the ones of the command line can't be deleted", id)
+            })),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )),
+    }
+}
 
-//     debug!("starting vision device: {}", payload.device_id);
-//     let _ = state
-//         .device_manager
-//         .update_device(DeviceControl {
-//             device: screenpipe_core::DeviceType::Vision(payload.device_id),
-//             is_running: true,
-//             is_paused: false,
-//         })
-//         .await;
+fn default_pause_content_type() -> String {
+    "all".to_string()
+}
 
-//     Ok(JsonResponse(VisionDeviceControlResponse {
-//         success: true,
-//         message: format!("started vision device: {}", payload.device_id),
-//     }))
-// }
+fn capture_pauses(
+    state: &AppState,
+) -> Result<&Arc<CapturePauses>, (StatusCode, JsonResponse<Value>)> {
+    state.capture_pauses.as_ref().ok_or_else(|| {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            JsonResponse(json!({"error": "capture can't be paused in this server"})),
+        )
+    })
+}
 
-// async fn stop_vision_device(
-//     State(state): State<Arc<AppState>>,
-//     Json(payload): Json<VisionDeviceControlRequest>,
-// ) -> Result<JsonResponse<VisionDeviceControlResponse>, (StatusCode, JsonResponse<Value>)> {
-//     debug!("stopping vision device: {}", payload.device_id);
-//     // Validate device exists
-//     let monitors = list_monitors().await;
-//     if !monitors.iter().any(|m| m.id() == payload.device_id) {
-//         return Err((
-//             StatusCode::BAD_REQUEST,
-//             JsonResponse(json!({
-//                 "error": format!("monitor not found: {}", payload.device_id),
-//                 "success": false
-//             })),
-//         ));
-//     }
+fn check_pause_content_type(content_type: &str) -> Result<(), (StatusCode, JsonResponse<Value>)> {
+    if CAPTURE_PAUSE_CONTENT_TYPES.contains(&content_type) {
+        Ok(())
+    } else {
+        Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({
+                "error": "content_type must be \"all\", \"vision\", \"audio\" or \"ui\""
+            })),
+        ))
+    }
+}
 
-//     debug!("stopping vision device: {}", payload.device_id);
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct PauseCaptureRequest {
+    /// "all", "vision", "audio" or "ui"
+    #[serde(default = "default_pause_content_type")]
+    content_type: String,
+    /// resume by itself after this long, else only at /capture/resume
+    #[serde(default)]
+    minutes: Option<i64>,
+}
 
-//     let _ = state
-//         .device_manager
-//         .update_device(DeviceControl {
-//             device: screenpipe_core::DeviceType::Vision(payload.device_id),
-//             is_running: false,
-//             is_paused: false,
-//         })
-//         .await;
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct ResumeCaptureRequest {
+    /// "all" ends every pause, including the ones of schedules until their
+    /// hours come again
+    #[serde(default = "default_pause_content_type")]
+    content_type: String,
+}
 
-//     Ok(JsonResponse(VisionDeviceControlResponse {
-//         success: true,
-//         message: format!("stopped vision device: {}", payload.device_id),
-//     }))
-// }
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct CapturePausesQuery {
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
+}
 
-// websocket events handler
-async fn ws_events_handler(ws: WebSocketUpgrade, query: Query<EventsQuery>) -> Response {
-    ws.on_upgrade(|socket| handle_socket(socket, query))
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct NewCapturePauseSchedule {
+    #[serde(default = "default_pause_content_type")]
+    content_type: String,
+    /// local time, e.g. "18:00-09:00" or "sat-sun 00:00-23:59"
+    hours: String,
 }
 
-async fn handle_socket(socket: WebSocket, query: Query<EventsQuery>) {
-    let (mut sender, mut receiver) = socket.split();
+#[oasgen]
+async fn capture_pause_status_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<CapturePauseStatus>, (StatusCode, JsonResponse<Value>)> {
+    Ok(JsonResponse(capture_pauses(&state)?.status()))
+}
 
-    let incoming = tokio::spawn(async move {
-        while let Some(Ok(msg)) = receiver.next().await {
-            if let Message::Text(t) = msg {
-                if let Ok(event) = serde_json::from_str::<ScreenpipeEvent>(&t) {
-                    let _ = send_event(&event.name, event.data);
-                }
-            }
-        }
-    });
-    // Handle the WebSocket connection here
-    // You can add your logic to handle messages, upgrades, etc.
+/// Pauses capture of everything or of one content type, until resumed or
+/// for some minutes. The pause is kept as a gap of the timeline.
+#[oasgen]
+async fn pause_capture_handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<PauseCaptureRequest>,
+) -> Result<JsonResponse<CapturePause>, (StatusCode, JsonResponse<Value>)> {
+    check_pause_content_type(&request.content_type)?;
+    if request.minutes.is_some_and(|minutes| minutes <= 0) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "minutes must be positive"})),
+        ));
+    }
 
-    let outgoing = tokio::spawn(async move {
-        let mut stream = subscribe_to_all_events();
-        loop {
-            tokio::select! {
-                event = stream.next() => {
-                    if let Some(mut event) = event {
-                        if !query.images.unwrap_or(false) && (event.name == "ocr_result" || event.name == "ui_frame") {
-                            if let Some(data) = event.data.as_object_mut() {
-                                data.remove("image");
-                            }
-                        }
-                        if let Err(e) = sender
-                            .send(Message::Text(
-                                serde_json::to_string(&event).unwrap_or_default(),
-                            ))
-                            .await
-                        {
-                            tracing::error!("Failed to send websocket message: {}", e);
-                            break;
-                        }
-                    }
-                }
-                _ = tokio::time::sleep(Duration::from_secs(1)) => {
-                    let _ = sender.send(Message::Ping(vec![])).await;
-                }
-            }
-        }
-    });
+    capture_pauses(&state)?
+        .pause(&request.content_type, request.minutes)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
 
-    // Wait for either task to finish
-    tokio::select! {
-        _ = incoming => {}
-        _ = outgoing => {}
-    }
+/// Ends the running pauses of a content type. Returns the pauses ended.
+#[oasgen]
+async fn resume_capture_handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ResumeCaptureRequest>,
+) -> Result<JsonResponse<Vec<CapturePause>>, (StatusCode, JsonResponse<Value>)> {
+    check_pause_content_type(&request.content_type)?;
 
-    debug!("WebSocket connection closed");
+    capture_pauses(&state)?
+        .resume(&request.content_type)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
 }
 
-async fn ws_health_handler(ws: WebSocketUpgrade, State(state): State<Arc<AppState>>) -> Response {
-    ws.on_upgrade(move |socket| handle_health_socket(socket, state))
+/// When capture was paused in a time range, to tell "recording was off"
+/// from a gap nothing explains.
+#[oasgen]
+async fn list_capture_pauses_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<CapturePausesQuery>,
+) -> Result<JsonResponse<Vec<CapturePause>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .list_capture_pauses(query.start_time, query.end_time)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
 }
 
-async fn handle_health_socket(mut socket: WebSocket, state: Arc<AppState>) {
-    let mut interval = tokio::time::interval(Duration::from_secs(5));
+#[oasgen]
+async fn list_capture_pause_schedules_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<Vec<CapturePauseSchedule>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .list_capture_pause_schedules()
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
 
-    loop {
-        tokio::select! {
-        _ = interval.tick() => {
-            let health_response = health_check(State(state.clone())).await;
-            let health_status = serde_json::to_string(&health_response.0).unwrap_or_default();
-            if let Err(e) = socket.send(Message::Text(health_status)).await {
-                error!("Failed to send health status: {}", e);
-                break;
-            }
-        }
-            result = socket.recv() => {
-                if result.is_none() {
-                    break;
-                }
-            }
-        }
+/// Pauses capture every day or week during some hours, e.g. "18:00-09:00".
+#[oasgen]
+async fn create_capture_pause_schedule_handler(
+    State(state): State<Arc<AppState>>,
+    Json(schedule): Json<NewCapturePauseSchedule>,
+) -> Result<JsonResponse<CapturePauseSchedule>, (StatusCode, JsonResponse<Value>)> {
+    check_pause_content_type(&schedule.content_type)?;
+    if let Err(e) = schedule.hours.parse::<RecordingHours>() {
+        return Err((StatusCode::BAD_REQUEST, JsonResponse(json!({"error": e}))));
     }
+    let pauses = capture_pauses(&state)?;
 
-    debug!("WebSocket connection closed gracefully");
+    let created = state
+        .db
+        .insert_capture_pause_schedule(&schedule.content_type, schedule.hours.trim())
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })?;
+    // pauses now when created during its hours
+    if let Err(e) = pauses.refresh(Utc::now(), Local::now().naive_local()).await {
+        error!("failed to refresh capture pauses: {}", e);
+    }
+    Ok(JsonResponse(created))
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "snake_case")]
-pub struct VideoExportRequest {
-    #[serde(deserialize_with = "deserialize_frame_ids")]
-    frame_ids: Vec<i64>,
-    fps: f64,
+/// Deletes a pause schedule, ending the pause it runs.
+#[oasgen]
+async fn delete_capture_pause_schedule_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    let pauses = capture_pauses(&state)?;
+    match state.db.delete_capture_pause_schedule(id).await {
+        Ok(true) => {
+            if let Err(e) = pauses.refresh(Utc::now(), Local::now().naive_local()).await {
+                error!("failed to refresh capture pauses: {}", e);
+            }
+            Ok(JsonResponse(json!({"success": true})))
+        }
+        Ok(false) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": format!("no pause schedule {}", id)})),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )),
+    }
 }
 
-#[derive(OaSchema, Debug, Deserialize)]
-#[serde(rename_all = "snake_case")]
-struct AudioDeviceControlRequest {
-    device_name: String,
+fn text_content_type(
+    content_type: &str,
+) -> Result<TagContentType, (StatusCode, JsonResponse<Value>)> {
+    match content_type {
+        "vision" => Ok(TagContentType::Vision),
+        "audio" => Ok(TagContentType::Audio),
+        _ => Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "Invalid content type"})),
+        )),
+    }
 }
 
+/// The text the OCR of a frame (vision) or a transcription (audio) was read
+/// as and the post-processing stages that ran on it.
 #[oasgen]
-async fn start_audio_device(
+async fn get_provenance_handler(
     State(state): State<Arc<AppState>>,
-    Json(payload): Json<AudioDeviceControlRequest>,
-) -> Result<Json<AudioDeviceControlResponse>, (StatusCode, JsonResponse<Value>)> {
-    let device_name = payload.device_name.clone();
-    let device: AudioDevice;
-    // todo Better handling error
-    match AudioDevice::from_name(&payload.device_name) {
-        Ok(audio_device) => device = audio_device,
-        Err(e) => {
-            return Err((
-                StatusCode::UNPROCESSABLE_ENTITY,
-                JsonResponse(
-                    json!({"success": false, "message": format!("device {} not found: {}", device_name.clone(), e)}),
-                ),
-            ))
-        }
-    };
-
-    if let Err(e) = state.audio_manager.start_device(&device).await {
-        return Err((
+    Path((content_type, id)): Path<(String, i64)>,
+) -> Result<JsonResponse<TextProvenance>, (StatusCode, JsonResponse<Value>)> {
+    let content_type = text_content_type(&content_type)?;
+    match state.db.get_text_provenance(content_type, id).await {
+        Ok(Some(provenance)) => Ok(JsonResponse(provenance)),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "no post-processing ran on this text"})),
+        )),
+        Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
-            JsonResponse(json!({
-                "success": false,
-                "message": format!("Failed to start recording device {}: {}", device_name.clone(), e)
-            })),
-        ));
+            JsonResponse(json!({"error": e.to_string()})),
+        )),
     }
-
-    Ok(Json(AudioDeviceControlResponse {
-        success: true,
-        message: format!("started device: {}", device_name),
-    }))
 }
 
+/// Runs the current post-processing stages again on the text as read and
+/// stores the result.
 #[oasgen]
-async fn stop_audio_device(
+async fn rerun_pipeline_handler(
     State(state): State<Arc<AppState>>,
-    Json(payload): Json<AudioDeviceControlRequest>,
-) -> Result<Json<AudioDeviceControlResponse>, (StatusCode, JsonResponse<Value>)> {
-    let device_name = payload.device_name.clone();
-
-    if let Err(e) = state.audio_manager.stop_device(&device_name).await {
+    Path((content_type, id)): Path<(String, i64)>,
+) -> Result<JsonResponse<TextProvenance>, (StatusCode, JsonResponse<Value>)> {
+    let content_type = text_content_type(&content_type)?;
+    let Some(pipeline) = &state.text_pipeline else {
         return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            JsonResponse(json!({
-                "success": false,
-                "message": format!("Failed to stop recording device {}: {}", device_name.clone(), e)
-            })),
+            StatusCode::BAD_REQUEST,
+            JsonResponse(
+                json!({"error": "no pipeline, start with --ocr-pipeline or --transcription-pipeline"}),
+            ),
         ));
+    };
+    match pipeline.rerun(&state.db, content_type, id).await {
+        Ok(Some(provenance)) => {
+            audit(
+                &state.db,
+                "rerun_pipeline",
+                &format!("{:?}:{}", content_type, id),
+                None,
+            )
+            .await;
+            Ok(JsonResponse(provenance))
+        }
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "not found"})),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )),
     }
+}
 
-    Ok(Json(AudioDeviceControlResponse {
-        success: true,
-        message: format!("stopped recording audio device: {}", device_name),
-    }))
+/// The content processor plugins loaded and what they did, none unless
+/// started with --enable-plugins.
+#[oasgen]
+async fn list_plugins_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<Vec<PluginStatus>>, (StatusCode, JsonResponse<Value>)> {
+    Ok(JsonResponse(
+        state
+            .plugins
+            .as_ref()
+            .map(|plugins| plugins.status())
+            .unwrap_or_default(),
+    ))
 }
 
-fn deserialize_frame_ids<'de, D>(deserializer: D) -> Result<Vec<i64>, D::Error>
-where
-    D: serde::Deserializer<'de>,
-{
-    let s: String = String::deserialize(deserializer)?;
-    Ok(s.split(',').filter_map(|id| id.parse().ok()).collect())
+fn extension_manager(
+    state: &AppState,
+) -> Result<&Arc<ExtensionManager>, (StatusCode, JsonResponse<Value>)> {
+    state.extensions.as_ref().ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            JsonResponse(
+                json!({"error": "extensions are disabled, start with --enable-extensions"}),
+            ),
+        )
+    })
 }
 
-#[derive(Debug, Serialize)]
-struct ExportProgress {
-    status: String,
-    progress: f32,
-    video_data: Option<Vec<u8>>,
-    error: Option<String>,
+/// The extensions installed and whether they run, none unless started with
+/// --enable-extensions.
+#[oasgen]
+async fn list_extensions_handler(
+    State(state): State<Arc<AppState>>,
+) -> Result<JsonResponse<Vec<ExtensionStatus>>, (StatusCode, JsonResponse<Value>)> {
+    Ok(JsonResponse(
+        state
+            .extensions
+            .as_ref()
+            .map(|extensions| extensions.list())
+            .unwrap_or_default(),
+    ))
+}
+
+#[oasgen]
+async fn get_extension_handler(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<JsonResponse<ExtensionStatus>, (StatusCode, JsonResponse<Value>)> {
+    extension_manager(&state)?
+        .status(&name)
+        .map(JsonResponse)
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                JsonResponse(json!({"error": "extension not found"})),
+            )
+        })
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct InstallExtensionRequest {
+    /// a directory holding an extension.json, copied to the extensions
+    path: String,
 }
 
+/// Installs an extension, disabled until enabled.
 #[oasgen]
-async fn start_audio(
+async fn install_extension_handler(
     State(state): State<Arc<AppState>>,
-) -> Result<Response, (StatusCode, JsonResponse<Value>)> {
-    match state.audio_manager.start().await {
-        Ok(_) => Ok(Response::builder().status(200).body(Body::empty()).unwrap()),
+    JsonResponse(request): JsonResponse<InstallExtensionRequest>,
+) -> Result<JsonResponse<ExtensionStatus>, (StatusCode, JsonResponse<Value>)> {
+    match extension_manager(&state)?.install(std::path::Path::new(&request.path)) {
+        Ok(status) => {
+            audit(&state.db, "install_extension", &status.name, None).await;
+            Ok(JsonResponse(status))
+        }
         Err(e) => Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            JsonResponse(json!({
-                "success": false,
-                "message": format!("Failed to start audio processing: {}", e),
-            })),
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": format!("{:#}", e)})),
         )),
     }
 }
 
-#[oasgen]
-async fn stop_audio(
-    State(state): State<Arc<AppState>>,
-) -> Result<Response, (StatusCode, JsonResponse<Value>)> {
-    match state.audio_manager.stop().await {
-        Ok(_) => Ok(Response::builder().status(200).body(Body::empty()).unwrap()),
+async fn set_extension_enabled(
+    state: &AppState,
+    name: &str,
+    enabled: bool,
+) -> Result<JsonResponse<ExtensionStatus>, (StatusCode, JsonResponse<Value>)> {
+    let extensions = extension_manager(state)?;
+    let result = if enabled {
+        extensions.enable(name)
+    } else {
+        extensions.disable(name)
+    };
+    match result {
+        Ok(Some(status)) => {
+            let action = if enabled {
+                "enable_extension"
+            } else {
+                "disable_extension"
+            };
+            audit(&state.db, action, name, None).await;
+            Ok(JsonResponse(status))
+        }
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "extension not found"})),
+        )),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
-            JsonResponse(json!({
-                "success": false,
-                "message": format!("Failed to start audio processing: {}", e),
-            })),
+            JsonResponse(json!({"error": e.to_string()})),
         )),
     }
 }
 
-pub async fn handle_video_export_ws(
-    ws: WebSocketUpgrade,
+/// Starts an extension, and on each later start of screenpipe.
+#[oasgen]
+async fn enable_extension_handler(
     State(state): State<Arc<AppState>>,
-    Query(payload): Query<VideoExportRequest>,
-) -> impl IntoResponse {
-    if payload.frame_ids.is_empty() {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(json!({ "error": "No valid frame IDs provided" })),
-        )
-            .into_response();
-    }
-
-    ws.on_upgrade(move |socket| async move { handle_video_export(socket, state, payload).await })
+    Path(name): Path<String>,
+) -> Result<JsonResponse<ExtensionStatus>, (StatusCode, JsonResponse<Value>)> {
+    set_extension_enabled(&state, &name, true).await
 }
 
-async fn handle_video_export(
-    mut socket: WebSocket,
-    state: Arc<AppState>,
-    payload: VideoExportRequest,
-) {
-    let temp_dir = match tempfile::tempdir() {
-        Ok(dir) => dir,
-        Err(e) => {
-            let _ = socket
-                .send(Message::Text(
-                    serde_json::to_string(&ExportProgress {
-                        status: "error".to_string(),
-                        progress: 0.0,
-                        video_data: None,
-                        error: Some(format!("Failed to create temp directory: {}", e)),
-                    })
-                    .unwrap(),
-                ))
-                .await;
-            return;
-        }
-    };
-
-    let frames_dir = temp_dir.path().join("frames");
-    if let Err(e) = tokio::fs::create_dir_all(&frames_dir).await {
-        let _ = socket
-            .send(Message::Text(
-                serde_json::to_string(&ExportProgress {
-                    status: "error".to_string(),
-                    progress: 0.0,
-                    video_data: None,
-                    error: Some(format!("Failed to create frames directory: {}", e)),
-                })
-                .unwrap(),
-            ))
-            .await;
-        return;
-    }
-
-    let output_filename = format!(
-        "screenpipe_export_{}.mp4",
-        chrono::Utc::now().format("%Y%m%d_%H%M%S")
-    );
-    let output_path = temp_dir.path().join(&output_filename);
-
-    let mut frames = Vec::new();
-    let mut skipped_frames = Vec::new();
-
-    // Send initial status
-    let _ = socket
-        .send(Message::Text(
-            serde_json::to_string(&ExportProgress {
-                status: "extracting".to_string(),
-                progress: 0.0,
-                video_data: None,
-                error: None,
-            })
-            .unwrap(),
-        ))
-        .await;
-
-    // Process frames
-    for (index, frame_id) in payload.frame_ids.iter().enumerate() {
-        let progress = (index as f32 / payload.frame_ids.len() as f32) * 0.5;
-        let _ = socket
-            .send(Message::Text(
-                serde_json::to_string(&ExportProgress {
-                    status: "extracting".to_string(),
-                    progress,
-                    video_data: None,
-                    error: None,
-                })
-                .unwrap(),
-            ))
-            .await;
+/// Stops an extension until it is enabled again.
+#[oasgen]
+async fn disable_extension_handler(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<JsonResponse<ExtensionStatus>, (StatusCode, JsonResponse<Value>)> {
+    set_extension_enabled(&state, &name, false).await
+}
 
-        match state.db.get_frame(*frame_id).await {
-            Ok(Some((file_path, offset_index))) => {
-                match extract_high_quality_frame(&file_path, offset_index, &frames_dir).await {
-                    Ok(frame_path) => {
-                        frames.push(FrameContent {
-                            file_path: frame_path,
-                            timestamp: Some(chrono::Utc::now()),
-                            window_name: None,
-                            app_name: None,
-                            ocr_results: None,
-                            tags: None,
-                        });
-                    }
-                    Err(e) => {
-                        error!("Failed to extract frame {}: {}", frame_id, e);
-                        skipped_frames.push(*frame_id);
-                    }
-                }
-            }
-            Ok(None) => {
-                error!("Frame {} not found in database", frame_id);
-                skipped_frames.push(*frame_id);
-            }
-            Err(e) => {
-                error!("Database error for frame {}: {}", frame_id, e);
-                skipped_frames.push(*frame_id);
-            }
-        }
-    }
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct TextSpansQuery {
+    #[serde(flatten)]
+    pagination: PaginationQuery,
+    /// substring the span's text must contain
+    #[serde(default)]
+    q: Option<String>,
+    #[serde(default)]
+    start_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    end_time: Option<DateTime<Utc>>,
+    /// only spans that stayed on screen at least this long
+    #[serde(default)]
+    min_duration_secs: f64,
+}
 
-    if frames.is_empty() {
-        let _ = socket
-            .send(Message::Text(
-                serde_json::to_string(&ExportProgress {
-                    status: "error".to_string(),
-                    progress: 0.0,
-                    video_data: None,
-                    error: Some("No valid frames to process".to_string()),
-                })
-                .unwrap(),
-            ))
-            .await;
-        return;
-    }
+/// Lists text that stayed on screen across frames, e.g. to see how long an
+/// error banner was visible.
+#[oasgen]
+async fn list_text_spans_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TextSpansQuery>,
+) -> Result<JsonResponse<Vec<TextSpan>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .search_text_spans(
+            query.q.as_deref(),
+            query.start_time,
+            query.end_time,
+            query.min_duration_secs,
+            query.pagination.limit,
+            query.pagination.offset,
+        )
+        .await
+        .map(JsonResponse)
+        .map_err(|e| read_query_error("failed to list text spans", e))
+}
 
-    // Send encoding status
-    let _ = socket
-        .send(Message::Text(
-            serde_json::to_string(&ExportProgress {
-                status: "encoding".to_string(),
-                progress: 0.5,
-                video_data: None,
-                error: None,
-            })
-            .unwrap(),
-        ))
-        .await;
+#[derive(OaSchema, Deserialize)]
+pub struct ScreenshotRequest {
+    /// monitors to capture, all of them when empty
+    #[serde(default)]
+    pub monitor_ids: Vec<u32>,
+    #[serde(default = "default_include_image")]
+    pub include_image: bool,
+}
 
-    // Create video
-    match write_frames_to_video(&frames, output_path.to_str().unwrap(), payload.fps).await {
-        Ok(_) => match tokio::fs::read(&output_path).await {
-            Ok(video_data) => {
-                let _ = socket
-                    .send(Message::Text(
-                        serde_json::to_string(&ExportProgress {
-                            status: "completed".to_string(),
-                            progress: 1.0,
-                            video_data: Some(video_data),
-                            error: None,
-                        })
-                        .unwrap(),
-                    ))
-                    .await;
-            }
-            Err(e) => {
-                let _ = socket
-                    .send(Message::Text(
-                        serde_json::to_string(&ExportProgress {
-                            status: "error".to_string(),
-                            progress: 1.0,
-                            video_data: None,
-                            error: Some(format!("Failed to read video file: {}", e)),
-                        })
-                        .unwrap(),
-                    ))
-                    .await;
-            }
-        },
-        Err(e) => {
-            let _ = socket
-                .send(Message::Text(
-                    serde_json::to_string(&ExportProgress {
-                        status: "error".to_string(),
-                        progress: 1.0,
-                        video_data: None,
-                        error: Some(format!("Failed to create video: {}", e)),
-                    })
-                    .unwrap(),
-                ))
-                .await;
-        }
-    }
+fn default_include_image() -> bool {
+    true
+}
 
-    // Cleanup
-    if let Err(e) = tokio::fs::remove_dir_all(&temp_dir).await {
-        error!("Failed to clean up temp directory: {}", e);
-    }
+#[derive(OaSchema, Serialize)]
+pub struct ScreenshotResult {
+    pub monitor_id: u32,
+    pub frame_id: i64,
+    pub timestamp: DateTime<Utc>,
+    pub app_name: Option<String>,
+    pub window_name: Option<String>,
+    pub browser_url: Option<String>,
+    pub text: String,
+    pub text_json: String,
+    pub confidence: f64,
+    /// base64 encoded PNG of the whole monitor
+    pub image: Option<String>,
 }
 
+/// Captures the requested monitors right now, stores each capture as a frame
+/// and returns it with its OCR output, without waiting for the recording loop.
+/// The png of the frame is served at /frames/{frame_id} afterwards.
 #[oasgen]
-async fn get_pipe_build_status(
-    Path(pipe_id): Path<String>,
+async fn capture_screenshot_handler(
     State(state): State<Arc<AppState>>,
-) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
-    let pipe_dir = state.screenpipe_dir.join("pipes").join(&pipe_id);
-    let update_temp_dir = std::env::temp_dir().join(format!("{}_update", pipe_id));
-    let temp_dir = pipe_dir.with_extension("_temp");
-    
-    // 1. First check if the update temp directory exists
-    if update_temp_dir.exists() {
-        debug!("Update temp directory exists for pipe: {}", pipe_id);
-        
-        // Check if there's a pipe.json in the update temp directory
-        let update_pipe_json_path = update_temp_dir.join("pipe.json");
-        if update_pipe_json_path.exists() {
-            let pipe_json = tokio::fs::read_to_string(&update_pipe_json_path)
-                .await
-                .map_err(|e| {
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        JsonResponse(json!({"error": format!("Failed to read update temp pipe config: {}", e)})),
-                    )
-                })?;
-
-            let pipe_config: Value = serde_json::from_str(&pipe_json).map_err(|e| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    JsonResponse(json!({"error": format!("Failed to parse update temp pipe config: {}", e)})),
-                )
-            })?;
+    JsonResponse(payload): JsonResponse<ScreenshotRequest>,
+) -> Result<JsonResponse<Vec<ScreenshotResult>>, (StatusCode, JsonResponse<Value>)> {
+    if state.vision_disabled {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "vision is disabled"})),
+        ));
+    }
 
-            // Return the buildStatus if it exists
-            if let Some(build_status) = pipe_config.get("buildStatus") {
-                debug!("Found build status in update temp directory for pipe: {}", pipe_id);
-                return Ok(JsonResponse(build_status.clone()));
+    let monitors = if payload.monitor_ids.is_empty() {
+        list_monitors().await
+    } else {
+        let mut monitors = Vec::with_capacity(payload.monitor_ids.len());
+        for monitor_id in &payload.monitor_ids {
+            match get_monitor_by_id(*monitor_id).await {
+                Some(monitor) => monitors.push(monitor),
+                None => {
+                    return Err((
+                        StatusCode::NOT_FOUND,
+                        JsonResponse(json!({"error": format!("monitor {} not found", monitor_id)})),
+                    ))
+                }
             }
         }
-        
-        // If no buildStatus found in update temp directory, return a default in_progress status
-        return Ok(JsonResponse(json!({
-            "status": "in_progress",
-            "step": "downloading",
-            "message": "Update in progress"
-        })));
-    }
-
-    // 2. Check if the pipe directory exists
-    if pipe_dir.exists() {
-        // Then check if there's a pipe.json file
-        let pipe_json_path = pipe_dir.join("pipe.json");
-        if pipe_json_path.exists() {
-            let pipe_json = tokio::fs::read_to_string(&pipe_json_path)
-                .await
-                .map_err(|e| {
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        JsonResponse(json!({"error": format!("Failed to read pipe config: {}", e)})),
-                    )
-                })?;
+        monitors
+    };
 
-            let pipe_config: Value = serde_json::from_str(&pipe_json).map_err(|e| {
+    let mut results = Vec::with_capacity(monitors.len());
+    for monitor in monitors {
+        let monitor_id = monitor.id();
+        let result = capture_screenshot(&state, &monitor, payload.include_image)
+            .await
+            .map_err(|e| {
+                error!("failed to capture monitor {}: {}", monitor_id, e);
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
-                    JsonResponse(json!({"error": format!("Failed to parse pipe config: {}", e)})),
+                    JsonResponse(json!({
+                        "error": format!("failed to capture monitor {}: {}", monitor_id, e)
+                    })),
                 )
             })?;
+        results.push(result);
+    }
 
-            // Check if there's a buildStatus field
-            if let Some(build_status) = pipe_config.get("buildStatus") {
-                // Return the build status directly
-                return Ok(JsonResponse(build_status.clone()));
-            }
-        } else {
-            // Pipe directory exists but pipe.json doesn't exist yet
-            // This likely means the pipe is still being created
-            debug!("Pipe directory exists but pipe.json not found for pipe: {}", pipe_id);
-            return Ok(JsonResponse(json!({
-                "status": "in_progress",
-                "step": "creating_config",
-                "message": "Creating pipe configuration"
-            })));
-        }
+    Ok(JsonResponse(results))
+}
+
+/// Stores one capture as a single-image chunk of its own device, so frames of
+/// the recording loop keep their offsets in the monitor's video chunk.
+async fn capture_screenshot(
+    state: &AppState,
+    monitor: &SafeMonitor,
+    include_image: bool,
+) -> anyhow::Result<ScreenshotResult> {
+    let monitor_id = monitor.id();
+    let window_filters = WindowFilters::new(&state.ignored_windows, &state.included_windows);
+    let capture = capture_now(
+        monitor,
+        &window_filters,
+        &state.ocr_engine,
+        &state.languages,
+    )
+    .await?;
+
+    let timestamp = Utc::now();
+    let mut png = Vec::new();
+    capture
+        .image
+        .write_to(&mut std::io::Cursor::new(&mut png), ImageFormat::Png)?;
+    let file_path = state.screenpipe_dir.join("data").join(format!(
+        "snapshot_monitor_{}_{}.png",
+        monitor_id,
+        timestamp.format("%Y-%m-%d_%H-%M-%S%.3f")
+    ));
+    tokio::fs::write(&file_path, &png).await?;
+
+    let device_name = format!("snapshot_monitor_{}", monitor_id);
+    state
+        .db
+        .insert_video_chunk(&file_path.to_string_lossy(), &device_name)
+        .await?;
+
+    let windows = &capture.window_ocr_results;
+    let focused = windows.iter().find(|w| w.focused).or(windows.first());
+    let frame_id = state
+        .db
+        .insert_frame(
+            &device_name,
+            Some(timestamp),
+            focused.and_then(|w| w.browser_url.as_deref()),
+            focused.map(|w| w.app_name.as_str()),
+            focused.map(|w| w.window_name.as_str()),
+            focused.is_some_and(|w| w.focused),
+        )
+        .await?;
+
+    let text = windows
+        .iter()
+        .map(|w| w.text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let text_json = serde_json::to_string(
+        &windows
+            .iter()
+            .flat_map(|w| w.text_json.iter())
+            .collect::<Vec<_>>(),
+    )?;
+    let confidence = if windows.is_empty() {
+        0.0
     } else {
-        // If pipe directory doesn't exist, check temp directory
-        if temp_dir.exists() {
-            let temp_pipe_json = temp_dir.join("pipe.json");
-            if temp_pipe_json.exists() {
-                let pipe_json = tokio::fs::read_to_string(&temp_pipe_json)
-                    .await
-                    .map_err(|e| {
-                        (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            JsonResponse(
-                                json!({"error": format!("Failed to read temp pipe config: {}", e)}),
-                            ),
-                        )
-                    })?;
+        windows.iter().map(|w| w.confidence).sum::<f64>() / windows.len() as f64
+    };
 
-                let pipe_config: Value = serde_json::from_str(&pipe_json).map_err(|e| {
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        JsonResponse(
-                            json!({"error": format!("Failed to parse temp pipe config: {}", e)}),
-                        ),
-                    )
-                })?;
+    state
+        .db
+        .insert_ocr_text(
+            frame_id,
+            &text,
+            &text_json,
+            Arc::new((*state.ocr_engine).clone().into()),
+        )
+        .await?;
+    debug!(
+        "captured monitor {} on demand as frame {}",
+        monitor_id, frame_id
+    );
 
-                debug!("Found build status in temp directory for pipe: {}", pipe_id);
-                if let Some(build_status) = pipe_config.get("buildStatus") {
-                    return Ok(JsonResponse(build_status.clone()));
-                }
-            }
-            
-            // Temp directory exists but no pipe.json or no buildStatus
-            return Ok(JsonResponse(json!({
-                "status": "in_progress",
-                "step": "initializing",
-                "message": "Initializing pipe"
-            })));
+    Ok(ScreenshotResult {
+        monitor_id,
+        frame_id,
+        timestamp,
+        app_name: focused.map(|w| w.app_name.clone()),
+        window_name: focused.map(|w| w.window_name.clone()),
+        browser_url: focused.and_then(|w| w.browser_url.clone()),
+        text,
+        text_json,
+        confidence,
+        image: include_image.then(|| general_purpose::STANDARD.encode(&png)),
+    })
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct ListMeetingsQuery {
+    #[serde(flatten)]
+    pagination: PaginationQuery,
+    #[serde(default)]
+    start_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    end_time: Option<DateTime<Utc>>,
+}
+
+#[oasgen]
+async fn list_meetings_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListMeetingsQuery>,
+) -> Result<JsonResponse<Vec<Meeting>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .list_meetings(
+            query.start_time,
+            query.end_time,
+            query.pagination.limit,
+            query.pagination.offset,
+        )
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+#[derive(OaSchema, Serialize)]
+pub struct MeetingDetails {
+    #[serde(flatten)]
+    pub meeting: Meeting,
+    /// none while the meeting goes on
+    pub duration_secs: Option<i64>,
+    pub participants: Vec<MeetingParticipant>,
+}
+
+fn meeting_db_error(e: sqlx::Error) -> (StatusCode, JsonResponse<Value>) {
+    match e {
+        sqlx::Error::RowNotFound => (
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": "meeting not found"})),
+        ),
+        e => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        ),
+    }
+}
+
+#[oasgen]
+async fn get_meeting_handler(
+    State(state): State<Arc<AppState>>,
+    Path(meeting_id): Path<i64>,
+) -> Result<JsonResponse<MeetingDetails>, (StatusCode, JsonResponse<Value>)> {
+    let meeting = state
+        .db
+        .get_meeting(meeting_id)
+        .await
+        .map_err(meeting_db_error)?;
+    let participants = state
+        .db
+        .get_meeting_participants(meeting_id)
+        .await
+        .map_err(meeting_db_error)?;
+
+    Ok(JsonResponse(MeetingDetails {
+        duration_secs: meeting
+            .end_time
+            .map(|end| (end - meeting.start_time).num_seconds()),
+        meeting,
+        participants,
+    }))
+}
+
+/// What was said during the meeting, oldest first.
+#[oasgen]
+async fn get_meeting_transcript_handler(
+    State(state): State<Arc<AppState>>,
+    Path(meeting_id): Path<i64>,
+) -> Result<JsonResponse<Vec<TranscriptLine>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .get_meeting_transcript(meeting_id)
+        .await
+        .map(JsonResponse)
+        .map_err(meeting_db_error)
+}
+
+/// The meeting with its participants and transcript as Markdown.
+async fn meeting_export_handler(
+    State(state): State<Arc<AppState>>,
+    Path(meeting_id): Path<i64>,
+) -> Response {
+    match export_meeting(&state.db, meeting_id).await {
+        Ok(markdown) => {
+            ([(CONTENT_TYPE, "text/markdown; charset=utf-8")], markdown).into_response()
         }
-        
-        // If neither pipe directory nor temp directory exists, return not found
+        Err(e) => meeting_db_error(e).into_response(),
+    }
+}
+
+#[derive(OaSchema, Deserialize)]
+pub struct DetectMeetingsRequest {
+    pub start_time: DateTime<Utc>,
+    /// defaults to now
+    #[serde(default)]
+    pub end_time: Option<DateTime<Utc>>,
+}
+
+/// Detects the meetings of a past range from what was recorded, e.g. to go
+/// over what was stored before detection was enabled. Returns the meetings
+/// detected or extended.
+#[oasgen]
+async fn detect_meetings_handler(
+    State(state): State<Arc<AppState>>,
+    JsonResponse(request): JsonResponse<DetectMeetingsRequest>,
+) -> Result<JsonResponse<Vec<Meeting>>, (StatusCode, JsonResponse<Value>)> {
+    let end_time = request.end_time.unwrap_or_else(Utc::now);
+    if end_time <= request.start_time {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({"error": "end_time must be after start_time"})),
+        ));
+    }
+    state
+        .db
+        .detect_meetings(request.start_time, end_time)
+        .await
+        .map(JsonResponse)
+        .map_err(meeting_db_error)
+}
+
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct WorkSessionsQuery {
+    start_time: DateTime<Utc>,
+    /// defaults to now
+    #[serde(default)]
+    end_time: Option<DateTime<Utc>>,
+}
+
+/// The work sessions overlapping the range, oldest first.
+#[oasgen]
+async fn list_work_sessions_handler(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<WorkSessionsQuery>,
+) -> Result<JsonResponse<Vec<WorkSession>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .get_work_sessions(query.start_time, query.end_time.unwrap_or_else(Utc::now))
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
+
+#[oasgen]
+async fn get_work_session_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<JsonResponse<WorkSession>, (StatusCode, JsonResponse<Value>)> {
+    match state.db.get_work_session(id).await {
+        Ok(Some(session)) => Ok(JsonResponse(session)),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": format!("work session {} not found", id)})),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )),
+    }
+}
+
+#[derive(OaSchema, Deserialize)]
+pub struct DetectWorkSessionsRequest {
+    pub start_time: DateTime<Utc>,
+    /// defaults to now
+    #[serde(default)]
+    pub end_time: Option<DateTime<Utc>>,
+    /// minutes without anything recorded that end a session
+    #[serde(default = "default_break_minutes")]
+    pub break_minutes: u64,
+}
+
+fn default_break_minutes() -> u64 {
+    DEFAULT_BREAK_MINUTES
+}
+
+/// Detects the work sessions of a range in place of the ones detected
+/// before, e.g. for what was recorded before detection was enabled.
+#[oasgen]
+async fn detect_work_sessions_handler(
+    State(state): State<Arc<AppState>>,
+    JsonResponse(request): JsonResponse<DetectWorkSessionsRequest>,
+) -> Result<JsonResponse<Vec<WorkSession>>, (StatusCode, JsonResponse<Value>)> {
+    let end_time = request.end_time.unwrap_or_else(Utc::now);
+    if end_time <= request.start_time || request.break_minutes == 0 {
         return Err((
-            StatusCode::NOT_FOUND,
-            JsonResponse(json!({"error": "Pipe not found"})),
+            StatusCode::BAD_REQUEST,
+            JsonResponse(json!({
+                "error": "end_time must be after start_time and break_minutes positive"
+            })),
         ));
     }
+    state
+        .db
+        .detect_work_sessions(
+            request.start_time,
+            end_time,
+            chrono::Duration::minutes(request.break_minutes as i64),
+        )
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
+}
 
-    // If we get here, there's a pipe.json but no buildStatus field
-    Ok(JsonResponse(json!(null)))
+#[derive(OaSchema, Deserialize)]
+pub(crate) struct CalendarHintsQuery {
+    start_time: DateTime<Utc>,
+    end_time: DateTime<Utc>,
 }
 
 #[oasgen]
-async fn keyword_search_handler(
-    Query(query): Query<KeywordSearchRequest>,
+async fn list_calendar_hints_handler(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<Vec<SearchMatch>>, (StatusCode, JsonResponse<Value>)> {
-    let matches = state
+    Query(query): Query<CalendarHintsQuery>,
+) -> Result<JsonResponse<Vec<CalendarHint>>, (StatusCode, JsonResponse<Value>)> {
+    state
         .db
-        .search_with_text_positions(
-            &query.query,
-            query.limit,
-            query.offset,
-            query.start_time,
-            query.end_time,
-            query.fuzzy_match,
-            query.order,
-            query.app_names,
-        )
+        .list_calendar_hints(query.start_time, query.end_time)
         .await
+        .map(JsonResponse)
         .map_err(|e| {
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 JsonResponse(json!({"error": e.to_string()})),
             )
-        })?;
+        })
+}
 
-    Ok(JsonResponse(matches))
+/// A calendar event meetings detected over it are named after.
+#[oasgen]
+async fn add_calendar_hint_handler(
+    State(state): State<Arc<AppState>>,
+    JsonResponse(hint): JsonResponse<NewCalendarHint>,
+) -> Result<JsonResponse<CalendarHint>, (StatusCode, JsonResponse<Value>)> {
+    if hint.title.trim().is_empty() || hint.end_time <= hint.start_time {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            JsonResponse(
+                json!({"error": "a calendar hint needs a title and to end after it starts"}),
+            ),
+        ));
+    }
+    state
+        .db
+        .insert_calendar_hint(&hint)
+        .await
+        .map(JsonResponse)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                JsonResponse(json!({"error": e.to_string()})),
+            )
+        })
 }
 
-fn from_comma_separated_string<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let s: Option<String> = Option::deserialize(deserializer)?;
-    Ok(s.map(|s| s.split(',').map(String::from).collect()))
+#[oasgen]
+async fn delete_calendar_hint_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<i64>,
+) -> Result<JsonResponse<Value>, (StatusCode, JsonResponse<Value>)> {
+    match state.db.delete_calendar_hint(id).await {
+        Ok(true) => Ok(JsonResponse(json!({"success": true}))),
+        Ok(false) => Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": format!("calendar hint {} not found", id)})),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            JsonResponse(json!({"error": e.to_string()})),
+        )),
+    }
+}
+
+/// Slides shown during the meeting in deck order. The image of each slide is
+/// served by `/frames/:frame_id`.
+#[oasgen]
+async fn get_meeting_slides_handler(
+    State(state): State<Arc<AppState>>,
+    Path(meeting_id): Path<i64>,
+) -> Result<JsonResponse<Vec<MeetingSlide>>, (StatusCode, JsonResponse<Value>)> {
+    state
+        .db
+        .get_meeting(meeting_id)
+        .await
+        .map_err(meeting_db_error)?;
+    state
+        .db
+        .get_meeting_slides(meeting_id)
+        .await
+        .map(JsonResponse)
+        .map_err(meeting_db_error)
 }
 
 #[derive(OaSchema, Deserialize)]
-pub struct KeywordSearchRequest {
-    query: String,
-    #[serde(default = "default_limit")]
-    limit: u32,
-    #[serde(default)]
-    offset: u32,
-    #[serde(default)]
-    start_time: Option<DateTime<Utc>>,
-    #[serde(default)]
-    end_time: Option<DateTime<Utc>>,
-    #[serde(default)]
-    fuzzy_match: bool,
-    #[serde(default)]
-    order: Order,
-    #[serde(default)]
-    #[serde(deserialize_with = "from_comma_separated_string")]
-    app_names: Option<Vec<String>>,
+pub struct LinkParticipantSpeakerRequest {
+    pub speaker_id: i64,
 }
 
 #[oasgen]
-pub async fn get_frame_data(
+async fn link_participant_speaker_handler(
     State(state): State<Arc<AppState>>,
-    Path(frame_id): Path<i64>,
-) -> Result<Response<Body>, (StatusCode, JsonResponse<Value>)> {
-    let start_time = Instant::now();
+    Path((meeting_id, participant_id)): Path<(i64, i64)>,
+    JsonResponse(payload): JsonResponse<LinkParticipantSpeakerRequest>,
+) -> Result<JsonResponse<MeetingParticipant>, (StatusCode, JsonResponse<Value>)> {
+    let participants = state
+        .db
+        .get_meeting_participants(meeting_id)
+        .await
+        .map_err(meeting_db_error)?;
+    if !participants.iter().any(|p| p.id == participant_id) {
+        return Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": format!(
+                "participant {} not found in meeting {}",
+                participant_id, meeting_id
+            )})),
+        ));
+    }
 
-    match timeout(Duration::from_secs(5), async {
-        // Try to get frame from cache if enabled
-        if let Some(cache) = &state.frame_image_cache {
-            let cache_result = cache.try_lock();
-            match cache_result {
-                Ok(mut cache) => {
-                    if let Some((file_path, timestamp)) = cache.get(&frame_id) {
-                        if timestamp.elapsed() < Duration::from_secs(300) {
-                            debug!(
-                                "Cache hit for frame_id: {}. Retrieved in {:?}",
-                                frame_id,
-                                start_time.elapsed()
-                            );
-                            return serve_file(file_path).await;
-                        }
-                        cache.pop(&frame_id);
-                    }
-                }
-                Err(_) => {
-                    debug!("Cache lock contention for frame_id: {}", frame_id);
-                }
-            }
-        }
+    if state
+        .db
+        .get_speaker_by_id(payload.speaker_id)
+        .await
+        .is_err()
+    {
+        return Err((
+            StatusCode::NOT_FOUND,
+            JsonResponse(json!({"error": format!("speaker {} not found", payload.speaker_id)})),
+        ));
+    }
 
-        // If not in cache or cache disabled, get from database
-        match state.db.get_frame(frame_id).await {
-            Ok(Some((file_path, offset_index))) => {
-                match extract_frame_from_video(&file_path, offset_index).await {
-                    Ok(frame_path) => {
-                        // Store in cache if enabled and we can get the lock
-                        if let Some(cache) = &state.frame_image_cache {
-                            if let Ok(mut cache) = cache.try_lock() {
-                                cache.put(frame_id, (frame_path.clone(), Instant::now()));
-                            }
-                        }
+    state
+        .db
+        .link_participant_speaker(participant_id, payload.speaker_id)
+        .await
+        .map(JsonResponse)
+        .map_err(meeting_db_error)
+}
 
-                        debug!("Frame {} extracted in {:?}", frame_id, start_time.elapsed());
-                        serve_file(&frame_path).await
-                    }
-                    Err(e) => {
-                        error!("Failed to extract frame {}: {}", frame_id, e);
-                        Err((
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            JsonResponse(json!({
-                                "error": format!("Failed to extract frame: {}", e),
-                                "frame_id": frame_id,
-                                "file_path": file_path
-                            })),
-                        ))
-                    }
-                }
-            }
-            Ok(None) => Err((
-                StatusCode::NOT_FOUND,
-                JsonResponse(json!({
-                    "error": "Frame not found",
-                    "frame_id": frame_id
-                })),
-            )),
-            Err(e) => Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                JsonResponse(json!({
-                    "error": format!("Database error: {}", e),
-                    "frame_id": frame_id
-                })),
-            )),
-        }
-    })
-    .await
-    {
-        Ok(result) => result,
-        Err(_) => {
-            error!("Request timeout for frame_id: {}", frame_id);
-            Err((
-                StatusCode::REQUEST_TIMEOUT,
-                JsonResponse(json!({
-                    "error": "Request timed out",
-                    "frame_id": frame_id
-                })),
+#[derive(OaSchema, Deserialize)]
+pub struct ReocrRequest {
+    /// explicit frames to re-process, takes precedence over the time range
+    pub frame_ids: Option<Vec<i64>>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    #[serde(default = "default_reocr_limit")]
+    pub limit: u32,
+}
+
+fn default_reocr_limit() -> u32 {
+    100
+}
+
+#[derive(OaSchema, Serialize)]
+pub struct ReocrFrameResult {
+    pub frame_id: i64,
+    pub success: bool,
+    pub text: Option<String>,
+    pub confidence: Option<f64>,
+    pub error: Option<String>,
+}
+
+#[derive(OaSchema, Serialize)]
+pub struct ReocrResponse {
+    pub ocr_engine: String,
+    pub results: Vec<ReocrFrameResult>,
+}
+
+#[oasgen]
+async fn reocr_frames_handler(
+    State(state): State<Arc<AppState>>,
+    JsonResponse(payload): JsonResponse<ReocrRequest>,
+) -> Result<JsonResponse<ReocrResponse>, (StatusCode, JsonResponse<Value>)> {
+    let frame_ids = match (payload.frame_ids, payload.start_time, payload.end_time) {
+        (Some(ids), _, _) => ids,
+        (None, Some(start), Some(end)) => state
+            .db
+            .get_frame_ids_in_range(start, end, payload.limit)
+            .await
+            .map_err(|e| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    JsonResponse(json!({"error": format!("failed to list frames: {}", e)})),
+                )
+            })?,
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                JsonResponse(
+                    json!({"error": "either frame_ids or start_time and end_time are required"}),
+                ),
             ))
         }
+    };
+
+    let mut results = Vec::with_capacity(frame_ids.len());
+    for frame_id in frame_ids {
+        match reocr_frame(&state, frame_id).await {
+            Ok((text, confidence)) => results.push(ReocrFrameResult {
+                frame_id,
+                success: true,
+                text: Some(text),
+                confidence,
+                error: None,
+            }),
+            Err(e) => {
+                error!("failed to re-ocr frame {}: {}", frame_id, e);
+                results.push(ReocrFrameResult {
+                    frame_id,
+                    success: false,
+                    text: None,
+                    confidence: None,
+                    error: Some(e.to_string()),
+                });
+            }
+        }
     }
+
+    Ok(JsonResponse(ReocrResponse {
+        ocr_engine: format!("{:?}", state.ocr_engine),
+        results,
+    }))
+}
+
+/// Decodes a stored frame from its video chunk, runs the configured OCR engine
+/// on it and replaces the frame's ocr_text row with the new output.
+async fn reocr_frame(state: &AppState, frame_id: i64) -> anyhow::Result<(String, Option<f64>)> {
+    let (file_path, offset_index) = state
+        .db
+        .get_frame(frame_id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("frame not found"))?;
+
+    let frame_path = extract_frame_from_video(&file_path, offset_index).await?;
+    let image = image::open(&frame_path)?;
+
+    let (text, text_json, confidence) =
+        perform_ocr_with_engine(&state.ocr_engine, &image, state.languages.clone())
+            .await
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
+    state
+        .db
+        .replace_ocr_text(
+            frame_id,
+            &text,
+            &text_json,
+            Arc::new((*state.ocr_engine).clone().into()),
+        )
+        .await?;
+
+    debug!("re-ocr'd frame {} with {:?}", frame_id, state.ocr_engine);
+    Ok((text, confidence))
 }
 
 async fn serve_file(path: &str) -> Result<Response, (StatusCode, JsonResponse<Value>)> {
+    serve_file_as(path, "image/jpeg").await
+}
+
+async fn serve_file_as(
+    path: &str,
+    content_type: &str,
+) -> Result<Response, (StatusCode, JsonResponse<Value>)> {
     match File::open(path).await {
         Ok(file) => {
             let stream = ReaderStream::new(file);
             let body = Body::from_stream(stream);
 
             let response = Response::builder()
-                .header("content-type", "image/jpeg")
+                .header("content-type", content_type)
                 .header("cache-control", "public, max-age=604800") // Cache for 7 days
                 .body(body)
                 .map_err(|e| {
@@ -0,0 +1,254 @@
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use oasgen::OaSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::{debug, info};
+
+// comparisons kept for the report
+const MAX_RECENT_COMPARISONS: usize = 50;
+// ids listed in a diff
+const MAX_DIFF_IDS: usize = 10;
+
+/// Search implementation run in the shadow of `/search` on a sample of
+/// requests. Its results are compared with the ones returned, never
+/// returned themselves.
+#[derive(OaSchema, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ShadowCandidate {
+    /// decayed relevance ranking, for requests ranked the standard way
+    Decayed,
+    /// the single ordering of all kinds of content of `/v2/search`
+    V2,
+}
+
+impl fmt::Display for ShadowCandidate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ShadowCandidate::Decayed => "decayed",
+            ShadowCandidate::V2 => "v2",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShadowConfig {
+    pub candidate: ShadowCandidate,
+    /// share of the requests shadowed, between 0 and 1
+    pub sample_rate: f64,
+}
+
+/// How the page of the candidate compared with the page returned, items
+/// named by their `/v2/search` id.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize)]
+pub struct ShadowComparison {
+    pub query: String,
+    pub timestamp: DateTime<Utc>,
+    pub primary_ms: f64,
+    pub candidate_ms: f64,
+    pub primary_count: usize,
+    pub candidate_count: usize,
+    /// share of the items of both pages found in both, 1 when they hold
+    /// the same items
+    pub overlap: f64,
+    /// first position where the pages differ, `None` when they are the same
+    pub first_difference: Option<usize>,
+    /// items returned that the candidate didn't find
+    pub missing: Vec<String>,
+    /// items the candidate found that weren't returned
+    pub extra: Vec<String>,
+    /// why the candidate failed, if it did
+    pub error: Option<String>,
+}
+
+impl ShadowComparison {
+    pub fn is_identical(&self) -> bool {
+        self.error.is_none() && self.first_difference.is_none()
+    }
+}
+
+/// Compares the page returned for `query` with the one of the candidate.
+pub fn compare_pages(
+    query: &str,
+    primary: &[String],
+    candidate: Result<Vec<String>, String>,
+    primary_time: Duration,
+    candidate_time: Duration,
+) -> ShadowComparison {
+    let mut comparison = ShadowComparison {
+        query: query.to_string(),
+        timestamp: Utc::now(),
+        primary_ms: primary_time.as_secs_f64() * 1000.0,
+        candidate_ms: candidate_time.as_secs_f64() * 1000.0,
+        primary_count: primary.len(),
+        candidate_count: 0,
+        overlap: 0.0,
+        first_difference: None,
+        missing: Vec::new(),
+        extra: Vec::new(),
+        error: None,
+    };
+    let candidate = match candidate {
+        Ok(candidate) => candidate,
+        Err(e) => {
+            comparison.error = Some(e);
+            return comparison;
+        }
+    };
+
+    let primary_ids: HashSet<&String> = primary.iter().collect();
+    let candidate_ids: HashSet<&String> = candidate.iter().collect();
+    let union = primary_ids.union(&candidate_ids).count();
+    comparison.candidate_count = candidate.len();
+    comparison.overlap = if union == 0 {
+        1.0
+    } else {
+        primary_ids.intersection(&candidate_ids).count() as f64 / union as f64
+    };
+    comparison.first_difference = primary
+        .iter()
+        .zip(&candidate)
+        .position(|(a, b)| a != b)
+        // one page is the start of the other
+        .or((primary.len() != candidate.len()).then_some(primary.len().min(candidate.len())));
+    comparison.missing = primary
+        .iter()
+        .filter(|id| !candidate_ids.contains(id))
+        .take(MAX_DIFF_IDS)
+        .cloned()
+        .collect();
+    comparison.extra = candidate
+        .iter()
+        .filter(|id| !primary_ids.contains(id))
+        .take(MAX_DIFF_IDS)
+        .cloned()
+        .collect();
+    comparison
+}
+
+/// Comparisons since the server started.
+#[derive(OaSchema, Debug, Serialize, Deserialize)]
+pub struct ShadowReport {
+    pub candidate: ShadowCandidate,
+    pub sample_rate: f64,
+    pub sampled: u64,
+    pub identical: u64,
+    pub errors: u64,
+    /// mean overlap of the pages of the candidates that didn't fail
+    pub mean_overlap: f64,
+    pub mean_primary_ms: f64,
+    pub mean_candidate_ms: f64,
+    /// most recent first
+    pub recent: Vec<ShadowComparison>,
+}
+
+#[derive(Default)]
+struct ShadowStats {
+    sampled: u64,
+    identical: u64,
+    errors: u64,
+    overlap_sum: f64,
+    primary_ms_sum: f64,
+    candidate_ms_sum: f64,
+    recent: VecDeque<ShadowComparison>,
+}
+
+/// Picks the requests to shadow and keeps how the candidate compared.
+pub struct ShadowRunner {
+    config: ShadowConfig,
+    requests: AtomicU64,
+    stats: Mutex<ShadowStats>,
+}
+
+impl ShadowRunner {
+    pub fn new(config: ShadowConfig) -> Self {
+        ShadowRunner {
+            config,
+            requests: AtomicU64::new(0),
+            stats: Mutex::new(ShadowStats::default()),
+        }
+    }
+
+    pub fn candidate(&self) -> ShadowCandidate {
+        self.config.candidate
+    }
+
+    /// Whether to shadow this request. Sampled requests are spread evenly,
+    /// e.g. every 10th at a rate of 0.1.
+    pub fn sample(&self) -> bool {
+        let rate = self.config.sample_rate.clamp(0.0, 1.0);
+        let n = self.requests.fetch_add(1, Ordering::Relaxed) as f64;
+        ((n + 1.0) * rate).floor() > (n * rate).floor()
+    }
+
+    pub fn record(&self, comparison: ShadowComparison) {
+        let candidate = self.config.candidate;
+        if let Some(error) = &comparison.error {
+            info!(
+                "shadow {} search failed for {:?}: {}",
+                candidate, comparison.query, error
+            );
+        } else if comparison.is_identical() {
+            debug!(
+                "shadow {} search matched for {:?}: {:.1}ms vs {:.1}ms",
+                candidate, comparison.query, comparison.primary_ms, comparison.candidate_ms
+            );
+        } else {
+            info!(
+                "shadow {} search differs for {:?}: overlap {:.2}, first difference at {:?}, missing {:?}, extra {:?}, {:.1}ms vs {:.1}ms",
+                candidate,
+                comparison.query,
+                comparison.overlap,
+                comparison.first_difference,
+                comparison.missing,
+                comparison.extra,
+                comparison.primary_ms,
+                comparison.candidate_ms
+            );
+        }
+
+        let mut stats = self.stats.lock().unwrap();
+        stats.sampled += 1;
+        if comparison.error.is_some() {
+            stats.errors += 1;
+        } else {
+            stats.overlap_sum += comparison.overlap;
+            stats.candidate_ms_sum += comparison.candidate_ms;
+        }
+        if comparison.is_identical() {
+            stats.identical += 1;
+        }
+        stats.primary_ms_sum += comparison.primary_ms;
+        if stats.recent.len() == MAX_RECENT_COMPARISONS {
+            stats.recent.pop_back();
+        }
+        stats.recent.push_front(comparison);
+    }
+
+    pub fn report(&self) -> ShadowReport {
+        let stats = self.stats.lock().unwrap();
+        let completed = stats.sampled - stats.errors;
+        let mean = |sum: f64, count: u64| {
+            if count == 0 {
+                0.0
+            } else {
+                sum / count as f64
+            }
+        };
+        ShadowReport {
+            candidate: self.config.candidate,
+            sample_rate: self.config.sample_rate,
+            sampled: stats.sampled,
+            identical: stats.identical,
+            errors: stats.errors,
+            mean_overlap: mean(stats.overlap_sum, completed),
+            mean_primary_ms: mean(stats.primary_ms_sum, stats.sampled),
+            mean_candidate_ms: mean(stats.candidate_ms_sum, completed),
+            recent: stats.recent.iter().cloned().collect(),
+        }
+    }
+}
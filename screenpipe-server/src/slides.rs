@@ -0,0 +1,87 @@
+use screenpipe_events::MEETING_APPS;
+use std::collections::HashSet;
+
+const PRESENTATION_APPS: &[&str] = &["keynote", "powerpoint", "impress"];
+
+// window titles of slideshows in presentation apps, browsers and screen shares
+const PRESENTATION_WINDOW_MARKERS: &[&str] = &[
+    "slide show",
+    "slideshow",
+    "presenter view",
+    "presenting",
+    "google slides",
+    "is sharing",
+    "screen sharing",
+    "shared screen",
+];
+
+const BROWSERS: &[&str] = &["chrome", "safari", "firefox", "edge", "brave"];
+
+// slides are sparse: a handful of large lines, unlike documents or UIs
+const MAX_SLIDE_LINES: usize = 30;
+const MIN_SLIDE_WORDS: usize = 3;
+const MAX_SLIDE_WORDS: usize = 250;
+
+// word overlap above which two frames show the same slide
+const SAME_SLIDE_SIMILARITY: f64 = 0.8;
+// share of a slide's words a later frame must keep to count as its next build step
+const BUILD_STEP_COVERAGE: f64 = 0.9;
+
+/// How a frame relates to a slide already in the deck.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SlideMatch {
+    /// the same slide shown again
+    Same,
+    /// the slide with more content revealed (build animation)
+    Extends,
+    Different,
+}
+
+/// Whether a window shows a full-screen presentation, either played locally
+/// or shared in a call.
+pub fn is_presentation_frame(app_name: &str, window_name: &str, text: &str) -> bool {
+    let app_name = app_name.to_lowercase();
+    let window_name = window_name.to_lowercase();
+
+    let presenting = PRESENTATION_WINDOW_MARKERS
+        .iter()
+        .any(|marker| window_name.contains(marker));
+    let known_app = PRESENTATION_APPS
+        .iter()
+        .chain(MEETING_APPS)
+        .chain(BROWSERS)
+        .any(|app| app_name.contains(app));
+    if !presenting || !known_app {
+        return false;
+    }
+
+    let lines = text.lines().filter(|line| !line.trim().is_empty()).count();
+    let words = text.split_whitespace().count();
+    lines <= MAX_SLIDE_LINES && (MIN_SLIDE_WORDS..=MAX_SLIDE_WORDS).contains(&words)
+}
+
+/// Compares the text of a new frame against a slide already in the deck.
+pub fn compare_slides(slide_text: &str, frame_text: &str) -> SlideMatch {
+    let slide = words(slide_text);
+    let frame = words(frame_text);
+    if slide.is_empty() || frame.is_empty() {
+        return SlideMatch::Different;
+    }
+
+    let shared = slide.intersection(&frame).count() as f64;
+    let union = slide.union(&frame).count() as f64;
+    if shared / union >= SAME_SLIDE_SIMILARITY {
+        SlideMatch::Same
+    } else if frame.len() > slide.len() && shared / slide.len() as f64 >= BUILD_STEP_COVERAGE {
+        SlideMatch::Extends
+    } else {
+        SlideMatch::Different
+    }
+}
+
+fn words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
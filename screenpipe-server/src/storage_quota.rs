@@ -0,0 +1,200 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use clap::ValueEnum;
+use oasgen::OaSchema;
+use screenpipe_db::{DatabaseManager, MediaChunkKind};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+use walkdir::WalkDir;
+
+// chunks looked at per query while over budget
+const EVICTION_BATCH_SIZE: u32 = 50;
+// chunks with anything recorded this recently may still be written or read
+const EVICTION_SETTLE_MINUTES: i64 = 10;
+
+/// Which media goes first once over the storage budget.
+#[derive(OaSchema, Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvictionPolicy {
+    /// the oldest recordings
+    Oldest,
+    /// the recordings with the least OCR text or transcription, oldest first
+    /// among equals
+    LeastText,
+}
+
+impl EvictionPolicy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EvictionPolicy::Oldest => "oldest",
+            EvictionPolicy::LeastText => "least_text",
+        }
+    }
+}
+
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize)]
+pub struct StorageStatus {
+    pub budget_bytes: u64,
+    pub policy: EvictionPolicy,
+    /// size of the screenpipe directory at the last check
+    pub used_bytes: Option<u64>,
+    pub last_checked: Option<DateTime<Utc>>,
+    pub evicted_chunks: i64,
+    pub evicted_bytes: i64,
+    /// why the last check failed, or the budget couldn't be met
+    pub last_error: Option<String>,
+}
+
+/// The size of the files under `dir`.
+pub fn dir_size(dir: &Path) -> u64 {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Keeps the screenpipe directory, database included, under a budget by
+/// deleting the files of video and audio chunks the policy picks. Tagged
+/// chunks are kept, and so are the frames and transcriptions of the ones
+/// evicted: they stay searchable, and their media is reported purged.
+pub struct StorageQuota {
+    db: Arc<DatabaseManager>,
+    dir: PathBuf,
+    budget_bytes: u64,
+    policy: EvictionPolicy,
+    status: Mutex<StorageStatus>,
+}
+
+impl StorageQuota {
+    pub fn new(
+        db: Arc<DatabaseManager>,
+        dir: PathBuf,
+        budget_bytes: u64,
+        policy: EvictionPolicy,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            db,
+            dir,
+            budget_bytes,
+            policy,
+            status: Mutex::new(StorageStatus {
+                budget_bytes,
+                policy,
+                used_bytes: None,
+                last_checked: None,
+                evicted_chunks: 0,
+                evicted_bytes: 0,
+                last_error: None,
+            }),
+        })
+    }
+
+    pub fn start(self: &Arc<Self>, interval: Duration) {
+        let quota = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                match quota.enforce().await {
+                    Ok((0, _)) => {}
+                    Ok((evicted, freed)) => info!(
+                        "storage quota: evicted {} chunks, {} MB freed",
+                        evicted,
+                        freed / 1_000_000
+                    ),
+                    Err(e) => {
+                        error!("storage quota: failed: {}", e);
+                        quota.status.lock().unwrap().last_error = Some(e.to_string());
+                    }
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+
+    pub fn status(&self) -> StorageStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    async fn used_bytes(&self) -> Result<u64> {
+        let dir = self.dir.clone();
+        Ok(tokio::task::spawn_blocking(move || dir_size(&dir)).await?)
+    }
+
+    /// Evicts media until the directory is under budget, or there's nothing
+    /// left to evict. Returns how many chunks were evicted and how many bytes
+    /// that freed.
+    pub async fn enforce(&self) -> Result<(u64, u64)> {
+        let mut used = self.used_bytes().await?;
+        let (mut evicted, mut freed) = (0, 0);
+        let mut last_error = None;
+        let before = Utc::now() - ChronoDuration::minutes(EVICTION_SETTLE_MINUTES);
+        'evict: while used > self.budget_bytes {
+            let candidates = self
+                .db
+                .list_eviction_candidates(
+                    before,
+                    self.policy == EvictionPolicy::LeastText,
+                    EVICTION_BATCH_SIZE,
+                )
+                .await?;
+            if candidates.is_empty() {
+                let message = format!(
+                    "{} MB used of a {} MB budget, and no media left to evict",
+                    used / 1_000_000,
+                    self.budget_bytes / 1_000_000
+                );
+                warn!("storage quota: {}", message);
+                last_error = Some(message);
+                break;
+            }
+            for (kind, id, file_path) in candidates {
+                let kind = match kind.as_str() {
+                    "video" => MediaChunkKind::Video,
+                    _ => MediaChunkKind::Audio,
+                };
+                let bytes = self.evict(kind, id, &file_path).await?;
+                evicted += 1;
+                freed += bytes;
+                used = used.saturating_sub(bytes);
+                if used <= self.budget_bytes {
+                    break 'evict;
+                }
+            }
+        }
+
+        let (evicted_chunks, evicted_bytes) = self.db.media_eviction_totals().await?;
+        let mut status = self.status.lock().unwrap();
+        status.used_bytes = Some(used);
+        status.last_checked = Some(Utc::now());
+        status.evicted_chunks = evicted_chunks;
+        status.evicted_bytes = evicted_bytes;
+        status.last_error = last_error;
+        Ok((evicted, freed))
+    }
+
+    /// Deletes the file of a chunk and records it was. Returns the bytes
+    /// freed, none when the file was already gone.
+    async fn evict(&self, kind: MediaChunkKind, id: i64, file_path: &str) -> Result<u64> {
+        let bytes = match tokio::fs::metadata(file_path).await {
+            Ok(metadata) => {
+                tokio::fs::remove_file(file_path).await?;
+                metadata.len()
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                debug!("storage quota: {} is gone", file_path);
+                0
+            }
+            Err(e) => return Err(e.into()),
+        };
+        self.db
+            .record_media_eviction(kind, id, file_path, bytes, self.policy.as_str())
+            .await?;
+        debug!("storage quota: evicted {} ({} bytes)", file_path, bytes);
+        Ok(bytes)
+    }
+}
@@ -0,0 +1,251 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use oasgen::OaSchema;
+use screenpipe_db::{DatabaseManager, Subject, SubjectErasure};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::audit::{audit, current_actor};
+use crate::video_utils::redact_video_frames;
+
+/// Name of the key erasure reports are sealed with, in the data dir.
+pub const SIGNING_KEY_FILE: &str = "erasure_signing.key";
+
+/// What erasing a subject removed, as counts and hashes so the report
+/// doesn't hold what it erased.
+///
+/// The report is sealed with a key kept in the data dir. The seal only tells
+/// the report wasn't edited by someone without access to that key: anyone
+/// who can read the data dir can seal a report of their own, so it is no
+/// proof of the erasure to a third party.
+#[derive(OaSchema, Debug, Clone, Serialize, Deserialize)]
+pub struct ErasureReport {
+    pub erased_at: DateTime<Utc>,
+    /// who asked for the erasure, as in the audit log
+    pub actor: String,
+    pub summary: ErasureSummary,
+    /// frames blacked out of the video chunks still holding other frames
+    pub redacted_frames: Vec<i64>,
+    pub removed_files: Vec<String>,
+    /// what couldn't be removed or redacted, to do by hand
+    pub errors: Vec<String>,
+    /// hex HMAC-SHA256 of the report without its signature
+    #[serde(default)]
+    pub signature: String,
+}
+
+/// Counts of what was erased, and the SHA-256 of the erased texts so a copy
+/// found later can be matched against the report.
+#[derive(OaSchema, Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ErasureSummary {
+    /// hex SHA-256 of the subject's lowercased name, or of their speaker id
+    pub subject_sha256: String,
+    pub frames: u64,
+    pub ocr_rows: u64,
+    pub audio_chunks: u64,
+    pub audio_transcriptions: u64,
+    pub ui_rows: u64,
+    pub ui_traversals: u64,
+    #[serde(default)]
+    pub clipboard_entries: u64,
+    pub speakers: u64,
+    pub speaker_embeddings: u64,
+    pub meeting_participants: u64,
+    pub search_clicks: u64,
+    pub tag_suggestions: u64,
+    pub audit_entries: u64,
+    pub browser_contexts: u64,
+    /// matches in chunks still being recorded, erase again once they're done
+    pub skipped_live: u64,
+    /// hex SHA-256 of each erased screen text, transcription, ui text and
+    /// clipboard entry
+    pub text_sha256: Vec<String>,
+}
+
+fn sha256_hex(text: &str) -> String {
+    Sha256::digest(text.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// The hash standing for the subject in the report and the audit log.
+pub fn subject_hash(subject: &Subject) -> String {
+    match subject.name.as_deref().map(str::trim) {
+        Some(name) if !name.is_empty() => sha256_hex(&name.to_lowercase()),
+        _ => sha256_hex(&subject.speaker_id.unwrap_or_default().to_string()),
+    }
+}
+
+fn summarize(subject: &Subject, erasure: &SubjectErasure) -> ErasureSummary {
+    let deletion = &erasure.deletion;
+    let export = &erasure.export;
+    ErasureSummary {
+        subject_sha256: subject_hash(subject),
+        frames: deletion.frames,
+        ocr_rows: deletion.ocr_rows,
+        audio_chunks: deletion.audio_chunks,
+        audio_transcriptions: deletion.audio_transcriptions,
+        ui_rows: deletion.ui_rows,
+        ui_traversals: deletion.ui_traversals,
+        clipboard_entries: deletion.clipboard_entries,
+        speakers: erasure.speakers,
+        speaker_embeddings: erasure.speaker_embeddings,
+        meeting_participants: erasure.meeting_participants,
+        search_clicks: erasure.search_clicks,
+        tag_suggestions: erasure.tag_suggestions,
+        audit_entries: erasure.audit_entries,
+        browser_contexts: erasure.browser_contexts,
+        skipped_live: erasure.skipped_live,
+        text_sha256: export
+            .ocr_hits
+            .iter()
+            .map(|hit| hit.text.as_str())
+            .chain(export.transcripts.iter().map(|t| t.transcription.as_str()))
+            .chain(export.ui_hits.iter().map(|hit| hit.text.as_str()))
+            .chain(export.clipboard_hits.iter().map(|hit| hit.text.as_str()))
+            .map(sha256_hex)
+            .collect(),
+    }
+}
+
+/// Reads the sealing key at `path`, creating a random one readable by the
+/// user only when there's none.
+pub async fn load_or_create_signing_key(path: &Path) -> Result<String> {
+    if tokio::fs::try_exists(path).await? {
+        return Ok(tokio::fs::read_to_string(path).await?.trim().to_string());
+    }
+    let key = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    tokio::fs::write(path, &key).await?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        tokio::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600)).await?;
+    }
+    info!(
+        "created an erasure report sealing key, stored in {}",
+        path.display()
+    );
+    Ok(key)
+}
+
+fn report_mac(key: &str, report: &ErasureReport) -> Hmac<Sha256> {
+    let unsigned = ErasureReport {
+        signature: String::new(),
+        ..report.clone()
+    };
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("hmac takes keys of any length");
+    mac.update(&serde_json::to_vec(&unsigned).unwrap_or_default());
+    mac
+}
+
+pub fn sign_report(key: &str, report: &mut ErasureReport) {
+    report.signature = report_mac(key, report)
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+}
+
+pub fn verify_report(key: &str, report: &ErasureReport) -> bool {
+    let Ok(signature) = (0..report.signature.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(report.signature.get(i..i + 2).unwrap_or("zz"), 16))
+        .collect::<Result<Vec<u8>, _>>()
+    else {
+        return false;
+    };
+    report_mac(key, report).verify_slice(&signature).is_ok()
+}
+
+/// Erases the subject from the database, blacks the frames mentioning them
+/// out of the video chunks kept, removes the media files left unused and
+/// returns the sealed report of it all.
+pub async fn erase_subject(
+    db: &DatabaseManager,
+    subject: &Subject,
+    signing_key: &str,
+) -> Result<ErasureReport> {
+    let erasure = db.erase_subject(subject).await?;
+    let mut errors = Vec::new();
+
+    // chunks removed whole need no redaction
+    let mut to_redact: BTreeMap<&str, Vec<(i64, i64)>> = BTreeMap::new();
+    for hit in &erasure.export.ocr_hits {
+        if !erasure.deletion.video_files.contains(&hit.file_path) {
+            to_redact
+                .entry(hit.file_path.as_str())
+                .or_default()
+                .push((hit.frame_id, hit.offset_index));
+        }
+    }
+    let mut redacted_frames = Vec::new();
+    for (file_path, frames) in to_redact {
+        let offsets: Vec<_> = frames.iter().map(|(_, offset)| (*offset, None)).collect();
+        match redact_video_frames(file_path, &offsets).await {
+            Ok(()) => {
+                redacted_frames.extend(frames.iter().map(|(frame_id, _)| *frame_id));
+                // the synced copy is replaced by the redacted one
+                if let Err(e) = db.reset_video_chunk_sync(file_path).await {
+                    errors.push(format!("failed to resync {}: {}", file_path, e));
+                }
+            }
+            Err(e) => {
+                error!("subject erasure: {}", e);
+                errors.push(format!("failed to redact {}: {}", file_path, e));
+            }
+        }
+    }
+
+    let mut removed_files = Vec::new();
+    for path in erasure
+        .deletion
+        .video_files
+        .iter()
+        .chain(&erasure.deletion.audio_files)
+        .chain(&erasure.deletion.image_files)
+    {
+        match tokio::fs::remove_file(path).await {
+            Ok(()) => removed_files.push(path.clone()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                error!("subject erasure: failed to remove {}: {}", path, e);
+                errors.push(format!("failed to remove {}: {}", path, e));
+            }
+        }
+    }
+
+    let deletion = &erasure.deletion;
+    audit(
+        db,
+        "erase_subject",
+        &format!("subject_sha256={}", subject_hash(subject)),
+        Some(
+            (deletion.frames
+                + deletion.audio_transcriptions
+                + deletion.ui_rows
+                + erasure.speakers
+                + erasure.meeting_participants) as i64,
+        ),
+    )
+    .await;
+
+    let mut report = ErasureReport {
+        erased_at: Utc::now(),
+        actor: current_actor(),
+        summary: summarize(subject, &erasure),
+        redacted_frames,
+        removed_files,
+        errors,
+        signature: String::new(),
+    };
+    sign_report(signing_key, &mut report);
+    Ok(report)
+}
@@ -0,0 +1,478 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use oasgen::OaSchema;
+use reqwest::Client;
+use screenpipe_core::egress;
+use screenpipe_db::{DatabaseManager, WindowFocusTime, WorkSession};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt::Write;
+use tracing::warn;
+
+use crate::digest::{digest_meeting, top_apps, AppTime, DigestMeeting};
+use crate::journal::{local_day, shorten};
+
+const TOP_APPS: usize = 8;
+const MAX_TOPICS: usize = 8;
+// of the screen, and as many heard
+const MAX_EXCERPTS: usize = 5;
+// words shorter than this are rarely a topic
+const MIN_TOPIC_CHARS: usize = 5;
+const LLM_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(180);
+
+const STOPWORDS: &[&str] = &[
+    "about",
+    "after",
+    "again",
+    "being",
+    "before",
+    "because",
+    "could",
+    "every",
+    "going",
+    "maybe",
+    "other",
+    "people",
+    "really",
+    "right",
+    "should",
+    "something",
+    "still",
+    "their",
+    "there",
+    "these",
+    "thing",
+    "things",
+    "think",
+    "those",
+    "through",
+    "today",
+    "where",
+    "which",
+    "while",
+    "would",
+];
+
+#[derive(OaSchema, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SummaryPeriod {
+    #[default]
+    Day,
+    Week,
+}
+
+impl SummaryPeriod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SummaryPeriod::Day => "day",
+            SummaryPeriod::Week => "week",
+        }
+    }
+
+    /// The first and last day of the period `date` is in, weeks starting on
+    /// monday.
+    pub fn days(&self, date: NaiveDate) -> (NaiveDate, NaiveDate) {
+        match self {
+            SummaryPeriod::Day => (date, date),
+            SummaryPeriod::Week => {
+                let monday = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+                (monday, monday + Duration::days(6))
+            }
+        }
+    }
+}
+
+#[derive(OaSchema, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SummaryExcerpt {
+    pub timestamp: DateTime<Utc>,
+    /// the app it was read in, or who said it
+    pub source: String,
+    pub text: String,
+    /// the frame it was read from, none when it was heard
+    pub frame_id: Option<i64>,
+}
+
+/// What happened over a day or a week.
+#[derive(OaSchema, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    pub period: SummaryPeriod,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    /// "template", or the model of the llm that wrote the overview and the
+    /// topics
+    pub generator: String,
+    /// a few sentences from the llm, none with the template
+    pub overview: Option<String>,
+    /// focused time when focus sessions are recorded, screen time otherwise
+    pub top_apps: Vec<AppTime>,
+    pub meetings: Vec<DigestMeeting>,
+    pub key_topics: Vec<String>,
+    pub excerpts: Vec<SummaryExcerpt>,
+    /// time away, left out of the time in apps
+    #[serde(default)]
+    pub idle_minutes: i64,
+    /// the work sessions detected in the period, oldest first
+    #[serde(default)]
+    pub sessions: Vec<WorkSession>,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Time focused in each app, the longest first.
+pub fn focused_apps(focus: &[WindowFocusTime], limit: usize) -> Vec<AppTime> {
+    let mut secs: HashMap<&str, f64> = HashMap::new();
+    for window in focus.iter().filter(|w| !w.app_name.is_empty()) {
+        *secs.entry(&window.app_name).or_default() += window.focused_secs;
+    }
+    let mut apps: Vec<AppTime> = secs
+        .into_iter()
+        .map(|(app_name, secs)| AppTime {
+            app_name: app_name.to_string(),
+            minutes: (secs / 60.0).round() as i64,
+        })
+        .filter(|app| app.minutes > 0)
+        .collect();
+    apps.sort_by(|a, b| b.minutes.cmp(&a.minutes).then(a.app_name.cmp(&b.app_name)));
+    apps.truncate(limit);
+    apps
+}
+
+/// The words found in the most `texts`, at least two, ignoring case and
+/// short or common words.
+pub fn key_terms(texts: &[&str], limit: usize) -> Vec<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for text in texts {
+        let mut words: Vec<String> = text
+            .split(|c: char| !c.is_alphabetic())
+            .filter(|word| word.chars().count() >= MIN_TOPIC_CHARS)
+            .map(str::to_lowercase)
+            .filter(|word| !STOPWORDS.contains(&word.as_str()))
+            .collect();
+        words.sort();
+        words.dedup();
+        for word in words {
+            *counts.entry(word).or_default() += 1;
+        }
+    }
+    let mut terms: Vec<(String, usize)> = counts.into_iter().filter(|(_, n)| *n >= 2).collect();
+    terms.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    terms
+        .into_iter()
+        .take(limit)
+        .map(|(word, _)| word)
+        .collect()
+}
+
+/// The summary of the period `date` is in, in the local timezone, from the
+/// app sessions, focus sessions, meetings, tags, transcriptions and idle
+/// intervals.
+pub async fn build_summary(
+    db: &DatabaseManager,
+    period: SummaryPeriod,
+    date: NaiveDate,
+) -> Result<Summary> {
+    let (first, last) = period.days(date);
+    let (start, _) = local_day(first);
+    let (_, end) = local_day(last);
+    let day = db.get_journal_day(start, end).await?;
+    let focus = db.get_focus_time(start, end, None).await?;
+    let transcriptions = db
+        .get_longest_transcriptions(start, end, MAX_EXCERPTS as u32)
+        .await?;
+    let idle_minutes = db
+        .get_idle_intervals(start, end)
+        .await?
+        .iter()
+        .map(|idle| (idle.ended_at.min(end) - idle.started_at.max(start)).num_minutes())
+        .sum();
+    let work_sessions = db.get_work_sessions(start, end).await?;
+
+    let apps = if focus.is_empty() {
+        top_apps(&day, TOP_APPS)
+    } else {
+        focused_apps(&focus, TOP_APPS)
+    };
+
+    let mut sessions: Vec<_> = day
+        .sessions
+        .iter()
+        .filter(|s| s.excerpt.is_some())
+        .collect();
+    sessions.sort_by_key(|s| -(s.end - s.start).num_seconds());
+    let mut excerpts: Vec<SummaryExcerpt> = sessions
+        .iter()
+        .take(MAX_EXCERPTS)
+        .filter_map(|session| {
+            let excerpt = session.excerpt.as_ref()?;
+            Some(SummaryExcerpt {
+                timestamp: session.start,
+                source: session.app_name.clone(),
+                text: shorten(&excerpt.text),
+                frame_id: Some(excerpt.frame_id),
+            })
+        })
+        .collect();
+    excerpts.extend(transcriptions.iter().map(|line| {
+        SummaryExcerpt {
+            timestamp: line.timestamp,
+            source: line
+                .speaker_name
+                .clone()
+                .unwrap_or_else(|| line.device_name.clone()),
+            text: shorten(&line.text),
+            frame_id: None,
+        }
+    }));
+    excerpts.sort_by_key(|excerpt| excerpt.timestamp);
+
+    let texts: Vec<&str> = day
+        .sessions
+        .iter()
+        .filter_map(|s| s.excerpt.as_ref().map(|e| e.text.as_str()))
+        .chain(
+            day.meetings
+                .iter()
+                .flat_map(|m| m.transcript.iter().map(|l| l.text.as_str())),
+        )
+        .chain(transcriptions.iter().map(|l| l.text.as_str()))
+        .collect();
+    let mut key_topics: Vec<String> = day.tags.iter().map(|tag| tag.name.clone()).collect();
+    key_topics.truncate(MAX_TOPICS);
+    for term in key_terms(&texts, MAX_TOPICS) {
+        if key_topics.len() >= MAX_TOPICS {
+            break;
+        }
+        if !key_topics
+            .iter()
+            .any(|topic| topic.eq_ignore_ascii_case(&term))
+        {
+            key_topics.push(term);
+        }
+    }
+
+    Ok(Summary {
+        period,
+        start_date: first,
+        end_date: last,
+        generator: "template".to_string(),
+        overview: None,
+        top_apps: apps,
+        meetings: day.meetings.iter().map(digest_meeting).collect(),
+        key_topics,
+        excerpts,
+        idle_minutes,
+        sessions: work_sessions,
+        generated_at: Utc::now(),
+    })
+}
+
+/// The summary of the period `date` is in, the one kept unless `refresh`.
+/// With `llm` the overview and the topics are written by it, the template
+/// ones are kept when it fails. Summaries of a period that is over are kept,
+/// unless the llm failed so it gets another chance.
+pub async fn summarize(
+    db: &DatabaseManager,
+    llm: Option<&SummaryLlm>,
+    period: SummaryPeriod,
+    date: NaiveDate,
+    refresh: bool,
+) -> Result<Summary> {
+    let (first, last) = period.days(date);
+    if !refresh {
+        if let Some(stored) = db.get_summary(period.as_str(), first).await? {
+            match serde_json::from_str(&stored.content) {
+                Ok(summary) => return Ok(summary),
+                Err(e) => warn!(
+                    "summary: the {} of {} is unreadable: {}",
+                    period.as_str(),
+                    first,
+                    e
+                ),
+            }
+        }
+    }
+
+    let mut summary = build_summary(db, period, date).await?;
+    let mut complete = true;
+    if let Some(llm) = llm {
+        match llm.summarize(&summary).await {
+            Ok((overview, topics)) => {
+                summary.overview = Some(overview);
+                if !topics.is_empty() {
+                    summary.key_topics = topics;
+                }
+                summary.generator = llm.model.clone();
+            }
+            Err(e) => {
+                warn!("summary: llm failed, using the template: {}", e);
+                complete = false;
+            }
+        }
+    }
+
+    let (_, end) = local_day(last);
+    if complete && end <= Utc::now() {
+        db.save_summary(
+            period.as_str(),
+            first,
+            &summary.generator,
+            &serde_json::to_string(&summary)?,
+        )
+        .await?;
+    }
+    Ok(summary)
+}
+
+/// What the llm is asked to summarize: the template summary, as text.
+pub fn summary_prompt(summary: &Summary) -> String {
+    let mut prompt = String::new();
+    let _ = writeln!(
+        prompt,
+        "Activity recorded from {} to {}.",
+        summary.start_date, summary.end_date
+    );
+    if !summary.top_apps.is_empty() {
+        let _ = writeln!(prompt, "\nApps used, in minutes:");
+        for app in &summary.top_apps {
+            let _ = writeln!(prompt, "- {}: {}", app.app_name, app.minutes);
+        }
+    }
+    if summary.idle_minutes > 0 {
+        let _ = writeln!(prompt, "\nAway for {} minutes.", summary.idle_minutes);
+    }
+    if !summary.sessions.is_empty() {
+        let _ = writeln!(prompt, "\nWork sessions:");
+        for session in &summary.sessions {
+            let _ = writeln!(
+                prompt,
+                "- {} to {} in {}{}",
+                session.started_at.format("%Y-%m-%d %H:%M UTC"),
+                session.ended_at.format("%H:%M UTC"),
+                session.apps.join(", "),
+                session
+                    .topic
+                    .as_ref()
+                    .map(|topic| format!(", on {}", topic))
+                    .unwrap_or_default()
+            );
+        }
+    }
+    if !summary.meetings.is_empty() {
+        let _ = writeln!(prompt, "\nMeetings:");
+        for meeting in &summary.meetings {
+            let _ = writeln!(
+                prompt,
+                "- {} at {} with {}",
+                meeting.app,
+                meeting.start.format("%Y-%m-%d %H:%M UTC"),
+                meeting.speakers.join(", ")
+            );
+        }
+    }
+    if !summary.key_topics.is_empty() {
+        let _ = writeln!(
+            prompt,
+            "\nFrequent words: {}",
+            summary.key_topics.join(", ")
+        );
+    }
+    if !summary.excerpts.is_empty() {
+        let _ = writeln!(prompt, "\nExcerpts of what was read or heard:");
+        for excerpt in &summary.excerpts {
+            let _ = writeln!(prompt, "- {}: {}", excerpt.source, excerpt.text);
+        }
+    }
+    let _ = write!(
+        prompt,
+        "\nAnswer with a json object: {{\"overview\": a few sentences on what \
+         was worked on, \"key_topics\": at most {} short topics}}.",
+        MAX_TOPICS
+    );
+    prompt
+}
+
+/// The overview and topics of the llm reply, none when it holds no json
+/// object with an overview.
+pub fn parse_summary_reply(reply: &str) -> Option<(String, Vec<String>)> {
+    let (start, end) = (reply.find('{')?, reply.rfind('}')?);
+    if start >= end {
+        return None;
+    }
+    let reply: Value = serde_json::from_str(&reply[start..=end]).ok()?;
+    let overview = reply["overview"].as_str()?.trim().to_string();
+    if overview.is_empty() {
+        return None;
+    }
+    let topics = reply["key_topics"]
+        .as_array()
+        .map(|topics| {
+            topics
+                .iter()
+                .filter_map(Value::as_str)
+                .map(str::trim)
+                .filter(|topic| !topic.is_empty())
+                .take(MAX_TOPICS)
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    Some((overview, topics))
+}
+
+/// Writes the overview and topics of summaries through an OpenAI compatible
+/// chat completions api.
+pub struct SummaryLlm {
+    client: Client,
+    url: String,
+    model: String,
+    api_key: Option<String>,
+}
+
+impl SummaryLlm {
+    /// `url` is the chat completions endpoint, e.g.
+    /// "http://localhost:11434/v1/chat/completions" for ollama.
+    pub fn new(url: String, model: String, api_key: Option<String>) -> Self {
+        Self {
+            client: egress::http_client(),
+            url,
+            model,
+            api_key: api_key.filter(|key| !key.is_empty()),
+        }
+    }
+
+    pub async fn summarize(&self, summary: &Summary) -> Result<(String, Vec<String>)> {
+        egress::check_egress(&self.url)?;
+        let mut request = self
+            .client
+            .post(&self.url)
+            .timeout(LLM_TIMEOUT)
+            .json(&json!({
+                "model": self.model,
+                "temperature": 0.2,
+                "messages": [
+                    {
+                        "role": "system",
+                        "content": "You summarize what someone did from what their screen showed and their microphone heard.",
+                    },
+                    {
+                        "role": "user",
+                        "content": summary_prompt(summary),
+                    },
+                ],
+            }));
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            return Err(anyhow!("{}: {}", status, body));
+        }
+        let body: Value = serde_json::from_str(&body)?;
+        let reply = body["choices"][0]["message"]["content"]
+            .as_str()
+            .ok_or_else(|| anyhow!("no reply in {}", body))?;
+        parse_summary_reply(reply).ok_or_else(|| anyhow!("no summary in {}", reply))
+    }
+}
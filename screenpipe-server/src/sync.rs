@@ -0,0 +1,779 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Method, StatusCode, Url};
+use screenpipe_core::egress;
+use screenpipe_core::media_crypto::{self, MediaKey};
+use screenpipe_db::{DatabaseManager, MediaChunkKind, SyncRun};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::{BufReader, BufWriter, Read, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio_util::io::ReaderStream;
+use tracing::{debug, error, info, warn};
+
+const SYNC_TIMEOUT: Duration = Duration::from_secs(300);
+// chunks uploaded per table at each run
+const MEDIA_BATCH_SIZE: u32 = 50;
+// audio chunks younger than this may still be written
+const AUDIO_SETTLE_MINUTES: i64 = 5;
+// S3 takes objects up to 5 GiB in one upload, bigger ones in parts
+const MAX_SINGLE_PUT: u64 = 5 * 1024 * 1024 * 1024;
+const PART_SIZE: u64 = 512 * 1024 * 1024;
+/// A snapshot of the whole database is uploaded when none of this many runs
+/// before was one, a delta otherwise.
+pub const SNAPSHOT_EVERY: u32 = 48;
+
+/// An S3 compatible bucket: AWS S3, MinIO, Backblaze B2... Objects are
+/// addressed path-style, `{endpoint}/{bucket}/{key}`, which all of them
+/// serve.
+#[derive(Clone)]
+pub struct S3Config {
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    pub access_key: String,
+    pub secret_key: String,
+    /// folder of the bucket the objects go in, e.g. "laptop"
+    pub prefix: String,
+}
+
+impl S3Config {
+    /// AWS S3 in `region`.
+    pub fn aws_endpoint(region: &str) -> String {
+        format!("https://s3.{}.amazonaws.com", region)
+    }
+}
+
+// every byte but the unreserved ones is percent encoded, `/` too unless
+// `keep_slash`
+fn uri_encode(s: &str, keep_slash: bool) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if keep_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("hmac takes keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// The `Authorization` header of a request signed with AWS Signature
+/// Version 4. `path` and `query` are canonical, uri encoded and the query
+/// sorted, and `headers` the signed ones with lowercase names, `host`,
+/// `x-amz-date` and `x-amz-content-sha256` among them.
+pub fn sigv4_authorization(
+    config: &S3Config,
+    method: &str,
+    path: &str,
+    query: &str,
+    headers: &[(&str, &str)],
+    payload_sha256: &str,
+    at: DateTime<Utc>,
+) -> String {
+    let mut headers = headers.to_vec();
+    headers.sort();
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        method, path, query, canonical_headers, signed_headers, payload_sha256
+    );
+
+    let date = at.format("%Y%m%d").to_string();
+    let scope = format!("{}/{}/s3/aws4_request", date, config.region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        at.format("%Y%m%dT%H%M%SZ"),
+        scope,
+        sha256_hex(canonical_request.as_bytes())
+    );
+    let key = [date.as_str(), &config.region, "s3", "aws4_request"]
+        .iter()
+        .fold(
+            format!("AWS4{}", config.secret_key).into_bytes(),
+            |key, part| hmac_sha256(&key, part),
+        );
+    let signature: String = hmac_sha256(&key, &string_to_sign)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        config.access_key, scope, signed_headers, signature
+    )
+}
+
+fn xml_text(xml: &str, tag: &str) -> Vec<String> {
+    let (open, close) = (format!("<{}>", tag), format!("</{}>", tag));
+    let mut values = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        let Some(end) = rest.find(&close) else {
+            break;
+        };
+        values.push(
+            rest[..end]
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&quot;", "\"")
+                .replace("&apos;", "'")
+                .replace("&amp;", "&"),
+        );
+        rest = &rest[end + close.len()..];
+    }
+    values
+}
+
+/// The keys of a ListObjectsV2 response and the token of the next page, if
+/// it's truncated.
+pub fn parse_list_objects(xml: &str) -> (Vec<String>, Option<String>) {
+    let truncated = xml_text(xml, "IsTruncated")
+        .first()
+        .is_some_and(|value| value == "true");
+    let next = xml_text(xml, "NextContinuationToken").into_iter().next();
+    (xml_text(xml, "Key"), next.filter(|_| truncated))
+}
+
+/// The objects of an S3 compatible bucket under the prefix of its config.
+pub struct S3Store {
+    client: Client,
+    config: S3Config,
+}
+
+impl S3Store {
+    pub fn new(config: S3Config) -> Self {
+        Self {
+            client: egress::http_client(),
+            config,
+        }
+    }
+
+    fn full_key(&self, key: &str) -> String {
+        let prefix = self.config.prefix.trim_matches('/');
+        if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}/{}", prefix, key)
+        }
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        key: Option<&str>,
+        query: &[(&str, &str)],
+        body: Vec<u8>,
+    ) -> Result<reqwest::Response> {
+        let payload_sha256 = sha256_hex(&body);
+        self.send_body(
+            method,
+            key,
+            query,
+            body.len() as u64,
+            payload_sha256,
+            body.into(),
+        )
+        .await
+    }
+
+    /// Sends `len` bytes of the file at `path` from `offset`, read as they
+    /// are sent.
+    async fn send_file(
+        &self,
+        method: Method,
+        key: &str,
+        query: &[(&str, &str)],
+        path: &Path,
+        offset: u64,
+        len: u64,
+    ) -> Result<reqwest::Response> {
+        let payload_sha256 = {
+            let path = path.to_path_buf();
+            tokio::task::spawn_blocking(move || file_sha256(&path, offset, len)).await??
+        };
+        let mut file = tokio::fs::File::open(path).await?;
+        file.seek(SeekFrom::Start(offset)).await?;
+        let body = reqwest::Body::wrap_stream(ReaderStream::new(file.take(len)));
+        self.send_body(method, Some(key), query, len, payload_sha256, body)
+            .await
+    }
+
+    async fn send_body(
+        &self,
+        method: Method,
+        key: Option<&str>,
+        query: &[(&str, &str)],
+        len: u64,
+        payload_sha256: String,
+        body: reqwest::Body,
+    ) -> Result<reqwest::Response> {
+        let endpoint = Url::parse(&self.config.endpoint)?;
+        let host = match (endpoint.host_str(), endpoint.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => return Err(anyhow!("no host in {}", self.config.endpoint)),
+        };
+        let path = match key {
+            Some(key) => format!(
+                "/{}/{}",
+                uri_encode(&self.config.bucket, false),
+                uri_encode(&self.full_key(key), true)
+            ),
+            None => format!("/{}", uri_encode(&self.config.bucket, false)),
+        };
+        let mut query: Vec<(String, String)> = query
+            .iter()
+            .map(|(name, value)| (uri_encode(name, false), uri_encode(value, false)))
+            .collect();
+        query.sort();
+        let query = query
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let authorization = sigv4_authorization(
+            &self.config,
+            method.as_str(),
+            &path,
+            &query,
+            &[
+                ("host", &host),
+                ("x-amz-content-sha256", &payload_sha256),
+                ("x-amz-date", &amz_date),
+            ],
+            &payload_sha256,
+            now,
+        );
+
+        let mut url = format!("{}{}", self.config.endpoint.trim_end_matches('/'), path);
+        if !query.is_empty() {
+            url = format!("{}?{}", url, query);
+        }
+        egress::check_egress(&url)?;
+        let response = self
+            .client
+            .request(method, url)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_sha256)
+            .header("authorization", authorization)
+            .header("content-length", len)
+            .body(body)
+            .timeout(SYNC_TIMEOUT)
+            .send()
+            .await?;
+        Ok(response)
+    }
+
+    async fn error(response: reqwest::Response) -> anyhow::Error {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        anyhow!("{}: {}", status, body)
+    }
+
+    pub async fn put(&self, key: &str, body: Vec<u8>) -> Result<()> {
+        let response = self.send(Method::PUT, Some(key), &[], body).await?;
+        if !response.status().is_success() {
+            return Err(Self::error(response).await);
+        }
+        Ok(())
+    }
+
+    /// Uploads the file at `path` without reading it in memory, in parts
+    /// when it's too big for one upload.
+    pub async fn put_file(&self, key: &str, path: &Path) -> Result<()> {
+        let len = tokio::fs::metadata(path).await?.len();
+        if len > MAX_SINGLE_PUT {
+            return self.put_multipart(key, path, len).await;
+        }
+        let response = self.send_file(Method::PUT, key, &[], path, 0, len).await?;
+        if !response.status().is_success() {
+            return Err(Self::error(response).await);
+        }
+        Ok(())
+    }
+
+    async fn put_multipart(&self, key: &str, path: &Path, len: u64) -> Result<()> {
+        let response = self
+            .send(Method::POST, Some(key), &[("uploads", "")], Vec::new())
+            .await?;
+        if !response.status().is_success() {
+            return Err(Self::error(response).await);
+        }
+        let upload_id = xml_text(&response.text().await?, "UploadId")
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("no upload id for {}", key))?;
+        let result = self.put_parts(key, path, len, &upload_id).await;
+        if result.is_err() {
+            // the parts uploaded are kept, and billed, until aborted
+            let query = [("uploadId", upload_id.as_str())];
+            if let Err(e) = self
+                .send(Method::DELETE, Some(key), &query, Vec::new())
+                .await
+            {
+                warn!("sync: failed to abort the upload of {}: {}", key, e);
+            }
+        }
+        result
+    }
+
+    async fn put_parts(&self, key: &str, path: &Path, len: u64, upload_id: &str) -> Result<()> {
+        let mut etags = Vec::new();
+        let mut offset = 0;
+        while offset < len {
+            let part_len = PART_SIZE.min(len - offset);
+            let number = (etags.len() + 1).to_string();
+            let query = [("partNumber", number.as_str()), ("uploadId", upload_id)];
+            let response = self
+                .send_file(Method::PUT, key, &query, path, offset, part_len)
+                .await?;
+            if !response.status().is_success() {
+                return Err(Self::error(response).await);
+            }
+            let etag = response
+                .headers()
+                .get("etag")
+                .and_then(|etag| etag.to_str().ok())
+                .ok_or_else(|| anyhow!("no etag for part {} of {}", number, key))?;
+            etags.push(etag.to_string());
+            offset += part_len;
+        }
+        let parts: String = etags
+            .iter()
+            .enumerate()
+            .map(|(i, etag)| {
+                format!(
+                    "<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>",
+                    i + 1,
+                    etag
+                )
+            })
+            .collect();
+        let body = format!(
+            "<CompleteMultipartUpload>{}</CompleteMultipartUpload>",
+            parts
+        );
+        let query = [("uploadId", upload_id)];
+        let response = self
+            .send(Method::POST, Some(key), &query, body.into_bytes())
+            .await?;
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        // a failed completion can come with a 200
+        if !status.is_success() || body.contains("<Error>") {
+            return Err(anyhow!("{}: {}", status, body));
+        }
+        Ok(())
+    }
+
+    /// Writes the object at `key` to `path` as it is received, returns false
+    /// when there's no such object.
+    pub async fn get_to_file(&self, key: &str, path: &Path) -> Result<bool> {
+        let mut response = self.send(Method::GET, Some(key), &[], Vec::new()).await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+        if !response.status().is_success() {
+            return Err(Self::error(response).await);
+        }
+        let mut file = tokio::fs::File::create(path).await?;
+        while let Some(chunk) = response.chunk().await? {
+            file.write_all(&chunk).await?;
+        }
+        file.flush().await?;
+        Ok(true)
+    }
+
+    /// Deletes the object at `key`, if any.
+    pub async fn delete(&self, key: &str) -> Result<()> {
+        let response = self
+            .send(Method::DELETE, Some(key), &[], Vec::new())
+            .await?;
+        if !response.status().is_success() && response.status() != StatusCode::NOT_FOUND {
+            return Err(Self::error(response).await);
+        }
+        Ok(())
+    }
+
+    /// The keys starting with `prefix`, without the prefix of the config.
+    pub async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let full_prefix = self.full_key(prefix);
+        let mut keys = Vec::new();
+        let mut token: Option<String> = None;
+        loop {
+            let mut query = vec![("list-type", "2"), ("prefix", full_prefix.as_str())];
+            if let Some(token) = &token {
+                query.push(("continuation-token", token.as_str()));
+            }
+            let response = self.send(Method::GET, None, &query, Vec::new()).await?;
+            if !response.status().is_success() {
+                return Err(Self::error(response).await);
+            }
+            let (page, next) = parse_list_objects(&response.text().await?);
+            let own_prefix = self.full_key("");
+            keys.extend(
+                page.iter()
+                    .map(|key| key.strip_prefix(&own_prefix).unwrap_or(key).to_string()),
+            );
+            match next {
+                Some(next) => token = Some(next),
+                None => return Ok(keys),
+            }
+        }
+    }
+}
+
+fn media_object_key(file_name: &str) -> String {
+    format!("media/{}", file_name)
+}
+
+fn file_sha256(path: &Path, offset: u64, len: u64) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    std::io::Seek::seek(&mut file, SeekFrom::Start(offset))?;
+    let mut reader = BufReader::new(file).take(len);
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut reader, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn encrypt_file_to(key: &MediaKey, path: &Path, output: &Path) -> std::io::Result<()> {
+    media_crypto::encrypt_stream(
+        key,
+        BufReader::new(std::fs::File::open(path)?),
+        BufWriter::new(std::fs::File::create(output)?),
+    )
+}
+
+static SYNC_STORE: OnceLock<Arc<S3Store>> = OnceLock::new();
+
+/// The bucket media files missing here are downloaded from.
+pub fn set_sync_store(store: Arc<S3Store>) {
+    if SYNC_STORE.set(store).is_err() {
+        warn!("sync bucket was set already, keeping the first one");
+    }
+}
+
+/// Downloads the media file at `path` from the sync bucket when it's
+/// missing, as after a restore without the media. It stays encrypted with
+/// the media key. Returns whether it was downloaded.
+pub async fn hydrate_media(path: &Path) -> Result<bool> {
+    let Some(store) = SYNC_STORE.get() else {
+        return Ok(false);
+    };
+    download_media(store, path).await
+}
+
+async fn download_media(store: &S3Store, path: &Path) -> Result<bool> {
+    if tokio::fs::try_exists(path).await? {
+        return Ok(false);
+    }
+    let Some(file_name) = path.file_name() else {
+        return Ok(false);
+    };
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    // renamed in place once complete, a reader never sees half a file
+    let partial = path.with_extension("partial");
+    let object_key = media_object_key(&file_name.to_string_lossy());
+    match store.get_to_file(&object_key, &partial).await {
+        Ok(true) => {}
+        Ok(false) => return Ok(false),
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&partial).await;
+            return Err(e);
+        }
+    }
+    tokio::fs::rename(&partial, path).await?;
+    debug!("sync: downloaded {}", path.display());
+    Ok(true)
+}
+
+/// Uploads the video and audio chunks once they are written, and the
+/// database at each run: a snapshot of all of it every `SNAPSHOT_EVERY`
+/// runs or after something was deleted, the rows added since the run before
+/// otherwise. A snapshot replaces the uploads before it, and deleted chunks
+/// are deleted from the bucket. Everything is encrypted with the media key
+/// before it leaves, chunks encrypted on disk already are uploaded as they
+/// are.
+pub struct SyncEngine {
+    db: Arc<DatabaseManager>,
+    store: Arc<S3Store>,
+    key: MediaKey,
+}
+
+impl SyncEngine {
+    pub fn new(db: Arc<DatabaseManager>, store: Arc<S3Store>, key: MediaKey) -> Arc<Self> {
+        Arc::new(Self { db, store, key })
+    }
+
+    pub fn start(self: &Arc<Self>, interval: Duration) {
+        let engine = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                match engine.sync().await {
+                    Ok((media, Some(run))) => info!(
+                        "sync: uploaded {} media files and a {} of {} bytes",
+                        media, run.kind, run.bytes
+                    ),
+                    Ok((media, None)) => debug!("sync: uploaded {} media files", media),
+                    Err(e) => error!("sync: failed: {}", e),
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+
+    /// Uploads the chunks written since the run before, deletes the ones
+    /// deleted here from the bucket, then uploads the database. Returns how
+    /// many chunks were uploaded and the run of the database, none when
+    /// nothing was added to it.
+    pub async fn sync(&self) -> Result<(u64, Option<SyncRun>)> {
+        let media = self.push_media().await?;
+        self.delete_media().await?;
+        let run = self.push_database().await?;
+        Ok((media, run))
+    }
+
+    async fn push_media(&self) -> Result<u64> {
+        let mut uploaded = 0;
+        let videos = self.db.list_unsynced_video_chunks(MEDIA_BATCH_SIZE).await?;
+        for (id, file_path) in videos {
+            uploaded += self
+                .push_media_file(MediaChunkKind::Video, id, &file_path)
+                .await?;
+        }
+        let before = Utc::now() - ChronoDuration::minutes(AUDIO_SETTLE_MINUTES);
+        let audio = self
+            .db
+            .list_unsynced_audio_chunks(before, MEDIA_BATCH_SIZE)
+            .await?;
+        for (id, file_path) in audio {
+            uploaded += self
+                .push_media_file(MediaChunkKind::Audio, id, &file_path)
+                .await?;
+        }
+        Ok(uploaded)
+    }
+
+    async fn push_media_file(&self, kind: MediaChunkKind, id: i64, file_path: &str) -> Result<u64> {
+        let path = Path::new(file_path);
+        let (Some(file_name), true) = (path.file_name(), tokio::fs::try_exists(path).await?) else {
+            // deleted files have nothing left to upload
+            self.db.mark_chunk_synced(kind, id).await?;
+            return Ok(0);
+        };
+        let object_key = media_object_key(&file_name.to_string_lossy());
+        let encrypted = {
+            let path = path.to_path_buf();
+            tokio::task::spawn_blocking(move || media_crypto::is_encrypted_file(&path)).await??
+        };
+        if encrypted {
+            self.store.put_file(&object_key, path).await?;
+        } else {
+            let dir = tempfile::tempdir()?;
+            let output = dir.path().join("media.enc");
+            self.encrypt_to(path, &output).await?;
+            self.store.put_file(&object_key, &output).await?;
+        }
+        self.db.mark_chunk_synced(kind, id).await?;
+        Ok(1)
+    }
+
+    async fn encrypt_to(&self, path: &Path, output: &Path) -> Result<()> {
+        let key = self.key.clone();
+        let (path, output) = (path.to_path_buf(), output.to_path_buf());
+        tokio::task::spawn_blocking(move || encrypt_file_to(&key, &path, &output)).await??;
+        Ok(())
+    }
+
+    /// Deletes the chunks uploaded then deleted here from the bucket.
+    async fn delete_media(&self) -> Result<()> {
+        for file_path in self.db.list_deleted_sync_media(MEDIA_BATCH_SIZE).await? {
+            if let Some(file_name) = Path::new(&file_path).file_name() {
+                self.store
+                    .delete(&media_object_key(&file_name.to_string_lossy()))
+                    .await?;
+            }
+            self.db.forget_deleted_sync_media(&file_path).await?;
+        }
+        Ok(())
+    }
+
+    async fn push_database(&self) -> Result<Option<SyncRun>> {
+        let runs = self.db.list_sync_runs(SNAPSHOT_EVERY).await?;
+        // deltas only carry new rows, deletions and masked text reach the
+        // bucket with a snapshot
+        let snapshot_needed = self.db.take_sync_snapshot_needed().await?;
+        let result = self.push_database_run(&runs, snapshot_needed).await;
+        if result.is_err() && snapshot_needed {
+            self.db.request_sync_snapshot().await?;
+        }
+        result
+    }
+
+    async fn push_database_run(
+        &self,
+        runs: &[SyncRun],
+        snapshot_needed: bool,
+    ) -> Result<Option<SyncRun>> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("sync.sqlite");
+        let (kind, watermarks, rows) = match runs.first() {
+            Some(last) if !snapshot_needed && runs.iter().any(|run| run.kind == "snapshot") => {
+                let (watermarks, rows) = self.db.create_sync_delta(&path, &last.watermarks).await?;
+                if rows == 0 {
+                    return Ok(None);
+                }
+                ("delta", watermarks, rows)
+            }
+            _ => ("snapshot", self.db.create_sync_snapshot(&path).await?, 0),
+        };
+
+        let encrypted = dir.path().join("sync.sqlite.enc");
+        self.encrypt_to(&path, &encrypted).await?;
+        let bytes = tokio::fs::metadata(&encrypted).await?.len();
+        // keys sort in the order they were uploaded
+        let object_key = format!(
+            "db/{}-{}.sqlite.enc",
+            Utc::now().format("%Y%m%dT%H%M%S%.3fZ"),
+            kind
+        );
+        self.store.put_file(&object_key, &encrypted).await?;
+        let run = self
+            .db
+            .insert_sync_run(kind, &object_key, &watermarks, rows, bytes)
+            .await?;
+        if kind == "snapshot" {
+            self.prune(&run).await?;
+        }
+        Ok(Some(run))
+    }
+
+    /// Deletes the snapshots and deltas before `snapshot`, which holds all
+    /// they did, less what was deleted since.
+    async fn prune(&self, snapshot: &SyncRun) -> Result<()> {
+        for object_key in self.store.list("db/").await? {
+            if object_key < snapshot.object_key {
+                self.store.delete(&object_key).await?;
+            }
+        }
+        self.db.delete_sync_runs_before(snapshot.id).await?;
+        Ok(())
+    }
+}
+
+/// What `restore` did.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RestoreReport {
+    pub snapshot: String,
+    pub deltas: usize,
+    pub delta_rows: u64,
+    pub relocated_chunks: u64,
+    pub downloaded_media: u64,
+}
+
+/// Restores the database synced to `store` in `data_dir`, which must not
+/// hold one: the last snapshot, then the deltas uploaded after it. The
+/// chunks are moved to the data dir, their files are downloaded now with
+/// `download_all_media` and otherwise the first time they are read, see
+/// `hydrate_media`.
+pub async fn restore(
+    store: &S3Store,
+    key: &MediaKey,
+    data_dir: &Path,
+    download_all_media: bool,
+) -> Result<RestoreReport> {
+    let db_path = data_dir.join("db.sqlite");
+    if tokio::fs::try_exists(&db_path).await? {
+        return Err(anyhow!(
+            "{} exists, restore into an empty data directory",
+            db_path.display()
+        ));
+    }
+    let mut keys = store.list("db/").await?;
+    keys.sort();
+    let snapshot = keys
+        .iter()
+        .rposition(|key| key.ends_with("-snapshot.sqlite.enc"))
+        .ok_or_else(|| anyhow!("no snapshot in the sync bucket"))?;
+
+    tokio::fs::create_dir_all(data_dir).await?;
+    let mut report = RestoreReport {
+        snapshot: keys[snapshot].clone(),
+        ..Default::default()
+    };
+    let dir = tempfile::tempdir()?;
+    fetch_decrypted(store, key, &keys[snapshot], &db_path, dir.path()).await?;
+    let db = DatabaseManager::new(&db_path.to_string_lossy()).await?;
+
+    for (i, delta_key) in keys[snapshot + 1..].iter().enumerate() {
+        let delta_path = dir.path().join(format!("delta-{}.sqlite", i));
+        fetch_decrypted(store, key, delta_key, &delta_path, dir.path()).await?;
+        report.delta_rows += db.apply_sync_delta(&delta_path).await?;
+        report.deltas += 1;
+    }
+
+    let media_dir: PathBuf = data_dir.join("data");
+    tokio::fs::create_dir_all(&media_dir).await?;
+    report.relocated_chunks = db.relocate_media(&media_dir).await?;
+    if download_all_media {
+        for media_key in store.list("media/").await? {
+            let Some(file_name) = media_key.strip_prefix("media/") else {
+                continue;
+            };
+            if download_media(store, &media_dir.join(file_name)).await? {
+                report.downloaded_media += 1;
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Downloads the object at `object_key` to `output` decrypted, through a
+/// file in `dir`.
+async fn fetch_decrypted(
+    store: &S3Store,
+    key: &MediaKey,
+    object_key: &str,
+    output: &Path,
+    dir: &Path,
+) -> Result<()> {
+    let encrypted = dir.join("download.enc");
+    if !store.get_to_file(object_key, &encrypted).await? {
+        return Err(anyhow!("{} is gone from the sync bucket", object_key));
+    }
+    let key = key.clone();
+    let output = output.to_path_buf();
+    tokio::task::spawn_blocking(move || media_crypto::decrypt_file(&key, &encrypted, &output))
+        .await??;
+    Ok(())
+}
@@ -0,0 +1,171 @@
+use anyhow::{anyhow, Result};
+use reqwest::Client;
+use screenpipe_core::egress;
+use screenpipe_db::{normalize_tag, DatabaseManager, TagSuggestionSample};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error, info};
+
+// text of a sample sent to the llm, in characters
+const MAX_SAMPLE_CHARS: usize = 4_000;
+const LLM_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// What the llm is asked for a sample: at most three of the existing tags,
+/// as a json array.
+pub fn suggestion_prompt(taxonomy: &[String], text: &str) -> String {
+    let text: String = text.chars().take(MAX_SAMPLE_CHARS).collect();
+    format!(
+        "Tags: {}\n\nText captured from the screen or a microphone:\n{}\n\n\
+         Answer with a json array of at most three of the tags above that \
+         describe the text, [] when none does. Use no other tags.",
+        serde_json::to_string(taxonomy).unwrap_or_default(),
+        text.trim()
+    )
+}
+
+/// The tags of the taxonomy found in the llm reply, a json array or a list
+/// separated by commas or lines, spelled as in the taxonomy. Anything else
+/// is dropped.
+pub fn parse_suggestions(reply: &str, taxonomy: &[String]) -> Vec<String> {
+    let json = match (reply.find('['), reply.rfind(']')) {
+        (Some(start), Some(end)) if start < end => {
+            serde_json::from_str::<Vec<String>>(&reply[start..=end]).ok()
+        }
+        _ => None,
+    };
+    let candidates = json.unwrap_or_else(|| {
+        reply
+            .split([',', '\n'])
+            .map(|tag| tag.trim_matches(|c: char| c.is_whitespace() || "-*\"'`".contains(c)))
+            .map(str::to_string)
+            .collect()
+    });
+
+    let mut tags: Vec<String> = Vec::new();
+    for candidate in candidates {
+        let candidate = normalize_tag(&candidate).to_lowercase();
+        if let Some(tag) = taxonomy.iter().find(|tag| tag.to_lowercase() == candidate) {
+            if !tags.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+    }
+    tags
+}
+
+/// Samples untagged frames and audio chunks now and then and asks an llm,
+/// through an OpenAI compatible chat completions api, which of the existing
+/// tags fit them. The tags are stored as pending suggestions to accept or
+/// reject at /tag-suggestions, never applied directly.
+pub struct TagSuggester {
+    db: Arc<DatabaseManager>,
+    client: Client,
+    url: String,
+    model: String,
+    api_key: Option<String>,
+    samples: u32,
+}
+
+impl TagSuggester {
+    /// `url` is the chat completions endpoint, e.g.
+    /// "http://localhost:11434/v1/chat/completions" for ollama, asked for up
+    /// to `samples` frames and `samples` audio chunks a run.
+    pub fn new(
+        db: Arc<DatabaseManager>,
+        url: String,
+        model: String,
+        api_key: Option<String>,
+        samples: u32,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            db,
+            client: egress::http_client(),
+            url,
+            model,
+            api_key: api_key.filter(|key| !key.is_empty()),
+            samples,
+        })
+    }
+
+    pub fn start(self: &Arc<Self>, interval: Duration) {
+        let suggester = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                match suggester.run().await {
+                    Ok(0) => debug!("tag suggestions: nothing suggested"),
+                    Ok(suggested) => info!("tag suggestions: suggested {} tags", suggested),
+                    Err(e) => error!("tag suggestions: {}", e),
+                }
+            }
+        });
+    }
+
+    /// Asks suggestions for a new sample. Returns how many tags were
+    /// suggested. Stops at the first failing request, the samples left are
+    /// asked about again next time.
+    pub async fn run(&self) -> Result<usize> {
+        let taxonomy: Vec<String> = self
+            .db
+            .list_tags()
+            .await?
+            .into_iter()
+            .map(|tag| tag.name)
+            .collect();
+        // with nothing to pick from there is nothing to suggest
+        if taxonomy.is_empty() {
+            return Ok(0);
+        }
+
+        let mut suggested = 0;
+        for sample in self.db.tag_suggestion_samples(self.samples).await? {
+            let tags = self.suggest(&taxonomy, &sample).await?;
+            self.db
+                .insert_tag_suggestions(&sample, &tags, &self.model)
+                .await?;
+            suggested += tags.len();
+        }
+        Ok(suggested)
+    }
+
+    async fn suggest(
+        &self,
+        taxonomy: &[String],
+        sample: &TagSuggestionSample,
+    ) -> Result<Vec<String>> {
+        egress::check_egress(&self.url)?;
+        let mut request = self
+            .client
+            .post(&self.url)
+            .timeout(LLM_TIMEOUT)
+            .json(&json!({
+                "model": self.model,
+                "temperature": 0,
+                "messages": [
+                    {
+                        "role": "system",
+                        "content": "You tag screen recordings and transcripts with tags chosen from a fixed list.",
+                    },
+                    {
+                        "role": "user",
+                        "content": suggestion_prompt(taxonomy, &sample.text),
+                    },
+                ],
+            }));
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            return Err(anyhow!("{}: {}", status, body));
+        }
+        let body: Value = serde_json::from_str(&body)?;
+        let reply = body["choices"][0]["message"]["content"]
+            .as_str()
+            .ok_or_else(|| anyhow!("no reply in {}", body))?;
+        Ok(parse_suggestions(reply, taxonomy))
+    }
+}
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use reqwest::Client;
+use screenpipe_core::egress;
 use serde::{Deserialize, Serialize};
 use tracing::{debug, error, info};
 
@@ -16,9 +16,12 @@ struct OllamaResponse {
 
 /// Generates embeddings for text using Ollama's nomic-embed-text model
 pub async fn generate_embedding(text: &str, frame_id: i64) -> Result<Vec<f32>> {
-    let client = Client::new();
-    
-    debug!("generating embedding for frame_id: {}, text: {}", frame_id, text);
+    let client = egress::http_client();
+
+    debug!(
+        "generating embedding for frame_id: {}, text: {}",
+        frame_id, text
+    );
 
     // Check if Ollama server is running
     if let Err(e) = client.get("http://localhost:11434/api/version").send().await {
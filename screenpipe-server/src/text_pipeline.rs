@@ -0,0 +1,326 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use regex::{Captures, Regex};
+use screenpipe_core::SecretMasker;
+use screenpipe_db::{
+    ContentHook, ContentProcessor, DatabaseManager, HookContent, HookOutcome, StageProvenance,
+    TagContentType, TextProvenance,
+};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Arc;
+use unicode_normalization::UnicodeNormalization;
+
+/// Text shorter than this has too few words to tell its language.
+const MIN_LANGUAGE_CHARS: usize = 20;
+
+/// A post-processing stage run on OCR text or transcriptions before they are
+/// stored.
+pub trait TextStage: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Recorded with the name, changes when the stage would change the text
+    /// differently.
+    fn version(&self) -> &str;
+
+    /// The text as the stage leaves it, and tags for its frame or audio chunk.
+    fn apply(&self, text: &str) -> (String, Vec<String>);
+
+    /// Whether the stage masks secrets, which are then masked in the text
+    /// recorded as read too.
+    fn masks(&self) -> bool {
+        false
+    }
+}
+
+/// The built-in stages, see `TextPipeline::from_config`.
+#[derive(Clone, Copy, Debug, ValueEnum, PartialEq)]
+pub enum PipelineStage {
+    /// unicode NFKC, no control characters, single spaces, no blank lines
+    Normalize,
+    /// the corrections of --spell-corrections
+    Spell,
+    /// secrets masked like --mask-secrets does
+    Redact,
+    /// tags the language, e.g. `lang:eng`, when it can be told reliably
+    Language,
+}
+
+pub struct Normalize;
+
+impl TextStage for Normalize {
+    fn name(&self) -> &str {
+        "normalize"
+    }
+
+    fn version(&self) -> &str {
+        "1"
+    }
+
+    fn apply(&self, text: &str) -> (String, Vec<String>) {
+        let text: String = text
+            .nfkc()
+            .filter(|c| !c.is_control() || *c == '\n' || *c == '\t')
+            .collect();
+        let text = text
+            .lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+        (text, Vec::new())
+    }
+}
+
+/// Replaces whole words from a list of corrections, keeping their case.
+pub struct SpellCorrect {
+    corrections: HashMap<String, String>,
+    version: String,
+    words: Regex,
+}
+
+impl SpellCorrect {
+    pub fn new(corrections: HashMap<String, String>) -> Self {
+        // the version tells the lists apart
+        let sorted: BTreeMap<_, _> = corrections
+            .iter()
+            .map(|(word, correction)| (word.to_lowercase(), correction.clone()))
+            .collect();
+        let digest = Sha256::digest(serde_json::to_vec(&sorted).unwrap_or_default());
+        Self {
+            version: format!("{:x}", digest)[..8].to_string(),
+            corrections: sorted.into_iter().collect(),
+            words: Regex::new(r"[\p{L}\p{N}']+").unwrap(),
+        }
+    }
+
+    /// Reads a JSON object of words to their correction.
+    pub fn load(path: &Path) -> Result<Self> {
+        let corrections = serde_json::from_str(
+            &std::fs::read_to_string(path)
+                .with_context(|| format!("failed to read {}", path.display()))?,
+        )
+        .with_context(|| format!("{} is not an object of corrections", path.display()))?;
+        Ok(Self::new(corrections))
+    }
+}
+
+fn match_case(word: &str, correction: &str) -> String {
+    let mut letters = word.chars().filter(|c| c.is_alphabetic());
+    if word.chars().count() > 1 && letters.all(|c| c.is_uppercase()) {
+        return correction.to_uppercase();
+    }
+    let mut chars = correction.chars();
+    match (word.chars().next(), chars.next()) {
+        (Some(first), Some(correction_first)) if first.is_uppercase() => {
+            correction_first.to_uppercase().chain(chars).collect()
+        }
+        _ => correction.to_string(),
+    }
+}
+
+impl TextStage for SpellCorrect {
+    fn name(&self) -> &str {
+        "spell"
+    }
+
+    fn version(&self) -> &str {
+        &self.version
+    }
+
+    fn apply(&self, text: &str) -> (String, Vec<String>) {
+        if self.corrections.is_empty() {
+            return (text.to_string(), Vec::new());
+        }
+        let text = self.words.replace_all(text, |captures: &Captures| {
+            let word = &captures[0];
+            match self.corrections.get(&word.to_lowercase()) {
+                Some(correction) => match_case(word, correction),
+                None => word.to_string(),
+            }
+        });
+        (text.into_owned(), Vec::new())
+    }
+}
+
+pub struct Redact {
+    masker: SecretMasker,
+}
+
+impl Redact {
+    pub fn new(masker: SecretMasker) -> Self {
+        Self { masker }
+    }
+}
+
+impl TextStage for Redact {
+    fn name(&self) -> &str {
+        "redact"
+    }
+
+    fn version(&self) -> &str {
+        "1"
+    }
+
+    fn apply(&self, text: &str) -> (String, Vec<String>) {
+        (self.masker.mask(text), Vec::new())
+    }
+
+    fn masks(&self) -> bool {
+        true
+    }
+}
+
+pub struct LanguageTag;
+
+impl TextStage for LanguageTag {
+    fn name(&self) -> &str {
+        "language"
+    }
+
+    fn version(&self) -> &str {
+        "1"
+    }
+
+    fn apply(&self, text: &str) -> (String, Vec<String>) {
+        let letters = text.chars().filter(|c| c.is_alphabetic()).count();
+        let tags = match whatlang::detect(text) {
+            Some(info) if letters >= MIN_LANGUAGE_CHARS && info.is_reliable() => {
+                vec![format!("lang:{}", info.lang().code())]
+            }
+            _ => Vec::new(),
+        };
+        (text.to_string(), tags)
+    }
+}
+
+/// The stages run, in order, on OCR text and on transcriptions before they
+/// are stored. What they ran on and which ran is recorded, so stored text can
+/// be traced back and re-processed when the stages change.
+pub struct TextPipeline {
+    ocr: Vec<Arc<dyn TextStage>>,
+    transcription: Vec<Arc<dyn TextStage>>,
+}
+
+impl TextPipeline {
+    pub fn new(ocr: Vec<Arc<dyn TextStage>>, transcription: Vec<Arc<dyn TextStage>>) -> Self {
+        Self { ocr, transcription }
+    }
+
+    /// The built-in stages for each source, the spell stage reading its
+    /// corrections from `spell_corrections`.
+    pub fn from_config(
+        ocr: &[PipelineStage],
+        transcription: &[PipelineStage],
+        spell_corrections: Option<&Path>,
+        masker: SecretMasker,
+    ) -> Result<Self> {
+        let spell = if ocr.contains(&PipelineStage::Spell)
+            || transcription.contains(&PipelineStage::Spell)
+        {
+            let path = spell_corrections.context("the spell stage needs --spell-corrections")?;
+            Some(Arc::new(SpellCorrect::load(path)?) as Arc<dyn TextStage>)
+        } else {
+            None
+        };
+        let build = |stages: &[PipelineStage]| -> Vec<Arc<dyn TextStage>> {
+            stages
+                .iter()
+                .map(|stage| match stage {
+                    PipelineStage::Normalize => Arc::new(Normalize) as Arc<dyn TextStage>,
+                    PipelineStage::Spell => spell.clone().unwrap(),
+                    PipelineStage::Redact => Arc::new(Redact::new(masker.clone())),
+                    PipelineStage::Language => Arc::new(LanguageTag),
+                })
+                .collect()
+        };
+        Ok(Self::new(build(ocr), build(transcription)))
+    }
+
+    fn stages(&self, hook: ContentHook) -> &[Arc<dyn TextStage>] {
+        match hook {
+            ContentHook::OnOcr => &self.ocr,
+            ContentHook::OnTranscription => &self.transcription,
+            ContentHook::OnFrame => &[],
+        }
+    }
+
+    /// The names of the stages run on OCR text and on transcriptions.
+    pub fn describe(&self) -> (Vec<String>, Vec<String>) {
+        let names = |stages: &[Arc<dyn TextStage>]| {
+            stages
+                .iter()
+                .map(|stage| stage.name().to_string())
+                .collect()
+        };
+        (names(&self.ocr), names(&self.transcription))
+    }
+
+    /// Runs the stages of `hook` on `text`, the outcome holding the text when
+    /// they changed it.
+    pub fn run(&self, hook: ContentHook, text: &str) -> HookOutcome {
+        let mut outcome = HookOutcome::default();
+        let mut current = text.to_string();
+        let mut raw = text.to_string();
+        for stage in self.stages(hook) {
+            if stage.masks() {
+                raw = stage.apply(&raw).0;
+            }
+            let (processed, tags) = stage.apply(&current);
+            outcome.stages.push(StageProvenance {
+                stage: stage.name().to_string(),
+                version: stage.version().to_string(),
+                changed: processed != current,
+            });
+            outcome.tags.extend(tags);
+            current = processed;
+        }
+        if current != text {
+            outcome.text = Some(current);
+        }
+        if raw != text {
+            outcome.raw_text = Some(raw);
+        }
+        outcome
+    }
+
+    /// Runs the current stages again on the text a frame's OCR or a
+    /// transcription was read as, and stores the result. Tags are not added
+    /// again. None when the content is gone.
+    pub async fn rerun(
+        &self,
+        db: &DatabaseManager,
+        content_type: TagContentType,
+        id: i64,
+    ) -> Result<Option<TextProvenance>> {
+        // without provenance no stage ran, the stored text is as read
+        let raw_text = match db.get_text_provenance(content_type, id).await? {
+            Some(provenance) => provenance.raw_text,
+            None => match db.get_stored_text(content_type, id).await? {
+                Some(text) => text,
+                None => return Ok(None),
+            },
+        };
+        let hook = match content_type {
+            TagContentType::Vision => ContentHook::OnOcr,
+            TagContentType::Audio => ContentHook::OnTranscription,
+        };
+        let outcome = self.run(hook, &raw_text);
+        let text = outcome.text.as_deref().unwrap_or(&raw_text);
+        let recorded = outcome.raw_text.as_deref().unwrap_or(&raw_text);
+        if !db
+            .update_processed_text(content_type, id, recorded, text, &outcome.stages)
+            .await?
+        {
+            return Ok(None);
+        }
+        Ok(db.get_text_provenance(content_type, id).await?)
+    }
+}
+
+impl ContentProcessor for TextPipeline {
+    fn process(&self, content: &HookContent) -> HookOutcome {
+        self.run(content.hook, &content.text)
+    }
+}
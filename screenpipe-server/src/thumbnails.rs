@@ -0,0 +1,71 @@
+use anyhow::Result;
+use image::codecs::jpeg::JpegEncoder;
+use image::DynamicImage;
+use once_cell::sync::Lazy;
+use screenpipe_db::DatabaseManager;
+use tokio::sync::Semaphore;
+use tracing::{debug, warn};
+
+use crate::media_encryption::media_key;
+use crate::video_utils::extract_frame_from_video;
+
+pub const DEFAULT_THUMBNAIL_WIDTH: u32 = 160;
+pub const MIN_THUMBNAIL_WIDTH: u32 = 16;
+pub const MAX_THUMBNAIL_WIDTH: u32 = 640;
+const THUMBNAIL_QUALITY: u8 = 70;
+
+// a timeline asks for a whole strip at once, each one an ffmpeg run
+static THUMBNAIL_PERMITS: Lazy<Semaphore> = Lazy::new(|| Semaphore::new(4));
+
+/// A jpeg of `image` `width` pixels wide, keeping its aspect ratio. Smaller
+/// images aren't scaled up.
+pub fn encode_thumbnail(image: &DynamicImage, width: u32) -> Result<Vec<u8>> {
+    let image = image.thumbnail(width, u32::MAX).to_rgb8();
+    let mut jpeg = Vec::new();
+    JpegEncoder::new_with_quality(&mut jpeg, THUMBNAIL_QUALITY).encode_image(&image)?;
+    Ok(jpeg)
+}
+
+/// The jpeg thumbnail of a frame at `width`, from the cache or made from its
+/// video chunk and cached, unless media is encrypted. None when there's no
+/// such frame, or its media is gone and it had no thumbnail yet.
+pub async fn frame_thumbnail(
+    db: &DatabaseManager,
+    frame_id: i64,
+    width: u32,
+) -> Result<Option<Vec<u8>>> {
+    if let Some(thumbnail) = db.get_frame_thumbnail(frame_id, width).await? {
+        return Ok(Some(thumbnail));
+    }
+    let Some((file_path, offset_index)) = db.get_frame(frame_id).await? else {
+        return Ok(None);
+    };
+    if file_path.is_empty() {
+        return Ok(None);
+    }
+
+    let _permit = THUMBNAIL_PERMITS.acquire().await?;
+    // made while waiting for a permit
+    if let Some(thumbnail) = db.get_frame_thumbnail(frame_id, width).await? {
+        return Ok(Some(thumbnail));
+    }
+    let frame_path = extract_frame_from_video(&file_path, offset_index).await?;
+    let image = image::open(&frame_path);
+    let _ = tokio::fs::remove_file(&frame_path).await;
+    let thumbnail = encode_thumbnail(&image?, width)?;
+
+    // encrypted media stays out of the database, which isn't
+    if media_key().is_none() {
+        // a frame deleted meanwhile still gets its thumbnail served, once
+        if let Err(e) = db.insert_frame_thumbnail(frame_id, width, &thumbnail).await {
+            warn!("failed to cache the thumbnail of frame {}: {}", frame_id, e);
+        }
+    }
+    debug!(
+        "made a {}px thumbnail of frame {}, {} bytes",
+        width,
+        frame_id,
+        thumbnail.len()
+    );
+    Ok(Some(thumbnail))
+}
@@ -0,0 +1,142 @@
+use chrono::{Duration, Local, NaiveDate};
+use oasgen::OaSchema;
+use screenpipe_db::{DatabaseManager, TopicTerm};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tracing::{debug, error};
+
+use crate::journal::local_day;
+use crate::summary::SummaryPeriod;
+
+// terms kept for each day
+const TERMS_PER_DAY: usize = 50;
+// days extracted going back the first time the job runs
+const BACKFILL_DAYS: i64 = 30;
+
+/// Extracts the terms standing out on `date`, in the local timezone, in
+/// place of the ones extracted before.
+pub async fn extract_day(
+    db: &DatabaseManager,
+    date: NaiveDate,
+) -> Result<Vec<TopicTerm>, sqlx::Error> {
+    let (start, end) = local_day(date);
+    db.extract_day_terms(date, start, end, TERMS_PER_DAY).await
+}
+
+/// `term` as terms are extracted, lowercase words one space apart.
+pub fn normalize_term(term: &str) -> String {
+    term.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Extracts the terms of every day that is over and wasn't extracted yet,
+/// for the last month.
+pub struct TopicExtractor {
+    db: Arc<DatabaseManager>,
+}
+
+impl TopicExtractor {
+    pub fn new(db: Arc<DatabaseManager>) -> Arc<Self> {
+        Arc::new(Self { db })
+    }
+
+    pub fn start(self: &Arc<Self>, interval: std::time::Duration) {
+        let extractor = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = extractor.extract_missing_days().await {
+                    error!("topics: failed to extract terms: {}", e);
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+
+    async fn extract_missing_days(&self) -> Result<(), sqlx::Error> {
+        let today = Local::now().date_naive();
+        let first = today - Duration::days(BACKFILL_DAYS);
+        let done = self.db.get_topic_days(first, today).await?;
+        for day in first.iter_days().take_while(|day| *day < today) {
+            if done.contains(&day) {
+                continue;
+            }
+            let terms = extract_day(&self.db, day).await?;
+            debug!("topics: {} terms on {}", terms.len(), day);
+        }
+        Ok(())
+    }
+}
+
+/// How a term stood out over a day or a week.
+#[derive(OaSchema, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrendPoint {
+    /// the first day of the period
+    pub start_date: NaiveDate,
+    /// texts holding the term over the period
+    pub documents: i64,
+    /// the share of the texts holding it, on average over the days
+    /// extracted
+    pub share: f64,
+    /// the scores of its days added up
+    pub score: f64,
+}
+
+#[derive(OaSchema, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TopicTrend {
+    pub term: String,
+    /// one for each period with a day extracted, oldest first, zero when the
+    /// term didn't stand out
+    pub points: Vec<TrendPoint>,
+}
+
+/// The trend of each of `terms` by `period`, from the terms extracted and
+/// the days they were extracted from.
+pub fn topic_trends(
+    terms: &[String],
+    extracted: &[TopicTerm],
+    days: &[NaiveDate],
+    period: SummaryPeriod,
+) -> Vec<TopicTrend> {
+    // the days extracted of each period
+    let mut periods: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+    for day in days {
+        *periods.entry(period.days(*day).0).or_default() += 1;
+    }
+    terms
+        .iter()
+        .map(|term| {
+            let mut points: BTreeMap<NaiveDate, TrendPoint> = periods
+                .keys()
+                .map(|start_date| {
+                    (
+                        *start_date,
+                        TrendPoint {
+                            start_date: *start_date,
+                            documents: 0,
+                            share: 0.0,
+                            score: 0.0,
+                        },
+                    )
+                })
+                .collect();
+            for row in extracted.iter().filter(|row| row.term == *term) {
+                let start_date = period.days(row.day).0;
+                let (Some(point), Some(days)) =
+                    (points.get_mut(&start_date), periods.get(&start_date))
+                else {
+                    continue;
+                };
+                point.documents += row.documents;
+                point.share += row.share / *days as f64;
+                point.score += row.score;
+            }
+            TopicTrend {
+                term: term.clone(),
+                points: points.into_values().collect(),
+            }
+        })
+        .collect()
+}
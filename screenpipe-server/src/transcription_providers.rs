@@ -0,0 +1,100 @@
+use anyhow::Result;
+use screenpipe_audio::transcription::{
+    register_transcription_provider, transcription_provider, transcription_providers,
+    DeepgramProvider, RemoteTranscriptionProvider,
+};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing::info;
+
+/// A transcription server to register as a provider, `<name>=<url>`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteTranscriptionProviderArg {
+    pub name: String,
+    pub url: String,
+}
+
+impl FromStr for RemoteTranscriptionProviderArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid transcription provider {:?}, expected name=url", s);
+        let (name, url) = s.split_once('=').ok_or_else(invalid)?;
+        let (name, url) = (name.trim(), url.trim());
+        if name.is_empty() || !(url.starts_with("http://") || url.starts_with("https://")) {
+            return Err(invalid());
+        }
+        Ok(Self {
+            name: name.to_string(),
+            url: url.to_string(),
+        })
+    }
+}
+
+/// A device transcribed by a provider, `<device>=<provider>`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceTranscriptionProviderArg {
+    pub device: String,
+    pub provider: String,
+}
+
+impl FromStr for DeviceTranscriptionProviderArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // device names may hold a '=', provider names can't
+        let (device, provider) = s
+            .rsplit_once('=')
+            .map(|(device, provider)| (device.trim(), provider.trim()))
+            .filter(|(device, provider)| !device.is_empty() && !provider.is_empty())
+            .ok_or_else(|| {
+                format!(
+                    "invalid device transcription provider {:?}, expected device=provider",
+                    s
+                )
+            })?;
+        Ok(Self {
+            device: device.to_string(),
+            provider: provider.to_string(),
+        })
+    }
+}
+
+/// Registers deepgram and the servers of `--remote-transcription-provider`,
+/// then checks the providers of `--device-transcription-provider` were
+/// registered, by them or by the program embedding screenpipe. Returns the
+/// provider of each device.
+pub fn register_providers(
+    remote: &[RemoteTranscriptionProviderArg],
+    devices: &[DeviceTranscriptionProviderArg],
+    deepgram_api_key: Option<String>,
+) -> Result<HashMap<String, String>> {
+    register_transcription_provider(Arc::new(DeepgramProvider::new(deepgram_api_key)));
+    for provider in remote {
+        register_transcription_provider(Arc::new(RemoteTranscriptionProvider::new(
+            &provider.name,
+            &provider.url,
+            std::env::var("SCREENPIPE_REMOTE_TRANSCRIPTION_API_KEY").ok(),
+        )));
+        info!("registered transcription provider {}", provider.name);
+    }
+
+    let mut selected = HashMap::new();
+    for device in devices {
+        if transcription_provider(&device.provider).is_none() {
+            let registered: Vec<String> = transcription_providers()
+                .iter()
+                .map(|provider| provider.name().to_string())
+                .collect();
+            anyhow::bail!(
+                "no transcription provider named {} for {}, registered: {}",
+                device.provider,
+                device.device,
+                registered.join(", ")
+            );
+        }
+        selected.insert(device.device.clone(), device.provider.clone());
+    }
+    Ok(selected)
+}
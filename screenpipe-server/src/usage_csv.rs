@@ -0,0 +1,116 @@
+use screenpipe_db::UsageRecord;
+use std::borrow::Cow;
+use std::str::FromStr;
+
+/// A column of the usage export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageColumn {
+    Timestamp,
+    Kind,
+    Id,
+    DeviceName,
+    AppName,
+    WindowName,
+    BrowserUrl,
+    DurationSecs,
+    SpeakerName,
+    TextLength,
+}
+
+impl UsageColumn {
+    pub const ALL: [UsageColumn; 10] = [
+        UsageColumn::Timestamp,
+        UsageColumn::Kind,
+        UsageColumn::Id,
+        UsageColumn::DeviceName,
+        UsageColumn::AppName,
+        UsageColumn::WindowName,
+        UsageColumn::BrowserUrl,
+        UsageColumn::DurationSecs,
+        UsageColumn::SpeakerName,
+        UsageColumn::TextLength,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            UsageColumn::Timestamp => "timestamp",
+            UsageColumn::Kind => "kind",
+            UsageColumn::Id => "id",
+            UsageColumn::DeviceName => "device_name",
+            UsageColumn::AppName => "app_name",
+            UsageColumn::WindowName => "window_name",
+            UsageColumn::BrowserUrl => "browser_url",
+            UsageColumn::DurationSecs => "duration_secs",
+            UsageColumn::SpeakerName => "speaker_name",
+            UsageColumn::TextLength => "text_length",
+        }
+    }
+
+    fn value(&self, record: &UsageRecord) -> String {
+        match self {
+            UsageColumn::Timestamp => record.timestamp.to_rfc3339(),
+            UsageColumn::Kind => record.kind.clone(),
+            UsageColumn::Id => record.id.to_string(),
+            UsageColumn::DeviceName => record.device_name.clone(),
+            UsageColumn::AppName => record.app_name.clone().unwrap_or_default(),
+            UsageColumn::WindowName => record.window_name.clone().unwrap_or_default(),
+            UsageColumn::BrowserUrl => record.browser_url.clone().unwrap_or_default(),
+            UsageColumn::DurationSecs => record
+                .duration_secs
+                .map(|secs| format!("{:.3}", secs))
+                .unwrap_or_default(),
+            UsageColumn::SpeakerName => record.speaker_name.clone().unwrap_or_default(),
+            UsageColumn::TextLength => record
+                .text_length
+                .map(|length| length.to_string())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl FromStr for UsageColumn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        UsageColumn::ALL
+            .into_iter()
+            .find(|column| column.name() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown column {:?}, expected one of {}",
+                    s,
+                    UsageColumn::ALL.map(|column| column.name()).join(", ")
+                )
+            })
+    }
+}
+
+/// Columns named in `columns`, comma separated, all of them by default.
+pub fn parse_columns(columns: Option<&str>) -> Result<Vec<UsageColumn>, String> {
+    match columns.map(str::trim).filter(|columns| !columns.is_empty()) {
+        Some(columns) => columns.split(',').map(|c| c.trim().parse()).collect(),
+        None => Ok(UsageColumn::ALL.to_vec()),
+    }
+}
+
+/// A field quoted when it holds a separator, a quote or a line break.
+fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+pub fn csv_header(columns: &[UsageColumn]) -> String {
+    let names: Vec<&str> = columns.iter().map(|column| column.name()).collect();
+    names.join(",") + "\r\n"
+}
+
+pub fn csv_row(record: &UsageRecord, columns: &[UsageColumn]) -> String {
+    let values: Vec<String> = columns
+        .iter()
+        .map(|column| csv_field(&column.value(record)).into_owned())
+        .collect();
+    values.join(",") + "\r\n"
+}
@@ -1,4 +1,5 @@
 use chrono::Utc;
+use clap::ValueEnum;
 use crossbeam::queue::ArrayQueue;
 use image::ImageFormat::{self};
 use screenpipe_core::{find_ffmpeg_path, Language};
@@ -49,6 +50,9 @@ impl VideoCapture {
         include_list: &[String],
         languages: Vec<Language>,
         capture_unfocused_windows: bool,
+        max_idle_interval: Option<Duration>,
+        capture_pauses: Option<Arc<CapturePauses>>,
+        encoding: VideoEncoding,
     ) -> Self {
         let fps = if fps.is_finite() && fps > 0.0 {
             fps
@@ -111,6 +115,7 @@ impl VideoCapture {
                     capture_window_filters.clone(),
                     capture_languages.clone(),
                     capture_unfocused,
+                    max_idle_interval,
                 )
                 .await
                 {
@@ -197,6 +202,15 @@ impl VideoCapture {
                     last_log_time = now;
                 }
 
+                // paused frames go neither to the video nor to the db
+                if capture_pauses
+                    .as_ref()
+                    .is_some_and(|pauses| pauses.vision_paused())
+                {
+                    debug!("Dropping frame {}, vision is paused", frame_number);
+                    continue;
+                }
+
                 debug!("Received frame {} for queueing", frame_number);
 
                 let result = Arc::new(result);
@@ -243,6 +257,7 @@ impl VideoCapture {
                 new_chunk_callback_clone,
                 monitor_id,
                 video_chunk_duration,
+                encoding,
             )
             .await
             {
@@ -337,7 +352,235 @@ impl VideoCapture {
     }
 }
 
-pub async fn start_ffmpeg_process(output_file: &str, fps: f64) -> Result<Child, anyhow::Error> {
+// the render node of the first GPU, where vaapi encodes
+const VAAPI_DEVICE: &str = "/dev/dri/renderD128";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum VideoCodec {
+    /// plays everywhere, about twice the size of h265
+    H264,
+    H265,
+    /// the smallest, slow to encode without a hardware encoder
+    Av1,
+}
+
+impl VideoCodec {
+    /// The name ffprobe gives it, recorded with each video chunk.
+    pub fn codec_name(&self) -> &'static str {
+        match self {
+            VideoCodec::H264 => "h264",
+            VideoCodec::H265 => "hevc",
+            VideoCodec::Av1 => "av1",
+        }
+    }
+
+    /// The codec ffprobe calls `codec_name`, if one of these.
+    pub fn from_codec_name(codec_name: &str) -> Option<Self> {
+        match codec_name {
+            "h264" => Some(VideoCodec::H264),
+            "hevc" => Some(VideoCodec::H265),
+            "av1" => Some(VideoCodec::Av1),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum VideoEncoder {
+    /// on the cpu
+    Software,
+    /// macOS, no av1
+    Videotoolbox,
+    /// NVIDIA GPUs
+    Nvenc,
+    /// Intel and AMD GPUs on Linux
+    Vaapi,
+    /// Intel Quick Sync
+    Qsv,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum VideoContainer {
+    Mp4,
+    /// still readable up to its last frame when recording stops abruptly
+    Mkv,
+}
+
+impl VideoContainer {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            VideoContainer::Mp4 => "mp4",
+            VideoContainer::Mkv => "mkv",
+        }
+    }
+}
+
+/// How captured frames are encoded into video chunks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VideoEncoding {
+    pub codec: VideoCodec,
+    pub encoder: VideoEncoder,
+    pub container: VideoContainer,
+    /// higher is smaller and blurrier. Hardware encoders take it as their
+    /// own quality scale, so the same value isn't the same quality
+    pub crf: u8,
+    /// a keyframe every this many captured frames, the encoder's default
+    /// when none. Fewer keyframes are smaller, more make frames quicker to
+    /// extract
+    pub keyframe_interval: Option<u32>,
+}
+
+impl Default for VideoEncoding {
+    fn default() -> Self {
+        Self {
+            codec: VideoCodec::H265,
+            encoder: VideoEncoder::Software,
+            container: VideoContainer::Mp4,
+            crf: 23,
+            keyframe_interval: None,
+        }
+    }
+}
+
+impl VideoEncoding {
+    /// The ffmpeg encoder, none when `encoder` can't encode `codec`.
+    pub fn encoder_name(&self) -> Option<&'static str> {
+        use VideoCodec::*;
+        use VideoEncoder::*;
+        match (self.encoder, self.codec) {
+            (Software, H264) => Some("libx264"),
+            (Software, H265) => Some("libx265"),
+            (Software, Av1) => Some("libsvtav1"),
+            (Videotoolbox, H264) => Some("h264_videotoolbox"),
+            (Videotoolbox, H265) => Some("hevc_videotoolbox"),
+            (Videotoolbox, Av1) => None,
+            (Nvenc, H264) => Some("h264_nvenc"),
+            (Nvenc, H265) => Some("hevc_nvenc"),
+            (Nvenc, Av1) => Some("av1_nvenc"),
+            (Vaapi, H264) => Some("h264_vaapi"),
+            (Vaapi, H265) => Some("hevc_vaapi"),
+            (Vaapi, Av1) => Some("av1_vaapi"),
+            (Qsv, H264) => Some("h264_qsv"),
+            (Qsv, H265) => Some("hevc_qsv"),
+            (Qsv, Av1) => Some("av1_qsv"),
+        }
+    }
+
+    /// This encoding, on the cpu when the ffmpeg found can't encode it with
+    /// `encoder`. ffmpeg may still fail to open a hardware encoder it has,
+    /// e.g. without a GPU.
+    pub async fn resolve(self) -> Self {
+        if self.encoder == VideoEncoder::Software {
+            return self;
+        }
+        let available = match self.encoder_name() {
+            Some(name) => has_ffmpeg_encoder(name).await,
+            None => false,
+        };
+        if available {
+            return self;
+        }
+        warn!(
+            "{:?} can't encode {:?} here, encoding on the cpu instead",
+            self.encoder, self.codec
+        );
+        Self {
+            encoder: VideoEncoder::Software,
+            ..self
+        }
+    }
+
+    /// The output arguments of ffmpeg encoding with this, filters aside. An
+    /// encoder that can't encode the codec is replaced by the cpu.
+    pub fn codec_args(&self) -> Vec<String> {
+        let encoding = match self.encoder_name() {
+            Some(_) => *self,
+            None => Self {
+                encoder: VideoEncoder::Software,
+                ..*self
+            },
+        };
+        let crf = self.crf.to_string();
+        // videotoolbox goes from 1 to 100, higher is better
+        let quality = (100 - 2 * self.crf.min(49) as u32).to_string();
+        let keyframe_interval = self.keyframe_interval.map(|frames| frames.to_string());
+
+        let mut args = vec!["-vcodec", encoding.encoder_name().unwrap_or("libx265")];
+        match (encoding.encoder, encoding.codec) {
+            (VideoEncoder::Software, VideoCodec::Av1) => {
+                args.extend(["-preset", "12", "-crf", &crf])
+            }
+            (VideoEncoder::Software, _) => args.extend(["-preset", "ultrafast", "-crf", &crf]),
+            (VideoEncoder::Videotoolbox, _) => args.extend(["-q:v", &quality]),
+            (VideoEncoder::Nvenc, _) => args.extend(["-preset", "p1", "-rc", "vbr", "-cq", &crf]),
+            (VideoEncoder::Vaapi, _) => args.extend(["-qp", &crf]),
+            (VideoEncoder::Qsv, _) => args.extend(["-preset", "veryfast", "-global_quality", &crf]),
+        }
+        if let Some(frames) = &keyframe_interval {
+            args.extend(["-g", frames]);
+        }
+        // quicktime only plays hevc tagged hvc1
+        if encoding.codec == VideoCodec::H265 && encoding.container == VideoContainer::Mp4 {
+            args.extend(["-tag:v", "hvc1"]);
+        }
+        match encoding.encoder {
+            // uploaded to the GPU as nv12 by the filters
+            VideoEncoder::Vaapi => {}
+            VideoEncoder::Qsv => args.extend(["-pix_fmt", "nv12"]),
+            _ => args.extend(["-pix_fmt", "yuv420p"]),
+        }
+        args.into_iter().map(str::to_string).collect()
+    }
+
+    /// The ffmpeg arguments encoding the PNG frames written to its stdin at
+    /// `fps` into `output_file`.
+    pub fn ffmpeg_args(&self, fps: f64, output_file: &str) -> Vec<String> {
+        let vaapi = self.encoder == VideoEncoder::Vaapi && self.encoder_name().is_some();
+        let mut args: Vec<String> = Vec::new();
+        if vaapi {
+            args.extend(["-vaapi_device".to_string(), VAAPI_DEVICE.to_string()]);
+        }
+        args.extend(
+            ["-f", "image2pipe", "-vcodec", "png", "-r"]
+                .into_iter()
+                .map(str::to_string),
+        );
+        args.extend([fps.to_string(), "-i".to_string(), "-".to_string()]);
+        let mut filter = "pad=width=ceil(iw/2)*2:height=ceil(ih/2)*2".to_string();
+        if vaapi {
+            filter.push_str(",format=nv12,hwupload");
+        }
+        args.extend(["-vf".to_string(), filter]);
+        args.extend(self.codec_args());
+        args.push(output_file.to_string());
+        args
+    }
+}
+
+async fn has_ffmpeg_encoder(name: &str) -> bool {
+    let Some(ffmpeg_path) = find_ffmpeg_path() else {
+        return false;
+    };
+    match Command::new(ffmpeg_path)
+        .args(["-hide_banner", "-encoders"])
+        .output()
+        .await
+    {
+        Ok(output) => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .any(|line| line.split_whitespace().nth(1) == Some(name)),
+        Err(e) => {
+            warn!("failed to list the encoders of ffmpeg: {}", e);
+            false
+        }
+    }
+}
+
+pub async fn start_ffmpeg_process(
+    output_file: &str,
+    fps: f64,
+    encoding: &VideoEncoding,
+) -> Result<Child, anyhow::Error> {
     // Overriding fps with max fps if over the max and warning user
     let fps = if fps > MAX_FPS {
         warn!("Overriding FPS from {} to {}", fps, MAX_FPS);
@@ -347,33 +590,8 @@ pub async fn start_ffmpeg_process(output_file: &str, fps: f64) -> Result<Child,
     };
 
     info!("Starting FFmpeg process for file: {}", output_file);
-    let fps_str = fps.to_string();
     let mut command = Command::new(find_ffmpeg_path().unwrap());
-    let mut args = vec![
-        "-f",
-        "image2pipe",
-        "-vcodec",
-        "png",
-        "-r",
-        &fps_str,
-        "-i",
-        "-",
-        "-vf",
-        "pad=width=ceil(iw/2)*2:height=ceil(ih/2)*2",
-    ];
-
-    args.extend_from_slice(&[
-        "-vcodec",
-        "libx265",
-        "-tag:v",
-        "hvc1",
-        "-preset",
-        "ultrafast",
-        "-crf",
-        "23",
-    ]);
-
-    args.extend_from_slice(&["-pix_fmt", "yuv420p", output_file]);
+    let args = encoding.ffmpeg_args(fps, output_file);
 
     command
         .args(&args)
@@ -412,6 +630,7 @@ async fn save_frames_as_video(
     new_chunk_callback: Arc<dyn Fn(&str) + Send + Sync>,
     monitor_id: u32,
     video_chunk_duration: Duration,
+    encoding: VideoEncoding,
 ) -> Result<(), anyhow::Error> {
     info!(
         "Starting save_frames_as_video function for monitor {}",
@@ -421,6 +640,9 @@ async fn save_frames_as_video(
     let mut frame_count = 0;
     let mut current_ffmpeg: Option<Child> = None;
     let mut current_stdin: Option<ChildStdin> = None;
+    let mut chunk_dimensions = (0, 0);
+    // frame that didn't fit the current chunk, e.g. after the monitor was downscaled
+    let mut pending_frame: Option<Arc<CaptureResult>> = None;
 
     // Track health metrics
     let start_time = std::time::Instant::now();
@@ -430,7 +652,7 @@ async fn save_frames_as_video(
     let stats_interval = Duration::from_secs(60);
 
     loop {
-        if frame_count >= frames_per_video || current_ffmpeg.is_none() {
+        if frame_count >= frames_per_video || current_ffmpeg.is_none() || pending_frame.is_some() {
             if let Some(child) = current_ffmpeg.take() {
                 info!(
                     "Finishing FFmpeg process for monitor {} after {} frames",
@@ -442,18 +664,23 @@ async fn save_frames_as_video(
 
             frame_count = 0;
             debug!("Waiting for first frame for monitor {}", monitor_id);
-            let first_frame = wait_for_first_frame(frame_queue).await;
+            let first_frame = match pending_frame.take() {
+                Some(frame) => frame,
+                None => wait_for_first_frame(frame_queue).await,
+            };
+            chunk_dimensions = (first_frame.image.width(), first_frame.image.height());
             let buffer = encode_frame(&first_frame);
             debug!("Got first frame for new chunk for monitor {}", monitor_id);
 
-            let output_file = create_output_file(output_path, monitor_id);
+            let output_file =
+                create_output_file(output_path, monitor_id, encoding.container.extension());
             info!(
                 "Starting new video chunk: {} for monitor {}",
                 output_file, monitor_id
             );
             new_chunk_callback(&output_file);
 
-            match start_ffmpeg_process(&output_file, fps).await {
+            match start_ffmpeg_process(&output_file, fps, &encoding).await {
                 Ok(mut child) => {
                     let mut stdin = child.stdin.take().expect("Failed to open stdin");
                     spawn_ffmpeg_loggers(child.stderr.take(), child.stdout.take());
@@ -505,12 +732,13 @@ async fn save_frames_as_video(
             "Processing frames for monitor {}, current count: {}/{}",
             monitor_id, frame_count, frames_per_video
         );
-        process_frames(
+        pending_frame = process_frames(
             frame_queue,
             &mut current_stdin,
             &mut frame_count,
             frames_per_video,
             fps,
+            chunk_dimensions,
         )
         .await;
 
@@ -546,11 +774,14 @@ fn encode_frame(frame: &CaptureResult) -> Vec<u8> {
     buffer
 }
 
-fn create_output_file(output_path: &str, monitor_id: u32) -> String {
+fn create_output_file(output_path: &str, monitor_id: u32, extension: &str) -> String {
     let time = Utc::now();
     let formatted_time = time.format("%Y-%m-%d_%H-%M-%S").to_string();
     PathBuf::from(output_path)
-        .join(format!("monitor_{}_{}.mp4", monitor_id, formatted_time))
+        .join(format!(
+            "monitor_{}_{}.{}",
+            monitor_id, formatted_time, extension
+        ))
         .to_str()
         .expect("Failed to create valid path")
         .to_string()
@@ -571,10 +802,20 @@ async fn process_frames(
     frame_count: &mut usize,
     frames_per_video: usize,
     fps: f64,
-) {
+    chunk_dimensions: (u32, u32),
+) -> Option<Arc<CaptureResult>> {
     let write_timeout = Duration::from_secs_f64(1.0 / fps);
     while *frame_count < frames_per_video {
         if let Some(frame) = frame_queue.pop() {
+            // the encoder can't change resolution mid-stream, start a new chunk instead
+            let dimensions = (frame.image.width(), frame.image.height());
+            if dimensions != chunk_dimensions {
+                debug!(
+                    "frame size changed from {:?} to {:?}, rotating video chunk",
+                    chunk_dimensions, dimensions
+                );
+                return Some(frame);
+            }
             let buffer = encode_frame(&frame);
             if let Some(stdin) = current_stdin.as_mut() {
                 if let Err(e) = write_frame_with_retry(stdin, &buffer).await {
@@ -590,6 +831,7 @@ async fn process_frames(
             tokio::time::sleep(write_timeout).await;
         }
     }
+    None
 }
 
 async fn write_frame_with_retry(
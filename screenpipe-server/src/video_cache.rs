@@ -16,6 +16,8 @@ use tokio::sync::mpsc::Sender;
 use tokio::sync::{mpsc, oneshot};
 use tracing::{debug, error};
 
+use crate::media_encryption::plain_media;
+
 type FrameChannel = mpsc::Sender<TimeSeriesFrame>;
 
 #[derive(Debug, Clone)]
@@ -647,13 +649,14 @@ async fn extract_frame(
     frame_tx: FrameChannel,
     cache_tx: mpsc::Sender<CacheMessage>,
 ) -> Result<usize> {
-    if !is_video_file_complete(&ffmpeg, &video_file_path).await? {
+    let media = plain_media(&video_file_path).await?;
+    if !is_video_file_complete(&ffmpeg, media.path()).await? {
         debug!("skipping incomplete video file: {}", video_file_path);
         return Ok(0);
     }
 
     // Get source FPS from video metadata
-    let source_fps = match get_video_fps(&ffmpeg, &video_file_path).await {
+    let source_fps = match get_video_fps(&ffmpeg, media.path()).await {
         Ok(fps) => fps,
         Err(e) => {
             error!("failed to get video fps, using default 1fps: {}", e);
@@ -700,7 +703,7 @@ async fn extract_frame(
     let mut cmd = Command::new(&ffmpeg);
     cmd.args([
         "-i",
-        &video_file_path,
+        media.path(),
         "-vf",
         &format!("{},format=yuv420p,scale=iw*0.8:ih*0.8", select_filter),
         "-strict",
@@ -0,0 +1,266 @@
+use anyhow::{anyhow, Result};
+use chrono::{Duration as ChronoDuration, Utc};
+use clap::ValueEnum;
+use screenpipe_core::find_ffmpeg_path;
+use screenpipe_db::DatabaseManager;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::{debug, error, info, warn};
+
+use crate::media_encryption::{encrypt_media_file, plain_media};
+use crate::video_utils::extract_frame_from_video;
+
+// chunks re-encoded at each pass, one after the other
+const COMPACTION_BATCH_SIZE: u32 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CompactionCodec {
+    /// the codec of recording, with a slower preset that compresses more
+    H265,
+    /// smaller still, slower to encode and to decode
+    Av1,
+}
+
+impl CompactionCodec {
+    /// The name ffprobe gives it, recorded with the chunk.
+    pub fn codec_name(&self) -> &'static str {
+        match self {
+            CompactionCodec::H265 => "hevc",
+            CompactionCodec::Av1 => "av1",
+        }
+    }
+}
+
+/// How old video chunks are re-encoded.
+#[derive(Debug, Clone)]
+pub struct CompactionConfig {
+    /// chunks whose last frame is older than this are compacted
+    pub after: ChronoDuration,
+    pub codec: CompactionCodec,
+    /// higher is smaller and blurrier, recording uses 23
+    pub crf: u8,
+    /// 2.0 halves width and height, 1.0 keeps them
+    pub scale: f64,
+}
+
+/// The ffmpeg arguments re-encoding the video at `input` to `output`. Every
+/// frame is kept with its timestamp, so frame offsets resolve to the same
+/// frames.
+pub fn compaction_args(config: &CompactionConfig, input: &str, output: &str) -> Vec<String> {
+    let crf = config.crf.to_string();
+    let mut args = vec!["-i", input, "-map", "0:v:0", "-vsync", "passthrough"];
+    let scale;
+    if config.scale.is_finite() && config.scale > 1.0 {
+        // yuv420p needs even dimensions
+        scale = format!("scale=trunc(iw/{0}/2)*2:trunc(ih/{0}/2)*2", config.scale);
+        args.extend(["-vf", scale.as_str()]);
+    }
+    match config.codec {
+        CompactionCodec::H265 => args.extend([
+            "-vcodec", "libx265", "-tag:v", "hvc1", "-preset", "slow", "-crf", &crf,
+        ]),
+        CompactionCodec::Av1 => args.extend(["-vcodec", "libsvtav1", "-preset", "8", "-crf", &crf]),
+    }
+    args.extend(["-pix_fmt", "yuv420p", "-an", "-y", output]);
+    args.into_iter().map(str::to_string).collect()
+}
+
+/// Where the compacted copy of the chunk at `path` is written, next to it.
+fn compacted_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!("{}_compacted.mp4", stem))
+}
+
+async fn count_frames(ffmpeg_path: &Path, video_path: &str) -> Result<u64> {
+    let output = Command::new(ffmpeg_path.with_file_name("ffprobe"))
+        .args([
+            "-v",
+            "error",
+            "-select_streams",
+            "v:0",
+            "-count_packets",
+            "-show_entries",
+            "stream=nb_read_packets",
+            "-of",
+            "csv=p=0",
+            video_path,
+        ])
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "ffprobe failed on {}: {}",
+            video_path,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().parse()?)
+}
+
+/// Re-encodes video chunks once they are old, to a lower bitrate, resolution
+/// or a more efficient codec, for recordings mostly read through their text
+/// by then. A chunk's frames point to the new file in the same transaction
+/// as the chunk, and still have to resolve to a frame through `get_frame`
+/// before the old file is removed.
+pub struct VideoCompactor {
+    db: Arc<DatabaseManager>,
+    config: CompactionConfig,
+}
+
+impl VideoCompactor {
+    pub fn new(db: Arc<DatabaseManager>, config: CompactionConfig) -> Arc<Self> {
+        Arc::new(Self { db, config })
+    }
+
+    pub fn start(self: &Arc<Self>, interval: Duration) {
+        let compactor = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                match compactor.compact_pending().await {
+                    Ok((0, _)) => {}
+                    Ok((compacted, saved)) => info!(
+                        "video compaction: compacted {} chunks, {} MB saved",
+                        compacted,
+                        saved / 1_000_000
+                    ),
+                    Err(e) => error!("video compaction: failed: {}", e),
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+
+    /// Compacts the chunks old enough. Returns how many were and how many
+    /// bytes that saved.
+    pub async fn compact_pending(&self) -> Result<(u64, u64)> {
+        let before = Utc::now() - self.config.after;
+        let chunks = self
+            .db
+            .list_compactable_video_chunks(before, COMPACTION_BATCH_SIZE)
+            .await?;
+        let (mut compacted, mut saved) = (0, 0);
+        for (id, file_path, first_frame, last_frame) in chunks {
+            match self
+                .compact(id, &file_path, &[first_frame, last_frame])
+                .await
+            {
+                Ok(Some(bytes)) => {
+                    compacted += 1;
+                    saved += bytes;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    // not tried again, the chunk keeps its file
+                    warn!("video compaction: failed to compact {}: {}", file_path, e);
+                    self.db.mark_video_chunk_compacted(id).await?;
+                }
+            }
+        }
+        Ok((compacted, saved))
+    }
+
+    /// Returns the bytes saved, none when there was nothing to save.
+    async fn compact(&self, id: i64, file_path: &str, frames: &[i64]) -> Result<Option<u64>> {
+        let original = Path::new(file_path);
+        if !tokio::fs::try_exists(original).await? {
+            debug!("video compaction: {} is gone", file_path);
+            self.db.mark_video_chunk_compacted(id).await?;
+            return Ok(None);
+        }
+        let before = tokio::fs::metadata(original).await?.len();
+        let output = compacted_path(original);
+        let output_path = output.to_string_lossy().into_owned();
+
+        let media = plain_media(file_path).await?;
+        if let Err(e) = self.encode(media.path(), &output_path).await {
+            let _ = tokio::fs::remove_file(&output).await;
+            return Err(e);
+        }
+        // an encrypted chunk stays encrypted
+        if media.was_encrypted() {
+            if let Err(e) = encrypt_media_file(&output).await {
+                let _ = tokio::fs::remove_file(&output).await;
+                return Err(e);
+            }
+        }
+        drop(media);
+
+        let after = tokio::fs::metadata(&output).await?.len();
+        if after >= before {
+            debug!("video compaction: {} can't get smaller", file_path);
+            tokio::fs::remove_file(&output).await?;
+            self.db.mark_video_chunk_compacted(id).await?;
+            return Ok(None);
+        }
+
+        self.db
+            .set_video_chunk_file(id, &output_path, Some(Utc::now()))
+            .await?;
+        if let Err(e) = self.verify(frames, &output_path).await {
+            self.db.set_video_chunk_file(id, file_path, None).await?;
+            let _ = tokio::fs::remove_file(&output).await;
+            return Err(e);
+        }
+        self.db
+            .set_video_chunk_codec(id, self.config.codec.codec_name())
+            .await?;
+        if let Err(e) = tokio::fs::remove_file(original).await {
+            warn!("video compaction: failed to remove {}: {}", file_path, e);
+        }
+        debug!(
+            "video compaction: {} went from {} to {} bytes",
+            file_path, before, after
+        );
+        Ok(Some(before - after))
+    }
+
+    /// Re-encodes the video at `input` to `output`, checking no frame was
+    /// lost.
+    async fn encode(&self, input: &str, output: &str) -> Result<()> {
+        let ffmpeg_path = find_ffmpeg_path().ok_or_else(|| anyhow!("ffmpeg not found"))?;
+        let result = Command::new(&ffmpeg_path)
+            .args(compaction_args(&self.config, input, output))
+            .output()
+            .await?;
+        if !result.status.success() {
+            return Err(anyhow!(
+                "ffmpeg failed: {}",
+                String::from_utf8_lossy(&result.stderr)
+            ));
+        }
+        let (source_frames, compacted_frames) = (
+            count_frames(&ffmpeg_path, input).await?,
+            count_frames(&ffmpeg_path, output).await?,
+        );
+        if source_frames != compacted_frames {
+            return Err(anyhow!(
+                "re-encoding kept {} frames of {}",
+                compacted_frames,
+                source_frames
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks `frames` resolve to a frame of the file at `file_path`.
+    async fn verify(&self, frames: &[i64], file_path: &str) -> Result<()> {
+        for frame_id in frames {
+            let (path, offset_index) = self
+                .db
+                .get_frame(*frame_id)
+                .await?
+                .ok_or_else(|| anyhow!("frame {} is gone", frame_id))?;
+            if path != file_path {
+                return Err(anyhow!("frame {} is in {}", frame_id, path));
+            }
+            let image = extract_frame_from_video(&path, offset_index).await?;
+            let _ = tokio::fs::remove_file(image).await;
+        }
+        Ok(())
+    }
+}
@@ -5,8 +5,10 @@ use chrono::{DateTime, Utc};
 use image::DynamicImage;
 use oasgen::OaSchema;
 use screenpipe_core::find_ffmpeg_path;
+use screenpipe_db::TextBounds;
 use screenpipe_db::VideoMetadata as DBVideoMetadata;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::path::Path;
 use std::path::PathBuf;
 use tokio::io::AsyncReadExt;
@@ -14,6 +16,9 @@ use tokio::process::Command;
 use tracing::{debug, error, info};
 use uuid::Uuid;
 
+use crate::media_encryption::{encrypt_media_file, plain_media};
+use crate::video::{VideoCodec, VideoContainer, VideoEncoding};
+
 #[derive(Debug, Deserialize)]
 struct FFprobeOutput {
     format: Format,
@@ -37,7 +42,9 @@ struct Stream {
 }
 
 pub async fn extract_frame(file_path: &str, offset_index: i64) -> Result<String> {
-    let ffmpeg_path = find_ffmpeg_path().expect("failed to find ffmpeg path");
+    let media = plain_media(file_path).await?;
+    let file_path = media.path();
+    let ffmpeg_path = find_ffmpeg_path().ok_or_else(|| anyhow::anyhow!("ffmpeg not found"))?;
 
     let offset_seconds = offset_index as f64 / 1000.0;
     let offset_str = format!("{:.3}", offset_seconds);
@@ -114,8 +121,10 @@ pub async fn validate_media(file_path: &str) -> Result<()> {
     if !try_exists(file_path).await? {
         return Err(anyhow::anyhow!("media file does not exist: {}", file_path));
     }
+    let media = plain_media(file_path).await?;
+    let file_path = media.path();
 
-    let ffmpeg_path = find_ffmpeg_path().expect("failed to find ffmpeg path");
+    let ffmpeg_path = find_ffmpeg_path().ok_or_else(|| anyhow::anyhow!("ffmpeg not found"))?;
     let status = Command::new(ffmpeg_path)
         .args(["-v", "error", "-i", file_path, "-f", "null", "-"])
         .output()
@@ -128,6 +137,64 @@ pub async fn validate_media(file_path: &str) -> Result<()> {
     }
 }
 
+/// How many frames of the video at `file_path` decode, decrypting it first
+/// when it's encrypted. Fails when the video doesn't decode at all.
+pub async fn count_video_frames(file_path: &str) -> Result<u64> {
+    let media = plain_media(file_path).await?;
+    let ffmpeg_path = find_ffmpeg_path().ok_or_else(|| anyhow::anyhow!("ffmpeg not found"))?;
+    let output = Command::new(ffmpeg_path.with_file_name("ffprobe"))
+        .args([
+            "-v",
+            "error",
+            "-select_streams",
+            "v:0",
+            "-count_frames",
+            "-show_entries",
+            "stream=nb_read_frames",
+            "-of",
+            "csv=p=0",
+            media.path(),
+        ])
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "ffprobe failed on {}: {}",
+            file_path,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().parse()?)
+}
+
+/// The codec of the first video stream of the plain file at `file_path`, as
+/// ffprobe names it.
+pub async fn probe_video_codec(file_path: &str) -> Result<String> {
+    let ffmpeg_path = find_ffmpeg_path().ok_or_else(|| anyhow::anyhow!("ffmpeg not found"))?;
+    let output = Command::new(ffmpeg_path.with_file_name("ffprobe"))
+        .args([
+            "-v",
+            "error",
+            "-select_streams",
+            "v:0",
+            "-show_entries",
+            "stream=codec_name",
+            "-of",
+            "csv=p=0",
+            file_path,
+        ])
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "ffprobe failed on {}: {}",
+            file_path,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
 pub async fn merge_videos(
     request: MergeVideosRequest,
     output_dir: PathBuf,
@@ -148,37 +215,65 @@ pub async fn merge_videos(
     // create a temporary file to store the list of input videos
     let temp_file = output_dir.join("input_list.txt");
     let mut file = tokio::fs::File::create(&temp_file).await?;
+    // encrypted chunks are merged from decrypted copies, kept until ffmpeg
+    // is done
+    let mut inputs = Vec::new();
+    let mut codecs = HashSet::new();
     for video_path in &request.video_paths {
         // video validation before writing in txt
         if let Err(e) = validate_media(video_path).await {
             error!("invalid file in merging, skipping: {:?}", e);
             continue;
         }
+        let media = plain_media(video_path).await?;
         // Escape single quotes in the file path
-        let escaped_path = video_path.replace("'", "'\\''");
+        let escaped_path = media.path().replace("'", "'\\''");
         tokio::io::AsyncWriteExt::write_all(
             &mut file,
             format!("file '{}'\n", escaped_path).as_bytes(),
         )
         .await?;
+        codecs.insert(probe_video_codec(media.path()).await.ok());
+        inputs.push(media);
     }
 
-    let ffmpeg_path = find_ffmpeg_path().expect("failed to find ffmpeg path");
-    let status = Command::new(ffmpeg_path)
-        .args([
-            "-f",
-            "concat",
-            "-safe",
-            "0",
-            "-i",
-            temp_file.to_str().unwrap(),
-            "-c",
-            "copy",
-            "-y",
-            output_path.to_str().unwrap(),
-        ])
-        .output()
-        .await?;
+    // chunks recorded with different codecs can't be joined as they are,
+    // they're decoded and re-encoded to h264, which plays everywhere
+    let mut args: Vec<String> = Vec::new();
+    if codecs.len() > 1 {
+        let mut streams = String::new();
+        for (index, media) in inputs.iter().enumerate() {
+            args.extend(["-i".to_string(), media.path().to_string()]);
+            streams.push_str(&format!("[{}:v]", index));
+        }
+        args.extend([
+            "-filter_complex".to_string(),
+            format!("{}concat=n={}:v=1:a=0[v]", streams, inputs.len()),
+            "-map".to_string(),
+            "[v]".to_string(),
+        ]);
+        args.extend(
+            VideoEncoding {
+                codec: VideoCodec::H264,
+                ..Default::default()
+            }
+            .codec_args(),
+        );
+    } else {
+        args.extend(
+            ["-f", "concat", "-safe", "0", "-i"]
+                .into_iter()
+                .map(str::to_string),
+        );
+        args.extend([
+            temp_file.to_string_lossy().into_owned(),
+            "-c".to_string(),
+            "copy".to_string(),
+        ]);
+    }
+    args.extend(["-y".to_string(), output_path.to_string_lossy().into_owned()]);
+    let ffmpeg_path = find_ffmpeg_path().ok_or_else(|| anyhow::anyhow!("ffmpeg not found"))?;
+    let status = Command::new(ffmpeg_path).args(&args).output().await?;
 
     // clean up the temporary file
     tokio::fs::remove_file(temp_file).await?;
@@ -214,11 +309,114 @@ pub async fn merge_videos(
     }
 }
 
+/// ffmpeg filter blacking out `region` (the whole picture when none) of the
+/// `offset_index`th frame of a chunk, leaving the other frames untouched.
+pub fn redaction_filter(offset_index: i64, region: Option<TextBounds>) -> String {
+    let (x, y, w, h) = match region {
+        Some(region) => (
+            region.left.max(0.0).floor().to_string(),
+            region.top.max(0.0).floor().to_string(),
+            region.width.max(1.0).ceil().to_string(),
+            region.height.max(1.0).ceil().to_string(),
+        ),
+        None => ("0".into(), "0".into(), "iw".into(), "ih".into()),
+    };
+    format!(
+        "drawbox=x={}:y={}:w={}:h={}:color=black:t=fill:enable='eq(n,{})'",
+        x, y, w, h, offset_index
+    )
+}
+
+/// Re-encodes a video chunk with one frame, or a region of it, blacked out.
+/// Chunks only last a minute or so, the whole chunk is re-encoded with the
+/// recording settings rather than just the GOP holding the frame.
+pub async fn redact_video_frame(
+    file_path: &str,
+    offset_index: i64,
+    region: Option<TextBounds>,
+) -> Result<()> {
+    redact_video_frames(file_path, &[(offset_index, region)]).await
+}
+
+/// `redact_video_frame` for several frames of a chunk, in one re-encoding.
+pub async fn redact_video_frames(
+    file_path: &str,
+    frames: &[(i64, Option<TextBounds>)],
+) -> Result<()> {
+    let offsets = frames
+        .iter()
+        .map(|(offset_index, _)| offset_index.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let filter = frames
+        .iter()
+        .map(|(offset_index, region)| redaction_filter(*offset_index, *region))
+        .collect::<Vec<_>>()
+        .join(",");
+    let media = plain_media(file_path).await?;
+    // the chunk keeps its codec and container
+    let container = match Path::new(file_path).extension() {
+        Some(extension) if extension == "mkv" => VideoContainer::Mkv,
+        _ => VideoContainer::Mp4,
+    };
+    let codec = match probe_video_codec(media.path()).await {
+        Ok(codec_name) => VideoCodec::from_codec_name(&codec_name),
+        Err(e) => {
+            debug!("failed to probe the codec of {}: {}", file_path, e);
+            None
+        }
+    };
+    let encoding = VideoEncoding {
+        codec: codec.unwrap_or(VideoCodec::H265),
+        container,
+        ..Default::default()
+    };
+    let redacted_path = format!("{}.redacted.{}", file_path, container.extension());
+    let ffmpeg_path = find_ffmpeg_path().ok_or_else(|| anyhow::anyhow!("ffmpeg not found"))?;
+    let output = Command::new(ffmpeg_path)
+        .args(["-i", media.path(), "-vf", &filter])
+        .args(encoding.codec_args())
+        .args(["-y", &redacted_path])
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        let _ = tokio::fs::remove_file(&redacted_path).await;
+        return Err(anyhow::anyhow!(
+            "ffmpeg failed to redact frames {} of {}: {}",
+            offsets,
+            file_path,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    // an encrypted chunk stays encrypted
+    if media.was_encrypted() {
+        if let Err(e) = encrypt_media_file(Path::new(&redacted_path)).await {
+            let _ = tokio::fs::remove_file(&redacted_path).await;
+            return Err(e);
+        }
+    }
+    tokio::fs::rename(&redacted_path, file_path).await?;
+    info!("redacted frames {} of {}", offsets, file_path);
+    Ok(())
+}
+
+/// Rate at which `extract_frames_from_video` samples a video recorded at
+/// `source_fps`.
+pub fn extracted_fps(source_fps: f64) -> f64 {
+    if source_fps > 10.0 {
+        1.0
+    } else {
+        source_fps
+    }
+}
+
 pub async fn extract_frames_from_video(
     video_path: &std::path::Path,
     output_path: Option<PathBuf>,
 ) -> Result<Vec<DynamicImage>> {
-    let ffmpeg_path = find_ffmpeg_path().expect("failed to find ffmpeg path");
+    let ffmpeg_path = find_ffmpeg_path().ok_or_else(|| anyhow::anyhow!("ffmpeg not found"))?;
     let temp_dir = tempfile::tempdir()?;
     let output_pattern = temp_dir.path().join("frame%d.jpg");
 
@@ -245,7 +443,7 @@ pub async fn extract_frames_from_video(
         }
     };
 
-    let target_fps = if source_fps > 10.0 { 1.0 } else { source_fps };
+    let target_fps = extracted_fps(source_fps);
     let fps_filter = format!("fps={}", target_fps);
 
     // Extract frames using ffmpeg
@@ -378,7 +576,7 @@ fn parse_time_from_filename(path: &str) -> Option<DateTime<Utc>> {
 }
 
 pub async fn get_video_metadata(video_path: &str) -> Result<VideoMetadata> {
-    let ffmpeg_path = find_ffmpeg_path().expect("failed to find ffmpeg path");
+    let ffmpeg_path = find_ffmpeg_path().ok_or_else(|| anyhow::anyhow!("ffmpeg not found"))?;
     let ffprobe_path = ffmpeg_path.with_file_name("ffprobe");
 
     // Try ffprobe first
@@ -555,7 +753,9 @@ impl VideoMetadataOverride {
 }
 
 pub async fn extract_frame_from_video(file_path: &str, offset_index: i64) -> Result<String> {
-    let ffmpeg_path = find_ffmpeg_path().expect("failed to find ffmpeg path");
+    let media = plain_media(file_path).await?;
+    let file_path = media.path();
+    let ffmpeg_path = find_ffmpeg_path().ok_or_else(|| anyhow::anyhow!("ffmpeg not found"))?;
 
     let source_fps = match get_video_fps(&ffmpeg_path, file_path).await {
         Ok(fps) => fps,
@@ -659,7 +859,9 @@ pub async fn extract_high_quality_frame(
     offset_index: i64,
     output_dir: &Path,
 ) -> Result<String> {
-    let ffmpeg_path = find_ffmpeg_path().expect("failed to find ffmpeg path");
+    let media = plain_media(file_path).await?;
+    let file_path = media.path();
+    let ffmpeg_path = find_ffmpeg_path().ok_or_else(|| anyhow::anyhow!("ffmpeg not found"))?;
 
     let source_fps = match get_video_fps(&ffmpeg_path, file_path).await {
         Ok(fps) => fps,
@@ -0,0 +1,235 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+/// Words watched when none are configured: how builds, tests and jobs end.
+pub const DEFAULT_KEYWORDS: &[&str] = &[
+    "failed",
+    "failure",
+    "error",
+    "completed",
+    "succeeded",
+    "finished",
+    "passed",
+];
+
+// how far after a metric's name its value may be printed ("loss: 0.12", "acc = 91.2%")
+const MAX_VALUE_DISTANCE: usize = 16;
+// window titles of terminals change with every command, don't keep them all
+const MAX_TRACKED_WINDOWS: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Above,
+    Below,
+}
+
+/// A metric to watch, written `loss<0.1` or `accuracy>0.9`: fires when the
+/// number printed after the metric's name crosses the value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Threshold {
+    pub metric: String,
+    pub direction: Direction,
+    pub value: f64,
+}
+
+impl FromStr for Threshold {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid threshold {:?}, expected e.g. \"loss<0.1\"", s);
+        let index = s.find(['<', '>']).ok_or_else(invalid)?;
+        let direction = if s[index..].starts_with('<') {
+            Direction::Below
+        } else {
+            Direction::Above
+        };
+        let metric = s[..index].trim();
+        let value = s[index + 1..]
+            .trim_start_matches('=')
+            .trim()
+            .parse::<f64>()
+            .map_err(|_| invalid())?;
+        if metric.is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(Threshold {
+            metric: metric.to_lowercase(),
+            direction,
+            value,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct WatchdogConfig {
+    /// app names or window titles to watch, matched as substrings
+    pub windows: Vec<String>,
+    /// state words to watch, `DEFAULT_KEYWORDS` when empty
+    pub keywords: Vec<String>,
+    pub thresholds: Vec<Threshold>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StateChange {
+    /// a state word appeared in the window
+    Keyword { keyword: String },
+    /// a metric crossed its threshold
+    Threshold {
+        metric: String,
+        value: f64,
+        threshold: f64,
+        direction: Direction,
+    },
+}
+
+/// Payload of the `watchdog_triggered` event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchdogEvent {
+    pub app_name: String,
+    pub window_name: String,
+    pub frame_id: i64,
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub change: StateChange,
+}
+
+#[derive(Debug, Default)]
+struct WindowState {
+    keywords: HashSet<String>,
+    metrics: HashMap<String, f64>,
+}
+
+/// Follows the OCR text of selected windows and reports when it changes
+/// state, e.g. a build turning "FAILED" or a training loss dropping below a
+/// target.
+#[derive(Debug)]
+pub struct Watchdog {
+    config: WatchdogConfig,
+    windows: HashMap<(String, String), WindowState>,
+}
+
+impl Watchdog {
+    pub fn new(config: WatchdogConfig) -> Self {
+        let keywords = if config.keywords.is_empty() {
+            DEFAULT_KEYWORDS.iter().map(|k| k.to_string()).collect()
+        } else {
+            config.keywords.iter().map(|k| k.to_lowercase()).collect()
+        };
+        Self {
+            config: WatchdogConfig {
+                windows: config.windows.iter().map(|w| w.to_lowercase()).collect(),
+                keywords,
+                thresholds: config.thresholds,
+            },
+            windows: HashMap::new(),
+        }
+    }
+
+    pub fn watches(&self, app_name: &str, window_name: &str) -> bool {
+        let app_name = app_name.to_lowercase();
+        let window_name = window_name.to_lowercase();
+        self.config
+            .windows
+            .iter()
+            .any(|pattern| app_name.contains(pattern) || window_name.contains(pattern))
+    }
+
+    /// Compares the text a watched window shows now with what it showed last
+    /// time. The first text seen for a window only sets the baseline, so what
+    /// was already on screen at startup doesn't fire.
+    pub fn observe(&mut self, app_name: &str, window_name: &str, text: &str) -> Vec<StateChange> {
+        if !self.watches(app_name, window_name) {
+            return Vec::new();
+        }
+
+        let text = text.to_lowercase();
+        let keywords: HashSet<String> = self
+            .config
+            .keywords
+            .iter()
+            .filter(|keyword| find_word(&text, keyword).is_some())
+            .cloned()
+            .collect();
+        let metrics: HashMap<String, f64> = self
+            .config
+            .thresholds
+            .iter()
+            .filter_map(|t| Some((t.metric.clone(), metric_value(&text, &t.metric)?)))
+            .collect();
+
+        if self.windows.len() >= MAX_TRACKED_WINDOWS {
+            self.windows.clear();
+        }
+        let key = (app_name.to_string(), window_name.to_string());
+        let Some(previous) = self.windows.get_mut(&key) else {
+            self.windows.insert(key, WindowState { keywords, metrics });
+            return Vec::new();
+        };
+
+        let mut changes: Vec<StateChange> = keywords
+            .difference(&previous.keywords)
+            .map(|keyword| StateChange::Keyword {
+                keyword: keyword.clone(),
+            })
+            .collect();
+        for threshold in &self.config.thresholds {
+            let (Some(before), Some(now)) = (
+                previous.metrics.get(&threshold.metric),
+                metrics.get(&threshold.metric),
+            ) else {
+                continue;
+            };
+            let crossed = match threshold.direction {
+                Direction::Below => *before >= threshold.value && *now < threshold.value,
+                Direction::Above => *before <= threshold.value && *now > threshold.value,
+            };
+            if crossed {
+                changes.push(StateChange::Threshold {
+                    metric: threshold.metric.clone(),
+                    value: *now,
+                    threshold: threshold.value,
+                    direction: threshold.direction,
+                });
+            }
+        }
+
+        previous.keywords = keywords;
+        // a metric scrolled out of view keeps its last value
+        previous.metrics.extend(metrics);
+        changes
+    }
+}
+
+/// Byte offset of the last occurrence of `word` in `text` that isn't part of
+/// a longer word.
+fn find_word(text: &str, word: &str) -> Option<usize> {
+    let is_word_char = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+    text.rmatch_indices(word).map(|(i, _)| i).find(|&i| {
+        !is_word_char(text[..i].chars().next_back())
+            && !is_word_char(text[i + word.len()..].chars().next())
+    })
+}
+
+/// The number printed right after the last mention of `metric`, logs print
+/// the latest value at the bottom.
+fn metric_value(text: &str, metric: &str) -> Option<f64> {
+    let start = find_word(text, metric)? + metric.len();
+    let rest = &text[start..];
+    let number_start = rest
+        .char_indices()
+        .take(MAX_VALUE_DISTANCE)
+        .find(|(_, c)| c.is_ascii_digit() || *c == '-')?
+        .0;
+    let number: String = rest[number_start..]
+        .chars()
+        .enumerate()
+        .take_while(|(i, c)| c.is_ascii_digit() || *c == '.' || (*i == 0 && *c == '-'))
+        .map(|(_, c)| c)
+        .collect();
+    number.trim_end_matches('.').parse().ok()
+}
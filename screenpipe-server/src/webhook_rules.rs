@@ -0,0 +1,102 @@
+use reqwest::Client;
+use screenpipe_core::egress;
+use screenpipe_db::{DatabaseManager, WebhookMatch};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, error, warn};
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Posts the matches of the webhook rules to their webhooks. The rules are
+/// evaluated by the database as OCR text and transcriptions are inserted.
+pub struct WebhookDispatcher {
+    db: Arc<DatabaseManager>,
+    client: Client,
+}
+
+impl WebhookDispatcher {
+    pub fn new(db: Arc<DatabaseManager>) -> Arc<Self> {
+        Arc::new(Self {
+            db,
+            client: egress::http_client(),
+        })
+    }
+
+    pub fn start(self: &Arc<Self>) {
+        let dispatcher = Arc::clone(self);
+        let mut matches = dispatcher.db.subscribe_webhook_matches();
+        tokio::spawn(async move {
+            loop {
+                match matches.recv().await {
+                    Ok(rule_match) => {
+                        // a slow webhook shouldn't hold back the others
+                        let dispatcher = Arc::clone(&dispatcher);
+                        tokio::spawn(async move { dispatcher.post(rule_match).await });
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("webhook rules: dropped {} matches", skipped)
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    async fn post(&self, rule_match: WebhookMatch) {
+        if let Err(e) = egress::check_egress(&rule_match.rule.webhook_url) {
+            error!("webhook rules: rule {}: {}", rule_match.rule.id, e);
+            return;
+        }
+        let result = self
+            .client
+            .post(&rule_match.rule.webhook_url)
+            .timeout(WEBHOOK_TIMEOUT)
+            .json(&payload(&rule_match))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        match result {
+            Ok(_) => debug!("webhook rules: posted match of rule {}", rule_match.rule.id),
+            Err(e) => error!(
+                "webhook rules: failed to post match of rule {}: {}",
+                rule_match.rule.id, e
+            ),
+        }
+    }
+}
+
+/// Body posted for a match. `text` is what chat webhooks such as Slack's
+/// display.
+fn payload(rule_match: &WebhookMatch) -> Value {
+    let source = if rule_match.content_type == "audio" {
+        rule_match.content["device_name"]
+            .as_str()
+            .unwrap_or("audio")
+            .to_string()
+    } else {
+        format!(
+            "{} - {}",
+            rule_match.content["app_name"].as_str().unwrap_or_default(),
+            rule_match.content["window_name"]
+                .as_str()
+                .unwrap_or_default()
+        )
+    };
+    json!({
+        "event": "webhook_rule_matched",
+        "text": format!(
+            "{}: \"{}\" seen in {}",
+            rule_match.rule.name, rule_match.matched, source
+        ),
+        "rule": {
+            "id": rule_match.rule.id,
+            "name": rule_match.rule.name,
+            "pattern": rule_match.rule.pattern,
+        },
+        "matched": rule_match.matched,
+        "type": if rule_match.content_type == "audio" { "Audio" } else { "OCR" },
+        "content": rule_match.content,
+    })
+}
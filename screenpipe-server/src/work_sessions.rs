@@ -0,0 +1,80 @@
+use chrono::{DateTime, Utc};
+use oasgen::OaSchema;
+use screenpipe_db::{DatabaseManager, WorkSession};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error};
+
+/// Minutes without anything recorded that end a session, unless set.
+pub const DEFAULT_BREAK_MINUTES: u64 = 15;
+
+// sessions are detected again this far back, so one going on is extended
+// rather than split
+const LOOKBACK_MINUTES: i64 = 120;
+
+/// Search results in the same work session.
+#[derive(OaSchema, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionGroup {
+    /// none for the results outside of any session
+    pub session: Option<WorkSession>,
+    /// positions of the results in `data`
+    pub results: Vec<usize>,
+}
+
+/// Groups the results at `timestamps` by the session they fall in, the
+/// groups in the order of their first result.
+pub fn group_by_session(
+    timestamps: &[DateTime<Utc>],
+    sessions: &[WorkSession],
+) -> Vec<SessionGroup> {
+    let mut groups: Vec<SessionGroup> = Vec::new();
+    for (i, timestamp) in timestamps.iter().enumerate() {
+        let session = sessions
+            .iter()
+            .find(|session| session.started_at <= *timestamp && *timestamp <= session.ended_at);
+        match groups
+            .iter_mut()
+            .find(|group| group.session.as_ref().map(|s| s.id) == session.map(|s| s.id))
+        {
+            Some(group) => group.results.push(i),
+            None => groups.push(SessionGroup {
+                session: session.cloned(),
+                results: vec![i],
+            }),
+        }
+    }
+    groups
+}
+
+/// Detects work sessions in what was recorded since the runs before.
+pub struct WorkSessionDetector {
+    db: Arc<DatabaseManager>,
+    min_break: chrono::Duration,
+}
+
+impl WorkSessionDetector {
+    pub fn new(db: Arc<DatabaseManager>, min_break: chrono::Duration) -> Arc<Self> {
+        Arc::new(Self { db, min_break })
+    }
+
+    pub fn start(self: &Arc<Self>, interval: Duration) {
+        let detector = Arc::clone(self);
+        let lookback = chrono::Duration::minutes(LOOKBACK_MINUTES)
+            .max(chrono::Duration::from_std(interval * 2).unwrap_or_default());
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let now = Utc::now();
+                match detector
+                    .db
+                    .detect_work_sessions(now - lookback, now, detector.min_break)
+                    .await
+                {
+                    Ok(sessions) => debug!("work sessions: {} detected", sessions.len()),
+                    Err(e) => error!("work sessions: failed to detect sessions: {}", e),
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,140 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use screenpipe_audio::audio_manager::AudioManagerBuilder;
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::{
+    auth::{create_token, ApiScope},
+    PipeManager, SCServer,
+};
+use serde_json::{json, Value};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use tower::ServiceExt;
+
+async fn setup_app(api_auth: bool) -> (Router, Arc<DatabaseManager>) {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let audio_manager = Arc::new(
+        AudioManagerBuilder::new()
+            .output_path("/tmp/screenpipe".into())
+            .build(db.clone())
+            .await
+            .unwrap(),
+    );
+    let app = SCServer::new(
+        db.clone(),
+        SocketAddr::from(([127, 0, 0, 1], 23972)),
+        PathBuf::from(""),
+        Arc::new(PipeManager::new(PathBuf::from(""))),
+        false,
+        false,
+        false,
+        audio_manager,
+    )
+    .with_api_auth(api_auth);
+    (app.create_router(false).await, db)
+}
+
+async fn send(
+    app: &Router,
+    method: &str,
+    uri: &str,
+    token: Option<&str>,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json");
+    if let Some(token) = token {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+    let body = body.map_or_else(Body::empty, |body| Body::from(body.to_string()));
+    let response = app
+        .clone()
+        .oneshot(request.body(body).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (
+        status,
+        serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+    )
+}
+
+#[tokio::test]
+async fn test_raw_sql_is_audited() {
+    let (app, db) = setup_app(true).await;
+    let (admin, _) = create_token(&db, "admin", &[ApiScope::Admin])
+        .await
+        .unwrap();
+    let (sql, _) = create_token(&db, "notebook", &[ApiScope::AdminSql])
+        .await
+        .unwrap();
+
+    let query = "INSERT INTO tags (name) VALUES ('x'), ('y')";
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/raw_sql",
+        Some(&sql),
+        Some(json!({ "query": query })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // the audit log is for admins only
+    let (status, _) = send(&app, "GET", "/audit", Some(&sql), None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, entries) = send(&app, "GET", "/audit?action=raw_sql", Some(&admin), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let entries = entries.as_array().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["actor"], "token:notebook");
+    assert_eq!(entries[0]["target"], query);
+    assert_eq!(entries[0]["rows_affected"], 2);
+}
+
+#[tokio::test]
+async fn test_tag_changes_are_audited() {
+    let (app, db) = setup_app(false).await;
+    db.insert_video_chunk("test_video.mp4", "test_device")
+        .await
+        .unwrap();
+    let frame_id = db
+        .insert_frame("test_device", None, None, None, None, false)
+        .await
+        .unwrap();
+    assert_eq!(frame_id, 1);
+
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/tags/vision/1",
+        None,
+        Some(json!({ "tags": ["work", "urgent"] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(
+        &app,
+        "DELETE",
+        "/tags/vision/1",
+        None,
+        Some(json!({ "tags": ["urgent"] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, entries) = send(&app, "GET", "/audit", None, None).await;
+    let entries = entries.as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["action"], "remove_tags");
+    assert_eq!(entries[0]["target"], "vision:1 urgent");
+    assert_eq!(entries[1]["action"], "add_tags");
+    // no connection info when called in process
+    assert_eq!(entries[1]["actor"], "local");
+}
@@ -0,0 +1,223 @@
+use axum::{
+    body::Body,
+    http::{Method, Request, StatusCode},
+    Router,
+};
+use screenpipe_audio::audio_manager::AudioManagerBuilder;
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::{
+    auth::{create_token, required_scope, ApiScope},
+    PipeManager, SCServer,
+};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use tower::ServiceExt;
+
+async fn setup_test_app() -> (Router, Arc<DatabaseManager>) {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let audio_manager = Arc::new(
+        AudioManagerBuilder::new()
+            .output_path("/tmp/screenpipe".into())
+            .build(db.clone())
+            .await
+            .unwrap(),
+    );
+    let app = SCServer::new(
+        db.clone(),
+        SocketAddr::from(([127, 0, 0, 1], 23949)),
+        PathBuf::from(""),
+        Arc::new(PipeManager::new(PathBuf::from(""))),
+        false,
+        false,
+        false,
+        audio_manager,
+    )
+    .with_api_auth(true);
+    (app.create_router(false).await, db)
+}
+
+async fn status(app: &Router, method: &str, uri: &str, token: Option<&str>) -> StatusCode {
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Content-Type", "application/json");
+    if let Some(token) = token {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+    app.clone()
+        .oneshot(request.body(Body::from("{}")).unwrap())
+        .await
+        .unwrap()
+        .status()
+}
+
+#[test]
+fn test_required_scope() {
+    assert_eq!(
+        required_scope(&Method::GET, "/search"),
+        Some(ApiScope::ReadSearch)
+    );
+    assert_eq!(
+        required_scope(&Method::POST, "/raw_sql"),
+        Some(ApiScope::AdminSql)
+    );
+    assert_eq!(
+        required_scope(&Method::DELETE, "/tags/vision/1"),
+        Some(ApiScope::WriteTags)
+    );
+    assert_eq!(
+        required_scope(&Method::POST, "/bulk/tags"),
+        Some(ApiScope::WriteTags)
+    );
+    assert_eq!(
+        required_scope(&Method::POST, "/tag-names/merge"),
+        Some(ApiScope::WriteTags)
+    );
+    assert_eq!(
+        required_scope(&Method::POST, "/search/tag"),
+        Some(ApiScope::WriteTags)
+    );
+    assert_eq!(
+        required_scope(&Method::POST, "/tag-suggestions/1/accept"),
+        Some(ApiScope::WriteTags)
+    );
+    assert_eq!(
+        required_scope(&Method::GET, "/tag-names"),
+        Some(ApiScope::ReadSearch)
+    );
+    assert_eq!(
+        required_scope(&Method::POST, "/tag-names/delete"),
+        Some(ApiScope::Delete)
+    );
+    assert_eq!(
+        required_scope(&Method::POST, "/pipes/download"),
+        Some(ApiScope::Admin)
+    );
+    assert_eq!(
+        required_scope(&Method::POST, "/pipes/enable"),
+        Some(ApiScope::Admin)
+    );
+    // a pipe's config can change what it runs
+    assert_eq!(
+        required_scope(&Method::POST, "/pipes/update"),
+        Some(ApiScope::Admin)
+    );
+    assert_eq!(
+        required_scope(&Method::POST, "/pipes/update-version"),
+        Some(ApiScope::Admin)
+    );
+    assert_eq!(
+        required_scope(&Method::POST, "/tag-rules"),
+        Some(ApiScope::WriteTags)
+    );
+    assert_eq!(
+        required_scope(&Method::POST, "/tag-rules/1/disable"),
+        Some(ApiScope::WriteTags)
+    );
+    assert_eq!(
+        required_scope(&Method::DELETE, "/tag-rules/1"),
+        Some(ApiScope::WriteTags)
+    );
+    assert_eq!(
+        required_scope(&Method::GET, "/tag-rules"),
+        Some(ApiScope::ReadSearch)
+    );
+    assert_eq!(
+        required_scope(&Method::POST, "/webhook-rules"),
+        Some(ApiScope::Admin)
+    );
+    assert_eq!(
+        required_scope(&Method::POST, "/experimental/input_control"),
+        Some(ApiScope::Admin)
+    );
+    assert_eq!(
+        required_scope(&Method::POST, "/bulk/delete"),
+        Some(ApiScope::Delete)
+    );
+    assert_eq!(
+        required_scope(&Method::POST, "/speakers/delete"),
+        Some(ApiScope::Delete)
+    );
+    assert_eq!(
+        required_scope(&Method::POST, "/search/delete"),
+        Some(ApiScope::Delete)
+    );
+    assert_eq!(
+        required_scope(&Method::POST, "/subjects/erase"),
+        Some(ApiScope::Delete)
+    );
+    assert_eq!(
+        required_scope(&Method::POST, "/frames/3/redact"),
+        Some(ApiScope::Delete)
+    );
+    assert_eq!(
+        required_scope(&Method::POST, "/speakers/update"),
+        Some(ApiScope::Write)
+    );
+    assert_eq!(
+        required_scope(&Method::POST, "/graphql"),
+        Some(ApiScope::ReadSearch)
+    );
+    assert_eq!(
+        required_scope(&Method::POST, "/extensions/install"),
+        Some(ApiScope::Admin)
+    );
+    assert_eq!(
+        required_scope(&Method::GET, "/extensions"),
+        Some(ApiScope::ReadSearch)
+    );
+    assert_eq!(
+        required_scope(&Method::GET, "/auth/tokens"),
+        Some(ApiScope::Admin)
+    );
+    assert_eq!(
+        required_scope(&Method::GET, "/push/destinations"),
+        Some(ApiScope::Admin)
+    );
+    assert_eq!(
+        required_scope(&Method::GET, "/audit"),
+        Some(ApiScope::Admin)
+    );
+    assert_eq!(required_scope(&Method::GET, "/health"), None);
+    assert_eq!(required_scope(&Method::OPTIONS, "/raw_sql"), None);
+}
+
+#[tokio::test]
+async fn test_scopes_are_enforced() {
+    let (app, db) = setup_test_app().await;
+    let (reader, _) = create_token(&db, "dashboard", &[ApiScope::ReadSearch])
+        .await
+        .unwrap();
+
+    assert_eq!(status(&app, "GET", "/health", None).await, StatusCode::OK);
+    assert_eq!(
+        status(&app, "GET", "/search", None).await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        status(&app, "GET", "/search", Some("sp_wrong")).await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        status(&app, "GET", "/search", Some(&reader)).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        status(&app, "POST", "/raw_sql", Some(&reader)).await,
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        status(&app, "GET", "/auth/tokens", Some(&reader)).await,
+        StatusCode::FORBIDDEN
+    );
+
+    let (admin, _) = create_token(&db, "admin", &[ApiScope::Admin])
+        .await
+        .unwrap();
+    assert_eq!(
+        status(&app, "GET", "/auth/tokens", Some(&admin)).await,
+        StatusCode::OK
+    );
+    let tokens = db.list_api_tokens().await.unwrap();
+    assert_eq!(tokens.len(), 2);
+    assert!(tokens[0].last_used_at.is_some());
+}
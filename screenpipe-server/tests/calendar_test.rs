@@ -0,0 +1,146 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{header::CONTENT_TYPE, Request, StatusCode},
+    Router,
+};
+use chrono::{DateTime, Duration, Utc};
+use screenpipe_audio::audio_manager::AudioManagerBuilder;
+use screenpipe_db::{DatabaseManager, Meeting, MeetingParticipant};
+use screenpipe_server::{
+    calendar::{event_title, render_calendar, CalendarMeeting},
+    PipeManager, SCServer,
+};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use tower::ServiceExt;
+
+async fn setup_test_app() -> (Router, Arc<DatabaseManager>) {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let audio_manager = Arc::new(
+        AudioManagerBuilder::new()
+            .output_path("/tmp/screenpipe".into())
+            .build(db.clone())
+            .await
+            .unwrap(),
+    );
+    let router = SCServer::new(
+        db.clone(),
+        SocketAddr::from(([127, 0, 0, 1], 23962)),
+        PathBuf::from(""),
+        Arc::new(PipeManager::new(PathBuf::from(""))),
+        false,
+        false,
+        false,
+        audio_manager,
+    )
+    .create_router(false)
+    .await;
+    (router, db)
+}
+
+fn participant(name: &str, is_local: bool) -> MeetingParticipant {
+    let seen: DateTime<Utc> = "2025-03-14T10:00:00Z".parse().unwrap();
+    MeetingParticipant {
+        id: 1,
+        meeting_id: 1,
+        name: name.to_string(),
+        is_local,
+        speaker_id: None,
+        frame_id: None,
+        first_seen: seen,
+        last_seen: seen,
+    }
+}
+
+#[test]
+fn test_render_calendar() {
+    let start: DateTime<Utc> = "2025-03-14T10:00:00Z".parse().unwrap();
+    let mut meeting = CalendarMeeting {
+        meeting: Meeting {
+            id: 7,
+            meeting_app: "zoom.us".to_string(),
+            start_time: start,
+            end_time: Some(start + Duration::minutes(45)),
+            source: "live".to_string(),
+            title: None,
+        },
+        participants: vec![],
+        speech: Some((start + Duration::minutes(2), start + Duration::minutes(40))),
+    };
+    assert_eq!(event_title(&meeting), "Call on zoom.us");
+    meeting.participants = vec![
+        participant("Me (You)", true),
+        participant("Alice; Product", false),
+        participant("Bob", false),
+    ];
+    assert_eq!(event_title(&meeting), "Call with Alice; Product, Bob");
+    meeting.meeting.title = Some("Weekly sync".to_string());
+    assert_eq!(event_title(&meeting), "Weekly sync");
+    meeting.meeting.title = None;
+
+    let ics = render_calendar(&[meeting.clone()], "http://localhost:3030/");
+    assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+    assert!(ics.ends_with("END:VCALENDAR\r\n"));
+    assert!(ics.contains("\r\nUID:meeting-7@screenpipe\r\n"));
+    // timed from what was said
+    assert!(ics.contains("\r\nDTSTART:20250314T100200Z\r\n"));
+    assert!(ics.contains("\r\nDTEND:20250314T104000Z\r\n"));
+    assert!(ics.contains("\r\nSUMMARY:Call with Alice\\; Product\\, Bob\r\n"));
+    assert!(ics.contains("\r\nURL:http://localhost:3030/meetings/7\r\n"));
+    assert!(ics.split("\r\n").all(|line| line.len() <= 75));
+
+    // nothing said, timed from the meeting
+    meeting.speech = None;
+    meeting.participants = (0..20)
+        .map(|i| participant(&format!("Participant {}", i), false))
+        .collect();
+    let ics = render_calendar(&[meeting], "http://localhost:3030");
+    assert!(ics.contains("\r\nDTSTART:20250314T100000Z\r\n"));
+    assert!(ics.contains("\r\nDTEND:20250314T104500Z\r\n"));
+    assert!(ics.split("\r\n").all(|line| line.len() <= 75));
+    // folded lines unfold to the title and description
+    let unfolded = ics.replace("\r\n ", "");
+    assert!(unfolded.contains("Participant 2 and 17 others\r\n"));
+    assert!(unfolded.contains("Participant 18\\, Participant 19\r\n"));
+}
+
+#[tokio::test]
+async fn test_calendar_feed() {
+    let (app, db) = setup_test_app().await;
+    let meeting_id = db
+        .start_meeting("zoom.us", Utc::now() - Duration::minutes(30))
+        .await
+        .unwrap();
+    db.end_meetings(Utc::now()).await.unwrap();
+    // too old for the feed
+    db.start_meeting("meet", Utc::now() - Duration::days(200))
+        .await
+        .unwrap();
+    db.end_meetings(Utc::now() - Duration::days(200))
+        .await
+        .unwrap();
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/calendar/meetings.ics")
+                .header("host", "screenpipe.local:3030")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[CONTENT_TYPE],
+        "text/calendar; charset=utf-8"
+    );
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let ics = String::from_utf8(body.to_vec()).unwrap();
+    assert_eq!(ics.matches("BEGIN:VEVENT").count(), 1);
+    assert!(ics.contains("SUMMARY:Call on zoom.us"));
+    assert!(ics.contains(&format!(
+        "URL:http://screenpipe.local:3030/meetings/{}",
+        meeting_id
+    )));
+}
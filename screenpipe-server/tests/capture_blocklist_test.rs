@@ -0,0 +1,105 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use screenpipe_audio::audio_manager::AudioManagerBuilder;
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::{PipeManager, SCServer};
+use serde_json::{json, Value};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use tower::ServiceExt;
+
+async fn setup_app() -> (Router, Arc<DatabaseManager>) {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let audio_manager = Arc::new(
+        AudioManagerBuilder::new()
+            .output_path("/tmp/screenpipe".into())
+            .build(db.clone())
+            .await
+            .unwrap(),
+    );
+    let app = SCServer::new(
+        db.clone(),
+        SocketAddr::from(([127, 0, 0, 1], 23968)),
+        PathBuf::from(""),
+        Arc::new(PipeManager::new(PathBuf::from(""))),
+        false,
+        false,
+        false,
+        audio_manager,
+    )
+    .create_router(false)
+    .await;
+    (app, db)
+}
+
+async fn send(app: &Router, method: &str, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json");
+    let body = body.map_or_else(Body::empty, |body| Body::from(body.to_string()));
+    let response = app
+        .clone()
+        .oneshot(request.body(body).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (
+        status,
+        serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+    )
+}
+
+#[tokio::test]
+async fn test_capture_blocklist_endpoints() {
+    let (app, db) = setup_app().await;
+
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/capture/blocklist",
+        Some(json!({"kind": "title", "pattern": "Vault"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/capture/blocklist",
+        Some(json!({"kind": "app", "pattern": "  "})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, rule) = send(
+        &app,
+        "POST",
+        "/capture/blocklist",
+        Some(json!({"kind": "app", "pattern": "Bitwarden"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(rule["source"], "api");
+
+    db.insert_video_chunk("video.mp4", "monitor_1")
+        .await
+        .unwrap();
+    let frame_id = db
+        .insert_frame("monitor_1", None, None, Some("Bitwarden"), None, true)
+        .await
+        .unwrap();
+    assert_eq!(frame_id, 0);
+
+    let (status, rules) = send(&app, "GET", "/capture/blocklist", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(rules.as_array().unwrap().len(), 1);
+
+    let uri = format!("/capture/blocklist/{}", rule["id"]);
+    let (status, _) = send(&app, "DELETE", &uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, "DELETE", &uri, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,159 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use chrono::{Duration, NaiveDate, NaiveDateTime, Utc};
+use screenpipe_audio::audio_manager::AudioManagerBuilder;
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::{pause::CapturePauses, PipeManager, SCServer};
+use serde_json::{json, Value};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use tower::ServiceExt;
+
+async fn setup_app() -> (Router, Arc<DatabaseManager>, Arc<CapturePauses>) {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let audio_manager = Arc::new(
+        AudioManagerBuilder::new()
+            .output_path("/tmp/screenpipe".into())
+            .build(db.clone())
+            .await
+            .unwrap(),
+    );
+    let capture_pauses = CapturePauses::new(db.clone(), None);
+    let app = SCServer::new(
+        db.clone(),
+        SocketAddr::from(([127, 0, 0, 1], 23970)),
+        PathBuf::from(""),
+        Arc::new(PipeManager::new(PathBuf::from(""))),
+        false,
+        false,
+        false,
+        audio_manager,
+    )
+    .with_capture_pauses(capture_pauses.clone())
+    .create_router(false)
+    .await;
+    (app, db, capture_pauses)
+}
+
+async fn send(app: &Router, method: &str, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json");
+    let body = body.map_or_else(Body::empty, |body| Body::from(body.to_string()));
+    let response = app
+        .clone()
+        .oneshot(request.body(body).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (
+        status,
+        serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+    )
+}
+
+// 2025-03-18 is a tuesday
+fn local(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2025, 3, day)
+        .unwrap()
+        .and_hms_opt(hour, minute, 0)
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_pause_and_resume_capture() {
+    let (app, _db, capture_pauses) = setup_app().await;
+
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/capture/pause",
+        Some(json!({"content_type": "screen"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(&app, "POST", "/capture/pause", Some(json!({"minutes": 0}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, pause) = send(
+        &app,
+        "POST",
+        "/capture/pause",
+        Some(json!({"content_type": "vision", "minutes": 30})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(pause["resume_at"].is_string());
+    assert!(capture_pauses.vision_paused());
+    assert!(!capture_pauses.audio_paused());
+
+    let (status, paused) = send(&app, "GET", "/capture/pause", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(paused["vision_paused"], true);
+    assert_eq!(paused["pauses"].as_array().unwrap().len(), 1);
+
+    let (status, ended) = send(&app, "POST", "/capture/resume", Some(json!({}))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(ended.as_array().unwrap().len(), 1);
+    assert!(!capture_pauses.vision_paused());
+
+    // the pause stays as a gap of the timeline
+    let uri = format!(
+        "/capture/pauses?start_time={}&end_time={}",
+        (Utc::now() - Duration::hours(1)).format("%Y-%m-%dT%H:%M:%SZ"),
+        (Utc::now() + Duration::hours(1)).format("%Y-%m-%dT%H:%M:%SZ"),
+    );
+    let (status, gaps) = send(&app, "GET", &uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(gaps.as_array().unwrap().len(), 1);
+    assert!(gaps[0]["ended_at"].is_string());
+}
+
+#[tokio::test]
+async fn test_capture_pause_schedules() {
+    let (app, db, capture_pauses) = setup_app().await;
+
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/capture/pause/schedules",
+        Some(json!({"hours": "evenings"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, schedule) = send(
+        &app,
+        "POST",
+        "/capture/pause/schedules",
+        Some(json!({"hours": "mon-fri 18:00-09:00"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let now = Utc::now();
+    capture_pauses.refresh(now, local(18, 20, 0)).await.unwrap();
+    assert!(capture_pauses.vision_paused());
+    assert!(capture_pauses.audio_paused());
+
+    // resumed by hand, the schedule doesn't pause again until its next hours
+    capture_pauses.resume("all").await.unwrap();
+    capture_pauses.refresh(now, local(18, 21, 0)).await.unwrap();
+    assert!(!capture_pauses.vision_paused());
+    capture_pauses.refresh(now, local(19, 10, 0)).await.unwrap();
+    assert!(!capture_pauses.vision_paused());
+    capture_pauses.refresh(now, local(19, 19, 0)).await.unwrap();
+    assert!(capture_pauses.vision_paused());
+
+    let uri = format!("/capture/pause/schedules/{}", schedule["id"]);
+    let (status, _) = send(&app, "DELETE", &uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(!capture_pauses.vision_paused());
+    assert!(db.list_running_capture_pauses().await.unwrap().is_empty());
+    let (status, _) = send(&app, "DELETE", &uri, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,158 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Method, Request, StatusCode},
+    Router,
+};
+use chrono::Utc;
+use screenpipe_audio::audio_manager::AudioManagerBuilder;
+use screenpipe_db::{ClickSignal, DatabaseManager};
+use screenpipe_server::{content_v2::SearchResponseV2, PipeManager, SCServer};
+use screenpipe_vision::OcrEngine;
+use serde_json::{json, Value};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use tower::ServiceExt;
+
+async fn setup_test_app(click_tracking: bool) -> (Router, Arc<DatabaseManager>) {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let audio_manager = Arc::new(
+        AudioManagerBuilder::new()
+            .output_path("/tmp/screenpipe".into())
+            .build(db.clone())
+            .await
+            .unwrap(),
+    );
+    let router = SCServer::new(
+        db.clone(),
+        SocketAddr::from(([127, 0, 0, 1], 23954)),
+        PathBuf::from(""),
+        Arc::new(PipeManager::new(PathBuf::from(""))),
+        false,
+        false,
+        false,
+        audio_manager,
+    )
+    .with_click_tracking(click_tracking)
+    .create_router(false)
+    .await;
+    (router, db)
+}
+
+async fn request(
+    app: &Router,
+    method: Method,
+    uri: &str,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let request = Request::builder().method(method).uri(uri);
+    let request = match body {
+        Some(body) => request
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string())),
+        None => request.body(Body::empty()),
+    };
+    let response = app.clone().oneshot(request.unwrap()).await.unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn test_clicks_are_opt_in() {
+    let (app, db) = setup_test_app(false).await;
+    let (status, _) = request(
+        &app,
+        Method::POST,
+        "/search/clicks",
+        Some(json!({"q": "budget", "content_type": "ocr", "content_id": 1})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert!(db.list_click_signals(10, 0).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_clicks_boost_decayed_ranking() {
+    let (app, db) = setup_test_app(true).await;
+    db.insert_video_chunk("video.mp4", "monitor_1")
+        .await
+        .unwrap();
+    // the same text, the most recent frame ranks first until the other is
+    // clicked
+    let mut ids = Vec::new();
+    for hours_ago in [2, 1] {
+        let frame_id = db
+            .insert_frame(
+                "monitor_1",
+                Some(Utc::now() - chrono::Duration::hours(hours_ago)),
+                None,
+                Some("Sheets"),
+                Some("budget"),
+                false,
+            )
+            .await
+            .unwrap();
+        db.insert_ocr_text(
+            frame_id,
+            "quarterly budget review",
+            "",
+            Arc::new(OcrEngine::Tesseract),
+        )
+        .await
+        .unwrap();
+        ids.push(frame_id);
+    }
+    let ranked = || async {
+        let (status, body) = request(
+            &app,
+            Method::GET,
+            "/v2/search?q=budget&content_type=ocr&ranking=decayed",
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let response: SearchResponseV2 = serde_json::from_value(body).unwrap();
+        response
+            .data
+            .into_iter()
+            .map(|item| item.id)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        ranked().await,
+        vec![format!("ocr:{}", ids[1]), format!("ocr:{}", ids[0])]
+    );
+
+    let (status, _) = request(
+        &app,
+        Method::POST,
+        "/search/clicks",
+        Some(json!({
+            "q": "Budget",
+            "content_type": "ocr",
+            "content_id": ids[0],
+            "dwell_secs": 45.0
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        ranked().await,
+        vec![format!("ocr:{}", ids[0]), format!("ocr:{}", ids[1])]
+    );
+
+    let (status, body) = request(&app, Method::GET, "/search/clicks", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let signals: Vec<ClickSignal> = serde_json::from_value(body).unwrap();
+    assert_eq!(signals.len(), 1);
+    assert_eq!(signals[0].query, "budget");
+    assert_eq!(signals[0].content_id, ids[0]);
+    assert!(signals[0].boost > 1.0);
+
+    let (status, body) = request(&app, Method::DELETE, "/search/clicks", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["deleted"], 1);
+    assert_eq!(
+        ranked().await,
+        vec![format!("ocr:{}", ids[1]), format!("ocr:{}", ids[0])]
+    );
+}
@@ -0,0 +1,64 @@
+use screenpipe_core::SecretMasker;
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::clipboard::{clipboard_ignored, ClipboardMonitor};
+use std::sync::Arc;
+
+fn terminal() -> Option<(String, String)> {
+    Some(("Terminal".to_string(), "zsh".to_string()))
+}
+
+fn bitwarden() -> Option<(String, String)> {
+    Some(("Bitwarden".to_string(), "My Vault".to_string()))
+}
+
+#[test]
+fn test_clipboard_ignored() {
+    let ignored = vec!["bitwarden".to_string(), "".to_string()];
+    assert!(clipboard_ignored(&ignored, "Bitwarden", "My Vault"));
+    assert!(clipboard_ignored(&ignored, "Firefox", "bitwarden.com"));
+    assert!(!clipboard_ignored(&ignored, "Terminal", "zsh"));
+    assert!(!clipboard_ignored(&[], "Bitwarden", ""));
+}
+
+#[tokio::test]
+async fn test_clipboard_monitor_records_copied_text() {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let monitor = ClipboardMonitor::new(
+        db.clone(),
+        vec!["bitwarden".to_string()],
+        Some(Arc::new(SecretMasker::default())),
+        None,
+    );
+
+    let first = monitor.record("cargo test", terminal).await.unwrap();
+    assert!(first.is_some());
+    // the clipboard didn't change
+    assert_eq!(monitor.record("cargo test", terminal).await.unwrap(), None);
+    assert_eq!(monitor.record("hunter2", bitwarden).await.unwrap(), None);
+    assert_eq!(monitor.record("   ", terminal).await.unwrap(), None);
+    assert!(monitor
+        .record(
+            "AWS_SECRET_ACCESS_KEY=wJalrXUtnFEMI/K7MDENG/bPxRfiCYEXAMPLEKEY",
+            || None
+        )
+        .await
+        .unwrap()
+        .is_some());
+
+    let rows: Vec<(String, Option<String>, Option<String>)> =
+        sqlx::query_as("SELECT text, app_name, window_name FROM clipboard ORDER BY id")
+            .fetch_all(&db.pool)
+            .await
+            .unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(
+        rows[0],
+        (
+            "cargo test".to_string(),
+            Some("Terminal".to_string()),
+            Some("zsh".to_string())
+        )
+    );
+    assert!(rows[1].0.contains("[REDACTED:"));
+    assert_eq!((rows[1].1.clone(), rows[1].2.clone()), (None, None));
+}
@@ -0,0 +1,49 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use screenpipe_server::clock_sync::{to_server_time, ClockExchange};
+
+fn at(millis: i64) -> DateTime<Utc> {
+    Utc.timestamp_millis_opt(1_736_000_000_000 + millis)
+        .unwrap()
+}
+
+#[test]
+fn test_clock_exchange_offset_and_delay() {
+    // agent 5s behind, 40ms each way, 10ms spent on the server
+    let exchange = ClockExchange {
+        t0: at(0),
+        t1: at(5_040),
+        t2: at(5_050),
+        t3: at(90),
+    };
+    assert_eq!(exchange.offset_ms(), 5_000.0);
+    assert_eq!(exchange.delay_ms(), 80.0);
+    assert!(exchange.is_valid());
+
+    // a slow way back only shifts the estimate by half of the difference
+    let asymmetric = ClockExchange {
+        t3: at(150),
+        ..exchange
+    };
+    assert_eq!(asymmetric.offset_ms(), 4_970.0);
+    assert_eq!(asymmetric.delay_ms(), 140.0);
+}
+
+#[test]
+fn test_clock_exchange_going_backwards_is_invalid() {
+    let exchange = ClockExchange {
+        t0: at(100),
+        t1: at(5_040),
+        t2: at(5_050),
+        t3: at(0),
+    };
+    assert!(!exchange.is_valid());
+}
+
+#[test]
+fn test_to_server_time() {
+    assert_eq!(to_server_time(at(0), 5_000.0), at(5_000));
+    assert_eq!(
+        to_server_time(at(0), -250.5),
+        at(0) - Duration::microseconds(250_500)
+    );
+}
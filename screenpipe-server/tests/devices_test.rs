@@ -0,0 +1,116 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use screenpipe_audio::audio_manager::AudioManagerBuilder;
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::{PipeManager, SCServer};
+use screenpipe_vision::capture_settings::get_monitor_settings;
+use serde_json::{json, Value};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use tower::ServiceExt;
+
+async fn setup_test_app() -> (Router, Arc<DatabaseManager>) {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let audio_manager = Arc::new(
+        AudioManagerBuilder::new()
+            .output_path("/tmp/screenpipe".into())
+            .build(db.clone())
+            .await
+            .unwrap(),
+    );
+    let app = SCServer::new(
+        db.clone(),
+        SocketAddr::from(([127, 0, 0, 1], 23971)),
+        PathBuf::from(""),
+        Arc::new(PipeManager::new(PathBuf::from(""))),
+        false,
+        false,
+        false,
+        audio_manager,
+    )
+    .create_router(false)
+    .await;
+    (app, db)
+}
+
+async fn send(app: &Router, method: &str, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json");
+    let body = match body {
+        Some(body) => Body::from(body.to_string()),
+        None => Body::empty(),
+    };
+    let response = app
+        .clone()
+        .oneshot(request.body(body).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn test_update_device_settings() {
+    let (app, db) = setup_test_app().await;
+    db.insert_video_chunk("chunk.mp4", "monitor_7")
+        .await
+        .unwrap();
+    let mic = db.register_device("audio_input", "mic").await.unwrap();
+
+    let (status, devices) = send(&app, "GET", "/devices", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(devices.as_array().unwrap().len(), 2);
+    let monitor = devices
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|device| device["kind"] == "monitor")
+        .unwrap();
+    assert_eq!(monitor["name"], "monitor_7");
+    assert_eq!(monitor["enabled"], true);
+
+    let uri = format!("/devices/{}", monitor["id"]);
+    let (status, device) = send(
+        &app,
+        "POST",
+        &uri,
+        Some(json!({"enabled": false, "fps": 0.2})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(device["enabled"], false);
+    assert_eq!(device["fps"], 0.2);
+    // applied to the capture loop of the monitor right away
+    let settings = get_monitor_settings(7);
+    assert!(!settings.enabled);
+    assert_eq!(settings.fps, Some(0.2));
+
+    let (status, device) = send(&app, "POST", &uri, Some(json!({"fps": 0}))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(device["fps"], Value::Null);
+    assert_eq!(device["enabled"], false);
+
+    let (status, _) = send(
+        &app,
+        "POST",
+        &uri,
+        Some(json!({"engine": "no such engine"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(
+        &app,
+        "POST",
+        &format!("/devices/{}", mic.id),
+        Some(json!({"fps": 1})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(&app, "POST", "/devices/1000", Some(json!({}))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
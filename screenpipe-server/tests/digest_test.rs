@@ -0,0 +1,153 @@
+use chrono::{Duration, Local, NaiveDate, NaiveTime, Utc};
+use screenpipe_db::{DatabaseManager, KeywordHits, NewWebhookRule, SearchedQuery};
+use screenpipe_server::digest::{
+    build_digest, next_digest_time, render_digest, AppTime, Digest, DigestMeeting,
+};
+use screenpipe_server::journal::local_day;
+use screenpipe_vision::OcrEngine;
+use std::sync::Arc;
+
+#[test]
+fn test_next_digest_time() {
+    let date = NaiveDate::from_ymd_opt(2025, 3, 14).unwrap();
+    let at = NaiveTime::from_hms_opt(8, 0, 0).unwrap();
+    assert_eq!(
+        next_digest_time(date.and_hms_opt(7, 59, 0).unwrap(), at),
+        date.and_time(at)
+    );
+    assert_eq!(
+        next_digest_time(date.and_time(at), at),
+        date.succ_opt().unwrap().and_time(at)
+    );
+}
+
+#[test]
+fn test_render_digest() {
+    let start = "2025-03-14T10:00:00Z".parse().unwrap();
+    let digest = Digest {
+        date: NaiveDate::from_ymd_opt(2025, 3, 14).unwrap(),
+        apps: vec![
+            AppTime {
+                app_name: "Code".to_string(),
+                minutes: 135,
+            },
+            AppTime {
+                app_name: "Slack".to_string(),
+                minutes: 20,
+            },
+        ],
+        meetings: vec![DigestMeeting {
+            app: "zoom.us".to_string(),
+            start,
+            end: Some(start + Duration::minutes(30)),
+            speakers: (1..=8).map(|i| format!("Speaker {}", i)).collect(),
+        }],
+        searches: vec![SearchedQuery {
+            query: "budget".to_string(),
+            clicks: 1,
+        }],
+        keywords: vec![KeywordHits {
+            keyword: "invoice".to_string(),
+            frames: 3,
+            transcriptions: 1,
+        }],
+    };
+
+    let (subject, body) = render_digest(&digest, &Utc, "http://localhost:3030/");
+    assert_eq!(subject, "screenpipe digest for Fri, Mar 14");
+    assert!(body.contains("\n- Code: 2h 15m\n- Slack: 20m\n"));
+    assert!(body.contains("\n- 10:00–10:30 zoom.us with Speaker 1, Speaker 2"));
+    assert!(body.contains("Speaker 6 and 2 others\n"));
+    assert!(body.contains("\n- \"budget\" (1 result opened)\n"));
+    assert!(body.contains("\n- \"invoice\": 3 frames, 1 transcription\n"));
+    assert!(body.ends_with("http://localhost:3030/journal?date=2025-03-14\n"));
+
+    let empty = Digest {
+        apps: vec![],
+        meetings: vec![],
+        searches: vec![],
+        keywords: vec![],
+        ..digest
+    };
+    let (_, body) = render_digest(&empty, &Utc, "http://localhost:3030");
+    assert!(body.contains("Nothing recorded this day."));
+    assert!(!body.contains("Top apps"));
+}
+
+#[tokio::test]
+async fn test_build_digest() {
+    let db = DatabaseManager::new("sqlite::memory:").await.unwrap();
+    let date = Local::now().date_naive();
+    let (midnight, _) = local_day(date);
+    let at = |minutes: i64| midnight + Duration::hours(10) + Duration::minutes(minutes);
+
+    db.insert_video_chunk("video.mp4", "monitor_1")
+        .await
+        .unwrap();
+    for (minutes, app) in [(0, "Code"), (4, "Code"), (8, "Code"), (9, "Slack")] {
+        let frame_id = db
+            .insert_frame("monitor_1", Some(at(minutes)), None, Some(app), None, true)
+            .await
+            .unwrap();
+        let text = if app == "Slack" {
+            "is the invoice paid?"
+        } else {
+            "fn main()"
+        };
+        db.insert_ocr_text(frame_id, text, "", Arc::new(OcrEngine::Tesseract))
+            .await
+            .unwrap();
+    }
+
+    let meeting_id = db.start_meeting("zoom.us", at(30)).await.unwrap();
+    db.end_meetings(at(60)).await.unwrap();
+    let frame_id = db
+        .insert_frame("monitor_1", Some(at(31)), None, Some("zoom.us"), None, true)
+        .await
+        .unwrap();
+    db.upsert_meeting_participants(
+        meeting_id,
+        &[("Alice".to_string(), false), ("Me".to_string(), true)],
+        frame_id,
+        at(31),
+    )
+    .await
+    .unwrap();
+
+    db.insert_webhook_rule(&NewWebhookRule {
+        name: "invoices".to_string(),
+        pattern: "invoice".to_string(),
+        is_regex: false,
+        content_type: None,
+        app_name: None,
+        window_name: None,
+        speaker_id: None,
+        webhook_url: "http://localhost:9/hook".to_string(),
+        cooldown_secs: 300,
+    })
+    .await
+    .unwrap();
+
+    let keywords = vec!["Invoice".to_string(), "standup".to_string()];
+    let digest = build_digest(&db, date, &keywords).await.unwrap();
+    assert_eq!(digest.apps.len(), 1);
+    assert_eq!(digest.apps[0].app_name, "Code");
+    assert_eq!(digest.apps[0].minutes, 8);
+    assert_eq!(digest.meetings.len(), 1);
+    assert_eq!(digest.meetings[0].speakers, vec!["Alice".to_string()]);
+    // counted once, keywords never seen are left out
+    assert_eq!(
+        digest.keywords,
+        vec![KeywordHits {
+            keyword: "Invoice".to_string(),
+            frames: 1,
+            transcriptions: 0,
+        }]
+    );
+
+    let yesterday = build_digest(&db, date.pred_opt().unwrap(), &keywords)
+        .await
+        .unwrap();
+    assert!(yesterday.is_empty());
+    assert!(yesterday.keywords.is_empty());
+}
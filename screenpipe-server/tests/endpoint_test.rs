@@ -7,7 +7,7 @@ mod tests {
     use chrono::DateTime;
     use chrono::{Duration, Utc};
     use screenpipe_audio::audio_manager::AudioManagerBuilder;
-    use screenpipe_db::{ContentType, DatabaseManager, SearchResult};
+    use screenpipe_db::{ContentType, DatabaseManager, SearchResult, TagFilter};
     use screenpipe_server::PipeManager;
     use screenpipe_server::SCServer;
     use screenpipe_server::{ContentItem, PaginatedResponse};
@@ -253,6 +253,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -273,6 +276,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -293,6 +299,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -313,6 +322,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -333,6 +345,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -353,6 +368,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -373,6 +391,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -457,6 +478,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -478,6 +502,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -500,6 +527,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -526,6 +556,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -551,6 +584,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -570,6 +606,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -655,6 +694,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -685,6 +727,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -731,6 +776,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -754,6 +802,9 @@ mod tests {
                 None,
                 None,
                 None,
+                None,
+                false,
+                &TagFilter::default(),
             )
             .await
             .unwrap();
@@ -0,0 +1,121 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{header::CONTENT_TYPE, Request, StatusCode},
+    Router,
+};
+use chrono::{Duration, Utc};
+use screenpipe_audio::audio_manager::AudioManagerBuilder;
+use screenpipe_db::{AudioDevice, DatabaseManager, DeviceType};
+use screenpipe_server::{
+    content_v2::{ContentItemV2, ContentKind},
+    PipeManager, SCServer,
+};
+use screenpipe_vision::OcrEngine;
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use tower::ServiceExt;
+
+async fn setup_test_app() -> (Router, Arc<DatabaseManager>) {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let audio_manager = Arc::new(
+        AudioManagerBuilder::new()
+            .output_path("/tmp/screenpipe".into())
+            .build(db.clone())
+            .await
+            .unwrap(),
+    );
+    let router = SCServer::new(
+        db.clone(),
+        SocketAddr::from(([127, 0, 0, 1], 23958)),
+        PathBuf::from(""),
+        Arc::new(PipeManager::new(PathBuf::from(""))),
+        false,
+        false,
+        false,
+        audio_manager,
+    )
+    .create_router(false)
+    .await;
+    (router, db)
+}
+
+async fn export(app: &Router, uri: &str) -> Vec<ContentItemV2> {
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[CONTENT_TYPE], "application/x-ndjson");
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    String::from_utf8(body.to_vec())
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_export_jsonl() {
+    let (app, db) = setup_test_app().await;
+    assert!(export(&app, "/search/export").await.is_empty());
+
+    db.insert_video_chunk("video.mp4", "monitor_1")
+        .await
+        .unwrap();
+    // hours apart, in windows of their own
+    for hours_ago in [3, 2, 1] {
+        let frame_id = db
+            .insert_frame(
+                "monitor_1",
+                Some(Utc::now() - Duration::hours(hours_ago)),
+                None,
+                Some("Notes"),
+                Some("plans"),
+                false,
+            )
+            .await
+            .unwrap();
+        db.insert_ocr_text(
+            frame_id,
+            &format!("plans from {} hours ago", hours_ago),
+            "",
+            Arc::new(OcrEngine::Tesseract),
+        )
+        .await
+        .unwrap();
+    }
+    let audio_chunk_id = db.insert_audio_chunk("audio.mp4").await.unwrap();
+    db.insert_audio_transcription(
+        audio_chunk_id,
+        "talking about plans",
+        0,
+        "",
+        &AudioDevice {
+            name: "mic".to_string(),
+            device_type: DeviceType::Input,
+        },
+        None,
+        None,
+        None,
+    )
+    .await
+    .unwrap();
+
+    let items = export(&app, "/search/export").await;
+    assert_eq!(items.len(), 4);
+    assert!(items.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+    assert_eq!(items[0].text, "plans from 3 hours ago");
+    assert_eq!(items[3].kind, ContentKind::Audio);
+
+    let start = (Utc::now() - Duration::minutes(150)).format("%Y-%m-%dT%H:%M:%SZ");
+    let items = export(
+        &app,
+        &format!("/search/export?content_type=ocr&start_time={}", start),
+    )
+    .await;
+    let texts: Vec<&str> = items.iter().map(|item| item.text.as_str()).collect();
+    assert_eq!(
+        texts,
+        vec!["plans from 2 hours ago", "plans from 1 hours ago"]
+    );
+}
@@ -0,0 +1,140 @@
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::extensions::{ExtensionManager, ExtensionState, ExtensionStatus};
+use serde_json::json;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::tempdir;
+
+fn write_extension(dir: &Path, manifest: serde_json::Value) {
+    std::fs::create_dir_all(dir).unwrap();
+    std::fs::write(dir.join("extension.json"), manifest.to_string()).unwrap();
+}
+
+async fn manager(dir: &Path) -> Arc<ExtensionManager> {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let extensions = ExtensionManager::new(
+        dir.join("extensions"),
+        db,
+        "http://localhost:3030".to_string(),
+        false,
+    );
+    extensions.start().unwrap();
+    extensions
+}
+
+// polls until the extension is in `state`
+async fn wait_for(
+    extensions: &ExtensionManager,
+    name: &str,
+    state: ExtensionState,
+) -> ExtensionStatus {
+    for _ in 0..100 {
+        let status = extensions.status(name).unwrap();
+        if status.state == state {
+            return status;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("{} never got {:?}", name, state);
+}
+
+#[tokio::test]
+async fn test_install_validates_manifest() {
+    let dir = tempdir().unwrap();
+    let extensions = manager(dir.path()).await;
+
+    let admin = dir.path().join("admin");
+    write_extension(
+        &admin,
+        json!({"name": "admin", "entry": ["true"], "permissions": ["admin"]}),
+    );
+    assert!(extensions.install(&admin).is_err());
+    let schedule = dir.path().join("schedule");
+    write_extension(
+        &schedule,
+        json!({"name": "schedule", "entry": ["true"], "schedule": "every day"}),
+    );
+    assert!(extensions.install(&schedule).is_err());
+    let name = dir.path().join("name");
+    write_extension(&name, json!({"name": "../name", "entry": ["true"]}));
+    assert!(extensions.install(&name).is_err());
+
+    let source = dir.path().join("summarizer");
+    write_extension(
+        &source,
+        json!({
+            "name": "summarizer",
+            "version": "0.1.0",
+            "entry": ["sleep", "30"],
+            "permissions": ["read-search", "write-tags"]
+        }),
+    );
+    std::fs::write(source.join("prompt.txt"), "summarize").unwrap();
+    let status = extensions.install(&source).unwrap();
+    assert_eq!(status.version, "0.1.0");
+    assert_eq!(status.permissions, vec!["read-search", "write-tags"]);
+    assert!(!status.enabled);
+    assert_eq!(status.state, ExtensionState::Stopped);
+    assert!(dir.path().join("extensions/summarizer/prompt.txt").exists());
+    assert!(extensions.install(&source).is_err());
+    assert!(extensions.enable("missing").unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_extension_lifecycle() {
+    let dir = tempdir().unwrap();
+    write_extension(
+        &dir.path().join("extensions/service"),
+        json!({"name": "service", "entry": ["sleep", "30"]}),
+    );
+    let extensions = manager(dir.path()).await;
+    assert_eq!(extensions.list().len(), 1);
+
+    extensions.enable("service").unwrap();
+    let running = wait_for(&extensions, "service", ExtensionState::Running).await;
+    assert!(running.pid.is_some());
+    assert!(running.last_started_at.is_some());
+
+    // enabled extensions start with screenpipe
+    let restarted = manager(dir.path()).await;
+    assert!(restarted.status("service").unwrap().enabled);
+    restarted.disable("service").unwrap();
+
+    let status = extensions.disable("service").unwrap().unwrap();
+    assert!(!status.enabled);
+    let stopped = wait_for(&extensions, "service", ExtensionState::Stopped).await;
+    assert_eq!(stopped.pid, None);
+}
+
+#[tokio::test]
+async fn test_failing_extension_gives_up() {
+    let dir = tempdir().unwrap();
+    write_extension(
+        &dir.path().join("extensions/broken"),
+        json!({"name": "broken", "entry": ["sh", "-c", "exit 1"], "max_restarts": 1}),
+    );
+    let extensions = manager(dir.path()).await;
+
+    extensions.enable("broken").unwrap();
+    let failed = wait_for(&extensions, "broken", ExtensionState::Failed).await;
+    assert_eq!(failed.restarts, 1);
+    assert!(failed.last_exit.unwrap().contains('1'));
+}
+
+#[tokio::test]
+async fn test_scheduled_extension() {
+    let dir = tempdir().unwrap();
+    write_extension(
+        &dir.path().join("extensions/daily"),
+        json!({"name": "daily", "entry": ["true"], "schedule": "0 0 9 * * *"}),
+    );
+    let extensions = manager(dir.path()).await;
+
+    extensions.enable("daily").unwrap();
+    let scheduled = wait_for(&extensions, "daily", ExtensionState::Scheduled).await;
+    assert!(scheduled.next_run_at.is_some());
+    extensions.disable("daily").unwrap();
+    let stopped = wait_for(&extensions, "daily", ExtensionState::Stopped).await;
+    assert_eq!(stopped.next_run_at, None);
+}
@@ -0,0 +1,95 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::focus_sessions::FocusSessionMonitor;
+use std::sync::Arc;
+
+fn at(minute: u32, second: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 4, 5, 9, minute, second).unwrap()
+}
+
+fn window(app_name: &str, window_name: &str) -> Option<(String, String)> {
+    Some((app_name.to_string(), window_name.to_string()))
+}
+
+#[tokio::test]
+async fn test_focus_session_monitor_records_sessions() {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let monitor = FocusSessionMonitor::new(db.clone(), vec!["1password".to_string()], None);
+
+    let first = monitor
+        .record(window("Code", "main.rs"), at(0, 0))
+        .await
+        .unwrap();
+    // the same window keeps its session
+    assert_eq!(
+        monitor
+            .record(window("Code", "main.rs"), at(0, 30))
+            .await
+            .unwrap(),
+        first
+    );
+    monitor
+        .record(window("Firefox", "docs"), at(1, 0))
+        .await
+        .unwrap();
+    assert_eq!(
+        monitor
+            .record(window("1Password", "Vault"), at(1, 20))
+            .await
+            .unwrap(),
+        None
+    );
+    monitor
+        .record(window("Code", "main.rs"), at(2, 0))
+        .await
+        .unwrap();
+    monitor
+        .record(window("Code", "main.rs"), at(2, 40))
+        .await
+        .unwrap();
+
+    let sessions = db
+        .get_focus_sessions(at(0, 0), at(5, 0), None)
+        .await
+        .unwrap();
+    let sessions: Vec<_> = sessions
+        .iter()
+        .map(|session| {
+            (
+                session.app_name.as_str(),
+                session.started_at,
+                session.ended_at,
+            )
+        })
+        .collect();
+    assert_eq!(
+        sessions,
+        vec![
+            ("Code", at(0, 0), Some(at(1, 0))),
+            ("Firefox", at(1, 0), Some(at(1, 20))),
+            ("Code", at(2, 0), None),
+        ]
+    );
+
+    // the open session counts up to when it was last seen, the range cuts
+    // the first one
+    let time = db.get_focus_time(at(0, 30), at(5, 0), None).await.unwrap();
+    let time: Vec<_> = time
+        .iter()
+        .map(|window| {
+            (
+                window.app_name.as_str(),
+                window.sessions,
+                window.focused_secs.round() as i64,
+            )
+        })
+        .collect();
+    assert_eq!(time, vec![("Code", 2, 70), ("Firefox", 1, 20)]);
+
+    assert_eq!(db.close_stale_focus_sessions().await.unwrap(), 1);
+    let last = db
+        .get_focus_sessions(at(2, 0), at(2, 0) + Duration::hours(1), Some("Code"))
+        .await
+        .unwrap();
+    assert_eq!(last[0].ended_at, Some(at(2, 40)));
+}
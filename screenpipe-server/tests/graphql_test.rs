@@ -0,0 +1,67 @@
+#![cfg(feature = "graphql")]
+
+use async_graphql::Request;
+use screenpipe_db::{DatabaseManager, OcrEngine, TagContentType};
+use screenpipe_server::graphql::schema;
+use serde_json::json;
+use std::sync::Arc;
+
+#[tokio::test]
+async fn test_frame_with_ocr_tags_and_video_chunk() {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    db.insert_video_chunk("test_video.mp4", "test_device")
+        .await
+        .unwrap();
+    let frame_id = db
+        .insert_frame("test_device", None, None, Some("firefox"), None, true)
+        .await
+        .unwrap();
+    db.insert_ocr_text(
+        frame_id,
+        "invoice 42",
+        r#"[{"text": "invoice 42", "conf": "91.5", "left": "10", "top": "20", "width": "100", "height": "12"}]"#,
+        Arc::new(OcrEngine::Tesseract),
+    )
+    .await
+    .unwrap();
+    db.add_tags(
+        frame_id,
+        TagContentType::Vision,
+        vec!["receipt".to_string()],
+    )
+    .await
+    .unwrap();
+
+    let response = schema()
+        .execute(
+            Request::new(
+                r#"{
+                    frames(tag: "receipt") {
+                        appName
+                        ocr { text blocks { text left } }
+                        tags
+                        videoChunk { filePath frames { id } }
+                    }
+                }"#,
+            )
+            .data(db.clone()),
+        )
+        .await;
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    assert_eq!(
+        response.data.into_json().unwrap(),
+        json!({
+            "frames": [{
+                "appName": "firefox",
+                "ocr": { "text": "invoice 42", "blocks": [{ "text": "invoice 42", "left": 10.0 }] },
+                "tags": ["receipt"],
+                "videoChunk": { "filePath": "test_video.mp4", "frames": [{ "id": frame_id }] },
+            }]
+        })
+    );
+
+    let response = schema()
+        .execute(Request::new("{ frames(tag: \"youtube\") { id } }").data(db))
+        .await;
+    assert_eq!(response.data.into_json().unwrap(), json!({ "frames": [] }));
+}
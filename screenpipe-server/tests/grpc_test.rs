@@ -0,0 +1,356 @@
+#![cfg(feature = "grpc")]
+
+use futures::StreamExt;
+use screenpipe_db::DatabaseManager;
+use screenpipe_events::send_event;
+use screenpipe_server::{
+    auth::{create_token, ApiScope},
+    grpc::{
+        proto::{self, screenpipe_server::Screenpipe, search_result::Content},
+        GrpcService,
+    },
+};
+use serde_json::json;
+use std::{sync::Arc, time::Duration};
+use tonic::Request;
+
+async fn setup_service() -> (GrpcService, Arc<DatabaseManager>) {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    (GrpcService::new(db.clone()), db)
+}
+
+fn with_token<T>(message: T, token: Option<&str>) -> Request<T> {
+    let mut request = Request::new(message);
+    if let Some(token) = token {
+        request.metadata_mut().insert(
+            "authorization",
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+    }
+    request
+}
+
+#[tokio::test]
+async fn test_inserted_frame_is_found_by_search() {
+    let (service, _db) = setup_service().await;
+
+    let frame_id = service
+        .insert_frame(Request::new(proto::InsertFrameRequest {
+            device_name: "laptop".to_string(),
+            app_name: Some("firefox".to_string()),
+            window_name: Some("invoices".to_string()),
+            focused: true,
+            text: "invoice 42 from acme".to_string(),
+            tags: vec!["receipt".to_string()],
+            ..Default::default()
+        }))
+        .await
+        .unwrap()
+        .into_inner()
+        .id;
+
+    let results = service
+        .search(Request::new(proto::SearchRequest {
+            q: "invoice".to_string(),
+            content_type: proto::ContentType::Ocr as i32,
+            ..Default::default()
+        }))
+        .await
+        .unwrap()
+        .into_inner()
+        .results;
+    assert_eq!(results.len(), 1);
+    let Some(Content::Ocr(ocr)) = &results[0].content else {
+        panic!("expected an ocr result, got {:?}", results[0]);
+    };
+    assert_eq!(ocr.frame_id, frame_id);
+    assert_eq!(ocr.text, "invoice 42 from acme");
+    assert_eq!(ocr.app_name, "firefox");
+    assert_eq!(ocr.tags, vec!["receipt".to_string()]);
+
+    let missing = service
+        .insert_frame(Request::new(proto::InsertFrameRequest {
+            text: "no device".to_string(),
+            ..Default::default()
+        }))
+        .await
+        .unwrap_err();
+    assert_eq!(missing.code(), tonic::Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn test_inserted_transcription_is_found_by_search() {
+    let (service, _db) = setup_service().await;
+
+    let chunk_id = service
+        .insert_transcription(Request::new(proto::InsertTranscriptionRequest {
+            device_name: "microphone".to_string(),
+            is_input_device: true,
+            transcription: "let's ship the release on friday".to_string(),
+            transcription_engine: "whisper".to_string(),
+            speaker_id: None,
+        }))
+        .await
+        .unwrap()
+        .into_inner()
+        .id;
+
+    let results = service
+        .search(Request::new(proto::SearchRequest {
+            q: "release".to_string(),
+            content_type: proto::ContentType::Audio as i32,
+            ..Default::default()
+        }))
+        .await
+        .unwrap()
+        .into_inner()
+        .results;
+    assert_eq!(results.len(), 1);
+    let Some(Content::Audio(audio)) = &results[0].content else {
+        panic!("expected an audio result, got {:?}", results[0]);
+    };
+    assert_eq!(audio.chunk_id, chunk_id);
+    assert_eq!(audio.device_name, "microphone");
+    assert_eq!(audio.device_type, "input");
+}
+
+#[tokio::test]
+async fn test_add_get_and_remove_tags() {
+    let (service, _db) = setup_service().await;
+    let frame_id = service
+        .insert_frame(Request::new(proto::InsertFrameRequest {
+            device_name: "laptop".to_string(),
+            text: "quarterly report".to_string(),
+            ..Default::default()
+        }))
+        .await
+        .unwrap()
+        .into_inner()
+        .id;
+
+    let tags_request = |tags: &[&str]| proto::TagsRequest {
+        content_type: proto::TagContentType::Vision as i32,
+        id: frame_id,
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+    };
+    let mut tags = service
+        .add_tags(Request::new(tags_request(&["work", "finance"])))
+        .await
+        .unwrap()
+        .into_inner()
+        .tags;
+    tags.sort();
+    assert_eq!(tags, vec!["finance".to_string(), "work".to_string()]);
+
+    let tags = service
+        .remove_tags(Request::new(tags_request(&["work"])))
+        .await
+        .unwrap()
+        .into_inner()
+        .tags;
+    assert_eq!(tags, vec!["finance".to_string()]);
+
+    let tags = service
+        .get_tags(Request::new(proto::GetTagsRequest {
+            content_type: proto::TagContentType::Vision as i32,
+            id: frame_id,
+        }))
+        .await
+        .unwrap()
+        .into_inner()
+        .tags;
+    assert_eq!(tags, vec!["finance".to_string()]);
+}
+
+#[tokio::test]
+async fn test_name_an_unnamed_speaker() {
+    let (service, db) = setup_service().await;
+    let speaker = db.insert_speaker(&[0.1; 512]).await.unwrap();
+    service
+        .insert_transcription(Request::new(proto::InsertTranscriptionRequest {
+            device_name: "microphone".to_string(),
+            is_input_device: true,
+            transcription: "hello everyone".to_string(),
+            transcription_engine: "whisper".to_string(),
+            speaker_id: Some(speaker.id),
+        }))
+        .await
+        .unwrap();
+
+    let unnamed = service
+        .list_unnamed_speakers(Request::new(proto::ListUnnamedSpeakersRequest::default()))
+        .await
+        .unwrap()
+        .into_inner()
+        .speakers;
+    assert_eq!(
+        unnamed.iter().map(|s| s.id).collect::<Vec<_>>(),
+        vec![speaker.id]
+    );
+
+    let updated = service
+        .update_speaker(Request::new(proto::UpdateSpeakerRequest {
+            id: speaker.id,
+            name: Some("alice".to_string()),
+            metadata: None,
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(updated.name, "alice");
+
+    let found = service
+        .search_speakers(Request::new(proto::SearchSpeakersRequest {
+            name_prefix: "ali".to_string(),
+        }))
+        .await
+        .unwrap()
+        .into_inner()
+        .speakers;
+    assert_eq!(
+        found.iter().map(|s| s.id).collect::<Vec<_>>(),
+        vec![speaker.id]
+    );
+
+    let fetched = service
+        .get_speaker(Request::new(proto::GetSpeakerRequest { id: speaker.id }))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(fetched.name, "alice");
+
+    let missing = service
+        .get_speaker(Request::new(proto::GetSpeakerRequest { id: 999 }))
+        .await
+        .unwrap_err();
+    assert_eq!(missing.code(), tonic::Code::NotFound);
+}
+
+#[tokio::test]
+async fn test_stream_events_filters_by_name_and_drops_images() {
+    let (service, _db) = setup_service().await;
+    let mut events = service
+        .stream_events(Request::new(proto::StreamEventsRequest {
+            names: vec!["ocr_result".to_string()],
+            images: false,
+        }))
+        .await
+        .unwrap()
+        .into_inner();
+
+    send_event("grpc_test_other", json!({ "skipped": true })).unwrap();
+    send_event(
+        "ocr_result",
+        json!({ "text": "hello from grpc", "image": "aGVsbG8=" }),
+    )
+    .unwrap();
+
+    let event = tokio::time::timeout(Duration::from_secs(5), events.next())
+        .await
+        .expect("no event received")
+        .unwrap()
+        .unwrap();
+    assert_eq!(event.name, "ocr_result");
+    let data: serde_json::Value = serde_json::from_str(&event.data).unwrap();
+    assert_eq!(data, json!({ "text": "hello from grpc" }));
+}
+
+#[tokio::test]
+async fn test_calls_need_a_token_with_their_scope() {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let service = GrpcService::new(db.clone()).with_api_auth(true);
+    let (reader, _) = create_token(&db, "reader", &[ApiScope::ReadSearch])
+        .await
+        .unwrap();
+    let (tagger, _) = create_token(&db, "tagger", &[ApiScope::WriteTags])
+        .await
+        .unwrap();
+    let (admin, _) = create_token(&db, "admin", &[ApiScope::Admin])
+        .await
+        .unwrap();
+
+    let search = |token: Option<&str>| {
+        with_token(
+            proto::SearchRequest {
+                q: "invoice".to_string(),
+                ..Default::default()
+            },
+            token,
+        )
+    };
+    let status = service.search(search(None)).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::Unauthenticated);
+    let status = service.search(search(Some("sp_wrong"))).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::Unauthenticated);
+    service.search(search(Some(&reader))).await.unwrap();
+
+    let insert = |token: Option<&str>| {
+        with_token(
+            proto::InsertFrameRequest {
+                device_name: "laptop".to_string(),
+                text: "invoice 42".to_string(),
+                ..Default::default()
+            },
+            token,
+        )
+    };
+    let status = service
+        .insert_frame(insert(Some(&reader)))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::PermissionDenied);
+    let frame_id = service
+        .insert_frame(insert(Some(&admin)))
+        .await
+        .unwrap()
+        .into_inner()
+        .id;
+
+    // tags need the tags scope, reading is not enough
+    let tags = |token: Option<&str>| {
+        with_token(
+            proto::TagsRequest {
+                content_type: proto::TagContentType::Vision as i32,
+                id: frame_id,
+                tags: vec!["receipt".to_string()],
+            },
+            token,
+        )
+    };
+    let status = service.add_tags(tags(Some(&reader))).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::PermissionDenied);
+    service.add_tags(tags(Some(&tagger))).await.unwrap();
+    let status = service.remove_tags(tags(Some(&reader))).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::PermissionDenied);
+
+    let update = |token: Option<&str>| {
+        with_token(
+            proto::UpdateSpeakerRequest {
+                id: 1,
+                name: Some("alice".to_string()),
+                metadata: None,
+            },
+            token,
+        )
+    };
+    let status = service
+        .update_speaker(update(Some(&tagger)))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::PermissionDenied);
+
+    let status = service
+        .stream_events(with_token(proto::StreamEventsRequest::default(), None))
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(status.code(), tonic::Code::Unauthenticated);
+
+    // tag changes are audited under the token's name
+    let entries = db
+        .list_audit_log(Some("add_tags"), None, None, 10, 0)
+        .await
+        .unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].actor, "token:tagger");
+}
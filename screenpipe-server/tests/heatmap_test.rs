@@ -0,0 +1,49 @@
+use chrono::{DateTime, FixedOffset, Utc};
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::heatmap::build_heatmap;
+
+#[tokio::test]
+async fn test_activity_heatmap() {
+    let db = DatabaseManager::new("sqlite::memory:").await.unwrap();
+    let at = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+
+    db.insert_video_chunk("video.mp4", "monitor_1")
+        .await
+        .unwrap();
+    // a monday morning, and a sunday night
+    for timestamp in [
+        "2025-03-10T09:15:00Z",
+        "2025-03-10T09:45:00Z",
+        "2025-03-10T10:05:00Z",
+        "2025-03-16T23:30:00Z",
+    ] {
+        db.insert_frame("monitor_1", Some(at(timestamp)), None, None, None, true)
+            .await
+            .unwrap();
+    }
+    sqlx::query(
+        "INSERT INTO ui_monitoring (text_output, timestamp, app, window) VALUES ('text', '2025-03-10 09:20:00', 'Code', 'main.rs')",
+    )
+    .execute(&db.pool)
+    .await
+    .unwrap();
+
+    let counts = db
+        .get_hourly_counts(at("2025-03-10T09:30:00Z"), at("2025-03-17T00:00:00Z"))
+        .await
+        .unwrap();
+    // the hour the range starts in is whole
+    assert_eq!(counts.len(), 3);
+    assert_eq!(counts[0].hour, at("2025-03-10T09:00:00Z"));
+    assert_eq!((counts[0].frames, counts[0].ui_events), (2, 1));
+
+    let cells = build_heatmap(&counts, &FixedOffset::east_opt(2 * 3600).unwrap());
+    assert_eq!(cells.len(), 7 * 24);
+    let cell = |weekday: u32, hour: u32| &cells[(weekday * 24 + hour) as usize];
+    assert_eq!((cell(0, 11).frames, cell(0, 11).ui_events), (2, 1));
+    assert_eq!(cell(0, 12).frames, 1);
+    // sunday night in utc is monday in the timezone
+    assert_eq!(cell(0, 1).frames, 1);
+    assert_eq!(cell(6, 23).frames, 0);
+    assert_eq!(cells.iter().map(|c| c.frames).sum::<i64>(), 4);
+}
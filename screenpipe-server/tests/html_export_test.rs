@@ -0,0 +1,140 @@
+use chrono::{Duration, Local, NaiveDate, TimeZone, Utc};
+use screenpipe_db::{AudioDevice, DatabaseManager, DeviceType, TranscriptionExport};
+use screenpipe_server::html_export::{export_html, transcript_blocks, TimeBound};
+use screenpipe_server::journal::local_day;
+use screenpipe_vision::OcrEngine;
+use std::sync::Arc;
+
+fn transcription(device: &str, minutes: i64, text: &str) -> TranscriptionExport {
+    TranscriptionExport {
+        id: minutes,
+        audio_chunk_id: 1,
+        offset_index: 0,
+        timestamp: Utc.with_ymd_and_hms(2025, 3, 14, 10, 0, 0).unwrap()
+            + Duration::minutes(minutes),
+        file_path: "audio.mp4".to_string(),
+        transcription: text.to_string(),
+        device: device.to_string(),
+        is_input_device: device == "mic",
+        speaker_id: None,
+        speaker_name: None,
+        transcription_engine: String::new(),
+        start_time: None,
+        end_time: None,
+    }
+}
+
+#[test]
+fn test_time_bound() {
+    let date = NaiveDate::from_ymd_opt(2025, 3, 14).unwrap();
+    let bound: TimeBound = "2025-03-14".parse().unwrap();
+    assert_eq!(bound, TimeBound::Date(date));
+    assert_eq!(bound.start(), local_day(date).0);
+    assert_eq!(bound.end(), local_day(date).1);
+
+    let nine_thirty = Local
+        .from_local_datetime(&date.and_hms_opt(9, 30, 0).unwrap())
+        .unwrap()
+        .with_timezone(&Utc);
+    assert_eq!(
+        "2025-03-14 09:30".parse::<TimeBound>(),
+        Ok(TimeBound::Time(nine_thirty))
+    );
+    assert_eq!(
+        "2025-03-14T09:30:00Z".parse::<TimeBound>(),
+        Ok(TimeBound::Time(
+            Utc.with_ymd_and_hms(2025, 3, 14, 9, 30, 0).unwrap()
+        ))
+    );
+    assert!("yesterday".parse::<TimeBound>().is_err());
+}
+
+#[test]
+fn test_transcript_blocks() {
+    let blocks = transcript_blocks(vec![
+        transcription("mic", 0, "morning"),
+        transcription("speaker", 1, "hi there"),
+        transcription("mic", 2, "shall we start"),
+        transcription("mic", 3, " "),
+        transcription("mic", 10, "back again"),
+    ]);
+    assert_eq!(blocks.len(), 3);
+    assert_eq!(blocks[0].device, "mic");
+    assert_eq!(blocks[0].lines.len(), 2);
+    assert_eq!(blocks[0].end - blocks[0].start, Duration::minutes(2));
+    assert_eq!(blocks[1].device, "speaker");
+    assert_eq!(blocks[2].lines[0].transcription, "back again");
+}
+
+#[tokio::test]
+async fn test_export_html() {
+    let db = DatabaseManager::new("sqlite::memory:").await.unwrap();
+    let start = Utc::now() - Duration::hours(1);
+    db.insert_video_chunk("video.mp4", "monitor_1")
+        .await
+        .unwrap();
+    for minutes in [0, 2] {
+        let frame_id = db
+            .insert_frame(
+                "monitor_1",
+                Some(start + Duration::minutes(minutes)),
+                None,
+                Some("Code"),
+                Some("main.rs"),
+                true,
+            )
+            .await
+            .unwrap();
+        db.insert_ocr_text(
+            frame_id,
+            "if a < b && b > c { launch() }",
+            "",
+            Arc::new(OcrEngine::Tesseract),
+        )
+        .await
+        .unwrap();
+    }
+    let audio_chunk_id = db.insert_audio_chunk("audio.mp4").await.unwrap();
+    db.insert_audio_transcription(
+        audio_chunk_id,
+        "let's ship it",
+        0,
+        "",
+        &AudioDevice {
+            name: "mic".to_string(),
+            device_type: DeviceType::Input,
+        },
+        None,
+        None,
+        None,
+    )
+    .await
+    .unwrap();
+
+    let out = tempfile::tempdir().unwrap();
+    let output = out.path().join("session/timeline.html");
+    let entries = export_html(
+        &db,
+        &output,
+        start,
+        Utc::now() + Duration::minutes(1),
+        false,
+    )
+    .await
+    .unwrap();
+    assert_eq!(entries, 2);
+
+    let html = std::fs::read_to_string(&output).unwrap();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<strong>Code</strong>"));
+    assert!(html.contains("main.rs"));
+    assert!(html.contains("if a &lt; b &amp;&amp; b &gt; c { launch() }"));
+    assert!(html.contains("let&#39;s ship it"));
+    // nothing loaded from elsewhere
+    assert!(!html.contains("src=\"http"));
+    assert!(!html.contains("<img"));
+
+    assert!(export_html(&db, &output, start, start, false)
+        .await
+        .is_err());
+}
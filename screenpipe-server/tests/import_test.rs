@@ -0,0 +1,106 @@
+use chrono::{Duration, Local, TimeZone, Utc};
+use image::{Rgb, RgbImage};
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::import::{import, timestamp_from_name, ImportSource, ImportSummary};
+use screenpipe_vision::OcrEngine;
+use std::sync::Arc;
+
+#[test]
+fn test_timestamp_from_name() {
+    let local = |h, m, s| {
+        Local
+            .with_ymd_and_hms(2024, 3, 14, h, m, s)
+            .unwrap()
+            .with_timezone(&Utc)
+    };
+    assert_eq!(
+        timestamp_from_name("Screenshot 2024-03-14 at 10.22.31.png"),
+        Some(local(10, 22, 31))
+    );
+    assert_eq!(
+        timestamp_from_name("Screen Recording 2024-03-14 at 10.22.31 PM.mov"),
+        Some(local(22, 22, 31))
+    );
+    assert_eq!(
+        timestamp_from_name("CleanShot 2024-03-14 at 12.05.00 AM@2x.png"),
+        Some(local(0, 5, 0))
+    );
+    assert_eq!(
+        timestamp_from_name("Screenshot_20240314_102231.png"),
+        Some(local(10, 22, 31))
+    );
+    assert_eq!(
+        timestamp_from_name("monitor_1_2024-03-14_10-22-31.mp4"),
+        Some(local(10, 22, 31))
+    );
+    assert_eq!(
+        timestamp_from_name("capture-1710411751123.png"),
+        Utc.timestamp_millis_opt(1710411751123).single()
+    );
+    assert_eq!(timestamp_from_name("holiday.png"), None);
+    assert_eq!(
+        timestamp_from_name("Screenshot 2024-13-45 at 10.22.31.png"),
+        None
+    );
+}
+
+#[tokio::test]
+async fn test_import_screenshots() {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let screenshots = tempfile::tempdir().unwrap();
+    let data_dir = tempfile::tempdir().unwrap();
+    RgbImage::from_pixel(64, 64, Rgb([255, 255, 255]))
+        .save(
+            screenshots
+                .path()
+                .join("Screenshot 2024-03-14 at 10.22.31.png"),
+        )
+        .unwrap();
+    std::fs::write(screenshots.path().join("notes.txt"), "not a screenshot").unwrap();
+
+    let run = || {
+        import(
+            db.clone(),
+            data_dir.path(),
+            screenshots.path(),
+            ImportSource::Screenshots,
+            OcrEngine::Tesseract,
+            Vec::new(),
+        )
+    };
+    let summary = run().await.unwrap();
+    assert_eq!(summary.files, 1);
+    assert_eq!(summary.frames, 1);
+    assert_eq!(summary.failed, 0);
+
+    // at the time it was taken, copied into the data dir
+    let taken = timestamp_from_name("2024-03-14 10.22.31").unwrap();
+    let records = db
+        .get_usage_records(
+            taken - Duration::minutes(1),
+            taken + Duration::minutes(1),
+            true,
+            false,
+        )
+        .await
+        .unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].timestamp, taken);
+    assert_eq!(records[0].device_name, "imported_screenshots");
+    assert_eq!(
+        std::fs::read_dir(data_dir.path().join("data"))
+            .unwrap()
+            .count(),
+        1
+    );
+
+    // run again, nothing is imported twice
+    assert_eq!(
+        run().await.unwrap(),
+        ImportSummary {
+            files: 1,
+            skipped: 1,
+            ..Default::default()
+        }
+    );
+}
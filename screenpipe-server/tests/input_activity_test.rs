@@ -0,0 +1,76 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::input_activity::{InputActivityMonitor, InputCounter, InputKind};
+use std::sync::Arc;
+
+fn at(minute: u32, second: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 4, 2, 9, minute, second).unwrap()
+}
+
+#[test]
+fn test_input_counter_counts_per_minute_and_app() {
+    let mut counter = InputCounter::default();
+    for second in [0, 1, 2, 10, 11] {
+        counter.record(InputKind::KeyPress, at(0, second), "Code");
+    }
+    counter.record(InputKind::Click, at(0, 20), "Code");
+    counter.record(InputKind::Click, at(0, 30), "Firefox");
+    counter.record(InputKind::Scroll, at(0, 31), "Firefox");
+    // the pause before it spans the minute, a burst of its own
+    counter.record(InputKind::KeyPress, at(1, 5), "Code");
+
+    let minutes = counter.take();
+    let summary: Vec<_> = minutes
+        .iter()
+        .map(|minute| {
+            (
+                minute.timestamp,
+                minute.app_name.as_str(),
+                minute.keypresses,
+                minute.typing_bursts,
+                minute.clicks,
+                minute.scrolls,
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (at(0, 0), "Code", 5, 2, 1, 0),
+            (at(0, 0), "Firefox", 0, 0, 1, 1),
+            (at(1, 0), "Code", 1, 1, 0, 0),
+        ]
+    );
+    assert!(counter.take().is_empty());
+}
+
+#[tokio::test]
+async fn test_input_activity_monitor_stores_counts() {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let monitor = InputActivityMonitor::new(db.clone(), vec!["1password".to_string()], None);
+    let now = Utc::now();
+
+    monitor.refresh(Some(("Code".to_string(), "main.rs".to_string())));
+    monitor.record(InputKind::KeyPress, now);
+    monitor.record(InputKind::KeyPress, now);
+    monitor.refresh(Some(("1Password".to_string(), "Vault".to_string())));
+    monitor.record(InputKind::Click, now);
+    assert_eq!(monitor.flush().await.unwrap(), 2);
+    // a second flush of the same minute adds up
+    monitor.refresh(Some(("Code".to_string(), "main.rs".to_string())));
+    monitor.record(InputKind::Click, now);
+    assert_eq!(monitor.flush().await.unwrap(), 1);
+
+    let report = db
+        .get_input_activity(now - Duration::minutes(2), now + Duration::minutes(1), None)
+        .await
+        .unwrap();
+    assert_eq!(report.active_minutes, 1);
+    let apps: Vec<_> = report
+        .apps
+        .iter()
+        .map(|app| (app.app_name.as_str(), app.keypresses, app.clicks))
+        .collect();
+    // the ignored app is counted without its name
+    assert_eq!(apps, vec![("Code", 2, 1), ("", 0, 1)]);
+}
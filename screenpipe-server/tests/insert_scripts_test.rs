@@ -0,0 +1,119 @@
+use chrono::Utc;
+use screenpipe_db::{DatabaseManager, InsertEvent, OcrEngine, TagContentType};
+use screenpipe_server::scripts::{InsertScripts, ScriptAction, ScriptRunner};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+const JIRA_SCRIPT: &str = r#"
+    for key in find_all(event.text, "\\b[A-Z][A-Z0-9]+-\\d+\\b") {
+        add_tag(key);
+    }
+    if matches(event.text, "(?i)incident") {
+        set_metadata("severity", 1);
+        call_webhook("http://localhost:9/hook", #{ id: event.id, app: event.app_name });
+    }
+"#;
+
+fn ocr_event(text: &str) -> InsertEvent {
+    InsertEvent {
+        content_type: "ocr".to_string(),
+        id: 7,
+        target_id: 7,
+        text: text.to_string(),
+        app_name: Some("Chrome".to_string()),
+        window_name: None,
+        browser_url: None,
+        device_name: "monitor_1".to_string(),
+        speaker_id: None,
+        timestamp: Utc::now(),
+    }
+}
+
+#[test]
+fn test_insert_scripts() {
+    let scripts = InsertScripts::new(vec![
+        ("jira".to_string(), JIRA_SCRIPT.to_string()),
+        ("broken".to_string(), "add_tag(".to_string()),
+        // stopped by the operation limit, what it asked for is dropped
+        (
+            "forever".to_string(),
+            "add_tag(\"never\"); loop {}".to_string(),
+        ),
+    ]);
+    assert_eq!(scripts.names(), vec!["jira", "forever"]);
+
+    let actions = scripts.run(&ocr_event("incident on PROJ-42 and OPS-7"));
+    assert_eq!(
+        actions,
+        vec![
+            (
+                "jira".to_string(),
+                ScriptAction::AddTag("PROJ-42".to_string())
+            ),
+            (
+                "jira".to_string(),
+                ScriptAction::AddTag("OPS-7".to_string())
+            ),
+            (
+                "jira".to_string(),
+                ScriptAction::SetMetadata {
+                    key: "severity".to_string(),
+                    value: "1".to_string()
+                }
+            ),
+            (
+                "jira".to_string(),
+                ScriptAction::CallWebhook {
+                    url: "http://localhost:9/hook".to_string(),
+                    body: json!({"id": 7, "app": "Chrome"}),
+                }
+            ),
+        ]
+    );
+    assert!(scripts.run(&ocr_event("nothing to see")).is_empty());
+}
+
+#[tokio::test]
+async fn test_script_runner() {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    ScriptRunner::new(
+        db.clone(),
+        InsertScripts::new(vec![("jira".to_string(), JIRA_SCRIPT.to_string())]),
+    )
+    .start();
+
+    db.insert_video_chunk("video.mp4", "monitor_1")
+        .await
+        .unwrap();
+    let frame_id = db
+        .insert_frame("monitor_1", None, None, Some("Jira"), None, true)
+        .await
+        .unwrap();
+    db.insert_ocr_text(
+        frame_id,
+        "incident PROJ-42",
+        "",
+        Arc::new(OcrEngine::Tesseract),
+    )
+    .await
+    .unwrap();
+
+    let mut tags = Vec::new();
+    for _ in 0..50 {
+        tags = db.get_tags(frame_id, TagContentType::Vision).await.unwrap();
+        if !tags.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(tags, vec!["PROJ-42".to_string()]);
+    let metadata = db
+        .get_metadata(frame_id, TagContentType::Vision)
+        .await
+        .unwrap();
+    assert_eq!(
+        (metadata[0].key.as_str(), metadata[0].value.as_str()),
+        ("severity", "1")
+    );
+}
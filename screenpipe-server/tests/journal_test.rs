@@ -0,0 +1,123 @@
+use chrono::{NaiveDate, TimeZone, Utc};
+use screenpipe_db::{
+    AppSession, JournalDay, JournalExcerpt, JournalMeeting, Meeting, MeetingParticipant, TagUsage,
+    TranscriptLine,
+};
+use screenpipe_server::journal::{render_journal, render_meeting_export};
+
+#[test]
+fn test_render_journal() {
+    let at = |h, m| Utc.with_ymd_and_hms(2025, 3, 14, h, m, 0).unwrap();
+    let day = JournalDay {
+        start: at(0, 0),
+        end: at(23, 59),
+        sessions: vec![
+            AppSession {
+                app_name: "Code".to_string(),
+                device_name: "monitor_1".to_string(),
+                start: at(9, 0),
+                end: at(9, 45),
+                frame_count: 90,
+                window_names: vec!["main.rs".to_string(), "lib.rs".to_string()],
+                excerpt: Some(JournalExcerpt {
+                    frame_id: 42,
+                    text: "fn main()\n    {  println!(\"hello\") }".to_string(),
+                }),
+            },
+            // too short to be listed
+            AppSession {
+                app_name: "Finder".to_string(),
+                device_name: "monitor_1".to_string(),
+                start: at(9, 46),
+                end: at(9, 46),
+                frame_count: 1,
+                window_names: Vec::new(),
+                excerpt: None,
+            },
+        ],
+        meetings: vec![JournalMeeting {
+            meeting: Meeting {
+                id: 1,
+                meeting_app: "zoom.us".to_string(),
+                start_time: at(10, 0),
+                end_time: Some(at(10, 30)),
+                source: "live".to_string(),
+                title: None,
+            },
+            participants: vec![MeetingParticipant {
+                id: 1,
+                meeting_id: 1,
+                name: "Dan".to_string(),
+                is_local: true,
+                speaker_id: None,
+                frame_id: None,
+                first_seen: at(10, 0),
+                last_seen: at(10, 30),
+            }],
+            transcript: vec![TranscriptLine {
+                timestamp: at(10, 5),
+                speaker_name: None,
+                device_name: "MacBook Pro Microphone (input)".to_string(),
+                text: " let's ship it ".to_string(),
+                file_path: "/data/audio 1.mp4".to_string(),
+                start_time: Some(12.4),
+            }],
+        }],
+        tags: vec![TagUsage {
+            name: "rust".to_string(),
+            frames: 3,
+            audio_chunks: 1,
+        }],
+    };
+
+    let md = render_journal(
+        NaiveDate::from_ymd_opt(2025, 3, 14).unwrap(),
+        &day,
+        &Utc,
+        "http://localhost:3030/",
+    );
+
+    assert!(md.starts_with("# Journal 2025-03-14\n"));
+    assert!(md.contains("- **09:00–09:45** Code (45 min, monitor_1)\n"));
+    assert!(md.contains("  - windows: main.rs, lib.rs\n"));
+    assert!(md.contains(
+        "  - > fn main() { println!(\"hello\") } [frame](http://localhost:3030/frames/42)\n"
+    ));
+    assert!(!md.contains("Finder"));
+    assert!(md.contains("### 10:00–10:30 zoom.us\n"));
+    assert!(md.contains("Participants: Dan (me)\n"));
+    assert!(md.contains(
+        "- **10:05** MacBook Pro Microphone (input): let's ship it ([audio at 12s](</data/audio 1.mp4>))\n"
+    ));
+    assert!(md.contains("- `rust`: 3 frames, 1 audio chunks\n"));
+    assert!(!md.contains("Nothing recorded"));
+
+    let mut meeting = day.meetings[0].clone();
+    let md = render_meeting_export(&meeting, &Utc);
+    assert!(md.starts_with("# Meeting on zoom.us\n\n2025-03-14 10:00–10:30 (30 min)\n\n"));
+    assert!(md.contains("Participants: Dan (me)\n"));
+    meeting.meeting.title = Some("Release".to_string());
+    meeting.meeting.end_time = None;
+    let md = render_meeting_export(&meeting, &Utc);
+    assert!(md.starts_with("# Release\n\n2025-03-14 10:00–…\n\n"));
+}
+
+#[test]
+fn test_render_empty_journal() {
+    let start = Utc.with_ymd_and_hms(2025, 3, 14, 0, 0, 0).unwrap();
+    let day = JournalDay {
+        start,
+        end: start + chrono::Duration::days(1),
+        sessions: Vec::new(),
+        meetings: Vec::new(),
+        tags: Vec::new(),
+    };
+
+    let md = render_journal(
+        NaiveDate::from_ymd_opt(2025, 3, 14).unwrap(),
+        &day,
+        &Utc,
+        "http://localhost:3030",
+    );
+    assert_eq!(md, "# Journal 2025-03-14\n\nNothing recorded this day.\n");
+}
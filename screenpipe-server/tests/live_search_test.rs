@@ -0,0 +1,171 @@
+use axum::{
+    body::{Body, BodyDataStream},
+    http::{Request, StatusCode},
+    Router,
+};
+use chrono::{DateTime, Duration, Utc};
+use futures::StreamExt;
+use screenpipe_audio::audio_manager::AudioManagerBuilder;
+use screenpipe_db::{DatabaseManager, OcrEngine};
+use screenpipe_server::{PipeManager, SCServer};
+use serde_json::Value;
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use tower::ServiceExt;
+
+async fn setup_test_app() -> (Router, Arc<DatabaseManager>) {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let audio_manager = Arc::new(
+        AudioManagerBuilder::new()
+            .output_path("/tmp/screenpipe".into())
+            .build(db.clone())
+            .await
+            .unwrap(),
+    );
+    let router = SCServer::new(
+        db.clone(),
+        SocketAddr::from(([127, 0, 0, 1], 23953)),
+        PathBuf::from(""),
+        Arc::new(PipeManager::new(PathBuf::from(""))),
+        false,
+        false,
+        false,
+        audio_manager,
+    )
+    .create_router(false)
+    .await;
+    db.insert_video_chunk("video.mp4", "monitor_1")
+        .await
+        .unwrap();
+    (router, db)
+}
+
+async fn insert_frame(db: &DatabaseManager, timestamp: DateTime<Utc>, text: &str) {
+    let frame_id = db
+        .insert_frame(
+            "monitor_1",
+            Some(timestamp),
+            None,
+            Some("Zoom"),
+            None,
+            false,
+        )
+        .await
+        .unwrap();
+    db.insert_ocr_text(frame_id, text, "", Arc::new(OcrEngine::Tesseract))
+        .await
+        .unwrap();
+}
+
+/// Server-sent events of a live search, read as they come.
+struct LiveEvents {
+    body: BodyDataStream,
+    buffer: String,
+}
+
+impl LiveEvents {
+    async fn open(app: &Router, uri: &str) -> Self {
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        Self {
+            body: response.into_body().into_data_stream(),
+            buffer: String::new(),
+        }
+    }
+
+    /// Text of the next result, `None` when none comes within `wait`.
+    async fn next_text(&mut self, wait: std::time::Duration) -> Option<String> {
+        let deadline = tokio::time::Instant::now() + wait;
+        loop {
+            if let Some(end) = self.buffer.find("\n\n") {
+                let event: String = self.buffer.drain(..end + 2).collect();
+                let Some(data) = event.lines().find_map(|line| line.strip_prefix("data:")) else {
+                    continue;
+                };
+                let item: Value = serde_json::from_str(data.trim()).unwrap();
+                return Some(item["content"]["text"].as_str().unwrap().to_string());
+            }
+            let chunk = tokio::time::timeout_at(deadline, self.body.next())
+                .await
+                .ok()??
+                .unwrap();
+            self.buffer.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_live_search_catches_up_on_the_newest_results_only() {
+    let (app, db) = setup_test_app().await;
+    let now = Utc::now();
+    for hours_ago in (1..=5).rev() {
+        insert_frame(
+            &db,
+            now - Duration::hours(hours_ago),
+            &format!("standup {}h ago", hours_ago),
+        )
+        .await;
+    }
+
+    let start_time = (now - Duration::days(30)).format("%Y-%m-%dT%H:%M:%SZ");
+    let mut events = LiveEvents::open(
+        &app,
+        &format!(
+            "/search/live?q=standup&content_type=ocr&limit=2&start_time={}",
+            start_time
+        ),
+    )
+    .await;
+
+    // the two newest, oldest first, and nothing older
+    let wait = std::time::Duration::from_secs(5);
+    assert_eq!(
+        events.next_text(wait).await.as_deref(),
+        Some("standup 2h ago")
+    );
+    assert_eq!(
+        events.next_text(wait).await.as_deref(),
+        Some("standup 1h ago")
+    );
+    assert_eq!(
+        events.next_text(std::time::Duration::from_secs(3)).await,
+        None
+    );
+}
+
+#[tokio::test]
+async fn test_live_search_sends_new_results_once() {
+    let (app, db) = setup_test_app().await;
+    let mut events =
+        LiveEvents::open(&app, "/search/live?q=standup&content_type=ocr&limit=2").await;
+    insert_frame(&db, Utc::now(), "standup notes").await;
+
+    let wait = std::time::Duration::from_secs(5);
+    assert_eq!(
+        events.next_text(wait).await.as_deref(),
+        Some("standup notes")
+    );
+
+    // more content than `limit` coming in between two looks is all sent
+    for n in 0..3 {
+        insert_frame(
+            &db,
+            Utc::now() + Duration::milliseconds(n),
+            &format!("standup follow-up {}", n),
+        )
+        .await;
+    }
+    for n in 0..3 {
+        assert_eq!(
+            events.next_text(wait).await,
+            Some(format!("standup follow-up {}", n))
+        );
+    }
+    assert_eq!(
+        events.next_text(std::time::Duration::from_secs(3)).await,
+        None
+    );
+}
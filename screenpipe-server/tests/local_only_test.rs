@@ -0,0 +1,98 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::Request,
+};
+use clap::Parser;
+use screenpipe_audio::audio_manager::AudioManagerBuilder;
+use screenpipe_core::egress;
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::{
+    local_only::{enforce, violations},
+    Cli, PipeManager, SCServer,
+};
+use serde_json::Value;
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use tower::ServiceExt;
+
+fn cli(args: &[&str]) -> Cli {
+    Cli::try_parse_from(std::iter::once("screenpipe").chain(args.iter().copied())).unwrap()
+}
+
+#[test]
+fn test_local_only_violations() {
+    assert!(violations(&cli(&[])).is_empty());
+    assert!(violations(&cli(&[
+        "--smtp-url",
+        "smtp://localhost:25",
+        "--outage-notifier",
+        "http://127.0.0.1:9000/alerts",
+    ]))
+    .is_empty());
+
+    let found = violations(&cli(&[
+        "-a",
+        "deepgram",
+        "-o",
+        "unstructured",
+        "--enable-llm",
+        "--trash-purge-webhook",
+        "https://hooks.example.com/purge",
+        "--outage-notifier",
+        "desktop",
+        "--outage-notifier",
+        "https://hooks.example.com/outage",
+    ]));
+    assert_eq!(found.len(), 5, "{:?}", found);
+    assert!(found[0].contains("deepgram"));
+}
+
+#[tokio::test]
+async fn test_local_only_mode_is_reported_by_health() {
+    let mut refused = cli(&["--local-only", "-o", "unstructured"]);
+    assert!(enforce(&mut refused).is_err());
+
+    let mut local = cli(&["--local-only"]);
+    enforce(&mut local).unwrap();
+    assert!(local.disable_telemetry);
+    assert!(egress::check_egress("https://api.notion.com/v1/pages").is_err());
+
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let audio_manager = Arc::new(
+        AudioManagerBuilder::new()
+            .output_path("/tmp/screenpipe".into())
+            .build(db.clone())
+            .await
+            .unwrap(),
+    );
+    let app = SCServer::new(
+        db.clone(),
+        SocketAddr::from(([127, 0, 0, 1], 23973)),
+        PathBuf::from(""),
+        Arc::new(PipeManager::new(PathBuf::from(""))),
+        false,
+        false,
+        false,
+        audio_manager,
+    )
+    .create_router(false)
+    .await;
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/health")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let health: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(health["local_only"]["enabled"], true);
+    assert_eq!(health["local_only"]["telemetry"], false);
+    assert_eq!(health["local_only"]["blocked_requests"], 1);
+    assert_eq!(
+        health["local_only"]["last_blocked"],
+        "https://api.notion.com"
+    );
+}
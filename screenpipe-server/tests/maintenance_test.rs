@@ -0,0 +1,131 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::Request,
+};
+use chrono::{Duration, Utc};
+use screenpipe_audio::audio_manager::AudioManagerBuilder;
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::{
+    maintenance::{is_capture_idle, MaintenanceScheduler},
+    PipeManager, SCServer,
+};
+use serde_json::Value;
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use tower::ServiceExt;
+
+#[test]
+fn test_is_capture_idle() {
+    let now = Utc::now();
+    let idle_after = Duration::minutes(10);
+    assert!(is_capture_idle(&[None, None, None], now, idle_after));
+    assert!(is_capture_idle(
+        &[Some(now - Duration::minutes(30)), None, None],
+        now,
+        idle_after
+    ));
+    // audio still coming in
+    assert!(!is_capture_idle(
+        &[
+            Some(now - Duration::minutes(30)),
+            Some(now - Duration::minutes(1)),
+            None
+        ],
+        now,
+        idle_after
+    ));
+}
+
+#[tokio::test]
+async fn test_run_if_due() {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let scheduler =
+        MaintenanceScheduler::new(db.clone(), Duration::hours(6), Duration::minutes(10));
+
+    db.insert_video_chunk("video.mp4", "monitor_1")
+        .await
+        .unwrap();
+    db.insert_frame("monitor_1", None, None, Some("Code"), None, true)
+        .await
+        .unwrap();
+    // capturing right now
+    assert!(!scheduler.run_if_due(Utc::now()).await.unwrap());
+    assert!(!scheduler.status().capture_idle);
+    assert!(scheduler.status().last_started.is_none());
+
+    let later = Utc::now() + Duration::minutes(15);
+    assert!(scheduler.run_if_due(later).await.unwrap());
+    let status = scheduler.status();
+    assert!(status.capture_idle);
+    assert!(!status.running);
+    assert_eq!(status.last_started, Some(later));
+    assert!(!status.last_steps.is_empty());
+    assert!(status.last_steps.iter().all(|step| step.error.is_none()));
+
+    // not again before the interval
+    assert!(!scheduler
+        .run_if_due(later + Duration::hours(1))
+        .await
+        .unwrap());
+    // long overdue, runs even while capturing
+    db.insert_frame(
+        "monitor_1",
+        Some(later + Duration::hours(30)),
+        None,
+        Some("Code"),
+        None,
+        true,
+    )
+    .await
+    .unwrap();
+    assert!(scheduler
+        .run_if_due(later + Duration::hours(30))
+        .await
+        .unwrap());
+    assert!(!scheduler.status().capture_idle);
+}
+
+#[tokio::test]
+async fn test_health_reports_maintenance() {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let audio_manager = Arc::new(
+        AudioManagerBuilder::new()
+            .output_path("/tmp/screenpipe".into())
+            .build(db.clone())
+            .await
+            .unwrap(),
+    );
+    let scheduler =
+        MaintenanceScheduler::new(db.clone(), Duration::hours(6), Duration::minutes(10));
+    scheduler.run(Utc::now()).await;
+    let app = SCServer::new(
+        db.clone(),
+        SocketAddr::from(([127, 0, 0, 1], 23966)),
+        PathBuf::from(""),
+        Arc::new(PipeManager::new(PathBuf::from(""))),
+        false,
+        false,
+        false,
+        audio_manager,
+    )
+    .with_maintenance(Some(scheduler))
+    .create_router(false)
+    .await;
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/health")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let health: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(health["maintenance"]["running"], false);
+    assert!(health["maintenance"]["last_finished"].is_string());
+    assert_eq!(
+        health["maintenance"]["last_steps"][0]["name"],
+        "merge ocr_text_fts"
+    );
+}
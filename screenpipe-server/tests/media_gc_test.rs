@@ -0,0 +1,116 @@
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::media_gc::{collect_garbage, DanglingChunk, GcOptions};
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_collect_garbage() {
+    let db = DatabaseManager::new("sqlite::memory:").await.unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let media_dir = dir.path().join("data");
+    std::fs::create_dir(&media_dir).unwrap();
+    let referenced = media_dir.join("monitor_1_2025.mp4");
+    let orphan = media_dir.join("monitor_1_2024.mp4");
+    let notes = media_dir.join("notes.txt");
+    std::fs::write(&referenced, b"video").unwrap();
+    std::fs::write(&orphan, b"orphan").unwrap();
+    std::fs::write(&notes, b"not media").unwrap();
+
+    db.insert_video_chunk(&referenced.to_string_lossy(), "monitor_1")
+        .await
+        .unwrap();
+    let gone = db
+        .insert_video_chunk("/gone/monitor_2_2025.mp4", "monitor_2")
+        .await
+        .unwrap();
+    db.insert_frame("monitor_2", None, None, Some("Code"), None, true)
+        .await
+        .unwrap();
+
+    let report = collect_garbage(
+        &db,
+        dir.path(),
+        GcOptions {
+            min_age: Duration::ZERO,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let orphan = orphan.canonicalize().unwrap();
+    assert_eq!(
+        report.orphaned_files,
+        vec![orphan.to_string_lossy().into_owned()]
+    );
+    assert_eq!(report.orphaned_bytes, 6);
+    assert_eq!(
+        report.dangling_chunks,
+        vec![DanglingChunk {
+            kind: "video".to_string(),
+            id: gone,
+            file_path: "/gone/monitor_2_2025.mp4".to_string(),
+        }]
+    );
+    // only reported
+    assert!(orphan.exists());
+    assert_eq!(report.cleared_chunks, 0);
+
+    // too recent to be taken
+    let report = collect_garbage(
+        &db,
+        dir.path(),
+        GcOptions {
+            min_age: Duration::from_secs(60 * 60),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert!(report.orphaned_files.is_empty());
+
+    let report = collect_garbage(
+        &db,
+        dir.path(),
+        GcOptions {
+            delete_files: true,
+            clear_missing: true,
+            min_age: Duration::ZERO,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!((report.deleted_files, report.cleared_chunks), (1, 1));
+    assert!(!orphan.exists());
+    assert!(referenced.exists() && notes.exists());
+    assert_eq!(db.list_media_references().await.unwrap().len(), 1);
+    // the frame stays without its media
+    let frame = db.get_frame(1).await.unwrap();
+    assert!(frame.is_some_and(|(file_path, _)| file_path.is_empty()));
+}
+
+#[tokio::test]
+async fn test_collect_garbage_refuses() {
+    let db = DatabaseManager::new("sqlite::memory:").await.unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let options = GcOptions {
+        delete_files: true,
+        clear_missing: true,
+        min_age: Duration::ZERO,
+    };
+
+    // the data directory isn't there, or isn't mounted
+    assert!(collect_garbage(&db, dir.path(), options).await.is_err());
+
+    let media_dir = dir.path().join("data");
+    std::fs::create_dir(&media_dir).unwrap();
+    let file = media_dir.join("monitor_1_2025.mp4");
+    std::fs::write(&file, b"video").unwrap();
+    // the recordings point at the media's old place
+    for device in ["monitor_1", "monitor_2"] {
+        db.insert_video_chunk(&format!("/moved/{device}_2025.mp4"), device)
+            .await
+            .unwrap();
+    }
+    assert!(collect_garbage(&db, dir.path(), options).await.is_err());
+    assert!(file.exists());
+    assert_eq!(db.list_media_references().await.unwrap().len(), 2);
+}
@@ -0,0 +1,101 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use chrono::Duration;
+use screenpipe_audio::audio_manager::AudioManagerBuilder;
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::media_integrity::{moved_file, MediaVerifier, VerificationReport};
+use screenpipe_server::{PipeManager, SCServer};
+use serde_json::Value;
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use tower::ServiceExt;
+
+async fn setup_test_app() -> (Router, Arc<DatabaseManager>) {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let audio_manager = Arc::new(
+        AudioManagerBuilder::new()
+            .output_path("/tmp/screenpipe".into())
+            .build(db.clone())
+            .await
+            .unwrap(),
+    );
+    let app = SCServer::new(
+        db.clone(),
+        SocketAddr::from(([127, 0, 0, 1], 23974)),
+        PathBuf::from(""),
+        Arc::new(PipeManager::new(PathBuf::from(""))),
+        false,
+        false,
+        false,
+        audio_manager,
+    )
+    .create_router(false)
+    .await;
+    (app, db)
+}
+
+#[tokio::test]
+async fn test_moved_file() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("data")).unwrap();
+    let moved = dir.path().join("data").join("monitor_1_2025.mp4");
+    std::fs::write(&moved, b"video").unwrap();
+
+    assert_eq!(
+        moved_file(dir.path(), "/old/place/monitor_1_2025.mp4").await,
+        Some(moved.clone())
+    );
+    // the file itself didn't move
+    assert_eq!(moved_file(dir.path(), &moved.to_string_lossy()).await, None);
+    assert_eq!(moved_file(dir.path(), "/old/place/other.mp4").await, None);
+}
+
+#[tokio::test]
+async fn test_missing_media_is_listed() {
+    let (app, db) = setup_test_app().await;
+    let dir = tempfile::tempdir().unwrap();
+    db.insert_video_chunk("/gone/old.mp4", "monitor_1")
+        .await
+        .unwrap();
+    db.insert_frame("monitor_1", None, None, Some("Code"), None, true)
+        .await
+        .unwrap();
+    db.insert_video_chunk("/gone/current.mp4", "monitor_1")
+        .await
+        .unwrap();
+
+    let verifier = MediaVerifier::new(
+        db.clone(),
+        dir.path().to_path_buf(),
+        Duration::days(7),
+        true,
+    );
+    assert_eq!(
+        verifier.verify_pending().await.unwrap(),
+        VerificationReport {
+            checked: 1,
+            broken: 1,
+            healed: 0,
+        }
+    );
+    // checked already
+    assert_eq!(verifier.verify_pending().await.unwrap().checked, 0);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/media/issues")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let issues: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(issues.as_array().unwrap().len(), 1);
+    assert_eq!(issues[0]["file_path"], "/gone/old.mp4");
+    assert_eq!(issues[0]["issue"], "missing");
+}
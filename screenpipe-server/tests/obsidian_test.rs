@@ -0,0 +1,94 @@
+use chrono::{NaiveDate, Utc};
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::obsidian::{daily_note, merge_note, note_id, ObsidianNotes, ObsidianSync};
+use std::{fs, path::Path, sync::Arc};
+
+fn notes_in(dir: &Path) -> Vec<String> {
+    let mut notes: Vec<String> = fs::read_dir(dir)
+        .unwrap()
+        .flatten()
+        .filter(|entry| entry.path().is_file())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    notes.sort();
+    notes
+}
+
+#[test]
+fn test_merge_note_keeps_user_text() {
+    let date = NaiveDate::from_ymd_opt(2025, 3, 14).unwrap();
+    let note = daily_note(
+        date,
+        "# Journal 2025-03-14\n\nNothing recorded this day.\n".to_string(),
+    );
+    let content = merge_note(&note, None);
+    assert!(content.starts_with("---\nscreenpipe_id: day-2025-03-14\ndate: 2025-03-14\n"));
+    assert_eq!(note_id(&content).as_deref(), Some("day-2025-03-14"));
+
+    let edited = format!("{}\n## My notes\n\nfollow up with Alice\n", content);
+    let updated = daily_note(date, "# Journal 2025-03-14\n\n## Timeline\n".to_string());
+    let merged = merge_note(&updated, Some(&edited));
+    assert!(merged.contains("## Timeline"));
+    assert!(!merged.contains("Nothing recorded"));
+    assert!(merged.ends_with("\n## My notes\n\nfollow up with Alice\n"));
+    // merging again changes nothing
+    assert_eq!(merge_note(&updated, Some(&merged)), merged);
+
+    assert_eq!(note_id("# no front matter\nscreenpipe_id: day-1"), None);
+}
+
+#[tokio::test]
+async fn test_obsidian_sync_updates_notes_in_place() {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    db.insert_video_chunk("video.mp4", "monitor_1")
+        .await
+        .unwrap();
+    db.insert_frame(
+        "monitor_1",
+        Some(Utc::now()),
+        None,
+        Some("Code"),
+        Some("main.rs"),
+        true,
+    )
+    .await
+    .unwrap();
+    db.start_meeting("zoom.us", Utc::now()).await.unwrap();
+
+    let vault = tempfile::tempdir().unwrap();
+    let sync = ObsidianSync::new(
+        db.clone(),
+        vault.path().to_path_buf(),
+        "screenpipe".to_string(),
+        ObsidianNotes::All,
+        "http://localhost:3030".to_string(),
+    );
+
+    assert_eq!(sync.sync().await.unwrap(), 2);
+    let dir = vault.path().join("screenpipe");
+    let daily = notes_in(&dir);
+    assert_eq!(daily.len(), 1);
+    let meetings = notes_in(&dir.join("meetings"));
+    assert_eq!(meetings.len(), 1);
+    assert!(meetings[0].ends_with(" zoom.us.md"));
+
+    // nothing changed, nothing is written
+    assert_eq!(sync.sync().await.unwrap(), 0);
+
+    // a renamed note with text of the user is updated, not duplicated
+    let renamed = dir.join("today.md");
+    fs::rename(dir.join(&daily[0]), &renamed).unwrap();
+    let mut content = fs::read_to_string(&renamed).unwrap();
+    content.push_str("my own notes\n");
+    fs::write(&renamed, content).unwrap();
+    db.end_meetings(Utc::now()).await.unwrap();
+
+    // the meeting ended, in its note and the daily one
+    assert_eq!(sync.sync().await.unwrap(), 2);
+    assert_eq!(notes_in(&dir), vec!["today.md"]);
+    assert!(fs::read_to_string(&renamed)
+        .unwrap()
+        .ends_with("my own notes\n"));
+    let meeting = fs::read_to_string(dir.join("meetings").join(&meetings[0])).unwrap();
+    assert!(meeting.contains("\nend: "));
+}
@@ -0,0 +1,46 @@
+use screenpipe_server::ocr_providers::{register_providers, RemoteOcrProviderArg};
+use screenpipe_vision::ocr_provider;
+
+#[test]
+fn test_remote_ocr_provider_arg() {
+    assert_eq!(
+        "paddle@2.7=http://gpu.local:8866/ocr"
+            .parse::<RemoteOcrProviderArg>()
+            .unwrap(),
+        RemoteOcrProviderArg {
+            name: "paddle".to_string(),
+            version: "2.7".to_string(),
+            url: "http://gpu.local:8866/ocr".to_string(),
+        }
+    );
+    assert_eq!(
+        "gpu=https://ocr.example.com"
+            .parse::<RemoteOcrProviderArg>()
+            .unwrap()
+            .version,
+        "unversioned"
+    );
+    for invalid in [
+        "paddle",
+        "=http://localhost/ocr",
+        "a/b=http://localhost",
+        "x=ftp://host",
+    ] {
+        assert!(
+            invalid.parse::<RemoteOcrProviderArg>().is_err(),
+            "{}",
+            invalid
+        );
+    }
+}
+
+#[test]
+fn test_register_providers() {
+    let remote = vec!["paddle@2.7=http://localhost:8866/ocr".parse().unwrap()];
+    register_providers(&remote, Some("paddle")).unwrap();
+    let provider = ocr_provider("paddle").unwrap();
+    assert_eq!((provider.name(), provider.version()), ("paddle", "2.7"));
+
+    let error = register_providers(&[], Some("tesseract-gpu")).unwrap_err();
+    assert!(error.to_string().contains("paddle"));
+}
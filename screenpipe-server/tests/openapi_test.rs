@@ -0,0 +1,62 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use screenpipe_audio::audio_manager::AudioManagerBuilder;
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::{openapi_spec, PipeManager, SCServer};
+use serde_json::Value;
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use tower::ServiceExt;
+
+#[test]
+fn test_openapi_spec_documents_the_api() {
+    let spec = openapi_spec();
+    assert_eq!(spec["info"]["title"], "screenpipe");
+    assert_eq!(spec["info"]["version"], env!("CARGO_PKG_VERSION"));
+
+    let paths = spec["paths"].as_object().unwrap();
+    for path in ["/search", "/health", "/raw_sql", "/speakers/merge"] {
+        assert!(paths.contains_key(path), "{} is missing", path);
+    }
+    assert!(paths.keys().any(|path| path.starts_with("/tags/")));
+    assert!(spec["paths"]["/search"]["get"].is_object());
+}
+
+#[tokio::test]
+async fn test_openapi_spec_is_served() {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let audio_manager = Arc::new(
+        AudioManagerBuilder::new()
+            .output_path("/tmp/screenpipe".into())
+            .build(db.clone())
+            .await
+            .unwrap(),
+    );
+    let app = SCServer::new(
+        db,
+        SocketAddr::from(([127, 0, 0, 1], 23951)),
+        PathBuf::from(""),
+        Arc::new(PipeManager::new(PathBuf::from(""))),
+        false,
+        false,
+        false,
+        audio_manager,
+    )
+    .create_router(false)
+    .await;
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/openapi.json")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let served: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(served["paths"], openapi_spec()["paths"]);
+}
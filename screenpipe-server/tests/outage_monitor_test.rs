@@ -0,0 +1,131 @@
+use chrono::{NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday};
+use screenpipe_db::{DeviceActivity, DeviceOcrFailures};
+use screenpipe_server::outage_monitor::{
+    OcrFailureTracker, OutageAlert, OutageTracker, RecordingHours,
+};
+
+fn local(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+    // january 2025 starts on a wednesday
+    NaiveDate::from_ymd_opt(2025, 1, day)
+        .unwrap()
+        .and_hms_opt(hour, minute, 0)
+        .unwrap()
+}
+
+fn activity(device_name: &str, hour: u32, minute: u32) -> DeviceActivity {
+    DeviceActivity {
+        device_name: device_name.to_string(),
+        kind: "vision".to_string(),
+        last_seen: Utc.from_utc_datetime(&local(1, hour, minute)),
+    }
+}
+
+#[test]
+fn test_parse_recording_hours() {
+    assert_eq!(
+        "mon-fri 09:00-18:30".parse::<RecordingHours>().unwrap(),
+        RecordingHours {
+            days: Some((Weekday::Mon, Weekday::Fri)),
+            start: chrono::NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            end: chrono::NaiveTime::from_hms_opt(18, 30, 0).unwrap(),
+        }
+    );
+    assert!("9am-5pm".parse::<RecordingHours>().is_err());
+    assert!("weekdays 09:00-18:00".parse::<RecordingHours>().is_err());
+}
+
+#[test]
+fn test_recording_hours_contains() {
+    let office: RecordingHours = "mon-fri 09:00-18:00".parse().unwrap();
+    assert!(office.contains(local(1, 9, 0)));
+    assert!(!office.contains(local(1, 18, 0)));
+    // saturday
+    assert!(!office.contains(local(4, 10, 0)));
+
+    let night: RecordingHours = "fri 22:00-02:00".parse().unwrap();
+    assert!(night.contains(local(3, 23, 0)));
+    // saturday morning, still friday's window
+    assert!(night.contains(local(4, 1, 0)));
+    assert!(!night.contains(local(4, 23, 0)));
+}
+
+#[test]
+fn test_outage_tracker_alerts_once_and_on_recovery() {
+    let mut tracker = OutageTracker::new(
+        chrono::Duration::minutes(15),
+        vec!["09:00-18:00".parse().unwrap()],
+    );
+    let check = |tracker: &mut OutageTracker, hour, minute, activity| {
+        tracker.check(
+            Utc.from_utc_datetime(&local(1, hour, minute)),
+            local(1, hour, minute),
+            activity,
+        )
+    };
+
+    // silent overnight, the window only counts from 09:00
+    assert!(check(&mut tracker, 9, 0, vec![activity("monitor_1", 2, 0)]).is_empty());
+    assert!(check(&mut tracker, 9, 10, vec![]).is_empty());
+    assert_eq!(
+        check(&mut tracker, 9, 16, vec![]),
+        vec![OutageAlert::Outage {
+            device_name: "monitor_1".to_string(),
+            kind: "vision".to_string(),
+            last_seen: Utc.from_utc_datetime(&local(1, 2, 0)),
+            silent_minutes: 7 * 60 + 16,
+        }]
+    );
+    // reported once
+    assert!(check(&mut tracker, 9, 30, vec![]).is_empty());
+    assert_eq!(
+        check(&mut tracker, 9, 45, vec![activity("monitor_1", 9, 44)]),
+        vec![OutageAlert::Recovered {
+            device_name: "monitor_1".to_string(),
+            kind: "vision".to_string(),
+            down_minutes: 7 * 60 + 44,
+        }]
+    );
+    // outside the recording hours nothing is reported
+    assert!(check(&mut tracker, 20, 0, vec![]).is_empty());
+}
+
+#[test]
+fn test_ocr_failure_tracker() {
+    let mut tracker = OcrFailureTracker::new(50);
+    let failures = |frames: i64, without_text: i64| {
+        vec![DeviceOcrFailures {
+            device_name: "monitor_1".to_string(),
+            frames,
+            without_text,
+        }]
+    };
+
+    assert!(tracker.check(failures(40, 20)).is_empty());
+    // too few frames to tell
+    assert!(tracker.check(failures(5, 5)).is_empty());
+    assert_eq!(
+        tracker.check(failures(40, 30)),
+        vec![OutageAlert::OcrFailures {
+            device_name: "monitor_1".to_string(),
+            frames: 40,
+            without_text: 30,
+        }]
+    );
+    // reported once
+    assert!(tracker.check(failures(40, 40)).is_empty());
+    assert_eq!(
+        tracker.check(failures(40, 2)),
+        vec![OutageAlert::OcrRecovered {
+            device_name: "monitor_1".to_string(),
+        }]
+    );
+    assert_eq!(
+        OutageAlert::OcrFailures {
+            device_name: "monitor_1".to_string(),
+            frames: 40,
+            without_text: 30,
+        }
+        .message(),
+        "no text read from 30 of the last 40 frames of monitor_1"
+    );
+}
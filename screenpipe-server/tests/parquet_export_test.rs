@@ -0,0 +1,99 @@
+use arrow_array::{Array, Int64Array, StringArray};
+use chrono::{Duration, Local, Utc};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use screenpipe_db::{AudioDevice, DatabaseManager, DeviceType};
+use screenpipe_server::parquet_export::{export_parquet, partition_path, ParquetSummary};
+use screenpipe_vision::OcrEngine;
+use std::{fs::File, path::Path, sync::Arc};
+
+fn read_rows(path: &Path) -> Vec<arrow_array::RecordBatch> {
+    ParquetRecordBatchReaderBuilder::try_new(File::open(path).unwrap())
+        .unwrap()
+        .build()
+        .unwrap()
+        .map(|batch| batch.unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_export_parquet_by_day() {
+    let db = DatabaseManager::new("sqlite::memory:").await.unwrap();
+    db.insert_video_chunk("video.mp4", "monitor_1")
+        .await
+        .unwrap();
+    for (days_ago, text) in [(3, "old notes"), (0, "fresh notes")] {
+        let frame_id = db
+            .insert_frame(
+                "monitor_1",
+                Some(Utc::now() - Duration::days(days_ago)),
+                None,
+                Some("Notes"),
+                Some(text),
+                true,
+            )
+            .await
+            .unwrap();
+        db.insert_ocr_text(frame_id, text, "", Arc::new(OcrEngine::Tesseract))
+            .await
+            .unwrap();
+    }
+    let audio_chunk_id = db.insert_audio_chunk("audio.mp4").await.unwrap();
+    db.insert_audio_transcription(
+        audio_chunk_id,
+        "hello",
+        0,
+        "",
+        &AudioDevice {
+            name: "mic".to_string(),
+            device_type: DeviceType::Input,
+        },
+        None,
+        Some(0.0),
+        Some(1.5),
+    )
+    .await
+    .unwrap();
+
+    let out = tempfile::tempdir().unwrap();
+    let today = Local::now().date_naive();
+    let first_day = today - Duration::days(3);
+    let summary = export_parquet(&db, out.path(), first_day, today, false)
+        .await
+        .unwrap();
+    // frames and text of both days, transcriptions of today
+    assert_eq!(
+        summary,
+        ParquetSummary {
+            files: 5,
+            skipped: 0,
+            rows: 5,
+        }
+    );
+    assert!(!partition_path(out.path(), "audio_transcriptions", first_day).exists());
+
+    let batches = read_rows(&partition_path(out.path(), "ocr_text", first_day));
+    let text = batches[0]
+        .column_by_name("text")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    assert_eq!(text.value(0), "old notes");
+
+    let batches = read_rows(&partition_path(out.path(), "audio_transcriptions", today));
+    assert_eq!(batches[0].num_rows(), 1);
+    let speaker_id = batches[0]
+        .column_by_name("speaker_id")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap();
+    assert!(speaker_id.is_null(0));
+
+    // past days are exported once, today again
+    let summary = export_parquet(&db, out.path(), first_day, today, false)
+        .await
+        .unwrap();
+    assert_eq!(summary.skipped, 2);
+    assert_eq!(summary.files, 3);
+}
@@ -0,0 +1,75 @@
+use screenpipe_server::participants::{detect_participant_labels, ParticipantLabel};
+use std::collections::HashMap;
+
+fn block(text: &str, left: f64, top: f64, width: f64, height: f64) -> HashMap<String, String> {
+    HashMap::from([
+        ("text".to_string(), text.to_string()),
+        ("left".to_string(), left.to_string()),
+        ("top".to_string(), top.to_string()),
+        ("width".to_string(), width.to_string()),
+        ("height".to_string(), height.to_string()),
+    ])
+}
+
+#[test]
+fn test_detects_labels_in_call_grid() {
+    // 2x2 gallery view with the toolbar at the bottom
+    let blocks = vec![
+        block("Alice Martin (Host)", 0.02, 0.52, 0.12, 0.02),
+        block("Bob Chen", 0.52, 0.52, 0.07, 0.02),
+        block("Carla Díaz", 0.02, 0.06, 0.08, 0.02),
+        block("Dan O'Neil (You)", 0.52, 0.06, 0.10, 0.02),
+        block("Mute", 0.05, 0.01, 0.03, 0.015),
+        block("Participants", 0.40, 0.01, 0.06, 0.015),
+        block("Chat", 0.50, 0.01, 0.03, 0.015),
+    ];
+
+    let labels = detect_participant_labels("zoom.us", "Zoom Meeting", &blocks);
+    assert_eq!(
+        labels,
+        vec![
+            ParticipantLabel {
+                name: "Alice Martin".to_string(),
+                is_local: false,
+            },
+            ParticipantLabel {
+                name: "Bob Chen".to_string(),
+                is_local: false,
+            },
+            ParticipantLabel {
+                name: "Carla Díaz".to_string(),
+                is_local: false,
+            },
+            ParticipantLabel {
+                name: "Dan O'Neil".to_string(),
+                is_local: true,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_ignores_paragraph_text_and_other_apps() {
+    // capitalized lines stacked like a paragraph are not a grid
+    let paragraph = vec![
+        block("Quarterly Planning", 0.10, 0.50, 0.15, 0.02),
+        block("Next Steps", 0.10, 0.475, 0.08, 0.02),
+        block("Open Questions", 0.10, 0.45, 0.11, 0.02),
+    ];
+    assert!(detect_participant_labels("zoom.us", "Zoom Meeting", &paragraph).is_empty());
+
+    let grid = vec![
+        block("Alice Martin", 0.02, 0.52, 0.12, 0.02),
+        block("Bob Chen", 0.52, 0.52, 0.07, 0.02),
+    ];
+    assert!(detect_participant_labels("Finder", "Documents", &grid).is_empty());
+}
+
+#[test]
+fn test_requires_block_positions() {
+    let blocks = vec![
+        HashMap::from([("text".to_string(), "Alice Martin".to_string())]),
+        HashMap::from([("text".to_string(), "Bob Chen".to_string())]),
+    ];
+    assert!(detect_participant_labels("Microsoft Teams", "Standup", &blocks).is_empty());
+}
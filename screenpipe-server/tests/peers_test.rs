@@ -0,0 +1,183 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use screenpipe_audio::audio_manager::AudioManagerBuilder;
+use screenpipe_db::{DatabaseManager, OcrEngine};
+use screenpipe_server::{
+    peers::{
+        sign_request, verify_request, Peer, Peers, PEER_NONCE_HEADER, PEER_SIGNATURE_HEADER,
+        PEER_TIME_HEADER,
+    },
+    PipeManager, SCServer,
+};
+use serde_json::Value;
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use tower::ServiceExt;
+
+const SECRET: &str = "correct horse battery staple";
+
+async fn server(db: Arc<DatabaseManager>, peers: Arc<Peers>) -> SCServer {
+    let audio_manager = Arc::new(
+        AudioManagerBuilder::new()
+            .output_path("/tmp/screenpipe".into())
+            .build(db.clone())
+            .await
+            .unwrap(),
+    );
+    SCServer::new(
+        db,
+        SocketAddr::from(([127, 0, 0, 1], 23970)),
+        PathBuf::from(""),
+        Arc::new(PipeManager::new(PathBuf::from(""))),
+        false,
+        false,
+        false,
+        audio_manager,
+    )
+    .with_peers(Some(peers), SocketAddr::from(([127, 0, 0, 1], 0)))
+}
+
+async fn get(app: &Router, uri: &str) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+#[test]
+fn test_peer_arg() {
+    assert_eq!(
+        "desktop=https://192.168.1.20:3035/".parse::<Peer>(),
+        Ok(Peer {
+            name: "desktop".to_string(),
+            url: "https://192.168.1.20:3035".to_string(),
+            discovered: false,
+        })
+    );
+    assert!("https://192.168.1.20:3035".parse::<Peer>().is_err());
+    // peers only answer over tls
+    assert!("desktop=http://192.168.1.20:3035".parse::<Peer>().is_err());
+    assert!("desktop=192.168.1.20".parse::<Peer>().is_err());
+}
+
+#[test]
+fn test_verify_peer_request() {
+    let path = "/peers/search?q=mockups";
+    let signature = sign_request(SECRET, 1_000, "nonce", path);
+    let verify = |secret: &str, signature: &str, path: &str, now: i64| {
+        verify_request(
+            secret,
+            Some("1000"),
+            Some("nonce"),
+            Some(signature),
+            path,
+            now,
+        )
+    };
+    assert_eq!(
+        verify(SECRET, &signature, path, 1_030),
+        Ok("nonce".to_string())
+    );
+    assert!(verify("another secret", &signature, path, 1_030).is_err());
+    assert!(verify(SECRET, &signature, "/peers/search?q=other", 1_030).is_err());
+    // too old
+    assert!(verify(SECRET, &signature, path, 1_100).is_err());
+    assert!(verify_request(SECRET, None, None, None, path, 1_000).is_err());
+}
+
+#[tokio::test]
+async fn test_search_peers() {
+    let desktop_db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    desktop_db
+        .insert_video_chunk("desktop.mp4", "monitor_1")
+        .await
+        .unwrap();
+    let frame = desktop_db
+        .insert_frame("monitor_1", None, None, Some("Figma"), None, true)
+        .await
+        .unwrap();
+    desktop_db
+        .insert_ocr_text(frame, "desktop mockups", "", Arc::new(OcrEngine::Tesseract))
+        .await
+        .unwrap();
+    let desktop_peers = Peers::new("desktop".to_string(), SECRET.to_string(), Vec::new()).unwrap();
+    let desktop = server(desktop_db, desktop_peers.clone())
+        .await
+        .create_peer_router()
+        .await;
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("https://{}", listener.local_addr().unwrap());
+    let (status, _) = get(&desktop, "/peers/search?q=mockups").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // a signed request is answered once
+    let path = "/peers/search?q=mockups";
+    let time = chrono::Utc::now().timestamp();
+    let signed = || {
+        Request::builder()
+            .uri(path)
+            .header(PEER_TIME_HEADER, time.to_string())
+            .header(PEER_NONCE_HEADER, "nonce")
+            .header(
+                PEER_SIGNATURE_HEADER,
+                sign_request(SECRET, time, "nonce", path),
+            )
+            .body(Body::empty())
+            .unwrap()
+    };
+    let response = desktop.clone().oneshot(signed()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = desktop.clone().oneshot(signed()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    tokio::spawn(async move { desktop_peers.serve(listener, desktop).await });
+
+    let laptop = |secret: &str| {
+        Peers::new(
+            "laptop".to_string(),
+            secret.to_string(),
+            vec![Peer {
+                name: "desktop".to_string(),
+                url: url.clone(),
+                discovered: false,
+            }],
+        )
+        .unwrap()
+    };
+    let laptop_db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let app = server(laptop_db.clone(), laptop(SECRET))
+        .await
+        .create_router(false)
+        .await;
+    let (status, peers) = get(&app, "/peers").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(peers[0]["name"], "desktop");
+
+    let (status, results) = get(&app, "/search/peers?q=mockups").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(results["data"].as_array().unwrap().len(), 0);
+    assert_eq!(results["peers"][0]["peer"], "desktop");
+    assert_eq!(results["peers"][0]["error"], Value::Null);
+    assert_eq!(results["peers"][0]["total"], 1);
+    assert_eq!(
+        results["peers"][0]["data"][0]["content"]["text"],
+        "desktop mockups"
+    );
+
+    // the desktop refuses another secret
+    let app = server(laptop_db, laptop("not the secret of the desktop"))
+        .await
+        .create_router(false)
+        .await;
+    // the desktop's certificate isn't the one of another secret
+    let (status, results) = get(&app, "/search/peers?q=mockups").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(results["peers"][0]["error"].is_string());
+    assert_eq!(results["peers"][0]["total"], 0);
+}
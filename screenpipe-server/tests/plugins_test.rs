@@ -0,0 +1,141 @@
+use screenpipe_db::{ContentHook, ContentProcessor, HookContent};
+use screenpipe_server::plugins::{PluginCapability, PluginHost};
+use serde_json::json;
+use std::path::Path;
+
+// a module returning `outcome` from each of its hooks
+fn module(outcome: &str, extra: &str) -> String {
+    format!(
+        r#"(module
+            {extra}
+            (memory (export "memory") 1)
+            (data (i32.const 0) "{data}")
+            (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+            (func (export "on_ocr") (param i32 i32) (result i64)
+                (i64.const {len})))"#,
+        extra = extra,
+        data = outcome.replace('"', "\\\""),
+        len = outcome.len(),
+    )
+}
+
+fn write_plugin(dir: &Path, name: &str, manifest: serde_json::Value, wat: &str) {
+    let dir = dir.join(name);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("plugin.json"), manifest.to_string()).unwrap();
+    std::fs::write(dir.join("plugin.wat"), wat).unwrap();
+}
+
+fn ocr(text: &str) -> HookContent {
+    HookContent {
+        hook: ContentHook::OnOcr,
+        id: None,
+        text: text.to_string(),
+        app_name: Some("Code".to_string()),
+        window_name: None,
+        browser_url: None,
+        device: None,
+        speaker_id: None,
+    }
+}
+
+#[test]
+fn test_plugin_capabilities() {
+    let dir = tempfile::tempdir().unwrap();
+    write_plugin(
+        dir.path(),
+        "a-redact",
+        json!({"name": "redact", "wasm": "plugin.wat", "hooks": ["on_ocr"], "capabilities": ["modify", "tag"]}),
+        &module(r#"{"text":"[redacted]","tags":["wasm"]}"#, ""),
+    );
+    write_plugin(
+        dir.path(),
+        "b-drop",
+        json!({"name": "drop", "wasm": "plugin.wat", "hooks": ["on_ocr"]}),
+        &module(r#"{"drop":true}"#, ""),
+    );
+    // asks for the log without the capability
+    write_plugin(
+        dir.path(),
+        "c-log",
+        json!({"name": "log", "wasm": "plugin.wat", "hooks": ["on_ocr"]}),
+        &module(
+            r#"{"drop":true}"#,
+            r#"(import "screenpipe" "log" (func (param i32 i32)))"#,
+        ),
+    );
+    write_plugin(
+        dir.path(),
+        "d-disabled",
+        json!({"name": "disabled", "wasm": "plugin.wat", "hooks": ["on_ocr"], "capabilities": ["drop"], "enabled": false}),
+        &module(r#"{"drop":true}"#, ""),
+    );
+
+    let host = PluginHost::load(dir.path()).unwrap();
+    let outcome = host.process(&ocr("the secret plan"));
+    assert!(!outcome.drop);
+    assert_eq!(outcome.text.as_deref(), Some("[redacted]"));
+    assert_eq!(outcome.tags, vec!["wasm".to_string()]);
+    // not registered for the hook
+    let frame = host.process(&HookContent {
+        hook: ContentHook::OnFrame,
+        ..ocr("the secret plan")
+    });
+    assert_eq!(frame, Default::default());
+
+    let status = host.status();
+    assert_eq!(
+        status.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(),
+        vec!["redact", "drop"]
+    );
+    assert_eq!(
+        status[0].capabilities,
+        vec![PluginCapability::Modify, PluginCapability::Tag]
+    );
+    assert_eq!(
+        (status[0].calls, status[0].modified, status[0].tagged),
+        (1, 1, 1)
+    );
+    assert_eq!(
+        (status[1].calls, status[1].dropped, status[1].denied),
+        (1, 0, 1)
+    );
+}
+
+#[test]
+fn test_plugin_limits() {
+    let dir = tempfile::tempdir().unwrap();
+    write_plugin(
+        dir.path(),
+        "loop",
+        json!({"name": "loop", "wasm": "plugin.wat", "hooks": ["on_ocr"], "capabilities": ["drop"]}),
+        r#"(module
+            (memory (export "memory") 1)
+            (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+            (func (export "on_ocr") (param i32 i32) (result i64)
+                (loop $forever (br $forever))
+                (i64.const 0)))"#,
+    );
+    write_plugin(
+        dir.path(),
+        "oom",
+        json!({"name": "oom", "wasm": "plugin.wat", "hooks": ["on_ocr"], "capabilities": ["drop"]}),
+        r#"(module
+            (memory (export "memory") 1)
+            (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+            (func (export "on_ocr") (param i32 i32) (result i64)
+                (if (i32.eq (memory.grow (i32.const 4096)) (i32.const -1))
+                    (then unreachable))
+                (i64.const 0)))"#,
+    );
+
+    let host = PluginHost::load(dir.path()).unwrap();
+    for _ in 0..2 {
+        // failing plugins leave the content as it was
+        assert_eq!(host.process(&ocr("kept")), Default::default());
+    }
+    for status in host.status() {
+        assert_eq!((status.calls, status.errors), (2, 2), "{}", status.name);
+        assert!(status.last_error.is_some());
+    }
+}
@@ -0,0 +1,186 @@
+use chrono::{Duration, Local, NaiveDate, Utc};
+use screenpipe_db::{
+    AppCategoryRule, DatabaseManager, NewAppCategoryRule, UsageRecord, WindowFocusTime,
+};
+use screenpipe_server::digest::AppTime;
+use screenpipe_server::journal::local_day;
+use screenpipe_server::productivity::{
+    categorize, day_productivity, parse_app_category, productivity,
+};
+
+fn rule(kind: &str, pattern: &str, category: &str) -> AppCategoryRule {
+    AppCategoryRule {
+        id: 0,
+        kind: kind.to_string(),
+        pattern: pattern.to_string(),
+        category: category.to_string(),
+        source: "api".to_string(),
+        created_at: Utc::now(),
+    }
+}
+
+fn frame(app: &str, url: Option<&str>, duration_secs: f64) -> UsageRecord {
+    UsageRecord {
+        kind: "frame".to_string(),
+        id: 0,
+        timestamp: Utc::now(),
+        device_name: "monitor_1".to_string(),
+        app_name: Some(app.to_string()),
+        window_name: None,
+        browser_url: url.map(str::to_string),
+        duration_secs: Some(duration_secs),
+        speaker_name: None,
+        text_length: None,
+    }
+}
+
+#[test]
+fn test_parse_app_category() {
+    assert_eq!(
+        parse_app_category("url:https://youtube.com=distraction"),
+        Ok(NewAppCategoryRule {
+            kind: "url".to_string(),
+            pattern: "https://youtube.com".to_string(),
+            category: "distraction".to_string(),
+        })
+    );
+    assert_eq!(
+        parse_app_category("Slack=communication").unwrap().kind,
+        "app"
+    );
+    assert!(parse_app_category("Slack=meetings").is_err());
+    assert!(parse_app_category("window:=deep_work").is_err());
+    assert!(parse_app_category("Slack").is_err());
+}
+
+#[test]
+fn test_categorize() {
+    let rules = [
+        rule("app", "Arc", "deep_work"),
+        rule("window", "Inbox", "communication"),
+        rule("url", "youtube.com", "distraction"),
+        rule("url", "youtube.com/@mitocw", "deep_work"),
+    ];
+    assert_eq!(categorize(&rules, "Arc", "Home", None), Some("deep_work"));
+    assert_eq!(
+        categorize(&rules, "Arc", "Inbox (3)", None),
+        Some("communication")
+    );
+    assert_eq!(
+        categorize(
+            &rules,
+            "Arc",
+            "Inbox",
+            Some("https://www.YouTube.com/watch")
+        ),
+        Some("distraction")
+    );
+    // the longest pattern wins
+    assert_eq!(
+        categorize(
+            &rules,
+            "Arc",
+            "",
+            Some("https://youtube.com/@mitocw/videos")
+        ),
+        Some("deep_work")
+    );
+    assert_eq!(categorize(&rules, "Finder", "", None), None);
+}
+
+#[test]
+fn test_day_productivity() {
+    let date = NaiveDate::from_ymd_opt(2025, 3, 13).unwrap();
+    let rules = [
+        rule("app", "Code", "deep_work"),
+        rule("app", "Slack", "communication"),
+        rule("url", "youtube.com", "distraction"),
+    ];
+    let window = |app: &str, focused_secs: f64| WindowFocusTime {
+        app_name: app.to_string(),
+        window_name: String::new(),
+        sessions: 1,
+        focused_secs,
+    };
+    let focus = [
+        window("Code", 3600.0),
+        window("Slack", 1200.0),
+        window("Arc", 1800.0),
+        window("Finder", 600.0),
+    ];
+    // a third of the frames of the browser are on youtube
+    let frames = [
+        frame("Arc", Some("https://youtube.com/watch"), 100.0),
+        frame("Arc", Some("https://docs.rs"), 200.0),
+        frame("Code", None, 50.0),
+    ];
+
+    let day = day_productivity(date, &rules, &focus, &frames);
+    assert_eq!(day.source, "focus");
+    let minutes: Vec<(&str, i64)> = day
+        .categories
+        .iter()
+        .map(|time| (time.category.as_str(), time.minutes))
+        .collect();
+    assert_eq!(
+        minutes,
+        vec![
+            ("deep_work", 60),
+            ("communication", 20),
+            ("distraction", 10)
+        ]
+    );
+    assert_eq!(
+        day.categories[2].apps,
+        vec![AppTime {
+            app_name: "Arc".to_string(),
+            minutes: 10,
+        }]
+    );
+    // the rest of the browser and finder
+    assert_eq!(day.uncategorized_minutes, 30);
+    // (60 + 20 / 2) / 90
+    assert_eq!(day.score, Some(78));
+
+    let empty = day_productivity(date, &rules, &[], &[]);
+    assert_eq!(empty.score, None);
+    assert_eq!(empty.categories.len(), 3);
+}
+
+#[tokio::test]
+async fn test_productivity_from_frames() {
+    let db = DatabaseManager::new("sqlite::memory:").await.unwrap();
+    let date = Local::now().date_naive() - Duration::days(1);
+    let (midnight, _) = local_day(date);
+    let at = |minutes: i64| midnight + Duration::hours(10) + Duration::minutes(minutes);
+
+    db.set_config_app_category_rules(&[parse_app_category("Code=deep_work").unwrap()])
+        .await
+        .unwrap();
+    db.insert_app_category_rule(
+        &parse_app_category("url:news.ycombinator.com=distraction").unwrap(),
+    )
+    .await
+    .unwrap();
+    db.insert_video_chunk("video.mp4", "monitor_1")
+        .await
+        .unwrap();
+    for (minutes, app, url) in [
+        (0, "Code", None),
+        (4, "Code", None),
+        (8, "Arc", Some("https://news.ycombinator.com/")),
+        (10, "Code", None),
+    ] {
+        db.insert_frame("monitor_1", Some(at(minutes)), url, Some(app), None, true)
+            .await
+            .unwrap();
+    }
+
+    let days = productivity(&db, date, date).await.unwrap();
+    assert_eq!(days.len(), 1);
+    // without focus sessions the frames last until the next one
+    assert_eq!(days[0].source, "frames");
+    assert_eq!(days[0].categories[0].minutes, 8);
+    assert_eq!(days[0].categories[2].minutes, 2);
+    assert_eq!(days[0].score, Some(80));
+}
@@ -0,0 +1,247 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::State,
+    http::{HeaderMap, Request, StatusCode},
+    routing::post,
+    Json, Router,
+};
+use chrono::{TimeZone, Utc};
+use screenpipe_audio::audio_manager::AudioManagerBuilder;
+use screenpipe_db::{DatabaseManager, NewPushDestination, PushItem, TagContentType};
+use screenpipe_server::{
+    push::{notion_page, readwise_highlight, PushSync},
+    PipeManager, SCServer,
+};
+use screenpipe_vision::OcrEngine;
+use serde_json::{json, Value};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use tokio::sync::Mutex;
+use tower::ServiceExt;
+
+async fn setup_test_app() -> (Router, Arc<DatabaseManager>) {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let audio_manager = Arc::new(
+        AudioManagerBuilder::new()
+            .output_path("/tmp/screenpipe".into())
+            .build(db.clone())
+            .await
+            .unwrap(),
+    );
+    let router = SCServer::new(
+        db.clone(),
+        SocketAddr::from(([127, 0, 0, 1], 23964)),
+        PathBuf::from(""),
+        Arc::new(PipeManager::new(PathBuf::from(""))),
+        false,
+        false,
+        false,
+        audio_manager,
+    )
+    .create_router(false)
+    .await;
+    (router, db)
+}
+
+fn frame_item() -> PushItem {
+    PushItem {
+        content_type: "vision".to_string(),
+        content_id: 12,
+        timestamp: Utc.with_ymd_and_hms(2025, 3, 14, 10, 0, 0).unwrap(),
+        device_name: "monitor_1".to_string(),
+        app_name: "Arc".to_string(),
+        window_name: "Pricing".to_string(),
+        text: "Pro plan $20 per month".to_string(),
+        tags: "bookmark,pricing ideas".to_string(),
+    }
+}
+
+#[test]
+fn test_push_payloads() {
+    let item = frame_item();
+    let highlight = readwise_highlight(&item, "http://localhost:3030/");
+    assert_eq!(highlight["text"], "Pro plan $20 per month");
+    assert_eq!(highlight["title"], "Arc – Pricing");
+    assert_eq!(highlight["source_url"], "http://localhost:3030/frames/12");
+    assert_eq!(highlight["highlighted_at"], "2025-03-14T10:00:00Z");
+    assert_eq!(highlight["note"], ".bookmark .pricing_ideas");
+
+    let schema = json!({
+        "properties": {
+            "Page": { "type": "title" },
+            "Date": { "type": "date" },
+            "Tags": { "type": "multi_select" },
+            "URL": { "type": "rich_text" },
+        }
+    });
+    let page = notion_page("db1", &schema, &item, "http://localhost:3030");
+    assert_eq!(page["parent"]["database_id"], "db1");
+    assert_eq!(
+        page["properties"]["Page"]["title"][0]["text"]["content"],
+        "Arc – Pricing"
+    );
+    assert_eq!(
+        page["properties"]["Date"]["date"]["start"],
+        "2025-03-14T10:00:00Z"
+    );
+    assert_eq!(
+        page["properties"]["Tags"]["multi_select"][1]["name"],
+        "pricing ideas"
+    );
+    // not a url column, left alone
+    assert!(page["properties"].get("URL").is_none());
+    let children = page["children"].as_array().unwrap();
+    assert_eq!(
+        children[0]["paragraph"]["rich_text"][0]["text"]["content"],
+        "Pro plan $20 per month"
+    );
+    assert_eq!(
+        children[1]["paragraph"]["rich_text"][0]["text"]["link"]["url"],
+        "http://localhost:3030/frames/12"
+    );
+
+    // long text is split in blocks notion accepts
+    let long = PushItem {
+        text: "a".repeat(4500),
+        ..item
+    };
+    let page = notion_page("db1", &json!({}), &long, "http://localhost:3030");
+    assert!(page["properties"]["Name"].is_object());
+    assert_eq!(page["children"].as_array().unwrap().len(), 4);
+}
+
+#[tokio::test]
+async fn test_push_destinations_api() {
+    let (app, _db) = setup_test_app().await;
+    let request = |method: &str, body: Value| {
+        Request::builder()
+            .method(method)
+            .uri("/push/destinations")
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(request(
+            "POST",
+            json!({"name": "notes", "kind": "notion", "api_key": "secret_1"}),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .clone()
+        .oneshot(request(
+            "POST",
+            json!({"name": "highlights", "kind": "readwise", "api_key": "secret_2"}),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/push/destinations")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let destinations: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(destinations[0]["kind"], "readwise");
+    assert_eq!(destinations[0]["tags"], "bookmark");
+    // the key stays on the server
+    assert!(destinations[0].get("api_key").is_none());
+    assert!(!String::from_utf8_lossy(&body).contains("secret_2"));
+}
+
+#[derive(Default)]
+struct Received {
+    authorization: Vec<String>,
+    highlights: Vec<Value>,
+}
+
+#[tokio::test]
+async fn test_push_to_readwise() {
+    let received = Arc::new(Mutex::new(Received::default()));
+    let readwise = Router::new()
+        .route(
+            "/highlights/",
+            post(
+                |State(received): State<Arc<Mutex<Received>>>,
+                 headers: HeaderMap,
+                 Json(body): Json<Value>| async move {
+                    let mut received = received.lock().await;
+                    received
+                        .authorization
+                        .push(headers["authorization"].to_str().unwrap().to_string());
+                    received
+                        .highlights
+                        .extend(body["highlights"].as_array().unwrap().iter().cloned());
+                    Json(json!([]))
+                },
+            ),
+        )
+        .with_state(received.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let readwise_url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, readwise).await });
+
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    db.insert_video_chunk("video.mp4", "monitor_1")
+        .await
+        .unwrap();
+    for text in ["saved for later", "not tagged"] {
+        let frame_id = db
+            .insert_frame("monitor_1", None, None, Some("Arc"), Some("Blog"), true)
+            .await
+            .unwrap();
+        db.insert_ocr_text(frame_id, text, "", Arc::new(OcrEngine::Tesseract))
+            .await
+            .unwrap();
+        if text == "saved for later" {
+            db.add_tags(
+                frame_id,
+                TagContentType::Vision,
+                vec!["bookmark".to_string()],
+            )
+            .await
+            .unwrap();
+        }
+    }
+    db.insert_push_destination(&NewPushDestination {
+        name: "highlights".to_string(),
+        kind: "readwise".to_string(),
+        api_key: "rw_token".to_string(),
+        notion_database_id: None,
+        tags: vec!["bookmark".to_string()],
+    })
+    .await
+    .unwrap();
+
+    let sync = PushSync::with_api_urls(
+        db.clone(),
+        "http://localhost:3030".to_string(),
+        "http://127.0.0.1:9".to_string(),
+        readwise_url,
+    );
+    assert_eq!(sync.push_all().await.unwrap(), 1);
+    {
+        let received = received.lock().await;
+        assert_eq!(received.authorization, vec!["Token rw_token".to_string()]);
+        assert_eq!(received.highlights.len(), 1);
+        assert_eq!(received.highlights[0]["text"], "saved for later");
+    }
+    let destination = &db.list_push_destinations().await.unwrap()[0];
+    assert!(destination.last_pushed_at.is_some());
+    assert!(destination.last_error.is_none());
+
+    // pushed once
+    assert_eq!(sync.push_all().await.unwrap(), 0);
+    assert_eq!(received.lock().await.highlights.len(), 1);
+}
@@ -0,0 +1,220 @@
+use axum::{
+    body::Body,
+    http::{header::RETRY_AFTER, Method, Request, StatusCode},
+    response::Response,
+    Router,
+};
+use screenpipe_audio::audio_manager::AudioManagerBuilder;
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::{
+    auth::{create_token, ApiScope},
+    rate_limit::{EndpointClass, RateLimit, RateLimiter, RateLimits},
+    PipeManager, SCServer,
+};
+use std::{
+    net::SocketAddr,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tower::ServiceExt;
+
+async fn setup_test_app(rate_limits: RateLimits, api_auth: bool) -> (Router, Arc<DatabaseManager>) {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let audio_manager = Arc::new(
+        AudioManagerBuilder::new()
+            .output_path("/tmp/screenpipe".into())
+            .build(db.clone())
+            .await
+            .unwrap(),
+    );
+    let app = SCServer::new(
+        db.clone(),
+        SocketAddr::from(([127, 0, 0, 1], 23950)),
+        PathBuf::from(""),
+        Arc::new(PipeManager::new(PathBuf::from(""))),
+        false,
+        false,
+        false,
+        audio_manager,
+    )
+    .with_api_auth(api_auth)
+    .with_rate_limits(rate_limits)
+    .create_router(false)
+    .await;
+    (app, db)
+}
+
+async fn get(app: &Router, uri: &str, token: Option<&str>) -> Response {
+    let mut request = Request::builder().uri(uri);
+    if let Some(token) = token {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+    app.clone()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
+
+#[test]
+fn test_parse_rate_limit() {
+    assert_eq!(
+        "60/min".parse::<RateLimit>().unwrap(),
+        RateLimit {
+            requests: 60,
+            per: Duration::from_secs(60),
+        }
+    );
+    assert_eq!(
+        "5/s".parse::<RateLimit>().unwrap().per,
+        Duration::from_secs(1)
+    );
+    assert!("0/min".parse::<RateLimit>().is_err());
+    assert!("60".parse::<RateLimit>().is_err());
+    assert!("60/day".parse::<RateLimit>().is_err());
+}
+
+#[test]
+fn test_endpoint_class() {
+    assert_eq!(
+        EndpointClass::of(&Method::GET, "/search"),
+        Some(EndpointClass::Search)
+    );
+    assert_eq!(
+        EndpointClass::of(&Method::POST, "/raw_sql"),
+        Some(EndpointClass::Search)
+    );
+    assert_eq!(
+        EndpointClass::of(&Method::GET, "/frames/1"),
+        Some(EndpointClass::Read)
+    );
+    assert_eq!(
+        EndpointClass::of(&Method::POST, "/add"),
+        Some(EndpointClass::Write)
+    );
+    // changes made under /search/ don't get the search budget
+    assert_eq!(
+        EndpointClass::of(&Method::GET, "/search/keyword"),
+        Some(EndpointClass::Search)
+    );
+    for path in ["/search/delete", "/search/tag", "/search/clicks"] {
+        assert_eq!(
+            EndpointClass::of(&Method::POST, path),
+            Some(EndpointClass::Write)
+        );
+    }
+    assert_eq!(
+        EndpointClass::of(&Method::DELETE, "/search/clicks"),
+        Some(EndpointClass::Write)
+    );
+    assert_eq!(EndpointClass::of(&Method::GET, "/health"), None);
+}
+
+#[test]
+fn test_rate_limiter_refills_over_the_period() {
+    let limiter = RateLimiter::new(RateLimits {
+        search: Some("2/s".parse().unwrap()),
+        ..Default::default()
+    });
+    let start = Instant::now();
+
+    assert!(limiter.check("a", EndpointClass::Search, start).is_ok());
+    assert!(limiter.check("a", EndpointClass::Search, start).is_ok());
+    let retry_after = limiter
+        .check("a", EndpointClass::Search, start)
+        .unwrap_err();
+    assert_eq!(retry_after, Duration::from_millis(500));
+
+    // other clients and classes have their own budget
+    assert!(limiter.check("b", EndpointClass::Search, start).is_ok());
+    assert!(limiter.check("a", EndpointClass::Read, start).is_ok());
+
+    let later = start + Duration::from_millis(500);
+    assert!(limiter.check("a", EndpointClass::Search, later).is_ok());
+    assert!(limiter.check("a", EndpointClass::Search, later).is_err());
+}
+
+#[tokio::test]
+async fn test_rate_limited_requests_get_429() {
+    let (app, _) = setup_test_app(
+        RateLimits {
+            search: Some("2/min".parse().unwrap()),
+            ..Default::default()
+        },
+        false,
+    )
+    .await;
+
+    for _ in 0..2 {
+        let response = get(&app, "/search?limit=1", None).await;
+        assert_ne!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+    let response = get(&app, "/search?limit=1", None).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()[RETRY_AFTER], "30");
+
+    // health checks and other classes stay available
+    assert_eq!(get(&app, "/health", None).await.status(), StatusCode::OK);
+    assert_ne!(
+        get(&app, "/speakers/unnamed", None).await.status(),
+        StatusCode::TOO_MANY_REQUESTS
+    );
+    // a token not verified by auth shares the budget of the address
+    assert_eq!(
+        get(&app, "/search?limit=1", Some("sp_pipe")).await.status(),
+        StatusCode::TOO_MANY_REQUESTS
+    );
+}
+
+#[tokio::test]
+async fn test_verified_tokens_have_their_own_budget() {
+    let (app, db) = setup_test_app(
+        RateLimits {
+            search: Some("1/min".parse().unwrap()),
+            ..Default::default()
+        },
+        true,
+    )
+    .await;
+    let (pipe, _) = create_token(&db, "pipe", &[ApiScope::ReadSearch])
+        .await
+        .unwrap();
+    let (dashboard, _) = create_token(&db, "dashboard", &[ApiScope::ReadSearch])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        get(&app, "/search?limit=1", Some(&pipe)).await.status(),
+        StatusCode::OK
+    );
+    assert_eq!(
+        get(&app, "/search?limit=1", Some(&pipe)).await.status(),
+        StatusCode::TOO_MANY_REQUESTS
+    );
+    assert_eq!(
+        get(&app, "/search?limit=1", Some(&dashboard))
+            .await
+            .status(),
+        StatusCode::OK
+    );
+}
+
+#[test]
+fn test_rate_limiter_forgets_idle_clients() {
+    let limiter = RateLimiter::new(RateLimits {
+        search: Some("2/s".parse().unwrap()),
+        ..Default::default()
+    });
+    let start = Instant::now();
+    for client in 0..100 {
+        assert!(limiter
+            .check(&client.to_string(), EndpointClass::Search, start)
+            .is_ok());
+    }
+    assert_eq!(limiter.tracked(), 100);
+
+    // refilled by now, dropped on the next sweep
+    let later = start + Duration::from_secs(60);
+    assert!(limiter.check("a", EndpointClass::Search, later).is_ok());
+    assert_eq!(limiter.tracked(), 1);
+}
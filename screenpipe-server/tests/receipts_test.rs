@@ -0,0 +1,88 @@
+use screenpipe_server::receipts::detect_receipt;
+
+#[test]
+fn test_detect_receipt_extracts_fields() {
+    let text = "Blue Bottle Coffee\n\
+                123 Main St\n\
+                2024-03-14 09:12\n\
+                Latte 1 $5.50\n\
+                Croissant 1 $4.25\n\
+                Subtotal $9.75\n\
+                Tax $0.85\n\
+                Total $10.60\n\
+                Thank you for your visit";
+
+    let receipt = detect_receipt(text).expect("should detect a receipt");
+    assert_eq!(receipt.vendor.as_deref(), Some("Blue Bottle Coffee"));
+    assert_eq!(receipt.date.as_deref(), Some("2024-03-14"));
+    assert_eq!(receipt.total, Some(10.60));
+    assert_eq!(receipt.currency.as_deref(), Some("USD"));
+}
+
+#[test]
+fn test_detect_invoice_with_european_amounts() {
+    let text = "INVOICE\n\
+                From: Acme GmbH\n\
+                Invoice # 2024-001\n\
+                Date: 14.03.2024\n\
+                Consulting 10h 1.200,00\n\
+                Travel 150,00\n\
+                VAT 19% 256,50\n\
+                Amount due EUR 1.606,50";
+
+    let receipt = detect_receipt(text).expect("should detect an invoice");
+    assert_eq!(receipt.vendor.as_deref(), Some("Acme GmbH"));
+    assert_eq!(receipt.total, Some(1606.50));
+    assert_eq!(receipt.currency.as_deref(), Some("EUR"));
+}
+
+#[test]
+fn test_regular_text_is_not_a_receipt() {
+    assert!(detect_receipt("fn main() {\n    println!(\"total: {}\", 5.00);\n}").is_none());
+    assert!(detect_receipt("the total cost of the project was discussed in the meeting").is_none());
+}
+
+#[test]
+fn test_keywords_inside_other_words_are_not_counted() {
+    // "syntax", "private", "unpaid" and "qtyField" hold keywords, none is one
+    let editor = "fn private_total(syntax: &Syntax) -> f64 {\n\
+                  let qtyField = syntax.unpaid_items();\n\
+                  let base = 1,200.00\n\
+                  let shipping = 15.00\n\
+                  let total = 1,215.00";
+    assert!(detect_receipt(editor).is_none());
+
+    let prose = "The unpaid interns reviewed the syntax of the private API.\n\
+                 Budget 1,200.00\n\
+                 Spent 800.00\n\
+                 Total 400.00";
+    assert!(detect_receipt(prose).is_none());
+}
+
+#[test]
+fn test_non_ascii_digits_are_not_amounts() {
+    // arabic-indic and fullwidth digits must neither panic nor count as amounts
+    let text = "Corner Shop\n\
+                Receipt\n\
+                Bread ١٢.٣٤\n\
+                Milk ２.５０\n\
+                Subtotal １４.８４\n\
+                Total ١٤.٨٤\n\
+                Thank you for your visit";
+    assert!(detect_receipt(text).is_none());
+
+    let text = "Corner Shop\n\
+                Receipt\n\
+                Bread $3.00\n\
+                Milk $2.50\n\
+                Subtotal $5.50\n\
+                Tip ١٢.٣٤\n\
+                Total $5.50\n\
+                Thank you for your visit";
+    let receipt = detect_receipt(text).expect("should detect a receipt");
+    assert_eq!(receipt.total, Some(5.50));
+
+    // a total in non-ascii digits is no total at all
+    let text = text.replace("Total $5.50", "Total １２.３４");
+    assert!(detect_receipt(&text).is_none());
+}
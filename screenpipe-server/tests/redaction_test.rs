@@ -0,0 +1,133 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use screenpipe_audio::audio_manager::AudioManagerBuilder;
+use screenpipe_db::{DatabaseManager, OcrEngine, TagContentType};
+use screenpipe_server::{PipeManager, SCServer};
+use serde_json::{json, Value};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use tower::ServiceExt;
+
+async fn setup_app() -> (Router, Arc<DatabaseManager>) {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let audio_manager = Arc::new(
+        AudioManagerBuilder::new()
+            .output_path("/tmp/screenpipe".into())
+            .build(db.clone())
+            .await
+            .unwrap(),
+    );
+    let app = SCServer::new(
+        db.clone(),
+        SocketAddr::from(([127, 0, 0, 1], 23975)),
+        PathBuf::from(""),
+        Arc::new(PipeManager::new(PathBuf::from(""))),
+        false,
+        false,
+        false,
+        audio_manager,
+    )
+    .create_router(false)
+    .await;
+    (app, db)
+}
+
+async fn redact(app: &Router, frame_id: i64, body: Value) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method("POST")
+        .uri(format!("/frames/{}/redact", frame_id))
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (
+        status,
+        serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+    )
+}
+
+async fn provenance_status(app: &Router, frame_id: i64) -> StatusCode {
+    let request = Request::builder()
+        .uri(format!("/provenance/vision/{}", frame_id))
+        .body(Body::empty())
+        .unwrap();
+    app.clone().oneshot(request).await.unwrap().status()
+}
+
+#[tokio::test]
+async fn test_redact_frame_endpoint() {
+    let (app, db) = setup_app().await;
+    db.insert_video_chunk("/data/monitor_1_a.mp4", "monitor_1")
+        .await
+        .unwrap();
+    let frame_id = db
+        .insert_frame(
+            "monitor_1",
+            None,
+            None,
+            Some("Bank"),
+            Some("Accounts"),
+            true,
+        )
+        .await
+        .unwrap();
+    db.insert_ocr_text(
+        frame_id,
+        "iban DE89 3704 0044",
+        "",
+        Arc::new(OcrEngine::Tesseract),
+    )
+    .await
+    .unwrap();
+    db.set_text_provenance(TagContentType::Vision, frame_id, "iban DE89 3704 0044", &[])
+        .await
+        .unwrap();
+    assert_eq!(provenance_status(&app, frame_id).await, StatusCode::OK);
+
+    let (status, _) = redact(&app, 999, json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // the chunk is still being recorded
+    let (status, _) = redact(&app, frame_id, json!({"reason": "bank details"})).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let ocr: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM ocr_text WHERE frame_id = ?1")
+        .bind(frame_id)
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+    assert_eq!(ocr, 1);
+
+    db.insert_video_chunk("/data/monitor_1_b.mp4", "monitor_1")
+        .await
+        .unwrap();
+    let (status, redaction) = redact(
+        &app,
+        frame_id,
+        json!({
+            "region": {"left": 10.0, "top": 20.0, "width": 300.0, "height": 40.0},
+            "reason": "bank details"
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(redaction["frame_id"], frame_id);
+    assert_eq!(redaction["region_width"], 300.0);
+    assert_eq!(redaction["reason"], "bank details");
+
+    let ocr: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM ocr_text WHERE frame_id = ?1")
+        .bind(frame_id)
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+    assert_eq!(ocr, 0);
+    assert_eq!(db.get_frame_redactions(frame_id).await.unwrap().len(), 1);
+    // the text as read goes too
+    assert_eq!(
+        provenance_status(&app, frame_id).await,
+        StatusCode::NOT_FOUND
+    );
+}
@@ -0,0 +1,105 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use image::{DynamicImage, ImageFormat, RgbImage};
+use screenpipe_audio::audio_manager::AudioManagerBuilder;
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::{PipeManager, SCServer};
+use serde_json::Value;
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use tower::ServiceExt;
+
+async fn setup_test_app() -> (Router, Arc<DatabaseManager>) {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let audio_manager = Arc::new(
+        AudioManagerBuilder::new()
+            .output_path("/tmp/screenpipe".into())
+            .build(db.clone())
+            .await
+            .unwrap(),
+    );
+    let app = SCServer::new(
+        db.clone(),
+        SocketAddr::from(([127, 0, 0, 1], 23976)),
+        PathBuf::from(""),
+        Arc::new(PipeManager::new(PathBuf::from(""))),
+        false,
+        false,
+        false,
+        audio_manager,
+    )
+    .create_router(false)
+    .await;
+    (app, db)
+}
+
+async fn get(app: &Router, uri: &str) -> (StatusCode, Option<String>, Vec<u8>) {
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get("content-type")
+        .map(|value| value.to_str().unwrap().to_string());
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, content_type, body.to_vec())
+}
+
+// a frame stored the way /vision/screenshot stores one
+async fn insert_snapshot_frame(db: &DatabaseManager, file_path: &str) -> i64 {
+    db.insert_video_chunk(file_path, "snapshot_monitor_1")
+        .await
+        .unwrap();
+    db.insert_frame("snapshot_monitor_1", None, None, Some("Code"), None, true)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_missing_frame_is_not_found() {
+    let (app, _db) = setup_test_app().await;
+    let (status, _, body) = get(&app, "/frames/999").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["error"], "Frame not found");
+}
+
+#[tokio::test]
+async fn test_snapshot_frame_without_its_file_is_not_found() {
+    let (app, db) = setup_test_app().await;
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir
+        .path()
+        .join("snapshot_monitor_1_2024-03-14_09-12-00.000.png");
+    let frame_id = insert_snapshot_frame(&db, &file_path.to_string_lossy()).await;
+
+    let (status, _, body) = get(&app, &format!("/frames/{}", frame_id)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["error"], "media not found");
+}
+
+#[tokio::test]
+async fn test_snapshot_frame_is_served_as_png() {
+    let (app, db) = setup_test_app().await;
+    let dir = tempfile::tempdir().unwrap();
+    let file_path = dir
+        .path()
+        .join("snapshot_monitor_1_2024-03-14_09-12-00.000.png");
+    DynamicImage::ImageRgb8(RgbImage::new(64, 32))
+        .save_with_format(&file_path, ImageFormat::Png)
+        .unwrap();
+    let frame_id = insert_snapshot_frame(&db, &file_path.to_string_lossy()).await;
+
+    let (status, content_type, body) = get(&app, &format!("/frames/{}", frame_id)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type.as_deref(), Some("image/png"));
+    assert_eq!(body, std::fs::read(&file_path).unwrap());
+    let image = image::load_from_memory_with_format(&body, ImageFormat::Png).unwrap();
+    assert_eq!((image.width(), image.height()), (64, 32));
+}
@@ -0,0 +1,134 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use chrono::Utc;
+use screenpipe_audio::audio_manager::AudioManagerBuilder;
+use screenpipe_db::{AudioDevice, DatabaseManager, DeviceType};
+use screenpipe_server::{
+    content_v2::{ContentKind, SearchResponseV2},
+    PipeManager, SCServer,
+};
+use screenpipe_vision::OcrEngine;
+use std::{collections::HashSet, net::SocketAddr, path::PathBuf, sync::Arc};
+use tower::ServiceExt;
+
+async fn setup_test_app() -> (Router, Arc<DatabaseManager>) {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let audio_manager = Arc::new(
+        AudioManagerBuilder::new()
+            .output_path("/tmp/screenpipe".into())
+            .build(db.clone())
+            .await
+            .unwrap(),
+    );
+    let router = SCServer::new(
+        db.clone(),
+        SocketAddr::from(([127, 0, 0, 1], 23952)),
+        PathBuf::from(""),
+        Arc::new(PipeManager::new(PathBuf::from(""))),
+        false,
+        false,
+        false,
+        audio_manager,
+    )
+    .create_router(false)
+    .await;
+    (router, db)
+}
+
+async fn search(app: &Router, uri: &str) -> SearchResponseV2 {
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn test_search_v2_pages_every_kind_once() {
+    let (app, db) = setup_test_app().await;
+    db.insert_video_chunk("video.mp4", "monitor_1")
+        .await
+        .unwrap();
+    // frames captured at the same time, only the tie breaking orders them
+    let timestamp = Utc::now();
+    for n in 0..3 {
+        let frame_id = db
+            .insert_frame(
+                "monitor_1",
+                Some(timestamp),
+                None,
+                Some("Zoom"),
+                Some("standup"),
+                false,
+            )
+            .await
+            .unwrap();
+        db.insert_ocr_text(
+            frame_id,
+            &format!("meeting notes {}", n),
+            "",
+            Arc::new(OcrEngine::Tesseract),
+        )
+        .await
+        .unwrap();
+    }
+    let audio_chunk_id = db.insert_audio_chunk("audio.mp4").await.unwrap();
+    db.insert_audio_transcription(
+        audio_chunk_id,
+        "meeting recap",
+        0,
+        "whisper",
+        &AudioDevice {
+            name: "microphone".to_string(),
+            device_type: DeviceType::Input,
+        },
+        None,
+        Some(1.0),
+        Some(3.5),
+    )
+    .await
+    .unwrap();
+
+    let mut ids = Vec::new();
+    let mut offset = Some(0);
+    while let Some(next) = offset {
+        let page = search(
+            &app,
+            &format!("/v2/search?q=meeting&limit=2&offset={}", next),
+        )
+        .await;
+        assert_eq!(page.pagination.total, 4);
+        assert!(page.data.len() <= 2);
+        ids.extend(page.data.iter().map(|item| item.id.clone()));
+        offset = page.pagination.next_offset;
+    }
+    assert_eq!(ids.len(), 4);
+    assert_eq!(ids.iter().collect::<HashSet<_>>().len(), 4);
+    // the same pages every time
+    let again = search(&app, "/v2/search?q=meeting&limit=4").await;
+    assert_eq!(
+        again.data.iter().map(|item| &item.id).collect::<Vec<_>>(),
+        ids.iter().collect::<Vec<_>>()
+    );
+
+    let audio = again
+        .data
+        .iter()
+        .find(|item| item.kind == ContentKind::Audio)
+        .unwrap();
+    assert_eq!(audio.device_name.as_deref(), Some("microphone"));
+    assert_eq!(audio.media.start_time, Some(1.0));
+    let ocr = again
+        .data
+        .iter()
+        .find(|item| item.kind == ContentKind::Ocr)
+        .unwrap();
+    assert_eq!(ocr.device_name.as_deref(), Some("monitor_1"));
+    assert_eq!(ocr.app_name.as_deref(), Some("Zoom"));
+}
@@ -0,0 +1,166 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use chrono::Utc;
+use screenpipe_audio::audio_manager::AudioManagerBuilder;
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::{
+    shadow::{compare_pages, ShadowCandidate, ShadowConfig, ShadowReport, ShadowRunner},
+    PipeManager, SCServer,
+};
+use screenpipe_vision::OcrEngine;
+use std::{net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
+use tower::ServiceExt;
+
+async fn setup_test_app(shadow_search: Option<ShadowConfig>) -> (Router, Arc<DatabaseManager>) {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let audio_manager = Arc::new(
+        AudioManagerBuilder::new()
+            .output_path("/tmp/screenpipe".into())
+            .build(db.clone())
+            .await
+            .unwrap(),
+    );
+    let router = SCServer::new(
+        db.clone(),
+        SocketAddr::from(([127, 0, 0, 1], 23956)),
+        PathBuf::from(""),
+        Arc::new(PipeManager::new(PathBuf::from(""))),
+        false,
+        false,
+        false,
+        audio_manager,
+    )
+    .with_shadow_search(shadow_search)
+    .create_router(false)
+    .await;
+    (router, db)
+}
+
+async fn get(app: &Router, uri: &str) -> (StatusCode, Vec<u8>) {
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, body.to_vec())
+}
+
+#[test]
+fn test_compare_pages() {
+    let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+    let time = Duration::from_millis(10);
+
+    let same = compare_pages(
+        "q",
+        &ids(&["ocr:1", "ocr:2"]),
+        Ok(ids(&["ocr:1", "ocr:2"])),
+        time,
+        time,
+    );
+    assert!(same.is_identical());
+    assert_eq!(same.overlap, 1.0);
+
+    let reordered = compare_pages(
+        "q",
+        &ids(&["ocr:1", "ocr:2", "ocr:3"]),
+        Ok(ids(&["ocr:1", "ocr:3", "ocr:4"])),
+        time,
+        time,
+    );
+    assert!(!reordered.is_identical());
+    assert_eq!(reordered.first_difference, Some(1));
+    assert_eq!(reordered.overlap, 0.5);
+    assert_eq!(reordered.missing, ids(&["ocr:2"]));
+    assert_eq!(reordered.extra, ids(&["ocr:4"]));
+
+    let shorter = compare_pages(
+        "q",
+        &ids(&["ocr:1", "ocr:2"]),
+        Ok(ids(&["ocr:1"])),
+        time,
+        time,
+    );
+    assert_eq!(shorter.first_difference, Some(1));
+
+    let failed = compare_pages("q", &ids(&["ocr:1"]), Err("boom".to_string()), time, time);
+    assert!(!failed.is_identical());
+    assert_eq!(failed.error.as_deref(), Some("boom"));
+}
+
+#[test]
+fn test_shadow_sampling() {
+    let shadow = ShadowRunner::new(ShadowConfig {
+        candidate: ShadowCandidate::V2,
+        sample_rate: 0.25,
+    });
+    let sampled: Vec<bool> = (0..8).map(|_| shadow.sample()).collect();
+    assert_eq!(sampled.iter().filter(|sampled| **sampled).count(), 2);
+    assert_eq!(
+        sampled,
+        vec![false, false, false, true, false, false, false, true]
+    );
+}
+
+#[tokio::test]
+async fn test_shadow_search_report() {
+    let (app, _) = setup_test_app(None).await;
+    let (status, _) = get(&app, "/shadow/report").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (app, db) = setup_test_app(Some(ShadowConfig {
+        candidate: ShadowCandidate::V2,
+        sample_rate: 1.0,
+    }))
+    .await;
+    db.insert_video_chunk("video.mp4", "monitor_1")
+        .await
+        .unwrap();
+    for minutes_ago in [3, 2, 1] {
+        let frame_id = db
+            .insert_frame(
+                "monitor_1",
+                Some(Utc::now() - chrono::Duration::minutes(minutes_ago)),
+                None,
+                Some("Notes"),
+                Some("todo"),
+                false,
+            )
+            .await
+            .unwrap();
+        db.insert_ocr_text(
+            frame_id,
+            "groceries and errands",
+            "",
+            Arc::new(OcrEngine::Tesseract),
+        )
+        .await
+        .unwrap();
+    }
+
+    let (status, _) = get(&app, "/search?q=groceries&content_type=ocr").await;
+    assert_eq!(status, StatusCode::OK);
+
+    // the candidate runs in the background
+    let mut report = None;
+    for _ in 0..50 {
+        let (status, body) = get(&app, "/shadow/report").await;
+        assert_eq!(status, StatusCode::OK);
+        let current: ShadowReport = serde_json::from_slice(&body).unwrap();
+        if current.sampled == 1 {
+            report = Some(current);
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let report = report.expect("shadow search never ran");
+    assert_eq!(report.candidate, ShadowCandidate::V2);
+    assert_eq!(report.identical, 1);
+    assert_eq!(report.errors, 0);
+    assert_eq!(report.recent[0].query, "groceries");
+    assert_eq!(report.recent[0].primary_count, 3);
+}
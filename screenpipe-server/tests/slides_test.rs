@@ -0,0 +1,57 @@
+use screenpipe_server::slides::{compare_slides, is_presentation_frame, SlideMatch};
+
+const SLIDE: &str = "Q3 Roadmap\nShip offline sync\nRedesign onboarding\nHire two engineers";
+
+#[test]
+fn test_detects_presentation_frames() {
+    assert!(is_presentation_frame(
+        "Microsoft PowerPoint",
+        "PowerPoint Slide Show - roadmap.pptx",
+        SLIDE
+    ));
+    assert!(is_presentation_frame(
+        "Google Chrome",
+        "Q3 Roadmap - Google Slides",
+        SLIDE
+    ));
+
+    // editing a deck or reading a long document is not a presentation
+    assert!(!is_presentation_frame(
+        "Microsoft PowerPoint",
+        "roadmap.pptx",
+        SLIDE
+    ));
+    let document = "lorem ipsum dolor sit amet\n".repeat(60);
+    assert!(!is_presentation_frame(
+        "Microsoft PowerPoint",
+        "PowerPoint Slide Show - roadmap.pptx",
+        &document
+    ));
+    assert!(!is_presentation_frame("Finder", "slideshow", SLIDE));
+}
+
+#[test]
+fn test_compare_slides() {
+    // OCR jitter on the same slide
+    assert_eq!(
+        compare_slides(
+            SLIDE,
+            "Q3 Roadmap\nShip offline sync\nRedesign onboarding\nHire two engineer"
+        ),
+        SlideMatch::Same
+    );
+
+    // build animation revealing more bullets
+    assert_eq!(
+        compare_slides(
+            "Q3 Roadmap\nShip offline sync",
+            "Q3 Roadmap\nShip offline sync\nRedesign onboarding\nHire two engineers"
+        ),
+        SlideMatch::Extends
+    );
+
+    assert_eq!(
+        compare_slides(SLIDE, "Thank you\nQuestions?"),
+        SlideMatch::Different
+    );
+}
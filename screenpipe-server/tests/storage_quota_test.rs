@@ -0,0 +1,107 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use chrono::{Duration, Utc};
+use screenpipe_audio::audio_manager::AudioManagerBuilder;
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::storage_quota::{EvictionPolicy, StorageQuota};
+use screenpipe_server::{PipeManager, SCServer};
+use serde_json::Value;
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use tower::ServiceExt;
+
+async fn setup_test_app() -> (Router, Arc<DatabaseManager>) {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let audio_manager = Arc::new(
+        AudioManagerBuilder::new()
+            .output_path("/tmp/screenpipe".into())
+            .build(db.clone())
+            .await
+            .unwrap(),
+    );
+    let app = SCServer::new(
+        db.clone(),
+        SocketAddr::from(([127, 0, 0, 1], 23972)),
+        PathBuf::from(""),
+        Arc::new(PipeManager::new(PathBuf::from(""))),
+        false,
+        false,
+        false,
+        audio_manager,
+    )
+    .create_router(false)
+    .await;
+    (app, db)
+}
+
+async fn get(app: &Router, uri: &str) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn test_storage_quota_evicts_oldest_media() {
+    let (app, db) = setup_test_app().await;
+    let dir = tempfile::tempdir().unwrap();
+    let mut frames = Vec::new();
+    for (name, days_ago) in [("old.mp4", 3), ("older.mp4", 2), ("current.mp4", 1)] {
+        let path = dir.path().join(name);
+        std::fs::write(&path, vec![0u8; 1000]).unwrap();
+        db.insert_video_chunk(&path.to_string_lossy(), "monitor_1")
+            .await
+            .unwrap();
+        let frame_id = db
+            .insert_frame(
+                "monitor_1",
+                Some(Utc::now() - Duration::days(days_ago)),
+                None,
+                Some("Code"),
+                None,
+                true,
+            )
+            .await
+            .unwrap();
+        frames.push(frame_id);
+    }
+
+    let quota = StorageQuota::new(
+        db.clone(),
+        dir.path().to_path_buf(),
+        2500,
+        EvictionPolicy::Oldest,
+    );
+    assert_eq!(quota.enforce().await.unwrap(), (1, 1000));
+    assert!(!dir.path().join("old.mp4").exists());
+    assert!(dir.path().join("older.mp4").exists());
+    let status = quota.status();
+    assert_eq!(status.used_bytes, Some(2000));
+    assert_eq!((status.evicted_chunks, status.evicted_bytes), (1, 1000));
+    assert_eq!(status.last_error, None);
+
+    let (status, body) = get(&app, &format!("/frames/{}", frames[0])).await;
+    assert_eq!(status, StatusCode::GONE);
+    assert_eq!(body["error"], "media purged");
+    assert_eq!(body["reason"], "oldest");
+
+    // under budget, nothing else goes
+    assert_eq!(quota.enforce().await.unwrap(), (0, 0));
+
+    // the chunk a monitor still writes is never evicted
+    let quota = StorageQuota::new(
+        db.clone(),
+        dir.path().to_path_buf(),
+        0,
+        EvictionPolicy::Oldest,
+    );
+    assert_eq!(quota.enforce().await.unwrap(), (1, 1000));
+    assert!(dir.path().join("current.mp4").exists());
+    assert!(quota.status().last_error.is_some());
+}
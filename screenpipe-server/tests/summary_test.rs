@@ -0,0 +1,178 @@
+use chrono::{Duration, Local, NaiveDate};
+use screenpipe_db::{AudioDevice, DatabaseManager, DeviceType, WindowFocusTime};
+use screenpipe_server::digest::AppTime;
+use screenpipe_server::journal::local_day;
+use screenpipe_server::summary::{
+    focused_apps, key_terms, parse_summary_reply, summarize, SummaryPeriod,
+};
+use screenpipe_vision::OcrEngine;
+use std::sync::Arc;
+
+#[test]
+fn test_summary_period_days() {
+    // a thursday
+    let date = NaiveDate::from_ymd_opt(2025, 3, 13).unwrap();
+    assert_eq!(SummaryPeriod::Day.days(date), (date, date));
+    assert_eq!(
+        SummaryPeriod::Week.days(date),
+        (
+            NaiveDate::from_ymd_opt(2025, 3, 10).unwrap(),
+            NaiveDate::from_ymd_opt(2025, 3, 16).unwrap()
+        )
+    );
+}
+
+#[test]
+fn test_key_terms_and_focused_apps() {
+    let texts = [
+        "Quarterly budget review with finance",
+        "the budget for the offsite, would finance agree?",
+        "Budget spreadsheet",
+        "would you like some coffee",
+    ];
+    // in two texts at least, common words left out
+    assert_eq!(key_terms(&texts, 5), vec!["budget", "finance"]);
+
+    let window = |app: &str, focused_secs: f64| WindowFocusTime {
+        app_name: app.to_string(),
+        window_name: String::new(),
+        sessions: 1,
+        focused_secs,
+    };
+    let focus = [
+        window("Code", 1800.0),
+        window("Slack", 600.0),
+        window("Code", 1200.0),
+        window("Finder", 10.0),
+    ];
+    assert_eq!(
+        focused_apps(&focus, 5),
+        vec![
+            AppTime {
+                app_name: "Code".to_string(),
+                minutes: 50,
+            },
+            AppTime {
+                app_name: "Slack".to_string(),
+                minutes: 10,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_parse_summary_reply() {
+    let reply = "Sure!\n```json\n{\"overview\": \"Worked on the budget.\", \"key_topics\": [\"budget\", \" \"]}\n```";
+    assert_eq!(
+        parse_summary_reply(reply),
+        Some((
+            "Worked on the budget.".to_string(),
+            vec!["budget".to_string()]
+        ))
+    );
+    assert_eq!(parse_summary_reply("{\"key_topics\": []}"), None);
+    assert_eq!(parse_summary_reply("no idea"), None);
+}
+
+#[tokio::test]
+async fn test_summarize_keeps_past_periods() {
+    let db = DatabaseManager::new("sqlite::memory:").await.unwrap();
+    let date = Local::now().date_naive() - Duration::days(1);
+    let (midnight, _) = local_day(date);
+    let at = |minutes: i64| midnight + Duration::hours(10) + Duration::minutes(minutes);
+
+    db.insert_video_chunk("video.mp4", "monitor_1")
+        .await
+        .unwrap();
+    for minutes in [0, 4, 8] {
+        let frame_id = db
+            .insert_frame(
+                "monitor_1",
+                Some(at(minutes)),
+                None,
+                Some("Code"),
+                None,
+                true,
+            )
+            .await
+            .unwrap();
+        db.insert_ocr_text(
+            frame_id,
+            "budget forecast for the quarter",
+            "",
+            Arc::new(OcrEngine::Tesseract),
+        )
+        .await
+        .unwrap();
+    }
+    let audio_chunk_id = db.insert_audio_chunk("audio.mp4").await.unwrap();
+    db.insert_audio_transcription(
+        audio_chunk_id,
+        "let's go over the budget forecast",
+        0,
+        "",
+        &AudioDevice {
+            name: "mic".to_string(),
+            device_type: DeviceType::Input,
+        },
+        None,
+        None,
+        None,
+    )
+    .await
+    .unwrap();
+    sqlx::query("UPDATE audio_transcriptions SET timestamp = ?1")
+        .bind(at(2))
+        .execute(&db.pool)
+        .await
+        .unwrap();
+
+    let summary = summarize(&db, None, SummaryPeriod::Day, date, false)
+        .await
+        .unwrap();
+    assert_eq!(summary.generator, "template");
+    assert_eq!(summary.overview, None);
+    assert_eq!(summary.top_apps.len(), 1);
+    assert_eq!(summary.top_apps[0].minutes, 8);
+    assert_eq!(summary.key_topics, vec!["budget", "forecast"]);
+    assert_eq!(summary.excerpts.len(), 2);
+    assert!(summary.excerpts[0].frame_id.is_some());
+    assert_eq!(summary.excerpts[1].source, "mic");
+    assert!(db.get_summary("day", date).await.unwrap().is_some());
+
+    // the day is over, what comes in late shows once refreshed
+    for minutes in [60, 70, 80] {
+        db.insert_frame(
+            "monitor_1",
+            Some(at(minutes)),
+            None,
+            Some("Slack"),
+            None,
+            true,
+        )
+        .await
+        .unwrap();
+    }
+    let kept = summarize(&db, None, SummaryPeriod::Day, date, false)
+        .await
+        .unwrap();
+    assert_eq!(kept, summary);
+    let refreshed = summarize(&db, None, SummaryPeriod::Day, date, true)
+        .await
+        .unwrap();
+    assert_eq!(refreshed.top_apps[0].app_name, "Slack");
+
+    // the week is still going on when it holds today
+    let week = summarize(
+        &db,
+        None,
+        SummaryPeriod::Week,
+        Local::now().date_naive(),
+        false,
+    )
+    .await
+    .unwrap();
+    let (monday, _) = SummaryPeriod::Week.days(Local::now().date_naive());
+    assert_eq!(week.start_date, monday);
+    assert!(db.get_summary("week", monday).await.unwrap().is_none());
+}
@@ -0,0 +1,153 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    routing::post,
+    Json, Router,
+};
+use screenpipe_audio::audio_manager::AudioManagerBuilder;
+use screenpipe_db::{DatabaseManager, TagContentType};
+use screenpipe_server::{
+    tag_suggestions::{parse_suggestions, suggestion_prompt, TagSuggester},
+    PipeManager, SCServer,
+};
+use screenpipe_vision::OcrEngine;
+use serde_json::{json, Value};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use tower::ServiceExt;
+
+async fn setup_test_app() -> (Router, Arc<DatabaseManager>) {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let audio_manager = Arc::new(
+        AudioManagerBuilder::new()
+            .output_path("/tmp/screenpipe".into())
+            .build(db.clone())
+            .await
+            .unwrap(),
+    );
+    let router = SCServer::new(
+        db.clone(),
+        SocketAddr::from(([127, 0, 0, 1], 23965)),
+        PathBuf::from(""),
+        Arc::new(PipeManager::new(PathBuf::from(""))),
+        false,
+        false,
+        false,
+        audio_manager,
+    )
+    .create_router(false)
+    .await;
+    (router, db)
+}
+
+#[test]
+fn test_parse_suggestions() {
+    let taxonomy = vec!["meeting".to_string(), "project/Alpha".to_string()];
+    assert_eq!(
+        parse_suggestions(
+            "Sure! [\"Meeting\", \"project/alpha\", \"lunch\"]",
+            &taxonomy
+        ),
+        vec!["meeting", "project/Alpha"]
+    );
+    assert_eq!(
+        parse_suggestions("- meeting\n- travel\n- meeting", &taxonomy),
+        vec!["meeting"]
+    );
+    assert!(parse_suggestions("[]", &taxonomy).is_empty());
+
+    let prompt = suggestion_prompt(&taxonomy, &"x".repeat(10_000));
+    assert!(prompt.contains(r#"["meeting","project/Alpha"]"#));
+    assert!(prompt.len() < 5_000);
+}
+
+#[tokio::test]
+async fn test_tag_suggestions() {
+    let llm = Router::new().route(
+        "/v1/chat/completions",
+        post(|Json(body): Json<Value>| async move {
+            let prompt = body["messages"][1]["content"].as_str().unwrap();
+            let reply = if prompt.contains("standup") {
+                r#"["Meeting", "standup"]"#
+            } else {
+                "[]"
+            };
+            Json(json!({"choices": [{"message": {"role": "assistant", "content": reply}}]}))
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let llm_url = format!(
+        "http://{}/v1/chat/completions",
+        listener.local_addr().unwrap()
+    );
+    tokio::spawn(async move { axum::serve(listener, llm).await });
+
+    let (app, db) = setup_test_app().await;
+    db.insert_video_chunk("video.mp4", "monitor_1")
+        .await
+        .unwrap();
+    let mut frames = Vec::new();
+    for text in ["daily standup", "weather today", "budget review"] {
+        let frame_id = db
+            .insert_frame("monitor_1", None, None, None, None, true)
+            .await
+            .unwrap();
+        db.insert_ocr_text(frame_id, text, "", Arc::new(OcrEngine::Tesseract))
+            .await
+            .unwrap();
+        frames.push(frame_id);
+    }
+    db.add_tags(
+        frames[2],
+        TagContentType::Vision,
+        vec!["meeting".to_string(), "finance".to_string()],
+    )
+    .await
+    .unwrap();
+
+    let suggester = TagSuggester::new(db.clone(), llm_url, "test".to_string(), None, 10);
+    // only tags of the taxonomy are suggested, untagged frames are sampled once
+    assert_eq!(suggester.run().await.unwrap(), 1);
+    assert_eq!(suggester.run().await.unwrap(), 0);
+    assert!(db
+        .get_tags(frames[0], TagContentType::Vision)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/tag-suggestions?status=pending")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let suggestions: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(suggestions.as_array().unwrap().len(), 1);
+    assert_eq!(suggestions[0]["content_id"], frames[0]);
+    assert_eq!(suggestions[0]["tag"], "meeting");
+    assert_eq!(suggestions[0]["model"], "test");
+
+    let accept = |id: i64| {
+        Request::builder()
+            .method("POST")
+            .uri(format!("/tag-suggestions/{}/accept", id))
+            .body(Body::empty())
+            .unwrap()
+    };
+    let id = suggestions[0]["id"].as_i64().unwrap();
+    let response = app.clone().oneshot(accept(id)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        db.get_tags(frames[0], TagContentType::Vision)
+            .await
+            .unwrap(),
+        vec!["meeting"]
+    );
+    // decided already
+    let response = app.clone().oneshot(accept(id)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
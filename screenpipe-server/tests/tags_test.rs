@@ -148,7 +148,7 @@ async fn test_add_tags_and_search() {
                 assert!(audio.tags.contains(&"test".to_string()));
                 assert!(audio.tags.contains(&"audio".to_string()));
             }
-            ContentItem::UI(_) => {
+            ContentItem::UI(_) | ContentItem::Clipboard(_) => {
                 unreachable!()
             }
         }
@@ -351,6 +351,9 @@ async fn test_search_by_multiple_tags() {
             ContentItem::UI(_) => {
                 panic!("UI content should not be included in the results");
             }
+            ContentItem::Clipboard(_) => {
+                panic!("clipboard content should not be included in the results");
+            }
         }
     }
 }
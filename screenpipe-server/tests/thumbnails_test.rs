@@ -0,0 +1,92 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use image::{DynamicImage, RgbImage};
+use screenpipe_audio::audio_manager::AudioManagerBuilder;
+use screenpipe_db::DatabaseManager;
+use screenpipe_server::thumbnails::encode_thumbnail;
+use screenpipe_server::{PipeManager, SCServer};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use tower::ServiceExt;
+
+async fn setup_test_app() -> (Router, Arc<DatabaseManager>) {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let audio_manager = Arc::new(
+        AudioManagerBuilder::new()
+            .output_path("/tmp/screenpipe".into())
+            .build(db.clone())
+            .await
+            .unwrap(),
+    );
+    let app = SCServer::new(
+        db.clone(),
+        SocketAddr::from(([127, 0, 0, 1], 23973)),
+        PathBuf::from(""),
+        Arc::new(PipeManager::new(PathBuf::from(""))),
+        false,
+        false,
+        false,
+        audio_manager,
+    )
+    .create_router(false)
+    .await;
+    (app, db)
+}
+
+async fn get(app: &Router, uri: &str) -> (StatusCode, Option<String>, Vec<u8>) {
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get("content-type")
+        .map(|value| value.to_str().unwrap().to_string());
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, content_type, body.to_vec())
+}
+
+#[test]
+fn test_encode_thumbnail_keeps_aspect_ratio() {
+    let image = DynamicImage::ImageRgb8(RgbImage::new(1920, 1080));
+    let jpeg = encode_thumbnail(&image, 160).unwrap();
+    let thumbnail = image::load_from_memory(&jpeg).unwrap();
+    assert_eq!((thumbnail.width(), thumbnail.height()), (160, 90));
+
+    // never scaled up
+    let image = DynamicImage::ImageRgb8(RgbImage::new(100, 50));
+    let thumbnail = image::load_from_memory(&encode_thumbnail(&image, 160).unwrap()).unwrap();
+    assert_eq!(thumbnail.width(), 100);
+}
+
+#[tokio::test]
+async fn test_frame_thumbnail_endpoint() {
+    let (app, db) = setup_test_app().await;
+    db.insert_video_chunk("/data/chunk.mp4", "monitor_1")
+        .await
+        .unwrap();
+    let frame_id = db
+        .insert_frame("monitor_1", None, None, Some("Code"), None, true)
+        .await
+        .unwrap();
+    let jpeg = encode_thumbnail(&DynamicImage::ImageRgb8(RgbImage::new(320, 180)), 160).unwrap();
+    db.insert_frame_thumbnail(frame_id, 160, &jpeg)
+        .await
+        .unwrap();
+
+    // cached, the video chunk isn't read
+    let (status, content_type, body) = get(&app, &format!("/frames/{}/thumbnail", frame_id)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type.as_deref(), Some("image/jpeg"));
+    assert_eq!(body, jpeg);
+
+    let (status, _, _) = get(&app, &format!("/frames/{}/thumbnail?width=4000", frame_id)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _, _) = get(&app, "/frames/999/thumbnail").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,137 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use screenpipe_db::{document_terms, DatabaseManager, TopicTerm};
+use screenpipe_server::summary::SummaryPeriod;
+use screenpipe_server::topics::{normalize_term, topic_trends};
+use screenpipe_vision::OcrEngine;
+use std::sync::Arc;
+
+#[test]
+fn test_document_terms() {
+    let mut terms: Vec<String> =
+        document_terms("The Kubernetes migration, with 3 nodes: kubernetes migration!")
+            .into_iter()
+            .collect();
+    terms.sort();
+    assert_eq!(
+        terms,
+        vec!["kubernetes", "kubernetes migration", "migration", "nodes"]
+    );
+    assert_eq!(
+        normalize_term("  Kubernetes   Migration "),
+        "kubernetes migration"
+    );
+}
+
+#[test]
+fn test_topic_trends_by_week() {
+    let date = |day: u32| NaiveDate::from_ymd_opt(2025, 3, day).unwrap();
+    let term = |day: u32, share: f64| TopicTerm {
+        day: date(day),
+        term: "kubernetes".to_string(),
+        documents: 10,
+        share,
+        score: share * 2.0,
+    };
+    // two weeks extracted, the term only stood out in the second
+    let days: Vec<NaiveDate> = (10..=23).map(date).collect();
+    let trends = topic_trends(
+        &["kubernetes".to_string(), "hiring".to_string()],
+        &[term(17, 0.5), term(18, 0.2)],
+        &days,
+        SummaryPeriod::Week,
+    );
+    assert_eq!(trends.len(), 2);
+    let points = &trends[0].points;
+    assert_eq!(points.len(), 2);
+    assert_eq!(points[0].start_date, date(10));
+    assert_eq!(points[0].documents, 0);
+    assert_eq!(points[1].start_date, date(17));
+    assert_eq!(points[1].documents, 20);
+    assert!((points[1].share - 0.1).abs() < 1e-9);
+    assert!((points[1].score - 1.4).abs() < 1e-9);
+    assert!(trends[1].points.iter().all(|p| p.documents == 0));
+}
+
+#[tokio::test]
+async fn test_extract_day_terms() {
+    let db = DatabaseManager::new("sqlite::memory:").await.unwrap();
+    let at = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+    let day = |d: u32| NaiveDate::from_ymd_opt(2025, 3, d).unwrap();
+    let bounds = |d: u32| {
+        let start = day(d).and_hms_opt(0, 0, 0).unwrap().and_utc();
+        (start, start + chrono::Duration::days(1))
+    };
+
+    db.insert_video_chunk("video.mp4", "monitor_1")
+        .await
+        .unwrap();
+    let texts = [
+        ("2025-03-10T09:00:00Z", "standup notes: hiring plan"),
+        ("2025-03-10T10:00:00Z", "hiring pipeline review"),
+        ("2025-03-10T11:00:00Z", "hiring budget"),
+        (
+            "2025-03-11T09:00:00Z",
+            "kubernetes migration runbook, hiring",
+        ),
+        (
+            "2025-03-11T09:00:01Z",
+            "kubernetes migration runbook, hiring",
+        ),
+        (
+            "2025-03-11T10:00:00Z",
+            "kubernetes migration checklist, hiring",
+        ),
+        (
+            "2025-03-11T11:00:00Z",
+            "slack: kubernetes migration is done? hiring",
+        ),
+    ];
+    for (timestamp, text) in texts {
+        let frame_id = db
+            .insert_frame("monitor_1", Some(at(timestamp)), None, None, None, true)
+            .await
+            .unwrap();
+        db.insert_ocr_text(frame_id, text, "", Arc::new(OcrEngine::Tesseract))
+            .await
+            .unwrap();
+    }
+
+    let (start, end) = bounds(10);
+    let first = db.extract_day_terms(day(10), start, end, 10).await.unwrap();
+    assert_eq!(first.len(), 1);
+    assert_eq!(first[0].term, "hiring");
+    assert_eq!(first[0].share, 1.0);
+
+    let (start, end) = bounds(11);
+    let second = db.extract_day_terms(day(11), start, end, 10).await.unwrap();
+    // the same screen read twice counts once
+    let terms: Vec<(&str, i64)> = second
+        .iter()
+        .map(|t| (t.term.as_str(), t.documents))
+        .collect();
+    assert_eq!(
+        terms,
+        vec![
+            ("kubernetes", 3),
+            ("kubernetes migration", 3),
+            ("migration", 3),
+            ("hiring", 3),
+        ]
+    );
+    // found the day before, it weighs less
+    assert!(second[3].score < second[0].score);
+
+    let trend = db
+        .get_topic_trend(&["kubernetes migration".to_string()], day(1), day(31))
+        .await
+        .unwrap();
+    assert_eq!(trend.len(), 1);
+    assert_eq!(trend[0].day, day(11));
+    assert_eq!(
+        db.get_topic_days(day(1), day(31)).await.unwrap(),
+        vec![day(10), day(11)]
+    );
+    let top = db.get_top_topics(day(1), day(31), 2).await.unwrap();
+    assert_eq!(top[0].term, "hiring");
+    assert_eq!(top[0].days, 2);
+}
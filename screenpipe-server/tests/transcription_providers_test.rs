@@ -0,0 +1,92 @@
+use screenpipe_audio::transcription::transcription_provider;
+use screenpipe_server::transcription_providers::{
+    register_providers, DeviceTranscriptionProviderArg, RemoteTranscriptionProviderArg,
+};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+#[test]
+fn test_transcription_provider_args() {
+    assert_eq!(
+        "whisper-cpp=http://gpu.local:8080/inference"
+            .parse::<RemoteTranscriptionProviderArg>()
+            .unwrap(),
+        RemoteTranscriptionProviderArg {
+            name: "whisper-cpp".to_string(),
+            url: "http://gpu.local:8080/inference".to_string(),
+        }
+    );
+    assert_eq!(
+        "Display = 1 (output)=deepgram"
+            .parse::<DeviceTranscriptionProviderArg>()
+            .unwrap(),
+        DeviceTranscriptionProviderArg {
+            device: "Display = 1 (output)".to_string(),
+            provider: "deepgram".to_string(),
+        }
+    );
+    for invalid in ["whisper", "=http://localhost", "x=ftp://host"] {
+        assert!(
+            invalid.parse::<RemoteTranscriptionProviderArg>().is_err(),
+            "{}",
+            invalid
+        );
+    }
+    assert!("mic=".parse::<DeviceTranscriptionProviderArg>().is_err());
+}
+
+#[tokio::test]
+async fn test_register_transcription_providers() {
+    // answers any request like a whisper.cpp server
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/inference", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        // the whole request, closing before would reset the connection
+        let mut request = Vec::new();
+        let mut buffer = [0; 4096];
+        loop {
+            let read = socket.read(&mut buffer).await.unwrap();
+            if read == 0 {
+                break;
+            }
+            request.extend_from_slice(&buffer[..read]);
+            let text = String::from_utf8_lossy(&request).to_lowercase();
+            let Some(end) = text.find("\r\n\r\n") else {
+                continue;
+            };
+            let length = text
+                .lines()
+                .find_map(|line| line.strip_prefix("content-length:"))
+                .map_or(0, |length| length.trim().parse::<usize>().unwrap());
+            if request.len() >= end + 4 + length {
+                break;
+            }
+        }
+        let body = r#"{"text":" hello world\n"}"#;
+        let response = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        socket.write_all(response.as_bytes()).await.unwrap();
+    });
+
+    let remote = vec![format!("whisper-cpp={}", url).parse().unwrap()];
+    let devices = vec!["mic (input)=whisper-cpp".parse().unwrap()];
+    let selected = register_providers(&remote, &devices, None).unwrap();
+    assert_eq!(selected["mic (input)"], "whisper-cpp");
+    assert!(transcription_provider("deepgram").is_some_and(|p| p.supports_streaming()));
+
+    let provider = transcription_provider("whisper-cpp").unwrap();
+    assert!(!provider.supports_streaming());
+    let text = provider
+        .transcribe(&[0.0; 16000], 16000, "mic (input)", &[])
+        .await
+        .unwrap();
+    assert_eq!(text, "hello world");
+
+    let unknown = vec!["mic (input)=parakeet".parse().unwrap()];
+    let error = register_providers(&[], &unknown, None).unwrap_err();
+    assert!(error.to_string().contains("whisper-cpp"));
+}
@@ -0,0 +1,127 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use chrono::{Duration, Utc};
+use screenpipe_audio::audio_manager::AudioManagerBuilder;
+use screenpipe_db::{DatabaseManager, UsageRecord};
+use screenpipe_server::{
+    usage_csv::{csv_header, csv_row, parse_columns, UsageColumn},
+    PipeManager, SCServer,
+};
+use screenpipe_vision::OcrEngine;
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use tower::ServiceExt;
+
+async fn setup_test_app() -> (Router, Arc<DatabaseManager>) {
+    let db = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+    let audio_manager = Arc::new(
+        AudioManagerBuilder::new()
+            .output_path("/tmp/screenpipe".into())
+            .build(db.clone())
+            .await
+            .unwrap(),
+    );
+    let router = SCServer::new(
+        db.clone(),
+        SocketAddr::from(([127, 0, 0, 1], 23960)),
+        PathBuf::from(""),
+        Arc::new(PipeManager::new(PathBuf::from(""))),
+        false,
+        false,
+        false,
+        audio_manager,
+    )
+    .create_router(false)
+    .await;
+    (router, db)
+}
+
+async fn get(app: &Router, uri: &str) -> (StatusCode, String) {
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[test]
+fn test_csv_rows() {
+    assert_eq!(parse_columns(None).unwrap(), UsageColumn::ALL.to_vec());
+    let columns = parse_columns(Some("timestamp, window_name,text_length")).unwrap();
+    assert_eq!(
+        columns,
+        vec![
+            UsageColumn::Timestamp,
+            UsageColumn::WindowName,
+            UsageColumn::TextLength
+        ]
+    );
+    assert!(parse_columns(Some("timestamp,password")).is_err());
+
+    let record = UsageRecord {
+        kind: "frame".to_string(),
+        id: 1,
+        timestamp: "2025-03-14T10:00:00Z".parse().unwrap(),
+        device_name: "monitor_1".to_string(),
+        app_name: Some("Code".to_string()),
+        window_name: Some("\"main.rs\", modified".to_string()),
+        browser_url: None,
+        duration_secs: Some(2.0),
+        speaker_name: None,
+        text_length: None,
+    };
+    assert_eq!(
+        csv_header(&columns),
+        "timestamp,window_name,text_length\r\n"
+    );
+    assert_eq!(
+        csv_row(&record, &columns),
+        "2025-03-14T10:00:00+00:00,\"\"\"main.rs\"\", modified\",\r\n"
+    );
+}
+
+#[tokio::test]
+async fn test_usage_export() {
+    let (app, db) = setup_test_app().await;
+    let (status, body) = get(&app, "/usage/export?columns=app_name,kind").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, "app_name,kind\r\n");
+
+    db.insert_video_chunk("video.mp4", "monitor_1")
+        .await
+        .unwrap();
+    // hours apart, read in windows of their own
+    for (hours_ago, app_name) in [(3, "Code"), (1, "Slack")] {
+        let frame_id = db
+            .insert_frame(
+                "monitor_1",
+                Some(Utc::now() - Duration::hours(hours_ago)),
+                None,
+                Some(app_name),
+                Some("main"),
+                false,
+            )
+            .await
+            .unwrap();
+        db.insert_ocr_text(frame_id, "text", "", Arc::new(OcrEngine::Tesseract))
+            .await
+            .unwrap();
+    }
+
+    let (status, body) = get(&app, "/usage/export?columns=app_name,kind,text_length").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body,
+        "app_name,kind,text_length\r\nCode,frame,4\r\nSlack,frame,4\r\n"
+    );
+
+    let (status, _) = get(&app, "/usage/export?columns=password").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = get(&app, "/usage/export?content_type=ui").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,29 @@
+use chrono::Duration;
+use screenpipe_server::video_compaction::{compaction_args, CompactionCodec, CompactionConfig};
+
+fn config(codec: CompactionCodec, scale: f64) -> CompactionConfig {
+    CompactionConfig {
+        after: Duration::days(30),
+        codec,
+        crf: 32,
+        scale,
+    }
+}
+
+#[test]
+fn test_compaction_args() {
+    let args = compaction_args(&config(CompactionCodec::H265, 1.0), "in.mp4", "out.mp4");
+    assert_eq!(args.first().map(String::as_str), Some("-i"));
+    assert_eq!(args.last().map(String::as_str), Some("out.mp4"));
+    // every frame keeps its timestamp
+    assert!(args.windows(2).any(|w| w == ["-vsync", "passthrough"]));
+    assert!(args.windows(2).any(|w| w == ["-vcodec", "libx265"]));
+    assert!(args.windows(2).any(|w| w == ["-crf", "32"]));
+    assert!(!args.iter().any(|arg| arg == "-vf"));
+
+    let args = compaction_args(&config(CompactionCodec::Av1, 2.0), "in.mp4", "out.mp4");
+    assert!(args.windows(2).any(|w| w == ["-vcodec", "libsvtav1"]));
+    assert!(args
+        .windows(2)
+        .any(|w| w == ["-vf", "scale=trunc(iw/2/2)*2:trunc(ih/2/2)*2"]));
+}
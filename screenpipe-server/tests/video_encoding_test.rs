@@ -0,0 +1,67 @@
+use screenpipe_server::{VideoCodec, VideoContainer, VideoEncoder, VideoEncoding};
+
+fn has(args: &[String], pair: [&str; 2]) -> bool {
+    args.windows(2).any(|w| w == pair)
+}
+
+#[test]
+fn test_default_encoding_args() {
+    let args = VideoEncoding::default().ffmpeg_args(1.0, "out.mp4");
+    assert!(has(&args, ["-f", "image2pipe"]));
+    assert!(has(&args, ["-r", "1"]));
+    assert!(has(&args, ["-vcodec", "libx265"]));
+    assert!(has(&args, ["-crf", "23"]));
+    assert!(has(&args, ["-tag:v", "hvc1"]));
+    assert!(has(&args, ["-pix_fmt", "yuv420p"]));
+    assert!(!args.iter().any(|arg| arg == "-g"));
+    assert_eq!(args.last().map(String::as_str), Some("out.mp4"));
+}
+
+#[test]
+fn test_hardware_encoding_args() {
+    let encoding = VideoEncoding {
+        codec: VideoCodec::H264,
+        encoder: VideoEncoder::Nvenc,
+        crf: 28,
+        keyframe_interval: Some(10),
+        ..Default::default()
+    };
+    let args = encoding.ffmpeg_args(0.5, "out.mp4");
+    assert!(has(&args, ["-vcodec", "h264_nvenc"]));
+    assert!(has(&args, ["-cq", "28"]));
+    assert!(has(&args, ["-g", "10"]));
+    assert!(!args.iter().any(|arg| arg == "hvc1"));
+
+    let args = VideoEncoding {
+        encoder: VideoEncoder::Vaapi,
+        container: VideoContainer::Mkv,
+        ..Default::default()
+    }
+    .ffmpeg_args(1.0, "out.mkv");
+    assert_eq!(args.first().map(String::as_str), Some("-vaapi_device"));
+    assert!(has(&args, ["-vcodec", "hevc_vaapi"]));
+    assert!(args
+        .iter()
+        .any(|arg| arg.starts_with("pad=") && arg.ends_with(",format=nv12,hwupload")));
+    // no hvc1 tag in mkv, and frames go to the GPU as nv12
+    assert!(!args.iter().any(|arg| arg == "hvc1" || arg == "-pix_fmt"));
+}
+
+#[test]
+fn test_unsupported_encoder_falls_back_to_cpu() {
+    let encoding = VideoEncoding {
+        codec: VideoCodec::Av1,
+        encoder: VideoEncoder::Videotoolbox,
+        ..Default::default()
+    };
+    assert_eq!(encoding.encoder_name(), None);
+    assert!(has(&encoding.codec_args(), ["-vcodec", "libsvtav1"]));
+}
+
+#[test]
+fn test_codec_names() {
+    for codec in [VideoCodec::H264, VideoCodec::H265, VideoCodec::Av1] {
+        assert_eq!(VideoCodec::from_codec_name(codec.codec_name()), Some(codec));
+    }
+    assert_eq!(VideoCodec::from_codec_name("vp9"), None);
+}
@@ -1,7 +1,8 @@
 use anyhow::Result;
 use dirs::{self, home_dir};
 use screenpipe_core::Language;
-use screenpipe_server::video_utils::extract_frames_from_video;
+use screenpipe_db::TextBounds;
+use screenpipe_server::video_utils::{extract_frames_from_video, redaction_filter};
 use screenpipe_vision::capture_screenshot_by_window::CapturedWindow;
 #[cfg(target_os = "macos")]
 use screenpipe_vision::perform_ocr_apple;
@@ -131,6 +132,7 @@ async fn test_extract_frames_and_ocr() -> Result<()> {
         app_name: "test_app".to_string(),
         is_focused: true,
         process_id: 1234,
+        bounds: (0, 0, first_frame.width(), first_frame.height()),
     };
 
     // perform ocr using apple native (macos only)
@@ -177,3 +179,24 @@ async fn test_get_video_metadata() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_redaction_filter_targets_single_frame() {
+    assert_eq!(
+        redaction_filter(12, None),
+        "drawbox=x=0:y=0:w=iw:h=ih:color=black:t=fill:enable='eq(n,12)'"
+    );
+    // regions are widened to whole pixels so no edge of the text survives
+    assert_eq!(
+        redaction_filter(
+            0,
+            Some(TextBounds {
+                left: 10.6,
+                top: 20.2,
+                width: 99.1,
+                height: 14.0,
+            })
+        ),
+        "drawbox=x=10:y=20:w=100:h=14:color=black:t=fill:enable='eq(n,0)'"
+    );
+}
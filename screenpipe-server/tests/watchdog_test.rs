@@ -0,0 +1,102 @@
+use screenpipe_server::watchdog::{Direction, StateChange, Threshold, Watchdog, WatchdogConfig};
+
+fn watchdog(thresholds: &[&str]) -> Watchdog {
+    Watchdog::new(WatchdogConfig {
+        windows: vec!["Terminal".to_string()],
+        keywords: Vec::new(),
+        thresholds: thresholds.iter().map(|t| t.parse().unwrap()).collect(),
+    })
+}
+
+fn keywords(changes: Vec<StateChange>) -> Vec<String> {
+    let mut keywords: Vec<String> = changes
+        .into_iter()
+        .filter_map(|change| match change {
+            StateChange::Keyword { keyword } => Some(keyword),
+            _ => None,
+        })
+        .collect();
+    keywords.sort();
+    keywords
+}
+
+#[test]
+fn test_watchdog_fires_when_keyword_appears() {
+    let mut watchdog = watchdog(&[]);
+
+    // the first look only records what is already on screen
+    assert!(watchdog
+        .observe("Terminal", "cargo test", "error: build failed")
+        .is_empty());
+    assert!(watchdog
+        .observe("Terminal", "cargo test", "running 12 tests")
+        .is_empty());
+    assert_eq!(
+        keywords(watchdog.observe(
+            "Terminal",
+            "cargo test",
+            "test result: FAILED. 11 passed; 1 failed"
+        )),
+        vec!["failed", "passed"]
+    );
+    // still failed, nothing new
+    assert!(watchdog
+        .observe("Terminal", "cargo test", "test result: FAILED")
+        .is_empty());
+    // part of a longer word
+    assert!(watchdog
+        .observe("Terminal", "cargo test", "errors_total FAILEDX")
+        .is_empty());
+    // windows that aren't watched
+    assert!(watchdog
+        .observe("Slack", "general", "deploy failed")
+        .is_empty());
+}
+
+#[test]
+fn test_watchdog_fires_on_threshold_crossing() {
+    let mut watchdog = watchdog(&["loss<0.1", "accuracy >= 0.9"]);
+
+    assert!(watchdog
+        .observe("Terminal", "train.py", "epoch 1 loss: 0.52 accuracy: 0.61")
+        .is_empty());
+    assert!(watchdog
+        .observe("Terminal", "train.py", "epoch 2 loss: 0.21 accuracy: 0.84")
+        .is_empty());
+    assert_eq!(
+        watchdog.observe("Terminal", "train.py", "epoch 3 loss: 0.08 accuracy: 0.93"),
+        vec![
+            StateChange::Threshold {
+                metric: "loss".to_string(),
+                value: 0.08,
+                threshold: 0.1,
+                direction: Direction::Below,
+            },
+            StateChange::Threshold {
+                metric: "accuracy".to_string(),
+                value: 0.93,
+                threshold: 0.9,
+                direction: Direction::Above,
+            },
+        ]
+    );
+    // staying past the threshold doesn't fire again
+    assert!(watchdog
+        .observe("Terminal", "train.py", "epoch 4 loss: 0.07 accuracy: 0.94")
+        .is_empty());
+}
+
+#[test]
+fn test_parse_threshold() {
+    assert_eq!(
+        "val_loss <= 0.25".parse::<Threshold>().unwrap(),
+        Threshold {
+            metric: "val_loss".to_string(),
+            direction: Direction::Below,
+            value: 0.25,
+        }
+    );
+    assert!("loss".parse::<Threshold>().is_err());
+    assert!("<0.1".parse::<Threshold>().is_err());
+    assert!("loss>high".parse::<Threshold>().is_err());
+}
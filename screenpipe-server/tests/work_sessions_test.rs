@@ -0,0 +1,37 @@
+use chrono::{Duration, TimeZone, Utc};
+use screenpipe_db::WorkSession;
+use screenpipe_server::work_sessions::group_by_session;
+
+#[test]
+fn test_group_by_session() {
+    let start = Utc.with_ymd_and_hms(2025, 3, 13, 9, 0, 0).unwrap();
+    let at = |minutes: i64| start + Duration::minutes(minutes);
+    let session = |id: i64, from: i64, to: i64| WorkSession {
+        id,
+        started_at: at(from),
+        ended_at: at(to),
+        dominant_app: Some("Code".to_string()),
+        dominant_window: None,
+        topic: None,
+        apps: vec!["Code".to_string()],
+        frames: 10,
+        transcriptions: 0,
+    };
+    let sessions = [session(1, 0, 30), session(2, 60, 90)];
+
+    // groups in the order of their first result
+    let groups = group_by_session(&[at(80), at(45), at(10), at(60), at(0)], &sessions);
+    let ids: Vec<(Option<i64>, Vec<usize>)> = groups
+        .iter()
+        .map(|group| (group.session.as_ref().map(|s| s.id), group.results.clone()))
+        .collect();
+    assert_eq!(
+        ids,
+        vec![
+            (Some(2), vec![0, 3]),
+            (None, vec![1]),
+            (Some(1), vec![2, 4])
+        ]
+    );
+    assert!(group_by_session(&[], &sessions).is_empty());
+}
@@ -49,6 +49,7 @@ async fn main() {
         window_filters,
         languages.clone(),
         false,
+        None,
     )
     .await;
 
@@ -102,6 +102,7 @@ async fn main() -> Result<()> {
             window_filters,
             vec![],
             false,
+            None,
         )
         .await
     });
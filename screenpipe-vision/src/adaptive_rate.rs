@@ -0,0 +1,69 @@
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// growth of the interval per capture that showed no activity
+const BACKOFF_FACTOR: f64 = 1.5;
+
+static LAST_INPUT: Lazy<Mutex<Option<Instant>>> = Lazy::new(|| Mutex::new(None));
+
+/// Notes keyboard or mouse input, activity the capture loops count even when
+/// the screen barely changes.
+pub fn record_input() {
+    *LAST_INPUT.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+}
+
+/// Whether keyboard or mouse input was noted after `since`.
+pub fn input_since(since: Instant) -> bool {
+    LAST_INPUT
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .map_or(false, |last| last > since)
+}
+
+/// Stretches the capture interval while the screen is idle and snaps back to
+/// the base interval as soon as something changes.
+///
+/// Activity is whatever the caller observes between two captures: a pixel
+/// difference above the skip threshold, keyboard or mouse input or a change
+/// of the focused window.
+#[derive(Debug, Clone)]
+pub struct AdaptiveCaptureRate {
+    max_interval: Duration,
+    current: Option<Duration>,
+    last_focus: Option<(String, String)>,
+}
+
+impl AdaptiveCaptureRate {
+    pub fn new(max_interval: Duration) -> Self {
+        Self {
+            max_interval,
+            current: None,
+            last_focus: None,
+        }
+    }
+
+    /// Records the outcome of a capture and returns how long to wait before
+    /// the next one. `base` is the interval used while the screen is active;
+    /// `focus` is the (app, window) that had focus, if any.
+    pub fn next_interval(
+        &mut self,
+        base: Duration,
+        changed: bool,
+        focus: Option<(String, String)>,
+    ) -> Duration {
+        let focus_changed = focus.is_some() && focus != self.last_focus;
+        if focus.is_some() {
+            self.last_focus = focus;
+        }
+
+        let max_interval = self.max_interval.max(base);
+        let next = match self.current {
+            _ if changed || focus_changed => base,
+            None => base,
+            Some(current) => current.max(base).mul_f64(BACKOFF_FACTOR).min(max_interval),
+        };
+        self.current = Some(next);
+        next
+    }
+}
@@ -17,6 +17,10 @@ impl WindowsUrlDetector {
     }
 
     fn validate_url(url: &str) -> Result<bool, Error> {
+        // local-only mode doesn't visit the page, a url that parses will do
+        if screenpipe_core::egress::local_only() {
+            return Ok(reqwest::Url::parse(url).is_ok());
+        }
         let client = Client::new();
         let response = client.get(url).send();
         match response {
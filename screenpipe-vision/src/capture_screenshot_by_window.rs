@@ -141,6 +141,8 @@ pub struct CapturedWindow {
     pub window_name: String,
     pub process_id: i32,
     pub is_focused: bool,
+    /// Window position and size in screen coordinates (x, y, width, height)
+    pub bounds: (i32, i32, u32, u32),
 }
 
 pub struct WindowFilters {
@@ -189,6 +191,20 @@ impl WindowFilters {
     }
 }
 
+/// App name and title of the focused window, none when no window has the
+/// focus or it can't be read.
+pub fn focused_window() -> Option<(String, String)> {
+    Window::all().ok()?.into_iter().find_map(|window| {
+        if !window.is_focused().unwrap_or(false) {
+            return None;
+        }
+        Some((
+            window.app_name().ok()?.to_string(),
+            window.title().ok()?.to_string(),
+        ))
+    })
+}
+
 pub async fn capture_all_visible_windows(
     monitor: &SafeMonitor,
     window_filters: &WindowFilters,
@@ -240,9 +256,16 @@ pub async fn capture_all_visible_windows(
                 }
             };
 
+            let bounds = (
+                window.x().unwrap_or(0),
+                window.y().unwrap_or(0),
+                window.width().unwrap_or(0),
+                window.height().unwrap_or(0),
+            );
+
             // Capture image immediately while we have access to the window
             match window.capture_image() {
-                Ok(buffer) => Some((app_name, title, is_focused, buffer, process_id)),
+                Ok(buffer) => Some((app_name, title, is_focused, buffer, process_id, bounds)),
                 Err(e) => {
                     error!(
                         "Failed to capture image for window {} ({}): {}",
@@ -259,7 +282,7 @@ pub async fn capture_all_visible_windows(
     }
 
     // Process the captured data
-    for (app_name, window_name, is_focused, buffer, process_id) in windows_data {
+    for (app_name, window_name, is_focused, buffer, process_id, bounds) in windows_data {
         // Convert to DynamicImage
         let image = DynamicImage::ImageRgba8(
             image::ImageBuffer::from_raw(buffer.width(), buffer.height(), buffer.into_raw())
@@ -279,6 +302,7 @@ pub async fn capture_all_visible_windows(
                 window_name,
                 process_id: process_id as i32,
                 is_focused,
+                bounds,
             });
         }
     }
@@ -0,0 +1,74 @@
+use image::{imageops::FilterType, DynamicImage};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::Duration;
+
+/// Capture settings that can be changed per monitor while recording is running.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorCaptureSettings {
+    pub enabled: bool,
+    /// Overrides the global capture fps when set
+    pub fps: Option<f64>,
+    /// 1.0 keeps the native resolution, 2.0 halves width and height
+    pub downscale_factor: f64,
+}
+
+impl Default for MonitorCaptureSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            fps: None,
+            downscale_factor: 1.0,
+        }
+    }
+}
+
+impl From<screenpipe_db::MonitorSettings> for MonitorCaptureSettings {
+    fn from(settings: screenpipe_db::MonitorSettings) -> Self {
+        Self {
+            enabled: settings.enabled,
+            fps: settings.fps,
+            downscale_factor: settings.downscale_factor,
+        }
+    }
+}
+
+impl MonitorCaptureSettings {
+    /// Interval between captures, falling back to `default` when no fps override is set
+    pub fn interval(&self, default: Duration) -> Duration {
+        match self.fps {
+            Some(fps) if fps.is_finite() && fps > 0.0 => Duration::from_secs_f64(1.0 / fps),
+            _ => default,
+        }
+    }
+
+    pub fn downscale(&self, image: DynamicImage) -> DynamicImage {
+        if !self.downscale_factor.is_finite() || self.downscale_factor <= 1.0 {
+            return image;
+        }
+        let width = ((image.width() as f64 / self.downscale_factor) as u32).max(1);
+        let height = ((image.height() as f64 / self.downscale_factor) as u32).max(1);
+        image.resize_exact(width, height, FilterType::Triangle)
+    }
+}
+
+static MONITOR_SETTINGS: Lazy<RwLock<HashMap<u32, MonitorCaptureSettings>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Replaces the settings of a monitor, picked up by its capture loop on the next frame
+pub fn set_monitor_settings(monitor_id: u32, settings: MonitorCaptureSettings) {
+    MONITOR_SETTINGS
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(monitor_id, settings);
+}
+
+pub fn get_monitor_settings(monitor_id: u32) -> MonitorCaptureSettings {
+    MONITOR_SETTINGS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&monitor_id)
+        .cloned()
+        .unwrap_or_default()
+}
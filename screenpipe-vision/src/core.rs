@@ -1,11 +1,15 @@
+use crate::adaptive_rate::{input_since, AdaptiveCaptureRate};
 #[cfg(target_os = "macos")]
 use crate::apple::perform_ocr_apple;
 use crate::capture_screenshot_by_window::CapturedWindow;
 use crate::capture_screenshot_by_window::WindowFilters;
+use crate::capture_settings::get_monitor_settings;
 use crate::custom_ocr::perform_ocr_custom;
 #[cfg(target_os = "windows")]
 use crate::microsoft::perform_ocr_windows;
-use crate::monitor::get_monitor_by_id;
+use crate::monitor::{get_monitor_by_id, SafeMonitor};
+use crate::ocr_provider::ocr_provider;
+use crate::secure_fields::{find_secure_fields, mask_secure_fields};
 use crate::tesseract::perform_ocr_tesseract;
 use crate::utils::OcrEngine;
 use crate::utils::{capture_screenshot, compare_with_previous_image};
@@ -152,8 +156,12 @@ pub async fn continuous_capture(
     window_filters: Arc<WindowFilters>,
     languages: Vec<Language>,
     capture_unfocused_windows: bool,
+    max_idle_interval: Option<Duration>,
 ) -> Result<(), ContinuousCaptureError> {
     let mut frame_counter: u64 = 0;
+    // without a max idle interval frames are captured at a fixed rate
+    let mut adaptive_rate = max_idle_interval.map(AdaptiveCaptureRate::new);
+    let mut previous_capture = Instant::now();
     let mut previous_image: Option<DynamicImage> = None;
     let mut max_average: Option<MaxAverageFrame> = None;
     let mut max_avg_value = 0.0;
@@ -172,7 +180,16 @@ pub async fn continuous_capture(
     };
 
     loop {
+        // 2. Pick up settings changed at runtime for this monitor
+        let settings = get_monitor_settings(monitor_id);
+        let frame_interval = settings.interval(interval);
+        if !settings.enabled {
+            tokio::time::sleep(frame_interval).await;
+            continue;
+        }
+
         // 3. Capture screenshot
+        let captured_at = Instant::now();
         let capture_result =
             match capture_screenshot(&monitor, &window_filters, capture_unfocused_windows).await {
                 Ok(result) => result,
@@ -185,7 +202,15 @@ pub async fn continuous_capture(
             };
 
         // 4. Process captured image
-        let (image, window_images, image_hash, _capture_duration) = capture_result;
+        let (image, mut window_images, image_hash, _capture_duration) = capture_result;
+        let image = settings.downscale(image);
+        for window in window_images.iter_mut() {
+            window.image = settings.downscale(std::mem::take(&mut window.image));
+        }
+        let focus = window_images
+            .iter()
+            .find(|window| window.is_focused)
+            .map(|window| (window.app_name.clone(), window.window_name.clone()));
 
         let should_skip = should_skip_frame(
             &previous_image,
@@ -199,9 +224,16 @@ pub async fn continuous_capture(
         )
         .await;
 
+        let input = input_since(previous_capture);
+        previous_capture = captured_at;
+        let frame_interval = match adaptive_rate.as_mut() {
+            Some(rate) => rate.next_interval(frame_interval, !should_skip || input, focus),
+            None => frame_interval,
+        };
+
         if should_skip {
             frame_counter += 1;
-            tokio::time::sleep(interval).await;
+            tokio::time::sleep(frame_interval).await;
             continue;
         }
 
@@ -219,7 +251,7 @@ pub async fn continuous_capture(
         }
 
         frame_counter += 1;
-        tokio::time::sleep(interval).await;
+        tokio::time::sleep(frame_interval).await;
     }
 }
 
@@ -364,6 +396,40 @@ pub async fn process_ocr_task(
     Ok(())
 }
 
+/// Captures a monitor right away and runs OCR on its windows, skipping the
+/// frame comparison and queueing of `continuous_capture`.
+pub async fn capture_now(
+    monitor: &SafeMonitor,
+    window_filters: &WindowFilters,
+    ocr_engine: &OcrEngine,
+    languages: &[Language],
+) -> Result<CaptureResult> {
+    let (image, window_images, _, _) = capture_screenshot(monitor, window_filters, false).await?;
+
+    let mut window_ocr_results = Vec::with_capacity(window_images.len());
+    let mut total_confidence = 0.0;
+    let mut window_count = 0;
+    for captured_window in window_images {
+        let ocr_result = process_window_ocr(
+            captured_window,
+            ocr_engine,
+            languages,
+            &mut total_confidence,
+            &mut window_count,
+        )
+        .await
+        .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+        window_ocr_results.push(ocr_result);
+    }
+
+    Ok(CaptureResult {
+        image,
+        frame_number: 0,
+        timestamp: Instant::now(),
+        window_ocr_results,
+    })
+}
+
 async fn process_window_ocr(
     captured_window: CapturedWindow,
     ocr_engine: &OcrEngine,
@@ -381,11 +447,35 @@ async fn process_window_ocr(
     )
     .await;
 
-    // Perform OCR based on the selected engine
-    let (window_text, window_json_output, confidence) =
-        perform_ocr_with_engine(ocr_engine, &captured_window.image, languages.to_vec())
+    // Never let the content of password fields reach OCR, even when briefly
+    // unmasked, in background windows as much as in the focused one
+    let mut image = captured_window.image;
+    let secure_fields = {
+        let app_name = app_name.clone();
+        let process_id = captured_window.process_id;
+        tokio::task::spawn_blocking(move || find_secure_fields(&app_name, process_id))
             .await
-            .map_err(|e| ContinuousCaptureError::ErrorProcessingOcr(e.to_string()))?;
+            .unwrap_or_default()
+    };
+    if !secure_fields.is_empty() {
+        debug!(
+            "masking {} secure field(s) in {} before ocr",
+            secure_fields.len(),
+            app_name
+        );
+        mask_secure_fields(&mut image, captured_window.bounds, &secure_fields);
+    }
+
+    // Perform OCR based on the selected engine. A window OCR failed on is
+    // kept without text, which the outage monitor counts as a failure.
+    let (window_text, window_json_output, confidence) =
+        match perform_ocr_with_engine(ocr_engine, &image, languages.to_vec()).await {
+            Ok(output) => output,
+            Err(e) => {
+                warn!("ocr failed on {}: {}", captured_window.app_name, e);
+                (String::new(), "[]".to_string(), None)
+            }
+        };
 
     // Update confidence metrics
     if let Some(conf) = confidence {
@@ -394,7 +484,7 @@ async fn process_window_ocr(
     }
 
     Ok(WindowOcrResult {
-        image: captured_window.image,
+        image,
         window_name: captured_window.window_name,
         app_name: captured_window.app_name,
         text: window_text,
@@ -434,7 +524,9 @@ async fn get_browser_url_if_needed(
     }
 }
 
-async fn perform_ocr_with_engine(
+/// Runs the given OCR engine on a single image, returning the plain text, the
+/// engine's JSON output and the average confidence when the engine reports one.
+pub async fn perform_ocr_with_engine(
     ocr_engine: &OcrEngine,
     image: &DynamicImage,
     languages: Vec<Language>,
@@ -453,6 +545,16 @@ async fn perform_ocr_with_engine(
         OcrEngine::Custom(config) => perform_ocr_custom(image, languages, config)
             .await
             .map_err(|e| ContinuousCaptureError::ErrorProcessingOcr(e.to_string())),
+        OcrEngine::Provider(name) => match ocr_provider(name) {
+            Some(provider) => provider
+                .perform_ocr(image, &languages)
+                .await
+                .map_err(|e| ContinuousCaptureError::ErrorProcessingOcr(e.to_string())),
+            None => Err(ContinuousCaptureError::ErrorProcessingOcr(format!(
+                "no OCR provider named {}",
+                name
+            ))),
+        },
         _ => Err(ContinuousCaptureError::ErrorProcessingOcr(
             "Unsupported OCR engine".to_string(),
         )),
@@ -63,8 +63,10 @@ pub async fn perform_ocr_custom(
         "languages": languages.iter().map(|l| l.to_string()).collect::<Vec<_>>(),
     });
 
+    screenpipe_core::egress::check_egress(&config.api_url)?;
+
     // Create client with timeout
-    let client = reqwest::Client::builder()
+    let client = screenpipe_core::egress::http_client_builder()
         .timeout(std::time::Duration::from_millis(config.timeout_ms))
         .build()?;
 
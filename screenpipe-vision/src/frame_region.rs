@@ -0,0 +1,67 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+use screenpipe_db::TextBounds;
+
+const OUTLINE_COLOR: Rgba<u8> = Rgba([255, 0, 0, 255]);
+
+/// The pixels of `region`, in fractions of the image from its top left
+/// corner, as (x, y, width, height) clamped to the image. None when it falls
+/// outside of it.
+pub fn region_pixels(image: &DynamicImage, region: &TextBounds) -> Option<(u32, u32, u32, u32)> {
+    let (width, height) = (image.width() as f32, image.height() as f32);
+    let left = (region.left * width).floor().clamp(0.0, width);
+    let top = (region.top * height).floor().clamp(0.0, height);
+    let right = ((region.left + region.width) * width)
+        .ceil()
+        .clamp(0.0, width);
+    let bottom = ((region.top + region.height) * height)
+        .ceil()
+        .clamp(0.0, height);
+    if right <= left || bottom <= top {
+        return None;
+    }
+    Some((
+        left as u32,
+        top as u32,
+        (right - left) as u32,
+        (bottom - top) as u32,
+    ))
+}
+
+/// `region` of the image with `padding`, a fraction of the image, around
+/// it so the text keeps some context.
+pub fn crop_region(
+    image: &DynamicImage,
+    region: &TextBounds,
+    padding: f32,
+) -> Option<DynamicImage> {
+    let padding = padding.max(0.0);
+    let padded = TextBounds {
+        left: region.left - padding,
+        top: region.top - padding,
+        width: region.width + 2.0 * padding,
+        height: region.height + 2.0 * padding,
+    };
+    let (x, y, width, height) = region_pixels(image, &padded)?;
+    Some(image.crop_imm(x, y, width, height))
+}
+
+/// The whole image with `region` outlined, `thickness` pixels wide.
+pub fn annotate_region(image: &DynamicImage, region: &TextBounds, thickness: u32) -> RgbaImage {
+    let mut annotated = image.to_rgba8();
+    let Some((x, y, width, height)) = region_pixels(image, region) else {
+        return annotated;
+    };
+    let thickness = thickness.max(1);
+    for py in y..y + height {
+        for px in x..x + width {
+            let on_edge = px < x + thickness
+                || px + thickness >= x + width
+                || py < y + thickness
+                || py + thickness >= y + height;
+            if on_edge {
+                annotated.put_pixel(px, py, OUTLINE_COLOR);
+            }
+        }
+    }
+    annotated
+}
@@ -1,18 +1,30 @@
+pub mod adaptive_rate;
 #[cfg(target_os = "macos")]
 pub mod apple;
+pub mod capture_settings;
 pub mod core;
 pub mod custom_ocr;
+pub mod frame_region;
 #[cfg(target_os = "windows")]
 pub mod microsoft;
 pub mod monitor;
+pub mod ocr_provider;
 #[cfg(target_os = "macos")]
 pub mod run_ui_monitoring_macos;
+pub mod secure_fields;
 pub mod tesseract;
 pub mod utils;
+pub mod whiteboard;
 #[cfg(target_os = "macos")]
 pub use apple::perform_ocr_apple;
-pub use core::{continuous_capture, process_ocr_task, CaptureResult, RealtimeVisionEvent, UIFrame};
+pub use core::{
+    capture_now, continuous_capture, perform_ocr_with_engine, process_ocr_task, CaptureResult,
+    RealtimeVisionEvent, UIFrame,
+};
 // pub use types::CaptureResult;
+pub use ocr_provider::{
+    ocr_provider, ocr_providers, register_ocr_provider, OcrProvider, RemoteOcrProvider,
+};
 pub use utils::OcrEngine;
 pub mod capture_screenshot_by_window;
 pub use custom_ocr::perform_ocr_custom;
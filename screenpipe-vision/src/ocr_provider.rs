@@ -0,0 +1,93 @@
+use anyhow::Result;
+use image::DynamicImage;
+use once_cell::sync::Lazy;
+use screenpipe_core::Language;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+
+use crate::custom_ocr::{perform_ocr_custom, CustomOcrConfig};
+
+/// The plain text, the engine's JSON output and the average confidence when
+/// the engine reports one, as `perform_ocr_with_engine` returns them.
+pub type OcrOutput = (String, String, Option<f64>);
+
+pub type OcrFuture<'a> = Pin<Box<dyn Future<Output = Result<OcrOutput>> + Send + 'a>>;
+
+/// An OCR engine registered at runtime instead of built in, e.g. PaddleOCR or
+/// a remote GPU service. Selected with `OcrEngine::Provider` by its name.
+pub trait OcrProvider: Send + Sync {
+    /// Unique among the registered providers.
+    fn name(&self) -> &str;
+
+    /// Recorded with the name in the ocr_engine column of the text it reads.
+    fn version(&self) -> &str;
+
+    fn perform_ocr<'a>(
+        &'a self,
+        image: &'a DynamicImage,
+        languages: &'a [Language],
+    ) -> OcrFuture<'a>;
+}
+
+static PROVIDERS: Lazy<RwLock<BTreeMap<String, Arc<dyn OcrProvider>>>> =
+    Lazy::new(|| RwLock::new(BTreeMap::new()));
+
+/// Registers `provider` under its name, returning the provider it replaces.
+pub fn register_ocr_provider(provider: Arc<dyn OcrProvider>) -> Option<Arc<dyn OcrProvider>> {
+    PROVIDERS
+        .write()
+        .unwrap()
+        .insert(provider.name().to_string(), provider)
+}
+
+pub fn ocr_provider(name: &str) -> Option<Arc<dyn OcrProvider>> {
+    PROVIDERS.read().unwrap().get(name).cloned()
+}
+
+/// The registered providers, by name.
+pub fn ocr_providers() -> Vec<Arc<dyn OcrProvider>> {
+    PROVIDERS.read().unwrap().values().cloned().collect()
+}
+
+/// What the ocr_engine column records for text read by a provider.
+pub fn provider_id(provider: &dyn OcrProvider) -> String {
+    format!("{}/{}", provider.name(), provider.version())
+}
+
+/// An OCR service reached over HTTP, with the requests and responses of the
+/// custom OCR engine.
+pub struct RemoteOcrProvider {
+    name: String,
+    version: String,
+    config: CustomOcrConfig,
+}
+
+impl RemoteOcrProvider {
+    pub fn new(name: &str, version: &str, config: CustomOcrConfig) -> Self {
+        Self {
+            name: name.to_string(),
+            version: version.to_string(),
+            config,
+        }
+    }
+}
+
+impl OcrProvider for RemoteOcrProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn version(&self) -> &str {
+        &self.version
+    }
+
+    fn perform_ocr<'a>(
+        &'a self,
+        image: &'a DynamicImage,
+        languages: &'a [Language],
+    ) -> OcrFuture<'a> {
+        Box::pin(perform_ocr_custom(image, languages.to_vec(), &self.config))
+    }
+}
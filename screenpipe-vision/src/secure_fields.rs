@@ -0,0 +1,156 @@
+use image::{DynamicImage, GenericImage, Rgba};
+#[cfg(not(target_os = "windows"))]
+use once_cell::sync::Lazy;
+#[cfg(not(target_os = "windows"))]
+use screenpipe_core::Desktop;
+use screenpipe_core::Selector;
+use std::collections::BTreeMap;
+use tracing::debug;
+
+/// Screen rectangle (x, y, width, height) as reported by the accessibility API
+pub type FieldBounds = (f64, f64, f64, f64);
+
+// accessibility access is only available where the operator engine is implemented
+// (and permission was granted); without it masking is silently skipped
+#[cfg(not(target_os = "windows"))]
+static DESKTOP: Lazy<Option<Desktop>> = Lazy::new(|| match Desktop::new(false, false) {
+    Ok(desktop) => Some(desktop),
+    Err(e) => {
+        debug!(
+            "accessibility unavailable, secure field masking disabled: {}",
+            e
+        );
+        None
+    }
+});
+
+/// Selects password fields in the accessibility tree. macOS gives them the
+/// AXTextField role of any text field, only their subrole tells them apart.
+pub fn secure_field_selector() -> Selector {
+    Selector::Attributes(BTreeMap::from([(
+        "AXSubrole".to_string(),
+        "AXSecureTextField".to_string(),
+    )]))
+}
+
+/// Looks up password and other masked input fields of `app_name` (running as
+/// `process_id`) in the accessibility tree: by subrole on macOS, by the
+/// IsPassword property of UI Automation on Windows. Nothing is found on Linux
+/// yet. This walks the tree synchronously, call it off the async runtime.
+pub fn find_secure_fields(app_name: &str, process_id: i32) -> Vec<FieldBounds> {
+    #[cfg(target_os = "windows")]
+    {
+        let _ = app_name;
+        find_password_fields(process_id)
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = process_id;
+        find_secure_fields_by_selector(app_name)
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn find_secure_fields_by_selector(app_name: &str) -> Vec<FieldBounds> {
+    let Some(desktop) = DESKTOP.as_ref() else {
+        return Vec::new();
+    };
+
+    let app = match desktop.application(app_name) {
+        Ok(app) => app,
+        Err(e) => {
+            debug!("no accessibility element for app {}: {}", app_name, e);
+            return Vec::new();
+        }
+    };
+
+    match app
+        .locator(secure_field_selector())
+        .and_then(|locator| locator.all())
+    {
+        Ok(elements) => elements
+            .iter()
+            .filter_map(|element| element.bounds().ok())
+            .filter(|(_, _, width, height)| *width > 0.0 && *height > 0.0)
+            .collect(),
+        Err(e) => {
+            debug!("failed to search secure fields in {}: {}", app_name, e);
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn find_password_fields(process_id: i32) -> Vec<FieldBounds> {
+    use uiautomation::types::{TreeScope, UIProperty};
+    use uiautomation::variants::Variant;
+    use uiautomation::UIAutomation;
+
+    let find = || -> uiautomation::Result<Vec<FieldBounds>> {
+        let automation = UIAutomation::new()?;
+        let condition = automation.create_and_condition(
+            automation.create_property_condition(
+                UIProperty::ProcessId,
+                Variant::from(process_id),
+                None,
+            )?,
+            automation.create_property_condition(
+                UIProperty::IsPassword,
+                Variant::from(true),
+                None,
+            )?,
+        )?;
+        let elements = automation
+            .get_root_element()?
+            .find_all(TreeScope::Descendants, &condition)?;
+        Ok(elements
+            .iter()
+            .filter_map(|element| element.get_bounding_rectangle().ok())
+            .map(|rect| {
+                (
+                    rect.get_left() as f64,
+                    rect.get_top() as f64,
+                    rect.get_width() as f64,
+                    rect.get_height() as f64,
+                )
+            })
+            .filter(|(_, _, width, height)| *width > 0.0 && *height > 0.0)
+            .collect())
+    };
+    find().unwrap_or_else(|e| {
+        debug!(
+            "failed to search password fields of process {}: {}",
+            process_id, e
+        );
+        Vec::new()
+    })
+}
+
+/// Blacks out `fields` in a window screenshot so their content never reaches OCR.
+///
+/// `window_bounds` is the window frame in screen coordinates; the screenshot may
+/// be larger than the frame on HiDPI displays, so field rectangles are scaled.
+pub fn mask_secure_fields(
+    image: &mut DynamicImage,
+    window_bounds: (i32, i32, u32, u32),
+    fields: &[FieldBounds],
+) {
+    let (window_x, window_y, window_width, _) = window_bounds;
+    if window_width == 0 {
+        return;
+    }
+    let scale = image.width() as f64 / window_width as f64;
+
+    for (x, y, width, height) in fields {
+        let left = ((x - window_x as f64) * scale).max(0.0) as u32;
+        let top = ((y - window_y as f64) * scale).max(0.0) as u32;
+        let right = (((x + width - window_x as f64) * scale).max(0.0) as u32).min(image.width());
+        let bottom = (((y + height - window_y as f64) * scale).max(0.0) as u32).min(image.height());
+
+        for py in top..bottom {
+            for px in left..right {
+                image.put_pixel(px, py, Rgba([0, 0, 0, 255]));
+            }
+        }
+    }
+}
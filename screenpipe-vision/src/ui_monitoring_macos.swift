@@ -486,6 +486,11 @@ func traverseAndStoreUIElements(_ element: AXUIElement, appName: String, windowN
                 return nil
             }
 
+            // password fields, their value is never stored
+            if isSecureTextField(element) {
+                return nil
+            }
+
             // Add depth limit check
             if depth > 100 {
                 print("max depth reached: depth=\(depth), app=\(appName), window=\(windowName)")
@@ -642,6 +647,10 @@ func traverseAndStoreUIElements(_ element: AXUIElement, appName: String, windowN
     }
 }
 
+func isSecureTextField(_ element: AXUIElement) -> Bool {
+    return (getAttributeValue(element, forAttribute: "AXSubrole") as? String) == "AXSecureTextField"
+}
+
 func getRelevantValue(_ element: AXUIElement) -> String? {
     let attributesToCheck = ["AXDescription", "AXValue", "AXLabel", "AXRoleDescription", "AXHelp"]
     let unwantedValues = ["0", "", "\u{200E}", "3", "\u{200F}"]  // LRM and RLM marks
@@ -678,6 +687,10 @@ func updateElementAndChildren(
         return false
     }
 
+    if isSecureTextField(element) {
+        return false
+    }
+
     let elementWrapper = AXUIElementWrapper(element: element)
     if visitedElements.contains(elementWrapper) { return false }
     visitedElements.insert(elementWrapper)
@@ -4,6 +4,7 @@ use crate::capture_screenshot_by_window::{
 use crate::core::MaxAverageFrame;
 use crate::custom_ocr::CustomOcrConfig;
 use crate::monitor::SafeMonitor;
+use crate::ocr_provider::{ocr_provider, provider_id};
 use image::DynamicImage;
 use image_compare::{Algorithm, Metric, Similarity};
 use tracing::{debug, warn};
@@ -19,6 +20,8 @@ pub enum OcrEngine {
     WindowsNative,
     AppleNative,
     Custom(CustomOcrConfig),
+    /// the name of a provider registered with `register_ocr_provider`
+    Provider(String),
 }
 
 impl From<OcrEngine> for screenpipe_db::OcrEngine {
@@ -31,6 +34,11 @@ impl From<OcrEngine> for screenpipe_db::OcrEngine {
             OcrEngine::Custom(config) => {
                 screenpipe_db::OcrEngine::Custom(DBCustomOcrConfig::from(config))
             }
+            OcrEngine::Provider(name) => screenpipe_db::OcrEngine::Provider(
+                ocr_provider(&name)
+                    .map(|provider| provider_id(provider.as_ref()))
+                    .unwrap_or(name),
+            ),
         }
     }
 }
@@ -43,6 +51,10 @@ impl From<screenpipe_db::OcrEngine> for OcrEngine {
             screenpipe_db::OcrEngine::WindowsNative => OcrEngine::WindowsNative,
             screenpipe_db::OcrEngine::AppleNative => OcrEngine::AppleNative,
             screenpipe_db::OcrEngine::Custom(config) => OcrEngine::Custom(config.into()),
+            screenpipe_db::OcrEngine::Provider(id) => {
+                // the id is the name and the version
+                OcrEngine::Provider(id.split('/').next().unwrap_or_default().to_string())
+            }
         }
     }
 }
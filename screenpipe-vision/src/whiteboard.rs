@@ -0,0 +1,266 @@
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, GrayImage, Rgb, RgbImage};
+use std::collections::VecDeque;
+
+/// Corners of the board in image coordinates: top-left, top-right,
+/// bottom-right, bottom-left.
+pub type Quad = [(f32, f32); 4];
+
+// board detection runs on a thumbnail, the exact outline doesn't need full resolution
+const DETECTION_SIZE: u32 = 400;
+// the board must cover at least this share of the photo to be cropped
+const MIN_BOARD_AREA: f32 = 0.2;
+// background lighting is estimated on a grid this many pixels wide
+const BACKGROUND_SIZE: u32 = 32;
+// ink/background ratios mapped to black and white by the contrast cleanup
+const BLACK_POINT: f32 = 0.5;
+const WHITE_POINT: f32 = 0.9;
+
+/// Prepares a photo of a whiteboard for OCR: the board is cropped and
+/// straightened when its outline can be found, then uneven lighting and
+/// glare are flattened so the marker strokes stand out on a white background.
+pub fn enhance_whiteboard(image: &DynamicImage) -> DynamicImage {
+    let rgb = image.to_rgb8();
+    let straightened = match find_board(&rgb) {
+        Some(quad) => warp_perspective(&rgb, &quad),
+        None => rgb,
+    };
+    DynamicImage::ImageRgb8(clean_contrast(&straightened))
+}
+
+/// Finds the outline of the board: the largest bright region of the photo,
+/// with its corners taken as the extreme points along both diagonals.
+pub fn find_board(image: &RgbImage) -> Option<Quad> {
+    let (width, height) = image.dimensions();
+    if width < 2 || height < 2 {
+        return None;
+    }
+
+    let scale = (DETECTION_SIZE as f32 / width.max(height) as f32).min(1.0);
+    let thumb = DynamicImage::ImageRgb8(image.clone())
+        .resize_exact(
+            ((width as f32 * scale).round() as u32).max(1),
+            ((height as f32 * scale).round() as u32).max(1),
+            FilterType::Triangle,
+        )
+        .to_luma8();
+
+    let threshold = otsu_threshold(&thumb)?;
+    let region = largest_region(&thumb, threshold);
+    let (thumb_width, thumb_height) = thumb.dimensions();
+    if (region.len() as f32) < MIN_BOARD_AREA * (thumb_width * thumb_height) as f32 {
+        return None;
+    }
+
+    let corner = |key: fn(&(u32, u32)) -> i64| {
+        let max = region.iter().max_by_key(|p| key(p))?;
+        Some(((max.0 as f32 + 0.5) / scale, (max.1 as f32 + 0.5) / scale))
+    };
+    let quad = [
+        corner(|&(x, y)| -(x as i64 + y as i64))?,
+        corner(|&(x, y)| x as i64 - y as i64)?,
+        corner(|&(x, y)| x as i64 + y as i64)?,
+        corner(|&(x, y)| y as i64 - x as i64)?,
+    ];
+
+    (quad_area(&quad) >= MIN_BOARD_AREA * (width * height) as f32).then_some(quad)
+}
+
+/// Maps the quadrilateral `quad` of `image` onto an upright rectangle.
+pub fn warp_perspective(image: &RgbImage, quad: &Quad) -> RgbImage {
+    let [tl, tr, br, bl] = *quad;
+    let out_width = distance(tl, tr).max(distance(bl, br)).round().max(1.0) as u32;
+    let out_height = distance(tl, bl).max(distance(tr, br)).round().max(1.0) as u32;
+
+    let (w, h) = (out_width as f32, out_height as f32);
+    let Some(homography) = homography([(0.0, 0.0), (w, 0.0), (w, h), (0.0, h)], *quad) else {
+        return image.clone();
+    };
+
+    RgbImage::from_fn(out_width, out_height, |x, y| {
+        let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+        let denominator = homography[6] * px + homography[7] * py + 1.0;
+        let sx = (homography[0] * px + homography[1] * py + homography[2]) / denominator;
+        let sy = (homography[3] * px + homography[4] * py + homography[5]) / denominator;
+        sample_bilinear(image, sx - 0.5, sy - 0.5)
+    })
+}
+
+/// Divides out the background lighting and stretches what is left so the
+/// board turns white and the strokes keep their color.
+pub fn clean_contrast(image: &RgbImage) -> RgbImage {
+    let (width, height) = image.dimensions();
+    let gray = DynamicImage::ImageRgb8(image.clone()).to_luma8();
+    let grid_width = BACKGROUND_SIZE.min(width).max(1);
+    let grid_height = ((height as f32 * grid_width as f32 / width as f32).round() as u32).max(1);
+    let background = imageops::resize(
+        &imageops::blur(
+            &imageops::resize(&gray, grid_width, grid_height, FilterType::Triangle),
+            1.0,
+        ),
+        width,
+        height,
+        FilterType::Triangle,
+    );
+
+    RgbImage::from_fn(width, height, |x, y| {
+        let light = (background.get_pixel(x, y)[0] as f32).max(1.0);
+        let pixel = image.get_pixel(x, y);
+        Rgb(pixel.0.map(|channel| {
+            let ratio = channel as f32 / light;
+            let level = (ratio - BLACK_POINT) / (WHITE_POINT - BLACK_POINT);
+            (level.clamp(0.0, 1.0) * 255.0).round() as u8
+        }))
+    })
+}
+
+/// Level splitting the image into dark and bright pixels, none for an image of
+/// a single shade.
+fn otsu_threshold(image: &GrayImage) -> Option<u8> {
+    let mut histogram = [0u64; 256];
+    for pixel in image.pixels() {
+        histogram[pixel[0] as usize] += 1;
+    }
+
+    let total = image.pixels().len() as f64;
+    let sum: f64 = histogram
+        .iter()
+        .enumerate()
+        .map(|(level, count)| level as f64 * *count as f64)
+        .sum();
+
+    let (mut background_weight, mut background_sum) = (0.0, 0.0);
+    let (mut best_level, mut best_variance) = (None, 0.0);
+    for (level, count) in histogram.iter().enumerate() {
+        background_weight += *count as f64;
+        background_sum += level as f64 * *count as f64;
+        let foreground_weight = total - background_weight;
+        if background_weight == 0.0 || foreground_weight == 0.0 {
+            continue;
+        }
+        let background_mean = background_sum / background_weight;
+        let foreground_mean = (sum - background_sum) / foreground_weight;
+        let variance =
+            background_weight * foreground_weight * (background_mean - foreground_mean).powi(2);
+        if variance > best_variance {
+            best_variance = variance;
+            best_level = Some(level as u8);
+        }
+    }
+    best_level
+}
+
+/// Pixels of the largest 4-connected region brighter than `threshold`.
+fn largest_region(image: &GrayImage, threshold: u8) -> Vec<(u32, u32)> {
+    let (width, height) = image.dimensions();
+    let mut visited = vec![false; (width * height) as usize];
+    let mut largest = Vec::new();
+
+    for start in 0..width * height {
+        let (x, y) = (start % width, start / width);
+        if visited[start as usize] || image.get_pixel(x, y)[0] <= threshold {
+            continue;
+        }
+
+        let mut region = Vec::new();
+        let mut queue = VecDeque::from([(x, y)]);
+        visited[start as usize] = true;
+        while let Some((x, y)) = queue.pop_front() {
+            region.push((x, y));
+            let neighbors = [
+                (x.wrapping_sub(1), y),
+                (x + 1, y),
+                (x, y.wrapping_sub(1)),
+                (x, y + 1),
+            ];
+            for (nx, ny) in neighbors {
+                if nx >= width || ny >= height {
+                    continue;
+                }
+                let index = (ny * width + nx) as usize;
+                if !visited[index] && image.get_pixel(nx, ny)[0] > threshold {
+                    visited[index] = true;
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+
+        if region.len() > largest.len() {
+            largest = region;
+        }
+    }
+    largest
+}
+
+/// Solves for the projective transform taking each point of `from` to the
+/// matching point of `to`, returned as the first 8 coefficients of the 3x3
+/// matrix (the last one is 1).
+fn homography(from: Quad, to: Quad) -> Option<[f32; 8]> {
+    let mut system = [[0f64; 9]; 8];
+    for (i, ((x, y), (u, v))) in from.iter().zip(to.iter()).enumerate() {
+        let (x, y, u, v) = (*x as f64, *y as f64, *u as f64, *v as f64);
+        system[2 * i] = [x, y, 1.0, 0.0, 0.0, 0.0, -u * x, -u * y, u];
+        system[2 * i + 1] = [0.0, 0.0, 0.0, x, y, 1.0, -v * x, -v * y, v];
+    }
+
+    // gaussian elimination with partial pivoting
+    for column in 0..8 {
+        let pivot = (column..8).max_by(|a, b| {
+            system[*a][column]
+                .abs()
+                .total_cmp(&system[*b][column].abs())
+        })?;
+        if system[pivot][column].abs() < 1e-9 {
+            return None;
+        }
+        system.swap(column, pivot);
+        for row in 0..8 {
+            if row == column {
+                continue;
+            }
+            let pivot_row = system[column];
+            let factor = system[row][column] / pivot_row[column];
+            for (value, pivot_value) in system[row].iter_mut().zip(pivot_row).skip(column) {
+                *value -= factor * pivot_value;
+            }
+        }
+    }
+
+    let mut coefficients = [0f32; 8];
+    for (i, coefficient) in coefficients.iter_mut().enumerate() {
+        *coefficient = (system[i][8] / system[i][i]) as f32;
+    }
+    Some(coefficients)
+}
+
+fn sample_bilinear(image: &RgbImage, x: f32, y: f32) -> Rgb<u8> {
+    let (width, height) = image.dimensions();
+    let x = x.clamp(0.0, (width - 1) as f32);
+    let y = y.clamp(0.0, (height - 1) as f32);
+    let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+    let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+    let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+
+    let (a, b) = (image.get_pixel(x0, y0), image.get_pixel(x1, y0));
+    let (c, d) = (image.get_pixel(x0, y1), image.get_pixel(x1, y1));
+    Rgb(std::array::from_fn(|i| {
+        let top = a[i] as f32 * (1.0 - fx) + b[i] as f32 * fx;
+        let bottom = c[i] as f32 * (1.0 - fx) + d[i] as f32 * fx;
+        (top * (1.0 - fy) + bottom * fy).round() as u8
+    }))
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+// shoelace formula
+fn quad_area(quad: &Quad) -> f32 {
+    let mut twice_area = 0.0;
+    for i in 0..4 {
+        let (x0, y0) = quad[i];
+        let (x1, y1) = quad[(i + 1) % 4];
+        twice_area += x0 * y1 - x1 * y0;
+    }
+    twice_area.abs() / 2.0
+}
@@ -0,0 +1,52 @@
+#[cfg(test)]
+mod tests {
+    use screenpipe_vision::adaptive_rate::{input_since, record_input, AdaptiveCaptureRate};
+    use std::time::{Duration, Instant};
+
+    fn focus(app: &str) -> Option<(String, String)> {
+        Some((app.to_string(), "window".to_string()))
+    }
+
+    #[test]
+    fn test_adaptive_rate_backs_off_to_max_interval_when_idle() {
+        let base = Duration::from_secs(1);
+        let mut rate = AdaptiveCaptureRate::new(Duration::from_secs(60));
+
+        assert_eq!(rate.next_interval(base, true, focus("code")), base);
+        let mut previous = base;
+        for _ in 0..20 {
+            let next = rate.next_interval(base, false, focus("code"));
+            assert!(next >= previous);
+            previous = next;
+        }
+        assert_eq!(previous, Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_adaptive_rate_resets_on_change_or_focus_switch() {
+        let base = Duration::from_millis(500);
+        let mut rate = AdaptiveCaptureRate::new(Duration::from_secs(60));
+
+        rate.next_interval(base, true, focus("code"));
+        for _ in 0..5 {
+            rate.next_interval(base, false, focus("code"));
+        }
+        assert!(rate.next_interval(base, false, focus("code")) > base);
+        assert_eq!(rate.next_interval(base, true, focus("code")), base);
+
+        for _ in 0..5 {
+            rate.next_interval(base, false, focus("code"));
+        }
+        assert_eq!(rate.next_interval(base, false, focus("slack")), base);
+        // losing focus information is not activity
+        assert!(rate.next_interval(base, false, None) > base);
+    }
+
+    #[test]
+    fn test_input_since() {
+        let before = Instant::now();
+        record_input();
+        assert!(input_since(before));
+        assert!(!input_since(Instant::now()));
+    }
+}
@@ -0,0 +1,44 @@
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, Rgba, RgbaImage};
+    use screenpipe_db::TextBounds;
+    use screenpipe_vision::frame_region::{annotate_region, crop_region, region_pixels};
+
+    fn bounds(left: f32, top: f32, width: f32, height: f32) -> TextBounds {
+        TextBounds {
+            left,
+            top,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn test_crop_region() {
+        let image = DynamicImage::ImageRgba8(RgbaImage::new(200, 100));
+        let region = bounds(0.25, 0.5, 0.5, 0.25);
+        assert_eq!(region_pixels(&image, &region), Some((50, 50, 100, 25)));
+
+        let cropped = crop_region(&image, &region, 0.0).unwrap();
+        assert_eq!((cropped.width(), cropped.height()), (100, 25));
+        let padded = crop_region(&image, &region, 0.125).unwrap();
+        assert_eq!((padded.width(), padded.height()), (150, 51));
+        // the padding stops at the edges of the image
+        let padded = crop_region(&image, &region, 0.375).unwrap();
+        assert_eq!((padded.width(), padded.height()), (200, 88));
+
+        assert!(crop_region(&image, &bounds(1.2, 0.0, 0.1, 0.1), 0.0).is_none());
+    }
+
+    #[test]
+    fn test_annotate_region() {
+        let image = DynamicImage::ImageRgba8(RgbaImage::new(100, 100));
+        let annotated = annotate_region(&image, &bounds(0.125, 0.125, 0.5, 0.5), 2);
+        assert_eq!(annotated.dimensions(), (100, 100));
+        assert_eq!(annotated.get_pixel(12, 30), &Rgba([255, 0, 0, 255]));
+        assert_eq!(annotated.get_pixel(62, 62), &Rgba([255, 0, 0, 255]));
+        // inside and outside the outline the image is left as it was
+        assert_eq!(annotated.get_pixel(30, 30), &Rgba([0, 0, 0, 0]));
+        assert_eq!(annotated.get_pixel(80, 80), &Rgba([0, 0, 0, 0]));
+    }
+}
@@ -0,0 +1,63 @@
+use image::DynamicImage;
+use screenpipe_core::Language;
+use screenpipe_vision::ocr_provider::OcrFuture;
+use screenpipe_vision::{
+    ocr_provider, perform_ocr_with_engine, register_ocr_provider, OcrEngine, OcrProvider,
+};
+use std::sync::Arc;
+
+struct FakeProvider;
+
+impl OcrProvider for FakeProvider {
+    fn name(&self) -> &str {
+        "fake"
+    }
+
+    fn version(&self) -> &str {
+        "1.2"
+    }
+
+    fn perform_ocr<'a>(
+        &'a self,
+        image: &'a DynamicImage,
+        languages: &'a [Language],
+    ) -> OcrFuture<'a> {
+        Box::pin(async move {
+            Ok((
+                format!("{}x{} {}", image.width(), image.height(), languages.len()),
+                "[]".to_string(),
+                Some(0.9),
+            ))
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_ocr_provider() {
+    assert!(register_ocr_provider(Arc::new(FakeProvider)).is_none());
+    assert!(ocr_provider("fake").is_some());
+
+    let engine = OcrEngine::Provider("fake".to_string());
+    let image = DynamicImage::new_rgb8(4, 3);
+    let (text, json, confidence) =
+        perform_ocr_with_engine(&engine, &image, vec![Language::English])
+            .await
+            .unwrap();
+    assert_eq!(
+        (text.as_str(), json.as_str(), confidence),
+        ("4x3 1", "[]", Some(0.9))
+    );
+
+    // recorded with the version declared, found again by the name
+    let recorded: screenpipe_db::OcrEngine = engine.into();
+    assert_eq!(recorded.column_value(), "fake/1.2");
+    assert!(matches!(OcrEngine::from(recorded), OcrEngine::Provider(name) if name == "fake"));
+
+    assert!(perform_ocr_with_engine(
+        &OcrEngine::Provider("missing".to_string()),
+        &image,
+        Vec::new()
+    )
+    .await
+    .is_err());
+}
@@ -0,0 +1,25 @@
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, GenericImageView, Rgba};
+    use screenpipe_vision::secure_fields::{mask_secure_fields, secure_field_selector};
+
+    #[test]
+    fn test_secure_fields_are_found_by_subrole() {
+        let selector = secure_field_selector();
+        // a macOS password field has the role of any text field
+        assert!(selector.matches_attributes("AXTextField", Some("AXSecureTextField")));
+        assert!(!selector.matches_attributes("AXTextField", None));
+        assert!(!selector.matches_attributes("AXTextField", Some("AXSearchField")));
+        // AXSecureTextField is never a role
+        assert!(!selector.matches_attributes("AXSecureTextField", None));
+    }
+
+    #[test]
+    fn test_mask_secure_fields_ignores_fields_outside_window() {
+        let mut image = DynamicImage::new_rgba8(100, 50);
+        mask_secure_fields(&mut image, (0, 0, 100, 50), &[(500.0, 500.0, 20.0, 5.0)]);
+        assert!(image
+            .pixels()
+            .all(|(_, _, pixel)| pixel == Rgba([0, 0, 0, 0])));
+    }
+}
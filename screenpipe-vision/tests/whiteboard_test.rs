@@ -0,0 +1,87 @@
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, Rgb, RgbImage};
+    use screenpipe_vision::whiteboard::{clean_contrast, enhance_whiteboard, find_board};
+
+    fn inside(quad: &[(f32, f32); 4], x: f32, y: f32) -> bool {
+        (0..4).all(|i| {
+            let (ax, ay) = quad[i];
+            let (bx, by) = quad[(i + 1) % 4];
+            (bx - ax) * (y - ay) - (by - ay) * (x - ax) >= 0.0
+        })
+    }
+
+    // a white board photographed at an angle on a dark wall, with a
+    // horizontal marker stroke across its middle
+    fn skewed_board() -> (RgbImage, [(f32, f32); 4]) {
+        let quad = [(60.0, 40.0), (340.0, 60.0), (320.0, 260.0), (40.0, 240.0)];
+        let image = RgbImage::from_fn(400, 300, |x, y| {
+            let (x, y) = (x as f32 + 0.5, y as f32 + 0.5);
+            if !inside(&quad, x, y) {
+                Rgb([60, 50, 40])
+            } else if (145.0..155.0).contains(&y) && (120.0..280.0).contains(&x) {
+                Rgb([20, 20, 120])
+            } else {
+                Rgb([235, 235, 230])
+            }
+        });
+        (image, quad)
+    }
+
+    #[test]
+    fn test_find_board_returns_corners_of_skewed_board() {
+        let (image, quad) = skewed_board();
+        let found = find_board(&image).expect("board not found");
+        for (corner, expected) in found.iter().zip(quad.iter()) {
+            assert!(
+                (corner.0 - expected.0).abs() < 6.0 && (corner.1 - expected.1).abs() < 6.0,
+                "corner {:?} too far from {:?}",
+                corner,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_enhance_whiteboard_crops_board_and_whitens_background() {
+        let (image, _) = skewed_board();
+        let enhanced = enhance_whiteboard(&DynamicImage::ImageRgb8(image)).to_rgb8();
+        let (width, height) = enhanced.dimensions();
+        assert!((275..=290).contains(&width), "width {}", width);
+        assert!((195..=210).contains(&height), "height {}", height);
+
+        // the wall is cropped away, board corners are white
+        for (x, y) in [
+            (3, 3),
+            (width - 4, 3),
+            (3, height - 4),
+            (width - 4, height - 4),
+        ] {
+            assert!(enhanced.get_pixel(x, y).0.iter().all(|c| *c > 200));
+        }
+        // the stroke stays dark and keeps its color
+        let stroke = enhanced.get_pixel(width / 2, height / 2);
+        assert!(
+            stroke[0] < 60 && stroke[2] > stroke[0],
+            "stroke {:?}",
+            stroke
+        );
+    }
+
+    #[test]
+    fn test_clean_contrast_flattens_uneven_lighting() {
+        // the board gets darker to the right, as if lit from the left
+        let image = RgbImage::from_fn(300, 100, |x, _| {
+            let light = 240 - (x * 120 / 300) as u8;
+            Rgb([light, light, light])
+        });
+        let cleaned = clean_contrast(&image);
+        assert!(cleaned.pixels().all(|p| p.0.iter().all(|c| *c > 230)));
+    }
+
+    #[test]
+    fn test_find_board_ignores_photo_without_board() {
+        let image = RgbImage::from_pixel(200, 100, Rgb([60, 50, 40]));
+        assert!(find_board(&image).is_none());
+    }
+}
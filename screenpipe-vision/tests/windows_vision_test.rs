@@ -32,6 +32,7 @@ mod tests {
         let window_images = vec![CapturedWindow {
             app_name: "test_app".to_string(),
             window_name: "test_window".to_string(),
+            bounds: (0, 0, image.width(), image.height()),
             image,
             is_focused: true,
             process_id: 1234,
@@ -78,6 +79,7 @@ mod tests {
             window_filters, // window filters as empty vec
             vec![],         // languages as empty vec
             save_text_files_flag,
+            None,
         ));
 
         // Wait for a short duration to allow some captures to occur